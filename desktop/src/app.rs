@@ -1,16 +1,19 @@
 use eframe::egui;
 use std::collections::{HashMap, HashSet};
 use std::sync::mpsc::{self, Receiver};
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 
 use crate::core::disk::Partition;
 use crate::core::dism::{DismProgress, ImageInfo};
 use crate::core::hardware_info::HardwareInfo;
+use crate::core::power::CompletionAction;
+use crate::core::storage_controller::RaidController;
 use crate::core::system_info::SystemInfo;
-use crate::download::aria2::DownloadProgress;
+use crate::download::engine::DownloadProgress;
 use crate::download::config::ConfigManager;
-use crate::download::manager::DownloadManager;
+use crate::ui::a11y::dialog_keys;
 use crate::ui::advanced_options::AdvancedOptions;
+use crate::ui::task_tray::show_busy_notice;
 use crate::tr;
 
 // 异步加载系统/硬件信息的通道
@@ -26,6 +29,7 @@ struct AsyncInfoResult {
 pub enum Panel {
     SystemInstall,
     SystemBackup,
+    BackupSchedule,
     OnlineDownload,
     Tools,
     HardwareInfo,
@@ -171,10 +175,26 @@ pub struct InstallOptions {
     pub repair_boot: bool,
     pub unattended_install: bool,
     pub export_drivers: bool,
-    pub auto_reboot: bool,
+    /// 安装完成后的操作
+    pub completion_action: CompletionAction,
     pub boot_mode: BootModeSelection,
     pub advanced_options: AdvancedOptions,
     pub driver_action: DriverAction,
+    /// 格式化前导出目标分区现有系统的驱动，释放镜像后重新注入
+    pub harvest_drivers: bool,
+    /// 按硬件 ID 下载本机网卡和存储控制器驱动，放到数据分区供 PE 注入
+    pub hwid_drivers: bool,
+    /// 导出已连接的 Wi-Fi 配置，PE 中导入后联网
+    pub wifi_profiles: bool,
+    /// 镜像位于 SMB 共享时，PE 中连接共享使用的账户
+    pub share_user: String,
+    pub share_password: String,
+    /// 释放镜像后按镜像中的哈希校验已写入的文件
+    pub apply_verify: crate::core::apply_verify::ApplyVerifyMode,
+    /// 安装到虚拟磁盘（本机 VHD 启动）
+    pub vhd_target: Option<crate::core::vhd::VhdTarget>,
+    /// GHO 磁盘镜像中要还原的分区序号，0 表示不指定
+    pub gho_partition: u32,
 }
 
 /// 主应用结构
@@ -188,14 +208,31 @@ pub struct App {
     // 硬件信息
     pub hardware_info: Option<HardwareInfo>,
     pub hardware_info_loading: bool,
+    // 检测到的 Intel VMD / RST RAID 控制器，以及驱动包中缺少驱动的控制器
+    pub raid_controllers: Vec<RaidController>,
+    pub raid_missing_drivers: Vec<RaidController>,
+    /// 后台检测 RAID 控制器的结果（控制器、缺少驱动的控制器）
+    pub raid_controllers_rx: Option<Receiver<(Vec<RaidController>, Vec<RaidController>)>>,
+    // 本地存储控制器驱动包
+    pub driver_packs: Vec<crate::core::driver_pack::DriverPack>,
 
     // 磁盘分区列表
     pub partitions: Vec<Partition>,
     pub selected_partition: Option<usize>,
+    /// 设备变化监视（插拔磁盘后自动刷新分区列表和硬件信息）
+    pub device_watcher: Option<crate::core::watcher::DeviceWatcher>,
+    /// 安装/备份进行中收到的分区列表，任务结束后再应用
+    pub pending_device_partitions: Option<Vec<Partition>>,
 
     // 在线资源
     pub config: Option<ConfigManager>,
     pub selected_online_system: Option<usize>,
+    /// 用户导入的本地镜像
+    pub local_images: Vec<crate::download::local_catalog::LocalImage>,
+    /// 是否显示本机硬件不支持的在线镜像
+    pub show_unsupported_images: bool,
+    /// 手动输入的磁力链接 / 种子地址
+    pub torrent_link_input: String,
     
     // 远程配置
     pub remote_config: Option<crate::download::server_config::RemoteConfig>,
@@ -209,6 +246,16 @@ pub struct App {
     pub local_image_path: String,
     pub image_volumes: Vec<ImageInfo>,
     pub selected_volume: Option<usize>,
+    /// 备份镜像的还原点
+    pub restore_points: crate::ui::restore_points::RestorePointState,
+    /// 镜像库选择器
+    pub image_library: crate::ui::image_library::ImageLibraryState,
+    /// GHO 磁盘镜像中的分区（分区镜像为空）
+    pub gho_partitions: Vec<crate::core::ghost::GhoPartition>,
+    /// 要还原的 GHO 镜像分区序号，0 表示不指定（磁盘镜像必须由用户选择）
+    pub selected_gho_partition: u32,
+    /// 后台读取 GHO 分区表的结果
+    pub gho_partitions_rx: Option<std::sync::mpsc::Receiver<Vec<crate::core::ghost::GhoPartition>>>,
 
 
     // Win7检测日志去重（仅在结果变化时输出）
@@ -220,13 +267,34 @@ pub struct App {
     pub repair_boot: bool,
     pub unattended_install: bool,
     pub export_drivers: bool,
-    pub auto_reboot: bool,
+    pub completion_action: CompletionAction,
     pub selected_boot_mode: BootModeSelection,
     pub driver_action: DriverAction,
+    pub harvest_drivers: bool,
+    pub hwid_drivers: bool,
+    pub wifi_profiles: bool,
+    pub share_user: String,
+    pub share_password: String,
+    /// 网络镜像在本机无法读取分卷信息时手动填写的分卷序号
+    pub network_volume_index: u32,
+    /// 共享中的镜像在选择时本机能否直接读取（只在加载镜像时检查，避免每帧访问网络）
+    pub share_image_readable: bool,
+    pub apply_verify: crate::core::apply_verify::ApplyVerifyMode,
+    // 安装到虚拟磁盘（文件放在所选分区根目录）
+    pub vhd_install_enabled: bool,
+    pub vhd_install_file_name: String,
+    pub vhd_install_size_gb: u64,
 
     // 高级选项
     pub advanced_options: AdvancedOptions,
     pub show_advanced_options: bool,
+    pub show_unattend_settings: bool,
+    // 当前镜像命中的服务器安装预设
+    pub active_image_preset: Option<crate::download::config::ImagePreset>,
+    // 预设匹配去重（镜像路径+分卷，变化时才重新匹配）
+    pub last_preset_image_key: Option<String>,
+    // 所选分卷的版本、架构与本机兼容性
+    pub image_compat: crate::ui::system_install::ImageCompatState,
     pub storage_driver_default_target: Option<String>,
 
     // 安装相关
@@ -244,6 +312,12 @@ pub struct App {
     pub download_progress: Option<DownloadProgress>,
     pub pending_download_url: Option<String>,
     pub pending_download_filename: Option<String>,
+    /// 待下载镜像的分块清单地址（有旧版本时用于增量下载）
+    pub pending_chunk_manifest_url: Option<String>,
+    /// 当前增量下载复用的旧版本文件
+    pub delta_base_file: Option<String>,
+    /// 下载引擎已在完成前校验过哈希，下载页面无需再次校验
+    pub download_hash_checked: bool,
     pub download_save_path: String,
 
     // 安装进度
@@ -261,10 +335,17 @@ pub struct App {
     pub backup_mode: BackupMode,
     pub backup_format: BackupFormat,
     pub backup_swm_split_size: u32,  // SWM分卷大小（MB）
+    /// 备份压缩方式
+    pub backup_compression: crate::core::capture_options::CaptureCompression,
+    /// 备份排除规则（每行一条，WimScript.ini 格式）
+    pub backup_exclusions: String,
+    /// 定时备份设置页
+    pub backup_schedule: crate::ui::backup_schedule::BackupScheduleState,
 
     // 工具箱
     pub tool_message: String,
     pub tool_target_partition: Option<String>,
+    pub tools_search: String,
     
     // 一键修复引导对话框
     pub show_repair_boot_dialog: bool,
@@ -275,8 +356,7 @@ pub struct App {
     // tokio 运行时
     pub runtime: tokio::runtime::Runtime,
 
-    // 下载管理器
-    pub download_manager: Arc<Mutex<Option<DownloadManager>>>,
+    // 下载任务
     pub download_gid: Option<String>,
     pub download_progress_rx: Option<Receiver<DownloadProgress>>,
     pub download_init_error: Option<String>,
@@ -288,6 +368,10 @@ pub struct App {
     // 安装进度通道
     pub install_progress_rx: Option<Receiver<DismProgress>>,
     pub install_error: Option<String>,
+    // 安装开始时间（用于安装统计）
+    pub install_started_at: Option<std::time::Instant>,
+    // 本次安装统计是否已上报
+    pub install_report_sent: bool,
     
     // 自动重启标志（防止重复触发）
     pub auto_reboot_triggered: bool,
@@ -295,6 +379,10 @@ pub struct App {
     // ISO 挂载状态
     pub iso_mounting: bool,
     pub iso_mount_error: Option<String>,
+    // 安装页面挂载的 ISO 路径（安装结束或更换镜像时卸载）
+    pub mounted_iso_path: Option<String>,
+    // 挂载的 ISO 中找到的安装镜像
+    pub iso_install_images: Vec<String>,
     
     // 镜像信息加载状态
     pub image_info_loading: bool,
@@ -330,6 +418,11 @@ pub struct App {
     pub soft_icon_cache: std::collections::HashMap<String, SoftIconState>,
     pub soft_icon_loading: std::collections::HashSet<String>,
     
+    // 日志控制台
+    pub show_log_console: bool,
+    pub log_console_level: tracing::Level,
+    pub log_console_auto_scroll: bool,
+    
     // 错误对话框
     pub show_error_dialog: bool,
     pub error_dialog_message: String,
@@ -450,19 +543,133 @@ pub struct App {
     pub image_verify_result_rx: Option<Receiver<crate::ui::tools::ImageVerifyResult>>,
     pub image_verify_cancel_flag: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
     
+    // 自加密硬盘解锁对话框
+    pub show_sed_unlock_dialog: bool,
+    pub sed_unlock_loading: bool,
+    pub sed_unlock_message: String,
+    pub sed_unlock_drives: Vec<crate::core::opal::OpalDrive>,
+    pub sed_unlock_selected: Option<usize>,
+    pub sed_unlock_password: String,
+    pub sed_unlock_psid: String,
+    pub sed_unlock_psid_confirm: bool,
+    pub sed_unlock_drives_rx: Option<Receiver<Result<Vec<crate::core::opal::OpalDrive>, String>>>,
+    pub sed_unlock_result_rx: Option<Receiver<Result<String, String>>>,
+    
+    // 存储驱动包管理对话框
+    pub show_driver_packs_dialog: bool,
+    pub driver_packs_loading: bool,
+    pub driver_packs_message: String,
+    pub driver_packs_rx: Option<Receiver<Result<String, String>>>,
+    
+    // 恢复引导配置对话框
+    pub show_bcd_restore_dialog: bool,
+    pub bcd_restore_loading: bool,
+    pub bcd_restore_message: String,
+    pub bcd_backups: Vec<crate::core::bcdedit::BcdBackup>,
+    pub bcd_restore_selected: Option<usize>,
+    pub bcd_restore_confirm: bool,
+    pub bcd_restore_rx: Option<Receiver<Result<String, String>>>,
+    
+    // 驱动器优化对话框
+    pub show_drive_optimize_dialog: bool,
+    pub drive_optimize_loading: bool,
+    pub drive_optimize_message: String,
+    pub drive_optimize_volumes: Vec<crate::ui::tools::drive_optimize::OptimizableVolume>,
+    pub drive_optimize_selected: Option<usize>,
+    pub drive_optimize_progress: Option<(u8, String)>,
+    pub drive_optimize_rx: Option<Receiver<crate::ui::tools::drive_optimize::OptimizeProgress>>,
+    
+    // 注册表配置单元恢复对话框
+    pub show_hive_restore_dialog: bool,
+    pub hive_restore_loading: bool,
+    pub hive_restore_message: String,
+    pub hive_restore_image_path: String,
+    pub hive_restore_images: Vec<ImageInfo>,
+    pub hive_restore_images_rx: Option<Receiver<Result<Vec<ImageInfo>, String>>>,
+    pub hive_restore_index: u32,
+    pub hive_restore_hive: crate::core::hive_restore::Hive,
+    pub hive_restore_target: Option<String>,
+    pub hive_restore_confirm: bool,
+    pub hive_restore_rx: Option<Receiver<Result<String, String>>>,
+    
+    // 从备份提取文件对话框
+    pub show_wim_browser_dialog: bool,
+    pub wim_browser_state: crate::ui::tools::WimBrowserDialogState,
+    
+    // 镜像格式转换对话框
+    pub show_image_convert_dialog: bool,
+    pub image_convert_state: crate::ui::tools::ImageConvertDialogState,
+    
+    // 镜像分卷管理对话框
+    pub show_wim_manage_dialog: bool,
+    pub wim_manage_state: crate::ui::tools::WimManageDialogState,
+    
+    // 一键恢复环境对话框
+    pub show_recovery_env_dialog: bool,
+    pub recovery_env_state: crate::ui::tools::RecoveryEnvDialogState,
+    
+    // 局域网分发对话框
+    pub show_netcast_dialog: bool,
+    pub netcast_state: crate::ui::tools::NetcastDialogState,
+    
+    // 引导项编辑对话框
+    pub show_boot_editor_dialog: bool,
+    pub boot_editor_state: crate::ui::tools::BootEditorDialogState,
+    
     // 应用配置（小白模式等）
     pub app_config: crate::core::app_config::AppConfig,
     
+    // 待接受的镜像许可协议及接受后的操作
+    pub pending_eula: Option<(crate::download::config::ImageEula, EulaAction)>,
+    pub eula_agree_checked: bool,
+    
+    // 技师锁定（解锁仅对本次运行有效）
+    pub technician_unlocked: bool,
+    pub pending_technician_action: Option<TechnicianAction>,
+    pub technician_pin_input: String,
+    pub technician_unlock_message: String,
+    pub technician_throttle: crate::core::tech_lock::UnlockThrottle,
+    pub technician_new_pin: String,
+    pub technician_new_pin_confirm: String,
+    pub technician_settings_message: String,
+    
+    // 内置帮助窗口当前主题
+    pub help_topic: Option<crate::download::help::HelpTopic>,
+    
+    // 运行环境检测向导
+    pub show_diagnostic_wizard: bool,
+    pub diagnostic_items: Vec<crate::core::diagnostics::DiagnosticItem>,
+    pub diagnostic_rx: Option<Receiver<Vec<crate::core::diagnostics::DiagnosticItem>>>,
+    
+    // 拖放到窗口上的镜像文件（等待用户选择操作）
+    pub dropped_image: Option<crate::ui::drop_image::DroppedImage>,
+    
+    // 运营方品牌定制
+    pub branding: crate::core::branding::Branding,
+    // 品牌定制是否已应用到窗口标题和主题
+    pub branding_applied: bool,
+    
+    // 当前显示器布局标识及最近一次的窗口状态（退出时保存）
+    pub window_layout_key: String,
+    pub window_state: Option<crate::core::window_state::WindowState>,
+    
     // PE下载待校验的MD5
     pub pending_pe_md5: Option<String>,
     
-    // MD5校验状态
-    pub md5_verify_state: crate::ui::download_progress::Md5VerifyState,
+    // 下载文件哈希校验状态
+    pub hash_verify_state: crate::ui::download_progress::HashVerifyState,
     
     // 小白模式相关
     pub easy_mode_selected_system: Option<usize>,
     pub easy_mode_selected_volume: Option<usize>,
-    pub easy_mode_show_confirm_dialog: bool,
+    /// 小白模式向导当前步骤
+    pub easy_mode_step: EasyModeStep,
+    /// 小白模式目标分区（partitions 索引）
+    pub easy_mode_target_partition: Option<usize>,
+    /// 小白模式安装选项
+    pub easy_mode_options: EasyModeOptions,
+    /// 小白模式确认页"已备份数据"勾选
+    pub easy_mode_confirm_checked: bool,
     pub easy_mode_system_logo_cache: HashMap<String, EasyModeLogoState>,
     pub easy_mode_logo_loading: HashSet<String>,
     /// 小白模式自动安装标志：下载完成后自动开始安装
@@ -536,6 +743,76 @@ pub struct App {
     pub bitlocker_decryption_needed: bool,
 }
 
+/// 小白模式向导步骤
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EasyModeStep {
+    #[default]
+    SelectImage,      // 选择镜像
+    SelectPartition,  // 选择分区
+    Options,          // 选项
+    Confirm,          // 确认
+    Execute,          // 执行
+}
+
+impl EasyModeStep {
+    /// 全部步骤（按顺序）
+    pub const ALL: [EasyModeStep; 5] = [
+        EasyModeStep::SelectImage,
+        EasyModeStep::SelectPartition,
+        EasyModeStep::Options,
+        EasyModeStep::Confirm,
+        EasyModeStep::Execute,
+    ];
+
+    /// 步骤名称
+    pub fn title(&self) -> &'static str {
+        match self {
+            EasyModeStep::SelectImage => "选择镜像",
+            EasyModeStep::SelectPartition => "选择分区",
+            EasyModeStep::Options => "选项",
+            EasyModeStep::Confirm => "确认",
+            EasyModeStep::Execute => "执行",
+        }
+    }
+
+    /// 步骤序号（从0开始）
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    /// 下一步
+    pub fn next(&self) -> Self {
+        Self::ALL.get(self.index() + 1).copied().unwrap_or(*self)
+    }
+
+    /// 上一步
+    pub fn prev(&self) -> Self {
+        self.index().checked_sub(1).map(|i| Self::ALL[i]).unwrap_or(*self)
+    }
+}
+
+/// 小白模式安装选项（默认全部开启）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EasyModeOptions {
+    pub bypass_nro: bool,
+    pub remove_uwp_apps: bool,
+    pub import_storage_drivers: bool,
+    pub keep_current_drivers: bool,
+    pub auto_reboot: bool,
+}
+
+impl Default for EasyModeOptions {
+    fn default() -> Self {
+        Self {
+            bypass_nro: true,
+            remove_uwp_apps: true,
+            import_storage_drivers: true,
+            keep_current_drivers: true,
+            auto_reboot: true,
+        }
+    }
+}
+
 /// 小白模式Logo状态
 #[derive(Clone)]
 pub enum EasyModeLogoState {
@@ -576,6 +853,22 @@ pub enum PeDownloadThenAction {
     Backup,   // 继续备份
 }
 
+/// 接受许可协议后要执行的操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EulaAction {
+    OnlineDownload(usize),  // 下载在线镜像（索引）
+    OnlineInstall(usize),   // 下载后安装在线镜像（索引）
+    LocalInstall,           // 安装本地镜像
+}
+
+/// 输入技师 PIN 后要执行的操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TechnicianAction {
+    OpenTool(crate::ui::tools::catalog::ToolId),  // 打开会修改本机的工具
+    LocalInstall,                                 // 安装系统
+    Settings,                                     // 修改设置
+}
+
 /// BitLocker解锁模式
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum BitLockerUnlockMode {
@@ -603,11 +896,20 @@ impl Default for App {
             current_panel: Panel::SystemInstall,
             system_info: None,
             hardware_info: None,
+            raid_controllers: Vec::new(),
+            raid_missing_drivers: Vec::new(),
+            raid_controllers_rx: None,
+            driver_packs: Vec::new(),
             hardware_info_loading: false,
             partitions: Vec::new(),
             selected_partition: None,
+            device_watcher: None,
+            pending_device_partitions: None,
             config: None,
             selected_online_system: None,
+            local_images: crate::download::local_catalog::LocalCatalog::load(),
+            show_unsupported_images: false,
+            torrent_link_input: String::new(),
             remote_config: None,
             remote_config_loading: false,
             selected_pe_for_install: None,
@@ -615,17 +917,37 @@ impl Default for App {
             local_image_path: String::new(),
             image_volumes: Vec::new(),
             selected_volume: None,
+            restore_points: Default::default(),
+            image_library: Default::default(),
+            gho_partitions: Vec::new(),
+            selected_gho_partition: 0,
+            gho_partitions_rx: None,
             last_is_win7: None,
             last_is_uefi_mode: None,
             format_partition: true,
             repair_boot: true,
             unattended_install: true,
             export_drivers: true,
-            auto_reboot: false,
+            completion_action: CompletionAction::StayInPe,
             selected_boot_mode: BootModeSelection::Auto,
             driver_action: DriverAction::AutoImport,
+            harvest_drivers: false,
+            hwid_drivers: false,
+            wifi_profiles: false,
+            share_user: String::new(),
+            share_password: String::new(),
+            network_volume_index: 1,
+            share_image_readable: false,
+            apply_verify: crate::core::apply_verify::ApplyVerifyMode::default(),
+            vhd_install_enabled: false,
+            vhd_install_file_name: "LetRecovery.vhdx".to_string(),
+            vhd_install_size_gb: 60,
             advanced_options: AdvancedOptions::default(),
             show_advanced_options: false,
+            show_unattend_settings: false,
+            active_image_preset: None,
+            last_preset_image_key: None,
+            image_compat: Default::default(),
             storage_driver_default_target: None,
             install_options: InstallOptions::default(),
             install_target_partition: String::new(),
@@ -639,6 +961,9 @@ impl Default for App {
             download_progress: None,
             pending_download_url: None,
             pending_download_filename: None,
+            pending_chunk_manifest_url: None,
+            delta_base_file: None,
+            download_hash_checked: false,
             download_save_path: String::new(),
             install_progress: InstallProgress::default(),
             is_installing: false,
@@ -652,14 +977,17 @@ impl Default for App {
             backup_mode: BackupMode::Direct,
             backup_format: BackupFormat::Wim,
             backup_swm_split_size: 4096,  // 默认4GB分卷
+            backup_compression: crate::core::capture_options::CaptureCompression::default(),
+            backup_exclusions: crate::core::capture_options::DEFAULT_EXCLUSIONS.join("\n"),
+            backup_schedule: Default::default(),
             tool_message: String::new(),
             tool_target_partition: None,
+            tools_search: String::new(),
             show_repair_boot_dialog: false,
             repair_boot_loading: false,
             repair_boot_message: String::new(),
             repair_boot_selected_partition: None,
             runtime,
-            download_manager: Arc::new(Mutex::new(None)),
             download_gid: None,
             download_progress_rx: None,
             download_init_error: None,
@@ -667,9 +995,13 @@ impl Default for App {
             backup_error: None,
             install_progress_rx: None,
             install_error: None,
+            install_started_at: None,
+            install_report_sent: false,
             auto_reboot_triggered: false,
             iso_mounting: false,
             iso_mount_error: None,
+            mounted_iso_path: None,
+            iso_install_images: Vec::new(),
             image_info_loading: false,
             pe_downloading: false,
             pe_download_error: None,
@@ -686,6 +1018,9 @@ impl Default for App {
             pending_soft_download: None,
             soft_icon_cache: HashMap::new(),
             soft_icon_loading: HashSet::new(),
+            show_log_console: false,
+            log_console_level: tracing::Level::INFO,
+            log_console_auto_scroll: true,
             show_error_dialog: false,
             error_dialog_message: String::new(),
             show_network_info_dialog: false,
@@ -782,16 +1117,92 @@ impl Default for App {
             image_verify_progress_rx: None,
             image_verify_result_rx: None,
             image_verify_cancel_flag: None,
+            show_sed_unlock_dialog: false,
+            sed_unlock_loading: false,
+            sed_unlock_message: String::new(),
+            sed_unlock_drives: Vec::new(),
+            sed_unlock_selected: None,
+            sed_unlock_password: String::new(),
+            sed_unlock_psid: String::new(),
+            sed_unlock_psid_confirm: false,
+            sed_unlock_drives_rx: None,
+            sed_unlock_result_rx: None,
+            show_driver_packs_dialog: false,
+            driver_packs_loading: false,
+            driver_packs_message: String::new(),
+            driver_packs_rx: None,
+            show_bcd_restore_dialog: false,
+            bcd_restore_loading: false,
+            bcd_restore_message: String::new(),
+            bcd_backups: Vec::new(),
+            bcd_restore_selected: None,
+            bcd_restore_confirm: false,
+            bcd_restore_rx: None,
+            show_drive_optimize_dialog: false,
+            drive_optimize_loading: false,
+            drive_optimize_message: String::new(),
+            drive_optimize_volumes: Vec::new(),
+            drive_optimize_selected: None,
+            drive_optimize_progress: None,
+            drive_optimize_rx: None,
+            show_hive_restore_dialog: false,
+            hive_restore_loading: false,
+            hive_restore_message: String::new(),
+            hive_restore_image_path: String::new(),
+            hive_restore_images: Vec::new(),
+            hive_restore_images_rx: None,
+            hive_restore_index: 1,
+            hive_restore_hive: crate::core::hive_restore::Hive::Software,
+            hive_restore_target: None,
+            hive_restore_confirm: false,
+            hive_restore_rx: None,
+            show_wim_browser_dialog: false,
+            wim_browser_state: crate::ui::tools::WimBrowserDialogState::default(),
+            show_image_convert_dialog: false,
+            image_convert_state: crate::ui::tools::ImageConvertDialogState::default(),
+            show_wim_manage_dialog: false,
+            wim_manage_state: crate::ui::tools::WimManageDialogState::default(),
+            show_recovery_env_dialog: false,
+            recovery_env_state: crate::ui::tools::RecoveryEnvDialogState::default(),
+            show_netcast_dialog: false,
+            netcast_state: crate::ui::tools::NetcastDialogState::default(),
+            show_boot_editor_dialog: false,
+            boot_editor_state: crate::ui::tools::BootEditorDialogState::default(),
             // 应用配置（小白模式等）
             app_config: crate::core::app_config::AppConfig::load(),
+            pending_eula: None,
+            eula_agree_checked: false,
+            technician_unlocked: false,
+            pending_technician_action: None,
+            technician_pin_input: String::new(),
+            technician_unlock_message: String::new(),
+            technician_throttle: Default::default(),
+            technician_new_pin: String::new(),
+            technician_new_pin_confirm: String::new(),
+            technician_settings_message: String::new(),
+            help_topic: None,
+            show_diagnostic_wizard: false,
+            diagnostic_items: Vec::new(),
+            diagnostic_rx: None,
+            dropped_image: None,
+            // 运营方品牌定制（先加载本地配置，远程配置到达后再合并）
+            branding: crate::core::branding::Branding::resolve(None),
+            branding_applied: false,
+            window_layout_key: crate::core::window_state::layout_key(
+                &crate::core::window_state::enumerate_monitors(),
+            ),
+            window_state: None,
             // PE下载待校验的MD5
             pending_pe_md5: None,
-            // MD5校验状态
-            md5_verify_state: crate::ui::download_progress::Md5VerifyState::NotStarted,
+            // 下载文件哈希校验状态
+            hash_verify_state: crate::ui::download_progress::HashVerifyState::NotStarted,
             // 小白模式相关
             easy_mode_selected_system: None,
             easy_mode_selected_volume: None,
-            easy_mode_show_confirm_dialog: false,
+            easy_mode_step: EasyModeStep::default(),
+            easy_mode_target_partition: None,
+            easy_mode_options: EasyModeOptions::default(),
+            easy_mode_confirm_checked: false,
             easy_mode_system_logo_cache: HashMap::new(),
             easy_mode_logo_loading: HashSet::new(),
             easy_mode_auto_install: false,
@@ -839,9 +1250,12 @@ impl App {
 
         // 设置视觉样式
         Self::setup_style(&cc.egui_ctx);
+        crate::ui::touch::apply_style(&cc.egui_ctx);
+        cc.egui_ctx.set_zoom_factor(crate::utils::dpi::ui_zoom());
 
         let mut app = Self::default();
         app.load_initial_data();
+        app.start_device_watcher(&cc.egui_ctx);
         app
     }
 
@@ -856,12 +1270,15 @@ impl App {
         // 设置视觉样式
         log::info!("设置样式...");
         Self::setup_style(&cc.egui_ctx);
+        crate::ui::touch::apply_style(&cc.egui_ctx);
+        cc.egui_ctx.set_zoom_factor(crate::utils::dpi::ui_zoom());
 
         log::info!("创建App实例...");
         let mut app = Self::default();
         
         log::info!("加载预加载数据...");
         app.load_initial_data_with_preloaded(preloaded);
+        app.start_device_watcher(&cc.egui_ctx);
         
         log::info!("App::new_with_preloaded 完成");
         app
@@ -946,16 +1363,74 @@ impl App {
         ctx.options_mut(|o| *o = options);
     }
 
+    /// 应用品牌定制（窗口标题、主题色）
+    fn apply_branding(&mut self, ctx: &egui::Context) {
+        self.branding_applied = true;
+
+        ctx.send_viewport_cmd(egui::ViewportCommand::Title(self.branding.window_title()));
+
+        if let Some([r, g, b]) = self.branding.theme_rgb() {
+            let accent = egui::Color32::from_rgb(r, g, b);
+            ctx.all_styles_mut(|style| {
+                style.visuals.selection.bg_fill = accent;
+                style.visuals.hyperlink_color = accent;
+            });
+        }
+
+        if !self.branding.is_default() {
+            log::info!("已应用品牌定制: {}", self.branding.title());
+        }
+    }
+
+    /// 记录当前窗口位置和大小
+    ///
+    /// 最大化或最小化时只更新最大化标记，保留还原后的位置和大小
+    fn track_window_state(&mut self, ctx: &egui::Context) {
+        let (outer, inner, maximized, minimized) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.outer_rect,
+                viewport.inner_rect,
+                viewport.maximized.unwrap_or(false),
+                viewport.minimized.unwrap_or(false),
+            )
+        });
+
+        if minimized {
+            return;
+        }
+
+        if maximized {
+            if let Some(state) = self.window_state.as_mut() {
+                state.maximized = true;
+            }
+            return;
+        }
+
+        if let (Some(outer), Some(inner)) = (outer, inner) {
+            self.window_state = Some(crate::core::window_state::WindowState {
+                x: outer.min.x,
+                y: outer.min.y,
+                width: inner.width(),
+                height: inner.height(),
+                maximized: false,
+            });
+        }
+    }
+
     fn load_initial_data(&mut self) {
         // 加载系统信息
         self.system_info = SystemInfo::collect().ok();
 
         // 加载硬件信息
         self.hardware_info = crate::core::hardware_info::HardwareInfo::collect().ok();
+        self.tune_wimlib();
 
         // 加载分区列表
         self.partitions = crate::core::disk::DiskManager::get_partitions().unwrap_or_default();
 
+        self.detect_raid_controllers();
+
         // 判断是否为PE环境
         let is_pe = self.system_info.as_ref().map(|s| s.is_pe_environment).unwrap_or(false);
         
@@ -1010,9 +1485,17 @@ impl App {
 
         // 使用预加载的硬件信息（可能为 None，稍后异步加载）
         self.hardware_info = preloaded.hardware_info.clone();
+        self.tune_wimlib();
 
         // 使用预加载的分区列表
         self.partitions = preloaded.partitions.clone();
+
+        // 首次启动或存在无法继续的问题时显示运行环境检测向导
+        self.diagnostic_items = preloaded.diagnostics.clone();
+        self.show_diagnostic_wizard = !self.app_config.diagnostic_completed
+            || crate::core::diagnostics::has_blocking(&self.diagnostic_items);
+
+        self.detect_raid_controllers();
         
         // 如果系统信息或硬件信息为空，启动异步加载
         if self.system_info.is_none() || self.hardware_info.is_none() {
@@ -1049,13 +1532,10 @@ impl App {
             self.remote_config_loading = false;
             
             if remote_config.loaded {
-                self.config = Some(ConfigManager::load_from_content_full_with_gpu(
-                    remote_config.dl_content.as_deref(),
-                    remote_config.pe_content.as_deref(),
-                    remote_config.soft_content.as_deref(),
-                    remote_config.easy_content.as_deref(),
-                    remote_config.gpu_content.as_deref(),
-                ));
+                self.config = Some(ConfigManager::from_remote(remote_config));
+                self.last_preset_image_key = None;
+                self.branding = crate::core::branding::Branding::resolve(remote_config.branding_content.as_deref());
+                self.branding_applied = false;
                 log::info!("使用预加载的远程配置");
                 
                 // 成功获取云端PE配置后，保存到本地缓存（不含下载链接）
@@ -1164,6 +1644,7 @@ impl App {
                         }
                         if self.hardware_info.is_none() {
                             self.hardware_info = result.hardware_info;
+                            self.tune_wimlib();
                         }
                         
                         // 清除接收端，避免重复处理
@@ -1174,6 +1655,57 @@ impl App {
         }
     }
     
+    /// 启动设备变化监视（演示模式下分区是模拟的，不监视）
+    fn start_device_watcher(&mut self, ctx: &egui::Context) {
+        if crate::core::demo::is_enabled() {
+            return;
+        }
+        let ctx = ctx.clone();
+        // 监视失败时为 None，安装页面显示"刷新分区"按钮供手动刷新
+        self.device_watcher = crate::core::watcher::DeviceWatcher::start(move || ctx.request_repaint());
+    }
+
+    /// 应用设备变化后刷新的分区列表和硬件信息
+    fn process_device_events(&mut self) {
+        use crate::core::watcher::DeviceEvent;
+
+        while let Some(event) = self.device_watcher.as_ref().and_then(|w| w.try_recv()) {
+            match event {
+                DeviceEvent::Partitions(partitions) => self.pending_device_partitions = Some(partitions),
+                DeviceEvent::Hardware(hardware_info) => {
+                    self.hardware_info = Some(hardware_info);
+                    self.tune_wimlib();
+                }
+            }
+        }
+
+        // 安装/备份进行中不改变分区索引，任务结束后再应用
+        if self.is_installing || self.is_backing_up {
+            return;
+        }
+        if let Some(partitions) = self.pending_device_partitions.take() {
+            self.apply_device_partitions(partitions);
+        }
+    }
+
+    /// 替换分区列表，按盘符保留已选分区（所选分区已移除时取消选择）
+    fn apply_device_partitions(&mut self, partitions: Vec<Partition>) {
+        let remap = |old: &[Partition], index: Option<usize>| -> Option<usize> {
+            let letter = &old.get(index?)?.letter;
+            partitions.iter().position(|p| &p.letter == letter)
+        };
+        self.selected_partition = remap(&self.partitions, self.selected_partition);
+        self.backup_source_partition = remap(&self.partitions, self.backup_source_partition);
+        self.easy_mode_target_partition = remap(&self.partitions, self.easy_mode_target_partition);
+        log::info!("[WATCHER] 分区列表已更新: {} -> {} 个分区", self.partitions.len(), partitions.len());
+
+        self.partitions = partitions;
+        if self.selected_partition.is_none() {
+            self.partition_has_unattend = false;
+        }
+        self.refresh_windows_partitions_cache();
+    }
+
     /// 开始异步加载远程配置
     pub fn start_remote_config_loading(&mut self) {
         use std::sync::mpsc;
@@ -1193,6 +1725,39 @@ impl App {
         });
     }
     
+    /// 根据当前镜像匹配服务器下发的安装预设
+    ///
+    /// 仅在镜像路径或所选分卷变化时重新匹配，命中后写入高级选项，
+    /// 之后用户的手动修改会保留并在高级选项窗口中提示
+    pub fn update_image_preset(&mut self) {
+        let key = format!("{}|{:?}", self.local_image_path, self.selected_volume);
+        if self.last_preset_image_key.as_deref() == Some(key.as_str()) {
+            return;
+        }
+        self.last_preset_image_key = Some(key);
+
+        let file_name = std::path::Path::new(&self.local_image_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let volume_name = self
+            .selected_volume
+            .and_then(|idx| self.image_volumes.get(idx))
+            .map(|img| img.name.clone())
+            .unwrap_or_default();
+
+        let preset = self.config.as_ref().and_then(|c| {
+            c.find_image_preset(&[file_name.as_str(), volume_name.as_str()])
+                .cloned()
+        });
+
+        if let Some(ref preset) = preset {
+            log::info!("[PRESET] 镜像 {} 命中预设: {}", file_name, preset.name);
+            self.advanced_options.apply_preset(preset);
+        }
+        self.active_image_preset = preset;
+    }
+    
     /// 检查远程配置加载状态
     pub fn check_remote_config_loading(&mut self) {
         if !self.remote_config_loading {
@@ -1205,13 +1770,10 @@ impl App {
                 self.remote_config_rx = None;
                 
                 if remote_config.loaded {
-                    self.config = Some(ConfigManager::load_from_content_full_with_gpu(
-                        remote_config.dl_content.as_deref(),
-                        remote_config.pe_content.as_deref(),
-                        remote_config.soft_content.as_deref(),
-                        remote_config.easy_content.as_deref(),
-                        remote_config.gpu_content.as_deref(),
-                    ));
+                    self.config = Some(ConfigManager::from_remote(&remote_config));
+                    self.last_preset_image_key = None;
+                    self.branding = crate::core::branding::Branding::resolve(remote_config.branding_content.as_deref());
+                    self.branding_applied = false;
                     log::info!("远程配置加载成功");
                     
                     // 成功获取云端PE配置后，保存到本地缓存（不含下载链接）
@@ -1286,6 +1848,47 @@ impl App {
         self.system_info.as_ref().map(|s| s.is_pe_environment).unwrap_or(false)
     }
 
+    /// 按 CPU 逻辑处理器数设置 wimlib 捕获时的压缩线程数（硬件信息未就绪时按系统报告的并行度）
+    fn tune_wimlib(&self) {
+        let logical_processors = self
+            .hardware_info
+            .as_ref()
+            .map(|hw| hw.cpu.logical_processors)
+            .filter(|&n| n > 0)
+            .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get() as u32))
+            .unwrap_or(0);
+        crate::core::wimlib::set_tuning(crate::core::wimlib::WimlibTuning::for_cpu(logical_processors));
+    }
+
+    /// 后台检测 Intel VMD / RST RAID 控制器及驱动包是否包含对应驱动，并刷新本地驱动包列表
+    pub fn detect_raid_controllers(&mut self) {
+        use crate::core::storage_controller;
+
+        let (tx, rx) = mpsc::channel();
+        self.raid_controllers_rx = Some(rx);
+        std::thread::spawn(move || {
+            let controllers = storage_controller::detect_controllers();
+            let missing = storage_controller::missing_drivers(&controllers);
+            let _ = tx.send((controllers, missing));
+        });
+        self.driver_packs = crate::core::driver_pack::list_packs();
+    }
+
+    /// 应用后台检测到的 RAID 控制器
+    fn process_raid_detection(&mut self) {
+        let Some(ref rx) = self.raid_controllers_rx else {
+            return;
+        };
+        if let Ok((controllers, missing)) = rx.try_recv() {
+            for controller in &missing {
+                log::warn!("[STORAGE] 驱动包中缺少 {} 的驱动", controller.display_name());
+            }
+            self.raid_controllers = controllers;
+            self.raid_missing_drivers = missing;
+            self.raid_controllers_rx = None;
+        }
+    }
+
     /// 显示错误对话框
     pub fn show_error(&mut self, message: &str) {
         self.error_dialog_message = message.to_string();
@@ -1300,6 +1903,12 @@ impl eframe::App for App {
         
         // 处理异步加载的系统/硬件信息
         self.process_async_info_results();
+
+        // 处理设备变化后刷新的分区列表和硬件信息
+        self.process_device_events();
+
+        // 处理后台检测到的 RAID 控制器
+        self.process_raid_detection();
         
         // 处理图标加载结果
         self.process_icon_load_results(ctx);
@@ -1309,6 +1918,25 @@ impl eframe::App for App {
         
         // 检查工具箱异步操作结果
         self.check_tools_async_operations();
+
+        // 推进后台任务进度（切换页面后继续更新）
+        self.pump_background_tasks();
+        
+        // 应用品牌定制
+        if !self.branding_applied {
+            self.apply_branding(ctx);
+        }
+        
+        // 记录窗口状态
+        self.track_window_state(ctx);
+        
+        // 运行环境检测向导（存在无法继续的问题时不显示主界面）
+        if self.show_diagnostic_wizard {
+            self.show_diagnostic_dialog(ctx);
+            if crate::core::diagnostics::has_blocking(&self.diagnostic_items) {
+                return;
+            }
+        }
         
         // 错误对话框
         if self.show_error_dialog {
@@ -1317,13 +1945,14 @@ impl eframe::App for App {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ctx, |ui| {
+                    let keys = dialog_keys(ui);
                     ui.vertical_centered(|ui| {
                         ui.add_space(10.0);
                         ui.colored_label(egui::Color32::RED, "❌");
                         ui.add_space(10.0);
                         ui.label(&self.error_dialog_message);
                         ui.add_space(20.0);
-                        if ui.button("确定").clicked() {
+                        if ui.button("确定").clicked() || keys.enter || keys.escape {
                             self.show_error_dialog = false;
                             self.error_dialog_message.clear();
                         }
@@ -1340,6 +1969,7 @@ impl eframe::App for App {
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .min_width(400.0)
                 .show(ctx, |ui| {
+                    let keys = dialog_keys(ui);
                     ui.vertical_centered(|ui| {
                         ui.add_space(10.0);
                         ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⚠");
@@ -1370,7 +2000,7 @@ impl eframe::App for App {
                     ui.add_space(15.0);
                     
                     ui.vertical_centered(|ui| {
-                        if ui.button("我知道了").clicked() {
+                        if ui.button("我知道了").clicked() || keys.enter || keys.escape {
                             self.show_unattend_conflict_modal = false;
                         }
                     });
@@ -1392,6 +2022,22 @@ impl eframe::App for App {
                 self.render_backup_bitlocker_dialog(ui);
             });
 
+        // 演示模式提示条
+        if crate::core::demo::is_enabled() {
+            egui::TopBottomPanel::top("demo_banner").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        "演示模式：分区为模拟数据，格式化、安装、备份均不会修改真实磁盘",
+                    );
+                    if ui.small_button("重置模拟磁盘").clicked() {
+                        crate::core::demo::reset();
+                        self.refresh_partitions();
+                    }
+                });
+            });
+        }
+
         // 底部状态栏
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -1421,21 +2067,34 @@ impl eframe::App for App {
                         },
                     ));
                 }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.toggle_value(&mut self.show_log_console, "📋 日志");
+                });
             });
         });
 
+        // 后台任务栏（位于状态栏上方）
+        self.render_task_tray(ctx);
+
+        // 日志控制台（位于状态栏上方）
+        self.render_log_console(ctx);
+
         // 左侧导航栏
         egui::SidePanel::left("nav_panel")
             .min_width(150.0)
             .show(ctx, |ui| {
                 ui.vertical_centered(|ui| {
-                    ui.heading("LetRecovery");
+                    if let Some(logo_url) = self.branding.logo_url.clone() {
+                        self.draw_system_logo(ui, ctx, &logo_url, 48.0);
+                    }
+                    ui.heading(self.branding.title());
                 });
 
                 ui.add_space(20.0);
 
                 // 检查是否有操作正在进行
-                let is_busy = self.is_installing || self.is_backing_up || self.current_download.is_some();
+                let is_busy = self.is_busy();
                 
                 // 检查是否启用小白模式（PE环境下强制禁用）
                 let is_pe = self.system_info.as_ref()
@@ -1454,10 +2113,7 @@ impl eframe::App for App {
                 // 小白模式显示"系统重装"，普通模式显示"系统安装"
                 let system_install_label = if easy_mode { tr!("系统重装") } else { tr!("系统安装") };
                 if ui
-                    .add_enabled(
-                        !is_busy || self.current_panel == Panel::SystemInstall,
-                        egui::SelectableLabel::new(self.current_panel == Panel::SystemInstall, system_install_label),
-                    )
+                    .add(egui::SelectableLabel::new(self.current_panel == Panel::SystemInstall, system_install_label))
                     .clicked()
                 {
                     self.current_panel = Panel::SystemInstall;
@@ -1466,40 +2122,37 @@ impl eframe::App for App {
                 // 小白模式下隐藏以下菜单
                 if !easy_mode {
                     if ui
-                        .add_enabled(
-                            !is_busy || self.current_panel == Panel::SystemBackup,
-                            egui::SelectableLabel::new(self.current_panel == Panel::SystemBackup, tr!("系统备份")),
-                        )
+                        .add(egui::SelectableLabel::new(self.current_panel == Panel::SystemBackup, tr!("系统备份")))
                         .clicked()
                     {
                         self.current_panel = Panel::SystemBackup;
                     }
 
+                    // 计划任务只在正常系统中有意义
+                    if !is_pe
+                        && ui
+                            .add(egui::SelectableLabel::new(self.current_panel == Panel::BackupSchedule, tr!("定时备份")))
+                            .clicked()
+                    {
+                        self.current_panel = Panel::BackupSchedule;
+                    }
+
                     if ui
-                        .add_enabled(
-                            !is_busy || self.current_panel == Panel::OnlineDownload,
-                            egui::SelectableLabel::new(self.current_panel == Panel::OnlineDownload, tr!("在线下载")),
-                        )
+                        .add(egui::SelectableLabel::new(self.current_panel == Panel::OnlineDownload, tr!("在线下载")))
                         .clicked()
                     {
                         self.current_panel = Panel::OnlineDownload;
                     }
 
                     if ui
-                        .add_enabled(
-                            !is_busy || self.current_panel == Panel::Tools,
-                            egui::SelectableLabel::new(self.current_panel == Panel::Tools, tr!("工具箱")),
-                        )
+                        .add(egui::SelectableLabel::new(self.current_panel == Panel::Tools, tr!("工具箱")))
                         .clicked()
                     {
                         self.current_panel = Panel::Tools;
                     }
 
                     if ui
-                        .add_enabled(
-                            !is_busy || self.current_panel == Panel::HardwareInfo,
-                            egui::SelectableLabel::new(self.current_panel == Panel::HardwareInfo, tr!("硬件信息")),
-                        )
+                        .add(egui::SelectableLabel::new(self.current_panel == Panel::HardwareInfo, tr!("硬件信息")))
                         .clicked()
                     {
                         self.current_panel = Panel::HardwareInfo;
@@ -1507,10 +2160,7 @@ impl eframe::App for App {
                 }
 
                 if ui
-                    .add_enabled(
-                        !is_busy || self.current_panel == Panel::About,
-                        egui::SelectableLabel::new(self.current_panel == Panel::About, tr!("关于")),
-                    )
+                    .add(egui::SelectableLabel::new(self.current_panel == Panel::About, tr!("关于")))
                     .clicked()
                 {
                     self.current_panel = Panel::About;
@@ -1524,17 +2174,37 @@ impl eframe::App for App {
             .unwrap_or(false);
         let easy_mode_for_panel = self.app_config.easy_mode_enabled && !is_pe_for_panel;
         
+        // 有操作进行时仍可浏览其他页面，但禁止在页面中发起新的操作
+        let lock_actions = self.is_busy();
+
         egui::CentralPanel::default().show(ctx, |ui| match self.current_panel {
             Panel::SystemInstall => {
-                if easy_mode_for_panel {
-                    self.show_easy_mode_install(ui, ctx);
-                } else {
-                    self.show_system_install(ui);
-                }
+                self.show_announcements(ui);
+                show_busy_notice(ui, lock_actions);
+                ui.add_enabled_ui(!lock_actions, |ui| {
+                    if easy_mode_for_panel {
+                        self.show_easy_mode_install(ui, ctx);
+                    } else {
+                        self.show_system_install(ui);
+                    }
+                });
+            }
+            Panel::SystemBackup => {
+                show_busy_notice(ui, lock_actions);
+                ui.add_enabled_ui(!lock_actions, |ui| self.show_system_backup(ui));
+            }
+            Panel::BackupSchedule => {
+                show_busy_notice(ui, lock_actions);
+                ui.add_enabled_ui(!lock_actions, |ui| self.show_backup_schedule(ui));
+            }
+            Panel::OnlineDownload => {
+                show_busy_notice(ui, lock_actions);
+                ui.add_enabled_ui(!lock_actions, |ui| self.show_online_download(ui));
+            }
+            Panel::Tools => {
+                show_busy_notice(ui, lock_actions);
+                ui.add_enabled_ui(!lock_actions, |ui| self.show_tools(ui));
             }
-            Panel::SystemBackup => self.show_system_backup(ui),
-            Panel::OnlineDownload => self.show_online_download(ui),
-            Panel::Tools => self.show_tools(ui),
             Panel::HardwareInfo => self.show_hardware_info(ui),
             Panel::DownloadProgress => self.show_download_progress(ui),
             Panel::InstallProgress => self.show_install_progress(ui),
@@ -1542,6 +2212,26 @@ impl eframe::App for App {
             Panel::About => self.show_about(ui),
         });
 
+        // 镜像许可协议对话框
+        if self.pending_eula.is_some() {
+            self.show_eula_dialog(ctx);
+        }
+
+        // 技师 PIN 解锁对话框
+        if self.pending_technician_action.is_some() {
+            self.show_technician_unlock_dialog(ctx);
+        }
+
+        // 拖放镜像文件
+        self.handle_dropped_files(ctx);
+        if self.dropped_image.is_some() {
+            self.show_dropped_image_dialog(ctx, easy_mode_for_panel);
+        }
+        self.render_drop_overlay(ctx);
+
+        // 镜像或分卷变化时匹配服务器安装预设
+        self.update_image_preset();
+
         // 高级选项窗口
         if self.show_advanced_options {
             // 如果勾选了格式化，则不禁用无人值守相关选项
@@ -1653,6 +2343,11 @@ impl eframe::App for App {
                     }
                 }
                 
+                // 服务器预设优先于自动检测结果
+                if let Some(ref preset) = self.active_image_preset {
+                    self.advanced_options.apply_preset(preset);
+                }
+                
                 self.last_is_win7 = Some(is_win7);
                 self.last_is_uefi_mode = Some(is_uefi_mode);
             }
@@ -1662,17 +2357,80 @@ impl eframe::App for App {
                 .min_width(500.0)
                 .min_height(400.0)
                 .show(ctx, |ui| {
+                    if let Some(ref preset) = self.active_image_preset {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(100, 180, 255),
+                                format!("ℹ 已应用服务器推荐预设: {}", preset.name),
+                            );
+                        });
+                        let overrides = self.advanced_options.preset_overrides(preset);
+                        if !overrides.is_empty() {
+                            ui.horizontal_wrapped(|ui| {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 165, 0),
+                                    format!("已手动修改推荐项: {}", overrides.join("、")),
+                                );
+                                if ui.small_button("恢复推荐").clicked() {
+                                    self.advanced_options.apply_preset(preset);
+                                }
+                            });
+                        }
+                        ui.separator();
+                    }
                     self.advanced_options
-                        .show_ui(ui, self.hardware_info.as_ref(), unattend_disabled, is_win7, is_uefi_mode);
+                        .show_ui(
+                            ui,
+                            self.hardware_info.as_ref(),
+                            unattend_disabled,
+                            is_win7,
+                            is_uefi_mode,
+                            &self.app_config.recent_driver_dirs,
+                            &self.driver_packs,
+                        );
                 });
         }
 
+        // 无人值守设置窗口
+        if self.show_unattend_settings {
+            egui::Window::new("无人值守设置")
+                .open(&mut self.show_unattend_settings)
+                .min_width(460.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        crate::ui::unattend_settings::show_unattend_settings(
+                            ui,
+                            &mut self.advanced_options.unattend,
+                            self.unattended_install,
+                        );
+                    });
+                });
+        }
+
+        // 镜像库选择器
+        self.show_image_library_window(ctx);
+
+        // 帮助窗口（显示在其他窗口之上）
+        self.show_help_window(ctx);
+
         // 如果有正在进行的任务，定期刷新
         let tools_loading = self.windows_partitions_loading 
             || self.driver_backup_loading 
             || self.import_storage_driver_loading 
             || self.remove_appx_loading
             || self.gho_password_loading
+            || self.sed_unlock_loading
+            || self.driver_packs_loading
+            || self.bcd_restore_loading
+            || self.drive_optimize_loading
+            || self.hive_restore_loading
+            || self.wim_browser_state.is_loading()
+            || self.image_convert_state.is_loading()
+            || self.wim_manage_state.is_loading()
+            || self.recovery_env_state.is_loading()
+            || self.netcast_state.is_loading()
+            || self.backup_schedule.is_loading()
+            || self.boot_editor_state.is_loading()
             || self.nvidia_uninstall_loading
             || self.nvidia_uninstall_hardware_loading
             || self.partition_copy_partitions_loading
@@ -1685,8 +2443,21 @@ impl eframe::App for App {
         
         if self.is_installing || self.is_backing_up || self.current_download.is_some() 
             || self.iso_mounting || self.pe_downloading || self.remote_config_loading 
+            || self.gho_partitions_rx.is_some()
+            || self.raid_controllers_rx.is_some()
             || tools_loading {
             ctx.request_repaint_after(std::time::Duration::from_millis(100));
         }
     }
+
+    fn on_exit(&mut self) {
+        // 保存窗口状态
+        if let Some(state) = self.window_state {
+            self.app_config.save_window_state(&self.window_layout_key, state);
+        }
+        // 卸载安装页面挂载的 ISO
+        self.release_mounted_iso();
+        // 卸载挂载为虚拟驱动器的备份镜像
+        self.wim_browser_state.unmount_all();
+    }
 }
@@ -2,8 +2,14 @@
 //! 管理 config.json 配置文件，用于存储用户偏好设置
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::core::wimlib::ImageBackend;
+use crate::core::io_priority::{IoOptions, IoPriority};
+use crate::core::scheduler::BackupSchedule;
+use crate::core::tech_lock;
+use crate::core::window_state::WindowState;
 use crate::utils::path::get_exe_dir;
 
 /// 应用配置
@@ -32,6 +38,77 @@ pub struct AppConfig {
     /// 界面语言代码（默认 "zh-CN"）
     #[serde(default = "default_language")]
     pub language: String,
+    
+    /// 已关闭的公告ID
+    #[serde(default)]
+    pub dismissed_announcements: Vec<String>,
+    
+    /// 是否发送匿名安装统计（默认关闭，需用户主动开启）
+    #[serde(default)]
+    pub telemetry_enabled: bool,
+    
+    /// 工具箱收藏的工具标识
+    #[serde(default)]
+    pub favorite_tools: Vec<String>,
+    
+    /// 窗口状态（按显示器布局分别记录）
+    #[serde(default)]
+    pub window_states: HashMap<String, WindowState>,
+    
+    /// 最近使用的镜像文件
+    #[serde(default)]
+    pub recent_images: Vec<String>,
+    
+    /// 最近使用的备份保存位置
+    #[serde(default)]
+    pub recent_backup_paths: Vec<String>,
+    
+    /// 最近使用的驱动目录
+    #[serde(default)]
+    pub recent_driver_dirs: Vec<String>,
+    
+    /// 备份/复制的 I/O 优先级
+    #[serde(default)]
+    pub io_priority: IoPriority,
+    
+    /// 备份/复制限速（MB/s，0 表示不限速）
+    #[serde(default)]
+    pub copy_throttle_mb: u32,
+    
+    /// 镜像释放/捕获后端
+    #[serde(default)]
+    pub image_backend: ImageBackend,
+    
+    /// 技师 PIN 的盐
+    #[serde(default)]
+    pub technician_pin_salt: String,
+    
+    /// 技师 PIN 的哈希（为空表示未启用技师锁定）
+    #[serde(default)]
+    pub technician_pin_hash: String,
+    
+    /// 是否已完成首次运行环境检测
+    #[serde(default)]
+    pub diagnostic_completed: bool,
+    
+    /// 定时自动备份设置
+    #[serde(default)]
+    pub backup_schedule: BackupSchedule,
+}
+
+/// 最近使用列表最多保留的条数
+const MAX_RECENT_ITEMS: usize = 10;
+
+/// 将路径移到最近使用列表最前面（忽略大小写去重），返回列表是否变化
+fn push_recent(list: &mut Vec<String>, path: &str) -> bool {
+    let path = path.trim();
+    if path.is_empty() || list.first().is_some_and(|p| p.eq_ignore_ascii_case(path)) {
+        return false;
+    }
+    list.retain(|p| !p.eq_ignore_ascii_case(path));
+    list.insert(0, path.to_string());
+    list.truncate(MAX_RECENT_ITEMS);
+    true
 }
 
 /// 日志默认启用
@@ -58,6 +135,20 @@ impl Default for AppConfig {
             log_enabled: true,  // 日志默认启用
             log_retention_days: 7,  // 默认保留7天
             language: String::from("zh-CN"),  // 默认简体中文
+            dismissed_announcements: Vec::new(),
+            telemetry_enabled: false,
+            favorite_tools: Vec::new(),
+            window_states: HashMap::new(),
+            recent_images: Vec::new(),
+            recent_backup_paths: Vec::new(),
+            recent_driver_dirs: Vec::new(),
+            io_priority: IoPriority::Normal,
+            copy_throttle_mb: 0,
+            image_backend: ImageBackend::Auto,
+            technician_pin_salt: String::new(),
+            technician_pin_hash: String::new(),
+            diagnostic_completed: false,
+            backup_schedule: BackupSchedule::default(),
         }
     }
 }
@@ -157,6 +248,161 @@ impl AppConfig {
         }
     }
     
+    /// 关闭公告并保存
+    pub fn dismiss_announcement(&mut self, id: &str) {
+        if self.dismissed_announcements.iter().any(|d| d == id) {
+            return;
+        }
+        self.dismissed_announcements.push(id.to_string());
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 标记首次运行环境检测已完成并保存
+    pub fn mark_diagnostic_completed(&mut self) {
+        if self.diagnostic_completed {
+            return;
+        }
+        self.diagnostic_completed = true;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 设置匿名安装统计开关并保存
+    pub fn set_telemetry_enabled(&mut self, enabled: bool) {
+        self.telemetry_enabled = enabled;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 设置备份/复制的 I/O 优先级并保存
+    pub fn set_io_priority(&mut self, priority: IoPriority) {
+        self.io_priority = priority;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 设置备份/复制限速并保存
+    pub fn set_copy_throttle_mb(&mut self, mb_per_sec: u32) {
+        self.copy_throttle_mb = mb_per_sec;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 设置镜像释放/捕获后端并保存
+    pub fn set_image_backend(&mut self, backend: ImageBackend) {
+        self.image_backend = backend;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 设置定时备份并保存
+    pub fn set_backup_schedule(&mut self, schedule: BackupSchedule) {
+        self.backup_schedule = schedule;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 是否已设置技师 PIN
+    pub fn has_technician_pin(&self) -> bool {
+        !self.technician_pin_hash.is_empty()
+    }
+    
+    /// 设置技师 PIN 并保存
+    pub fn set_technician_pin(&mut self, pin: &str) -> anyhow::Result<()> {
+        let salt = tech_lock::new_salt()?;
+        self.technician_pin_hash = tech_lock::hash_pin(pin, &salt);
+        self.technician_pin_salt = salt;
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+        Ok(())
+    }
+    
+    /// 关闭技师锁定并保存
+    pub fn clear_technician_pin(&mut self) {
+        self.technician_pin_salt.clear();
+        self.technician_pin_hash.clear();
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 校验技师 PIN
+    pub fn verify_technician_pin(&self, pin: &str) -> bool {
+        tech_lock::verify_pin(pin, &self.technician_pin_salt, &self.technician_pin_hash)
+    }
+    
+    /// 备份/复制使用的 I/O 选项
+    pub fn io_options(&self) -> IoOptions {
+        IoOptions {
+            priority: self.io_priority,
+            throttle_mb_per_sec: self.copy_throttle_mb,
+        }
+    }
+    
+    /// 检查工具是否已收藏
+    pub fn is_favorite_tool(&self, key: &str) -> bool {
+        self.favorite_tools.iter().any(|k| k == key)
+    }
+    
+    /// 切换工具收藏状态并保存
+    pub fn toggle_favorite_tool(&mut self, key: &str) {
+        if self.is_favorite_tool(key) {
+            self.favorite_tools.retain(|k| k != key);
+        } else {
+            self.favorite_tools.push(key.to_string());
+        }
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 记录指定显示器布局下的窗口状态并保存
+    pub fn save_window_state(&mut self, layout_key: &str, state: WindowState) {
+        if self.window_states.get(layout_key) == Some(&state) {
+            return;
+        }
+        self.window_states.insert(layout_key.to_string(), state);
+        if let Err(e) = self.save() {
+            log::warn!("保存配置失败: {}", e);
+        }
+    }
+    
+    /// 记录最近使用的镜像文件
+    pub fn add_recent_image(&mut self, path: &str) {
+        if push_recent(&mut self.recent_images, path) {
+            if let Err(e) = self.save() {
+                log::warn!("保存配置失败: {}", e);
+            }
+        }
+    }
+    
+    /// 记录最近使用的备份保存位置
+    pub fn add_recent_backup_path(&mut self, path: &str) {
+        if push_recent(&mut self.recent_backup_paths, path) {
+            if let Err(e) = self.save() {
+                log::warn!("保存配置失败: {}", e);
+            }
+        }
+    }
+    
+    /// 记录最近使用的驱动目录
+    pub fn add_recent_driver_dir(&mut self, path: &str) {
+        if push_recent(&mut self.recent_driver_dirs, path) {
+            if let Err(e) = self.save() {
+                log::warn!("保存配置失败: {}", e);
+            }
+        }
+    }
+    
     /// 设置日志记录状态并保存
     pub fn set_log_enabled(&mut self, enabled: bool) {
         self.log_enabled = enabled;
@@ -234,3 +480,24 @@ pub fn get_current_username() -> Option<String> {
 pub fn get_current_username() -> Option<String> {
     std::env::var("USER").ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_recent() {
+        let mut list = Vec::new();
+        assert!(push_recent(&mut list, "D:\\a.wim"));
+        assert!(push_recent(&mut list, "D:\\b.iso"));
+        assert!(!push_recent(&mut list, "d:\\B.ISO"));
+        assert!(push_recent(&mut list, "d:\\A.WIM"));
+        assert_eq!(list, vec!["d:\\A.WIM".to_string(), "D:\\b.iso".to_string()]);
+
+        for i in 0..20 {
+            push_recent(&mut list, &format!("E:\\{}.gho", i));
+        }
+        assert_eq!(list.len(), MAX_RECENT_ITEMS);
+        assert_eq!(list[0], "E:\\19.gho");
+    }
+}
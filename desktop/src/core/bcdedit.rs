@@ -1,9 +1,226 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
+use crate::core::demo;
+use crate::core::disk::{DiskManager, PartitionStyle, TempDriveLetter};
+use crate::core::driver_signing::{self, SigningOverride};
+use crate::core::nt5_boot::{self, NT5_LOADER_FILES};
+use crate::core::system_utils::get_file_version;
 use crate::utils::cmd::create_command;
 use crate::utils::encoding::gbk_to_utf8;
-use crate::utils::path::get_bin_dir;
+use crate::utils::path::{get_bin_dir, get_exe_dir};
+
+/// 最多保留的 BCD 备份数量
+const MAX_BCD_BACKUPS: usize = 20;
+
+/// BCD 备份信息（与备份文件同名的 .json 文件）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BcdBackup {
+    /// 备份文件路径
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// 备份时间
+    pub created: String,
+    /// 备份原因
+    pub reason: String,
+    /// 备份来源的 BCD 存储文件，为空表示系统存储
+    #[serde(default)]
+    pub store: String,
+}
+
+/// BCD 备份目录
+pub fn bcd_backup_dir() -> PathBuf {
+    get_exe_dir().join("bcd_backup")
+}
+
+/// 备份文件名，同一秒内的多次备份追加序号
+fn backup_file_name(timestamp: &str, index: usize) -> String {
+    if index == 0 {
+        format!("BCD_{}", timestamp)
+    } else {
+        format!("BCD_{}_{}", timestamp, index)
+    }
+}
+
+/// 列出 BCD 备份（最新的在前）
+pub fn list_bcd_backups() -> Vec<BcdBackup> {
+    let Ok(entries) = std::fs::read_dir(bcd_backup_dir()) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<BcdBackup> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|ext| ext == "json").unwrap_or(false))
+        .filter_map(|meta_path| {
+            let path = meta_path.with_extension("");
+            if !path.is_file() {
+                return None;
+            }
+            let content = std::fs::read_to_string(&meta_path).ok()?;
+            let mut backup: BcdBackup = serde_json::from_str(&content).ok()?;
+            backup.path = path;
+            Some(backup)
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.path.cmp(&a.path));
+    backups
+}
+
+/// Windows 启动管理器的固定标识符（`/v` 输出中 {bootmgr} 的实际 GUID）
+const BOOTMGR_GUID: &str = "{9dea862c-5cdd-4e70-acc1-f32b344d4795}";
+
+/// 引导项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootEntry {
+    /// 标识符 GUID
+    pub identifier: String,
+    pub description: String,
+    /// 引导程序所在设备，如 partition=C:
+    pub device: String,
+    /// 系统所在设备
+    pub osdevice: String,
+    /// 安全模式类型（minimal / network），未设置时为 None
+    pub safeboot: Option<String>,
+    /// 是否在启动菜单中显示
+    pub in_menu: bool,
+}
+
+/// 启动菜单
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootMenu {
+    /// 菜单等待秒数
+    pub timeout: Option<u32>,
+    /// 默认引导项
+    pub default: Option<String>,
+    /// 菜单中的引导项（按显示顺序），其后是不在菜单中的系统引导项
+    pub entries: Vec<BootEntry>,
+}
+
+impl BootMenu {
+    pub fn is_default(&self, identifier: &str) -> bool {
+        self.default.as_deref().is_some_and(|d| d.eq_ignore_ascii_case(identifier))
+    }
+}
+
+/// 安全模式类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SafeBootMode {
+    #[default]
+    Minimal,
+    Network,
+    CommandPrompt,
+}
+
+impl SafeBootMode {
+    pub const ALL: [SafeBootMode; 3] = [SafeBootMode::Minimal, SafeBootMode::Network, SafeBootMode::CommandPrompt];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SafeBootMode::Minimal => "安全模式",
+            SafeBootMode::Network => "带网络连接的安全模式",
+            SafeBootMode::CommandPrompt => "带命令提示符的安全模式",
+        }
+    }
+
+    /// safeboot 的取值
+    fn safeboot_value(&self) -> &'static str {
+        match self {
+            SafeBootMode::Network => "network",
+            SafeBootMode::Minimal | SafeBootMode::CommandPrompt => "minimal",
+        }
+    }
+}
+
+/// 把 `bcdedit /enum /v` 的输出拆分为对象，每个对象为 (键, 值) 列表；多值项的续行使用同一个键
+fn parse_enum_sections(output: &str) -> Vec<Vec<(String, String)>> {
+    let mut sections = Vec::new();
+    for block in output.replace("\r\n", "\n").split("\n\n") {
+        let lines: Vec<&str> = block.lines().filter(|line| !line.trim().is_empty()).collect();
+        // 对象标题及其下划线
+        let start = if lines.get(1).is_some_and(|line| line.trim_start().starts_with("---")) { 2 } else { 0 };
+        let mut section: Vec<(String, String)> = Vec::new();
+        for line in &lines[start.min(lines.len())..] {
+            if line.starts_with(char::is_whitespace) {
+                if let Some((key, _)) = section.last() {
+                    let key = key.clone();
+                    section.push((key, line.trim().to_string()));
+                }
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            // 中文系统只有标识符一项是本地化的
+            let key = if key == "标识符" { "identifier" } else { key };
+            section.push((key.to_string(), value.trim().to_string()));
+        }
+        if !section.is_empty() {
+            sections.push(section);
+        }
+    }
+    sections
+}
+
+fn section_value<'a>(section: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    section.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+/// 解析 `bcdedit /enum all /v` 的输出
+fn parse_boot_menu(output: &str) -> BootMenu {
+    let sections = parse_enum_sections(output);
+    let is_bootmgr = |section: &[(String, String)]| {
+        section_value(section, "identifier").is_some_and(|id| id.eq_ignore_ascii_case(BOOTMGR_GUID))
+    };
+
+    let mut menu = BootMenu::default();
+    let mut display_order: Vec<String> = Vec::new();
+    if let Some(bootmgr) = sections.iter().find(|section| is_bootmgr(section)) {
+        menu.timeout = section_value(bootmgr, "timeout").and_then(|t| t.parse().ok());
+        menu.default = section_value(bootmgr, "default").map(str::to_string);
+        display_order = bootmgr
+            .iter()
+            .filter(|(key, _)| key == "displayorder")
+            .map(|(_, value)| value.to_lowercase())
+            .collect();
+    }
+
+    for section in sections.iter().filter(|section| !is_bootmgr(section)) {
+        let Some(identifier) = section_value(section, "identifier") else {
+            continue;
+        };
+        let in_menu = display_order.contains(&identifier.to_lowercase());
+        // 只列出菜单中的项和系统引导项（有 osdevice），忽略恢复、内存诊断等内部对象
+        if !in_menu && section_value(section, "osdevice").is_none() {
+            continue;
+        }
+        let value = |key: &str| section_value(section, key).unwrap_or_default().to_string();
+        menu.entries.push(BootEntry {
+            identifier: identifier.to_string(),
+            description: value("description"),
+            device: value("device"),
+            osdevice: value("osdevice"),
+            safeboot: section_value(section, "safeboot").map(str::to_string),
+            in_menu,
+        });
+    }
+
+    let position = |entry: &BootEntry| {
+        display_order
+            .iter()
+            .position(|id| *id == entry.identifier.to_lowercase())
+            .unwrap_or(usize::MAX)
+    };
+    menu.entries.sort_by_key(position);
+    menu
+}
+
+/// 从 `bcdedit /copy` 的输出中读取新引导项的 GUID
+fn parse_copied_guid(output: &str) -> Option<String> {
+    let start = output.find('{')?;
+    let end = start + output[start..].find('}')?;
+    Some(output[start..=end].to_string())
+}
 
 pub struct BootManager {
     bcdedit_path: String,
@@ -45,6 +262,14 @@ impl BootManager {
     pub fn find_esp_on_same_disk(&self, windows_partition: &str) -> Result<String> {
         println!("[BOOT] 查找 {} 所在磁盘的 ESP 分区...", windows_partition);
         
+        // 优先按 GPT 分区类型查找，ESP 没有盘符时直接使用卷 GUID 路径
+        if let Some(esp) = DiskManager::disk_number_of(windows_partition)
+            .and_then(|disk| DiskManager::find_esp_volume(Some(disk)))
+        {
+            println!("[BOOT] 找到 ESP: {}", esp);
+            return Ok(esp);
+        }
+        
         // 提取盘符（去掉冒号）
         let drive_letter = windows_partition.trim_end_matches(':').trim_end_matches('\\');
         
@@ -170,7 +395,13 @@ assign letter=S
             return Ok("S:".to_string());
         }
         
-        // 方法2: 使用 mountvol /s 挂载 ESP 到 S:
+        // 方法2: 按 GPT 分区类型查找，无需分配盘符
+        if let Some(esp) = DiskManager::find_esp_volume(None) {
+            println!("[BOOT] 找到 ESP: {}", esp);
+            return Ok(esp);
+        }
+        
+        // 方法3: 使用 mountvol /s 挂载 ESP 到 S:
         println!("[BOOT] 尝试使用 mountvol /s 挂载 ESP");
         let output = create_command("mountvol").args(["S:", "/s"]).output();
         if output.is_ok() {
@@ -181,7 +412,7 @@ assign letter=S
             }
         }
         
-        // 方法3: 使用 diskpart 查找所有磁盘的 ESP
+        // 方法4: 使用 diskpart 查找所有磁盘的 ESP
         self.find_esp_with_diskpart()
     }
 
@@ -244,39 +475,337 @@ assign letter=S
         anyhow::bail!("未找到 EFI 系统分区")
     }
 
-    /// 设置默认引导项
-    pub fn set_default_boot(&self, guid: &str) -> Result<()> {
-        let output = create_command(&self.bcdedit_path)
-            .args(["/default", guid])
-            .output()?;
-
+    /// 执行 bcdedit，store 为 None 时操作系统存储；返回标准输出
+    ///
+    /// 演示模式下只允许读取（/enum），不修改真实的引导配置。
+    fn run_bcdedit(&self, store: Option<&str>, args: &[&str]) -> Result<String> {
+        if args.first() != Some(&"/enum") {
+            demo::refuse("修改引导配置")?;
+        }
+        let mut command = create_command(&self.bcdedit_path);
+        if let Some(store) = store {
+            command.args(["/store", store]);
+        }
+        let output = command.args(args).output().context("执行 bcdedit 失败")?;
+        let stdout = gbk_to_utf8(&output.stdout);
         if !output.status.success() {
-            anyhow::bail!("Failed to set default boot entry");
+            let stderr = gbk_to_utf8(&output.stderr);
+            anyhow::bail!("{}", if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() });
         }
+        Ok(stdout)
+    }
+
+    /// 读取启动菜单：等待时间、默认项和各引导项
+    pub fn enumerate(&self, store: Option<&str>) -> Result<BootMenu> {
+        let output = self
+            .run_bcdedit(store, &["/enum", "all", "/v"])
+            .context("读取引导配置失败")?;
+        Ok(parse_boot_menu(&output))
+    }
+
+    /// 设置默认引导项
+    pub fn set_default_boot(&self, store: Option<&str>, guid: &str) -> Result<()> {
+        self.backup_before_change(store, "设置默认引导项前");
+        self.run_bcdedit(store, &["/default", guid])
+            .context("设置默认引导项失败")?;
         Ok(())
     }
 
     /// 设置引导超时
-    pub fn set_timeout(&self, seconds: u32) -> Result<()> {
-        let output = create_command(&self.bcdedit_path)
-            .args(["/timeout", &seconds.to_string()])
+    pub fn set_timeout(&self, store: Option<&str>, seconds: u32) -> Result<()> {
+        self.backup_before_change(store, "设置引导等待时间前");
+        self.run_bcdedit(store, &["/timeout", &seconds.to_string()])
+            .context("设置引导等待时间失败")?;
+        Ok(())
+    }
+
+    /// 修改引导项名称
+    pub fn set_description(&self, store: Option<&str>, guid: &str, description: &str) -> Result<()> {
+        self.backup_before_change(store, "修改引导项名称前");
+        self.run_bcdedit(store, &["/set", guid, "description", description])
+            .context("修改引导项名称失败")?;
+        Ok(())
+    }
+
+    /// 删除引导项
+    pub fn delete_boot_entry(&self, store: Option<&str>, guid: &str) -> Result<()> {
+        self.backup_before_change(store, "删除引导项前");
+        self.run_bcdedit(store, &["/delete", guid, "/f"])
+            .context("删除引导项失败")?;
+        Ok(())
+    }
+
+    /// 复制引导项并设置为安全模式启动，加到启动菜单末尾；返回新引导项的 GUID
+    pub fn add_safe_mode_entry(
+        &self,
+        store: Option<&str>,
+        guid: &str,
+        description: &str,
+        mode: SafeBootMode,
+    ) -> Result<String> {
+        self.backup_before_change(store, "添加安全模式引导项前");
+        let output = self
+            .run_bcdedit(store, &["/copy", guid, "/d", description])
+            .context("复制引导项失败")?;
+        let new_guid = parse_copied_guid(&output)
+            .ok_or_else(|| anyhow::anyhow!("无法读取新引导项的标识符: {}", output.trim()))?;
+
+        self.run_bcdedit(store, &["/set", &new_guid, "safeboot", mode.safeboot_value()])
+            .context("设置安全模式失败")?;
+        if mode == SafeBootMode::CommandPrompt {
+            self.run_bcdedit(store, &["/set", &new_guid, "safebootalternateshell", "yes"])
+                .context("设置命令提示符外壳失败")?;
+        }
+        self.run_bcdedit(store, &["/displayorder", &new_guid, "/addlast"])
+            .context("添加到启动菜单失败")?;
+        println!("[BOOT] 已添加{}引导项 {}", mode.label(), new_guid);
+        Ok(new_guid)
+    }
+
+    /// 查找各分区上的 BCD 存储文件（活动分区的 \Boot\BCD 和已挂载 ESP 的 \EFI\Microsoft\Boot\BCD）
+    pub fn find_stores(&self) -> Vec<String> {
+        ('C'..='Z')
+            .flat_map(|letter| {
+                [
+                    format!("{}:\\EFI\\Microsoft\\Boot\\BCD", letter),
+                    format!("{}:\\Boot\\BCD", letter),
+                ]
+            })
+            .filter(|store| Path::new(store).is_file())
+            .collect()
+    }
+
+    /// 导出 BCD 存储到备份目录
+    ///
+    /// store 为 None 时导出系统存储，否则导出指定的 BCD 文件
+    pub fn backup_bcd(&self, store: Option<&str>, reason: &str) -> Result<BcdBackup> {
+        let dir = bcd_backup_dir();
+        std::fs::create_dir_all(&dir).context("创建BCD备份目录失败")?;
+
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+        let path = (0..)
+            .map(|index| dir.join(backup_file_name(&timestamp, index)))
+            .find(|p| !p.exists())
+            .unwrap_or_default();
+        let path_str = path.to_string_lossy().to_string();
+
+        let mut args: Vec<&str> = Vec::new();
+        if let Some(store) = store {
+            args.extend(["/store", store]);
+        }
+        args.extend(["/export", &path_str]);
+
+        let output = create_command(&self.bcdedit_path).args(&args).output()?;
+        if !output.status.success() || !path.exists() {
+            anyhow::bail!("导出BCD失败: {}", gbk_to_utf8(&output.stdout).trim());
+        }
+
+        let backup = BcdBackup {
+            path,
+            created: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            reason: reason.to_string(),
+            store: store.unwrap_or_default().to_string(),
+        };
+        std::fs::write(backup.path.with_extension("json"), serde_json::to_string_pretty(&backup)?)
+            .context("写入BCD备份信息失败")?;
+        println!("[BOOT] BCD 已备份到 {}", path_str);
+
+        Self::prune_bcd_backups();
+        Ok(backup)
+    }
+
+    /// 修改 BCD 前备份，失败时只记录日志，不影响后续操作
+    pub fn backup_before_change(&self, store: Option<&str>, reason: &str) {
+        // 演示模式下随后的修改会被拒绝，不需要备份
+        if demo::is_enabled() {
+            return;
+        }
+        if let Err(e) = self.backup_bcd(store, reason) {
+            println!("[BOOT] 警告: BCD 备份失败: {}", e);
+        }
+    }
+
+    /// 删除超出数量上限的旧备份
+    fn prune_bcd_backups() {
+        for backup in list_bcd_backups().iter().skip(MAX_BCD_BACKUPS) {
+            let _ = std::fs::remove_file(&backup.path);
+            let _ = std::fs::remove_file(backup.path.with_extension("json"));
+        }
+    }
+
+    /// 从备份恢复 BCD（恢复前先备份当前配置）
+    pub fn restore_bcd(&self, backup: &BcdBackup) -> Result<()> {
+        demo::refuse("恢复引导配置")?;
+        let backup_path = backup.path.to_string_lossy().to_string();
+        println!("[BOOT] 从 {} 恢复 BCD", backup_path);
+
+        if backup.store.is_empty() {
+            self.backup_before_change(None, "恢复引导配置前");
+            let output = create_command(&self.bcdedit_path)
+                .args(["/import", &backup_path])
+                .output()?;
+            if !output.status.success() {
+                anyhow::bail!("导入BCD失败: {}", gbk_to_utf8(&output.stdout).trim());
+            }
+            return Ok(());
+        }
+
+        // BCD 文件位于 ESP 时，盘符可能已经失效，重新挂载 ESP
+        let mut store = backup.store.clone();
+        if !Path::new(&store).exists() && store.to_uppercase().contains("\\EFI\\") {
+            let esp = self.find_and_mount_esp()?;
+            let relative = &store[store.find('\\').unwrap_or(0)..];
+            store = format!("{}{}", esp, relative);
+        }
+        if !Path::new(&store).exists() {
+            anyhow::bail!("BCD 文件不存在: {}", store);
+        }
+
+        self.backup_before_change(Some(&store), "恢复引导配置前");
+        // BCD 文件带有隐藏和系统属性，覆盖前需要去掉
+        let _ = create_command("attrib").args(["-h", "-s", "-r", &store]).output();
+        let result = std::fs::copy(&backup.path, &store).context("写入BCD文件失败");
+        let _ = create_command("attrib").args(["+h", "+s", &store]).output();
+        result?;
+        Ok(())
+    }
+
+    /// 获取盘符所在分区的分区号
+    fn partition_number_of(&self, windows_partition: &str) -> Option<u32> {
+        let drive_letter = windows_partition.trim_end_matches(':').trim_end_matches('\\');
+        let script = format!("select volume {}\ndetail partition\n", drive_letter);
+        let script_path = std::env::temp_dir().join("detail_partition.txt");
+        std::fs::write(&script_path, &script).ok()?;
+
+        let output = create_command("diskpart")
+            .args(["/s", &script_path.to_string_lossy()])
+            .output()
+            .ok()?;
+        nt5_boot::parse_partition_number(&gbk_to_utf8(&output.stdout))
+    }
+
+    /// 目标分区上是否为 XP / 2003（按 ntoskrnl.exe 的版本号判断）
+    fn is_nt5_target(windows_partition: &str) -> bool {
+        let kernel = format!("{}\\Windows\\System32\\ntoskrnl.exe", windows_partition);
+        let major = get_file_version(Path::new(&kernel)).map(|(major, _, _, _)| major as u32);
+        nt5_boot::is_nt5_system(windows_partition, major)
+    }
+
+    /// 修复 XP / 2003 的引导：写入 NT5 引导扇区、激活分区、补齐 NTLDR 和 boot.ini
+    pub fn repair_nt5_boot(&self, windows_partition: &str) -> Result<()> {
+        let disk_number = DiskManager::disk_number_of(windows_partition);
+        let style = DiskManager::get_partitions()
+            .ok()
+            .and_then(|partitions| {
+                let letter = windows_partition.trim_end_matches('\\').to_uppercase();
+                partitions.into_iter().find(|p| p.letter.to_uppercase() == letter)
+            })
+            .map(|p| p.partition_style);
+        if style == Some(PartitionStyle::GPT) {
+            anyhow::bail!("XP / 2003 只能从 MBR 磁盘以 Legacy 方式启动，{} 位于 GPT 磁盘上", windows_partition);
+        }
+
+        println!("[BOOT] NT5 系统：使用 NTLDR 引导");
+        let root = format!("{}\\", windows_partition);
+
+        // 补齐引导文件：程序目录 bin\nt5 或系统自带的 SP 源文件
+        for name in NT5_LOADER_FILES {
+            let target = Path::new(&root).join(name);
+            if target.exists() {
+                continue;
+            }
+            let candidates = [
+                get_bin_dir().join("nt5").join(name),
+                Path::new(&root).join("WINDOWS\\ServicePackFiles\\i386").join(name),
+                Path::new(&root).join("i386").join(name),
+            ];
+            let source = candidates
+                .iter()
+                .find(|p| p.exists())
+                .ok_or_else(|| anyhow::anyhow!("缺少引导文件 {}，请将其放到程序目录 bin\\nt5 下", name))?;
+            std::fs::copy(source, &target).with_context(|| format!("复制 {} 失败", name))?;
+            println!("[BOOT] 已复制 {}", source.display());
+        }
+
+        let boot_ini = Path::new(&root).join("boot.ini");
+        if !boot_ini.exists() {
+            let partition_number = self.partition_number_of(windows_partition).unwrap_or(1);
+            let content = nt5_boot::boot_ini_content(disk_number.unwrap_or(0), partition_number);
+            std::fs::write(&boot_ini, content).context("写入 boot.ini 失败")?;
+            println!("[BOOT] 已创建 boot.ini（分区 {}）", partition_number);
+        }
+
+        // NTLDR 只能从活动分区启动
+        let drive_letter = windows_partition.trim_end_matches(':').trim_end_matches('\\');
+        let script_path = std::env::temp_dir().join("set_active.txt");
+        std::fs::write(&script_path, format!("select volume {}\nactive\n", drive_letter))?;
+        let output = create_command("diskpart")
+            .args(["/s", &script_path.to_string_lossy()])
             .output()?;
+        println!("[BOOT] 设置活动分区:\n{}", gbk_to_utf8(&output.stdout));
 
+        let bootsect_path = get_bin_dir().join("bootsect.exe");
+        if !bootsect_path.exists() {
+            anyhow::bail!("缺少 bootsect.exe，无法写入 NT5 引导扇区");
+        }
+        let output = create_command(&bootsect_path)
+            .args(["/nt52", windows_partition, "/mbr", "/force"])
+            .output()?;
+        println!("[BOOT] bootsect stdout: {}", gbk_to_utf8(&output.stdout));
         if !output.status.success() {
-            anyhow::bail!("Failed to set boot timeout");
+            anyhow::bail!("写入 NT5 引导扇区失败: {}", gbk_to_utf8(&output.stderr));
         }
+
+        for name in NT5_LOADER_FILES.iter().chain(["boot.ini"].iter()) {
+            let _ = create_command("attrib")
+                .args(["+h", "+s", "+r", &format!("{}{}", root, name)])
+                .output();
+        }
+
+        println!("[BOOT] NT5 引导修复成功");
         Ok(())
     }
 
-    /// 删除引导项
-    pub fn delete_boot_entry(&self, guid: &str) -> Result<()> {
+    /// 查找默认项指向目标系统分区的 BCD 存储文件（ESP 或活动分区上的 \Boot\BCD）
+    pub fn find_store_for(&self, windows_partition: &str) -> Option<String> {
+        let mut stores: Vec<String> = Vec::new();
+        if let Ok(esp) = self.find_esp_on_same_disk(windows_partition) {
+            stores.push(format!("{}\\EFI\\Microsoft\\Boot\\BCD", esp));
+        }
+        stores.extend(('C'..='Z').map(|letter| format!("{}:\\Boot\\BCD", letter)));
+
+        let device = format!("partition={}", windows_partition.trim_end_matches('\\')).to_uppercase();
+        stores.into_iter().filter(|store| Path::new(store).exists()).find(|store| {
+            create_command(&self.bcdedit_path)
+                .args(["/store", store, "/enum", "{default}"])
+                .output()
+                .map(|output| gbk_to_utf8(&output.stdout).to_uppercase().contains(&device))
+                .unwrap_or(false)
+        })
+    }
+
+    /// 仅在新系统首次启动时放宽驱动签名强制，首次启动完成后由 SetupComplete.cmd 恢复
+    ///
+    /// Windows 7 及以上使用测试签名模式，开启安全启动时无效
+    pub fn disable_integrity_checks_once(&self, windows_partition: &str) -> Result<()> {
+        let store = self
+            .find_store_for(windows_partition)
+            .ok_or_else(|| anyhow::anyhow!("未找到 {} 的引导配置", windows_partition))?;
+        self.backup_before_change(Some(&store), "禁用驱动签名强制前");
+
+        let kernel = format!("{}\\Windows\\System32\\ntoskrnl.exe", windows_partition);
+        let build = get_file_version(Path::new(&kernel)).map(|(_, _, build, _)| build as u32);
+        let mode = SigningOverride::for_build(build);
         let output = create_command(&self.bcdedit_path)
-            .args(["/delete", guid, "/f"])
+            .args(["/store", &store, "/set", "{default}", mode.element(), "on"])
             .output()?;
-
         if !output.status.success() {
-            anyhow::bail!("Failed to delete boot entry");
+            anyhow::bail!("设置 {} 失败: {}", mode.element(), gbk_to_utf8(&output.stdout).trim());
         }
+
+        driver_signing::stage_restore(windows_partition, mode)?;
+
+        println!("[BOOT] 已在 {} 中设置首次启动 {} on", store, mode.element());
         Ok(())
     }
 
@@ -298,6 +827,14 @@ assign letter=S
             anyhow::bail!("Windows 目录不存在: {}", windows_path);
         }
 
+        // XP / 2003 没有 bcdboot 所需的引导文件，且只能以 Legacy 方式启动
+        if Self::is_nt5_target(windows_partition) {
+            if use_uefi {
+                println!("[BOOT] 警告: XP / 2003 不支持 UEFI 启动，改用 Legacy 引导（需在固件中开启 CSM）");
+            }
+            return self.repair_nt5_boot(windows_partition);
+        }
+
         if use_uefi {
             // UEFI 模式：需要找到并挂载 ESP 分区
             println!("[BOOT] UEFI 模式：查找 ESP 分区");
@@ -307,17 +844,26 @@ assign letter=S
                 .or_else(|_| self.find_and_mount_esp());
             
             match esp_result {
-                Ok(esp_letter) => {
-                    println!("[BOOT] ESP 分区: {}", esp_letter);
+                Ok(esp) => {
+                    println!("[BOOT] ESP 分区: {}", esp);
+
+                    let store = format!("{}\\EFI\\Microsoft\\Boot\\BCD", esp);
+                    if Path::new(&store).exists() {
+                        self.backup_before_change(Some(&store), "修复引导前");
+                    }
                     
                     // 确保 EFI 目录存在
-                    let efi_ms_dir = format!("{}\\EFI\\Microsoft", esp_letter);
-                    let efi_boot_dir = format!("{}\\EFI\\Boot", esp_letter);
+                    let efi_ms_dir = format!("{}\\EFI\\Microsoft", esp);
+                    let efi_boot_dir = format!("{}\\EFI\\Boot", esp);
                     
                     // 创建必要的目录
                     let _ = std::fs::create_dir_all(&efi_ms_dir);
                     let _ = std::fs::create_dir_all(&efi_boot_dir);
                     
+                    // bcdboot 的 /s 参数只接受盘符，ESP 没有盘符时临时分配
+                    let esp_drive = TempDriveLetter::acquire(&esp)?;
+                    let esp_letter = esp_drive.drive();
+                    
                     // 使用 bcdboot 写入 UEFI 引导文件
                     // bcdboot C:\Windows /s S: /f UEFI /l zh-cn
                     println!("[BOOT] 执行: bcdboot {} /s {} /f UEFI /l zh-cn", windows_path, esp_letter);
@@ -395,6 +941,7 @@ assign letter=S
                 }
                 Err(e) => {
                     println!("[BOOT] 查找 ESP 失败: {}，尝试默认方式", e);
+                    self.backup_before_change(None, "修复引导前");
                     
                     // 尝试默认方式（让 bcdboot 自动处理）
                     let output = create_command(&self.bcdboot_path)
@@ -414,6 +961,7 @@ assign letter=S
         } else {
             // Legacy/BIOS 模式
             println!("[BOOT] Legacy 模式：写入 MBR 引导");
+            self.backup_before_change(None, "修复引导前");
             
             // 使用 bootsect 写入引导扇区
             let bootsect_path = get_bin_dir().join("bootsect.exe");
@@ -463,6 +1011,55 @@ assign letter=S
         Ok(())
     }
 
+    /// 为安装在虚拟磁盘中的系统添加本机 VHD 启动项
+    ///
+    /// 引导文件写入宿主分区所在物理磁盘的 ESP（UEFI）或活动分区（Legacy），
+    /// 不写 bootsect，原有引导项保留
+    pub fn add_vhd_boot_entry(&self, vhd_partition: &str, host_partition: &str, use_uefi: bool) -> Result<()> {
+        let windows_path = format!("{}\\Windows", vhd_partition);
+
+        println!("[BOOT] ========== 添加 VHD 启动项 ==========");
+        println!("[BOOT] Windows 路径: {}，宿主分区: {}", windows_path, host_partition);
+
+        if !Path::new(&windows_path).exists() {
+            anyhow::bail!("Windows 目录不存在: {}", windows_path);
+        }
+
+        let output = if use_uefi {
+            let esp = self
+                .find_esp_on_same_disk(host_partition)
+                .or_else(|_| self.find_and_mount_esp())?;
+            println!("[BOOT] ESP 分区: {}", esp);
+
+            let store = format!("{}\\EFI\\Microsoft\\Boot\\BCD", esp);
+            self.backup_before_change(Path::new(&store).exists().then_some(store.as_str()), "添加 VHD 启动项前");
+
+            let esp_drive = TempDriveLetter::acquire(&esp)?;
+            let esp_letter = esp_drive.drive();
+            println!("[BOOT] 执行: bcdboot {} /s {} /f UEFI /l zh-cn", windows_path, esp_letter);
+            create_command(&self.bcdboot_path)
+                .args([&windows_path, "/s", &esp_letter, "/f", "UEFI", "/l", "zh-cn"])
+                .output()?
+        } else {
+            self.backup_before_change(None, "添加 VHD 启动项前");
+            println!("[BOOT] 执行: bcdboot {} /f BIOS /l zh-cn", windows_path);
+            create_command(&self.bcdboot_path)
+                .args([&windows_path, "/f", "BIOS", "/l", "zh-cn"])
+                .output()?
+        };
+
+        let stdout = gbk_to_utf8(&output.stdout);
+        let stderr = gbk_to_utf8(&output.stderr);
+        println!("[BOOT] bcdboot stdout: {}", stdout);
+        println!("[BOOT] bcdboot stderr: {}", stderr);
+        if !output.status.success() {
+            anyhow::bail!("添加 VHD 启动项失败: {}", if stderr.trim().is_empty() { stdout } else { stderr });
+        }
+
+        println!("[BOOT] ========== VHD 启动项已添加 ==========");
+        Ok(())
+    }
+
     /// 查找 EFI 分区
     pub fn find_efi_partition(&self) -> Result<String> {
         self.find_and_mount_esp()
@@ -474,3 +1071,67 @@ impl Default for BootManager {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_file_name() {
+        assert_eq!(backup_file_name("20260101_120000", 0), "BCD_20260101_120000");
+        assert_eq!(backup_file_name("20260101_120000", 2), "BCD_20260101_120000_2");
+        // 文件名按时间排序
+        assert!(backup_file_name("20260102_000000", 0) > backup_file_name("20260101_235959", 1));
+    }
+
+    #[test]
+    fn test_parse_boot_menu() {
+        let output = "\r\nWindows Boot Manager\r\n--------------------\r\n\
+            identifier              {9dea862c-5cdd-4e70-acc1-f32b344d4795}\r\n\
+            device                  partition=\\Device\\HarddiskVolume1\r\n\
+            description             Windows Boot Manager\r\n\
+            default                 {c0f6f4d5-0000-0000-0000-000000000002}\r\n\
+            displayorder            {C0F6F4D5-0000-0000-0000-000000000002}\r\n\
+            \x20                       {c0f6f4d5-0000-0000-0000-000000000001}\r\n\
+            toolsdisplayorder       {b2721d73-1db4-4c62-bf78-c548a880142d}\r\n\
+            timeout                 5\r\n\r\n\
+            Windows Boot Loader\r\n-------------------\r\n\
+            identifier              {c0f6f4d5-0000-0000-0000-000000000001}\r\n\
+            device                  partition=D:\r\ndescription             Windows 10\r\n\
+            osdevice                partition=D:\r\nsafeboot                Minimal\r\n\r\n\
+            Windows 启动加载器\r\n-------------------\r\n\
+            标识符                  {c0f6f4d5-0000-0000-0000-000000000002}\r\n\
+            device                  partition=C:\r\ndescription             Windows 11\r\n\
+            osdevice                partition=C:\r\n\r\n\
+            Windows Boot Loader\r\n-------------------\r\n\
+            identifier              {c0f6f4d5-0000-0000-0000-000000000003}\r\n\
+            description             Windows Recovery Environment\r\nosdevice                ramdisk=[\\Device\\HarddiskVolume3]\\Recovery\\WindowsRE\\Winre.wim\r\n\r\n\
+            Windows Memory Tester\r\n---------------------\r\n\
+            identifier              {b2721d73-1db4-4c62-bf78-c548a880142d}\r\n\
+            description             Windows Memory Diagnostic\r\n";
+        let menu = parse_boot_menu(output);
+        assert_eq!(menu.timeout, Some(5));
+        assert!(menu.is_default("{C0F6F4D5-0000-0000-0000-000000000002}"));
+
+        let names: Vec<&str> = menu.entries.iter().map(|e| e.description.as_str()).collect();
+        assert_eq!(names, ["Windows 11", "Windows 10", "Windows Recovery Environment"]);
+        assert_eq!(menu.entries[0].device, "partition=C:");
+        assert!(menu.entries[0].safeboot.is_none());
+        assert_eq!(menu.entries[1].safeboot.as_deref(), Some("Minimal"));
+        assert!(menu.entries[1].in_menu);
+        assert!(!menu.entries[2].in_menu);
+    }
+
+    #[test]
+    fn test_parse_copied_guid() {
+        assert_eq!(
+            parse_copied_guid("The entry was successfully copied to {0d3a1a8e-1111-2222-3333-444455556666}.\r\n").as_deref(),
+            Some("{0d3a1a8e-1111-2222-3333-444455556666}")
+        );
+        assert_eq!(
+            parse_copied_guid("已将该项成功复制到 {0d3a1a8e-1111-2222-3333-444455556666}。").as_deref(),
+            Some("{0d3a1a8e-1111-2222-3333-444455556666}")
+        );
+        assert!(parse_copied_guid("参数错误。").is_none());
+    }
+}
@@ -26,6 +26,9 @@ use super::fveapi::{
     FveVolumeStatus,
 };
 
+pub use super::auto_unlock::{AutoUnlockKey, AUTO_UNLOCK_DIR};
+use super::secure_dir::SensitiveDir;
+
 /// 驱动器类型常量
 const DRIVE_FIXED: u32 = 3;
 
@@ -837,6 +840,10 @@ impl BitLockerManager {
         let drive_letter = drive.chars().next().unwrap_or('C');
         let letter = format!("{}:", drive_letter);
 
+        if let Err(e) = crate::core::demo::refuse("解密 BitLocker 分区") {
+            return DecryptResult::failure(&letter, &e.to_string(), None);
+        }
+
         let status = self.get_status(drive_letter);
 
         match status {
@@ -1494,6 +1501,143 @@ pub fn get_recovery_key_partition(drive: &str) -> Result<String, String> {
     BitLockerManager::new().get_recovery_key(drive)
 }
 
+// ==================== 重装后解锁数据分区 ====================
+
+/// 安装准备阶段为数据分区添加的一次性保护器和暂存的密钥文件
+///
+/// 交给新系统（或 PE）之前离开作用域时（安装失败、提前返回）删除保护器和密钥文件。
+pub struct AutoUnlockKeys {
+    dir: SensitiveDir,
+    keys: Vec<AutoUnlockKey>,
+    handed_off: bool,
+}
+
+impl AutoUnlockKeys {
+    /// 已添加一次性保护器的分区数
+    pub fn count(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// 密钥文件和解锁脚本所在的暂存目录
+    pub fn dir(&self) -> &std::path::Path {
+        self.dir.path()
+    }
+
+    /// 密钥已交给新系统，一次性保护器由新系统首次启动时删除
+    ///
+    /// `keep_staged` 为 true 时保留暂存目录，由 PE 安装到新系统。
+    pub fn hand_off(&mut self, keep_staged: bool) {
+        self.handed_off = true;
+        if keep_staged {
+            self.dir.keep();
+        }
+    }
+}
+
+impl Drop for AutoUnlockKeys {
+    fn drop(&mut self) {
+        if self.handed_off {
+            return;
+        }
+        for key in &self.keys {
+            let result = crate::utils::command::new_command("manage-bde")
+                .args(["-protectors", "-delete", &key.letter, "-id", &key.protector_id])
+                .output();
+            match result {
+                Ok(output) if output.status.success() => {
+                    log::info!("[BITLOCKER] 已删除 {} 的一次性保护器", key.letter)
+                }
+                _ => log::warn!("[BITLOCKER] 删除 {} 的一次性保护器失败", key.letter),
+            }
+        }
+    }
+}
+
+/// 暂存目录中的密钥文件名
+fn key_files(dir: &std::path::Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|name| name.to_lowercase().ends_with(".bek"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 为目标分区以外已解锁的 BitLocker 数据分区添加一次性外部密钥保护器，
+/// 密钥文件和解锁脚本写入受限访问的 `stage_dir`
+///
+/// 只能趁分区处于解锁状态时添加保护器；没有需要处理的分区时返回 None。
+pub fn prepare_auto_unlock(target_partition: &str, stage_dir: &std::path::Path) -> Option<AutoUnlockKeys> {
+    if crate::core::demo::is_enabled() {
+        return None;
+    }
+    let manager = BitLockerManager::new();
+    let target = target_partition.trim_end_matches('\\').to_uppercase();
+    let volumes: Vec<VolumeInfo> = manager
+        .get_encrypted_volumes()
+        .into_iter()
+        .filter(|v| v.status == VolumeStatus::EncryptedUnlocked && v.letter.to_uppercase() != target)
+        .collect();
+    if volumes.is_empty() {
+        return None;
+    }
+
+    let dir = match SensitiveDir::create(stage_dir) {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("[BITLOCKER] 创建密钥暂存目录失败，跳过数据分区解锁: {:#}", e);
+            return None;
+        }
+    };
+    let mut keys = AutoUnlockKeys { dir, keys: Vec::new(), handed_off: false };
+    for volume in volumes {
+        let before = key_files(keys.dir());
+        let output = crate::utils::command::new_command("manage-bde")
+            .args(["-protectors", "-add", &volume.letter, "-RecoveryKey"])
+            .arg(keys.dir())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                log::warn!(
+                    "[BITLOCKER] {} 添加一次性保护器失败: {}",
+                    volume.letter,
+                    decode_windows_output(&output.stdout).trim()
+                );
+                continue;
+            }
+            Err(e) => {
+                log::warn!("[BITLOCKER] 执行 manage-bde 失败: {}", e);
+                continue;
+            }
+        }
+        let added = key_files(keys.dir())
+            .into_iter()
+            .filter(|name| !before.contains(name))
+            .find_map(|name| AutoUnlockKey::from_key_file(&volume.letter, &name));
+        match added {
+            Some(key) => {
+                log::info!("[BITLOCKER] {} 将在新系统首次启动时解锁", volume.letter);
+                keys.keys.push(key);
+            }
+            None => log::warn!("[BITLOCKER] {} 未找到生成的密钥文件", volume.letter),
+        }
+    }
+    if keys.keys.is_empty() {
+        return None;
+    }
+
+    let script = super::auto_unlock::generate_script(&keys.keys);
+    if let Err(e) = std::fs::write(keys.dir().join(super::auto_unlock::UNLOCK_SCRIPT), script) {
+        log::warn!("[BITLOCKER] 写入解锁脚本失败: {}", e);
+        return None;
+    }
+    Some(keys)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
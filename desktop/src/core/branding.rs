@@ -0,0 +1,153 @@
+//! 运营方品牌定制模块
+//! 支持三级覆盖：内置默认值 < 服务器下发 branding 配置 < 程序目录下的 branding.json
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::utils::path::get_exe_dir;
+
+/// 默认显示名称
+pub const DEFAULT_TITLE: &str = "LetRecovery";
+
+/// 默认窗口标题
+pub const DEFAULT_WINDOW_TITLE: &str = "LetRecovery - Windows系统一键重装工具";
+
+/// 品牌定制信息
+///
+/// 所有字段均为可选，未设置的字段沿用上一级的值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Branding {
+    /// 显示名称（导航栏标题）
+    #[serde(default)]
+    pub title: Option<String>,
+    /// 窗口标题
+    #[serde(default)]
+    pub window_title: Option<String>,
+    /// Logo 图片 URL
+    #[serde(default)]
+    pub logo_url: Option<String>,
+    /// 主题色，格式 #RRGGBB
+    #[serde(default)]
+    pub theme_color: Option<String>,
+    /// 帮助页面 URL
+    #[serde(default)]
+    pub help_url: Option<String>,
+    /// 联系方式
+    #[serde(default)]
+    pub contact: Option<String>,
+}
+
+impl Branding {
+    /// 本地品牌配置文件路径
+    fn get_local_path() -> PathBuf {
+        get_exe_dir().join("branding.json")
+    }
+
+    /// 从JSON字符串解析
+    pub fn parse(content: &str) -> Option<Self> {
+        match serde_json::from_str::<Branding>(content) {
+            Ok(branding) => Some(branding),
+            Err(e) => {
+                log::warn!("解析品牌配置失败: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 加载程序目录下的本地品牌配置
+    pub fn load_local() -> Option<Self> {
+        let path = Self::get_local_path();
+        let content = std::fs::read_to_string(&path).ok()?;
+        log::info!("加载本地品牌配置: {}", path.display());
+        Self::parse(&content)
+    }
+
+    /// 按优先级合并各级品牌配置
+    ///
+    /// 服务器配置覆盖内置默认值，本地 branding.json 再覆盖服务器配置
+    pub fn resolve(remote_content: Option<&str>) -> Self {
+        let mut branding = Self::default();
+        if let Some(remote) = remote_content.and_then(Self::parse) {
+            branding.overlay(remote);
+        }
+        if let Some(local) = Self::load_local() {
+            branding.overlay(local);
+        }
+        branding
+    }
+
+    /// 用另一份配置中已设置的字段覆盖当前值
+    pub fn overlay(&mut self, other: Branding) {
+        fn pick(target: &mut Option<String>, value: Option<String>) {
+            if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+                *target = Some(value.trim().to_string());
+            }
+        }
+
+        pick(&mut self.title, other.title);
+        pick(&mut self.window_title, other.window_title);
+        pick(&mut self.logo_url, other.logo_url);
+        pick(&mut self.theme_color, other.theme_color);
+        pick(&mut self.help_url, other.help_url);
+        pick(&mut self.contact, other.contact);
+    }
+
+    /// 获取显示名称
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or(DEFAULT_TITLE)
+    }
+
+    /// 获取窗口标题
+    pub fn window_title(&self) -> String {
+        match (&self.window_title, &self.title) {
+            (Some(window_title), _) => window_title.clone(),
+            (None, Some(title)) => title.clone(),
+            (None, None) => DEFAULT_WINDOW_TITLE.to_string(),
+        }
+    }
+
+    /// 解析主题色
+    pub fn theme_rgb(&self) -> Option<[u8; 3]> {
+        let hex = self.theme_color.as_deref()?.trim().trim_start_matches('#');
+        if hex.len() != 6 || !hex.is_ascii() {
+            log::warn!("品牌主题色格式无效: {:?}", self.theme_color);
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some([channel(0)?, channel(2)?, channel(4)?])
+    }
+
+    /// 是否为默认品牌（无任何定制）
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_keeps_unset_fields() {
+        let mut branding = Branding::parse(r#"{"title":"Remote","help_url":"https://remote.example"}"#).unwrap();
+        branding.overlay(Branding::parse(r#"{"title":"Local","contact":" "}"#).unwrap());
+
+        assert_eq!(branding.title(), "Local");
+        assert_eq!(branding.window_title(), "Local");
+        assert_eq!(branding.help_url.as_deref(), Some("https://remote.example"));
+        assert_eq!(branding.contact, None);
+    }
+
+    #[test]
+    fn test_theme_rgb() {
+        let mut branding = Branding::default();
+        assert_eq!(branding.theme_rgb(), None);
+        assert!(branding.is_default());
+
+        branding.theme_color = Some("#1E88E5".to_string());
+        assert_eq!(branding.theme_rgb(), Some([0x1E, 0x88, 0xE5]));
+
+        branding.theme_color = Some("blue".to_string());
+        assert_eq!(branding.theme_rgb(), None);
+    }
+}
@@ -0,0 +1,261 @@
+//! 演示模式
+//!
+//! 以 `--demo` 启动时，分区列表换成内存中的模拟磁盘布局，格式化、分区、安装和备份只修改模拟布局，
+//! 不会触碰真实磁盘。培训讲师和评审可以在自己的电脑上走完整个流程；界面测试也以此为后端。
+//! 一键分区、分区对拷等直接操作磁盘的工具在演示模式下禁用。
+//! 模拟分区使用本机未占用的盘符，即使某处漏掉了演示模式判断，也不会落到真实分区上。
+
+use anyhow::{bail, Result};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use super::bitlocker::VolumeStatus;
+use super::disk::{GptAttributes, GptPartitionType, Partition, PartitionStyle, SectorSize, StorageBus};
+
+pub use crate::utils::cli::DEMO_ARG;
+
+/// 模拟安装后系统占用的空间（MB）
+const INSTALLED_SYSTEM_MB: u64 = 24 * 1024;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LAYOUT: Mutex<Option<DemoLayout>> = Mutex::new(None);
+
+/// 启用演示模式
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+    log::info!("[DEMO] 已启用演示模式，所有磁盘操作均为模拟");
+}
+
+/// 是否处于演示模式
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// 演示模式下拒绝会修改真实系统的操作
+pub fn refuse(action: &str) -> Result<()> {
+    if is_enabled() {
+        bail!("演示模式下不能{}", action);
+    }
+    Ok(())
+}
+
+/// 在全局模拟布局上执行操作（首次使用时创建示例布局）
+pub fn with_layout<R>(f: impl FnOnce(&mut DemoLayout) -> R) -> R {
+    let mut guard = LAYOUT.lock().unwrap();
+    f(guard.get_or_insert_with(DemoLayout::sample))
+}
+
+/// 恢复为初始示例布局
+pub fn reset() {
+    *LAYOUT.lock().unwrap() = Some(DemoLayout::sample());
+}
+
+/// 内存中的模拟磁盘布局
+#[derive(Debug, Clone)]
+pub struct DemoLayout {
+    partitions: Vec<Partition>,
+}
+
+fn partition(letter: &str, disk: u32, number: u32, total_mb: u64, free_mb: u64, label: &str, style: PartitionStyle) -> Partition {
+    Partition {
+        letter: letter.to_string(),
+        total_size_mb: total_mb,
+        free_size_mb: free_mb,
+        label: label.to_string(),
+        is_system_partition: false,
+        has_windows: false,
+        partition_style: style,
+        disk_number: Some(disk),
+        partition_number: Some(number),
+        bitlocker_status: VolumeStatus::NotEncrypted,
+        gpt_type_guid: if style == PartitionStyle::GPT {
+            GptPartitionType::BasicData.guid().to_string()
+        } else {
+            String::new()
+        },
+        gpt_attributes: GptAttributes::default(),
+        sector_size: SectorSize::default(),
+        storage_bus: StorageBus::Other,
+    }
+}
+
+/// 本机是否存在该盘符
+fn letter_in_use(letter: &str) -> bool {
+    Path::new(&format!("{}\\", letter)).exists()
+}
+
+/// 本机未占用的盘符（按字母顺序取最后 count 个，避开常用的 C:、D:）
+fn unused_letters(count: usize) -> Vec<String> {
+    let mut letters: Vec<String> = ('E'..='Z')
+        .rev()
+        .map(|c| format!("{}:", c))
+        .filter(|letter| !letter_in_use(letter))
+        .take(count)
+        .collect();
+    letters.reverse();
+    letters
+}
+
+impl DemoLayout {
+    /// 示例布局，使用本机未占用的盘符
+    pub fn sample() -> Self {
+        let letters = unused_letters(4);
+        let letter = |i: usize| letters.get(i).map(String::as_str).unwrap_or("");
+        Self::with_letters([letter(0), letter(1), letter(2), letter(3)])
+    }
+
+    /// 示例布局：GPT 系统盘（系统、数据）+ MBR 机械盘（备份）+ U 盘，依次使用给定盘符
+    pub fn with_letters(letters: [&str; 4]) -> Self {
+        let mut system = partition(letters[0], 0, 3, 120 * 1024, 62 * 1024, "系统", PartitionStyle::GPT);
+        system.is_system_partition = true;
+        system.has_windows = true;
+        let data = partition(letters[1], 0, 4, 356 * 1024, 210 * 1024, "数据", PartitionStyle::GPT);
+        let backup = partition(letters[2], 1, 1, 931 * 1024, 700 * 1024, "备份", PartitionStyle::MBR);
+        let mut usb = partition(letters[3], 2, 1, 29 * 1024, 25 * 1024, "U盘", PartitionStyle::MBR);
+        usb.storage_bus = StorageBus::Usb;
+        Self {
+            partitions: vec![system, data, backup, usb],
+        }
+    }
+
+    /// 当前分区列表
+    pub fn partitions(&self) -> Vec<Partition> {
+        self.partitions.clone()
+    }
+
+    fn find_mut(&mut self, letter: &str) -> Result<&mut Partition> {
+        let letter = normalize_letter(letter);
+        match self.partitions.iter_mut().find(|p| p.letter == letter) {
+            Some(partition) => Ok(partition),
+            None => bail!("模拟磁盘中不存在分区 {}", letter),
+        }
+    }
+
+    /// 格式化分区
+    pub fn format(&mut self, letter: &str) -> Result<()> {
+        let partition = self.find_mut(letter)?;
+        partition.free_size_mb = partition.total_size_mb;
+        partition.has_windows = false;
+        Ok(())
+    }
+
+    /// 在分区上“安装”系统
+    pub fn install_windows(&mut self, letter: &str) -> Result<()> {
+        let partition = self.find_mut(letter)?;
+        if partition.total_size_mb < INSTALLED_SYSTEM_MB {
+            bail!("分区 {} 空间不足", partition.letter);
+        }
+        partition.free_size_mb = partition.total_size_mb - INSTALLED_SYSTEM_MB;
+        partition.has_windows = true;
+        Ok(())
+    }
+
+    /// 从分区的可用空间中分出新分区
+    pub fn shrink_and_create(&mut self, source: &str, new_letter: &str, size_mb: u64) -> Result<()> {
+        let new_letter = normalize_letter(new_letter);
+        if self.partitions.iter().any(|p| p.letter == new_letter) || letter_in_use(&new_letter) {
+            bail!("盘符 {} 已被占用", new_letter);
+        }
+        let source = self.find_mut(source)?;
+        if size_mb == 0 || size_mb >= source.free_size_mb {
+            bail!("分区 {} 可用空间不足", source.letter);
+        }
+        source.total_size_mb -= size_mb;
+        source.free_size_mb -= size_mb;
+
+        let (disk, style) = (source.disk_number.unwrap_or(0), source.partition_style);
+        let number = self
+            .partitions
+            .iter()
+            .filter(|p| p.disk_number == Some(disk))
+            .filter_map(|p| p.partition_number)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        self.partitions
+            .push(partition(&new_letter, disk, number, size_mb, size_mb, "新加卷", style));
+        Ok(())
+    }
+
+    /// 删除分区（不能删除当前系统分区）
+    pub fn delete(&mut self, letter: &str) -> Result<()> {
+        let letter = normalize_letter(letter);
+        let Some(index) = self.partitions.iter().position(|p| p.letter == letter) else {
+            bail!("模拟磁盘中不存在分区 {}", letter);
+        };
+        if self.partitions[index].is_system_partition {
+            bail!("不能删除当前系统分区");
+        }
+        self.partitions.remove(index);
+        Ok(())
+    }
+}
+
+/// "c" / "c:" / "C:\" 统一为 "C:"
+fn normalize_letter(letter: &str) -> String {
+    letter
+        .chars()
+        .next()
+        .map(|c| format!("{}:", c.to_ascii_uppercase()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> DemoLayout {
+        DemoLayout::with_letters(["C:", "D:", "E:", "F:"])
+    }
+
+    fn get(layout: &DemoLayout, letter: &str) -> Partition {
+        layout.partitions().into_iter().find(|p| p.letter == letter).unwrap()
+    }
+
+    #[test]
+    fn test_format_and_install() {
+        let mut layout = layout();
+        layout.format("d").unwrap();
+        let data = get(&layout, "D:");
+        assert_eq!(data.free_size_mb, data.total_size_mb);
+
+        layout.install_windows("D:\\").unwrap();
+        let data = get(&layout, "D:");
+        assert!(data.has_windows);
+        assert_eq!(data.free_size_mb, data.total_size_mb - INSTALLED_SYSTEM_MB);
+
+        layout.format("D:").unwrap();
+        assert!(!get(&layout, "D:").has_windows);
+        assert!(layout.format("Z:").is_err());
+        assert!(layout.install_windows("F:").is_ok());
+    }
+
+    #[test]
+    fn test_shrink_and_delete() {
+        let mut layout = layout();
+        let before = get(&layout, "D:");
+        layout.shrink_and_create("D:", "g", 10 * 1024).unwrap();
+        assert_eq!(get(&layout, "D:").total_size_mb, before.total_size_mb - 10 * 1024);
+        let new = get(&layout, "G:");
+        assert_eq!(new.disk_number, Some(0));
+        assert_eq!(new.partition_number, Some(5));
+        assert_eq!(new.partition_style, PartitionStyle::GPT);
+
+        assert!(layout.shrink_and_create("D:", "G:", 1024).is_err());
+        assert!(layout.shrink_and_create("F:", "H:", 100 * 1024).is_err());
+
+        layout.delete("G:").unwrap();
+        assert_eq!(layout.partitions().len(), 4);
+        assert!(layout.delete("C:").is_err());
+    }
+
+    #[test]
+    fn test_sample_avoids_used_letters() {
+        let layout = DemoLayout::sample();
+        for partition in layout.partitions() {
+            assert!(!partition.letter.is_empty());
+            assert!(!letter_in_use(&partition.letter));
+        }
+    }
+}
@@ -0,0 +1,321 @@
+//! 运行环境检测
+//!
+//! 启动时检查依赖文件、系统组件、wimlib 加载、管理员权限、配置服务器连通性和剩余空间，
+//! 首次启动或存在无法继续的问题时以检查清单的形式展示，并给出修复方法。
+//! 依赖文件或系统组件缺失时程序无法正常工作，只能修复后重新检测或退出（演示模式下只作提示）。
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::core::disk::DiskManager;
+use crate::download::server_config::{RemoteConfig, SERVER_BASE_URL};
+use crate::utils::cmd::create_command;
+use crate::utils::path::get_exe_dir;
+
+/// 完整程序包下载地址
+pub const RELEASES_URL: &str = "https://github.com/NORMAL-EX/LetRecovery/releases";
+
+/// 程序目录中必需的依赖文件
+const REQUIRED_FILES: &[&str] = &[
+    "bin/bcdedit.exe",
+    "bin/bcdboot.exe",
+    "bin/bootsect.exe",
+    "bin/format.com",
+    "bin/ghost/ghost64.exe",
+];
+
+/// System32 中必需的系统组件（极限精简系统可能缺失）
+///
+/// 分区和格式化已改用原生 API，但引导修复、ESP 挂载、分区扩展等仍调用 diskpart。
+const REQUIRED_COMPONENTS: &[(&str, &str)] = &[
+    ("diskpart.exe", "磁盘分区工具"),
+    ("fmifs.dll", "磁盘格式化库"),
+    ("wimgapi.dll", "WIM 镜像处理库"),
+    ("advapi32.dll", "高级 Windows API 库"),
+];
+
+/// 程序所在分区剩余空间低于此值时提示（下载系统镜像通常需要 5-8 GB）
+const LOW_SPACE_WARNING: u64 = 8 * 1024 * 1024 * 1024;
+/// 程序所在分区剩余空间低于此值时视为失败（日志和配置都无法写入）
+const LOW_SPACE_FAIL: u64 = 100 * 1024 * 1024;
+/// 服务器连通性检测超时
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 检测项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticCheck {
+    Dependencies,
+    SystemComponents,
+    Wimlib,
+    Admin,
+    Network,
+    DiskSpace,
+}
+
+impl DiagnosticCheck {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiagnosticCheck::Dependencies => "程序依赖文件",
+            DiagnosticCheck::SystemComponents => "系统核心组件",
+            DiagnosticCheck::Wimlib => "wimlib 镜像库",
+            DiagnosticCheck::Admin => "管理员权限",
+            DiagnosticCheck::Network => "配置服务器连接",
+            DiagnosticCheck::DiskSpace => "程序所在分区剩余空间",
+        }
+    }
+}
+
+/// 检测结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// 部分功能受影响
+    Warning,
+    /// 程序无法正常工作
+    Fail,
+}
+
+/// 单项检测结果
+#[derive(Debug, Clone)]
+pub struct DiagnosticItem {
+    pub check: DiagnosticCheck,
+    pub status: CheckStatus,
+    /// 检测详情
+    pub detail: String,
+    /// 修复方法（通过时为空）
+    pub fix: Option<String>,
+}
+
+impl DiagnosticItem {
+    fn pass(check: DiagnosticCheck, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(check: DiagnosticCheck, status: CheckStatus, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// 是否存在无法继续的问题
+pub fn has_blocking(items: &[DiagnosticItem]) -> bool {
+    items.iter().any(|item| item.status == CheckStatus::Fail)
+}
+
+/// 未通过的检测项数量
+pub fn problem_count(items: &[DiagnosticItem]) -> usize {
+    items.iter().filter(|item| item.status != CheckStatus::Pass).count()
+}
+
+/// 缺失的依赖文件
+pub fn missing_dependencies() -> Vec<String> {
+    let exe_dir = get_exe_dir();
+    REQUIRED_FILES
+        .iter()
+        .filter(|file| !exe_dir.join(file).exists())
+        .map(|file| file.to_string())
+        .collect()
+}
+
+/// 缺失的系统组件（“文件名 - 说明”）
+pub fn missing_system_components() -> Vec<String> {
+    let system_root = std::env::var("SYSTEMROOT")
+        .or_else(|_| std::env::var("WINDIR"))
+        .unwrap_or_else(|_| "C:\\Windows".to_string());
+    let system32 = Path::new(&system_root).join("System32");
+    REQUIRED_COMPONENTS
+        .iter()
+        .filter(|(file, _)| !system32.join(file).exists())
+        .map(|(file, description)| format!("{} - {}", file, description))
+        .collect()
+}
+
+/// 缺失文件类检测：演示模式下不需要外部工具，只作提示
+fn missing_files_item(check: DiagnosticCheck, missing: Vec<String>, fix: &str) -> DiagnosticItem {
+    if missing.is_empty() {
+        return DiagnosticItem::pass(check, "完整");
+    }
+    for file in &missing {
+        log::warn!("[DIAG] {}缺失: {}", check.name(), file);
+    }
+    let status = if super::demo::is_enabled() {
+        CheckStatus::Warning
+    } else {
+        CheckStatus::Fail
+    };
+    DiagnosticItem::problem(check, status, format!("缺少: {}", missing.join("、")), fix)
+}
+
+fn check_wimlib() -> DiagnosticItem {
+    match super::wimlib::Wimlib::new() {
+        Ok(_) => DiagnosticItem::pass(DiagnosticCheck::Wimlib, "已加载"),
+        Err(e) => DiagnosticItem::problem(
+            DiagnosticCheck::Wimlib,
+            CheckStatus::Warning,
+            e,
+            "将使用 wimgapi 释放和捕获镜像，镜像完整性校验和镜像内容浏览不可用；可将 wimlib.dll 放到程序目录",
+        ),
+    }
+}
+
+fn check_admin() -> DiagnosticItem {
+    if crate::utils::privilege::is_admin() {
+        DiagnosticItem::pass(DiagnosticCheck::Admin, "已获得")
+    } else if super::demo::is_enabled() {
+        DiagnosticItem::pass(DiagnosticCheck::Admin, "演示模式无需管理员权限")
+    } else {
+        DiagnosticItem::problem(
+            DiagnosticCheck::Admin,
+            CheckStatus::Fail,
+            "未以管理员身份运行",
+            "右键程序选择“以管理员身份运行”",
+        )
+    }
+}
+
+/// 根据剩余空间判断状态
+fn free_space_status(free_bytes: u64) -> CheckStatus {
+    if free_bytes < LOW_SPACE_FAIL {
+        CheckStatus::Fail
+    } else if free_bytes < LOW_SPACE_WARNING {
+        CheckStatus::Warning
+    } else {
+        CheckStatus::Pass
+    }
+}
+
+fn check_disk_space() -> DiagnosticItem {
+    let exe_dir = get_exe_dir();
+    let drive: String = exe_dir.to_string_lossy().chars().take(2).collect();
+    let Some(free) = DiskManager::get_free_space_bytes(&drive) else {
+        return DiagnosticItem::pass(DiagnosticCheck::DiskSpace, "无法获取（可能位于网络路径）");
+    };
+    let detail = format!("{} 剩余 {:.1} GB", drive, free as f64 / (1024.0 * 1024.0 * 1024.0));
+    match free_space_status(free) {
+        CheckStatus::Pass => DiagnosticItem::pass(DiagnosticCheck::DiskSpace, detail),
+        status => DiagnosticItem::problem(
+            DiagnosticCheck::DiskSpace,
+            status,
+            detail,
+            "清理该分区，或在在线下载页面把下载目录改到其他分区",
+        ),
+    }
+}
+
+/// 对程序所在分区打开 Windows 磁盘清理
+pub fn open_disk_cleanup() {
+    let drive: String = get_exe_dir().to_string_lossy().chars().take(1).collect();
+    if let Err(e) = create_command("cleanmgr.exe").args(["/d", &drive]).spawn() {
+        log::warn!("[DIAG] 启动磁盘清理失败: {}", e);
+    }
+}
+
+/// 根据启动时预加载远程配置的结果生成连通性检测项（避免启动时重复请求）
+pub fn network_item_from(remote_config: Option<&RemoteConfig>) -> DiagnosticItem {
+    match remote_config {
+        Some(config) if config.loaded => DiagnosticItem::pass(DiagnosticCheck::Network, "已连接"),
+        Some(config) => network_problem(config.error.clone().unwrap_or_else(|| "连接失败".to_string())),
+        None => network_problem("连接超时".to_string()),
+    }
+}
+
+fn network_problem(detail: String) -> DiagnosticItem {
+    DiagnosticItem::problem(
+        DiagnosticCheck::Network,
+        CheckStatus::Warning,
+        detail,
+        "检查网络连接或代理设置；离线时仍可安装本地镜像，在线下载和 PE 下载不可用",
+    )
+}
+
+/// 检测配置服务器连通性（阻塞，最长 5 秒）
+pub fn check_network() -> DiagnosticItem {
+    let result = reqwest::blocking::Client::builder()
+        .timeout(NETWORK_TIMEOUT)
+        .build()
+        .and_then(|client| client.head(SERVER_BASE_URL).send());
+    match result {
+        // 能收到任何 HTTP 响应即说明网络可达
+        Ok(_) => DiagnosticItem::pass(DiagnosticCheck::Network, "已连接"),
+        Err(e) => network_problem(e.to_string()),
+    }
+}
+
+/// 本地检测（不访问网络，启动时调用）
+pub fn run_local() -> Vec<DiagnosticItem> {
+    let items = vec![
+        missing_files_item(
+            DiagnosticCheck::Dependencies,
+            missing_dependencies(),
+            "程序文件不完整，请重新下载完整程序包并解压全部文件",
+        ),
+        missing_files_item(
+            DiagnosticCheck::SystemComponents,
+            missing_system_components(),
+            "当前系统为极限精简系统，暂不支持；请在完整的 Windows 或 PE 中运行",
+        ),
+        check_wimlib(),
+        check_admin(),
+        check_disk_space(),
+    ];
+    log::info!("[DIAG] 本地环境检测完成，{} 项未通过", problem_count(&items));
+    items
+}
+
+/// 全部检测（含网络，需在后台线程调用）
+pub fn run_all() -> Vec<DiagnosticItem> {
+    let mut items = run_local();
+    items.push(check_network());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_space_status() {
+        assert_eq!(free_space_status(0), CheckStatus::Fail);
+        assert_eq!(free_space_status(LOW_SPACE_FAIL), CheckStatus::Warning);
+        assert_eq!(free_space_status(LOW_SPACE_WARNING - 1), CheckStatus::Warning);
+        assert_eq!(free_space_status(LOW_SPACE_WARNING), CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_network_item_from_preload() {
+        assert_eq!(network_item_from(None).status, CheckStatus::Warning);
+        let loaded = RemoteConfig {
+            loaded: true,
+            ..Default::default()
+        };
+        assert_eq!(network_item_from(Some(&loaded)).status, CheckStatus::Pass);
+        let failed = RemoteConfig {
+            error: Some("dns error".to_string()),
+            ..Default::default()
+        };
+        let item = network_item_from(Some(&failed));
+        assert_eq!(item.detail, "dns error");
+        assert!(item.fix.is_some());
+    }
+
+    #[test]
+    fn test_blocking() {
+        let mut items = vec![
+            DiagnosticItem::pass(DiagnosticCheck::Admin, "已获得"),
+            network_item_from(None),
+        ];
+        assert!(!has_blocking(&items));
+        assert_eq!(problem_count(&items), 1);
+        items.push(DiagnosticItem::problem(DiagnosticCheck::Dependencies, CheckStatus::Fail, "缺少", "修复"));
+        assert!(has_blocking(&items));
+    }
+}
@@ -4,24 +4,34 @@ use crate::utils::cmd::create_command;
 use crate::utils::encoding::gbk_to_utf8;
 use crate::utils::path::get_bin_dir;
 use crate::core::bitlocker::{BitLockerManager, VolumeStatus};
+use crate::core::demo;
+use crate::core::disk_native;
+use crate::core::partition_plan;
+pub use crate::core::partition_plan::{
+    describe_gpt_partition, gpt_attributes_command, gpt_type_command, DiskConversion, GptAttributes, GptPartitionType,
+    PartitionPlan, PlannedPartition, ESP_SIZE_MB, MSR_SIZE_MB,
+};
 
 #[cfg(windows)]
 use windows::{
     core::PCWSTR,
     Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE},
     Win32::Storage::FileSystem::{
-        CreateFileW, GetDiskFreeSpaceExW, GetDriveTypeW, GetVolumeInformationW,
+        CreateFileW, DeleteVolumeMountPointW, FindFirstVolumeW, FindNextVolumeW, FindVolumeClose,
+        GetDiskFreeSpaceExW, GetDriveTypeW, GetVolumeInformationW, GetVolumeNameForVolumeMountPointW,
+        SetVolumeMountPointW,
         FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
     },
     Win32::System::IO::DeviceIoControl,
     Win32::System::Ioctl::{
-        IOCTL_DISK_GET_DRIVE_LAYOUT_EX, IOCTL_STORAGE_GET_DEVICE_NUMBER,
-        PARTITION_STYLE_GPT, PARTITION_STYLE_MBR,
+        PropertyStandardQuery, StorageAccessAlignmentProperty, StorageDeviceProperty, IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+        IOCTL_DISK_GET_PARTITION_INFO_EX, IOCTL_STORAGE_GET_DEVICE_NUMBER, IOCTL_STORAGE_QUERY_PROPERTY,
+        PARTITION_STYLE_GPT, PARTITION_STYLE_MBR, STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR,
+        STORAGE_PROPERTY_QUERY,
     },
 };
 
 // 驱动器类型常量
-#[allow(dead_code)]
 const DRIVE_REMOVABLE: u32 = 2;
 const DRIVE_FIXED: u32 = 3;
 #[allow(dead_code)]
@@ -32,7 +42,7 @@ const DRIVE_RAMDISK: u32 = 6;
 
 /// 获取 diskpart 可执行文件路径
 /// 优先使用内置的 diskpart，如果不存在则使用系统的
-fn get_diskpart_path() -> String {
+pub fn get_diskpart_path() -> String {
     let builtin_diskpart = get_bin_dir().join("diskpart").join("diskpart.exe");
     if builtin_diskpart.exists() {
         log::info!("使用内置 diskpart: {}", builtin_diskpart.display());
@@ -46,6 +56,16 @@ fn get_diskpart_path() -> String {
 /// 自动创建分区的标志文件名
 pub const AUTO_CREATED_PARTITION_MARKER: &str = "LetRecovery_AutoCreated.marker";
 
+/// 自动创建的数据分区卷标（PE 端按卷标查找该分区）
+pub const DATA_PARTITION_LABEL: &str = "LETRECOVERY_DATA";
+
+/// 数据分区的图标文件名及内容（autorun.inf 引用）
+const DATA_PARTITION_ICON: &str = "LetRecovery.ico";
+const DATA_PARTITION_ICON_BYTES: &[u8] = include_bytes!("../../assets/icon.ico");
+
+/// 数据分区的说明文件名
+const DATA_PARTITION_README: &str = "README_请勿删除.txt";
+
 /// 分区表类型
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum PartitionStyle {
@@ -65,6 +85,18 @@ impl std::fmt::Display for PartitionStyle {
     }
 }
 
+/// 将 GPT 分区信息中的 GUID 字节（混合字节序）格式化为字符串
+pub fn format_guid(bytes: &[u8; 16]) -> String {
+    format!(
+        "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        bytes[3], bytes[2], bytes[1], bytes[0],
+        bytes[5], bytes[4],
+        bytes[7], bytes[6],
+        bytes[8], bytes[9],
+        bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]
+    )
+}
+
 #[derive(Debug, Clone)]
 pub struct Partition {
     pub letter: String,
@@ -77,6 +109,84 @@ pub struct Partition {
     pub disk_number: Option<u32>,
     pub partition_number: Option<u32>,
     pub bitlocker_status: VolumeStatus,
+    /// GPT 分区类型 GUID（MBR 分区为空）
+    pub gpt_type_guid: String,
+    /// GPT 分区属性
+    pub gpt_attributes: GptAttributes,
+    /// 所在磁盘的扇区大小
+    pub sector_size: SectorSize,
+    /// 所在磁盘的总线类型
+    pub storage_bus: StorageBus,
+}
+
+/// 磁盘总线类型（只区分安装时需要特殊处理的几类）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageBus {
+    #[default]
+    Other,
+    Usb,
+    /// SD 卡
+    Sd,
+    /// eMMC（平板常见的板载存储）
+    Mmc,
+}
+
+impl StorageBus {
+    /// 由 STORAGE_BUS_TYPE 转换
+    pub fn from_bus_type(bus_type: i32) -> Self {
+        match bus_type {
+            7 => StorageBus::Usb,
+            12 => StorageBus::Sd,
+            13 => StorageBus::Mmc,
+            _ => StorageBus::Other,
+        }
+    }
+
+    /// 是否为 eMMC / SD 存储，这类存储可能报告为可移动磁盘，但可以作为安装目标
+    pub fn is_flash_card(&self) -> bool {
+        matches!(self, StorageBus::Sd | StorageBus::Mmc)
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            StorageBus::Other => "",
+            StorageBus::Usb => "USB",
+            StorageBus::Sd => "SD",
+            StorageBus::Mmc => "eMMC",
+        }
+    }
+}
+
+/// 磁盘逻辑/物理扇区大小
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectorSize {
+    pub logical: u32,
+    pub physical: u32,
+}
+
+impl Default for SectorSize {
+    fn default() -> Self {
+        Self { logical: 512, physical: 512 }
+    }
+}
+
+impl SectorSize {
+    /// 是否为 4K 原生磁盘（逻辑扇区 4096 字节），Ghost 无法在这类磁盘上还原 512 字节扇区的镜像
+    pub fn is_native_4k(&self) -> bool {
+        self.logical >= 4096
+    }
+
+    /// 扇区格式简称（512n / 512e / 4Kn）
+    pub fn label(&self) -> &'static str {
+        if self.is_native_4k() {
+            "4Kn"
+        } else if self.physical > self.logical {
+            "512e"
+        } else {
+            "512n"
+        }
+    }
 }
 
 /// 分区详细信息
@@ -112,6 +222,10 @@ pub struct DiskManager;
 impl DiskManager {
     /// 获取所有固定磁盘分区列表
     pub fn get_partitions() -> Result<Vec<Partition>> {
+        if demo::is_enabled() {
+            return Ok(demo::with_layout(|layout| layout.partitions()));
+        }
+
         let mut partitions = Vec::new();
         let is_pe = Self::is_pe_environment();
 
@@ -129,14 +243,17 @@ impl DiskManager {
     }
 
     fn get_partition_info(drive: &str, is_pe: bool, bitlocker_manager: &BitLockerManager) -> Result<Partition> {
-        let path = format!("{}\\", drive);
+        let path = volume_root(drive);
         let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
 
+        let storage_bus = Self::get_storage_bus(drive);
+
         #[cfg(windows)]
         {
-            // 获取驱动器类型
+            // 获取驱动器类型，平板的 eMMC / SD 存储可能报告为可移动磁盘
             let drive_type = unsafe { GetDriveTypeW(PCWSTR(wide_path.as_ptr())) };
-            if drive_type != DRIVE_FIXED {
+            let is_flash_card = drive_type == DRIVE_REMOVABLE && storage_bus.is_flash_card();
+            if drive_type != DRIVE_FIXED && !is_flash_card {
                 anyhow::bail!("Not a fixed drive");
             }
         }
@@ -196,6 +313,12 @@ impl DiskManager {
         let letter_char = drive.chars().next().unwrap_or('C');
         let bitlocker_status = bitlocker_manager.get_status(letter_char);
 
+        let (gpt_type_guid, gpt_attributes) = Self::get_gpt_partition_info(drive).unwrap_or_default();
+        let sector_size = detail
+            .disk_number
+            .and_then(Self::get_sector_size)
+            .unwrap_or_default();
+
         Ok(Partition {
             letter: drive.to_string(),
             total_size_mb: total_bytes / 1024 / 1024,
@@ -207,16 +330,21 @@ impl DiskManager {
             disk_number: detail.disk_number,
             partition_number: detail.partition_number,
             bitlocker_status,
+            gpt_type_guid,
+            gpt_attributes,
+            sector_size,
+            storage_bus,
         })
     }
 
     /// 使用 Windows API 获取分区表类型和分区号 (GPT/MBR)
     #[cfg(windows)]
     fn get_partition_style(drive: &str) -> PartitionDetail {
-        let letter = drive.chars().next().unwrap_or('C');
-        
         // 先获取磁盘号和分区号
-        let (disk_number, partition_number) = Self::get_device_number(letter);
+        let (disk_number, partition_number) = match Self::volume_device_path(drive) {
+            Some(device_path) => Self::get_device_number(&device_path),
+            None => (None, None),
+        };
         
         // 再获取分区表类型
         let style = if let Some(disk_num) = disk_number {
@@ -243,11 +371,10 @@ impl DiskManager {
 
     /// 使用 IOCTL_STORAGE_GET_DEVICE_NUMBER 获取磁盘号和分区号
     #[cfg(windows)]
-    fn get_device_number(letter: char) -> (Option<u32>, Option<u32>) {
+    fn get_device_number(device_path: &str) -> (Option<u32>, Option<u32>) {
         unsafe {
             // 打开卷设备
-            let volume_path = format!("\\\\.\\{}:", letter);
-            let wide_path: Vec<u16> = volume_path.encode_utf16().chain(std::iter::once(0)).collect();
+            let wide_path: Vec<u16> = device_path.encode_utf16().chain(std::iter::once(0)).collect();
 
             let handle = CreateFileW(
                 PCWSTR::from_raw(wide_path.as_ptr()),
@@ -292,6 +419,127 @@ impl DiskManager {
         }
     }
 
+    /// 使用 IOCTL_STORAGE_QUERY_PROPERTY（StorageDeviceProperty）获取分区所在磁盘的总线类型
+    #[cfg(windows)]
+    pub fn get_storage_bus(partition: &str) -> StorageBus {
+        let Some(device_path) = Self::volume_device_path(partition) else {
+            return StorageBus::Other;
+        };
+        unsafe {
+            let wide_path: Vec<u16> = device_path.encode_utf16().chain(std::iter::once(0)).collect();
+
+            let handle = match CreateFileW(
+                PCWSTR::from_raw(wide_path.as_ptr()),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            ) {
+                Ok(h) if h != INVALID_HANDLE_VALUE => h,
+                _ => return StorageBus::Other,
+            };
+
+            let query = STORAGE_PROPERTY_QUERY {
+                PropertyId: StorageDeviceProperty,
+                QueryType: PropertyStandardQuery,
+                AdditionalParameters: [0],
+            };
+            let mut buffer = [0u8; 512];
+            let mut bytes_returned: u32 = 0;
+
+            let result = DeviceIoControl(
+                handle,
+                IOCTL_STORAGE_QUERY_PROPERTY,
+                Some(&query as *const _ as *const _),
+                std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+                Some(buffer.as_mut_ptr() as *mut _),
+                buffer.len() as u32,
+                Some(&mut bytes_returned),
+                None,
+            );
+
+            let _ = CloseHandle(handle);
+
+            // STORAGE_DEVICE_DESCRIPTOR 中 BusType 位于 offset 28
+            if result.is_err() || bytes_returned < 32 {
+                return StorageBus::Other;
+            }
+            StorageBus::from_bus_type(i32::from_le_bytes([buffer[28], buffer[29], buffer[30], buffer[31]]))
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn get_storage_bus(_partition: &str) -> StorageBus {
+        StorageBus::Other
+    }
+
+    /// 使用 IOCTL_STORAGE_QUERY_PROPERTY（StorageAccessAlignmentProperty）获取磁盘逻辑/物理扇区大小
+    #[cfg(windows)]
+    pub fn get_sector_size(disk_number: u32) -> Option<SectorSize> {
+        unsafe {
+            let disk_path = format!("\\\\.\\PhysicalDrive{}", disk_number);
+            let wide_path: Vec<u16> = disk_path.encode_utf16().chain(std::iter::once(0)).collect();
+
+            let handle = CreateFileW(
+                PCWSTR::from_raw(wide_path.as_ptr()),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            )
+            .ok()?;
+
+            if handle == INVALID_HANDLE_VALUE {
+                return None;
+            }
+
+            let query = STORAGE_PROPERTY_QUERY {
+                PropertyId: StorageAccessAlignmentProperty,
+                QueryType: PropertyStandardQuery,
+                AdditionalParameters: [0],
+            };
+            let mut descriptor = STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR::default();
+            let mut bytes_returned: u32 = 0;
+
+            let result = DeviceIoControl(
+                handle,
+                IOCTL_STORAGE_QUERY_PROPERTY,
+                Some(&query as *const _ as *const _),
+                std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+                Some(&mut descriptor as *mut _ as *mut _),
+                std::mem::size_of::<STORAGE_ACCESS_ALIGNMENT_DESCRIPTOR>() as u32,
+                Some(&mut bytes_returned),
+                None,
+            );
+
+            let _ = CloseHandle(handle);
+
+            if result.is_err() || descriptor.BytesPerLogicalSector == 0 {
+                return None;
+            }
+            Some(SectorSize {
+                logical: descriptor.BytesPerLogicalSector,
+                physical: descriptor.BytesPerPhysicalSector.max(descriptor.BytesPerLogicalSector),
+            })
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn get_sector_size(_disk_number: u32) -> Option<SectorSize> {
+        None
+    }
+
+    /// 对 SSD 分区的全部空闲空间执行 TRIM，返回 TRIM 的字节数（设备不支持时返回 0）
+    pub fn trim_free_space(partition: &str) -> Result<u64> {
+        let device_path = Self::volume_device_path(partition)
+            .ok_or_else(|| anyhow::anyhow!("无法获取卷设备路径: {}", partition))?;
+        crate::core::volume_trim::trim_free_space(partition, &device_path)
+    }
+
     /// 使用 IOCTL_DISK_GET_DRIVE_LAYOUT_EX 获取磁盘分区表类型
     #[cfg(windows)]
     fn get_disk_partition_style_api(disk_number: u32) -> PartitionStyle {
@@ -353,8 +601,327 @@ impl DiskManager {
         }
     }
 
-    /// 格式化指定分区
+    /// 使用 IOCTL_DISK_GET_PARTITION_INFO_EX 获取 GPT 分区类型 GUID 和属性
+    #[cfg(windows)]
+    pub fn get_gpt_partition_info(partition: &str) -> Option<(String, GptAttributes)> {
+        let device_path = Self::volume_device_path(partition)?;
+        unsafe {
+            let wide_path: Vec<u16> = device_path.encode_utf16().chain(std::iter::once(0)).collect();
+
+            let handle = CreateFileW(
+                PCWSTR::from_raw(wide_path.as_ptr()),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            )
+            .ok()?;
+
+            if handle == INVALID_HANDLE_VALUE {
+                return None;
+            }
+
+            // PARTITION_INFORMATION_EX 结构大小固定为 144 字节
+            let mut buffer = [0u8; 144];
+            let mut bytes_returned: u32 = 0;
+
+            let result = DeviceIoControl(
+                handle,
+                IOCTL_DISK_GET_PARTITION_INFO_EX,
+                None,
+                0,
+                Some(buffer.as_mut_ptr() as *mut _),
+                buffer.len() as u32,
+                Some(&mut bytes_returned),
+                None,
+            );
+
+            let _ = CloseHandle(handle);
+
+            if result.is_err() || (bytes_returned as usize) < buffer.len() {
+                return None;
+            }
+
+            // offset 0: PartitionStyle，offset 32: PartitionType GUID，offset 64: Attributes
+            let style = u32::from_le_bytes(buffer[0..4].try_into().ok()?);
+            if style != PARTITION_STYLE_GPT.0 as u32 {
+                return None;
+            }
+            let type_guid: [u8; 16] = buffer[32..48].try_into().ok()?;
+            let attributes = u64::from_le_bytes(buffer[64..72].try_into().ok()?);
+            Some((format_guid(&type_guid), GptAttributes(attributes)))
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn get_gpt_partition_info(_partition: &str) -> Option<(String, GptAttributes)> {
+        None
+    }
+
+    /// 获取卷 GUID 路径（如 `\\?\Volume{...}\`），支持盘符、挂载文件夹和卷 GUID 路径
+    #[cfg(windows)]
+    pub fn volume_guid_path(partition: &str) -> Option<String> {
+        let root: Vec<u16> = volume_root(partition)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut name = [0u16; 64];
+        unsafe { GetVolumeNameForVolumeMountPointW(PCWSTR(root.as_ptr()), &mut name).ok()? };
+        Some(String::from_utf16_lossy(&name).trim_end_matches('\0').to_string())
+    }
+
+    #[cfg(not(windows))]
+    pub fn volume_guid_path(_partition: &str) -> Option<String> {
+        None
+    }
+
+    /// 用于 CreateFileW 打开卷设备的路径（盘符为 `\\.\C:`，其他为不带结尾反斜杠的卷 GUID 路径）
+    fn volume_device_path(partition: &str) -> Option<String> {
+        match drive_letter_of(partition) {
+            Some(letter) => Some(format!("\\\\.\\{}:", letter)),
+            None => Self::volume_guid_path(partition).map(|p| p.trim_end_matches('\\').to_string()),
+        }
+    }
+
+    /// 挂载文件夹的根目录（盘符用尽时将卷挂载到这里的子文件夹）
+    pub fn mount_root() -> std::path::PathBuf {
+        std::env::temp_dir().join("LetRecovery_Mounts")
+    }
+
+    /// 将卷挂载到 NTFS 空文件夹（SetVolumeMountPointW）
+    ///
+    /// `volume` 可以是卷 GUID 路径或已有的盘符/挂载路径，返回挂载文件夹路径（不带结尾反斜杠）
+    pub fn mount_volume_folder(volume: &str, folder: &Path) -> Result<String> {
+        std::fs::create_dir_all(folder)?;
+        let mount_point = volume_root(&folder.to_string_lossy());
+        Self::set_mount_point(volume, &mount_point)?;
+        Ok(mount_point.trim_end_matches('\\').to_string())
+    }
+
+    /// 卸载文件夹挂载点并删除空文件夹
+    pub fn unmount_volume_folder(folder: &str) -> Result<()> {
+        Self::remove_mount_point(&volume_root(folder))?;
+        let _ = std::fs::remove_dir(folder);
+        Ok(())
+    }
+
+    /// 为卷设置挂载点（盘符根目录或 NTFS 空文件夹，需带结尾反斜杠）
+    #[cfg(windows)]
+    fn set_mount_point(volume: &str, mount_point: &str) -> Result<()> {
+        let volume_name = Self::volume_guid_path(volume)
+            .ok_or_else(|| anyhow::anyhow!("无法获取卷 {} 的 GUID 路径", volume))?;
+        let wide_mount: Vec<u16> = mount_point.encode_utf16().chain(std::iter::once(0)).collect();
+        let wide_volume: Vec<u16> = volume_name.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            SetVolumeMountPointW(PCWSTR(wide_mount.as_ptr()), PCWSTR(wide_volume.as_ptr()))
+                .map_err(|e| anyhow::anyhow!("挂载 {} 到 {} 失败: {}", volume_name, mount_point, e))?;
+        }
+        println!("[DISK] 已将 {} 挂载到 {}", volume_name, mount_point);
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn set_mount_point(_volume: &str, _mount_point: &str) -> Result<()> {
+        anyhow::bail!("仅支持 Windows")
+    }
+
+    /// 删除挂载点（盘符根目录或文件夹）
+    #[cfg(windows)]
+    fn remove_mount_point(mount_point: &str) -> Result<()> {
+        let wide_mount: Vec<u16> = mount_point.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            DeleteVolumeMountPointW(PCWSTR(wide_mount.as_ptr()))
+                .map_err(|e| anyhow::anyhow!("卸载挂载点 {} 失败: {}", mount_point, e))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    fn remove_mount_point(_mount_point: &str) -> Result<()> {
+        anyhow::bail!("仅支持 Windows")
+    }
+
+    /// 枚举系统中的全部卷，返回卷 GUID 路径（不带结尾反斜杠）
+    #[cfg(windows)]
+    pub fn list_volumes() -> Vec<String> {
+        let mut volumes = Vec::new();
+        let mut name = [0u16; 64];
+
+        let handle = match unsafe { FindFirstVolumeW(&mut name) } {
+            Ok(h) => h,
+            Err(e) => {
+                println!("[DISK] 枚举卷失败: {}", e);
+                return volumes;
+            }
+        };
+
+        loop {
+            let volume = String::from_utf16_lossy(&name).trim_end_matches('\0').to_string();
+            volumes.push(volume.trim_end_matches('\\').to_string());
+
+            name = [0u16; 64];
+            if unsafe { FindNextVolumeW(handle, &mut name) }.is_err() {
+                break;
+            }
+        }
+        let _ = unsafe { FindVolumeClose(handle) };
+
+        volumes
+    }
+
+    #[cfg(not(windows))]
+    pub fn list_volumes() -> Vec<String> {
+        Vec::new()
+    }
+
+    /// 分区所在的磁盘号（支持盘符、挂载文件夹和卷 GUID 路径）
+    #[cfg(windows)]
+    pub fn disk_number_of(partition: &str) -> Option<u32> {
+        Self::get_device_number(&Self::volume_device_path(partition)?).0
+    }
+
+    #[cfg(not(windows))]
+    pub fn disk_number_of(_partition: &str) -> Option<u32> {
+        None
+    }
+
+    /// 分区所在的磁盘号和分区号
+    #[cfg(windows)]
+    pub fn partition_number_of(partition: &str) -> (Option<u32>, Option<u32>) {
+        Self::volume_device_path(partition).map_or((None, None), |path| Self::get_device_number(&path))
+    }
+
+    #[cfg(not(windows))]
+    pub fn partition_number_of(_partition: &str) -> (Option<u32>, Option<u32>) {
+        (None, None)
+    }
+
+    /// 按 GPT 分区类型查找 EFI 系统分区，返回卷 GUID 路径（不带结尾反斜杠）
+    ///
+    /// ESP 通常没有盘符，直接通过卷 GUID 路径访问其中的文件；指定磁盘号时只查找该磁盘
+    pub fn find_esp_volume(disk_number: Option<u32>) -> Option<String> {
+        Self::list_volumes().into_iter().find(|volume| {
+            let is_esp = Self::get_gpt_partition_info(volume)
+                .is_some_and(|(guid, _)| GptPartitionType::from_guid(&guid) == Some(GptPartitionType::Esp));
+            is_esp && disk_number.map_or(true, |disk| Self::disk_number_of(volume) == Some(disk))
+        })
+    }
+
+    /// 为分区分配访问路径：优先分配空闲盘符，盘符用尽时挂载到文件夹
+    ///
+    /// 返回盘符（如 "S:"）或挂载文件夹路径
+    pub fn assign_access_path(disk_number: u32, partition_number: u32) -> Result<String> {
+        let (assign, access_path) = match Self::find_available_drive_letter() {
+            Some(letter) => (format!("assign letter={}", letter), format!("{}:", letter)),
+            None => {
+                let folder = Self::mount_root().join(format!("Disk{}_Part{}", disk_number, partition_number));
+                std::fs::create_dir_all(&folder)?;
+                let folder = folder.to_string_lossy().to_string();
+                (format!("assign mount=\"{}\"", folder), folder)
+            }
+        };
+
+        Self::run_partition_command(disk_number, partition_number, &format!("{}\n", assign))?;
+        if !Path::new(&volume_root(&access_path)).exists() {
+            anyhow::bail!("分区 {}/{} 分配访问路径 {} 失败", disk_number, partition_number, access_path);
+        }
+        Ok(access_path)
+    }
+
+    /// 设置 GPT 分区类型
+    pub fn set_partition_type(disk_number: u32, partition_number: u32, partition_type: GptPartitionType) -> Result<()> {
+        Self::run_partition_command(disk_number, partition_number, &gpt_type_command(partition_type))
+    }
+
+    /// 设置 GPT 分区属性
+    pub fn set_partition_attributes(disk_number: u32, partition_number: u32, attributes: GptAttributes) -> Result<()> {
+        Self::run_partition_command(disk_number, partition_number, &gpt_attributes_command(attributes))
+    }
+
+    /// 设置 MBR 分区类型 ID（如 0x27 为恢复分区）
+    pub fn set_mbr_partition_id(disk_number: u32, partition_number: u32, id: u8) -> Result<()> {
+        Self::run_partition_command(disk_number, partition_number, &format!("set id={:02X} override\n", id))
+    }
+
+    /// 移除盘符（只删除挂载点，不影响分区数据）
+    pub fn remove_drive_letter(letter: char) -> Result<()> {
+        Self::remove_mount_point(&format!("{}:\\", letter))
+    }
+
+    /// 让目标分区所在磁盘的分区表匹配固件启动模式的规划（保留数据）
+    pub fn plan_for_firmware(partition: &str, uefi: bool) -> Result<PartitionPlan> {
+        let disk_number = Self::disk_number_of(partition)
+            .ok_or_else(|| anyhow::anyhow!("无法确定分区 {} 所在的磁盘", partition))?;
+        let style = Self::get_partitions()?
+            .into_iter()
+            .find(|p| p.letter.eq_ignore_ascii_case(partition))
+            .map(|p| p.partition_style)
+            .unwrap_or_default();
+        PartitionPlan::match_firmware(disk_number, style, uefi)
+    }
+
+    /// 执行分区规划，返回创建的分区名称
+    ///
+    /// 重新分区会清空整个磁盘，调用方须事先确认；mbr2gpt 转换保留现有分区和数据。
+    pub fn plan_and_apply(plan: &PartitionPlan) -> Result<Vec<String>> {
+        log::info!("[DISK] 分区规划: {}", plan.describe());
+        match plan.conversion {
+            DiskConversion::None => Ok(Vec::new()),
+            DiskConversion::Mbr2Gpt => {
+                partition_plan::convert_mbr_to_gpt(plan.disk_number, !Self::is_pe_environment())?;
+                Ok(vec!["ESP".to_string()])
+            }
+            DiskConversion::Repartition if disk_native::format_available() => {
+                disk_native::repartition(plan).map_err(|e| anyhow::anyhow!("分区操作失败: {}", e))
+            }
+            DiskConversion::Repartition => {
+                // 缺少 fmifs.dll 时无法原生格式化，改用 diskpart
+                log::warn!("[DISK] FormatEx 不可用，使用 diskpart 重新分区");
+                plan.apply_with_diskpart(&get_diskpart_path())
+            }
+        }
+    }
+
+    /// 选中指定分区后执行 diskpart 命令
+    fn run_partition_command(disk_number: u32, partition_number: u32, command: &str) -> Result<()> {
+        let script_content = format!(
+            "select disk {}\nselect partition {}\n{}",
+            disk_number, partition_number, command
+        );
+
+        let script_path = std::env::temp_dir().join("lr_partition_attr.txt");
+        std::fs::write(&script_path, &script_content)?;
+
+        let output = create_command(&get_diskpart_path())
+            .args(["/s", script_path.to_str().unwrap()])
+            .output()?;
+
+        let _ = std::fs::remove_file(&script_path);
+
+        let output_text = gbk_to_utf8(&output.stdout);
+        println!("[DISK] Diskpart 输出: {}", output_text);
+
+        let output_lower = output_text.to_lowercase();
+        if output_lower.contains("error") || output_lower.contains("错误") {
+            anyhow::bail!("设置分区 {}/{} 失败: {}", disk_number, partition_number, output_text);
+        }
+        Ok(())
+    }
+
+    /// 格式化指定分区（没有盘符的分区会临时分配盘符）
     pub fn format_partition(partition: &str) -> Result<String> {
+        if demo::is_enabled() {
+            demo::with_layout(|layout| layout.format(partition))?;
+            return Ok(format!("[演示] 已格式化 {}", partition));
+        }
+
+        if disk_native::format_available() {
+            disk_native::format_volume(partition, "NTFS", "", |_| {})?;
+            return Ok(format!("已格式化 {}", partition));
+        }
+
         let bin_dir = get_bin_dir();
         let format_exe = if Self::is_pe_environment() {
             bin_dir.join("format.com").to_string_lossy().to_string()
@@ -362,8 +929,9 @@ impl DiskManager {
             "format.com".to_string()
         };
 
+        let drive = TempDriveLetter::acquire(partition)?;
         let output = create_command(&format_exe)
-            .args([partition, "/FS:NTFS", "/q", "/y"])
+            .args([drive.drive().as_str(), "/FS:NTFS", "/q", "/y"])
             .output()?;
 
         Ok(gbk_to_utf8(&output.stdout))
@@ -375,6 +943,11 @@ impl DiskManager {
         new_letter: &str,
         size_mb: u64,
     ) -> Result<String> {
+        if demo::is_enabled() {
+            demo::with_layout(|layout| layout.shrink_and_create(source_partition, new_letter, size_mb))?;
+            return Ok(format!("[演示] 已从 {} 分出 {} MB 的 {}", source_partition, size_mb, new_letter));
+        }
+
         let script_content = format!(
             "select volume {}\nshrink desired={}\ncreate partition primary size={}\nformat fs=ntfs quick\nassign letter={}",
             source_partition.chars().next().unwrap_or('C'),
@@ -398,6 +971,16 @@ impl DiskManager {
 
     /// 删除指定分区
     pub fn delete_partition(partition_letter: &str) -> Result<String> {
+        if demo::is_enabled() {
+            demo::with_layout(|layout| layout.delete(partition_letter))?;
+            return Ok(format!("[演示] 已删除 {}", partition_letter));
+        }
+
+        if let (Some(disk_number), Some(partition_number)) = Self::partition_number_of(partition_letter) {
+            disk_native::delete_partition(disk_number, partition_number)?;
+            return Ok(format!("已删除 {}", partition_letter));
+        }
+
         let script_content = format!(
             "select volume {}\ndelete partition override",
             partition_letter.chars().next().unwrap_or('Y')
@@ -542,7 +1125,7 @@ impl DiskManager {
     /// 获取指定分区的剩余空间（字节）
     #[cfg(windows)]
     pub fn get_free_space_bytes(partition: &str) -> Option<u64> {
-        let path = format!("{}\\", partition);
+        let path = volume_root(partition);
         let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
         
         let mut free_bytes_available: u64 = 0;
@@ -842,10 +1425,11 @@ impl DiskManager {
             "select volume {}\n\
             shrink desired={}\n\
             create partition primary\n\
-            format fs=ntfs quick label=\"LetRecovery\"\n\
+            format fs=ntfs quick label=\"{}\"\n\
             assign letter={}",
             source_letter,
             actual_size_mb,
+            DATA_PARTITION_LABEL,
             new_letter
         );
 
@@ -898,7 +1482,7 @@ impl DiskManager {
             std::thread::sleep(std::time::Duration::from_secs(1));
         }
 
-        // 写入标志文件
+        // 写入标志文件（保留给按标志文件查找的旧版 PE）
         let marker_path = format!("{}:\\{}", new_letter, AUTO_CREATED_PARTITION_MARKER);
         std::fs::write(
             &marker_path,
@@ -915,6 +1499,11 @@ impl DiskManager {
         )
         .map_err(|e| anyhow::anyhow!("写入标志文件失败: {}", e))?;
 
+        // 图标和说明文件只是提示用户，写入失败不影响安装
+        if let Err(e) = Self::write_data_partition_notice(new_letter) {
+            println!("[DISK] 写入数据分区说明文件失败: {}", e);
+        }
+
         println!(
             "[DISK] 新分区 {}: 创建成功，大小 {} MB，标志文件已写入",
             new_letter, actual_size_mb
@@ -923,8 +1512,76 @@ impl DiskManager {
         Ok(new_letter)
     }
 
-    /// 检查分区是否是自动创建的（通过检查标志文件）
+    /// 为数据分区写入 autorun.inf 图标和说明文件，避免用户在安装过程中误删
+    fn write_data_partition_notice(letter: char) -> Result<()> {
+        let root = format!("{}:\\", letter);
+        std::fs::write(format!("{}{}", root, DATA_PARTITION_ICON), DATA_PARTITION_ICON_BYTES)?;
+        std::fs::write(format!("{}autorun.inf", root), utf16le_with_bom(&data_partition_autorun()))?;
+        // 带 BOM，旧版记事本也能正确识别 UTF-8
+        std::fs::write(
+            format!("{}{}", root, DATA_PARTITION_README),
+            format!("\u{FEFF}{}", data_partition_readme()),
+        )?;
+
+        for name in ["autorun.inf", DATA_PARTITION_ICON] {
+            let _ = create_command("attrib")
+                .args(["+h", "+s", &format!("{}{}", root, name)])
+                .output();
+        }
+        Ok(())
+    }
+
+    /// 获取卷标
+    #[cfg(windows)]
+    pub fn volume_label(letter: char) -> String {
+        let root: Vec<u16> = format!("{}:\\", letter)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut volume_name = [0u16; 261];
+        unsafe {
+            let _ = GetVolumeInformationW(
+                PCWSTR(root.as_ptr()),
+                Some(&mut volume_name),
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        String::from_utf16_lossy(&volume_name)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    #[cfg(not(windows))]
+    pub fn volume_label(_letter: char) -> String {
+        String::new()
+    }
+
+    /// 获取分区标识（磁盘ID:分区偏移），用于在 PE 中盘符变化后确认是同一个分区
+    pub fn partition_identity(letter: char) -> Option<String> {
+        let script_path = std::env::temp_dir().join("lr_partition_id.txt");
+        std::fs::write(
+            &script_path,
+            format!("select volume {}\nuniqueid disk\ndetail partition", letter),
+        )
+        .ok()?;
+
+        let output = create_command(&get_diskpart_path())
+            .args(["/s", script_path.to_str().unwrap()])
+            .output()
+            .ok()?;
+        let _ = std::fs::remove_file(&script_path);
+
+        parse_partition_identity(&gbk_to_utf8(&output.stdout))
+    }
+
+    /// 检查分区是否是自动创建的（通过卷标，兼容旧版本的标志文件）
     pub fn is_auto_created_partition(letter: char) -> bool {
+        if Self::volume_label(letter).eq_ignore_ascii_case(DATA_PARTITION_LABEL) {
+            return true;
+        }
         let marker_path = format!("{}:\\{}", letter, AUTO_CREATED_PARTITION_MARKER);
         Path::new(&marker_path).exists()
     }
@@ -1152,3 +1809,202 @@ impl DiskManager {
         Ok(Some((format!("{}:", new_letter), true)))
     }
 }
+
+/// 临时盘符
+///
+/// 分区内部统一通过卷 GUID 路径访问，只有 bcdboot、format 等必须使用盘符的外部工具才需要临时分配，
+/// 离开作用域时自动移除临时分配的盘符
+pub struct TempDriveLetter {
+    letter: char,
+    assigned: bool,
+}
+
+impl TempDriveLetter {
+    /// 获取分区的盘符：已有盘符直接使用，否则临时分配一个空闲盘符
+    pub fn acquire(partition: &str) -> Result<Self> {
+        if let Some(letter) = drive_letter_of(partition) {
+            return Ok(Self { letter, assigned: false });
+        }
+
+        let letter = DiskManager::find_available_drive_letter()
+            .ok_or_else(|| anyhow::anyhow!("没有可用的盘符，无法为 {} 分配临时盘符", partition))?;
+        DiskManager::set_mount_point(partition, &format!("{}:\\", letter))?;
+        Ok(Self { letter, assigned: true })
+    }
+
+    /// 盘符（如 "S:"）
+    pub fn drive(&self) -> String {
+        format!("{}:", self.letter)
+    }
+}
+
+impl Drop for TempDriveLetter {
+    fn drop(&mut self) {
+        if self.assigned {
+            if let Err(e) = DiskManager::remove_mount_point(&format!("{}:\\", self.letter)) {
+                println!("[DISK] 移除临时盘符 {}: 失败: {}", self.letter, e);
+            }
+        }
+    }
+}
+
+/// 分区路径是否为卷 GUID 路径（`\\?\Volume{...}`）
+pub fn is_volume_guid_path(partition: &str) -> bool {
+    partition
+        .get(..11)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("\\\\?\\Volume{"))
+}
+
+/// 分区路径对应的盘符（仅 "C:"、"C:\\" 形式）
+pub fn drive_letter_of(partition: &str) -> Option<char> {
+    let trimmed = partition.trim_end_matches('\\');
+    let mut chars = trimmed.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), None) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// 分区根目录（带结尾反斜杠），用于 GetDiskFreeSpaceExW 等需要根路径的 API
+///
+/// 分区可以是盘符（"C:"）、卷 GUID 路径或挂载文件夹
+pub fn volume_root(partition: &str) -> String {
+    format!("{}\\", partition.trim_end_matches('\\'))
+}
+
+/// 数据分区的 autorun.inf 内容（资源管理器据此显示图标和名称）
+fn data_partition_autorun() -> String {
+    format!(
+        "[autorun]\r\nicon={}\r\nlabel=LetRecovery 临时数据 - 请勿删除\r\n",
+        DATA_PARTITION_ICON
+    )
+}
+
+/// 数据分区的说明文件内容
+fn data_partition_readme() -> String {
+    [
+        "此分区由 LetRecovery 自动创建，用于存放系统安装过程中的镜像和配置文件。",
+        "",
+        "安装尚未完成时请勿删除或格式化此分区，否则重启进入 PE 后将无法继续安装。",
+        "安装完成后此分区会被自动删除，空间归还给系统分区。",
+        "如果安装已结束但分区仍然存在，可以在磁盘管理中手动删除。",
+        "",
+        "This partition was created by LetRecovery to hold the installation image.",
+        "Do not delete it until the installation has finished; it is removed automatically afterwards.",
+        "",
+    ]
+    .join("\r\n")
+}
+
+/// 编码为带 BOM 的 UTF-16LE（autorun.inf 不支持 UTF-8）
+fn utf16le_with_bom(text: &str) -> Vec<u8> {
+    std::iter::once(0xFEFF)
+        .chain(text.encode_utf16())
+        .flat_map(|unit: u16| unit.to_le_bytes())
+        .collect()
+}
+
+/// 从 diskpart `uniqueid disk` 和 `detail partition` 的输出中解析分区标识（磁盘ID:偏移）
+fn parse_partition_identity(output: &str) -> Option<String> {
+    let mut disk_id = None;
+    let mut offset = None;
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        if disk_id.is_none() && (key.eq_ignore_ascii_case("Disk ID") || key == "磁盘 ID") {
+            disk_id = Some(value.trim_matches(|c| c == '{' || c == '}').to_uppercase());
+        } else if offset.is_none() && (key.contains("Offset") || key.contains("偏移")) {
+            let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+            if !digits.is_empty() {
+                offset = Some(digits);
+            }
+        }
+    }
+
+    Some(format!("{}:{}", disk_id?, offset?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partition_identity() {
+        let output = "Volume 3 is the selected volume.\r\n\r\n\
+            Disk ID: {5A1B2C3D-0000-4E5F-8A9B-0C1D2E3F4A5B}\r\n\r\n\
+            Partition 4\r\nType    : ebd0a0a2-b9e5-4433-87c0-68b6b72699c7\r\n\
+            Hidden  : No\r\nOffset in Bytes: 128849018880\r\n";
+        assert_eq!(
+            parse_partition_identity(output).as_deref(),
+            Some("5A1B2C3D-0000-4E5F-8A9B-0C1D2E3F4A5B:128849018880")
+        );
+
+        let output = "磁盘 ID: 1a2b3c4d\r\n分区 2\r\n类型  : 07\r\n偏移量(字节): 1048576\r\n";
+        assert_eq!(parse_partition_identity(output).as_deref(), Some("1A2B3C4D:1048576"));
+
+        assert_eq!(parse_partition_identity("Partition 2\r\nType : 07\r\n"), None);
+    }
+
+    #[test]
+    fn test_format_guid() {
+        let esp = [
+            0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b,
+        ];
+        assert_eq!(GptPartitionType::from_guid(&format_guid(&esp)), Some(GptPartitionType::Esp));
+    }
+
+    #[test]
+    fn test_volume_paths() {
+        let guid = "\\\\?\\Volume{12345678-1234-1234-1234-123456789abc}\\";
+        assert!(is_volume_guid_path(guid));
+        assert!(!is_volume_guid_path("C:"));
+        assert_eq!(drive_letter_of("d:\\"), Some('D'));
+        assert_eq!(drive_letter_of("C:"), Some('C'));
+        assert_eq!(drive_letter_of(guid), None);
+        assert_eq!(drive_letter_of("X:\\Mounts\\Disk1"), None);
+        assert_eq!(volume_root("C:"), "C:\\");
+        assert_eq!(volume_root(guid), guid);
+        assert_eq!(volume_root("X:\\Mounts\\Disk1\\"), "X:\\Mounts\\Disk1\\");
+    }
+
+    #[test]
+    fn test_storage_bus() {
+        assert_eq!(StorageBus::from_bus_type(12), StorageBus::Sd);
+        assert_eq!(StorageBus::from_bus_type(13), StorageBus::Mmc);
+        assert!(StorageBus::from_bus_type(13).is_flash_card());
+        assert!(!StorageBus::from_bus_type(7).is_flash_card());
+        assert_eq!(StorageBus::from_bus_type(17), StorageBus::Other);
+    }
+
+    #[test]
+    fn test_sector_size_label() {
+        assert_eq!(SectorSize::default().label(), "512n");
+        assert_eq!(SectorSize { logical: 512, physical: 4096 }.label(), "512e");
+        let native = SectorSize { logical: 4096, physical: 4096 };
+        assert_eq!(native.label(), "4Kn");
+        assert!(native.is_native_4k());
+        assert!(!SectorSize { logical: 512, physical: 4096 }.is_native_4k());
+    }
+
+    #[test]
+    fn test_temp_drive_letter_existing() {
+        // 已有盘符时直接使用，不会分配临时盘符
+        let drive = TempDriveLetter::acquire("d:\\").unwrap();
+        assert_eq!(drive.drive(), "D:");
+        assert!(!drive.assigned);
+    }
+
+    #[test]
+    fn test_utf16le_with_bom() {
+        assert_eq!(utf16le_with_bom("A中"), vec![0xFF, 0xFE, 0x41, 0x00, 0x2D, 0x4E]);
+    }
+
+    #[test]
+    fn test_data_partition_autorun() {
+        assert!(data_partition_autorun().starts_with("[autorun]\r\nicon=LetRecovery.ico\r\n"));
+    }
+}
@@ -0,0 +1,1060 @@
+//! 原生磁盘操作
+//!
+//! 不依赖 diskpart.exe 和 format.com：分区表通过 IOCTL_DISK_* 直接写入，格式化调用 fmifs.dll 的 FormatEx，
+//! 失败时返回结构化的 [`DiskOpError`]，不再解析本地化的命令行输出，极限精简系统中也可使用。
+
+use crate::core::disk::{
+    GptAttributes, GptPartitionType, PartitionStyle, PlannedPartition, ESP_SIZE_MB, MSR_SIZE_MB,
+};
+
+/// 分区对齐（1 MiB，与磁盘管理和 diskpart 一致）
+pub const ALIGNMENT: u64 = 1024 * 1024;
+/// DRIVE_LAYOUT_INFORMATION_EX 头部大小（PartitionStyle + PartitionCount + 40 字节联合体）
+const LAYOUT_HEADER_SIZE: usize = 48;
+/// PARTITION_INFORMATION_EX 大小
+const PARTITION_ENTRY_SIZE: usize = 144;
+/// MBR 主分区表项数（IOCTL_DISK_SET_DRIVE_LAYOUT_EX 要求 MBR 分区数为 4 的倍数）
+const MBR_ENTRY_COUNT: usize = 4;
+/// GPT 分区名最大长度（UTF-16 字符）
+const GPT_NAME_LEN: usize = 36;
+/// PARTITION_STYLE 取值
+const STYLE_MBR: u32 = 0;
+const STYLE_GPT: u32 = 1;
+
+/// FormatEx 回调命令（fmifs.dll 未公开，取值参考 Rufus/ReactOS）
+const FCC_PROGRESS: u32 = 0;
+const FCC_INCOMPATIBLE_FILE_SYSTEM: u32 = 3;
+const FCC_ACCESS_DENIED: u32 = 6;
+const FCC_MEDIA_WRITE_PROTECTED: u32 = 7;
+const FCC_VOLUME_IN_USE: u32 = 8;
+const FCC_CANT_QUICK_FORMAT: u32 = 9;
+const FCC_DONE: u32 = 11;
+const FCC_BAD_LABEL: u32 = 12;
+const FCC_CLUSTER_SIZE_TOO_SMALL: u32 = 16;
+const FCC_CLUSTER_SIZE_TOO_BIG: u32 = 17;
+const FCC_VOLUME_TOO_SMALL: u32 = 18;
+const FCC_VOLUME_TOO_BIG: u32 = 19;
+const FCC_NO_MEDIA_IN_DRIVE: u32 = 20;
+
+/// FormatEx 报告的格式化失败原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum FormatFailure {
+    #[error("文件系统不受支持")]
+    IncompatibleFileSystem,
+    #[error("拒绝访问")]
+    AccessDenied,
+    #[error("介质写保护")]
+    WriteProtected,
+    #[error("卷正在使用中")]
+    VolumeInUse,
+    #[error("无法快速格式化")]
+    CantQuickFormat,
+    #[error("卷标无效")]
+    BadLabel,
+    #[error("簇大小过小")]
+    ClusterSizeTooSmall,
+    #[error("簇大小过大")]
+    ClusterSizeTooBig,
+    #[error("分区太小，不能使用该文件系统")]
+    VolumeTooSmall,
+    #[error("分区太大，不能使用该文件系统（FAT32 最大 32 GB）")]
+    VolumeTooBig,
+    #[error("驱动器中没有介质")]
+    NoMedia,
+    #[error("未知错误")]
+    Unknown,
+}
+
+impl FormatFailure {
+    /// 回调命令对应的失败原因，非错误命令返回 None
+    fn from_command(command: u32) -> Option<Self> {
+        match command {
+            FCC_INCOMPATIBLE_FILE_SYSTEM => Some(Self::IncompatibleFileSystem),
+            FCC_ACCESS_DENIED => Some(Self::AccessDenied),
+            FCC_MEDIA_WRITE_PROTECTED => Some(Self::WriteProtected),
+            FCC_VOLUME_IN_USE => Some(Self::VolumeInUse),
+            FCC_CANT_QUICK_FORMAT => Some(Self::CantQuickFormat),
+            FCC_BAD_LABEL => Some(Self::BadLabel),
+            FCC_CLUSTER_SIZE_TOO_SMALL => Some(Self::ClusterSizeTooSmall),
+            FCC_CLUSTER_SIZE_TOO_BIG => Some(Self::ClusterSizeTooBig),
+            FCC_VOLUME_TOO_SMALL => Some(Self::VolumeTooSmall),
+            FCC_VOLUME_TOO_BIG => Some(Self::VolumeTooBig),
+            FCC_NO_MEDIA_IN_DRIVE => Some(Self::NoMedia),
+            _ => None,
+        }
+    }
+}
+
+/// 原生磁盘操作错误
+#[derive(Debug, thiserror::Error)]
+pub enum DiskOpError {
+    #[error("无效的分区规划: {0}")]
+    InvalidPlan(String),
+
+    #[error("磁盘 {disk} 空间不足：需要 {required_mb} MB，可用 {available_mb} MB")]
+    InsufficientSpace { disk: u32, required_mb: u64, available_mb: u64 },
+
+    #[error("{operation}失败 (0x{code:08X}): {message}")]
+    Io { operation: String, code: u32, message: String },
+
+    #[error("无法锁定卷 {0}，可能有程序正在使用该分区")]
+    VolumeInUse(String),
+
+    #[error("磁盘 {disk} 偏移 {offset} 处的新分区未能挂载为卷")]
+    VolumeNotReady { disk: u32, offset: u64 },
+
+    #[error("磁盘 {disk} 上不存在分区 {partition}")]
+    PartitionNotFound { disk: u32, partition: u32 },
+
+    #[error("不支持的操作: {0}")]
+    Unsupported(String),
+
+    #[error("无法加载格式化接口 fmifs.dll: {0}")]
+    FormatUnavailable(String),
+
+    #[error("格式化 {volume} 失败: {failure}")]
+    FormatFailed { volume: String, failure: FormatFailure },
+}
+
+/// 分区在磁盘上的位置（字节）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionExtent {
+    pub offset: u64,
+    pub length: u64,
+}
+
+fn align_up(value: u64) -> u64 {
+    value.div_ceil(ALIGNMENT) * ALIGNMENT
+}
+
+fn align_down(value: u64) -> u64 {
+    value / ALIGNMENT * ALIGNMENT
+}
+
+/// 分区的固定大小（MB），ESP/MSR 未指定时使用默认大小，None 表示使用剩余空间
+fn fixed_size_mb(partition: &PlannedPartition) -> Option<u64> {
+    partition.size_mb.or(match partition.partition_type {
+        GptPartitionType::Esp => Some(ESP_SIZE_MB),
+        GptPartitionType::Msr => Some(MSR_SIZE_MB),
+        _ => None,
+    })
+}
+
+/// 在可用范围 [usable_start, usable_end) 内按顺序排列分区，起点和大小按 1 MiB 对齐
+///
+/// 至多一个分区使用剩余空间；MBR 磁盘最多 4 个主分区，且不支持微软保留分区。
+pub fn compute_extents(
+    disk_number: u32,
+    style: PartitionStyle,
+    partitions: &[PlannedPartition],
+    usable_start: u64,
+    usable_end: u64,
+) -> Result<Vec<PartitionExtent>, DiskOpError> {
+    match style {
+        PartitionStyle::GPT => {}
+        PartitionStyle::MBR => {
+            if partitions.len() > MBR_ENTRY_COUNT {
+                return Err(DiskOpError::InvalidPlan(format!("MBR 磁盘最多 {} 个主分区", MBR_ENTRY_COUNT)));
+            }
+            if partitions.iter().any(|p| p.partition_type == GptPartitionType::Msr) {
+                return Err(DiskOpError::InvalidPlan("MBR 磁盘不支持微软保留分区".to_string()));
+            }
+        }
+        PartitionStyle::Unknown => return Err(DiskOpError::InvalidPlan("无效的分区表类型".to_string())),
+    }
+    if partitions.is_empty() {
+        return Err(DiskOpError::InvalidPlan("没有要创建的分区".to_string()));
+    }
+    if partitions.iter().filter(|p| fixed_size_mb(p).is_none()).count() > 1 {
+        return Err(DiskOpError::InvalidPlan("只有一个分区可以使用剩余空间".to_string()));
+    }
+
+    let start = align_up(usable_start).max(ALIGNMENT);
+    let end = align_down(usable_end);
+    let available = end.saturating_sub(start);
+    let fixed: u64 = partitions.iter().filter_map(fixed_size_mb).map(|mb| mb * ALIGNMENT).sum();
+    let needs_remaining = partitions.iter().any(|p| fixed_size_mb(p).is_none());
+    // 使用剩余空间的分区至少保留 1 MiB
+    let required = fixed + if needs_remaining { ALIGNMENT } else { 0 };
+    if required > available {
+        return Err(DiskOpError::InsufficientSpace {
+            disk: disk_number,
+            required_mb: required / ALIGNMENT,
+            available_mb: available / ALIGNMENT,
+        });
+    }
+
+    let mut offset = start;
+    Ok(partitions
+        .iter()
+        .map(|p| {
+            let length = fixed_size_mb(p).map_or(available - fixed, |mb| mb * ALIGNMENT);
+            let extent = PartitionExtent { offset, length };
+            offset += length;
+            extent
+        })
+        .collect())
+}
+
+/// 解析 GUID 字符串为分区表中的字节（混合字节序，与 [`crate::core::disk::format_guid`] 相反）
+fn parse_guid(guid: &str) -> Option<[u8; 16]> {
+    let hex: String = guid.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if hex.len() != 32 || guid.trim_matches(|c| c == '{' || c == '}').len() != 36 {
+        return None;
+    }
+    let mut raw = [0u8; 16];
+    for (i, byte) in raw.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    let mut bytes = raw;
+    bytes[0..4].copy_from_slice(&[raw[3], raw[2], raw[1], raw[0]]);
+    bytes[4..6].copy_from_slice(&[raw[5], raw[4]]);
+    bytes[6..8].copy_from_slice(&[raw[7], raw[6]]);
+    Some(bytes)
+}
+
+/// MBR 分区类型 ID
+fn mbr_type_id(partition: &PlannedPartition) -> u8 {
+    match partition.partition_type {
+        GptPartitionType::Esp => 0xEF,
+        GptPartitionType::Recovery => 0x27,
+        _ if partition.file_system.eq_ignore_ascii_case("FAT32") => 0x0C,
+        _ => 0x07,
+    }
+}
+
+/// GPT 分区名（与 diskpart 创建的分区一致）
+fn gpt_partition_name(partition_type: GptPartitionType) -> &'static str {
+    match partition_type {
+        GptPartitionType::Esp => "EFI system partition",
+        GptPartitionType::Msr => "Microsoft reserved partition",
+        GptPartitionType::BasicData | GptPartitionType::Recovery => "Basic data partition",
+    }
+}
+
+/// 生成 IOCTL_DISK_SET_DRIVE_LAYOUT_EX 的输入
+///
+/// `header_union` 为创建分区表后读回的 DRIVE_LAYOUT_INFORMATION_EX 联合体部分（GPT 磁盘 ID 和可用范围，
+/// 或 MBR 磁盘签名）；`partition_ids` 为各分区的 GPT 分区 ID；`hide` 为 false 时去掉 GPT 隐藏属性，
+/// 以便新分区先挂载为卷完成格式化。
+fn layout_buffer(
+    style: PartitionStyle,
+    header_union: &[u8; 40],
+    partitions: &[PlannedPartition],
+    extents: &[PartitionExtent],
+    partition_ids: &[[u8; 16]],
+    sector_size: u32,
+    hide: bool,
+) -> Vec<u8> {
+    let is_gpt = style == PartitionStyle::GPT;
+    let style_code = if is_gpt { STYLE_GPT } else { STYLE_MBR };
+    let count = if is_gpt { partitions.len() } else { MBR_ENTRY_COUNT };
+
+    let mut buffer = vec![0u8; LAYOUT_HEADER_SIZE + count * PARTITION_ENTRY_SIZE];
+    buffer[0..4].copy_from_slice(&style_code.to_le_bytes());
+    buffer[4..8].copy_from_slice(&(count as u32).to_le_bytes());
+    buffer[8..48].copy_from_slice(header_union);
+
+    for index in 0..count {
+        let entry = &mut buffer[LAYOUT_HEADER_SIZE + index * PARTITION_ENTRY_SIZE..][..PARTITION_ENTRY_SIZE];
+        entry[0..4].copy_from_slice(&style_code.to_le_bytes());
+        // RewritePartition：MBR 的空表项也需要写入
+        entry[28] = 1;
+
+        let (Some(partition), Some(extent)) = (partitions.get(index), extents.get(index)) else {
+            continue;
+        };
+        entry[8..16].copy_from_slice(&extent.offset.to_le_bytes());
+        entry[16..24].copy_from_slice(&extent.length.to_le_bytes());
+        entry[24..28].copy_from_slice(&(index as u32 + 1).to_le_bytes());
+
+        if is_gpt {
+            let type_guid = parse_guid(partition.partition_type.guid()).unwrap_or_default();
+            let mut attributes = partition.gpt_attributes;
+            if !hide {
+                attributes.set(GptAttributes::HIDDEN, false);
+            }
+            entry[32..48].copy_from_slice(&type_guid);
+            entry[48..64].copy_from_slice(&partition_ids.get(index).copied().unwrap_or_default());
+            entry[64..72].copy_from_slice(&attributes.0.to_le_bytes());
+            let name = gpt_partition_name(partition.partition_type).encode_utf16().take(GPT_NAME_LEN);
+            for (i, unit) in name.enumerate() {
+                entry[72 + i * 2..74 + i * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        } else {
+            entry[32] = mbr_type_id(partition);
+            entry[33] = partition.active as u8;
+            entry[34] = 1;
+            let hidden_sectors = (extent.offset / sector_size.max(1) as u64) as u32;
+            entry[36..40].copy_from_slice(&hidden_sectors.to_le_bytes());
+        }
+    }
+    buffer
+}
+
+/// 磁盘上现有的分区
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExistingPartition {
+    /// 分区号（MBR 扩展分区容器为 0）
+    pub number: u32,
+    pub extent: PartitionExtent,
+    /// 是否占用 MBR 主分区表项（含扩展分区容器），GPT 分区均为 true
+    pub primary: bool,
+}
+
+/// 解析 IOCTL_DISK_GET_DRIVE_LAYOUT_EX 的输出，返回分区表类型和现有分区（按位置排序）
+pub fn parse_layout(buffer: &[u8]) -> (PartitionStyle, Vec<ExistingPartition>) {
+    let read_u32 = |at: usize| u32::from_le_bytes(buffer[at..at + 4].try_into().unwrap_or_default());
+    let read_u64 = |at: usize| u64::from_le_bytes(buffer[at..at + 8].try_into().unwrap_or_default());
+
+    if buffer.len() < LAYOUT_HEADER_SIZE {
+        return (PartitionStyle::Unknown, Vec::new());
+    }
+    let style = match read_u32(0) {
+        STYLE_MBR => PartitionStyle::MBR,
+        STYLE_GPT => PartitionStyle::GPT,
+        _ => PartitionStyle::Unknown,
+    };
+    let count = (read_u32(4) as usize).min((buffer.len() - LAYOUT_HEADER_SIZE) / PARTITION_ENTRY_SIZE);
+    let mut partitions: Vec<ExistingPartition> = (0..count)
+        .map(|index| (index, LAYOUT_HEADER_SIZE + index * PARTITION_ENTRY_SIZE))
+        .filter(|&(_, entry)| read_u64(entry + 16) > 0)
+        .map(|(index, entry)| ExistingPartition {
+            number: read_u32(entry + 24),
+            extent: PartitionExtent { offset: read_u64(entry + 8), length: read_u64(entry + 16) },
+            primary: style != PartitionStyle::MBR || index < MBR_ENTRY_COUNT,
+        })
+        .collect();
+    partitions.sort_by_key(|p| p.extent.offset);
+    (style, partitions)
+}
+
+/// 从 IOCTL_DISK_GET_DRIVE_LAYOUT_EX 的输出中删除指定分区，返回被删除分区的位置
+///
+/// GPT 直接移除表项，MBR 清空对应的主分区表项；其余表项标记为重写。
+fn remove_layout_entry(
+    buffer: &mut Vec<u8>,
+    disk_number: u32,
+    partition_number: u32,
+) -> Result<PartitionExtent, DiskOpError> {
+    let read_u32 = |b: &[u8], at: usize| u32::from_le_bytes(b[at..at + 4].try_into().unwrap_or_default());
+    let read_u64 = |b: &[u8], at: usize| u64::from_le_bytes(b[at..at + 8].try_into().unwrap_or_default());
+
+    if buffer.len() < LAYOUT_HEADER_SIZE {
+        return Err(DiskOpError::PartitionNotFound { disk: disk_number, partition: partition_number });
+    }
+    let style = read_u32(buffer, 0);
+    let count = (read_u32(buffer, 4) as usize).min((buffer.len() - LAYOUT_HEADER_SIZE) / PARTITION_ENTRY_SIZE);
+    let entry_at = |index: usize| LAYOUT_HEADER_SIZE + index * PARTITION_ENTRY_SIZE;
+
+    let index = (0..count)
+        .find(|&i| read_u32(buffer, entry_at(i) + 24) == partition_number && read_u64(buffer, entry_at(i) + 16) > 0)
+        .ok_or(DiskOpError::PartitionNotFound { disk: disk_number, partition: partition_number })?;
+    let extent = PartitionExtent {
+        offset: read_u64(buffer, entry_at(index) + 8),
+        length: read_u64(buffer, entry_at(index) + 16),
+    };
+
+    if style == STYLE_GPT {
+        buffer.drain(entry_at(index)..entry_at(index + 1));
+        buffer[4..8].copy_from_slice(&(count as u32 - 1).to_le_bytes());
+    } else {
+        if index >= MBR_ENTRY_COUNT {
+            return Err(DiskOpError::Unsupported("暂不支持删除扩展分区中的逻辑分区".to_string()));
+        }
+        let start = entry_at(index);
+        buffer[start + 8..start + PARTITION_ENTRY_SIZE].fill(0);
+    }
+    let count = read_u32(buffer, 4) as usize;
+    for i in 0..count.min((buffer.len() - LAYOUT_HEADER_SIZE) / PARTITION_ENTRY_SIZE) {
+        buffer[entry_at(i) + 28] = 1;
+    }
+    Ok(extent)
+}
+
+#[cfg(windows)]
+pub use native::{delete_partition, format_available, format_volume, read_partitions, repartition};
+
+#[cfg(not(windows))]
+pub fn format_available() -> bool {
+    false
+}
+
+#[cfg(not(windows))]
+pub fn format_volume(
+    _volume: &str,
+    _file_system: &str,
+    _label: &str,
+    _on_progress: impl Fn(u8) + Send + 'static,
+) -> Result<(), DiskOpError> {
+    Err(DiskOpError::Unsupported("仅支持 Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+pub fn repartition(_plan: &crate::core::disk::PartitionPlan) -> Result<Vec<String>, DiskOpError> {
+    Err(DiskOpError::Unsupported("仅支持 Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+pub fn delete_partition(_disk_number: u32, _partition_number: u32) -> Result<(), DiskOpError> {
+    Err(DiskOpError::Unsupported("仅支持 Windows".to_string()))
+}
+
+#[cfg(not(windows))]
+pub fn read_partitions(_disk_number: u32) -> Result<(PartitionStyle, Vec<ExistingPartition>), DiskOpError> {
+    Err(DiskOpError::Unsupported("仅支持 Windows".to_string()))
+}
+
+#[cfg(windows)]
+mod native {
+    use std::ffi::c_void;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    use libloading::Library;
+    use windows::{
+        core::PCWSTR,
+        Win32::Foundation::{CloseHandle, GENERIC_READ, GENERIC_WRITE, HANDLE},
+        Win32::Storage::FileSystem::{
+            CreateFileW, DeleteVolumeMountPointW, GetVolumePathNamesForVolumeNameW, SetVolumeMountPointW,
+            FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        },
+        Win32::System::Com::CoCreateGuid,
+        Win32::System::IO::DeviceIoControl,
+        Win32::System::Ioctl::{
+            FSCTL_DISMOUNT_VOLUME, FSCTL_LOCK_VOLUME, IOCTL_DISK_CREATE_DISK, IOCTL_DISK_DELETE_DRIVE_LAYOUT,
+            IOCTL_DISK_GET_DRIVE_GEOMETRY_EX, IOCTL_DISK_GET_DRIVE_LAYOUT_EX, IOCTL_DISK_SET_DRIVE_LAYOUT_EX,
+            IOCTL_DISK_UPDATE_PROPERTIES,
+        },
+    };
+
+    use super::*;
+    use crate::core::disk::{DiskManager, PartitionPlan};
+
+    /// IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS 常量
+    const IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS: u32 = 0x00560000;
+    /// GPT 分区表最大分区数
+    const GPT_MAX_PARTITIONS: u32 = 128;
+    /// FormatEx 介质类型：硬盘
+    const FMIFS_HARDDISK: u32 = 0x0C;
+    /// 等待新分区挂载为卷的超时
+    const VOLUME_ARRIVAL_TIMEOUT: Duration = Duration::from_secs(20);
+
+    type FormatExFn = unsafe extern "system" fn(
+        drive_root: PCWSTR,
+        media_flag: u32,
+        file_system: PCWSTR,
+        label: PCWSTR,
+        quick_format: i32,
+        cluster_size: u32,
+        callback: FormatCallback,
+    );
+    type FormatCallback = unsafe extern "system" fn(command: u32, sub_action: u32, action_info: *mut c_void) -> u8;
+
+    /// 一次 FormatEx 调用的状态（回调没有上下文参数，只能通过全局变量传递）
+    struct FormatSession {
+        on_progress: Box<dyn Fn(u8) + Send>,
+        failure: Option<FormatFailure>,
+        success: bool,
+    }
+
+    static FMIFS: OnceLock<Result<Library, String>> = OnceLock::new();
+    static FORMAT_SESSION: Mutex<Option<FormatSession>> = Mutex::new(None);
+    /// FormatEx 调用互斥（同一时间只能格式化一个卷）
+    static FORMAT_LOCK: Mutex<()> = Mutex::new(());
+
+    fn fmifs() -> Result<&'static Library, DiskOpError> {
+        FMIFS
+            .get_or_init(|| unsafe { Library::new("fmifs.dll") }.map_err(|e| e.to_string()))
+            .as_ref()
+            .map_err(|e| DiskOpError::FormatUnavailable(e.clone()))
+    }
+
+    /// FormatEx 是否可用
+    pub fn format_available() -> bool {
+        fmifs().is_ok_and(|lib| unsafe { lib.get::<FormatExFn>(b"FormatEx\0") }.is_ok())
+    }
+
+    unsafe extern "system" fn format_callback(command: u32, _sub_action: u32, action_info: *mut c_void) -> u8 {
+        let mut session = FORMAT_SESSION.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(session) = session.as_mut() else {
+            return 1;
+        };
+        match command {
+            FCC_PROGRESS if !action_info.is_null() => {
+                (session.on_progress)((*(action_info as *const u32)).min(100) as u8);
+            }
+            FCC_DONE => {
+                session.success = !action_info.is_null() && *(action_info as *const u8) != 0;
+            }
+            _ => {
+                if let Some(failure) = FormatFailure::from_command(command) {
+                    session.failure.get_or_insert(failure);
+                }
+            }
+        }
+        1
+    }
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// 快速格式化卷（盘符、挂载文件夹或卷 GUID 路径），进度为 0-100
+    pub fn format_volume(
+        volume: &str,
+        file_system: &str,
+        label: &str,
+        on_progress: impl Fn(u8) + Send + 'static,
+    ) -> Result<(), DiskOpError> {
+        let format_ex = unsafe { fmifs()?.get::<FormatExFn>(b"FormatEx\0") }
+            .map_err(|e| DiskOpError::FormatUnavailable(e.to_string()))?;
+
+        let _guard = FORMAT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        *FORMAT_SESSION.lock().unwrap_or_else(|e| e.into_inner()) = Some(FormatSession {
+            on_progress: Box::new(on_progress),
+            failure: None,
+            success: false,
+        });
+
+        let root = wide(&crate::core::disk::volume_root(volume));
+        let fs = wide(if file_system.is_empty() { "NTFS" } else { file_system });
+        let label = wide(label);
+        log::info!("[DISK] FormatEx {} ({})", volume, String::from_utf16_lossy(&fs[..fs.len() - 1]));
+        unsafe {
+            format_ex(
+                PCWSTR(root.as_ptr()),
+                FMIFS_HARDDISK,
+                PCWSTR(fs.as_ptr()),
+                PCWSTR(label.as_ptr()),
+                1,
+                0,
+                format_callback,
+            );
+        }
+
+        let session = FORMAT_SESSION.lock().unwrap_or_else(|e| e.into_inner()).take();
+        match session {
+            Some(session) if session.success => Ok(()),
+            session => Err(DiskOpError::FormatFailed {
+                volume: volume.to_string(),
+                failure: session.and_then(|s| s.failure).unwrap_or(FormatFailure::Unknown),
+            }),
+        }
+    }
+
+    /// 自动关闭的设备句柄
+    struct Device(HANDLE);
+
+    impl Device {
+        fn open(path: &str) -> Result<Self, DiskOpError> {
+            let wide_path = wide(path);
+            let handle = unsafe {
+                CreateFileW(
+                    PCWSTR(wide_path.as_ptr()),
+                    GENERIC_READ.0 | GENERIC_WRITE.0,
+                    FILE_SHARE_READ | FILE_SHARE_WRITE,
+                    None,
+                    OPEN_EXISTING,
+                    Default::default(),
+                    None,
+                )
+            }
+            .map_err(|e| io_error(&format!("打开 {} ", path), e))?;
+            Ok(Self(handle))
+        }
+
+        fn ioctl(&self, operation: &str, code: u32, input: &[u8], output: &mut [u8]) -> Result<u32, DiskOpError> {
+            let mut bytes_returned = 0u32;
+            unsafe {
+                DeviceIoControl(
+                    self.0,
+                    code,
+                    (!input.is_empty()).then_some(input.as_ptr() as *const c_void),
+                    input.len() as u32,
+                    (!output.is_empty()).then_some(output.as_mut_ptr() as *mut c_void),
+                    output.len() as u32,
+                    Some(&mut bytes_returned),
+                    None,
+                )
+            }
+            .map_err(|e| io_error(operation, e))?;
+            Ok(bytes_returned)
+        }
+    }
+
+    impl Drop for Device {
+        fn drop(&mut self) {
+            let _ = unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    fn io_error(operation: &str, error: windows::core::Error) -> DiskOpError {
+        DiskOpError::Io {
+            operation: operation.to_string(),
+            code: error.code().0 as u32,
+            message: error.message().to_string(),
+        }
+    }
+
+    fn new_guid() -> Result<[u8; 16], DiskOpError> {
+        let guid = unsafe { CoCreateGuid() }.map_err(|e| io_error("生成 GUID", e))?;
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&guid.data1.to_le_bytes());
+        bytes[4..6].copy_from_slice(&guid.data2.to_le_bytes());
+        bytes[6..8].copy_from_slice(&guid.data3.to_le_bytes());
+        bytes[8..16].copy_from_slice(&guid.data4);
+        Ok(bytes)
+    }
+
+    /// 锁定并卸载卷，句柄关闭前其他程序无法访问
+    fn lock_volume(volume: &str) -> Result<Device, DiskOpError> {
+        let device = Device::open(volume.trim_end_matches('\\'))?;
+        device
+            .ioctl("锁定卷", FSCTL_LOCK_VOLUME, &[], &mut [])
+            .map_err(|_| DiskOpError::VolumeInUse(volume.to_string()))?;
+        device.ioctl("卸载卷", FSCTL_DISMOUNT_VOLUME, &[], &mut [])?;
+        Ok(device)
+    }
+
+    /// 锁定并卸载磁盘上的全部卷
+    fn lock_disk_volumes(disk_number: u32) -> Result<Vec<Device>, DiskOpError> {
+        DiskManager::list_volumes()
+            .iter()
+            .filter(|volume| DiskManager::disk_number_of(volume) == Some(disk_number))
+            .map(|volume| lock_volume(volume))
+            .collect()
+    }
+
+    /// 卷所在的磁盘号和起始偏移
+    fn volume_extent(volume: &str) -> Option<(u32, u64)> {
+        let path = wide(volume.trim_end_matches('\\'));
+        let handle = unsafe {
+            CreateFileW(
+                PCWSTR(path.as_ptr()),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            )
+        }
+        .ok()?;
+        let device = Device(handle);
+        // VOLUME_DISK_EXTENTS { NumberOfDiskExtents, DISK_EXTENT { DiskNumber, StartingOffset, ExtentLength } }
+        let mut buffer = [0u8; 32];
+        device
+            .ioctl("查询卷位置", IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS, &[], &mut buffer)
+            .ok()?;
+        let count = u32::from_le_bytes(buffer[0..4].try_into().ok()?);
+        (count == 1).then(|| {
+            (
+                u32::from_le_bytes(buffer[8..12].try_into().unwrap_or_default()),
+                u64::from_le_bytes(buffer[16..24].try_into().unwrap_or_default()),
+            )
+        })
+    }
+
+    /// 等待新分区挂载为卷，返回卷 GUID 路径（不带结尾反斜杠）
+    fn wait_for_volume(disk_number: u32, offset: u64) -> Result<String, DiskOpError> {
+        let started = Instant::now();
+        loop {
+            if let Some(volume) = DiskManager::list_volumes()
+                .into_iter()
+                .find(|volume| volume_extent(volume) == Some((disk_number, offset)))
+            {
+                return Ok(volume);
+            }
+            if started.elapsed() > VOLUME_ARRIVAL_TIMEOUT {
+                return Err(DiskOpError::VolumeNotReady { disk: disk_number, offset });
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+    }
+
+    /// 卷当前的盘符
+    fn volume_letters(volume: &str) -> Vec<char> {
+        let name = wide(&crate::core::disk::volume_root(volume));
+        let mut paths = [0u16; 1024];
+        let mut length = 0u32;
+        if unsafe { GetVolumePathNamesForVolumeNameW(PCWSTR(name.as_ptr()), Some(&mut paths), &mut length) }.is_err() {
+            return Vec::new();
+        }
+        String::from_utf16_lossy(&paths[..(length as usize).min(paths.len())])
+            .split('\0')
+            .filter_map(crate::core::disk::drive_letter_of)
+            .collect()
+    }
+
+    /// 为卷分配盘符：指定盘符时替换自动分配的盘符，未指定时沿用自动分配的盘符或选择空闲盘符
+    fn assign_letter(volume: &str, requested: Option<char>) -> Result<char, DiskOpError> {
+        let current = volume_letters(volume);
+        let letter = match requested.map(|l| l.to_ascii_uppercase()) {
+            Some(letter) if current.contains(&letter) => return Ok(letter),
+            Some(letter) => letter,
+            None if !current.is_empty() => return Ok(current[0]),
+            None => DiskManager::find_available_drive_letter()
+                .ok_or_else(|| DiskOpError::Unsupported("没有可用的盘符".to_string()))?,
+        };
+        for old in current {
+            let mount = wide(&format!("{}:\\", old));
+            let _ = unsafe { DeleteVolumeMountPointW(PCWSTR(mount.as_ptr())) };
+        }
+        let mount = wide(&format!("{}:\\", letter));
+        let name = wide(&crate::core::disk::volume_root(volume));
+        unsafe { SetVolumeMountPointW(PCWSTR(mount.as_ptr()), PCWSTR(name.as_ptr())) }
+            .map_err(|e| io_error(&format!("分配盘符 {}: ", letter), e))?;
+        Ok(letter)
+    }
+
+    fn physical_drive(disk_number: u32) -> String {
+        format!("\\\\.\\PhysicalDrive{}", disk_number)
+    }
+
+    /// 读取分区表布局
+    fn read_layout(disk: &Device) -> Result<Vec<u8>, DiskOpError> {
+        let mut buffer = vec![0u8; LAYOUT_HEADER_SIZE + GPT_MAX_PARTITIONS as usize * PARTITION_ENTRY_SIZE];
+        let size = disk.ioctl("读取分区表", IOCTL_DISK_GET_DRIVE_LAYOUT_EX, &[], &mut buffer)?;
+        buffer.truncate(size as usize);
+        Ok(buffer)
+    }
+
+    fn write_layout(disk: &Device, layout: &[u8]) -> Result<(), DiskOpError> {
+        disk.ioctl("写入分区表", IOCTL_DISK_SET_DRIVE_LAYOUT_EX, layout, &mut [])?;
+        disk.ioctl("刷新磁盘属性", IOCTL_DISK_UPDATE_PROPERTIES, &[], &mut [])?;
+        Ok(())
+    }
+
+    /// 清空磁盘并按规划创建、格式化分区，返回创建的分区名称（与 diskpart 方式一致）
+    pub fn repartition(plan: &PartitionPlan) -> Result<Vec<String>, DiskOpError> {
+        let is_gpt = match plan.target_style {
+            PartitionStyle::GPT => true,
+            PartitionStyle::MBR => false,
+            PartitionStyle::Unknown => return Err(DiskOpError::InvalidPlan("无效的分区表类型".to_string())),
+        };
+        let disk_number = plan.disk_number;
+        let partition_ids = plan.partitions.iter().map(|_| new_guid()).collect::<Result<Vec<_>, _>>()?;
+
+        let locks = lock_disk_volumes(disk_number)?;
+        let disk = Device::open(&physical_drive(disk_number))?;
+
+        // DISK_GEOMETRY_EX：BytesPerSector 位于偏移 20，DiskSize 位于偏移 24
+        let mut geometry = [0u8; 256];
+        disk.ioctl("读取磁盘几何信息", IOCTL_DISK_GET_DRIVE_GEOMETRY_EX, &[], &mut geometry)?;
+        let sector_size = u32::from_le_bytes(geometry[20..24].try_into().unwrap_or_default());
+        let disk_size = u64::from_le_bytes(geometry[24..32].try_into().unwrap_or_default());
+
+        // 空白磁盘没有分区表可删除
+        if let Err(e) = disk.ioctl("清除分区表", IOCTL_DISK_DELETE_DRIVE_LAYOUT, &[], &mut []) {
+            log::warn!("[DISK] {}", e);
+        }
+
+        // CREATE_DISK { PartitionStyle, union { MBR: Signature; GPT: DiskId, MaxPartitionCount } }
+        let mut create = [0u8; 24];
+        let disk_id = new_guid()?;
+        if is_gpt {
+            create[0..4].copy_from_slice(&STYLE_GPT.to_le_bytes());
+            create[4..20].copy_from_slice(&disk_id);
+            create[20..24].copy_from_slice(&GPT_MAX_PARTITIONS.to_le_bytes());
+        } else {
+            create[0..4].copy_from_slice(&STYLE_MBR.to_le_bytes());
+            let signature = u32::from_le_bytes(disk_id[0..4].try_into().unwrap_or_default()).max(1);
+            create[4..8].copy_from_slice(&signature.to_le_bytes());
+        }
+        disk.ioctl("初始化分区表", IOCTL_DISK_CREATE_DISK, &create, &mut [])?;
+        disk.ioctl("刷新磁盘属性", IOCTL_DISK_UPDATE_PROPERTIES, &[], &mut [])?;
+
+        // 读回磁盘 ID/签名；GPT 的可用范围为 StartingUsableOffset 和 UsableLength
+        let layout = read_layout(&disk)?;
+        let mut header_union = [0u8; 40];
+        header_union.copy_from_slice(&layout[8..48]);
+        let (usable_start, usable_end) = if is_gpt {
+            let start = u64::from_le_bytes(layout[24..32].try_into().unwrap_or_default());
+            let length = u64::from_le_bytes(layout[32..40].try_into().unwrap_or_default());
+            (start, start + length)
+        } else {
+            (0, disk_size)
+        };
+
+        let extents = compute_extents(disk_number, plan.target_style, &plan.partitions, usable_start, usable_end)?;
+        let layout = layout_buffer(
+            plan.target_style,
+            &header_union,
+            &plan.partitions,
+            &extents,
+            &partition_ids,
+            sector_size,
+            false,
+        );
+        write_layout(&disk, &layout)?;
+        drop(locks);
+
+        let mut created = Vec::new();
+        for (i, (partition, extent)) in plan.partitions.iter().zip(&extents).enumerate() {
+            if partition.partition_type == GptPartitionType::Msr {
+                created.push("MSR".to_string());
+                continue;
+            }
+
+            let volume = wait_for_volume(disk_number, extent.offset)?;
+            let label = if partition.label.is_empty() { "新加卷" } else { &partition.label };
+            format_volume(&volume, &partition.file_system, label, |_| {})?;
+
+            let is_esp = is_gpt && partition.partition_type == GptPartitionType::Esp;
+            let hide_letter = if is_gpt {
+                is_esp || partition.gpt_attributes.contains(GptAttributes::NO_DRIVE_LETTER)
+            } else {
+                partition.partition_type == GptPartitionType::Recovery
+            };
+            if hide_letter {
+                for letter in volume_letters(&volume) {
+                    let mount = wide(&format!("{}:\\", letter));
+                    let _ = unsafe { DeleteVolumeMountPointW(PCWSTR(mount.as_ptr())) };
+                }
+                created.push(if is_esp {
+                    "ESP".to_string()
+                } else {
+                    partition.partition_type.label().to_string()
+                });
+            } else {
+                match assign_letter(&volume, partition.drive_letter) {
+                    Ok(letter) => created.push(format!("{}:", letter)),
+                    Err(e) => {
+                        log::warn!("[DISK] {}", e);
+                        created.push(format!("分区 {}", i + 1));
+                    }
+                }
+            }
+        }
+
+        // 隐藏属性会让卷不再挂载，格式化完成后再写入
+        if is_gpt && plan.partitions.iter().any(|p| p.gpt_attributes.contains(GptAttributes::HIDDEN)) {
+            let layout = layout_buffer(
+                plan.target_style,
+                &header_union,
+                &plan.partitions,
+                &extents,
+                &partition_ids,
+                sector_size,
+                true,
+            );
+            write_layout(&disk, &layout)?;
+        }
+
+        log::info!("[DISK] 磁盘 {} 原生分区完成: {}", disk_number, created.join(", "));
+        Ok(created)
+    }
+
+    /// 读取磁盘的分区表类型和现有分区
+    pub fn read_partitions(disk_number: u32) -> Result<(PartitionStyle, Vec<ExistingPartition>), DiskOpError> {
+        let disk = Device::open(&physical_drive(disk_number))?;
+        Ok(parse_layout(&read_layout(&disk)?))
+    }
+
+    /// 删除分区（按磁盘号和分区号），分区上的卷会先被锁定并卸载
+    pub fn delete_partition(disk_number: u32, partition_number: u32) -> Result<(), DiskOpError> {
+        let disk = Device::open(&physical_drive(disk_number))?;
+        let mut layout = read_layout(&disk)?;
+        let extent = remove_layout_entry(&mut layout, disk_number, partition_number)?;
+
+        let lock = DiskManager::list_volumes()
+            .into_iter()
+            .find(|volume| volume_extent(volume) == Some((disk_number, extent.offset)))
+            .map(|volume| lock_volume(&volume))
+            .transpose()?;
+        write_layout(&disk, &layout)?;
+        drop(lock);
+
+        log::info!("[DISK] 已删除磁盘 {} 分区 {}", disk_number, partition_number);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn planned(partition_type: GptPartitionType, size_mb: Option<u64>) -> PlannedPartition {
+        PlannedPartition::new(partition_type, size_mb)
+    }
+
+    #[test]
+    fn test_compute_extents_aligns_and_fills_remaining() {
+        let partitions = vec![
+            planned(GptPartitionType::Esp, None),
+            planned(GptPartitionType::Msr, None),
+            planned(GptPartitionType::BasicData, None),
+            planned(GptPartitionType::Recovery, Some(1024)),
+        ];
+        // GPT 可用范围从 LBA 34 开始，末尾保留备份分区表
+        let disk_size = 100 * 1024 * ALIGNMENT;
+        let extents = compute_extents(0, PartitionStyle::GPT, &partitions, 34 * 512, disk_size - 33 * 512).unwrap();
+
+        assert_eq!(extents[0], PartitionExtent { offset: ALIGNMENT, length: ESP_SIZE_MB * ALIGNMENT });
+        assert_eq!(extents[1].offset, (1 + ESP_SIZE_MB) * ALIGNMENT);
+        assert_eq!(extents[1].length, MSR_SIZE_MB * ALIGNMENT);
+        assert_eq!(extents[2].offset, (1 + ESP_SIZE_MB + MSR_SIZE_MB) * ALIGNMENT);
+        let last = extents[3];
+        assert_eq!(last.length, 1024 * ALIGNMENT);
+        assert_eq!(last.offset + last.length, disk_size - ALIGNMENT);
+        assert!(extents.iter().all(|e| e.offset % ALIGNMENT == 0 && e.length % ALIGNMENT == 0));
+        assert!(extents.windows(2).all(|w| w[0].offset + w[0].length == w[1].offset));
+    }
+
+    #[test]
+    fn test_compute_extents_rejects_invalid_plans() {
+        let disk_size = 10 * 1024 * ALIGNMENT;
+        let msr = vec![planned(GptPartitionType::Msr, None)];
+        assert!(matches!(
+            compute_extents(0, PartitionStyle::MBR, &msr, 0, disk_size),
+            Err(DiskOpError::InvalidPlan(_))
+        ));
+
+        let two_remaining = vec![planned(GptPartitionType::BasicData, None), planned(GptPartitionType::BasicData, None)];
+        assert!(matches!(
+            compute_extents(0, PartitionStyle::GPT, &two_remaining, 0, disk_size),
+            Err(DiskOpError::InvalidPlan(_))
+        ));
+
+        let five = vec![planned(GptPartitionType::BasicData, Some(100)); 5];
+        assert!(matches!(
+            compute_extents(0, PartitionStyle::MBR, &five, 0, disk_size),
+            Err(DiskOpError::InvalidPlan(_))
+        ));
+
+        let too_big = vec![planned(GptPartitionType::BasicData, Some(20 * 1024))];
+        match compute_extents(3, PartitionStyle::MBR, &too_big, 0, disk_size) {
+            Err(DiskOpError::InsufficientSpace { disk, required_mb, available_mb }) => {
+                assert_eq!((disk, required_mb, available_mb), (3, 20 * 1024, 10 * 1024 - 1));
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_guid_round_trip() {
+        for partition_type in GptPartitionType::ALL {
+            let bytes = parse_guid(partition_type.guid()).unwrap();
+            assert!(crate::core::disk::format_guid(&bytes).eq_ignore_ascii_case(partition_type.guid()));
+        }
+        assert_eq!(parse_guid("{c12a7328-f81f-11d2-ba4b-00a0c93ec93b}").unwrap()[0], 0x28);
+        assert!(parse_guid("c12a7328f81f11d2ba4b00a0c93ec93b").is_none());
+        assert!(parse_guid("not-a-guid").is_none());
+    }
+
+    #[test]
+    fn test_layout_buffer_gpt() {
+        let mut recovery = planned(GptPartitionType::Recovery, Some(1024));
+        recovery.gpt_attributes.set(GptAttributes::HIDDEN, true);
+        let partitions = vec![planned(GptPartitionType::Esp, None), recovery];
+        let extents = vec![
+            PartitionExtent { offset: ALIGNMENT, length: 300 * ALIGNMENT },
+            PartitionExtent { offset: 301 * ALIGNMENT, length: 1024 * ALIGNMENT },
+        ];
+        let ids = [[0x11; 16], [0x22; 16]];
+        let header = [0xAB; 40];
+
+        let buffer = layout_buffer(PartitionStyle::GPT, &header, &partitions, &extents, &ids, 512, false);
+        assert_eq!(buffer.len(), LAYOUT_HEADER_SIZE + 2 * PARTITION_ENTRY_SIZE);
+        assert_eq!(&buffer[0..8], &[1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(&buffer[8..48], &header[..]);
+
+        let second = &buffer[LAYOUT_HEADER_SIZE + PARTITION_ENTRY_SIZE..];
+        assert_eq!(u64::from_le_bytes(second[8..16].try_into().unwrap()), 301 * ALIGNMENT);
+        assert_eq!(u32::from_le_bytes(second[24..28].try_into().unwrap()), 2);
+        assert_eq!(second[28], 1);
+        assert_eq!(crate::core::disk::format_guid(&second[32..48].try_into().unwrap()).to_lowercase(), GptPartitionType::Recovery.guid());
+        assert_eq!(&second[48..64], &[0x22; 16]);
+        let attributes = GptAttributes(u64::from_le_bytes(second[64..72].try_into().unwrap()));
+        assert!(!attributes.contains(GptAttributes::HIDDEN));
+        assert!(attributes.contains(GptAttributes::NO_DRIVE_LETTER));
+        assert_eq!(&second[72..76], &[b'B', 0, b'a', 0]);
+
+        let hidden = layout_buffer(PartitionStyle::GPT, &header, &partitions, &extents, &ids, 512, true);
+        let attributes = u64::from_le_bytes(hidden[LAYOUT_HEADER_SIZE + PARTITION_ENTRY_SIZE + 64..][..8].try_into().unwrap());
+        assert!(GptAttributes(attributes).contains(GptAttributes::HIDDEN));
+    }
+
+    #[test]
+    fn test_layout_buffer_mbr_pads_to_four_entries() {
+        let mut os = planned(GptPartitionType::BasicData, None);
+        os.active = true;
+        let partitions = vec![os, planned(GptPartitionType::Recovery, Some(1024))];
+        let extents = vec![
+            PartitionExtent { offset: ALIGNMENT, length: 1000 * ALIGNMENT },
+            PartitionExtent { offset: 1001 * ALIGNMENT, length: 1024 * ALIGNMENT },
+        ];
+
+        let buffer = layout_buffer(PartitionStyle::MBR, &[0; 40], &partitions, &extents, &[], 512, false);
+        assert_eq!(buffer.len(), LAYOUT_HEADER_SIZE + 4 * PARTITION_ENTRY_SIZE);
+        assert_eq!(u32::from_le_bytes(buffer[4..8].try_into().unwrap()), 4);
+
+        let entry = |i: usize| &buffer[LAYOUT_HEADER_SIZE + i * PARTITION_ENTRY_SIZE..][..PARTITION_ENTRY_SIZE];
+        assert_eq!((entry(0)[32], entry(0)[33], entry(0)[34]), (0x07, 1, 1));
+        assert_eq!(u32::from_le_bytes(entry(0)[36..40].try_into().unwrap()), 2048);
+        assert_eq!((entry(1)[32], entry(1)[33]), (0x27, 0));
+        assert_eq!(entry(3)[28], 1);
+        assert!(entry(3)[8..28].iter().chain(&entry(3)[32..]).all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_remove_layout_entry() {
+        let partitions = vec![
+            planned(GptPartitionType::Esp, None),
+            planned(GptPartitionType::BasicData, Some(100)),
+            planned(GptPartitionType::BasicData, Some(200)),
+        ];
+        let extents = compute_extents(0, PartitionStyle::GPT, &partitions, 0, 1024 * ALIGNMENT).unwrap();
+        let mut gpt = layout_buffer(PartitionStyle::GPT, &[0; 40], &partitions, &extents, &[[1; 16], [2; 16], [3; 16]], 512, true);
+        for i in 0..3 {
+            gpt[LAYOUT_HEADER_SIZE + i * PARTITION_ENTRY_SIZE + 28] = 0;
+        }
+
+        let removed = remove_layout_entry(&mut gpt, 0, 2).unwrap();
+        assert_eq!(removed, extents[1]);
+        assert_eq!(gpt.len(), LAYOUT_HEADER_SIZE + 2 * PARTITION_ENTRY_SIZE);
+        assert_eq!(u32::from_le_bytes(gpt[4..8].try_into().unwrap()), 2);
+        assert_eq!(gpt[LAYOUT_HEADER_SIZE + PARTITION_ENTRY_SIZE + 48], 3);
+        assert!((0..2).all(|i| gpt[LAYOUT_HEADER_SIZE + i * PARTITION_ENTRY_SIZE + 28] == 1));
+        assert!(matches!(
+            remove_layout_entry(&mut gpt, 0, 2),
+            Err(DiskOpError::PartitionNotFound { disk: 0, partition: 2 })
+        ));
+
+        let mbr_parts = vec![planned(GptPartitionType::BasicData, Some(100))];
+        let mut mbr = layout_buffer(PartitionStyle::MBR, &[0; 40], &mbr_parts, &extents[..1], &[], 512, false);
+        remove_layout_entry(&mut mbr, 0, 1).unwrap();
+        assert_eq!(mbr.len(), LAYOUT_HEADER_SIZE + 4 * PARTITION_ENTRY_SIZE);
+        assert!(mbr[LAYOUT_HEADER_SIZE + 8..LAYOUT_HEADER_SIZE + 28].iter().all(|&b| b == 0));
+        assert_eq!(mbr[LAYOUT_HEADER_SIZE + 28], 1);
+        assert_eq!(mbr[LAYOUT_HEADER_SIZE + 32], 0);
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        let partitions = vec![
+            planned(GptPartitionType::BasicData, Some(100)),
+            planned(GptPartitionType::Recovery, Some(200)),
+        ];
+        let extents = compute_extents(0, PartitionStyle::MBR, &partitions, 0, 1024 * ALIGNMENT).unwrap();
+        let mbr = layout_buffer(PartitionStyle::MBR, &[0; 40], &partitions, &extents, &[], 512, false);
+        let (style, parsed) = parse_layout(&mbr);
+        assert_eq!(style, PartitionStyle::MBR);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!((parsed[0].number, parsed[0].extent, parsed[0].primary), (1, extents[0], true));
+        assert_eq!((parsed[1].number, parsed[1].extent), (2, extents[1]));
+
+        let gpt = layout_buffer(PartitionStyle::GPT, &[0; 40], &partitions, &extents, &[[1; 16], [2; 16]], 512, false);
+        assert_eq!(parse_layout(&gpt).0, PartitionStyle::GPT);
+        assert_eq!(parse_layout(&[0; 8]), (PartitionStyle::Unknown, Vec::new()));
+    }
+
+    #[test]
+    fn test_format_failure_from_command() {
+        assert_eq!(FormatFailure::from_command(FCC_VOLUME_IN_USE), Some(FormatFailure::VolumeInUse));
+        assert_eq!(FormatFailure::from_command(FCC_VOLUME_TOO_BIG), Some(FormatFailure::VolumeTooBig));
+        assert_eq!(FormatFailure::from_command(FCC_PROGRESS), None);
+        assert_eq!(FormatFailure::from_command(FCC_DONE), None);
+    }
+}
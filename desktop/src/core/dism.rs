@@ -1,8 +1,8 @@
 //! 镜像操作模块
 //!
 //! 该模块封装了 Windows 系统镜像操作功能：
-//! - 镜像释放/应用：使用 wimgapi.dll
-//! - 镜像备份/捕获：使用 wimgapi.dll
+//! - 镜像释放/应用：使用 wimgapi.dll，存在 wimlib.dll 时可改用 wimlib
+//! - 镜像备份/捕获：使用 wimgapi.dll，存在 wimlib.dll 时可改用 wimlib
 //! - 离线驱动导入：使用 dism.exe 命令行（优先使用 {程序目录}\bin\Dism\dism.exe）
 //! - 离线 CAB 包导入：使用 dism.exe 命令行
 //! - 镜像信息获取：使用 wimgapi.dll + WIM XML 解析
@@ -12,10 +12,12 @@ use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 
+use crate::core::capture_options::CaptureOptions;
 use crate::core::dism_cmd::DismCmd;
 use crate::core::driver::DriverManager;
 use crate::core::system_utils;
-use crate::core::wimgapi::{WimManager, WimProgress, WIM_COMPRESS_LZX, Wimgapi};
+use crate::core::wimgapi::{WimManager, WimProgress, Wimgapi};
+use crate::core::wimlib::{ImageBackend, Wimlib, WimlibProgress};
 
 /// 操作进度
 #[derive(Debug, Clone)]
@@ -45,12 +47,19 @@ pub struct ImageInfo {
 
 pub struct Dism {
     is_pe: bool,
+    backend: ImageBackend,
 }
 
 impl Dism {
     pub fn new() -> Self {
+        Self::with_backend(ImageBackend::default())
+    }
+
+    /// 指定镜像释放/捕获后端
+    pub fn with_backend(backend: ImageBackend) -> Self {
         Self {
             is_pe: crate::core::system_info::SystemInfo::check_pe_environment(),
+            backend,
         }
     }
 
@@ -60,11 +69,39 @@ impl Dism {
     }
 
     // ========================================================================
-    // 镜像操作 - 使用 wimgapi.dll
+    // 镜像操作 - 使用 wimgapi.dll / wimlib.dll
     // ========================================================================
 
-    /// 应用系统镜像 (WIM/ESD)
-    /// 使用 wimgapi.dll 实现
+    /// 是否为 SWM 分卷镜像
+    fn is_swm(image_file: &str) -> bool {
+        image_file.to_lowercase().ends_with(".swm")
+    }
+
+    /// 按后端设置加载 wimlib
+    fn load_wimlib(&self) -> Option<Wimlib> {
+        self.backend.load_wimlib()
+    }
+
+    /// 将 wimlib 进度转发为 DismProgress
+    fn forward_wimlib_progress(
+        progress_tx: Option<Sender<DismProgress>>,
+    ) -> (Sender<WimlibProgress>, std::thread::JoinHandle<()>) {
+        let (wimlib_tx, wimlib_rx) = std::sync::mpsc::channel::<WimlibProgress>();
+        let handle = std::thread::spawn(move || {
+            while let Ok(progress) = wimlib_rx.recv() {
+                if let Some(ref tx) = progress_tx {
+                    let _ = tx.send(DismProgress {
+                        percentage: progress.percentage,
+                        status: progress.status,
+                    });
+                }
+            }
+        });
+        (wimlib_tx, handle)
+    }
+
+    /// 应用系统镜像 (WIM/ESD/SWM)
+    /// 优先使用 wimlib（按后端设置），否则使用 wimgapi.dll
     pub fn apply_image(
         &self,
         image_file: &str,
@@ -72,7 +109,25 @@ impl Dism {
         index: u32,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
-        println!("[Dism] 使用 wimgapi 应用镜像: {} -> {}", image_file, apply_dir);
+        // SWM 分卷镜像需要 wimlib 支持引用分卷，否则交给 wimgapi
+        let wimlib = self
+            .load_wimlib()
+            .filter(|w| w.supports_apply() && (!Self::is_swm(image_file) || w.supports_split_wim()));
+        if let Some(wimlib) = wimlib {
+            log::info!("[Dism] 使用 wimlib 应用镜像: {} -> {}", image_file, apply_dir);
+            let (wimlib_tx, forward_thread) = Self::forward_wimlib_progress(progress_tx);
+            let result = wimlib.apply_image(image_file, index, apply_dir, Some(wimlib_tx));
+            let _ = forward_thread.join();
+            return match result {
+                Ok(_) => {
+                    log::info!("[Dism] 镜像应用成功");
+                    Ok(())
+                }
+                Err(e) => anyhow::bail!("镜像应用失败: {}", e.detailed()),
+            };
+        }
+
+        log::info!("[Dism] 使用 wimgapi 应用镜像: {} -> {}", image_file, apply_dir);
 
         let wim_manager = WimManager::new()
             .map_err(|e| anyhow::anyhow!("wimgapi 初始化失败: {}", e))?;
@@ -101,7 +156,7 @@ impl Dism {
 
         match result {
             Ok(_) => {
-                println!("[Dism] 镜像应用成功");
+                log::info!("[Dism] 镜像应用成功");
                 Ok(())
             }
             Err(e) => {
@@ -111,16 +166,53 @@ impl Dism {
     }
 
     /// 捕获系统镜像 (备份)
-    /// 使用 wimgapi.dll 实现
+    /// 优先使用 wimlib（按后端设置），否则使用 wimgapi.dll；按 `options` 设置压缩方式和排除规则
     pub fn capture_image(
         &self,
         image_file: &str,
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
-        println!("[Dism] 使用 wimgapi 捕获镜像: {} -> {}", capture_dir, image_file);
+        log::info!(
+            "[Dism] 捕获选项: {}，排除 {} 条规则",
+            options.compression.label(),
+            options.exclusions.len()
+        );
+
+        // 分卷备份始终交给 wimgapi
+        let wimlib = self
+            .load_wimlib()
+            .filter(|w| w.supports_capture() && !Self::is_swm(image_file));
+        if let Some(wimlib) = wimlib {
+            log::info!("[Dism] 使用 wimlib 捕获镜像: {} -> {}", capture_dir, image_file);
+            // wimlib 通过 WimScript.ini 格式的配置文件读取排除规则
+            let config_file = std::env::temp_dir().join("LetRecovery_WimScript.ini");
+            options.write_wimscript(&config_file)?;
+            let (wimlib_tx, forward_thread) = Self::forward_wimlib_progress(progress_tx);
+            let result = wimlib.capture_image(
+                capture_dir,
+                image_file,
+                name,
+                description,
+                options.compression.wimlib_type(),
+                Some(&config_file),
+                Some(wimlib_tx),
+            );
+            let _ = forward_thread.join();
+            let _ = std::fs::remove_file(&config_file);
+            return match result {
+                Ok(_) => {
+                    log::info!("[Dism] 镜像捕获成功");
+                    Ok(())
+                }
+                Err(e) => anyhow::bail!("镜像捕获失败: {}", e.detailed()),
+            };
+        }
+
+        log::info!("[Dism] 使用 wimgapi 捕获镜像: {} -> {}", capture_dir, image_file);
 
         let wim_manager = WimManager::new()
             .map_err(|e| anyhow::anyhow!("wimgapi 初始化失败: {}", e))?;
@@ -144,7 +236,8 @@ impl Dism {
             image_file,
             name,
             description,
-            WIM_COMPRESS_LZX,
+            options.compression.wimgapi_type(),
+            &options.exclusions,
             Some(wim_tx),
         );
 
@@ -152,7 +245,7 @@ impl Dism {
 
         match result {
             Ok(_) => {
-                println!("[Dism] 镜像捕获成功");
+                log::info!("[Dism] 镜像捕获成功");
                 Ok(())
             }
             Err(e) => {
@@ -169,12 +262,13 @@ impl Dism {
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
-        println!("[Dism] 使用 wimgapi 追加镜像: {} -> {}", capture_dir, image_file);
+        log::info!("[Dism] 使用 wimgapi 追加镜像: {} -> {}", capture_dir, image_file);
 
         // 对于追加操作，WimManager 的 capture_image 在文件存在时会自动追加
-        self.capture_image(image_file, capture_dir, name, description, progress_tx)
+        self.capture_image(image_file, capture_dir, name, description, options, progress_tx)
     }
 
     // ========================================================================
@@ -190,13 +284,13 @@ impl Dism {
             anyhow::bail!("PE环境下无法导出当前系统驱动，请使用 export_drivers_from_system 并指定目标系统分区");
         }
 
-        println!("[Dism] 使用 Windows API 导出驱动到: {}", destination);
+        log::info!("[Dism] 使用 Windows API 导出驱动到: {}", destination);
 
         let manager = DriverManager::new()
             .map_err(|e| anyhow::anyhow!("驱动管理器初始化失败: {}", e))?;
 
         let count = manager.export_drivers(Path::new(destination), true)?;
-        println!("[Dism] 成功导出 {} 个驱动", count);
+        log::info!("[Dism] 成功导出 {} 个驱动", count);
         Ok(())
     }
 
@@ -205,7 +299,7 @@ impl Dism {
     pub fn export_drivers_from_system(&self, system_partition: &str, destination: &str) -> Result<()> {
         std::fs::create_dir_all(destination)?;
 
-        println!("[Dism] 使用 Windows API 从 {} 导出驱动到: {}", system_partition, destination);
+        log::info!("[Dism] 使用 Windows API 从 {} 导出驱动到: {}", system_partition, destination);
 
         let manager = DriverManager::new()
             .map_err(|e| anyhow::anyhow!("驱动管理器初始化失败: {}", e))?;
@@ -214,10 +308,37 @@ impl Dism {
             Path::new(system_partition),
             Path::new(destination),
         )?;
-        println!("[Dism] 成功导出 {} 个驱动", count);
+        log::info!("[Dism] 成功导出 {} 个驱动", count);
         Ok(())
     }
 
+    /// 从目标分区的现有系统导出第三方驱动（格式化前调用，供释放镜像后重新注入）
+    ///
+    /// 使用 dism.exe /Export-Driver；目标分区上没有 Windows 时返回 0，否则返回导出的驱动包数量
+    pub fn harvest_drivers(&self, target_partition: &str, destination: &str) -> Result<usize> {
+        let system_root = format!("{}\\", target_partition.trim_end_matches('\\'));
+        if !Path::new(&system_root).join("Windows\\System32\\config\\SYSTEM").exists() {
+            log::info!("[Dism] {} 上没有可导出驱动的 Windows 系统", target_partition);
+            return Ok(0);
+        }
+
+        // 清除上次安装残留的驱动，避免注入旧驱动
+        if Path::new(destination).exists() {
+            let _ = std::fs::remove_dir_all(destination);
+        }
+
+        let dism_cmd = DismCmd::new()
+            .map_err(|e| anyhow::anyhow!("dism.exe 初始化失败: {}", e))?;
+        dism_cmd.export_drivers_offline(&system_root, destination, None)?;
+
+        // dism.exe 为每个驱动包创建一个子目录
+        let count = std::fs::read_dir(destination)
+            .map(|entries| entries.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()).count())
+            .unwrap_or(0);
+        log::info!("[Dism] 已从 {} 导出 {} 个第三方驱动包", target_partition, count);
+        Ok(count)
+    }
+
     /// 导入驱动 - 使用 Windows API
     /// 在PE环境下，自动转为离线操作
     pub fn add_drivers(&self, target_path: &str, driver_path: &str) -> Result<()> {
@@ -235,7 +356,7 @@ impl Dism {
             anyhow::bail!("PE环境下无法使用在线方式添加驱动，请使用 add_drivers_offline");
         }
 
-        println!("[Dism] 使用 Windows API 导入驱动: {}", driver_path);
+        log::info!("[Dism] 使用 Windows API 导入驱动: {}", driver_path);
 
         let manager = DriverManager::new()
             .map_err(|e| anyhow::anyhow!("驱动管理器初始化失败: {}", e))?;
@@ -245,7 +366,7 @@ impl Dism {
             true, // force
         )?;
 
-        println!(
+        log::info!(
             "[Dism] 驱动导入完成: 成功 {}, 失败 {}, 需要重启: {}",
             success, fail, need_reboot
         );
@@ -264,14 +385,14 @@ impl Dism {
     /// 
     /// 优先使用 {程序目录}\bin\Dism\dism.exe
     pub fn add_drivers_offline(&self, image_path: &str, driver_path: &str) -> Result<()> {
-        println!("[Dism] 离线导入驱动: {} -> {}", driver_path, image_path);
+        log::info!("[Dism] 离线导入驱动: {} -> {}", driver_path, image_path);
 
         // 规范化路径：移除尾部的反斜杠
         let image_path_clean = image_path.trim_end_matches('\\').trim_end_matches('/');
         
         // 使用 dism.exe 命令行进行离线驱动注入
         // 这将使用 DISM 的 /Add-Driver 和 /Add-Package 功能
-        println!("[Dism] 使用 dism.exe 命令行进行离线驱动注入...");
+        log::info!("[Dism] 使用 dism.exe 命令行进行离线驱动注入...");
         
         let dism_cmd = DismCmd::new()
             .map_err(|e| anyhow::anyhow!("DISM 命令行初始化失败: {}", e))?;
@@ -279,14 +400,14 @@ impl Dism {
         // 智能导入：自动识别并处理驱动文件和 CAB 包
         match dism_cmd.import_drivers_smart(image_path_clean, driver_path, None) {
             Ok(_) => {
-                println!("[Dism] 离线驱动注入完成");
+                log::info!("[Dism] 离线驱动注入完成");
                 Ok(())
             }
             Err(e) => {
-                println!("[Dism] dism.exe 导入失败: {}", e);
+                log::warn!("[Dism] dism.exe 导入失败: {}", e);
                 
                 // 尝试回退到 DriverManager（仅当 DISM 完全失败时）
-                println!("[Dism] 尝试使用备用方法（DriverManager）...");
+                log::info!("[Dism] 尝试使用备用方法（DriverManager）...");
                 
                 let manager = DriverManager::new()
                     .map_err(|e| anyhow::anyhow!("驱动管理器初始化失败: {}", e))?;
@@ -296,7 +417,7 @@ impl Dism {
                     Path::new(driver_path),
                 )?;
 
-                println!(
+                log::info!(
                     "[Dism] 备用方法完成: 成功 {}, 失败 {}",
                     success, fail
                 );
@@ -316,15 +437,15 @@ impl Dism {
     /// 获取 WIM/ESD 镜像信息（所有分卷）
     /// 使用 wimgapi.dll 或直接解析 WIM XML 元数据
     pub fn get_image_info(&self, image_file: &str) -> Result<Vec<ImageInfo>> {
-        println!("[Dism] 开始获取镜像信息: {}", image_file);
+        log::info!("[Dism] 开始获取镜像信息: {}", image_file);
         
         // 首先尝试使用 wimgapi
         match WimManager::new() {
             Ok(wim_manager) => {
-                println!("[Dism] wimgapi.dll 加载成功");
+                log::info!("[Dism] wimgapi.dll 加载成功");
                 match wim_manager.get_image_info(image_file) {
                     Ok(images) => {
-                        println!("[Dism] 从 wimgapi 成功获取 {} 个镜像信息", images.len());
+                        log::info!("[Dism] 从 wimgapi 成功获取 {} 个镜像信息", images.len());
                         return Ok(images.into_iter().map(|img| ImageInfo {
                             index: img.index,
                             name: img.name,
@@ -337,28 +458,28 @@ impl Dism {
                         }).collect());
                     }
                     Err(e) => {
-                        println!("[Dism] wimgapi 获取镜像信息失败: {}", e);
+                        log::warn!("[Dism] wimgapi 获取镜像信息失败: {}", e);
                     }
                 }
             }
             Err(e) => {
-                println!("[Dism] wimgapi.dll 加载失败: {} (这可能是PE环境缺少该DLL)", e);
+                log::warn!("[Dism] wimgapi.dll 加载失败: {} (这可能是PE环境缺少该DLL)", e);
             }
         }
 
         // 尝试直接解析 WIM XML 元数据（仅对WIM有效，ESD的元数据是压缩的）
-        println!("[Dism] 尝试直接解析 WIM XML 元数据...");
+        log::info!("[Dism] 尝试直接解析 WIM XML 元数据...");
         match Self::parse_wim_xml_metadata(image_file) {
             Ok(images) => {
                 if !images.is_empty() {
-                    println!("[Dism] 从 WIM XML 元数据成功解析出 {} 个镜像", images.len());
+                    log::info!("[Dism] 从 WIM XML 元数据成功解析出 {} 个镜像", images.len());
                     return Ok(images);
                 } else {
-                    println!("[Dism] WIM XML 解析成功但未找到镜像信息");
+                    log::info!("[Dism] WIM XML 解析成功但未找到镜像信息");
                 }
             }
             Err(e) => {
-                println!("[Dism] WIM XML 直接解析失败: {} (ESD文件的元数据是压缩的，需要wimgapi)", e);
+                log::warn!("[Dism] WIM XML 直接解析失败: {} (ESD文件的元数据是压缩的，需要wimgapi)", e);
             }
         }
 
@@ -465,7 +586,7 @@ impl Dism {
         use std::fs::File;
         use std::io::{Read, Seek, SeekFrom};
 
-        println!("[Dism] 尝试直接解析 WIM XML 元数据: {}", image_file);
+        log::info!("[Dism] 尝试直接解析 WIM XML 元数据: {}", image_file);
 
         let mut file = File::open(image_file)?;
         let mut header = [0u8; 208];
@@ -483,7 +604,7 @@ impl Dism {
             anyhow::bail!("XML 元数据位置无效");
         }
 
-        println!("[Dism] XML 偏移: {}, 大小: {}", xml_offset, xml_size);
+        log::info!("[Dism] XML 偏移: {}, 大小: {}", xml_offset, xml_size);
 
         file.seek(SeekFrom::Start(xml_offset))?;
         let mut xml_data = vec![0u8; xml_size as usize];
@@ -4,6 +4,7 @@
 //! - 离线驱动导入（Add-Driver）
 //! - 离线 CAB 包导入（Add-Package）
 //! - 驱动导出
+//! - 镜像导出（格式转换）
 //!
 //! 优先使用程序目录下的 `bin\Dism\dism.exe`，
 //! 如果不存在则回退到系统 DISM。
@@ -473,6 +474,62 @@ impl DismCmd {
         self.execute_with_progress_args(&args, progress_tx, "驱动导出")
     }
 
+    // ========================================================================
+    // 镜像导出
+    // ========================================================================
+
+    /// 导出镜像中的一个分卷，目标文件已存在时追加为新分卷
+    ///
+    /// 等效于: `dism /Export-Image /SourceImageFile:<source> /SourceIndex:<index> /DestinationImageFile:<dest> /Compress:<compress>`
+    ///
+    /// # 参数
+    /// - `source`: 源镜像（WIM/ESD/SWM，SWM 自动引用同目录下的其余分卷）
+    /// - `index`: 分卷索引（从 1 开始）
+    /// - `destination`: 目标镜像文件
+    /// - `compress`: 压缩方式（max、fast、none 或 recovery，recovery 用于导出为 ESD）
+    /// - `progress_tx`: 可选的进度发送器
+    pub fn export_image(
+        &self,
+        source: &str,
+        index: u32,
+        destination: &str,
+        compress: &str,
+        progress_tx: Option<Sender<DismCmdProgress>>,
+    ) -> Result<()> {
+        if !Path::new(source).exists() {
+            bail!("源镜像不存在: {}", source);
+        }
+
+        log::info!("[DismCmd] 导出镜像: {} #{} -> {} ({})", source, index, destination, compress);
+
+        Self::send_progress(&progress_tx, 0, "正在准备导出镜像...");
+
+        let scratch_dir = Self::ensure_scratch_directory();
+
+        let mut args = vec![
+            "/Export-Image".to_string(),
+            format!("/SourceImageFile:{}", source),
+            format!("/SourceIndex:{}", index),
+            format!("/DestinationImageFile:{}", destination),
+            format!("/Compress:{}", compress),
+        ];
+        if let Some(pattern) = Self::swm_pattern(source) {
+            args.push(format!("/SWMFile:{}", pattern));
+        }
+        args.push(format!("/scratchdir:{}", scratch_dir));
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        self.execute_with_progress_args(&args, progress_tx, "镜像导出")
+    }
+
+    /// SWM 分卷的通配路径（如 `D:\install*.swm`），非 SWM 返回 None
+    fn swm_pattern(source: &str) -> Option<String> {
+        if !source.to_ascii_lowercase().ends_with(".swm") {
+            return None;
+        }
+        Some(format!("{}*.swm", &source[..source.len() - 4]))
+    }
+
     // ========================================================================
     // 综合驱动和 CAB 导入
     // ========================================================================
@@ -587,6 +644,18 @@ impl DismCmd {
         self.execute_and_get_output(&args)
     }
 
+    /// 获取 WIM/ESD 中指定分卷的详细信息（英文输出，便于解析）
+    pub fn get_wim_info(&self, wim_file: &str, index: u32) -> Result<String> {
+        let args = [
+            "/English",
+            "/Get-WimInfo",
+            &format!("/WimFile:{}", wim_file),
+            &format!("/Index:{}", index),
+        ];
+
+        self.execute_and_get_output(&args)
+    }
+
     // ========================================================================
     // 内部辅助方法
     // ========================================================================
@@ -966,4 +1035,11 @@ mod tests {
         let scratch = DismCmd::ensure_scratch_directory();
         assert!(!scratch.is_empty());
     }
+
+    #[test]
+    fn test_swm_pattern() {
+        assert_eq!(DismCmd::swm_pattern("D:\\Backup\\install.swm"), Some("D:\\Backup\\install*.swm".to_string()));
+        assert_eq!(DismCmd::swm_pattern("D:\\install.SWM"), Some("D:\\install*.swm".to_string()));
+        assert_eq!(DismCmd::swm_pattern("D:\\install.wim"), None);
+    }
 }
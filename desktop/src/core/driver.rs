@@ -35,6 +35,7 @@ const DIGCF_ALLCLASSES: u32 = 0x0000_0004;
 const SPDRP_DRIVER: u32 = 0x0000_0009;
 const SPDRP_INF_PATH: u32 = 0x0000_0010;
 const SPDRP_HARDWAREID: u32 = 0x0000_0001;
+const SPDRP_COMPATIBLEIDS: u32 = 0x0000_0002;
 const SPDRP_DEVICEDESC: u32 = 0x0000_0000;
 const SPDRP_MFG: u32 = 0x0000_000B;
 const SPDRP_CLASS: u32 = 0x0000_0007;
@@ -252,6 +253,21 @@ pub struct DriverInfo {
     pub is_oem: bool,
 }
 
+/// 设备信息（按硬件 ID 匹配驱动时使用）
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    /// 设备描述
+    pub description: String,
+    /// 设备类别（未安装驱动的设备可能为空）
+    pub device_class: String,
+    /// 硬件 ID（从具体到通用）
+    pub hardware_ids: Vec<String>,
+    /// 兼容 ID
+    pub compatible_ids: Vec<String>,
+    /// 是否已安装驱动
+    pub has_driver: bool,
+}
+
 // ============================================================================
 // SetupAPI 封装
 // ============================================================================
@@ -347,6 +363,108 @@ impl SetupApi {
         }
     }
 
+    /// 获取设备属性（多字符串）
+    fn get_device_property_multi_string(
+        &self,
+        dev_info: HDevInfo,
+        dev_info_data: &SpDevInfoData,
+        property: u32,
+    ) -> Vec<String> {
+        let mut buffer = vec![0u8; 4096];
+        let mut required_size: u32 = 0;
+        let mut reg_type: u32 = 0;
+
+        let result = unsafe {
+            (self.get_device_registry_property)(
+                dev_info,
+                dev_info_data,
+                property,
+                &mut reg_type,
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+                &mut required_size,
+            )
+        };
+
+        if result.0 == 0 || (reg_type != REG_SZ && reg_type != REG_MULTI_SZ) {
+            return Vec::new();
+        }
+
+        let wide_slice = unsafe {
+            std::slice::from_raw_parts(
+                buffer.as_ptr() as *const u16,
+                required_size.min(buffer.len() as u32) as usize / 2,
+            )
+        };
+        wide_slice
+            .split(|&c| c == 0)
+            .filter(|part| !part.is_empty())
+            .map(|part| OsString::from_wide(part).to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// 枚举所有当前存在的设备及其硬件 ID
+    fn enumerate_devices(&self) -> Result<Vec<DeviceInfo>> {
+        let mut devices = Vec::new();
+
+        let dev_info = unsafe {
+            (self.get_class_devs)(
+                null_mut(),
+                null_mut(),
+                HWND::default(),
+                DIGCF_PRESENT | DIGCF_ALLCLASSES,
+            )
+        };
+
+        if dev_info.is_null() || dev_info == (-1isize as *mut c_void) {
+            bail!("SetupDiGetClassDevsW 失败: {}", get_last_error());
+        }
+
+        let mut index = 0u32;
+        loop {
+            let mut dev_info_data = SpDevInfoData::default();
+
+            let result = unsafe {
+                (self.enum_device_info)(dev_info, index, &mut dev_info_data)
+            };
+            index += 1;
+
+            if result.0 == 0 {
+                if get_last_error() == ERROR_NO_MORE_ITEMS {
+                    break;
+                }
+                continue;
+            }
+
+            let hardware_ids =
+                self.get_device_property_multi_string(dev_info, &dev_info_data, SPDRP_HARDWAREID);
+            if hardware_ids.is_empty() {
+                continue;
+            }
+
+            devices.push(DeviceInfo {
+                description: self
+                    .get_device_property_string(dev_info, &dev_info_data, SPDRP_DEVICEDESC)
+                    .unwrap_or_default(),
+                device_class: self
+                    .get_device_property_string(dev_info, &dev_info_data, SPDRP_CLASS)
+                    .unwrap_or_default(),
+                hardware_ids,
+                compatible_ids: self
+                    .get_device_property_multi_string(dev_info, &dev_info_data, SPDRP_COMPATIBLEIDS),
+                has_driver: self
+                    .get_device_property_string(dev_info, &dev_info_data, SPDRP_DRIVER)
+                    .is_some(),
+            });
+        }
+
+        unsafe {
+            let _ = (self.destroy_device_info_list)(dev_info);
+        }
+
+        Ok(devices)
+    }
+
     /// 枚举所有设备的驱动信息
     fn enumerate_drivers(&self) -> Result<Vec<DriverInfo>> {
         let mut drivers = Vec::new();
@@ -625,6 +743,11 @@ impl DriverManager {
         self.setup_api.enumerate_drivers()
     }
 
+    /// 枚举当前存在的设备（含未安装驱动的设备）
+    pub fn enumerate_devices(&self) -> Result<Vec<DeviceInfo>> {
+        self.setup_api.enumerate_devices()
+    }
+
     /// 枚举第三方 (OEM) 驱动
     pub fn enumerate_oem_drivers(&self) -> Result<Vec<DriverInfo>> {
         let all_drivers = self.setup_api.enumerate_drivers()?;
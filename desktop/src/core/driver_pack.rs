@@ -0,0 +1,295 @@
+//! 存储控制器驱动包管理模块
+//!
+//! 程序目录 drivers\storage_controller 下的每个子目录是一个驱动包（如 vmd、amd_raid、apple_ssd、virtio）。
+//! 根据 INF 中声明的硬件 ID 判断驱动包是否匹配当前硬件，支持从服务器下载更新的驱动包（.cab / .zip / .7z），
+//! 安装时可以只导入选中的驱动包。直接放在根目录下的驱动只在导入全部驱动包时导入。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::core::storage_controller::{
+    driver_dir_supports, pci_id_of, present_pci_ids, read_inf_text, RaidController,
+};
+use crate::download::config::OnlineDriverPack;
+
+/// 驱动包信息文件（下载安装时写入）
+const PACK_INFO_FILE: &str = "pack.json";
+
+/// 内置驱动包的显示名称（目录名, 显示名称）
+const KNOWN_PACKS: [(&str, &str); 4] = [
+    ("vmd", "Intel VMD / RST"),
+    ("amd_raid", "AMD RAID"),
+    ("apple_ssd", "Apple SSD"),
+    ("virtio", "VirtIO"),
+];
+
+/// 驱动包信息文件内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PackInfo {
+    name: String,
+    version: String,
+}
+
+/// 本地驱动包
+#[derive(Debug, Clone)]
+pub struct DriverPack {
+    /// 驱动包ID（目录名）
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 版本号（内置驱动包没有版本信息时为空）
+    pub version: String,
+    /// 驱动包目录
+    pub path: PathBuf,
+    /// INF 中声明的 PCI 硬件 ID（VEN_xxxx&DEV_xxxx）
+    pub hardware_ids: Vec<String>,
+    /// 是否匹配当前硬件
+    pub matched: bool,
+}
+
+/// 驱动包根目录
+pub fn packs_dir() -> PathBuf {
+    crate::utils::path::get_exe_dir()
+        .join("drivers")
+        .join("storage_controller")
+}
+
+/// 驱动包ID是否合法（只能作为单级目录名）
+pub fn is_valid_pack_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 驱动包的显示名称
+fn pack_display_name(id: &str) -> String {
+    KNOWN_PACKS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(id))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// 解析 INF 文本中声明的 PCI 硬件 ID
+pub fn parse_inf_hardware_ids(text: &str) -> Vec<String> {
+    let upper = text.to_uppercase();
+    let mut ids: Vec<String> = upper
+        .match_indices("VEN_")
+        .filter_map(|(index, _)| pci_id_of(&upper[index..]))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// 收集目录（递归）中所有 INF 声明的硬件 ID
+fn collect_hardware_ids(dir: &Path) -> Vec<String> {
+    let mut ids: Vec<String> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("inf"))
+                .unwrap_or(false)
+        })
+        .filter_map(|e| read_inf_text(e.path()))
+        .flat_map(|text| parse_inf_hardware_ids(&text))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// 列出本地驱动包，并标记与当前硬件匹配的驱动包
+pub fn list_packs() -> Vec<DriverPack> {
+    let present = present_pci_ids();
+    let Ok(entries) = std::fs::read_dir(packs_dir()) else {
+        return Vec::new();
+    };
+
+    let mut packs: Vec<DriverPack> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let id = path.file_name()?.to_string_lossy().to_string();
+            // 跳过下载安装时残留的临时目录
+            if !is_valid_pack_id(&id) {
+                return None;
+            }
+            let info: PackInfo = std::fs::read_to_string(path.join(PACK_INFO_FILE))
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default();
+            let hardware_ids = collect_hardware_ids(&path);
+            let matched = hardware_ids.iter().any(|id| present.contains(id));
+            Some(DriverPack {
+                name: if info.name.is_empty() { pack_display_name(&id) } else { info.name },
+                version: info.version,
+                id,
+                path,
+                hardware_ids,
+                matched,
+            })
+        })
+        .collect();
+
+    packs.sort_by(|a, b| b.matched.cmp(&a.matched).then_with(|| a.id.cmp(&b.id)));
+    packs
+}
+
+/// 要导入的驱动目录
+///
+/// 未选择时导入整个驱动包根目录；否则先导入包含已检测 VMD / RAID 控制器驱动的驱动包，再导入选中的驱动包
+pub fn selected_pack_dirs(selected: &[String], controllers: &[RaidController]) -> Vec<PathBuf> {
+    let root = packs_dir();
+    if selected.is_empty() {
+        return vec![root];
+    }
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&root) {
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.is_dir() && controllers.iter().any(|c| driver_dir_supports(&path, c)) {
+                dirs.push(path);
+            }
+        }
+    }
+    for id in selected.iter().filter(|id| is_valid_pack_id(id)) {
+        let path = root.join(id);
+        if path.is_dir() && !dirs.contains(&path) {
+            dirs.push(path);
+        }
+    }
+    dirs
+}
+
+/// 校验下载内容的 SHA256，索引中没有提供哈希时拒绝安装
+pub fn verify_sha256(data: &[u8], expected: Option<&str>) -> anyhow::Result<()> {
+    let expected = match expected.map(str::trim).filter(|hash| !hash.is_empty()) {
+        Some(hash) => hash,
+        None => anyhow::bail!("索引中没有提供 SHA256，拒绝安装未经校验的驱动"),
+    };
+    let actual: String = Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!("SHA256 校验失败（期望 {}，实际 {}）", expected, actual);
+    }
+    Ok(())
+}
+
+/// 在线驱动包是否比本地版本新（本地不存在或版本不同）
+pub fn needs_update(local: Option<&DriverPack>, online: &OnlineDriverPack) -> bool {
+    match local {
+        Some(pack) => pack.version != online.version,
+        None => true,
+    }
+}
+
+/// 将下载的驱动包（.cab / .zip / .7z）解压安装到驱动包目录（替换旧版本）
+pub fn install_pack_from_archive(pack: &OnlineDriverPack, archive_path: &Path) -> anyhow::Result<()> {
+    if !is_valid_pack_id(&pack.id) {
+        anyhow::bail!("驱动包ID无效: {}", pack.id);
+    }
+
+    let root = packs_dir();
+    let staging = root.join(format!("{}.new", pack.id));
+    let target = root.join(&pack.id);
+
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging).context("清理临时目录失败")?;
+    }
+    std::fs::create_dir_all(&staging).context("创建临时目录失败")?;
+
+    let files = crate::utils::archive::extract(archive_path, &staging, |_| {}).context("解压驱动包失败")?;
+    if collect_hardware_ids(&staging).is_empty() {
+        let _ = std::fs::remove_dir_all(&staging);
+        anyhow::bail!("驱动包中没有找到驱动文件（共 {} 个文件）", files.len());
+    }
+
+    let info = PackInfo {
+        name: pack.name.clone(),
+        version: pack.version.clone(),
+    };
+    std::fs::write(staging.join(PACK_INFO_FILE), serde_json::to_string_pretty(&info)?)
+        .context("写入驱动包信息失败")?;
+
+    if target.exists() {
+        std::fs::remove_dir_all(&target).context("删除旧版本驱动包失败")?;
+    }
+    std::fs::rename(&staging, &target).context("安装驱动包失败")?;
+
+    log::info!("[DRIVER PACK] 已安装 {} {}", pack.id, pack.version);
+    Ok(())
+}
+
+/// 下载并安装在线驱动包
+pub fn download_and_install(pack: &OnlineDriverPack) -> anyhow::Result<()> {
+    if pack.download_url.is_empty() {
+        anyhow::bail!("驱动包 {} 没有下载地址", pack.name);
+    }
+    log::info!("[DRIVER PACK] 下载 {}: {}", pack.id, pack.download_url);
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(600))
+        .build()
+        .context("创建 HTTP 客户端失败")?;
+    let response = client
+        .get(&pack.download_url)
+        .send()
+        .context("下载驱动包失败")?;
+    if !response.status().is_success() {
+        anyhow::bail!("下载驱动包失败，服务器返回: {}", response.status());
+    }
+    let bytes = response.bytes().context("读取驱动包内容失败")?;
+    verify_sha256(&bytes, pack.sha256.as_deref()).with_context(|| format!("驱动包 {} 校验失败", pack.name))?;
+
+    let root = packs_dir();
+    std::fs::create_dir_all(&root).context("创建驱动包目录失败")?;
+    let archive_path = root.join(format!("{}.download", pack.id));
+    std::fs::write(&archive_path, &bytes).context("保存驱动包失败")?;
+
+    let result = install_pack_from_archive(pack, &archive_path);
+    let _ = std::fs::remove_file(&archive_path);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inf_hardware_ids() {
+        let inf = "[Intel.NTamd64]\n\
+                   %VMD% = iaStorVD_Inst, PCI\\VEN_8086&DEV_9A0B&CC_0104\n\
+                   %VMD2% = iaStorVD_Inst, PCI\\ven_8086&dev_467f&CC_0104\n\
+                   %VMD% = iaStorVD_Inst, PCI\\VEN_8086&DEV_9A0B\n";
+        assert_eq!(
+            parse_inf_hardware_ids(inf),
+            vec!["VEN_8086&DEV_467F".to_string(), "VEN_8086&DEV_9A0B".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_pack_id_and_name() {
+        assert!(is_valid_pack_id("amd_raid"));
+        assert!(!is_valid_pack_id("..\\system32"));
+        assert!(!is_valid_pack_id(""));
+        assert_eq!(pack_display_name("VirtIO"), "VirtIO");
+        assert_eq!(pack_display_name("custom"), "custom");
+    }
+
+    #[test]
+    fn test_verify_sha256() {
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_sha256(b"abc", Some(abc)).is_ok());
+        assert!(verify_sha256(b"abc", Some(&abc.to_uppercase())).is_ok());
+        assert!(verify_sha256(b"abd", Some(abc)).is_err());
+        assert!(verify_sha256(b"abc", None).is_err());
+        assert!(verify_sha256(b"abc", Some(" ")).is_err());
+    }
+}
@@ -5,7 +5,8 @@
 //! # 功能
 //! - 验证 GHO 文件有效性
 //! - 获取 GHO 镜像信息
-//! - 恢复 GHO 镜像到指定分区（支持进度回调）
+//! - 列出磁盘镜像中的分区
+//! - 恢复 GHO 镜像（或磁盘镜像中的某个分区）到指定分区（支持进度回调）
 //! - 支持取消正在进行的操作
 
 use anyhow::{Context, Result};
@@ -73,6 +74,33 @@ pub struct GhoImageInfo {
     pub compression_ratio: f32,
 }
 
+/// 在文件开头查找源磁盘分区表的范围
+const PARTITION_TABLE_SCAN_BYTES: u64 = 64 * 1024;
+const SECTOR_SIZE: usize = 512;
+
+/// GHO 磁盘镜像中的分区
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhoPartition {
+    /// 在镜像中的序号（从 1 开始，对应 Ghost 的 src=文件:序号）
+    pub number: u32,
+    /// 分区类型
+    pub kind: String,
+    /// 分区大小（字节）
+    pub size_bytes: u64,
+}
+
+impl GhoPartition {
+    /// 显示文本
+    pub fn label(&self) -> String {
+        format!(
+            "{} - {}（{:.1} GB）",
+            self.number,
+            self.kind,
+            self.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+        )
+    }
+}
+
 /// Ghost 错误类型
 #[derive(Debug, thiserror::Error)]
 pub enum GhostError {
@@ -236,10 +264,30 @@ impl Ghost {
         Ok(info)
     }
 
+    /// 列出磁盘镜像中的分区，分区镜像或无法识别分区表时返回空列表
+    ///
+    /// 压缩的镜像读不到分区表；结果只用于让用户选择，不据此自动指定分区。需要读文件，应在后台线程调用
+    pub fn list_partitions(&self, gho_file: &str) -> Result<Vec<GhoPartition>> {
+        self.validate_image(gho_file)?;
+
+        let file = std::fs::File::open(gho_file).context("无法打开文件")?;
+        let mut data = Vec::new();
+        file.take(PARTITION_TABLE_SCAN_BYTES)
+            .read_to_end(&mut data)
+            .context("无法读取文件头")?;
+
+        let partitions = parse_partition_table(&data);
+        log::info!("[GHOST] {} 包含 {} 个分区: {:?}", gho_file, partitions.len(), partitions);
+        Ok(partitions)
+    }
+
     /// 恢复 GHO 镜像到指定分区
+    ///
+    /// `source_partition` 为磁盘镜像中要还原的分区序号（从 1 开始），0 表示不指定（分区镜像）。
     pub fn restore_image(
         &self,
         gho_file: &str,
+        source_partition: u32,
         disk_number: u32,
         partition_number: u32,
         progress_tx: Option<Sender<DismProgress>>,
@@ -260,12 +308,15 @@ impl Ghost {
 
         let target_partition = format!("{}:{}", disk_number, partition_number);
         
-        println!("[GHOST] ========================================");
-        println!("[GHOST] 开始恢复 GHO 镜像");
-        println!("[GHOST] 镜像文件: {}", gho_file);
-        println!("[GHOST] 目标分区: {} (磁盘 {} 分区 {})", target_partition, disk_number, partition_number);
-        println!("[GHOST] Ghost 路径: {}", self.ghost_path);
-        println!("[GHOST] ========================================");
+        log::info!("[GHOST] ========================================");
+        log::info!("[GHOST] 开始恢复 GHO 镜像");
+        log::info!("[GHOST] 镜像文件: {}", gho_file);
+        if source_partition > 0 {
+            log::info!("[GHOST] 镜像中的分区: {}", source_partition);
+        }
+        log::info!("[GHOST] 目标分区: {} (磁盘 {} 分区 {})", target_partition, disk_number, partition_number);
+        log::info!("[GHOST] Ghost 路径: {}", self.ghost_path);
+        log::info!("[GHOST] ========================================");
 
         let image_info = self.get_image_info(gho_file).ok();
         let estimated_size = image_info.as_ref().map(|i| i.original_size).unwrap_or(0);
@@ -277,12 +328,17 @@ impl Ghost {
             });
         }
 
+        let source = if source_partition > 0 {
+            format!("{}:{}", gho_file, source_partition)
+        } else {
+            gho_file.to_string()
+        };
         let clone_param = format!(
             "-clone,mode=pload,src={},dst={}",
-            gho_file, target_partition
+            source, target_partition
         );
 
-        println!("[GHOST] 执行命令: {} {} -sure -fx -batch", self.ghost_path, clone_param);
+        log::info!("[GHOST] 执行命令: {} {} -sure -fx -batch", self.ghost_path, clone_param);
 
         let mut child = create_command(&self.ghost_path)
             .args([&clone_param, "-sure", "-fx", "-batch"])
@@ -303,6 +359,7 @@ impl Ghost {
     pub fn restore_image_to_letter(
         &self,
         gho_file: &str,
+        source_partition: u32,
         target_letter: &str,
         partitions: &[crate::core::disk::Partition],
         progress_tx: Option<Sender<DismProgress>>,
@@ -314,7 +371,7 @@ impl Ghost {
             format!("{}:", letter)
         };
 
-        println!("[GHOST] 解析目标盘符: {}", letter);
+        log::info!("[GHOST] 解析目标盘符: {}", letter);
 
         let partition = partitions
             .iter()
@@ -323,7 +380,7 @@ impl Ghost {
                 format!("找不到分区 {}", letter)
             ))?;
 
-        println!("[GHOST] 找到分区信息: letter={}, disk={:?}, partition={:?}", 
+        log::info!("[GHOST] 找到分区信息: letter={}, disk={:?}, partition={:?}", 
             partition.letter, partition.disk_number, partition.partition_number);
 
         let disk_number = partition.disk_number.ok_or_else(|| {
@@ -334,14 +391,21 @@ impl Ghost {
             GhostError::InvalidPartition(format!("无法获取 {} 的分区号，请刷新分区列表", letter))
         })?;
 
+        if partition.sector_size.is_native_4k() {
+            log::warn!(
+                "[GHOST] 警告: 磁盘 {} 为 4Kn 原生扇区磁盘（逻辑扇区 {} 字节），512 字节扇区的 GHO 镜像还原可能失败",
+                disk_number, partition.sector_size.logical
+            );
+        }
+
         let ghost_disk = disk_number + 1;
         let ghost_partition = partition_number;
 
-        println!("[GHOST] 转换分区格式:");
-        println!("[GHOST]   Windows: Disk {} Partition {}", disk_number, partition_number);
-        println!("[GHOST]   Ghost:   {}:{}", ghost_disk, ghost_partition);
+        log::info!("[GHOST] 转换分区格式:");
+        log::info!("[GHOST]   Windows: Disk {} Partition {}", disk_number, partition_number);
+        log::info!("[GHOST]   Ghost:   {}:{}", ghost_disk, ghost_partition);
 
-        self.restore_image(gho_file, ghost_disk, ghost_partition, progress_tx)
+        self.restore_image(gho_file, source_partition, ghost_disk, ghost_partition, progress_tx)
     }
 
     /// 监控 Ghost 进程并报告进度
@@ -373,7 +437,7 @@ impl Ghost {
                 let reader = BufReader::new(stderr);
                 for line in reader.lines().map_while(Result::ok) {
                     let line_utf8 = gbk_to_utf8(line.as_bytes());
-                    println!("[GHOST STDERR] {}", line_utf8);
+                    log::warn!("[GHOST STDERR] {}", line_utf8);
                     if let Ok(mut content) = stderr_content_clone.lock() {
                         content.push_str(&line_utf8);
                         content.push('\n');
@@ -393,20 +457,20 @@ impl Ghost {
         };
         let estimated_duration = Duration::from_secs(estimated_seconds);
         
-        println!("[GHOST] 预计恢复时间: {} 秒", estimated_seconds);
+        log::info!("[GHOST] 预计恢复时间: {} 秒", estimated_seconds);
         
         let mut last_progress: u8 = 0;
 
         loop {
             if cancel_flag.load(Ordering::SeqCst) {
-                println!("[GHOST] 收到取消请求，终止进程");
+                log::warn!("[GHOST] 收到取消请求，终止进程");
                 let _ = child.kill();
                 return Err(GhostError::Cancelled.into());
             }
 
             match child.try_wait() {
                 Ok(Some(status)) => {
-                    println!("[GHOST] 进程退出，状态码: {:?}", status.code());
+                    log::info!("[GHOST] 进程退出，状态码: {:?}", status.code());
                     
                     if let Some(handle) = stdout_handle {
                         let _ = handle.join();
@@ -428,9 +492,9 @@ impl Ghost {
                     }
 
                     if status.success() || status.code() == Some(0) {
-                        println!("[GHOST] ========================================");
-                        println!("[GHOST] 镜像恢复成功!");
-                        println!("[GHOST] ========================================");
+                        log::info!("[GHOST] ========================================");
+                        log::info!("[GHOST] 镜像恢复成功!");
+                        log::info!("[GHOST] ========================================");
                         return Ok(());
                     } else {
                         let error_msg = if stderr_output.trim().is_empty() {
@@ -438,7 +502,7 @@ impl Ghost {
                         } else {
                             format!("Ghost 错误: {}", stderr_output.trim())
                         };
-                        println!("[GHOST] 恢复失败: {}", error_msg);
+                        log::warn!("[GHOST] 恢复失败: {}", error_msg);
                         return Err(GhostError::ExecutionFailed(error_msg).into());
                     }
                 }
@@ -449,7 +513,7 @@ impl Ghost {
                     
                     if progress > last_progress {
                         last_progress = progress;
-                        println!("[GHOST] 进度: {}% (已运行 {:.0} 秒)", progress, elapsed.as_secs_f64());
+                        log::debug!("[GHOST] 进度: {}% (已运行 {:.0} 秒)", progress, elapsed.as_secs_f64());
                         
                         if let Some(ref tx) = progress_tx {
                             let _ = tx.send(DismProgress {
@@ -482,7 +546,7 @@ impl Ghost {
 
             if let Ok(line) = line {
                 let line_utf8 = gbk_to_utf8(line.as_bytes());
-                println!("[GHOST STDOUT] {}", line_utf8);
+                log::debug!("[GHOST STDOUT] {}", line_utf8);
                 lines.push(line_utf8);
             }
         }
@@ -518,12 +582,12 @@ impl Ghost {
 
         let source_partition = format!("{}:{}", disk_number, partition_number);
         
-        println!("[GHOST] ========================================");
-        println!("[GHOST] 开始创建 GHO 镜像");
-        println!("[GHOST] 源分区: {} (磁盘 {} 分区 {})", source_partition, disk_number, partition_number);
-        println!("[GHOST] 输出文件: {}", gho_file);
-        println!("[GHOST] 压缩级别: {}", compression);
-        println!("[GHOST] ========================================");
+        log::info!("[GHOST] ========================================");
+        log::info!("[GHOST] 开始创建 GHO 镜像");
+        log::info!("[GHOST] 源分区: {} (磁盘 {} 分区 {})", source_partition, disk_number, partition_number);
+        log::info!("[GHOST] 输出文件: {}", gho_file);
+        log::info!("[GHOST] 压缩级别: {}", compression);
+        log::info!("[GHOST] ========================================");
 
         if let Some(ref tx) = progress_tx {
             let _ = tx.send(DismProgress {
@@ -555,6 +619,186 @@ impl Ghost {
     }
 }
 
+/// 从 GHO 文件开头的数据中解析源磁盘的分区表
+///
+/// Ghost 镜像格式未公开。磁盘镜像在文件头之后保存源磁盘的 MBR 扇区，此处按扇区对齐查找第一个
+/// 有效的分区表；保护性 MBR（类型 0xEE）之后是 GPT 头时改为读取 GPT 分区项，GPT 头和分区项都要通过
+/// CRC32 校验。MBR 只列出主分区（不展开扩展分区中的逻辑分区）。
+fn parse_partition_table(data: &[u8]) -> Vec<GhoPartition> {
+    // 第一个扇区是 GHO 文件头
+    for offset in (SECTOR_SIZE..=data.len().saturating_sub(SECTOR_SIZE)).step_by(SECTOR_SIZE) {
+        let Some(entries) = parse_mbr(&data[offset..offset + SECTOR_SIZE]) else {
+            continue;
+        };
+        if entries.iter().any(|e| e.type_id == 0xEE) {
+            match parse_gpt(data, offset) {
+                Some(partitions) => return partitions,
+                None => continue,
+            }
+        }
+
+        return entries
+            .iter()
+            .filter(|e| !matches!(e.type_id, 0x05 | 0x0F | 0x85))
+            .enumerate()
+            .map(|(i, e)| GhoPartition {
+                number: i as u32 + 1,
+                kind: mbr_type_label(e.type_id),
+                size_bytes: e.sectors * SECTOR_SIZE as u64,
+            })
+            .collect();
+    }
+    Vec::new()
+}
+
+/// MBR 分区项
+struct MbrEntry {
+    type_id: u8,
+    start_lba: u64,
+    sectors: u64,
+}
+
+/// 解析 MBR 扇区，返回按起始扇区排序的分区项；不是有效的分区表时返回 None
+fn parse_mbr(sector: &[u8]) -> Option<Vec<MbrEntry>> {
+    if sector.len() < SECTOR_SIZE || sector[510] != 0x55 || sector[511] != 0xAA {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    for raw in sector[446..510].chunks_exact(16) {
+        if raw[0] != 0x00 && raw[0] != 0x80 {
+            return None;
+        }
+        let type_id = raw[4];
+        let start_lba = u32::from_le_bytes([raw[8], raw[9], raw[10], raw[11]]) as u64;
+        let sectors = u32::from_le_bytes([raw[12], raw[13], raw[14], raw[15]]) as u64;
+        if type_id == 0 {
+            if raw.iter().any(|&b| b != 0) {
+                return None;
+            }
+            continue;
+        }
+        if start_lba == 0 || sectors == 0 {
+            return None;
+        }
+        entries.push(MbrEntry { type_id, start_lba, sectors });
+    }
+    if entries.is_empty() {
+        return None;
+    }
+
+    entries.sort_by_key(|e| e.start_lba);
+    // 分区不能重叠
+    if entries.windows(2).any(|w| w[0].start_lba + w[0].sectors > w[1].start_lba) {
+        return None;
+    }
+    Some(entries)
+}
+
+/// 解析紧跟在保护性 MBR 之后的 GPT 头和分区项
+fn parse_gpt(data: &[u8], mbr_offset: usize) -> Option<Vec<GhoPartition>> {
+    let header = data.get(mbr_offset + SECTOR_SIZE..mbr_offset + 2 * SECTOR_SIZE)?;
+    if &header[..8] != b"EFI PART" {
+        return None;
+    }
+    let header_size = u32::from_le_bytes(header[12..16].try_into().ok()?) as usize;
+    if !(92..=SECTOR_SIZE).contains(&header_size) {
+        return None;
+    }
+    let mut check = header[..header_size].to_vec();
+    check[16..20].fill(0);
+    if crc32(&check) != u32::from_le_bytes(header[16..20].try_into().ok()?) {
+        return None;
+    }
+    let entries_lba = u64::from_le_bytes(header[72..80].try_into().ok()?);
+    let entry_count = u32::from_le_bytes(header[80..84].try_into().ok()?) as usize;
+    let entry_size = u32::from_le_bytes(header[84..88].try_into().ok()?) as usize;
+    if entry_size < 128 || entry_count > 1024 {
+        return None;
+    }
+    let entries_offset = mbr_offset.checked_add(usize::try_from(entries_lba).ok()?.checked_mul(SECTOR_SIZE)?)?;
+    let entries = data.get(entries_offset..entries_offset + entry_count * entry_size)?;
+    if crc32(entries) != u32::from_le_bytes(header[88..92].try_into().ok()?) {
+        return None;
+    }
+
+    let mut partitions = Vec::new();
+    for raw in entries.chunks_exact(entry_size) {
+        if raw[..16].iter().all(|&b| b == 0) {
+            continue;
+        }
+        let first_lba = u64::from_le_bytes(raw[32..40].try_into().ok()?);
+        let last_lba = u64::from_le_bytes(raw[40..48].try_into().ok()?);
+        if last_lba < first_lba {
+            return None;
+        }
+        let name: Vec<u16> = raw[56..128]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        let name = String::from_utf16_lossy(&name);
+        let type_label = gpt_type_label(&raw[..16]);
+        partitions.push(GhoPartition {
+            number: partitions.len() as u32 + 1,
+            kind: if name.is_empty() || name == type_label {
+                type_label
+            } else {
+                format!("{}（{}）", type_label, name)
+            },
+            size_bytes: (last_lba - first_lba + 1) * SECTOR_SIZE as u64,
+        });
+    }
+    if partitions.is_empty() {
+        None
+    } else {
+        Some(partitions)
+    }
+}
+
+/// CRC32（IEEE 802.3，GPT 使用）
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn mbr_type_label(type_id: u8) -> String {
+    match type_id {
+        0x07 => "NTFS".to_string(),
+        0x0B | 0x0C => "FAT32".to_string(),
+        0x01 | 0x04 | 0x06 | 0x0E => "FAT".to_string(),
+        0x27 => "恢复分区".to_string(),
+        0x83 => "Linux".to_string(),
+        _ => format!("类型 0x{:02X}", type_id),
+    }
+}
+
+/// GPT 分区类型（GUID 按磁盘上的混合字节序存储）
+fn gpt_type_label(guid: &[u8]) -> String {
+    let text = format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+        u32::from_le_bytes([guid[0], guid[1], guid[2], guid[3]]),
+        u16::from_le_bytes([guid[4], guid[5]]),
+        u16::from_le_bytes([guid[6], guid[7]]),
+        guid[8],
+        guid[9],
+        guid[10..16].iter().map(|b| format!("{:02X}", b)).collect::<String>()
+    );
+    match text.as_str() {
+        "C12A7328-F81F-11D2-BA4B-00A0C93EC93B" => "EFI 系统分区".to_string(),
+        "E3C9E316-0B5C-4DB8-817D-F92DF00215AE" => "MSR 保留分区".to_string(),
+        "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7" => "基本数据分区".to_string(),
+        "DE94BBA4-06D1-4D40-A16A-BFD50179D6AC" => "恢复分区".to_string(),
+        _ => text,
+    }
+}
+
 impl Default for Ghost {
     fn default() -> Self {
         Self::new()
@@ -588,4 +832,91 @@ mod tests {
         ghost.reset_cancel();
         assert!(!ghost.cancel_flag.load(Ordering::SeqCst));
     }
+
+    fn mbr_sector(entries: &[(u8, u32, u32)]) -> Vec<u8> {
+        let mut sector = vec![0u8; SECTOR_SIZE];
+        for (i, &(type_id, start, sectors)) in entries.iter().enumerate() {
+            let raw = &mut sector[446 + i * 16..462 + i * 16];
+            raw[4] = type_id;
+            raw[8..12].copy_from_slice(&start.to_le_bytes());
+            raw[12..16].copy_from_slice(&sectors.to_le_bytes());
+        }
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
+        sector
+    }
+
+    #[test]
+    fn test_parse_mbr_partition_table() {
+        let mut data = vec![0xFE, 0xEF];
+        data.resize(SECTOR_SIZE * 3, 0x5A);
+        // 系统保留分区 100 MB + NTFS 50 GB，放在第三个扇区
+        let table = mbr_sector(&[(0x07, 2048, 204800), (0x07, 206848, 104857600)]);
+        data[SECTOR_SIZE * 2..].copy_from_slice(&table);
+
+        let partitions = parse_partition_table(&data);
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].number, 1);
+        assert_eq!(partitions[1].size_bytes, 50 * 1024 * 1024 * 1024);
+        assert_eq!(partitions[1].kind, "NTFS");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_table() {
+        // 分区重叠
+        let mut data = vec![0u8; SECTOR_SIZE];
+        data.extend(mbr_sector(&[(0x07, 2048, 4096), (0x07, 4096, 4096)]));
+        assert!(parse_partition_table(&data).is_empty());
+
+        // 分区镜像没有分区表
+        assert!(parse_partition_table(&[0u8; SECTOR_SIZE * 4]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_gpt_partition_table() {
+        let mut data = vec![0u8; SECTOR_SIZE];
+        data.extend(mbr_sector(&[(0xEE, 1, u32::MAX)]));
+        let mut header = vec![0u8; SECTOR_SIZE];
+        header[..8].copy_from_slice(b"EFI PART");
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+
+        let esp = [
+            0x28, 0x73, 0x2A, 0xC1, 0x1F, 0xF8, 0xD2, 0x11, 0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B,
+        ];
+        let basic = [
+            0xA2, 0xA0, 0xD0, 0xEB, 0xE5, 0xB9, 0x33, 0x44, 0x87, 0xC0, 0x68, 0xB6, 0xB7, 0x26, 0x99, 0xC7,
+        ];
+        let mut entries = vec![0u8; 4 * 128];
+        for (i, (guid, first, last)) in [(esp, 2048u64, 206847u64), (basic, 206848, 104857599)].iter().enumerate() {
+            let raw = &mut entries[i * 128..(i + 1) * 128];
+            raw[..16].copy_from_slice(guid);
+            raw[32..40].copy_from_slice(&first.to_le_bytes());
+            raw[40..48].copy_from_slice(&last.to_le_bytes());
+        }
+        header[88..92].copy_from_slice(&crc32(&entries).to_le_bytes());
+        let header_crc = crc32(&header[..92]);
+        header[16..20].copy_from_slice(&header_crc.to_le_bytes());
+
+        let mut with_header = data.clone();
+        with_header.extend(&header);
+        with_header.extend(&entries);
+        let partitions = parse_partition_table(&with_header);
+        assert_eq!(partitions.len(), 2);
+        assert_eq!(partitions[0].kind, "EFI 系统分区");
+        assert_eq!(partitions[1].kind, "基本数据分区");
+
+        // 校验值不符（压缩数据中碰巧出现的字节）时不当作分区表
+        entries[0] ^= 0xFF;
+        data.extend(&header);
+        data.extend(&entries);
+        assert!(parse_partition_table(&data).is_empty());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
 }
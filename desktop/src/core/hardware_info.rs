@@ -5,6 +5,9 @@ use std::collections::HashMap;
 use std::ffi::OsString;
 use std::mem::{size_of, zeroed};
 use std::os::windows::ffi::OsStringExt;
+use std::sync::OnceLock;
+use std::thread::{Scope, ScopedJoinHandle};
+use std::time::Instant;
 
 use windows::core::{BSTR, PCWSTR, VARIANT};
 use windows::Win32::Foundation::{BOOL, CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
@@ -308,6 +311,55 @@ impl Drop for ComInitGuard {
 const RPC_C_AUTHN_DEFAULT: u32 = 0xFFFFFFFF;
 const RPC_C_AUTHZ_NONE: u32 = 0;
 
+// 连接在 MTA 中建立，MTA 内的 COM 接口可在已加入 MTA 的任意线程中使用
+unsafe impl Send for WmiConnection {}
+unsafe impl Sync for WmiConnection {}
+
+/// WMI 连接池
+///
+/// 每个命名空间只在首次查询时连接一次，同一次采集的各查询共享连接
+/// （慢速机器上每次建立连接需要数百毫秒）。
+/// 使用连接池的线程须已通过 [`ComInitGuard`] 加入 MTA，且连接池须先于这些守卫释放。
+#[derive(Default)]
+struct WmiPool {
+    cimv2: OnceLock<Option<WmiConnection>>,
+    storage: OnceLock<Option<WmiConnection>>,
+    volume_encryption: OnceLock<Option<WmiConnection>>,
+}
+
+impl WmiPool {
+    /// root\cimv2
+    fn cimv2(&self) -> Option<&WmiConnection> {
+        self.cimv2.get_or_init(WmiConnection::connect_cimv2).as_ref()
+    }
+
+    /// root\Microsoft\Windows\Storage（MSFT_PhysicalDisk）
+    fn storage(&self) -> Option<&WmiConnection> {
+        self.storage
+            .get_or_init(|| WmiConnection::connect("ROOT\\Microsoft\\Windows\\Storage"))
+            .as_ref()
+    }
+
+    /// BitLocker 所在的命名空间
+    fn volume_encryption(&self) -> Option<&WmiConnection> {
+        self.volume_encryption
+            .get_or_init(|| WmiConnection::connect("ROOT\\CIMV2\\Security\\MicrosoftVolumeEncryption"))
+            .as_ref()
+    }
+}
+
+/// 在加入 MTA 的线程中运行采集函数
+fn spawn_collector<'scope, T, F>(scope: &'scope Scope<'scope, '_>, collector: F) -> ScopedJoinHandle<'scope, T>
+where
+    T: Send + 'scope,
+    F: FnOnce() -> T + Send + 'scope,
+{
+    scope.spawn(move || {
+        let _com = ComInitGuard::new();
+        collector()
+    })
+}
+
 impl WmiConnection {
     /// 连接到指定的 WMI 命名空间
     fn connect(namespace: &str) -> Option<Self> {
@@ -666,12 +718,10 @@ fn variant_to_u16_array(var: &VARIANT) -> Option<Vec<u16>> {
 // ============================================================================
 
 /// 使用 WMI 获取内存条信息
-fn get_memory_sticks_wmi() -> Vec<MemoryStickInfo> {
-    let _com = ComInitGuard::new();
-
+fn get_memory_sticks_wmi(wmi: &WmiPool) -> Vec<MemoryStickInfo> {
     let mut sticks = Vec::new();
 
-    let Some(wmi) = WmiConnection::connect_cimv2() else {
+    let Some(wmi) = wmi.cimv2() else {
         return sticks;
     };
 
@@ -716,10 +766,8 @@ fn get_memory_sticks_wmi() -> Vec<MemoryStickInfo> {
 }
 
 /// 使用 WMI 获取内存插槽数
-fn get_memory_slot_count_wmi() -> u32 {
-    let _com = ComInitGuard::new();
-
-    let Some(wmi) = WmiConnection::connect_cimv2() else {
+fn get_memory_slot_count_wmi(wmi: &WmiPool) -> u32 {
+    let Some(wmi) = wmi.cimv2() else {
         return 0;
     };
 
@@ -739,10 +787,8 @@ fn get_memory_slot_count_wmi() -> u32 {
 }
 
 /// 使用 WMI 获取主板序列号
-fn get_baseboard_serial_wmi() -> Option<String> {
-    let _com = ComInitGuard::new();
-
-    let wmi = WmiConnection::connect_cimv2()?;
+fn get_baseboard_serial_wmi(wmi: &WmiPool) -> Option<String> {
+    let wmi = wmi.cimv2()?;
     let result = wmi.query("SELECT SerialNumber FROM Win32_BaseBoard")?;
 
     for obj in result {
@@ -758,10 +804,8 @@ fn get_baseboard_serial_wmi() -> Option<String> {
 }
 
 /// 使用 WMI 获取 BIOS 序列号
-fn get_bios_serial_wmi() -> Option<String> {
-    let _com = ComInitGuard::new();
-
-    let wmi = WmiConnection::connect_cimv2()?;
+fn get_bios_serial_wmi(wmi: &WmiPool) -> Option<String> {
+    let wmi = wmi.cimv2()?;
     let result = wmi.query("SELECT SerialNumber FROM Win32_BIOS")?;
 
     for obj in result {
@@ -777,10 +821,8 @@ fn get_bios_serial_wmi() -> Option<String> {
 }
 
 /// 使用 WMI 获取机箱类型
-fn get_chassis_types_wmi() -> Option<Vec<u16>> {
-    let _com = ComInitGuard::new();
-
-    let wmi = WmiConnection::connect_cimv2()?;
+fn get_chassis_types_wmi(wmi: &WmiPool) -> Option<Vec<u16>> {
+    let wmi = wmi.cimv2()?;
     let result = wmi.query("SELECT ChassisTypes FROM Win32_SystemEnclosure")?;
 
     for obj in result {
@@ -795,10 +837,8 @@ fn get_chassis_types_wmi() -> Option<Vec<u16>> {
 }
 
 /// 使用 WMI 获取电池信息
-fn get_battery_wmi_info() -> (Option<u32>, Option<u32>, Option<String>) {
-    let _com = ComInitGuard::new();
-
-    let Some(wmi) = WmiConnection::connect_cimv2() else {
+fn get_battery_wmi_info(wmi: &WmiPool) -> (Option<u32>, Option<u32>, Option<String>) {
+    let Some(wmi) = wmi.cimv2() else {
         return (None, None, None);
     };
 
@@ -817,10 +857,8 @@ fn get_battery_wmi_info() -> (Option<u32>, Option<u32>, Option<String>) {
 }
 
 /// 使用 WMI 获取便携电池制造商
-fn get_portable_battery_manufacturer_wmi() -> Option<String> {
-    let _com = ComInitGuard::new();
-
-    let wmi = WmiConnection::connect_cimv2()?;
+fn get_portable_battery_manufacturer_wmi(wmi: &WmiPool) -> Option<String> {
+    let wmi = wmi.cimv2()?;
     let result = wmi.query("SELECT Manufacturer FROM Win32_PortableBattery")?;
 
     for obj in result {
@@ -836,12 +874,10 @@ fn get_portable_battery_manufacturer_wmi() -> Option<String> {
 }
 
 /// 使用 WMI 获取磁盘大小信息
-fn get_disk_sizes_wmi() -> HashMap<u32, u64> {
-    let _com = ComInitGuard::new();
-
+fn get_disk_sizes_wmi(wmi: &WmiPool) -> HashMap<u32, u64> {
     let mut sizes = HashMap::new();
 
-    let Some(wmi) = WmiConnection::connect_cimv2() else {
+    let Some(wmi) = wmi.cimv2() else {
         return sizes;
     };
 
@@ -860,11 +896,9 @@ fn get_disk_sizes_wmi() -> HashMap<u32, u64> {
 
 /// 使用 WMI 获取 BitLocker 加密状态
 /// 通过 Win32_EncryptableVolume 类查询（需要管理员权限）
-fn get_bitlocker_status_wmi(drive_letter: &str) -> BitLockerStatus {
-    let _com = ComInitGuard::new();
-
+fn get_bitlocker_status_wmi(wmi: &WmiPool, drive_letter: &str) -> BitLockerStatus {
     // BitLocker 信息在 root\cimv2\Security\MicrosoftVolumeEncryption 命名空间
-    let Some(wmi) = WmiConnection::connect("ROOT\\CIMV2\\Security\\MicrosoftVolumeEncryption") else {
+    let Some(wmi) = wmi.volume_encryption() else {
         // 尝试备用方法：通过注册表检查
         return get_bitlocker_status_registry(drive_letter);
     };
@@ -959,24 +993,56 @@ fn get_bitlocker_status_registry(drive_letter: &str) -> BitLockerStatus {
 // ============================================================================
 
 impl HardwareInfo {
+    /// 采集硬件信息
+    ///
+    /// 调用线程可能已初始化为 STA（如 UI 线程），因此在独立的 MTA 线程中采集。
     pub fn collect() -> Result<Self, Box<dyn std::error::Error>> {
-        let mut info = HardwareInfo::default();
-        Self::get_computer_info(&mut info);
-        info.os = Self::get_os_info();
-        info.cpu = Self::get_cpu_info();
-        info.memory = Self::get_memory_info();
-        info.motherboard = Self::get_motherboard_info();
-        info.bios = Self::get_bios_info();
-        info.disks = Self::get_disk_info();
-        info.gpus = Self::get_gpu_info();
-        info.network_adapters = Self::get_network_adapters();
-        info.system_bitlocker_status = Self::get_system_bitlocker_status();
-        info.system_serial_number = Self::get_system_serial_number();
-        info.device_type = Self::get_device_type();
-        info.battery = Self::get_battery_info();
+        let started = Instant::now();
+        let info = std::thread::scope(|scope| spawn_collector(scope, Self::collect_in_mta).join())
+            .map_err(|_| "硬件信息采集线程异常退出")?;
+        log::info!("[HARDWARE] 硬件信息采集完成，耗时 {} ms", started.elapsed().as_millis());
         Ok(info)
     }
 
+    /// 依赖 WMI 的采集函数在各自的线程中并行执行并共享同一个连接池，
+    /// 只读注册表和 WinAPI 的部分在当前线程执行；连接池在本线程退出 MTA 前释放。
+    fn collect_in_mta() -> Self {
+        let wmi = WmiPool::default();
+        let mut info = HardwareInfo::default();
+
+        std::thread::scope(|scope| {
+            let memory = spawn_collector(scope, || Self::get_memory_info(&wmi));
+            let disks = spawn_collector(scope, || Self::get_disk_info(&wmi));
+            let bitlocker = spawn_collector(scope, || Self::get_system_bitlocker_status(&wmi));
+            let system = spawn_collector(scope, || {
+                let battery = Self::get_battery_info(&wmi);
+                let device_type = Self::get_device_type(&wmi, battery.is_some());
+                (
+                    Self::get_motherboard_info(&wmi),
+                    Self::get_system_serial_number(&wmi),
+                    device_type,
+                    battery,
+                )
+            });
+
+            Self::get_computer_info(&mut info);
+            info.os = Self::get_os_info();
+            info.cpu = Self::get_cpu_info();
+            info.bios = Self::get_bios_info();
+            info.gpus = Self::get_gpu_info();
+            info.network_adapters = Self::get_network_adapters();
+
+            // 单个采集线程出错时保留默认值
+            info.memory = memory.join().unwrap_or_default();
+            info.disks = disks.join().unwrap_or_default();
+            info.system_bitlocker_status = bitlocker.join().unwrap_or_default();
+            (info.motherboard, info.system_serial_number, info.device_type, info.battery) =
+                system.join().unwrap_or_default();
+        });
+
+        info
+    }
+
     pub fn to_formatted_text(&self, sys_info: Option<&crate::core::system_info::SystemInfo>) -> String {
         let mut lines = Vec::new();
         let arch_str = match self.os.architecture.as_str() {
@@ -1083,7 +1149,7 @@ impl HardwareInfo {
         cpu_info
     }
 
-    fn get_memory_info() -> MemoryInfo {
+    fn get_memory_info(wmi: &WmiPool) -> MemoryInfo {
         let mut mem_info = MemoryInfo::default();
 
         // 使用 GlobalMemoryStatusEx 获取内存总量
@@ -1100,10 +1166,10 @@ impl HardwareInfo {
         }
 
         // 使用 WMI 获取内存条详细信息
-        mem_info.sticks = get_memory_sticks_wmi();
+        mem_info.sticks = get_memory_sticks_wmi(wmi);
 
         // 使用 WMI 获取内存插槽数
-        mem_info.slot_count = get_memory_slot_count_wmi();
+        mem_info.slot_count = get_memory_slot_count_wmi(wmi);
         if mem_info.slot_count == 0 && !mem_info.sticks.is_empty() {
             mem_info.slot_count = mem_info.sticks.len() as u32;
         }
@@ -1111,7 +1177,7 @@ impl HardwareInfo {
         mem_info
     }
 
-    fn get_motherboard_info() -> MotherboardInfo {
+    fn get_motherboard_info(wmi: &WmiPool) -> MotherboardInfo {
         let mut mb_info = MotherboardInfo::default();
         let bios_path = r"HARDWARE\DESCRIPTION\System\BIOS";
         if let Some(manufacturer) = read_registry_string(HKEY_LOCAL_MACHINE, bios_path, "BaseBoardManufacturer") { mb_info.manufacturer = manufacturer; }
@@ -1119,7 +1185,7 @@ impl HardwareInfo {
         if let Some(version) = read_registry_string(HKEY_LOCAL_MACHINE, bios_path, "BaseBoardVersion") { mb_info.version = version; }
 
         // 使用 WMI 获取主板序列号
-        if let Some(serial) = get_baseboard_serial_wmi() {
+        if let Some(serial) = get_baseboard_serial_wmi(wmi) {
             mb_info.serial_number = serial;
         }
 
@@ -1136,19 +1202,20 @@ impl HardwareInfo {
         bios_info
     }
 
-    fn get_disk_info() -> Vec<DiskInfo> {
+    fn get_disk_info(wmi: &WmiPool) -> Vec<DiskInfo> {
         let mut disks = Vec::new();
         let partition_styles = get_disk_partition_styles();
 
-        // 使用 WMI 获取磁盘大小
-        let disk_sizes = get_disk_sizes_wmi();
+        // 使用 WMI 获取磁盘大小和媒体类型
+        let disk_sizes = get_disk_sizes_wmi(wmi);
+        let media_info = get_wmi_disk_media_info(wmi);
 
         for i in 0..16 {
             let path = format!(r"\\.\PhysicalDrive{}", i);
             if let Some(mut disk) = query_disk_info(&path) {
                 disk.disk_index = i;
                 // 使用综合检测方法判断是否为SSD
-                disk.is_ssd = detect_disk_is_ssd(i, &disk.model, &disk.interface_type);
+                disk.is_ssd = detect_disk_is_ssd(&media_info, i, &disk.model, &disk.interface_type);
                 if let Some(style) = partition_styles.get(&i) { disk.partition_style = style.clone(); }
                 // 如果DeviceIoControl没有获取到大小，使用WMI的结果
                 if disk.size == 0 {
@@ -1216,12 +1283,12 @@ impl HardwareInfo {
         adapters
     }
 
-    fn get_system_bitlocker_status() -> BitLockerStatus {
+    fn get_system_bitlocker_status(wmi: &WmiPool) -> BitLockerStatus {
         let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
-        get_bitlocker_status_wmi(&system_drive)
+        get_bitlocker_status_wmi(wmi, &system_drive)
     }
 
-    fn get_system_serial_number() -> String {
+    fn get_system_serial_number(wmi: &WmiPool) -> String {
         // 首先尝试从注册表获取
         if let Some(serial) = read_registry_string(HKEY_LOCAL_MACHINE, r"HARDWARE\DESCRIPTION\System\BIOS", "SystemSerialNumber") {
             if !serial.is_empty() && !is_placeholder(&serial) {
@@ -1230,16 +1297,16 @@ impl HardwareInfo {
         }
 
         // 使用 WMI 获取 BIOS 序列号
-        if let Some(serial) = get_bios_serial_wmi() {
+        if let Some(serial) = get_bios_serial_wmi(wmi) {
             return serial;
         }
 
         String::new()
     }
 
-    fn get_device_type() -> DeviceType {
+    fn get_device_type(wmi: &WmiPool, has_battery: bool) -> DeviceType {
         // 使用 WMI 获取机箱类型
-        if let Some(chassis_types) = get_chassis_types_wmi() {
+        if let Some(chassis_types) = get_chassis_types_wmi(wmi) {
             for chassis_type in chassis_types {
                 let device_type = match chassis_type {
                     3 | 4 | 5 | 6 | 7 | 15 | 16 | 35 | 36 => DeviceType::Desktop,
@@ -1256,14 +1323,14 @@ impl HardwareInfo {
         }
 
         // 如果无法通过机箱类型判断，检查是否有电池
-        if Self::get_battery_info().is_some() {
+        if has_battery {
             return DeviceType::Laptop;
         }
 
         DeviceType::Unknown
     }
 
-    fn get_battery_info() -> Option<BatteryInfo> {
+    fn get_battery_info(wmi: &WmiPool) -> Option<BatteryInfo> {
         #[link(name = "kernel32")] extern "system" { fn GetSystemPowerStatus(lpSystemPowerStatus: *mut SYSTEM_POWER_STATUS) -> i32; }
         unsafe {
             let mut power_status: SYSTEM_POWER_STATUS = zeroed();
@@ -1276,7 +1343,7 @@ impl HardwareInfo {
             battery.is_charging = (power_status.BatteryFlag & 8) != 0;
 
             // 使用 WMI 获取电池详细信息
-            let (design_capacity, full_charge_capacity, name) = get_battery_wmi_info();
+            let (design_capacity, full_charge_capacity, name) = get_battery_wmi_info(wmi);
             if let Some(dc) = design_capacity {
                 battery.design_capacity_mwh = dc;
             }
@@ -1288,7 +1355,7 @@ impl HardwareInfo {
             }
 
             // 使用 WMI 获取电池制造商
-            if let Some(mfr) = get_portable_battery_manufacturer_wmi() {
+            if let Some(mfr) = get_portable_battery_manufacturer_wmi(wmi) {
                 battery.manufacturer = mfr;
             }
 
@@ -1437,12 +1504,11 @@ struct WmiDiskMediaInfo {
 
 /// 使用 WMI MSFT_PhysicalDisk 类获取所有磁盘的媒体类型信息
 /// 这是 Windows 8+ 上最可靠的方法
-fn get_wmi_disk_media_info() -> HashMap<u32, WmiDiskMediaInfo> {
-    let _com = ComInitGuard::new();
+fn get_wmi_disk_media_info(wmi: &WmiPool) -> HashMap<u32, WmiDiskMediaInfo> {
     let mut disk_info_map = HashMap::new();
     
     // 连接到 Storage 命名空间
-    let Some(wmi) = WmiConnection::connect("ROOT\\Microsoft\\Windows\\Storage") else {
+    let Some(wmi) = wmi.storage() else {
         return disk_info_map;
     };
     
@@ -1740,11 +1806,8 @@ fn is_known_usb_ssd(model: &str) -> bool {
 
 /// 综合检测磁盘类型
 /// 返回 true 表示是 SSD，false 表示是 HDD
-fn detect_disk_is_ssd(disk_index: u32, model: &str, interface: &str) -> bool {
-    // 获取 WMI 信息（如果可用）
-    static WMI_DISK_INFO: std::sync::OnceLock<HashMap<u32, WmiDiskMediaInfo>> = std::sync::OnceLock::new();
-    let wmi_info = WMI_DISK_INFO.get_or_init(get_wmi_disk_media_info);
-    
+fn detect_disk_is_ssd(wmi_info: &HashMap<u32, WmiDiskMediaInfo>, disk_index: u32, model: &str, interface: &str) -> bool {
+    // WMI 信息（如果可用）
     let wmi_disk = wmi_info.get(&disk_index);
     
     // =====================================================
@@ -1963,7 +2026,7 @@ fn query_disk_info(path: &str) -> Option<DiskInfo> {
         if descriptor.ProductIdOffset > 0 && (descriptor.ProductIdOffset as usize) < buffer.len() { let offset = descriptor.ProductIdOffset as usize; if let Some(end) = buffer[offset..].iter().position(|&b| b == 0) { disk.model = String::from_utf8_lossy(&buffer[offset..offset + end]).trim().to_string(); } }
         if descriptor.SerialNumberOffset > 0 && (descriptor.SerialNumberOffset as usize) < buffer.len() { let offset = descriptor.SerialNumberOffset as usize; if let Some(end) = buffer[offset..].iter().position(|&b| b == 0) { disk.serial_number = String::from_utf8_lossy(&buffer[offset..offset + end]).trim().to_string(); } }
         if descriptor.ProductRevisionOffset > 0 && (descriptor.ProductRevisionOffset as usize) < buffer.len() { let offset = descriptor.ProductRevisionOffset as usize; if let Some(end) = buffer[offset..].iter().position(|&b| b == 0) { disk.firmware_revision = String::from_utf8_lossy(&buffer[offset..offset + end]).trim().to_string(); } }
-        disk.interface_type = match descriptor.BusType { 1 => "SCSI".to_string(), 3 => "ATA".to_string(), 7 => "USB".to_string(), 11 => "SATA".to_string(), 17 => "NVMe".to_string(), _ => format!("Unknown({})", descriptor.BusType) };
+        disk.interface_type = match descriptor.BusType { 1 => "SCSI".to_string(), 3 => "ATA".to_string(), 7 => "USB".to_string(), 11 => "SATA".to_string(), 12 => "SD".to_string(), 13 => "eMMC".to_string(), 17 => "NVMe".to_string(), _ => format!("Unknown({})", descriptor.BusType) };
         disk.media_type = if descriptor.RemovableMedia != 0 { "可移动".to_string() } else { "固定".to_string() };
         let mut length_info: GET_LENGTH_INFORMATION = zeroed();
         let mut bytes_ret: u32 = 0;
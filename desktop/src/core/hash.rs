@@ -0,0 +1,365 @@
+//! 文件哈希校验
+//!
+//! 计算 MD5 / SHA1 / SHA256 并与服务器声明的值比对。几 GB 的镜像校验耗时主要在读盘和哈希计算上：
+//! 多个读取线程按块交错读取文件（第 i 块由第 i % N 个线程读取），主线程按顺序取块后分发给
+//! 每种算法各自的计算线程，读盘和多种算法的计算同时进行，内存占用以队列深度为上限。
+//!
+//! 服务器哈希配置格式（按下载地址或文件名匹配，算法可任选）：
+//! `{"files": [{"name": "Win11_24H2.iso", "sha256": "..."}, {"url": "https://...", "md5": "...", "sha1": "..."}]}`
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::core::md5::Md5Context;
+
+/// 每次读取的块大小
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+/// 读取线程数
+const READER_THREADS: usize = 2;
+/// 每个队列最多缓存的块数
+const QUEUE_DEPTH: usize = 4;
+
+/// 哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 3] = [HashAlgorithm::Md5, HashAlgorithm::Sha1, HashAlgorithm::Sha256];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Sha256 => "SHA256",
+        }
+    }
+}
+
+enum Hasher {
+    Md5(Md5Context),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Hasher::Md5(Md5Context::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(sha2::Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(context) => context.update(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// 大写十六进制摘要
+    fn finalize_hex(self) -> String {
+        let digest: Vec<u8> = match self {
+            Hasher::Md5(context) => context.finalize().to_vec(),
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+}
+
+/// 预期的文件哈希（十六进制，不区分大小写）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedHashes {
+    #[serde(default)]
+    pub md5: Option<String>,
+    #[serde(default)]
+    pub sha1: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+}
+
+impl ExpectedHashes {
+    /// 只校验 MD5
+    pub fn md5(value: &str) -> Self {
+        Self {
+            md5: Some(value.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// 指定算法的预期值（忽略空值）
+    pub fn get(&self, algorithm: HashAlgorithm) -> Option<&str> {
+        let value = match algorithm {
+            HashAlgorithm::Md5 => &self.md5,
+            HashAlgorithm::Sha1 => &self.sha1,
+            HashAlgorithm::Sha256 => &self.sha256,
+        };
+        value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
+
+    /// 已声明的算法
+    pub fn algorithms(&self) -> Vec<HashAlgorithm> {
+        HashAlgorithm::ALL.into_iter().filter(|a| self.get(*a).is_some()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.algorithms().is_empty()
+    }
+}
+
+/// 哈希不匹配
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashMismatch {
+    pub algorithm: HashAlgorithm,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// 服务器哈希配置中的一项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclaredHash {
+    /// 文件名（不区分大小写）
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 下载地址（完全匹配）
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(flatten)]
+    pub hashes: ExpectedHashes,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeclaredHashList {
+    files: Vec<DeclaredHash>,
+}
+
+/// 解析服务器哈希配置
+pub fn parse_declared(content: &str) -> Vec<DeclaredHash> {
+    match serde_json::from_str::<DeclaredHashList>(content) {
+        Ok(list) => {
+            log::info!("文件哈希配置加载成功，共 {} 项", list.files.len());
+            list.files
+        }
+        Err(e) => {
+            log::warn!("解析文件哈希配置失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 查找下载文件的预期哈希：先按下载地址匹配，再按文件名匹配
+pub fn find_declared<'a>(list: &'a [DeclaredHash], url: Option<&str>, file_name: Option<&str>) -> Option<&'a ExpectedHashes> {
+    let declared = || list.iter().filter(|d| !d.hashes.is_empty());
+    url.and_then(|url| declared().find(|d| d.url.as_deref() == Some(url)))
+        .or_else(|| {
+            let file_name = file_name?;
+            declared().find(|d| d.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(file_name)))
+        })
+        .map(|d| &d.hashes)
+}
+
+/// 读取线程：依次读取编号为 reader, reader + N, reader + 2N... 的块
+fn read_chunks(path: &Path, reader: usize, chunks: u64, total: u64, tx: SyncSender<std::io::Result<Arc<Vec<u8>>>>) {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            let _ = tx.send(Err(e));
+            return;
+        }
+    };
+    for index in (reader as u64..chunks).step_by(READER_THREADS) {
+        let offset = index * CHUNK_SIZE as u64;
+        let mut buffer = vec![0u8; (total - offset).min(CHUNK_SIZE as u64) as usize];
+        let result = file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut buffer))
+            .map(|_| Arc::new(buffer));
+        let failed = result.is_err();
+        if tx.send(result).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// 计算文件哈希，返回各算法的大写十六进制摘要
+///
+/// `on_progress(已处理字节, 总字节)` 在主线程中按块调用
+pub fn hash_file(
+    path: &Path,
+    algorithms: &[HashAlgorithm],
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<Vec<(HashAlgorithm, String)>> {
+    let total = std::fs::metadata(path)
+        .with_context(|| format!("无法读取文件: {}", path.display()))?
+        .len();
+    if algorithms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let chunks = total.div_ceil(CHUNK_SIZE as u64);
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..READER_THREADS)
+            .map(|reader| {
+                let (tx, rx) = mpsc::sync_channel(QUEUE_DEPTH);
+                scope.spawn(move || read_chunks(path, reader, chunks, total, tx));
+                rx
+            })
+            .collect();
+
+        let (senders, workers): (Vec<_>, Vec<_>) = algorithms
+            .iter()
+            .map(|&algorithm| {
+                let (tx, rx) = mpsc::sync_channel::<Arc<Vec<u8>>>(QUEUE_DEPTH);
+                let worker = scope.spawn(move || {
+                    let mut hasher = Hasher::new(algorithm);
+                    for chunk in rx {
+                        hasher.update(&chunk);
+                    }
+                    (algorithm, hasher.finalize_hex())
+                });
+                (tx, worker)
+            })
+            .unzip();
+
+        // 按顺序取块：第 i 块一定来自第 i % N 个读取线程
+        let mut processed = 0u64;
+        for index in 0..chunks {
+            let chunk = readers[(index % READER_THREADS as u64) as usize]
+                .recv()
+                .context("读取线程异常退出")?
+                .with_context(|| format!("读取文件失败: {}", path.display()))?;
+            processed += chunk.len() as u64;
+            for tx in &senders {
+                tx.send(chunk.clone()).map_err(|_| anyhow!("哈希计算线程异常退出"))?;
+            }
+            on_progress(processed, total);
+        }
+        drop(senders);
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join().map_err(|_| anyhow!("哈希计算线程异常退出")))
+            .collect()
+    })
+}
+
+/// 校验文件哈希，返回不匹配的算法（为空表示全部通过）
+pub fn verify_file(
+    path: &Path,
+    expected: &ExpectedHashes,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<Vec<HashMismatch>> {
+    let actual = hash_file(path, &expected.algorithms(), on_progress)?;
+    Ok(actual
+        .into_iter()
+        .filter_map(|(algorithm, actual)| {
+            let expected = expected.get(algorithm)?;
+            (!expected.eq_ignore_ascii_case(&actual)).then(|| HashMismatch {
+                algorithm,
+                expected: expected.to_uppercase(),
+                actual,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, data: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("lr_hash_test_{}_{}", std::process::id(), name));
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_known_digests() {
+        let path = write_temp("abc", b"abc");
+        let digests = hash_file(&path, &HashAlgorithm::ALL, |_, _| {}).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            digests,
+            vec![
+                (HashAlgorithm::Md5, "900150983CD24FB0D6963F7D28E17F72".to_string()),
+                (HashAlgorithm::Sha1, "A9993E364706816ABA3E25717850C26C9CD0D89D".to_string()),
+                (
+                    HashAlgorithm::Sha256,
+                    "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_chunk_matches_sequential() {
+        // 跨越多个块且最后一块不满
+        let data: Vec<u8> = (0..CHUNK_SIZE * 3 + 12345).map(|i| (i * 31 % 251) as u8).collect();
+        let path = write_temp("chunks", &data);
+        let mut reports = Vec::new();
+        let digests = hash_file(&path, &[HashAlgorithm::Sha256, HashAlgorithm::Md5], |done, total| {
+            reports.push((done, total))
+        })
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for (algorithm, digest) in digests {
+            let mut hasher = Hasher::new(algorithm);
+            hasher.update(&data);
+            assert_eq!(digest, hasher.finalize_hex(), "{}", algorithm.name());
+        }
+        assert_eq!(reports.len(), 4);
+        assert_eq!(reports.last(), Some(&(data.len() as u64, data.len() as u64)));
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let path = write_temp("verify", b"abc");
+        let expected = ExpectedHashes {
+            md5: Some("900150983cd24fb0d6963f7d28e17f72".to_string()),
+            sha1: Some(" ".to_string()),
+            sha256: Some("00".to_string()),
+        };
+        let mismatches = verify_file(&path, &expected, |_, _| {}).unwrap();
+        assert!(verify_file(&path, &ExpectedHashes::md5("900150983CD24FB0D6963F7D28E17F72"), |_, _| {})
+            .unwrap()
+            .is_empty());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].algorithm, HashAlgorithm::Sha256);
+        assert_eq!(mismatches[0].expected, "00");
+        assert!(hash_file(&path, &[HashAlgorithm::Md5], |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn test_find_declared() {
+        let list = parse_declared(
+            r#"{"files": [
+                {"name": "Win11.iso", "sha256": "AA"},
+                {"url": "https://example.com/a/Win11.iso", "md5": "BB"},
+                {"name": "empty.iso"}
+            ]}"#,
+        );
+        assert_eq!(list.len(), 3);
+        let by_url = find_declared(&list, Some("https://example.com/a/Win11.iso"), Some("win11.iso")).unwrap();
+        assert_eq!(by_url.get(HashAlgorithm::Md5), Some("BB"));
+        let by_name = find_declared(&list, Some("https://mirror/Win11.iso"), Some("win11.ISO")).unwrap();
+        assert_eq!(by_name.algorithms(), vec![HashAlgorithm::Sha256]);
+        assert!(find_declared(&list, None, Some("empty.iso")).is_none());
+        assert!(parse_declared("[]").is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+//! 操作历史模块
+//! 以 JSON Lines 格式追加记录到程序目录下的 history.jsonl
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::utils::path::get_exe_dir;
+
+/// 操作类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    /// 接受镜像许可协议
+    EulaAccepted,
+}
+
+/// 历史记录条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 记录时间（本地时间）
+    pub time: String,
+    /// 操作类型
+    pub kind: HistoryKind,
+    /// 操作对象（镜像名称、分区等）
+    pub target: String,
+    /// 附加信息
+    #[serde(default)]
+    pub detail: String,
+}
+
+impl HistoryEntry {
+    /// 以当前时间创建记录
+    pub fn new(kind: HistoryKind, target: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            kind,
+            target: target.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+/// 操作历史
+pub struct OperationHistory;
+
+impl OperationHistory {
+    /// 历史文件路径
+    fn get_history_path() -> PathBuf {
+        get_exe_dir().join("history.jsonl")
+    }
+
+    /// 追加一条记录
+    pub fn append(entry: &HistoryEntry) -> Result<()> {
+        let path = Self::get_history_path();
+        let line = serde_json::to_string(entry).context("序列化历史记录失败")?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("打开历史文件失败: {}", path.display()))?;
+        writeln!(file, "{}", line).context("写入历史记录失败")?;
+        Ok(())
+    }
+
+    /// 读取全部记录（跳过无法解析的行）
+    pub fn load() -> Vec<HistoryEntry> {
+        std::fs::read_to_string(Self::get_history_path())
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// 解析 JSON Lines 内容
+    fn parse(content: &str) -> Vec<HistoryEntry> {
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    /// 检查是否已接受过指定许可协议
+    pub fn has_accepted_eula(eula_key: &str) -> bool {
+        Self::load()
+            .iter()
+            .any(|e| e.kind == HistoryKind::EulaAccepted && e.detail == eula_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_invalid_lines() {
+        let entry = HistoryEntry::new(HistoryKind::EulaAccepted, "win10.iso", "oem@1");
+        let content = format!("{}\nnot json\n\n", serde_json::to_string(&entry).unwrap());
+        let entries = OperationHistory::parse(&content);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].kind, HistoryKind::EulaAccepted);
+        assert_eq!(entries[0].detail, "oem@1");
+    }
+}
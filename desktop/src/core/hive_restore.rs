@@ -0,0 +1,200 @@
+//! 注册表配置单元恢复模块
+//!
+//! 以只读方式挂载备份镜像，取出其中单个配置单元（SOFTWARE、SYSTEM 等）替换到目标系统，
+//! 用于修复个别配置单元损坏而不必整盘还原。
+//! - 离线系统（PE 下或其他分区）：先备份原文件和事务日志，再直接复制
+//! - 当前运行的系统：通过 RegReplaceKey 安排在下次启动时替换
+
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::core::system_utils;
+use crate::core::wimgapi::Wimgapi;
+
+/// 可恢复的配置单元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hive {
+    Software,
+    System,
+    Sam,
+    Security,
+}
+
+impl Hive {
+    pub const ALL: [Hive; 4] = [Hive::Software, Hive::System, Hive::Sam, Hive::Security];
+
+    /// config 目录下的文件名（同时也是 HKLM 下的键名）
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Hive::Software => "SOFTWARE",
+            Hive::System => "SYSTEM",
+            Hive::Sam => "SAM",
+            Hive::Security => "SECURITY",
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Hive::Software => "SOFTWARE（软件与系统设置）",
+            Hive::System => "SYSTEM（驱动与服务）",
+            Hive::Sam => "SAM（本地账户）",
+            Hive::Security => "SECURITY（安全策略）",
+        }
+    }
+}
+
+/// 系统根目录下的配置单元目录
+pub fn config_dir(system_root: &Path) -> PathBuf {
+    system_root.join("Windows").join("System32").join("config")
+}
+
+/// 文件头是否为注册表配置单元（"regf"）
+pub fn is_hive_header(header: &[u8]) -> bool {
+    header.starts_with(b"regf")
+}
+
+fn is_hive_file(path: &Path) -> bool {
+    let mut header = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map(|_| is_hive_header(&header))
+        .unwrap_or(false)
+}
+
+/// 备份文件名（如 SOFTWARE.LetRecovery-20240115-103000）
+fn backup_name(file_name: &str, timestamp: &str) -> String {
+    format!("{}.LetRecovery-{}", file_name, timestamp)
+}
+
+/// 目标分区是否为当前运行的系统
+pub fn is_running_system(system_root: &str, is_pe: bool) -> bool {
+    if is_pe {
+        return false;
+    }
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    system_root
+        .trim_end_matches('\\')
+        .eq_ignore_ascii_case(system_drive.trim_end_matches('\\'))
+}
+
+/// 只读挂载镜像，取出配置单元复制到 dest_dir，返回复制出的文件
+pub fn extract_hive_from_image(image_file: &str, index: u32, hive: Hive, dest_dir: &Path) -> Result<PathBuf> {
+    let wimgapi = Wimgapi::new(None).map_err(|e| anyhow::anyhow!("无法加载 wimgapi.dll: {}", e))?;
+    let mount_dir = std::env::temp_dir().join(format!("LetRecovery_HiveMount_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&mount_dir);
+    std::fs::create_dir_all(&mount_dir).context("创建挂载目录失败")?;
+    std::fs::create_dir_all(dest_dir).context("创建临时目录失败")?;
+
+    println!("[HIVE RESTORE] 只读挂载 {} #{} -> {}", image_file, index, mount_dir.display());
+    wimgapi
+        .mount_image(&mount_dir, Path::new(image_file), index, None)
+        .map_err(|e| anyhow::anyhow!("挂载镜像失败: {}", e))?;
+
+    let source = config_dir(&mount_dir).join(hive.file_name());
+    let dest = dest_dir.join(hive.file_name());
+    let copy_result = if source.is_file() {
+        std::fs::copy(&source, &dest)
+            .map(|_| ())
+            .with_context(|| format!("复制配置单元失败: {}", source.display()))
+    } else {
+        Err(anyhow::anyhow!("镜像中没有找到 {}（不是系统分区的备份？）", hive.file_name()))
+    };
+
+    if let Err(e) = wimgapi.unmount_image(&mount_dir, Path::new(image_file), index, false) {
+        println!("[HIVE RESTORE] 卸载镜像失败: {}", e);
+    }
+    let _ = std::fs::remove_dir_all(&mount_dir);
+
+    copy_result?;
+    if !is_hive_file(&dest) {
+        bail!("镜像中的 {} 不是有效的配置单元", hive.file_name());
+    }
+    Ok(dest)
+}
+
+/// 用新的配置单元文件替换目标系统中的配置单元，返回结果说明
+pub fn replace_hive(system_root: &str, hive: Hive, new_hive: &Path, is_pe: bool) -> Result<String> {
+    let config = config_dir(Path::new(system_root));
+    let current = config.join(hive.file_name());
+    if !current.exists() {
+        bail!("目标系统中不存在 {}", current.display());
+    }
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let backup = config.join(backup_name(hive.file_name(), &timestamp));
+
+    if is_running_system(system_root, is_pe) {
+        // RegReplaceKey 要求新文件与原配置单元在同一卷
+        let staged = config.join(format!("{}.LetRecovery-new", hive.file_name()));
+        std::fs::copy(new_hive, &staged).context("复制配置单元到系统目录失败")?;
+        if let Err(e) = system_utils::replace_live_hive(hive.file_name(), &staged, &backup) {
+            let _ = std::fs::remove_file(&staged);
+            return Err(e);
+        }
+        return Ok(format!(
+            "已安排替换 {}，重启后生效。原配置单元将保存为 {}",
+            hive.file_name(),
+            backup.display()
+        ));
+    }
+
+    // 离线替换：原配置单元和事务日志一起改名备份，避免旧日志回放到新配置单元
+    std::fs::rename(&current, &backup).with_context(|| format!("备份 {} 失败", current.display()))?;
+    for log_ext in ["LOG", "LOG1", "LOG2"] {
+        let log = config.join(format!("{}.{}", hive.file_name(), log_ext));
+        if log.exists() {
+            let log_backup = config.join(backup_name(&format!("{}.{}", hive.file_name(), log_ext), &timestamp));
+            if let Err(e) = std::fs::rename(&log, &log_backup) {
+                println!("[HIVE RESTORE] 备份事务日志失败 {}: {}", log.display(), e);
+            }
+        }
+    }
+
+    if let Err(e) = std::fs::copy(new_hive, &current) {
+        // 复制失败时还原原文件
+        let _ = std::fs::rename(&backup, &current);
+        bail!("写入配置单元失败: {}", e);
+    }
+
+    println!("[HIVE RESTORE] 已替换 {}，备份: {}", current.display(), backup.display());
+    Ok(format!("已替换 {}，原配置单元已备份为 {}", current.display(), backup.display()))
+}
+
+/// 从备份镜像恢复单个配置单元到目标系统
+pub fn restore_hive_from_image(
+    image_file: &str,
+    index: u32,
+    hive: Hive,
+    system_root: &str,
+    is_pe: bool,
+) -> Result<String> {
+    let staging = std::env::temp_dir().join(format!("LetRecovery_Hive_{}", std::process::id()));
+    let result = extract_hive_from_image(image_file, index, hive, &staging)
+        .and_then(|new_hive| replace_hive(system_root, hive, &new_hive, is_pe));
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hive_names() {
+        assert_eq!(Hive::Software.file_name(), "SOFTWARE");
+        assert_eq!(Hive::ALL.len(), 4);
+        assert_eq!(
+            backup_name("SYSTEM", "20240115-103000"),
+            "SYSTEM.LetRecovery-20240115-103000"
+        );
+    }
+
+    #[test]
+    fn test_is_hive_header() {
+        assert!(is_hive_header(b"regf\x01\x00\x00\x00"));
+        assert!(!is_hive_header(b"MZ\x90\x00"));
+        assert!(!is_hive_header(b""));
+    }
+}
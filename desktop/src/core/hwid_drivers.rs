@@ -0,0 +1,297 @@
+//! 按硬件 ID 下载网卡和存储驱动
+//!
+//! 通过 SetupAPI 读取本机 PCI / USB 网卡和存储控制器的硬件 ID，与服务器下发的驱动索引比对，
+//! 下载匹配的驱动并解压到数据分区 drivers\hwid 下，由 PE 安装时注入新系统，
+//! 避免装好后没有网卡驱动无法联网、或新平台找不到硬盘。
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::core::driver::DeviceInfo;
+use crate::core::driver_pack::{is_valid_pack_id, verify_sha256};
+use crate::download::config::OnlineHwidDriver;
+
+/// 驱动目录下存放按硬件 ID 下载的驱动的子目录
+pub const HWID_DRIVERS_DIR: &str = "hwid";
+
+/// 网卡和存储控制器的设备类别
+const TARGET_CLASSES: [&str; 3] = ["Net", "SCSIAdapter", "HDC"];
+
+/// 兼容 ID 前缀：PCI 类代码 01（大容量存储）、02（网络），
+/// USB 接口类 02（通信设备）、08（大容量存储）和 RNDIS 网卡
+const TARGET_COMPATIBLE_PREFIXES: [&str; 5] = [
+    "PCI\\CC_01",
+    "PCI\\CC_02",
+    "USB\\CLASS_02",
+    "USB\\CLASS_08",
+    "USB\\CLASS_E0&SUBCLASS_01&PROT_03",
+];
+
+/// 匹配到的驱动及对应的设备
+#[derive(Debug, Clone)]
+pub struct MatchedDriver {
+    pub driver: OnlineHwidDriver,
+    /// 匹配的设备描述
+    pub devices: Vec<String>,
+}
+
+/// 暂存结果
+#[derive(Debug, Clone, Default)]
+pub struct StageReport {
+    /// 已下载的驱动名称
+    pub staged: Vec<String>,
+    /// 下载失败的驱动名称和原因
+    pub failed: Vec<(String, String)>,
+}
+
+impl StageReport {
+    /// 一行摘要，用于日志
+    pub fn summary(&self) -> String {
+        if self.staged.is_empty() && self.failed.is_empty() {
+            return "没有匹配本机网卡或存储控制器的驱动".to_string();
+        }
+        let mut summary = format!("已下载 {} 个驱动", self.staged.len());
+        if !self.failed.is_empty() {
+            summary.push_str(&format!("，{} 个失败", self.failed.len()));
+        }
+        summary
+    }
+}
+
+/// 是否为 PCI / USB 总线上的网卡或存储控制器
+pub fn is_target_device(device: &DeviceInfo) -> bool {
+    let on_bus = device.hardware_ids.first().is_some_and(|id| {
+        let id = id.to_uppercase();
+        id.starts_with("PCI\\") || id.starts_with("USB\\")
+    });
+    if !on_bus {
+        return false;
+    }
+    if TARGET_CLASSES
+        .iter()
+        .any(|class| class.eq_ignore_ascii_case(&device.device_class))
+    {
+        return true;
+    }
+    // 没有驱动的设备没有类别，按兼容 ID 中的类代码判断
+    device.compatible_ids.iter().any(|id| {
+        let id = id.to_uppercase();
+        TARGET_COMPATIBLE_PREFIXES.iter().any(|prefix| id.starts_with(prefix))
+    })
+}
+
+/// 索引中的硬件 ID 是否匹配设备 ID：完全相同，或为设备 ID 在某个 `&` 之前的部分（不区分大小写）
+pub fn id_matches(index_id: &str, device_id: &str) -> bool {
+    let index_id = index_id.trim().to_uppercase();
+    let device_id = device_id.trim().to_uppercase();
+    !index_id.is_empty()
+        && device_id
+            .strip_prefix(&index_id)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('&'))
+}
+
+/// 找出与设备匹配的驱动，每个驱动只出现一次
+pub fn match_drivers(devices: &[DeviceInfo], index: &[OnlineHwidDriver]) -> Vec<MatchedDriver> {
+    let mut matched: Vec<MatchedDriver> = Vec::new();
+    for device in devices.iter().filter(|device| is_target_device(device)) {
+        let Some(driver) = index.iter().find(|driver| {
+            device
+                .hardware_ids
+                .iter()
+                .chain(&device.compatible_ids)
+                .any(|device_id| driver.hardware_ids.iter().any(|id| id_matches(id, device_id)))
+        }) else {
+            continue;
+        };
+        let description = if device.description.is_empty() {
+            device.hardware_ids[0].clone()
+        } else {
+            device.description.clone()
+        };
+        match matched.iter_mut().find(|m| m.driver.id == driver.id) {
+            Some(existing) => existing.devices.push(description),
+            None => matched.push(MatchedDriver {
+                driver: driver.clone(),
+                devices: vec![description],
+            }),
+        }
+    }
+    matched
+}
+
+/// 枚举本机网卡和存储控制器
+pub fn detect_devices() -> Result<Vec<DeviceInfo>> {
+    let devices = crate::core::driver::DriverManager::new()?.enumerate_devices()?;
+    Ok(devices.into_iter().filter(is_target_device).collect())
+}
+
+/// 下载驱动并解压到 `root\{id}`（替换旧版本）
+pub fn stage_driver(driver: &OnlineHwidDriver, root: &Path) -> Result<()> {
+    if !is_valid_pack_id(&driver.id) {
+        bail!("驱动ID无效: {}", driver.id);
+    }
+    if driver.download_url.is_empty() {
+        bail!("驱动 {} 没有下载地址", driver.name);
+    }
+    log::info!("[HWID DRIVERS] 下载 {}: {}", driver.id, driver.download_url);
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(std::time::Duration::from_secs(600))
+        .build()
+        .context("创建 HTTP 客户端失败")?;
+    let response = client
+        .get(&driver.download_url)
+        .send()
+        .context("下载驱动失败")?;
+    if !response.status().is_success() {
+        bail!("下载驱动失败，服务器返回: {}", response.status());
+    }
+    let bytes = response.bytes().context("读取驱动内容失败")?;
+    verify_sha256(&bytes, driver.sha256.as_deref()).with_context(|| format!("驱动 {} 校验失败", driver.name))?;
+
+    std::fs::create_dir_all(root).context("创建驱动目录失败")?;
+    let archive_path = root.join(format!("{}.download", driver.id));
+    let staging = root.join(format!("{}.new", driver.id));
+    let target = root.join(&driver.id);
+    std::fs::write(&archive_path, &bytes).context("保存驱动失败")?;
+
+    let result = (|| -> Result<()> {
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging).context("清理临时目录失败")?;
+        }
+        let files = crate::utils::archive::extract(&archive_path, &staging, |_| {}).context("解压驱动失败")?;
+        if !contains_inf(&staging) {
+            bail!("驱动包中没有找到 INF 文件（共 {} 个文件）", files.len());
+        }
+        if target.exists() {
+            std::fs::remove_dir_all(&target).context("删除旧版本驱动失败")?;
+        }
+        std::fs::rename(&staging, &target).context("保存驱动失败")?;
+        Ok(())
+    })();
+
+    let _ = std::fs::remove_file(&archive_path);
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+/// 检测本机设备，下载匹配的驱动到 `drivers_dir\hwid`
+///
+/// 单个驱动下载失败不影响其他驱动，失败原因记录在结果中
+pub fn stage_matched(
+    index: &[OnlineHwidDriver],
+    drivers_dir: &Path,
+    mut on_progress: impl FnMut(usize, usize, &str),
+) -> Result<StageReport> {
+    let devices = detect_devices()?;
+    for device in &devices {
+        log::info!(
+            "[HWID DRIVERS] 设备: {} ({}) {:?}",
+            device.description,
+            device.device_class,
+            device.hardware_ids
+        );
+    }
+
+    let matched = match_drivers(&devices, index);
+    let root = drivers_dir.join(HWID_DRIVERS_DIR);
+    let mut report = StageReport::default();
+    for (i, item) in matched.iter().enumerate() {
+        on_progress(i, matched.len(), &item.driver.name);
+        log::info!("[HWID DRIVERS] {} 匹配设备: {:?}", item.driver.name, item.devices);
+        match stage_driver(&item.driver, &root) {
+            Ok(()) => report.staged.push(item.driver.name.clone()),
+            Err(e) => {
+                log::warn!("[HWID DRIVERS] {} 下载失败: {:#}", item.driver.name, e);
+                report.failed.push((item.driver.name.clone(), format!("{:#}", e)));
+            }
+        }
+    }
+    on_progress(matched.len(), matched.len(), "");
+    Ok(report)
+}
+
+fn contains_inf(dir: &Path) -> bool {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .any(|e| {
+            e.path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("inf"))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(class: &str, hardware_ids: &[&str], compatible_ids: &[&str]) -> DeviceInfo {
+        DeviceInfo {
+            description: format!("{} device", class),
+            device_class: class.to_string(),
+            hardware_ids: hardware_ids.iter().map(|s| s.to_string()).collect(),
+            compatible_ids: compatible_ids.iter().map(|s| s.to_string()).collect(),
+            has_driver: !class.is_empty(),
+        }
+    }
+
+    fn driver(id: &str, hardware_ids: &[&str]) -> OnlineHwidDriver {
+        OnlineHwidDriver {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: "1.0".to_string(),
+            hardware_ids: hardware_ids.iter().map(|s| s.to_string()).collect(),
+            download_url: format!("https://example.com/{}.cab", id),
+            sha256: None,
+            file_size: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_is_target_device() {
+        assert!(is_target_device(&device("Net", &["PCI\\VEN_8086&DEV_15F3&SUBSYS_00008086&REV_03"], &[])));
+        assert!(is_target_device(&device("SCSIAdapter", &["PCI\\VEN_8086&DEV_9A0B"], &[])));
+        // 未安装驱动的网卡按 PCI 类代码识别
+        assert!(is_target_device(&device("", &["PCI\\VEN_10EC&DEV_8125&REV_05"], &["PCI\\VEN_10EC&CC_020000", "PCI\\CC_0200"])));
+        assert!(is_target_device(&device("", &["USB\\VID_0B95&PID_1790"], &["USB\\Class_02&SubClass_06&Prot_00"])));
+        // 显卡、软件设备和 ACPI 设备不处理
+        assert!(!is_target_device(&device("Display", &["PCI\\VEN_10DE&DEV_2684"], &["PCI\\CC_0300"])));
+        assert!(!is_target_device(&device("Net", &["ROOT\\KDNIC"], &[])));
+        assert!(!is_target_device(&device("", &[], &[])));
+    }
+
+    #[test]
+    fn test_id_matches() {
+        let device_id = "PCI\\VEN_8086&DEV_15F3&SUBSYS_00008086&REV_03";
+        assert!(id_matches("PCI\\VEN_8086&DEV_15F3", device_id));
+        assert!(id_matches("pci\\ven_8086&dev_15f3&subsys_00008086&rev_03", device_id));
+        assert!(!id_matches("PCI\\VEN_8086&DEV_15F", device_id));
+        assert!(!id_matches("PCI\\VEN_8086&DEV_15F2", device_id));
+        assert!(!id_matches("", device_id));
+    }
+
+    #[test]
+    fn test_match_drivers() {
+        let devices = vec![
+            device("Net", &["PCI\\VEN_8086&DEV_15F3&SUBSYS_00008086&REV_03"], &[]),
+            device("Net", &["PCI\\VEN_8086&DEV_125C&REV_04"], &[]),
+            device("", &["PCI\\VEN_10EC&DEV_8125&REV_05"], &["PCI\\CC_0200"]),
+            device("Display", &["PCI\\VEN_10DE&DEV_2684"], &[]),
+        ];
+        let index = vec![
+            driver("intel_i225", &["PCI\\VEN_8086&DEV_15F3", "PCI\\VEN_8086&DEV_125C"]),
+            driver("rtl8125", &["PCI\\VEN_10EC&DEV_8125"]),
+            driver("nvidia", &["PCI\\VEN_10DE&DEV_2684"]),
+        ];
+        let matched = match_drivers(&devices, &index);
+        assert_eq!(matched.len(), 2);
+        assert_eq!(matched[0].driver.id, "intel_i225");
+        assert_eq!(matched[0].devices.len(), 2);
+        assert_eq!(matched[1].driver.id, "rtl8125");
+        assert!(match_drivers(&devices, &[]).is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+//! 镜像与本机兼容性检查
+//!
+//! 安装前读取所选分卷的版本、版本号、架构和安装类型（优先通过 wimlib 读取 XML 元数据，
+//! 失败时回退到 `dism /Get-WimInfo`），与本机 CPU 架构、固件模式、TPM 和安全启动状态比对，
+//! 提示 ARM64/32 位镜像装到 x64 UEFI 电脑、Windows 11 装到不满足要求的电脑等情况。
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::core::dism_cmd::DismCmd;
+use crate::core::image_library::architecture_name;
+use crate::core::wimlib::Wimlib;
+
+/// Windows 11 的最低版本号
+const WIN11_MIN_BUILD: u32 = 22000;
+
+/// 分卷的系统信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageDetails {
+    pub name: String,
+    /// 版本标识，如 Professional
+    pub edition_id: String,
+    /// 安装类型，如 Client、Server
+    pub installation_type: String,
+    pub major: u32,
+    pub minor: u32,
+    pub build: u32,
+    pub sp_build: u32,
+    /// 架构：x86、x64、ARM64
+    pub architecture: String,
+}
+
+impl ImageDetails {
+    /// 完整版本号，如 10.0.22631.2861
+    pub fn version(&self) -> String {
+        format!("{}.{}.{}.{}", self.major, self.minor, self.build, self.sp_build)
+    }
+
+    /// 是否为 Windows 11 客户端
+    pub fn is_win11(&self) -> bool {
+        self.major == 10 && self.build >= WIN11_MIN_BUILD && !self.installation_type.eq_ignore_ascii_case("Server")
+    }
+
+    /// 一行摘要，用于安装页显示
+    pub fn summary(&self) -> String {
+        [self.name.as_str(), self.edition_id.as_str(), &self.version(), self.architecture.as_str()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+/// 本机信息（来自 `system_info` 和 `hardware_info`）
+#[derive(Debug, Clone, Default)]
+pub struct HostInfo {
+    /// CPU 架构：x86、x64、ARM64，未知时为空
+    pub architecture: String,
+    /// 已启用的 TPM 版本（如 2.0），没有 TPM 时为 None
+    pub tpm_version: Option<String>,
+    pub secure_boot: bool,
+}
+
+/// 兼容性问题级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompatLevel {
+    /// 装好后无法启动
+    Blocking,
+    /// 可以安装，但可能影响使用或更新
+    Warning,
+}
+
+/// 兼容性问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatIssue {
+    pub level: CompatLevel,
+    pub message: String,
+}
+
+impl CompatIssue {
+    fn blocking(message: String) -> Self {
+        Self { level: CompatLevel::Blocking, message }
+    }
+
+    fn warning(message: String) -> Self {
+        Self { level: CompatLevel::Warning, message }
+    }
+}
+
+/// 读取分卷信息：优先使用 wimlib，失败时调用 DISM
+pub fn read_details(image_file: &str, index: u32) -> Result<ImageDetails> {
+    match read_details_wimlib(image_file, index) {
+        Ok(details) => Ok(details),
+        Err(e) => {
+            log::warn!("[IMAGE COMPAT] wimlib 读取分卷信息失败: {:#}，改用 DISM", e);
+            let output = DismCmd::new()?.get_wim_info(image_file, index)?;
+            parse_dism_wim_info(&output)
+        }
+    }
+}
+
+fn read_details_wimlib(image_file: &str, index: u32) -> Result<ImageDetails> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    let wim = wimlib
+        .open_image_file(image_file)
+        .map_err(|e| anyhow!("打开镜像失败: {}", e.detailed()))?;
+    if index == 0 || index as i32 > wim.get_image_count() {
+        bail!("分卷 {} 不存在", index);
+    }
+
+    let index = index as i32;
+    let property = |name: &str| wim.get_image_property(index, name).unwrap_or_default().trim().to_string();
+    let number = |name: &str| property(name).parse().unwrap_or(0);
+    let (name, _) = wim.get_image_info(index);
+    Ok(ImageDetails {
+        name,
+        edition_id: property("WINDOWS/EDITIONID"),
+        installation_type: property("WINDOWS/INSTALLATIONTYPE"),
+        major: number("WINDOWS/VERSION/MAJOR"),
+        minor: number("WINDOWS/VERSION/MINOR"),
+        build: number("WINDOWS/VERSION/BUILD"),
+        sp_build: number("WINDOWS/VERSION/SPBUILD"),
+        architecture: architecture_name(&property("WINDOWS/ARCH")),
+    })
+}
+
+/// 解析 `dism /English /Get-WimInfo /Index:n` 的输出
+pub fn parse_dism_wim_info(output: &str) -> Result<ImageDetails> {
+    let mut details = ImageDetails::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Name" => details.name = value.to_string(),
+            "Edition" => details.edition_id = value.to_string(),
+            "Installation" => details.installation_type = value.to_string(),
+            "Architecture" => details.architecture = value.to_string(),
+            "ServicePack Build" => details.sp_build = value.parse().unwrap_or(0),
+            "Version" => {
+                let mut parts = value.split('.').map(|part| part.parse().unwrap_or(0));
+                details.major = parts.next().unwrap_or(0);
+                details.minor = parts.next().unwrap_or(0);
+                details.build = parts.next().unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    if details.major == 0 && details.architecture.is_empty() {
+        bail!("DISM 输出中没有分卷信息");
+    }
+    Ok(details)
+}
+
+/// 检查分卷能否在本机上正常运行；`uefi_target` 表示将以 UEFI 方式引导
+pub fn check(details: &ImageDetails, host: &HostInfo, uefi_target: bool) -> Vec<CompatIssue> {
+    let mut issues = Vec::new();
+    let image_arch = details.architecture.as_str();
+    let host_arch = host.architecture.as_str();
+
+    if !image_arch.is_empty() && !host_arch.is_empty() && !image_arch.eq_ignore_ascii_case(host_arch) {
+        match (image_arch, host_arch) {
+            ("x86", "x64") if uefi_target => issues.push(CompatIssue::blocking(
+                "32 位系统无法从 64 位 UEFI 固件启动，请改用 x64 镜像或以 Legacy 方式引导".to_string(),
+            )),
+            ("x86", "x64") => issues.push(CompatIssue::warning(
+                "这是 32 位系统镜像，本机为 x64 处理器，32 位系统最多只能使用约 4GB 内存".to_string(),
+            )),
+            _ => issues.push(CompatIssue::blocking(format!(
+                "镜像架构为 {}，本机处理器为 {}，安装后无法启动",
+                image_arch, host_arch
+            ))),
+        }
+    }
+
+    if details.is_win11() {
+        if !uefi_target {
+            issues.push(CompatIssue::warning(
+                "Windows 11 要求 UEFI 引导，以 Legacy 方式安装后可能无法接收功能更新".to_string(),
+            ));
+        }
+        let tpm2 = host
+            .tpm_version
+            .as_deref()
+            .is_some_and(|version| version.trim().starts_with('2'));
+        if !tpm2 {
+            issues.push(CompatIssue::warning(match host.tpm_version {
+                Some(ref version) => format!("本机 TPM 版本为 {}，Windows 11 要求 TPM 2.0", version),
+                None => "未检测到已启用的 TPM，Windows 11 要求 TPM 2.0".to_string(),
+            }));
+        }
+        if !host.secure_boot {
+            issues.push(CompatIssue::warning(
+                "本机未开启安全启动，Windows 11 要求支持安全启动".to_string(),
+            ));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(architecture: &str, build: u32) -> ImageDetails {
+        ImageDetails {
+            name: "Windows".to_string(),
+            installation_type: "Client".to_string(),
+            major: 10,
+            build,
+            architecture: architecture.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn host(architecture: &str, tpm: Option<&str>, secure_boot: bool) -> HostInfo {
+        HostInfo {
+            architecture: architecture.to_string(),
+            tpm_version: tpm.map(str::to_string),
+            secure_boot,
+        }
+    }
+
+    #[test]
+    fn test_parse_dism_wim_info() {
+        let output = "Deployment Image Servicing and Management tool\nVersion: 10.0.22621.1\n\n\
+            Details for image : D:\\sources\\install.wim\n\nIndex : 6\nName : Windows 11 Pro\n\
+            Architecture : x64\nHal : <undefined>\nVersion : 10.0.22631\nServicePack Build : 2861\n\
+            ServicePack Level : 0\nEdition : Professional\nInstallation : Client\n\n\
+            The operation completed successfully.\n";
+        let details = parse_dism_wim_info(output).unwrap();
+        assert_eq!(details.name, "Windows 11 Pro");
+        assert_eq!(details.edition_id, "Professional");
+        assert_eq!(details.version(), "10.0.22631.2861");
+        assert_eq!(details.architecture, "x64");
+        assert!(details.is_win11());
+        assert!(parse_dism_wim_info("Error: 87").is_err());
+    }
+
+    #[test]
+    fn test_check_architecture() {
+        let x64_host = host("x64", Some("2.0"), true);
+        assert!(check(&details("x64", 19045), &x64_host, true).is_empty());
+
+        let issues = check(&details("ARM64", 19045), &x64_host, true);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].level, CompatLevel::Blocking);
+
+        assert_eq!(check(&details("x86", 19045), &x64_host, true)[0].level, CompatLevel::Blocking);
+        assert_eq!(check(&details("x86", 19045), &x64_host, false)[0].level, CompatLevel::Warning);
+        assert_eq!(check(&details("x64", 19045), &host("ARM64", None, true), true)[0].level, CompatLevel::Blocking);
+        // 架构未知时不提示
+        assert!(check(&details("x64", 19045), &host("", None, true), true).is_empty());
+    }
+
+    #[test]
+    fn test_check_win11_requirements() {
+        let win11 = details("x64", 22631);
+        assert!(check(&win11, &host("x64", Some("2.0"), true), true).is_empty());
+        assert_eq!(check(&win11, &host("x64", Some("1.2"), true), true).len(), 1);
+        assert_eq!(check(&win11, &host("x64", None, false), false).len(), 3);
+
+        // Windows Server 2025 版本号高于 22000，但不受 Windows 11 要求限制
+        let mut server = details("x64", 26100);
+        server.installation_type = "Server".to_string();
+        assert!(check(&server, &host("x64", None, false), true).is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+//! 镜像格式转换
+//!
+//! 把 ESD 转换为 WIM，或在 LZX、XPRESS 与 LZMS 固实压缩之间重新压缩。
+//! 通过 wimlib（wimlib_export_image + 写入标志）或 dism.exe /Export-Image 导出所选分卷，
+//! 开始前按分卷的展开大小估算输出文件大小，并检查目标分区的剩余空间。
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+
+use crate::core::disk::{drive_letter_of, DiskManager};
+use crate::core::dism::DismProgress;
+use crate::core::dism_cmd::{DismCmd, DismCmdProgress};
+use crate::core::wimlib::{ExportCompression, Wimlib, WimlibProgress};
+
+/// 输出文件之外预留的空间（临时文件、元数据）
+const SPACE_MARGIN_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 目标格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConvertFormat {
+    /// WIM，LZX 压缩
+    #[default]
+    WimLzx,
+    /// WIM，XPRESS 压缩
+    WimXpress,
+    /// ESD，LZMS 固实压缩
+    Esd,
+}
+
+impl ConvertFormat {
+    /// 全部格式（按显示顺序）
+    pub const ALL: [ConvertFormat; 3] = [ConvertFormat::WimLzx, ConvertFormat::WimXpress, ConvertFormat::Esd];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConvertFormat::WimLzx => "WIM（LZX 标准压缩）",
+            ConvertFormat::WimXpress => "WIM（XPRESS 快速压缩）",
+            ConvertFormat::Esd => "ESD（LZMS 固实压缩）",
+        }
+    }
+
+    /// 输出文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConvertFormat::WimLzx | ConvertFormat::WimXpress => "wim",
+            ConvertFormat::Esd => "esd",
+        }
+    }
+
+    /// dism /Compress 参数
+    fn dism_compress(&self) -> &'static str {
+        match self {
+            ConvertFormat::WimLzx => "max",
+            ConvertFormat::WimXpress => "fast",
+            ConvertFormat::Esd => "recovery",
+        }
+    }
+
+    fn wimlib_compression(&self) -> ExportCompression {
+        match self {
+            ConvertFormat::WimLzx => ExportCompression::Lzx,
+            ConvertFormat::WimXpress => ExportCompression::Xpress,
+            ConvertFormat::Esd => ExportCompression::LzmsSolid,
+        }
+    }
+
+    /// 输出大小占展开大小的百分比（按系统镜像的经验值，偏保守）
+    fn estimated_ratio_percent(&self) -> u64 {
+        match self {
+            ConvertFormat::WimLzx => 45,
+            ConvertFormat::WimXpress => 55,
+            ConvertFormat::Esd => 35,
+        }
+    }
+}
+
+/// 转换后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertBackend {
+    Wimlib,
+    Dism,
+}
+
+impl ConvertBackend {
+    /// 全部后端（按显示顺序）
+    pub const ALL: [ConvertBackend; 2] = [ConvertBackend::Wimlib, ConvertBackend::Dism];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConvertBackend::Wimlib => "wimlib",
+            ConvertBackend::Dism => "DISM",
+        }
+    }
+
+    /// 默认后端：wimlib 支持导出时使用 wimlib，否则使用 DISM
+    pub fn detect() -> Self {
+        match Wimlib::new() {
+            Ok(wimlib) if wimlib.supports_export() => ConvertBackend::Wimlib,
+            _ => ConvertBackend::Dism,
+        }
+    }
+}
+
+/// 转换请求
+#[derive(Debug, Clone)]
+pub struct ConvertRequest {
+    /// 源镜像（WIM/ESD/SWM）
+    pub source: String,
+    /// 要导出的分卷索引（从 1 开始）
+    pub indexes: Vec<u32>,
+    /// 输出文件
+    pub destination: String,
+    pub format: ConvertFormat,
+    pub backend: ConvertBackend,
+    /// 所选分卷的展开大小之和（字节），用于估算输出大小
+    pub expanded_bytes: u64,
+}
+
+/// 估算输出文件大小（字节）
+///
+/// 多个分卷共享的文件在输出中只存储一次，多分卷时实际大小通常更小。
+pub fn estimate_output_bytes(expanded_bytes: u64, format: ConvertFormat) -> u64 {
+    expanded_bytes / 100 * format.estimated_ratio_percent()
+}
+
+/// 检查剩余空间是否足以容纳输出文件（无法获取剩余空间时不阻止）
+pub fn check_free_space(free_bytes: Option<u64>, output_bytes: u64) -> Result<()> {
+    let Some(free_bytes) = free_bytes else {
+        return Ok(());
+    };
+    let required = output_bytes + SPACE_MARGIN_BYTES;
+    if free_bytes < required {
+        bail!(
+            "目标分区剩余空间不足：预计需要 {:.1} GB，可用 {:.1} GB",
+            gigabytes(required),
+            gigabytes(free_bytes)
+        );
+    }
+    Ok(())
+}
+
+/// 默认输出路径：目标分区根目录下与源镜像同名，扩展名按目标格式
+///
+/// 与源镜像路径相同时在文件名后加 `_converted`。
+pub fn default_output_path(source: &str, partition: &str, format: ConvertFormat) -> String {
+    let file_name = source.rsplit(['\\', '/']).next().unwrap_or(source);
+    let stem = match file_name.rfind('.') {
+        Some(pos) if pos > 0 => &file_name[..pos],
+        _ => file_name,
+    };
+    let stem = if stem.is_empty() { "install" } else { stem };
+    let dir = partition.trim_end_matches('\\');
+    let path = format!("{}\\{}.{}", dir, stem, format.extension());
+    if path.eq_ignore_ascii_case(source) {
+        format!("{}\\{}_converted.{}", dir, stem, format.extension())
+    } else {
+        path
+    }
+}
+
+fn gigabytes(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0 / 1024.0
+}
+
+/// 多分卷依次导出时的总进度
+fn overall_percent(done: usize, count: usize, current: u8) -> u8 {
+    if count == 0 {
+        return 100;
+    }
+    ((done * 100 + current.min(100) as usize) / count) as u8
+}
+
+/// 执行转换，进度通过 progress_tx 发送；失败时删除不完整的输出文件
+pub fn convert_image(request: &ConvertRequest, progress_tx: Sender<DismProgress>) -> Result<()> {
+    if request.indexes.is_empty() {
+        bail!("请至少选择一个分卷");
+    }
+    if request.destination.eq_ignore_ascii_case(&request.source) {
+        bail!("输出文件不能与源镜像相同");
+    }
+    if Path::new(&request.destination).exists() {
+        bail!("输出文件已存在: {}", request.destination);
+    }
+
+    let free_bytes = request
+        .destination
+        .get(..2)
+        .and_then(drive_letter_of)
+        .and_then(|letter| DiskManager::get_free_space_bytes(&format!("{}:", letter)));
+    check_free_space(free_bytes, estimate_output_bytes(request.expanded_bytes, request.format))?;
+
+    if let Some(parent) = Path::new(&request.destination).parent() {
+        std::fs::create_dir_all(parent).context("创建输出目录失败")?;
+    }
+
+    log::info!(
+        "[CONVERT] {} {:?} -> {}（{}，{}）",
+        request.source,
+        request.indexes,
+        request.destination,
+        request.format.label(),
+        request.backend.label()
+    );
+    let result = match request.backend {
+        ConvertBackend::Wimlib => export_with_wimlib(request, progress_tx),
+        ConvertBackend::Dism => export_with_dism(request, progress_tx),
+    };
+    if result.is_err() {
+        let _ = std::fs::remove_file(&request.destination);
+    }
+    result
+}
+
+fn export_with_wimlib(request: &ConvertRequest, progress_tx: Sender<DismProgress>) -> Result<()> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    if !wimlib.supports_export() {
+        bail!("当前 wimlib 不支持导出镜像，请改用 DISM");
+    }
+
+    let (wimlib_tx, wimlib_rx) = mpsc::channel::<WimlibProgress>();
+    let forward_thread = std::thread::spawn(move || {
+        while let Ok(progress) = wimlib_rx.recv() {
+            let _ = progress_tx.send(DismProgress {
+                percentage: progress.percentage,
+                status: progress.status,
+            });
+        }
+    });
+    let result = wimlib.export_images(
+        &request.source,
+        &request.indexes,
+        &request.destination,
+        request.format.wimlib_compression(),
+        Some(wimlib_tx),
+    );
+    let _ = forward_thread.join();
+    result.map_err(|e| anyhow!("镜像导出失败: {}", e.detailed()))
+}
+
+/// dism /Export-Image 每次导出一个分卷，依次追加到输出文件
+fn export_with_dism(request: &ConvertRequest, progress_tx: Sender<DismProgress>) -> Result<()> {
+    let dism = DismCmd::new()?;
+    let count = request.indexes.len();
+    for (done, &index) in request.indexes.iter().enumerate() {
+        let (dism_tx, dism_rx) = mpsc::channel::<DismCmdProgress>();
+        let progress_tx = progress_tx.clone();
+        let forward_thread = std::thread::spawn(move || {
+            while let Ok(progress) = dism_rx.recv() {
+                let _ = progress_tx.send(DismProgress {
+                    percentage: overall_percent(done, count, progress.percentage),
+                    status: format!("分卷 {}（{}/{}）: {}", index, done + 1, count, progress.status),
+                });
+            }
+        });
+        let result = dism.export_image(
+            &request.source,
+            index,
+            &request.destination,
+            request.format.dism_compress(),
+            Some(dism_tx),
+        );
+        let _ = forward_thread.join();
+        result.with_context(|| format!("导出分卷 {} 失败", index))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_output_path() {
+        assert_eq!(
+            default_output_path("E:\\sources\\install.esd", "D:", ConvertFormat::WimLzx),
+            "D:\\install.wim"
+        );
+        assert_eq!(
+            default_output_path("D:\\install.wim", "D:\\", ConvertFormat::WimXpress),
+            "D:\\install_converted.wim"
+        );
+        assert_eq!(default_output_path("D:\\backup.wim", "F:", ConvertFormat::Esd), "F:\\backup.esd");
+    }
+
+    #[test]
+    fn test_free_space_check() {
+        let gb = 1024 * 1024 * 1024;
+        let output = estimate_output_bytes(10 * gb, ConvertFormat::Esd);
+        assert!(output < estimate_output_bytes(10 * gb, ConvertFormat::WimLzx));
+        assert!(check_free_space(Some(10 * gb), output).is_ok());
+        assert!(check_free_space(Some(output), output).is_err());
+        assert!(check_free_space(None, output).is_ok());
+    }
+
+    #[test]
+    fn test_overall_percent() {
+        assert_eq!(overall_percent(0, 2, 50), 25);
+        assert_eq!(overall_percent(1, 2, 100), 100);
+        assert_eq!(overall_percent(0, 1, 40), 40);
+    }
+}
@@ -0,0 +1,479 @@
+//! 本地镜像库
+//!
+//! 扫描用户添加的目录（也可以是整个分区）中的 WIM/ESD/SWM/GHO/ISO 文件，读取分卷名称、版本和架构，
+//! 结果缓存到程序目录下的 image_library.json。再次扫描时，大小和修改时间都未变化的镜像直接复用缓存，
+//! 不重新打开镜像。系统安装和镜像校验页通过镜像库选择器搜索并选取镜像。
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::core::image_verify::ImageType;
+use crate::core::wimlib::{find_swm_parts, Wimlib};
+use crate::utils::path::get_exe_dir;
+
+/// 镜像库缓存文件名
+pub const LIBRARY_FILE: &str = "image_library.json";
+const LIBRARY_VERSION: u32 = 1;
+
+/// 扫描目录的最大深度（从添加的目录算起）
+pub const MAX_SCAN_DEPTH: usize = 4;
+
+/// 扫描时跳过的目录（不区分大小写）
+const SKIPPED_DIRS: [&str; 9] = [
+    "windows",
+    "program files",
+    "program files (x86)",
+    "programdata",
+    "$recycle.bin",
+    "system volume information",
+    "recovery",
+    "$windows.~bt",
+    "$windows.~ws",
+];
+
+/// 镜像中的一个分卷
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryVolume {
+    pub index: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 系统版本号，如 10.0.22631.2861
+    #[serde(default)]
+    pub version: String,
+    /// 架构，如 x64、ARM64
+    #[serde(default)]
+    pub architecture: String,
+    /// 展开后的大小（字节）
+    #[serde(default)]
+    pub size_bytes: u64,
+}
+
+/// 镜像库中的一个镜像文件
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryImage {
+    pub path: String,
+    /// 镜像类型（WIM、ESD、GHO 等）
+    pub kind: String,
+    pub size_bytes: u64,
+    /// 修改时间（Unix 秒），用于判断缓存是否过期
+    pub modified: u64,
+    /// 分卷信息（ISO 为其中安装镜像的分卷，GHO 为空）
+    #[serde(default)]
+    pub volumes: Vec<LibraryVolume>,
+    /// 读取分卷信息失败的原因
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl LibraryImage {
+    /// 文件名
+    pub fn file_name(&self) -> String {
+        self.path.rsplit(['\\', '/']).next().unwrap_or(&self.path).to_string()
+    }
+
+    /// 镜像文件是否仍然存在
+    pub fn exists(&self) -> bool {
+        Path::new(&self.path).is_file()
+    }
+
+    /// 是否匹配搜索关键字：按空格拆分，每个关键字都要出现在路径、类型或任一分卷的名称、版本、架构中
+    pub fn matches(&self, query: &str) -> bool {
+        let haystack = std::iter::once(self.path.as_str())
+            .chain(std::iter::once(self.kind.as_str()))
+            .chain(self.volumes.iter().flat_map(|volume| {
+                [
+                    volume.name.as_str(),
+                    volume.description.as_str(),
+                    volume.version.as_str(),
+                    volume.architecture.as_str(),
+                ]
+            }))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .to_lowercase();
+        query
+            .split_whitespace()
+            .all(|keyword| haystack.contains(&keyword.to_lowercase()))
+    }
+}
+
+/// 镜像库缓存文件结构
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageLibrary {
+    pub version: u32,
+    /// 用户添加的扫描目录
+    pub folders: Vec<String>,
+    pub images: Vec<LibraryImage>,
+    /// 上次扫描时间（Unix 秒）
+    #[serde(default)]
+    pub scanned_at: Option<i64>,
+}
+
+impl ImageLibrary {
+    fn get_library_path() -> PathBuf {
+        get_exe_dir().join(LIBRARY_FILE)
+    }
+
+    /// 加载镜像库，文件不存在或版本不匹配时返回空库
+    pub fn load() -> Self {
+        let empty = Self {
+            version: LIBRARY_VERSION,
+            ..Default::default()
+        };
+        let Ok(content) = std::fs::read_to_string(Self::get_library_path()) else {
+            return empty;
+        };
+        match serde_json::from_str::<ImageLibrary>(&content) {
+            Ok(library) if library.version == LIBRARY_VERSION => library,
+            Ok(_) => {
+                log::warn!("镜像库版本不匹配，忽略");
+                empty
+            }
+            Err(e) => {
+                log::warn!("解析镜像库失败: {}", e);
+                empty
+            }
+        }
+    }
+
+    /// 保存镜像库
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_library_path();
+        let content = serde_json::to_string_pretty(self).context("序列化镜像库失败")?;
+        std::fs::write(&path, content).with_context(|| format!("写入镜像库失败: {}", path.display()))?;
+        log::info!("镜像库已保存: {:?}, 共 {} 个镜像", path, self.images.len());
+        Ok(())
+    }
+
+    /// 添加扫描目录，目录不存在或已添加时报错
+    pub fn add_folder(&mut self, folder: &str) -> Result<()> {
+        let folder = folder.trim();
+        if !Path::new(folder).is_dir() {
+            bail!("目录不存在: {}", folder);
+        }
+        if self.folders.iter().any(|f| f.eq_ignore_ascii_case(folder)) {
+            bail!("目录已在镜像库中: {}", folder);
+        }
+        self.folders.push(folder.to_string());
+        Ok(())
+    }
+
+    /// 移除扫描目录及其下的镜像
+    pub fn remove_folder(&mut self, folder: &str) {
+        self.folders.retain(|f| !f.eq_ignore_ascii_case(folder));
+        let folders = self.folders.clone();
+        self.images
+            .retain(|image| folders.iter().any(|folder| is_under(&image.path, folder)));
+    }
+
+    /// 搜索镜像，空关键字返回全部
+    pub fn search(&self, query: &str) -> Vec<&LibraryImage> {
+        self.images.iter().filter(|image| image.matches(query)).collect()
+    }
+
+    /// 按路径查找镜像（不区分大小写）
+    pub fn find(&self, path: &str) -> Option<&LibraryImage> {
+        self.images.iter().find(|image| image.path.eq_ignore_ascii_case(path))
+    }
+}
+
+/// 将 wimlib 的 WINDOWS/ARCH 代码转换为架构名称
+pub fn architecture_name(code: &str) -> String {
+    match code.trim() {
+        "0" => "x86".to_string(),
+        "5" => "ARM".to_string(),
+        "9" => "x64".to_string(),
+        "12" => "ARM64".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// 是否为镜像库收录的文件：SWM 和 GHO 分卷只收录第一个文件（install.swm，不收录 install2.swm 和 .ghs）
+///
+/// 主分卷的文件名本身可能以数字结尾（如 win10.swm），按同目录下实际存在的分卷判断。
+pub fn is_image_file(path: &Path) -> bool {
+    let Some(extension) = path.extension().map(|e| e.to_string_lossy().to_lowercase()) else {
+        return false;
+    };
+    match extension.as_str() {
+        "wim" | "esd" | "iso" | "gho" => true,
+        "swm" => find_swm_parts(&path.to_string_lossy()).first().is_some_and(|first| first == path),
+        _ => false,
+    }
+}
+
+/// 路径是否位于目录下（不区分大小写）
+fn is_under(path: &str, folder: &str) -> bool {
+    let path = path.to_lowercase().replace('/', "\\");
+    let folder = folder.to_lowercase().replace('/', "\\");
+    let folder = folder.trim_end_matches('\\');
+    path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('\\'))
+}
+
+/// 在目录中查找镜像文件，跳过系统目录和无法访问的目录
+pub fn find_image_files(folder: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(folder)
+        .max_depth(MAX_SCAN_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !entry.file_type().is_dir()
+                || !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().to_lowercase().as_str())
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_image_file(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// 扫描所有目录，返回新的镜像列表
+///
+/// 大小和修改时间未变化的镜像复用 `cached` 中的信息；`on_progress(已处理数, 总数, 当前文件)`
+pub fn scan(
+    folders: &[String],
+    cached: &[LibraryImage],
+    mut on_progress: impl FnMut(usize, usize, &str),
+) -> Vec<LibraryImage> {
+    // 目录可能互相包含（如 D:\ 和 D:\Images），按小写路径去重
+    let files: BTreeMap<String, PathBuf> = folders
+        .iter()
+        .flat_map(|folder| find_image_files(Path::new(folder)))
+        .map(|path| (path.to_string_lossy().to_lowercase(), path))
+        .collect();
+    let total = files.len();
+    log::info!("[IMAGE LIBRARY] 扫描 {} 个目录，找到 {} 个镜像文件", folders.len(), total);
+
+    let mut images = Vec::with_capacity(total);
+    for (done, path) in files.into_values().enumerate() {
+        let path_str = path.to_string_lossy().to_string();
+        on_progress(done, total, &path_str);
+
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        let size_bytes = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let cached = cached.iter().find(|image| {
+            image.path.eq_ignore_ascii_case(&path_str) && image.size_bytes == size_bytes && image.modified == modified
+        });
+        images.push(match cached {
+            Some(image) => image.clone(),
+            None => read_image(path_str, size_bytes, modified),
+        });
+    }
+    on_progress(total, total, "");
+    images
+}
+
+/// 读取镜像信息，WIM 系列通过 wimlib 读取分卷
+fn read_image(path: String, size_bytes: u64, modified: u64) -> LibraryImage {
+    let kind = ImageType::from_extension(&path);
+    let mut image = LibraryImage {
+        kind: kind.to_string(),
+        size_bytes,
+        modified,
+        ..Default::default()
+    };
+    let volumes = match kind {
+        ImageType::Iso => Some(read_iso_volumes(&path)),
+        _ if kind.is_wim_family() => Some(read_volumes(&path)),
+        _ => None,
+    };
+    match volumes {
+        Some(Ok(volumes)) => image.volumes = volumes,
+        Some(Err(e)) => {
+            log::warn!("[IMAGE LIBRARY] 读取镜像信息失败 {}: {:#}", path, e);
+            image.error = Some(format!("{:#}", e));
+        }
+        None => {}
+    }
+    image.path = path;
+    image
+}
+
+/// 临时挂载 ISO 读取其中安装镜像的分卷信息，不含安装镜像的 ISO（如 PE）返回空列表
+///
+/// 只卸载本次挂载的 ISO，不影响安装页面已挂载的镜像。
+#[cfg(windows)]
+fn read_iso_volumes(path: &str) -> Result<Vec<LibraryVolume>> {
+    use crate::core::iso::IsoMounter;
+
+    let letter = IsoMounter::mount_iso_winapi(path).context("挂载 ISO 失败")?;
+    let drive = format!("{}:", letter);
+    let result = match IsoMounter::find_install_image_in_drive(&drive) {
+        Some(image) => read_volumes(&image),
+        None => Ok(Vec::new()),
+    };
+    if let Err(e) = IsoMounter::unmount_iso_by_path(path) {
+        log::warn!("[IMAGE LIBRARY] 卸载 ISO 失败 {}: {:#}", path, e);
+    }
+    result
+}
+
+#[cfg(not(windows))]
+fn read_iso_volumes(_path: &str) -> Result<Vec<LibraryVolume>> {
+    bail!("ISO 挂载仅支持 Windows 系统")
+}
+
+/// 读取 WIM/ESD/SWM 的分卷信息
+fn read_volumes(path: &str) -> Result<Vec<LibraryVolume>> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    let wim = wimlib
+        .open_image_file(path)
+        .map_err(|e| anyhow!("打开镜像失败: {}", e.detailed()))?;
+    let count = wim.get_image_count();
+    if count < 0 {
+        bail!("无法读取镜像信息");
+    }
+
+    Ok((1..=count)
+        .map(|index| {
+            let (name, description) = wim.get_image_info(index);
+            let property = |name: &str| wim.get_image_property(index, name).unwrap_or_default();
+            let version = ["MAJOR", "MINOR", "BUILD", "SPBUILD"]
+                .iter()
+                .map(|part| property(&format!("WINDOWS/VERSION/{}", part)))
+                .take_while(|part| !part.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(".");
+            LibraryVolume {
+                index: index as u32,
+                name,
+                description,
+                version,
+                architecture: architecture_name(&property("WINDOWS/ARCH")),
+                size_bytes: property("TOTALBYTES").trim().parse().unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(path: &str, volumes: &[(&str, &str, &str)]) -> LibraryImage {
+        LibraryImage {
+            path: path.to_string(),
+            kind: "WIM".to_string(),
+            volumes: volumes
+                .iter()
+                .enumerate()
+                .map(|(i, (name, version, architecture))| LibraryVolume {
+                    index: i as u32 + 1,
+                    name: name.to_string(),
+                    version: version.to_string(),
+                    architecture: architecture.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_image_file() {
+        assert!(is_image_file(Path::new("D:\\Images\\install.WIM")));
+        assert!(is_image_file(Path::new("backup.gho")));
+        assert!(!is_image_file(Path::new("backup001.ghs")));
+        assert!(!is_image_file(Path::new("readme.txt")));
+        assert!(!is_image_file(Path::new("wim")));
+        assert_eq!(architecture_name("9"), "x64");
+        assert_eq!(architecture_name("12"), "ARM64");
+        assert_eq!(architecture_name(""), "");
+    }
+
+    #[test]
+    fn test_is_image_file_swm() {
+        let dir = std::env::temp_dir().join(format!("letrecovery_swm_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["install.swm", "install2.swm", "win10.swm", "win102.swm", "win11.swm"] {
+            std::fs::write(dir.join(name), b"swm").unwrap();
+        }
+        assert!(is_image_file(&dir.join("install.swm")));
+        assert!(!is_image_file(&dir.join("install2.swm")));
+        // 主分卷文件名以数字结尾
+        assert!(is_image_file(&dir.join("win10.swm")));
+        assert!(!is_image_file(&dir.join("win102.swm")));
+        assert!(is_image_file(&dir.join("win11.swm")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_search() {
+        let library = ImageLibrary {
+            images: vec![
+                image("D:\\Images\\win11.wim", &[("Windows 11 专业版", "10.0.22631", "x64")]),
+                image("D:\\Images\\win10_arm.esd", &[("Windows 10 家庭版", "10.0.19045", "ARM64")]),
+                image("E:\\Ghost\\xp.gho", &[]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(library.search("").len(), 3);
+        assert_eq!(library.search("  ").len(), 3);
+        assert_eq!(library.search("专业版 X64")[0].file_name(), "win11.wim");
+        assert_eq!(library.search("arm64").len(), 1);
+        assert_eq!(library.search("22631 arm64").len(), 0);
+        assert_eq!(library.search("ghost").len(), 1);
+        assert!(library.find("d:\\images\\WIN11.wim").is_some());
+    }
+
+    #[test]
+    fn test_remove_folder() {
+        let mut library = ImageLibrary {
+            folders: vec!["D:\\Images".to_string(), "E:\\".to_string()],
+            images: vec![
+                image("D:\\Images\\win11.wim", &[]),
+                image("D:\\Images2\\win10.wim", &[]),
+                image("E:\\xp.gho", &[]),
+            ],
+            ..Default::default()
+        };
+        library.remove_folder("d:\\images");
+        assert_eq!(library.folders, vec!["E:\\"]);
+        assert_eq!(library.images.len(), 1);
+        assert_eq!(library.images[0].path, "E:\\xp.gho");
+    }
+
+    #[test]
+    fn test_scan_reuses_cache() {
+        let dir = std::env::temp_dir().join(format!("letrecovery_library_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::create_dir_all(dir.join("Windows")).unwrap();
+        std::fs::write(dir.join("a.iso"), b"iso").unwrap();
+        std::fs::write(dir.join("sub").join("b.gho"), b"ghost").unwrap();
+        std::fs::write(dir.join("sub").join("b001.ghs"), b"span").unwrap();
+        std::fs::write(dir.join("Windows").join("c.iso"), b"skipped").unwrap();
+
+        // 重叠的目录不会产生重复条目
+        let folders = vec![dir.to_string_lossy().to_string(), dir.join("sub").to_string_lossy().to_string()];
+        let mut calls = 0;
+        let images = scan(&folders, &[], |_, _, _| calls += 1);
+        assert_eq!(images.len(), 2);
+        assert_eq!(calls, 3);
+        assert!(images.iter().all(|image| image.volumes.is_empty()));
+        let gho = images.iter().find(|image| image.path.ends_with("b.gho")).unwrap();
+        assert_eq!(gho.size_bytes, 5);
+        assert!(gho.error.is_none());
+
+        // 大小和修改时间未变化时直接使用缓存内容
+        let mut cached = images.clone();
+        cached[0].volumes.push(LibraryVolume::default());
+        let rescanned = scan(&folders, &cached, |_, _, _| {});
+        assert_eq!(rescanned, cached);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
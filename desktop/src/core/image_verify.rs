@@ -1,7 +1,7 @@
 //! 镜像校验模块
 //!
 //! 提供对各种系统镜像格式的完整性校验功能：
-//! - WIM/ESD: 使用 wimlib 进行完整性校验（支持 Integrity Table 验证）
+//! - WIM/ESD: 使用 wimlib 进行完整性校验（支持 Integrity Table 验证），多镜像文件校验失败时逐个定位损坏的镜像
 //! - SWM: 加载所有分卷并验证完整性
 //! - GHO: 验证文件头和基本结构
 //! - ISO: 挂载后检查内部镜像文件
@@ -15,14 +15,14 @@ use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{self, Sender};
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 use crate::core::iso::IsoMounter;
 use crate::core::wimgapi::{Wimgapi, WIM_COMPRESS_NONE, WIM_GENERIC_READ, WIM_OPEN_EXISTING, WIM_REFERENCE_APPEND};
-use crate::core::wimlib::Wimlib;
+use crate::core::wimlib::{find_swm_parts, WimHandle, Wimlib, WimlibProgress};
 
 // ============================================================================
 // 类型定义
@@ -63,8 +63,64 @@ impl ImageType {
     pub fn is_wim_family(&self) -> bool {
         matches!(self, Self::Wim | Self::Esd | Self::Swm)
     }
+
+    /// 根据文件头判断镜像类型
+    ///
+    /// `header` 需包含文件开头至少 `SNIFF_HEADER_LEN` 字节才能识别 ISO
+    pub fn sniff_header(header: &[u8]) -> Self {
+        if header.starts_with(b"MSWIM\0\0\0") && header.len() >= 44 {
+            let flags = u32::from_le_bytes([header[16], header[17], header[18], header[19]]);
+            let total_parts = u16::from_le_bytes([header[42], header[43]]);
+            return if total_parts > 1 {
+                Self::Swm
+            } else if flags & WIM_HEADER_FLAG_COMPRESS_LZMS != 0 {
+                Self::Esd
+            } else {
+                Self::Wim
+            };
+        }
+
+        if header.starts_with(&[0xFE, 0xEF]) || header.starts_with(b"GF") {
+            return Self::Gho;
+        }
+
+        if header.len() >= ISO_SIGNATURE_OFFSET + 5
+            && &header[ISO_SIGNATURE_OFFSET..ISO_SIGNATURE_OFFSET + 5] == b"CD001"
+        {
+            return Self::Iso;
+        }
+
+        Self::Unknown
+    }
+
+    /// 读取文件头判断镜像类型
+    ///
+    /// GHS 分卷没有文件签名，签名无法识别时按扩展名处理
+    pub fn detect(path: &str) -> Self {
+        let mut header = Vec::with_capacity(SNIFF_HEADER_LEN);
+        let read = File::open(path)
+            .and_then(|f| f.take(SNIFF_HEADER_LEN as u64).read_to_end(&mut header));
+        if let Err(e) = read {
+            log::warn!("读取镜像文件头失败: {} - {}", path, e);
+            return Self::Unknown;
+        }
+
+        match Self::sniff_header(&header) {
+            Self::Unknown if path.to_lowercase().ends_with(".ghs") => Self::Gho,
+            sniffed => sniffed,
+        }
+    }
 }
 
+/// WIM 头部标志：LZMS 压缩（ESD 格式）
+const WIM_HEADER_FLAG_COMPRESS_LZMS: u32 = 0x0008_0000;
+
+/// ISO 9660 主卷描述符中 "CD001" 签名的偏移
+const ISO_SIGNATURE_OFFSET: usize = 32769;
+
+/// 识别镜像类型需要读取的文件头长度
+pub const SNIFF_HEADER_LEN: usize = ISO_SIGNATURE_OFFSET + 5;
+
 impl std::fmt::Display for ImageType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -353,9 +409,14 @@ impl ImageVerifier {
         // 打开 WIM 文件
         let wim_handle = match wimlib.open_wim(file_path) {
             Ok(h) => h,
-            Err(e) => return VerifyResult::corrupted(file_path, ImageType::Wim, format!("无法打开镜像: {}", e)),
+            Err(e) => return VerifyResult::corrupted(file_path, ImageType::Wim, format!("无法打开镜像: {}", e.detailed())),
         };
 
+        self.verify_opened_wim(&wimlib, &wim_handle, file_path, reporter)
+    }
+
+    /// 校验已打开的 WIM（读取镜像信息并校验全部数据）
+    fn verify_opened_wim(&self, wimlib: &Wimlib, wim_handle: &WimHandle, file_path: &str, reporter: &ProgressReporter) -> VerifyResult {
         reporter.report(20, "正在读取镜像信息...", file_path);
 
         // 获取镜像数量
@@ -388,17 +449,24 @@ impl ImageVerifier {
 
         reporter.report(50, "正在校验完整性...", file_path);
 
-        // 启动进度监控线程
+        // 启动进度监控线程（校验结束后通过 finished 退出，取消时同时中止 wimlib 的校验）
         let cancel_flag = Arc::clone(&self.cancel_flag);
+        let finished = Arc::new(AtomicBool::new(false));
+        let monitor_finished = Arc::clone(&finished);
         let reporter_tx = reporter.tx.clone();
+        let wim_state = wim_handle.state();
         let monitor = thread::spawn(move || {
             let mut last_progress = 0u8;
             loop {
                 if cancel_flag.load(Ordering::SeqCst) {
+                    wim_state.cancel();
+                    break;
+                }
+                if monitor_finished.load(Ordering::SeqCst) {
                     break;
                 }
 
-                let current = Wimlib::get_global_progress();
+                let current = wim_state.progress();
                 if current > last_progress {
                     last_progress = current;
                     if let Some(ref tx) = reporter_tx {
@@ -422,6 +490,7 @@ impl ImageVerifier {
         let verify_result = wim_handle.verify();
 
         // 等待监控线程结束
+        finished.store(true, Ordering::SeqCst);
         let _ = monitor.join();
 
         // 检查取消状态
@@ -440,12 +509,66 @@ impl ImageVerifier {
             Err(e) => {
                 result.status = VerifyStatus::Corrupted;
                 result.message = format!("校验失败: {}", e);
+                if let Some(hint) = e.hint() {
+                    result.details.push(format!("修复建议: {}", hint));
+                }
+                // 多镜像文件逐个校验，找出损坏的镜像
+                if image_count > 1 && wimlib.supports_verify_image() {
+                    self.locate_corrupted_images(wim_handle, &mut result, reporter);
+                }
             }
         }
 
         result
     }
 
+    /// 逐个校验镜像，在详细信息中标记每个镜像的校验结果
+    fn locate_corrupted_images(&self, wim_handle: &WimHandle, result: &mut VerifyResult, reporter: &ProgressReporter) {
+        let total = result.image_count;
+        let mut corrupted = Vec::new();
+
+        for index in 1..=total {
+            if self.is_cancelled() {
+                return;
+            }
+
+            // 每个镜像占 50%-100% 中的一段
+            let base = Self::calculate_progress(50, index - 1, total, 50);
+            let span = (50 / total).max(1) as u8;
+            let (tx, rx) = mpsc::channel::<WimlibProgress>();
+            let reporter_tx = reporter.tx.clone();
+            let forward = thread::spawn(move || {
+                while let Ok(progress) = rx.recv() {
+                    if let Some(ref sender) = reporter_tx {
+                        let _ = sender.send(VerifyProgress::new(
+                            Self::calculate_progress(base, progress.percentage as u32, 100, span),
+                            format!("正在逐个校验镜像 {}/{}: {}", index, total, progress.status),
+                            "",
+                        ));
+                    }
+                }
+            });
+
+            let verify_result = wim_handle.verify_image(index as i32, Some(tx));
+            let _ = forward.join();
+
+            let mark = match verify_result {
+                Ok(()) => " ✅".to_string(),
+                Err(e) => {
+                    corrupted.push(index.to_string());
+                    format!(" ❌ {}", e)
+                }
+            };
+            if let Some(detail) = result.details.get_mut(index as usize - 1) {
+                detail.push_str(&mark);
+            }
+        }
+
+        if !corrupted.is_empty() {
+            result.message = format!("校验失败，损坏的镜像: {}（其余镜像可以正常使用）", corrupted.join("、"));
+        }
+    }
+
     // ========================================================================
     // SWM 分卷校验
     // ========================================================================
@@ -454,13 +577,22 @@ impl ImageVerifier {
         reporter.report(5, "正在扫描分卷文件...", file_path);
 
         // 查找所有分卷
-        let swm_files = match Self::find_swm_parts(file_path) {
-            Ok(files) => files,
-            Err(e) => return VerifyResult::error(file_path, ImageType::Swm, e),
-        };
-
-        if swm_files.is_empty() {
-            return VerifyResult::error(file_path, ImageType::Swm, "未找到分卷文件");
+        let swm_files: Vec<String> = find_swm_parts(file_path)
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+
+        // wimlib 支持引用分卷时校验全部数据，否则用 wimgapi 检查镜像结构
+        if let Some(wimlib) = Wimlib::new().ok().filter(|w| w.supports_split_wim()) {
+            reporter.report(10, format!("找到 {} 个分卷，正在引用...", swm_files.len()), file_path);
+            let wim_handle = match wimlib.open_split_wim(file_path) {
+                Ok(h) => h,
+                Err(e) => return VerifyResult::corrupted(file_path, ImageType::Swm, format!("无法打开分卷镜像: {}", e.detailed())),
+            };
+            let mut result = self.verify_opened_wim(&wimlib, &wim_handle, file_path, reporter);
+            result.part_count = swm_files.len() as u16;
+            result.details.insert(0, format!("找到 {} 个分卷文件", swm_files.len()));
+            return result;
         }
 
         let mut result = VerifyResult::default();
@@ -559,42 +691,6 @@ impl ImageVerifier {
         result
     }
 
-    /// 查找 SWM 分卷文件
-    fn find_swm_parts(main_swm: &str) -> Result<Vec<String>, String> {
-        let path = Path::new(main_swm);
-        let parent = path.parent().ok_or("无法获取文件目录")?;
-        let stem = path.file_stem().and_then(|s| s.to_str()).ok_or("无法获取文件名")?;
-
-        // 移除已有的数字后缀（如 install2 -> install）
-        let base_name = stem.trim_end_matches(|c: char| c.is_ascii_digit());
-
-        let mut parts = Vec::new();
-
-        // 添加主文件
-        if path.exists() {
-            parts.push(main_swm.to_string());
-        }
-
-        // 查找其他分卷
-        for i in 2..=999 {
-            let part_name = format!("{}{}.swm", base_name, i);
-            let part_path = parent.join(&part_name);
-
-            if part_path.exists() {
-                parts.push(part_path.to_string_lossy().to_string());
-            } else {
-                break;
-            }
-        }
-
-        if parts.is_empty() {
-            return Err("未找到任何分卷文件".to_string());
-        }
-
-        parts.sort();
-        Ok(parts)
-    }
-
     // ========================================================================
     // GHO 校验
     // ========================================================================
@@ -846,6 +942,30 @@ mod tests {
         assert_eq!(ImageType::from_extension("test.txt"), ImageType::Unknown);
     }
 
+    #[test]
+    fn test_image_type_sniff_header() {
+        let mut wim = vec![0u8; 64];
+        wim[..8].copy_from_slice(b"MSWIM\0\0\0");
+        wim[42] = 1;
+        assert_eq!(ImageType::sniff_header(&wim), ImageType::Wim);
+
+        let mut esd = wim.clone();
+        esd[16..20].copy_from_slice(&WIM_HEADER_FLAG_COMPRESS_LZMS.to_le_bytes());
+        assert_eq!(ImageType::sniff_header(&esd), ImageType::Esd);
+
+        let mut swm = wim.clone();
+        swm[42] = 3;
+        assert_eq!(ImageType::sniff_header(&swm), ImageType::Swm);
+
+        assert_eq!(ImageType::sniff_header(&[0xFE, 0xEF, 0, 0]), ImageType::Gho);
+
+        let mut iso = vec![0u8; SNIFF_HEADER_LEN];
+        iso[ISO_SIGNATURE_OFFSET..].copy_from_slice(b"CD001");
+        assert_eq!(ImageType::sniff_header(&iso), ImageType::Iso);
+
+        assert_eq!(ImageType::sniff_header(b"PK\x03\x04"), ImageType::Unknown);
+    }
+
     #[test]
     fn test_image_type_is_wim_family() {
         assert!(ImageType::Wim.is_wim_family());
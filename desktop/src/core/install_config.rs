@@ -1,66 +1,8 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
-/// 系统安装配置（用于PE环境内安装）
-#[derive(Debug, Clone, Default)]
-pub struct InstallConfig {
-    /// 无人值守安装
-    pub unattended: bool,
-    /// 驱动还原（兼容旧版本）
-    pub restore_drivers: bool,
-    /// 驱动操作模式: 0=无, 1=仅保存, 2=自动导入
-    pub driver_action_mode: u8,
-    /// 立即重启
-    pub auto_reboot: bool,
-    /// 原系统引导GUID（用于删除旧引导项）
-    pub original_guid: String,
-    /// 安装分卷索引
-    pub volume_index: u32,
-    /// 目标分区盘符
-    pub target_partition: String,
-    /// 镜像文件路径（相对于数据分区）
-    pub image_path: String,
-    /// 是否为GHO格式
-    pub is_gho: bool,
-    
-    // 高级选项
-    /// 移除快捷方式小箭头
-    pub remove_shortcut_arrow: bool,
-    /// Win11恢复经典右键
-    pub restore_classic_context_menu: bool,
-    /// OOBE绕过强制联网
-    pub bypass_nro: bool,
-    /// 禁用Windows更新
-    pub disable_windows_update: bool,
-    /// 禁用Windows安全中心
-    pub disable_windows_defender: bool,
-    /// 禁用系统保留空间
-    pub disable_reserved_storage: bool,
-    /// 禁用用户账户控制
-    pub disable_uac: bool,
-    /// 禁用自动设备加密
-    pub disable_device_encryption: bool,
-    /// 删除预装UWP应用
-    pub remove_uwp_apps: bool,
-    /// 导入磁盘控制器驱动
-    pub import_storage_controller_drivers: bool,
-    /// 自定义用户名
-    pub custom_username: String,
-    /// 自定义系统盘卷标
-    pub volume_label: String,
-    
-    // Win7 专用选项
-    /// Win7 UEFI 补丁（使用 UefiSeven）
-    pub win7_uefi_patch: bool,
-    /// Win7 注入USB3驱动
-    pub win7_inject_usb3_driver: bool,
-    /// Win7 注入NVMe驱动
-    pub win7_inject_nvme_driver: bool,
-    /// Win7 修复ACPI蓝屏
-    pub win7_fix_acpi_bsod: bool,
-    /// Win7 修复存储控制器蓝屏
-    pub win7_fix_storage_bsod: bool,
-}
+use crate::core::config_schema::CONFIG_SCHEMA_VERSION;
+pub use crate::core::config_schema::{BackupConfig, InstallConfig};
 
 impl InstallConfig {
     /// 根据DriverAction获取driver_action_mode值
@@ -82,36 +24,6 @@ impl InstallConfig {
             _ => crate::app::DriverAction::AutoImport,
         }
     }
-    
-    /// 判断是否需要导入驱动
-    pub fn should_import_drivers(&self) -> bool {
-        // 优先使用新的driver_action_mode
-        if self.driver_action_mode > 0 {
-            self.driver_action_mode == 2 // AutoImport
-        } else {
-            // 兼容旧版本
-            self.restore_drivers
-        }
-    }
-}
-
-/// 系统备份配置（用于PE环境内备份）
-#[derive(Debug, Clone, Default)]
-pub struct BackupConfig {
-    /// 备份保存路径（相对路径）
-    pub save_path: String,
-    /// 备份名称
-    pub name: String,
-    /// 备份描述
-    pub description: String,
-    /// 源分区盘符
-    pub source_partition: String,
-    /// 是否增量备份
-    pub incremental: bool,
-    /// 备份格式: 0=WIM, 1=ESD, 2=SWM, 3=GHO
-    pub format: u8,
-    /// SWM分卷大小（MB）
-    pub swm_split_size: u32,
 }
 
 /// 配置文件管理器
@@ -123,8 +35,12 @@ impl ConfigFileManager {
     const BACKUP_MARKER: &'static str = "LetRecovery_Backup.marker";
     
     /// 配置文件名
-    const INSTALL_CONFIG: &'static str = "LetRecovery_Install.ini";
-    const BACKUP_CONFIG: &'static str = "LetRecovery_Backup.ini";
+    const INSTALL_CONFIG: &'static str = "LetRecovery_Install.json";
+    const BACKUP_CONFIG: &'static str = "LetRecovery_Backup.json";
+
+    /// 旧版INI配置文件名（供旧版PE读取，同时兼容旧版桌面端写入的配置）
+    const LEGACY_INSTALL_CONFIG: &'static str = "LetRecovery_Install.ini";
+    const LEGACY_BACKUP_CONFIG: &'static str = "LetRecovery_Backup.ini";
     
     /// PE文件目录名
     const PE_DIR: &'static str = "LetRecovery_PE";
@@ -132,9 +48,6 @@ impl ConfigFileManager {
     /// 临时数据目录名
     const DATA_DIR: &'static str = "LetRecovery_Data";
 
-    /// 自动创建分区的标志文件名（与 disk.rs 中的常量保持一致）
-    const AUTO_CREATED_PARTITION_MARKER: &'static str = "LetRecovery_AutoCreated.marker";
-
     /// 查找包含安装标记文件的分区
     pub fn find_install_marker_partition() -> Option<String> {
         for letter in ['C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K'] {
@@ -160,12 +73,17 @@ impl ConfigFileManager {
     /// 查找包含配置文件的数据分区
     pub fn find_data_partition() -> Option<String> {
         for letter in ['C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K'] {
-            let config_path = format!("{}:\\{}\\{}", letter, Self::DATA_DIR, Self::INSTALL_CONFIG);
-            if Path::new(&config_path).exists() {
-                return Some(format!("{}:", letter));
-            }
-            let backup_config_path = format!("{}:\\{}\\{}", letter, Self::DATA_DIR, Self::BACKUP_CONFIG);
-            if Path::new(&backup_config_path).exists() {
+            let data_dir = format!("{}:\\{}", letter, Self::DATA_DIR);
+            let has_config = [
+                Self::INSTALL_CONFIG,
+                Self::LEGACY_INSTALL_CONFIG,
+                Self::BACKUP_CONFIG,
+                Self::LEGACY_BACKUP_CONFIG,
+            ]
+            .iter()
+            .any(|name| Path::new(&format!("{}\\{}", data_dir, name)).exists());
+
+            if has_config {
                 return Some(format!("{}:", letter));
             }
         }
@@ -178,6 +96,11 @@ impl ConfigFileManager {
         data_partition: &str,
         config: &InstallConfig,
     ) -> Result<()> {
+        config.validate().context("安装配置校验失败")?;
+
+        let mut config = config.clone();
+        config.schema_version = config.schema_version.max(CONFIG_SCHEMA_VERSION);
+
         // 创建数据目录
         let data_dir = format!("{}\\{}", data_partition, Self::DATA_DIR);
         std::fs::create_dir_all(&data_dir)
@@ -190,10 +113,16 @@ impl ConfigFileManager {
 
         // 写入配置文件
         let config_path = format!("{}\\{}", data_dir, Self::INSTALL_CONFIG);
-        let content = Self::serialize_install_config(config);
+        let content = serde_json::to_string_pretty(&config)
+            .context("序列化安装配置失败")?;
         std::fs::write(&config_path, &content)
             .context("写入安装配置文件失败")?;
 
+        // 同时写入旧版INI，保证旧版PE仍能读取
+        let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_INSTALL_CONFIG);
+        std::fs::write(&legacy_path, Self::serialize_install_config(&config))
+            .context("写入旧版安装配置文件失败")?;
+
         println!("[CONFIG] 安装配置已写入: {}", config_path);
         println!("[CONFIG] 安装标记已写入: {}", marker_path);
 
@@ -206,6 +135,11 @@ impl ConfigFileManager {
         data_partition: &str,
         config: &BackupConfig,
     ) -> Result<()> {
+        config.validate().context("备份配置校验失败")?;
+
+        let mut config = config.clone();
+        config.schema_version = config.schema_version.max(CONFIG_SCHEMA_VERSION);
+
         // 创建数据目录
         let data_dir = format!("{}\\{}", data_partition, Self::DATA_DIR);
         std::fs::create_dir_all(&data_dir)
@@ -218,10 +152,16 @@ impl ConfigFileManager {
 
         // 写入配置文件
         let config_path = format!("{}\\{}", data_dir, Self::BACKUP_CONFIG);
-        let content = Self::serialize_backup_config(config);
+        let content = serde_json::to_string_pretty(&config)
+            .context("序列化备份配置失败")?;
         std::fs::write(&config_path, &content)
             .context("写入备份配置文件失败")?;
 
+        // 同时写入旧版INI，保证旧版PE仍能读取
+        let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_BACKUP_CONFIG);
+        std::fs::write(&legacy_path, Self::serialize_backup_config(&config))
+            .context("写入旧版备份配置文件失败")?;
+
         println!("[CONFIG] 备份配置已写入: {}", config_path);
         println!("[CONFIG] 备份标记已写入: {}", marker_path);
 
@@ -229,19 +169,75 @@ impl ConfigFileManager {
     }
 
     /// 读取安装配置
+    ///
+    /// 优先读取JSON配置，不存在时回退到旧版INI配置
     pub fn read_install_config(data_partition: &str) -> Result<InstallConfig> {
-        let config_path = format!("{}\\{}\\{}", data_partition, Self::DATA_DIR, Self::INSTALL_CONFIG);
-        let content = std::fs::read_to_string(&config_path)
-            .context("读取安装配置文件失败")?;
-        Self::deserialize_install_config(&content)
+        let data_dir = Self::get_data_dir(data_partition);
+        let config_path = format!("{}\\{}", data_dir, Self::INSTALL_CONFIG);
+
+        let config = if Path::new(&config_path).exists() {
+            let content = std::fs::read_to_string(&config_path)
+                .context("读取安装配置文件失败")?;
+            Self::parse_install_config(&content)?
+        } else {
+            let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_INSTALL_CONFIG);
+            let content = std::fs::read_to_string(&legacy_path)
+                .context("读取安装配置文件失败")?;
+            Self::deserialize_install_config(&content)?
+        };
+
+        config.validate().context("安装配置校验失败")?;
+        Ok(config)
     }
 
     /// 读取备份配置
+    ///
+    /// 优先读取JSON配置，不存在时回退到旧版INI配置
     pub fn read_backup_config(data_partition: &str) -> Result<BackupConfig> {
-        let config_path = format!("{}\\{}\\{}", data_partition, Self::DATA_DIR, Self::BACKUP_CONFIG);
-        let content = std::fs::read_to_string(&config_path)
-            .context("读取备份配置文件失败")?;
-        Self::deserialize_backup_config(&content)
+        let data_dir = Self::get_data_dir(data_partition);
+        let config_path = format!("{}\\{}", data_dir, Self::BACKUP_CONFIG);
+
+        let config = if Path::new(&config_path).exists() {
+            let content = std::fs::read_to_string(&config_path)
+                .context("读取备份配置文件失败")?;
+            Self::parse_backup_config(&content)?
+        } else {
+            let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_BACKUP_CONFIG);
+            let content = std::fs::read_to_string(&legacy_path)
+                .context("读取备份配置文件失败")?;
+            Self::deserialize_backup_config(&content)?
+        };
+
+        config.validate().context("备份配置校验失败")?;
+        Ok(config)
+    }
+
+    /// 解析JSON格式的安装配置
+    pub fn parse_install_config(content: &str) -> Result<InstallConfig> {
+        let config: InstallConfig = serde_json::from_str(content)
+            .context("解析安装配置文件失败")?;
+        Self::warn_newer_schema(config.schema_version, &config.extra);
+        Ok(config)
+    }
+
+    /// 解析JSON格式的备份配置
+    pub fn parse_backup_config(content: &str) -> Result<BackupConfig> {
+        let config: BackupConfig = serde_json::from_str(content)
+            .context("解析备份配置文件失败")?;
+        Self::warn_newer_schema(config.schema_version, &config.extra);
+        Ok(config)
+    }
+
+    /// 配置由更新版本写入时给出提示（未识别字段会被保留但不会生效）
+    fn warn_newer_schema(version: u32, extra: &serde_json::Map<String, serde_json::Value>) {
+        if version > CONFIG_SCHEMA_VERSION {
+            log::warn!(
+                "配置文件版本 {} 高于当前支持的版本 {}，以下字段将被忽略: {:?}",
+                version,
+                CONFIG_SCHEMA_VERSION,
+                extra.keys().collect::<Vec<_>>()
+            );
+        }
     }
 
     /// 清理所有分区上的标记和配置文件
@@ -267,9 +263,11 @@ impl ConfigFileManager {
         
         for letter in b'A'..=b'Z' {
             let c = letter as char;
-            let marker_path = format!("{}:\\{}", c, Self::AUTO_CREATED_PARTITION_MARKER);
-            
-            if Path::new(&marker_path).exists() {
+            if !Path::new(&format!("{}:\\", c)).exists() {
+                continue;
+            }
+
+            if crate::core::disk::DiskManager::is_auto_created_partition(c) {
                 println!("[CONFIG] 发现自动创建的分区: {}:", c);
                 
                 // 尝试删除分区
@@ -288,8 +286,7 @@ impl ConfigFileManager {
     /// 检查指定分区是否是自动创建的
     pub fn is_auto_created_partition(partition: &str) -> bool {
         let letter = partition.chars().next().unwrap_or('X');
-        let marker_path = format!("{}:\\{}", letter, Self::AUTO_CREATED_PARTITION_MARKER);
-        Path::new(&marker_path).exists()
+        crate::core::disk::DiskManager::is_auto_created_partition(letter)
     }
 
     /// 获取数据目录路径
@@ -302,7 +299,7 @@ impl ConfigFileManager {
         format!("{}\\{}", partition, Self::PE_DIR)
     }
 
-    /// 序列化安装配置为INI格式
+    /// 序列化安装配置为旧版INI格式
     fn serialize_install_config(config: &InstallConfig) -> String {
         format!(
             r#"[Install]
@@ -315,6 +312,7 @@ VolumeIndex={}
 TargetPartition={}
 ImagePath={}
 IsGho={}
+GhoPartition={}
 
 [Advanced]
 RemoveShortcutArrow={}
@@ -346,6 +344,7 @@ Win7FixStorageBsod={}
             config.target_partition,
             config.image_path,
             config.is_gho,
+            config.gho_partition,
             config.remove_shortcut_arrow,
             config.restore_classic_context_menu,
             config.bypass_nro,
@@ -366,7 +365,7 @@ Win7FixStorageBsod={}
         )
     }
 
-    /// 序列化备份配置为INI格式
+    /// 序列化备份配置为旧版INI格式
     fn serialize_backup_config(config: &BackupConfig) -> String {
         format!(
             r#"[Backup]
@@ -388,7 +387,7 @@ SwmSplitSize={}
         )
     }
 
-    /// 反序列化安装配置
+    /// 反序列化旧版INI安装配置
     fn deserialize_install_config(content: &str) -> Result<InstallConfig> {
         let mut config = InstallConfig::default();
         
@@ -412,6 +411,7 @@ SwmSplitSize={}
                     "TargetPartition" => config.target_partition = value.to_string(),
                     "ImagePath" => config.image_path = value.to_string(),
                     "IsGho" => config.is_gho = value.parse().unwrap_or(false),
+                    "GhoPartition" => config.gho_partition = value.parse().unwrap_or(0),
                     "RemoveShortcutArrow" => config.remove_shortcut_arrow = value.parse().unwrap_or(false),
                     "RestoreClassicContextMenu" => config.restore_classic_context_menu = value.parse().unwrap_or(false),
                     "BypassNRO" => config.bypass_nro = value.parse().unwrap_or(false),
@@ -429,7 +429,9 @@ SwmSplitSize={}
                     "Win7InjectNvmeDriver" => config.win7_inject_nvme_driver = value.parse().unwrap_or(false),
                     "Win7FixAcpiBsod" => config.win7_fix_acpi_bsod = value.parse().unwrap_or(false),
                     "Win7FixStorageBsod" => config.win7_fix_storage_bsod = value.parse().unwrap_or(false),
-                    _ => {}
+                    _ => {
+                        config.extra.insert(key.to_string(), value.into());
+                    }
                 }
             }
         }
@@ -437,7 +439,7 @@ SwmSplitSize={}
         Ok(config)
     }
 
-    /// 反序列化备份配置
+    /// 反序列化旧版INI备份配置
     fn deserialize_backup_config(content: &str) -> Result<BackupConfig> {
         let mut config = BackupConfig::default();
         
@@ -459,7 +461,9 @@ SwmSplitSize={}
                     "Incremental" => config.incremental = value.parse().unwrap_or(false),
                     "Format" => config.format = value.parse().unwrap_or(0),
                     "SwmSplitSize" => config.swm_split_size = value.parse().unwrap_or(4096),
-                    _ => {}
+                    _ => {
+                        config.extra.insert(key.to_string(), value.into());
+                    }
                 }
             }
         }
@@ -467,3 +471,133 @@ SwmSplitSize={}
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config_schema::ConfigError;
+    use crate::core::capture_options::{CaptureCompression, CaptureOptions};
+    use crate::core::power::CompletionAction;
+
+    fn sample_install_config() -> InstallConfig {
+        InstallConfig {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            driver_action_mode: 2,
+            harvest_drivers: true,
+            hwid_drivers: true,
+            wifi_profiles: true,
+            apply_verify: crate::core::apply_verify::ApplyVerifyMode::Sampled,
+            completion_action: Some(CompletionAction::Shutdown),
+            volume_index: 3,
+            target_partition: "C:".to_string(),
+            image_path: "install.wim".to_string(),
+            bypass_nro: true,
+            bypass_tpm_check: true,
+            custom_username: "Admin".to_string(),
+            script_hooks: vec![crate::core::script_hooks::ScriptHook {
+                file: "tweaks.ps1".to_string(),
+                ..Default::default()
+            }],
+            unattend: crate::core::unattend::UnattendSettings {
+                time_zone: "China Standard Time".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_install_config_round_trip() {
+        let config = sample_install_config();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("\"BypassNRO\":true"));
+        assert!(json.contains("\"BypassTPMCheck\":true"));
+        assert!(json.contains("\"BypassRAMCheck\":false"));
+        assert!(json.contains("\"SchemaVersion\":1"));
+        assert!(json.contains("\"HarvestDrivers\":true"));
+        assert!(json.contains("\"ApplyVerify\":\"Sampled\""));
+        assert!(json.contains("\"CompletionAction\":\"Shutdown\""));
+
+        let parsed = ConfigFileManager::parse_install_config(&json).unwrap();
+        assert_eq!(parsed.volume_index, 3);
+        assert_eq!(parsed.driver_action_mode, 2);
+        assert!(parsed.harvest_drivers);
+        assert!(parsed.hwid_drivers);
+        assert!(parsed.wifi_profiles);
+        assert_eq!(parsed.apply_verify, crate::core::apply_verify::ApplyVerifyMode::Sampled);
+        assert_eq!(parsed.completion_action(), CompletionAction::Shutdown);
+        assert_eq!(parsed.custom_username, "Admin");
+        assert!(parsed.bypass_nro);
+        assert!(parsed.bypass_tpm_check && !parsed.bypass_secure_boot_check);
+        assert_eq!(parsed.script_hooks, config.script_hooks);
+        assert_eq!(parsed.unattend, config.unattend);
+        assert!(parsed.validate().is_ok());
+
+        // 账户密码由 install_secrets 传递，不写入配置
+        let mut with_password = config;
+        with_password.unattend.password = "secret".to_string();
+        assert!(!serde_json::to_string(&with_password).unwrap().contains("secret"));
+    }
+
+    #[test]
+    fn test_unknown_fields_preserved() {
+        let json = r#"{"SchemaVersion":2,"TargetPartition":"D:","ImagePath":"a.esd","VolumeIndex":1,"FutureOption":{"Level":5}}"#;
+        let parsed = ConfigFileManager::parse_install_config(json).unwrap();
+        assert_eq!(parsed.target_partition, "D:");
+        assert!(parsed.extra.contains_key("FutureOption"));
+
+        let written = serde_json::to_string(&parsed).unwrap();
+        assert!(written.contains("\"FutureOption\":{\"Level\":5}"));
+    }
+
+    #[test]
+    fn test_completion_action_falls_back_to_auto_reboot() {
+        let mut config = InstallConfig::default();
+        assert_eq!(config.completion_action(), CompletionAction::StayInPe);
+        config.auto_reboot = true;
+        assert_eq!(config.completion_action(), CompletionAction::RebootCountdown);
+        assert!(!serde_json::to_string(&config).unwrap().contains("CompletionAction"));
+
+        config.completion_action = Some(CompletionAction::FirmwareSetup);
+        assert_eq!(config.completion_action(), CompletionAction::FirmwareSetup);
+    }
+
+    #[test]
+    fn test_legacy_ini_still_readable() {
+        let ini = "[Install]\nTargetPartition=C:\nImagePath=install.wim\nVolumeIndex=2\nBypassNRO=true\nSomethingNew=1\n";
+        let parsed = ConfigFileManager::deserialize_install_config(ini).unwrap();
+        assert_eq!(parsed.schema_version, 0);
+        assert_eq!(parsed.volume_index, 2);
+        assert_eq!(parsed.gho_partition, 0);
+
+        let ini = "[Install]\nTargetPartition=C:\nImagePath=disk.gho\nIsGho=true\nGhoPartition=2\n";
+        let parsed = ConfigFileManager::deserialize_install_config(ini).unwrap();
+        assert!(parsed.is_gho);
+        assert_eq!(parsed.gho_partition, 2);
+        assert!(parsed.bypass_nro);
+        assert_eq!(parsed.extra.get("SomethingNew").and_then(|v| v.as_str()), Some("1"));
+    }
+
+    #[test]
+    fn test_backup_capture_options() {
+        // 旧版配置没有捕获选项，使用默认的压缩方式和排除列表
+        let json = r#"{"SchemaVersion":1,"SavePath":"D:\\backup.wim","Name":"backup","SourcePartition":"C:"}"#;
+        let parsed = ConfigFileManager::parse_backup_config(json).unwrap();
+        assert_eq!(parsed.capture, CaptureOptions::default());
+
+        let mut config = parsed;
+        config.capture.compression = CaptureCompression::Fast;
+        config.capture.exclusions = vec!["\\Temp".to_string()];
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains("\"Compression\":\"Fast\""));
+        let parsed = ConfigFileManager::parse_backup_config(&json).unwrap();
+        assert_eq!(parsed.capture, config.capture);
+        assert!(parsed.validate().is_ok());
+
+        config.capture.exclusions.push("\\*".to_string());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "Capture", .. })
+        ));
+    }
+}
@@ -0,0 +1,172 @@
+//! 备份/复制的 I/O 优先级与限速
+//!
+//! 后台优先级通过 SetFileInformationByHandle 为文件句柄设置低 I/O 优先级提示；
+//! 由 wimgapi 自行打开文件的捕获操作无法拿到句柄，改为让工作线程进入后台模式。
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::time::{Duration, Instant};
+
+/// I/O 优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IoPriority {
+    /// 正常优先级
+    #[default]
+    Normal,
+    /// 后台优先级（不影响前台使用）
+    Background,
+}
+
+impl IoPriority {
+    /// 全部优先级（按显示顺序）
+    pub const ALL: [IoPriority; 2] = [IoPriority::Normal, IoPriority::Background];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            IoPriority::Normal => "正常",
+            IoPriority::Background => "后台（低优先级）",
+        }
+    }
+}
+
+/// 备份/复制的 I/O 选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IoOptions {
+    /// I/O 优先级
+    pub priority: IoPriority,
+    /// 限速（MB/s），0 表示不限速
+    pub throttle_mb_per_sec: u32,
+}
+
+/// 为文件句柄设置 I/O 优先级提示
+#[cfg(windows)]
+pub fn apply_to_file(file: &File, priority: IoPriority) {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        FileIoPriorityHintInfo, IoPriorityHintLow, IoPriorityHintNormal, SetFileInformationByHandle,
+        FILE_IO_PRIORITY_HINT_INFO,
+    };
+
+    let info = FILE_IO_PRIORITY_HINT_INFO {
+        PriorityHint: match priority {
+            IoPriority::Normal => IoPriorityHintNormal,
+            IoPriority::Background => IoPriorityHintLow,
+        },
+    };
+
+    unsafe {
+        if let Err(e) = SetFileInformationByHandle(
+            HANDLE(file.as_raw_handle()),
+            FileIoPriorityHintInfo,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<FILE_IO_PRIORITY_HINT_INFO>() as u32,
+        ) {
+            println!("[IO] 设置 I/O 优先级提示失败: {}", e);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn apply_to_file(_file: &File, _priority: IoPriority) {}
+
+/// 当前线程后台模式守卫，Drop 时恢复
+pub struct BackgroundModeGuard {
+    active: bool,
+}
+
+impl BackgroundModeGuard {
+    /// 后台优先级时让当前线程进入后台模式（降低 I/O 与调度优先级）
+    pub fn enter(priority: IoPriority) -> Self {
+        let active = priority == IoPriority::Background && set_thread_background(true);
+        Self { active }
+    }
+}
+
+impl Drop for BackgroundModeGuard {
+    fn drop(&mut self) {
+        if self.active {
+            set_thread_background(false);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn set_thread_background(begin: bool) -> bool {
+    use windows::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_MODE_BACKGROUND_BEGIN,
+        THREAD_MODE_BACKGROUND_END,
+    };
+
+    let mode = if begin {
+        THREAD_MODE_BACKGROUND_BEGIN
+    } else {
+        THREAD_MODE_BACKGROUND_END
+    };
+    unsafe { SetThreadPriority(GetCurrentThread(), mode).is_ok() }
+}
+
+#[cfg(not(windows))]
+fn set_thread_background(_begin: bool) -> bool {
+    false
+}
+
+/// 吞吐量限速器
+pub struct Throttle {
+    bytes_per_sec: u64,
+    start: Instant,
+    transferred: u64,
+}
+
+impl Throttle {
+    /// 创建限速器，0 表示不限速
+    pub fn new(mb_per_sec: u32) -> Self {
+        Self {
+            bytes_per_sec: mb_per_sec as u64 * 1024 * 1024,
+            start: Instant::now(),
+            transferred: 0,
+        }
+    }
+
+    /// 是否启用了限速
+    pub fn is_enabled(&self) -> bool {
+        self.bytes_per_sec > 0
+    }
+
+    /// 记录已传输的字节数，超出限速时休眠
+    pub fn consume(&mut self, bytes: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.transferred += bytes;
+        if let Some(delay) = throttle_delay(self.transferred, self.bytes_per_sec, self.start.elapsed()) {
+            std::thread::sleep(delay);
+        }
+    }
+}
+
+/// 计算按限速传输 transferred 字节还需等待的时间
+fn throttle_delay(transferred: u64, bytes_per_sec: u64, elapsed: Duration) -> Option<Duration> {
+    if bytes_per_sec == 0 {
+        return None;
+    }
+    let expected = Duration::from_secs_f64(transferred as f64 / bytes_per_sec as f64);
+    expected.checked_sub(elapsed).filter(|d| !d.is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_delay() {
+        const MB: u64 = 1024 * 1024;
+        assert_eq!(throttle_delay(10 * MB, 0, Duration::ZERO), None);
+        assert_eq!(
+            throttle_delay(10 * MB, 10 * MB, Duration::from_millis(400)),
+            Some(Duration::from_millis(600))
+        );
+        assert_eq!(throttle_delay(10 * MB, 10 * MB, Duration::from_secs(2)), None);
+    }
+}
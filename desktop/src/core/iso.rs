@@ -379,6 +379,24 @@ impl IsoMounter {
         Ok(())
     }
 
+    /// 卸载指定 ISO，按路径卸载失败时卸载全部 ISO
+    pub fn unmount_path(iso_path: &str) -> Result<()> {
+        #[cfg(windows)]
+        {
+            if let Err(e) = Self::unmount_iso_by_path(iso_path) {
+                println!("[ISO] 按路径卸载失败: {}，尝试卸载全部 ISO", e);
+                return Self::unmount();
+            }
+            Ok(())
+        }
+
+        #[cfg(not(windows))]
+        {
+            let _ = iso_path;
+            Self::unmount()
+        }
+    }
+
     /// 查找已挂载的 ISO 驱动器盘符（后备方案，遍历 D-Z）
     pub fn find_iso_drive() -> Option<String> {
         // 遍历 D 到 Z 所有盘符
@@ -401,25 +419,34 @@ impl IsoMounter {
         None
     }
 
+    /// ISO 中可能存放安装镜像的位置
+    ///
+    /// 标准介质位于 sources 目录；多架构介质（如 x64 + x86 合盘）按架构分目录存放
+    pub fn install_image_candidates(drive: &str) -> Vec<String> {
+        let mut candidates = Vec::new();
+        for dir in ["", "\\x64", "\\x86", "\\arm64"] {
+            for name in ["install.wim", "install.esd", "install.swm"] {
+                candidates.push(format!("{}{}\\sources\\{}", drive, dir, name));
+            }
+        }
+        candidates
+    }
+
+    /// 列出挂载的 ISO 中的全部安装镜像
+    pub fn find_install_images_in_drive(drive: &str) -> Vec<String> {
+        let images: Vec<String> = Self::install_image_candidates(drive)
+            .into_iter()
+            .filter(|path| Path::new(path).exists())
+            .collect();
+        println!("[ISO] 在 {} 找到 {} 个安装镜像: {:?}", drive, images.len(), images);
+        images
+    }
+
     /// 在挂载的 ISO 中查找系统镜像文件
     /// 如果传入 drive 参数，则只在该盘符下查找
     /// 否则遍历所有盘符
     pub fn find_install_image_in_drive(drive: &str) -> Option<String> {
-        let paths = [
-            format!("{}\\sources\\install.wim", drive),
-            format!("{}\\sources\\install.esd", drive),
-            format!("{}\\sources\\install.swm", drive),
-        ];
-
-        for path in &paths {
-            if Path::new(path).exists() {
-                println!("[ISO] 在 {} 找到安装镜像: {}", drive, path);
-                return Some(path.clone());
-            }
-        }
-
-        println!("[ISO] 在 {} 未找到安装镜像", drive);
-        None
+        Self::find_install_images_in_drive(drive).into_iter().next()
     }
 
     /// 在挂载的 ISO 中查找系统镜像文件（遍历所有盘符）
@@ -494,3 +521,17 @@ impl Drop for IsoMounter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_install_image_candidates() {
+        let candidates = IsoMounter::install_image_candidates("F:");
+        assert_eq!(candidates[0], "F:\\sources\\install.wim");
+        assert_eq!(candidates[1], "F:\\sources\\install.esd");
+        assert!(candidates.contains(&"F:\\x64\\sources\\install.esd".to_string()));
+        assert_eq!(candidates.len(), 12);
+    }
+}
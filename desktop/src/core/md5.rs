@@ -0,0 +1,208 @@
+//! MD5 计算（纯 Rust 实现，无外部依赖）
+
+/// MD5上下文
+pub struct Md5Context {
+    state: [u32; 4],
+    count: [u32; 2],
+    buffer: [u8; 64],
+}
+
+impl Md5Context {
+    pub fn new() -> Self {
+        Self {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            count: [0, 0],
+            buffer: [0u8; 64],
+        }
+    }
+    
+    pub fn update(&mut self, input: &[u8]) {
+        let input_len = input.len();
+        let mut index = ((self.count[0] >> 3) & 0x3F) as usize;
+        
+        self.count[0] = self.count[0].wrapping_add((input_len as u32) << 3);
+        if self.count[0] < (input_len as u32) << 3 {
+            self.count[1] = self.count[1].wrapping_add(1);
+        }
+        self.count[1] = self.count[1].wrapping_add((input_len as u32) >> 29);
+        
+        let part_len = 64 - index;
+        let mut i = 0;
+        
+        if input_len >= part_len {
+            self.buffer[index..64].copy_from_slice(&input[..part_len]);
+            let block: [u8; 64] = self.buffer;
+            self.transform(&block);
+            
+            i = part_len;
+            while i + 63 < input_len {
+                let block: [u8; 64] = input[i..i + 64].try_into().unwrap();
+                self.transform(&block);
+                i += 64;
+            }
+            index = 0;
+        }
+        
+        self.buffer[index..index + (input_len - i)].copy_from_slice(&input[i..]);
+    }
+    
+    pub fn finalize(mut self) -> [u8; 16] {
+        let bits: [u8; 8] = [
+            self.count[0] as u8,
+            (self.count[0] >> 8) as u8,
+            (self.count[0] >> 16) as u8,
+            (self.count[0] >> 24) as u8,
+            self.count[1] as u8,
+            (self.count[1] >> 8) as u8,
+            (self.count[1] >> 16) as u8,
+            (self.count[1] >> 24) as u8,
+        ];
+        
+        let index = ((self.count[0] >> 3) & 0x3F) as usize;
+        let pad_len = if index < 56 { 56 - index } else { 120 - index };
+        
+        let mut padding = [0u8; 64];
+        padding[0] = 0x80;
+        self.update(&padding[..pad_len]);
+        self.update(&bits);
+        
+        let mut digest = [0u8; 16];
+        for (i, &s) in self.state.iter().enumerate() {
+            digest[i * 4] = s as u8;
+            digest[i * 4 + 1] = (s >> 8) as u8;
+            digest[i * 4 + 2] = (s >> 16) as u8;
+            digest[i * 4 + 3] = (s >> 24) as u8;
+        }
+        digest
+    }
+    
+    fn transform(&mut self, block: &[u8; 64]) {
+        let mut a = self.state[0];
+        let mut b = self.state[1];
+        let mut c = self.state[2];
+        let mut d = self.state[3];
+        
+        let mut x = [0u32; 16];
+        for i in 0..16 {
+            x[i] = u32::from_le_bytes([
+                block[i * 4],
+                block[i * 4 + 1],
+                block[i * 4 + 2],
+                block[i * 4 + 3],
+            ]);
+        }
+        
+        // Round 1
+        macro_rules! ff {
+            ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
+                $a = $a.wrapping_add(($b & $c) | (!$b & $d))
+                    .wrapping_add($x)
+                    .wrapping_add($ac);
+                $a = $a.rotate_left($s).wrapping_add($b);
+            };
+        }
+        
+        ff!(a, b, c, d, x[0], 7, 0xd76aa478);
+        ff!(d, a, b, c, x[1], 12, 0xe8c7b756);
+        ff!(c, d, a, b, x[2], 17, 0x242070db);
+        ff!(b, c, d, a, x[3], 22, 0xc1bdceee);
+        ff!(a, b, c, d, x[4], 7, 0xf57c0faf);
+        ff!(d, a, b, c, x[5], 12, 0x4787c62a);
+        ff!(c, d, a, b, x[6], 17, 0xa8304613);
+        ff!(b, c, d, a, x[7], 22, 0xfd469501);
+        ff!(a, b, c, d, x[8], 7, 0x698098d8);
+        ff!(d, a, b, c, x[9], 12, 0x8b44f7af);
+        ff!(c, d, a, b, x[10], 17, 0xffff5bb1);
+        ff!(b, c, d, a, x[11], 22, 0x895cd7be);
+        ff!(a, b, c, d, x[12], 7, 0x6b901122);
+        ff!(d, a, b, c, x[13], 12, 0xfd987193);
+        ff!(c, d, a, b, x[14], 17, 0xa679438e);
+        ff!(b, c, d, a, x[15], 22, 0x49b40821);
+        
+        // Round 2
+        macro_rules! gg {
+            ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
+                $a = $a.wrapping_add(($b & $d) | ($c & !$d))
+                    .wrapping_add($x)
+                    .wrapping_add($ac);
+                $a = $a.rotate_left($s).wrapping_add($b);
+            };
+        }
+        
+        gg!(a, b, c, d, x[1], 5, 0xf61e2562);
+        gg!(d, a, b, c, x[6], 9, 0xc040b340);
+        gg!(c, d, a, b, x[11], 14, 0x265e5a51);
+        gg!(b, c, d, a, x[0], 20, 0xe9b6c7aa);
+        gg!(a, b, c, d, x[5], 5, 0xd62f105d);
+        gg!(d, a, b, c, x[10], 9, 0x02441453);
+        gg!(c, d, a, b, x[15], 14, 0xd8a1e681);
+        gg!(b, c, d, a, x[4], 20, 0xe7d3fbc8);
+        gg!(a, b, c, d, x[9], 5, 0x21e1cde6);
+        gg!(d, a, b, c, x[14], 9, 0xc33707d6);
+        gg!(c, d, a, b, x[3], 14, 0xf4d50d87);
+        gg!(b, c, d, a, x[8], 20, 0x455a14ed);
+        gg!(a, b, c, d, x[13], 5, 0xa9e3e905);
+        gg!(d, a, b, c, x[2], 9, 0xfcefa3f8);
+        gg!(c, d, a, b, x[7], 14, 0x676f02d9);
+        gg!(b, c, d, a, x[12], 20, 0x8d2a4c8a);
+        
+        // Round 3
+        macro_rules! hh {
+            ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
+                $a = $a.wrapping_add($b ^ $c ^ $d)
+                    .wrapping_add($x)
+                    .wrapping_add($ac);
+                $a = $a.rotate_left($s).wrapping_add($b);
+            };
+        }
+        
+        hh!(a, b, c, d, x[5], 4, 0xfffa3942);
+        hh!(d, a, b, c, x[8], 11, 0x8771f681);
+        hh!(c, d, a, b, x[11], 16, 0x6d9d6122);
+        hh!(b, c, d, a, x[14], 23, 0xfde5380c);
+        hh!(a, b, c, d, x[1], 4, 0xa4beea44);
+        hh!(d, a, b, c, x[4], 11, 0x4bdecfa9);
+        hh!(c, d, a, b, x[7], 16, 0xf6bb4b60);
+        hh!(b, c, d, a, x[10], 23, 0xbebfbc70);
+        hh!(a, b, c, d, x[13], 4, 0x289b7ec6);
+        hh!(d, a, b, c, x[0], 11, 0xeaa127fa);
+        hh!(c, d, a, b, x[3], 16, 0xd4ef3085);
+        hh!(b, c, d, a, x[6], 23, 0x04881d05);
+        hh!(a, b, c, d, x[9], 4, 0xd9d4d039);
+        hh!(d, a, b, c, x[12], 11, 0xe6db99e5);
+        hh!(c, d, a, b, x[15], 16, 0x1fa27cf8);
+        hh!(b, c, d, a, x[2], 23, 0xc4ac5665);
+        
+        // Round 4
+        macro_rules! ii {
+            ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
+                $a = $a.wrapping_add($c ^ ($b | !$d))
+                    .wrapping_add($x)
+                    .wrapping_add($ac);
+                $a = $a.rotate_left($s).wrapping_add($b);
+            };
+        }
+        
+        ii!(a, b, c, d, x[0], 6, 0xf4292244);
+        ii!(d, a, b, c, x[7], 10, 0x432aff97);
+        ii!(c, d, a, b, x[14], 15, 0xab9423a7);
+        ii!(b, c, d, a, x[5], 21, 0xfc93a039);
+        ii!(a, b, c, d, x[12], 6, 0x655b59c3);
+        ii!(d, a, b, c, x[3], 10, 0x8f0ccc92);
+        ii!(c, d, a, b, x[10], 15, 0xffeff47d);
+        ii!(b, c, d, a, x[1], 21, 0x85845dd1);
+        ii!(a, b, c, d, x[8], 6, 0x6fa87e4f);
+        ii!(d, a, b, c, x[15], 10, 0xfe2ce6e0);
+        ii!(c, d, a, b, x[6], 15, 0xa3014314);
+        ii!(b, c, d, a, x[13], 21, 0x4e0811a1);
+        ii!(a, b, c, d, x[4], 6, 0xf7537e82);
+        ii!(d, a, b, c, x[11], 10, 0xbd3af235);
+        ii!(c, d, a, b, x[2], 15, 0x2ad7d2bb);
+        ii!(b, c, d, a, x[9], 21, 0xeb86d391);
+        
+        self.state[0] = self.state[0].wrapping_add(a);
+        self.state[1] = self.state[1].wrapping_add(b);
+        self.state[2] = self.state[2].wrapping_add(c);
+        self.state[3] = self.state[3].wrapping_add(d);
+    }
+}
@@ -1,23 +1,94 @@
 pub mod app_config;
+#[path = "../../../shared/apply_verify.rs"]
+pub mod apply_verify;
+#[path = "../../../shared/auto_unlock.rs"]
+pub mod auto_unlock;
+#[path = "../../../shared/backup_catalog.rs"]
+pub mod backup_catalog;
 pub mod bcdedit;
 pub mod bitlocker;
+pub mod branding;
+#[path = "../../../shared/capture_options.rs"]
+pub mod capture_options;
 pub mod fveapi;
 pub mod cabinet;
+#[path = "../../../shared/config_schema.rs"]
+pub mod config_schema;
+pub mod demo;
+pub mod diagnostics;
 pub mod disk;
+pub mod disk_native;
+#[path = "../../../shared/disk_policy.rs"]
+pub mod disk_policy;
 pub mod dism;
 pub mod dism_cmd;
 pub mod driver;
+pub mod driver_pack;
+#[path = "../../../shared/driver_signing.rs"]
+pub mod driver_signing;
 pub mod ghost;
 pub mod gho_password;
 pub mod hardware_info;
+#[path = "../../../shared/health_check.rs"]
+pub mod health_check;
+pub mod hash;
+pub mod history;
+pub mod hive_restore;
+pub mod hwid_drivers;
+pub mod image_compat;
+pub mod image_convert;
+#[path = "../../../shared/image_language.rs"]
+pub mod image_language;
+pub mod image_library;
 pub mod image_verify;
 pub mod install_config;
+#[path = "../../../shared/install_engine.rs"]
+pub mod install_engine;
+#[path = "../../../shared/install_secrets.rs"]
+pub mod install_secrets;
+pub mod io_priority;
 pub mod iso;
+pub mod md5;
+#[path = "../../../shared/netcast.rs"]
+pub mod netcast;
+#[path = "../../../shared/network_source.rs"]
+pub mod network_source;
+#[path = "../../../shared/nt5_boot.rs"]
+pub mod nt5_boot;
 pub mod nvidia_driver;
+#[path = "../../../shared/offline_registry.rs"]
+pub mod offline_registry;
+pub mod opal;
 pub mod pe;
+#[path = "../../../shared/partition_plan.rs"]
+pub mod partition_plan;
+pub mod power;
 pub mod quick_partition;
+pub mod recovery_env;
 pub mod registry;
+pub mod restore_points;
+pub mod scheduler;
+pub mod script_hooks;
+#[path = "../../../shared/secure_dir.rs"]
+pub mod secure_dir;
+pub mod storage_controller;
 pub mod system_info;
 pub mod system_utils;
+pub mod tech_lock;
+#[path = "../../../shared/unattend.rs"]
+pub mod unattend;
+pub mod vhd;
+#[path = "../../../shared/volume_trim.rs"]
+pub mod volume_trim;
+pub mod watcher;
+pub mod wifi_profile;
+pub mod wim_browser;
+pub mod wim_manage;
+pub mod wim_mount;
 pub mod wimgapi;
+#[path = "../../../shared/wimlib.rs"]
 pub mod wimlib;
+pub mod window_state;
+pub mod winre;
+#[path = "../../../shared/zst_pack.rs"]
+pub mod zst_pack;
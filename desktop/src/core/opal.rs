@@ -0,0 +1,216 @@
+//! 自加密硬盘（TCG Opal / eDrive）模块
+//!
+//! 硬件加密的固态硬盘被锁定后，系统仍能看到磁盘但无法读写，格式化只会报出难以理解的错误。
+//! 这里通过 sedutil-cli（放在 bin\sedutil 目录）检测已锁定的硬盘，并提供两种处理方式：
+//! - 使用锁定密码解锁（数据保留）
+//! - 使用硬盘标签上的 PSID 恢复出厂设置（清除全部数据）
+
+use anyhow::Context;
+
+use crate::utils::cmd::create_command;
+use crate::utils::path::get_bin_dir;
+
+/// 自加密硬盘信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpalDrive {
+    /// 设备路径（如 \\.\PhysicalDrive1）
+    pub device: String,
+    /// 磁盘号
+    pub disk_number: Option<u32>,
+    /// 支持的 TCG 规范（如 "2"、"12"、"E"）
+    pub ssc: String,
+    /// 型号
+    pub model: String,
+    /// 是否已启用锁定
+    pub locking_enabled: bool,
+    /// 是否处于锁定状态
+    pub locked: bool,
+}
+
+impl OpalDrive {
+    /// 显示名称
+    pub fn display_name(&self) -> String {
+        match self.disk_number {
+            Some(n) => format!("磁盘 {} - {}", n, self.model),
+            None => format!("{} - {}", self.device, self.model),
+        }
+    }
+}
+
+/// sedutil-cli 路径
+fn sedutil_path() -> std::path::PathBuf {
+    get_bin_dir().join("sedutil").join("sedutil-cli.exe")
+}
+
+/// 检查 sedutil-cli 是否可用
+pub fn is_available() -> bool {
+    sedutil_path().exists()
+}
+
+/// 执行 sedutil-cli，返回标准输出（失败时包含标准错误）
+fn run_sedutil(args: &[&str]) -> anyhow::Result<String> {
+    let output = create_command(sedutil_path())
+        .args(args)
+        .output()
+        .context("执行 sedutil-cli 失败")?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if !output.status.success() {
+        anyhow::bail!("sedutil-cli 返回错误 ({}): {}", output.status, text.trim());
+    }
+    Ok(text)
+}
+
+/// 解析 `sedutil-cli --scan` 输出，返回支持 Opal 的设备（设备路径, 规范, 型号）
+///
+/// 输出格式：`\\.\PhysicalDrive0  12  Samsung SSD 850 EVO 250GB   EMT02B6Q`，不支持时规范列为 `No`
+pub fn parse_scan_output(output: &str) -> Vec<(String, String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let device = parts.next()?;
+            if !device.to_lowercase().contains("physicaldrive") {
+                return None;
+            }
+            let ssc = parts.next()?;
+            if ssc.eq_ignore_ascii_case("no") {
+                return None;
+            }
+            // 最后一列为固件版本
+            let mut rest: Vec<&str> = parts.collect();
+            rest.pop();
+            Some((device.to_string(), ssc.to_string(), rest.join(" ")))
+        })
+        .collect()
+}
+
+/// 解析 `sedutil-cli --query` 输出中的锁定状态，返回（已启用锁定, 已锁定）
+pub fn parse_locking_state(output: &str) -> (bool, bool) {
+    let flag = |name: &str| {
+        output
+            .split(',')
+            .flat_map(|part| part.split('\n'))
+            .map(str::trim)
+            .any(|item| item.replace(' ', "").eq_ignore_ascii_case(&format!("{}=Y", name)))
+    };
+    (flag("LockingEnabled"), flag("Locked"))
+}
+
+/// 从设备路径解析磁盘号
+fn disk_number_of(device: &str) -> Option<u32> {
+    let lower = device.to_lowercase();
+    let index = lower.find("physicaldrive")? + "physicaldrive".len();
+    lower[index..].parse().ok()
+}
+
+/// 扫描所有支持 Opal 的硬盘及其锁定状态
+pub fn scan_drives() -> anyhow::Result<Vec<OpalDrive>> {
+    if !is_available() {
+        anyhow::bail!("未找到 sedutil-cli: {}", sedutil_path().display());
+    }
+
+    let output = run_sedutil(&["--scan"])?;
+    let drives = parse_scan_output(&output)
+        .into_iter()
+        .map(|(device, ssc, model)| {
+            let (locking_enabled, locked) = match run_sedutil(&["--query", &device]) {
+                Ok(query) => parse_locking_state(&query),
+                Err(e) => {
+                    log::warn!("[OPAL] 查询 {} 状态失败: {:#}", device, e);
+                    (false, false)
+                }
+            };
+            OpalDrive {
+                disk_number: disk_number_of(&device),
+                device,
+                ssc,
+                model,
+                locking_enabled,
+                locked,
+            }
+        })
+        .collect();
+    Ok(drives)
+}
+
+/// 获取已锁定的自加密硬盘（sedutil-cli 不可用时返回空列表）
+pub fn locked_drives() -> Vec<OpalDrive> {
+    if !is_available() {
+        return Vec::new();
+    }
+    match scan_drives() {
+        Ok(drives) => drives.into_iter().filter(|d| d.locked).collect(),
+        Err(e) => {
+            log::warn!("[OPAL] 扫描自加密硬盘失败: {:#}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 格式化失败时的补充说明：存在已锁定的自加密硬盘时提示用户先解锁
+pub fn format_failure_hint() -> Option<String> {
+    let locked = locked_drives();
+    if locked.is_empty() {
+        return None;
+    }
+    let names: Vec<String> = locked.iter().map(|d| d.display_name()).collect();
+    Some(format!(
+        "检测到已锁定的自加密硬盘（{}），请先在工具箱「自加密硬盘解锁」中解锁或恢复出厂设置",
+        names.join("、")
+    ))
+}
+
+/// 使用锁定密码解锁硬盘（数据保留，重启前有效）
+pub fn unlock(device: &str, password: &str) -> anyhow::Result<()> {
+    log::info!("[OPAL] 解锁 {}", device);
+    run_sedutil(&["--setlockingrange", "0", "rw", password, device]).context("解锁全盘锁定范围失败")?;
+    // 关闭影子MBR，使系统能看到真实的分区表（未启用影子MBR时会失败，可忽略）
+    if let Err(e) = run_sedutil(&["--setmbrdone", "on", password, device]) {
+        log::warn!("[OPAL] 设置 MBRDone 失败（可忽略）: {:#}", e);
+    }
+    Ok(())
+}
+
+/// 使用 PSID 恢复出厂设置（清除全部数据并解除锁定）
+pub fn psid_revert(device: &str, psid: &str) -> anyhow::Result<()> {
+    let psid: String = psid.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if psid.len() != 32 {
+        anyhow::bail!("PSID 应为 32 位字母或数字，当前为 {} 位", psid.len());
+    }
+    log::warn!("[OPAL] 使用 PSID 恢复出厂设置: {}", device);
+    run_sedutil(&["--yesIreallywanttoERASEALLmydatausingthePSID", &psid, device])
+        .context("PSID 恢复出厂设置失败，请确认 PSID 正确")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scan_output() {
+        let output = "Scanning for Opal compliant disks\n\
+                      \\\\.\\PhysicalDrive0  12  Samsung SSD 850 EVO 250GB                EMT02B6Q\n\
+                      \\\\.\\PhysicalDrive1 No  ST1000DM003-1ER162                       CC45\n\
+                      No more disks present ending scan\n";
+        let drives = parse_scan_output(output);
+        assert_eq!(drives.len(), 1);
+        assert_eq!(drives[0].0, "\\\\.\\PhysicalDrive0");
+        assert_eq!(drives[0].1, "12");
+        assert_eq!(drives[0].2, "Samsung SSD 850 EVO 250GB");
+        assert_eq!(disk_number_of(&drives[0].0), Some(0));
+    }
+
+    #[test]
+    fn test_parse_locking_state() {
+        let locked = "Locking function (0x0002)\n    Locked = Y, LockingEnabled = Y, LockingSupported = Y, MBRDone = N, MBREnabled = Y, MediaEncrypt = Y\n";
+        assert_eq!(parse_locking_state(locked), (true, true));
+
+        let unlocked = "Locking function (0x0002)\n    Locked = N, LockingEnabled = N, LockingSupported = Y, MBRDone = N, MBREnabled = N, MediaEncrypt = Y\n";
+        assert_eq!(parse_locking_state(unlocked), (false, false));
+    }
+}
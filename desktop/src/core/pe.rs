@@ -1,5 +1,6 @@
 use anyhow::Result;
 use std::path::Path;
+use crate::core::power::CompletionAction;
 use crate::utils::cmd::create_command;
 
 use crate::utils::encoding::gbk_to_utf8;
@@ -90,41 +91,11 @@ impl PeManager {
             .ok_or_else(|| anyhow::anyhow!("无法找到ISO挂载点"))?;
         println!("[PE] ISO已挂载到: {}", mount_point);
 
-        // 2. 查找PE WIM文件
-        let wim_paths = [
-            format!("{}\\sources\\boot.wim", mount_point),
-            format!("{}\\Boot\\boot.wim", mount_point),
-            format!("{}\\boot.wim", mount_point),
-            format!("{}\\BOOT\\BOOT.WIM", mount_point),
-        ];
-
-        let mut wim_path = None;
-        for path in &wim_paths {
-            if Path::new(path).exists() {
-                wim_path = Some(path.clone());
-                break;
-            }
-        }
-
-        let wim_path = wim_path.ok_or_else(|| anyhow::anyhow!("ISO中未找到 boot.wim"))?;
+        // 2. 查找PE WIM文件和boot.sdi
+        let (wim_path, sdi_path) = Self::find_boot_files(&mount_point)?;
         println!("[PE] 找到WIM: {}", wim_path);
 
-        // 3. 查找boot.sdi
-        let sdi_paths = [
-            format!("{}\\boot\\boot.sdi", mount_point),
-            format!("{}\\Boot\\boot.sdi", mount_point),
-            format!("{}\\BOOT\\BOOT.SDI", mount_point),
-        ];
-
-        let mut sdi_path = None;
-        for path in &sdi_paths {
-            if Path::new(path).exists() {
-                sdi_path = Some(path.clone());
-                break;
-            }
-        }
-
-        // 4. 复制必要文件到系统分区
+        // 3. 复制必要文件到系统分区
         let target_dir = "C:\\LetRecovery_PE";
         std::fs::create_dir_all(target_dir)?;
 
@@ -142,19 +113,42 @@ impl PeManager {
             self.create_default_sdi(target_dir)?
         };
 
-        // 5. 卸载ISO
+        // 4. 卸载ISO
         let _ = crate::core::iso::IsoMounter::unmount();
 
-        // 6. 创建BCD引导项
+        // 5. 创建BCD引导项
         self.create_pe_boot_entry(display_name, &target_wim, &target_sdi)?;
 
-        // 7. 设置下次启动
+        // 6. 设置下次启动
         self.set_next_boot()?;
 
         println!("[PE] ========== PE启动准备完成 ==========");
         Ok(())
     }
 
+    /// 在挂载的ISO中查找 boot.wim 和 boot.sdi（boot.sdi 可能不存在）
+    pub fn find_boot_files(mount_point: &str) -> Result<(String, Option<String>)> {
+        let wim_paths = [
+            format!("{}\\sources\\boot.wim", mount_point),
+            format!("{}\\Boot\\boot.wim", mount_point),
+            format!("{}\\boot.wim", mount_point),
+            format!("{}\\BOOT\\BOOT.WIM", mount_point),
+        ];
+        let wim_path = wim_paths
+            .into_iter()
+            .find(|path| Path::new(path).exists())
+            .ok_or_else(|| anyhow::anyhow!("ISO中未找到 boot.wim"))?;
+
+        let sdi_paths = [
+            format!("{}\\boot\\boot.sdi", mount_point),
+            format!("{}\\Boot\\boot.sdi", mount_point),
+            format!("{}\\BOOT\\BOOT.SDI", mount_point),
+        ];
+        let sdi_path = sdi_paths.into_iter().find(|path| Path::new(path).exists());
+
+        Ok((wim_path, sdi_path))
+    }
+
     /// 从WIM直接启动PE
     fn boot_from_wim(&self, wim_path: &str, display_name: &str) -> Result<()> {
         println!("[PE] 从WIM启动PE");
@@ -181,7 +175,7 @@ impl PeManager {
     }
 
     /// 创建默认的boot.sdi文件
-    fn create_default_sdi(&self, target_dir: &str) -> Result<String> {
+    pub fn create_default_sdi(&self, target_dir: &str) -> Result<String> {
         let sdi_path = format!("{}\\boot.sdi", target_dir);
         
         // 尝试从Windows系统复制
@@ -227,6 +221,9 @@ impl PeManager {
         let is_uefi = Self::is_uefi_boot();
         println!("[PE] 引导模式: {}", if is_uefi { "UEFI" } else { "Legacy" });
 
+        // 修改前备份BCD，出问题时可以通过"恢复引导配置"还原
+        crate::core::bcdedit::BootManager::new().backup_before_change(None, "创建PE引导项前");
+
         // 清理旧的PE引导项
         let _ = self.cleanup_old_pe_entries();
 
@@ -367,13 +364,13 @@ impl PeManager {
     /// 重启系统
     pub fn reboot() {
         println!("[PE] 执行重启");
-        let _ = create_command("shutdown")
-            .args(["/r", "/t", "3", "/c", "LetRecovery 正在重启到 PE 环境..."])
-            .spawn();
+        if let Err(e) = CompletionAction::RebootCountdown.execute(3, "LetRecovery 正在重启到 PE 环境...") {
+            println!("[PE] {:#}", e);
+        }
     }
 
     /// 从bcdedit输出中提取GUID
-    fn extract_guid(output: &str) -> Result<String> {
+    pub fn extract_guid(output: &str) -> Result<String> {
         for word in output.split_whitespace() {
             if word.starts_with('{') && word.ends_with('}') {
                 return Ok(word.to_string());
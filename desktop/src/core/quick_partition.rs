@@ -29,7 +29,9 @@ use crate::utils::cmd::create_command;
 use crate::utils::encoding::gbk_to_utf8;
 use crate::utils::path::get_bin_dir;
 
-use super::disk::PartitionStyle;
+use super::disk::{
+    DiskManager, GptAttributes, GptPartitionType, PartitionPlan, PartitionStyle, PlannedPartition, SectorSize,
+};
 use super::system_info::BootMode;
 
 /// 获取 diskpart 可执行文件路径
@@ -59,6 +61,8 @@ pub struct PhysicalDisk {
     pub partitions: Vec<DiskPartitionInfo>,
     /// 未分配空间（字节）
     pub unallocated_bytes: u64,
+    /// 逻辑/物理扇区大小
+    pub sector_size: SectorSize,
 }
 
 impl PhysicalDisk {
@@ -110,6 +114,8 @@ pub struct DiskPartitionInfo {
     pub is_recovery: bool,
     /// 分区类型 GUID（GPT）或类型 ID（MBR）
     pub partition_type: String,
+    /// GPT 分区属性（MBR 分区为 0）
+    pub gpt_attributes: GptAttributes,
     /// 已使用空间（字节）
     pub used_bytes: u64,
     /// 空闲空间（字节）
@@ -165,6 +171,35 @@ pub struct PartitionLayout {
     pub is_esp: bool,
     /// 文件系统类型
     pub file_system: String,
+    /// GPT 分区类型（仅 GPT 磁盘生效，ESP 由 is_esp 决定）
+    pub gpt_type: GptPartitionType,
+    /// GPT 分区属性（仅 GPT 磁盘生效）
+    pub gpt_attributes: GptAttributes,
+}
+
+impl PartitionLayout {
+    /// 转换为分区规划中的分区，`use_remaining` 为 true 时使用剩余空间
+    ///
+    /// GPT 分区类型和属性只在 GPT 磁盘上生效。
+    fn to_planned(&self, style: PartitionStyle, use_remaining: bool) -> PlannedPartition {
+        let partition_type = match (self.is_esp, style) {
+            (true, _) => GptPartitionType::Esp,
+            (false, PartitionStyle::GPT) => self.gpt_type,
+            _ => GptPartitionType::BasicData,
+        };
+        let size_mb = (self.size_gb * 1024.0) as u64;
+        let mut planned = PlannedPartition::new(partition_type, Some(size_mb));
+        if !matches!(partition_type, GptPartitionType::Esp | GptPartitionType::Msr) {
+            if use_remaining {
+                planned.size_mb = None;
+            }
+            planned.label = self.label.clone();
+            planned.file_system = self.file_system.clone();
+            planned.gpt_attributes = self.gpt_attributes;
+        }
+        planned.drive_letter = self.drive_letter;
+        planned
+    }
 }
 
 impl Default for PartitionLayout {
@@ -175,6 +210,8 @@ impl Default for PartitionLayout {
             label: String::new(),
             is_esp: false,
             file_system: "NTFS".to_string(),
+            gpt_type: GptPartitionType::BasicData,
+            gpt_attributes: GptAttributes::default(),
         }
     }
 }
@@ -439,6 +476,7 @@ fn get_disk_info(disk_number: u32) -> Option<PhysicalDisk> {
             is_initialized,
             partitions,
             unallocated_bytes: unallocated,
+            sector_size: DiskManager::get_sector_size(disk_number).unwrap_or_default(),
         })
     }
 }
@@ -495,7 +533,7 @@ fn parse_partition_layout(
             continue;
         }
 
-        let (is_esp, is_msr, is_recovery, partition_type) = if style == PartitionStyle::GPT {
+        let (is_esp, is_msr, is_recovery, partition_type, gpt_attributes) = if style == PartitionStyle::GPT {
             // GPT: 分区类型 GUID 在 union 开始处 (offset 32)
             // PARTITION_INFORMATION_GPT 结构:
             // offset 0 (32): PartitionType GUID (16 bytes)
@@ -509,16 +547,10 @@ fn parse_partition_layout(
             let is_msr = type_guid == MSR_PARTITION_TYPE_GUID;
             let is_recovery = type_guid == RECOVERY_PARTITION_TYPE_GUID;
 
-            let type_str = format!(
-                "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
-                type_guid[3], type_guid[2], type_guid[1], type_guid[0],
-                type_guid[5], type_guid[4],
-                type_guid[7], type_guid[6],
-                type_guid[8], type_guid[9],
-                type_guid[10], type_guid[11], type_guid[12], type_guid[13], type_guid[14], type_guid[15]
-            );
+            let type_str = super::disk::format_guid(&type_guid);
+            let attributes = u64::from_le_bytes(partition_data[64..72].try_into().unwrap_or([0; 8]));
 
-            (is_esp, is_msr, is_recovery, type_str)
+            (is_esp, is_msr, is_recovery, type_str, GptAttributes(attributes))
         } else {
             // MBR: 分区类型 ID 在 union 开始处 (offset 32)
             // PARTITION_INFORMATION_MBR 结构:
@@ -528,7 +560,7 @@ fn parse_partition_layout(
             // offset 4 (36): HiddenSectors (4 bytes)
             let type_id = partition_data[32];
             let type_str = format!("0x{:02X}", type_id);
-            (false, false, false, type_str)
+            (false, false, false, type_str, GptAttributes::default())
         };
 
         // 获取盘符
@@ -552,6 +584,7 @@ fn parse_partition_layout(
             is_msr,
             is_recovery,
             partition_type,
+            gpt_attributes,
             used_bytes,
             free_bytes,
         });
@@ -773,104 +806,31 @@ pub fn execute_quick_partition(
         layouts.len()
     );
 
-    // 构建 diskpart 脚本
-    let mut script = String::new();
-
-    // 选择磁盘
-    script.push_str(&format!("select disk {}\n", disk_number));
-
-    // 清除磁盘（删除所有分区）
-    script.push_str("clean\n");
-
-    // 转换分区表类型
-    match partition_style {
-        PartitionStyle::GPT => {
-            script.push_str("convert gpt\n");
-        }
-        PartitionStyle::MBR => {
-            script.push_str("convert mbr\n");
-        }
-        _ => {
-            return QuickPartitionResult {
-                success: false,
-                message: "无效的分区表类型".to_string(),
-                created_partitions: Vec::new(),
-            };
-        }
+    if partition_style == PartitionStyle::Unknown {
+        return QuickPartitionResult {
+            success: false,
+            message: "无效的分区表类型".to_string(),
+            created_partitions: Vec::new(),
+        };
     }
 
-    let mut created_partitions = Vec::new();
-
-    // 创建分区
-    for (i, layout) in layouts.iter().enumerate() {
-        let is_last = i == layouts.len() - 1;
-
-        if layout.is_esp {
-            // 创建 ESP 分区
-            let size_mb = (layout.size_gb * 1024.0) as u64;
-            script.push_str(&format!("create partition efi size={}\n", size_mb));
-            script.push_str("format fs=fat32 quick label=\"EFI\"\n");
-            created_partitions.push("ESP".to_string());
-        } else {
-            // 创建普通分区
-            if is_last {
-                // 最后一个分区使用剩余空间
-                script.push_str("create partition primary\n");
-            } else {
-                let size_mb = (layout.size_gb * 1024.0) as u64;
-                script.push_str(&format!("create partition primary size={}\n", size_mb));
-            }
-
-            // 格式化
-            let label = if layout.label.is_empty() {
-                "新加卷".to_string()
-            } else {
-                layout.label.clone()
-            };
-            let fs = if layout.file_system.is_empty() {
-                "NTFS"
-            } else {
-                &layout.file_system
-            };
-            script.push_str(&format!("format fs={} quick label=\"{}\"\n", fs, label));
-
-            // 分配盘符
-            if let Some(letter) = layout.drive_letter {
-                script.push_str(&format!("assign letter={}\n", letter));
-                created_partitions.push(format!("{}:", letter));
-            } else {
-                script.push_str("assign\n");
-                created_partitions.push(format!("分区 {}", i + 1));
-            }
-        }
-    }
+    // 最后一个分区使用剩余空间
+    let partitions = layouts
+        .iter()
+        .enumerate()
+        .map(|(i, layout)| layout.to_planned(partition_style, i == layouts.len() - 1))
+        .collect();
+    let plan = PartitionPlan::repartition(disk_number, partition_style, partitions);
 
-    // 执行脚本
-    match execute_diskpart_script(&script) {
-        Ok(output) => {
-            // 检查输出是否包含错误
-            let output_lower = output.to_lowercase();
-            if output_lower.contains("错误")
-                || output_lower.contains("error")
-                || output_lower.contains("失败")
-                || output_lower.contains("failed")
-            {
-                QuickPartitionResult {
-                    success: false,
-                    message: format!("分区操作失败: {}", output),
-                    created_partitions: Vec::new(),
-                }
-            } else {
-                QuickPartitionResult {
-                    success: true,
-                    message: "分区操作完成".to_string(),
-                    created_partitions,
-                }
-            }
-        }
+    match DiskManager::plan_and_apply(&plan) {
+        Ok(created_partitions) => QuickPartitionResult {
+            success: true,
+            message: "分区操作完成".to_string(),
+            created_partitions,
+        },
         Err(e) => QuickPartitionResult {
             success: false,
-            message: format!("执行 diskpart 失败: {}", e),
+            message: format!("{:#}", e),
             created_partitions: Vec::new(),
         },
     }
@@ -1025,13 +985,8 @@ pub fn create_esp_partition(disk_number: u32, size_mb: u64) -> Result<String> {
 
 /// 删除指定分区
 pub fn delete_partition(disk_number: u32, partition_number: u32) -> Result<String> {
-    let mut script = String::new();
-
-    script.push_str(&format!("select disk {}\n", disk_number));
-    script.push_str(&format!("select partition {}\n", partition_number));
-    script.push_str("delete partition override\n");
-
-    execute_diskpart_script(&script)
+    super::disk_native::delete_partition(disk_number, partition_number)?;
+    Ok(format!("已删除磁盘 {} 分区 {}", disk_number, partition_number))
 }
 
 /// 缩小分区
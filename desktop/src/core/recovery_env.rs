@@ -0,0 +1,818 @@
+//! 一键恢复环境
+//!
+//! 把 LetRecovery PE 安装到系统盘末尾的隐藏恢复分区，并在启动菜单中添加「一键恢复」项
+//! （可选开机按 F11 直接进入），系统无法启动时也能进入 PE 还原备份。
+//! 可选同时注册为 Windows RE（reagentc），让「高级启动」和连续启动失败后的自动修复进入同一个 PE。
+//! 安装信息写入恢复分区的 `LetRecovery_RE\recovery.json`，据此检测、更新和卸载。
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::mpsc::Sender;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::bcdedit::BootManager;
+use crate::core::dism::DismProgress;
+use crate::core::disk::{get_diskpart_path, DiskManager, GptPartitionType, PartitionStyle, TempDriveLetter};
+use crate::core::disk_native::{self, ExistingPartition};
+use crate::core::iso::IsoMounter;
+use crate::core::pe::PeManager;
+use crate::core::winre::reagentc;
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+use crate::utils::path::get_bin_dir;
+
+/// 恢复分区中存放 PE 文件的目录
+pub const RECOVERY_DIR: &str = "LetRecovery_RE";
+/// 安装信息文件（位于 RECOVERY_DIR 中）
+const INFO_FILE: &str = "recovery.json";
+/// 恢复分区卷标
+pub const RECOVERY_LABEL: &str = "LETRECOVERY_RE";
+/// 默认启动菜单名称
+pub const DEFAULT_ENTRY_NAME: &str = "一键恢复";
+/// 默认恢复分区大小（MB）
+pub const DEFAULT_SIZE_MB: u64 = 2048;
+/// 恢复分区最小大小（MB）
+pub const MIN_SIZE_MB: u64 = 1024;
+/// 恢复分区最大大小（MB）
+pub const MAX_SIZE_MB: u64 = 16384;
+/// 安装后启动菜单的最短等待时间（秒），否则来不及选择「一键恢复」
+const MIN_BOOT_TIMEOUT: u32 = 3;
+/// MBR 恢复分区类型 ID
+const MBR_RECOVERY_ID: u8 = 0x27;
+/// 启动管理器自定义按键：F11（扫描码 0x85）触发自定义动作 0x5400000f
+const HOTKEY_ACTION: [&str; 2] = ["0x1000085000001", "0x5400000f"];
+const HOTKEY_ELEMENT: &str = "custom:0x5400000f";
+/// MBR 磁盘最多 4 个主分区
+const MBR_MAX_PRIMARY: usize = 4;
+/// 注册 Windows RE 前系统自带 Winre.wim 的备份后缀
+const WINRE_BACKUP_SUFFIX: &str = ".letrecovery.bak";
+
+/// 安装信息（写入恢复分区）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct RecoveryInfo {
+    /// 启动菜单名称
+    pub display_name: String,
+    /// 安装的 PE 文件名
+    pub pe_file: String,
+    /// 安装时间（Unix 时间戳，秒）
+    pub installed_at: u64,
+    /// 恢复分区大小（MB）
+    pub size_mb: u64,
+    pub ramdisk_guid: String,
+    pub loader_guid: String,
+    /// 是否设置了 F11 热键
+    pub hotkey: bool,
+    /// 是否注册为 Windows RE
+    pub winre: bool,
+}
+
+impl RecoveryInfo {
+    /// 安装时间文本
+    pub fn installed_at_text(&self) -> String {
+        chrono::DateTime::from_timestamp(self.installed_at as i64, 0)
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    }
+}
+
+/// 已安装的一键恢复环境
+#[derive(Debug, Clone)]
+pub struct RecoveryStatus {
+    /// 恢复分区的卷 GUID 路径
+    pub volume: String,
+    pub info: RecoveryInfo,
+    /// 启动菜单项是否仍存在
+    pub entry_present: bool,
+}
+
+/// 安装选项
+#[derive(Debug, Clone)]
+pub struct RecoveryInstallOptions {
+    /// PE 文件（WIM 或 ISO）
+    pub pe_path: String,
+    pub display_name: String,
+    /// 新建恢复分区的大小（MB），更新已有分区时忽略
+    pub size_mb: u64,
+    pub hotkey: bool,
+    pub winre: bool,
+}
+
+impl Default for RecoveryInstallOptions {
+    fn default() -> Self {
+        Self {
+            pe_path: String::new(),
+            display_name: DEFAULT_ENTRY_NAME.to_string(),
+            size_mb: DEFAULT_SIZE_MB,
+            hotkey: true,
+            winre: false,
+        }
+    }
+}
+
+impl RecoveryInstallOptions {
+    /// 检查选项是否有效
+    pub fn validate(&self) -> Result<(), String> {
+        let extension = Path::new(&self.pe_path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if self.pe_path.trim().is_empty() {
+            return Err("请选择 PE 文件".to_string());
+        }
+        if extension != "wim" && extension != "iso" {
+            return Err("PE 文件必须是 WIM 或 ISO".to_string());
+        }
+        let name = self.display_name.trim();
+        if name.is_empty() {
+            return Err("启动菜单名称不能为空".to_string());
+        }
+        if name.contains('"') {
+            return Err("启动菜单名称不能包含引号".to_string());
+        }
+        if !(MIN_SIZE_MB..=MAX_SIZE_MB).contains(&self.size_mb) {
+            return Err(format!("恢复分区大小必须在 {} ~ {} MB 之间", MIN_SIZE_MB, MAX_SIZE_MB));
+        }
+        Ok(())
+    }
+}
+
+fn bcdedit_path() -> String {
+    get_bin_dir().join("bcdedit.exe").to_string_lossy().to_string()
+}
+
+/// 执行 bcdedit，失败时返回错误
+fn bcdedit(args: &[&str]) -> Result<String> {
+    let output = create_command(&bcdedit_path())
+        .args(args)
+        .output()
+        .context("执行 bcdedit 失败")?;
+    let stdout = gbk_to_utf8(&output.stdout);
+    log::info!("[RECOVERY] bcdedit {:?}: {}", args, stdout.trim());
+    if !output.status.success() {
+        bail!("bcdedit {} 失败: {} {}", args.join(" "), stdout.trim(), gbk_to_utf8(&output.stderr).trim());
+    }
+    Ok(stdout)
+}
+
+/// 执行 diskpart 脚本，输出包含错误信息时返回错误
+fn run_diskpart(script: &str) -> Result<String> {
+    log::debug!("[RECOVERY] Diskpart 脚本内容:\n{}", script);
+    let script_path = std::env::temp_dir().join("lr_recovery_env.txt");
+    std::fs::write(&script_path, script)?;
+    let output = create_command(&get_diskpart_path())
+        .args(["/s", script_path.to_str().unwrap()])
+        .output();
+    let _ = std::fs::remove_file(&script_path);
+    let output = output.context("执行 diskpart 失败")?;
+
+    let output_text = gbk_to_utf8(&output.stdout);
+    log::info!("[RECOVERY] Diskpart 输出: {}", output_text);
+    let output_lower = output_text.to_lowercase();
+    if ["错误", "error", "失败", "failed", "无法", "denied"].iter().any(|k| output_lower.contains(k)) {
+        bail!("Diskpart 执行失败: {}", output_text.trim());
+    }
+    Ok(output_text)
+}
+
+/// 从系统分区末尾缩小出恢复分区并格式化的 diskpart 脚本
+pub fn create_partition_script(system_letter: char, size_mb: u64, new_letter: char) -> String {
+    format!(
+        "select volume {}\n\
+        shrink desired={} minimum={}\n\
+        create partition primary size={}\n\
+        format fs=ntfs quick label=\"{}\"\n\
+        assign letter={}\n",
+        system_letter, size_mb, size_mb, size_mb, RECOVERY_LABEL, new_letter
+    )
+}
+
+/// 从 `bcdedit /enum {bootmgr}` 输出中读取启动菜单等待时间
+pub fn parse_boot_timeout(output: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let mut parts = line.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(key), Some(value)) if key.eq_ignore_ascii_case("timeout") => value.parse().ok(),
+            _ => None,
+        }
+    })
+}
+
+/// 从 `bcdedit /enum` 输出中读取列表元素的全部值（第二个值起在缩进的续行中）
+pub fn parse_list_element(output: &str, name: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut in_element = false;
+    for line in output.lines() {
+        let mut parts = line.split_whitespace();
+        if !line.starts_with(char::is_whitespace) {
+            in_element = parts.next().is_some_and(|key| key.eq_ignore_ascii_case(name));
+        }
+        if in_element {
+            values.extend(parts.map(str::to_string));
+        }
+    }
+    values
+}
+
+/// 在现有的自定义按键中加入或移除 F11 热键，保留其他程序（如 OEM 恢复）设置的按键
+pub fn merge_custom_actions(existing: &[String], hotkey: bool) -> Vec<String> {
+    let is_hotkey = |pair: &[String]| {
+        pair.len() == 2 && pair.iter().zip(HOTKEY_ACTION).all(|(value, ours)| value.eq_ignore_ascii_case(ours))
+    };
+    let mut actions: Vec<String> = existing.chunks(2).filter(|pair| !is_hotkey(pair)).flatten().cloned().collect();
+    if hotkey {
+        actions.extend(HOTKEY_ACTION.iter().map(|value| value.to_string()));
+    }
+    actions
+}
+
+/// 写入启动管理器的自定义按键，为空时删除该设置
+fn set_custom_actions(actions: &[String]) -> Result<()> {
+    if actions.is_empty() {
+        bcdedit(&["/deletevalue", "{bootmgr}", "customactions"])?;
+    } else {
+        let mut args = vec!["/set", "{bootmgr}", "customactions"];
+        args.extend(actions.iter().map(String::as_str));
+        bcdedit(&args)?;
+    }
+    Ok(())
+}
+
+/// 当前启动管理器的自定义按键
+fn current_custom_actions() -> Vec<String> {
+    bcdedit(&["/enum", "{bootmgr}"])
+        .map(|output| parse_list_element(&output, "customactions"))
+        .unwrap_or_default()
+}
+
+/// MBR 磁盘的主分区表是否已满（扩展分区容器也占用一项）
+pub fn mbr_primary_full(style: PartitionStyle, partitions: &[ExistingPartition]) -> bool {
+    style == PartitionStyle::MBR && partitions.iter().filter(|p| p.primary).count() >= MBR_MAX_PRIMARY
+}
+
+/// 恢复分区是否紧跟在系统分区之后，只有这样删除后的空间才能合并到系统分区
+pub fn directly_follows(partitions: &[ExistingPartition], system_number: u32, recovery_number: u32) -> bool {
+    let Some(system) = partitions.iter().find(|p| p.number == system_number) else {
+        return false;
+    };
+    let system_end = system.extent.offset + system.extent.length;
+    partitions
+        .iter()
+        .filter(|p| p.extent.offset >= system_end)
+        .min_by_key(|p| p.extent.offset)
+        // 逻辑分区不能扩展到扩展分区之外的主分区
+        .is_some_and(|p| p.number == recovery_number && p.primary == system.primary)
+}
+
+fn info_path(root: &str) -> String {
+    format!("{}\\{}\\{}", root.trim_end_matches('\\'), RECOVERY_DIR, INFO_FILE)
+}
+
+/// 查找已安装的一键恢复环境
+pub fn detect() -> Option<RecoveryStatus> {
+    DiskManager::list_volumes().into_iter().find_map(|volume| {
+        let content = std::fs::read_to_string(info_path(&volume)).ok()?;
+        let info: RecoveryInfo = match serde_json::from_str(&content) {
+            Ok(info) => info,
+            Err(e) => {
+                log::warn!("[RECOVERY] 解析 {} 失败: {}", info_path(&volume), e);
+                return None;
+            }
+        };
+        let entry_present = !info.loader_guid.is_empty() && bcdedit(&["/enum", &info.loader_guid]).is_ok();
+        Some(RecoveryStatus { volume, info, entry_present })
+    })
+}
+
+/// 待安装的 PE 文件，来自 ISO 时离开作用域自动卸载
+struct PeFiles {
+    wim: String,
+    sdi: Option<String>,
+    iso: Option<String>,
+}
+
+impl PeFiles {
+    fn open(pe_path: &str) -> Result<Self> {
+        if !Path::new(pe_path).exists() {
+            bail!("PE 文件不存在: {}", pe_path);
+        }
+        if !pe_path.to_lowercase().ends_with(".iso") {
+            return Ok(Self { wim: pe_path.to_string(), sdi: None, iso: None });
+        }
+
+        let mount_point = IsoMounter::mount_iso(pe_path).context("挂载 PE ISO 失败")?;
+        match PeManager::find_boot_files(&mount_point) {
+            Ok((wim, sdi)) => Ok(Self { wim, sdi, iso: Some(pe_path.to_string()) }),
+            Err(e) => {
+                let _ = IsoMounter::unmount_path(pe_path);
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for PeFiles {
+    fn drop(&mut self) {
+        if let Some(ref iso) = self.iso {
+            let _ = IsoMounter::unmount_path(iso);
+        }
+    }
+}
+
+/// 从系统分区缩小出恢复分区，返回新分区的卷 GUID 路径（不保留盘符）
+fn create_partition(size_mb: u64) -> Result<String> {
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    let system_letter = system_drive.chars().next().unwrap_or('C');
+
+    if let Some(disk) = DiskManager::disk_number_of(&system_drive) {
+        match disk_native::read_partitions(disk) {
+            Ok((style, partitions)) if mbr_primary_full(style, &partitions) => bail!(
+                "系统盘（磁盘 {}）是 MBR 分区表且已有 {} 个主分区，无法再创建恢复分区",
+                disk,
+                MBR_MAX_PRIMARY
+            ),
+            Ok(_) => {}
+            Err(e) => log::warn!("[RECOVERY] 读取磁盘 {} 分区表失败: {}", disk, e),
+        }
+    }
+
+    let max_shrink_mb = DiskManager::query_shrink_max(system_letter)?;
+    if max_shrink_mb < size_mb {
+        bail!(
+            "系统分区 {} 最多只能缩小 {} MB，不足以创建 {} MB 的恢复分区，请清理磁盘或进行碎片整理后重试",
+            system_drive,
+            max_shrink_mb,
+            size_mb
+        );
+    }
+
+    let letter = DiskManager::find_available_drive_letter().context("没有可用的盘符")?;
+    run_diskpart(&create_partition_script(system_letter, size_mb, letter))?;
+
+    let root = format!("{}:\\", letter);
+    for _ in 0..10 {
+        if Path::new(&root).exists() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+    let volume = DiskManager::volume_guid_path(&format!("{}:", letter))
+        .with_context(|| format!("无法访问新建的恢复分区 {}:", letter))?;
+    DiskManager::remove_drive_letter(letter)?;
+    Ok(volume.trim_end_matches('\\').to_string())
+}
+
+/// 新建的恢复分区，安装完成前出错时删除并把空间合并回系统分区
+///
+/// 安装信息在最后才写入，中途失败留下的分区无法被 `detect` 识别，重试时会再缩小一次系统分区。
+struct NewPartition {
+    volume: String,
+    completed: bool,
+}
+
+impl NewPartition {
+    fn create(size_mb: u64) -> Result<Self> {
+        Ok(Self { volume: create_partition(size_mb)?, completed: false })
+    }
+
+    /// 安装完成，保留分区
+    fn keep(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for NewPartition {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        log::warn!("[RECOVERY] 安装未完成，删除新建的恢复分区 {}", self.volume);
+        let (Some(disk), Some(partition)) = DiskManager::partition_number_of(&self.volume) else {
+            log::warn!("[RECOVERY] 无法确定新建恢复分区的分区号，请手动删除");
+            return;
+        };
+        let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        let mergeable = follows_system_partition(disk, partition, &system_drive);
+        if let Err(e) = disk_native::delete_partition(disk, partition) {
+            log::warn!("[RECOVERY] 删除新建的恢复分区失败: {}", e);
+            return;
+        }
+        if mergeable {
+            if let Err(e) = extend_system_partition(&system_drive) {
+                log::warn!("[RECOVERY] 合并空间到 {} 失败: {:#}", system_drive, e);
+            }
+        }
+    }
+}
+
+/// 把系统分区扩展到其后的未分配空间
+fn extend_system_partition(system_drive: &str) -> Result<String> {
+    run_diskpart(&format!("select volume {}\nextend\n", system_drive.chars().next().unwrap_or('C')))
+}
+
+/// 隐藏恢复分区：设置为恢复分区类型，不再自动分配盘符
+fn hide_partition(volume: &str) -> Result<()> {
+    let (Some(disk), Some(partition)) = DiskManager::partition_number_of(volume) else {
+        bail!("无法确定恢复分区的分区号");
+    };
+    if DiskManager::get_gpt_partition_info(volume).is_some() {
+        let partition_type = GptPartitionType::Recovery;
+        DiskManager::set_partition_type(disk, partition, partition_type)?;
+        DiskManager::set_partition_attributes(disk, partition, partition_type.default_attributes())
+    } else {
+        DiskManager::set_mbr_partition_id(disk, partition, MBR_RECOVERY_ID)
+    }
+}
+
+/// 删除启动菜单项和 F11 热键，单项失败只记录日志
+fn remove_boot_entries(info: &RecoveryInfo) {
+    for guid in [&info.loader_guid, &info.ramdisk_guid] {
+        if !guid.is_empty() {
+            if let Err(e) = bcdedit(&["/delete", guid, "/f"]) {
+                log::warn!("[RECOVERY] {:#}", e);
+            }
+        }
+    }
+    if info.hotkey {
+        if let Err(e) = set_custom_actions(&merge_custom_actions(&current_custom_actions(), false)) {
+            log::warn!("[RECOVERY] {:#}", e);
+        }
+        let _ = bcdedit(&["/deletevalue", "{bootmgr}", HOTKEY_ELEMENT]);
+    }
+}
+
+/// 创建从恢复分区启动 PE 的引导项，返回 (ramdisk GUID, loader GUID)
+fn create_boot_entries(drive: &str, display_name: &str, hotkey: bool) -> Result<(String, String)> {
+    let ramdisk_guid = PeManager::extract_guid(&bcdedit(&[
+        "/create",
+        "/d",
+        &format!("{} RAM", display_name),
+        "/device",
+    ])?)?;
+    let result = (|| -> Result<String> {
+        bcdedit(&["/set", &ramdisk_guid, "ramdisksdidevice", &format!("partition={}", drive)])?;
+        bcdedit(&["/set", &ramdisk_guid, "ramdisksdipath", &format!("\\{}\\boot.sdi", RECOVERY_DIR)])?;
+        let loader_guid =
+            PeManager::extract_guid(&bcdedit(&["/create", "/d", display_name, "/application", "osloader"])?)?;
+        Ok(loader_guid)
+    })();
+    let loader_guid = match result {
+        Ok(guid) => guid,
+        Err(e) => {
+            let _ = bcdedit(&["/delete", &ramdisk_guid, "/f"]);
+            return Err(e);
+        }
+    };
+
+    let result = (|| -> Result<()> {
+        let winload = if PeManager::is_uefi_boot() {
+            "\\windows\\system32\\boot\\winload.efi"
+        } else {
+            "\\windows\\system32\\boot\\winload.exe"
+        };
+        let device = format!("ramdisk=[{}]\\{}\\boot.wim,{}", drive, RECOVERY_DIR, ramdisk_guid);
+        bcdedit(&["/set", &loader_guid, "device", &device])?;
+        bcdedit(&["/set", &loader_guid, "osdevice", &device])?;
+        bcdedit(&["/set", &loader_guid, "path", winload])?;
+        bcdedit(&["/set", &loader_guid, "systemroot", "\\windows"])?;
+        bcdedit(&["/set", &loader_guid, "detecthal", "yes"])?;
+        bcdedit(&["/set", &loader_guid, "winpe", "yes"])?;
+        bcdedit(&["/set", &loader_guid, "ems", "no"])?;
+        bcdedit(&["/displayorder", &loader_guid, "/addlast"])?;
+
+        let timeout = bcdedit(&["/enum", "{bootmgr}"]).ok().and_then(|output| parse_boot_timeout(&output));
+        if timeout.map_or(true, |t| t < MIN_BOOT_TIMEOUT) {
+            bcdedit(&["/timeout", &MIN_BOOT_TIMEOUT.to_string()])?;
+        }
+
+        if hotkey {
+            set_custom_actions(&merge_custom_actions(&current_custom_actions(), true))?;
+            bcdedit(&["/set", "{bootmgr}", HOTKEY_ELEMENT, &loader_guid])?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        remove_boot_entries(&RecoveryInfo {
+            ramdisk_guid,
+            loader_guid,
+            hotkey,
+            ..Default::default()
+        });
+        return Err(e);
+    }
+    Ok((ramdisk_guid, loader_guid))
+}
+
+fn system_winre_dir() -> String {
+    let windir = std::env::var("windir").unwrap_or_else(|_| "C:\\Windows".to_string());
+    format!("{}\\System32\\Recovery", windir)
+}
+
+/// 把恢复分区中的 PE 注册为 Windows RE（先备份系统自带的 Winre.wim）
+fn register_winre(drive: &str) -> Result<()> {
+    let target_dir = format!("{}\\Recovery\\WindowsRE", drive);
+    std::fs::create_dir_all(&target_dir)?;
+    std::fs::copy(
+        format!("{}\\{}\\boot.wim", drive, RECOVERY_DIR),
+        format!("{}\\Winre.wim", target_dir),
+    )
+    .context("复制 Winre.wim 失败")?;
+
+    // 禁用后系统会把当前的 Winre.wim 移回 System32\Recovery
+    let _ = reagentc(&["/disable"]);
+    let system_winre = format!("{}\\Winre.wim", system_winre_dir());
+    let backup = format!("{}{}", system_winre, WINRE_BACKUP_SUFFIX);
+    if Path::new(&system_winre).exists() && !Path::new(&backup).exists() {
+        std::fs::copy(&system_winre, &backup).context("备份系统 Winre.wim 失败")?;
+    }
+
+    let result = reagentc(&["/setreimage", "/path", &target_dir]).and_then(|_| reagentc(&["/enable"]));
+    if let Err(e) = result {
+        log::warn!("[RECOVERY] 注册 Windows RE 失败，恢复原设置: {:#}", e);
+        restore_winre();
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// 恢复系统自带的 Windows RE，失败时只记录日志
+fn restore_winre() {
+    let _ = reagentc(&["/disable"]);
+    let dir = system_winre_dir();
+    let system_winre = format!("{}\\Winre.wim", dir);
+    let backup = format!("{}{}", system_winre, WINRE_BACKUP_SUFFIX);
+    if Path::new(&backup).exists() {
+        if Path::new(&system_winre).exists() {
+            let _ = std::fs::remove_file(&backup);
+        } else if let Err(e) = std::fs::rename(&backup, &system_winre) {
+            log::warn!("[RECOVERY] 还原系统 Winre.wim 失败: {}", e);
+        }
+    }
+    if Path::new(&system_winre).exists() {
+        let result = reagentc(&["/setreimage", "/path", &dir]).and_then(|_| reagentc(&["/enable"]));
+        if let Err(e) = result {
+            log::warn!("[RECOVERY] 重新启用系统 Windows RE 失败: {:#}", e);
+        }
+    }
+}
+
+/// 安装或更新一键恢复环境
+pub fn install(options: &RecoveryInstallOptions, progress: Sender<DismProgress>) -> Result<String> {
+    options.validate().map_err(anyhow::Error::msg)?;
+    let report = |percentage: u8, status: &str| {
+        let _ = progress.send(DismProgress { percentage, status: status.to_string() });
+    };
+    let display_name = options.display_name.trim();
+
+    report(5, "准备 PE 文件...");
+    let pe = PeFiles::open(&options.pe_path)?;
+    let wim_mb = std::fs::metadata(&pe.wim).map(|m| m.len()).unwrap_or(0) / 1024 / 1024;
+
+    let existing = detect();
+    let (volume, size_mb, new_partition) = match existing {
+        Some(ref status) => {
+            // 更新时覆盖原有文件，放不下时保留原来的恢复环境
+            if wim_mb + 64 > status.info.size_mb {
+                bail!(
+                    "PE 文件约 {} MB，现有恢复分区只有 {} MB，请卸载后以更大的分区重新安装",
+                    wim_mb,
+                    status.info.size_mb
+                );
+            }
+            BootManager::new().backup_before_change(None, "更新一键恢复前");
+            (status.volume.clone(), status.info.size_mb, None)
+        }
+        None => {
+            if wim_mb + 64 > options.size_mb {
+                bail!("PE 文件约 {} MB，恢复分区至少需要 {} MB", wim_mb, wim_mb + 64);
+            }
+            report(15, "创建恢复分区...");
+            BootManager::new().backup_before_change(None, "安装一键恢复前");
+            let partition = NewPartition::create(options.size_mb)?;
+            (partition.volume.clone(), options.size_mb, Some(partition))
+        }
+    };
+
+    let mut info = RecoveryInfo {
+        display_name: display_name.to_string(),
+        pe_file: Path::new(&options.pe_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        installed_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        size_mb,
+        hotkey: options.hotkey,
+        ..Default::default()
+    };
+
+    let mut warning = None;
+    {
+        let guard = TempDriveLetter::acquire(&volume)?;
+        let drive = guard.drive();
+        let target_dir = format!("{}\\{}", drive, RECOVERY_DIR);
+        std::fs::create_dir_all(&target_dir)?;
+
+        report(30, "复制 PE 文件...");
+        std::fs::copy(&pe.wim, format!("{}\\boot.wim", target_dir))
+            .context("复制 boot.wim 失败，恢复分区空间可能不足")?;
+        match pe.sdi {
+            Some(ref sdi) => {
+                std::fs::copy(sdi, format!("{}\\boot.sdi", target_dir)).context("复制 boot.sdi 失败")?;
+            }
+            None => {
+                PeManager::new().create_default_sdi(&target_dir)?;
+            }
+        }
+
+        // 新文件复制完成后才移除旧的启动菜单项
+        if let Some(ref status) = existing {
+            report(60, "移除旧的启动菜单项...");
+            remove_boot_entries(&status.info);
+        }
+
+        report(70, "创建启动菜单项...");
+        let (ramdisk_guid, loader_guid) = create_boot_entries(&drive, display_name, options.hotkey)?;
+        info.ramdisk_guid = ramdisk_guid;
+        info.loader_guid = loader_guid;
+
+        if existing.as_ref().is_some_and(|s| s.info.winre) && !options.winre {
+            restore_winre();
+        }
+        if options.winre {
+            report(80, "注册 Windows RE...");
+            match register_winre(&drive) {
+                Ok(_) => info.winre = true,
+                Err(e) => warning = Some(format!("注册 Windows RE 失败: {:#}", e)),
+            }
+        }
+
+        let written = serde_json::to_string_pretty(&info)
+            .context("序列化安装信息失败")
+            .and_then(|content| std::fs::write(info_path(&drive), content).context("写入安装信息失败"));
+        if let Err(e) = written {
+            remove_boot_entries(&info);
+            if info.winre {
+                restore_winre();
+            }
+            return Err(e);
+        }
+    }
+    if let Some(partition) = new_partition {
+        partition.keep();
+    }
+
+    report(90, "隐藏恢复分区...");
+    if let Err(e) = hide_partition(&volume) {
+        log::warn!("[RECOVERY] 隐藏恢复分区失败: {:#}", e);
+        warning.get_or_insert_with(|| format!("隐藏恢复分区失败: {:#}", e));
+    }
+
+    report(100, "完成");
+    let hotkey = if options.hotkey { "，开机按 F11 或" } else { "，开机时" };
+    let message = format!("已安装一键恢复环境{}在启动菜单中选择「{}」即可进入", hotkey, display_name);
+    Ok(match warning {
+        Some(w) => format!("{}（{}）", message, w),
+        None => message,
+    })
+}
+
+/// 恢复分区是否与系统分区在同一磁盘上且紧跟其后
+fn follows_system_partition(disk: u32, recovery_number: u32, system_drive: &str) -> bool {
+    let (Some(system_disk), Some(system_number)) = DiskManager::partition_number_of(system_drive) else {
+        return false;
+    };
+    if system_disk != disk {
+        return false;
+    }
+    match disk_native::read_partitions(disk) {
+        Ok((_, partitions)) => directly_follows(&partitions, system_number, recovery_number),
+        Err(e) => {
+            log::warn!("[RECOVERY] 读取磁盘 {} 分区表失败: {}", disk, e);
+            false
+        }
+    }
+}
+
+/// 卸载一键恢复环境：删除启动菜单项和恢复分区，可选把空间合并回系统分区
+pub fn remove(status: &RecoveryStatus, extend_system: bool) -> Result<String> {
+    BootManager::new().backup_before_change(None, "卸载一键恢复前");
+    remove_boot_entries(&status.info);
+    if status.info.winre {
+        restore_winre();
+    }
+
+    let (Some(disk), Some(partition)) = DiskManager::partition_number_of(&status.volume) else {
+        bail!("无法确定恢复分区的分区号");
+    };
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    let mergeable = extend_system && follows_system_partition(disk, partition, &system_drive);
+    disk_native::delete_partition(disk, partition).map_err(|e| anyhow::anyhow!("删除恢复分区失败: {}", e))?;
+
+    if !extend_system {
+        return Ok("已卸载一键恢复环境".to_string());
+    }
+    if !mergeable {
+        return Ok(format!(
+            "已卸载一键恢复环境，恢复分区不紧跟在 {} 之后，空间保留为未分配",
+            system_drive
+        ));
+    }
+    Ok(match extend_system_partition(&system_drive) {
+        Ok(_) => format!("已卸载一键恢复环境，空间已合并到 {}", system_drive),
+        Err(e) => format!("已卸载一键恢复环境，但合并空间到 {} 失败: {:#}", system_drive, e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_partition_script() {
+        let script = create_partition_script('C', 2048, 'R');
+        assert!(script.starts_with("select volume C\n"));
+        assert!(script.contains("shrink desired=2048 minimum=2048\n"));
+        assert!(script.contains("create partition primary size=2048\n"));
+        assert!(script.contains("label=\"LETRECOVERY_RE\""));
+        assert!(script.ends_with("assign letter=R\n"));
+    }
+
+    #[test]
+    fn test_parse_boot_timeout() {
+        let output = "Windows 启动管理器\n--------------------\nidentifier              {bootmgr}\n\
+            displayorder            {current}\ntimeout                 30\n";
+        assert_eq!(parse_boot_timeout(output), Some(30));
+        assert_eq!(parse_boot_timeout("timeout 0"), Some(0));
+        assert_eq!(parse_boot_timeout("identifier {bootmgr}"), None);
+    }
+
+    #[test]
+    fn test_custom_actions() {
+        let output = "identifier              {bootmgr}\n\
+            customactions           0x1000044000001\n\
+            \x20                       0x54000001\n\
+            custom:54000001         {a1b2c3d4-0000-0000-0000-000000000000}\n\
+            timeout                 30\n";
+        let existing = parse_list_element(output, "customactions");
+        assert_eq!(existing, vec!["0x1000044000001", "0x54000001"]);
+        assert!(parse_list_element("timeout 30", "customactions").is_empty());
+
+        let added = merge_custom_actions(&existing, true);
+        assert_eq!(added, vec!["0x1000044000001", "0x54000001", "0x1000085000001", "0x5400000f"]);
+        // 重复安装不重复添加，卸载只移除 F11
+        assert_eq!(merge_custom_actions(&added, true), added);
+        assert_eq!(merge_custom_actions(&added, false), existing);
+        assert!(merge_custom_actions(&merge_custom_actions(&[], true), false).is_empty());
+    }
+
+    #[test]
+    fn test_partition_layout_checks() {
+        let partition = |number: u32, offset: u64, length: u64, primary: bool| ExistingPartition {
+            number,
+            extent: disk_native::PartitionExtent { offset, length },
+            primary,
+        };
+        let layout = vec![
+            partition(1, 1, 100, true),
+            partition(2, 101, 800, true),
+            partition(3, 901, 50, true),
+        ];
+        assert!(directly_follows(&layout, 2, 3));
+        assert!(!directly_follows(&layout, 1, 3));
+        assert!(!directly_follows(&layout, 3, 2));
+        assert!(!directly_follows(&layout, 9, 3));
+
+        assert!(!mbr_primary_full(PartitionStyle::MBR, &layout));
+        let mut full = layout.clone();
+        full.push(partition(0, 951, 40, true));
+        assert!(mbr_primary_full(PartitionStyle::MBR, &full));
+        assert!(!mbr_primary_full(PartitionStyle::GPT, &full));
+        // 逻辑分区不占用主分区表项
+        full[3].primary = false;
+        assert!(!mbr_primary_full(PartitionStyle::MBR, &full));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut options = RecoveryInstallOptions {
+            pe_path: "D:\\PE\\LetRecovery.wim".to_string(),
+            ..Default::default()
+        };
+        assert!(options.validate().is_ok());
+
+        options.pe_path = "D:\\PE\\LetRecovery.ISO".to_string();
+        assert!(options.validate().is_ok());
+
+        options.pe_path = "D:\\PE\\LetRecovery.esd".to_string();
+        assert!(options.validate().is_err());
+
+        options.pe_path = "D:\\PE\\LetRecovery.wim".to_string();
+        options.size_mb = 512;
+        assert!(options.validate().is_err());
+
+        options.size_mb = DEFAULT_SIZE_MB;
+        options.display_name = "  ".to_string();
+        assert!(options.validate().is_err());
+    }
+}
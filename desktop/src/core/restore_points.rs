@@ -0,0 +1,133 @@
+//! 还原点
+//!
+//! 按镜像中的分卷列出备份链，匹配备份目录（`core::backup_catalog`）中的记录；
+//! 目录与镜像不一致时（镜像被其他工具修改），以镜像中的分卷为准，按名称匹配备份记录。
+//! 删除还原点时删除镜像中的分卷并同步更新目录。
+
+use anyhow::{bail, Result};
+use std::sync::mpsc::Sender;
+
+use crate::core::backup_catalog::{BackupCatalog, CatalogEntry};
+use crate::core::dism::DismProgress;
+use crate::core::wim_manage::{self, WimImageEntry};
+
+/// 还原点：镜像中的一个分卷及其备份记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestorePoint {
+    pub index: u32,
+    pub name: String,
+    /// 展开大小（字节）
+    pub total_bytes: u64,
+    /// 对应的备份记录，不是由本程序备份的分卷为 None
+    pub entry: Option<CatalogEntry>,
+}
+
+impl RestorePoint {
+    /// 备份时间（本地时间），未知时返回 "未知"
+    pub fn created_at_text(&self) -> String {
+        self.entry
+            .as_ref()
+            .and_then(|entry| chrono::DateTime::from_timestamp(entry.created_at as i64, 0))
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "未知".to_string())
+    }
+}
+
+impl BackupCatalog {
+    /// 按镜像中的分卷生成还原点列表，优先按索引和名称匹配记录，其次只按名称匹配
+    pub fn restore_points(&self, images: &[WimImageEntry]) -> Vec<RestorePoint> {
+        let same_name = |entry: &CatalogEntry, image: &WimImageEntry| entry.name.trim().eq_ignore_ascii_case(image.name.trim());
+        images
+            .iter()
+            .map(|image| {
+                let entry = self
+                    .entries
+                    .iter()
+                    .find(|entry| entry.index == image.index && same_name(entry, image))
+                    .or_else(|| self.entries.iter().find(|entry| same_name(entry, image)))
+                    .cloned();
+                RestorePoint {
+                    index: image.index,
+                    name: image.name.clone(),
+                    total_bytes: image.total_bytes,
+                    entry,
+                }
+            })
+            .collect()
+    }
+}
+
+/// 删除还原点（删除镜像中的分卷并更新目录），进度通过 progress_tx 发送
+pub fn delete_restore_point(
+    image_file: &str,
+    images: &[WimImageEntry],
+    index: u32,
+    progress_tx: Sender<DismProgress>,
+) -> Result<()> {
+    delete_restore_points(image_file, images, &[index], progress_tx)
+}
+
+/// 一次删除多个还原点，镜像只重写一次
+pub fn delete_restore_points(
+    image_file: &str,
+    images: &[WimImageEntry],
+    indices: &[u32],
+    progress_tx: Sender<DismProgress>,
+) -> Result<()> {
+    for &index in indices {
+        wim_manage::check_deletable(images, index)?;
+    }
+    if indices.len() >= images.len() {
+        bail!("不能删除镜像中的全部分卷");
+    }
+    wim_manage::delete_images(image_file, indices, progress_tx)?;
+
+    if let Some(mut catalog) = BackupCatalog::load(image_file)? {
+        // 从大到小移除，前面条目的索引不会变化
+        let mut indices = indices.to_vec();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        for index in indices {
+            catalog.remove(index);
+        }
+        catalog.save(image_file)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: u32, name: &str) -> CatalogEntry {
+        CatalogEntry {
+            index,
+            name: name.to_string(),
+            created_at: 1_700_000_000 + index as u64,
+            ..Default::default()
+        }
+    }
+
+    fn image(index: u32, name: &str) -> WimImageEntry {
+        WimImageEntry {
+            index,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_restore_points() {
+        let catalog = BackupCatalog {
+            schema_version: 1,
+            entries: vec![entry(1, "base"), entry(3, "weekly")],
+        };
+        // 分卷 2 被其他工具删除后 weekly 变为分卷 2，另有一个外部添加的分卷
+        let images = [image(1, "base"), image(2, "Weekly"), image(3, "imported")];
+        let points = catalog.restore_points(&images);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0].entry.as_ref().map(|e| e.index), Some(1));
+        assert_eq!(points[1].entry.as_ref().map(|e| e.name.as_str()), Some("weekly"));
+        assert!(points[2].entry.is_none());
+        assert_eq!(points[2].created_at_text(), "未知");
+    }
+}
@@ -0,0 +1,631 @@
+//! 定时自动备份
+//!
+//! 通过 schtasks 注册以 SYSTEM 身份运行的计划任务，按设定的周期以 `--scheduled-backup` 启动本程序。
+//! 定时备份把分区追加到同一个 WIM 镜像（首次为新建），正在运行的系统分区通过卷影副本捕获；
+//! 备份完成后记录还原点，并按保留数量删除最旧的定时备份分卷（手动备份的分卷不受影响）。
+//! 计划设置保存在 config.json。
+//!
+//! 计划任务以 SYSTEM 身份运行，不能直接启动便携目录（普通用户可写）中的程序，也不能读取那里的配置，
+//! 否则任何用户替换程序或修改配置都可以获得 SYSTEM 权限。注册时把程序和本次的备份参数复制到
+//! ProgramData 下只允许 SYSTEM 和管理员访问的目录，计划任务只使用该目录中的文件，
+//! 最近一次运行结果也写在该目录中。
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use crate::core::backup_catalog;
+use crate::core::capture_options::CaptureOptions;
+use crate::core::dism::{Dism, DismProgress};
+use crate::core::io_priority::{BackgroundModeGuard, IoPriority};
+use crate::core::restore_points;
+use crate::core::wim_manage::{self, WimImageEntry};
+use crate::core::wimlib::ImageBackend;
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+use crate::utils::path::get_exe_dir;
+
+/// 定时备份命令行参数
+pub const SCHEDULED_BACKUP_ARG: &str = "--scheduled-backup";
+/// 计划任务名
+const TASK_NAME: &str = "LetRecoveryScheduledBackup";
+/// 定时备份的分卷名前缀，保留策略只清理带此前缀的分卷
+pub const SCHEDULED_NAME_PREFIX: &str = "定时备份";
+/// 最近一次运行结果文件（位于计划任务目录）
+const STATUS_FILE: &str = "scheduled_backup.json";
+/// 计划任务使用的备份参数文件（位于计划任务目录）
+const TASK_FILE: &str = "task.json";
+/// schtasks /TR 参数的最大长度
+const MAX_TASK_COMMAND_LEN: usize = 261;
+
+/// 星期（schtasks /D 的取值）
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("MON", "星期一"),
+    ("TUE", "星期二"),
+    ("WED", "星期三"),
+    ("THU", "星期四"),
+    ("FRI", "星期五"),
+    ("SAT", "星期六"),
+    ("SUN", "星期日"),
+];
+
+/// 备份周期
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduleFrequency {
+    Hourly,
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl ScheduleFrequency {
+    pub const ALL: [ScheduleFrequency; 3] =
+        [ScheduleFrequency::Hourly, ScheduleFrequency::Daily, ScheduleFrequency::Weekly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Hourly => "每隔若干小时",
+            ScheduleFrequency::Daily => "每隔若干天",
+            ScheduleFrequency::Weekly => "每隔若干周",
+        }
+    }
+
+    /// 间隔单位
+    pub fn unit(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Hourly => "小时",
+            ScheduleFrequency::Daily => "天",
+            ScheduleFrequency::Weekly => "周",
+        }
+    }
+
+    /// schtasks /MO 允许的最大间隔
+    pub fn max_interval(&self) -> u32 {
+        match self {
+            ScheduleFrequency::Hourly => 23,
+            ScheduleFrequency::Daily => 365,
+            ScheduleFrequency::Weekly => 52,
+        }
+    }
+
+    fn schtasks_value(&self) -> &'static str {
+        match self {
+            ScheduleFrequency::Hourly => "HOURLY",
+            ScheduleFrequency::Daily => "DAILY",
+            ScheduleFrequency::Weekly => "WEEKLY",
+        }
+    }
+}
+
+/// 定时备份设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BackupSchedule {
+    /// 是否启用（关闭时删除计划任务）
+    pub enabled: bool,
+    /// 源分区盘符（如 C:）
+    pub source_partition: String,
+    /// 备份镜像路径（WIM），每次备份追加为一个新分卷
+    pub image_path: String,
+    pub frequency: ScheduleFrequency,
+    /// 间隔（每隔几小时/天/周）
+    pub interval: u32,
+    /// 开始时间（HH:MM）
+    pub start_time: String,
+    /// 每周备份的星期（0 = 星期一）
+    pub weekday: u8,
+    /// 保留的定时备份数量（0 表示不限制）
+    pub keep_count: u32,
+    pub capture: CaptureOptions,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source_partition: String::new(),
+            image_path: String::new(),
+            frequency: ScheduleFrequency::default(),
+            interval: 1,
+            start_time: "03:00".to_string(),
+            weekday: 0,
+            keep_count: 7,
+            capture: CaptureOptions::default(),
+        }
+    }
+}
+
+impl BackupSchedule {
+    /// 星期的显示名称
+    pub fn weekday_labels() -> impl Iterator<Item = &'static str> {
+        WEEKDAYS.iter().map(|(_, label)| *label)
+    }
+
+    /// 校验设置
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.source_partition.trim().is_empty() {
+            return Err("请选择要备份的分区".to_string());
+        }
+        let image_path = self.image_path.trim();
+        if image_path.is_empty() {
+            return Err("请设置备份镜像的保存位置".to_string());
+        }
+        if !image_path.to_ascii_lowercase().ends_with(".wim") {
+            return Err("定时备份只支持 WIM 格式".to_string());
+        }
+        if image_path.starts_with("\\\\") {
+            return Err("计划任务以 SYSTEM 身份运行，无法访问网络路径，请保存到本地磁盘".to_string());
+        }
+        let source = self.source_partition.trim().trim_end_matches('\\');
+        if image_path.get(..source.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(source)) {
+            return Err("备份镜像不能保存在要备份的分区上".to_string());
+        }
+        if self.interval == 0 || self.interval > self.frequency.max_interval() {
+            return Err(format!(
+                "间隔必须在 1 到 {} {}之间",
+                self.frequency.max_interval(),
+                self.frequency.unit()
+            ));
+        }
+        if parse_start_time(&self.start_time).is_none() {
+            return Err(format!("开始时间格式错误（应为 HH:MM）: {}", self.start_time));
+        }
+        if usize::from(self.weekday) >= WEEKDAYS.len() {
+            return Err(format!("星期设置无效: {}", self.weekday));
+        }
+        self.capture.validate()
+    }
+
+    /// schtasks /Create 的参数
+    fn create_task_args(&self, exe_path: &Path) -> Result<Vec<String>> {
+        let command = format!("\"{}\" {}", exe_path.display(), SCHEDULED_BACKUP_ARG);
+        if command.chars().count() > MAX_TASK_COMMAND_LEN {
+            bail!("程序路径过长，无法注册计划任务: {}", exe_path.display());
+        }
+        let (hour, minute) =
+            parse_start_time(&self.start_time).with_context(|| format!("开始时间格式错误: {}", self.start_time))?;
+
+        let mut args: Vec<String> = [
+            "/Create",
+            "/TN",
+            TASK_NAME,
+            "/SC",
+            self.frequency.schtasks_value(),
+            "/MO",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.push(self.interval.to_string());
+        args.push("/ST".to_string());
+        args.push(format!("{:02}:{:02}", hour, minute));
+        if self.frequency == ScheduleFrequency::Weekly {
+            args.push("/D".to_string());
+            args.push(WEEKDAYS[usize::from(self.weekday)].0.to_string());
+        }
+        args.extend(["/RU", "SYSTEM", "/RL", "HIGHEST", "/F", "/TR"].iter().map(|s| s.to_string()));
+        args.push(command);
+        Ok(args)
+    }
+
+    /// 本次定时备份的分卷名称
+    fn volume_name(&self) -> String {
+        format!("{} {}", SCHEDULED_NAME_PREFIX, chrono::Local::now().format("%Y-%m-%d %H:%M"))
+    }
+}
+
+/// 解析 HH:MM 格式的开始时间
+pub fn parse_start_time(text: &str) -> Option<(u8, u8)> {
+    let (hour, minute) = text.trim().split_once(':')?;
+    let hour: u8 = hour.trim().parse().ok()?;
+    let minute: u8 = minute.trim().parse().ok()?;
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+/// 按保留数量选出要删除的定时备份分卷（最旧的优先），按索引从大到小返回，
+/// 依次删除时前面的分卷索引不会变化
+pub fn prune_candidates(images: &[WimImageEntry], keep_count: u32) -> Vec<u32> {
+    if keep_count == 0 {
+        return Vec::new();
+    }
+    let mut scheduled: Vec<u32> = images
+        .iter()
+        .filter(|image| image.name.starts_with(SCHEDULED_NAME_PREFIX))
+        .map(|image| image.index)
+        .collect();
+    scheduled.sort_unstable();
+    let excess = scheduled.len().saturating_sub(keep_count as usize);
+    let mut candidates: Vec<u32> = scheduled.into_iter().take(excess).collect();
+    candidates.reverse();
+    candidates
+}
+
+/// 计划任务使用的程序和参数所在目录（%ProgramData%\LetRecovery\ScheduledBackup）
+pub fn task_dir() -> PathBuf {
+    let program_data = std::env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".to_string());
+    Path::new(&program_data).join("LetRecovery").join("ScheduledBackup")
+}
+
+/// 计划任务执行的备份参数（注册时从界面设置生成，运行时不再读取 config.json）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ScheduledTask {
+    pub schedule: BackupSchedule,
+    pub image_backend: ImageBackend,
+    pub io_priority: IoPriority,
+}
+
+impl ScheduledTask {
+    /// 读取计划任务目录中的备份参数
+    pub fn load() -> Result<Self> {
+        let path = task_dir().join(TASK_FILE);
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("读取备份参数失败: {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("解析备份参数失败: {}", path.display()))
+    }
+}
+
+/// 把程序和备份参数复制到计划任务目录，返回计划任务启动的程序路径
+fn install_task_files(task: &ScheduledTask) -> Result<PathBuf> {
+    let dir = task_dir();
+    let status = ScheduleStatus::load();
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .with_context(|| format!("清理计划任务目录失败（定时备份可能正在运行）: {}", dir.display()))?;
+    }
+    std::fs::create_dir_all(&dir).with_context(|| format!("创建计划任务目录失败: {}", dir.display()))?;
+    crate::core::secure_dir::restrict_to_admins(&dir)?;
+
+    let exe_path = std::env::current_exe().context("无法获取程序路径")?;
+    let exe_name = exe_path.file_name().context("无法获取程序文件名")?;
+    let task_exe = dir.join(exe_name);
+    std::fs::copy(&exe_path, &task_exe).with_context(|| format!("复制程序失败: {}", task_exe.display()))?;
+    // wimlib.dll 等程序目录中的动态库
+    for entry in std::fs::read_dir(get_exe_dir()).context("读取程序目录失败")?.flatten() {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("dll")) {
+            std::fs::copy(&path, dir.join(entry.file_name()))
+                .with_context(|| format!("复制失败: {}", path.display()))?;
+        }
+    }
+
+    let content = serde_json::to_string_pretty(task).context("序列化备份参数失败")?;
+    std::fs::write(dir.join(TASK_FILE), content).context("写入备份参数失败")?;
+    if let Some(status) = status {
+        status.save()?;
+    }
+    Ok(task_exe)
+}
+
+/// 计划任务是否已注册
+pub fn is_task_registered() -> bool {
+    create_command("schtasks.exe")
+        .args(["/Query", "/TN", TASK_NAME])
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+fn run_schtasks(args: &[String]) -> Result<()> {
+    log::info!("[SCHEDULER] schtasks {}", args.join(" "));
+    let output = create_command("schtasks.exe")
+        .args(args)
+        .output()
+        .context("无法执行 schtasks")?;
+    if !output.status.success() {
+        let stderr = gbk_to_utf8(&output.stderr);
+        let stdout = gbk_to_utf8(&output.stdout);
+        let message = if stderr.trim().is_empty() { stdout } else { stderr };
+        bail!("schtasks 执行失败: {}", message.trim());
+    }
+    Ok(())
+}
+
+/// 按设置注册或删除计划任务（已注册的任务会被覆盖）
+pub fn apply_schedule(task: &ScheduledTask) -> Result<()> {
+    let schedule = &task.schedule;
+    if !schedule.enabled {
+        return remove_task();
+    }
+    schedule.validate().map_err(anyhow::Error::msg)?;
+    let exe_path = install_task_files(task)?;
+    run_schtasks(&schedule.create_task_args(&exe_path)?)?;
+    log::info!("[SCHEDULER] 已注册定时备份计划任务");
+    Ok(())
+}
+
+/// 删除计划任务和计划任务目录（未注册时忽略）
+pub fn remove_task() -> Result<()> {
+    if is_task_registered() {
+        run_schtasks(&["/Delete", "/TN", TASK_NAME, "/F"].map(String::from))?;
+        log::info!("[SCHEDULER] 已删除定时备份计划任务");
+    }
+    let dir = task_dir();
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).with_context(|| format!("删除计划任务目录失败: {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// 立即运行计划任务
+pub fn run_task_now() -> Result<()> {
+    run_schtasks(&["/Run", "/TN", TASK_NAME].map(String::from))
+}
+
+/// 最近一次定时备份的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ScheduleStatus {
+    /// 运行时间（Unix 时间戳，秒）
+    pub last_run_at: u64,
+    pub success: bool,
+    pub message: String,
+}
+
+impl ScheduleStatus {
+    fn path() -> PathBuf {
+        task_dir().join(STATUS_FILE)
+    }
+
+    /// 读取最近一次运行结果，从未运行时返回 None
+    pub fn load() -> Option<Self> {
+        let content = std::fs::read_to_string(Self::path()).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path();
+        let content = serde_json::to_string_pretty(self).context("序列化运行结果失败")?;
+        std::fs::write(&path, content).with_context(|| format!("写入运行结果失败: {}", path.display()))
+    }
+
+    /// 运行时间（本地时间）
+    pub fn last_run_text(&self) -> String {
+        chrono::DateTime::from_timestamp(self.last_run_at as i64, 0)
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "未知".to_string())
+    }
+}
+
+/// 卷影副本，Drop 时删除
+struct ShadowCopy {
+    id: String,
+    /// 卷影副本的设备路径（如 \\?\GLOBALROOT\Device\HarddiskVolumeShadowCopy3）
+    device_object: String,
+}
+
+impl ShadowCopy {
+    /// 为分区创建卷影副本
+    fn create(partition: &str) -> Result<Self> {
+        let volume = crate::core::disk::volume_root(partition).replace('\'', "''");
+        let script = format!(
+            "$r = Invoke-CimMethod -ClassName Win32_ShadowCopy -MethodName Create \
+             -Arguments @{{Volume='{}'; Context='ClientAccessible'}}; \
+             if ($r.ReturnValue -ne 0) {{ Write-Output \"ERROR $($r.ReturnValue)\"; exit 1 }}; \
+             $s = Get-CimInstance Win32_ShadowCopy -Filter \"ID='$($r.ShadowID)'\"; \
+             Write-Output $r.ShadowID; Write-Output $s.DeviceObject",
+            volume
+        );
+        let output = create_command("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .output()
+            .context("无法执行 PowerShell")?;
+        let stdout = gbk_to_utf8(&output.stdout);
+        let mut lines = stdout.lines().map(str::trim).filter(|line| !line.is_empty());
+        let (Some(id), Some(device_object)) = (lines.next(), lines.next()) else {
+            bail!("创建卷影副本失败: {}", stdout.trim());
+        };
+        if !output.status.success() || !device_object.starts_with("\\\\?\\") {
+            bail!("创建卷影副本失败: {}", stdout.trim());
+        }
+        log::info!("[SCHEDULER] 已创建卷影副本 {} -> {}", id, device_object);
+        Ok(Self {
+            id: id.to_string(),
+            device_object: device_object.to_string(),
+        })
+    }
+}
+
+impl Drop for ShadowCopy {
+    fn drop(&mut self) {
+        let script = format!(
+            "Get-CimInstance Win32_ShadowCopy -Filter \"ID='{}'\" | Remove-CimInstance",
+            self.id.replace('\'', "")
+        );
+        match create_command("powershell").args(["-NoProfile", "-Command", &script]).output() {
+            Ok(output) if output.status.success() => log::info!("[SCHEDULER] 已删除卷影副本 {}", self.id),
+            _ => log::warn!("[SCHEDULER] 删除卷影副本失败: {}", self.id),
+        }
+    }
+}
+
+/// 执行一次定时备份：捕获/追加分卷、记录还原点、按保留数量清理旧备份
+///
+/// 返回结果说明（清理旧备份失败只记录警告，不影响备份结果）
+pub fn run_scheduled_backup(
+    schedule: &BackupSchedule,
+    image_backend: ImageBackend,
+    io_priority: IoPriority,
+    progress_tx: Option<Sender<DismProgress>>,
+) -> Result<String> {
+    schedule.validate().map_err(anyhow::Error::msg)?;
+    let image_file = schedule.image_path.trim();
+    let source = schedule.source_partition.trim();
+    if !Path::new(&crate::core::disk::volume_root(source)).exists() {
+        bail!("源分区不存在: {}", source);
+    }
+    if let Some(parent) = Path::new(image_file).parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("创建备份目录失败: {}", parent.display()))?;
+    }
+
+    // 正在运行的分区（尤其是系统分区）有大量被占用的文件，通过卷影副本捕获
+    let shadow = ShadowCopy::create(source);
+    let capture_dir = match shadow {
+        Ok(ref shadow) => crate::core::disk::volume_root(&shadow.device_object),
+        Err(ref e) => {
+            let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+            if source.trim_end_matches('\\').eq_ignore_ascii_case(&system_drive) {
+                bail!("{:#}", e);
+            }
+            log::warn!("[SCHEDULER] {:#}，直接捕获分区", e);
+            crate::core::disk::volume_root(source)
+        }
+    };
+
+    let _io_guard = BackgroundModeGuard::enter(io_priority);
+    let dism = Dism::with_backend(image_backend);
+    let name = schedule.volume_name();
+    let description = format!("{} 的定时备份", source);
+    let size_before = backup_catalog::image_size(image_file);
+    if Path::new(image_file).exists() {
+        dism.append_image(image_file, &capture_dir, &name, &description, &schedule.capture, progress_tx.clone())?;
+    } else {
+        dism.capture_image(image_file, &capture_dir, &name, &description, &schedule.capture, progress_tx.clone())?;
+    }
+    drop(shadow);
+
+    if let Err(e) = backup_catalog::record_backup(image_file, source, &name, &description, size_before) {
+        log::warn!("[SCHEDULER] 记录还原点失败: {:#}", e);
+    }
+
+    let mut message = format!("已备份 {} 到 {}（{}）", source, image_file, name);
+    match prune_old_backups(image_file, schedule.keep_count, progress_tx) {
+        Ok(0) => {}
+        Ok(count) => message.push_str(&format!("，已清理 {} 个旧备份", count)),
+        Err(e) => {
+            log::warn!("[SCHEDULER] 清理旧备份失败: {:#}", e);
+            message.push_str(&format!("，清理旧备份失败: {:#}", e));
+        }
+    }
+    Ok(message)
+}
+
+/// 删除超出保留数量的定时备份，返回删除的数量
+fn prune_old_backups(image_file: &str, keep_count: u32, progress_tx: Option<Sender<DismProgress>>) -> Result<usize> {
+    let images = wim_manage::list_images(image_file)?;
+    let candidates = prune_candidates(&images, keep_count);
+    if candidates.is_empty() {
+        return Ok(0);
+    }
+    log::info!("[SCHEDULER] 删除旧的定时备份: 分卷 {:?}", candidates);
+    // 所有分卷一次删除，镜像只重写一次
+    let (tx, rx) = std::sync::mpsc::channel::<DismProgress>();
+    let forward_thread = std::thread::spawn(move || {
+        while let Ok(progress) = rx.recv() {
+            if let Some(ref forward) = progress_tx {
+                let _ = forward.send(progress);
+            }
+        }
+    });
+    let result = restore_points::delete_restore_points(image_file, &images, &candidates, tx);
+    let _ = forward_thread.join();
+    result?;
+    Ok(candidates.len())
+}
+
+/// 保存运行结果
+pub fn save_status(result: &Result<String>) {
+    let status = ScheduleStatus {
+        last_run_at: chrono::Utc::now().timestamp().max(0) as u64,
+        success: result.is_ok(),
+        message: match result {
+            Ok(message) => message.clone(),
+            Err(e) => format!("{:#}", e),
+        },
+    };
+    if let Err(e) = status.save() {
+        log::warn!("[SCHEDULER] {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(index: u32, name: &str) -> WimImageEntry {
+        WimImageEntry {
+            index,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn schedule() -> BackupSchedule {
+        BackupSchedule {
+            enabled: true,
+            source_partition: "C:".to_string(),
+            image_path: "D:\\Backup\\system.wim".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_start_time() {
+        assert_eq!(parse_start_time("03:00"), Some((3, 0)));
+        assert_eq!(parse_start_time(" 23:59 "), Some((23, 59)));
+        assert_eq!(parse_start_time("24:00"), None);
+        assert_eq!(parse_start_time("12:60"), None);
+        assert_eq!(parse_start_time("1200"), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(schedule().validate().is_ok());
+
+        let mut s = schedule();
+        s.image_path = "C:\\Backup\\system.wim".to_string();
+        assert!(s.validate().is_err());
+
+        let mut s = schedule();
+        s.image_path = "D:\\Backup\\system.esd".to_string();
+        assert!(s.validate().is_err());
+
+        let mut s = schedule();
+        s.image_path = "\\\\nas\\backup\\system.wim".to_string();
+        assert!(s.validate().is_err());
+
+        let mut s = schedule();
+        s.frequency = ScheduleFrequency::Hourly;
+        s.interval = 24;
+        assert!(s.validate().is_err());
+        s.interval = 6;
+        assert!(s.validate().is_ok());
+    }
+
+    #[test]
+    fn test_create_task_args() {
+        let mut s = schedule();
+        s.frequency = ScheduleFrequency::Weekly;
+        s.interval = 2;
+        s.weekday = 6;
+        s.start_time = "1:05".to_string();
+        let args = s.create_task_args(Path::new("C:\\Program Files\\LetRecovery\\LetRecovery.exe")).unwrap();
+        assert_eq!(
+            args,
+            [
+                "/Create", "/TN", TASK_NAME, "/SC", "WEEKLY", "/MO", "2", "/ST", "01:05", "/D", "SUN", "/RU", "SYSTEM",
+                "/RL", "HIGHEST", "/F", "/TR",
+                "\"C:\\Program Files\\LetRecovery\\LetRecovery.exe\" --scheduled-backup",
+            ]
+        );
+
+        s.frequency = ScheduleFrequency::Daily;
+        let args = s.create_task_args(Path::new("C:\\LetRecovery.exe")).unwrap();
+        assert!(!args.iter().any(|arg| arg == "/D"));
+    }
+
+    #[test]
+    fn test_prune_candidates() {
+        let images = [
+            image(1, "手动备份"),
+            image(2, "定时备份 2026-01-01 03:00"),
+            image(3, "定时备份 2026-01-02 03:00"),
+            image(4, "定时备份 2026-01-03 03:00"),
+            image(5, "定时备份 2026-01-04 03:00"),
+        ];
+        assert_eq!(prune_candidates(&images, 2), [3, 2]);
+        assert!(prune_candidates(&images, 4).is_empty());
+        assert!(prune_candidates(&images, 0).is_empty());
+    }
+}
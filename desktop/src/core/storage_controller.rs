@@ -0,0 +1,288 @@
+//! Intel VMD / RST RAID 控制器检测模块
+//!
+//! 开启 VMD 或 RAID 模式的机器上，硬盘挂在 Intel 控制器下面，没有对应驱动时
+//! PE 和新系统都看不到目标硬盘（新系统表现为 INACCESSIBLE_BOOT_DEVICE）。
+//! 这里通过 PCI 设备的硬件 ID 检测这类控制器，并检查驱动包中是否包含对应驱动。
+
+use std::path::Path;
+
+/// Intel 厂商 ID
+const INTEL_VENDOR: &str = "VEN_8086";
+
+/// RAID 控制器的 PCI 类代码（大容量存储 / RAID）
+const RAID_CLASS_CODE: &str = "CC_0104";
+
+/// Intel VMD 控制器设备 ID
+const VMD_DEVICE_IDS: [&str; 9] = [
+    "9A0B", // Tiger Lake
+    "4C3D", // Rocket Lake
+    "467F", // Alder Lake
+    "A77F", // Raptor Lake
+    "7D0B", // Meteor Lake
+    "AD0B", // Arrow Lake
+    "09AB", // VMD 托管控制器
+    "201D", // Xeon Scalable
+    "28C0", // Xeon Sapphire Rapids
+];
+
+/// Intel RST RAID 模式 SATA 控制器设备 ID（硬件 ID 中不含类代码时使用）
+const RST_RAID_DEVICE_IDS: [&str; 3] = ["2822", "2826", "282A"];
+
+/// 控制器类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerKind {
+    /// Intel VMD（卷管理设备）
+    Vmd,
+    /// Intel RST RAID 模式
+    RstRaid,
+}
+
+impl ControllerKind {
+    /// 显示名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ControllerKind::Vmd => "Intel VMD",
+            ControllerKind::RstRaid => "Intel RST RAID",
+        }
+    }
+}
+
+/// 检测到的存储控制器
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaidController {
+    /// 控制器类型
+    pub kind: ControllerKind,
+    /// 设备 ID（如 "9A0B"）
+    pub device_id: String,
+    /// 设备描述
+    pub name: String,
+    /// 当前系统是否已加载驱动
+    pub driver_loaded: bool,
+}
+
+impl RaidController {
+    /// 显示名称
+    pub fn display_name(&self) -> String {
+        if self.name.is_empty() {
+            format!("{} (DEV_{})", self.kind.as_str(), self.device_id)
+        } else {
+            format!("{} (DEV_{})", self.name, self.device_id)
+        }
+    }
+}
+
+/// 从硬件 ID 中取出设备 ID
+fn device_id_of(hardware_id: &str) -> Option<String> {
+    let upper = hardware_id.to_uppercase();
+    let index = upper.find("DEV_")? + "DEV_".len();
+    upper.get(index..index + 4).map(str::to_string)
+}
+
+/// 根据 PCI 硬件 ID 判断是否为 Intel VMD / RST RAID 控制器，返回（类型, 设备 ID）
+pub fn classify_hardware_id(hardware_id: &str) -> Option<(ControllerKind, String)> {
+    let upper = hardware_id.to_uppercase();
+    if !upper.contains(INTEL_VENDOR) {
+        return None;
+    }
+    let device_id = device_id_of(&upper)?;
+    if VMD_DEVICE_IDS.contains(&device_id.as_str()) {
+        Some((ControllerKind::Vmd, device_id))
+    } else if upper.contains(RAID_CLASS_CODE) || RST_RAID_DEVICE_IDS.contains(&device_id.as_str()) {
+        Some((ControllerKind::RstRaid, device_id))
+    } else {
+        None
+    }
+}
+
+/// 枚举 PCI 设备，检测 Intel VMD / RST RAID 控制器
+#[cfg(windows)]
+pub fn detect_controllers() -> Vec<RaidController> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let mut controllers: Vec<RaidController> = Vec::new();
+    let Ok(pci) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(r"SYSTEM\CurrentControlSet\Enum\PCI") else {
+        return controllers;
+    };
+
+    for device_key in pci.enum_keys().filter_map(|k| k.ok()) {
+        // 子键名形如 VEN_8086&DEV_9A0B&SUBSYS_...，先粗筛 Intel 设备
+        if !device_key.to_uppercase().contains(INTEL_VENDOR) {
+            continue;
+        }
+        let Ok(device) = pci.open_subkey(&device_key) else {
+            continue;
+        };
+        for instance_key in device.enum_keys().filter_map(|k| k.ok()) {
+            let Ok(instance) = device.open_subkey(&instance_key) else {
+                continue;
+            };
+            let hardware_ids: Vec<String> = instance.get_value("HardwareID").unwrap_or_default();
+            let compatible_ids: Vec<String> = instance.get_value("CompatibleIDs").unwrap_or_default();
+            let Some((kind, device_id)) = std::iter::once(device_key.clone())
+                .chain(hardware_ids)
+                .chain(compatible_ids)
+                .find_map(|id| classify_hardware_id(&id))
+            else {
+                continue;
+            };
+            if controllers.iter().any(|c| c.device_id == device_id) {
+                continue;
+            }
+
+            let name: String = instance.get_value("DeviceDesc").unwrap_or_default();
+            let service: String = instance.get_value("Service").unwrap_or_default();
+            controllers.push(RaidController {
+                kind,
+                device_id,
+                // DeviceDesc 形如 "@oem12.inf,%vmd%;Intel RST VMD Controller"
+                name: name.rsplit(';').next().unwrap_or_default().to_string(),
+                driver_loaded: !service.is_empty(),
+            });
+        }
+    }
+
+    for controller in &controllers {
+        log::info!(
+            "[STORAGE] 检测到 {}: {}，驱动已加载: {}",
+            controller.kind.as_str(),
+            controller.display_name(),
+            controller.driver_loaded
+        );
+    }
+    controllers
+}
+
+#[cfg(not(windows))]
+pub fn detect_controllers() -> Vec<RaidController> {
+    Vec::new()
+}
+
+/// 从硬件 ID 中取出 `VEN_xxxx&DEV_xxxx` 部分
+pub fn pci_id_of(hardware_id: &str) -> Option<String> {
+    let upper = hardware_id.to_uppercase();
+    let start = upper.find("VEN_")?;
+    let id = upper.get(start..start + "VEN_xxxx&DEV_xxxx".len())?;
+    let valid = id[4..8].chars().all(|c| c.is_ascii_hexdigit())
+        && &id[8..13] == "&DEV_"
+        && id[13..].chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| id.to_string())
+}
+
+/// 当前系统中所有 PCI 设备的 `VEN_xxxx&DEV_xxxx`
+#[cfg(windows)]
+pub fn present_pci_ids() -> Vec<String> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let Ok(pci) = RegKey::predef(HKEY_LOCAL_MACHINE).open_subkey(r"SYSTEM\CurrentControlSet\Enum\PCI") else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = pci
+        .enum_keys()
+        .filter_map(|k| k.ok())
+        .filter_map(|k| pci_id_of(&k))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[cfg(not(windows))]
+pub fn present_pci_ids() -> Vec<String> {
+    Vec::new()
+}
+
+/// 读取 INF 文件文本（兼容 UTF-16 LE 编码）
+pub fn read_inf_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    } else {
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// 检查 INF 文本是否声明支持指定设备 ID
+pub fn inf_supports_device(inf_text: &str, device_id: &str) -> bool {
+    inf_text
+        .to_uppercase()
+        .contains(&format!("{}&DEV_{}", INTEL_VENDOR, device_id.to_uppercase()))
+}
+
+/// 检查驱动目录（递归）中是否有支持该控制器的驱动
+pub fn driver_dir_supports(dir: &Path, controller: &RaidController) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.filter_map(|e| e.ok()).any(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            driver_dir_supports(&path, controller)
+        } else {
+            path.extension()
+                .map(|ext| ext.eq_ignore_ascii_case("inf"))
+                .unwrap_or(false)
+                && read_inf_text(&path)
+                    .map(|text| inf_supports_device(&text, &controller.device_id))
+                    .unwrap_or(false)
+        }
+    })
+}
+
+/// 驱动包（程序目录 drivers\storage_controller）中缺少驱动的控制器
+pub fn missing_drivers(controllers: &[RaidController]) -> Vec<RaidController> {
+    let dir = crate::utils::path::get_exe_dir()
+        .join("drivers")
+        .join("storage_controller");
+    controllers
+        .iter()
+        .filter(|c| !driver_dir_supports(&dir, c))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_hardware_id() {
+        assert_eq!(
+            classify_hardware_id(r"PCI\VEN_8086&DEV_9A0B&SUBSYS_0A0B1028&REV_00"),
+            Some((ControllerKind::Vmd, "9A0B".to_string()))
+        );
+        assert_eq!(
+            classify_hardware_id(r"PCI\VEN_8086&DEV_A352&CC_010400"),
+            Some((ControllerKind::RstRaid, "A352".to_string()))
+        );
+        assert_eq!(
+            classify_hardware_id("VEN_8086&DEV_282A&SUBSYS_00000000&REV_10"),
+            Some((ControllerKind::RstRaid, "282A".to_string()))
+        );
+        // AHCI 模式和其他厂商不处理
+        assert_eq!(classify_hardware_id(r"PCI\VEN_8086&DEV_A352&CC_010601"), None);
+        assert_eq!(classify_hardware_id(r"PCI\VEN_1022&DEV_43C8&CC_0104"), None);
+    }
+
+    #[test]
+    fn test_pci_id_of() {
+        assert_eq!(
+            pci_id_of(r"PCI\VEN_1af4&DEV_1001&SUBSYS_00021AF4&REV_00").as_deref(),
+            Some("VEN_1AF4&DEV_1001")
+        );
+        assert_eq!(pci_id_of("VEN_8086"), None);
+        assert_eq!(pci_id_of(r"PCI\VEN_XYZW&DEV_1001"), None);
+    }
+
+    #[test]
+    fn test_inf_supports_device() {
+        let inf = "[INTEL.NTamd64.10.0]\n%PCI\\VEN_8086&DEV_9A0B&CC_0104.DeviceDesc% = iaStorVD_Inst, PCI\\VEN_8086&DEV_9A0B&CC_0104\n";
+        assert!(inf_supports_device(inf, "9a0b"));
+        assert!(!inf_supports_device(inf, "467F"));
+    }
+}
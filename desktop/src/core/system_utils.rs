@@ -2,6 +2,8 @@
 //!
 //! 提供不依赖 DISM 的系统操作功能：
 //! - 离线注册表读取 (advapi32.dll - RegLoadKey/RegUnLoadKey)
+//! - 配置单元替换 (RegReplaceKey)
+//! - 目录映射为盘符 (DefineDosDevice)
 //! - 组件存储清理 (Task Scheduler API)
 //! - 系统信息获取
 //! - PE文件架构检测
@@ -12,31 +14,20 @@ use std::path::Path;
 
 use anyhow::{bail, Result};
 
+pub use crate::core::offline_registry::enable_hive_privileges;
+
 #[cfg(windows)]
 use windows::core::PCWSTR;
 #[cfg(windows)]
-use windows::Win32::Foundation::{CloseHandle, GetLastError, HANDLE, LUID};
-#[cfg(windows)]
-use windows::Win32::Storage::FileSystem::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW};
-#[cfg(windows)]
-use windows::Win32::Security::{
-    AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES,
-    SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+use windows::Win32::Storage::FileSystem::{
+    DefineDosDeviceW, GetFileVersionInfoSizeW, GetFileVersionInfoW, GetLogicalDrives, VerQueryValueW,
+    DDD_REMOVE_DEFINITION, DEFINE_DOS_DEVICE_FLAGS,
 };
 #[cfg(windows)]
 use windows::Win32::System::Registry::{
-    RegCloseKey, RegLoadKeyW, RegOpenKeyExW, RegQueryValueExW, RegUnLoadKeyW,
+    RegCloseKey, RegLoadKeyW, RegOpenKeyExW, RegQueryValueExW, RegReplaceKeyW, RegUnLoadKeyW,
     HKEY, HKEY_LOCAL_MACHINE, KEY_READ, REG_VALUE_TYPE,
 };
-#[cfg(windows)]
-use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
-
-// ============================================================================
-// 常量定义
-// ============================================================================
-
-const SE_RESTORE_NAME: &str = "SeRestorePrivilege";
-const SE_BACKUP_NAME: &str = "SeBackupPrivilege";
 
 /// 系统架构类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -249,70 +240,6 @@ pub fn get_system_architecture(system_root: &str) -> SystemArchitecture {
     SystemArchitecture::Amd64
 }
 
-// ============================================================================
-// 权限提升
-// ============================================================================
-
-/// 启用指定权限
-#[cfg(windows)]
-fn enable_privilege(privilege_name: &str) -> Result<()> {
-    unsafe {
-        let mut token_handle = HANDLE::default();
-        let process = GetCurrentProcess();
-
-        // OpenProcessToken 返回 Result
-        if let Err(e) = OpenProcessToken(process, TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token_handle) {
-            bail!("OpenProcessToken 失败: {}", e);
-        }
-
-        let wide_name = to_wide(privilege_name);
-        let mut luid = LUID::default();
-
-        // LookupPrivilegeValueW 返回 Result
-        if let Err(e) = LookupPrivilegeValueW(PCWSTR::null(), PCWSTR(wide_name.as_ptr()), &mut luid) {
-            let _ = CloseHandle(token_handle);
-            bail!("LookupPrivilegeValueW 失败: {}", e);
-        }
-
-        let tp = TOKEN_PRIVILEGES {
-            PrivilegeCount: 1,
-            Privileges: [LUID_AND_ATTRIBUTES {
-                Luid: luid,
-                Attributes: SE_PRIVILEGE_ENABLED,
-            }],
-        };
-
-        // AdjustTokenPrivileges 返回 Result
-        if let Err(e) = AdjustTokenPrivileges(
-            token_handle,
-            false,
-            Some(&tp),
-            0,
-            None,
-            None,
-        ) {
-            let _ = CloseHandle(token_handle);
-            bail!("AdjustTokenPrivileges 失败: {}", e);
-        }
-
-        // 检查 GetLastError，AdjustTokenPrivileges 即使成功也可能设置错误码
-        let last_error = GetLastError();
-        let _ = CloseHandle(token_handle);
-        
-        if last_error.0 != 0 && last_error.0 != 1300 {
-            // 1300 = ERROR_NOT_ALL_ASSIGNED，表示部分权限未能分配，可以忽略
-            println!("[SystemUtils] AdjustTokenPrivileges 警告: 错误码 {}", last_error.0);
-        }
-    }
-
-    Ok(())
-}
-
-#[cfg(not(windows))]
-fn enable_privilege(_privilege_name: &str) -> Result<()> {
-    Ok(())
-}
-
 // ============================================================================
 // 离线注册表操作
 // ============================================================================
@@ -352,8 +279,7 @@ pub struct OfflineSystemInfo {
 #[cfg(windows)]
 pub fn get_offline_system_info(system_root: &str) -> Result<OfflineSystemInfo> {
     // 需要 SeRestorePrivilege 和 SeBackupPrivilege 权限
-    enable_privilege(SE_RESTORE_NAME)?;
-    enable_privilege(SE_BACKUP_NAME)?;
+    enable_hive_privileges()?;
 
     let system_root_path = Path::new(system_root);
     let software_hive = system_root_path
@@ -481,6 +407,104 @@ pub fn get_offline_system_info(_system_root: &str) -> Result<OfflineSystemInfo>
     bail!("仅支持 Windows 平台")
 }
 
+/// 替换当前运行系统的 HKLM 配置单元（下次启动时生效）
+///
+/// new_file 与 old_file 须与原配置单元位于同一卷，old_file 接收原配置单元的备份
+#[cfg(windows)]
+pub fn replace_live_hive(key_name: &str, new_file: &Path, old_file: &Path) -> Result<()> {
+    enable_hive_privileges()?;
+
+    let wide_key_name = to_wide(key_name);
+    let wide_new = path_to_wide(new_file);
+    let wide_old = path_to_wide(old_file);
+
+    println!("[SystemUtils] 替换配置单元 HKLM\\{}: {:?}", key_name, new_file);
+    let result = unsafe {
+        RegReplaceKeyW(
+            HKEY_LOCAL_MACHINE,
+            PCWSTR(wide_key_name.as_ptr()),
+            PCWSTR(wide_new.as_ptr()),
+            PCWSTR(wide_old.as_ptr()),
+        )
+    };
+    if result.0 != 0 {
+        bail!("RegReplaceKeyW 失败: 错误码 {}", result.0);
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn replace_live_hive(_key_name: &str, _new_file: &Path, _old_file: &Path) -> Result<()> {
+    bail!("仅支持 Windows 平台")
+}
+
+/// 当前已占用盘符的位掩码（bit 0 为 A:）
+#[cfg(windows)]
+pub fn used_drive_letters() -> u32 {
+    unsafe { GetLogicalDrives() }
+}
+
+#[cfg(not(windows))]
+pub fn used_drive_letters() -> u32 {
+    0
+}
+
+/// 映射盘符使用的设备名
+///
+/// 建在全局命名空间：以管理员身份运行时，当前会话内的映射对未提升权限的资源管理器不可见。
+fn global_drive_device(letter: char) -> String {
+    format!("Global\\{}:", letter)
+}
+
+/// 通知资源管理器驱动器已添加或移除
+#[cfg(windows)]
+fn notify_shell_drive(letter: char, added: bool) {
+    use windows::Win32::UI::Shell::{SHChangeNotify, SHCNE_DRIVEADD, SHCNE_DRIVEREMOVED, SHCNF_PATHW};
+
+    let root = to_wide(&format!("{}:\\", letter));
+    let event = if added { SHCNE_DRIVEADD } else { SHCNE_DRIVEREMOVED };
+    unsafe { SHChangeNotify(event, SHCNF_PATHW, Some(root.as_ptr() as *const _), None) };
+}
+
+/// 把目录映射为盘符（效果同 subst），并通知资源管理器显示该驱动器
+#[cfg(windows)]
+pub fn map_drive_letter(letter: char, target: &Path) -> Result<()> {
+    let wide_device = to_wide(&global_drive_device(letter));
+    let wide_target = path_to_wide(target);
+    unsafe {
+        DefineDosDeviceW(
+            DEFINE_DOS_DEVICE_FLAGS(0),
+            PCWSTR(wide_device.as_ptr()),
+            PCWSTR(wide_target.as_ptr()),
+        )
+    }
+    .map_err(|e| anyhow::anyhow!("映射盘符 {}: 失败: {}", letter, e))?;
+    notify_shell_drive(letter, true);
+    println!("[SystemUtils] 已映射 {}: -> {:?}", letter, target);
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn map_drive_letter(_letter: char, _target: &Path) -> Result<()> {
+    bail!("仅支持 Windows 平台")
+}
+
+/// 移除 map_drive_letter 创建的盘符映射
+#[cfg(windows)]
+pub fn unmap_drive_letter(letter: char) -> Result<()> {
+    let wide_device = to_wide(&global_drive_device(letter));
+    unsafe { DefineDosDeviceW(DDD_REMOVE_DEFINITION, PCWSTR(wide_device.as_ptr()), PCWSTR::null()) }
+        .map_err(|e| anyhow::anyhow!("移除盘符 {}: 失败: {}", letter, e))?;
+    notify_shell_drive(letter, false);
+    println!("[SystemUtils] 已移除盘符映射 {}:", letter);
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn unmap_drive_letter(_letter: char) -> Result<()> {
+    bail!("仅支持 Windows 平台")
+}
+
 /// 获取离线系统版本字符串（简化版）
 pub fn get_offline_system_edition(system_root: &str) -> Result<String> {
     let info = get_offline_system_info(system_root)?;
@@ -0,0 +1,169 @@
+//! 技师锁定模式
+//!
+//! 装机店把启动好的 PE 交给顾客使用时，可设置技师 PIN，把格式化、一键分区、
+//! 安装系统等破坏性操作和程序设置锁在 PIN 之后，信息查看和校验类工具不受影响。
+//! PIN 只保存 PBKDF2 加盐哈希；config.json 与程序放在一起，锁定只用于防止误操作，不能替代磁盘加密。
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+
+/// PIN 最短位数
+pub const MIN_PIN_LEN: usize = 4;
+/// PIN 最长位数
+pub const MAX_PIN_LEN: usize = 12;
+/// 连续输错多少次后暂停输入
+const MAX_FAILURES: u32 = 5;
+/// 暂停输入的时长
+const LOCKOUT: Duration = Duration::from_secs(30);
+/// PBKDF2 迭代次数
+const PBKDF2_ROUNDS: u32 = 100_000;
+/// 盐的字节数
+const SALT_LEN: usize = 16;
+
+/// 检查 PIN 格式（4-12 位数字）
+pub fn is_valid_pin(pin: &str) -> bool {
+    (MIN_PIN_LEN..=MAX_PIN_LEN).contains(&pin.len()) && pin.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 用系统随机数生成盐（十六进制）
+pub fn new_salt() -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    getrandom::getrandom(&mut salt).map_err(|e| anyhow!("获取系统随机数失败: {}", e))?;
+    Ok(to_hex(&salt))
+}
+
+/// 计算 PIN 的 PBKDF2-HMAC-SHA256 哈希（十六进制）
+pub fn hash_pin(pin: &str, salt: &str) -> String {
+    to_hex(&pbkdf2_sha256(pin.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS))
+}
+
+/// PBKDF2-HMAC-SHA256，输出一个块（32 字节）
+fn pbkdf2_sha256(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let mut first = salt.to_vec();
+    first.extend_from_slice(&1u32.to_be_bytes());
+    let mut block = hmac_sha256(password, &first);
+    let mut output = block;
+    for _ in 1..rounds {
+        block = hmac_sha256(password, &block);
+        output.iter_mut().zip(block.iter()).for_each(|(o, b)| *o ^= b);
+    }
+    output
+}
+
+/// HMAC-SHA256
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block_key = [0u8; 64];
+    if key.len() > block_key.len() {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha256::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 校验 PIN
+pub fn verify_pin(pin: &str, salt: &str, hash: &str) -> bool {
+    !hash.is_empty() && hash_pin(pin, salt).eq_ignore_ascii_case(hash)
+}
+
+/// 解锁失败计数，连续输错后暂停一段时间
+#[derive(Debug, Clone, Default)]
+pub struct UnlockThrottle {
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl UnlockThrottle {
+    /// 记录一次失败
+    pub fn record_failure(&mut self, now: Instant) {
+        self.failures += 1;
+        if self.failures >= MAX_FAILURES {
+            self.failures = 0;
+            self.locked_until = Some(now + LOCKOUT);
+        }
+    }
+
+    /// 解锁成功后清零
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 剩余暂停时间（未暂停时为 None）
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.locked_until
+            .and_then(|until| until.checked_duration_since(now))
+            .filter(|d| !d.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_pin() {
+        assert!(is_valid_pin("1234"));
+        assert!(is_valid_pin("123456789012"));
+        assert!(!is_valid_pin("123"));
+        assert!(!is_valid_pin("1234567890123"));
+        assert!(!is_valid_pin("12a4"));
+        assert!(!is_valid_pin("１２３４"));
+    }
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_pin("2580", "salt");
+        assert_eq!(hash.len(), 64);
+        assert!(verify_pin("2580", "salt", &hash));
+        assert!(!verify_pin("2581", "salt", &hash));
+        assert!(!verify_pin("2580", "other", &hash));
+        assert!(!verify_pin("2580", "salt", ""));
+    }
+
+    #[test]
+    fn test_pbkdf2_sha256() {
+        // RFC 7914 第 11 节的 PBKDF2-HMAC-SHA256 测试向量
+        assert_eq!(
+            to_hex(&pbkdf2_sha256(b"passwd", b"salt", 1)),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc"
+        );
+        assert_eq!(
+            to_hex(&pbkdf2_sha256(b"password", b"salt", 2)),
+            "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43"
+        );
+    }
+
+    #[test]
+    fn test_new_salt() {
+        let salt = new_salt().unwrap();
+        assert_eq!(salt.len(), SALT_LEN * 2);
+        assert_ne!(salt, new_salt().unwrap());
+    }
+
+    #[test]
+    fn test_throttle() {
+        let now = Instant::now();
+        let mut throttle = UnlockThrottle::default();
+        for _ in 0..MAX_FAILURES - 1 {
+            throttle.record_failure(now);
+        }
+        assert_eq!(throttle.remaining(now), None);
+        throttle.record_failure(now);
+        assert_eq!(throttle.remaining(now), Some(LOCKOUT));
+        assert_eq!(throttle.remaining(now + LOCKOUT), None);
+        throttle.reset();
+        assert_eq!(throttle.remaining(now), None);
+    }
+}
@@ -0,0 +1,294 @@
+//! VHD/VHDX 虚拟磁盘
+//!
+//! 用于把系统安装到虚拟磁盘（本机 VHD 启动）：
+//! - 创建动态扩展的 VHDX/VHD 文件 (CreateVirtualDisk)
+//! - 附加 (AttachVirtualDisk) 后用 diskpart 分区、格式化并分配盘符
+//! - 安装完成后分离 (DetachVirtualDisk)，引导项由 bcdboot 写入
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::core::disk::{get_diskpart_path, DiskManager};
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+#[cfg(windows)]
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Win32::Foundation::{CloseHandle, HANDLE, WIN32_ERROR},
+    Win32::Storage::Vhd::{
+        AttachVirtualDisk, CreateVirtualDisk, DetachVirtualDisk, GetVirtualDiskPhysicalPath, OpenVirtualDisk,
+        ATTACH_VIRTUAL_DISK_FLAG_PERMANENT_LIFETIME, ATTACH_VIRTUAL_DISK_PARAMETERS, ATTACH_VIRTUAL_DISK_VERSION_1,
+        CREATE_VIRTUAL_DISK_FLAG_NONE, CREATE_VIRTUAL_DISK_PARAMETERS, CREATE_VIRTUAL_DISK_VERSION_2,
+        DETACH_VIRTUAL_DISK_FLAG_NONE, OPEN_VIRTUAL_DISK_FLAG_NONE, OPEN_VIRTUAL_DISK_PARAMETERS,
+        OPEN_VIRTUAL_DISK_VERSION_1, VIRTUAL_DISK_ACCESS_DETACH, VIRTUAL_DISK_ACCESS_NONE, VIRTUAL_STORAGE_TYPE,
+        VIRTUAL_STORAGE_TYPE_DEVICE_VHD, VIRTUAL_STORAGE_TYPE_DEVICE_VHDX,
+    },
+};
+
+#[cfg(windows)]
+const VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT: windows::core::GUID =
+    windows::core::GUID::from_u128(0xEC984AEC_A0F9_47e9_901F_71415A66345B);
+
+/// 安装到虚拟磁盘的参数
+#[derive(Debug, Clone)]
+pub struct VhdTarget {
+    /// 虚拟磁盘文件完整路径（如 D:\LetRecovery.vhdx）
+    pub path: String,
+    /// 最大容量（GB）
+    pub size_gb: u64,
+}
+
+impl VhdTarget {
+    pub fn size_bytes(&self) -> u64 {
+        self.size_gb * 1024 * 1024 * 1024
+    }
+}
+
+/// 已附加的虚拟磁盘，离开作用域时自动分离
+pub struct AttachedVhd {
+    path: String,
+    pub disk_number: u32,
+    /// 系统分区盘符（如 "V:"）
+    pub partition: String,
+}
+
+impl Drop for AttachedVhd {
+    fn drop(&mut self) {
+        if let Err(e) = detach(&self.path) {
+            println!("[VHD] 分离虚拟磁盘失败: {:#}", e);
+        }
+    }
+}
+
+/// 是否为 VHDX 格式（否则按 VHD 处理）
+fn is_vhdx(path: &str) -> bool {
+    !path.to_lowercase().ends_with(".vhd")
+}
+
+/// 检查虚拟磁盘文件名（不含路径，扩展名为 .vhd 或 .vhdx）
+pub fn is_valid_file_name(name: &str) -> bool {
+    let name = name.trim();
+    let lower = name.to_lowercase();
+    let stem_len = if lower.ends_with(".vhdx") {
+        name.len() - 5
+    } else if lower.ends_with(".vhd") {
+        name.len() - 4
+    } else {
+        return false;
+    };
+    stem_len > 0 && !name.contains(['\\', '/', ':', '*', '?', '"', '<', '>', '|'])
+}
+
+/// 从 \\.\PhysicalDriveN 中解析磁盘号
+pub fn parse_physical_drive_number(physical_path: &str) -> Option<u32> {
+    let lower = physical_path.trim_end_matches('\0').to_lowercase();
+    let index = lower.rfind("physicaldrive")?;
+    lower[index + "physicaldrive".len()..].parse().ok()
+}
+
+/// 初始化虚拟磁盘并创建单个 NTFS 系统分区的 diskpart 脚本
+fn partition_script(disk_number: u32, gpt: bool, letter: char) -> String {
+    format!(
+        "select disk {}\nclean\nconvert {}\ncreate partition primary\nformat fs=ntfs quick label=\"VHD_System\"\nassign letter={}\n",
+        disk_number,
+        if gpt { "gpt" } else { "mbr" },
+        letter
+    )
+}
+
+#[cfg(windows)]
+fn storage_type(path: &str) -> VIRTUAL_STORAGE_TYPE {
+    VIRTUAL_STORAGE_TYPE {
+        DeviceId: if is_vhdx(path) { VIRTUAL_STORAGE_TYPE_DEVICE_VHDX } else { VIRTUAL_STORAGE_TYPE_DEVICE_VHD },
+        VendorId: VIRTUAL_STORAGE_TYPE_VENDOR_MICROSOFT,
+    }
+}
+
+#[cfg(windows)]
+fn to_wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// 创建动态扩展的虚拟磁盘并附加，返回磁盘号
+#[cfg(windows)]
+fn create_and_attach(path: &str, size_bytes: u64) -> Result<u32> {
+    let wide_path = to_wide(path);
+    unsafe {
+        let storage_type = storage_type(path);
+        let mut params: CREATE_VIRTUAL_DISK_PARAMETERS = std::mem::zeroed();
+        params.Version = CREATE_VIRTUAL_DISK_VERSION_2;
+        params.Anonymous.Version2.MaximumSize = size_bytes;
+
+        let mut handle = HANDLE::default();
+        let result = CreateVirtualDisk(
+            &storage_type,
+            PCWSTR::from_raw(wide_path.as_ptr()),
+            VIRTUAL_DISK_ACCESS_NONE,
+            None,
+            CREATE_VIRTUAL_DISK_FLAG_NONE,
+            0,
+            &params,
+            None,
+            &mut handle,
+        );
+        if result != WIN32_ERROR(0) {
+            bail!("CreateVirtualDisk 失败: {:?}", result);
+        }
+
+        let mut attach_params: ATTACH_VIRTUAL_DISK_PARAMETERS = std::mem::zeroed();
+        attach_params.Version = ATTACH_VIRTUAL_DISK_VERSION_1;
+        let result = AttachVirtualDisk(
+            handle,
+            None,
+            ATTACH_VIRTUAL_DISK_FLAG_PERMANENT_LIFETIME,
+            0,
+            Some(&attach_params),
+            None,
+        );
+        if result != WIN32_ERROR(0) {
+            let _ = CloseHandle(handle);
+            bail!("AttachVirtualDisk 失败: {:?}", result);
+        }
+
+        let mut path_buffer = [0u16; 260];
+        let mut path_size = (path_buffer.len() * 2) as u32;
+        let result = GetVirtualDiskPhysicalPath(handle, &mut path_size, PWSTR::from_raw(path_buffer.as_mut_ptr()));
+        let _ = CloseHandle(handle);
+        if result != WIN32_ERROR(0) {
+            bail!("GetVirtualDiskPhysicalPath 失败: {:?}", result);
+        }
+
+        let physical_path = String::from_utf16_lossy(&path_buffer[..path_size as usize / 2]);
+        println!("[VHD] 已附加: {} -> {}", path, physical_path.trim_end_matches('\0'));
+        parse_physical_drive_number(&physical_path)
+            .ok_or_else(|| anyhow::anyhow!("无法解析虚拟磁盘的物理路径: {}", physical_path))
+    }
+}
+
+#[cfg(not(windows))]
+fn create_and_attach(_path: &str, _size_bytes: u64) -> Result<u32> {
+    bail!("仅支持 Windows 平台")
+}
+
+/// 分离虚拟磁盘
+#[cfg(windows)]
+pub fn detach(path: &str) -> Result<()> {
+    let wide_path = to_wide(path);
+    unsafe {
+        let storage_type = storage_type(path);
+        let mut open_params: OPEN_VIRTUAL_DISK_PARAMETERS = std::mem::zeroed();
+        open_params.Version = OPEN_VIRTUAL_DISK_VERSION_1;
+
+        let mut handle = HANDLE::default();
+        let result = OpenVirtualDisk(
+            &storage_type,
+            PCWSTR::from_raw(wide_path.as_ptr()),
+            VIRTUAL_DISK_ACCESS_DETACH,
+            OPEN_VIRTUAL_DISK_FLAG_NONE,
+            Some(&open_params),
+            &mut handle,
+        );
+        if result != WIN32_ERROR(0) {
+            bail!("OpenVirtualDisk 失败: {:?}", result);
+        }
+
+        let result = DetachVirtualDisk(handle, DETACH_VIRTUAL_DISK_FLAG_NONE, 0);
+        let _ = CloseHandle(handle);
+        if result != WIN32_ERROR(0) {
+            bail!("DetachVirtualDisk 失败: {:?}", result);
+        }
+    }
+    println!("[VHD] 已分离: {}", path);
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub fn detach(_path: &str) -> Result<()> {
+    bail!("仅支持 Windows 平台")
+}
+
+/// 创建虚拟磁盘、分区格式化并分配盘符，用作系统安装目标
+///
+/// 本机 VHD 启动时虚拟磁盘会扩展到最大容量，因此宿主分区须有足够的剩余空间
+pub fn prepare_install_target(target: &VhdTarget, gpt: bool) -> Result<AttachedVhd> {
+    println!("[VHD] ========== 创建虚拟磁盘 ==========");
+    println!("[VHD] 路径: {}，容量: {} GB，分区表: {}", target.path, target.size_gb, if gpt { "GPT" } else { "MBR" });
+
+    if Path::new(&target.path).exists() {
+        bail!("虚拟磁盘文件已存在: {}", target.path);
+    }
+    let host = target.path.get(..2).unwrap_or_default();
+    if let Some(free) = DiskManager::get_free_space_bytes(host) {
+        if free < target.size_bytes() {
+            bail!(
+                "{} 剩余空间不足：本机 VHD 启动需要 {} GB，当前可用 {} GB",
+                host,
+                target.size_gb,
+                free / 1024 / 1024 / 1024
+            );
+        }
+    }
+    if let Some(parent) = Path::new(&target.path).parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("无法创建目录: {}", parent.display()))?;
+    }
+
+    let disk_number = create_and_attach(&target.path, target.size_bytes())?;
+    // 先构造守卫，后续步骤失败时自动分离
+    let mut attached = AttachedVhd {
+        path: target.path.clone(),
+        disk_number,
+        partition: String::new(),
+    };
+
+    let letter = DiskManager::find_available_drive_letter().ok_or_else(|| anyhow::anyhow!("没有可用的盘符"))?;
+    let script_path = std::env::temp_dir().join("lr_vhd_partition.txt");
+    std::fs::write(&script_path, partition_script(disk_number, gpt, letter))?;
+    let output = create_command(&get_diskpart_path())
+        .args(["/s", &script_path.to_string_lossy()])
+        .output()
+        .context("无法启动 diskpart")?;
+    let _ = std::fs::remove_file(&script_path);
+
+    let output_text = gbk_to_utf8(&output.stdout);
+    println!("[VHD] Diskpart 输出: {}", output_text);
+    attached.partition = format!("{}:", letter);
+    if !Path::new(&format!("{}\\", attached.partition)).exists() {
+        bail!("虚拟磁盘分区失败: {}", output_text.trim());
+    }
+
+    println!("[VHD] 虚拟磁盘已就绪: 磁盘 {}，分区 {}", disk_number, attached.partition);
+    Ok(attached)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_physical_drive_number() {
+        assert_eq!(parse_physical_drive_number("\\\\.\\PhysicalDrive3"), Some(3));
+        assert_eq!(parse_physical_drive_number("\\\\.\\PHYSICALDRIVE12\0"), Some(12));
+        assert_eq!(parse_physical_drive_number("\\\\.\\CdRom0"), None);
+    }
+
+    #[test]
+    fn test_partition_script() {
+        let script = partition_script(2, true, 'V');
+        assert!(script.starts_with("select disk 2\nclean\nconvert gpt\n"));
+        assert!(script.ends_with("assign letter=V\n"));
+        assert!(partition_script(2, false, 'V').contains("convert mbr"));
+        assert!(is_vhdx("D:\\a.VHDX"));
+        assert!(!is_vhdx("D:\\a.vhd"));
+    }
+
+    #[test]
+    fn test_is_valid_file_name() {
+        assert!(is_valid_file_name("LetRecovery.vhdx"));
+        assert!(is_valid_file_name(" win10.VHD "));
+        assert!(!is_valid_file_name(".vhdx"));
+        assert!(!is_valid_file_name("win10.iso"));
+        assert!(!is_valid_file_name("dir\\win10.vhdx"));
+        assert!(!is_valid_file_name(""));
+    }
+}
@@ -0,0 +1,245 @@
+//! 设备变化监视
+//!
+//! 后台线程创建隐藏窗口接收 WM_DEVICECHANGE（卷到达/移除的广播和磁盘设备接口通知），
+//! 事件平息后重新读取分区列表和硬件信息并发给界面线程。
+//! 插入 U 盘或移动硬盘后，安装/备份的目标分区列表随之更新，无需手动刷新。
+
+#![allow(non_snake_case)]
+
+use std::cell::RefCell;
+use std::ffi::c_void;
+use std::mem::{size_of, zeroed};
+use std::ptr::{null, null_mut};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use windows::core::GUID;
+
+use crate::core::disk::{DiskManager, Partition};
+use crate::core::hardware_info::HardwareInfo;
+
+/// 最后一个事件之后等待多久再刷新（插入一块磁盘会连续产生多个事件，卷挂载也需要时间）
+const SETTLE_DELAY: Duration = Duration::from_millis(1500);
+
+const WM_DEVICECHANGE: u32 = 0x0219;
+const DBT_DEVICEARRIVAL: usize = 0x8000;
+const DBT_DEVICEREMOVECOMPLETE: usize = 0x8004;
+const DBT_DEVTYP_DEVICEINTERFACE: u32 = 5;
+const DEVICE_NOTIFY_WINDOW_HANDLE: u32 = 0;
+/// 磁盘设备接口（GUID_DEVINTERFACE_DISK）
+const GUID_DEVINTERFACE_DISK: GUID = GUID::from_u128(0x53f56307_b6bf_11d0_94f2_00a0c91efb8b);
+
+type Hwnd = *mut c_void;
+type WndProc = unsafe extern "system" fn(Hwnd, u32, usize, isize) -> isize;
+
+#[repr(C)]
+struct WndClassW {
+    style: u32,
+    lpfnWndProc: Option<WndProc>,
+    cbClsExtra: i32,
+    cbWndExtra: i32,
+    hInstance: *mut c_void,
+    hIcon: *mut c_void,
+    hCursor: *mut c_void,
+    hbrBackground: *mut c_void,
+    lpszMenuName: *const u16,
+    lpszClassName: *const u16,
+}
+
+#[repr(C)]
+struct Msg {
+    hwnd: Hwnd,
+    message: u32,
+    wParam: usize,
+    lParam: isize,
+    time: u32,
+    pt: [i32; 2],
+}
+
+#[repr(C)]
+struct DevBroadcastDeviceInterfaceW {
+    dbcc_size: u32,
+    dbcc_devicetype: u32,
+    dbcc_reserved: u32,
+    dbcc_classguid: GUID,
+    dbcc_name: [u16; 1],
+}
+
+#[link(name = "user32")]
+extern "system" {
+    fn RegisterClassW(wnd_class: *const WndClassW) -> u16;
+    fn CreateWindowExW(
+        ex_style: u32,
+        class_name: *const u16,
+        window_name: *const u16,
+        style: u32,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        parent: Hwnd,
+        menu: *mut c_void,
+        instance: *mut c_void,
+        param: *mut c_void,
+    ) -> Hwnd;
+    fn DefWindowProcW(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> isize;
+    fn GetMessageW(msg: *mut Msg, hwnd: Hwnd, filter_min: u32, filter_max: u32) -> i32;
+    fn TranslateMessage(msg: *const Msg) -> i32;
+    fn DispatchMessageW(msg: *const Msg) -> isize;
+    fn RegisterDeviceNotificationW(recipient: *mut c_void, filter: *const c_void, flags: u32) -> *mut c_void;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetModuleHandleW(module_name: *const u16) -> *mut c_void;
+}
+
+thread_local! {
+    /// 窗口过程把设备事件转发给刷新线程
+    static TRIGGER: RefCell<Option<Sender<()>>> = const { RefCell::new(None) };
+}
+
+/// 设备变化后重新读取的状态
+pub enum DeviceEvent {
+    /// 分区列表（读取较快，先发送）
+    Partitions(Vec<Partition>),
+    /// 硬件信息
+    Hardware(HardwareInfo),
+}
+
+/// 设备变化监视器
+pub struct DeviceWatcher {
+    rx: Receiver<DeviceEvent>,
+}
+
+impl DeviceWatcher {
+    /// 启动监视，`notify` 在有新数据时调用（用于唤醒界面刷新）
+    ///
+    /// 无法创建接收设备通知的窗口时返回 None，由界面改为手动刷新
+    pub fn start(notify: impl Fn() + Send + 'static) -> Option<Self> {
+        let (trigger_tx, trigger_rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        std::thread::spawn(move || run_message_loop(trigger_tx, ready_tx));
+        if let Err(e) = ready_rx.recv().unwrap_or_else(|_| Err("监视线程已退出".to_string())) {
+            log::warn!("[WATCHER] 无法监视设备变化: {}", e);
+            return None;
+        }
+
+        let (event_tx, event_rx) = mpsc::channel();
+        std::thread::spawn(move || refresh_loop(trigger_rx, event_tx, notify));
+        Some(Self { rx: event_rx })
+    }
+
+    /// 取出一条事件
+    pub fn try_recv(&self) -> Option<DeviceEvent> {
+        self.rx.try_recv().ok()
+    }
+}
+
+/// 等待设备事件平息后刷新
+fn refresh_loop(trigger_rx: Receiver<()>, event_tx: Sender<DeviceEvent>, notify: impl Fn()) {
+    while trigger_rx.recv().is_ok() {
+        loop {
+            match trigger_rx.recv_timeout(SETTLE_DELAY) {
+                Ok(()) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        log::info!("[WATCHER] 设备已变化，刷新分区列表和硬件信息");
+        match DiskManager::get_partitions() {
+            Ok(partitions) => {
+                if event_tx.send(DeviceEvent::Partitions(partitions)).is_err() {
+                    return;
+                }
+                notify();
+            }
+            Err(e) => log::warn!("[WATCHER] 读取分区列表失败: {}", e),
+        }
+        if let Ok(hardware_info) = HardwareInfo::collect() {
+            if event_tx.send(DeviceEvent::Hardware(hardware_info)).is_err() {
+                return;
+            }
+            notify();
+        }
+    }
+}
+
+/// 在当前线程创建隐藏窗口并运行消息循环，窗口创建结果通过 `ready_tx` 返回
+fn run_message_loop(trigger_tx: Sender<()>, ready_tx: Sender<Result<(), String>>) {
+    TRIGGER.with(|trigger| *trigger.borrow_mut() = Some(trigger_tx));
+    if let Err(e) = unsafe { create_notify_window() } {
+        let _ = ready_tx.send(Err(e));
+        return;
+    }
+    log::info!("[WATCHER] 已开始监视设备变化");
+    let _ = ready_tx.send(Ok(()));
+
+    unsafe {
+        let mut msg: Msg = zeroed();
+        while GetMessageW(&mut msg, null_mut(), 0, 0) > 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+/// 创建接收设备通知的隐藏顶层窗口（卷到达/移除只广播给顶层窗口，仅消息窗口收不到）
+unsafe fn create_notify_window() -> Result<Hwnd, String> {
+    let instance = GetModuleHandleW(null());
+    let class_name: Vec<u16> = "LetRecoveryDeviceWatcher".encode_utf16().chain(std::iter::once(0)).collect();
+    let mut wnd_class: WndClassW = zeroed();
+    wnd_class.lpfnWndProc = Some(window_proc);
+    wnd_class.hInstance = instance;
+    wnd_class.lpszClassName = class_name.as_ptr();
+    if RegisterClassW(&wnd_class) == 0 {
+        return Err(format!("注册窗口类失败: {}", std::io::Error::last_os_error()));
+    }
+
+    let hwnd = CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        class_name.as_ptr(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        null_mut(),
+        null_mut(),
+        instance,
+        null_mut(),
+    );
+    if hwnd.is_null() {
+        return Err(format!("创建窗口失败: {}", std::io::Error::last_os_error()));
+    }
+
+    // 未分区的新磁盘没有卷，不产生卷广播，另外订阅磁盘设备接口通知
+    let mut filter: DevBroadcastDeviceInterfaceW = zeroed();
+    filter.dbcc_size = size_of::<DevBroadcastDeviceInterfaceW>() as u32;
+    filter.dbcc_devicetype = DBT_DEVTYP_DEVICEINTERFACE;
+    filter.dbcc_classguid = GUID_DEVINTERFACE_DISK;
+    let notification = RegisterDeviceNotificationW(
+        hwnd,
+        &filter as *const DevBroadcastDeviceInterfaceW as *const c_void,
+        DEVICE_NOTIFY_WINDOW_HANDLE,
+    );
+    if notification.is_null() {
+        log::warn!("[WATCHER] 订阅磁盘设备通知失败: {}", std::io::Error::last_os_error());
+    }
+
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn window_proc(hwnd: Hwnd, msg: u32, wparam: usize, lparam: isize) -> isize {
+    if msg == WM_DEVICECHANGE && matches!(wparam, DBT_DEVICEARRIVAL | DBT_DEVICEREMOVECOMPLETE) {
+        TRIGGER.with(|trigger| {
+            if let Some(ref tx) = *trigger.borrow() {
+                let _ = tx.send(());
+            }
+        });
+        return 1;
+    }
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
@@ -0,0 +1,121 @@
+//! Wi-Fi 配置携带
+//!
+//! 重启进入 PE 前用 `netsh wlan export profile key=clear` 导出当前已连接的无线网络配置到数据分区，
+//! PE 启动 WLAN 服务后导入，使安装过程中也能联网（上报日志和统计、重新下载缺失文件等）。
+//! 导出的文件包含明文密码，放在只允许 SYSTEM 和管理员访问的目录中；
+//! 安装准备失败时随守卫删除，PE 导入后立即删除。
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::core::secure_dir::SensitiveDir;
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// 数据目录下存放 Wi-Fi 配置的子目录
+pub const WIFI_PROFILE_DIR: &str = "wifi";
+
+/// `netsh wlan show interfaces` 中配置文件名所在行的名称（英文和中文系统）
+const PROFILE_KEYS: [&str; 2] = ["Profile", "配置文件"];
+
+/// 从 `netsh wlan show interfaces` 的输出中读取已连接网络的配置文件名
+pub fn parse_connected_profiles(output: &str) -> Vec<String> {
+    let mut profiles: Vec<String> = Vec::new();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if PROFILE_KEYS.contains(&key.trim()) && !value.is_empty() && !profiles.iter().any(|p| p == value) {
+            profiles.push(value.to_string());
+        }
+    }
+    profiles
+}
+
+/// 读取配置文件 XML 中的网络名称
+pub fn profile_name(xml: &str) -> Option<String> {
+    let start = xml.find("<name>")? + "<name>".len();
+    let end = start + xml[start..].find("</name>")?;
+    let name = xml[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    Some(name.trim().to_string())
+}
+
+/// 导出当前已连接的 Wi-Fi 配置到 `dest`，返回目录守卫和导出的配置数（未连接无线网络时为 None）
+///
+/// 配置交给 PE 后调用守卫的 `keep`，否则离开作用域时删除目录。
+pub fn export_connected(dest: &Path) -> Result<Option<(SensitiveDir, usize)>> {
+    let output = new_command("netsh")
+        .args(["wlan", "show", "interfaces"])
+        .output()
+        .context("执行 netsh 失败")?;
+    if !output.status.success() {
+        bail!("没有无线网卡或 WLAN 服务未运行");
+    }
+    let connected = parse_connected_profiles(&gbk_to_utf8(&output.stdout));
+    if connected.is_empty() {
+        return Ok(None);
+    }
+    log::info!("[WIFI] 已连接的网络: {:?}", connected);
+
+    // netsh 只能按名称导出单个配置，名称中的空格和引号难以传参，先全部导出再按名称筛选
+    // 临时目录中有全部已保存网络的明文密码，任何返回路径上都随守卫删除
+    let staging = std::env::temp_dir().join(format!("LetRecovery_Wifi_{}", std::process::id()));
+    let staging = SensitiveDir::create(staging)?;
+    let output = new_command("netsh")
+        .args(["wlan", "export", "profile", "key=clear"])
+        .arg(format!("folder={}", staging.path().display()))
+        .output()
+        .context("执行 netsh 失败")?;
+    if !output.status.success() {
+        bail!("导出 Wi-Fi 配置失败: {}", gbk_to_utf8(&output.stdout).trim());
+    }
+
+    let exported = SensitiveDir::create(dest)?;
+    let mut count = 0;
+    for entry in std::fs::read_dir(staging.path())?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let Ok(xml) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if profile_name(&xml).is_some_and(|name| connected.contains(&name)) {
+            std::fs::copy(&path, dest.join(entry.file_name())).context("保存 Wi-Fi 配置失败")?;
+            count += 1;
+        }
+    }
+    Ok((count > 0).then_some((exported, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_connected_profiles() {
+        let english = "There is 1 interface on the system:\r\n\r\n    Name                   : WLAN\r\n\
+            \x20   State                  : connected\r\n    SSID                   : Home: 5G\r\n\
+            \x20   Profile                : Home: 5G\r\n    Signal                 : 90%\r\n";
+        assert_eq!(parse_connected_profiles(english), vec!["Home: 5G".to_string()]);
+
+        let chinese = "    名称                   : WLAN\r\n    状态                   : 已连接\r\n\
+            \x20   配置文件               : Office\r\n";
+        assert_eq!(parse_connected_profiles(chinese), vec!["Office".to_string()]);
+
+        let disconnected = "    Name                   : WLAN\r\n    State                  : disconnected\r\n";
+        assert!(parse_connected_profiles(disconnected).is_empty());
+    }
+
+    #[test]
+    fn test_profile_name() {
+        let xml = "<?xml version=\"1.0\"?>\n<WLANProfile xmlns=\"http://www.microsoft.com/networking/WLAN/profile/v1\">\n\
+            \t<name>Tom &amp; Jerry</name>\n\t<SSIDConfig><SSID><name>Tom &amp; Jerry</name></SSID></SSIDConfig>\n</WLANProfile>";
+        assert_eq!(profile_name(xml).as_deref(), Some("Tom & Jerry"));
+        assert!(profile_name("<WLANProfile/>").is_none());
+    }
+}
@@ -0,0 +1,138 @@
+//! 备份镜像文件浏览
+//!
+//! 通过 wimlib 列出 WIM/ESD/SWM 中某个镜像的目录树，支持按文件名搜索，
+//! 并把选中的文件或目录提取到指定位置，使系统备份也能当作文件备份使用。
+//! wimlib 句柄不能跨线程，打开、浏览、提取都在同一个后台线程中进行。
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::core::wimlib::{WimDirEntry, Wimlib, WimlibProgress};
+
+/// 搜索结果上限
+pub const SEARCH_LIMIT: usize = 500;
+
+/// 发给后台线程的请求
+pub enum BrowseRequest {
+    /// 列出目录（空字符串为根目录）
+    List(String),
+    /// 按文件名搜索
+    Search(String),
+    /// 提取到目标目录
+    Extract { paths: Vec<String>, target_dir: String },
+}
+
+/// 后台线程的响应
+pub enum BrowseEvent {
+    /// 镜像已打开
+    Opened,
+    Listing { path: String, entries: Vec<WimDirEntry> },
+    SearchResult { keyword: String, entries: Vec<WimDirEntry> },
+    Progress(WimlibProgress),
+    Extracted(Result<String, String>),
+    /// 打开或浏览失败
+    Error(String),
+}
+
+/// 备份镜像浏览会话（丢弃时后台线程结束并关闭镜像）
+pub struct WimBrowser {
+    request_tx: Sender<BrowseRequest>,
+    event_rx: Receiver<BrowseEvent>,
+}
+
+impl WimBrowser {
+    /// 在后台线程中打开镜像（index 从 1 开始）
+    pub fn open(image_file: String, index: i32) -> Self {
+        let (request_tx, request_rx) = mpsc::channel();
+        let (event_tx, event_rx) = mpsc::channel();
+        std::thread::spawn(move || run_worker(image_file, index, request_rx, event_tx));
+        Self { request_tx, event_rx }
+    }
+
+    /// 发送请求（后台线程已退出时忽略）
+    pub fn send(&self, request: BrowseRequest) {
+        let _ = self.request_tx.send(request);
+    }
+
+    /// 取出一条响应
+    pub fn try_recv(&self) -> Option<BrowseEvent> {
+        self.event_rx.try_recv().ok()
+    }
+}
+
+fn run_worker(image_file: String, index: i32, request_rx: Receiver<BrowseRequest>, event_tx: Sender<BrowseEvent>) {
+    let wimlib = match Wimlib::new() {
+        Ok(wimlib) if wimlib.supports_browse() => wimlib,
+        Ok(_) => {
+            let _ = event_tx.send(BrowseEvent::Error("当前 wimlib 版本不支持浏览镜像内容".to_string()));
+            return;
+        }
+        Err(e) => {
+            let _ = event_tx.send(BrowseEvent::Error(e));
+            return;
+        }
+    };
+    let wim = match wimlib.open_image_file(&image_file) {
+        Ok(wim) => wim,
+        Err(e) => {
+            let _ = event_tx.send(BrowseEvent::Error(format!("打开镜像失败: {}", e.detailed())));
+            return;
+        }
+    };
+    println!("[WIM BROWSER] 已打开 {} #{}", image_file, index);
+    let _ = event_tx.send(BrowseEvent::Opened);
+
+    while let Ok(request) = request_rx.recv() {
+        let event = match request {
+            BrowseRequest::List(path) => match wim.list_dir(index, &path) {
+                Ok(entries) => BrowseEvent::Listing { path, entries },
+                Err(e) => BrowseEvent::Error(format!("读取目录失败: {}", e)),
+            },
+            BrowseRequest::Search(keyword) => match wim.search(index, &keyword, SEARCH_LIMIT) {
+                Ok(entries) => BrowseEvent::SearchResult { keyword, entries },
+                Err(e) => BrowseEvent::Error(format!("搜索失败: {}", e)),
+            },
+            BrowseRequest::Extract { paths, target_dir } => {
+                println!("[WIM BROWSER] 提取 {} 项到 {}", paths.len(), target_dir);
+                let (progress_tx, progress_rx) = mpsc::channel();
+                let forward_tx = event_tx.clone();
+                let forwarder = std::thread::spawn(move || {
+                    while let Ok(progress) = progress_rx.recv() {
+                        let _ = forward_tx.send(BrowseEvent::Progress(progress));
+                    }
+                });
+                let result = wim
+                    .extract_paths(index, &paths, &target_dir, Some(progress_tx))
+                    .map(|_| format!("已提取 {} 项到 {}", paths.len(), target_dir))
+                    .map_err(|e| format!("提取失败: {}", e));
+                let _ = forwarder.join();
+                BrowseEvent::Extracted(result)
+            }
+        };
+        if event_tx.send(event).is_err() {
+            break;
+        }
+    }
+    println!("[WIM BROWSER] 已关闭 {}", image_file);
+}
+
+/// 上级目录（根目录返回 None）
+pub fn parent_path(path: &str) -> Option<String> {
+    let trimmed = path.trim_end_matches('\\');
+    if trimmed.is_empty() {
+        return None;
+    }
+    Some(trimmed.rsplit_once('\\').map(|(parent, _)| parent.to_string()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parent_path() {
+        assert_eq!(parent_path(""), None);
+        assert_eq!(parent_path("\\"), None);
+        assert_eq!(parent_path("\\Windows"), Some(String::new()));
+        assert_eq!(parent_path("\\Windows\\System32"), Some("\\Windows".to_string()));
+    }
+}
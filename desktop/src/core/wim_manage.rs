@@ -0,0 +1,260 @@
+//! WIM 分卷管理
+//!
+//! 列出 WIM/ESD 中的全部分卷，删除分卷，修改分卷名称、描述和标志（FLAGS，如 Professional），
+//! 以及向分卷添加、从分卷删除文件（驱动、脚本、壁纸等）而无需重新捕获。
+//! 通过 wimlib 就地修改镜像；导出分卷为新 WIM 复用镜像格式转换（`core::image_convert`）。
+
+use anyhow::{anyhow, bail, Result};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+
+use crate::core::dism::DismProgress;
+use crate::core::wimlib::{WimUpdateCommand, Wimlib, WimlibProgress};
+
+/// WIM 中的一个分卷
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WimImageEntry {
+    /// 分卷索引（从 1 开始）
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    /// 版本标志（FLAGS），如 Professional
+    pub flags: String,
+    /// 展开大小（字节）
+    pub total_bytes: u64,
+}
+
+/// 可修改的分卷信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageProperties {
+    pub name: String,
+    pub description: String,
+    pub flags: String,
+}
+
+impl From<&WimImageEntry> for ImageProperties {
+    fn from(entry: &WimImageEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            description: entry.description.clone(),
+            flags: entry.flags.clone(),
+        }
+    }
+}
+
+fn open_wimlib() -> Result<Wimlib> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    if !wimlib.supports_manage() {
+        bail!("当前 wimlib 不支持管理分卷");
+    }
+    Ok(wimlib)
+}
+
+/// 读取镜像中的全部分卷
+pub fn list_images(image_file: &str) -> Result<Vec<WimImageEntry>> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    let wim = wimlib
+        .open_image_file(image_file)
+        .map_err(|e| anyhow!("打开镜像失败: {}", e.detailed()))?;
+    let count = wim.get_image_count();
+    if count < 0 {
+        bail!("无法读取镜像信息");
+    }
+
+    Ok((1..=count)
+        .map(|index| {
+            let (name, description) = wim.get_image_info(index);
+            WimImageEntry {
+                index: index as u32,
+                name,
+                description,
+                flags: wim.get_image_property(index, "FLAGS").unwrap_or_default(),
+                total_bytes: wim
+                    .get_image_property(index, "TOTALBYTES")
+                    .and_then(|bytes| bytes.trim().parse().ok())
+                    .unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+/// 检查修改后的分卷信息：名称不能为空，也不能与其他分卷重复
+pub fn validate_properties(images: &[WimImageEntry], index: u32, properties: &ImageProperties) -> Result<()> {
+    let name = properties.name.trim();
+    if name.is_empty() {
+        bail!("分卷名称不能为空");
+    }
+    if let Some(other) = images
+        .iter()
+        .find(|image| image.index != index && image.name.trim().eq_ignore_ascii_case(name))
+    {
+        bail!("名称与分卷 {} 重复", other.index);
+    }
+    Ok(())
+}
+
+/// 检查是否可以删除分卷：镜像至少保留一个分卷
+pub fn check_deletable(images: &[WimImageEntry], index: u32) -> Result<()> {
+    if !images.iter().any(|image| image.index == index) {
+        bail!("分卷 {} 不存在", index);
+    }
+    if images.len() <= 1 {
+        bail!("镜像只有一个分卷，不能删除");
+    }
+    Ok(())
+}
+
+/// 修改分卷信息，只写入有变化的属性
+pub fn set_properties(image_file: &str, index: u32, old: &ImageProperties, new: &ImageProperties) -> Result<()> {
+    let changes: Vec<(&str, &str)> = [
+        ("NAME", old.name.trim(), new.name.trim()),
+        ("DESCRIPTION", old.description.trim(), new.description.trim()),
+        ("FLAGS", old.flags.trim(), new.flags.trim()),
+    ]
+    .into_iter()
+    .filter(|(_, old, new)| old != new)
+    .map(|(property, _, new)| (property, new))
+    .collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    log::info!("[WIM] 修改 {} 分卷 {}: {:?}", image_file, index, changes);
+    open_wimlib()?
+        .set_image_properties(image_file, index, &changes)
+        .map_err(|e| anyhow!("修改分卷信息失败: {}", e.detailed()))
+}
+
+/// 生成添加命令：文件添加到镜像内目录 `target_dir` 下，文件夹的内容合并到 `target_dir`
+pub fn add_command(source: &Path, target_dir: &str) -> WimUpdateCommand {
+    let target_dir = target_dir.trim().trim_end_matches(['\\', '/']);
+    let target = match source.file_name() {
+        Some(name) if !source.is_dir() => format!("{}\\{}", target_dir, name.to_string_lossy()),
+        _ => target_dir.to_string(),
+    };
+    WimUpdateCommand::Add {
+        source: source.to_path_buf(),
+        target,
+    }
+}
+
+/// 检查镜像内要删除的路径，不允许删除根目录
+pub fn validate_delete_path(path: &str) -> Result<()> {
+    if path.trim().trim_matches(['\\', '/']).is_empty() {
+        bail!("请填写要删除的镜像内路径");
+    }
+    Ok(())
+}
+
+/// 就地向分卷添加或删除文件，进度通过 progress_tx 发送（只追加新数据，不重写整个镜像）
+pub fn update_files(
+    image_file: &str,
+    index: u32,
+    commands: &[WimUpdateCommand],
+    progress_tx: Sender<DismProgress>,
+) -> Result<()> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("wimlib 不可用: {}", e))?;
+    if !wimlib.supports_update() {
+        bail!("当前 wimlib 不支持更新镜像");
+    }
+    log::info!("[WIM] 更新 {} 分卷 {}: {:?}", image_file, index, commands);
+
+    let (wimlib_tx, wimlib_rx) = mpsc::channel::<WimlibProgress>();
+    let forward_thread = std::thread::spawn(move || forward_progress(wimlib_rx, progress_tx));
+    let result = wimlib.update_image(image_file, index, commands, false, Some(wimlib_tx));
+    let _ = forward_thread.join();
+    result.map_err(|e| anyhow!("更新分卷失败: {}", e.detailed()))
+}
+
+/// 把 wimlib 进度转发为 DISM 进度
+fn forward_progress(wimlib_rx: mpsc::Receiver<WimlibProgress>, progress_tx: Sender<DismProgress>) {
+    while let Ok(progress) = wimlib_rx.recv() {
+        let _ = progress_tx.send(DismProgress {
+            percentage: progress.percentage,
+            status: progress.status,
+        });
+    }
+}
+
+/// 删除分卷，进度通过 progress_tx 发送（需要重写整个镜像）
+pub fn delete_image(image_file: &str, index: u32, progress_tx: Sender<DismProgress>) -> Result<()> {
+    delete_images(image_file, &[index], progress_tx)
+}
+
+/// 一次删除多个分卷，只重写一次镜像
+pub fn delete_images(image_file: &str, indices: &[u32], progress_tx: Sender<DismProgress>) -> Result<()> {
+    let wimlib = open_wimlib()?;
+    log::info!("[WIM] 删除 {} 的分卷 {:?}", image_file, indices);
+
+    let (wimlib_tx, wimlib_rx) = mpsc::channel::<WimlibProgress>();
+    let forward_thread = std::thread::spawn(move || forward_progress(wimlib_rx, progress_tx));
+    let result = wimlib.delete_images(image_file, indices, Some(wimlib_tx));
+    let _ = forward_thread.join();
+    result.map_err(|e| anyhow!("删除分卷失败: {}", e.detailed()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: u32, name: &str) -> WimImageEntry {
+        WimImageEntry {
+            index,
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_properties() {
+        let images = vec![entry(1, "Windows 11 Home"), entry(2, "Windows 11 Pro")];
+        let mut properties = ImageProperties::from(&images[1]);
+        assert!(validate_properties(&images, 2, &properties).is_ok());
+
+        properties.name = " windows 11 home ".to_string();
+        assert!(validate_properties(&images, 2, &properties).is_err());
+        assert!(validate_properties(&images, 1, &properties).is_ok());
+
+        properties.name = "  ".to_string();
+        assert!(validate_properties(&images, 2, &properties).is_err());
+    }
+
+    #[test]
+    fn test_add_command() {
+        let dir = std::env::temp_dir().join(format!("lr_wim_add_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("wallpaper.jpg");
+        std::fs::write(&file, b"").unwrap();
+
+        assert_eq!(
+            add_command(&file, "\\Windows\\Web\\"),
+            WimUpdateCommand::Add {
+                source: file.clone(),
+                target: "\\Windows\\Web\\wallpaper.jpg".to_string(),
+            }
+        );
+        assert_eq!(
+            add_command(&dir, "\\Drivers"),
+            WimUpdateCommand::Add {
+                source: dir.clone(),
+                target: "\\Drivers".to_string(),
+            }
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_delete_path() {
+        assert!(validate_delete_path("\\Windows\\Web\\old.jpg").is_ok());
+        assert!(validate_delete_path("\\").is_err());
+        assert!(validate_delete_path("  ").is_err());
+    }
+
+    #[test]
+    fn test_check_deletable() {
+        let images = vec![entry(1, "Windows 11 Home"), entry(2, "Windows 11 Pro")];
+        assert!(check_deletable(&images, 2).is_ok());
+        assert!(check_deletable(&images, 3).is_err());
+        assert!(check_deletable(&images[..1], 1).is_err());
+    }
+}
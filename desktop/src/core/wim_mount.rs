@@ -0,0 +1,110 @@
+//! 备份镜像挂载为虚拟驱动器
+//!
+//! wimlib 的 wimlib_mount_image 依赖 FUSE，Windows 版不提供，这里使用 wimgapi 以只读方式
+//! 挂载到临时目录，再通过 DefineDosDevice 映射为盘符，便于在资源管理器中浏览。
+//! 关闭对话框或程序退出时卸载全部挂载。
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::core::system_utils;
+use crate::core::wimgapi::Wimgapi;
+
+/// 已挂载的备份镜像
+#[derive(Debug, Clone)]
+pub struct MountedImage {
+    pub image_file: String,
+    pub index: u32,
+    pub mount_dir: PathBuf,
+    /// 映射的盘符（映射失败时为 None，仍可通过挂载目录访问）
+    pub drive_letter: Option<char>,
+}
+
+impl MountedImage {
+    /// 在资源管理器中打开的位置
+    pub fn browse_path(&self) -> String {
+        match self.drive_letter {
+            Some(letter) => format!("{}:\\", letter),
+            None => self.mount_dir.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// 从 Z 往前选一个未占用的盘符（不使用 A-C）
+pub fn pick_free_letter(used_mask: u32) -> Option<char> {
+    (b'D'..=b'Z')
+        .rev()
+        .find(|letter| used_mask & (1 << (letter - b'A')) == 0)
+        .map(|letter| letter as char)
+}
+
+/// 以只读方式挂载镜像并映射盘符（index 从 1 开始）
+pub fn mount_image(image_file: &str, index: u32) -> Result<MountedImage> {
+    if image_file.to_lowercase().ends_with(".swm") {
+        bail!("分卷镜像（SWM）不支持挂载，请使用“从备份提取文件”");
+    }
+
+    let wimgapi = Wimgapi::new(None).map_err(|e| anyhow::anyhow!("无法加载 wimgapi.dll: {}", e))?;
+    let mount_dir = std::env::temp_dir().join(format!(
+        "LetRecovery_Browse_{}_{}",
+        std::process::id(),
+        chrono::Local::now().format("%H%M%S%3f")
+    ));
+    std::fs::create_dir_all(&mount_dir).context("创建挂载目录失败")?;
+
+    println!("[WIM MOUNT] 只读挂载 {} #{} -> {}", image_file, index, mount_dir.display());
+    if let Err(e) = wimgapi.mount_image(&mount_dir, Path::new(image_file), index, None) {
+        let _ = std::fs::remove_dir_all(&mount_dir);
+        bail!("挂载镜像失败: {}", e);
+    }
+
+    let drive_letter = pick_free_letter(system_utils::used_drive_letters()).and_then(|letter| {
+        match system_utils::map_drive_letter(letter, &mount_dir) {
+            Ok(()) => Some(letter),
+            Err(e) => {
+                println!("[WIM MOUNT] {:#}，仅通过目录访问", e);
+                None
+            }
+        }
+    });
+
+    Ok(MountedImage {
+        image_file: image_file.to_string(),
+        index,
+        mount_dir,
+        drive_letter,
+    })
+}
+
+/// 移除盘符并卸载镜像（不保存更改）
+pub fn unmount_image(mounted: &MountedImage) -> Result<()> {
+    println!("[WIM MOUNT] 卸载 {} #{}", mounted.image_file, mounted.index);
+    if let Some(letter) = mounted.drive_letter {
+        if let Err(e) = system_utils::unmap_drive_letter(letter) {
+            println!("[WIM MOUNT] {:#}", e);
+        }
+    }
+
+    let wimgapi = Wimgapi::new(None).map_err(|e| anyhow::anyhow!("无法加载 wimgapi.dll: {}", e))?;
+    wimgapi
+        .unmount_image(&mounted.mount_dir, Path::new(&mounted.image_file), mounted.index, false)
+        .map_err(|e| anyhow::anyhow!("卸载镜像失败: {}", e))?;
+    let _ = std::fs::remove_dir_all(&mounted.mount_dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_free_letter() {
+        assert_eq!(pick_free_letter(0), Some('Z'));
+        // C: D: Z: 已占用
+        let used = (1 << 2) | (1 << 3) | (1 << 25);
+        assert_eq!(pick_free_letter(used), Some('Y'));
+        // D-Z 全部占用
+        let all = (0..26).fold(0u32, |mask, i| mask | (1 << i));
+        assert_eq!(pick_free_letter(all), None);
+    }
+}
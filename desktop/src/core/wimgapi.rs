@@ -21,6 +21,8 @@ use std::sync::Arc;
 
 use libloading::Library;
 
+use crate::core::wimlib::find_swm_parts;
+
 #[cfg(windows)]
 use windows::Win32::Foundation::GetLastError;
 
@@ -347,6 +349,11 @@ pub struct WimProgress {
 
 static GLOBAL_PROGRESS: AtomicU8 = AtomicU8::new(0);
 
+/// 判断 WIM_MSG_PROCESS 报告的文件（完整路径）是否被排除
+fn is_capture_excluded(path: *const u16) -> bool {
+    crate::core::capture_options::is_excluded_from_active_capture(&wide_cstr_to_string(path))
+}
+
 /// 进度回调函数
 /// 
 /// 根据 Microsoft 文档，WIM_MSG_PROGRESS 消息中：
@@ -357,7 +364,7 @@ static GLOBAL_PROGRESS: AtomicU8 = AtomicU8::new(0);
 extern "system" fn progress_callback(
     msg_id: u32,
     wparam: usize,
-    _lparam: isize,
+    lparam: isize,
     _user_data: *mut c_void,
 ) -> u32 {
     match msg_id {
@@ -383,7 +390,10 @@ extern "system" fn progress_callback(
             return WIM_MSG_ABORT_IMAGE;
         }
         WIM_MSG_PROCESS => {
-            // 文件处理消息，静默处理
+            // wParam 为文件完整路径，lParam 指向 BOOL，置为 FALSE 时跳过该文件
+            if lparam != 0 && is_capture_excluded(wparam as *const u16) {
+                unsafe { *(lparam as *mut i32) = 0 };
+            }
         }
         _ => {
             // 记录未知消息类型，便于调试
@@ -448,6 +458,20 @@ fn utf16_ptr_to_string(ptr: *const u16, max_len: usize) -> String {
     }
 }
 
+/// 将以 NUL 结尾的 UTF-16 指针转换为 Rust 字符串
+fn wide_cstr_to_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe {
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+    }
+}
+
 /// 获取最后的 Win32 错误码
 #[cfg(windows)]
 fn get_last_error() -> u32 {
@@ -1272,6 +1296,18 @@ impl WimManager {
         })
     }
 
+    /// 镜像为 SWM 分卷时引用其余分卷
+    fn reference_swm_parts(&self, wim_handle: Handle, image_file: &str) -> Result<(), WimApiError> {
+        if !image_file.to_lowercase().ends_with(".swm") {
+            return Ok(());
+        }
+        for part in find_swm_parts(image_file).iter().skip(1) {
+            println!("[WIMGAPI] 引用分卷: {}", part.display());
+            self.wimgapi.set_reference_file(wim_handle, part, WIM_REFERENCE_APPEND)?;
+        }
+        Ok(())
+    }
+
     /// 释放/应用 WIM/ESD 镜像到目标目录
     ///
     /// # 参数
@@ -1307,6 +1343,12 @@ impl WimManager {
         // 设置临时路径
         self.wimgapi.set_temp_path(wim_handle, &temp_dir)?;
 
+        // SWM 分卷镜像需要引用其余分卷（相当于 DISM 的 /SWMFile）
+        if let Err(e) = self.reference_swm_parts(wim_handle, image_file) {
+            self.wimgapi.close(wim_handle)?;
+            return Err(e);
+        }
+
         // 注册进度回调
         self.wimgapi.register_callback(wim_handle);
 
@@ -1378,7 +1420,9 @@ impl WimManager {
     /// - `name`: 镜像名称
     /// - `description`: 镜像描述
     /// - `compression`: 压缩类型
+    /// - `exclusions`: 排除规则（WimScript.ini 格式，相对于源目录）
     /// - `progress_tx`: 进度发送器 (可选)
+    #[allow(clippy::too_many_arguments)]
     pub fn capture_image(
         &self,
         source_dir: &str,
@@ -1386,6 +1430,7 @@ impl WimManager {
         name: &str,
         description: &str,
         compression: u32,
+        exclusions: &[String],
         progress_tx: Option<std::sync::mpsc::Sender<WimProgress>>,
     ) -> Result<(), WimApiError> {
         let source_path = Path::new(source_dir);
@@ -1437,8 +1482,10 @@ impl WimManager {
             }
         });
 
-        // 捕获镜像
+        // 捕获镜像（排除规则在 WIM_MSG_PROCESS 回调中生效）
+        crate::core::capture_options::set_active_capture(Some((source_dir, exclusions)));
         let capture_result = self.wimgapi.capture_image(wim_handle, source_path, 0);
+        crate::core::capture_options::set_active_capture(None);
 
         let image_handle = match capture_result {
             Ok(h) => h,
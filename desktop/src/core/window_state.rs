@@ -0,0 +1,241 @@
+//! 窗口状态模块
+//! 按显示器布局分别记录窗口位置、大小和最大化状态，启动时恢复并限制在可见区域内
+
+use serde::{Deserialize, Serialize};
+
+/// 窗口默认大小
+pub const DEFAULT_WINDOW_SIZE: [f32; 2] = [950.0, 680.0];
+
+/// 窗口最小大小
+pub const MIN_WINDOW_SIZE: [f32; 2] = [800.0, 600.0];
+
+/// 标题栏至少需要可见的高度/宽度，保证窗口仍可被拖动
+const VISIBLE_TITLE_HEIGHT: f32 = 30.0;
+const VISIBLE_TITLE_WIDTH: f32 = 100.0;
+
+/// 显示器工作区（逻辑坐标，已按显示器缩放换算）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MonitorArea {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// 显示缩放比例（1.0 = 100%）
+    pub scale: f32,
+    /// 是否为主显示器
+    pub is_primary: bool,
+}
+
+impl MonitorArea {
+    /// 计算与指定矩形的重叠面积
+    fn overlap(&self, x: f32, y: f32, width: f32, height: f32) -> f32 {
+        let w = (self.x + self.width).min(x + width) - self.x.max(x);
+        let h = (self.y + self.height).min(y + height) - self.y.max(y);
+        if w > 0.0 && h > 0.0 {
+            w * h
+        } else {
+            0.0
+        }
+    }
+}
+
+/// 窗口状态
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowState {
+    /// 窗口外框左上角 X（逻辑坐标）
+    pub x: f32,
+    /// 窗口外框左上角 Y（逻辑坐标）
+    pub y: f32,
+    /// 客户区宽度
+    pub width: f32,
+    /// 客户区高度
+    pub height: f32,
+    /// 是否最大化
+    #[serde(default)]
+    pub maximized: bool,
+}
+
+impl WindowState {
+    /// 将界面坐标换算为窗口坐标（PE 高分辨率屏幕上界面额外缩放时使用）
+    pub fn zoomed(&self, zoom: f32) -> WindowState {
+        WindowState {
+            x: self.x * zoom,
+            y: self.y * zoom,
+            width: self.width * zoom,
+            height: self.height * zoom,
+            maximized: self.maximized,
+        }
+    }
+
+    /// 将窗口限制在当前显示器布局的可见区域内
+    ///
+    /// 标题栏不在任何显示器上时（如外接显示器已断开），移动到主显示器居中；
+    /// 无法获取显示器信息时原样返回
+    pub fn clamp_to(&self, monitors: &[MonitorArea]) -> WindowState {
+        if monitors.is_empty() {
+            return *self;
+        }
+
+        let title_visible = monitors.iter().any(|m| {
+            let w = (m.x + m.width).min(self.x + self.width) - m.x.max(self.x);
+            let h = (m.y + m.height).min(self.y + VISIBLE_TITLE_HEIGHT) - m.y.max(self.y);
+            w >= VISIBLE_TITLE_WIDTH && h > 0.0
+        });
+
+        let monitor = if title_visible {
+            monitors
+                .iter()
+                .max_by(|a, b| {
+                    let oa = a.overlap(self.x, self.y, self.width, self.height);
+                    let ob = b.overlap(self.x, self.y, self.width, self.height);
+                    oa.total_cmp(&ob)
+                })
+                .unwrap_or(&monitors[0])
+        } else {
+            monitors.iter().find(|m| m.is_primary).unwrap_or(&monitors[0])
+        };
+
+        let width = self.width.min(monitor.width).max(MIN_WINDOW_SIZE[0]);
+        let height = self.height.min(monitor.height).max(MIN_WINDOW_SIZE[1]);
+
+        let (x, y) = if title_visible {
+            (
+                self.x.min(monitor.x + monitor.width - width).max(monitor.x),
+                self.y.min(monitor.y + monitor.height - height).max(monitor.y),
+            )
+        } else {
+            (
+                monitor.x + (monitor.width - width).max(0.0) / 2.0,
+                monitor.y + (monitor.height - height).max(0.0) / 2.0,
+            )
+        };
+
+        WindowState {
+            x,
+            y,
+            width,
+            height,
+            maximized: self.maximized,
+        }
+    }
+}
+
+/// 生成显示器布局标识，用于区分不同的多显示器配置
+pub fn layout_key(monitors: &[MonitorArea]) -> String {
+    if monitors.is_empty() {
+        return "default".to_string();
+    }
+    let mut parts: Vec<String> = monitors
+        .iter()
+        .map(|m| {
+            format!(
+                "{}x{}@{},{}x{}",
+                (m.width * m.scale).round() as i32,
+                (m.height * m.scale).round() as i32,
+                (m.x * m.scale).round() as i32,
+                (m.y * m.scale).round() as i32,
+                (m.scale * 100.0).round() as i32
+            )
+        })
+        .collect();
+    parts.sort();
+    parts.join(";")
+}
+
+/// 枚举所有显示器的工作区
+#[cfg(windows)]
+pub fn enumerate_monitors() -> Vec<MonitorArea> {
+    use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO,
+    };
+    use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+
+    const MONITORINFOF_PRIMARY: u32 = 1;
+
+    unsafe extern "system" fn callback(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<MonitorArea>);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if GetMonitorInfoW(monitor, &mut info).as_bool() {
+            let mut dpi_x = 96u32;
+            let mut dpi_y = 96u32;
+            let scale = if GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).is_ok() {
+                dpi_x as f32 / 96.0
+            } else {
+                1.0
+            };
+            let work = info.rcWork;
+            monitors.push(MonitorArea {
+                x: work.left as f32 / scale,
+                y: work.top as f32 / scale,
+                width: (work.right - work.left) as f32 / scale,
+                height: (work.bottom - work.top) as f32 / scale,
+                scale,
+                is_primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            });
+        }
+        BOOL(1)
+    }
+
+    let mut monitors: Vec<MonitorArea> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(callback),
+            LPARAM(&mut monitors as *mut Vec<MonitorArea> as isize),
+        );
+    }
+    monitors
+}
+
+#[cfg(not(windows))]
+pub fn enumerate_monitors() -> Vec<MonitorArea> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(x: f32, width: f32, is_primary: bool) -> MonitorArea {
+        MonitorArea {
+            x,
+            y: 0.0,
+            width,
+            height: 1040.0,
+            scale: 1.0,
+            is_primary,
+        }
+    }
+
+    #[test]
+    fn test_clamp_keeps_visible_window() {
+        let monitors = [monitor(0.0, 1920.0, true), monitor(1920.0, 2560.0, false)];
+        let state = WindowState { x: 2000.0, y: 100.0, width: 950.0, height: 680.0, maximized: false };
+        assert_eq!(state.clamp_to(&monitors), state);
+
+        // 超出右边界时拉回显示器内
+        let state = WindowState { x: 4000.0, ..state };
+        assert_eq!(state.clamp_to(&monitors).x, 1920.0 + 2560.0 - 950.0);
+    }
+
+    #[test]
+    fn test_clamp_moves_offscreen_window_to_primary() {
+        let monitors = [monitor(0.0, 1920.0, true)];
+        let state = WindowState { x: 2500.0, y: 100.0, width: 3000.0, height: 680.0, maximized: true };
+        let clamped = state.clamp_to(&monitors);
+        assert_eq!(clamped.width, 1920.0);
+        assert_eq!(clamped.x, 0.0);
+        assert_eq!(clamped.y, (1040.0 - 680.0) / 2.0);
+        assert!(clamped.maximized);
+    }
+}
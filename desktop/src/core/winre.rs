@@ -0,0 +1,264 @@
+//! Windows RE 与「重置此电脑」
+//!
+//! 封装 reagentc：查询 Windows RE 状态，把备份的 WIM 注册为系统的恢复映像（`/setosimage`），
+//! 让 Windows 自带的「重置此电脑」使用该镜像。Windows 10 起 reagentc 不再支持 `/setosimage`，
+//! 「重置此电脑」只能使用系统自身的文件，这些系统上拒绝注册。
+//! 注册前备份整个 ReAgent.xml，取消注册时按 `/disable`、还原备份、`/enable` 的顺序恢复原配置。
+//! reagentc 要求镜像文件名为 install.wim，其他文件名在同目录创建硬链接，取消注册时一并删除。
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::core::wim_manage;
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// reagentc 识别的恢复映像文件名
+pub const IMAGE_FILE_NAME: &str = "install.wim";
+/// 硬链接标记文件（与 install.wim 同目录），取消注册时据此删除创建的硬链接
+const LINK_MARKER: &str = "install.wim.letrecovery";
+/// 注册前 ReAgent.xml 的备份后缀
+const REAGENT_BACKUP_SUFFIX: &str = ".letrecovery.bak";
+/// 不再支持 `/setosimage` 的第一个版本（Windows 10 RTM）
+const SETOSIMAGE_REMOVED_BUILD: u32 = 10240;
+
+/// Windows RE 状态（`reagentc /info`）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WinreStatus {
+    pub enabled: bool,
+    /// Windows RE 映像位置
+    pub winre_location: String,
+    /// BCD 标识符
+    pub bcd_id: String,
+    /// 已注册的恢复映像所在目录，未注册时为空
+    pub image_location: String,
+    pub image_index: Option<u32>,
+    /// 当前系统能否注册自定义恢复映像（Windows 8 / 8.1）
+    pub os_image_supported: bool,
+}
+
+impl WinreStatus {
+    /// 是否已注册恢复映像
+    pub fn image_registered(&self) -> bool {
+        !self.image_location.is_empty()
+    }
+}
+
+/// 执行 reagentc，失败时返回错误
+pub fn reagentc(args: &[&str]) -> Result<String> {
+    let output = create_command("reagentc.exe")
+        .args(args)
+        .output()
+        .context("执行 reagentc 失败")?;
+    let stdout = gbk_to_utf8(&output.stdout);
+    log::info!("[WINRE] reagentc {:?}: {}", args, stdout.trim());
+    if !output.status.success() {
+        bail!("reagentc {} 失败: {}", args.join(" "), stdout.trim());
+    }
+    Ok(stdout)
+}
+
+/// 解析 `reagentc /info` 的输出（支持中英文系统）
+pub fn parse_info(output: &str) -> WinreStatus {
+    let mut status = WinreStatus::default();
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once([':', '：']) else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+        // 自定义映像（Custom image）不是 /setosimage 注册的恢复映像
+        if key.contains("custom") || key.contains("自定义") {
+            continue;
+        }
+        if key.contains("status") || key.contains("状态") {
+            status.enabled = value.eq_ignore_ascii_case("enabled") || value == "已启用";
+        } else if key.contains("windows re location") || key.contains("windows re 位置") {
+            status.winre_location = value.to_string();
+        } else if key.contains("bcd") {
+            status.bcd_id = value.to_string();
+        } else if key.contains("image location") || key.contains("映像位置") {
+            status.image_location = value.to_string();
+        } else if key.contains("image index") || key.contains("映像索引") {
+            status.image_index = value.parse().ok().filter(|&index| index > 0);
+        }
+    }
+    status
+}
+
+/// 查询 Windows RE 状态
+pub fn query_status() -> Result<WinreStatus> {
+    let mut status = parse_info(&reagentc(&["/info"])?);
+    status.os_image_supported = os_build().is_some_and(supports_os_image);
+    Ok(status)
+}
+
+/// 当前系统的版本号（读取 ntdll.dll 的文件版本）
+fn os_build() -> Option<u32> {
+    let ntdll = Path::new(&windir()).join("System32").join("ntdll.dll");
+    crate::core::system_utils::get_file_version(&ntdll).map(|(_, _, build, _)| build as u32)
+}
+
+/// 该版本的 reagentc 是否支持 `/setosimage`（Windows 8 / 8.1）
+pub fn supports_os_image(build: u32) -> bool {
+    build < SETOSIMAGE_REMOVED_BUILD
+}
+
+/// 把备份镜像中的指定分卷注册为「重置此电脑」使用的恢复映像
+///
+/// 只支持 Windows 8 / 8.1，Windows 10 及以上版本返回错误
+pub fn register_image(image_path: &str, index: u32) -> Result<String> {
+    let build = os_build().context("无法确定当前系统版本")?;
+    if !supports_os_image(build) {
+        bail!(
+            "当前系统（版本 {}）的「重置此电脑」只能使用系统自身的文件，reagentc 不再支持 /setosimage；\
+             可安装一键恢复环境，从启动菜单进入 PE 还原备份",
+            build
+        );
+    }
+
+    let image = Path::new(image_path);
+    if !image.exists() {
+        bail!("镜像文件不存在: {}", image_path);
+    }
+    let images = wim_manage::list_images(image_path)?;
+    if !images.iter().any(|entry| entry.index == index) {
+        bail!("镜像中没有分卷 {}", index);
+    }
+    let dir = image.parent().context("无法确定镜像所在目录")?;
+    let install = dir.join(IMAGE_FILE_NAME);
+
+    let is_install = image
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().eq_ignore_ascii_case(IMAGE_FILE_NAME));
+    let linked = !is_install;
+    if linked {
+        if install.exists() {
+            bail!("目录 {} 中已有其他 {}，请把镜像放到单独的目录后重试", dir.display(), IMAGE_FILE_NAME);
+        }
+        std::fs::hard_link(image, &install).context("创建 install.wim 硬链接失败（镜像需位于 NTFS 分区）")?;
+        let _ = std::fs::write(dir.join(LINK_MARKER), image_path);
+    }
+
+    // 第一次注册时备份原配置，取消注册时整体还原
+    let reagent_xml = reagent_xml_path();
+    let backup = reagent_backup_path();
+    if reagent_xml.exists() && !backup.exists() {
+        if let Err(e) = std::fs::copy(&reagent_xml, &backup) {
+            if linked {
+                remove_link(dir);
+            }
+            return Err(e).with_context(|| format!("备份 {} 失败", reagent_xml.display()));
+        }
+    }
+
+    let dir_text = dir.to_string_lossy().to_string();
+    let target = windir();
+    let args = ["/setosimage", "/path", &dir_text, "/index", &index.to_string(), "/target", &target];
+    if let Err(e) = reagentc(&args) {
+        if linked {
+            remove_link(dir);
+        }
+        return Err(e);
+    }
+    Ok(format!("已将 {} 的分卷 {} 注册为恢复映像", image_path, index))
+}
+
+/// 删除注册时创建的 install.wim 硬链接
+fn remove_link(dir: &Path) {
+    let marker = dir.join(LINK_MARKER);
+    if marker.exists() {
+        if let Err(e) = std::fs::remove_file(dir.join(IMAGE_FILE_NAME)) {
+            log::warn!("[WINRE] 删除 install.wim 硬链接失败: {}", e);
+        }
+        let _ = std::fs::remove_file(marker);
+    }
+}
+
+fn windir() -> String {
+    std::env::var("windir").unwrap_or_else(|_| "C:\\Windows".to_string())
+}
+
+fn reagent_xml_path() -> PathBuf {
+    PathBuf::from(windir()).join("System32").join("Recovery").join("ReAgent.xml")
+}
+
+fn reagent_backup_path() -> PathBuf {
+    PathBuf::from(format!("{}{}", reagent_xml_path().display(), REAGENT_BACKUP_SUFFIX))
+}
+
+/// 取消注册恢复映像，「重置此电脑」恢复使用系统自带的方式
+///
+/// 禁用 Windows RE 后还原注册前备份的 ReAgent.xml，再重新启用，由 reagentc 按原配置重建
+pub fn unregister_image() -> Result<()> {
+    let status = query_status()?;
+    if !status.image_registered() {
+        return Ok(());
+    }
+    let backup = reagent_backup_path();
+    if !backup.exists() {
+        bail!("没有找到注册前备份的 ReAgent.xml，恢复映像不是由本程序注册的");
+    }
+
+    let was_enabled = status.enabled;
+    reagentc(&["/disable"])?;
+    let result = std::fs::copy(&backup, reagent_xml_path()).context("还原 ReAgent.xml 失败");
+    if was_enabled {
+        reagentc(&["/enable"])?;
+    }
+    result?;
+    let _ = std::fs::remove_file(&backup);
+
+    remove_link(Path::new(&status.image_location));
+    log::info!("[WINRE] 已取消注册恢复映像 {}", status.image_location);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info_english() {
+        let output = "Windows Recovery Environment (Windows RE) and system reset configuration\n\
+            Information:\n\n\
+            \x20   Windows RE status:         Enabled\n\
+            \x20   Windows RE location:       \\\\?\\GLOBALROOT\\device\\harddisk0\\partition4\\Recovery\\WindowsRE\n\
+            \x20   Boot Configuration Data (BCD) identifier: 7a7c3b2e-1d2f-11ee-9d5b-8c8caa1e2f10\n\
+            \x20   Recovery image location:   \\\\?\\GLOBALROOT\\device\\harddisk0\\partition3\\Backup\n\
+            \x20   Recovery image index:      2\n\
+            \x20   Custom image location:\n\
+            \x20   Custom image index:        0\n\n\
+            REAGENTC.EXE: Operation Successful.\n";
+        let status = parse_info(output);
+        assert!(status.enabled);
+        assert!(status.winre_location.ends_with("\\Recovery\\WindowsRE"));
+        assert_eq!(status.bcd_id, "7a7c3b2e-1d2f-11ee-9d5b-8c8caa1e2f10");
+        assert!(status.image_location.ends_with("\\Backup"));
+        assert_eq!(status.image_index, Some(2));
+        assert!(status.image_registered());
+    }
+
+    #[test]
+    fn test_parse_info_chinese() {
+        let output = "Windows 恢复环境(Windows RE)和系统重置配置\n信息:\n\n\
+            \x20   Windows RE 状态:         Disabled\n\
+            \x20   Windows RE 位置:\n\
+            \x20   引导配置数据(BCD)标识符: 00000000-0000-0000-0000-000000000000\n\
+            \x20   恢复映像位置:\n\
+            \x20   恢复映像索引:           0\n";
+        let status = parse_info(output);
+        assert!(!status.enabled);
+        assert!(status.winre_location.is_empty());
+        assert_eq!(status.image_index, None);
+        assert!(!status.image_registered());
+    }
+
+    #[test]
+    fn test_supports_os_image() {
+        assert!(supports_os_image(9200));
+        assert!(supports_os_image(9600));
+        assert!(!supports_os_image(10240));
+        assert!(!supports_os_image(22631));
+    }
+}
@@ -0,0 +1,136 @@
+//! 公告/维护通知模块
+//! 由服务器下发，在首页以横幅形式展示
+
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+
+/// 公告级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementLevel {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+/// 公告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Announcement {
+    /// 公告ID（用于记录用户关闭状态）
+    pub id: String,
+    /// 标题
+    #[serde(default)]
+    pub title: String,
+    /// 内容（Markdown 子集：标题、列表、粗体行）
+    pub content: String,
+    /// 级别
+    #[serde(default)]
+    pub level: AnnouncementLevel,
+    /// 是否允许用户关闭
+    #[serde(default = "default_dismissible")]
+    pub dismissible: bool,
+    /// 开始显示时间（本地时间，如 "2026-03-01 08:00"，也支持 RFC3339）
+    #[serde(default)]
+    pub start: Option<String>,
+    /// 结束显示时间
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+/// 公告默认可关闭
+fn default_dismissible() -> bool {
+    true
+}
+
+/// 公告列表JSON格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnouncementList {
+    pub announcements: Vec<Announcement>,
+}
+
+impl Announcement {
+    /// 解析公告列表（JSON格式）
+    pub fn parse_list(content: &str) -> Vec<Announcement> {
+        match serde_json::from_str::<AnnouncementList>(content) {
+            Ok(list) => {
+                log::info!("公告加载成功，共 {} 条", list.announcements.len());
+                list.announcements
+            }
+            Err(e) => {
+                log::warn!("解析公告列表失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 检查公告在指定时间是否处于显示时间窗口内
+    ///
+    /// 时间格式无法解析时视为未设置该边界
+    pub fn is_active_at(&self, now: DateTime<Local>) -> bool {
+        let after_start = self
+            .start
+            .as_deref()
+            .and_then(parse_time)
+            .map(|start| now >= start)
+            .unwrap_or(true);
+        let before_end = self
+            .end
+            .as_deref()
+            .and_then(parse_time)
+            .map(|end| now < end)
+            .unwrap_or(true);
+        after_start && before_end
+    }
+
+    /// 检查公告当前是否应该显示
+    pub fn is_active(&self) -> bool {
+        self.is_active_at(Local::now())
+    }
+}
+
+/// 解析公告时间
+fn parse_time(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(value, fmt).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .or_else(|| {
+            log::warn!("公告时间格式无效: {}", value);
+            None
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_schedule() {
+        let content = r#"{
+            "announcements": [
+                { "id": "mirror-down", "title": "镜像维护", "content": "部分镜像暂不可用",
+                  "level": "warning", "start": "2026-03-01 08:00", "end": "2026-03-02" }
+            ]
+        }"#;
+        let list = Announcement::parse_list(content);
+        assert_eq!(list.len(), 1);
+        let notice = &list[0];
+        assert_eq!(notice.level, AnnouncementLevel::Warning);
+        assert!(notice.dismissible);
+
+        let at = |s: &str| parse_time(s).unwrap();
+        assert!(!notice.is_active_at(at("2026-03-01 07:59")));
+        assert!(notice.is_active_at(at("2026-03-01 12:00")));
+        assert!(!notice.is_active_at(at("2026-03-02 00:00")));
+    }
+}
@@ -1,5 +1,11 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::announcement::Announcement;
+use super::help::{self, HelpOverride};
+use super::server_config::RemoteConfig;
+use crate::core::hash::{self, DeclaredHash};
 
 /// 在线系统镜像信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +13,9 @@ pub struct OnlineSystem {
     pub download_url: String,
     pub display_name: String,
     pub is_win11: bool,
+    /// 分块清单地址（用于增量下载，可选）
+    #[serde(default)]
+    pub chunk_manifest_url: Option<String>,
 }
 
 /// 在线 PE 信息
@@ -196,6 +205,20 @@ pub struct ConfigManager {
     pub gpu_driver_list: Vec<OnlineGpuDriver>,
     /// 小白模式配置
     pub easy_mode_config: Option<EasyModeConfig>,
+    /// 镜像安装预设
+    pub image_presets: Vec<ImagePreset>,
+    /// 服务器公告
+    pub announcements: Vec<Announcement>,
+    /// 镜像许可协议
+    pub image_eulas: Vec<ImageEula>,
+    /// 存储控制器驱动包
+    pub driver_packs: Vec<OnlineDriverPack>,
+    /// 按硬件 ID 匹配的网卡/存储驱动索引
+    pub hwid_drivers: Vec<OnlineHwidDriver>,
+    /// 服务器下发的帮助内容
+    pub help_overrides: Vec<HelpOverride>,
+    /// 服务器声明的文件哈希
+    pub file_hashes: Vec<DeclaredHash>,
 }
 
 impl ConfigManager {
@@ -227,7 +250,7 @@ impl ConfigManager {
             Vec::new()
         };
 
-        Ok(Self { systems, pe_list, software_list: Vec::new(), gpu_driver_list: Vec::new(), easy_mode_config: None })
+        Ok(Self { systems, pe_list, ..Default::default() })
     }
     
     /// 从远程配置内容加载
@@ -244,7 +267,7 @@ impl ConfigManager {
             .map(|c| Self::parse_pe_list(c))
             .unwrap_or_default();
         
-        Self { systems, pe_list, software_list: Vec::new(), gpu_driver_list: Vec::new(), easy_mode_config: None }
+        Self { systems, pe_list, ..Default::default() }
     }
     
     /// 从远程配置内容加载（包含软件列表）
@@ -270,7 +293,7 @@ impl ConfigManager {
             .map(|c| Self::parse_software_list(c))
             .unwrap_or_default();
         
-        Self { systems, pe_list, software_list, gpu_driver_list: Vec::new(), easy_mode_config: None }
+        Self { systems, pe_list, software_list, ..Default::default() }
     }
     
     /// 从远程配置内容加载（完整版，包含所有配置）
@@ -301,7 +324,7 @@ impl ConfigManager {
         let easy_mode_config = easy_content
             .and_then(|c| EasyModeConfig::parse(c));
         
-        Self { systems, pe_list, software_list, gpu_driver_list: Vec::new(), easy_mode_config }
+        Self { systems, pe_list, software_list, easy_mode_config, ..Default::default() }
     }
     
     /// 从远程配置内容加载（完整版+GPU驱动，包含所有配置）
@@ -338,11 +361,66 @@ impl ConfigManager {
             .map(|c| Self::parse_gpu_driver_list(c))
             .unwrap_or_default();
         
-        Self { systems, pe_list, software_list, gpu_driver_list, easy_mode_config }
+        Self { systems, pe_list, software_list, gpu_driver_list, easy_mode_config, ..Default::default() }
+    }
+
+    /// 从远程配置加载（包含所有配置项）
+    pub fn from_remote(remote: &RemoteConfig) -> Self {
+        let mut config = Self::load_from_content_full_with_gpu(
+            remote.dl_content.as_deref(),
+            remote.pe_content.as_deref(),
+            remote.soft_content.as_deref(),
+            remote.easy_content.as_deref(),
+            remote.gpu_content.as_deref(),
+        );
+
+        config.image_presets = remote
+            .preset_content
+            .as_deref()
+            .map(Self::parse_image_presets)
+            .unwrap_or_default();
+
+        config.image_eulas = remote
+            .eula_content
+            .as_deref()
+            .map(Self::parse_image_eulas)
+            .unwrap_or_default();
+
+        config.driver_packs = remote
+            .driver_content
+            .as_deref()
+            .map(Self::parse_driver_packs)
+            .unwrap_or_default();
+
+        config.hwid_drivers = remote
+            .hwid_content
+            .as_deref()
+            .map(Self::parse_hwid_drivers)
+            .unwrap_or_default();
+
+        config.announcements = remote
+            .notice_content
+            .as_deref()
+            .map(Announcement::parse_list)
+            .unwrap_or_default();
+
+        config.help_overrides = remote
+            .help_content
+            .as_deref()
+            .map(help::parse_overrides)
+            .unwrap_or_default();
+
+        config.file_hashes = remote
+            .hash_content
+            .as_deref()
+            .map(hash::parse_declared)
+            .unwrap_or_default();
+
+        config
     }
 
     /// 解析系统列表
-    /// 格式: URL,显示名称,Win11/Win10
+    /// 格式: URL,显示名称,Win11/Win10[,分块清单URL]
     pub fn parse_system_list(content: &str) -> Vec<OnlineSystem> {
         content
             .lines()
@@ -354,12 +432,17 @@ impl ConfigManager {
                         download_url: parts[0].trim().to_string(),
                         display_name: parts[1].trim().to_string(),
                         is_win11: parts[2].trim().eq_ignore_ascii_case("Win11"),
+                        chunk_manifest_url: parts
+                            .get(3)
+                            .map(|s| s.trim().to_string())
+                            .filter(|s| !s.is_empty()),
                     })
                 } else if parts.len() >= 2 {
                     Some(OnlineSystem {
                         download_url: parts[0].trim().to_string(),
                         display_name: parts[1].trim().to_string(),
                         is_win11: parts[1].to_lowercase().contains("11"),
+                        chunk_manifest_url: None,
                     })
                 } else {
                     None
@@ -435,6 +518,69 @@ impl ConfigManager {
         }
     }
 
+    /// 解析镜像安装预设（JSON格式）
+    pub fn parse_image_presets(content: &str) -> Vec<ImagePreset> {
+        match serde_json::from_str::<ImagePresetList>(content) {
+            Ok(list) => {
+                log::info!("镜像安装预设加载成功，共 {} 条", list.presets.len());
+                list.presets
+            }
+            Err(e) => {
+                log::warn!("解析镜像安装预设失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 查找与镜像匹配的安装预设
+    ///
+    /// 依次用镜像文件名、分卷名称匹配，返回第一条命中的预设
+    pub fn find_image_preset(&self, names: &[&str]) -> Option<&ImagePreset> {
+        self.image_presets
+            .iter()
+            .find(|preset| names.iter().any(|name| preset.matches(name)))
+    }
+
+    /// 解析镜像许可协议（JSON格式）
+    pub fn parse_image_eulas(content: &str) -> Vec<ImageEula> {
+        match serde_json::from_str::<ImageEulaList>(content) {
+            Ok(list) => list.eulas,
+            Err(e) => {
+                log::warn!("解析镜像许可协议失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 解析存储控制器驱动包列表（JSON格式）
+    pub fn parse_driver_packs(content: &str) -> Vec<OnlineDriverPack> {
+        match serde_json::from_str::<DriverPackList>(content) {
+            Ok(list) => list.packs,
+            Err(e) => {
+                log::warn!("解析驱动包列表失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 解析硬件 ID 驱动索引（JSON格式）
+    pub fn parse_hwid_drivers(content: &str) -> Vec<OnlineHwidDriver> {
+        match serde_json::from_str::<HwidDriverIndex>(content) {
+            Ok(index) => index.drivers,
+            Err(e) => {
+                log::warn!("解析硬件 ID 驱动索引失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 查找与镜像匹配的许可协议
+    pub fn find_image_eula(&self, names: &[&str]) -> Option<&ImageEula> {
+        self.image_eulas
+            .iter()
+            .find(|eula| names.iter().any(|name| keywords_match(&eula.keywords, name)))
+    }
+
     /// 检查配置是否为空
     pub fn is_empty(&self) -> bool {
         self.systems.is_empty() && self.pe_list.is_empty()
@@ -503,3 +649,212 @@ impl EasyModeConfig {
             .collect()
     }
 }
+
+/// 镜像安装预设
+///
+/// 由服务器下发，按关键字匹配镜像文件名或分卷名称，
+/// `options` 的键为高级选项字段名（如 `disable_windows_update`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePreset {
+    /// 预设名称
+    pub name: String,
+    /// 匹配关键字（不区分大小写，任一命中即可）
+    #[serde(rename = "match")]
+    pub keywords: Vec<String>,
+    /// 推荐的高级选项
+    #[serde(default)]
+    pub options: BTreeMap<String, bool>,
+}
+
+impl ImagePreset {
+    /// 检查名称是否命中该预设
+    pub fn matches(&self, name: &str) -> bool {
+        keywords_match(&self.keywords, name)
+    }
+}
+
+/// 检查名称是否包含任一关键字（不区分大小写）
+fn keywords_match(keywords: &[String], name: &str) -> bool {
+    let name = name.to_lowercase();
+    keywords
+        .iter()
+        .map(|k| k.trim().to_lowercase())
+        .any(|k| !k.is_empty() && name.contains(&k))
+}
+
+/// 镜像安装预设JSON格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagePresetList {
+    pub presets: Vec<ImagePreset>,
+}
+
+/// 镜像许可协议（部分 OEM 镜像要求用户下载/安装前接受）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageEula {
+    /// 协议ID
+    pub id: String,
+    /// 协议版本（版本变化后需重新接受）
+    #[serde(default)]
+    pub version: String,
+    /// 标题
+    pub title: String,
+    /// 协议正文
+    pub text: String,
+    /// 匹配关键字（镜像文件名或名称）
+    #[serde(rename = "match")]
+    pub keywords: Vec<String>,
+}
+
+impl ImageEula {
+    /// 接受记录的标识（ID@版本）
+    pub fn acceptance_key(&self) -> String {
+        format!("{}@{}", self.id, self.version)
+    }
+}
+
+/// 镜像许可协议JSON格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageEulaList {
+    pub eulas: Vec<ImageEula>,
+}
+
+/// 在线存储控制器驱动包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnlineDriverPack {
+    /// 驱动包ID（即 drivers\storage_controller 下的目录名）
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 版本号
+    pub version: String,
+    /// 描述
+    #[serde(default)]
+    pub description: String,
+    /// 下载URL（.cab 格式）
+    pub download_url: String,
+    /// 文件的 SHA256（十六进制），缺少时不下载该驱动包
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 文件大小
+    #[serde(default)]
+    pub file_size: String,
+}
+
+/// 驱动包列表JSON格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriverPackList {
+    pub packs: Vec<OnlineDriverPack>,
+}
+
+/// 按硬件 ID 匹配的在线驱动（网卡、存储控制器）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnlineHwidDriver {
+    /// 驱动ID（即数据分区 drivers\hwid 下的目录名）
+    pub id: String,
+    /// 显示名称
+    pub name: String,
+    /// 版本号
+    #[serde(default)]
+    pub version: String,
+    /// 支持的硬件 ID，如 PCI\VEN_8086&DEV_15F3，匹配设备硬件 ID 的前缀
+    pub hardware_ids: Vec<String>,
+    /// 下载URL（.cab / .zip / .7z 格式）
+    pub download_url: String,
+    /// 文件的 SHA256（十六进制），缺少时不下载该驱动
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 文件大小
+    #[serde(default)]
+    pub file_size: String,
+}
+
+/// 硬件 ID 驱动索引JSON格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HwidDriverIndex {
+    pub drivers: Vec<OnlineHwidDriver>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_match_image_presets() {
+        let content = r#"{
+            "presets": [
+                { "name": "Win7", "match": ["win7", "Windows 7"], "options": { "win7_uefi_patch": true } },
+                { "name": "LTSC", "match": ["LTSC"], "options": { "disable_windows_update": true } }
+            ]
+        }"#;
+        let config = ConfigManager {
+            image_presets: ConfigManager::parse_image_presets(content),
+            ..Default::default()
+        };
+
+        assert_eq!(config.image_presets.len(), 2);
+        let preset = config
+            .find_image_preset(&["zh-cn_windows_10_enterprise_ltsc_2021.iso", ""])
+            .unwrap();
+        assert_eq!(preset.name, "LTSC");
+        assert_eq!(preset.options.get("disable_windows_update"), Some(&true));
+        assert_eq!(
+            config.find_image_preset(&["", "Windows 7 旗舰版"]).map(|p| p.name.as_str()),
+            Some("Win7")
+        );
+        assert!(config.find_image_preset(&["win11_24h2.iso", ""]).is_none());
+    }
+
+    #[test]
+    fn test_find_image_eula() {
+        let content = r#"{"eulas":[{"id":"oem","version":"2","title":"OEM","text":"...","match":["oem_"]}]}"#;
+        let config = ConfigManager {
+            image_eulas: ConfigManager::parse_image_eulas(content),
+            ..Default::default()
+        };
+
+        let eula = config.find_image_eula(&["OEM_Win10.iso"]).unwrap();
+        assert_eq!(eula.acceptance_key(), "oem@2");
+        assert!(config.find_image_eula(&["win10.iso"]).is_none());
+    }
+
+    #[test]
+    fn test_parse_driver_packs() {
+        let content = r#"{
+            "packs": [
+                { "id": "amd_raid", "name": "AMD RAID", "version": "9.3.2",
+                  "download_url": "https://example.com/amd_raid.cab", "sha256": "abcd" },
+                { "id": "virtio", "name": "VirtIO", "version": "0.1.266",
+                  "download_url": "https://example.com/virtio.cab" }
+            ]
+        }"#;
+        let packs = ConfigManager::parse_driver_packs(content);
+        assert_eq!(packs.len(), 2);
+        assert_eq!(packs[0].sha256.as_deref(), Some("abcd"));
+        assert!(packs[1].sha256.is_none());
+    }
+
+    #[test]
+    fn test_parse_hwid_drivers() {
+        let content = r#"{
+            "drivers": [
+                { "id": "intel_i225", "name": "Intel I225/I226", "version": "2.1.3",
+                  "hardware_ids": ["PCI\\VEN_8086&DEV_15F3", "PCI\\VEN_8086&DEV_125C"],
+                  "download_url": "https://example.com/i225.cab", "sha256": "abcd" },
+                { "id": "rtl8125", "name": "Realtek 2.5G", "hardware_ids": ["PCI\\VEN_10EC&DEV_8125"],
+                  "download_url": "https://example.com/rtl8125.zip" }
+            ]
+        }"#;
+        let drivers = ConfigManager::parse_hwid_drivers(content);
+        assert_eq!(drivers.len(), 2);
+        assert_eq!(drivers[0].hardware_ids[1], "PCI\\VEN_8086&DEV_125C");
+        assert_eq!(drivers[0].sha256.as_deref(), Some("abcd"));
+        assert!(drivers[1].sha256.is_none());
+        assert!(drivers[1].version.is_empty());
+        assert!(ConfigManager::parse_hwid_drivers("not json").is_empty());
+    }
+
+    #[test]
+    fn test_parse_image_presets_invalid() {
+        assert!(ConfigManager::parse_image_presets("not json").is_empty());
+    }
+}
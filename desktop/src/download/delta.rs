@@ -0,0 +1,398 @@
+//! 镜像增量下载
+//!
+//! 服务器为镜像提供分块清单（内容定义分块 + 每块 MD5），客户端用相同的分块算法扫描本地旧版本镜像，
+//! 哈希相同的块直接从旧文件复制，其余块通过 HTTP Range 请求下载。
+//!
+//! 分块算法（服务器生成清单时须保持一致）：Gear 滚动哈希 `h = (h << 1) + GEAR[byte]`，
+//! GEAR 表由 SplitMix64（种子 `GEAR_SEED`）依次生成 256 项；块长度达到 `MIN_CHUNK` 后
+//! 在 `h & CHUNK_MASK == 0` 处切分，达到 `MAX_CHUNK` 时强制切分。
+//!
+//! 清单格式（JSON）：`{"file_size": 总字节数, "chunks": [{"offset": 偏移, "length": 长度, "md5": "十六进制"}]}`
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use super::engine::{DownloadCommand, DownloadProgress, DownloadStatus};
+use crate::core::md5::Md5Context;
+
+/// 最小块长度
+const MIN_CHUNK: usize = 512 * 1024;
+/// 最大块长度
+const MAX_CHUNK: usize = 8 * 1024 * 1024;
+/// 切分掩码（平均块长度约 2 MB）
+const CHUNK_MASK: u64 = (1 << 21) - 1;
+/// GEAR 表种子
+const GEAR_SEED: u64 = 0x4C65_7452_6563_6F76;
+/// 单次 Range 请求合并的最大字节数
+const MAX_RANGE_BYTES: u64 = 32 * 1024 * 1024;
+/// 旧版本文件名与新文件名至少相同的前缀长度
+const MIN_BASE_PREFIX: usize = 6;
+
+/// 分块清单
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChunkManifest {
+    pub file_size: u64,
+    pub chunks: Vec<ManifestChunk>,
+}
+
+/// 清单中的单个块
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestChunk {
+    pub offset: u64,
+    pub length: u64,
+    pub md5: String,
+}
+
+impl ChunkManifest {
+    /// 解析并校验清单（块必须连续覆盖整个文件）
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut manifest: ChunkManifest = serde_json::from_str(content).context("解析分块清单失败")?;
+        let mut expected_offset = 0;
+        for chunk in &mut manifest.chunks {
+            if chunk.offset != expected_offset || chunk.length == 0 || chunk.length > MAX_CHUNK as u64 {
+                bail!("分块清单无效: 偏移 {} 处的块不连续或长度异常", chunk.offset);
+            }
+            chunk.md5 = chunk.md5.to_uppercase();
+            expected_offset += chunk.length;
+        }
+        if expected_offset != manifest.file_size {
+            bail!("分块清单无效: 块总长度 {} 与文件大小 {} 不一致", expected_offset, manifest.file_size);
+        }
+        Ok(manifest)
+    }
+}
+
+/// 增量下载统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeltaStats {
+    /// 从旧版本复用的字节数
+    pub reused_bytes: u64,
+    /// 实际下载的字节数
+    pub downloaded_bytes: u64,
+}
+
+fn gear_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut state = GEAR_SEED;
+        let mut table = [0u64; 256];
+        for entry in table.iter_mut() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *entry = z ^ (z >> 31);
+        }
+        table
+    })
+}
+
+/// 按内容定义分块遍历数据流，回调参数为（块偏移，块数据）
+pub fn for_each_chunk<R: Read>(mut reader: R, mut on_chunk: impl FnMut(u64, &[u8]) -> Result<()>) -> Result<()> {
+    let gear = gear_table();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut chunk = Vec::with_capacity(MAX_CHUNK);
+    let mut offset = 0u64;
+    let mut hash = 0u64;
+
+    loop {
+        let read = reader.read(&mut buffer).context("读取文件失败")?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            chunk.push(byte);
+            hash = (hash << 1).wrapping_add(gear[byte as usize]);
+            if (chunk.len() >= MIN_CHUNK && hash & CHUNK_MASK == 0) || chunk.len() >= MAX_CHUNK {
+                on_chunk(offset, &chunk)?;
+                offset += chunk.len() as u64;
+                chunk.clear();
+                hash = 0;
+            }
+        }
+    }
+    if !chunk.is_empty() {
+        on_chunk(offset, &chunk)?;
+    }
+    Ok(())
+}
+
+/// 计算数据的 MD5（十六进制大写）
+fn md5_hex(data: &[u8]) -> String {
+    let mut context = Md5Context::new();
+    context.update(data);
+    context.finalize().iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+/// 在候选文件中挑选与新文件名最相近的旧版本（扩展名相同、公共前缀最长）
+pub fn pick_base_file(new_filename: &str, candidates: &[PathBuf]) -> Option<PathBuf> {
+    let new_lower = new_filename.to_lowercase();
+    let new_ext = Path::new(&new_lower).extension()?.to_string_lossy().to_string();
+
+    candidates
+        .iter()
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_lowercase();
+            let ext = Path::new(&name).extension()?.to_string_lossy().to_string();
+            if name == new_lower || ext != new_ext {
+                return None;
+            }
+            let prefix = new_lower.chars().zip(name.chars()).take_while(|(a, b)| a == b).count();
+            (prefix >= MIN_BASE_PREFIX).then_some((prefix, path))
+        })
+        .max_by_key(|(prefix, _)| *prefix)
+        .map(|(_, path)| path.clone())
+}
+
+/// 增量下载任务
+pub struct DeltaDownload<'a> {
+    pub url: &'a str,
+    pub manifest_url: &'a str,
+    pub base_file: &'a Path,
+    pub target_file: &'a Path,
+    pub progress_tx: &'a Sender<DownloadProgress>,
+    pub cmd_rx: &'a Receiver<DownloadCommand>,
+}
+
+impl DeltaDownload<'_> {
+    /// 执行增量下载，失败时删除临时文件
+    pub fn run(&self) -> Result<DeltaStats> {
+        let part_file = self.target_file.with_extension(format!(
+            "{}.part",
+            self.target_file.extension().map(|e| e.to_string_lossy()).unwrap_or_default()
+        ));
+        let result = self.download_to(&part_file);
+        match result {
+            Ok(stats) => {
+                let _ = std::fs::remove_file(self.target_file);
+                std::fs::rename(&part_file, self.target_file)
+                    .with_context(|| format!("重命名下载文件失败: {}", self.target_file.display()))?;
+                Ok(stats)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&part_file);
+                Err(e)
+            }
+        }
+    }
+
+    fn download_to(&self, part_file: &Path) -> Result<DeltaStats> {
+        let client = reqwest::blocking::Client::builder()
+            .connect_timeout(Duration::from_secs(15))
+            .timeout(Duration::from_secs(600))
+            .build()
+            .context("创建 HTTP 客户端失败")?;
+
+        let manifest_text = client
+            .get(self.manifest_url)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .and_then(|r| r.text())
+            .context("获取分块清单失败")?;
+        let manifest = ChunkManifest::parse(&manifest_text)?;
+
+        // 扫描旧版本，建立 MD5 -> (偏移, 长度) 索引
+        log::info!("[增量下载] 扫描旧版本: {}", self.base_file.display());
+        self.report(0, manifest.file_size, 0, DownloadStatus::Waiting)?;
+        let mut base_index: HashMap<String, (u64, u64)> = HashMap::new();
+        let base = File::open(self.base_file).with_context(|| format!("无法打开旧版本: {}", self.base_file.display()))?;
+        for_each_chunk(base, |offset, data| {
+            self.check_command(0, manifest.file_size)?;
+            base_index.entry(md5_hex(data)).or_insert((offset, data.len() as u64));
+            Ok(())
+        })?;
+
+        let mut base = File::open(self.base_file).with_context(|| format!("无法打开旧版本: {}", self.base_file.display()))?;
+        let mut output = File::create(part_file).with_context(|| format!("无法创建文件: {}", part_file.display()))?;
+        output.set_len(manifest.file_size).context("预分配文件空间失败")?;
+
+        let start = Instant::now();
+        let mut stats = DeltaStats::default();
+        let mut index = 0;
+        while index < manifest.chunks.len() {
+            self.check_command(stats.reused_bytes + stats.downloaded_bytes, manifest.file_size)?;
+            let chunk = &manifest.chunks[index];
+
+            if let Some(&(base_offset, length)) = base_index.get(&chunk.md5).filter(|(_, len)| *len == chunk.length) {
+                let mut data = vec![0u8; length as usize];
+                base.seek(SeekFrom::Start(base_offset))?;
+                base.read_exact(&mut data).context("读取旧版本失败")?;
+                if md5_hex(&data) == chunk.md5 {
+                    output.seek(SeekFrom::Start(chunk.offset))?;
+                    output.write_all(&data).context("写入文件失败")?;
+                    stats.reused_bytes += length;
+                    index += 1;
+                    self.report_stats(&stats, manifest.file_size, start)?;
+                    continue;
+                }
+            }
+
+            // 合并连续的缺失块为一次 Range 请求
+            let mut end = index + 1;
+            let mut range_len = chunk.length;
+            while end < manifest.chunks.len()
+                && !base_index.contains_key(&manifest.chunks[end].md5)
+                && range_len + manifest.chunks[end].length <= MAX_RANGE_BYTES
+            {
+                range_len += manifest.chunks[end].length;
+                end += 1;
+            }
+
+            let response = client
+                .get(self.url)
+                .header("Range", format!("bytes={}-{}", chunk.offset, chunk.offset + range_len - 1))
+                .send()
+                .context("下载分块失败")?;
+            if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                bail!("服务器不支持分段下载（HTTP {}）", response.status());
+            }
+            let body = response.bytes().context("下载分块失败")?;
+            if body.len() as u64 != range_len {
+                bail!("分块长度不符: 预期 {} 字节，实际 {} 字节", range_len, body.len());
+            }
+
+            let mut position = 0usize;
+            for chunk in &manifest.chunks[index..end] {
+                let data = &body[position..position + chunk.length as usize];
+                if md5_hex(data) != chunk.md5 {
+                    bail!("偏移 {} 处的分块校验失败", chunk.offset);
+                }
+                position += chunk.length as usize;
+            }
+            output.seek(SeekFrom::Start(chunk.offset))?;
+            output.write_all(&body).context("写入文件失败")?;
+            stats.downloaded_bytes += range_len;
+            index = end;
+            self.report_stats(&stats, manifest.file_size, start)?;
+        }
+
+        output.flush().context("写入文件失败")?;
+        log::info!(
+            "[增量下载] 完成: 复用 {} MB，下载 {} MB",
+            stats.reused_bytes / 1024 / 1024,
+            stats.downloaded_bytes / 1024 / 1024
+        );
+        Ok(stats)
+    }
+
+    /// 处理暂停/继续/取消命令
+    fn check_command(&self, completed: u64, total: u64) -> Result<()> {
+        while let Ok(cmd) = self.cmd_rx.try_recv() {
+            match cmd {
+                DownloadCommand::Pause => {
+                    let _ = self.progress_tx.send(Self::progress(completed, total, 0, DownloadStatus::Paused));
+                    loop {
+                        match self.cmd_rx.recv() {
+                            Ok(DownloadCommand::Resume) => break,
+                            Ok(DownloadCommand::Pause) => continue,
+                            Ok(DownloadCommand::Cancel) | Err(_) => bail!("下载已取消"),
+                        }
+                    }
+                }
+                DownloadCommand::Resume => {}
+                DownloadCommand::Cancel => bail!("下载已取消"),
+            }
+        }
+        Ok(())
+    }
+
+    fn report_stats(&self, stats: &DeltaStats, total: u64, start: Instant) -> Result<()> {
+        let elapsed = start.elapsed().as_secs_f64().max(0.001);
+        let speed = (stats.downloaded_bytes as f64 / elapsed) as u64;
+        self.report(stats.reused_bytes + stats.downloaded_bytes, total, speed, DownloadStatus::Active)
+    }
+
+    fn report(&self, completed: u64, total: u64, speed: u64, status: DownloadStatus) -> Result<()> {
+        self.progress_tx
+            .send(Self::progress(completed, total, speed, status))
+            .map_err(|_| anyhow!("下载已取消"))
+    }
+
+    fn progress(completed: u64, total: u64, speed: u64, status: DownloadStatus) -> DownloadProgress {
+        DownloadProgress {
+            gid: String::new(),
+            completed_length: completed,
+            total_length: total,
+            download_speed: speed,
+            percentage: if total > 0 { completed as f64 * 100.0 / total as f64 } else { 0.0 },
+            status,
+            peers: None,
+            file_path: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_survive_insertion() {
+        // 伪随机数据，在开头插入少量字节后大部分块应保持不变
+        let mut state = 1u64;
+        let data: Vec<u8> = (0..24 * 1024 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let mut shifted = vec![0xAAu8; 1000];
+        shifted.extend_from_slice(&data);
+
+        let mut original = Vec::new();
+        for_each_chunk(&data[..], |_, chunk| {
+            original.push(md5_hex(chunk));
+            Ok(())
+        })
+        .unwrap();
+        let mut offsets = Vec::new();
+        let mut reused = 0;
+        for_each_chunk(&shifted[..], |offset, chunk| {
+            offsets.push((offset, chunk.len()));
+            if original.contains(&md5_hex(chunk)) {
+                reused += 1;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(original.len() > 3);
+        assert!(reused + 1 >= offsets.len());
+        assert!(offsets.windows(2).all(|w| w[0].0 + w[0].1 as u64 == w[1].0));
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let ok = r#"{"file_size":10,"chunks":[{"offset":0,"length":4,"md5":"ab"},{"offset":4,"length":6,"md5":"CD"}]}"#;
+        let manifest = ChunkManifest::parse(ok).unwrap();
+        assert_eq!(manifest.chunks[0].md5, "AB");
+
+        let gap = r#"{"file_size":10,"chunks":[{"offset":0,"length":4,"md5":"ab"},{"offset":5,"length":5,"md5":"cd"}]}"#;
+        assert!(ChunkManifest::parse(gap).is_err());
+    }
+
+    #[test]
+    fn test_pick_base_file() {
+        let candidates = vec![
+            PathBuf::from("Win11_24H2_2025_08.iso"),
+            PathBuf::from("Win10_22H2_2025_08.iso"),
+            PathBuf::from("Win11_24H2_2025_08.wim"),
+            PathBuf::from("Win11_24H2_2025_09.iso"),
+        ];
+        assert_eq!(
+            pick_base_file("Win11_24H2_2025_09.iso", &candidates),
+            Some(PathBuf::from("Win11_24H2_2025_08.iso"))
+        );
+        assert_eq!(pick_base_file("LTSC_2021.iso", &candidates), None);
+    }
+}
@@ -0,0 +1,610 @@
+//! 原生分段下载引擎
+//!
+//! - 多连接分段下载：按连接数把文件切成若干段，每段一个线程通过 HTTP Range 请求下载
+//! - 断点续传：数据写入 `<文件>.part`，各段进度保存在 `<文件>.part.state`，
+//!   再次下载同一文件时（长度和 ETag/Last-Modified 一致）从上次位置继续；取消下载时保留这两个文件
+//! - 服务器不支持 Range 或未返回文件长度时退化为单连接下载（无法续传）
+//! - 完成后校验文件长度和声明的哈希（PE 列表中的 MD5 或服务器哈希配置），通过后才重命名为目标文件；
+//!   哈希不匹配时删除已下载的数据，避免续传时沿用损坏的分段
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::core::hash::{self, ExpectedHashes};
+
+/// 默认连接数
+pub const DEFAULT_CONNECTIONS: usize = 8;
+/// 每段最小长度（文件较小时减少连接数）
+const MIN_SEGMENT: u64 = 4 * 1024 * 1024;
+/// 单段连续失败的重试次数
+const MAX_RETRIES: u32 = 5;
+/// 进度上报间隔
+const REPORT_INTERVAL: Duration = Duration::from_millis(300);
+/// 续传状态保存间隔
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+/// 读写缓冲区大小
+const BUFFER_SIZE: usize = 256 * 1024;
+
+/// 下载控制命令
+#[derive(Debug, Clone)]
+pub enum DownloadCommand {
+    Pause,
+    Resume,
+    Cancel,
+}
+
+/// 下载进度信息
+#[derive(Debug, Clone)]
+pub struct DownloadProgress {
+    pub gid: String,
+    pub completed_length: u64,
+    pub total_length: u64,
+    pub download_speed: u64,
+    pub percentage: f64,
+    pub status: DownloadStatus,
+    /// 已连接的节点数（仅 BT 下载）
+    pub peers: Option<usize>,
+    /// 下载完成后保存的文件
+    pub file_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadStatus {
+    Waiting,
+    Active,
+    Paused,
+    /// 正在校验已有数据
+    Verifying,
+    Complete,
+    Error(String),
+}
+
+fn progress(completed: u64, total: u64, speed: u64, status: DownloadStatus) -> DownloadProgress {
+    DownloadProgress {
+        gid: String::new(),
+        completed_length: completed,
+        total_length: total,
+        download_speed: speed,
+        percentage: if total > 0 { completed as f64 * 100.0 / total as f64 } else { 0.0 },
+        status,
+        peers: None,
+        file_path: None,
+    }
+}
+
+/// 下载分段（`end` 不含）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Segment {
+    start: u64,
+    end: u64,
+    downloaded: u64,
+}
+
+/// 续传状态文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResumeState {
+    total_length: u64,
+    /// ETag 或 Last-Modified，用于判断服务器文件是否已变化
+    validator: Option<String>,
+    segments: Vec<Segment>,
+}
+
+/// 探测到的服务器文件信息
+struct RemoteFile {
+    /// 重定向后的最终地址
+    url: String,
+    total_length: Option<u64>,
+    accept_ranges: bool,
+    validator: Option<String>,
+    file_name: Option<String>,
+}
+
+/// 按连接数切分文件
+fn plan_segments(total: u64, connections: usize) -> Vec<Segment> {
+    let count = (total / MIN_SEGMENT).clamp(1, connections.max(1) as u64);
+    let size = total.div_ceil(count);
+    (0..count)
+        .map(|i| Segment {
+            start: i * size,
+            end: ((i + 1) * size).min(total),
+            downloaded: 0,
+        })
+        .filter(|s| s.start < s.end)
+        .collect()
+}
+
+/// 从 `Content-Range: bytes 0-0/12345` 中解析文件总长度
+fn parse_content_range_total(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// 从 Content-Disposition 中解析文件名（支持 filename*=UTF-8''）
+fn parse_content_disposition(value: &str) -> Option<String> {
+    let mut plain = None;
+    for part in value.split(';').map(str::trim) {
+        if let Some(encoded) = part.strip_prefix("filename*=") {
+            let encoded = encoded.trim_matches('"');
+            let encoded = encoded.split_once("''").map(|(_, v)| v).unwrap_or(encoded);
+            return Some(percent_decode(encoded)).filter(|n| !n.is_empty());
+        }
+        if let Some(name) = part.strip_prefix("filename=") {
+            plain = Some(name.trim_matches('"').to_string());
+        }
+    }
+    plain.filter(|n| !n.is_empty())
+}
+
+/// 从 URL 路径中取文件名（忽略查询参数）
+fn file_name_from_url(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let name = path.rsplit('/').next()?;
+    Some(percent_decode(name)).filter(|n| !n.is_empty() && !n.contains(':'))
+}
+
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// 去掉 Windows 文件名中的非法字符
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "\\/:*?\"<>|".contains(c) || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// 解析 "Name: value" 形式的请求头
+fn build_headers(headers: &[String]) -> reqwest::header::HeaderMap {
+    let mut map = reqwest::header::HeaderMap::new();
+    for header in headers {
+        let Some((name, value)) = header.split_once(':') else {
+            continue;
+        };
+        match (
+            reqwest::header::HeaderName::from_bytes(name.trim().as_bytes()),
+            reqwest::header::HeaderValue::from_str(value.trim()),
+        ) {
+            (Ok(name), Ok(value)) => {
+                map.insert(name, value);
+            }
+            _ => log::warn!("[下载] 忽略无效的请求头: {}", name),
+        }
+    }
+    map
+}
+
+/// 工作线程共享的控制标志
+#[derive(Default)]
+struct Control {
+    paused: AtomicBool,
+    /// 用户取消或其他分段失败时停止全部分段
+    stop: AtomicBool,
+}
+
+/// HTTP 下载任务
+pub struct HttpDownload<'a> {
+    pub url: &'a str,
+    /// 额外请求头（"Name: value"）
+    pub headers: &'a [String],
+    pub save_dir: &'a Path,
+    /// 保存的文件名，为空时取 Content-Disposition 或 URL 中的文件名
+    pub filename: Option<&'a str>,
+    pub connections: usize,
+    /// 预期哈希，为空时只校验文件长度
+    pub expected: Option<&'a ExpectedHashes>,
+    pub progress_tx: &'a Sender<DownloadProgress>,
+    pub cmd_rx: &'a Receiver<DownloadCommand>,
+}
+
+impl HttpDownload<'_> {
+    /// 执行下载，返回保存的文件路径
+    pub fn run(&self) -> Result<PathBuf> {
+        let client = reqwest::blocking::Client::builder()
+            .connect_timeout(Duration::from_secs(15))
+            .timeout(None)
+            .default_headers(build_headers(self.headers))
+            .build()
+            .context("创建 HTTP 客户端失败")?;
+
+        let _ = self.progress_tx.send(progress(0, 0, 0, DownloadStatus::Waiting));
+        let remote = self.probe(&client)?;
+
+        let file_name = self
+            .filename
+            .map(str::to_string)
+            .or_else(|| remote.file_name.clone())
+            .or_else(|| file_name_from_url(&remote.url))
+            .map(|n| sanitize_file_name(&n))
+            .unwrap_or_else(|| "download.bin".to_string());
+        std::fs::create_dir_all(self.save_dir)
+            .with_context(|| format!("无法创建下载目录: {}", self.save_dir.display()))?;
+        let target = self.save_dir.join(&file_name);
+        let part_file = self.save_dir.join(format!("{}.part", file_name));
+        let state_file = self.save_dir.join(format!("{}.part.state", file_name));
+
+        log::info!(
+            "[下载] {} -> {}，长度: {:?}，分段: {}",
+            remote.url,
+            target.display(),
+            remote.total_length,
+            remote.accept_ranges
+        );
+
+        let segments = match remote.total_length {
+            Some(total) if remote.accept_ranges => {
+                self.prepare_segmented(total, remote.validator.clone(), &part_file, &state_file)?
+            }
+            _ => {
+                // 单连接下载，无法续传
+                File::create(&part_file).with_context(|| format!("无法创建文件: {}", part_file.display()))?;
+                let _ = std::fs::remove_file(&state_file);
+                vec![Segment {
+                    start: 0,
+                    end: remote.total_length.unwrap_or(u64::MAX),
+                    downloaded: 0,
+                }]
+            }
+        };
+
+        let state = ResumeState {
+            total_length: remote.total_length.unwrap_or(0),
+            validator: remote.validator.clone(),
+            segments,
+        };
+        let resumable = remote.total_length.is_some() && remote.accept_ranges;
+        let completed = self.download_segments(&client, &remote.url, &part_file, &state_file, state, resumable)?;
+
+        if let Some(total) = remote.total_length {
+            let actual = std::fs::metadata(&part_file).map(|m| m.len()).unwrap_or(0);
+            if completed != total || actual != total {
+                bail!("文件长度校验失败: 预期 {} 字节，实际 {} 字节", total, completed.min(actual));
+            }
+        }
+        if let Some(expected) = self.expected.filter(|e| !e.is_empty()) {
+            self.verify_hashes(&part_file, &state_file, expected)?;
+        }
+
+        let _ = std::fs::remove_file(&state_file);
+        let _ = std::fs::remove_file(&target);
+        std::fs::rename(&part_file, &target)
+            .with_context(|| format!("重命名下载文件失败: {}", target.display()))?;
+        log::info!("[下载] 完成: {}", target.display());
+        Ok(target)
+    }
+
+    /// 校验下载数据的哈希，不匹配时删除数据和续传状态
+    fn verify_hashes(&self, part_file: &Path, state_file: &Path, expected: &ExpectedHashes) -> Result<()> {
+        let _ = self.progress_tx.send(progress(0, 0, 0, DownloadStatus::Verifying));
+        let mismatches = hash::verify_file(part_file, expected, |done, total| {
+            let _ = self.progress_tx.send(progress(done, total, 0, DownloadStatus::Verifying));
+        })?;
+        if mismatches.is_empty() {
+            log::info!("[下载] 哈希校验通过: {}", part_file.display());
+            return Ok(());
+        }
+
+        let _ = std::fs::remove_file(part_file);
+        let _ = std::fs::remove_file(state_file);
+        let details: Vec<String> = mismatches
+            .iter()
+            .map(|m| format!("{} 预期 {}，实际 {}", m.algorithm.name(), m.expected, m.actual))
+            .collect();
+        bail!("文件哈希校验失败，已删除下载的数据: {}", details.join("; "))
+    }
+
+    /// 用 Range: bytes=0-0 探测文件长度和是否支持分段
+    fn probe(&self, client: &reqwest::blocking::Client) -> Result<RemoteFile> {
+        let response = client
+            .get(self.url)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .timeout(Duration::from_secs(30))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .context("连接下载服务器失败")?;
+
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let accept_ranges = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let total_length = if accept_ranges {
+            header(reqwest::header::CONTENT_RANGE).and_then(|v| parse_content_range_total(&v))
+        } else {
+            response.content_length()
+        };
+
+        Ok(RemoteFile {
+            url: response.url().to_string(),
+            total_length,
+            accept_ranges,
+            validator: header(reqwest::header::ETAG).or_else(|| header(reqwest::header::LAST_MODIFIED)),
+            file_name: header(reqwest::header::CONTENT_DISPOSITION).and_then(|v| parse_content_disposition(&v)),
+        })
+    }
+
+    /// 读取续传状态，不可续传时重新分段并预分配文件
+    fn prepare_segmented(
+        &self,
+        total: u64,
+        validator: Option<String>,
+        part_file: &Path,
+        state_file: &Path,
+    ) -> Result<Vec<Segment>> {
+        let part_len = std::fs::metadata(part_file).map(|m| m.len()).ok();
+        let saved = std::fs::read_to_string(state_file)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ResumeState>(&content).ok())
+            .filter(|s| s.total_length == total && s.validator == validator && part_len == Some(total));
+        if let Some(saved) = saved {
+            let done: u64 = saved.segments.iter().map(|s| s.downloaded).sum();
+            log::info!("[下载] 续传，已完成 {} / {} 字节", done, total);
+            return Ok(saved.segments);
+        }
+
+        let file = File::create(part_file).with_context(|| format!("无法创建文件: {}", part_file.display()))?;
+        file.set_len(total).context("预分配文件空间失败")?;
+        Ok(plan_segments(total, self.connections))
+    }
+
+    /// 多线程下载全部未完成的分段，返回已下载的总字节数
+    fn download_segments(
+        &self,
+        client: &reqwest::blocking::Client,
+        url: &str,
+        part_file: &Path,
+        state_file: &Path,
+        mut state: ResumeState,
+        resumable: bool,
+    ) -> Result<u64> {
+        let control = Control::default();
+        let counters: Vec<AtomicU64> = state.segments.iter().map(|s| AtomicU64::new(s.downloaded)).collect();
+        let first_error: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+        let total = state.total_length;
+        let mut cancelled = false;
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = state
+                .segments
+                .iter()
+                .zip(&counters)
+                .filter(|(segment, counter)| segment.start + counter.load(Ordering::Relaxed) < segment.end)
+                .map(|(segment, counter)| {
+                    let (control, first_error) = (&control, &first_error);
+                    let segment = segment.clone();
+                    scope.spawn(move || {
+                        if let Err(e) = download_segment(client, url, part_file, &segment, counter, control, resumable) {
+                            if !control.stop.swap(true, Ordering::SeqCst) {
+                                *first_error.lock().unwrap() = Some(e);
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            let mut last_report = Instant::now();
+            let mut last_save = Instant::now();
+            let mut last_completed: u64 = counters.iter().map(|c| c.load(Ordering::Relaxed)).sum();
+            let mut speed = 0f64;
+            loop {
+                std::thread::sleep(REPORT_INTERVAL);
+                let finished = handles.iter().all(|h| h.is_finished());
+
+                while let Ok(cmd) = self.cmd_rx.try_recv() {
+                    match cmd {
+                        DownloadCommand::Pause => control.paused.store(true, Ordering::SeqCst),
+                        DownloadCommand::Resume => control.paused.store(false, Ordering::SeqCst),
+                        DownloadCommand::Cancel => {
+                            cancelled = true;
+                            control.stop.store(true, Ordering::SeqCst);
+                        }
+                    }
+                }
+
+                let completed: u64 = counters.iter().map(|c| c.load(Ordering::Relaxed)).sum();
+                let elapsed = last_report.elapsed().as_secs_f64().max(0.001);
+                let instant_speed = completed.saturating_sub(last_completed) as f64 / elapsed;
+                speed = if speed == 0.0 { instant_speed } else { speed * 0.7 + instant_speed * 0.3 };
+                last_report = Instant::now();
+                last_completed = completed;
+
+                let paused = control.paused.load(Ordering::SeqCst);
+                let status = if paused { DownloadStatus::Paused } else { DownloadStatus::Active };
+                let reported_speed = if paused { 0 } else { speed as u64 };
+                if self.progress_tx.send(progress(completed, total, reported_speed, status)).is_err() {
+                    // 下载页面已关闭
+                    cancelled = true;
+                    control.stop.store(true, Ordering::SeqCst);
+                }
+
+                if resumable && (finished || last_save.elapsed() >= STATE_SAVE_INTERVAL) {
+                    for (segment, counter) in state.segments.iter_mut().zip(&counters) {
+                        segment.downloaded = counter.load(Ordering::Relaxed);
+                    }
+                    if let Err(e) = save_state(state_file, &state) {
+                        log::warn!("[下载] 保存续传状态失败: {:#}", e);
+                    }
+                    last_save = Instant::now();
+                }
+
+                if finished {
+                    break;
+                }
+            }
+        });
+
+        if cancelled {
+            bail!("下载已取消");
+        }
+        if let Some(e) = first_error.into_inner().unwrap() {
+            return Err(e);
+        }
+        Ok(counters.iter().map(|c| c.load(Ordering::Relaxed)).sum())
+    }
+}
+
+/// 下载单个分段，连续失败超过重试次数时返回错误
+#[allow(clippy::too_many_arguments)]
+fn download_segment(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    part_file: &Path,
+    segment: &Segment,
+    counter: &AtomicU64,
+    control: &Control,
+    resumable: bool,
+) -> Result<()> {
+    let mut failures = 0;
+    loop {
+        let before = counter.load(Ordering::Relaxed);
+        if segment.start + before >= segment.end || control.stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        match fetch_range(client, url, part_file, segment, counter, control, resumable) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                if control.stop.load(Ordering::SeqCst) {
+                    return Ok(());
+                }
+                if !resumable {
+                    return Err(e);
+                }
+                // 有进展时重新计数
+                if counter.load(Ordering::Relaxed) > before {
+                    failures = 0;
+                }
+                failures += 1;
+                if failures > MAX_RETRIES {
+                    return Err(e.context(format!("分段 {}-{} 下载失败", segment.start, segment.end)));
+                }
+                log::warn!("[下载] 分段 {} 第 {} 次重试: {:#}", segment.start, failures, e);
+                std::thread::sleep(Duration::from_secs(failures as u64));
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fetch_range(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    part_file: &Path,
+    segment: &Segment,
+    counter: &AtomicU64,
+    control: &Control,
+    resumable: bool,
+) -> Result<()> {
+    let position = segment.start + counter.load(Ordering::Relaxed);
+    let mut request = client.get(url);
+    if resumable {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-{}", position, segment.end - 1));
+    }
+    let mut response = request.send().and_then(|r| r.error_for_status()).context("请求下载数据失败")?;
+    if resumable && response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        bail!("服务器不支持分段下载（HTTP {}）", response.status());
+    }
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(part_file)
+        .with_context(|| format!("无法打开文件: {}", part_file.display()))?;
+    file.seek(SeekFrom::Start(position))?;
+
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut position = position;
+    while position < segment.end {
+        while control.paused.load(Ordering::SeqCst) && !control.stop.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(200));
+        }
+        if control.stop.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let read = response.read(&mut buffer).context("读取下载数据失败")?;
+        if read == 0 {
+            if segment.end == u64::MAX {
+                // 长度未知，读到结尾即完成
+                break;
+            }
+            return Err(anyhow!("连接提前结束"));
+        }
+        let len = (read as u64).min(segment.end - position) as usize;
+        file.write_all(&buffer[..len]).context("写入文件失败")?;
+        position += len as u64;
+        counter.fetch_add(len as u64, Ordering::Relaxed);
+    }
+    file.flush().context("写入文件失败")?;
+    Ok(())
+}
+
+fn save_state(state_file: &Path, state: &ResumeState) -> Result<()> {
+    let content = serde_json::to_string(state)?;
+    std::fs::write(state_file, content).with_context(|| format!("无法写入: {}", state_file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_segments() {
+        let total = 100 * 1024 * 1024 + 7;
+        let segments = plan_segments(total, 8);
+        assert_eq!(segments.len(), 8);
+        assert_eq!(segments[0].start, 0);
+        assert_eq!(segments.last().unwrap().end, total);
+        assert!(segments.windows(2).all(|w| w[0].end == w[1].start));
+
+        // 小文件只用一个连接
+        assert_eq!(plan_segments(1024, 8), vec![Segment { start: 0, end: 1024, downloaded: 0 }]);
+        assert_eq!(plan_segments(10 * 1024 * 1024, 8).len(), 2);
+    }
+
+    #[test]
+    fn test_parse_headers() {
+        assert_eq!(parse_content_range_total("bytes 0-0/12345"), Some(12345));
+        assert_eq!(parse_content_range_total("bytes 0-0/*"), None);
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"Win11.iso\""),
+            Some("Win11.iso".to_string())
+        );
+        assert_eq!(
+            parse_content_disposition("attachment; filename=\"a.iso\"; filename*=UTF-8''%E7%B3%BB%E7%BB%9F.iso"),
+            Some("系统.iso".to_string())
+        );
+        assert_eq!(parse_content_disposition("inline"), None);
+    }
+
+    #[test]
+    fn test_file_name_from_url() {
+        assert_eq!(
+            file_name_from_url("https://example.com/pe/Cloud%20PE.iso?sign=abc#x"),
+            Some("Cloud PE.iso".to_string())
+        );
+        assert_eq!(file_name_from_url("https://example.com/"), None);
+        assert_eq!(sanitize_file_name("a:b?.iso"), "a_b_.iso");
+    }
+}
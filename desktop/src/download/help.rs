@@ -0,0 +1,170 @@
+//! 内置帮助文档
+//!
+//! 帮助内容为编译时内嵌的 Markdown（`help/{语言}/{主题}.md`，中英文各一份），
+//! 服务器可通过帮助配置覆盖或补充某个主题的内容：
+//! `{"topics": [{"key": "disable_defender", "lang": "zh-CN", "content": "..."}]}`，
+//! `lang` 为空时对所有语言生效。
+
+use serde::{Deserialize, Serialize};
+
+/// 帮助主题
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HelpTopic {
+    FormatPartition,
+    BootRepair,
+    Unattended,
+    DriverImport,
+    DisableDefender,
+    IncrementalBackup,
+    VhdInstall,
+    TechnicianLock,
+}
+
+impl HelpTopic {
+    pub const ALL: &'static [HelpTopic] = &[
+        HelpTopic::FormatPartition,
+        HelpTopic::BootRepair,
+        HelpTopic::Unattended,
+        HelpTopic::DriverImport,
+        HelpTopic::DisableDefender,
+        HelpTopic::IncrementalBackup,
+        HelpTopic::VhdInstall,
+        HelpTopic::TechnicianLock,
+    ];
+
+    /// 服务器配置中使用的主题标识
+    pub fn key(&self) -> &'static str {
+        match self {
+            HelpTopic::FormatPartition => "format_partition",
+            HelpTopic::BootRepair => "boot_repair",
+            HelpTopic::Unattended => "unattended",
+            HelpTopic::DriverImport => "driver_import",
+            HelpTopic::DisableDefender => "disable_defender",
+            HelpTopic::IncrementalBackup => "incremental_backup",
+            HelpTopic::VhdInstall => "vhd_install",
+            HelpTopic::TechnicianLock => "technician_lock",
+        }
+    }
+
+    /// 主题名称（中文原文，界面上经 tr! 翻译）
+    pub fn title(&self) -> &'static str {
+        match self {
+            HelpTopic::FormatPartition => "格式化分区",
+            HelpTopic::BootRepair => "添加引导",
+            HelpTopic::Unattended => "无人值守",
+            HelpTopic::DriverImport => "驱动导出与导入",
+            HelpTopic::DisableDefender => "禁用 Windows 安全中心",
+            HelpTopic::IncrementalBackup => "增量备份",
+            HelpTopic::VhdInstall => "安装到 VHDX 虚拟磁盘",
+            HelpTopic::TechnicianLock => "技师锁定",
+        }
+    }
+
+    /// 内置的帮助内容（中文界面用中文，其他语言用英文）
+    pub fn builtin(&self, lang: &str) -> &'static str {
+        if is_chinese(lang) {
+            match self {
+                HelpTopic::FormatPartition => include_str!("../../help/zh-CN/format_partition.md"),
+                HelpTopic::BootRepair => include_str!("../../help/zh-CN/boot_repair.md"),
+                HelpTopic::Unattended => include_str!("../../help/zh-CN/unattended.md"),
+                HelpTopic::DriverImport => include_str!("../../help/zh-CN/driver_import.md"),
+                HelpTopic::DisableDefender => include_str!("../../help/zh-CN/disable_defender.md"),
+                HelpTopic::IncrementalBackup => include_str!("../../help/zh-CN/incremental_backup.md"),
+                HelpTopic::VhdInstall => include_str!("../../help/zh-CN/vhd_install.md"),
+                HelpTopic::TechnicianLock => include_str!("../../help/zh-CN/technician_lock.md"),
+            }
+        } else {
+            match self {
+                HelpTopic::FormatPartition => include_str!("../../help/en-US/format_partition.md"),
+                HelpTopic::BootRepair => include_str!("../../help/en-US/boot_repair.md"),
+                HelpTopic::Unattended => include_str!("../../help/en-US/unattended.md"),
+                HelpTopic::DriverImport => include_str!("../../help/en-US/driver_import.md"),
+                HelpTopic::DisableDefender => include_str!("../../help/en-US/disable_defender.md"),
+                HelpTopic::IncrementalBackup => include_str!("../../help/en-US/incremental_backup.md"),
+                HelpTopic::VhdInstall => include_str!("../../help/en-US/vhd_install.md"),
+                HelpTopic::TechnicianLock => include_str!("../../help/en-US/technician_lock.md"),
+            }
+        }
+    }
+
+    /// 帮助内容：优先使用与当前语言匹配的服务器内容，其次不限语言的服务器内容，最后为内置内容
+    pub fn content<'a>(&self, lang: &str, overrides: &'a [HelpOverride]) -> &'a str {
+        let candidates = || overrides.iter().filter(|o| o.key == self.key());
+        candidates()
+            .find(|o| o.lang.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(lang)))
+            .or_else(|| candidates().find(|o| o.lang.is_none()))
+            .map(|o| o.content.as_str())
+            .unwrap_or_else(|| self.builtin(lang))
+    }
+}
+
+fn is_chinese(lang: &str) -> bool {
+    lang.to_lowercase().starts_with("zh")
+}
+
+/// 服务器下发的帮助内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HelpOverride {
+    /// 主题标识（见 `HelpTopic::key`）
+    pub key: String,
+    /// 语言代码（如 "zh-CN"），为空表示所有语言
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// Markdown 内容
+    pub content: String,
+}
+
+/// 帮助配置 JSON 格式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HelpOverrideList {
+    topics: Vec<HelpOverride>,
+}
+
+/// 解析服务器帮助配置
+pub fn parse_overrides(content: &str) -> Vec<HelpOverride> {
+    match serde_json::from_str::<HelpOverrideList>(content) {
+        Ok(list) => {
+            log::info!("帮助内容加载成功，共 {} 条", list.topics.len());
+            list.topics
+        }
+        Err(e) => {
+            log::warn!("解析帮助配置失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_content() {
+        for topic in HelpTopic::ALL {
+            assert!(topic.builtin("zh-CN").starts_with("# "), "{}", topic.key());
+            assert!(topic.builtin("en-US").starts_with("# "), "{}", topic.key());
+            assert_ne!(topic.builtin("zh-CN"), topic.builtin("en-US"));
+        }
+        assert_eq!(HelpTopic::BootRepair.builtin("zh-TW"), HelpTopic::BootRepair.builtin("zh-CN"));
+        assert_eq!(HelpTopic::BootRepair.builtin("ja-JP"), HelpTopic::BootRepair.builtin("en-US"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides = parse_overrides(
+            r#"{"topics": [
+                {"key": "disable_defender", "content": "all"},
+                {"key": "disable_defender", "lang": "en-US", "content": "english"},
+                {"key": "unknown", "content": "ignored"}
+            ]}"#,
+        );
+        assert_eq!(overrides.len(), 3);
+        assert_eq!(HelpTopic::DisableDefender.content("en-us", &overrides), "english");
+        assert_eq!(HelpTopic::DisableDefender.content("zh-CN", &overrides), "all");
+        assert_eq!(
+            HelpTopic::BootRepair.content("zh-CN", &overrides),
+            HelpTopic::BootRepair.builtin("zh-CN")
+        );
+        assert!(parse_overrides("not json").is_empty());
+    }
+}
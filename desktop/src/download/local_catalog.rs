@@ -0,0 +1,171 @@
+//! 本地镜像目录
+//!
+//! 用户导入的本地镜像与服务器镜像显示在同一列表中，通过相同的校验和安装流程处理。
+//! 目录保存在程序目录下的 local_images.json，WIM/ESD/SWM 的版本信息由 wimlib 读取。
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::config::OnlineSystem;
+use crate::core::image_verify::ImageType;
+use crate::core::wimlib::Wimlib;
+use crate::utils::path::get_exe_dir;
+
+/// 本地镜像条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalImage {
+    /// 镜像文件路径
+    pub path: String,
+    /// 显示名称
+    pub display_name: String,
+    pub is_win11: bool,
+    /// 镜像内包含的版本名称（ISO/GHO 为空）
+    #[serde(default)]
+    pub editions: Vec<String>,
+    /// 文件大小（字节）
+    #[serde(default)]
+    pub size_bytes: u64,
+}
+
+impl LocalImage {
+    /// 镜像文件是否仍然存在
+    pub fn exists(&self) -> bool {
+        Path::new(&self.path).is_file()
+    }
+
+    /// 转换为在线镜像条目（用于与服务器镜像统一排序）
+    pub fn to_online_system(&self) -> OnlineSystem {
+        OnlineSystem {
+            download_url: self.path.clone(),
+            display_name: self.display_name.clone(),
+            is_win11: self.is_win11,
+            chunk_manifest_url: None,
+        }
+    }
+}
+
+/// 本地镜像目录文件结构
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LocalCatalog {
+    pub images: Vec<LocalImage>,
+    pub version: u32,
+}
+
+impl LocalCatalog {
+    const CATALOG_VERSION: u32 = 1;
+
+    /// 目录文件路径
+    fn get_catalog_path() -> PathBuf {
+        get_exe_dir().join("local_images.json")
+    }
+
+    /// 加载本地镜像目录
+    pub fn load() -> Vec<LocalImage> {
+        let Ok(content) = std::fs::read_to_string(Self::get_catalog_path()) else {
+            return Vec::new();
+        };
+        match serde_json::from_str::<LocalCatalog>(&content) {
+            Ok(catalog) if catalog.version == Self::CATALOG_VERSION => catalog.images,
+            Ok(_) => {
+                log::warn!("本地镜像目录版本不匹配，忽略");
+                Vec::new()
+            }
+            Err(e) => {
+                log::warn!("解析本地镜像目录失败: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 保存本地镜像目录
+    pub fn save(images: &[LocalImage]) -> Result<()> {
+        let catalog = LocalCatalog {
+            images: images.to_vec(),
+            version: Self::CATALOG_VERSION,
+        };
+        let path = Self::get_catalog_path();
+        let content = serde_json::to_string_pretty(&catalog).context("序列化本地镜像目录失败")?;
+        std::fs::write(&path, content).with_context(|| format!("写入本地镜像目录失败: {}", path.display()))?;
+        log::info!("本地镜像目录已保存: {:?}, 共 {} 项", path, images.len());
+        Ok(())
+    }
+}
+
+/// 导入本地镜像并读取元数据
+pub fn import_image(path: &str) -> Result<LocalImage> {
+    let size_bytes = std::fs::metadata(path)
+        .with_context(|| format!("无法读取镜像文件: {}", path))?
+        .len();
+    let image_type = ImageType::detect(path);
+    if image_type == ImageType::Unknown {
+        bail!("无法识别的镜像格式，支持 WIM、ESD、SWM、GHO、ISO");
+    }
+
+    let file_stem = Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    let editions = if image_type.is_wim_family() {
+        read_wim_editions(path)?
+    } else {
+        Vec::new()
+    };
+
+    let display_name = match editions.as_slice() {
+        [] => file_stem.clone(),
+        [only] => only.clone(),
+        [first, rest @ ..] => format!("{} 等 {} 个版本", first, rest.len() + 1),
+    };
+    let is_win11 = if editions.is_empty() {
+        is_win11_name(&file_stem)
+    } else {
+        editions.iter().any(|e| is_win11_name(e))
+    };
+
+    Ok(LocalImage {
+        path: path.to_string(),
+        display_name,
+        is_win11,
+        editions,
+        size_bytes,
+    })
+}
+
+/// 通过 wimlib 读取镜像内的版本名称
+fn read_wim_editions(path: &str) -> Result<Vec<String>> {
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("无法加载 wimlib: {}", e))?;
+    let wim = wimlib.open_wim(path).map_err(|e| anyhow!("无法打开镜像: {}", e))?;
+    let count = wim.get_image_count();
+    if count <= 0 {
+        bail!("镜像文件中没有有效的系统镜像");
+    }
+    Ok((1..=count)
+        .map(|index| {
+            wim.get_image_name(index)
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("镜像 {}", index))
+        })
+        .collect())
+}
+
+/// 根据名称判断是否为 Win11 镜像
+fn is_win11_name(name: &str) -> bool {
+    let name = name.to_lowercase().replace([' ', '_', '-'], "");
+    name.contains("windows11") || name.contains("win11")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_win11_name() {
+        assert!(is_win11_name("Windows 11 Pro"));
+        assert!(is_win11_name("Win11_24H2_x64"));
+        assert!(is_win11_name("zh-cn_windows_11_consumer_editions"));
+        assert!(!is_win11_name("Windows 10 Enterprise LTSC"));
+        assert!(!is_win11_name("Win10_22H2"));
+    }
+}
@@ -0,0 +1,270 @@
+//! 远程配置清单（v2）
+//!
+//! 服务器在 `manifest.json` 中列出镜像站地址、各配置文件的路径和 SHA256、要求的最低客户端版本和过期时间；
+//! `manifest.json.sig` 是发布私钥对清单原始字节的 Ed25519 签名（十六进制）。
+//! 客户端只信任签名通过且未过期的清单，配置文件内容必须与清单中的 SHA256 一致；
+//! 过期时间防止镜像站重放旧清单（其中的文件可能已经撤下）。
+//!
+//! 发布公钥在构建时通过环境变量 `LETRECOVERY_MANIFEST_KEYS` 提供（十六进制，多个用逗号分隔），
+//! 未提供时不信任任何清单，在线配置回退到未签名的 v1 接口（见 `server_config`）。
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "min_client_version": "2026.1.0",
+//!   "expires": "2026-12-31T00:00:00Z",
+//!   "mirrors": ["https://letrecovery.cloud-pe.cn/v2/", "https://mirror.example.com/letrecovery/v2/"],
+//!   "files": { "pe": { "path": "config/pe", "sha256": "..." }, "dl": { "path": "config/dl", "sha256": "..." } },
+//!   "telemetry": "https://letrecovery.cloud-pe.cn/v2/report"
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::Deserialize;
+use sha2::Digest;
+
+/// 清单文件名（相对于镜像站地址）
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// 签名文件后缀
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+/// 支持的清单版本
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// 构建时提供的发布签名公钥
+///
+/// 更换密钥时先追加新公钥，旧版客户端淘汰后再移除旧公钥。
+pub fn trusted_public_keys() -> Vec<&'static str> {
+    option_env!("LETRECOVERY_MANIFEST_KEYS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// 清单中的配置文件
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ManifestFile {
+    /// 相对于镜像站地址的路径，也可以是完整 URL
+    pub path: String,
+    /// 文件内容的 SHA256（十六进制）
+    pub sha256: String,
+}
+
+impl ManifestFile {
+    /// 在指定镜像站上的下载地址
+    pub fn url(&self, mirror: &str) -> String {
+        resolve(mirror, &self.path)
+    }
+
+    /// 内容是否与清单中的 SHA256 一致
+    pub fn matches(&self, content: &[u8]) -> bool {
+        sha256_hex(content).eq_ignore_ascii_case(self.sha256.trim())
+    }
+}
+
+/// 远程配置清单
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// 要求的最低客户端版本，低于此版本时不使用远程配置
+    #[serde(default)]
+    pub min_client_version: Option<String>,
+    /// 过期时间（RFC 3339），过期的清单不再使用
+    pub expires: String,
+    /// 镜像站地址
+    pub mirrors: Vec<String>,
+    /// 配置文件（键与 v1 接口的字段名相同：pe、dl、soft 等）
+    #[serde(default)]
+    pub files: BTreeMap<String, ManifestFile>,
+    /// 安装统计上报地址
+    #[serde(default)]
+    pub telemetry: Option<String>,
+}
+
+impl Manifest {
+    /// 校验签名和有效期并解析清单
+    pub fn parse_signed(data: &[u8], signature: &str, public_keys: &[&str], now: DateTime<Utc>) -> Result<Self> {
+        if public_keys.is_empty() {
+            bail!("没有可信的清单签名公钥");
+        }
+        verify_signature(data, signature, public_keys)?;
+        let manifest: Manifest = serde_json::from_slice(data).context("解析配置清单失败")?;
+        if manifest.version != MANIFEST_VERSION {
+            bail!("不支持的配置清单版本: {}", manifest.version);
+        }
+        if manifest.mirrors.is_empty() {
+            bail!("配置清单中没有镜像站");
+        }
+        let expires = DateTime::parse_from_rfc3339(manifest.expires.trim())
+            .with_context(|| format!("配置清单过期时间格式无效: {}", manifest.expires))?;
+        if expires <= now {
+            bail!("配置清单已于 {} 过期", manifest.expires);
+        }
+        Ok(manifest)
+    }
+
+    /// 检查当前客户端版本是否满足清单要求
+    pub fn check_client_version(&self, current: &str) -> Result<()> {
+        match self.min_client_version {
+            Some(ref minimum) if compare_versions(current, minimum).is_lt() => {
+                bail!("客户端版本 {} 过低，在线功能需要 {} 或更高版本，请更新 LetRecovery", current, minimum)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 用任一公钥校验 Ed25519 签名
+pub fn verify_signature(data: &[u8], signature: &str, public_keys: &[&str]) -> Result<()> {
+    let signature = decode_hex(signature.trim())
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+        .map(|bytes| Signature::from_bytes(&bytes))
+        .context("签名格式无效")?;
+    let verified = public_keys.iter().any(|key| {
+        decode_hex(key)
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            .is_some_and(|key| key.verify_strict(data, &signature).is_ok())
+    });
+    if !verified {
+        bail!("配置清单签名校验失败");
+    }
+    Ok(())
+}
+
+/// 比较点分版本号（如 2026.2.6），缺少的部分按 0 处理，非数字部分忽略
+pub fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect()
+    };
+    let (a, b) = (parse(a), parse(b));
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// 按探测延迟排序镜像站：可访问的按延迟从低到高，不可访问的保持原顺序排在最后
+pub fn rank_by_latency(probes: Vec<(String, Option<Duration>)>) -> Vec<String> {
+    let (mut reachable, unreachable): (Vec<_>, Vec<_>) = probes.into_iter().partition(|(_, latency)| latency.is_some());
+    reachable.sort_by_key(|(_, latency)| *latency);
+    reachable.into_iter().chain(unreachable).map(|(mirror, _)| mirror).collect()
+}
+
+/// 拼接镜像站地址和相对路径，完整 URL 原样返回
+pub fn resolve(base: &str, path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        path.to_string()
+    } else {
+        format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    sha2::Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn signed(data: &[u8]) -> (String, String) {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        (to_hex(&key.sign(data).to_bytes()), to_hex(key.verifying_key().as_bytes()))
+    }
+
+    const MANIFEST: &str = r#"{"version": 2, "min_client_version": "2026.1", "expires": "2026-12-31T00:00:00Z",
+        "mirrors": ["https://a.example.com/v2/", "https://b.example.com/v2"],
+        "files": {"pe": {"path": "config/pe", "sha256": "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD"}}}"#;
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-16T00:00:00Z".parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_signed() {
+        let (signature, public_key) = signed(MANIFEST.as_bytes());
+        let manifest = Manifest::parse_signed(MANIFEST.as_bytes(), &signature, &[&public_key], now()).unwrap();
+        assert_eq!(manifest.mirrors.len(), 2);
+        let pe = &manifest.files["pe"];
+        assert_eq!(pe.url(&manifest.mirrors[1]), "https://b.example.com/v2/config/pe");
+        assert!(pe.matches(b"abc"));
+        assert!(!pe.matches(b"abd"));
+
+        // 内容被篡改或使用其他公钥时拒绝
+        let tampered = MANIFEST.replace("a.example.com", "evil.example.com");
+        assert!(Manifest::parse_signed(tampered.as_bytes(), &signature, &[&public_key], now()).is_err());
+        assert!(Manifest::parse_signed(MANIFEST.as_bytes(), &signature, &trusted_public_keys(), now()).is_err());
+        assert!(Manifest::parse_signed(MANIFEST.as_bytes(), "zz", &[&public_key], now()).is_err());
+        // 没有可信公钥时不接受任何清单
+        assert!(Manifest::parse_signed(MANIFEST.as_bytes(), &signature, &[], now()).is_err());
+    }
+
+    #[test]
+    fn test_expired() {
+        let (signature, public_key) = signed(MANIFEST.as_bytes());
+        let later = "2027-01-01T00:00:00Z".parse().unwrap();
+        assert!(Manifest::parse_signed(MANIFEST.as_bytes(), &signature, &[&public_key], later).is_err());
+
+        // 缺少过期时间的清单无效
+        let unbounded = MANIFEST.replace(r#""expires": "2026-12-31T00:00:00Z","#, "");
+        let (signature, public_key) = signed(unbounded.as_bytes());
+        assert!(Manifest::parse_signed(unbounded.as_bytes(), &signature, &[&public_key], now()).is_err());
+    }
+
+    #[test]
+    fn test_client_version() {
+        let (signature, public_key) = signed(MANIFEST.as_bytes());
+        let manifest = Manifest::parse_signed(MANIFEST.as_bytes(), &signature, &[&public_key], now()).unwrap();
+        assert!(manifest.check_client_version("2026.2.6").is_ok());
+        assert!(manifest.check_client_version("2026.1.0").is_ok());
+        assert!(manifest.check_client_version("2025.12.30").is_err());
+        assert_eq!(compare_versions("2026.10.1", "2026.9"), std::cmp::Ordering::Greater);
+        assert_eq!(compare_versions("v1.2", "1.2.0"), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_rank_by_latency() {
+        let probes = vec![
+            ("a".to_string(), None),
+            ("b".to_string(), Some(Duration::from_millis(300))),
+            ("c".to_string(), Some(Duration::from_millis(40))),
+            ("d".to_string(), None),
+        ];
+        assert_eq!(rank_by_latency(probes), vec!["c", "b", "a", "d"]);
+    }
+}
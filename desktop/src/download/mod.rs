@@ -1,5 +1,12 @@
-pub mod aria2;
+pub mod announcement;
 pub mod config;
-pub mod manager;
+pub mod delta;
+pub mod engine;
+pub mod help;
+pub mod local_catalog;
+pub mod manifest;
 pub mod pe_url_resolver;
+pub mod recommend;
 pub mod server_config;
+pub mod telemetry;
+pub mod torrent;
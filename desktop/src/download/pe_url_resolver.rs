@@ -82,7 +82,7 @@ pub struct PeDownloadResponse {
 pub struct PeUrlResolveResult {
     /// 最终的下载URL
     pub download_url: String,
-    /// 需要传递给aria2的headers（可能为空）
+    /// 下载时需要附带的headers（可能为空）
     pub headers: Vec<String>,
 }
 
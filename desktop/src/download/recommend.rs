@@ -0,0 +1,223 @@
+//! 根据本机硬件推荐在线系统镜像
+//!
+//! 按 CPU 代数、TPM、内存和磁盘容量给镜像列表排序：不满足 Win11 硬性要求的镜像默认隐藏，
+//! 小内存机器优先推荐 LTSC，每个镜像附带推荐理由。
+
+use super::config::OnlineSystem;
+use crate::core::hardware_info::HardwareInfo;
+use crate::core::system_info::SystemInfo;
+
+const GB: u64 = 1024 * 1024 * 1024;
+
+/// 用于推荐的硬件概况
+#[derive(Debug, Clone, Default)]
+pub struct HardwareProfile {
+    /// CPU 是否在 Win11 支持列表中（无法识别时为 None）
+    pub win11_cpu: Option<bool>,
+    /// 是否有 TPM 2.0
+    pub tpm2: bool,
+    /// 物理内存（字节）
+    pub memory_bytes: u64,
+    /// 最大磁盘容量（字节）
+    pub largest_disk_bytes: u64,
+}
+
+impl HardwareProfile {
+    pub fn from_info(hardware: &HardwareInfo, system: Option<&SystemInfo>) -> Self {
+        Self {
+            win11_cpu: cpu_supports_win11(&hardware.cpu.name),
+            tpm2: system.is_some_and(|s| s.tpm_enabled && s.tpm_version.trim().starts_with('2')),
+            memory_bytes: hardware.memory.total_physical,
+            largest_disk_bytes: hardware.disks.iter().map(|d| d.size).max().unwrap_or(0),
+        }
+    }
+}
+
+/// 推荐等级（按排序先后）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    Recommended,
+    Neutral,
+    NotRecommended,
+    Unsupported,
+}
+
+impl Verdict {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Recommended => "推荐",
+            Verdict::Neutral => "可用",
+            Verdict::NotRecommended => "不推荐",
+            Verdict::Unsupported => "不支持",
+        }
+    }
+}
+
+/// 单个镜像的推荐结果
+#[derive(Debug, Clone)]
+pub struct Recommendation {
+    pub verdict: Verdict,
+    pub reasons: Vec<String>,
+}
+
+/// 按 CPU 名称判断是否满足 Win11 要求（Intel 8 代 / AMD Ryzen 2000 及以上）
+pub fn cpu_supports_win11(cpu_name: &str) -> Option<bool> {
+    let name = cpu_name.to_lowercase();
+    if name.contains("core(tm) ultra") || name.contains("core ultra") {
+        return Some(true);
+    }
+
+    if name.contains("intel") {
+        // Core i3/i5/i7/i9：3 位型号为 1 代；4 位型号首位为代数，但 10 代起的移动版
+        // （i7-1165G7、i5-1235U）以 1 开头、前两位为代数；5 位型号前两位为代数
+        let pos = ["i3-", "i5-", "i7-", "i9-"].iter().filter_map(|p| name.find(p)).min()?;
+        let digits: String = name[pos + 3..].chars().take_while(|c| c.is_ascii_digit()).collect();
+        let generation: u32 = match digits.len() {
+            3 => 1,
+            4 if digits.starts_with('1') => digits[..2].parse().ok()?,
+            4 => digits[..1].parse().ok()?,
+            5 => digits[..2].parse().ok()?,
+            _ => return None,
+        };
+        return Some(generation >= 8);
+    }
+
+    if name.contains("ryzen") {
+        // Ryzen N NNNN[X/G/U/H]：1000 系列及 2000 系列 APU（Zen 架构）不受支持
+        let model = name
+            .split_whitespace()
+            .skip_while(|w| !w.starts_with("ryzen"))
+            .find(|w| w.len() >= 4 && w.chars().take(4).all(|c| c.is_ascii_digit()))?;
+        let series = model.chars().next()?.to_digit(10)?;
+        let suffix = &model[4..];
+        return Some(match series {
+            1 => false,
+            2 => !suffix.contains('g') && !suffix.contains('u') && !suffix.contains('h'),
+            _ => true,
+        });
+    }
+
+    None
+}
+
+/// 评估单个镜像
+pub fn evaluate(system: &OnlineSystem, profile: &HardwareProfile) -> Recommendation {
+    let mut verdict = Verdict::Neutral;
+    let mut reasons = Vec::new();
+    let is_ltsc = system.display_name.to_lowercase().contains("ltsc");
+    let memory_gb = profile.memory_bytes as f64 / GB as f64;
+
+    if system.is_win11 {
+        if profile.win11_cpu == Some(false) {
+            verdict = verdict.max(Verdict::Unsupported);
+            reasons.push("CPU 不在 Win11 支持列表（需 Intel 8 代 / AMD Ryzen 2000 及以上）".to_string());
+        }
+        if profile.memory_bytes > 0 && profile.memory_bytes < 4 * GB - GB / 2 {
+            verdict = verdict.max(Verdict::Unsupported);
+            reasons.push(format!("内存 {:.1} GB，低于 Win11 最低要求 4 GB", memory_gb));
+        }
+        if !profile.tpm2 {
+            verdict = verdict.max(Verdict::NotRecommended);
+            reasons.push("未检测到 TPM 2.0，需要绕过硬件检查".to_string());
+        }
+        if profile.largest_disk_bytes > 0 && profile.largest_disk_bytes < 64 * GB {
+            verdict = verdict.max(Verdict::NotRecommended);
+            reasons.push("磁盘容量小于 Win11 要求的 64 GB".to_string());
+        }
+        if verdict == Verdict::Neutral && profile.win11_cpu == Some(true) && profile.memory_bytes >= 8 * GB - GB {
+            verdict = Verdict::Recommended;
+            reasons.push("硬件满足 Win11 要求".to_string());
+        }
+    } else if profile.win11_cpu == Some(false) {
+        verdict = Verdict::Recommended;
+        reasons.push("CPU 不支持 Win11，推荐 Win10".to_string());
+    }
+
+    // 小内存机器（约 4 GB 及以下）优先 LTSC
+    if profile.memory_bytes > 0 && profile.memory_bytes <= 5 * GB && verdict != Verdict::Unsupported {
+        if is_ltsc {
+            verdict = verdict.min(Verdict::Recommended);
+            reasons.push(format!("内存 {:.1} GB，LTSC 占用更低", memory_gb));
+        } else {
+            verdict = verdict.max(Verdict::NotRecommended);
+            reasons.push(format!("内存 {:.1} GB，建议选择 LTSC 版本", memory_gb));
+        }
+    }
+
+    Recommendation { verdict, reasons }
+}
+
+/// 评估全部镜像，返回按推荐程度排序后的（原始索引，推荐结果），相同等级保持原顺序
+pub fn rank(systems: &[OnlineSystem], profile: &HardwareProfile) -> Vec<(usize, Recommendation)> {
+    let mut ranked: Vec<(usize, Recommendation)> = systems
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (i, evaluate(s, profile)))
+        .collect();
+    ranked.sort_by_key(|(_, r)| r.verdict);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn system(name: &str, is_win11: bool) -> OnlineSystem {
+        OnlineSystem {
+            download_url: String::new(),
+            display_name: name.to_string(),
+            is_win11,
+            chunk_manifest_url: None,
+        }
+    }
+
+    #[test]
+    fn test_cpu_supports_win11() {
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i5-7200U CPU @ 2.50GHz"), Some(false));
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i7-8700K CPU @ 3.70GHz"), Some(true));
+        assert_eq!(cpu_supports_win11("12th Gen Intel(R) Core(TM) i7-12700H"), Some(true));
+        assert_eq!(cpu_supports_win11("11th Gen Intel(R) Core(TM) i7-1165G7 @ 2.80GHz"), Some(true));
+        assert_eq!(cpu_supports_win11("12th Gen Intel(R) Core(TM) i5-1235U"), Some(true));
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i5-1035G1 CPU @ 1.00GHz"), Some(true));
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i7 CPU 920 @ 2.67GHz"), None);
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i5 CPU M 520 @ 2.40GHz"), None);
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) i5-520M"), Some(false));
+        assert_eq!(cpu_supports_win11("Intel(R) Core(TM) Ultra 7 155H"), Some(true));
+        assert_eq!(cpu_supports_win11("AMD Ryzen 7 1700X Eight-Core Processor"), Some(false));
+        assert_eq!(cpu_supports_win11("AMD Ryzen 5 2400G with Radeon Vega Graphics"), Some(false));
+        assert_eq!(cpu_supports_win11("AMD Ryzen 5 2600 Six-Core Processor"), Some(true));
+        assert_eq!(cpu_supports_win11("AMD Ryzen 7 5800H with Radeon Graphics"), Some(true));
+        assert_eq!(cpu_supports_win11("Intel(R) Celeron(R) N4020 CPU @ 1.10GHz"), None);
+    }
+
+    #[test]
+    fn test_rank() {
+        let systems = vec![
+            system("Windows 11 24H2 专业版", true),
+            system("Windows 10 22H2 专业版", false),
+            system("Windows 10 LTSC 2021", false),
+        ];
+        let old_pc = HardwareProfile {
+            win11_cpu: Some(false),
+            tpm2: false,
+            memory_bytes: 4 * GB,
+            largest_disk_bytes: 500 * GB,
+        };
+        let ranked = rank(&systems, &old_pc);
+        assert_eq!(ranked[0].0, 2);
+        assert_eq!(ranked[0].1.verdict, Verdict::Recommended);
+        assert_eq!(ranked[2].0, 0);
+        assert_eq!(ranked[2].1.verdict, Verdict::Unsupported);
+
+        let new_pc = HardwareProfile {
+            win11_cpu: Some(true),
+            tpm2: true,
+            memory_bytes: 16 * GB,
+            largest_disk_bytes: 1000 * GB,
+        };
+        let ranked = rank(&systems, &new_pc);
+        assert_eq!(ranked[0].0, 0);
+        assert_eq!(ranked[0].1.verdict, Verdict::Recommended);
+    }
+}
@@ -1,12 +1,34 @@
 //! 服务器配置模块
 //! 从远程服务器获取 PE 和系统镜像配置
+//!
+//! 构建时提供了发布公钥时只使用签名的 v2 清单（见 `manifest`）：按延迟排序镜像站，
+//! 配置文件逐个校验 SHA256，失败时换下一个镜像站，没有有效清单时不加载远程配置。
+//! 未提供公钥的构建无法校验清单，回退到未签名的 v1 接口并记录警告。
+
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use super::manifest::{self, Manifest, ManifestFile, MANIFEST_FILE, SIGNATURE_SUFFIX};
+use crate::utils::path::get_exe_dir;
 
 /// 全局服务器地址
 pub const SERVER_BASE_URL: &str = "https://letrecovery.cloud-pe.cn/v2/";
 
+/// 镜像站延迟探测超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 上次获取的清单缓存（主站不可用时从其中记录的镜像站获取清单）
+const MANIFEST_CACHE_FILE: &str = "manifest_cache.json";
+
+/// 清单缓存（保存原文和签名，读取时重新校验）
+#[derive(Serialize, Deserialize)]
+struct ManifestCache {
+    manifest: String,
+    signature: String,
+}
+
 /// 服务器配置响应
 #[derive(Debug, Clone, Deserialize)]
 pub struct ServerConfigResponse {
@@ -28,6 +50,33 @@ pub struct ServerConfigData {
     /// GPU驱动配置路径
     #[serde(default)]
     pub gpu: Option<String>,
+    /// 镜像安装预设配置路径
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// 品牌定制配置路径
+    #[serde(default)]
+    pub branding: Option<String>,
+    /// 公告配置路径
+    #[serde(default)]
+    pub notice: Option<String>,
+    /// 安装统计上报地址
+    #[serde(default)]
+    pub telemetry: Option<String>,
+    /// 镜像许可协议配置路径
+    #[serde(default)]
+    pub eula: Option<String>,
+    /// 存储控制器驱动包配置路径
+    #[serde(default)]
+    pub driver: Option<String>,
+    /// 硬件 ID 驱动索引配置路径
+    #[serde(default)]
+    pub hwid: Option<String>,
+    /// 帮助内容配置路径
+    #[serde(default)]
+    pub help: Option<String>,
+    /// 文件哈希配置路径
+    #[serde(default)]
+    pub hash: Option<String>,
 }
 
 /// 远程配置
@@ -43,6 +92,24 @@ pub struct RemoteConfig {
     pub easy_content: Option<String>,
     /// GPU驱动列表内容（从服务器获取）
     pub gpu_content: Option<String>,
+    /// 镜像安装预设内容（从服务器获取）
+    pub preset_content: Option<String>,
+    /// 品牌定制内容（从服务器获取）
+    pub branding_content: Option<String>,
+    /// 公告内容（从服务器获取）
+    pub notice_content: Option<String>,
+    /// 安装统计上报地址（仅用户开启后使用）
+    pub telemetry_url: Option<String>,
+    /// 镜像许可协议内容（从服务器获取）
+    pub eula_content: Option<String>,
+    /// 存储控制器驱动包列表内容（从服务器获取）
+    pub driver_content: Option<String>,
+    /// 硬件 ID 驱动索引内容（从服务器获取）
+    pub hwid_content: Option<String>,
+    /// 帮助内容（从服务器获取）
+    pub help_content: Option<String>,
+    /// 文件哈希列表内容（从服务器获取）
+    pub hash_content: Option<String>,
     /// 是否加载成功
     pub loaded: bool,
     /// 错误信息
@@ -57,36 +124,195 @@ impl RemoteConfig {
     /// 2. 根据返回的 URL 获取 PE 和系统镜像列表的内容
     /// 3. 支持完整 URL 和相对路径两种格式
     pub fn load_from_server() -> Self {
-        let mut config = RemoteConfig::default();
-        
         // 尝试加载配置
         match Self::fetch_config() {
-            Ok((pe_content, dl_content, soft_content, easy_content, gpu_content)) => {
-                config.pe_content = pe_content;
-                config.dl_content = dl_content;
-                config.soft_content = soft_content;
-                config.easy_content = easy_content;
-                config.gpu_content = gpu_content;
+            Ok(mut config) => {
                 config.loaded = true;
                 log::info!("远程配置加载成功");
+                config
             }
             Err(e) => {
-                config.error = Some(e.to_string());
-                config.loaded = false;
                 log::warn!("远程配置加载失败: {}", e);
+                RemoteConfig {
+                    error: Some(e.to_string()),
+                    loaded: false,
+                    ..Default::default()
+                }
             }
         }
-        
-        config
     }
     
     /// 获取服务器配置
-    fn fetch_config() -> Result<(Option<String>, Option<String>, Option<String>, Option<String>, Option<String>)> {
+    fn fetch_config() -> Result<Self> {
         let client = reqwest::blocking::Client::builder()
             .timeout(std::time::Duration::from_secs(10))
             .build()
             .context("创建 HTTP 客户端失败")?;
-        
+
+        let keys = manifest::trusted_public_keys();
+        if keys.is_empty() {
+            log::warn!("此版本未配置清单签名公钥，使用未签名的 v1 接口，配置内容未经校验");
+            return Self::fetch_legacy(&client);
+        }
+        let manifest = Self::fetch_manifest(&client, &keys)?;
+        Self::fetch_with_manifest(&client, &manifest)
+    }
+
+    /// 从镜像站获取并校验配置清单
+    ///
+    /// 清单不存在、签名无效或已过期时换下一个镜像站，全部失败时返回错误
+    fn fetch_manifest(client: &reqwest::blocking::Client, keys: &[&str]) -> Result<Manifest> {
+        let mut mirrors = vec![SERVER_BASE_URL.to_string()];
+        if let Some(cached) = Self::load_cached_manifest(keys) {
+            for mirror in cached.mirrors {
+                if !mirrors.contains(&mirror) {
+                    mirrors.push(mirror);
+                }
+            }
+        }
+
+        let mut last_error = None;
+        for mirror in Self::rank_mirrors(&mirrors) {
+            let url = manifest::resolve(&mirror, MANIFEST_FILE);
+            let fetched = Self::fetch_bytes(client, &url).and_then(|data| {
+                let signature = Self::fetch_bytes(client, &format!("{}{}", url, SIGNATURE_SUFFIX))?;
+                Ok((data, String::from_utf8_lossy(&signature).to_string()))
+            });
+            let (data, signature) = match fetched {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    log::info!("镜像站 {} 没有可用的配置清单: {:#}", mirror, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            match Manifest::parse_signed(&data, &signature, keys, chrono::Utc::now()) {
+                Ok(manifest) => {
+                    log::info!("已从 {} 获取配置清单，镜像站 {} 个", mirror, manifest.mirrors.len());
+                    Self::save_cached_manifest(&data, &signature);
+                    return Ok(manifest);
+                }
+                Err(e) => {
+                    log::warn!("镜像站 {} 的配置清单无效: {:#}", mirror, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("没有可用的镜像站")).context("获取配置清单失败"))
+    }
+
+    /// 读取上次的清单缓存（签名校验失败或已过期时忽略）
+    fn load_cached_manifest(keys: &[&str]) -> Option<Manifest> {
+        let content = std::fs::read_to_string(get_exe_dir().join(MANIFEST_CACHE_FILE)).ok()?;
+        let cache: ManifestCache = serde_json::from_str(&content).ok()?;
+        Manifest::parse_signed(cache.manifest.as_bytes(), &cache.signature, keys, chrono::Utc::now()).ok()
+    }
+
+    fn save_cached_manifest(data: &[u8], signature: &str) {
+        let cache = ManifestCache {
+            manifest: String::from_utf8_lossy(data).to_string(),
+            signature: signature.trim().to_string(),
+        };
+        let result = serde_json::to_string(&cache)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| {
+                std::fs::write(get_exe_dir().join(MANIFEST_CACHE_FILE), content).map_err(anyhow::Error::from)
+            });
+        if let Err(e) = result {
+            log::warn!("保存配置清单缓存失败: {:#}", e);
+        }
+    }
+
+    /// 并行探测镜像站延迟并排序（只有一个镜像站时不探测）
+    fn rank_mirrors(mirrors: &[String]) -> Vec<String> {
+        if mirrors.len() <= 1 {
+            return mirrors.to_vec();
+        }
+        let handles: Vec<_> = mirrors
+            .iter()
+            .cloned()
+            .map(|mirror| {
+                std::thread::spawn(move || {
+                    let latency = Self::probe_latency(&mirror);
+                    (mirror, latency)
+                })
+            })
+            .collect();
+        let probes = handles.into_iter().filter_map(|handle| handle.join().ok()).collect();
+        let ranked = manifest::rank_by_latency(probes);
+        log::info!("镜像站排序: {:?}", ranked);
+        ranked
+    }
+
+    /// 探测镜像站延迟，无法访问时返回 None（只要有 HTTP 响应即视为可访问）
+    fn probe_latency(mirror: &str) -> Option<Duration> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .ok()?;
+        let start = Instant::now();
+        client.head(mirror).send().ok()?;
+        Some(start.elapsed())
+    }
+
+    /// 按清单获取各配置文件
+    fn fetch_with_manifest(client: &reqwest::blocking::Client, manifest: &Manifest) -> Result<Self> {
+        manifest.check_client_version(env!("CARGO_PKG_VERSION"))?;
+        let mirrors = Self::rank_mirrors(&manifest.mirrors);
+        let fetch = |key: &str| {
+            manifest
+                .files
+                .get(key)
+                .and_then(|file| Self::fetch_verified(client, &mirrors, key, file))
+        };
+
+        Ok(RemoteConfig {
+            pe_content: fetch("pe"),
+            dl_content: fetch("dl"),
+            soft_content: fetch("soft"),
+            easy_content: fetch("easy"),
+            gpu_content: fetch("gpu"),
+            preset_content: fetch("preset"),
+            branding_content: fetch("branding"),
+            notice_content: fetch("notice"),
+            telemetry_url: manifest
+                .telemetry
+                .as_ref()
+                .zip(mirrors.first())
+                .map(|(url, mirror)| manifest::resolve(mirror, url)),
+            eula_content: fetch("eula"),
+            driver_content: fetch("driver"),
+            hwid_content: fetch("hwid"),
+            help_content: fetch("help"),
+            hash_content: fetch("hash"),
+            ..Default::default()
+        })
+    }
+
+    /// 按镜像站顺序下载配置文件，内容与清单中的 SHA256 不一致时换下一个镜像站
+    fn fetch_verified(
+        client: &reqwest::blocking::Client,
+        mirrors: &[String],
+        key: &str,
+        file: &ManifestFile,
+    ) -> Option<String> {
+        let mut urls: Vec<String> = mirrors.iter().map(|mirror| file.url(mirror)).collect();
+        urls.dedup();
+        for url in urls {
+            match Self::fetch_bytes(client, &url) {
+                Ok(content) if file.matches(&content) => match String::from_utf8(content) {
+                    Ok(text) => return Some(text),
+                    Err(_) => log::warn!("{} 不是有效的 UTF-8 文本", url),
+                },
+                Ok(_) => log::warn!("{} 的 SHA256 与清单不一致，尝试下一个镜像站", url),
+                Err(e) => log::warn!("获取 {} 配置失败: {:#}", key, e),
+            }
+        }
+        None
+    }
+
+    /// 通过 v1 接口获取服务器配置
+    fn fetch_legacy(client: &reqwest::blocking::Client) -> Result<Self> {
         // 请求服务器配置
         let config_url = SERVER_BASE_URL;
         log::info!("请求服务器配置: {}", config_url);
@@ -116,6 +342,15 @@ impl RemoteConfig {
         let soft_url = data.soft.as_ref().map(|s| Self::resolve_url(s));
         let easy_url = data.easy.as_ref().map(|s| Self::resolve_url(s));
         let gpu_url = data.gpu.as_ref().map(|s| Self::resolve_url(s));
+        let preset_url = data.preset.as_ref().map(|s| Self::resolve_url(s));
+        let branding_url = data.branding.as_ref().map(|s| Self::resolve_url(s));
+        let notice_url = data.notice.as_ref().map(|s| Self::resolve_url(s));
+        let telemetry_url = data.telemetry.as_ref().map(|s| Self::resolve_url(s));
+        let eula_url = data.eula.as_ref().map(|s| Self::resolve_url(s));
+        let driver_url = data.driver.as_ref().map(|s| Self::resolve_url(s));
+        let hwid_url = data.hwid.as_ref().map(|s| Self::resolve_url(s));
+        let help_url = data.help.as_ref().map(|s| Self::resolve_url(s));
+        let hash_url = data.hash.as_ref().map(|s| Self::resolve_url(s));
         
         log::info!("PE 配置 URL: {}", pe_url);
         log::info!("DL 配置 URL: {}", dl_url);
@@ -128,23 +363,87 @@ impl RemoteConfig {
         if let Some(ref url) = gpu_url {
             log::info!("GPU 配置 URL: {}", url);
         }
+        if let Some(ref url) = preset_url {
+            log::info!("Preset 配置 URL: {}", url);
+        }
+        if let Some(ref url) = branding_url {
+            log::info!("Branding 配置 URL: {}", url);
+        }
+        if let Some(ref url) = notice_url {
+            log::info!("Notice 配置 URL: {}", url);
+        }
+        if let Some(ref url) = eula_url {
+            log::info!("EULA 配置 URL: {}", url);
+        }
+        if let Some(ref url) = driver_url {
+            log::info!("Driver 配置 URL: {}", url);
+        }
+        if let Some(ref url) = hwid_url {
+            log::info!("Hwid 配置 URL: {}", url);
+        }
+        if let Some(ref url) = help_url {
+            log::info!("Help 配置 URL: {}", url);
+        }
+        if let Some(ref url) = hash_url {
+            log::info!("Hash 配置 URL: {}", url);
+        }
         
         // 获取 PE 配置内容
-        let pe_content = Self::fetch_text_content(&client, &pe_url).ok();
+        let pe_content = Self::fetch_text_content(client, &pe_url).ok();
         
         // 获取 DL 配置内容
-        let dl_content = Self::fetch_text_content(&client, &dl_url).ok();
+        let dl_content = Self::fetch_text_content(client, &dl_url).ok();
         
         // 获取 Soft 配置内容
-        let soft_content = soft_url.and_then(|url| Self::fetch_text_content(&client, &url).ok());
+        let soft_content = soft_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
         
         // 获取 Easy 配置内容
-        let easy_content = easy_url.and_then(|url| Self::fetch_text_content(&client, &url).ok());
+        let easy_content = easy_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
         
         // 获取 GPU 配置内容
-        let gpu_content = gpu_url.and_then(|url| Self::fetch_text_content(&client, &url).ok());
+        let gpu_content = gpu_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取镜像安装预设内容
+        let preset_content = preset_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取品牌定制内容
+        let branding_content = branding_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取公告内容
+        let notice_content = notice_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取镜像许可协议内容
+        let eula_content = eula_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取存储控制器驱动包列表内容
+        let driver_content = driver_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取硬件 ID 驱动索引内容
+        let hwid_content = hwid_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        // 获取帮助内容
+        let help_content = help_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
         
-        Ok((pe_content, dl_content, soft_content, easy_content, gpu_content))
+        // 获取文件哈希列表内容
+        let hash_content = hash_url.and_then(|url| Self::fetch_text_content(client, &url).ok());
+        
+        Ok(RemoteConfig {
+            pe_content,
+            dl_content,
+            soft_content,
+            easy_content,
+            gpu_content,
+            preset_content,
+            branding_content,
+            notice_content,
+            telemetry_url,
+            eula_content,
+            driver_content,
+            hwid_content,
+            help_content,
+            hash_content,
+            ..Default::default()
+        })
     }
     
     /// 解析 URL，支持完整 URL 和相对路径
@@ -176,6 +475,18 @@ impl RemoteConfig {
         
         Ok(content)
     }
+
+    /// 获取二进制内容
+    fn fetch_bytes(client: &reqwest::blocking::Client, url: &str) -> Result<Vec<u8>> {
+        let response = client
+            .get(url)
+            .send()
+            .with_context(|| format!("请求 {} 失败", url))?;
+        if !response.status().is_success() {
+            anyhow::bail!("请求 {} 返回错误状态码: {}", url, response.status());
+        }
+        Ok(response.bytes().context("读取响应内容失败")?.to_vec())
+    }
     
     /// 检查 PE 配置是否可用
     pub fn is_pe_available(&self) -> bool {
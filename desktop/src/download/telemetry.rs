@@ -0,0 +1,83 @@
+//! 匿名安装统计模块（需用户主动开启）
+//! 安装结束后向运营方服务器上报镜像、耗时、硬件类别和失败阶段，不包含任何个人信息
+
+use serde::Serialize;
+
+use crate::core::hardware_info::HardwareInfo;
+
+/// 安装统计报告
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallReport {
+    /// 客户端版本
+    pub client_version: String,
+    /// 镜像标识（文件名）
+    pub image_id: String,
+    /// 分卷名称
+    pub volume_name: String,
+    /// 安装模式（direct / pe）
+    pub install_mode: String,
+    /// 是否成功
+    pub success: bool,
+    /// 失败时所处步骤
+    pub failed_stage: Option<String>,
+    /// 耗时（秒）
+    pub duration_secs: u64,
+    /// 硬件类别
+    pub hardware: HardwareClass,
+}
+
+/// 硬件类别（仅粗粒度分类，不含序列号等可识别信息）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HardwareClass {
+    /// 设备类型（Desktop / Laptop ...）
+    pub device_type: String,
+    /// CPU 厂商
+    pub cpu_vendor: String,
+    /// CPU 架构
+    pub cpu_architecture: String,
+    /// 内存容量（GB，取整）
+    pub memory_gb: u64,
+    /// 是否存在 NVMe 磁盘
+    pub has_nvme: bool,
+    /// 是否存在 SSD
+    pub has_ssd: bool,
+}
+
+impl HardwareClass {
+    /// 从硬件信息提取硬件类别
+    pub fn from_hardware_info(info: &HardwareInfo) -> Self {
+        Self {
+            device_type: format!("{:?}", info.device_type),
+            cpu_vendor: info.cpu.manufacturer.clone(),
+            cpu_architecture: info.cpu.architecture.clone(),
+            memory_gb: info.memory.total_physical / (1024 * 1024 * 1024),
+            has_nvme: info
+                .disks
+                .iter()
+                .any(|d| d.interface_type.to_uppercase().contains("NVME")),
+            has_ssd: info.disks.iter().any(|d| d.is_ssd),
+        }
+    }
+}
+
+/// 在后台线程上报安装统计，失败只记录日志
+pub fn send_install_report(endpoint: String, report: InstallReport) {
+    std::thread::spawn(move || {
+        let result = reqwest::blocking::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .and_then(|client| client.post(&endpoint).json(&report).send());
+
+        match result {
+            Ok(resp) if resp.status().is_success() => {
+                log::info!("[TELEMETRY] 安装统计已上报");
+            }
+            Ok(resp) => {
+                log::warn!("[TELEMETRY] 安装统计上报失败，状态码: {}", resp.status());
+            }
+            Err(e) => {
+                log::warn!("[TELEMETRY] 安装统计上报失败: {}", e);
+            }
+        }
+    });
+}
@@ -0,0 +1,224 @@
+//! BitTorrent / 磁力链接下载
+//!
+//! 基于 librqbit：支持 `magnet:?` 链接和 `.torrent` 地址，文件保存在下载目录中（多文件种子为同名子目录）。
+//! librqbit 对收到的每个分块做 SHA-1 校验，校验失败的分块会丢弃并重新下载；
+//! 目录中已有未完成的文件时先逐块校验再继续，只有全部分块校验通过才视为完成，之后才交给安装流程。
+//! 完成后立即停止做种。
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::Duration;
+
+use librqbit::{AddTorrent, AddTorrentOptions, Session, TorrentStatsState};
+
+use super::engine::{self, DownloadCommand, DownloadProgress, DownloadStatus};
+
+/// 进度上报间隔
+const REPORT_INTERVAL: Duration = Duration::from_millis(500);
+/// 可直接安装的镜像扩展名
+const IMAGE_EXTENSIONS: &[&str] = &["iso", "wim", "esd", "swm", "gho", "ghs"];
+
+/// 是否为 BT 下载地址（磁力链接或 .torrent 文件）
+pub fn is_torrent_link(url: &str) -> bool {
+    let lower = url.trim().to_lowercase();
+    lower.starts_with("magnet:?") || lower.split(['?', '#']).next().unwrap_or_default().ends_with(".torrent")
+}
+
+/// 磁力链接中的显示名称（dn 参数）
+pub fn magnet_display_name(uri: &str) -> Option<String> {
+    let query = uri.trim().strip_prefix("magnet:?")?;
+    query
+        .split('&')
+        .find_map(|param| param.strip_prefix("dn="))
+        .map(|name| engine::percent_decode(&name.replace('+', " ")))
+        .filter(|name| !name.trim().is_empty())
+}
+
+/// 从下载到的文件中选出要安装的镜像：优先最大的镜像文件，其次最大的文件
+pub fn pick_image_file(files: &[(PathBuf, u64)]) -> Option<PathBuf> {
+    let is_image = |path: &Path| {
+        path.extension()
+            .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+            .unwrap_or(false)
+    };
+    files
+        .iter()
+        .filter(|(path, _)| is_image(path))
+        .max_by_key(|(_, size)| *size)
+        .or_else(|| files.iter().max_by_key(|(_, size)| *size))
+        .map(|(path, _)| path.clone())
+}
+
+/// 递归列出目录中的文件及大小
+fn list_files(dir: &Path, files: &mut Vec<(PathBuf, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            list_files(&path, files);
+        } else if let Ok(metadata) = entry.metadata() {
+            files.push((path, metadata.len()));
+        }
+    }
+}
+
+fn progress(completed: u64, total: u64, speed: u64, peers: usize, status: DownloadStatus) -> DownloadProgress {
+    DownloadProgress {
+        gid: String::new(),
+        completed_length: completed,
+        total_length: total,
+        download_speed: speed,
+        percentage: if total > 0 { completed as f64 * 100.0 / total as f64 } else { 0.0 },
+        status,
+        peers: Some(peers),
+        file_path: None,
+    }
+}
+
+/// BT 下载任务
+pub struct TorrentDownload<'a> {
+    /// 磁力链接或 .torrent 地址
+    pub uri: &'a str,
+    pub save_dir: &'a Path,
+    pub progress_tx: &'a Sender<DownloadProgress>,
+    pub cmd_rx: &'a Receiver<DownloadCommand>,
+}
+
+impl TorrentDownload<'_> {
+    /// 执行下载，返回要安装的镜像文件路径
+    pub fn run(&self) -> Result<PathBuf> {
+        std::fs::create_dir_all(self.save_dir)
+            .with_context(|| format!("无法创建下载目录: {}", self.save_dir.display()))?;
+        let runtime = tokio::runtime::Runtime::new().context("创建运行时失败")?;
+        runtime.block_on(self.download())
+    }
+
+    async fn download(&self) -> Result<PathBuf> {
+        let _ = self.progress_tx.send(progress(0, 0, 0, 0, DownloadStatus::Waiting));
+
+        let session = Session::new(self.save_dir.to_path_buf()).await.context("启动 BT 客户端失败")?;
+        log::info!("[BT] 添加任务: {}", self.uri);
+        let handle = session
+            .add_torrent(
+                AddTorrent::from_url(self.uri),
+                Some(AddTorrentOptions {
+                    // 允许复用目录中已有的文件（先校验再续传）
+                    overwrite: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .context("添加 BT 任务失败")?
+            .into_handle()
+            .context("添加 BT 任务失败")?;
+        log::info!("[BT] 已获取种子信息: {}", handle.name().unwrap_or_default());
+
+        let mut paused = false;
+        loop {
+            tokio::time::sleep(REPORT_INTERVAL).await;
+
+            while let Ok(cmd) = self.cmd_rx.try_recv() {
+                match cmd {
+                    DownloadCommand::Pause if !paused => {
+                        session.pause(&handle).await.context("暂停 BT 任务失败")?;
+                        paused = true;
+                    }
+                    DownloadCommand::Resume if paused => {
+                        session.unpause(&handle).await.context("继续 BT 任务失败")?;
+                        paused = false;
+                    }
+                    DownloadCommand::Cancel => {
+                        // 保留已下载的文件，下次下载时校验后续传
+                        let _ = session.delete(handle.id().into(), false).await;
+                        bail!("下载已取消");
+                    }
+                    _ => {}
+                }
+            }
+
+            let stats = handle.stats();
+            if let Some(error) = stats.error {
+                let _ = session.delete(handle.id().into(), false).await;
+                bail!("BT 下载失败: {}", error);
+            }
+            if stats.finished {
+                break;
+            }
+
+            let (speed, peers) = stats
+                .live
+                .as_ref()
+                .map(|live| ((live.download_speed.mbps * 1024.0 * 1024.0) as u64, live.snapshot.peer_stats.live))
+                .unwrap_or((0, 0));
+            let status = match stats.state {
+                TorrentStatsState::Initializing => DownloadStatus::Verifying,
+                TorrentStatsState::Paused => DownloadStatus::Paused,
+                TorrentStatsState::Live => DownloadStatus::Active,
+                TorrentStatsState::Error => DownloadStatus::Error("BT 下载出错".to_string()),
+            };
+            let report = progress(stats.progress_bytes, stats.total_bytes, speed, peers, status);
+            if self.progress_tx.send(report).is_err() {
+                // 下载页面已关闭
+                let _ = session.delete(handle.id().into(), false).await;
+                bail!("下载已取消");
+            }
+        }
+
+        // 全部分块已校验通过，停止做种后再交给安装流程
+        let name = handle.name().context("无法获取种子名称")?;
+        session
+            .delete(handle.id().into(), false)
+            .await
+            .context("停止 BT 任务失败")?;
+        log::info!("[BT] 下载完成: {}", name);
+
+        let root = self.save_dir.join(&name);
+        let mut files = Vec::new();
+        if root.is_dir() {
+            list_files(&root, &mut files);
+        } else if let Ok(metadata) = std::fs::metadata(&root) {
+            files.push((root.clone(), metadata.len()));
+        }
+        pick_image_file(&files).with_context(|| format!("下载目录中没有找到文件: {}", root.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_torrent_link() {
+        assert!(is_torrent_link("magnet:?xt=urn:btih:abcdef&dn=win11.iso"));
+        assert!(is_torrent_link(" MAGNET:?xt=urn:btih:abcdef"));
+        assert!(is_torrent_link("https://example.com/win11.torrent?token=1"));
+        assert!(!is_torrent_link("https://example.com/win11.iso"));
+        assert!(!is_torrent_link("https://example.com/a.torrent.iso"));
+    }
+
+    #[test]
+    fn test_magnet_display_name() {
+        assert_eq!(
+            magnet_display_name("magnet:?xt=urn:btih:abc&dn=Windows+11%20LTSC.iso&tr=udp%3A%2F%2Ft"),
+            Some("Windows 11 LTSC.iso".to_string())
+        );
+        assert_eq!(magnet_display_name("magnet:?xt=urn:btih:abc"), None);
+        assert_eq!(magnet_display_name("https://example.com/a.iso"), None);
+    }
+
+    #[test]
+    fn test_pick_image_file() {
+        let files = vec![
+            (PathBuf::from("d/readme.txt"), 10),
+            (PathBuf::from("d/bonus.zip"), 9_000),
+            (PathBuf::from("d/win10.ISO"), 4_000),
+            (PathBuf::from("d/win11.iso"), 5_000),
+        ];
+        assert_eq!(pick_image_file(&files), Some(PathBuf::from("d/win11.iso")));
+        assert_eq!(pick_image_file(&files[..2]), Some(PathBuf::from("d/bonus.zip")));
+        assert_eq!(pick_image_file(&[]), None);
+    }
+}
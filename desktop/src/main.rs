@@ -10,12 +10,18 @@ mod utils;
 use eframe::egui;
 use std::sync::Arc;
 
+use core::power::CompletionAction;
+use utils::exit_code::{CliFailure, ExitCode};
+use utils::json_progress::{self, ProgressEvent};
+
 /// 预加载的配置数据
 pub struct PreloadedConfig {
     pub remote_config: Option<download::server_config::RemoteConfig>,
     pub system_info: Option<core::system_info::SystemInfo>,
     pub hardware_info: Option<core::hardware_info::HardwareInfo>,
     pub partitions: Vec<core::disk::Partition>,
+    /// 运行环境检测结果
+    pub diagnostics: Vec<core::diagnostics::DiagnosticItem>,
 }
 
 fn main() -> eframe::Result<()> {
@@ -42,23 +48,57 @@ fn main() -> eframe::Result<()> {
 
     // 检查命令行参数，处理PE环境下的自动安装/备份
     let args: Vec<String> = std::env::args().collect();
-    
+
+    // 机器可读进度输出（供部署脚本集成）
+    if let Err(e) = json_progress::init_from_args(&args) {
+        cli_eprintln!("{}", e);
+        ExitCode::InvalidArguments.exit();
+    }
+
+    // 演示模式：磁盘操作全部模拟，修改真实系统的工具和命令行安装/备份被拒绝
+    let demo_mode = args.iter().any(|a| a == core::demo::DEMO_ARG);
+    if demo_mode {
+        core::demo::enable();
+    }
+
+    // 命令行子命令（install / backup / verify / list-partitions / list-images）
+    if let Some(invocation) = utils::cli::parse(&args[1..]) {
+        utils::cli::attach_console();
+        log::info!("命令行子命令: {:?}", invocation);
+        run_cli(invocation).exit();
+    }
+
     if args.contains(&"/PEINSTALL".to_string()) || args.contains(&"--pe-install".to_string()) {
         log::info!("检测到PE安装模式，执行自动安装...");
-        return run_pe_install();
+        run_pe_install().exit();
     }
     
     if args.contains(&"/PEBACKUP".to_string()) || args.contains(&"--pe-backup".to_string()) {
         log::info!("检测到PE备份模式，执行自动备份...");
-        return run_pe_backup();
+        run_pe_backup().exit();
+    }
+
+    if let Some(pos) = args.iter().position(|a| a == "--verify") {
+        let Some(image_path) = args.get(pos + 1) else {
+            show_error_message("--verify 需要指定镜像文件路径");
+            ExitCode::Failed.exit();
+        };
+        log::info!("命令行校验镜像: {}", image_path);
+        run_verify(image_path).exit();
     }
 
-    // 检查管理员权限
+    if args.iter().any(|a| a == core::scheduler::SCHEDULED_BACKUP_ARG) {
+        log::info!("检测到定时备份模式，执行定时备份...");
+        run_scheduled_backup().exit();
+    }
+
+    // 检查管理员权限（演示模式仍会读取真实的系统信息，同样需要）
     if !utils::privilege::is_admin() {
         log::warn!("需要管理员权限，正在尝试提升权限...");
         if let Err(e) = utils::privilege::restart_as_admin() {
             log::error!("提升权限失败: {}", e);
-            eprintln!("需要管理员权限运行此程序");
+            cli_eprintln!("需要管理员权限运行此程序");
+            ExitCode::Failed.exit();
         }
         return Ok(());
     }
@@ -68,38 +108,12 @@ fn main() -> eframe::Result<()> {
     // 检查是否为64位系统
     if !cfg!(target_arch = "x86_64") {
         log::error!("本程序仅支持64位系统");
-        eprintln!("本程序仅支持64位系统");
-        return Ok(());
-    }
-
-    // 检查依赖文件完整性
-    if let Err(missing_files) = check_dependencies() {
-        log::error!("依赖文件缺失: {:?}", missing_files);
-        let message = format!(
-            "程序文件不完整，无法正常运行。\n\n\
-            缺少以下文件：\n{}\n\n\
-            请重新下载完整安装包或修复程序文件。",
-            missing_files.join("\n")
-        );
-        show_error_message(&message);
-        return Ok(());
-    }
-
-    log::info!("依赖文件检查通过");
-
-    // 检查系统核心组件（极限精简系统检测）
-    if let Err(missing_components) = check_system_components() {
-        log::error!("系统组件缺失: {:?}", missing_components);
-        let message = format!(
-            "很抱歉，该软件目前暂时不支持您所使用的极限精简系统使用。\n\n\
-            缺少以下系统组件：\n{}",
-            missing_components.join("\n")
-        );
-        show_error_message(&message);
-        return Ok(());
+        cli_eprintln!("本程序仅支持64位系统");
+        ExitCode::Failed.exit();
     }
 
-    log::info!("系统组件检查通过");
+    // 运行环境检测（依赖文件、系统组件等问题在界面中以检查清单展示）
+    let diagnostics = core::diagnostics::run_local();
 
     // 防止重复运行
     let _mutex = match single_instance::SingleInstance::new("LetRecovery-mutex-2025") {
@@ -119,22 +133,42 @@ fn main() -> eframe::Result<()> {
     log::info!("正在预加载配置和系统信息...");
 
     // 在显示窗口前先加载服务器配置和系统信息
-    let preloaded_config = preload_all_config();
+    let preloaded_config = preload_all_config(diagnostics);
     let preloaded_config = Arc::new(preloaded_config);
 
     log::info!("预加载完成，初始化 GUI...");
 
+    // PE中的平板/二合一设备使用触屏布局，正常系统中由 Windows 自带的触摸键盘处理输入
+    if core::system_info::SystemInfo::check_pe_environment() {
+        ui::touch::detect();
+    }
+
     // 加载图标
     log::info!("加载图标...");
     let icon = load_icon();
 
     // 设置窗口选项
     log::info!("创建窗口选项...");
+    let zoom = utils::dpi::init(core::system_info::SystemInfo::check_pe_environment());
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(utils::dpi::scaled_size(core::window_state::DEFAULT_WINDOW_SIZE))
+        .with_min_inner_size(utils::dpi::scaled_size(core::window_state::MIN_WINDOW_SIZE))
+        .with_icon(icon);
+
+    // 恢复上次在当前显示器布局下的窗口状态
+    let monitors = core::window_state::enumerate_monitors();
+    let layout_key = core::window_state::layout_key(&monitors);
+    if let Some(state) = core::app_config::AppConfig::load().window_states.get(&layout_key) {
+        let state = state.zoomed(zoom).clamp_to(&monitors);
+        log::info!("恢复窗口状态: {:?} (布局: {})", state, layout_key);
+        viewport = viewport
+            .with_position([state.x, state.y])
+            .with_inner_size([state.width, state.height])
+            .with_maximized(state.maximized);
+    }
+
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([950.0, 680.0])
-            .with_min_inner_size([800.0, 600.0])
-            .with_icon(icon),
+        viewport,
         ..Default::default()
     };
 
@@ -152,7 +186,7 @@ fn main() -> eframe::Result<()> {
 }
 
 /// 预加载所有配置和系统信息
-fn preload_all_config() -> PreloadedConfig {
+fn preload_all_config(mut diagnostics: Vec<core::diagnostics::DiagnosticItem>) -> PreloadedConfig {
     use std::time::{Duration, Instant};
     
     // 只等待远程配置和分区信息（这两个比较快且重要）
@@ -193,6 +227,9 @@ fn preload_all_config() -> PreloadedConfig {
     let partitions = partitions_handle.join().ok().unwrap_or_default();
 
     log::info!("预加载完成，耗时: {:?}", start.elapsed());
+
+    // 启动时已请求过配置服务器，直接用结果判断连通性
+    diagnostics.push(core::diagnostics::network_item_from(remote_config.as_ref()));
     
     // 系统信息和硬件信息不在这里等待，改为在 App 中异步加载
     PreloadedConfig {
@@ -200,6 +237,7 @@ fn preload_all_config() -> PreloadedConfig {
         system_info: None,      // 稍后异步加载
         hardware_info: None,    // 稍后异步加载
         partitions,
+        diagnostics,
     }
 }
 
@@ -222,103 +260,41 @@ fn load_icon() -> egui::IconData {
     egui::IconData::default()
 }
 
-/// 检查程序依赖文件完整性
-/// 返回 Ok(()) 表示所有文件存在，Err(Vec<String>) 包含缺失的文件列表
-fn check_dependencies() -> Result<(), Vec<String>> {
-    let exe_dir = utils::path::get_exe_dir();
-    
-    // 必需的依赖文件列表
-    let required_files = [
-        // bin 目录 - 核心工具
-        "bin/bcdedit.exe",
-        "bin/bcdboot.exe",
-        "bin/bootsect.exe",
-        "bin/format.com",
-        "bin/aria2c.exe",
-        "bin/ghost/ghost64.exe",
-    ];
-    
-    let mut missing_files = Vec::new();
-    
-    for file in &required_files {
-        let file_path = exe_dir.join(file);
-        if !file_path.exists() {
-            log::warn!("依赖文件缺失: {}", file);
-            missing_files.push(file.to_string());
-        }
-    }
-    
-    if missing_files.is_empty() {
-        Ok(())
-    } else {
-        Err(missing_files)
-    }
-}
-
-/// 检查系统核心组件完整性（用于检测极限精简系统）
-/// 返回 Ok(()) 表示所有组件存在，Err(Vec<String>) 包含缺失的组件列表
-fn check_system_components() -> Result<(), Vec<String>> {
-    // 获取系统盘路径 (通过 SYSTEMROOT 环境变量，通常为 C:\Windows)
-    let system_root = std::env::var("SYSTEMROOT")
-        .or_else(|_| std::env::var("WINDIR"))
-        .unwrap_or_else(|_| "C:\\Windows".to_string());
-    
-    let system32_path = std::path::Path::new(&system_root).join("System32");
-    
-    // 必需的系统组件列表
-    let required_components = [
-        ("diskpart.exe", "磁盘分区工具"),
-        ("wimgapi.dll", "WIM 镜像处理库"),
-        ("advapi32.dll", "高级 Windows API 库"),
-    ];
-    
-    let mut missing_components = Vec::new();
-    
-    for (file, description) in &required_components {
-        let file_path = system32_path.join(file);
-        if !file_path.exists() {
-            log::warn!("系统组件缺失: {} ({})", file, description);
-            missing_components.push(format!("{} - {}", file, description));
-        }
-    }
-    
-    if missing_components.is_empty() {
-        Ok(())
-    } else {
-        Err(missing_components)
-    }
-}
-
 /// PE环境下自动执行安装
-fn run_pe_install() -> eframe::Result<()> {
+fn run_pe_install() -> ExitCode {
     use core::install_config::ConfigFileManager;
     
-    println!("[PE INSTALL] ========== PE自动安装模式 ==========");
+    cli_println!("[PE INSTALL] ========== PE自动安装模式 ==========");
+    json_progress::emit(ProgressEvent::Start { operation: "install" });
+    if core::demo::is_enabled() {
+        cli_eprintln!("[PE INSTALL] 错误: 演示模式下不能执行自动安装");
+        return ExitCode::Failed;
+    }
     
     // 查找配置文件所在分区
     let data_partition = match ConfigFileManager::find_data_partition() {
         Some(p) => p,
         None => {
-            eprintln!("[PE INSTALL] 错误: 未找到安装配置文件");
+            cli_eprintln!("[PE INSTALL] 错误: 未找到安装配置文件");
             show_error_message("未找到安装配置文件，无法继续安装。");
-            return Ok(());
+            return ExitCode::ConfigMissing;
         }
     };
     
-    println!("[PE INSTALL] 数据分区: {}", data_partition);
+    cli_println!("[PE INSTALL] 数据分区: {}", data_partition);
     
     // 读取安装配置
-    let config = match ConfigFileManager::read_install_config(&data_partition) {
+    let mut config = match ConfigFileManager::read_install_config(&data_partition) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("[PE INSTALL] 错误: 读取配置失败: {}", e);
-            show_error_message(&format!("读取安装配置失败: {}", e));
-            return Ok(());
+            cli_eprintln!("[PE INSTALL] 错误: 读取配置失败: {:#}", e);
+            show_error_message(&format!("读取安装配置失败: {:#}", e));
+            return ExitCode::ConfigMissing;
         }
     };
     
-    println!("[PE INSTALL] 目标分区: {}", config.target_partition);
-    println!("[PE INSTALL] 镜像文件: {}", config.image_path);
+    cli_println!("[PE INSTALL] 目标分区: {}", config.target_partition);
+    cli_println!("[PE INSTALL] 镜像文件: {}", config.image_path);
     
     // 查找安装标记分区
     let target_partition = match ConfigFileManager::find_install_marker_partition() {
@@ -328,73 +304,112 @@ fn run_pe_install() -> eframe::Result<()> {
     
     // 构建完整镜像路径
     let data_dir = ConfigFileManager::get_data_dir(&data_partition);
-    let image_path = format!("{}\\{}", data_dir, config.image_path);
+    // 主程序暂存的 BitLocker 一次性密钥，安装流程结束时（包括失败）删除
+    let auto_unlock_keys = core::auto_unlock::pending_keys(&data_dir);
+    // 主程序单独保存的共享密码等凭据，读取后立即删除
+    let secrets = core::install_secrets::InstallSecrets::take(&data_dir);
+    config.unattend.password = secrets.unattend_password.clone();
+    // 主程序携带的 Wi-Fi 配置（含明文密码），此安装流程不导入，直接删除
+    drop(core::secure_dir::SensitiveDir::existing(
+        std::path::Path::new(&data_dir).join(core::wifi_profile::WIFI_PROFILE_DIR),
+    ));
+
+    // 网络镜像源：连接 SMB 共享或下载到数据目录
+    let image_path = match config.network_source() {
+        Some(source) => {
+            cli_println!("[PE INSTALL] 网络镜像源: {}", config.image_source);
+            let on_progress = |percentage: u8| {
+                json_progress::emit(ProgressEvent::Progress { percentage, status: "下载网络镜像".to_string() });
+            };
+            match core::network_source::prepare(&source, &config.share_user, &secrets.share_password, &data_dir, on_progress) {
+                Ok(path) => path,
+                Err(e) => {
+                    cli_eprintln!("[PE INSTALL] 错误: 准备网络镜像失败: {:#}", e);
+                    show_error_message(&format!("准备网络镜像失败: {:#}", e));
+                    return ExitCode::ImageInvalid;
+                }
+            }
+        }
+        None => format!("{}\\{}", data_dir, config.image_path),
+    };
     
     if !std::path::Path::new(&image_path).exists() {
-        eprintln!("[PE INSTALL] 错误: 镜像文件不存在: {}", image_path);
+        cli_eprintln!("[PE INSTALL] 错误: 镜像文件不存在: {}", image_path);
         show_error_message(&format!("镜像文件不存在: {}", image_path));
-        return Ok(());
+        return ExitCode::ImageInvalid;
     }
     
-    println!("[PE INSTALL] 完整镜像路径: {}", image_path);
+    cli_println!("[PE INSTALL] 完整镜像路径: {}", image_path);
     
     // 执行安装
     let result = execute_pe_install(&target_partition, &image_path, &config, &data_dir);
+    drop(auto_unlock_keys);
     
     // 清理标记文件
     ConfigFileManager::cleanup_partition_markers(&target_partition);
     
     match result {
         Ok(_) => {
-            println!("[PE INSTALL] 安装完成!");
-            if config.auto_reboot {
-                println!("[PE INSTALL] 即将重启...");
-                let _ = utils::cmd::create_command("shutdown")
-                    .args(["/r", "/t", "10", "/c", "LetRecovery 系统安装完成，即将重启..."])
-                    .spawn();
-            } else {
+            cli_println!("[PE INSTALL] 安装完成!");
+            let action = config.completion_action();
+            if action == CompletionAction::StayInPe {
                 show_success_message("系统安装完成！请手动重启计算机。");
+            } else {
+                cli_println!("[PE INSTALL] 即将{}...", action.verb());
+                json_progress::emit(ProgressEvent::Finished {
+                    success: true,
+                    message: format!("系统安装完成，即将{}", action.verb()),
+                });
+                let delay = if action == CompletionAction::RebootNow { 0 } else { 10 };
+                if let Err(e) = action.execute(delay, "LetRecovery 系统安装完成") {
+                    cli_eprintln!("[PE INSTALL] {:#}", e);
+                }
             }
+            ExitCode::Success
         }
-        Err(e) => {
-            eprintln!("[PE INSTALL] 安装失败: {}", e);
-            show_error_message(&format!("系统安装失败: {}", e));
+        Err(failure) => {
+            cli_eprintln!("[PE INSTALL] 安装失败: {}", failure);
+            show_error_message(&format!("系统安装失败: {}", failure));
+            failure.code
         }
     }
-    
-    Ok(())
 }
 
 /// PE环境下自动执行备份
-fn run_pe_backup() -> eframe::Result<()> {
+fn run_pe_backup() -> ExitCode {
     use core::install_config::ConfigFileManager;
     
-    println!("[PE BACKUP] ========== PE自动备份模式 ==========");
+    cli_println!("[PE BACKUP] ========== PE自动备份模式 ==========");
+    json_progress::emit(ProgressEvent::Start { operation: "backup" });
+    if core::demo::is_enabled() {
+        cli_eprintln!("[PE BACKUP] 错误: 演示模式下不能执行自动备份");
+        return ExitCode::Failed;
+    }
     
     // 查找配置文件所在分区
     let data_partition = match ConfigFileManager::find_data_partition() {
         Some(p) => p,
         None => {
-            eprintln!("[PE BACKUP] 错误: 未找到备份配置文件");
+            cli_eprintln!("[PE BACKUP] 错误: 未找到备份配置文件");
             show_error_message("未找到备份配置文件，无法继续备份。");
-            return Ok(());
+            return ExitCode::ConfigMissing;
         }
     };
     
-    println!("[PE BACKUP] 数据分区: {}", data_partition);
+    cli_println!("[PE BACKUP] 数据分区: {}", data_partition);
     
     // 读取备份配置
     let config = match ConfigFileManager::read_backup_config(&data_partition) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("[PE BACKUP] 错误: 读取配置失败: {}", e);
-            show_error_message(&format!("读取备份配置失败: {}", e));
-            return Ok(());
+            cli_eprintln!("[PE BACKUP] 错误: 读取配置失败: {:#}", e);
+            show_error_message(&format!("读取备份配置失败: {:#}", e));
+            return ExitCode::ConfigMissing;
         }
     };
     
-    println!("[PE BACKUP] 源分区: {}", config.source_partition);
-    println!("[PE BACKUP] 保存路径: {}", config.save_path);
+    cli_println!("[PE BACKUP] 源分区: {}", config.source_partition);
+    cli_println!("[PE BACKUP] 保存路径: {}", config.save_path);
     
     // 查找备份标记分区
     let source_partition = match ConfigFileManager::find_backup_marker_partition() {
@@ -410,102 +425,643 @@ fn run_pe_backup() -> eframe::Result<()> {
     
     match result {
         Ok(_) => {
-            println!("[PE BACKUP] 备份完成!");
+            cli_println!("[PE BACKUP] 备份完成!");
             show_success_message(&format!("系统备份完成！\n保存位置: {}", config.save_path));
+            ExitCode::Success
         }
         Err(e) => {
-            eprintln!("[PE BACKUP] 备份失败: {}", e);
+            cli_eprintln!("[PE BACKUP] 备份失败: {}", e);
             show_error_message(&format!("系统备份失败: {}", e));
+            ExitCode::BackupFailed
         }
     }
-    
+}
+
+/// PE备份步骤总数
+const BACKUP_STEP_COUNT: u8 = 2;
+
+/// 输出命令行模式的步骤信息
+fn cli_step(tag: &str, step: u8, total: u8, name: &str) {
+    cli_println!("{} Step {}: {}", tag, step, name);
+    json_progress::emit(ProgressEvent::Step { step, total, name: name.to_string() });
+}
+
+/// 命令行校验镜像完整性
+fn run_verify(image_path: &str) -> ExitCode {
+    use core::image_verify::{ImageVerifier, VerifyProgress, VerifyStatus};
+
+    cli_println!("[VERIFY] 校验镜像: {}", image_path);
+    json_progress::emit(ProgressEvent::Start { operation: "verify" });
+
+    let progress_tx = json_progress::forward_progress(|p: VerifyProgress| {
+        let status = if p.current_item.is_empty() {
+            p.status
+        } else {
+            format!("{} {}", p.status, p.current_item)
+        };
+        (p.percentage, status)
+    });
+    let result = ImageVerifier::new().verify(image_path, progress_tx);
+
+    cli_println!("[VERIFY] {}: {}", result.status, result.message);
+    for detail in &result.details {
+        cli_println!("[VERIFY]   {}", detail);
+    }
+    json_progress::emit(ProgressEvent::Finished {
+        success: result.status == VerifyStatus::Valid,
+        message: format!("{}: {}", result.status, result.message),
+    });
+
+    match result.status {
+        VerifyStatus::Valid => ExitCode::Success,
+        VerifyStatus::Cancelled => ExitCode::Cancelled,
+        VerifyStatus::Corrupted | VerifyStatus::NotFound | VerifyStatus::Unsupported => ExitCode::ImageInvalid,
+        VerifyStatus::Error => ExitCode::Failed,
+    }
+}
+
+/// 命令行子命令
+fn run_cli(invocation: Result<utils::cli::Invocation, String>) -> ExitCode {
+    use utils::cli::{self, Command};
+
+    let invocation = match invocation {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::usage("LetRecovery.exe"));
+            return ExitCode::InvalidArguments;
+        }
+    };
+    if invocation.json {
+        json_progress::enable();
+    }
+
+    match invocation.command {
+        Command::Help => {
+            println!("{}", cli::usage("LetRecovery.exe"));
+            ExitCode::Success
+        }
+        Command::ListPartitions => run_list_partitions(invocation.json),
+        Command::ListImages { image } => run_list_images(&image, invocation.json),
+        Command::Verify { image } => run_verify(&image),
+        Command::Install(args) => run_cli_install(&args),
+        Command::Backup(args) => run_cli_backup(&args),
+    }
+}
+
+/// 命令行子命令失败：输出错误并返回退出码
+fn cli_fail(tag: &str, code: ExitCode, message: String) -> ExitCode {
+    cli_eprintln!("{} {}", tag, message);
+    json_progress::emit(ProgressEvent::Finished { success: false, message });
+    code
+}
+
+/// 检查分区是否可以在命令行中安装或备份（不能是正在运行的系统分区）
+fn check_cli_partition(partition: &str) -> Result<(), String> {
+    if core::demo::is_enabled() {
+        return Err("演示模式下不能在命令行中安装或备份".to_string());
+    }
+    if !utils::privilege::is_admin() {
+        return Err("需要以管理员身份运行".to_string());
+    }
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+    if !core::disk::DiskManager::is_pe_environment() && partition.eq_ignore_ascii_case(&system_drive) {
+        return Err(format!("{} 是正在运行的系统分区，请在 PE 中执行", partition));
+    }
     Ok(())
 }
 
+/// 命令行安装系统
+fn run_cli_install(args: &utils::cli::InstallArgs) -> ExitCode {
+    const TAG: &str = "[CLI INSTALL]";
+
+    cli_println!("{} {} (分卷 {}) -> {}", TAG, args.image, args.index, args.target);
+    json_progress::emit(ProgressEvent::Start { operation: "install" });
+
+    if let Err(e) = check_cli_partition(&args.target) {
+        return cli_fail(TAG, ExitCode::Failed, e);
+    }
+    if !std::path::Path::new(&args.image).exists() {
+        return cli_fail(TAG, ExitCode::ImageInvalid, format!("镜像文件不存在: {}", args.image));
+    }
+
+    let lower = args.image.to_lowercase();
+    let is_gho = lower.ends_with(".gho") || lower.ends_with(".ghs");
+    let action = if args.no_reboot {
+        CompletionAction::StayInPe
+    } else {
+        CompletionAction::RebootCountdown
+    };
+    let config = core::install_config::InstallConfig {
+        target_partition: args.target.clone(),
+        image_path: args.image.clone(),
+        volume_index: args.index,
+        is_gho,
+        gho_partition: args.index,
+        image_backend: core::app_config::AppConfig::load().image_backend,
+        completion_action: Some(action),
+        ..Default::default()
+    };
+
+    // 安装结束时会删除数据目录，使用单独的临时目录
+    let data_dir = std::env::temp_dir().join(format!("LetRecovery_CLI_{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir_all(&data_dir) {
+        return cli_fail(TAG, ExitCode::Failed, format!("创建临时目录失败: {}", e));
+    }
+    let data_dir = data_dir.to_string_lossy().to_string();
+
+    match execute_pe_install(&args.target, &args.image, &config, &data_dir) {
+        Ok(_) => {
+            let message = if action == CompletionAction::StayInPe {
+                "系统安装完成，请手动重启计算机".to_string()
+            } else {
+                format!("系统安装完成，即将{}", action.verb())
+            };
+            cli_println!("{} {}", TAG, message);
+            json_progress::emit(ProgressEvent::Finished { success: true, message });
+            if let Err(e) = action.execute(10, "LetRecovery 系统安装完成") {
+                cli_eprintln!("{} {:#}", TAG, e);
+            }
+            ExitCode::Success
+        }
+        Err(failure) => cli_fail(TAG, failure.code, format!("系统安装失败: {}", failure)),
+    }
+}
+
+/// 命令行备份分区
+fn run_cli_backup(args: &utils::cli::BackupArgs) -> ExitCode {
+    const TAG: &str = "[CLI BACKUP]";
+
+    cli_println!("{} {} -> {}", TAG, args.source, args.output);
+    json_progress::emit(ProgressEvent::Start { operation: "backup" });
+
+    if let Err(e) = check_cli_partition(&args.source) {
+        return cli_fail(TAG, ExitCode::Failed, e);
+    }
+    if let Some(parent) = std::path::Path::new(&args.output).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            return cli_fail(TAG, ExitCode::BackupFailed, format!("创建备份目录失败: {}", e));
+        }
+    }
+
+    let name = if args.name.trim().is_empty() {
+        core::backup_catalog::default_name(&args.source)
+    } else {
+        args.name.clone()
+    };
+    let config = core::install_config::BackupConfig {
+        save_path: args.output.clone(),
+        name,
+        description: args.description.clone(),
+        source_partition: args.source.clone(),
+        incremental: args.append,
+        image_backend: core::app_config::AppConfig::load().image_backend,
+        ..Default::default()
+    };
+
+    match execute_pe_backup(&args.source, &config) {
+        Ok(_) => {
+            let message = format!("系统备份完成，保存位置: {}", args.output);
+            cli_println!("{} {}", TAG, message);
+            json_progress::emit(ProgressEvent::Finished { success: true, message });
+            ExitCode::Success
+        }
+        Err(e) => cli_fail(TAG, ExitCode::BackupFailed, format!("系统备份失败: {:#}", e)),
+    }
+}
+
+/// 列出分区
+fn run_list_partitions(json: bool) -> ExitCode {
+    use utils::cli::PartitionRow;
+
+    let rows: Vec<PartitionRow> = core::disk::DiskManager::get_partitions()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| PartitionRow {
+            letter: p.letter,
+            label: p.label,
+            total_mb: p.total_size_mb,
+            free_mb: p.free_size_mb,
+            partition_style: p.partition_style.to_string(),
+            disk_number: p.disk_number,
+            partition_number: p.partition_number,
+            is_system: p.is_system_partition,
+            has_windows: p.has_windows,
+        })
+        .collect();
+
+    if json {
+        utils::cli::print_json(&rows);
+    } else {
+        let table: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                let mut flags = Vec::new();
+                if row.is_system {
+                    flags.push("系统");
+                }
+                if row.has_windows {
+                    flags.push("Windows");
+                }
+                vec![
+                    row.letter.clone(),
+                    row.label.clone(),
+                    format!("{:.1} GB", row.total_mb as f64 / 1024.0),
+                    format!("{:.1} GB", row.free_mb as f64 / 1024.0),
+                    row.partition_style.clone(),
+                    match (row.disk_number, row.partition_number) {
+                        (Some(disk), Some(partition)) => format!("{}:{}", disk, partition),
+                        _ => "-".to_string(),
+                    },
+                    flags.join(","),
+                ]
+            })
+            .collect();
+        println!(
+            "{}",
+            utils::cli::format_table(&["盘符", "卷标", "总容量", "可用", "分区表", "磁盘:分区", "备注"], &table)
+        );
+    }
+    ExitCode::Success
+}
+
+/// 列出镜像中的分卷
+fn run_list_images(image_path: &str, json: bool) -> ExitCode {
+    use utils::cli::ImageRow;
+
+    let entries = match core::wim_manage::list_images(image_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            cli_eprintln!("[CLI] 读取镜像失败: {:#}", e);
+            return ExitCode::ImageInvalid;
+        }
+    };
+    let rows: Vec<ImageRow> = entries
+        .into_iter()
+        .map(|entry| ImageRow {
+            index: entry.index,
+            name: entry.name,
+            description: entry.description,
+            size_bytes: entry.total_bytes,
+        })
+        .collect();
+
+    if json {
+        utils::cli::print_json(&rows);
+    } else {
+        let table: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                vec![
+                    row.index.to_string(),
+                    row.name.clone(),
+                    format!("{:.2} GB", row.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0),
+                    row.description.clone(),
+                ]
+            })
+            .collect();
+        println!("{}", utils::cli::format_table(&["分卷", "名称", "大小", "描述"], &table));
+    }
+    ExitCode::Success
+}
+
+/// 定时备份模式（由计划任务以 SYSTEM 身份启动，不显示任何窗口）
+fn run_scheduled_backup() -> ExitCode {
+    if core::demo::is_enabled() {
+        cli_eprintln!("[SCHEDULED BACKUP] 演示模式下不能执行定时备份");
+        return ExitCode::Failed;
+    }
+    if !utils::privilege::is_admin() {
+        cli_eprintln!("[SCHEDULED BACKUP] 定时备份需要管理员权限");
+        return ExitCode::Failed;
+    }
+
+    // 只使用注册计划任务时写入受保护目录的参数，不读取程序目录中的 config.json
+    let task = match core::scheduler::ScheduledTask::load() {
+        Ok(task) => task,
+        Err(e) => {
+            cli_eprintln!("[SCHEDULED BACKUP] {:#}", e);
+            return ExitCode::ConfigMissing;
+        }
+    };
+    let schedule = &task.schedule;
+    if !schedule.enabled {
+        cli_println!("[SCHEDULED BACKUP] 定时备份未启用，跳过");
+        return ExitCode::Success;
+    }
+
+    cli_println!(
+        "[SCHEDULED BACKUP] 备份 {} -> {}",
+        schedule.source_partition,
+        schedule.image_path
+    );
+    json_progress::emit(ProgressEvent::Start { operation: "backup" });
+    let result = core::scheduler::run_scheduled_backup(
+        schedule,
+        task.image_backend,
+        task.io_priority,
+        json_progress::dism_progress(),
+    );
+    core::scheduler::save_status(&result);
+
+    match result {
+        Ok(message) => {
+            cli_println!("[SCHEDULED BACKUP] {}", message);
+            json_progress::emit(ProgressEvent::Finished { success: true, message });
+            ExitCode::Success
+        }
+        Err(e) => {
+            cli_eprintln!("[SCHEDULED BACKUP] 备份失败: {:#}", e);
+            json_progress::emit(ProgressEvent::Finished {
+                success: false,
+                message: format!("{:#}", e),
+            });
+            ExitCode::BackupFailed
+        }
+    }
+}
+
 /// 执行PE安装
+///
+/// 通过安装任务引擎依次执行各步骤；修复引导失败时恢复修复前的 BCD，保留原有引导项
 fn execute_pe_install(
     target_partition: &str,
     image_path: &str,
     config: &core::install_config::InstallConfig,
     data_dir: &str,
-) -> anyhow::Result<()> {
+) -> Result<(), CliFailure> {
     use anyhow::Context;
-    
-    println!("[PE INSTALL] Step 1: 格式化分区");
-    // 格式化目标分区
-    let output = utils::cmd::create_command("cmd")
-        .args(["/c", &format!("format {} /FS:NTFS /Q /Y", target_partition)])
-        .output()
-        .context("执行格式化命令失败")?;
-    
-    if !output.status.success() {
-        let stderr = utils::encoding::gbk_to_utf8(&output.stderr);
-        anyhow::bail!("格式化分区失败: {}", stderr);
-    }
-    
-    println!("[PE INSTALL] Step 2: 释放镜像");
-    // 释放镜像
+    use core::install_engine::{InstallEngine, RetryPolicy, Step};
+    use std::cell::RefCell;
+    use std::time::Duration;
+
     let apply_dir = format!("{}\\", target_partition);
-    
-    if config.is_gho {
-        // GHO镜像使用Ghost
-        let ghost = core::ghost::Ghost::new();
-        if !ghost.is_available() {
-            anyhow::bail!("Ghost工具不可用");
+    let harvested_dir = format!("{}\\harvested_drivers", data_dir);
+    // zstd 驱动包的解压目录（位于目标分区，清理步骤中删除）
+    let pack_staging = std::path::PathBuf::from(format!("{}\\LetRecovery_Packs", target_partition));
+    let boot_manager = core::bcdedit::BootManager::new();
+    // 修复引导前的 BCD 备份（回滚时恢复）
+    let bcd_backup = RefCell::new(None);
+
+    let engine = InstallEngine::new()
+        .step(
+            // 格式化前从目标分区的现有系统导出第三方驱动
+            Step::new("harvest", "导出现有系统驱动", |_| {
+                if config.harvest_drivers {
+                    let count = core::dism::Dism::new().harvest_drivers(target_partition, &harvested_dir)?;
+                    cli_println!("[PE INSTALL] 已从现有系统导出 {} 个驱动包", count);
+                }
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            // 固件为 UEFI 而目标磁盘为 MBR 时，格式化前用 mbr2gpt 无损转换（需原系统可引导）
+            Step::new("convert", "转换分区表", |ctx| {
+                let plan = core::disk::DiskManager::plan_for_firmware(target_partition, detect_uefi_mode())?;
+                if plan.conversion == core::disk::DiskConversion::None {
+                    return Ok(());
+                }
+                ctx.status(plan.describe());
+                core::disk::DiskManager::plan_and_apply(&plan)?;
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            Step::new("format", "格式化分区", |_| {
+                let output = utils::cmd::create_command("cmd")
+                    .args(["/c", &format!("format {} /FS:NTFS /Q /Y", target_partition)])
+                    .output()
+                    .context("执行格式化命令失败")?;
+                if !output.status.success() {
+                    let stderr = utils::encoding::gbk_to_utf8(&output.stderr);
+                    anyhow::bail!("格式化分区失败: {}", stderr);
+                }
+                Ok(())
+            })
+            // 卷可能被短暂占用，稍后重试
+            .retry(RetryPolicy::times(3, Duration::from_secs(3))),
+        )
+        .step(
+            Step::new("apply", "释放镜像", |_| {
+                if config.is_gho {
+                    // GHO镜像使用Ghost
+                    let ghost = core::ghost::Ghost::new();
+                    if !ghost.is_available() {
+                        anyhow::bail!("Ghost工具不可用");
+                    }
+                    let partitions = core::disk::DiskManager::get_partitions().unwrap_or_default();
+                    ghost.restore_image_to_letter(image_path, config.gho_partition, target_partition, &partitions, json_progress::dism_progress())
+                } else {
+                    // WIM/ESD使用DISM
+                    core::dism::Dism::with_backend(config.image_backend)
+                        .apply_image(image_path, &apply_dir, config.volume_index, json_progress::dism_progress())
+                }
+            })
+            .after(&["format"]),
+        )
+        .step(
+            Step::new("verify", "校验已释放的文件", |ctx| {
+                if config.is_gho || config.apply_verify == core::apply_verify::ApplyVerifyMode::Off {
+                    return Ok(());
+                }
+                ctx.status(config.apply_verify.label());
+                let on_progress = |done: usize, total: usize| ctx.progress((done * 100 / total.max(1)) as u8);
+                match core::apply_verify::verify_applied_image(
+                    image_path,
+                    config.volume_index,
+                    &apply_dir,
+                    config.apply_verify,
+                    &on_progress,
+                ) {
+                    Ok(report) if report.is_ok() => {
+                        ctx.status(report.summary());
+                        Ok(())
+                    }
+                    Ok(report) => anyhow::bail!("{}", report.summary()),
+                    Err(e) => {
+                        ctx.warn(format!("无法进行释放后校验: {:#}", e));
+                        Ok(())
+                    }
+                }
+            })
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("drivers", "导入驱动", |_| {
+                let driver_path = format!("{}\\drivers", data_dir);
+                if config.should_import_drivers() && std::path::Path::new(&driver_path).exists() {
+                    for driver_dir in core::zst_pack::expand_packs(&driver_path, &pack_staging.join("drivers")) {
+                        core::dism::Dism::new().add_drivers_offline(&apply_dir, &driver_dir)?;
+                    }
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("hwid", "注入网卡/存储驱动", |_| {
+                let hwid_dir = format!("{}\\drivers\\{}", data_dir, core::hwid_drivers::HWID_DRIVERS_DIR);
+                // 自动导入模式下已随 drivers 目录一并导入
+                if config.hwid_drivers && !config.should_import_drivers() && std::path::Path::new(&hwid_dir).exists() {
+                    core::dism::Dism::new().add_drivers_offline(&apply_dir, &hwid_dir)?;
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["drivers"]),
+        )
+        .step(
+            Step::new("reinject", "重新注入现有系统驱动", |_| {
+                if config.harvest_drivers && std::path::Path::new(&harvested_dir).exists() {
+                    core::dism::Dism::new().add_drivers_offline(&apply_dir, &harvested_dir)?;
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["harvest", "apply"]),
+        )
+        .step(
+            Step::new("boot", "修复引导", |_| {
+                if bcd_backup.borrow().is_none() {
+                    match boot_manager.backup_bcd(None, "系统安装修复引导前") {
+                        Ok(backup) => *bcd_backup.borrow_mut() = Some(backup),
+                        Err(e) => log::warn!("修复引导前备份 BCD 失败: {:#}", e),
+                    }
+                }
+                boot_manager.repair_boot_advanced(target_partition, detect_uefi_mode())
+            })
+            .retry(RetryPolicy::times(2, Duration::from_secs(2)))
+            .after(&["apply"])
+            .rollback(|| match bcd_backup.borrow().as_ref() {
+                Some(backup) => boot_manager.restore_bcd(backup),
+                None => Ok(()),
+            }),
+        )
+        .step(
+            Step::new("options", "应用高级选项", |ctx| {
+                let mut advanced_options = ui::advanced_options::AdvancedOptions::default();
+                advanced_options.remove_shortcut_arrow = config.remove_shortcut_arrow;
+                advanced_options.restore_classic_context_menu = config.restore_classic_context_menu;
+                advanced_options.bypass_nro = config.bypass_nro;
+                advanced_options.bypass_tpm_check = config.bypass_tpm_check;
+                advanced_options.bypass_secure_boot_check = config.bypass_secure_boot_check;
+                advanced_options.bypass_ram_check = config.bypass_ram_check;
+                advanced_options.disable_windows_update = config.disable_windows_update;
+                advanced_options.disable_windows_defender = config.disable_windows_defender;
+                advanced_options.disable_reserved_storage = config.disable_reserved_storage;
+                advanced_options.disable_uac = config.disable_uac;
+                advanced_options.disable_device_encryption = config.disable_device_encryption;
+                advanced_options.remove_uwp_apps = config.remove_uwp_apps;
+                advanced_options.import_storage_controller_drivers = config.import_storage_controller_drivers;
+                advanced_options.storage_driver_packs = config.storage_driver_packs.clone();
+                advanced_options.disable_driver_signature_once = config.disable_driver_signature_once;
+                advanced_options.ensure_trim = config.ensure_trim;
+                advanced_options.write_cache_policy = config.write_cache_policy;
+                advanced_options.custom_username = !config.custom_username.is_empty();
+                advanced_options.username = config.custom_username.clone();
+
+                if let Err(e) = advanced_options.apply_to_system(target_partition) {
+                    ctx.warn(format!("应用高级选项失败: {:#}", e));
+                }
+                match core::auto_unlock::install_pending(data_dir, target_partition) {
+                    Ok(true) => ctx.status("已安装 BitLocker 数据分区解锁脚本"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装 BitLocker 数据分区解锁脚本失败: {:#}", e)),
+                }
+
+                // 生成无人值守配置
+                if config.unattended {
+                    generate_unattend_xml_pe(target_partition, &config)
+                        .context("生成无人值守配置失败")?;
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["boot"]),
+        )
+        .step(
+            Step::new("hooks", "安装首次启动脚本", |ctx| {
+                let scripts_dir = std::path::Path::new(data_dir).join(core::script_hooks::SCRIPTS_DIR);
+                // 未配置时按文件名顺序执行 scripts 文件夹中的全部脚本
+                let hooks = if config.script_hooks.is_empty() {
+                    core::script_hooks::discover(&scripts_dir, &[])
+                } else {
+                    config.script_hooks.clone()
+                };
+                match core::script_hooks::install_hooks(target_partition, &scripts_dir, &hooks) {
+                    Ok(0) => {}
+                    Ok(count) => ctx.status(format!("已安装 {} 个首次启动脚本", count)),
+                    Err(e) => ctx.warn(format!("安装首次启动脚本失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("health", "安装首次启动健康检查", |ctx| {
+                // 报告写入数据目录所在分区（数据目录本身会在安装结束后删除）
+                let report_partition = data_dir.rsplit_once('\\').map(|(partition, _)| partition);
+                match core::health_check::install_agent(target_partition, report_partition, &config.health_check) {
+                    Ok(true) => ctx.status("已安装首次启动健康检查"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装首次启动健康检查失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("cleanup", "清理临时文件", |_| {
+                let _ = std::fs::remove_dir_all(&pack_staging);
+                std::fs::remove_dir_all(data_dir).context("删除数据目录失败")
+            })
+            .optional(),
+        );
+
+    engine.run(report_engine_event).map_err(|failure| {
+        if !failure.rolled_back {
+            cli_eprintln!("[PE INSTALL] 警告: 回滚未全部完成");
         }
-        
-        let partitions = core::disk::DiskManager::get_partitions().unwrap_or_default();
-        ghost.restore_image_to_letter(image_path, target_partition, &partitions, None)?;
-    } else {
-        // WIM/ESD使用DISM
-        let dism = core::dism::Dism::new();
-        dism.apply_image(image_path, &apply_dir, config.volume_index, None)?;
-    }
-    
-    println!("[PE INSTALL] Step 3: 导入驱动");
-    // 导入驱动
-    if config.restore_drivers {
-        let driver_path = format!("{}\\drivers", data_dir);
-        if std::path::Path::new(&driver_path).exists() {
-            let dism = core::dism::Dism::new();
-            let _ = dism.add_drivers_offline(&apply_dir, &driver_path);
+        let code = match failure.step {
+            "format" => ExitCode::FormatFailed,
+            "apply" => ExitCode::ApplyFailed,
+            "boot" => ExitCode::BootRepairFailed,
+            _ => ExitCode::Failed,
+        };
+        code.fail(failure.error)
+    })
+}
+
+/// 将安装引擎事件输出到命令行和 JSON 进度
+fn report_engine_event(event: core::install_engine::EngineEvent) {
+    use core::install_engine::EngineEvent;
+
+    let warning = match event {
+        EngineEvent::StepStarted { index, total, name, .. } => {
+            cli_step("[PE INSTALL]", index as u8 + 1, total as u8, name);
+            return;
         }
-    }
-    
-    println!("[PE INSTALL] Step 4: 修复引导");
-    // 修复引导
-    let boot_manager = core::bcdedit::BootManager::new();
-    let use_uefi = detect_uefi_mode();
-    boot_manager.repair_boot_advanced(target_partition, use_uefi)?;
-    
-    println!("[PE INSTALL] Step 5: 应用高级选项");
-    // 应用高级选项
-    let mut advanced_options = ui::advanced_options::AdvancedOptions::default();
-    advanced_options.remove_shortcut_arrow = config.remove_shortcut_arrow;
-    advanced_options.restore_classic_context_menu = config.restore_classic_context_menu;
-    advanced_options.bypass_nro = config.bypass_nro;
-    advanced_options.disable_windows_update = config.disable_windows_update;
-    advanced_options.disable_windows_defender = config.disable_windows_defender;
-    advanced_options.disable_reserved_storage = config.disable_reserved_storage;
-    advanced_options.disable_uac = config.disable_uac;
-    advanced_options.disable_device_encryption = config.disable_device_encryption;
-    advanced_options.remove_uwp_apps = config.remove_uwp_apps;
-    advanced_options.import_storage_controller_drivers = config.import_storage_controller_drivers;
-    advanced_options.custom_username = !config.custom_username.is_empty();
-    advanced_options.username = config.custom_username.clone();
-    
-    let _ = advanced_options.apply_to_system(target_partition);
-    
-    // 生成无人值守配置
-    if config.unattended {
-        let _ = generate_unattend_xml_pe(target_partition, &config.custom_username);
-    }
-    
-    println!("[PE INSTALL] Step 6: 清理临时文件");
-    // 清理数据目录
-    let _ = std::fs::remove_dir_all(data_dir);
-    
-    Ok(())
+        EngineEvent::Status(status) => {
+            cli_println!("[PE INSTALL] {}", status);
+            json_progress::emit(ProgressEvent::Message { message: status });
+            return;
+        }
+        // 进度由各步骤的 DISM/Ghost 进度通道直接输出
+        EngineEvent::Progress(_) => return,
+        EngineEvent::Retrying { name, attempt, max_attempts, error } => {
+            format!("{}失败（第 {}/{} 次），稍后重试: {}", name, attempt, max_attempts, error)
+        }
+        EngineEvent::StepSkipped { name, reason } => format!("跳过{}: {}", name, reason),
+        EngineEvent::Warning { message, .. } => message,
+        EngineEvent::RollingBack { name } => format!("正在回滚: {}", name),
+        EngineEvent::RollbackFailed { name, error } => format!("回滚{}失败: {}", name, error),
+    };
+    cli_println!("[PE INSTALL] {}", warning);
+    json_progress::emit(ProgressEvent::Warning { message: warning });
 }
 
 /// 执行PE备份
@@ -513,26 +1069,50 @@ fn execute_pe_backup(
     source_partition: &str,
     config: &core::install_config::BackupConfig,
 ) -> anyhow::Result<()> {
-    let dism = core::dism::Dism::new();
+    let dism = core::dism::Dism::with_backend(config.image_backend);
     let capture_dir = format!("{}\\", source_partition);
+    let size_before = core::backup_catalog::image_size(&config.save_path);
     
+    cli_step("[PE BACKUP]", 1, BACKUP_STEP_COUNT, "捕获镜像");
     if config.incremental && std::path::Path::new(&config.save_path).exists() {
         dism.append_image(
             &config.save_path,
             &capture_dir,
             &config.name,
             &config.description,
-            None,
-        )
+            &config.capture,
+            json_progress::dism_progress(),
+        )?;
     } else {
         dism.capture_image(
             &config.save_path,
             &capture_dir,
             &config.name,
             &config.description,
-            None,
-        )
+            &config.capture,
+            json_progress::dism_progress(),
+        )?;
+    }
+
+    cli_step("[PE BACKUP]", 2, BACKUP_STEP_COUNT, "验证备份文件");
+    let size = std::fs::metadata(&config.save_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    if size == 0 {
+        anyhow::bail!("备份文件不存在或为空: {}", config.save_path);
     }
+
+    if let Err(e) = core::backup_catalog::record_backup(
+        &config.save_path,
+        source_partition,
+        &config.name,
+        &config.description,
+        size_before,
+    ) {
+        log::warn!("[PE BACKUP] 记录还原点失败: {:#}", e);
+    }
+
+    Ok(())
 }
 
 /// 检测UEFI模式（使用 Windows API）
@@ -592,107 +1172,33 @@ fn detect_uefi_mode() -> bool {
 }
 
 /// 生成无人值守XML (PE版本)
-fn generate_unattend_xml_pe(target_partition: &str, username: &str) -> anyhow::Result<()> {
-    use crate::core::system_utils::{get_file_version, get_system_architecture};
-    use std::path::Path;
+fn generate_unattend_xml_pe(target_partition: &str, config: &core::install_config::InstallConfig) -> anyhow::Result<()> {
+    use crate::core::image_language::TargetLanguage;
+    use crate::core::system_utils::get_system_architecture;
+    use crate::core::unattend::{write_unattend, UnattendBuilder, UnattendTarget};
     
-    let username = if username.is_empty() { "User" } else { username };
-    
-    // 检测目标系统架构
     let arch = get_system_architecture(target_partition);
-    let arch_str = arch.as_unattend_str();
-    
-    // 通过 ntdll.dll 文件版本检测目标系统版本
-    let ntdll_path = Path::new(target_partition).join("Windows").join("System32").join("ntdll.dll");
-    let (is_win7, is_win8) = match get_file_version(&ntdll_path) {
-        Some((major, minor, _, _)) => {
-            let is_win7 = major == 6 && minor == 1;
-            let is_win8 = major == 6 && (minor == 2 || minor == 3);
-            (is_win7, is_win8)
-        }
-        None => (false, false)
-    };
-    
-    // 根据系统版本生成不同的OOBE配置
-    // Win7: 移除HideOEMRegistrationScreen（家庭版不支持）
-    let oobe_section = if is_win7 {
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>"#
-    } else if is_win8 {
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>"#
-    } else {
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <HideOnlineAccountScreens>true</HideOnlineAccountScreens>
-                <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>
-                <ProtectYourPC>3</ProtectYourPC>
-                <SkipMachineOOBE>true</SkipMachineOOBE>
-                <SkipUserOOBE>true</SkipUserOOBE>
-            </OOBE>"#
-    };
-    
-    let xml_content = format!(r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            {oobe}
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{user}</DisplayName>
-                        <Group>Administrators</Group>
-                        <n>{user}</n>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <Username>{user}</Username>
-            </AutoLogon>
-        </component>
-    </settings>
-</unattend>"#, arch = arch_str, oobe = oobe_section, user = username);
-
-    let panther_dir = format!("{}\\Windows\\Panther", target_partition);
-    std::fs::create_dir_all(&panther_dir)?;
-    
-    let unattend_path = format!("{}\\unattend.xml", panther_dir);
-    std::fs::write(&unattend_path, &xml_content)?;
-    
-    Ok(())
+    let xml = UnattendBuilder::new(arch.as_unattend_str(), UnattendTarget::detect(target_partition))
+        .username(&config.custom_username)
+        .settings(&config.unattend)
+        .win11_bypass(config.win11_bypass())
+        .language(TargetLanguage::detect(target_partition))
+        .build();
+    write_unattend(target_partition, &xml)
 }
 
 
 /// 显示错误消息框
+///
+/// JSON 进度模式下不弹出消息框，改为输出失败事件
 fn show_error_message(message: &str) {
+    log::error!("{}", message);
+    if json_progress::is_enabled() {
+        eprintln!("错误: {}", message);
+        json_progress::emit(ProgressEvent::Finished { success: false, message: message.to_string() });
+        return;
+    }
+
     #[cfg(windows)]
     {
         use std::ffi::OsStr;
@@ -724,7 +1230,16 @@ fn show_error_message(message: &str) {
 }
 
 /// 显示成功消息框
+///
+/// JSON 进度模式下不弹出消息框，改为输出成功事件
 fn show_success_message(message: &str) {
+    log::info!("{}", message);
+    if json_progress::is_enabled() {
+        eprintln!("成功: {}", message);
+        json_progress::emit(ProgressEvent::Finished { success: true, message: message.to_string() });
+        return;
+    }
+
     #[cfg(windows)]
     {
         use std::ffi::OsStr;
@@ -0,0 +1,71 @@
+//! 无障碍辅助
+//! 对话框键盘操作（Enter 确认 / Escape 关闭）及读屏标签
+
+use egui;
+
+/// 当前帧对话框按键
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DialogKeys {
+    /// 按下 Enter（无修饰键）
+    pub enter: bool,
+    /// 按下 Escape
+    pub escape: bool,
+}
+
+/// 读取并消费对话框按键
+///
+/// 在对话框窗口内部调用；只有该窗口位于最上层时才读取并消费按键，
+/// 同时打开多个对话框时其他对话框不会响应同一次按键
+pub fn dialog_keys(ui: &egui::Ui) -> DialogKeys {
+    let layer = ui.layer_id();
+    let on_top = ui.ctx().memory(|m| {
+        m.layer_ids()
+            .filter(|l| l.order == egui::Order::Middle && m.areas().is_visible(l))
+            .last()
+            == Some(layer)
+    });
+    if !on_top {
+        return DialogKeys::default();
+    }
+    ui.ctx().input_mut(|i| DialogKeys {
+        enter: i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+        escape: i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+    })
+}
+
+/// 带标签的单行输入框，读屏软件会将标签作为输入框名称朗读
+///
+/// 调用方负责外层布局（通常位于 `ui.horizontal` 中）
+pub fn labeled_text_edit(
+    ui: &mut egui::Ui,
+    label: &str,
+    text: &mut String,
+    password: bool,
+    width: f32,
+) -> egui::Response {
+    let label = ui.label(label);
+    let response = ui
+        .add(
+            egui::TextEdit::singleline(text)
+                .password(password)
+                .desired_width(width),
+        )
+        .labelled_by(label.id);
+    super::touch::keyboard_for(ui, &response);
+    response
+}
+
+/// 当前没有控件持有焦点时将焦点交给指定控件，用于对话框打开后直接键盘输入
+pub fn focus_if_idle(ui: &egui::Ui, response: &egui::Response) {
+    if ui.memory(|m| m.focused().is_none()) {
+        response.request_focus();
+    }
+}
+
+/// 为图标按钮补充读屏名称和悬停提示
+pub fn icon_button_label(response: egui::Response, label: &str) -> egui::Response {
+    let enabled = response.enabled();
+    let label = label.to_string();
+    response.widget_info(|| egui::WidgetInfo::labeled(egui::WidgetType::Button, enabled, &label));
+    response.on_hover_text(label)
+}
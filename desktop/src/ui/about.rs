@@ -1,6 +1,9 @@
 use egui;
 
 use crate::app::App;
+use crate::core::wimlib::ImageBackend;
+use crate::core::io_priority::IoPriority;
+use crate::ui::a11y::icon_button_label;
 use crate::utils::i18n::{self};
 use crate::utils::logger::LogManager;
 use crate::tr;
@@ -23,198 +26,352 @@ impl App {
                     ui.strong("v2026.2.6");
                 });
 
+                // 运行环境检测
+                ui.horizontal(|ui| {
+                    ui.label(tr!("运行环境:"));
+                    let problems = crate::core::diagnostics::problem_count(&self.diagnostic_items);
+                    if problems == 0 {
+                        ui.colored_label(egui::Color32::GREEN, tr!("正常"));
+                    } else {
+                        ui.colored_label(egui::Color32::from_rgb(255, 165, 0), format!("{} {}", problems, tr!("项未通过")));
+                    }
+                    if ui.small_button(tr!("重新检测")).clicked() {
+                        self.show_diagnostic_wizard = true;
+                        self.start_diagnostics();
+                    }
+                });
+
+                // 运营方信息（品牌定制）
+                if self.branding.help_url.is_some() || self.branding.contact.is_some() {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("提供方:"));
+                        ui.strong(self.branding.title());
+                    });
+                    if let Some(ref help_url) = self.branding.help_url {
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("帮助:"));
+                            ui.hyperlink_to(help_url, help_url);
+                        });
+                    }
+                    if let Some(ref contact) = self.branding.contact {
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("联系方式:"));
+                            ui.label(contact);
+                        });
+                    }
+                }
+
                 ui.add_space(15.0);
                 
-                // 语言设置
+                // 技师锁定
                 ui.separator();
                 ui.add_space(10.0);
-                ui.heading(tr!("语言设置"));
+                self.show_technician_lock_settings(ui);
                 ui.add_space(10.0);
+
+                // 锁定时禁止修改以下设置
+                let settings_locked = self.is_technician_locked();
+                ui.add_enabled_ui(!settings_locked, |ui| {
+                    // 语言设置
+                    ui.separator();
+                    ui.add_space(10.0);
+                    ui.heading(tr!("语言设置"));
+                    ui.add_space(10.0);
                 
-                // 获取可用语言列表
-                let available_languages = i18n::get_available_languages();
-                let current_language = self.app_config.language.clone();
+                    // 获取可用语言列表
+                    let available_languages = i18n::get_available_languages();
+                    let current_language = self.app_config.language.clone();
                 
-                ui.horizontal(|ui| {
-                    ui.label(tr!("界面语言:"));
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("界面语言:"));
                     
-                    // 查找当前语言的显示名称
-                    let current_display = available_languages
-                        .iter()
-                        .find(|l| l.code == current_language)
-                        .map(|l| l.display_name.as_str())
-                        .unwrap_or("简体中文 - 中华人民共和国");
+                        // 查找当前语言的显示名称
+                        let current_display = available_languages
+                            .iter()
+                            .find(|l| l.code == current_language)
+                            .map(|l| l.display_name.as_str())
+                            .unwrap_or("简体中文 - 中华人民共和国");
                     
-                    egui::ComboBox::from_id_salt("language_selector")
-                        .selected_text(current_display)
-                        .width(280.0)
-                        .show_ui(ui, |ui| {
-                            for lang in &available_languages {
-                                let is_selected = lang.code == current_language;
-                                if ui.selectable_label(is_selected, &lang.display_name).clicked() {
-                                    if lang.code != current_language {
-                                        self.app_config.set_language(&lang.code);
+                        egui::ComboBox::from_id_salt("language_selector")
+                            .selected_text(current_display)
+                            .width(280.0)
+                            .show_ui(ui, |ui| {
+                                for lang in &available_languages {
+                                    let is_selected = lang.code == current_language;
+                                    if ui.selectable_label(is_selected, &lang.display_name).clicked() {
+                                        if lang.code != current_language {
+                                            self.app_config.set_language(&lang.code);
+                                        }
                                     }
                                 }
-                            }
-                        });
+                            });
                     
-                    // 刷新语言列表按钮
-                    if ui.button("🔄").on_hover_text(tr!("刷新语言列表")).clicked() {
-                        i18n::refresh_available_languages();
-                    }
-                });
+                        // 刷新语言列表按钮
+                        if icon_button_label(ui.button("🔄"), &tr!("刷新语言列表")).clicked() {
+                            i18n::refresh_available_languages();
+                        }
+                    });
                 
-                // 显示当前语言作者信息
-                if let Some(lang_info) = available_languages.iter().find(|l| l.code == current_language) {
-                    if lang_info.code != "zh-CN" {
-                        ui.add_space(5.0);
-                        ui.indent("lang_author", |ui| {
-                            ui.colored_label(
-                                egui::Color32::GRAY,
-                                format!("{}: {}", tr!("翻译作者"), lang_info.author),
-                            );
-                        });
+                    // 显示当前语言作者信息
+                    if let Some(lang_info) = available_languages.iter().find(|l| l.code == current_language) {
+                        if lang_info.code != "zh-CN" {
+                            ui.add_space(5.0);
+                            ui.indent("lang_author", |ui| {
+                                ui.colored_label(
+                                    egui::Color32::GRAY,
+                                    format!("{}: {}", tr!("翻译作者"), lang_info.author),
+                                );
+                            });
+                        }
                     }
-                }
                 
-                ui.add_space(5.0);
-                ui.indent("lang_desc", |ui| {
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("将语言文件放入程序目录的 lang 文件夹中，"),
-                    );
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("然后点击刷新按钮即可添加新语言。"),
-                    );
-                });
+                    ui.add_space(5.0);
+                    ui.indent("lang_desc", |ui| {
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("将语言文件放入程序目录的 lang 文件夹中，"),
+                        );
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("然后点击刷新按钮即可添加新语言。"),
+                        );
+                    });
 
-                ui.add_space(10.0);
-                ui.separator();
+                    ui.add_space(10.0);
+                    ui.separator();
                 
-                // 小白模式设置
-                ui.add_space(10.0);
-                ui.heading(tr!("模式设置"));
-                ui.add_space(10.0);
+                    // 小白模式设置
+                    ui.add_space(10.0);
+                    ui.heading(tr!("模式设置"));
+                    ui.add_space(10.0);
                 
-                let is_pe = self.system_info.as_ref()
-                    .map(|info| info.is_pe_environment)
-                    .unwrap_or(false);
+                    let is_pe = self.system_info.as_ref()
+                        .map(|info| info.is_pe_environment)
+                        .unwrap_or(false);
                 
-                ui.horizontal(|ui| {
-                    let mut easy_mode = self.app_config.easy_mode_enabled;
+                    ui.horizontal(|ui| {
+                        let mut easy_mode = self.app_config.easy_mode_enabled;
                     
-                    ui.add_enabled_ui(!is_pe, |ui| {
-                        if ui.checkbox(&mut easy_mode, tr!("启用小白模式")).changed() {
-                            self.app_config.set_easy_mode(easy_mode);
+                        ui.add_enabled_ui(!is_pe, |ui| {
+                            if ui.checkbox(&mut easy_mode, tr!("启用小白模式")).changed() {
+                                self.app_config.set_easy_mode(easy_mode);
+                            }
+                        });
+                    
+                        if is_pe {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                tr!("(PE环境下不可用)"),
+                            );
                         }
                     });
-                    
-                    if is_pe {
+                
+                    ui.add_space(5.0);
+                    ui.indent("easy_mode_desc", |ui| {
                         ui.colored_label(
-                            egui::Color32::from_rgb(255, 165, 0),
-                            tr!("(PE环境下不可用)"),
+                            egui::Color32::GRAY,
+                            tr!("小白模式提供简化的系统重装界面，自动应用推荐设置，"),
                         );
-                    }
-                });
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("适合不熟悉系统重装操作的用户。"),
+                        );
+                    });
                 
-                ui.add_space(5.0);
-                ui.indent("easy_mode_desc", |ui| {
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("小白模式提供简化的系统重装界面，自动应用推荐设置，"),
-                    );
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("适合不熟悉系统重装操作的用户。"),
-                    );
-                });
+                    ui.add_space(10.0);
+                    ui.separator();
                 
-                ui.add_space(10.0);
-                ui.separator();
-                
-                // 日志设置
-                ui.add_space(10.0);
-                ui.heading(tr!("日志设置"));
-                ui.add_space(10.0);
+                    // 日志设置
+                    ui.add_space(10.0);
+                    ui.heading(tr!("日志设置"));
+                    ui.add_space(10.0);
                 
-                // 日志开关
-                ui.horizontal(|ui| {
-                    let mut log_enabled = self.app_config.log_enabled;
-                    if ui.checkbox(&mut log_enabled, tr!("启用日志记录")).changed() {
-                        self.app_config.set_log_enabled(log_enabled);
-                    }
-                });
+                    // 日志开关
+                    ui.horizontal(|ui| {
+                        let mut log_enabled = self.app_config.log_enabled;
+                        if ui.checkbox(&mut log_enabled, tr!("启用日志记录")).changed() {
+                            self.app_config.set_log_enabled(log_enabled);
+                        }
+                    });
                 
-                ui.add_space(5.0);
-                ui.indent("log_desc", |ui| {
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("日志文件保存在程序目录的 log 文件夹中，"),
-                    );
-                    ui.colored_label(
-                        egui::Color32::GRAY,
-                        tr!("用于故障排查和问题诊断。关闭后将在下次启动时生效。"),
-                    );
-                });
+                    ui.add_space(5.0);
+                    ui.indent("log_desc", |ui| {
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("日志文件保存在程序目录的 logs 文件夹中，"),
+                        );
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("用于故障排查和问题诊断。关闭后将在下次启动时生效。"),
+                        );
+                    });
                 
-                // 日志目录信息
-                if self.app_config.log_enabled {
-                    ui.add_space(8.0);
+                    // 日志目录信息
+                    if self.app_config.log_enabled {
+                        ui.add_space(8.0);
                     
-                    let log_dir = LogManager::get_log_dir();
-                    let log_size = LogManager::get_log_dir_size();
-                    let size_str = LogManager::format_size(log_size);
+                        let log_dir = LogManager::get_log_dir();
+                        let log_size = LogManager::get_log_dir_size();
+                        let size_str = LogManager::format_size(log_size);
                     
-                    ui.horizontal(|ui| {
-                        ui.label(tr!("日志目录:"));
-                        ui.monospace(log_dir.display().to_string());
-                    });
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("日志目录:"));
+                            ui.monospace(log_dir.display().to_string());
+                        });
                     
-                    ui.horizontal(|ui| {
-                        ui.label(tr!("日志大小:"));
-                        ui.monospace(&size_str);
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("日志大小:"));
+                            ui.monospace(&size_str);
                         
-                        ui.add_space(20.0);
+                            ui.add_space(20.0);
+                        
+                            // 打开日志目录按钮
+                            if ui.button(format!("📂 {}", tr!("打开日志目录"))).clicked() {
+                                if log_dir.exists() {
+                                    #[cfg(windows)]
+                                    {
+                                        let _ = std::process::Command::new("explorer")
+                                            .arg(&log_dir)
+                                            .spawn();
+                                    }
+                                }
+                            }
                         
-                        // 打开日志目录按钮
-                        if ui.button(format!("📂 {}", tr!("打开日志目录"))).clicked() {
-                            if log_dir.exists() {
-                                #[cfg(windows)]
+                            // 导出日志按钮
+                            if ui.button(format!("📦 {}", tr!("导出日志"))).clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("ZIP", &["zip"])
+                                    .set_file_name("LetRecovery_logs.zip")
+                                    .save_file()
                                 {
-                                    let _ = std::process::Command::new("explorer")
-                                        .arg(&log_dir)
-                                        .spawn();
+                                    if let Err(e) = LogManager::export_logs(&path) {
+                                        log::error!("导出日志失败: {:#}", e);
+                                    }
                                 }
                             }
-                        }
                         
-                        // 清理日志按钮
-                        if ui.button(format!("🗑 {}", tr!("清理旧日志"))).clicked() {
-                            if let Err(e) = LogManager::cleanup_old_logs(self.app_config.log_retention_days) {
-                                log::warn!("清理日志失败: {}", e);
-                            } else {
-                                log::info!("日志清理完成");
+                            // 清理日志按钮
+                            if ui.button(format!("🗑 {}", tr!("清理旧日志"))).clicked() {
+                                if let Err(e) = LogManager::cleanup_old_logs(self.app_config.log_retention_days) {
+                                    log::warn!("清理日志失败: {}", e);
+                                } else {
+                                    log::info!("日志清理完成");
+                                }
+                            }
+                        });
+                    
+                        // 日志保留天数设置
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            ui.label(tr!("日志保留天数:"));
+                            let mut days = self.app_config.log_retention_days;
+                            let slider = egui::Slider::new(&mut days, 1..=30)
+                                .suffix(format!(" {}", tr!("天")));
+                            if ui.add(slider).changed() {
+                                self.app_config.set_log_retention_days(days);
                             }
+                        });
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                
+                    // 隐私设置
+                    ui.add_space(10.0);
+                    ui.heading(tr!("隐私设置"));
+                    ui.add_space(10.0);
+                
+                    ui.horizontal(|ui| {
+                        let mut telemetry_enabled = self.app_config.telemetry_enabled;
+                        if ui.checkbox(&mut telemetry_enabled, tr!("发送匿名安装统计")).changed() {
+                            self.app_config.set_telemetry_enabled(telemetry_enabled);
                         }
                     });
-                    
-                    // 日志保留天数设置
+                
                     ui.add_space(5.0);
+                    ui.indent("telemetry_desc", |ui| {
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("安装结束后上报镜像名称、耗时、硬件类别及失败步骤，"),
+                        );
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("帮助镜像维护者定位兼容性问题，不包含任何个人信息。"),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    // 备份与复制设置
+                    ui.add_space(10.0);
+                    ui.heading(tr!("备份与复制"));
+                    ui.add_space(10.0);
+
                     ui.horizontal(|ui| {
-                        ui.label(tr!("日志保留天数:"));
-                        let mut days = self.app_config.log_retention_days;
-                        let slider = egui::Slider::new(&mut days, 1..=30)
-                            .suffix(format!(" {}", tr!("天")));
+                        ui.label(tr!("I/O 优先级:"));
+                        let mut priority = self.app_config.io_priority;
+                        egui::ComboBox::from_id_salt("io_priority")
+                            .selected_text(tr!(priority.label()))
+                            .show_ui(ui, |ui| {
+                                for option in IoPriority::ALL {
+                                    ui.selectable_value(&mut priority, option, tr!(option.label()));
+                                }
+                            });
+                        if priority != self.app_config.io_priority {
+                            self.app_config.set_io_priority(priority);
+                        }
+                    });
+
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("复制限速:"));
+                        let mut throttle = self.app_config.copy_throttle_mb;
+                        let slider = egui::Slider::new(&mut throttle, 0..=500).suffix(" MB/s");
                         if ui.add(slider).changed() {
-                            self.app_config.set_log_retention_days(days);
+                            self.app_config.set_copy_throttle_mb(throttle);
+                        }
+                        if throttle == 0 {
+                            ui.colored_label(egui::Color32::GRAY, tr!("不限速"));
                         }
                     });
-                }
 
-                ui.add_space(10.0);
-                ui.separator();
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.label(tr!("镜像引擎:"));
+                        let mut backend = self.app_config.image_backend;
+                        egui::ComboBox::from_id_salt("image_backend")
+                            .selected_text(tr!(backend.label()))
+                            .show_ui(ui, |ui| {
+                                for option in ImageBackend::ALL {
+                                    ui.selectable_value(&mut backend, option, tr!(option.label()));
+                                }
+                            });
+                        if backend != self.app_config.image_backend {
+                            self.app_config.set_image_backend(backend);
+                        }
+                    });
+
+                    ui.add_space(5.0);
+                    ui.indent("io_priority_desc", |ui| {
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("后台优先级与限速用于系统备份和分区对拷，"),
+                        );
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("可在长时间备份时继续正常使用电脑。限速仅对分区对拷生效。"),
+                        );
+                        ui.colored_label(
+                            egui::Color32::GRAY,
+                            tr!("镜像引擎用于系统安装和备份，wimlib 需要程序目录中存在 wimlib.dll。"),
+                        );
+                    });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                });
 
                 ui.add_space(15.0);
 
@@ -1,8 +1,19 @@
 use serde::{Deserialize, Serialize};
 use walkdir::WalkDir;
 
+use crate::core::disk_policy::{self, WriteCachePolicy};
+use crate::core::driver_pack::DriverPack;
 use crate::core::hardware_info::HardwareInfo;
+use crate::core::health_check::{self, HealthCheckSettings};
+use crate::core::image_language::TargetLanguage;
+use crate::download::config::ImagePreset;
+use crate::download::help::HelpTopic;
+use crate::core::offline_registry::{OfflineHive, OfflineRegistrySession};
 use crate::core::registry::OfflineRegistry;
+use crate::core::script_hooks::{self, HookStage, ScriptHook};
+use crate::core::unattend::{UnattendSettings, Win11Bypass};
+use crate::ui::help::help_button;
+use crate::ui::recent::recent_menu;
 use std::path::PathBuf;
 
 /// 系统安装高级选项
@@ -12,6 +23,12 @@ pub struct AdvancedOptions {
     pub remove_shortcut_arrow: bool,
     pub restore_classic_context_menu: bool,
     pub bypass_nro: bool,
+    /// Win11 绕过 TPM 检查
+    pub bypass_tpm_check: bool,
+    /// Win11 绕过安全启动检查
+    pub bypass_secure_boot_check: bool,
+    /// Win11 绕过内存检查
+    pub bypass_ram_check: bool,
     pub disable_windows_update: bool,
     pub disable_windows_defender: bool,
     pub disable_reserved_storage: bool,
@@ -24,11 +41,19 @@ pub struct AdvancedOptions {
     pub deploy_script_path: String,
     pub run_script_first_login: bool,
     pub first_login_script_path: String,
+    /// 首次启动脚本钩子（程序目录 scripts 文件夹中的脚本，为空表示全部按文件名顺序执行）
+    pub script_hooks: Vec<ScriptHook>,
+    /// 首次启动健康检查（网络、激活、问题设备、磁盘健康报告）
+    pub health_check: HealthCheckSettings,
 
     // 自定义内容
     pub import_custom_drivers: bool,
     pub custom_drivers_path: String,
     pub import_storage_controller_drivers: bool,
+    /// 要导入的存储控制器驱动包（为空表示全部导入）
+    pub storage_driver_packs: Vec<String>,
+    /// 首次启动禁用驱动签名强制（用于未签名的旧驱动）
+    pub disable_driver_signature_once: bool,
     pub import_registry_file: bool,
     pub registry_file_path: String,
     pub import_custom_files: bool,
@@ -37,10 +62,16 @@ pub struct AdvancedOptions {
     // 用户设置
     pub custom_username: bool,
     pub username: String,
+    /// 无人值守设置（区域、时区、产品密钥、计算机名、密码等）
+    pub unattend: UnattendSettings,
     
     // 系统盘设置
     pub custom_volume_label: bool,
     pub volume_label: String,
+    /// 确保 SSD 启用 TRIM
+    pub ensure_trim: bool,
+    /// 磁盘写入缓存策略
+    pub write_cache_policy: WriteCachePolicy,
     
     // Win7 专用选项
     pub win7_inject_usb3_driver: bool,
@@ -74,11 +105,216 @@ impl AdvancedOptions {
         (usb3, nvme)
     }
     
+    /// 获取首次启动脚本钩子目录（程序运行目录下的 scripts）
+    pub fn get_hook_scripts_dir() -> Option<PathBuf> {
+        Self::get_program_dir().map(|b| b.join(script_hooks::SCRIPTS_DIR))
+    }
+
     /// 获取 UefiSeven 目录（程序运行目录下的 uefiseven）
     fn get_uefiseven_dir() -> Option<PathBuf> {
         Self::get_program_dir().map(|b| b.join("uefiseven"))
     }
     
+    /// 可由镜像预设控制的开关选项（字段名, 显示名称）
+    const PRESET_KEYS: &'static [(&'static str, &'static str)] = &[
+        ("remove_shortcut_arrow", "移除快捷方式小箭头"),
+        ("restore_classic_context_menu", "Win11恢复经典右键菜单"),
+        ("bypass_nro", "OOBE绕过强制联网"),
+        ("bypass_tpm_check", "Win11绕过TPM检查"),
+        ("bypass_secure_boot_check", "Win11绕过安全启动检查"),
+        ("bypass_ram_check", "Win11绕过内存检查"),
+        ("disable_windows_update", "禁用Windows更新"),
+        ("disable_windows_defender", "禁用Windows安全中心"),
+        ("disable_reserved_storage", "禁用系统保留空间"),
+        ("disable_uac", "禁用用户账户控制(UAC)"),
+        ("disable_device_encryption", "禁用自动设备加密"),
+        ("remove_uwp_apps", "删除预装UWP应用"),
+        ("import_storage_controller_drivers", "导入磁盘控制器驱动"),
+        ("disable_driver_signature_once", "首次启动禁用驱动签名强制"),
+        ("ensure_trim", "确保启用TRIM"),
+        ("win7_inject_usb3_driver", "注入USB3.0驱动"),
+        ("win7_inject_nvme_driver", "注入NVMe驱动"),
+        ("win7_fix_acpi_bsod", "修复ACPI_BIOS_ERROR蓝屏(0xA5)"),
+        ("win7_fix_storage_bsod", "修复INACCESSIBLE_BOOT_DEVICE蓝屏(0x7B)"),
+        ("win7_uefi_patch", "应用Win7 UEFI启动修补"),
+    ];
+
+    /// Win11 硬件要求检查绕过
+    pub fn win11_bypass(&self) -> Win11Bypass {
+        Win11Bypass {
+            tpm: self.bypass_tpm_check,
+            secure_boot: self.bypass_secure_boot_check,
+            ram: self.bypass_ram_check,
+        }
+    }
+
+    /// 按字段名获取开关选项
+    fn bool_option_mut(&mut self, key: &str) -> Option<&mut bool> {
+        Some(match key {
+            "remove_shortcut_arrow" => &mut self.remove_shortcut_arrow,
+            "restore_classic_context_menu" => &mut self.restore_classic_context_menu,
+            "bypass_nro" => &mut self.bypass_nro,
+            "bypass_tpm_check" => &mut self.bypass_tpm_check,
+            "bypass_secure_boot_check" => &mut self.bypass_secure_boot_check,
+            "bypass_ram_check" => &mut self.bypass_ram_check,
+            "disable_windows_update" => &mut self.disable_windows_update,
+            "disable_windows_defender" => &mut self.disable_windows_defender,
+            "disable_reserved_storage" => &mut self.disable_reserved_storage,
+            "disable_uac" => &mut self.disable_uac,
+            "disable_device_encryption" => &mut self.disable_device_encryption,
+            "remove_uwp_apps" => &mut self.remove_uwp_apps,
+            "import_storage_controller_drivers" => &mut self.import_storage_controller_drivers,
+            "disable_driver_signature_once" => &mut self.disable_driver_signature_once,
+            "ensure_trim" => &mut self.ensure_trim,
+            "win7_inject_usb3_driver" => &mut self.win7_inject_usb3_driver,
+            "win7_inject_nvme_driver" => &mut self.win7_inject_nvme_driver,
+            "win7_fix_acpi_bsod" => &mut self.win7_fix_acpi_bsod,
+            "win7_fix_storage_bsod" => &mut self.win7_fix_storage_bsod,
+            "win7_uefi_patch" => &mut self.win7_uefi_patch,
+            _ => return None,
+        })
+    }
+
+    /// 首次启动脚本钩子列表（启用、执行时机、顺序）
+    fn show_script_hooks(&mut self, ui: &mut egui::Ui) {
+        let Some(scripts_dir) = Self::get_hook_scripts_dir() else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("首次启动脚本钩子");
+            if ui.button("刷新").clicked() {
+                self.script_hooks = script_hooks::discover(&scripts_dir, &self.script_hooks);
+            }
+        });
+        ui.label(
+            egui::RichText::new(format!(
+                "读取 {} 中的 .cmd/.bat/.ps1 脚本，未刷新时按文件名顺序在部署完成时全部执行",
+                scripts_dir.display()
+            ))
+            .small()
+            .color(egui::Color32::GRAY),
+        );
+
+        let count = self.script_hooks.len();
+        let mut swap_with_previous = None;
+        for (i, hook) in self.script_hooks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut hook.enabled, hook.file.as_str());
+                egui::ComboBox::from_id_salt(("script_hook_stage", i))
+                    .selected_text(hook.stage.label())
+                    .show_ui(ui, |ui| {
+                        for stage in [HookStage::SetupComplete, HookStage::RunOnce] {
+                            ui.selectable_value(&mut hook.stage, stage, stage.label());
+                        }
+                    });
+                if ui.add_enabled(i > 0, egui::Button::new("↑")).clicked() {
+                    swap_with_previous = Some(i);
+                }
+                if ui.add_enabled(i + 1 < count, egui::Button::new("↓")).clicked() {
+                    swap_with_previous = Some(i + 1);
+                }
+            });
+        }
+        if let Some(i) = swap_with_previous {
+            self.script_hooks.swap(i - 1, i);
+            script_hooks::renumber(&mut self.script_hooks);
+        }
+    }
+
+    /// 首次启动健康检查（启用、报告上传地址）
+    fn show_health_check(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.health_check.enabled, "首次启动健康检查")
+            .on_hover_text("首次登录后检查网络、激活状态、问题设备和磁盘健康，生成报告后自动删除");
+        if !self.health_check.enabled {
+            return;
+        }
+        ui.indent("health_check", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("上传地址:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.health_check.report_url)
+                        .desired_width(260.0)
+                        .hint_text("留空表示只保存报告文件"),
+                );
+            });
+            if let Err(error) = self.health_check.validate() {
+                ui.colored_label(egui::Color32::RED, format!("✗ {}", error));
+            }
+            ui.label(
+                egui::RichText::new(format!(
+                    "报告保存在数据分区（或新系统盘）的 {} 文件夹",
+                    health_check::REPORTS_DIR
+                ))
+                .small()
+                .color(egui::Color32::GRAY),
+            );
+        });
+    }
+
+    /// 驱动包选择（未选择任何驱动包时表示全部导入）
+    fn show_driver_pack_selection(&mut self, ui: &mut egui::Ui, driver_packs: &[DriverPack]) {
+        ui.indent("storage_driver_packs", |ui| {
+            for pack in driver_packs {
+                let mut selected = self.storage_driver_packs.is_empty()
+                    || self.storage_driver_packs.contains(&pack.id);
+                let mut label = pack.name.clone();
+                if !pack.version.is_empty() {
+                    label.push_str(&format!(" {}", pack.version));
+                }
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut selected, label).changed() {
+                        if self.storage_driver_packs.is_empty() {
+                            self.storage_driver_packs = driver_packs.iter().map(|p| p.id.clone()).collect();
+                        }
+                        if selected {
+                            self.storage_driver_packs.push(pack.id.clone());
+                        } else {
+                            self.storage_driver_packs.retain(|id| id != &pack.id);
+                        }
+                        self.storage_driver_packs.sort();
+                        self.storage_driver_packs.dedup();
+                        // 全部取消等同于不导入
+                        if self.storage_driver_packs.is_empty() {
+                            self.import_storage_controller_drivers = false;
+                        }
+                        // 全部选中时恢复为“全部导入”
+                        if driver_packs.iter().all(|p| self.storage_driver_packs.contains(&p.id)) {
+                            self.storage_driver_packs.clear();
+                        }
+                    }
+                    if pack.matched {
+                        ui.colored_label(egui::Color32::GREEN, "✓ 匹配当前硬件");
+                    }
+                });
+            }
+        });
+    }
+
+    /// 应用镜像安装预设
+    pub fn apply_preset(&mut self, preset: &ImagePreset) {
+        for (key, value) in &preset.options {
+            match self.bool_option_mut(key) {
+                Some(option) => *option = *value,
+                None => log::warn!("[PRESET] 未知的预设选项: {}", key),
+            }
+        }
+    }
+
+    /// 获取被用户手动改动过的预设选项（显示名称）
+    pub fn preset_overrides(&self, preset: &ImagePreset) -> Vec<&'static str> {
+        let mut current = self.clone();
+        Self::PRESET_KEYS
+            .iter()
+            .filter(|(key, _)| {
+                let expected = preset.options.get(*key);
+                let actual = current.bool_option_mut(key).map(|v| *v);
+                matches!((expected, actual), (Some(e), Some(a)) if *e != a)
+            })
+            .map(|(_, label)| *label)
+            .collect()
+    }
+
     /// 显示依赖无人值守的复选框
     /// 如果无人值守被禁用，该复选框也会被禁用并显示提示
     fn show_unattend_dependent_checkbox(
@@ -136,13 +372,10 @@ impl AdvancedOptions {
             return Err(anyhow::anyhow!("UefiSeven bootx64.efi 不存在"));
         }
         
-        // 查找 EFI 系统分区
-        let efi_partition = Self::find_efi_partition()?;
-        println!("[UEFISEVEN] 找到 EFI 分区: {}", efi_partition);
-        
-        // 确保 EFI 分区已挂载
-        let efi_mount_point = Self::ensure_efi_mounted(&efi_partition)?;
-        println!("[UEFISEVEN] EFI 分区挂载点: {}", efi_mount_point);
+        // 按 GPT 分区类型查找 EFI 系统分区，直接通过卷 GUID 路径访问，无需分配盘符
+        let efi_mount_point = crate::core::disk::DiskManager::find_esp_volume(None)
+            .ok_or_else(|| anyhow::anyhow!("未找到 EFI 系统分区"))?;
+        println!("[UEFISEVEN] 找到 EFI 分区: {}", efi_mount_point);
         
         // Microsoft Boot 目录
         let ms_boot_dir = format!("{}\\EFI\\Microsoft\\Boot", efi_mount_point);
@@ -193,142 +426,20 @@ log=0
         Ok(())
     }
     
-    /// 查找 EFI 系统分区
-    fn find_efi_partition() -> anyhow::Result<String> {
-        use std::process::Command;
-        
-        // 使用 PowerShell 查找 EFI 分区
-        let output = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                r#"
-                $efiPart = Get-Partition | Where-Object { $_.GptType -eq '{c12a7328-f81f-11d2-ba4b-00a0c93ec93b}' } | Select-Object -First 1
-                if ($efiPart) {
-                    $efiPart.DiskNumber.ToString() + ':' + $efiPart.PartitionNumber.ToString()
-                }
-                "#
-            ])
-            .output()?;
-        
-        let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        
-        if result.is_empty() {
-            return Err(anyhow::anyhow!("未找到 EFI 系统分区"));
-        }
-        
-        Ok(result)
-    }
-    
-    /// 确保 EFI 分区已挂载，返回挂载点
-    fn ensure_efi_mounted(efi_partition: &str) -> anyhow::Result<String> {
-        use std::process::Command;
-        
-        // 解析磁盘号和分区号
-        let parts: Vec<&str> = efi_partition.split(':').collect();
-        if parts.len() != 2 {
-            return Err(anyhow::anyhow!("无效的 EFI 分区标识: {}", efi_partition));
-        }
-        
-        let disk_num = parts[0];
-        let part_num = parts[1];
-        
-        // 检查是否已经有挂载点
-        let check_output = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                &format!(r#"
-                $vol = Get-Partition -DiskNumber {} -PartitionNumber {} | Get-Volume -ErrorAction SilentlyContinue
-                if ($vol -and $vol.DriveLetter) {{
-                    $vol.DriveLetter + ':'
-                }}
-                "#, disk_num, part_num)
-            ])
-            .output()?;
-        
-        let existing_mount = String::from_utf8_lossy(&check_output.stdout).trim().to_string();
-        
-        if !existing_mount.is_empty() && existing_mount.len() == 2 {
-            return Ok(existing_mount);
-        }
-        
-        // 查找可用盘符
-        let find_letter = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                r#"
-                $used = (Get-Volume).DriveLetter
-                $available = 90..65 | ForEach-Object { [char]$_ } | Where-Object { $_ -notin $used }
-                if ($available) { $available[0] }
-                "#
-            ])
-            .output()?;
-        
-        let letter = String::from_utf8_lossy(&find_letter.stdout).trim().to_string();
-        
-        if letter.is_empty() {
-            return Err(anyhow::anyhow!("没有可用的盘符"));
-        }
-        
-        // 使用 mountvol 挂载 EFI 分区
-        let mount_result = Command::new("cmd")
-            .args([
-                "/c",
-                &format!("mountvol {}:\\ /s", letter)
-            ])
-            .output();
-        
-        match mount_result {
-            Ok(output) if output.status.success() => {
-                Ok(format!("{}:", letter))
-            }
-            Ok(output) => {
-                // mountvol /s 可能失败，尝试使用 diskpart
-                let diskpart_script = format!(
-                    "select disk {}\nselect partition {}\nassign letter={}\n",
-                    disk_num, part_num, letter
-                );
-                
-                let temp_script = std::env::temp_dir().join("efi_mount.txt");
-                std::fs::write(&temp_script, &diskpart_script)?;
-                
-                let diskpart_result = Command::new("diskpart")
-                    .args(["/s", &temp_script.to_string_lossy()])
-                    .output();
-                
-                let _ = std::fs::remove_file(&temp_script);
-                
-                match diskpart_result {
-                    Ok(dp_output) if dp_output.status.success() => {
-                        Ok(format!("{}:", letter))
-                    }
-                    _ => {
-                        let stderr = String::from_utf8_lossy(&output.stderr);
-                        Err(anyhow::anyhow!("挂载 EFI 分区失败: {}", stderr))
-                    }
-                }
-            }
-            Err(e) => Err(anyhow::anyhow!("执行挂载命令失败: {}", e))
-        }
-    }
-
     /// 应用选项到目标系统
     pub fn apply_to_system(&self, target_partition: &str) -> anyhow::Result<()> {
         println!("[ADVANCED] 开始应用高级选项到: {}", target_partition);
         
-        let windows_path = format!("{}\\Windows", target_partition);
-        let software_hive = format!("{}\\System32\\config\\SOFTWARE", windows_path);
-        let system_hive = format!("{}\\System32\\config\\SYSTEM", windows_path);
-        let default_hive = format!("{}\\System32\\config\\DEFAULT", windows_path);
-
-        // 加载离线注册表
+        // 加载离线注册表（离开本函数时自动卸载）
         println!("[ADVANCED] 加载离线注册表...");
-        OfflineRegistry::load_hive("pc-soft", &software_hive)?;
-        OfflineRegistry::load_hive("pc-sys", &system_hive)?;
-        // DEFAULT 用于设置默认用户配置（如经典右键菜单）
-        let default_loaded = OfflineRegistry::load_hive("pc-default", &default_hive).is_ok();
+        let mut registry = OfflineRegistrySession::open(
+            target_partition,
+            &[OfflineHive::Software, OfflineHive::System],
+        )?;
+        // 默认用户的 NTUSER.DAT 用于设置新用户配置（如经典右键菜单）
+        if let Err(e) = registry.load(OfflineHive::DefaultUser) {
+            println!("[ADVANCED] {:#} (继续执行)", e);
+        }
 
         // 创建脚本目录（用于存放自定义脚本）
         let scripts_dir = format!("{}\\{}", target_partition, Self::SCRIPTS_DIR);
@@ -340,8 +451,9 @@ log=0
         // 1. 移除快捷方式小箭头
         if self.remove_shortcut_arrow {
             println!("[ADVANCED] 移除快捷方式小箭头");
-            let _ = OfflineRegistry::set_string(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Shell Icons",
+            let _ = registry.set_string(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\Explorer\\Shell Icons",
                 "29",
                 "%systemroot%\\system32\\imageres.dll,197",
             );
@@ -350,25 +462,29 @@ log=0
         // 2. Win11恢复经典右键菜单
         if self.restore_classic_context_menu {
             println!("[ADVANCED] 恢复经典右键菜单");
-            // 在 DEFAULT hive 中设置（影响所有新用户）
-            if default_loaded {
+            // 在默认用户配置中设置（影响所有新用户）
+            if registry.is_loaded(OfflineHive::DefaultUser) {
                 // 创建空的 InprocServer32 键，这会禁用新式右键菜单
-                let _ = OfflineRegistry::create_key(
-                    "HKLM\\pc-default\\Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
+                let _ = registry.create_key(
+                    OfflineHive::DefaultUser,
+                    "Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
                 );
                 // 设置默认值为空字符串
-                let _ = OfflineRegistry::set_string(
-                    "HKLM\\pc-default\\Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
+                let _ = registry.set_string(
+                    OfflineHive::DefaultUser,
+                    "Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
                     "",
                     "",
                 );
             }
             // 同时在 SOFTWARE 中设置（系统级）
-            let _ = OfflineRegistry::create_key(
-                "HKLM\\pc-soft\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
+            let _ = registry.create_key(
+                OfflineHive::Software,
+                "Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
             );
-            let _ = OfflineRegistry::set_string(
-                "HKLM\\pc-soft\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
+            let _ = registry.set_string(
+                OfflineHive::Software,
+                "Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
                 "",
                 "",
             );
@@ -377,31 +493,41 @@ log=0
         // 3. OOBE绕过强制联网
         if self.bypass_nro {
             println!("[ADVANCED] 设置OOBE绕过联网");
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\OOBE",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\OOBE",
                 "BypassNRO",
                 1,
             );
         }
 
+        // 3.1 Win11 硬件要求检查绕过
+        for (key, name) in self.win11_bypass().registry_values() {
+            println!("[ADVANCED] 设置 {}\\{}", key, name);
+            let _ = registry.set_dword(OfflineHive::System, key, name, 1);
+        }
+
         // 4. 禁用Windows更新
         if self.disable_windows_update {
             println!("[ADVANCED] 禁用Windows更新服务");
             // 禁用 Windows Update 服务
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\wuauserv",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\wuauserv",
                 "Start",
                 4, // 4 = Disabled
             );
             // 禁用 Update Orchestrator Service
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\UsoSvc",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\UsoSvc",
                 "Start",
                 4,
             );
             // 设置策略禁用自动更新
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Policies\\Microsoft\\Windows\\WindowsUpdate\\AU",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Policies\\Microsoft\\Windows\\WindowsUpdate\\AU",
                 "NoAutoUpdate",
                 1,
             );
@@ -411,29 +537,34 @@ log=0
         if self.disable_windows_defender {
             println!("[ADVANCED] 禁用Windows Defender");
             // 禁用实时保护
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Policies\\Microsoft\\Windows Defender",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Policies\\Microsoft\\Windows Defender",
                 "DisableAntiSpyware",
                 1,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Policies\\Microsoft\\Windows Defender\\Real-Time Protection",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Policies\\Microsoft\\Windows Defender\\Real-Time Protection",
                 "DisableRealtimeMonitoring",
                 1,
             );
             // 禁用服务
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\WinDefend",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\WinDefend",
                 "Start",
                 4, // Disabled
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\WdNisSvc",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\WdNisSvc",
                 "Start",
                 4,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\SecurityHealthService",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\SecurityHealthService",
                 "Start",
                 4,
             );
@@ -442,13 +573,15 @@ log=0
         // 6. 禁用系统保留空间
         if self.disable_reserved_storage {
             println!("[ADVANCED] 禁用系统保留空间");
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\ReserveManager",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\ReserveManager",
                 "ShippedWithReserves",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\ReserveManager",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\ReserveManager",
                 "PassedPolicy",
                 0,
             );
@@ -457,13 +590,15 @@ log=0
         // 7. 禁用UAC
         if self.disable_uac {
             println!("[ADVANCED] 禁用UAC");
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Policies\\System",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\Policies\\System",
                 "EnableLUA",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Policies\\System",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Microsoft\\Windows\\CurrentVersion\\Policies\\System",
                 "ConsentPromptBehaviorAdmin",
                 0,
             );
@@ -473,20 +608,23 @@ log=0
         if self.disable_device_encryption {
             println!("[ADVANCED] 禁用自动设备加密");
             // 禁用 BitLocker 自动加密
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Control\\BitLocker",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Control\\BitLocker",
                 "PreventDeviceEncryption",
                 1,
             );
             // 禁用 MBAM (Microsoft BitLocker Administration and Monitoring)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-soft\\Policies\\Microsoft\\FVE",
+            let _ = registry.set_dword(
+                OfflineHive::Software,
+                "Policies\\Microsoft\\FVE",
                 "OSRecovery",
                 0,
             );
             // 禁用设备加密
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\BDESVC",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\BDESVC",
                 "Start",
                 4, // Disabled
             );
@@ -496,9 +634,11 @@ log=0
         if self.remove_uwp_apps {
             println!("[ADVANCED] 配置删除预装UWP应用");
             // 创建首次登录脚本来删除UWP应用
-            let remove_uwp_script = Self::generate_remove_uwp_script();
+            let language = TargetLanguage::detect_in(&registry, target_partition).unwrap_or_else(|| TargetLanguage::new("zh-CN"));
+            let remove_uwp_script = Self::generate_remove_uwp_script(&language);
             let uwp_script_path = format!("{}\\remove_uwp.ps1", scripts_dir);
-            std::fs::write(&uwp_script_path, &remove_uwp_script)?;
+            // 带 BOM，避免 Windows PowerShell 按 ANSI 读取非英文提示
+            std::fs::write(&uwp_script_path, format!("\u{feff}{}", remove_uwp_script))?;
             println!("[ADVANCED] UWP删除脚本已写入: {}", uwp_script_path);
         }
 
@@ -526,63 +666,60 @@ log=0
         if self.import_custom_drivers && !self.custom_drivers_path.is_empty() {
             println!("[ADVANCED] 导入自定义驱动: {}", self.custom_drivers_path);
             
-            // 先卸载注册表，因为 DISM 可能需要独占访问
-            let _ = OfflineRegistry::unload_hive("pc-soft");
-            let _ = OfflineRegistry::unload_hive("pc-sys");
-            if default_loaded {
-                let _ = OfflineRegistry::unload_hive("pc-default");
-            }
-            
-            // 使用 DISM 添加驱动
+            // 使用 DISM 添加驱动，期间卸载注册表，因为 DISM 可能需要独占访问
             let dism = crate::core::dism::Dism::new();
             let image_path = format!("{}\\", target_partition);
-            match dism.add_drivers_offline(&image_path, &self.custom_drivers_path) {
-                Ok(_) => println!("[ADVANCED] 自定义驱动导入成功"),
-                Err(e) => println!("[ADVANCED] 自定义驱动导入失败: {} (继续执行)", e),
+            // 目录中的 zstd 驱动包先解压到目标分区，导入后删除
+            let pack_staging = std::path::PathBuf::from(format!("{}\\LetRecovery_Packs", target_partition));
+            for driver_dir in crate::core::zst_pack::expand_packs(&self.custom_drivers_path, &pack_staging) {
+                match registry.while_unloaded(|| dism.add_drivers_offline(&image_path, &driver_dir)) {
+                    Ok(_) => println!("[ADVANCED] 自定义驱动导入成功: {}", driver_dir),
+                    Err(e) => println!("[ADVANCED] 自定义驱动导入失败: {} (继续执行)", e),
+                }
             }
-            
-            // 重新加载注册表
-            let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-            let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
+            let _ = std::fs::remove_dir_all(&pack_staging);
         }
 
         // 13. 导入磁盘控制器驱动（Win10/Win11 x64）
         if self.import_storage_controller_drivers {
-            let storage_drivers_dir = crate::utils::path::get_exe_dir()
-                .join("drivers")
-                .join("storage_controller");
-            if storage_drivers_dir.is_dir() {
-                println!(
-                    "[ADVANCED] 导入磁盘控制器驱动: {}",
-                    storage_drivers_dir.display()
-                );
-
-                // 先卸载注册表，因为 DISM 可能需要独占访问
-                let _ = OfflineRegistry::unload_hive("pc-soft");
-                let _ = OfflineRegistry::unload_hive("pc-sys");
-                if default_loaded {
-                    let _ = OfflineRegistry::unload_hive("pc-default");
-                }
-
+            let controllers = crate::core::storage_controller::detect_controllers();
+            let pack_dirs = crate::core::driver_pack::selected_pack_dirs(&self.storage_driver_packs, &controllers);
+            let pack_dirs: Vec<_> = pack_dirs.into_iter().filter(|dir| dir.is_dir()).collect();
+            if !pack_dirs.is_empty() {
+                // 导入期间卸载注册表，因为 DISM 可能需要独占访问
                 let dism = crate::core::dism::Dism::new();
                 let image_path = format!("{}\\", target_partition);
-                let storage_drivers_path = storage_drivers_dir.to_string_lossy().to_string();
-                match dism.add_drivers_offline(&image_path, &storage_drivers_path) {
-                    Ok(_) => println!("[ADVANCED] 磁盘控制器驱动导入成功"),
-                    Err(e) => println!("[ADVANCED] 磁盘控制器驱动导入失败: {} (继续执行)", e),
-                }
-
-                // 重新加载注册表
-                let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-                let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
+                registry.while_unloaded(|| {
+                    for storage_drivers_dir in &pack_dirs {
+                        println!(
+                            "[ADVANCED] 导入磁盘控制器驱动: {}",
+                            storage_drivers_dir.display()
+                        );
+                        let storage_drivers_path = storage_drivers_dir.to_string_lossy().to_string();
+                        match dism.add_drivers_offline(&image_path, &storage_drivers_path) {
+                            Ok(_) => println!("[ADVANCED] 磁盘控制器驱动导入成功"),
+                            Err(e) => println!("[ADVANCED] 磁盘控制器驱动导入失败: {} (继续执行)", e),
+                        }
+                    }
+                });
             } else {
                 println!(
-                    "[ADVANCED] 未找到磁盘控制器驱动目录: {}",
-                    storage_drivers_dir.display()
+                    "[ADVANCED] 未找到磁盘控制器驱动包: {}",
+                    crate::core::driver_pack::packs_dir().display()
                 );
             }
         }
 
+        // 13.5 首次启动禁用驱动签名强制（需在修复引导后执行）
+        if self.disable_driver_signature_once {
+            println!("[ADVANCED] 设置首次启动禁用驱动签名强制");
+            let boot_manager = crate::core::bcdedit::BootManager::new();
+            match boot_manager.disable_integrity_checks_once(target_partition) {
+                Ok(_) => println!("[ADVANCED] 驱动签名强制将在首次启动后恢复"),
+                Err(e) => println!("[ADVANCED] 设置驱动签名强制失败: {} (继续执行)", e),
+            }
+        }
+
         // 14. 导入注册表文件 - 实际导入到离线注册表
         if self.import_registry_file && !self.registry_file_path.is_empty() {
             println!("[ADVANCED] 导入注册表文件: {}", self.registry_file_path);
@@ -631,6 +768,19 @@ log=0
             std::fs::write(&volume_label_file, &self.volume_label)?;
         }
 
+        // 17.5 磁盘写入缓存与 TRIM 策略
+        if self.ensure_trim {
+            println!("[ADVANCED] 启用 TRIM");
+            let _ = disk_policy::enable_trim_offline(&registry);
+        }
+        if self.ensure_trim || self.write_cache_policy != WriteCachePolicy::Unchanged {
+            println!("[ADVANCED] 写入缓存策略: {}", self.write_cache_policy.label());
+            let script = disk_policy::generate_script(self.write_cache_policy, self.ensure_trim);
+            if let Err(e) = disk_policy::install_script(target_partition, &script) {
+                println!("[ADVANCED] 安装磁盘策略脚本失败: {} (继续执行)", e);
+            }
+        }
+
         // ============ Win7 专用选项 ============
         
         // 18. Win7 注入 USB3 驱动（固定读取程序运行目录下的 drivers\\usb3）
@@ -660,19 +810,13 @@ log=0
             } else {
                 println!("[ADVANCED] Win7: 处理USB3驱动目录: {}", usb3_path.to_string_lossy());
                 
-                // 先卸载注册表
-                let _ = OfflineRegistry::unload_hive("pc-soft");
-                let _ = OfflineRegistry::unload_hive("pc-sys");
-                if default_loaded {
-                    let _ = OfflineRegistry::unload_hive("pc-default");
-                }
-                
                 // 处理目录中的驱动（包括 .cab 文件）
                 let processed_path = Self::prepare_win7_drivers(&usb3_path)?;
                 
+                // 注入期间卸载注册表，因为 DISM 可能需要独占访问
                 let dism = crate::core::dism::Dism::new();
                 let image_path = format!("{}\\", target_partition);
-                match dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy()) {
+                match registry.while_unloaded(|| dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy())) {
                     Ok(_) => println!("[ADVANCED] Win7 USB3驱动注入成功"),
                     Err(e) => println!("[ADVANCED] Win7 USB3驱动注入失败: {} (继续执行)", e),
                 }
@@ -681,10 +825,6 @@ log=0
                 if processed_path != usb3_path {
                     let _ = std::fs::remove_dir_all(&processed_path);
                 }
-                
-                // 重新加载注册表
-                let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-                let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
             }
         }
         
@@ -715,19 +855,13 @@ log=0
             } else {
                 println!("[ADVANCED] Win7: 处理NVMe驱动目录: {}", nvme_path.to_string_lossy());
                 
-                // 先卸载注册表
-                let _ = OfflineRegistry::unload_hive("pc-soft");
-                let _ = OfflineRegistry::unload_hive("pc-sys");
-                if default_loaded {
-                    let _ = OfflineRegistry::unload_hive("pc-default");
-                }
-                
                 // 处理目录中的驱动（包括 .cab 文件）
                 let processed_path = Self::prepare_win7_drivers(&nvme_path)?;
                 
+                // 注入期间卸载注册表，因为 DISM 可能需要独占访问
                 let dism = crate::core::dism::Dism::new();
                 let image_path = format!("{}\\", target_partition);
-                match dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy()) {
+                match registry.while_unloaded(|| dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy())) {
                     Ok(_) => println!("[ADVANCED] Win7 NVMe驱动注入成功"),
                     Err(e) => println!("[ADVANCED] Win7 NVMe驱动注入失败: {} (继续执行)", e),
                 }
@@ -736,10 +870,6 @@ log=0
                 if processed_path != nvme_path {
                     let _ = std::fs::remove_dir_all(&processed_path);
                 }
-                
-                // 重新加载注册表
-                let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-                let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
             }
         }
         
@@ -748,39 +878,45 @@ log=0
             println!("[ADVANCED] Win7: 修复ACPI蓝屏问题");
             
             // 禁用 intelppm 服务 (Intel 电源管理)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\intelppm",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\intelppm",
                 "Start",
                 4, // 4 = Disabled
             );
             
             // 禁用 amdppm 服务 (AMD 电源管理)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\amdppm",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\amdppm",
                 "Start",
                 4,
             );
             
             // 禁用 Processor 服务
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\Processor",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\Processor",
                 "Start",
                 4,
             );
             
             // 同时设置 ControlSet002 (如果存在)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\intelppm",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\intelppm",
                 "Start",
                 4,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\amdppm",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\amdppm",
                 "Start",
                 4,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\Processor",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\Processor",
                 "Start",
                 4,
             );
@@ -795,212 +931,246 @@ log=0
             
             // ========== AHCI 相关驱动 ==========
             // msahci - Microsoft AHCI 驱动 (Win7原版自带但默认禁用)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\msahci",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\msahci",
                 "Start",
                 0, // 0 = Boot (启动时加载)
             );
             // 同时设置 ControlSet002
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\msahci",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\msahci",
                 "Start",
                 0,
             );
             
             // StorAHCI - 新版 AHCI 驱动 (Win8+)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\storahci",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\storahci",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\storahci",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\storahci",
                 "Start",
                 0,
             );
             
             // ========== IDE 相关驱动 ==========
             // pciide - 标准 PCI IDE 控制器
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\pciide",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\pciide",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\pciide",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\pciide",
                 "Start",
                 0,
             );
             
             // intelide - Intel IDE 控制器
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\intelide",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\intelide",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\intelide",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\intelide",
                 "Start",
                 0,
             );
             
             // atapi - ATAPI/PATA 驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\atapi",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\atapi",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\atapi",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\atapi",
                 "Start",
                 0,
             );
             
             // ========== Intel 存储驱动 ==========
             // iaStorV - Intel 快速存储技术 (RST)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\iaStorV",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\iaStorV",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\iaStorV",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\iaStorV",
                 "Start",
                 0,
             );
             
             // iaStorAV - Intel AHCI 驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\iaStorAV",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\iaStorAV",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\iaStorAV",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\iaStorAV",
                 "Start",
                 0,
             );
             
             // iaStor - 旧版 Intel 存储驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\iaStor",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\iaStor",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\iaStor",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\iaStor",
                 "Start",
                 0,
             );
             
             // ========== NVMe 驱动 ==========
             // stornvme - Microsoft NVMe 驱动 (需要注入驱动文件才能生效)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\stornvme",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\stornvme",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\stornvme",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\stornvme",
                 "Start",
                 0,
             );
             
             // ========== AMD 存储驱动 ==========
             // amd_sata - AMD SATA 驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\amd_sata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\amd_sata",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\amd_sata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\amd_sata",
                 "Start",
                 0,
             );
             
             // amd_xata - AMD AHCI 驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\amd_xata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\amd_xata",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\amd_xata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\amd_xata",
                 "Start",
                 0,
             );
             
             // amdsata - AMD SATA (另一版本)
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\amdsata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\amdsata",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\amdsata",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\amdsata",
                 "Start",
                 0,
             );
             
             // ========== VMware/VirtualBox 虚拟机存储驱动 ==========
             // LSI_SAS - VMware 默认存储控制器
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\LSI_SAS",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\LSI_SAS",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\LSI_SAS",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\LSI_SAS",
                 "Start",
                 0,
             );
             
             // LSI_SAS2 - VMware LSI Logic SAS
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\LSI_SAS2",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\LSI_SAS2",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\LSI_SAS2",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\LSI_SAS2",
                 "Start",
                 0,
             );
             
             // LSI_SCSI - LSI SCSI 控制器
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\LSI_SCSI",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\LSI_SCSI",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\LSI_SCSI",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\LSI_SCSI",
                 "Start",
                 0,
             );
             
             // megasas - MegaRAID SAS 控制器
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\megasas",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\megasas",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\megasas",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\megasas",
                 "Start",
                 0,
             );
             
             // ========== 通用 SCSI 驱动 ==========
             // vhdmp - VHD Mini-Port 驱动
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet001\\Services\\vhdmp",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet001\\Services\\vhdmp",
                 "Start",
                 0,
             );
-            let _ = OfflineRegistry::set_dword(
-                "HKLM\\pc-sys\\ControlSet002\\Services\\vhdmp",
+            let _ = registry.set_dword(
+                OfflineHive::System,
+                "ControlSet002\\Services\\vhdmp",
                 "Start",
                 0,
             );
@@ -1011,18 +1181,15 @@ log=0
 
         // 卸载注册表
         println!("[ADVANCED] 卸载离线注册表...");
-        let _ = OfflineRegistry::unload_hive("pc-soft");
-        let _ = OfflineRegistry::unload_hive("pc-sys");
-        if default_loaded {
-            let _ = OfflineRegistry::unload_hive("pc-default");
-        }
+        drop(registry);
 
         println!("[ADVANCED] 高级选项应用完成");
         Ok(())
     }
 
-    /// 生成删除预装UWP应用的PowerShell脚本
-    fn generate_remove_uwp_script() -> String {
+    /// 生成删除预装UWP应用的PowerShell脚本（提示文字使用目标系统语言）
+    fn generate_remove_uwp_script(language: &TargetLanguage) -> String {
+        let (removing, done) = language.uwp_script_messages();
         r#"# LetRecovery - 删除预装UWP应用脚本
 # 此脚本会删除大部分预装的UWP应用，保留必要的系统组件
 
@@ -1072,13 +1239,15 @@ $AppsToRemove = @(
 )
 
 foreach ($App in $AppsToRemove) {
-    Write-Host "正在删除: $App"
+    Write-Host "{{REMOVING}}: $App"
     Get-AppxPackage -Name $App -AllUsers | Remove-AppxPackage -AllUsers -ErrorAction SilentlyContinue
     Get-AppxProvisionedPackage -Online | Where-Object {$_.PackageName -like "*$App*"} | Remove-AppxProvisionedPackage -Online -ErrorAction SilentlyContinue
 }
 
-Write-Host "UWP应用清理完成"
-"#.to_string()
+Write-Host "{{DONE}}"
+"#
+        .replace("{{REMOVING}}", removing)
+        .replace("{{DONE}}", done)
     }
 
     /// 转换 .reg 文件内容以适配离线注册表
@@ -1262,7 +1431,7 @@ Write-Host "UWP应用清理完成"
     /// - `unattend_disabled`: 无人值守选项是否被禁用（由于目标分区已存在配置文件）
     /// - `is_win7`: 当前选择的镜像是否为 Windows 7
     /// - `is_uefi_mode`: 当前安装模式是否为 UEFI
-    pub fn show_ui(&mut self, ui: &mut egui::Ui, hardware_info: Option<&HardwareInfo>, unattend_disabled: bool, is_win7: bool, is_uefi_mode: bool) {
+    pub fn show_ui(&mut self, ui: &mut egui::Ui, hardware_info: Option<&HardwareInfo>, unattend_disabled: bool, is_win7: bool, is_uefi_mode: bool, recent_driver_dirs: &[String], driver_packs: &[DriverPack]) {
         egui::ScrollArea::vertical().show(ui, |ui| {
             // ============ Win7 专用选项（仅当选择Win7镜像时显示）============
             if is_win7 {
@@ -1414,9 +1583,20 @@ Write-Host "UWP应用清理完成"
                 unattend_disabled,
                 "此选项依赖无人值守配置，由于目标分区已存在配置文件而被禁用"
             );
+
+            ui.horizontal(|ui| {
+                ui.label("Win11 硬件要求:");
+                let hint = "写入 LabConfig，在不满足 Windows 11 要求的电脑上安装时不会卡在 OOBE";
+                ui.checkbox(&mut self.bypass_tpm_check, "绕过TPM检查").on_hover_text(hint);
+                ui.checkbox(&mut self.bypass_secure_boot_check, "绕过安全启动检查").on_hover_text(hint);
+                ui.checkbox(&mut self.bypass_ram_check, "绕过内存检查").on_hover_text(hint);
+            });
             
             ui.checkbox(&mut self.disable_windows_update, "禁用Windows更新");
-            ui.checkbox(&mut self.disable_windows_defender, "禁用Windows安全中心");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.disable_windows_defender, "禁用Windows安全中心");
+                help_button(ui, HelpTopic::DisableDefender);
+            });
             ui.checkbox(&mut self.disable_reserved_storage, "禁用系统保留空间");
             ui.checkbox(&mut self.disable_uac, "禁用用户账户控制(UAC)");
             ui.checkbox(&mut self.disable_device_encryption, "禁用自动设备加密");
@@ -1464,12 +1644,16 @@ Write-Host "UWP应用清理完成"
                 }
             });
 
+            self.show_script_hooks(ui);
+            self.show_health_check(ui);
+
             ui.add_space(15.0);
             ui.heading("自定义内容");
             ui.separator();
 
             ui.horizontal(|ui| {
                 ui.checkbox(&mut self.import_custom_drivers, "导入自定义驱动");
+                help_button(ui, HelpTopic::DriverImport);
                 if self.import_custom_drivers {
                     ui.text_edit_singleline(&mut self.custom_drivers_path);
                     if ui.button("浏览...").clicked() {
@@ -1477,6 +1661,9 @@ Write-Host "UWP应用清理完成"
                             self.custom_drivers_path = path.to_string_lossy().to_string();
                         }
                     }
+                    if let Some(path) = recent_menu(ui, recent_driver_dirs) {
+                        self.custom_drivers_path = path;
+                    }
                 }
             });
 
@@ -1485,6 +1672,7 @@ Write-Host "UWP应用清理完成"
                     &mut self.import_storage_controller_drivers,
                     "导入磁盘控制器驱动[Win11/Win10 X64]",
                 );
+                help_button(ui, HelpTopic::DriverImport);
             });
             ui.label(
                 egui::RichText::new(
@@ -1492,6 +1680,12 @@ Write-Host "UWP应用清理完成"
                 )
                 .small(),
             );
+            if self.import_storage_controller_drivers && !driver_packs.is_empty() {
+                self.show_driver_pack_selection(ui, driver_packs);
+            }
+
+            ui.checkbox(&mut self.disable_driver_signature_once, "首次启动禁用驱动签名强制")
+                .on_hover_text("用于需要安装未签名驱动（旧打印机、修改版驱动）的镜像，首次启动完成后自动恢复。Windows 7 及以上改为临时开启测试签名模式，只能加载自签名或测试签名的驱动。开启安全启动时无效");
 
             ui.horizontal(|ui| {
                 ui.checkbox(&mut self.import_registry_file, "导入注册表文件");
@@ -1571,6 +1765,25 @@ Write-Host "UWP应用清理完成"
             if self.custom_volume_label {
                 ui.label("提示: 卷标将在格式化分区时应用");
             }
+
+            ui.checkbox(&mut self.ensure_trim, "确保启用TRIM")
+                .on_hover_text("为 SSD 启用 TRIM，并在首次启动时用 fsutil 确认");
+            ui.horizontal(|ui| {
+                ui.label("磁盘写入缓存:");
+                egui::ComboBox::from_id_salt("write_cache_policy")
+                    .selected_text(self.write_cache_policy.label())
+                    .show_ui(ui, |ui| {
+                        for policy in WriteCachePolicy::ALL {
+                            ui.selectable_value(&mut self.write_cache_policy, policy, policy.label());
+                        }
+                    });
+            });
+            if self.write_cache_policy == WriteCachePolicy::EnabledNoFlush {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    "⚠ 关闭缓冲区刷新后断电可能丢失数据，仅建议用于带掉电保护的设备",
+                );
+            }
         });
     }
 }
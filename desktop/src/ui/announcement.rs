@@ -0,0 +1,79 @@
+use egui;
+
+use crate::app::App;
+use crate::download::announcement::{Announcement, AnnouncementLevel};
+use crate::ui::a11y::icon_button_label;
+
+impl App {
+    /// 显示服务器公告横幅（首页顶部）
+    pub fn show_announcements(&mut self, ui: &mut egui::Ui) {
+        let announcements: Vec<Announcement> = self
+            .config
+            .as_ref()
+            .map(|c| {
+                c.announcements
+                    .iter()
+                    .filter(|a| a.is_active())
+                    .filter(|a| !a.dismissible || !self.app_config.dismissed_announcements.contains(&a.id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut dismissed: Option<String> = None;
+
+        for announcement in &announcements {
+            let (icon, color) = match announcement.level {
+                AnnouncementLevel::Info => ("ℹ", egui::Color32::from_rgb(100, 181, 246)),
+                AnnouncementLevel::Warning => ("⚠", egui::Color32::from_rgb(255, 165, 0)),
+                AnnouncementLevel::Critical => ("⛔", egui::Color32::from_rgb(239, 83, 80)),
+            };
+
+            egui::Frame::group(ui.style())
+                .stroke(egui::Stroke::new(1.0, color))
+                .show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        let title = if announcement.title.is_empty() {
+                            "公告"
+                        } else {
+                            announcement.title.as_str()
+                        };
+                        ui.colored_label(color, egui::RichText::new(format!("{} {}", icon, title)).strong());
+
+                        if announcement.dismissible {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if icon_button_label(ui.small_button("✖"), "不再显示").clicked() {
+                                    dismissed = Some(announcement.id.clone());
+                                }
+                            });
+                        }
+                    });
+                    Self::show_markdown_lines(ui, &announcement.content);
+                });
+            ui.add_space(5.0);
+        }
+
+        if let Some(id) = dismissed {
+            self.app_config.dismiss_announcement(&id);
+        }
+    }
+
+    /// 按行渲染简单 Markdown（标题、列表、整行粗体）
+    pub(crate) fn show_markdown_lines(ui: &mut egui::Ui, content: &str) {
+        for line in content.lines() {
+            let line = line.trim_end();
+            if line.trim().is_empty() {
+                ui.add_space(4.0);
+            } else if let Some(text) = line.strip_prefix("## ").or_else(|| line.strip_prefix("# ")) {
+                ui.label(egui::RichText::new(text).strong().size(16.0));
+            } else if let Some(text) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                ui.label(format!("• {}", text));
+            } else if let Some(text) = line.strip_prefix("**").and_then(|l| l.strip_suffix("**")) {
+                ui.label(egui::RichText::new(text).strong());
+            } else {
+                ui.label(line);
+            }
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! 定时备份设置页
+//!
+//! 编辑 `core::scheduler` 的定时备份设置，保存时注册或删除计划任务，并显示最近一次运行结果。
+//! schtasks 和复制程序文件在后台线程中执行，不阻塞界面。
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::capture_options::{self, CaptureCompression};
+use crate::core::scheduler::{self, BackupSchedule, ScheduleFrequency, ScheduleStatus, ScheduledTask};
+
+/// 后台操作完成后的结果
+struct TaskReply {
+    message: Option<String>,
+    /// 注册成功的设置，写回配置
+    saved: Option<BackupSchedule>,
+    registered: bool,
+    status: Option<ScheduleStatus>,
+}
+
+/// 定时备份设置页状态
+#[derive(Default)]
+pub struct BackupScheduleState {
+    /// 正在编辑的设置（首次打开页面时从配置读取）
+    pub draft: Option<BackupSchedule>,
+    /// 排除规则（每行一条）
+    pub exclusions: String,
+    /// 计划任务是否已注册
+    pub registered: bool,
+    pub status: Option<ScheduleStatus>,
+    pub message: String,
+    task_rx: Option<mpsc::Receiver<TaskReply>>,
+}
+
+impl BackupScheduleState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.task_rx.is_some()
+    }
+
+    fn load(&mut self, schedule: &BackupSchedule) {
+        self.exclusions = schedule.capture.exclusions.join("\n");
+        self.draft = Some(schedule.clone());
+        self.refresh();
+    }
+
+    /// 重新读取计划任务状态和最近一次运行结果
+    fn refresh(&mut self) {
+        self.spawn(|| (None, None));
+    }
+
+    /// 在后台执行操作，完成后重新读取计划任务状态
+    fn spawn(&mut self, job: impl FnOnce() -> (Option<String>, Option<BackupSchedule>) + Send + 'static) {
+        let (tx, rx) = mpsc::channel();
+        self.task_rx = Some(rx);
+        std::thread::spawn(move || {
+            let (message, saved) = job();
+            let _ = tx.send(TaskReply {
+                message,
+                saved,
+                registered: scheduler::is_task_registered(),
+                status: ScheduleStatus::load(),
+            });
+        });
+    }
+
+    /// 检查后台操作结果，返回需要写回配置的设置
+    fn poll(&mut self) -> Option<BackupSchedule> {
+        let reply = self.task_rx.as_ref()?.try_recv().ok()?;
+        self.task_rx = None;
+        self.registered = reply.registered;
+        self.status = reply.status;
+        if let Some(message) = reply.message {
+            self.message = message;
+        }
+        reply.saved
+    }
+}
+
+impl App {
+    pub fn show_backup_schedule(&mut self, ui: &mut egui::Ui) {
+        ui.heading("定时备份");
+        ui.separator();
+
+        if self.backup_schedule.draft.is_none() {
+            let schedule = self.app_config.backup_schedule.clone();
+            self.backup_schedule.load(&schedule);
+        }
+        if let Some(schedule) = self.backup_schedule.poll() {
+            self.app_config.set_backup_schedule(schedule);
+        }
+
+        ui.label("按计划自动把分区追加备份到同一个 WIM 镜像，每次备份都是一个可单独还原的还原点。");
+        ui.label("计划任务以 SYSTEM 身份运行，运行中的系统分区通过卷影副本备份，无需重启。");
+        ui.add_space(10.0);
+
+        self.show_schedule_status(ui);
+        ui.add_space(10.0);
+        ui.separator();
+
+        let locked = self.is_technician_locked();
+        if locked {
+            ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "技师锁定中，无法修改定时备份设置");
+        }
+        ui.add_enabled_ui(!locked, |ui| {
+            self.show_schedule_settings(ui);
+        });
+    }
+
+    fn show_schedule_status(&mut self, ui: &mut egui::Ui) {
+        let state = &mut self.backup_schedule;
+        ui.horizontal(|ui| {
+            ui.label("计划任务:");
+            if state.registered {
+                ui.colored_label(egui::Color32::GREEN, "已注册");
+            } else {
+                ui.colored_label(egui::Color32::GRAY, "未注册");
+            }
+            if state.is_loading() {
+                ui.spinner();
+            } else if ui.small_button("刷新").clicked() {
+                state.refresh();
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("最近一次运行:");
+            match state.status {
+                Some(ref status) => {
+                    ui.label(status.last_run_text());
+                    if status.success {
+                        ui.colored_label(egui::Color32::GREEN, "成功");
+                    } else {
+                        ui.colored_label(egui::Color32::RED, "失败");
+                    }
+                }
+                None => {
+                    ui.label("从未运行");
+                }
+            }
+        });
+        if let Some(ref status) = state.status {
+            ui.indent("schedule_last_message", |ui| {
+                ui.colored_label(egui::Color32::GRAY, &status.message);
+            });
+        }
+    }
+
+    fn show_schedule_settings(&mut self, ui: &mut egui::Ui) {
+        let partitions: Vec<(String, String)> = self
+            .partitions
+            .iter()
+            .filter(|p| !p.letter.is_empty())
+            .map(|p| {
+                let system = if p.is_system_partition { " [系统]" } else { "" };
+                (p.letter.clone(), format!("{} {}{}", p.letter, p.label, system))
+            })
+            .collect();
+
+        let state = &mut self.backup_schedule;
+        let Some(ref mut schedule) = state.draft else {
+            return;
+        };
+
+        ui.add_space(10.0);
+        ui.checkbox(&mut schedule.enabled, "启用定时备份");
+        ui.add_space(5.0);
+
+        egui::Grid::new("backup_schedule_grid")
+            .num_columns(2)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("备份分区:");
+                let selected = partitions
+                    .iter()
+                    .find(|(letter, _)| letter.eq_ignore_ascii_case(&schedule.source_partition))
+                    .map(|(_, text)| text.clone())
+                    .unwrap_or_else(|| "请选择".to_string());
+                egui::ComboBox::from_id_salt("schedule_source_partition")
+                    .selected_text(selected)
+                    .width(250.0)
+                    .show_ui(ui, |ui| {
+                        for (letter, text) in &partitions {
+                            ui.selectable_value(&mut schedule.source_partition, letter.clone(), text);
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("备份镜像:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut schedule.image_path).desired_width(320.0));
+                    if ui.button("浏览...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WIM", &["wim"])
+                            .set_file_name("ScheduledBackup.wim")
+                            .save_file()
+                        {
+                            schedule.image_path = path.to_string_lossy().to_string();
+                        }
+                    }
+                });
+                ui.end_row();
+
+                ui.label("备份周期:");
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("schedule_frequency")
+                        .selected_text(schedule.frequency.label())
+                        .show_ui(ui, |ui| {
+                            for frequency in ScheduleFrequency::ALL {
+                                ui.selectable_value(&mut schedule.frequency, frequency, frequency.label());
+                            }
+                        });
+                    ui.add(
+                        egui::DragValue::new(&mut schedule.interval)
+                            .range(1..=schedule.frequency.max_interval()),
+                    );
+                    ui.label(schedule.frequency.unit());
+                    if schedule.frequency == ScheduleFrequency::Weekly {
+                        let labels: Vec<&str> = BackupSchedule::weekday_labels().collect();
+                        egui::ComboBox::from_id_salt("schedule_weekday")
+                            .selected_text(labels.get(usize::from(schedule.weekday)).copied().unwrap_or(""))
+                            .show_ui(ui, |ui| {
+                                for (i, label) in labels.iter().enumerate() {
+                                    ui.selectable_value(&mut schedule.weekday, i as u8, *label);
+                                }
+                            });
+                    }
+                });
+                ui.end_row();
+
+                ui.label("开始时间:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut schedule.start_time).desired_width(60.0));
+                    ui.label("(HH:MM)");
+                });
+                ui.end_row();
+
+                ui.label("保留数量:");
+                ui.horizontal(|ui| {
+                    ui.add(egui::DragValue::new(&mut schedule.keep_count).range(0..=999));
+                    ui.label("个定时备份（0 表示不限制，超出时删除最旧的，手动备份的分卷不受影响）");
+                });
+                ui.end_row();
+
+                ui.label("压缩方式:");
+                egui::ComboBox::from_id_salt("schedule_compression")
+                    .selected_text(schedule.capture.compression.label())
+                    .show_ui(ui, |ui| {
+                        for compression in CaptureCompression::ALL {
+                            ui.selectable_value(&mut schedule.capture.compression, compression, compression.label());
+                        }
+                    });
+                ui.end_row();
+            });
+
+        ui.add_space(5.0);
+        egui::CollapsingHeader::new("排除的文件和目录")
+            .id_salt("schedule_exclusions")
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut state.exclusions)
+                        .desired_width(500.0)
+                        .desired_rows(6)
+                        .font(egui::TextStyle::Monospace),
+                );
+                if ui.button("恢复默认").clicked() {
+                    state.exclusions = capture_options::DEFAULT_EXCLUSIONS.join("\n");
+                }
+            });
+        schedule.capture.exclusions = state
+            .exclusions
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+
+        let error = if schedule.enabled { schedule.validate().err() } else { None };
+        if let Some(ref e) = error {
+            ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+        }
+
+        ui.add_space(10.0);
+        let mut save = false;
+        let mut run_now = false;
+        let busy = state.is_loading();
+        ui.horizontal(|ui| {
+            save = ui.add_enabled(!busy && error.is_none(), egui::Button::new("保存")).clicked();
+            run_now = ui
+                .add_enabled(!busy && state.registered, egui::Button::new("立即备份"))
+                .on_hover_text("立即运行计划任务，在后台备份")
+                .clicked();
+        });
+
+        if save {
+            let task = ScheduledTask {
+                schedule: schedule.clone(),
+                image_backend: self.app_config.image_backend,
+                io_priority: self.app_config.io_priority,
+            };
+            state.message = "正在保存...".to_string();
+            state.spawn(move || match scheduler::apply_schedule(&task) {
+                Ok(_) => {
+                    let message = if task.schedule.enabled {
+                        "✅ 已保存并注册计划任务".to_string()
+                    } else {
+                        "✅ 已保存，计划任务已删除".to_string()
+                    };
+                    (Some(message), Some(task.schedule))
+                }
+                Err(e) => (Some(format!("❌ {:#}", e)), None),
+            });
+        } else if run_now {
+            state.spawn(|| {
+                let message = match scheduler::run_task_now() {
+                    Ok(_) => "✅ 已开始备份，完成后点击刷新查看结果".to_string(),
+                    Err(e) => format!("❌ {:#}", e),
+                };
+                (Some(message), None)
+            });
+        }
+
+        let state = &self.backup_schedule;
+        if !state.message.is_empty() {
+            ui.add_space(5.0);
+            ui.label(&state.message);
+        }
+    }
+}
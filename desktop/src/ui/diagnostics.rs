@@ -0,0 +1,124 @@
+//! 运行环境检测向导
+//!
+//! 首次启动或存在无法继续的问题时显示检查清单；也可在“关于”页面重新打开。
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::diagnostics::{self, CheckStatus, DiagnosticCheck};
+use crate::tr;
+
+impl App {
+    /// 在后台线程中重新检测（含网络）
+    pub fn start_diagnostics(&mut self) {
+        if self.diagnostic_rx.is_some() {
+            return;
+        }
+        log::info!("[DIAG] 开始重新检测运行环境");
+        let (tx, rx) = mpsc::channel();
+        self.diagnostic_rx = Some(rx);
+        std::thread::spawn(move || {
+            let _ = tx.send(diagnostics::run_all());
+        });
+    }
+
+    /// 显示运行环境检测向导
+    pub fn show_diagnostic_dialog(&mut self, ctx: &egui::Context) {
+        if let Some(ref rx) = self.diagnostic_rx {
+            match rx.try_recv() {
+                Ok(items) => {
+                    self.diagnostic_items = items;
+                    self.diagnostic_rx = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+                Err(mpsc::TryRecvError::Disconnected) => self.diagnostic_rx = None,
+            }
+        }
+
+        let checking = self.diagnostic_rx.is_some();
+        let blocking = diagnostics::has_blocking(&self.diagnostic_items);
+        let problems = diagnostics::problem_count(&self.diagnostic_items);
+        let mut recheck = false;
+        let mut finish = false;
+        let mut exit = false;
+
+        egui::Window::new(tr!("运行环境检测"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .min_width(480.0)
+            .show(ctx, |ui| {
+                if blocking {
+                    ui.colored_label(egui::Color32::RED, tr!("存在无法继续的问题，请按以下方法修复后重新检测。"));
+                } else if problems > 0 {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        tr!("部分功能可能受影响，可按以下方法修复，也可以直接开始使用。"),
+                    );
+                } else {
+                    ui.colored_label(egui::Color32::GREEN, tr!("运行环境检测通过。"));
+                }
+                ui.add_space(10.0);
+
+                for item in &self.diagnostic_items {
+                    let (icon, color) = match item.status {
+                        CheckStatus::Pass => ("✓", egui::Color32::GREEN),
+                        CheckStatus::Warning => ("⚠", egui::Color32::from_rgb(255, 165, 0)),
+                        CheckStatus::Fail => ("✗", egui::Color32::RED),
+                    };
+                    ui.horizontal(|ui| {
+                        ui.colored_label(color, icon);
+                        ui.label(egui::RichText::new(tr!(item.check.name())).strong());
+                        ui.colored_label(egui::Color32::GRAY, &item.detail);
+                    });
+                    if let Some(ref fix) = item.fix {
+                        ui.indent(item.check.name(), |ui| {
+                            ui.label(egui::RichText::new(format!("{} {}", tr!("修复方法:"), tr!(fix))).small());
+                            match item.check {
+                                DiagnosticCheck::Dependencies | DiagnosticCheck::Wimlib => {
+                                    ui.hyperlink_to(tr!("下载完整程序包"), diagnostics::RELEASES_URL);
+                                }
+                                DiagnosticCheck::DiskSpace => {
+                                    if ui.small_button(tr!("磁盘清理")).clicked() {
+                                        diagnostics::open_disk_cleanup();
+                                    }
+                                }
+                                _ => {}
+                            }
+                        });
+                    }
+                    ui.add_space(4.0);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if checking {
+                        ui.spinner();
+                        ui.label(tr!("正在检测..."));
+                    } else if ui.button(tr!("重新检测")).clicked() {
+                        recheck = true;
+                    }
+                    if blocking {
+                        if ui.button(tr!("退出程序")).clicked() {
+                            exit = true;
+                        }
+                    } else if ui.add_enabled(!checking, egui::Button::new(tr!("开始使用"))).clicked() {
+                        finish = true;
+                    }
+                });
+            });
+
+        if recheck {
+            self.start_diagnostics();
+        }
+        if finish {
+            self.show_diagnostic_wizard = false;
+            self.app_config.mark_diagnostic_completed();
+        }
+        if exit {
+            log::warn!("[DIAG] 运行环境存在无法继续的问题，用户选择退出");
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
@@ -1,28 +1,24 @@
 use egui;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 
 use crate::app::App;
-use crate::download::aria2::{Aria2Manager, DownloadProgress, DownloadStatus};
-
-/// 下载控制命令
-#[derive(Debug, Clone)]
-pub enum DownloadCommand {
-    Pause,
-    Resume,
-    Cancel,
-}
+use crate::core::hash::{self, ExpectedHashes, HashMismatch};
+use crate::download::engine::{self, DownloadCommand, DownloadProgress, DownloadStatus, HttpDownload};
+use crate::download::delta::{self, DeltaDownload};
+use crate::download::torrent::{self, TorrentDownload};
 
-/// MD5校验状态
+/// 下载文件哈希校验状态
 #[derive(Debug, Clone, PartialEq)]
-pub enum Md5VerifyState {
+pub enum HashVerifyState {
     /// 未开始
     NotStarted,
-    /// 正在校验
-    Verifying,
+    /// 正在校验（进度 0.0-1.0）
+    Verifying(f32),
     /// 校验通过
     Passed,
     /// 校验失败
-    Failed { expected: String, actual: String },
+    Failed(Vec<HashMismatch>),
     /// 校验出错
     Error(String),
 }
@@ -30,8 +26,8 @@ pub enum Md5VerifyState {
 /// 静态命令发送器（用于跨线程通信）
 static mut DOWNLOAD_CMD_SENDER: Option<mpsc::Sender<DownloadCommand>> = None;
 
-/// MD5校验结果接收器
-static mut MD5_VERIFY_RX: Option<mpsc::Receiver<Md5VerifyState>> = None;
+/// 哈希校验结果接收器
+static mut HASH_VERIFY_RX: Option<mpsc::Receiver<HashVerifyState>> = None;
 
 impl App {
     pub fn show_download_progress(&mut self, ui: &mut egui::Ui) {
@@ -41,12 +37,13 @@ impl App {
         // 从channel接收进度更新
         self.update_download_progress();
         
-        // 检查MD5校验结果
-        self.check_md5_verify_result();
+        // 检查哈希校验结果
+        self.check_hash_verify_result();
 
         // 如果有待下载的任务，开始下载
         if let Some(url) = self.pending_download_url.take() {
             let filename = self.pending_download_filename.take();
+            let chunk_manifest_url = self.pending_chunk_manifest_url.take();
             let save_path = if self.download_save_path.is_empty() {
                 crate::utils::path::get_exe_dir()
                     .join("downloads")
@@ -71,8 +68,30 @@ impl App {
                 }
             }
             
-            // 初始化 aria2 并开始下载
-            self.start_download_task_with_pe_check(&url, &save_path, filename.as_deref(), is_pe_download);
+            // 磁力链接和种子走 BT 下载
+            let is_torrent = torrent::is_torrent_link(&url);
+
+            // 服务器提供分块清单且本地有旧版本时增量下载，否则完整下载
+            let delta_base = chunk_manifest_url.filter(|_| !is_pe_download && !is_torrent).and_then(|manifest_url| {
+                let target_name = filename
+                    .clone()
+                    .unwrap_or_else(|| url.split('/').last().unwrap_or_default().to_string());
+                let base = self.find_delta_base_file(&save_path, &target_name)?;
+                Some((manifest_url, target_name, base))
+            });
+
+            match delta_base {
+                Some((manifest_url, target_name, base)) => {
+                    self.start_delta_download_task(&url, &manifest_url, &save_path, &target_name, base);
+                }
+                None if is_torrent => {
+                    self.start_torrent_download_task(&url, &save_path, filename.as_deref());
+                }
+                None => {
+                    // 开始完整下载
+                    self.start_download_task_with_pe_check(&url, &save_path, filename.as_deref(), is_pe_download);
+                }
+            }
         }
 
         // 显示初始化错误
@@ -103,7 +122,7 @@ impl App {
         // 克隆需要的数据以避免借用冲突
         let progress_clone = self.download_progress.clone();
         let filename_clone = self.current_download_filename.clone();
-        let md5_verify_state = self.md5_verify_state.clone();
+        let hash_verify_state = self.hash_verify_state.clone();
 
         // 显示当前下载状态
         if let Some(progress) = progress_clone {
@@ -113,6 +132,13 @@ impl App {
             if let Some(filename) = &filename_clone {
                 ui.label(format!("文件: {}", filename));
             }
+            if let Some(base) = &self.delta_base_file {
+                ui.label(
+                    egui::RichText::new(format!("增量下载：复用旧版本 {} 中相同的数据块", base))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
 
             // 进度条
             ui.add(
@@ -133,6 +159,10 @@ impl App {
                     "速度: {}/s",
                     Self::format_bytes(progress.download_speed)
                 ));
+                if let Some(peers) = progress.peers {
+                    ui.separator();
+                    ui.label(format!("节点: {}", peers));
+                }
             });
 
             // 状态
@@ -140,6 +170,7 @@ impl App {
                 DownloadStatus::Waiting => "等待中...",
                 DownloadStatus::Active => "下载中...",
                 DownloadStatus::Paused => "已暂停",
+                DownloadStatus::Verifying => "正在校验文件...",
                 DownloadStatus::Complete => "下载完成",
                 DownloadStatus::Error(msg) => msg.as_str(),
             };
@@ -165,38 +196,35 @@ impl App {
                         }
                     }
                     DownloadStatus::Complete => {
-                        // 检查MD5校验状态
-                        match &md5_verify_state {
-                            Md5VerifyState::NotStarted => {
-                                // 检查是否需要进行MD5校验（仅PE下载）
-                                if self.pending_pe_md5.is_some() && self.pe_download_then_action.is_some() {
-                                    ui.label("准备校验文件完整性...");
-                                    
-                                    // 启动异步MD5校验
-                                    let expected_md5 = self.pending_pe_md5.clone().unwrap();
-                                    let filename = self.current_download_filename.clone().unwrap_or_default();
-                                    let file_path = format!("{}\\{}", self.download_save_path, filename);
-                                    
-                                    log::info!("[MD5] 开始校验文件: {}", file_path);
-                                    log::info!("[MD5] 预期MD5: {}", expected_md5);
-                                    
-                                    self.start_md5_verify(&file_path, &expected_md5);
-                                    self.md5_verify_state = Md5VerifyState::Verifying;
-                                } else {
-                                    // 没有MD5校验，直接显示完成
-                                    if self.pending_pe_md5.is_none() && self.pe_download_then_action.is_some() {
-                                        log::warn!("[MD5] PE下载完成但未设置MD5，跳过校验");
+                        // 检查哈希校验状态
+                        match &hash_verify_state {
+                            HashVerifyState::NotStarted if self.download_hash_checked => {
+                                // 下载引擎已校验，哈希不匹配时不会进入完成状态
+                                self.hash_verify_state = HashVerifyState::Passed;
+                            }
+                            HashVerifyState::NotStarted => {
+                                // PE 使用 PE 列表中的 MD5，其他文件使用服务器声明的哈希
+                                match (self.expected_download_hashes(), self.downloaded_file_path()) {
+                                    (Some(expected), Some(file_path)) => {
+                                        ui.label("准备校验文件完整性...");
+                                        log::info!("[HASH] 开始校验文件: {}", file_path.display());
+                                        self.start_hash_verify(file_path, expected);
+                                        self.hash_verify_state = HashVerifyState::Verifying(0.0);
+                                    }
+                                    _ => {
+                                        // 没有声明哈希，直接显示完成
+                                        if self.pending_pe_md5.is_none() && self.pe_download_then_action.is_some() {
+                                            log::warn!("[HASH] PE下载完成但未设置MD5，跳过校验");
+                                        }
+                                        self.hash_verify_state = HashVerifyState::Passed;
                                     }
-                                    self.md5_verify_state = Md5VerifyState::Passed;
                                 }
                             }
-                            Md5VerifyState::Verifying => {
-                                ui.horizontal(|ui| {
-                                    ui.spinner();
-                                    ui.label("正在校验文件完整性，请稍候...");
-                                });
+                            HashVerifyState::Verifying(fraction) => {
+                                ui.spinner();
+                                ui.label(format!("正在校验文件完整性，请稍候... {:.0}%", fraction * 100.0));
                             }
-                            Md5VerifyState::Passed => {
+                            HashVerifyState::Passed => {
                                 ui.colored_label(egui::Color32::GREEN, "✓ 下载完成！");
                                 
                                 // 清除MD5校验值
@@ -300,21 +328,29 @@ impl App {
                                     }
                                 }
                             }
-                            Md5VerifyState::Failed { expected, actual } => {
-                                // MD5校验失败
-                                ui.colored_label(
-                                    egui::Color32::RED, 
-                                    "✗ 文件校验失败！文件可能已损坏。"
-                                );
-                                ui.add_space(5.0);
-                                ui.label(format!("预期MD5: {}", expected));
-                                ui.label(format!("实际MD5: {}", actual));
+                            HashVerifyState::Failed(mismatches) => {
+                                // 哈希校验失败
+                                ui.vertical(|ui| {
+                                    ui.colored_label(
+                                        egui::Color32::RED,
+                                        "✗ 文件校验失败！文件可能已损坏。"
+                                    );
+                                    ui.add_space(5.0);
+                                    for mismatch in mismatches {
+                                        ui.label(format!("预期{}: {}", mismatch.algorithm.name(), mismatch.expected));
+                                        ui.label(format!("实际{}: {}", mismatch.algorithm.name(), mismatch.actual));
+                                    }
+                                });
                                 ui.add_space(10.0);
                                 
-                                // 注意：删除文件的操作已移到 check_md5_verify_result() 中
+                                // 注意：删除文件的操作已移到 check_hash_verify_result() 中
                                 // 避免在 UI 渲染循环中重复执行
                                 
-                                if ui.button("返回重新下载").clicked() {
+                                if ui.button("重新下载").clicked() {
+                                    self.restart_current_download();
+                                }
+                                
+                                if ui.button("返回").clicked() {
                                     // 清理状态
                                     let action = self.pe_download_then_action.take();
                                     self.pending_pe_md5 = None;
@@ -334,7 +370,7 @@ impl App {
                                     }
                                 }
                             }
-                            Md5VerifyState::Error(err) => {
+                            HashVerifyState::Error(err) => {
                                 ui.colored_label(
                                     egui::Color32::from_rgb(255, 165, 0),
                                     format!("⚠ 校验出错: {}", err)
@@ -345,7 +381,7 @@ impl App {
                                 
                                 if ui.button("继续使用").clicked() {
                                     self.pending_pe_md5 = None;
-                                    self.md5_verify_state = Md5VerifyState::Passed;
+                                    self.hash_verify_state = HashVerifyState::Passed;
                                 }
                                 
                                 if ui.button("返回").clicked() {
@@ -409,69 +445,122 @@ impl App {
         }
     }
 
-    /// 启动异步MD5校验
-    fn start_md5_verify(&self, file_path: &str, expected_md5: &str) {
-        let file_path = file_path.to_string();
-        let expected_md5 = expected_md5.to_string();
-        
-        let (tx, rx) = mpsc::channel::<Md5VerifyState>();
+    /// 当前下载文件的预期哈希：PE 使用 PE 列表中的 MD5，其他文件查找服务器哈希配置
+    fn expected_download_hashes(&self) -> Option<ExpectedHashes> {
+        if let Some(md5) = self.pending_pe_md5.as_deref().filter(|_| self.pe_download_then_action.is_some()) {
+            return Some(ExpectedHashes::md5(md5));
+        }
+        let config = self.config.as_ref()?;
+        hash::find_declared(
+            &config.file_hashes,
+            self.current_download.as_deref(),
+            self.current_download_filename.as_deref(),
+        )
+        .cloned()
+    }
+
+    /// 下载完成的文件路径
+    fn downloaded_file_path(&self) -> Option<PathBuf> {
+        self.download_progress
+            .as_ref()
+            .and_then(|p| p.file_path.clone())
+            .or_else(|| {
+                let filename = self.current_download_filename.as_ref()?;
+                Some(Path::new(&self.download_save_path).join(filename))
+            })
+    }
+
+    /// 启动异步哈希校验
+    fn start_hash_verify(&self, file_path: PathBuf, expected: ExpectedHashes) {
+        let (tx, rx) = mpsc::channel::<HashVerifyState>();
         
         unsafe {
-            MD5_VERIFY_RX = Some(rx);
+            HASH_VERIFY_RX = Some(rx);
         }
         
         std::thread::spawn(move || {
-            log::info!("[MD5] 开始计算文件MD5: {}", file_path);
+            let algorithms: Vec<&str> = expected.algorithms().iter().map(|a| a.name()).collect();
+            log::info!("[HASH] 开始计算 {}: {}", algorithms.join("/"), file_path.display());
             let start_time = std::time::Instant::now();
             
-            match md5::calculate_file_md5(&file_path) {
-                Ok(actual_md5) => {
-                    let elapsed = start_time.elapsed();
-                    log::info!("[MD5] 计算完成，耗时: {:?}, 实际MD5: {}", elapsed, actual_md5);
-                    
-                    if actual_md5 == expected_md5 {
-                        log::info!("[MD5] ✓ 校验通过！");
-                        let _ = tx.send(Md5VerifyState::Passed);
-                    } else {
-                        log::error!("[MD5] ✗ 校验失败！预期: {}, 实际: {}", expected_md5, actual_md5);
-                        let _ = tx.send(Md5VerifyState::Failed {
-                            expected: expected_md5,
-                            actual: actual_md5,
-                        });
+            let mut last_percent = 0;
+            let result = hash::verify_file(&file_path, &expected, |done, total| {
+                let percent = if total > 0 { done * 100 / total } else { 100 };
+                if percent != last_percent {
+                    last_percent = percent;
+                    let _ = tx.send(HashVerifyState::Verifying(percent as f32 / 100.0));
+                }
+            });
+            
+            match result {
+                Ok(mismatches) if mismatches.is_empty() => {
+                    log::info!("[HASH] ✓ 校验通过！耗时: {:?}", start_time.elapsed());
+                    let _ = tx.send(HashVerifyState::Passed);
+                }
+                Ok(mismatches) => {
+                    for mismatch in &mismatches {
+                        log::error!(
+                            "[HASH] ✗ {} 校验失败！预期: {}, 实际: {}",
+                            mismatch.algorithm.name(),
+                            mismatch.expected,
+                            mismatch.actual
+                        );
                     }
+                    let _ = tx.send(HashVerifyState::Failed(mismatches));
                 }
                 Err(e) => {
-                    log::error!("[MD5] 计算出错: {}", e);
-                    let _ = tx.send(Md5VerifyState::Error(e.to_string()));
+                    log::error!("[HASH] 计算出错: {:#}", e);
+                    let _ = tx.send(HashVerifyState::Error(format!("{:#}", e)));
                 }
             }
         });
     }
 
-    /// 检查MD5校验结果
-    fn check_md5_verify_result(&mut self) {
+    /// 检查哈希校验结果
+    pub(crate) fn check_hash_verify_result(&mut self) {
+        let mut finished = false;
         unsafe {
-            if let Some(ref rx) = MD5_VERIFY_RX {
-                if let Ok(state) = rx.try_recv() {
+            if let Some(ref rx) = HASH_VERIFY_RX {
+                while let Ok(state) = rx.try_recv() {
+                    finished = !matches!(state, HashVerifyState::Verifying(_));
                     // 如果校验失败，在状态更新时删除文件（只执行一次）
-                    if let Md5VerifyState::Failed { .. } = &state {
-                        let filename = self.current_download_filename.clone().unwrap_or_default();
-                        let file_path = format!("{}\\{}", self.download_save_path, filename);
-                        if let Err(e) = std::fs::remove_file(&file_path) {
-                            log::warn!("[MD5] 删除校验失败的文件时出错: {} - {}", file_path, e);
-                        } else {
-                            log::info!("[MD5] 已删除校验失败的文件: {}", file_path);
+                    if let HashVerifyState::Failed(_) = &state {
+                        if let Some(file_path) = self.downloaded_file_path() {
+                            if let Err(e) = std::fs::remove_file(&file_path) {
+                                log::warn!("[HASH] 删除校验失败的文件时出错: {} - {}", file_path.display(), e);
+                            } else {
+                                log::info!("[HASH] 已删除校验失败的文件: {}", file_path.display());
+                            }
                         }
                     }
-                    self.md5_verify_state = state;
-                    MD5_VERIFY_RX = None;
+                    self.hash_verify_state = state;
+                    if finished {
+                        break;
+                    }
                 }
             }
+            if finished {
+                HASH_VERIFY_RX = None;
+            }
         }
     }
 
+    /// 重新下载当前文件（保留下载后的操作和校验值）
+    fn restart_current_download(&mut self) {
+        let Some(url) = self.current_download.take() else {
+            return;
+        };
+        log::info!("[HASH] 重新下载: {}", url);
+        self.pending_download_url = Some(url);
+        self.pending_download_filename = self.current_download_filename.take();
+        self.download_progress = None;
+        self.download_progress_rx = None;
+        self.download_gid = None;
+        self.hash_verify_state = HashVerifyState::NotStarted;
+    }
+
     /// 从channel更新下载进度
-    fn update_download_progress(&mut self) {
+    pub(crate) fn update_download_progress(&mut self) {
         if let Some(ref rx) = self.download_progress_rx {
             // 非阻塞接收所有可用的进度更新
             while let Ok(progress) = rx.try_recv() {
@@ -479,20 +568,31 @@ impl App {
                 if self.download_gid.is_none() && !progress.gid.is_empty() {
                     self.download_gid = Some(progress.gid.clone());
                 }
+                // 实际保存的文件名可能与预期不同（BT 任务、服务器指定的文件名）
+                if let Some(path) = &progress.file_path {
+                    self.current_download_filename = path.file_name().map(|n| n.to_string_lossy().to_string());
+                    if self.download_then_install {
+                        self.download_then_install_path = Some(path.to_string_lossy().to_string());
+                    }
+                }
                 self.download_progress = Some(progress);
             }
         }
     }
 
     /// 启动下载任务（带PE检查）
-    /// 
-    /// 优化：URL解析和aria2启动并行执行，大幅减少初始化时间
+    ///
+    /// PE 下载先解析真实下载链接，再由原生分段下载引擎下载
     fn start_download_task_with_pe_check(&mut self, url: &str, save_path: &str, filename: Option<&str>, is_pe_download: bool) {
         self.current_download_filename = filename.map(|s| s.to_string());
         self.current_download = Some(url.to_string());
         self.download_init_error = None;
         self.download_gid = None;
-        self.md5_verify_state = Md5VerifyState::NotStarted;  // 重置MD5校验状态
+        self.hash_verify_state = HashVerifyState::NotStarted;  // 重置哈希校验状态
+        self.delta_base_file = None;
+        // 分段下载在重命名为目标文件前校验哈希
+        let expected = self.expected_download_hashes();
+        self.download_hash_checked = expected.is_some();
 
         // 创建进度通道
         let (progress_tx, progress_rx) = mpsc::channel::<DownloadProgress>();
@@ -500,12 +600,6 @@ impl App {
 
         // 创建控制通道
         let (cmd_tx, cmd_rx) = mpsc::channel::<DownloadCommand>();
-        
-        // 清空旧的下载管理器状态
-        {
-            let mut guard = self.download_manager.lock().unwrap();
-            *guard = None;
-        }
 
         // 克隆需要的数据
         let url = url.to_string();
@@ -517,168 +611,206 @@ impl App {
 
         // 在后台线程中执行下载
         std::thread::spawn(move || {
-            // 创建新的tokio运行时
-            let rt = match tokio::runtime::Runtime::new() {
-                Ok(rt) => rt,
+            // 解析PE下载URL（如果需要）
+            let (final_url, headers) = if is_pe_download {
+                log::info!("[下载] 检测到PE下载，开始解析下载链接");
+                match crate::download::pe_url_resolver::resolve_pe_download_url_blocking(&url) {
+                    Ok(result) => {
+                        log::info!("[下载] PE下载链接解析成功: {}", result.download_url);
+                        log::info!("[下载] 解析到的headers数量: {}", result.headers.len());
+                        for (i, h) in result.headers.iter().enumerate() {
+                            let header_name = h.split(':').next().unwrap_or("Unknown");
+                            log::info!("[下载] 接收到Header[{}]: {}", i, header_name);
+                        }
+                        (result.download_url, result.headers)
+                    }
+                    Err(e) => {
+                        log::warn!("[下载] PE下载链接解析失败: {}，使用原始链接", e);
+                        (url.clone(), Vec::new())
+                    }
+                }
+            } else {
+                (url.clone(), Vec::new())
+            };
+
+            let task = HttpDownload {
+                url: &final_url,
+                headers: &headers,
+                save_dir: Path::new(&save_path),
+                filename: filename.as_deref(),
+                connections: engine::DEFAULT_CONNECTIONS,
+                expected: expected.as_ref(),
+                progress_tx: &progress_tx,
+                cmd_rx: &cmd_rx,
+            };
+            let progress = match task.run() {
+                Ok(path) => {
+                    let total = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    DownloadProgress {
+                        gid: String::new(),
+                        completed_length: total,
+                        total_length: total,
+                        download_speed: 0,
+                        percentage: 100.0,
+                        status: DownloadStatus::Complete,
+                        peers: None,
+                        file_path: Some(path),
+                    }
+                }
                 Err(e) => {
-                    let _ = progress_tx.send(DownloadProgress {
+                    log::warn!("[下载] 失败: {:#}", e);
+                    DownloadProgress {
                         gid: String::new(),
                         completed_length: 0,
                         total_length: 0,
                         download_speed: 0,
                         percentage: 0.0,
-                        status: DownloadStatus::Error(format!("创建运行时失败: {}", e)),
-                    });
-                    return;
+                        status: DownloadStatus::Error(format!("{:#}", e)),
+                        peers: None,
+                        file_path: None,
+                    }
                 }
             };
+            let _ = progress_tx.send(progress);
+        });
+    }
 
-            rt.block_on(async move {
-                let init_start = std::time::Instant::now();
-                log::info!("[下载] 开始并行初始化...");
-                
-                // ===== 核心优化：并行执行URL解析和aria2启动 =====
-                let url_for_resolve = url.clone();
-                
-                // 任务1：解析PE下载URL（如果需要）
-                let url_resolve_task = async {
-                    if is_pe_download {
-                        log::info!("[下载] 检测到PE下载，开始解析下载链接");
-                        match crate::download::pe_url_resolver::resolve_pe_download_url(&url_for_resolve).await {
-                            Ok(result) => {
-                                log::info!("[下载] PE下载链接解析成功: {}", result.download_url);
-                                log::info!("[下载] 解析到的headers数量: {}", result.headers.len());
-                                for (i, h) in result.headers.iter().enumerate() {
-                                    let header_name = h.split(':').next().unwrap_or("Unknown");
-                                    log::info!("[下载] 接收到Header[{}]: {}", i, header_name);
-                                }
-                                (result.download_url, Some(result.headers))
-                            }
-                            Err(e) => {
-                                log::warn!("[下载] PE下载链接解析失败: {}，使用原始链接", e);
-                                (url_for_resolve.clone(), None)
-                            }
-                        }
-                    } else {
-                        (url_for_resolve.clone(), None)
-                    }
-                };
-
-                // 任务2：启动aria2（与URL解析同时进行）
-                let aria2_start_task = async {
-                    log::info!("[下载] 启动aria2...");
-                    Aria2Manager::start().await
-                };
-
-                // 并行执行两个任务
-                let ((final_url, headers), aria2_result) = tokio::join!(
-                    url_resolve_task,
-                    aria2_start_task
-                );
+    /// 在下载目录和本地镜像列表中查找可复用的旧版本
+    fn find_delta_base_file(&self, save_path: &str, target_name: &str) -> Option<PathBuf> {
+        let mut candidates: Vec<PathBuf> = std::fs::read_dir(save_path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        candidates.extend(
+            self.local_images
+                .iter()
+                .filter(|image| image.exists())
+                .map(|image| PathBuf::from(&image.path)),
+        );
+        delta::pick_base_file(target_name, &candidates)
+    }
 
-                let init_elapsed = init_start.elapsed();
-                log::info!("[下载] 并行初始化完成，总耗时: {:?}", init_elapsed);
+    /// 启动增量下载任务（复用旧版本中相同的数据块）
+    fn start_delta_download_task(&mut self, url: &str, manifest_url: &str, save_path: &str, filename: &str, base: PathBuf) {
+        log::info!("[增量下载] {} 基于旧版本 {}", filename, base.display());
+        self.current_download_filename = Some(filename.to_string());
+        self.current_download = Some(url.to_string());
+        self.download_init_error = None;
+        self.download_gid = None;
+        self.hash_verify_state = HashVerifyState::NotStarted;
+        self.delta_base_file = Some(base.display().to_string());
+        self.download_hash_checked = false;
 
-                // 检查aria2启动结果
-                let aria2 = match aria2_result {
-                    Ok(manager) => manager,
-                    Err(e) => {
-                        let _ = progress_tx.send(DownloadProgress {
-                            gid: String::new(),
-                            completed_length: 0,
-                            total_length: 0,
-                            download_speed: 0,
-                            percentage: 0.0,
-                            status: DownloadStatus::Error(format!("初始化aria2失败: {}", e)),
-                        });
-                        return;
-                    }
-                };
-
-                // 添加下载任务（根据是否有headers选择方法）
-                log::info!("[下载] 准备添加下载任务，检查headers状态...");
-                let gid = match headers {
-                    Some(hdrs) if !hdrs.is_empty() => {
-                        log::info!("[下载] 使用带headers的下载方法，headers数量: {}", hdrs.len());
-                        for (i, h) in hdrs.iter().enumerate() {
-                            let header_name = h.split(':').next().unwrap_or("Unknown");
-                            log::info!("[下载] 传递Header[{}]: {}", i, header_name);
-                        }
-                        aria2.add_download_with_headers(&final_url, &save_path, filename.as_deref(), Some(hdrs)).await
-                    }
-                    Some(_hdrs) => {
-                        log::warn!("[下载] headers为空列表，使用普通下载方法");
-                        aria2.add_download(&final_url, &save_path, filename.as_deref()).await
-                    }
-                    _ => {
-                        log::info!("[下载] 无headers，使用普通下载方法");
-                        aria2.add_download(&final_url, &save_path, filename.as_deref()).await
-                    }
-                };
+        let (progress_tx, progress_rx) = mpsc::channel::<DownloadProgress>();
+        self.download_progress_rx = Some(progress_rx);
+        let (cmd_tx, cmd_rx) = mpsc::channel::<DownloadCommand>();
+        self.store_download_command_sender(cmd_tx);
 
-                let gid = match gid {
-                    Ok(gid) => gid,
-                    Err(e) => {
-                        let _ = progress_tx.send(DownloadProgress {
-                            gid: String::new(),
-                            completed_length: 0,
-                            total_length: 0,
-                            download_speed: 0,
-                            percentage: 0.0,
-                            status: DownloadStatus::Error(format!("添加任务失败: {}", e)),
-                        });
-                        return;
+        let url = url.to_string();
+        let manifest_url = manifest_url.to_string();
+        let target = Path::new(save_path).join(filename);
+
+        std::thread::spawn(move || {
+            let task = DeltaDownload {
+                url: &url,
+                manifest_url: &manifest_url,
+                base_file: &base,
+                target_file: &target,
+                progress_tx: &progress_tx,
+                cmd_rx: &cmd_rx,
+            };
+            let progress = match task.run() {
+                Ok(stats) => {
+                    let total = stats.reused_bytes + stats.downloaded_bytes;
+                    DownloadProgress {
+                        gid: String::new(),
+                        completed_length: total,
+                        total_length: total,
+                        download_speed: 0,
+                        percentage: 100.0,
+                        status: DownloadStatus::Complete,
+                        peers: None,
+                        file_path: Some(target.clone()),
                     }
-                };
-
-                // 定期获取进度并发送，同时监听控制命令
-                loop {
-                    // 处理控制命令（非阻塞）
-                    while let Ok(cmd) = cmd_rx.try_recv() {
-                        match cmd {
-                            DownloadCommand::Pause => {
-                                let _ = aria2.pause(&gid).await;
-                            }
-                            DownloadCommand::Resume => {
-                                let _ = aria2.resume(&gid).await;
-                            }
-                            DownloadCommand::Cancel => {
-                                let _ = aria2.cancel(&gid).await;
-                                return;
-                            }
-                        }
+                }
+                Err(e) => {
+                    log::warn!("[增量下载] 失败: {:#}", e);
+                    DownloadProgress {
+                        gid: String::new(),
+                        completed_length: 0,
+                        total_length: 0,
+                        download_speed: 0,
+                        percentage: 0.0,
+                        status: DownloadStatus::Error(format!("增量下载失败: {:#}", e)),
+                        peers: None,
+                        file_path: None,
                     }
+                }
+            };
+            let _ = progress_tx.send(progress);
+        });
+    }
 
-                    // 获取进度
-                    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    /// 启动 BT 下载任务（磁力链接或 .torrent）
+    fn start_torrent_download_task(&mut self, uri: &str, save_path: &str, filename: Option<&str>) {
+        log::info!("[BT] 开始下载: {}", uri);
+        self.current_download_filename = filename.map(|s| s.to_string()).or_else(|| torrent::magnet_display_name(uri));
+        self.current_download = Some(uri.to_string());
+        self.download_init_error = None;
+        self.download_gid = None;
+        self.hash_verify_state = HashVerifyState::NotStarted;
+        self.delta_base_file = None;
+        self.download_hash_checked = false;
 
-                    match aria2.get_status(&gid).await {
-                        Ok(progress) => {
-                            let is_complete = progress.status == DownloadStatus::Complete;
-                            let is_error = matches!(progress.status, DownloadStatus::Error(_));
+        let (progress_tx, progress_rx) = mpsc::channel::<DownloadProgress>();
+        self.download_progress_rx = Some(progress_rx);
+        let (cmd_tx, cmd_rx) = mpsc::channel::<DownloadCommand>();
+        self.store_download_command_sender(cmd_tx);
 
-                            if progress_tx.send(progress).is_err() {
-                                break; // 接收端已关闭
-                            }
+        let uri = uri.to_string();
+        let save_path = PathBuf::from(save_path);
 
-                            if is_complete || is_error {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            let _ = progress_tx.send(DownloadProgress {
-                                gid: gid.clone(),
-                                completed_length: 0,
-                                total_length: 0,
-                                download_speed: 0,
-                                percentage: 0.0,
-                                status: DownloadStatus::Error(format!("获取状态失败: {}", e)),
-                            });
-                            break;
-                        }
+        std::thread::spawn(move || {
+            let task = TorrentDownload {
+                uri: &uri,
+                save_dir: &save_path,
+                progress_tx: &progress_tx,
+                cmd_rx: &cmd_rx,
+            };
+            let progress = match task.run() {
+                Ok(path) => {
+                    let total = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    DownloadProgress {
+                        gid: String::new(),
+                        completed_length: total,
+                        total_length: total,
+                        download_speed: 0,
+                        percentage: 100.0,
+                        status: DownloadStatus::Complete,
+                        peers: None,
+                        file_path: Some(path),
                     }
                 }
-            });
+                Err(e) => {
+                    log::warn!("[BT] 失败: {:#}", e);
+                    DownloadProgress {
+                        gid: String::new(),
+                        completed_length: 0,
+                        total_length: 0,
+                        download_speed: 0,
+                        percentage: 0.0,
+                        status: DownloadStatus::Error(format!("{:#}", e)),
+                        peers: None,
+                        file_path: None,
+                    }
+                }
+            };
+            let _ = progress_tx.send(progress);
         });
     }
 
@@ -710,13 +842,13 @@ impl App {
         }
     }
 
-    fn cancel_current_download(&mut self) {
+    pub(crate) fn cancel_current_download(&mut self) {
         unsafe {
             if let Some(ref sender) = DOWNLOAD_CMD_SENDER {
                 let _ = sender.send(DownloadCommand::Cancel);
             }
             DOWNLOAD_CMD_SENDER = None;
-            MD5_VERIFY_RX = None;
+            HASH_VERIFY_RX = None;
         }
 
         // 先获取待执行操作
@@ -755,11 +887,13 @@ impl App {
         self.soft_download_then_run = false;
         self.soft_download_then_run_path = None;
         self.pending_pe_md5 = None;
-        self.md5_verify_state = Md5VerifyState::NotStarted;
+        self.hash_verify_state = HashVerifyState::NotStarted;
+        self.delta_base_file = None;
+        self.download_hash_checked = false;
         
         unsafe {
             DOWNLOAD_CMD_SENDER = None;
-            MD5_VERIFY_RX = None;
+            HASH_VERIFY_RX = None;
         }
     }
 
@@ -780,234 +914,3 @@ impl App {
         }
     }
 }
-
-/// MD5计算模块（纯Rust实现，无外部依赖）
-mod md5 {
-    use std::io::Read;
-    use std::path::Path;
-    
-    /// MD5上下文
-    pub struct Md5Context {
-        state: [u32; 4],
-        count: [u32; 2],
-        buffer: [u8; 64],
-    }
-    
-    impl Md5Context {
-        pub fn new() -> Self {
-            Self {
-                state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
-                count: [0, 0],
-                buffer: [0u8; 64],
-            }
-        }
-        
-        pub fn update(&mut self, input: &[u8]) {
-            let input_len = input.len();
-            let mut index = ((self.count[0] >> 3) & 0x3F) as usize;
-            
-            self.count[0] = self.count[0].wrapping_add((input_len as u32) << 3);
-            if self.count[0] < (input_len as u32) << 3 {
-                self.count[1] = self.count[1].wrapping_add(1);
-            }
-            self.count[1] = self.count[1].wrapping_add((input_len as u32) >> 29);
-            
-            let part_len = 64 - index;
-            let mut i = 0;
-            
-            if input_len >= part_len {
-                self.buffer[index..64].copy_from_slice(&input[..part_len]);
-                let block: [u8; 64] = self.buffer;
-                self.transform(&block);
-                
-                i = part_len;
-                while i + 63 < input_len {
-                    let block: [u8; 64] = input[i..i + 64].try_into().unwrap();
-                    self.transform(&block);
-                    i += 64;
-                }
-                index = 0;
-            }
-            
-            self.buffer[index..index + (input_len - i)].copy_from_slice(&input[i..]);
-        }
-        
-        pub fn finalize(mut self) -> [u8; 16] {
-            let bits: [u8; 8] = [
-                self.count[0] as u8,
-                (self.count[0] >> 8) as u8,
-                (self.count[0] >> 16) as u8,
-                (self.count[0] >> 24) as u8,
-                self.count[1] as u8,
-                (self.count[1] >> 8) as u8,
-                (self.count[1] >> 16) as u8,
-                (self.count[1] >> 24) as u8,
-            ];
-            
-            let index = ((self.count[0] >> 3) & 0x3F) as usize;
-            let pad_len = if index < 56 { 56 - index } else { 120 - index };
-            
-            let mut padding = [0u8; 64];
-            padding[0] = 0x80;
-            self.update(&padding[..pad_len]);
-            self.update(&bits);
-            
-            let mut digest = [0u8; 16];
-            for (i, &s) in self.state.iter().enumerate() {
-                digest[i * 4] = s as u8;
-                digest[i * 4 + 1] = (s >> 8) as u8;
-                digest[i * 4 + 2] = (s >> 16) as u8;
-                digest[i * 4 + 3] = (s >> 24) as u8;
-            }
-            digest
-        }
-        
-        fn transform(&mut self, block: &[u8; 64]) {
-            let mut a = self.state[0];
-            let mut b = self.state[1];
-            let mut c = self.state[2];
-            let mut d = self.state[3];
-            
-            let mut x = [0u32; 16];
-            for i in 0..16 {
-                x[i] = u32::from_le_bytes([
-                    block[i * 4],
-                    block[i * 4 + 1],
-                    block[i * 4 + 2],
-                    block[i * 4 + 3],
-                ]);
-            }
-            
-            // Round 1
-            macro_rules! ff {
-                ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
-                    $a = $a.wrapping_add(($b & $c) | (!$b & $d))
-                        .wrapping_add($x)
-                        .wrapping_add($ac);
-                    $a = $a.rotate_left($s).wrapping_add($b);
-                };
-            }
-            
-            ff!(a, b, c, d, x[0], 7, 0xd76aa478);
-            ff!(d, a, b, c, x[1], 12, 0xe8c7b756);
-            ff!(c, d, a, b, x[2], 17, 0x242070db);
-            ff!(b, c, d, a, x[3], 22, 0xc1bdceee);
-            ff!(a, b, c, d, x[4], 7, 0xf57c0faf);
-            ff!(d, a, b, c, x[5], 12, 0x4787c62a);
-            ff!(c, d, a, b, x[6], 17, 0xa8304613);
-            ff!(b, c, d, a, x[7], 22, 0xfd469501);
-            ff!(a, b, c, d, x[8], 7, 0x698098d8);
-            ff!(d, a, b, c, x[9], 12, 0x8b44f7af);
-            ff!(c, d, a, b, x[10], 17, 0xffff5bb1);
-            ff!(b, c, d, a, x[11], 22, 0x895cd7be);
-            ff!(a, b, c, d, x[12], 7, 0x6b901122);
-            ff!(d, a, b, c, x[13], 12, 0xfd987193);
-            ff!(c, d, a, b, x[14], 17, 0xa679438e);
-            ff!(b, c, d, a, x[15], 22, 0x49b40821);
-            
-            // Round 2
-            macro_rules! gg {
-                ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
-                    $a = $a.wrapping_add(($b & $d) | ($c & !$d))
-                        .wrapping_add($x)
-                        .wrapping_add($ac);
-                    $a = $a.rotate_left($s).wrapping_add($b);
-                };
-            }
-            
-            gg!(a, b, c, d, x[1], 5, 0xf61e2562);
-            gg!(d, a, b, c, x[6], 9, 0xc040b340);
-            gg!(c, d, a, b, x[11], 14, 0x265e5a51);
-            gg!(b, c, d, a, x[0], 20, 0xe9b6c7aa);
-            gg!(a, b, c, d, x[5], 5, 0xd62f105d);
-            gg!(d, a, b, c, x[10], 9, 0x02441453);
-            gg!(c, d, a, b, x[15], 14, 0xd8a1e681);
-            gg!(b, c, d, a, x[4], 20, 0xe7d3fbc8);
-            gg!(a, b, c, d, x[9], 5, 0x21e1cde6);
-            gg!(d, a, b, c, x[14], 9, 0xc33707d6);
-            gg!(c, d, a, b, x[3], 14, 0xf4d50d87);
-            gg!(b, c, d, a, x[8], 20, 0x455a14ed);
-            gg!(a, b, c, d, x[13], 5, 0xa9e3e905);
-            gg!(d, a, b, c, x[2], 9, 0xfcefa3f8);
-            gg!(c, d, a, b, x[7], 14, 0x676f02d9);
-            gg!(b, c, d, a, x[12], 20, 0x8d2a4c8a);
-            
-            // Round 3
-            macro_rules! hh {
-                ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
-                    $a = $a.wrapping_add($b ^ $c ^ $d)
-                        .wrapping_add($x)
-                        .wrapping_add($ac);
-                    $a = $a.rotate_left($s).wrapping_add($b);
-                };
-            }
-            
-            hh!(a, b, c, d, x[5], 4, 0xfffa3942);
-            hh!(d, a, b, c, x[8], 11, 0x8771f681);
-            hh!(c, d, a, b, x[11], 16, 0x6d9d6122);
-            hh!(b, c, d, a, x[14], 23, 0xfde5380c);
-            hh!(a, b, c, d, x[1], 4, 0xa4beea44);
-            hh!(d, a, b, c, x[4], 11, 0x4bdecfa9);
-            hh!(c, d, a, b, x[7], 16, 0xf6bb4b60);
-            hh!(b, c, d, a, x[10], 23, 0xbebfbc70);
-            hh!(a, b, c, d, x[13], 4, 0x289b7ec6);
-            hh!(d, a, b, c, x[0], 11, 0xeaa127fa);
-            hh!(c, d, a, b, x[3], 16, 0xd4ef3085);
-            hh!(b, c, d, a, x[6], 23, 0x04881d05);
-            hh!(a, b, c, d, x[9], 4, 0xd9d4d039);
-            hh!(d, a, b, c, x[12], 11, 0xe6db99e5);
-            hh!(c, d, a, b, x[15], 16, 0x1fa27cf8);
-            hh!(b, c, d, a, x[2], 23, 0xc4ac5665);
-            
-            // Round 4
-            macro_rules! ii {
-                ($a:expr, $b:expr, $c:expr, $d:expr, $x:expr, $s:expr, $ac:expr) => {
-                    $a = $a.wrapping_add($c ^ ($b | !$d))
-                        .wrapping_add($x)
-                        .wrapping_add($ac);
-                    $a = $a.rotate_left($s).wrapping_add($b);
-                };
-            }
-            
-            ii!(a, b, c, d, x[0], 6, 0xf4292244);
-            ii!(d, a, b, c, x[7], 10, 0x432aff97);
-            ii!(c, d, a, b, x[14], 15, 0xab9423a7);
-            ii!(b, c, d, a, x[5], 21, 0xfc93a039);
-            ii!(a, b, c, d, x[12], 6, 0x655b59c3);
-            ii!(d, a, b, c, x[3], 10, 0x8f0ccc92);
-            ii!(c, d, a, b, x[10], 15, 0xffeff47d);
-            ii!(b, c, d, a, x[1], 21, 0x85845dd1);
-            ii!(a, b, c, d, x[8], 6, 0x6fa87e4f);
-            ii!(d, a, b, c, x[15], 10, 0xfe2ce6e0);
-            ii!(c, d, a, b, x[6], 15, 0xa3014314);
-            ii!(b, c, d, a, x[13], 21, 0x4e0811a1);
-            ii!(a, b, c, d, x[4], 6, 0xf7537e82);
-            ii!(d, a, b, c, x[11], 10, 0xbd3af235);
-            ii!(c, d, a, b, x[2], 15, 0x2ad7d2bb);
-            ii!(b, c, d, a, x[9], 21, 0xeb86d391);
-            
-            self.state[0] = self.state[0].wrapping_add(a);
-            self.state[1] = self.state[1].wrapping_add(b);
-            self.state[2] = self.state[2].wrapping_add(c);
-            self.state[3] = self.state[3].wrapping_add(d);
-        }
-    }
-    
-    /// 计算文件的MD5值
-    pub fn calculate_file_md5<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
-        let mut file = std::fs::File::open(path)?;
-        let mut context = Md5Context::new();
-        let mut buffer = [0u8; 65536];  // 64KB缓冲区，提高大文件读取速度
-        
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            context.update(&buffer[..bytes_read]);
-        }
-        
-        let digest = context.finalize();
-        Ok(digest.iter().map(|b| format!("{:02X}", b)).collect())
-    }
-}
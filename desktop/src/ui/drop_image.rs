@@ -0,0 +1,147 @@
+use egui;
+
+use crate::app::{App, Panel};
+use crate::core::image_verify::ImageType;
+use crate::ui::a11y::dialog_keys;
+
+/// 拖入的镜像文件
+#[derive(Debug, Clone)]
+pub struct DroppedImage {
+    pub path: String,
+    /// 根据文件头识别的类型
+    pub image_type: ImageType,
+}
+
+impl App {
+    /// 处理拖放到窗口上的文件
+    pub fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        let Some(path) = dropped.iter().find_map(|f| f.path.clone()) else {
+            return;
+        };
+
+        let is_busy = self.is_installing || self.is_backing_up || self.current_download.is_some();
+        if is_busy {
+            log::info!("[DROP] 正在执行任务，忽略拖入的文件");
+            return;
+        }
+
+        let path = path.to_string_lossy().to_string();
+        let image_type = ImageType::detect(&path);
+        log::info!("[DROP] 拖入文件: {} (识别为 {})", path, image_type);
+
+        if image_type == ImageType::Unknown {
+            self.show_error(&format!("无法识别的镜像文件：\n{}\n\n支持 WIM、ESD、SWM、GHO、ISO 格式", path));
+            return;
+        }
+
+        self.dropped_image = Some(DroppedImage { path, image_type });
+    }
+
+    /// 拖动文件悬停在窗口上时显示提示遮罩
+    pub fn render_drop_overlay(&self, ctx: &egui::Context) {
+        if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+            return;
+        }
+
+        let screen = ctx.screen_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("drop_image_overlay"),
+        ));
+        painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(160));
+        painter.text(
+            screen.center(),
+            egui::Align2::CENTER_CENTER,
+            "松开以打开镜像文件（WIM / ESD / SWM / GHO / ISO）",
+            egui::FontId::proportional(22.0),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// 显示拖入镜像的操作选择对话框
+    pub fn show_dropped_image_dialog(&mut self, ctx: &egui::Context, easy_mode: bool) {
+        let Some(dropped) = self.dropped_image.clone() else {
+            return;
+        };
+
+        let mut should_close = false;
+        let extension_type = ImageType::from_extension(&dropped.path);
+
+        egui::Window::new("打开镜像文件")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let keys = dialog_keys(ui);
+                should_close = keys.escape;
+                ui.label(format!("文件: {}", dropped.path));
+                ui.label(format!("类型: {}", dropped.image_type));
+                if extension_type != dropped.image_type {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        format!("⚠ 扩展名与实际格式（{}）不符，安装前请先修改扩展名", dropped.image_type),
+                    );
+                }
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let can_install = !easy_mode && extension_type == dropped.image_type;
+                    let install = ui
+                        .add_enabled(can_install, egui::Button::new("安装此镜像"))
+                        .on_disabled_hover_text(if easy_mode {
+                            "小白模式下不支持安装本地镜像"
+                        } else {
+                            "扩展名与实际格式不符"
+                        });
+                    if install.clicked() || (can_install && keys.enter) {
+                        self.open_dropped_image_install(&dropped.path);
+                        should_close = true;
+                    }
+
+                    if ui.button("校验镜像").clicked() {
+                        self.open_dropped_image_verify(&dropped.path);
+                        should_close = true;
+                    }
+
+                    if dropped.image_type == ImageType::Gho && ui.button("查看GHO密码").clicked() {
+                        self.open_dropped_gho_password(&dropped.path);
+                        should_close = true;
+                    }
+
+                    if ui.button("取消").clicked() {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if should_close {
+            self.dropped_image = None;
+        }
+    }
+
+    /// 以拖入的镜像进入系统安装页
+    fn open_dropped_image_install(&mut self, path: &str) {
+        self.current_panel = Panel::SystemInstall;
+        self.local_image_path = path.to_string();
+        self.iso_mount_error = None;
+        self.load_image_volumes();
+    }
+
+    /// 以拖入的镜像打开镜像校验工具
+    fn open_dropped_image_verify(&mut self, path: &str) {
+        self.current_panel = Panel::Tools;
+        self.show_image_verify_dialog = true;
+        self.image_verify_file_path = path.to_string();
+        self.image_verify_result = None;
+        self.image_verify_progress = None;
+    }
+
+    /// 以拖入的镜像打开GHO密码查看工具
+    fn open_dropped_gho_password(&mut self, path: &str) {
+        self.current_panel = Panel::Tools;
+        self.show_gho_password_dialog = true;
+        self.gho_password_file_path = path.to_string();
+        self.gho_password_result = None;
+    }
+}
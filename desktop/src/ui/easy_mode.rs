@@ -3,8 +3,12 @@
 
 use egui;
 
-use crate::app::{App, EasyModeLogoState, Panel};
+use crate::app::{App, EasyModeLogoState, EasyModeOptions, EasyModeStep, Panel};
 use crate::download::config::EasyModeSystem;
+use crate::ui::a11y::icon_button_label;
+
+/// 小白模式目标分区最小容量（MB）
+const EASY_MODE_MIN_PARTITION_MB: u64 = 20 * 1024;
 
 /// Logo加载结果
 pub struct LogoLoadResult {
@@ -13,7 +17,7 @@ pub struct LogoLoadResult {
 }
 
 impl App {
-    /// 显示小白模式系统安装界面
+    /// 显示小白模式系统安装界面（分步向导）
     pub fn show_easy_mode_install(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
         // 检查ISO挂载状态和镜像信息加载状态（支持小白模式自动安装）
         self.check_iso_mount_status();
@@ -29,7 +33,7 @@ impl App {
                     "💡 您可以在\"关于\"页面中关闭小白模式",
                 );
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.small_button("×").clicked() {
+                    if icon_button_label(ui.small_button("×"), "关闭提示").clicked() {
                         self.app_config.dismiss_easy_mode_settings_tip();
                     }
                 });
@@ -66,7 +70,132 @@ impl App {
             return;
         }
         
+        // 步骤指示器
+        self.draw_easy_mode_step_indicator(ui);
+        ui.separator();
         ui.add_space(10.0);
+        
+        // 当前步骤的校验结果（用于禁用"下一步"并提示原因）
+        let validation = self.validate_easy_mode_step(&systems);
+        
+        // 底部导航栏高度预留
+        let content_height = (ui.available_height() - 60.0).max(100.0);
+        
+        egui::ScrollArea::vertical()
+            .id_salt("easy_mode_step_content")
+            .max_height(content_height)
+            .show(ui, |ui| match self.easy_mode_step {
+                EasyModeStep::SelectImage => self.show_easy_mode_select_image(ui, ctx, &systems),
+                EasyModeStep::SelectPartition => self.show_easy_mode_select_partition(ui),
+                EasyModeStep::Options => self.show_easy_mode_options(ui),
+                EasyModeStep::Confirm => self.show_easy_mode_confirm(ui, &systems),
+                EasyModeStep::Execute => self.show_easy_mode_execute(ui),
+            });
+        
+        if self.easy_mode_step == EasyModeStep::Execute {
+            return;
+        }
+        
+        // 导航按钮
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.easy_mode_step != EasyModeStep::SelectImage, egui::Button::new("上一步"))
+                .clicked()
+            {
+                self.easy_mode_step = self.easy_mode_step.prev();
+            }
+            
+            let is_confirm = self.easy_mode_step == EasyModeStep::Confirm;
+            let next_button = if is_confirm {
+                egui::Button::new(egui::RichText::new("开始安装").color(egui::Color32::WHITE))
+                    .fill(egui::Color32::from_rgb(200, 60, 60))
+            } else {
+                egui::Button::new("下一步")
+            };
+            
+            if ui.add_enabled(validation.is_ok(), next_button).clicked() {
+                if is_confirm {
+                    self.start_easy_mode_install(&systems);
+                } else {
+                    self.easy_mode_step = self.easy_mode_step.next();
+                }
+            }
+            
+            if let Err(reason) = &validation {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), format!("⚠ {}", reason));
+            }
+        });
+    }
+    
+    /// 绘制步骤指示器
+    fn draw_easy_mode_step_indicator(&self, ui: &mut egui::Ui) {
+        let current = self.easy_mode_step.index();
+        ui.horizontal_wrapped(|ui| {
+            for (i, step) in EasyModeStep::ALL.iter().enumerate() {
+                if i > 0 {
+                    ui.label(egui::RichText::new("→").weak());
+                }
+                let text = format!("{}. {}", i + 1, step.title());
+                if i == current {
+                    ui.colored_label(ui.visuals().selection.stroke.color, egui::RichText::new(text).strong());
+                } else if i < current {
+                    ui.colored_label(egui::Color32::GREEN, format!("✓ {}", text));
+                } else {
+                    ui.label(egui::RichText::new(text).weak());
+                }
+            }
+        });
+    }
+    
+    /// 校验当前步骤是否可以进入下一步
+    fn validate_easy_mode_step(&self, systems: &[(String, EasyModeSystem)]) -> Result<(), String> {
+        match self.easy_mode_step {
+            EasyModeStep::SelectImage => {
+                let system = self.easy_mode_selected_system
+                    .and_then(|idx| systems.get(idx))
+                    .ok_or("请选择要安装的系统")?;
+                self.easy_mode_selected_volume
+                    .and_then(|idx| system.1.volume.get(idx))
+                    .ok_or("请选择系统版本")?;
+                Ok(())
+            }
+            EasyModeStep::SelectPartition => {
+                let partition = self.easy_mode_target_partition
+                    .and_then(|idx| self.partitions.get(idx))
+                    .ok_or("请选择安装位置")?;
+                if partition.total_size_mb < EASY_MODE_MIN_PARTITION_MB {
+                    return Err(format!(
+                        "分区容量不足，至少需要 {} GB",
+                        EASY_MODE_MIN_PARTITION_MB / 1024
+                    ));
+                }
+                // 非系统分区直接安装时会格式化目标分区，程序和下载的镜像不能位于该分区
+                let exe_dir = crate::utils::path::get_exe_dir().to_string_lossy().to_uppercase();
+                if !partition.is_system_partition && exe_dir.starts_with(&partition.letter.to_uppercase()) {
+                    return Err("程序所在分区不能作为安装位置".to_string());
+                }
+                Ok(())
+            }
+            EasyModeStep::Options => Ok(()),
+            EasyModeStep::Confirm => {
+                if self.easy_mode_confirm_checked {
+                    Ok(())
+                } else {
+                    Err("请确认已备份重要文件".to_string())
+                }
+            }
+            EasyModeStep::Execute => Ok(()),
+        }
+    }
+    
+    /// 步骤1：选择镜像
+    fn show_easy_mode_select_image(
+        &mut self,
+        ui: &mut egui::Ui,
+        ctx: &egui::Context,
+        systems: &[(String, EasyModeSystem)],
+    ) {
         ui.label("请选择要安装的系统：");
         ui.add_space(15.0);
         
@@ -87,50 +216,41 @@ impl App {
         
         // 存储需要处理的点击事件
         let mut clicked_system_idx: Option<usize> = None;
-        let mut should_show_confirm = false;
         
-        egui::ScrollArea::vertical()
-            .max_height(ui.available_height() - 50.0)
-            .show(ui, |ui| {
-                // 添加左边距实现居中
-                ui.horizontal(|ui| {
-                    ui.add_space(left_margin);
-                    ui.vertical(|ui| {
-                        ui.horizontal_wrapped(|ui| {
-                            ui.spacing_mut().item_spacing = egui::vec2(spacing, spacing);
-                            
-                            for (idx, (name, system)) in systems.iter().enumerate() {
-                                let is_selected = self.easy_mode_selected_system == Some(idx);
-                                
-                                // 绘制系统卡片并获取交互结果
-                                let (card_clicked, install_clicked) = self.draw_system_card_v2(
-                                    ui,
-                                    ctx,
-                                    idx,
-                                    name,
-                                    system,
-                                    is_selected,
-                                    card_width,
-                                    card_height,
-                                );
-                                
-                                if card_clicked {
-                                    clicked_system_idx = Some(idx);
-                                }
-                                
-                                if install_clicked {
-                                    should_show_confirm = true;
-                                }
-                                
-                                // 每行显示指定数量的卡片后换行
-                                if (idx + 1) % cards_per_row == 0 {
-                                    ui.end_row();
-                                }
-                            }
-                        });
-                    });
+        // 添加左边距实现居中
+        ui.horizontal(|ui| {
+            ui.add_space(left_margin);
+            ui.vertical(|ui| {
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing = egui::vec2(spacing, spacing);
+                    
+                    for (idx, (name, system)) in systems.iter().enumerate() {
+                        let is_selected = self.easy_mode_selected_system == Some(idx);
+                        
+                        // 绘制系统卡片并获取交互结果
+                        let card_clicked = self.draw_system_card_v2(
+                            ui,
+                            ctx,
+                            idx,
+                            name,
+                            system,
+                            is_selected,
+                            card_width,
+                            card_height,
+                        );
+                        
+                        if card_clicked {
+                            clicked_system_idx = Some(idx);
+                        }
+                        
+                        // 每行显示指定数量的卡片后换行
+                        if (idx + 1) % cards_per_row == 0 {
+                            ui.end_row();
+                        }
+                    }
                 });
             });
+        });
         
         // 在循环外处理状态更新
         if let Some(idx) = clicked_system_idx {
@@ -144,19 +264,154 @@ impl App {
                 }
             }
         }
+    }
+    
+    /// 步骤2：选择分区
+    fn show_easy_mode_select_partition(&mut self, ui: &mut egui::Ui) {
+        // 默认选择当前系统分区
+        if self.easy_mode_target_partition.is_none() {
+            self.easy_mode_target_partition = self.partitions.iter().position(|p| p.is_system_partition);
+        }
         
-        if should_show_confirm {
-            self.easy_mode_show_confirm_dialog = true;
+        ui.label("请选择要安装系统的位置（通常为 C 盘）：");
+        ui.add_space(10.0);
+        
+        let mut clicked: Option<usize> = None;
+        for (idx, partition) in self.partitions.iter().enumerate() {
+            let label = if partition.label.is_empty() { "本地磁盘" } else { partition.label.as_str() };
+            let mut text = format!(
+                "{} {}  ({} / 共 {})",
+                partition.letter,
+                label,
+                Self::format_size(partition.free_size_mb),
+                Self::format_size(partition.total_size_mb),
+            );
+            if partition.is_system_partition {
+                text.push_str("  [当前系统]");
+            }
+            if ui
+                .selectable_label(self.easy_mode_target_partition == Some(idx), text)
+                .clicked()
+            {
+                clicked = Some(idx);
+            }
+        }
+        if clicked.is_some() {
+            self.easy_mode_target_partition = clicked;
+        }
+        
+        ui.add_space(10.0);
+        if let Some(partition) = self.easy_mode_target_partition.and_then(|i| self.partitions.get(i)) {
+            if !partition.is_system_partition {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    "⚠ 所选分区不是当前系统分区，安装后需要在启动菜单中选择新系统",
+                );
+            }
+        }
+    }
+    
+    /// 步骤3：安装选项
+    fn show_easy_mode_options(&mut self, ui: &mut egui::Ui) {
+        ui.label("以下为推荐设置，一般无需修改：");
+        ui.add_space(10.0);
+        
+        let options = &mut self.easy_mode_options;
+        ui.checkbox(&mut options.bypass_nro, "OOBE绕过强制联网");
+        ui.checkbox(&mut options.remove_uwp_apps, "删除预装UWP应用");
+        ui.checkbox(&mut options.import_storage_drivers, "导入磁盘控制器驱动");
+        ui.checkbox(&mut options.keep_current_drivers, "自动导入当前驱动");
+        ui.checkbox(&mut options.auto_reboot, "完成后自动重启");
+        
+        ui.add_space(10.0);
+        if ui.button("恢复推荐设置").clicked() {
+            self.easy_mode_options = EasyModeOptions::default();
         }
+    }
+    
+    /// 步骤4：确认
+    fn show_easy_mode_confirm(&mut self, ui: &mut egui::Ui, systems: &[(String, EasyModeSystem)]) {
+        let system = self.easy_mode_selected_system.and_then(|idx| systems.get(idx));
+        let volume_name = system
+            .and_then(|(_, sys)| self.easy_mode_selected_volume.and_then(|idx| sys.volume.get(idx)))
+            .map(|v| v.name.clone())
+            .unwrap_or_default();
+        let partition = self.easy_mode_target_partition.and_then(|idx| self.partitions.get(idx));
+        
+        ui.label(egui::RichText::new("请确认以下安装信息：").strong());
+        ui.add_space(10.0);
         
-        // 显示确认对话框
-        if self.easy_mode_show_confirm_dialog {
-            self.show_easy_mode_confirm_dialog(ctx, &systems);
+        let options = self.easy_mode_options;
+        let yes_no = |v: bool| if v { "是" } else { "否" };
+        egui::Grid::new("easy_mode_summary_grid")
+            .num_columns(2)
+            .spacing([20.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("系统:");
+                ui.strong(system.map(|(name, _)| name.as_str()).unwrap_or("-"));
+                ui.end_row();
+                ui.label("版本:");
+                ui.strong(&volume_name);
+                ui.end_row();
+                ui.label("安装位置:");
+                ui.strong(partition.map(|p| p.letter.as_str()).unwrap_or("-"));
+                ui.end_row();
+                ui.label("OOBE绕过强制联网:");
+                ui.label(yes_no(options.bypass_nro));
+                ui.end_row();
+                ui.label("删除预装UWP应用:");
+                ui.label(yes_no(options.remove_uwp_apps));
+                ui.end_row();
+                ui.label("导入磁盘控制器驱动:");
+                ui.label(yes_no(options.import_storage_drivers));
+                ui.end_row();
+                ui.label("自动导入当前驱动:");
+                ui.label(yes_no(options.keep_current_drivers));
+                ui.end_row();
+                ui.label("完成后自动重启:");
+                ui.label(yes_no(options.auto_reboot));
+                ui.end_row();
+            });
+        
+        ui.add_space(15.0);
+        ui.colored_label(
+            egui::Color32::RED,
+            format!(
+                "⚠ 此操作将清除 {} 盘上的所有数据！",
+                partition.map(|p| p.letter.trim_end_matches(':')).unwrap_or("目标")
+            ),
+        );
+        ui.add_space(5.0);
+        ui.checkbox(&mut self.easy_mode_confirm_checked, "我已备份重要文件，确认继续");
+    }
+    
+    /// 步骤5：执行
+    fn show_easy_mode_execute(&mut self, ui: &mut egui::Ui) {
+        let busy = self.is_installing || self.current_download.is_some() || self.pending_download_url.is_some();
+        if busy {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("正在执行安装，请勿关闭程序...");
+            });
+        } else {
+            ui.label("安装任务已结束。");
+            ui.add_space(10.0);
+            if ui.button("返回首页").clicked() {
+                self.reset_easy_mode_wizard();
+            }
         }
     }
     
+    /// 重置小白模式向导
+    pub fn reset_easy_mode_wizard(&mut self) {
+        self.easy_mode_step = EasyModeStep::SelectImage;
+        self.easy_mode_target_partition = None;
+        self.easy_mode_options = EasyModeOptions::default();
+        self.easy_mode_confirm_checked = false;
+    }
+    
     /// 绘制系统选择卡片（新版本，正确处理交互）
-    /// 返回 (卡片被点击, 安装按钮被点击)
+    /// 返回卡片是否被点击
     fn draw_system_card_v2(
         &mut self,
         ui: &mut egui::Ui,
@@ -167,9 +422,8 @@ impl App {
         is_selected: bool,
         width: f32,
         _height: f32,
-    ) -> (bool, bool) {
+    ) -> bool {
         let mut card_clicked = false;
-        let mut install_clicked = false;
         
         // 使用 egui 原版风格的 Frame
         let frame = if is_selected {
@@ -232,7 +486,7 @@ impl App {
                     ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
                 }
                 
-                // 下半部分：仅在选中时显示版本选择和安装按钮
+                // 下半部分：仅在选中时显示版本选择
                 if is_selected {
                     ui.add_space(5.0);
                     ui.separator();
@@ -260,23 +514,6 @@ impl App {
                                         }
                                     }
                                 });
-                            
-                            ui.add_space(12.0);
-                            
-                            // 安装按钮 - 检查是否选择了版本
-                            let can_install = self.easy_mode_selected_volume.is_some();
-                            
-                            let button = egui::Button::new(
-                                egui::RichText::new("开始安装").strong()
-                            );
-                            
-                            if ui.add_enabled(can_install, button).clicked() {
-                                install_clicked = true;
-                            }
-                            
-                            if !can_install {
-                                ui.label(egui::RichText::new("请先选择版本").small().weak());
-                            }
                         } else {
                             ui.label(egui::RichText::new("无可用版本").weak());
                         }
@@ -285,11 +522,11 @@ impl App {
             });
         });
         
-        (card_clicked, install_clicked)
+        card_clicked
     }
     
     /// 绘制系统Logo
-    fn draw_system_logo(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, logo_url: &str, size: f32) {
+    pub(crate) fn draw_system_logo(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, logo_url: &str, size: f32) {
         // 首先检查是否是内嵌 Logo 标识符
         if crate::ui::EmbeddedLogoType::is_embedded_logo_identifier(logo_url) {
             // 获取当前是否为深色模式
@@ -403,143 +640,17 @@ impl App {
         }
     }
     
-    /// 显示小白模式确认对话框
-    fn show_easy_mode_confirm_dialog(
-        &mut self,
-        ctx: &egui::Context,
-        systems: &[(String, EasyModeSystem)],
-    ) {
-        let selected_system = self.easy_mode_selected_system
-            .and_then(|idx| systems.get(idx));
-        let selected_volume = selected_system
-            .and_then(|(_, sys)| {
-                self.easy_mode_selected_volume.and_then(|idx| sys.volume.get(idx))
-            });
-        
-        if selected_system.is_none() || selected_volume.is_none() {
-            self.easy_mode_show_confirm_dialog = false;
+    /// 开始小白模式安装（向导确认后执行）
+    fn start_easy_mode_install(&mut self, systems: &[(String, EasyModeSystem)]) {
+        let Some((system_name, system)) = self.easy_mode_selected_system.and_then(|idx| systems.get(idx)) else {
             return;
-        }
-        
-        let (system_name, system) = selected_system.unwrap();
-        let volume = selected_volume.unwrap();
-        
-        let window_width = 420.0;
+        };
+        let Some(volume) = self.easy_mode_selected_volume.and_then(|idx| system.volume.get(idx)) else {
+            return;
+        };
+        let volume_number = volume.number;
+        let options = self.easy_mode_options;
         
-        egui::Window::new("确认重装系统")
-            .collapsible(false)
-            .resizable(false)
-            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
-            .fixed_size([window_width, 320.0])
-            .show(ctx, |ui| {
-                ui.add_space(10.0);
-                
-                // 警告标题
-                ui.horizontal(|ui| {
-                    let text = egui::RichText::new("⚠️ 警告").size(20.0).strong();
-                    let text_width = 80.0;
-                    ui.add_space((window_width - text_width) / 2.0 - 16.0);
-                    ui.colored_label(egui::Color32::from_rgb(255, 193, 7), text);
-                });
-                
-                ui.add_space(15.0);
-                
-                // 安装信息
-                ui.horizontal(|ui| {
-                    let text = format!("您即将安装: {} - {}", system_name, volume.name);
-                    let text_width = text.len() as f32 * 7.0;
-                    ui.add_space((window_width - text_width) / 2.0 - 16.0);
-                    ui.label(&text);
-                });
-                
-                ui.add_space(10.0);
-                
-                // 警告文字
-                ui.horizontal(|ui| {
-                    let text = "此操作将清除 C 盘（系统盘）上的所有数据！";
-                    let text_width = 280.0;
-                    ui.add_space((window_width - text_width) / 2.0 - 16.0);
-                    ui.colored_label(egui::Color32::RED, text);
-                });
-                
-                ui.add_space(5.0);
-                
-                // 备份提示
-                ui.horizontal(|ui| {
-                    let text = "请确保已备份重要文件。";
-                    let text_width = 150.0;
-                    ui.add_space((window_width - text_width) / 2.0 - 16.0);
-                    ui.label(text);
-                });
-                
-                ui.add_space(15.0);
-                ui.separator();
-                ui.add_space(10.0);
-                
-                // 优化标题
-                ui.horizontal(|ui| {
-                    let text_width = 130.0;
-                    ui.add_space((window_width - text_width) / 2.0 - 16.0);
-                    ui.label(egui::RichText::new("将自动应用以下优化：").small().strong());
-                });
-                
-                ui.add_space(5.0);
-                
-                // 优化选项 - Grid宽度约280
-                ui.horizontal(|ui| {
-                    let grid_width = 280.0;
-                    ui.add_space((window_width - grid_width) / 2.0 - 16.0);
-                    egui::Grid::new("easy_mode_options_grid")
-                        .num_columns(2)
-                        .spacing([20.0, 4.0])
-                        .show(ui, |ui| {
-                            ui.label(egui::RichText::new("• OOBE绕过强制联网").small());
-                            ui.label(egui::RichText::new("• 删除预装UWP应用").small());
-                            ui.end_row();
-                            ui.label(egui::RichText::new("• 导入磁盘控制器驱动").small());
-                            ui.label(egui::RichText::new("• 自动导入当前驱动").small());
-                            ui.end_row();
-                        });
-                });
-                
-                ui.add_space(20.0);
-                
-                // 按钮 - 两个按钮约150宽
-                ui.horizontal(|ui| {
-                    let buttons_width = 150.0;
-                    ui.add_space((window_width - buttons_width) / 2.0 - 16.0);
-                    
-                    if ui.button("取消").clicked() {
-                        self.easy_mode_show_confirm_dialog = false;
-                    }
-                    
-                    ui.add_space(20.0);
-                    
-                    let confirm_btn = egui::Button::new(
-                        egui::RichText::new("确认安装").color(egui::Color32::WHITE)
-                    ).fill(egui::Color32::from_rgb(200, 60, 60));
-                    
-                    if ui.add(confirm_btn).clicked() {
-                        self.easy_mode_show_confirm_dialog = false;
-                        self.start_easy_mode_install(
-                            system_name,
-                            system,
-                            volume.number,
-                        );
-                    }
-                });
-                
-                ui.add_space(10.0);
-            });
-    }
-    
-    /// 开始小白模式安装
-    fn start_easy_mode_install(
-        &mut self,
-        system_name: &str,
-        system: &EasyModeSystem,
-        volume_number: u32,
-    ) {
         log::info!("[EASY MODE] 开始安装 {} 分卷 {}", system_name, volume_number);
         
         // 设置安装参数
@@ -548,10 +659,10 @@ impl App {
             .unwrap_or("system.esd")
             .to_string();
         
-        // 设置高级选项（小白模式默认选项）
-        self.advanced_options.bypass_nro = true;  // OOBE绕过强制联网
-        self.advanced_options.remove_uwp_apps = true;  // 删除预装UWP应用
-        self.advanced_options.import_storage_controller_drivers = true;  // 导入磁盘控制器驱动
+        // 设置高级选项（小白模式向导选项）
+        self.advanced_options.bypass_nro = options.bypass_nro;  // OOBE绕过强制联网
+        self.advanced_options.remove_uwp_apps = options.remove_uwp_apps;  // 删除预装UWP应用
+        self.advanced_options.import_storage_controller_drivers = options.import_storage_drivers;  // 导入磁盘控制器驱动
         self.advanced_options.custom_volume_label = true;  // 自定义卷标
         self.advanced_options.volume_label = "OS".to_string();  // 系统盘卷标设置为"OS"
         
@@ -565,22 +676,28 @@ impl App {
         self.format_partition = true;
         self.repair_boot = true;
         self.unattended_install = true;
-        self.driver_action = crate::app::DriverAction::AutoImport;
-        self.auto_reboot = true;
-        
-        // 选择系统分区
-        let system_partition_idx = self.partitions.iter()
-            .position(|p| p.is_system_partition);
+        self.driver_action = if options.keep_current_drivers {
+            crate::app::DriverAction::AutoImport
+        } else {
+            crate::app::DriverAction::None
+        };
+        self.completion_action = if options.auto_reboot {
+            crate::core::power::CompletionAction::RebootCountdown
+        } else {
+            crate::core::power::CompletionAction::StayInPe
+        };
         
-        if system_partition_idx.is_none() {
-            self.show_error("未找到系统分区，无法进行安装");
+        // 选择目标分区
+        if self.easy_mode_target_partition.and_then(|idx| self.partitions.get(idx)).is_none() {
+            self.show_error("未找到目标分区，无法进行安装");
             return;
         }
         
-        self.selected_partition = system_partition_idx;
+        self.selected_partition = self.easy_mode_target_partition;
         
         // 保存分卷号
         self.install_volume_index = volume_number;
+        self.easy_mode_step = EasyModeStep::Execute;
         
         // 开始下载系统镜像
         let pe_dir = crate::utils::path::get_exe_dir()
@@ -0,0 +1,104 @@
+use egui;
+
+use crate::app::{App, EulaAction};
+use crate::ui::a11y::dialog_keys;
+use crate::core::history::{HistoryEntry, HistoryKind, OperationHistory};
+
+impl App {
+    /// 检查镜像是否需要先接受许可协议
+    ///
+    /// 命中未接受的协议时弹出协议对话框并返回 true，调用方应中止当前操作，
+    /// 用户接受后会重新执行 `action`
+    pub fn require_eula(&mut self, names: &[&str], action: EulaAction) -> bool {
+        let Some(eula) = self
+            .config
+            .as_ref()
+            .and_then(|c| c.find_image_eula(names))
+            .cloned()
+        else {
+            return false;
+        };
+
+        if OperationHistory::has_accepted_eula(&eula.acceptance_key()) {
+            return false;
+        }
+
+        log::info!("[EULA] 镜像需要接受许可协议: {}", eula.title);
+        self.pending_eula = Some((eula, action));
+        self.eula_agree_checked = false;
+        true
+    }
+
+    /// 显示许可协议对话框
+    pub fn show_eula_dialog(&mut self, ctx: &egui::Context) {
+        let Some((eula, action)) = self.pending_eula.clone() else {
+            return;
+        };
+
+        let mut accepted = false;
+        let mut declined = false;
+
+        egui::Window::new(format!("许可协议 - {}", eula.title))
+            .collapsible(false)
+            .resizable(true)
+            .default_width(560.0)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("下载或安装此镜像前，请阅读并接受以下协议：");
+                ui.add_space(5.0);
+
+                egui::ScrollArea::vertical()
+                    .max_height(320.0)
+                    .show(ui, |ui| {
+                        ui.label(&eula.text);
+                    });
+
+                ui.add_space(10.0);
+                ui.checkbox(&mut self.eula_agree_checked, "我已阅读并同意上述协议");
+                ui.add_space(5.0);
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(self.eula_agree_checked, egui::Button::new("接受并继续"))
+                        .clicked()
+                    {
+                        accepted = true;
+                    }
+                    if ui.button("拒绝").clicked() || keys.escape {
+                        declined = true;
+                    }
+                });
+            });
+
+        if declined {
+            log::info!("[EULA] 用户拒绝许可协议: {}", eula.title);
+            self.pending_eula = None;
+        } else if accepted {
+            let target = match action {
+                EulaAction::OnlineDownload(i) | EulaAction::OnlineInstall(i) => self
+                    .config
+                    .as_ref()
+                    .and_then(|c| c.systems.get(i))
+                    .map(|s| s.display_name.clone())
+                    .unwrap_or_default(),
+                EulaAction::LocalInstall => self.local_image_path.clone(),
+            };
+            let entry = HistoryEntry::new(HistoryKind::EulaAccepted, target, eula.acceptance_key());
+            if let Err(e) = OperationHistory::append(&entry) {
+                log::warn!("[EULA] 记录协议接受历史失败: {}", e);
+                self.pending_eula = None;
+                self.show_error(&format!("无法记录许可协议接受状态: {:#}", e));
+                return;
+            }
+
+            log::info!("[EULA] 用户已接受许可协议: {}", eula.acceptance_key());
+            self.pending_eula = None;
+            match action {
+                EulaAction::OnlineDownload(i) => self.start_online_system_download(i),
+                EulaAction::OnlineInstall(i) => self.start_online_system_install(i),
+                EulaAction::LocalInstall => self.start_installation(),
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! 内置帮助
+//!
+//! 各页面选项旁的“?”按钮打开对应主题的帮助窗口。按钮只在 egui 临时数据中记下请求的主题，
+//! 因此不持有 `App` 的子界面（如高级选项）也可以直接调用。
+
+use egui;
+
+use crate::app::App;
+use crate::download::help::HelpTopic;
+use crate::tr;
+use crate::ui::a11y::{dialog_keys, icon_button_label};
+
+fn request_id() -> egui::Id {
+    egui::Id::new("help_topic_request")
+}
+
+/// 选项旁的帮助按钮，点击后打开对应主题
+pub fn help_button(ui: &mut egui::Ui, topic: HelpTopic) {
+    let label = format!("{}: {}", tr!("帮助"), tr!(topic.title()));
+    if icon_button_label(ui.small_button("?"), &label).clicked() {
+        ui.ctx().data_mut(|d| d.insert_temp(request_id(), topic));
+    }
+}
+
+impl App {
+    /// 显示帮助窗口
+    pub fn show_help_window(&mut self, ctx: &egui::Context) {
+        if let Some(topic) = ctx.data_mut(|d| d.remove_temp::<HelpTopic>(request_id())) {
+            log::info!("打开帮助: {}", topic.key());
+            self.help_topic = Some(topic);
+        }
+        let Some(mut topic) = self.help_topic else {
+            return;
+        };
+
+        let lang = crate::utils::i18n::current_language();
+        let overrides = self
+            .config
+            .as_ref()
+            .map(|c| c.help_overrides.as_slice())
+            .unwrap_or_default();
+        let mut open = true;
+        let mut escape = false;
+
+        egui::Window::new(tr!("帮助"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([460.0, 360.0])
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                escape = dialog_keys(ui).escape;
+                ui.horizontal(|ui| {
+                    ui.label(tr!("主题:"));
+                    egui::ComboBox::from_id_salt("help_topic_select")
+                        .selected_text(tr!(topic.title()))
+                        .show_ui(ui, |ui| {
+                            for &t in HelpTopic::ALL {
+                                ui.selectable_value(&mut topic, t, tr!(t.title()));
+                            }
+                        });
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                    Self::show_markdown_lines(ui, topic.content(&lang, overrides));
+                });
+            });
+
+        self.help_topic = if open && !escape { Some(topic) } else { None };
+    }
+}
@@ -0,0 +1,280 @@
+//! 镜像库选择器
+//!
+//! 在系统安装和镜像校验页的路径输入框旁打开，管理镜像库目录、后台扫描并按关键字搜索镜像，
+//! 选中镜像（或其中的某个分卷）后填入对应页面。扫描和缓存逻辑见 `core::image_library`。
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::hardware_info::format_bytes;
+use crate::core::image_library::{self, ImageLibrary, LibraryImage};
+use crate::ui::a11y::icon_button_label;
+
+/// 选中镜像后填入的页面
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryTarget {
+    Install,
+    Verify,
+}
+
+/// 后台扫描消息
+pub enum ScanEvent {
+    /// 已处理数、总数、当前文件
+    Progress(usize, usize, String),
+    Done(Vec<LibraryImage>),
+}
+
+/// 镜像库窗口状态
+#[derive(Default)]
+pub struct ImageLibraryState {
+    /// 打开窗口的页面，为 None 时不显示窗口
+    pub target: Option<LibraryTarget>,
+    /// 首次打开时从缓存加载
+    pub library: Option<ImageLibrary>,
+    pub query: String,
+    pub scan_rx: Option<mpsc::Receiver<ScanEvent>>,
+    pub progress: Option<(usize, usize, String)>,
+    pub message: String,
+    /// 从镜像库选中的分卷，系统安装页加载完镜像信息后选中该分卷
+    pub pending_volume: Option<u32>,
+}
+
+impl ImageLibraryState {
+    /// 为指定页面打开镜像库
+    pub fn open(&mut self, target: LibraryTarget) {
+        self.target = Some(target);
+        self.message.clear();
+        if self.library.is_none() {
+            self.library = Some(ImageLibrary::load());
+        }
+    }
+
+    /// 是否正在扫描
+    pub fn is_scanning(&self) -> bool {
+        self.scan_rx.is_some()
+    }
+
+    /// 后台扫描全部目录，未变化的镜像复用缓存
+    fn start_scan(&mut self, ctx: &egui::Context) {
+        let Some(library) = self.library.as_ref() else {
+            return;
+        };
+        let folders = library.folders.clone();
+        let cached = library.images.clone();
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let images = image_library::scan(&folders, &cached, |done, total, file| {
+                let _ = tx.send(ScanEvent::Progress(done, total, file.to_string()));
+                ctx.request_repaint();
+            });
+            let _ = tx.send(ScanEvent::Done(images));
+            ctx.request_repaint();
+        });
+        self.scan_rx = Some(rx);
+        self.progress = None;
+        self.message.clear();
+    }
+
+    /// 读取扫描进度，完成后保存镜像库
+    fn poll_scan(&mut self) {
+        let Some(rx) = self.scan_rx.as_ref() else {
+            return;
+        };
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                ScanEvent::Progress(done, total, file) => self.progress = Some((done, total, file)),
+                ScanEvent::Done(images) => {
+                    self.scan_rx = None;
+                    self.progress = None;
+                    let Some(library) = self.library.as_mut() else {
+                        return;
+                    };
+                    library.images = images;
+                    library.scanned_at = Some(chrono::Local::now().timestamp());
+                    self.message = match library.save() {
+                        Ok(()) => format!("扫描完成，共 {} 个镜像", library.images.len()),
+                        Err(e) => format!("扫描完成，但保存镜像库失败: {:#}", e),
+                    };
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// 在路径输入框旁显示镜像库按钮，点击时返回 true
+pub fn library_button(ui: &mut egui::Ui) -> bool {
+    icon_button_label(ui.button("📚"), "从镜像库选择").clicked()
+}
+
+/// 镜像一行摘要：类型、大小和分卷数
+fn image_summary(image: &LibraryImage) -> String {
+    if image.volumes.is_empty() {
+        format!("{}  {}", image.kind, format_bytes(image.size_bytes))
+    } else {
+        format!("{}  {}  {} 个分卷", image.kind, format_bytes(image.size_bytes), image.volumes.len())
+    }
+}
+
+impl App {
+    /// 显示镜像库窗口
+    pub fn show_image_library_window(&mut self, ctx: &egui::Context) {
+        self.image_library.poll_scan();
+        let Some(target) = self.image_library.target else {
+            return;
+        };
+
+        let mut open = true;
+        // 选中的镜像路径和分卷索引
+        let mut selected: Option<(String, Option<u32>)> = None;
+        egui::Window::new("镜像库")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_size([620.0, 460.0])
+            .show(ctx, |ui| {
+                let state = &mut self.image_library;
+                let scanning = state.is_scanning();
+                let Some(library) = state.library.as_mut() else {
+                    return;
+                };
+
+                ui.label("镜像库目录（可以是整个分区）:");
+                let mut remove: Option<String> = None;
+                for folder in &library.folders {
+                    ui.horizontal(|ui| {
+                        ui.monospace(folder);
+                        if ui.add_enabled(!scanning, egui::Button::new("移除")).clicked() {
+                            remove = Some(folder.clone());
+                        }
+                    });
+                }
+                if library.folders.is_empty() {
+                    ui.weak("尚未添加目录");
+                }
+                if let Some(folder) = remove {
+                    library.remove_folder(&folder);
+                    if let Err(e) = library.save() {
+                        state.message = format!("保存镜像库失败: {:#}", e);
+                    }
+                }
+
+                let mut rescan = false;
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!scanning, egui::Button::new("添加目录...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                            match library.add_folder(&path.to_string_lossy()) {
+                                Ok(()) => rescan = true,
+                                Err(e) => state.message = format!("{:#}", e),
+                            }
+                        }
+                    }
+                    if ui
+                        .add_enabled(!scanning && !library.folders.is_empty(), egui::Button::new("重新扫描"))
+                        .clicked()
+                    {
+                        rescan = true;
+                    }
+                    if let Some(time) = library
+                        .scanned_at
+                        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    {
+                        ui.weak(format!(
+                            "上次扫描: {}",
+                            time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                        ));
+                    }
+                });
+
+                if scanning {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        match state.progress {
+                            Some((done, total, ref file)) => {
+                                ui.label(format!("正在扫描 {}/{}: {}", done, total, file));
+                            }
+                            None => {
+                                ui.label("正在查找镜像文件...");
+                            }
+                        }
+                    });
+                } else if !state.message.is_empty() {
+                    ui.label(&state.message);
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("搜索:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.query)
+                            .hint_text("文件名、版本名称、版本号或架构，空格分隔多个关键字")
+                            .desired_width(400.0),
+                    );
+                });
+
+                let results = library.search(&state.query);
+                ui.label(format!("共 {} 个镜像，匹配 {} 个", library.images.len(), results.len()));
+                egui::ScrollArea::vertical()
+                    .id_salt("image_library_results")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for image in results {
+                            ui.horizontal(|ui| {
+                                if ui.button("选择").clicked() {
+                                    selected = Some((image.path.clone(), None));
+                                }
+                                ui.strong(image.file_name());
+                                ui.weak(image_summary(image));
+                            });
+                            ui.weak(&image.path);
+                            if let Some(ref error) = image.error {
+                                ui.colored_label(egui::Color32::from_rgb(255, 100, 100), error);
+                            }
+                            // 校验针对整个镜像文件，只有系统安装可以直接选中分卷
+                            if target == LibraryTarget::Install {
+                                for volume in &image.volumes {
+                                    let label = format!(
+                                        "    {} - {}  {}  {}  {}",
+                                        volume.index,
+                                        volume.name,
+                                        volume.version,
+                                        volume.architecture,
+                                        format_bytes(volume.size_bytes)
+                                    );
+                                    if ui.selectable_label(false, label).clicked() {
+                                        selected = Some((image.path.clone(), Some(volume.index)));
+                                    }
+                                }
+                            }
+                            ui.separator();
+                        }
+                    });
+
+                if rescan {
+                    state.start_scan(ui.ctx());
+                }
+            });
+
+        if let Some((path, volume)) = selected {
+            log::info!("[IMAGE LIBRARY] 选择镜像: {} 分卷: {:?}", path, volume);
+            match target {
+                LibraryTarget::Install => {
+                    self.local_image_path = path;
+                    self.iso_mount_error = None;
+                    self.image_library.pending_volume = volume;
+                    self.load_image_volumes();
+                }
+                LibraryTarget::Verify => {
+                    self.image_verify_file_path = path;
+                    self.image_verify_result = None;
+                }
+            }
+            open = false;
+        }
+        if !open {
+            self.image_library.target = None;
+        }
+    }
+}
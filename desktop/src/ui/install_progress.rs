@@ -1,13 +1,15 @@
 use egui;
 use std::sync::mpsc;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::app::{App, BootModeSelection, InstallMode};
 use crate::core::dism::DismProgress;
 use crate::core::disk::{Partition, PartitionStyle};
 use crate::core::ghost::Ghost;
 use crate::core::install_config::{ConfigFileManager, InstallConfig};
+use crate::core::power::CompletionAction;
 use crate::ui::advanced_options::AdvancedOptions;
+use crate::utils::json_progress::{self, ProgressEvent};
 
 impl App {
     pub fn show_install_progress(&mut self, ui: &mut egui::Ui) {
@@ -59,30 +61,7 @@ impl App {
         egui::ScrollArea::vertical()
             .max_height(200.0)
             .show(ui, |ui| {
-                let mut steps = match self.install_mode {
-                    InstallMode::Direct => vec![
-                        "格式化分区",
-                        "导出驱动",
-                        "释放系统镜像",
-                        "导入驱动",
-                        "修复引导",
-                        "应用高级选项",
-                        "完成安装",
-                    ],
-                    InstallMode::ViaPE => vec![
-                        "检查PE环境",
-                        "安装PE引导",
-                        "导出驱动",
-                        "复制镜像文件",
-                        "写入配置文件",
-                        "准备重启",
-                    ],
-                };
-
-                // 如果需要 BitLocker 解密，插入解密步骤作为第一步
-                if self.bitlocker_decryption_needed {
-                    steps.insert(0, "解密 BitLocker 分区");
-                }
+                let steps = self.install_step_names();
 
                 // 计算有效步骤索引（用于显示）
                 let effective_install_step = if self.bitlocker_decryption_needed {
@@ -165,17 +144,63 @@ impl App {
                 self.current_panel = crate::app::Panel::SystemInstall;
             }
         }
+    }
 
-        // 启动安装线程
-        if self.install_step == 0 && self.is_installing && self.decrypting_partitions.is_empty() {
-            match self.install_mode {
-                InstallMode::Direct => self.start_direct_install_thread(),
-                InstallMode::ViaPE => self.start_pe_install_thread(),
-            }
+    /// 安装步骤名称（需要解密 BitLocker 时第一步为解密）
+    fn install_step_names(&self) -> Vec<&'static str> {
+        let mut steps = match self.install_mode {
+            InstallMode::Direct => vec![
+                "格式化分区",
+                "导出驱动",
+                "释放系统镜像",
+                "导入驱动",
+                "修复引导",
+                "应用高级选项",
+                "完成安装",
+            ],
+            InstallMode::ViaPE => vec![
+                "检查PE环境",
+                "安装PE引导",
+                "导出驱动",
+                "复制镜像文件",
+                "写入配置文件",
+                "准备重启",
+            ],
+        };
+        if self.bitlocker_decryption_needed {
+            steps.insert(0, "解密 BitLocker 分区");
+        }
+        steps
+    }
+
+    /// 输出安装步骤的 JSON 事件，step 为安装线程的步骤序号（0 为 BitLocker 解密）
+    pub(crate) fn emit_install_step(&self, step: usize, name: &str) {
+        let offset = usize::from(self.bitlocker_decryption_needed);
+        json_progress::emit(ProgressEvent::Step {
+            step: (step + offset) as u8,
+            total: self.install_step_names().len() as u8,
+            name: name.to_string(),
+        });
+    }
+
+    /// 安装状态已初始化且没有等待解密的分区时启动安装线程
+    ///
+    /// 由开始安装和解密完成时调用，不依赖安装进度页面是否显示
+    pub(crate) fn start_install_worker(&mut self) {
+        if self.install_step != 0 || !self.is_installing || !self.decrypting_partitions.is_empty() {
+            return;
+        }
+        if crate::core::demo::is_enabled() {
+            self.start_demo_install_thread();
+            return;
+        }
+        match self.install_mode {
+            InstallMode::Direct => self.start_direct_install_thread(),
+            InstallMode::ViaPE => self.start_pe_install_thread(),
         }
     }
 
-    fn update_install_progress(&mut self) {
+    pub(crate) fn update_install_progress(&mut self) {
         if let Some(ref rx) = self.install_progress_rx {
             while let Ok(progress) = rx.try_recv() {
                 // 处理 BitLocker 解密状态
@@ -183,12 +208,25 @@ impl App {
                     println!("[INSTALL UI] BitLocker 解密完成，准备开始安装");
                     self.decrypting_partitions.clear();
                     self.install_progress.current_step = "准备开始安装...".to_string();
+                    self.start_install_worker();
                     return;
                 } else if progress.status.starts_with("DECRYPTING:") {
+                    if self.install_progress.current_step.is_empty() {
+                        self.emit_install_step(0, "解密 BitLocker 分区");
+                    }
                     self.install_progress.current_step = progress.status.trim_start_matches("DECRYPTING:").to_string();
                     // 使用实际的解密进度（从加密百分比计算得出）
                     self.install_progress.step_progress = progress.percentage;
                     return;
+                } else if let Some(error) = progress.status.strip_prefix("ERROR:") {
+                    println!("[INSTALL UI] 安装失败: {}", error);
+                    json_progress::emit(ProgressEvent::Error { message: error.to_string() });
+                    json_progress::emit(ProgressEvent::Finished { success: false, message: error.to_string() });
+                    self.install_error = Some(error.to_string());
+                    let stage = self.install_progress.current_step.clone();
+                    self.report_install_result(Some(stage));
+                    self.release_mounted_iso();
+                    continue;
                 }
 
                 if let Some((step, name)) = parse_step_from_status(&progress.status) {
@@ -198,6 +236,7 @@ impl App {
                         self.install_step = step;
                         self.install_progress.current_step = name.clone();
                         println!("[INSTALL UI] 步骤更新: {} - {} ({}%)", step, name, progress.percentage);
+                        self.emit_install_step(step, &name);
                     }
                     
                     // 计算总进度
@@ -234,23 +273,162 @@ impl App {
                         }
                     };
                     
-                    self.install_progress.total_progress = 
+                    let total_progress =
                         (base_progress + (progress.percentage as usize * step_weight / 100)).min(100) as u8;
+                    if total_progress != self.install_progress.total_progress {
+                        json_progress::emit(ProgressEvent::Progress { percentage: total_progress, status: name.clone() });
+                        if total_progress >= 100 {
+                            // PE 安装此时只完成了准备工作，安装结果由 PE 报告
+                            json_progress::emit(match self.install_mode {
+                                InstallMode::Direct => ProgressEvent::Finished { success: true, message: "安装完成".to_string() },
+                                InstallMode::ViaPE => ProgressEvent::Handoff {
+                                    message: "PE 环境准备完成，重启后在 PE 中继续安装".to_string(),
+                                },
+                            });
+                        }
+                    }
+                    self.install_progress.total_progress = total_progress;
                     
-                    // 检查是否安装完成，并且用户勾选了自动重启
+                    if self.install_progress.total_progress >= 100 {
+                        // PE 安装此时尚未真正安装，结果未知，不上报成功
+                        if self.install_mode == InstallMode::Direct {
+                            self.report_install_result(None);
+                        }
+                        // 镜像已释放或复制完毕，卸载安装页面挂载的 ISO
+                        self.release_mounted_iso();
+                    }
+                    
+                    // 检查是否安装完成，并且用户选择了完成后自动执行的操作
+                    let action = self.install_options.completion_action;
                     if self.install_progress.total_progress >= 100 
-                        && self.install_options.auto_reboot 
+                        && action != CompletionAction::StayInPe 
                         && !self.auto_reboot_triggered 
                     {
-                        println!("[INSTALL] 安装完成，用户已勾选立即重启，执行自动重启");
                         self.auto_reboot_triggered = true;
-                        self.reboot_system();
+                        match self.install_mode {
+                            InstallMode::Direct => {
+                                println!("[INSTALL] 安装完成，执行完成后操作: {}", action.label());
+                                self.run_completion_action(action);
+                            }
+                            // PE 安装需先重启进入 PE，完成后操作由 PE 按配置执行
+                            InstallMode::ViaPE => {
+                                println!("[INSTALL] PE 环境准备完成，自动重启进入 PE");
+                                self.reboot_system();
+                            }
+                        }
                     }
                 }
             }
         }
     }
 
+    /// 上报匿名安装统计（仅在用户开启且服务器提供上报地址时）
+    fn report_install_result(&mut self, failed_stage: Option<String>) {
+        if self.install_report_sent || !self.app_config.telemetry_enabled {
+            return;
+        }
+        self.install_report_sent = true;
+
+        let Some(endpoint) = self
+            .remote_config
+            .as_ref()
+            .and_then(|c| c.telemetry_url.clone())
+        else {
+            return;
+        };
+
+        let image_id = Path::new(&self.install_image_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let volume_name = self
+            .image_volumes
+            .iter()
+            .find(|v| v.index == self.install_volume_index)
+            .map(|v| v.name.clone())
+            .unwrap_or_default();
+
+        let report = crate::download::telemetry::InstallReport {
+            client_version: env!("CARGO_PKG_VERSION").to_string(),
+            image_id,
+            volume_name,
+            install_mode: match self.install_mode {
+                InstallMode::Direct => "direct",
+                InstallMode::ViaPE => "pe",
+            }
+            .to_string(),
+            success: failed_stage.is_none(),
+            failed_stage,
+            duration_secs: self
+                .install_started_at
+                .map(|t| t.elapsed().as_secs())
+                .unwrap_or(0),
+            hardware: self
+                .hardware_info
+                .as_ref()
+                .map(crate::download::telemetry::HardwareClass::from_hardware_info)
+                .unwrap_or_default(),
+        };
+
+        crate::download::telemetry::send_install_report(endpoint, report);
+    }
+
+    /// 演示模式安装线程：按直接安装的步骤模拟进度，只修改模拟磁盘布局
+    fn start_demo_install_thread(&mut self) {
+        println!("[DEMO] 模拟安装到 {}", self.install_target_partition);
+        let (progress_tx, progress_rx) = mpsc::channel::<DismProgress>();
+        self.install_progress_rx = Some(progress_rx);
+
+        let target_partition = self.install_target_partition.clone();
+        let options = self.install_options.clone();
+
+        self.install_step = 1;
+        self.install_progress.current_step = "格式化分区".to_string();
+        self.emit_install_step(1, "格式化分区");
+
+        std::thread::spawn(move || {
+            let pause = |ms| std::thread::sleep(std::time::Duration::from_millis(ms));
+
+            send_step(&progress_tx, 1, "格式化分区", 0);
+            if options.format_partition {
+                if let Err(e) = crate::core::disk::DiskManager::format_partition(&target_partition) {
+                    let _ = progress_tx.send(DismProgress {
+                        percentage: 0,
+                        status: format!("ERROR:{}", e),
+                    });
+                    return;
+                }
+            }
+            pause(500);
+            send_step(&progress_tx, 1, "格式化分区", 100);
+
+            send_step(&progress_tx, 2, "导出驱动", 0);
+            pause(300);
+            send_step(&progress_tx, 2, "导出驱动", 100);
+
+            for percentage in (0..=100).step_by(5) {
+                send_step(&progress_tx, 3, "释放系统镜像", percentage);
+                pause(150);
+            }
+            if let Err(e) = crate::core::demo::with_layout(|layout| layout.install_windows(&target_partition)) {
+                let _ = progress_tx.send(DismProgress {
+                    percentage: 0,
+                    status: format!("ERROR:{}", e),
+                });
+                return;
+            }
+
+            for (step, name) in [(4, "导入驱动"), (5, "修复引导"), (6, "应用高级选项")] {
+                send_step(&progress_tx, step, name, 0);
+                pause(300);
+                send_step(&progress_tx, step, name, 100);
+            }
+
+            send_step(&progress_tx, 7, "完成安装", 100);
+            println!("[DEMO] 模拟安装完成");
+        });
+    }
+
     /// 直接安装线程
     fn start_direct_install_thread(&mut self) {
         println!("[INSTALL] ========== 开始直接安装 ==========");
@@ -267,6 +445,7 @@ impl App {
         let options = self.install_options.clone();
         let advanced_options = self.advanced_options.clone();
         let partitions: Vec<Partition> = self.partitions.clone();
+        let image_backend = self.app_config.image_backend;
         
         let partition_style = self.partitions
             .iter()
@@ -274,16 +453,71 @@ impl App {
             .map(|p| p.partition_style)
             .unwrap_or(PartitionStyle::Unknown);
 
+        let use_uefi = match options.boot_mode {
+            BootModeSelection::UEFI => true,
+            BootModeSelection::Legacy => false,
+            BootModeSelection::Auto => matches!(partition_style, PartitionStyle::GPT),
+        };
+
         self.install_step = 1;
         self.install_progress.current_step = "格式化分区".to_string();
+        self.emit_install_step(1, "格式化分区");
 
         std::thread::spawn(move || {
             println!("[INSTALL THREAD] 安装线程启动");
+
+            // 格式化前趁其他 BitLocker 数据分区仍处于解锁状态时添加一次性保护器，安装失败时随守卫删除
+            let auto_unlock_stage = std::env::temp_dir().join(crate::core::bitlocker::AUTO_UNLOCK_DIR);
+            let mut auto_unlock_keys = crate::core::bitlocker::prepare_auto_unlock(&target_partition, &auto_unlock_stage);
+            let mut installed_unlock_keys = None;
             
             let temp_dir = std::env::temp_dir();
             let driver_backup_path = temp_dir.join("LetRecovery_DriverBackup");
             let driver_backup_str = driver_backup_path.to_string_lossy().to_string();
 
+            // 安装到虚拟磁盘时，后续步骤的目标分区换成虚拟磁盘中的分区，线程结束时自动分离
+            let host_partition = target_partition.clone();
+            let mut vhd_guard = None;
+            let target_partition = match options.vhd_target {
+                Some(ref vhd) => {
+                    send_step(&progress_tx, 1, "创建虚拟磁盘", 0);
+                    println!("[INSTALL STEP 1] 创建虚拟磁盘: {}", vhd.path);
+                    match crate::core::vhd::prepare_install_target(vhd, use_uefi) {
+                        Ok(attached) => {
+                            let partition = attached.partition.clone();
+                            vhd_guard = Some(attached);
+                            partition
+                        }
+                        Err(e) => {
+                            println!("[INSTALL STEP 1] 创建虚拟磁盘失败: {:#}", e);
+                            let _ = progress_tx.send(DismProgress {
+                                percentage: 0,
+                                status: format!("ERROR:创建虚拟磁盘失败: {:#}", e),
+                            });
+                            return;
+                        }
+                    }
+                }
+                None => target_partition,
+            };
+
+            // 格式化前从目标分区的现有系统导出第三方驱动，释放镜像后重新注入（虚拟磁盘为新建的空盘）
+            let harvested_path = temp_dir.join("LetRecovery_HarvestedDrivers");
+            let harvested_str = harvested_path.to_string_lossy().to_string();
+            let harvested = options.harvest_drivers && options.vhd_target.is_none() && {
+                send_step(&progress_tx, 1, "导出现有系统驱动", 0);
+                match crate::core::dism::Dism::new().harvest_drivers(&target_partition, &harvested_str) {
+                    Ok(count) => {
+                        println!("[INSTALL STEP 1] 已从现有系统导出 {} 个驱动包", count);
+                        count > 0
+                    }
+                    Err(e) => {
+                        println!("[INSTALL STEP 1] 导出现有系统驱动失败: {} (继续安装)", e);
+                        false
+                    }
+                }
+            };
+
             // Step 1: 格式化分区
             send_step(&progress_tx, 1, "格式化分区", 0);
             std::thread::sleep(std::time::Duration::from_millis(50));
@@ -332,6 +566,15 @@ impl App {
             let image_lower = image_path.to_lowercase();
             let is_gho = image_lower.ends_with(".gho") || image_lower.ends_with(".ghs");
 
+            if is_gho && vhd_guard.is_some() {
+                println!("[INSTALL STEP 3] 错误: GHO 镜像不支持安装到虚拟磁盘");
+                let _ = progress_tx.send(DismProgress {
+                    percentage: 0,
+                    status: "ERROR:GHO 镜像不支持安装到虚拟磁盘".to_string(),
+                });
+                return;
+            }
+
             if is_gho {
                 println!("[INSTALL STEP 3] 检测到 GHO 镜像，使用 Ghost 恢复");
                 
@@ -350,16 +593,23 @@ impl App {
                         }
                     });
                     
-                    match ghost.restore_image_to_letter(&image_path, &target_partition, &partitions, Some(inner_tx)) {
+                    match ghost.restore_image_to_letter(&image_path, options.gho_partition, &target_partition, &partitions, Some(inner_tx)) {
                         Ok(_) => println!("[INSTALL STEP 3] Ghost 镜像恢复成功"),
-                        Err(e) => println!("[INSTALL STEP 3] Ghost 镜像恢复失败: {}", e),
+                        Err(e) => {
+                            println!("[INSTALL STEP 3] Ghost 镜像恢复失败: {}", e);
+                            let _ = progress_tx.send(DismProgress {
+                                percentage: 0,
+                                status: format!("ERROR:Ghost 镜像恢复失败: {}", e),
+                            });
+                            return;
+                        }
                     }
                 }
                 
                 send_step(&progress_tx, 3, "释放系统镜像", 100);
             } else {
                 println!("[INSTALL STEP 3] 使用 DISM 应用 WIM/ESD 镜像");
-                let dism = crate::core::dism::Dism::new();
+                let dism = crate::core::dism::Dism::with_backend(image_backend);
                 let apply_dir = format!("{}\\", target_partition);
                 
                 let step_tx = progress_tx.clone();
@@ -376,7 +626,40 @@ impl App {
                 
                 match dism.apply_image(&image_path, &apply_dir, volume_index, Some(inner_tx)) {
                     Ok(_) => println!("[INSTALL STEP 3] DISM 镜像释放成功"),
-                    Err(e) => println!("[INSTALL STEP 3] DISM 镜像释放失败: {}", e),
+                    Err(e) => {
+                        println!("[INSTALL STEP 3] DISM 镜像释放失败: {}", e);
+                        let _ = progress_tx.send(DismProgress {
+                            percentage: 0,
+                            status: format!("ERROR:镜像释放失败: {}", e),
+                        });
+                        return;
+                    }
+                }
+
+                // 按镜像中的哈希校验刚写入的文件，发现磁盘静默写入损坏时中止安装
+                if options.apply_verify != crate::core::apply_verify::ApplyVerifyMode::Off {
+                    println!("[INSTALL STEP 3] 释放后校验: {}", options.apply_verify.label());
+                    let on_progress = |done: usize, total: usize| {
+                        send_step(&progress_tx, 3, "校验已释放的文件", (done * 100 / total.max(1)) as u8);
+                    };
+                    match crate::core::apply_verify::verify_applied_image(
+                        &image_path,
+                        volume_index,
+                        &apply_dir,
+                        options.apply_verify,
+                        &on_progress,
+                    ) {
+                        Ok(report) if report.is_ok() => println!("[INSTALL STEP 3] {}", report.summary()),
+                        Ok(report) => {
+                            println!("[INSTALL STEP 3] 释放后校验失败: {}", report.summary());
+                            let _ = progress_tx.send(DismProgress {
+                                percentage: 0,
+                                status: format!("ERROR:释放后校验失败: {}", report.summary()),
+                            });
+                            return;
+                        }
+                        Err(e) => println!("[INSTALL STEP 3] 无法进行释放后校验: {} (继续安装)", e),
+                    }
                 }
                 send_step(&progress_tx, 3, "释放系统镜像", 100);
             }
@@ -423,6 +706,17 @@ impl App {
                 println!("[INSTALL STEP 4] 跳过驱动处理 (driver_action: {:?})", options.driver_action);
                 send_step(&progress_tx, 4, "导入驱动", 100);
             }
+
+            if harvested {
+                println!("[INSTALL STEP 4] 重新注入现有系统驱动");
+                send_step(&progress_tx, 4, "重新注入现有系统驱动", 50);
+                match import_drivers(&target_partition, &harvested_str) {
+                    Ok(_) => println!("[INSTALL STEP 4] 现有系统驱动注入成功"),
+                    Err(e) => println!("[INSTALL STEP 4] 现有系统驱动注入失败: {}", e),
+                }
+                let _ = std::fs::remove_dir_all(&harvested_path);
+                send_step(&progress_tx, 4, "重新注入现有系统驱动", 100);
+            }
             std::thread::sleep(std::time::Duration::from_millis(100));
 
             // Step 5: 修复引导
@@ -432,17 +726,16 @@ impl App {
                 println!("[INSTALL STEP 5] 开始修复引导");
                 send_step(&progress_tx, 5, "修复引导", 20);
                 
-                let use_uefi = match options.boot_mode {
-                    BootModeSelection::UEFI => true,
-                    BootModeSelection::Legacy => false,
-                    BootModeSelection::Auto => matches!(partition_style, PartitionStyle::GPT),
-                };
-                
                 println!("[INSTALL STEP 5] 引导模式: {}", if use_uefi { "UEFI" } else { "Legacy" });
                 send_step(&progress_tx, 5, "修复引导", 50);
                 
                 let boot_manager = crate::core::bcdedit::BootManager::new();
-                match boot_manager.repair_boot_advanced(&target_partition, use_uefi) {
+                let boot_result = if vhd_guard.is_some() {
+                    boot_manager.add_vhd_boot_entry(&target_partition, &host_partition, use_uefi)
+                } else {
+                    boot_manager.repair_boot_advanced(&target_partition, use_uefi)
+                };
+                match boot_result {
                     Ok(_) => {
                         println!("[INSTALL STEP 5] 引导修复成功");
                         
@@ -476,6 +769,34 @@ impl App {
                 Ok(_) => println!("[INSTALL STEP 6] 高级选项应用成功"),
                 Err(e) => println!("[INSTALL STEP 6] 高级选项应用失败: {}", e),
             }
+
+            if let Some(ref keys) = auto_unlock_keys {
+                match crate::core::auto_unlock::install(&target_partition, keys.dir()) {
+                    Ok(guard) => {
+                        println!("[INSTALL STEP 6] 已为 {} 个 BitLocker 分区安装首次启动解锁脚本", keys.count());
+                        installed_unlock_keys = Some(guard);
+                    }
+                    Err(e) => println!("[INSTALL STEP 6] 安装 BitLocker 数据分区解锁脚本失败: {:#}", e),
+                }
+            }
+
+            if let Some(scripts_dir) = AdvancedOptions::get_hook_scripts_dir() {
+                let hooks = if advanced_options.script_hooks.is_empty() {
+                    crate::core::script_hooks::discover(&scripts_dir, &[])
+                } else {
+                    advanced_options.script_hooks.clone()
+                };
+                match crate::core::script_hooks::install_hooks(&target_partition, &scripts_dir, &hooks) {
+                    Ok(0) => {}
+                    Ok(count) => println!("[INSTALL STEP 6] 已安装 {} 个首次启动脚本", count),
+                    Err(e) => println!("[INSTALL STEP 6] 安装首次启动脚本失败: {:#}", e),
+                }
+            }
+            match crate::core::health_check::install_agent(&target_partition, None, &advanced_options.health_check) {
+                Ok(true) => println!("[INSTALL STEP 6] 已安装首次启动健康检查"),
+                Ok(false) => {}
+                Err(e) => println!("[INSTALL STEP 6] 安装首次启动健康检查失败: {:#}", e),
+            }
             send_step(&progress_tx, 6, "应用高级选项", 50);
             
             if options.unattended_install {
@@ -488,6 +809,15 @@ impl App {
             send_step(&progress_tx, 6, "应用高级选项", 100);
             std::thread::sleep(std::time::Duration::from_millis(100));
 
+            // 重启前分离虚拟磁盘
+            drop(vhd_guard);
+
+            // 一次性密钥交给新系统首次启动时使用，删除本机的暂存文件
+            if let (Some(mut installed), Some(keys)) = (installed_unlock_keys, auto_unlock_keys.as_mut()) {
+                installed.keep();
+                keys.hand_off(false);
+            }
+
             // Step 7: 完成
             send_step(&progress_tx, 7, "完成安装", 100);
             println!("[INSTALL STEP 7] 安装完成!");
@@ -509,14 +839,22 @@ impl App {
         let volume_index = self.install_volume_index;
         let options = self.install_options.clone();
         let advanced_options = self.advanced_options.clone();
+        let image_backend = self.app_config.image_backend;
         
         // 获取选中的PE信息
         let pe_info = self.selected_pe_for_install.and_then(|idx| {
             self.config.as_ref().and_then(|c| c.pe_list.get(idx).cloned())
         });
+        // 按硬件 ID 匹配的驱动索引
+        let hwid_index = if options.hwid_drivers {
+            self.config.as_ref().map(|c| c.hwid_drivers.clone()).unwrap_or_default()
+        } else {
+            Vec::new()
+        };
 
         self.install_step = 1;
         self.install_progress.current_step = "检查PE环境".to_string();
+        self.emit_install_step(1, "检查PE环境");
 
         std::thread::spawn(move || {
             println!("[INSTALL PE THREAD] PE安装线程启动");
@@ -573,7 +911,7 @@ impl App {
             std::thread::sleep(std::time::Duration::from_millis(50));
             
             // 找一个可用的数据分区来存储数据（传入镜像路径以检查空间）
-            let (data_partition, _is_auto_created) = match find_data_partition(&target_partition, &image_path) {
+            let (data_partition, is_auto_created) = match find_data_partition(&target_partition, &image_path) {
                 Ok(result) => result,
                 Err(e) => {
                     println!("[INSTALL PE STEP 3] 查找数据分区失败: {}", e);
@@ -594,11 +932,11 @@ impl App {
                 crate::app::DriverAction::SaveOnly | crate::app::DriverAction::AutoImport
             );
             
+            let driver_path = format!("{}\\drivers", data_dir);
             if should_export {
                 println!("[INSTALL PE STEP 3] 导出驱动到: {} (driver_action: {:?})", data_dir, options.driver_action);
                 send_step(&progress_tx, 3, "导出驱动", 30);
                 
-                let driver_path = format!("{}\\drivers", data_dir);
                 match export_drivers(&driver_path) {
                     Ok(_) => println!("[INSTALL PE STEP 3] 驱动导出成功"),
                     Err(e) => println!("[INSTALL PE STEP 3] 驱动导出失败: {}", e),
@@ -606,6 +944,37 @@ impl App {
             } else {
                 println!("[INSTALL PE STEP 3] 跳过驱动导出 (driver_action: {:?})", options.driver_action);
             }
+
+            // 按硬件 ID 下载网卡和存储驱动，由 PE 注入新系统
+            let mut hwid_staged = false;
+            if !hwid_index.is_empty() {
+                println!("[INSTALL PE STEP 3] 按硬件 ID 下载驱动");
+                let on_progress = |done: usize, total: usize, _name: &str| {
+                    send_step(&progress_tx, 3, "下载网卡/存储驱动", (60 + done * 40 / total.max(1)) as u8);
+                };
+                match crate::core::hwid_drivers::stage_matched(&hwid_index, Path::new(&driver_path), on_progress) {
+                    Ok(report) => {
+                        println!("[INSTALL PE STEP 3] {}", report.summary());
+                        hwid_staged = !report.staged.is_empty();
+                    }
+                    Err(e) => println!("[INSTALL PE STEP 3] 按硬件 ID 下载驱动失败: {} (继续安装)", e),
+                }
+            }
+
+            // 导出已连接的 Wi-Fi 配置，PE 中导入后联网
+            // 配置文件写入成功前的任何返回路径上都随守卫删除（文件含明文密码）
+            let mut wifi_dir = None;
+            if options.wifi_profiles {
+                let dest = Path::new(&data_dir).join(crate::core::wifi_profile::WIFI_PROFILE_DIR);
+                match crate::core::wifi_profile::export_connected(&dest) {
+                    Ok(None) => println!("[INSTALL PE STEP 3] 未连接无线网络，跳过导出 Wi-Fi 配置"),
+                    Ok(Some((dir, count))) => {
+                        println!("[INSTALL PE STEP 3] 已导出 {} 个 Wi-Fi 配置", count);
+                        wifi_dir = Some(dir);
+                    }
+                    Err(e) => println!("[INSTALL PE STEP 3] 导出 Wi-Fi 配置失败: {:#} (继续安装)", e),
+                }
+            }
             send_step(&progress_tx, 3, "导出驱动", 100);
             std::thread::sleep(std::time::Duration::from_millis(100));
 
@@ -614,26 +983,43 @@ impl App {
             std::thread::sleep(std::time::Duration::from_millis(50));
             
             println!("[INSTALL PE STEP 4] 复制镜像文件到数据分区");
-            let image_filename = Path::new(&image_path)
+            // SWM 分卷镜像复制全部分卷，配置中记录主分卷
+            let image_parts = if image_path.to_lowercase().ends_with(".swm") {
+                crate::core::wimlib::find_swm_parts(&image_path)
+            } else {
+                vec![PathBuf::from(&image_path)]
+            };
+            let image_filename = image_parts[0]
                 .file_name()
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string();
-            let target_image_path = format!("{}\\{}", data_dir, image_filename);
             
-            // 使用带进度的复制函数
-            match copy_file_with_progress(&image_path, &target_image_path, |progress| {
-                send_step(&progress_tx, 4, "复制镜像文件", progress);
-            }) {
-                Ok(_) => println!("[INSTALL PE STEP 4] 镜像复制成功: {}", target_image_path),
-                Err(e) => {
-                    println!("[INSTALL PE STEP 4] 镜像复制失败: {}", e);
-                    // 发送错误状态，不是100%
-                    let _ = progress_tx.send(DismProgress {
-                        percentage: 0,
-                        status: format!("ERROR:复制失败: {}", e),
-                    });
-                    return;
+            // 网络镜像由 PE 直接读取共享、下载或从局域网发送端接收，不复制到本地
+            let network_source = crate::core::network_source::NetworkSource::parse(&image_path);
+            if network_source.is_some() {
+                println!("[INSTALL PE STEP 4] 网络镜像源，PE 中获取: {}", image_path);
+            } else {
+                // 使用带进度的复制函数，多个分卷按序号分摊进度
+                let part_count = image_parts.len();
+                for (i, part) in image_parts.iter().enumerate() {
+                    let target_part = Path::new(&data_dir).join(part.file_name().unwrap_or_default());
+                    let target_part = target_part.to_string_lossy().to_string();
+                    match copy_file_with_progress(&part.to_string_lossy(), &target_part, |progress| {
+                        let overall = (i * 100 + progress as usize) / part_count;
+                        send_step(&progress_tx, 4, "复制镜像文件", overall as u8);
+                    }) {
+                        Ok(_) => println!("[INSTALL PE STEP 4] 镜像复制成功: {}", target_part),
+                        Err(e) => {
+                            println!("[INSTALL PE STEP 4] 镜像复制失败: {}", e);
+                            // 发送错误状态，不是100%
+                            let _ = progress_tx.send(DismProgress {
+                                percentage: 0,
+                                status: format!("ERROR:复制失败: {}", e),
+                            });
+                            return;
+                        }
+                    }
                 }
             }
             send_step(&progress_tx, 4, "复制镜像文件", 100);
@@ -677,6 +1063,20 @@ impl App {
                 }
             }
 
+            // 复制首次启动脚本到数据目录，由 PE 安装到新系统
+            if let Some(source_scripts_dir) = AdvancedOptions::get_hook_scripts_dir() {
+                if source_scripts_dir.is_dir() {
+                    let scripts_dir = std::path::Path::new(&data_dir).join(crate::core::script_hooks::SCRIPTS_DIR);
+                    let _ = std::fs::create_dir_all(&scripts_dir);
+                    for hook in crate::core::script_hooks::discover(&source_scripts_dir, &[]) {
+                        match std::fs::copy(source_scripts_dir.join(&hook.file), scripts_dir.join(&hook.file)) {
+                            Ok(_) => println!("[INSTALL PE STEP 4.5] 复制首次启动脚本 {} 成功", hook.file),
+                            Err(e) => println!("[INSTALL PE STEP 4.5] 复制首次启动脚本 {} 失败: {}", hook.file, e),
+                        }
+                    }
+                }
+            }
+
             // Step 5: 写入配置文件
             send_step(&progress_tx, 5, "写入配置文件", 0);
             std::thread::sleep(std::time::Duration::from_millis(50));
@@ -690,15 +1090,36 @@ impl App {
                 unattended: options.unattended_install,
                 restore_drivers: options.export_drivers,
                 driver_action_mode: InstallConfig::driver_action_to_mode(options.driver_action),
-                auto_reboot: options.auto_reboot,
+                harvest_drivers: options.harvest_drivers,
+                hwid_drivers: hwid_staged,
+                wifi_profiles: wifi_dir.is_some(),
+                apply_verify: options.apply_verify,
+                auto_reboot: options.completion_action != CompletionAction::StayInPe,
+                completion_action: Some(options.completion_action),
                 original_guid: String::new(),
                 volume_index,
                 target_partition: target_partition.clone(),
                 image_path: image_filename,
+                image_source: if network_source.is_some() { image_path.clone() } else { String::new() },
+                share_user: options.share_user.clone(),
                 is_gho,
+                image_backend,
+                gho_partition: options.gho_partition,
+                data_partition_id: if is_auto_created {
+                    data_partition
+                        .chars()
+                        .next()
+                        .and_then(crate::core::disk::DiskManager::partition_identity)
+                        .unwrap_or_default()
+                } else {
+                    String::new()
+                },
                 remove_shortcut_arrow: advanced_options.remove_shortcut_arrow,
                 restore_classic_context_menu: advanced_options.restore_classic_context_menu,
                 bypass_nro: advanced_options.bypass_nro,
+                bypass_tpm_check: advanced_options.bypass_tpm_check,
+                bypass_secure_boot_check: advanced_options.bypass_secure_boot_check,
+                bypass_ram_check: advanced_options.bypass_ram_check,
                 disable_windows_update: advanced_options.disable_windows_update,
                 disable_windows_defender: advanced_options.disable_windows_defender,
                 disable_reserved_storage: advanced_options.disable_reserved_storage,
@@ -706,6 +1127,13 @@ impl App {
                 disable_device_encryption: advanced_options.disable_device_encryption,
                 remove_uwp_apps: advanced_options.remove_uwp_apps,
                 import_storage_controller_drivers: advanced_options.import_storage_controller_drivers,
+                storage_driver_packs: advanced_options.storage_driver_packs.clone(),
+                disable_driver_signature_once: advanced_options.disable_driver_signature_once,
+                ensure_trim: advanced_options.ensure_trim,
+                write_cache_policy: advanced_options.write_cache_policy,
+                script_hooks: advanced_options.script_hooks.clone(),
+                unattend: advanced_options.unattend.clone(),
+                health_check: advanced_options.health_check.clone(),
                 custom_username: if advanced_options.custom_username {
                     advanced_options.username.clone()
                 } else {
@@ -721,13 +1149,55 @@ impl App {
                 win7_inject_nvme_driver: advanced_options.win7_inject_nvme_driver,
                 win7_fix_acpi_bsod: advanced_options.win7_fix_acpi_bsod,
                 win7_fix_storage_bsod: advanced_options.win7_fix_storage_bsod,
+                ..Default::default()
             };
             
+            // 其他 BitLocker 数据分区的一次性密钥，由PE端安装到新系统；准备失败时随守卫删除
+            let auto_unlock_stage = Path::new(&data_dir).join(crate::core::bitlocker::AUTO_UNLOCK_DIR);
+            let mut auto_unlock_keys = crate::core::bitlocker::prepare_auto_unlock(&target_partition, &auto_unlock_stage);
+            if let Some(ref keys) = auto_unlock_keys {
+                println!("[INSTALL PE STEP 5] 已为 {} 个 BitLocker 分区生成一次性解锁密钥", keys.count());
+            }
+
+            // 共享密码和账户密码不写入配置文件，单独保存在受限访问的目录中；配置写入失败时随守卫删除
+            let secrets = crate::core::install_secrets::InstallSecrets {
+                share_password: options.share_password.clone(),
+                unattend_password: advanced_options.unattend.password.clone(),
+            };
+            let mut secrets_dir = match secrets.stage(&data_dir) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    println!("[INSTALL PE STEP 5] 保存安装凭据失败: {:#}", e);
+                    let _ = progress_tx.send(DismProgress {
+                        percentage: 0,
+                        status: format!("ERROR:保存安装凭据失败: {:#}", e),
+                    });
+                    return;
+                }
+            };
+
             match ConfigFileManager::write_install_config(&target_partition, &data_partition, &install_config) {
                 Ok(_) => println!("[INSTALL PE STEP 5] 配置文件写入成功"),
-                Err(e) => println!("[INSTALL PE STEP 5] 配置文件写入失败: {}", e),
+                Err(e) => {
+                    println!("[INSTALL PE STEP 5] 配置文件写入失败: {:#}", e);
+                    // 配置无效时PE端无法继续安装，不能进入重启流程
+                    let _ = progress_tx.send(DismProgress {
+                        percentage: 0,
+                        status: format!("ERROR:配置文件写入失败: {:#}", e),
+                    });
+                    return;
+                }
             }
             
+            if let Some(ref mut keys) = auto_unlock_keys {
+                keys.hand_off(true);
+            }
+            if let Some(ref mut dir) = wifi_dir {
+                dir.keep();
+            }
+            if let Some(ref mut dir) = secrets_dir {
+                dir.keep();
+            }
             send_step(&progress_tx, 5, "写入配置文件", 100);
             std::thread::sleep(std::time::Duration::from_millis(100));
 
@@ -739,10 +1209,19 @@ impl App {
     }
 
     fn reboot_system(&self) {
-        println!("[INSTALL] 执行重启命令");
-        let _ = crate::utils::cmd::create_command("shutdown")
-            .args(["/r", "/t", "5", "/c", "LetRecovery 系统安装完成，即将重启..."])
-            .spawn();
+        self.run_completion_action(CompletionAction::RebootCountdown);
+    }
+
+    /// 执行完成后的电源操作（立即重启不等待，其余操作留出 5 秒）
+    fn run_completion_action(&self, action: CompletionAction) {
+        if crate::core::demo::is_enabled() {
+            println!("[DEMO] 演示模式，跳过{}", action.label());
+            return;
+        }
+        let delay = if action == CompletionAction::RebootNow { 0 } else { 5 };
+        if let Err(e) = action.execute(delay, "LetRecovery 系统安装完成") {
+            println!("[INSTALL] {:#}", e);
+        }
     }
 }
 
@@ -773,8 +1252,10 @@ fn format_partition(partition: &str) -> anyhow::Result<()> {
     
     println!("[FORMAT] 格式化分区: {}", partition);
     
+    // format 只接受盘符，目标分区没有盘符时临时分配
+    let drive = crate::core::disk::TempDriveLetter::acquire(partition)?;
     let output = create_command("cmd")
-        .args(["/c", &format!("format {} /FS:NTFS /Q /Y", partition)])
+        .args(["/c", &format!("format {} /FS:NTFS /Q /Y", drive.drive())])
         .output()?;
     
     let stdout = crate::utils::encoding::gbk_to_utf8(&output.stdout);
@@ -784,9 +1265,17 @@ fn format_partition(partition: &str) -> anyhow::Result<()> {
     println!("[FORMAT] stderr: {}", stderr);
     
     if !output.status.success() {
+        if let Some(hint) = crate::core::opal::format_failure_hint() {
+            anyhow::bail!("格式化失败: {}\n{}", stderr, hint);
+        }
         anyhow::bail!("格式化失败: {}", stderr);
     }
     
+    // SSD 在释放镜像前 TRIM 全部空闲空间，失败不影响安装
+    if let Err(e) = crate::core::disk::DiskManager::trim_free_space(partition) {
+        println!("[FORMAT] TRIM 失败: {}", e);
+    }
+    
     Ok(())
 }
 
@@ -877,15 +1366,13 @@ fn copy_dir_recursive(src: &str, dst: &str) -> anyhow::Result<()> {
 
 /// 生成无人值守 XML 文件
 fn generate_unattend_xml(target_partition: &str, options: &AdvancedOptions) -> anyhow::Result<()> {
-    use crate::core::system_utils::{get_file_version, get_system_architecture};
+    use crate::core::image_language::TargetLanguage;
+    use crate::core::system_utils::get_system_architecture;
+    use crate::core::unattend::{write_unattend, UnattendBuilder, UnattendTarget};
     
     println!("[UNATTEND] 生成无人值守配置文件");
     
-    let username = if options.custom_username && !options.username.is_empty() {
-        options.username.clone()
-    } else {
-        "User".to_string()
-    };
+    let username = if options.custom_username { options.username.as_str() } else { "" };
 
     // 检测目标系统架构
     let arch = get_system_architecture(target_partition);
@@ -893,157 +1380,34 @@ fn generate_unattend_xml(target_partition: &str, options: &AdvancedOptions) -> a
     println!("[UNATTEND] 检测到目标系统架构: {}", arch_str);
 
     // 通过 ntdll.dll 文件版本检测目标系统版本
-    // Windows 7: 6.1.x, Windows 8: 6.2.x, Windows 8.1: 6.3.x, Windows 10/11: 10.0.x
-    let ntdll_path = Path::new(target_partition).join("Windows").join("System32").join("ntdll.dll");
-    let (is_win7, is_win8) = match get_file_version(&ntdll_path) {
-        Some((major, minor, build, _)) => {
-            println!("[UNATTEND] 检测到目标系统版本 (ntdll.dll): {}.{}.{}", major, minor, build);
-            
-            let is_win7 = major == 6 && minor == 1;
-            let is_win8 = major == 6 && (minor == 2 || minor == 3);
-            (is_win7, is_win8)
-        }
-        None => {
-            println!("[UNATTEND] 无法读取 ntdll.dll 版本: {:?}, 默认使用 Win10/11 配置", ntdll_path);
-            (false, false)
-        }
-    };
-
-    // 构建 FirstLogonCommands
-    let mut first_logon_commands = String::new();
-    let mut order = 1;
-
-    // 首次登录脚本
-    first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>cmd /c if exist %SystemDrive%\LetRecovery_Scripts\firstlogon.bat call %SystemDrive%\LetRecovery_Scripts\firstlogon.bat</CommandLine>
-                    <Description>Run first login script</Description>
-                </SynchronousCommand>"#, order));
-    order += 1;
+    let target = UnattendTarget::detect(target_partition);
+
+    let mut builder = UnattendBuilder::new(arch_str, target)
+        .username(username)
+        .settings(&options.unattend)
+        .win11_bypass(options.win11_bypass())
+        .language(TargetLanguage::detect(target_partition))
+        .deploy_command(
+            r"cmd /c if exist %SystemDrive%\LetRecovery_Scripts\deploy.bat call %SystemDrive%\LetRecovery_Scripts\deploy.bat",
+            "Run custom deploy script",
+        )
+        .first_logon_command(
+            r"cmd /c if exist %SystemDrive%\LetRecovery_Scripts\firstlogon.bat call %SystemDrive%\LetRecovery_Scripts\firstlogon.bat",
+            "Run first login script",
+        );
 
     // 如果需要删除UWP应用（仅Win10/11支持）
-    if options.remove_uwp_apps && !is_win7 && !is_win8 {
-        first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>powershell -ExecutionPolicy Bypass -File %SystemDrive%\LetRecovery_Scripts\remove_uwp.ps1</CommandLine>
-                    <Description>Remove preinstalled UWP apps</Description>
-                </SynchronousCommand>"#, order));
-        order += 1;
+    if options.remove_uwp_apps && target == UnattendTarget::Win10 {
+        builder = builder.first_logon_command(
+            r"powershell -ExecutionPolicy Bypass -File %SystemDrive%\LetRecovery_Scripts\remove_uwp.ps1",
+            "Remove preinstalled UWP apps",
+        );
     }
 
     // 清理脚本目录（最后执行）
-    first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>cmd /c rd /s /q %SystemDrive%\LetRecovery_Scripts</CommandLine>
-                    <Description>Cleanup scripts directory</Description>
-                </SynchronousCommand>"#, order));
-    
-    // 根据系统版本生成不同的OOBE配置
-    // Win7: 移除HideOEMRegistrationScreen（家庭版不支持）
-    let oobe_section = if is_win7 {
-        // Windows 7: 不支持 HideOnlineAccountScreens, HideWirelessSetupInOOBE, SkipMachineOOBE, SkipUserOOBE, HideLocalAccountScreen, HideOEMRegistrationScreen(家庭版)
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>"#.to_string()
-    } else if is_win8 {
-        // Windows 8/8.1: 支持 HideLocalAccountScreen，不支持其他新选项
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>"#.to_string()
-    } else {
-        // Windows 10/11: 完整支持所有OOBE选项
-        r#"<OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <HideOnlineAccountScreens>true</HideOnlineAccountScreens>
-                <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>
-                <ProtectYourPC>3</ProtectYourPC>
-                <SkipMachineOOBE>true</SkipMachineOOBE>
-                <SkipUserOOBE>true</SkipUserOOBE>
-            </OOBE>"#.to_string()
-    };
-    
-    let xml_content = format!(r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="specialize">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <ComputerName>*</ComputerName>
-        </component>
-        <component name="Microsoft-Windows-Deployment" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <RunSynchronous>
-                <RunSynchronousCommand wcm:action="add">
-                    <Order>1</Order>
-                    <Path>cmd /c if exist %SystemDrive%\LetRecovery_Scripts\deploy.bat call %SystemDrive%\LetRecovery_Scripts\deploy.bat</Path>
-                    <Description>Run custom deploy script</Description>
-                </RunSynchronousCommand>
-            </RunSynchronous>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            {oobe_section}
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{username}</DisplayName>
-                        <Group>Administrators</Group>
-                        <n>{username}</n>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <LogonCount>1</LogonCount>
-                <Username>{username}</Username>
-            </AutoLogon>
-            <FirstLogonCommands>{first_logon_commands}
-            </FirstLogonCommands>
-        </component>
-    </settings>
-</unattend>"#, arch = arch_str, oobe_section = oobe_section, username = username, first_logon_commands = first_logon_commands);
-
-    let panther_dir = format!("{}\\Windows\\Panther", target_partition);
-    std::fs::create_dir_all(&panther_dir)?;
-    
-    let unattend_path = format!("{}\\unattend.xml", panther_dir);
-    std::fs::write(&unattend_path, &xml_content)?;
-    println!("[UNATTEND] 已写入: {}", unattend_path);
-    
-    let sysprep_dir = format!("{}\\Windows\\System32\\Sysprep", target_partition);
-    if Path::new(&sysprep_dir).exists() {
-        let sysprep_unattend = format!("{}\\unattend.xml", sysprep_dir);
-        let _ = std::fs::write(&sysprep_unattend, &xml_content);
-        println!("[UNATTEND] 已写入: {}", sysprep_unattend);
-    }
-    
-    Ok(())
+    builder = builder.first_logon_command(r"cmd /c rd /s /q %SystemDrive%\LetRecovery_Scripts", "Cleanup scripts directory");
+
+    write_unattend(target_partition, &builder.build())
 }
 
 
@@ -1052,13 +1416,21 @@ fn generate_unattend_xml(target_partition: &str, options: &AdvancedOptions) -> a
 fn find_data_partition(exclude_partition: &str, image_path: &str) -> Result<(String, bool), String> {
     use crate::core::disk::DiskManager;
     
-    // 获取镜像文件大小
-    let image_size = match std::fs::metadata(image_path) {
-        Ok(meta) => meta.len(),
-        Err(e) => {
-            return Err(format!("无法获取镜像文件大小: {}", e));
-        }
+    // 获取镜像文件大小（SWM 分卷镜像为全部分卷之和）
+    let image_parts = if image_path.to_lowercase().ends_with(".swm") {
+        crate::core::wimlib::find_swm_parts(image_path)
+    } else {
+        vec![PathBuf::from(image_path)]
     };
+    let mut image_size = 0;
+    for part in &image_parts {
+        match std::fs::metadata(part) {
+            Ok(meta) => image_size += meta.len(),
+            Err(e) => {
+                return Err(format!("无法获取镜像文件大小: {}", e));
+            }
+        }
+    }
     
     println!("[DATA PARTITION] 镜像文件大小: {} bytes ({:.2} GB)", 
         image_size, 
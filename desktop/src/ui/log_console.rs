@@ -0,0 +1,114 @@
+use egui;
+use tracing::Level;
+
+use crate::app::App;
+use crate::ui::a11y::icon_button_label;
+use crate::utils::log_console::{self, LogRecord};
+
+/// 日志级别过滤选项
+const LEVEL_OPTIONS: [(Level, &str); 3] = [
+    (Level::ERROR, "错误"),
+    (Level::WARN, "警告"),
+    (Level::INFO, "信息"),
+];
+
+impl App {
+    /// 显示底部日志控制台面板
+    pub fn render_log_console(&mut self, ctx: &egui::Context) {
+        if !self.show_log_console {
+            return;
+        }
+
+        let records = log_console::snapshot(self.log_console_level);
+
+        egui::TopBottomPanel::bottom("log_console_panel")
+            .resizable(true)
+            .default_height(200.0)
+            .min_height(100.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("日志").strong());
+                    ui.separator();
+
+                    let current = LEVEL_OPTIONS
+                        .iter()
+                        .find(|(level, _)| *level == self.log_console_level)
+                        .map(|(_, name)| *name)
+                        .unwrap_or("信息");
+                    egui::ComboBox::from_id_salt("log_console_level")
+                        .selected_text(current)
+                        .show_ui(ui, |ui| {
+                            for (level, name) in LEVEL_OPTIONS {
+                                ui.selectable_value(&mut self.log_console_level, level, name);
+                            }
+                        });
+
+                    ui.checkbox(&mut self.log_console_auto_scroll, "自动滚动");
+
+                    if ui.button("复制").clicked() {
+                        ui.ctx().copy_text(log_console::format_records(&records));
+                    }
+                    if ui.button("导出...").clicked() {
+                        self.export_log_console(&records);
+                    }
+                    if ui.button("清空").clicked() {
+                        log_console::clear();
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if icon_button_label(ui.small_button("✖"), "关闭日志面板").clicked() {
+                            self.show_log_console = false;
+                        }
+                        ui.label(format!("{} 条", records.len()));
+                    });
+                });
+                ui.separator();
+
+                let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                egui::ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(self.log_console_auto_scroll)
+                    .show_rows(ui, row_height, records.len(), |ui, range| {
+                        for record in &records[range] {
+                            let text = egui::RichText::new(record.to_line())
+                                .monospace()
+                                .color(level_color(record.level, ui));
+                            ui.add(egui::Label::new(text).extend());
+                        }
+                    });
+            });
+
+        // 日志来自后台线程，面板打开时定期刷新
+        ctx.request_repaint_after(std::time::Duration::from_millis(500));
+    }
+
+    /// 导出当前过滤后的日志到文件
+    fn export_log_console(&mut self, records: &[LogRecord]) {
+        let file_name = format!(
+            "LetRecovery-{}.log",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(&file_name)
+            .add_filter("日志文件", &["log", "txt"])
+            .save_file()
+        else {
+            return;
+        };
+
+        match std::fs::write(&path, log_console::format_records(records)) {
+            Ok(_) => log::info!("日志已导出: {}", path.display()),
+            Err(e) => self.show_error(&format!("导出日志失败: {}", e)),
+        }
+    }
+}
+
+/// 日志级别对应的文字颜色
+fn level_color(level: Level, ui: &egui::Ui) -> egui::Color32 {
+    match level {
+        Level::ERROR => egui::Color32::from_rgb(239, 83, 80),
+        Level::WARN => egui::Color32::from_rgb(255, 165, 0),
+        Level::INFO => ui.visuals().text_color(),
+        _ => egui::Color32::GRAY,
+    }
+}
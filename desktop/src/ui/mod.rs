@@ -1,14 +1,31 @@
+pub mod a11y;
 pub mod about;
 pub mod advanced_options;
+pub mod announcement;
+pub mod backup_schedule;
+pub mod diagnostics;
 pub mod download_progress;
+pub mod drop_image;
 pub mod easy_mode;
 pub mod embedded_assets;
+pub mod eula;
 pub mod hardware_info;
+pub mod help;
+pub mod image_library;
 pub mod install_progress;
+pub mod log_console;
 pub mod online_download;
+pub mod password_input;
+pub mod recent;
+pub mod restore_points;
 pub mod system_backup;
 pub mod system_install;
+pub mod task_tray;
+pub mod tech_lock;
 pub mod tools;
+#[path = "../../../shared/touch.rs"]
+pub mod touch;
+pub mod unattend_settings;
 
 // 导出内嵌资源
 pub use embedded_assets::{EmbeddedAssets, EmbeddedLogoType};
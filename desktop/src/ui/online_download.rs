@@ -4,6 +4,9 @@ use std::sync::Mutex;
 
 use crate::app::{App, OnlineDownloadTab, PendingSoftDownload, SoftIconState};
 use crate::download::config::{OnlineSystem, OnlineSoftware, OnlineGpuDriver};
+use crate::download::local_catalog::{import_image, LocalCatalog};
+use crate::download::recommend::{self, HardwareProfile, Recommendation, Verdict};
+use crate::download::torrent;
 
 /// 图标加载结果
 struct IconLoadResult {
@@ -11,6 +14,14 @@ struct IconLoadResult {
     data: Option<Vec<u8>>,
 }
 
+/// 在线镜像的文件名（磁力链接取 dn 参数）
+fn online_system_file_name(system: &OnlineSystem) -> Option<String> {
+    if torrent::is_torrent_link(&system.download_url) {
+        return torrent::magnet_display_name(&system.download_url);
+    }
+    system.download_url.split('/').last().map(|s| s.to_string()).filter(|s| !s.is_empty())
+}
+
 impl App {
     pub fn show_online_download(&mut self, ui: &mut egui::Ui) {
         ui.heading("在线下载");
@@ -93,19 +104,57 @@ impl App {
                     self.start_remote_config_loading();
                 }
             }
-            return;
+            if self.local_images.is_empty() {
+                ui.add_space(10.0);
+                if ui.button("导入本地镜像...").clicked() {
+                    self.import_local_image();
+                }
+                return;
+            }
+            ui.add_space(10.0);
         }
 
         // 克隆配置以避免借用冲突
-        let systems: Vec<OnlineSystem> = self
+        let online_systems: Vec<OnlineSystem> = self
             .config
             .as_ref()
             .map(|c| c.systems.clone())
             .unwrap_or_default();
+        let online_count = online_systems.len();
+        // 本地镜像排在服务器镜像之后，索引从 online_count 开始
+        let mut systems = online_systems;
+        systems.extend(self.local_images.iter().map(|image| image.to_online_system()));
 
         let mut system_to_download: Option<usize> = None;
         let mut system_to_install: Option<usize> = None;
         let mut system_selected: Option<usize> = None;
+        let mut local_to_verify: Option<usize> = None;
+        let mut local_to_remove: Option<usize> = None;
+
+        // 按本机硬件排序并附带推荐理由（硬件信息未加载时保持原顺序）
+        let ranked: Vec<(usize, Option<Recommendation>)> = match &self.hardware_info {
+            Some(hardware) => {
+                let profile = HardwareProfile::from_info(hardware, self.system_info.as_ref());
+                recommend::rank(&systems, &profile)
+                    .into_iter()
+                    .map(|(i, r)| (i, Some(r)))
+                    .collect()
+            }
+            None => (0..systems.len()).map(|i| (i, None)).collect(),
+        };
+        let hidden_count = ranked
+            .iter()
+            .filter(|(_, r)| r.as_ref().is_some_and(|r| r.verdict == Verdict::Unsupported))
+            .count();
+
+        if hidden_count > 0 {
+            ui.horizontal(|ui| {
+                ui.checkbox(
+                    &mut self.show_unsupported_images,
+                    format!("显示本机硬件不支持的镜像（{} 个）", hidden_count),
+                );
+            });
+        }
 
         egui::ScrollArea::vertical()
             .max_height(350.0)
@@ -117,28 +166,90 @@ impl App {
                     .show(ui, |ui| {
                         ui.label("系统名称");
                         ui.label("类型");
+                        ui.label("推荐");
                         ui.label("操作");
                         ui.end_row();
 
-                        for (i, system) in systems.iter().enumerate() {
-                            if ui
-                                .selectable_label(
-                                    self.selected_online_system == Some(i),
-                                    &system.display_name,
-                                )
-                                .clicked()
-                            {
-                                system_selected = Some(i);
+                        for (i, recommendation) in &ranked {
+                            let i = *i;
+                            let system = &systems[i];
+                            let local = i.checked_sub(online_count).and_then(|j| self.local_images.get(j));
+                            let unsupported = recommendation
+                                .as_ref()
+                                .is_some_and(|r| r.verdict == Verdict::Unsupported);
+                            if unsupported && !self.show_unsupported_images {
+                                continue;
                             }
 
-                            ui.label(if system.is_win11 { "Win11" } else { "Win10" });
+                            if local.is_none() {
+                                if ui
+                                    .selectable_label(
+                                        self.selected_online_system == Some(i),
+                                        &system.display_name,
+                                    )
+                                    .clicked()
+                                {
+                                    system_selected = Some(i);
+                                }
+                            } else {
+                                ui.label(&system.display_name).on_hover_text(&system.download_url);
+                            }
 
-                            ui.horizontal(|ui| {
-                                if ui.button("下载").clicked() {
-                                    system_to_download = Some(i);
+                            let kind = if system.is_win11 { "Win11" } else { "Win10" };
+                            match local {
+                                Some(image) => {
+                                    ui.label(format!("{}（本地 {:.1} GB）", kind, image.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0))
+                                        .on_hover_text(image.editions.join("\n"));
                                 }
-                                if ui.button("安装").clicked() {
-                                    system_to_install = Some(i);
+                                None => {
+                                    ui.label(kind);
+                                }
+                            }
+
+                            match recommendation {
+                                Some(r) => {
+                                    let color = match r.verdict {
+                                        Verdict::Recommended => egui::Color32::from_rgb(0, 180, 0),
+                                        Verdict::Neutral => ui.visuals().text_color(),
+                                        Verdict::NotRecommended => egui::Color32::from_rgb(255, 165, 0),
+                                        Verdict::Unsupported => egui::Color32::from_rgb(255, 80, 80),
+                                    };
+                                    ui.vertical(|ui| {
+                                        ui.colored_label(color, r.verdict.label());
+                                        for reason in &r.reasons {
+                                            ui.label(egui::RichText::new(reason).small().color(egui::Color32::GRAY));
+                                        }
+                                    });
+                                }
+                                None => {
+                                    ui.label("-");
+                                }
+                            }
+
+                            ui.horizontal(|ui| match local {
+                                Some(image) => {
+                                    let exists = image.exists();
+                                    if ui.add_enabled(exists, egui::Button::new("校验")).clicked() {
+                                        local_to_verify = Some(i - online_count);
+                                    }
+                                    if ui
+                                        .add_enabled(exists, egui::Button::new("安装"))
+                                        .on_disabled_hover_text("镜像文件不存在")
+                                        .clicked()
+                                    {
+                                        system_to_install = Some(i);
+                                    }
+                                    if ui.button("移除").clicked() {
+                                        local_to_remove = Some(i - online_count);
+                                    }
+                                }
+                                None => {
+                                    if ui.button("下载").clicked() {
+                                        system_to_download = Some(i);
+                                    }
+                                    if ui.button("安装").clicked() {
+                                        system_to_install = Some(i);
+                                    }
                                 }
                             });
                             ui.end_row();
@@ -153,46 +264,25 @@ impl App {
 
         // 处理下载
         if let Some(i) = system_to_download {
-            if let Some(system) = systems.get(i) {
-                self.pending_download_url = Some(system.download_url.clone());
-                self.pending_download_filename = None;
-                self.download_then_install = false;
-                self.download_then_install_path = None;
-                self.current_panel = crate::app::Panel::DownloadProgress;
-            }
+            self.start_online_system_download(i);
         }
 
-        // 处理安装（下载后跳转到安装页面）
+        // 处理安装（服务器镜像下载后跳转到安装页面，本地镜像直接跳转）
         if let Some(i) = system_to_install {
-            if let Some(system) = systems.get(i) {
-                // 从URL提取文件名
-                let filename = system.download_url
-                    .split('/')
-                    .last()
-                    .unwrap_or("system.iso")
-                    .to_string();
-                
-                // 设置下载路径
-                let save_path = if self.download_save_path.is_empty() {
-                    crate::utils::path::get_exe_dir()
-                        .join("downloads")
-                        .to_string_lossy()
-                        .to_string()
-                } else {
-                    self.download_save_path.clone()
-                };
-                
-                // 计算完整的文件路径
-                let full_path = std::path::Path::new(&save_path)
-                    .join(&filename)
-                    .to_string_lossy()
-                    .to_string();
-                
-                self.pending_download_url = Some(system.download_url.clone());
-                self.pending_download_filename = Some(filename);
-                self.download_then_install = true;
-                self.download_then_install_path = Some(full_path);
-                self.current_panel = crate::app::Panel::DownloadProgress;
+            match i.checked_sub(online_count) {
+                Some(j) => self.start_local_image_install(j),
+                None => self.start_online_system_install(i),
+            }
+        }
+
+        if let Some(j) = local_to_verify {
+            self.start_local_image_verify(j);
+        }
+
+        if let Some(j) = local_to_remove {
+            self.local_images.remove(j);
+            if let Err(e) = LocalCatalog::save(&self.local_images) {
+                log::warn!("保存本地镜像目录失败: {}", e);
             }
         }
 
@@ -221,7 +311,150 @@ impl App {
             if self.remote_config_loading {
                 ui.spinner();
             }
+            if ui.button("导入本地镜像...").clicked() {
+                self.import_local_image();
+            }
         });
+
+        // 磁力链接 / 种子下载
+        ui.add_space(5.0);
+        ui.horizontal(|ui| {
+            ui.label("磁力链接:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.torrent_link_input)
+                    .hint_text("magnet:?xt=urn:btih:... 或 .torrent 地址")
+                    .desired_width(400.0),
+            );
+            let link = self.torrent_link_input.trim().to_string();
+            let valid = torrent::is_torrent_link(&link);
+            if ui.add_enabled(valid, egui::Button::new("BT 下载")).clicked() {
+                self.start_torrent_link_download(link, false);
+            }
+            if ui.add_enabled(valid, egui::Button::new("下载并安装")).clicked() {
+                self.start_torrent_link_download(link, true);
+            }
+        });
+    }
+
+    /// 下载手动输入的磁力链接或种子，`then_install` 为 true 时完成后跳转到安装页面
+    fn start_torrent_link_download(&mut self, link: String, then_install: bool) {
+        log::info!("[BT] 手动添加: {}", link);
+        self.pending_download_url = Some(link);
+        self.pending_download_filename = None;
+        self.pending_chunk_manifest_url = None;
+        self.download_then_install = then_install;
+        // 实际文件路径在下载完成后由 BT 任务给出
+        self.download_then_install_path = None;
+        self.torrent_link_input.clear();
+        self.current_panel = crate::app::Panel::DownloadProgress;
+    }
+
+    /// 选择本地镜像文件并加入镜像列表
+    fn import_local_image(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("系统镜像", &["wim", "esd", "swm", "gho", "ghs", "iso"])
+            .pick_file()
+        else {
+            return;
+        };
+        let path = path.to_string_lossy().to_string();
+        if self.local_images.iter().any(|image| image.path.eq_ignore_ascii_case(&path)) {
+            self.show_error(&format!("该镜像已在列表中：\n{}", path));
+            return;
+        }
+
+        match import_image(&path) {
+            Ok(image) => {
+                log::info!("[LOCAL IMAGE] 导入: {} ({} 个版本)", image.display_name, image.editions.len());
+                self.local_images.push(image);
+                if let Err(e) = LocalCatalog::save(&self.local_images) {
+                    log::warn!("保存本地镜像目录失败: {}", e);
+                }
+            }
+            Err(e) => self.show_error(&format!("导入镜像失败：{:#}", e)),
+        }
+    }
+
+    /// 以本地镜像进入系统安装页
+    fn start_local_image_install(&mut self, index: usize) {
+        let Some(image) = self.local_images.get(index) else {
+            return;
+        };
+        self.local_image_path = image.path.clone();
+        self.iso_mount_error = None;
+        self.current_panel = crate::app::Panel::SystemInstall;
+        self.load_image_volumes();
+    }
+
+    /// 以本地镜像打开镜像校验工具
+    fn start_local_image_verify(&mut self, index: usize) {
+        let Some(image) = self.local_images.get(index) else {
+            return;
+        };
+        self.image_verify_file_path = image.path.clone();
+        self.image_verify_result = None;
+        self.image_verify_progress = None;
+        self.show_image_verify_dialog = true;
+        self.current_panel = crate::app::Panel::Tools;
+    }
+    
+    /// 在线系统镜像是否需要先接受许可协议（需要时弹出协议对话框）
+    fn require_online_system_eula(&mut self, system: &OnlineSystem, action: crate::app::EulaAction) -> bool {
+        let filename = online_system_file_name(system).unwrap_or_default();
+        self.require_eula(&[&filename, &system.display_name], action)
+    }
+
+    /// 下载在线系统镜像
+    pub fn start_online_system_download(&mut self, index: usize) {
+        let Some(system) = self.config.as_ref().and_then(|c| c.systems.get(index).cloned()) else {
+            return;
+        };
+        if self.require_online_system_eula(&system, crate::app::EulaAction::OnlineDownload(index)) {
+            return;
+        }
+
+        self.pending_download_url = Some(system.download_url.clone());
+        self.pending_download_filename = None;
+        self.pending_chunk_manifest_url = system.chunk_manifest_url.clone();
+        self.download_then_install = false;
+        self.download_then_install_path = None;
+        self.current_panel = crate::app::Panel::DownloadProgress;
+    }
+
+    /// 下载在线系统镜像并在完成后跳转到安装页面
+    pub fn start_online_system_install(&mut self, index: usize) {
+        let Some(system) = self.config.as_ref().and_then(|c| c.systems.get(index).cloned()) else {
+            return;
+        };
+        if self.require_online_system_eula(&system, crate::app::EulaAction::OnlineInstall(index)) {
+            return;
+        }
+
+        // 从URL提取文件名
+        let filename = online_system_file_name(&system).unwrap_or_else(|| "system.iso".to_string());
+        
+        // 设置下载路径
+        let save_path = if self.download_save_path.is_empty() {
+            crate::utils::path::get_exe_dir()
+                .join("downloads")
+                .to_string_lossy()
+                .to_string()
+        } else {
+            self.download_save_path.clone()
+        };
+        
+        // 计算完整的文件路径
+        let full_path = std::path::Path::new(&save_path)
+            .join(&filename)
+            .to_string_lossy()
+            .to_string();
+        
+        self.pending_download_url = Some(system.download_url.clone());
+        self.pending_download_filename = Some(filename);
+        self.pending_chunk_manifest_url = system.chunk_manifest_url.clone();
+        self.download_then_install = true;
+        self.download_then_install_path = Some(full_path);
+        self.current_panel = crate::app::Panel::DownloadProgress;
     }
     
     /// 显示软件下载选项卡
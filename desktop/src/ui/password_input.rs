@@ -0,0 +1,196 @@
+//! 密码输入框
+//!
+//! PE 中的键盘布局经常和用户习惯的不一致（如 AZERTY / QWERTZ），按错键导致 BitLocker 等密码解锁失败。
+//! 密码框后显示当前键盘布局和大写锁定状态，可切换明文显示，并提供不受键盘布局影响的屏幕小键盘。
+
+use egui;
+
+/// 屏幕小键盘的字符行（小写）
+const KEYPAD_ROWS: [&str; 4] = ["1234567890-", "qwertyuiop", "asdfghjkl", "zxcvbnm.@"];
+
+/// 输入框的显示状态（保存在 egui 临时存储中）
+#[derive(Debug, Clone, Copy, Default)]
+struct FieldState {
+    /// 明文显示
+    reveal: bool,
+    /// 显示屏幕小键盘
+    keypad: bool,
+    /// 小键盘大写
+    shift: bool,
+}
+
+/// 根据语言 ID 获取键盘布局的（简称, 名称）
+fn layout_name(lang_id: u16) -> Option<(&'static str, &'static str)> {
+    Some(match lang_id {
+        0x0409 => ("EN", "英语(美国)"),
+        0x0809 => ("EN", "英语(英国)"),
+        0x0804 => ("中", "中文(简体)"),
+        0x0404 => ("繁", "中文(繁体)"),
+        0x0407 => ("DE", "德语"),
+        0x040C => ("FR", "法语"),
+        0x0410 => ("IT", "意大利语"),
+        0x0C0A | 0x040A => ("ES", "西班牙语"),
+        0x0419 => ("RU", "俄语"),
+        0x0411 => ("日", "日语"),
+        0x0412 => ("한", "韩语"),
+        _ => return None,
+    })
+}
+
+/// 当前线程的键盘布局语言 ID
+#[cfg(windows)]
+fn current_lang_id() -> u16 {
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
+
+    let hkl = unsafe { GetKeyboardLayout(0) };
+    (hkl.0 as usize & 0xFFFF) as u16
+}
+
+#[cfg(not(windows))]
+fn current_lang_id() -> u16 {
+    0x0409
+}
+
+/// 大写锁定是否开启
+#[cfg(windows)]
+fn caps_lock_on() -> bool {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, VK_CAPITAL};
+
+    unsafe { GetKeyState(VK_CAPITAL.0 as i32) & 1 != 0 }
+}
+
+#[cfg(not(windows))]
+fn caps_lock_on() -> bool {
+    false
+}
+
+/// 小键盘按键输出的字符
+fn keypad_char(key: char, shift: bool) -> char {
+    if shift {
+        key.to_ascii_uppercase()
+    } else {
+        key
+    }
+}
+
+/// 带标签的密码输入框（可切换明文显示）
+///
+/// 调用方负责外层布局（通常位于 `ui.horizontal` 中）
+pub fn password_field(ui: &mut egui::Ui, label: &str, text: &mut String, width: f32) -> egui::Response {
+    assisted_field(ui, label, text, width, true, "")
+}
+
+/// 带标签的恢复密钥输入框
+///
+/// 调用方负责外层布局（通常位于 `ui.horizontal` 中）
+pub fn recovery_key_field(ui: &mut egui::Ui, label: &str, text: &mut String, width: f32) -> egui::Response {
+    assisted_field(
+        ui,
+        label,
+        text,
+        width,
+        false,
+        "000000-000000-000000-000000-000000-000000-000000-000000",
+    )
+}
+
+/// 输入框及其后的明文切换、小键盘按钮和键盘布局提示
+fn assisted_field(
+    ui: &mut egui::Ui,
+    label: &str,
+    text: &mut String,
+    width: f32,
+    masked: bool,
+    hint: &str,
+) -> egui::Response {
+    let id = ui.id().with(label);
+    let mut state = ui.data_mut(|d| *d.get_temp_mut_or_default::<FieldState>(id));
+
+    let label = ui.label(label);
+    let response = ui
+        .add(
+            egui::TextEdit::singleline(text)
+                .password(masked && !state.reveal)
+                .desired_width(width)
+                .hint_text(hint),
+        )
+        .labelled_by(label.id);
+    super::touch::keyboard_for(ui, &response);
+
+    if masked {
+        let icon = if state.reveal { "🙈" } else { "👁" };
+        let tip = if state.reveal { "隐藏密码" } else { "显示密码" };
+        if super::a11y::icon_button_label(ui.button(icon), tip).clicked() {
+            state.reveal = !state.reveal;
+        }
+    }
+    if super::a11y::icon_button_label(ui.selectable_label(state.keypad, "🔢"), "屏幕小键盘（不受键盘布局影响）")
+        .clicked()
+    {
+        state.keypad = !state.keypad;
+    }
+
+    let lang_id = current_lang_id();
+    let (short, name) = layout_name(lang_id).unwrap_or(("?", "未知布局"));
+    ui.label(egui::RichText::new(short).small().strong())
+        .on_hover_text(format!("当前键盘布局: {} (0x{:04X})", name, lang_id));
+    if caps_lock_on() {
+        ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⇪ 大写锁定");
+    }
+
+    if state.keypad {
+        egui::Area::new(id.with("keypad"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(response.rect.left_bottom() + egui::vec2(0.0, 4.0))
+            .show(ui.ctx(), |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    for row in KEYPAD_ROWS {
+                        ui.horizontal(|ui| {
+                            for key in row.chars() {
+                                let ch = keypad_char(key, state.shift);
+                                if ui.button(ch.to_string()).clicked() {
+                                    text.push(ch);
+                                }
+                            }
+                        });
+                    }
+                    ui.horizontal(|ui| {
+                        if ui.selectable_label(state.shift, "⇧ 大写").clicked() {
+                            state.shift = !state.shift;
+                        }
+                        if ui.button("⌫ 退格").clicked() {
+                            text.pop();
+                        }
+                        if ui.button("清空").clicked() {
+                            text.clear();
+                        }
+                        if ui.button("关闭").clicked() {
+                            state.keypad = false;
+                        }
+                    });
+                });
+            });
+    }
+
+    ui.data_mut(|d| d.insert_temp(id, state));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_name() {
+        assert_eq!(layout_name(0x0409).map(|(s, _)| s), Some("EN"));
+        assert_eq!(layout_name(0x040C).map(|(_, n)| n), Some("法语"));
+        assert_eq!(layout_name(0x1234), None);
+    }
+
+    #[test]
+    fn test_keypad_char() {
+        assert_eq!(keypad_char('a', true), 'A');
+        assert_eq!(keypad_char('7', true), '7');
+        assert_eq!(keypad_char('q', false), 'q');
+    }
+}
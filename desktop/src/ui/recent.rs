@@ -0,0 +1,22 @@
+//! 最近使用列表下拉菜单
+
+use egui;
+
+use crate::ui::a11y::icon_button_label;
+
+/// 在路径输入框旁显示最近使用下拉按钮，返回用户选中的路径
+pub fn recent_menu(ui: &mut egui::Ui, items: &[String]) -> Option<String> {
+    let mut selected = None;
+    ui.add_enabled_ui(!items.is_empty(), |ui| {
+        let response = ui.menu_button("🕘", |ui| {
+            for item in items {
+                if ui.button(item).clicked() {
+                    selected = Some(item.clone());
+                    ui.close_menu();
+                }
+            }
+        });
+        icon_button_label(response.response, "最近使用");
+    });
+    selected
+}
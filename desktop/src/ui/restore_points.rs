@@ -0,0 +1,205 @@
+//! 还原点选择
+//!
+//! 所选镜像带有备份目录（`core::backup_catalog`）时，在系统安装页列出备份链，
+//! 点击还原点即选中对应分卷进行还原，也可以删除不再需要的旧还原点。
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::backup_catalog::BackupCatalog;
+use crate::core::dism::DismProgress;
+use crate::core::hardware_info::format_bytes;
+use crate::core::restore_points;
+use crate::core::wim_manage::WimImageEntry;
+
+/// 还原点列表状态
+#[derive(Default)]
+pub struct RestorePointState {
+    /// 当前镜像的备份目录，没有目录时不显示还原点
+    pub catalog: Option<BackupCatalog>,
+    /// 等待确认删除的分卷索引
+    pub confirm_delete: Option<u32>,
+    pub progress: Option<DismProgress>,
+    pub progress_rx: Option<mpsc::Receiver<DismProgress>>,
+    pub result_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub message: String,
+}
+
+impl RestorePointState {
+    /// 切换镜像时读取其备份目录
+    pub fn load(&mut self, image_path: &str) {
+        *self = Self::default();
+        self.catalog = match BackupCatalog::load(image_path) {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                log::warn!("[RESTORE POINT] {:#}", e);
+                None
+            }
+        };
+    }
+
+    /// 是否正在删除还原点
+    pub fn is_busy(&self) -> bool {
+        self.result_rx.is_some()
+    }
+}
+
+impl App {
+    /// 显示还原点列表，删除完成需要重新读取镜像信息时返回 true
+    pub fn show_restore_points(&mut self, ui: &mut egui::Ui) -> bool {
+        let reload = self.check_restore_point_result();
+        let Some(catalog) = self.restore_points.catalog.as_ref() else {
+            return reload;
+        };
+        if self.image_volumes.is_empty() {
+            return reload;
+        }
+
+        let images: Vec<WimImageEntry> = self
+            .image_volumes
+            .iter()
+            .map(|volume| WimImageEntry {
+                index: volume.index,
+                name: volume.name.clone(),
+                total_bytes: volume.size_bytes,
+                ..Default::default()
+            })
+            .collect();
+        let points = catalog.restore_points(&images);
+        let selected_index = self
+            .selected_volume
+            .and_then(|i| self.image_volumes.get(i))
+            .map(|volume| volume.index);
+        let busy = self.restore_points.is_busy() || self.is_installing;
+
+        let mut select: Option<u32> = None;
+        let mut delete: Option<u32> = None;
+        ui.add_space(5.0);
+        ui.label("还原点（增量备份链）:");
+        egui::ScrollArea::vertical()
+            .id_salt("restore_points")
+            .max_height(160.0)
+            .show(ui, |ui| {
+                egui::Grid::new("restore_points_grid")
+                    .num_columns(7)
+                    .striped(true)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        ui.strong("还原点");
+                        ui.strong("备份时间");
+                        ui.strong("源分区");
+                        ui.strong("Windows 版本");
+                        ui.strong("新增大小");
+                        ui.strong("展开大小");
+                        ui.label("");
+                        ui.end_row();
+
+                        for point in &points {
+                            let label = format!("{} - {}", point.index, point.name);
+                            if ui.selectable_label(selected_index == Some(point.index), label).clicked() {
+                                select = Some(point.index);
+                            }
+                            ui.label(point.created_at_text());
+                            match point.entry {
+                                Some(ref entry) => {
+                                    ui.label(&entry.source_partition);
+                                    ui.label(if entry.windows_build.is_empty() { "-" } else { entry.windows_build.as_str() });
+                                    ui.label(format_bytes(entry.added_bytes));
+                                }
+                                None => {
+                                    ui.label("-");
+                                    ui.label("-");
+                                    ui.label("-");
+                                }
+                            }
+                            ui.label(format_bytes(point.total_bytes));
+                            if ui
+                                .add_enabled(!busy && points.len() > 1, egui::Button::new("删除"))
+                                .on_disabled_hover_text("镜像至少保留一个还原点")
+                                .clicked()
+                            {
+                                self.restore_points.confirm_delete = Some(point.index);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        if let Some(index) = self.restore_points.confirm_delete {
+            ui.horizontal(|ui| {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    format!("确定删除还原点 {}？删除后需要重写整个镜像文件，无法恢复。", index),
+                );
+                if ui.button("删除").clicked() {
+                    delete = Some(index);
+                }
+                if ui.button("取消").clicked() {
+                    self.restore_points.confirm_delete = None;
+                }
+            });
+        }
+
+        if let Some(ref progress) = self.restore_points.progress {
+            ui.add(
+                egui::ProgressBar::new(progress.percentage as f32 / 100.0)
+                    .text(&progress.status)
+                    .animate(true),
+            );
+        } else if self.restore_points.is_busy() {
+            ui.spinner();
+        }
+        if !self.restore_points.message.is_empty() {
+            ui.label(&self.restore_points.message);
+        }
+
+        if let Some(index) = select {
+            self.selected_volume = self.image_volumes.iter().position(|volume| volume.index == index);
+        }
+        if let Some(index) = delete {
+            self.start_delete_restore_point(images, index);
+        }
+        reload
+    }
+
+    fn start_delete_restore_point(&mut self, images: Vec<WimImageEntry>, index: u32) {
+        let image_path = self.local_image_path.clone();
+        let state = &mut self.restore_points;
+        state.confirm_delete = None;
+        state.progress = None;
+        state.message = format!("正在删除还原点 {}...", index);
+
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        state.progress_rx = Some(progress_rx);
+        state.result_rx = Some(result_rx);
+
+        std::thread::spawn(move || {
+            let result = restore_points::delete_restore_point(&image_path, &images, index, progress_tx)
+                .map(|_| format!("✅ 已删除还原点 {}", index))
+                .map_err(|e| format!("❌ {:#}", e));
+            let _ = result_tx.send(result);
+        });
+    }
+
+    /// 检查删除结果，成功时返回 true
+    fn check_restore_point_result(&mut self) -> bool {
+        let state = &mut self.restore_points;
+        if let Some(ref rx) = state.progress_rx {
+            while let Ok(progress) = rx.try_recv() {
+                state.progress = Some(progress);
+            }
+        }
+
+        let Some(result) = state.result_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return false;
+        };
+        let reload = result.is_ok();
+        state.message = result.unwrap_or_else(|e| e);
+        state.progress = None;
+        state.progress_rx = None;
+        state.result_rx = None;
+        reload
+    }
+}
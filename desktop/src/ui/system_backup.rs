@@ -3,8 +3,14 @@ use std::sync::mpsc;
 use std::path::Path;
 
 use crate::app::{App, BackupFormat, BackupMode, Panel};
+use crate::core::backup_catalog;
+use crate::core::capture_options::{self, CaptureCompression, CaptureOptions};
 use crate::core::dism::{Dism, DismProgress};
 use crate::core::install_config::{BackupConfig, ConfigFileManager};
+use crate::download::help::HelpTopic;
+use crate::ui::help::help_button;
+use crate::ui::recent::recent_menu;
+use crate::utils::json_progress::{self, ProgressEvent};
 
 impl App {
     pub fn show_system_backup(&mut self, ui: &mut egui::Ui) {
@@ -179,6 +185,10 @@ impl App {
                     self.backup_incremental = Path::new(&self.backup_save_path).exists();
                 }
             }
+            if let Some(path) = recent_menu(ui, &self.app_config.recent_backup_paths) {
+                self.backup_save_path = path;
+                self.backup_incremental = Path::new(&self.backup_save_path).exists();
+            }
         });
 
         // 备份名称
@@ -200,7 +210,17 @@ impl App {
         ui.add_space(15.0);
 
         // 备份选项
-        ui.checkbox(&mut self.backup_incremental, "增量备份 (追加到现有镜像)");
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.backup_incremental, "增量备份 (追加到现有镜像)");
+            help_button(ui, HelpTopic::IncrementalBackup);
+        });
+
+        // 捕获选项（GHO 由 Ghost 自行处理）
+        let capture_error = if self.backup_format == BackupFormat::Gho {
+            None
+        } else {
+            self.show_capture_options(ui)
+        };
 
         // PE选择（仅在需要通过PE备份时显示）
         if show_pe_selector {
@@ -268,6 +288,7 @@ impl App {
             && !self.backup_save_path.is_empty()
             && !self.backup_name.is_empty()
             && !backup_blocked
+            && capture_error.is_none()
             && (!show_pe_selector || self.selected_pe_for_backup.is_some());
 
         ui.horizontal(|ui| {
@@ -374,6 +395,10 @@ impl App {
     fn check_bitlocker_for_backup(&self) -> Vec<crate::ui::tools::BitLockerPartition> {
         use crate::core::bitlocker::BitLockerManager;
         
+        if crate::core::demo::is_enabled() {
+            return Vec::new();
+        }
+
         let manager = BitLockerManager::new();
         if !manager.is_available() {
             return Vec::new();
@@ -402,6 +427,83 @@ impl App {
         locked_partitions
     }
 
+    /// 压缩方式和排除列表设置，排除规则有误时返回错误信息
+    fn show_capture_options(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        egui::CollapsingHeader::new("捕获选项")
+            .id_salt("backup_capture_options")
+            .show(ui, |ui| {
+                // ESD 固定使用 LZMS；追加到已有镜像时沿用镜像原有的压缩方式
+                if matches!(self.backup_format, BackupFormat::Wim | BackupFormat::Swm) {
+                    ui.horizontal(|ui| {
+                        ui.label("压缩方式:");
+                        egui::ComboBox::from_id_salt("backup_compression_select")
+                            .selected_text(self.backup_compression.label())
+                            .show_ui(ui, |ui| {
+                                for compression in CaptureCompression::ALL {
+                                    ui.selectable_value(&mut self.backup_compression, compression, compression.label());
+                                }
+                            });
+                        if self.backup_incremental {
+                            ui.label("(增量备份沿用现有镜像的压缩方式)");
+                        }
+                    });
+                }
+
+                ui.label("排除的文件和目录（每行一条，以 \\ 开头表示从分区根目录匹配，支持 * 和 ? 通配符）:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.backup_exclusions)
+                        .desired_width(500.0)
+                        .desired_rows(6)
+                        .font(egui::TextStyle::Monospace),
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button("导入 WimScript.ini...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter("WimScript", &["ini"]).pick_file() {
+                            match capture_options::load_wimscript(&path) {
+                                Ok(exclusions) => self.backup_exclusions = exclusions.join("\n"),
+                                Err(e) => self.show_error(&format!("{:#}", e)),
+                            }
+                        }
+                    }
+                    if ui.button("导出 WimScript.ini...").clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WimScript", &["ini"])
+                            .set_file_name("WimScript.ini")
+                            .save_file()
+                        {
+                            if let Err(e) = self.backup_capture_options().write_wimscript(&path) {
+                                self.show_error(&format!("{:#}", e));
+                            }
+                        }
+                    }
+                    if ui.button("恢复默认").clicked() {
+                        self.backup_exclusions = capture_options::DEFAULT_EXCLUSIONS.join("\n");
+                    }
+                });
+            });
+
+        let error = self.backup_capture_options().validate().err();
+        if let Some(ref e) = error {
+            ui.colored_label(egui::Color32::RED, format!("❌ 排除规则有误: {}", e));
+        }
+        error
+    }
+
+    /// 按界面设置生成捕获选项
+    fn backup_capture_options(&self) -> CaptureOptions {
+        CaptureOptions {
+            compression: self.backup_compression,
+            exclusions: self
+                .backup_exclusions
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+
     fn start_backup(&mut self) {
         let source_partition = self
             .partitions
@@ -442,11 +544,13 @@ impl App {
         }
         let source_partition = source_partition.unwrap();
 
+        self.app_config.add_recent_backup_path(&self.backup_save_path);
+
         let is_system_partition = source_partition.is_system_partition;
         let is_pe = self.is_pe_environment();
 
         // 确定备份模式
-        self.backup_mode = if is_pe || !is_system_partition {
+        self.backup_mode = if is_pe || !is_system_partition || crate::core::demo::is_enabled() {
             BackupMode::Direct
         } else {
             BackupMode::ViaPE
@@ -500,7 +604,7 @@ impl App {
         let is_pe = self.is_pe_environment();
 
         // 确定备份模式
-        self.backup_mode = if is_pe || !is_system_partition {
+        self.backup_mode = if is_pe || !is_system_partition || crate::core::demo::is_enabled() {
             BackupMode::Direct
         } else {
             BackupMode::ViaPE
@@ -510,33 +614,71 @@ impl App {
         self.backup_progress = 0;
         self.backup_error = None;
 
+        if crate::core::demo::is_enabled() {
+            self.start_demo_backup(source_partition);
+            return;
+        }
+
         match self.backup_mode {
             BackupMode::Direct => self.start_direct_backup(source_partition),
             BackupMode::ViaPE => self.start_pe_backup(source_partition),
         }
     }
 
+    /// 演示模式：模拟备份进度，不写入镜像文件
+    fn start_demo_backup(&mut self, source_partition: crate::core::disk::Partition) {
+        let (progress_tx, progress_rx) = mpsc::channel::<DismProgress>();
+        self.backup_progress_rx = Some(progress_rx);
+        log::info!("[DEMO] 模拟备份 {} -> {}", source_partition.letter, self.backup_save_path);
+
+        std::thread::spawn(move || {
+            for percentage in (0..100).step_by(5) {
+                let _ = progress_tx.send(DismProgress {
+                    percentage,
+                    status: format!("[演示] 正在备份 {}", source_partition.letter),
+                });
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+            let _ = progress_tx.send(DismProgress {
+                percentage: 100,
+                status: "备份完成".to_string(),
+            });
+        });
+    }
+
     fn start_direct_backup(&mut self, source_partition: crate::core::disk::Partition) {
         let (progress_tx, progress_rx) = mpsc::channel::<DismProgress>();
         self.backup_progress_rx = Some(progress_rx);
 
-        let capture_dir = format!("{}\\", source_partition.letter);
+        let capture_dir = crate::core::disk::volume_root(&source_partition.letter);
         let image_file = self.backup_save_path.clone();
         let name = self.backup_name.clone();
         let description = self.backup_description.clone();
         let is_incremental = self.backup_incremental;
+        let io_priority = self.app_config.io_priority;
+        let image_backend = self.app_config.image_backend;
+        let capture_options = self.backup_capture_options();
+        let source_letter = source_partition.letter.clone();
 
         std::thread::spawn(move || {
-            let dism = Dism::new();
+            // wimgapi/wimlib 自行打开文件，后台优先级通过线程后台模式生效
+            let _io_guard = crate::core::io_priority::BackgroundModeGuard::enter(io_priority);
+            let dism = Dism::with_backend(image_backend);
+            let size_before = backup_catalog::image_size(&image_file);
             
             let result = if is_incremental && Path::new(&image_file).exists() {
-                dism.append_image(&image_file, &capture_dir, &name, &description, Some(progress_tx.clone()))
+                dism.append_image(&image_file, &capture_dir, &name, &description, &capture_options, Some(progress_tx.clone()))
             } else {
-                dism.capture_image(&image_file, &capture_dir, &name, &description, Some(progress_tx.clone()))
+                dism.capture_image(&image_file, &capture_dir, &name, &description, &capture_options, Some(progress_tx.clone()))
             };
 
             match result {
                 Ok(_) => {
+                    if let Err(e) =
+                        backup_catalog::record_backup(&image_file, &source_letter, &name, &description, size_before)
+                    {
+                        log::warn!("[BACKUP] 记录还原点失败: {:#}", e);
+                    }
                     let _ = progress_tx.send(DismProgress {
                         percentage: 100,
                         status: "备份完成".to_string(),
@@ -565,6 +707,8 @@ impl App {
         let is_incremental = self.backup_incremental;
         let backup_format = self.backup_format.to_config_value();
         let swm_split_size = self.backup_swm_split_size;
+        let capture_options = self.backup_capture_options();
+        let image_backend = self.app_config.image_backend;
         
         let pe_info = self.selected_pe_for_backup.and_then(|idx| {
             self.config.as_ref().and_then(|c| c.pe_list.get(idx).cloned())
@@ -629,12 +773,15 @@ impl App {
                 incremental: is_incremental,
                 format: backup_format,
                 swm_split_size: swm_split_size,
+                capture: capture_options,
+                image_backend,
+                ..Default::default()
             };
             
             if let Err(e) = ConfigFileManager::write_backup_config(&source_letter, &data_partition, &backup_config) {
                 let _ = progress_tx.send(DismProgress {
                     percentage: 0,
-                    status: format!("备份失败: 配置文件写入失败 {}", e),
+                    status: format!("备份失败: 配置文件写入失败 {:#}", e),
                 });
                 return;
             }
@@ -656,11 +803,11 @@ impl App {
 
         let mut should_finish = false;
         let mut error_msg: Option<String> = None;
-        let mut latest_progress: Option<u8> = None;
+        let mut latest_progress: Option<(u8, String)> = None;
 
         if let Some(ref rx) = self.backup_progress_rx {
             while let Ok(progress) = rx.try_recv() {
-                latest_progress = Some(progress.percentage);
+                latest_progress = Some((progress.percentage, progress.status.clone()));
                 
                 if progress.percentage >= 100 {
                     should_finish = true;
@@ -671,15 +818,23 @@ impl App {
             }
         }
 
-        if let Some(p) = latest_progress {
+        if let Some((p, status)) = latest_progress {
+            if p != self.backup_progress {
+                json_progress::emit(ProgressEvent::Progress { percentage: p, status });
+            }
             self.backup_progress = p;
         }
 
         if let Some(err) = error_msg {
+            json_progress::emit(ProgressEvent::Error { message: err.clone() });
             self.backup_error = Some(err);
         }
 
         if should_finish {
+            json_progress::emit(ProgressEvent::Finished {
+                success: self.backup_error.is_none(),
+                message: self.backup_error.clone().unwrap_or_else(|| "备份完成".to_string()),
+            });
             self.is_backing_up = false;
             self.backup_progress_rx = None;
         }
@@ -738,9 +893,8 @@ impl App {
                     ui.add_space(10.0);
                     ui.horizontal(|ui| {
                         if ui.button("立即重启").clicked() {
-                            let _ = crate::utils::cmd::create_command("shutdown")
-                                .args(["/r", "/t", "5", "/c", "LetRecovery 即将重启到PE环境进行备份..."])
-                                .spawn();
+                            let _ = crate::core::power::CompletionAction::RebootCountdown
+                                .execute(5, "LetRecovery 即将重启到PE环境进行备份...");
                         }
                         if ui.button("稍后重启").clicked() {
                             self.current_panel = Panel::SystemBackup;
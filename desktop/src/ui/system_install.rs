@@ -1,13 +1,21 @@
 use egui;
+use std::path::Path;
 use std::sync::mpsc;
 
 use crate::app::{App, BootModeSelection, UnattendCheckResult};
-use crate::core::disk::{Partition, PartitionStyle};
+use crate::core::disk::{describe_gpt_partition, Partition, PartitionStyle};
+use crate::download::help::HelpTopic;
+use crate::ui::a11y::icon_button_label;
+use crate::ui::help::help_button;
+use crate::ui::image_library::{library_button, LibraryTarget};
+use crate::ui::recent::recent_menu;
 use crate::core::dism::ImageInfo;
+use crate::core::image_compat::{self, CompatIssue, CompatLevel, HostInfo, ImageDetails};
 
 /// ISO 挂载结果
 pub enum IsoMountResult {
-    Success(String),
+    /// ISO 路径和其中找到的安装镜像
+    Success(String, Vec<String>),
     Error(String),
 }
 
@@ -17,6 +25,17 @@ pub enum ImageInfoResult {
     Error(String),
 }
 
+/// 所选分卷的兼容性检查状态
+#[derive(Default)]
+pub struct ImageCompatState {
+    /// 镜像路径和分卷索引，变化时重新读取
+    pub key: Option<String>,
+    pub details: Option<ImageDetails>,
+    pub rx: Option<mpsc::Receiver<Option<ImageDetails>>>,
+    /// 用户确认仍然安装存在阻止性问题的分卷（更换镜像或分卷时重置）
+    pub accept_blocking: bool,
+}
+
 impl App {
     pub fn show_system_install(&mut self, ui: &mut egui::Ui) {
         ui.heading("系统安装");
@@ -32,7 +51,7 @@ impl App {
                     "💡 新手用户？可以在\"关于\"页面中开启小白模式，获得更简单的操作体验",
                 );
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if ui.small_button("×").clicked() {
+                    if icon_button_label(ui.small_button("×"), "关闭提示").clicked() {
                         self.app_config.dismiss_easy_mode_tip();
                     }
                 });
@@ -61,7 +80,13 @@ impl App {
             
             let text_edit = egui::TextEdit::singleline(&mut self.local_image_path)
                 .desired_width(400.0);
-            ui.add_enabled(!self.iso_mounting, text_edit);
+            // 手动填写的网络地址不会触发读取分卷，按网络镜像处理并清除上一个镜像的分卷信息
+            if ui.add_enabled(!self.iso_mounting, text_edit).changed()
+                && crate::core::network_source::NetworkSource::parse(&self.local_image_path).is_some()
+            {
+                self.share_image_readable = false;
+                self.clear_image_volumes();
+            }
             
             if ui.add_enabled(!self.iso_mounting, egui::Button::new("浏览...")).clicked() {
                 if let Some(path) = rfd::FileDialog::new()
@@ -73,8 +98,72 @@ impl App {
                     self.load_image_volumes();
                 }
             }
+
+            if !self.iso_mounting {
+                if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                    self.local_image_path = path;
+                    self.iso_mount_error = None;
+                    self.load_image_volumes();
+                }
+                if library_button(ui) {
+                    self.image_library.open(LibraryTarget::Install);
+                }
+            }
         });
 
+        // 共享中的镜像通过 PE 安装时，PE 用此账户重新连接共享并直接读取镜像
+        let network_source = crate::core::network_source::NetworkSource::parse(&self.local_image_path);
+        if show_pe_selector && matches!(network_source, Some(crate::core::network_source::NetworkSource::Share { .. })) {
+            ui.horizontal(|ui| {
+                ui.label("共享账户:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.share_user)
+                        .hint_text("用户名（可留空）")
+                        .desired_width(150.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.share_password)
+                        .password(true)
+                        .hint_text("密码")
+                        .desired_width(150.0),
+                );
+                ui.weak("PE 中连接共享直接读取镜像，不复制到本地");
+            });
+        }
+        if matches!(network_source, Some(crate::core::network_source::NetworkSource::Netcast { .. })) {
+            ui.weak("PE 启动后从局域网发送端接收镜像（工具箱 → 局域网分发），请先在发送端开始分发");
+        }
+        // HTTP 镜像下载后按 SHA-256 校验，校验值保存在地址的 #sha256= 中
+        if let Some(crate::core::network_source::NetworkSource::Http { ref url, ref sha256 }) = network_source {
+            let mut hash = sha256.clone();
+            ui.horizontal(|ui| {
+                let label = ui.label("SHA-256:");
+                let edit = egui::TextEdit::singleline(&mut hash)
+                    .hint_text("镜像文件的 SHA-256 校验值")
+                    .desired_width(400.0);
+                if ui.add(edit).labelled_by(label.id).changed() {
+                    self.local_image_path = format!("{}#sha256={}", url, hash.trim());
+                }
+            });
+            ui.weak("PE 下载镜像后校验，校验值不一致时不安装");
+        }
+        if let Some(Err(reason)) = network_source.as_ref().map(|source| source.check()) {
+            ui.colored_label(egui::Color32::from_rgb(255, 165, 0), format!("⚠ {}", reason));
+        }
+        // 本机无法读取的网络镜像由 PE 获取后安装，分卷序号手动填写
+        let remote_source = self.remote_image_source();
+        if let Some(ref source) = remote_source {
+            if !show_pe_selector {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⚠ 网络镜像只能通过 PE 安装到当前系统分区");
+            } else if !source.is_gho() {
+                ui.horizontal(|ui| {
+                    ui.label("分卷序号:");
+                    ui.add(egui::DragValue::new(&mut self.network_volume_index).range(1..=99));
+                    ui.weak("本机无法读取网络镜像的分卷信息，请填写要安装的分卷序号（通常为 1）");
+                });
+            }
+        }
+
         // 显示ISO挂载状态
         if self.iso_mounting {
             ui.horizontal(|ui| {
@@ -96,6 +185,25 @@ impl App {
             ui.colored_label(egui::Color32::RED, format!("ISO 挂载失败: {}", error));
         }
 
+        // 多架构 ISO 中有多个安装镜像时选择其一
+        if self.iso_install_images.len() > 1 && !self.iso_mounting {
+            let mut selected_image = self.local_image_path.clone();
+            ui.horizontal(|ui| {
+                ui.label("ISO 内镜像:");
+                egui::ComboBox::from_id_salt("iso_install_image")
+                    .selected_text(selected_image.clone())
+                    .show_ui(ui, |ui| {
+                        for image in &self.iso_install_images {
+                            ui.selectable_value(&mut selected_image, image.clone(), image.as_str());
+                        }
+                    });
+            });
+            if selected_image != self.local_image_path && !self.image_info_loading {
+                self.local_image_path = selected_image.clone();
+                self.start_image_info_loading(&selected_image);
+            }
+        }
+
         // 镜像分卷选择（过滤掉 WindowsPE 等非系统镜像）
         if !self.image_volumes.is_empty() {
             // 过滤出可安装的系统镜像
@@ -170,6 +278,35 @@ impl App {
                 }
             }
         }
+
+        // 备份镜像：列出还原点，删除后重新读取分卷
+        if self.show_restore_points(ui) {
+            let message = std::mem::take(&mut self.restore_points.message);
+            self.start_image_info_loading(&self.local_image_path.clone());
+            self.restore_points.message = message;
+        }
+        
+        // GHO 磁盘镜像：选择要还原的分区
+        if self.gho_partitions.len() > 1 {
+            ui.horizontal(|ui| {
+                ui.label("还原分区:");
+                let selected_text = self
+                    .gho_partitions
+                    .iter()
+                    .find(|p| p.number == self.selected_gho_partition)
+                    .map(|p| p.label())
+                    .unwrap_or_else(|| "请选择分区".to_string());
+                egui::ComboBox::from_id_salt("gho_partition_select")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for partition in &self.gho_partitions {
+                            ui.selectable_value(&mut self.selected_gho_partition, partition.number, partition.label());
+                        }
+                    })
+                    .response
+                    .on_hover_text("该 GHO 为整盘镜像，只把所选分区还原到下方的目标分区；分区列表读自镜像开头，请核对大小后选择");
+            });
+        }
         
         // 选择 Win10/11 镜像后，自动默认勾选磁盘控制器驱动
         self.update_storage_controller_driver_default();
@@ -195,6 +332,7 @@ impl App {
                         ui.label("可用空间");
                         ui.label("卷标");
                         ui.label("分区表");
+                        ui.label("分区类型");
                         ui.label("BitLocker");
                         ui.label("状态");
                         ui.end_row();
@@ -215,6 +353,11 @@ impl App {
                                     partition.letter.clone()
                                 }
                             };
+                            let label = if partition.storage_bus.is_flash_card() {
+                                format!("{} [{}]", label, partition.storage_bus.label())
+                            } else {
+                                label
+                            };
 
                             if ui
                                 .selectable_label(self.selected_partition == Some(i), &label)
@@ -227,6 +370,11 @@ impl App {
                             ui.label(Self::format_size(partition.free_size_mb));
                             ui.label(&partition.label);
                             ui.label(format!("{}", partition.partition_style));
+                            if partition.partition_style == PartitionStyle::GPT {
+                                ui.label(describe_gpt_partition(&partition.gpt_type_guid, partition.gpt_attributes));
+                            } else {
+                                ui.label("-");
+                            }
                             
                             // 显示 BitLocker 状态
                             let status_color = match partition.bitlocker_status {
@@ -267,7 +415,9 @@ impl App {
         // 安装选项
         ui.horizontal(|ui| {
             ui.checkbox(&mut self.format_partition, "格式化分区");
+            help_button(ui, HelpTopic::FormatPartition);
             ui.checkbox(&mut self.repair_boot, "添加引导");
+            help_button(ui, HelpTopic::BootRepair);
             
             // 无人值守选项 - 根据检测结果处理
             // 如果勾选了格式化分区，则无人值守不受限制（因为格式化会清除现有配置）
@@ -293,6 +443,7 @@ impl App {
                 ui.checkbox(&mut self.unattended_install, "无人值守")
                     .on_hover_text(unattend_tooltip);
             }
+            help_button(ui, HelpTopic::Unattended);
             
             // 驱动操作下拉框
             ui.label("驱动:");
@@ -316,10 +467,72 @@ impl App {
                         "自动导入",
                     );
                 });
+            help_button(ui, HelpTopic::DriverImport);
+
+            ui.checkbox(&mut self.harvest_drivers, "保留现有系统驱动")
+                .on_hover_text("格式化前从目标分区现有的 Windows 中导出第三方驱动，释放镜像后重新注入");
+
+            let has_hwid_index = self.config.as_ref().is_some_and(|c| !c.hwid_drivers.is_empty());
+            ui.add_enabled(has_hwid_index, egui::Checkbox::new(&mut self.hwid_drivers, "下载网卡/存储驱动"))
+                .on_hover_text("按本机网卡和存储控制器的硬件 ID 从服务器下载匹配的驱动，通过 PE 安装时注入新系统")
+                .on_disabled_hover_text("服务器未提供驱动索引");
+
+            ui.checkbox(&mut self.wifi_profiles, "PE 中连接 Wi-Fi")
+                .on_hover_text("重启进入 PE 前导出当前已连接的无线网络配置（含密码），PE 导入后立即删除，仅通过 PE 安装且 PE 支持无线网卡时生效");
+
+            ui.label("释放后校验:");
+            egui::ComboBox::from_id_salt("apply_verify_mode")
+                .selected_text(self.apply_verify.label())
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for mode in crate::core::apply_verify::ApplyVerifyMode::ALL {
+                        ui.selectable_value(&mut self.apply_verify, mode, mode.label());
+                    }
+                })
+                .response
+                .on_hover_text("释放镜像后按镜像中记录的哈希重新读取磁盘上的文件，发现硬盘故障导致的写入损坏（需要 wimlib，GHO 镜像不校验）");
             
-            ui.checkbox(&mut self.auto_reboot, "立即重启");
+            ui.label("完成后:");
+            egui::ComboBox::from_id_salt("completion_action")
+                .selected_text(self.completion_action.label())
+                .show_ui(ui, |ui| {
+                    for action in crate::core::power::CompletionAction::ALL {
+                        ui.selectable_value(&mut self.completion_action, action, action.label());
+                    }
+                })
+                .response
+                .on_hover_text("通过 PE 安装时同样适用于 PE 中的安装完成界面");
         });
 
+        // 安装到虚拟磁盘
+        let is_gho_image = self.local_image_path.to_lowercase().ends_with(".gho");
+        ui.horizontal(|ui| {
+            ui.add_enabled(
+                !is_gho_image,
+                egui::Checkbox::new(&mut self.vhd_install_enabled, "安装到 VHDX 虚拟磁盘（本机 VHD 启动）"),
+            )
+            .on_disabled_hover_text("GHO 镜像不支持安装到虚拟磁盘");
+            help_button(ui, HelpTopic::VhdInstall);
+            if self.vhd_install_enabled {
+                ui.label("文件名:");
+                ui.add(egui::TextEdit::singleline(&mut self.vhd_install_file_name).desired_width(140.0));
+                ui.label("容量:");
+                ui.add(egui::DragValue::new(&mut self.vhd_install_size_gb).range(20..=2048).suffix(" GB"));
+            }
+        });
+        if is_gho_image {
+            self.vhd_install_enabled = false;
+        }
+        if self.vhd_install_enabled {
+            ui.label(
+                egui::RichText::new(
+                    "虚拟磁盘文件创建在所选分区根目录，该分区不会被格式化；本机 VHD 启动时文件会扩展到设定容量，需预留足够空间",
+                )
+                .small()
+                .color(egui::Color32::GRAY),
+            );
+        }
+
         // 引导模式选择
         ui.horizontal(|ui| {
             ui.label("引导模式:");
@@ -410,11 +623,19 @@ impl App {
             );
         }
 
+        self.render_image_compat_warning(ui);
+        self.render_raid_controller_warning(ui);
+        self.render_gho_sector_warning(ui);
+
         ui.horizontal(|ui| {
             if ui.button("高级选项...").clicked() {
                 self.show_advanced_options = true;
             }
-            if ui.button("刷新分区").clicked() {
+            if ui.button("无人值守设置...").clicked() {
+                self.show_unattend_settings = true;
+            }
+            // 设备变化监视未运行时保留手动刷新
+            if self.device_watcher.is_none() && ui.button("刷新分区").clicked() {
                 self.refresh_partitions();
             }
         });
@@ -424,8 +645,14 @@ impl App {
         // 开始安装按钮
         let can_install = self.selected_partition.is_some()
             && !self.local_image_path.is_empty()
-            && (self.local_image_path.ends_with(".gho") || self.selected_volume.is_some())
+            && (!self.vhd_install_enabled || crate::core::vhd::is_valid_file_name(&self.vhd_install_file_name))
+            && match remote_source {
+                Some(ref source) => show_pe_selector && source.check().is_ok(),
+                None => self.local_image_path.ends_with(".gho") || self.selected_volume.is_some(),
+            }
+            && (self.gho_partitions.len() <= 1 || self.selected_gho_partition != 0)
             && !install_blocked
+            && !self.image_compat_blocked()
             && (!show_pe_selector || self.selected_pe_for_install.is_some());
 
         ui.horizontal(|ui| {
@@ -452,7 +679,7 @@ impl App {
         // 警告：安装到有系统的分区
         if let Some(idx) = self.selected_partition {
             if let Some(partition) = self.partitions.get(idx) {
-                if partition.has_windows && !self.format_partition {
+                if partition.has_windows && !self.format_partition && !self.vhd_install_enabled {
                     ui.add_space(5.0);
                     ui.colored_label(
                         egui::Color32::from_rgb(255, 165, 0),
@@ -466,7 +693,7 @@ impl App {
     /// 检查是否需要通过PE安装
     fn check_if_needs_pe_for_install(&self) -> bool {
         // 如果已经在PE环境中，不需要再进PE
-        if self.is_pe_environment() {
+        if self.is_pe_environment() || self.vhd_install_enabled || crate::core::demo::is_enabled() {
             return false;
         }
         
@@ -480,6 +707,17 @@ impl App {
         false
     }
 
+    /// 安装到虚拟磁盘时的目标（文件放在所选分区根目录）
+    fn vhd_install_target(&self, partition: &Partition) -> Option<crate::core::vhd::VhdTarget> {
+        if !self.vhd_install_enabled {
+            return None;
+        }
+        Some(crate::core::vhd::VhdTarget {
+            path: format!("{}\\{}", partition.letter, self.vhd_install_file_name.trim()),
+            size_gb: self.vhd_install_size_gb,
+        })
+    }
+
     /// 根据选择和分区表类型获取实际的引导模式
     fn get_actual_boot_mode(selection: BootModeSelection, partition_style: PartitionStyle) -> &'static str {
         match selection {
@@ -495,21 +733,64 @@ impl App {
         }
     }
 
+    /// 本机无法读取分卷信息的网络镜像源，由 PE 获取镜像后安装
+    ///
+    /// HTTP 和局域网分发的镜像在 PE 中才下载；共享中的镜像本机能访问时按本地文件读取分卷信息，
+    /// 需要 PE 中用共享账户连接时按网络镜像处理。
+    fn remote_image_source(&self) -> Option<crate::core::network_source::NetworkSource> {
+        use crate::core::network_source::NetworkSource;
+        NetworkSource::parse(&self.local_image_path).filter(|source| match source {
+            NetworkSource::Share { .. } => !self.share_image_readable,
+            NetworkSource::Http { .. } | NetworkSource::Netcast { .. } => true,
+        })
+    }
+
     pub fn load_image_volumes(&mut self) {
+        if Path::new(&self.local_image_path).is_file() {
+            self.app_config.add_recent_image(&self.local_image_path);
+        }
+
+        // 更换镜像时卸载之前挂载的 ISO
+        if !self.iso_install_images.contains(&self.local_image_path) {
+            self.release_mounted_iso();
+        }
+
         if self.local_image_path.to_lowercase().ends_with(".iso") {
             self.start_iso_mount();
             return;
         }
 
+        self.share_image_readable = matches!(
+            crate::core::network_source::NetworkSource::parse(&self.local_image_path),
+            Some(crate::core::network_source::NetworkSource::Share { ref path, .. }) if Path::new(path).is_file()
+        );
+        if self.remote_image_source().is_some() {
+            self.clear_image_volumes();
+            return;
+        }
+
         // 其他格式直接后台加载
         self.start_image_info_loading(&self.local_image_path.clone());
     }
 
+    /// 清除上一个镜像的分卷信息
+    fn clear_image_volumes(&mut self) {
+        self.image_volumes.clear();
+        self.selected_volume = None;
+        self.gho_partitions.clear();
+        self.restore_points = Default::default();
+    }
+
     fn start_image_info_loading(&mut self, image_path: &str) {
         let path_lower = image_path.to_lowercase();
+        self.gho_partitions.clear();
+        self.selected_gho_partition = 0;
+        self.gho_partitions_rx = None;
+        self.restore_points = Default::default();
         
         if path_lower.ends_with(".wim") || path_lower.ends_with(".esd") || path_lower.ends_with(".swm") {
             println!("[IMAGE INFO] 开始后台加载镜像信息: {}", image_path);
+            self.restore_points.load(image_path);
             
             self.image_info_loading = true;
             self.image_volumes.clear();
@@ -539,9 +820,30 @@ impl App {
                 }
             });
         } else if path_lower.ends_with(".gho") || path_lower.ends_with(".ghs") {
-            // GHO 文件不需要加载卷信息
+            // GHO 文件不需要加载卷信息，只在后台读取磁盘镜像中的分区表（文件开头的少量数据）
             self.image_volumes.clear();
             self.selected_volume = Some(0);
+            let (tx, rx) = mpsc::channel();
+            self.gho_partitions_rx = Some(rx);
+            let path = image_path.to_string();
+            std::thread::spawn(move || {
+                let partitions = crate::core::ghost::Ghost::new().list_partitions(&path).unwrap_or_else(|e| {
+                    println!("[IMAGE INFO] 读取 GHO 分区表失败: {}", e);
+                    Vec::new()
+                });
+                let _ = tx.send(partitions);
+            });
+        }
+    }
+
+    /// 卸载安装页面挂载的 ISO
+    pub fn release_mounted_iso(&mut self) {
+        self.iso_install_images.clear();
+        if let Some(iso_path) = self.mounted_iso_path.take() {
+            println!("[ISO MOUNT] 卸载 ISO: {}", iso_path);
+            if let Err(e) = crate::core::iso::IsoMounter::unmount_path(&iso_path) {
+                println!("[ISO MOUNT] 卸载失败: {}", e);
+            }
         }
     }
 
@@ -566,12 +868,14 @@ impl App {
                 Ok(drive) => {
                     println!("[ISO MOUNT THREAD] 挂载成功，盘符: {}，查找安装镜像...", drive);
                     // 使用刚挂载的盘符查找镜像，而不是遍历所有盘符
-                    if let Some(image_path) = crate::core::iso::IsoMounter::find_install_image_in_drive(&drive) {
-                        println!("[ISO MOUNT THREAD] 找到镜像: {}", image_path);
-                        let _ = tx.send(IsoMountResult::Success(image_path));
-                    } else {
+                    let images = crate::core::iso::IsoMounter::find_install_images_in_drive(&drive);
+                    if images.is_empty() {
                         println!("[ISO MOUNT THREAD] 未找到安装镜像");
+                        let _ = crate::core::iso::IsoMounter::unmount_path(&iso_path);
                         let _ = tx.send(IsoMountResult::Error("ISO 中未找到 install.wim/esd".to_string()));
+                    } else {
+                        println!("[ISO MOUNT THREAD] 找到镜像: {:?}", images);
+                        let _ = tx.send(IsoMountResult::Success(iso_path, images));
                     }
                 }
                 Err(e) => {
@@ -592,8 +896,11 @@ impl App {
                         ISO_MOUNT_RESULT_RX = None;
 
                         match result {
-                            IsoMountResult::Success(image_path) => {
+                            IsoMountResult::Success(iso_path, images) => {
+                                let image_path = images[0].clone();
                                 println!("[ISO MOUNT] 挂载完成，镜像路径: {}", image_path);
+                                self.mounted_iso_path = Some(iso_path);
+                                self.iso_install_images = images;
                                 self.local_image_path = image_path.clone();
                                 self.iso_mount_error = None;
                                 // 开始后台加载镜像信息
@@ -609,6 +916,13 @@ impl App {
             }
         }
 
+        if let Some(ref rx) = self.gho_partitions_rx {
+            if let Ok(partitions) = rx.try_recv() {
+                self.gho_partitions = partitions;
+                self.gho_partitions_rx = None;
+            }
+        }
+
         // 检查镜像信息加载状态
         if self.image_info_loading {
             unsafe {
@@ -647,6 +961,14 @@ impl App {
                                         self.easy_mode_pending_auto_start = false;
                                         self.show_error(&format!("未找到目标分卷 {}，请手动选择", target_volume_index));
                                     }
+                                } else if let Some(index) = self.image_library.pending_volume.take() {
+                                    // 从镜像库选中的分卷
+                                    self.selected_volume = self.image_volumes
+                                        .iter()
+                                        .position(|vol| vol.index == index);
+                                } else if self.restore_points.catalog.is_some() {
+                                    // 备份镜像：默认选择最新的还原点
+                                    self.selected_volume = self.image_volumes.len().checked_sub(1);
                                 } else {
                                     // 普通模式：自动选择第一个可安装的系统镜像
                                     self.selected_volume = self.image_volumes
@@ -665,6 +987,7 @@ impl App {
                                 println!("[IMAGE INFO] 加载失败: {}", error);
                                 self.image_volumes.clear();
                                 self.selected_volume = None;
+                                self.image_library.pending_volume = None;
                                 // 保存错误信息供UI显示
                                 self.iso_mount_error = Some(format!("镜像信息加载失败: {}", error));
                             }
@@ -754,9 +1077,191 @@ impl App {
         true
     }
 
+    /// 所选分卷变化时后台读取其版本和架构（wimlib 失败时回退到 DISM，可能需要数秒）
+    fn update_image_compat(&mut self) {
+        if let Some(ref rx) = self.image_compat.rx {
+            if let Ok(details) = rx.try_recv() {
+                self.image_compat.details = details;
+                self.image_compat.rx = None;
+            }
+        }
+
+        let path_lower = self.local_image_path.to_lowercase();
+        let index = self
+            .selected_volume
+            .and_then(|i| self.image_volumes.get(i))
+            .map(|volume| volume.index)
+            .filter(|_| path_lower.ends_with(".wim") || path_lower.ends_with(".esd") || path_lower.ends_with(".swm"));
+        let key = index.map(|index| format!("{}|{}", self.local_image_path, index));
+        if key == self.image_compat.key {
+            return;
+        }
+        self.image_compat = ImageCompatState { key, ..Default::default() };
+        let Some(index) = index else {
+            return;
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let image_path = self.local_image_path.clone();
+        std::thread::spawn(move || {
+            let details = match image_compat::read_details(&image_path, index) {
+                Ok(details) => Some(details),
+                Err(e) => {
+                    log::warn!("[IMAGE COMPAT] 读取分卷 {} 信息失败: {:#}", index, e);
+                    None
+                }
+            };
+            let _ = tx.send(details);
+        });
+        self.image_compat.rx = Some(rx);
+    }
+
+    /// 所选分卷与本机的兼容性问题
+    fn image_compat_issues(&self) -> Vec<CompatIssue> {
+        let Some(ref details) = self.image_compat.details else {
+            return Vec::new();
+        };
+        let host = HostInfo {
+            architecture: self
+                .hardware_info
+                .as_ref()
+                .map(|info| info.cpu.architecture.clone())
+                .filter(|arch| arch != "未知")
+                .unwrap_or_default(),
+            tpm_version: self
+                .system_info
+                .as_ref()
+                .filter(|info| info.tpm_enabled)
+                .map(|info| info.tpm_version.clone()),
+            secure_boot: self.system_info.as_ref().is_some_and(|info| info.secure_boot),
+        };
+        let uefi_target = match self.selected_partition.and_then(|idx| self.partitions.get(idx)) {
+            Some(partition) => Self::get_actual_boot_mode(self.selected_boot_mode, partition.partition_style) == "UEFI",
+            None => self
+                .system_info
+                .as_ref()
+                .is_some_and(|info| info.boot_mode == crate::core::system_info::BootMode::UEFI),
+        };
+        image_compat::check(details, &host, uefi_target)
+    }
+
+    /// 存在阻止性兼容问题且用户未确认时不能开始安装
+    fn image_compat_blocked(&self) -> bool {
+        !self.image_compat.accept_blocking
+            && self
+                .image_compat_issues()
+                .iter()
+                .any(|issue| issue.level == CompatLevel::Blocking)
+    }
+
+    /// 显示所选分卷的版本信息和兼容性提示
+    fn render_image_compat_warning(&mut self, ui: &mut egui::Ui) {
+        self.update_image_compat();
+        let Some(ref details) = self.image_compat.details else {
+            return;
+        };
+        ui.add_space(5.0);
+        ui.weak(format!("镜像信息: {}", details.summary()));
+        let mut blocking = false;
+        for issue in self.image_compat_issues() {
+            match issue.level {
+                CompatLevel::Blocking => {
+                    blocking = true;
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", issue.message))
+                }
+                CompatLevel::Warning => {
+                    ui.colored_label(egui::Color32::from_rgb(255, 165, 0), format!("⚠ {}", issue.message))
+                }
+            };
+        }
+        if blocking {
+            ui.checkbox(&mut self.image_compat.accept_blocking, "我已了解上述问题，仍然安装（安装后可能无法启动）");
+        }
+    }
+
+    /// Ghost 镜像按 512 字节扇区制作，还原到 4K 原生（4Kn）磁盘时 Ghost 会失败
+    fn render_gho_sector_warning(&self, ui: &mut egui::Ui) {
+        let path_lower = self.local_image_path.to_lowercase();
+        if !path_lower.ends_with(".gho") && !path_lower.ends_with(".ghs") {
+            return;
+        }
+        let Some(partition) = self.selected_partition.and_then(|idx| self.partitions.get(idx)) else {
+            return;
+        };
+        if partition.sector_size.is_native_4k() {
+            ui.add_space(5.0);
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 165, 0),
+                format!(
+                    "⚠ 目标磁盘为 4Kn 原生扇区磁盘（逻辑扇区 {} 字节），GHO 镜像通常在 512 字节扇区磁盘上制作，Ghost 还原会失败，建议改用 WIM/ESD 镜像",
+                    partition.sector_size.logical
+                ),
+            );
+        }
+    }
+
+    /// 检测到 Intel VMD / RST RAID 控制器时提示驱动情况
+    fn render_raid_controller_warning(&mut self, ui: &mut egui::Ui) {
+        if self.raid_controllers.is_empty() {
+            return;
+        }
+        let names = |list: &[crate::core::storage_controller::RaidController]| {
+            list.iter().map(|c| c.display_name()).collect::<Vec<_>>().join("、")
+        };
+
+        ui.add_space(5.0);
+        if self.advanced_options.import_storage_controller_drivers {
+            ui.colored_label(
+                egui::Color32::from_rgb(100, 180, 255),
+                format!("ℹ 检测到 {}，将导入磁盘控制器驱动", names(&self.raid_controllers)),
+            );
+        } else {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 165, 0),
+                format!(
+                    "⚠ 检测到 {}，建议在高级选项中勾选\"导入磁盘控制器驱动\"，否则新系统可能无法启动",
+                    names(&self.raid_controllers)
+                ),
+            );
+        }
+
+        // PE 中控制器没有加载驱动，挂在下面的硬盘不可见
+        let not_loaded: Vec<_> = self
+            .raid_controllers
+            .iter()
+            .filter(|c| !c.driver_loaded)
+            .cloned()
+            .collect();
+        if self.is_pe_environment() && !not_loaded.is_empty() {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!(
+                    "❌ 当前PE未加载 {} 的驱动，该控制器下的硬盘不可见，请使用集成 Intel RST VMD 驱动的PE，或在 BIOS 中关闭 VMD/RAID（改为 AHCI）",
+                    names(&not_loaded)
+                ),
+            );
+        } else if !self.is_pe_environment() && self.check_if_needs_pe_for_install() {
+            ui.colored_label(
+                egui::Color32::from_rgb(255, 165, 0),
+                "⚠ 请确认所选PE集成了 Intel RST VMD 驱动，否则重启进入PE后将看不到目标硬盘",
+            );
+        }
+
+        if !self.raid_missing_drivers.is_empty() {
+            ui.colored_label(
+                egui::Color32::RED,
+                format!(
+                    "❌ 程序目录 drivers\\storage_controller 中缺少 {} 的驱动，新系统将无法从该控制器下的硬盘启动。请放入 Intel RST VMD 驱动，或在 BIOS 中关闭 VMD/RAID（改为 AHCI）",
+                    names(&self.raid_missing_drivers)
+                ),
+            );
+        }
+    }
+
     fn update_storage_controller_driver_default(&mut self) {
         let mut target_id: Option<String> = None;
         let mut is_win10_or_11: bool = false;
+        let mut version_unknown = false;
 
         if let Some(idx) = self.selected_volume {
             if let Some(vol) = self.image_volumes.get(idx) {
@@ -767,13 +1272,17 @@ impl App {
                 // 直接使用 wimgapi 解析出的版本号
                 // major_version >= 10 表示 Windows 10 或更高版本
                 is_win10_or_11 = vol.major_version.map(|v| v >= 10).unwrap_or(false);
+                version_unknown = vol.major_version.is_none();
             }
         }
 
         // 只有当选择的镜像变化时才更新设置
         if target_id != self.storage_driver_default_target {
             self.storage_driver_default_target = target_id;
-            self.advanced_options.import_storage_controller_drivers = is_win10_or_11;
+            // 硬盘挂在 VMD / RAID 控制器下时，未知版本的镜像也导入驱动，否则新系统无法启动
+            let has_raid = !self.raid_controllers.is_empty();
+            self.advanced_options.import_storage_controller_drivers =
+                is_win10_or_11 || (has_raid && version_unknown);
             
             // 只在变化时打印日志
             if let Some(idx) = self.selected_volume {
@@ -783,6 +1292,8 @@ impl App {
                             "[STORAGE DRIVER] 镜像版本: major_version={}, is_win10_or_11={}",
                             v, is_win10_or_11
                         );
+                    } else if has_raid {
+                        println!("[STORAGE DRIVER] 未检测到版本信息，检测到 VMD/RAID 控制器，自动勾选磁盘控制器驱动");
                     } else {
                         println!("[STORAGE DRIVER] 未检测到版本信息，不自动勾选磁盘控制器驱动");
                     }
@@ -798,6 +1309,13 @@ impl App {
                     self.format_partition = true;
                     self.repair_boot = true;
                 }
+                // 平板的 eMMC / SD 存储容量小，且只能以 UEFI 方式启动：
+                // 关闭保留存储以免占用空间，并按 UEFI 写入该磁盘上的 ESP
+                if partition.storage_bus.is_flash_card() {
+                    self.repair_boot = true;
+                    self.selected_boot_mode = BootModeSelection::UEFI;
+                    self.advanced_options.disable_reserved_storage = true;
+                }
             }
         }
     }
@@ -855,6 +1373,11 @@ impl App {
     fn check_bitlocker_for_install(&self) -> Vec<crate::ui::tools::BitLockerPartition> {
         use crate::core::bitlocker::BitLockerManager;
         
+        // 演示模式下的分区是模拟的，不能查询同盘符的真实分区
+        if crate::core::demo::is_enabled() {
+            return Vec::new();
+        }
+
         let manager = BitLockerManager::new();
         if !manager.is_available() {
             return Vec::new();
@@ -914,7 +1437,7 @@ impl App {
     /// 注意：因为要进入PE环境安装系统，PE无法访问加密分区，所以必须等待完全解密完成
     /// 返回是否启动了解密流程
     fn initiate_bitlocker_decryption(&mut self) -> bool {
-        if self.is_pe_environment() {
+        if self.is_pe_environment() || crate::core::demo::is_enabled() {
             return false;
         }
 
@@ -960,6 +1483,39 @@ impl App {
     }
 
     pub fn start_installation(&mut self) {
+        // 存在阻止性兼容问题时需要用户在安装页确认
+        if self.image_compat_blocked() {
+            self.show_error("所选分卷与本机不兼容，请查看安装页上的提示，确认后再开始安装");
+            return;
+        }
+
+        // 0. 启用技师锁定时需要先输入 PIN
+        if self.require_technician(crate::app::TechnicianAction::LocalInstall) {
+            return;
+        }
+
+        // 部分镜像要求先接受许可协议
+        let image_file_name = Path::new(&self.local_image_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let volume_name = self
+            .selected_volume
+            .and_then(|i| self.image_volumes.get(i))
+            .map(|v| v.name.clone())
+            .unwrap_or_default();
+        if self.require_eula(&[&image_file_name, &volume_name], crate::app::EulaAction::LocalInstall) {
+            return;
+        }
+
+        for issue in self.image_compat_issues() {
+            log::warn!("[IMAGE COMPAT] {:?}: {}", issue.level, issue.message);
+        }
+
+        if self.advanced_options.import_custom_drivers {
+            self.app_config.add_recent_driver_dir(&self.advanced_options.custom_drivers_path);
+        }
+
         let partition = self
             .partitions
             .get(self.selected_partition.unwrap())
@@ -1005,15 +1561,19 @@ impl App {
     
     /// 初始化安装状态变量
     fn initialize_install_state(&mut self, partition: &crate::core::disk::Partition, image_path: String) {
-        let volume_index = self
-            .selected_volume
-            .and_then(|i| self.image_volumes.get(i).map(|v| v.index))
-            .unwrap_or(1);
+        let volume_index = if self.remote_image_source().is_some() {
+            self.network_volume_index
+        } else {
+            self.selected_volume
+                .and_then(|i| self.image_volumes.get(i).map(|v| v.index))
+                .unwrap_or(1)
+        };
 
         let is_system_partition = partition.is_system_partition;
         let is_pe = self.is_pe_environment();
 
-        self.install_mode = if is_pe || !is_system_partition {
+        // 安装到虚拟磁盘不会改动宿主分区上的现有系统，可直接进行；演示模式只模拟直接安装
+        self.install_mode = if is_pe || !is_system_partition || self.vhd_install_enabled || crate::core::demo::is_enabled() {
             crate::app::InstallMode::Direct
         } else {
             crate::app::InstallMode::ViaPE
@@ -1024,16 +1584,32 @@ impl App {
             repair_boot: self.repair_boot,
             unattended_install: self.unattended_install,
             export_drivers: matches!(self.driver_action, crate::app::DriverAction::SaveOnly | crate::app::DriverAction::AutoImport),
-            auto_reboot: self.auto_reboot,
+            completion_action: self.completion_action,
             boot_mode: self.selected_boot_mode,
             advanced_options: self.advanced_options.clone(),
             driver_action: self.driver_action,
+            harvest_drivers: self.harvest_drivers,
+            hwid_drivers: self.hwid_drivers && self.config.as_ref().is_some_and(|c| !c.hwid_drivers.is_empty()),
+            wifi_profiles: self.wifi_profiles,
+            share_user: self.share_user.clone(),
+            share_password: self.share_password.clone(),
+            apply_verify: self.apply_verify,
+            vhd_target: self.vhd_install_target(partition),
+            gho_partition: self.selected_gho_partition,
         };
 
+        if self.install_options.vhd_target.is_some() {
+            self.install_options.format_partition = false;
+            self.install_options.repair_boot = true;
+        }
+
         self.is_installing = true;
         self.current_panel = crate::app::Panel::InstallProgress;
         self.install_progress = crate::app::InstallProgress::default();
         self.auto_reboot_triggered = false;
+        self.install_error = None;
+        self.install_started_at = Some(std::time::Instant::now());
+        self.install_report_sent = false;
 
         self.install_target_partition = partition.letter.clone();
         self.install_image_path = image_path;
@@ -1149,15 +1725,9 @@ impl App {
             }
         }
 
-        // 正常开始步骤 1 (或 0 如果是 ViaPE 的话，但这里我们统一用 0 作为特殊解密步骤)
-        // InstallProgress UI 里的 start_xxx_thread 会在 step == 0 时启动
-        // 但我们需要区分 "解密等待中(step=0)" 和 "刚初始化准备开始(step=0)"
-        // 为了区分，我们将 install_step 设为 1 表示准备好开始安装了 (对于 Direct 模式)
-        // 或者保持 0，但在 UI update 中判断 decrypting_partitions 是否为空
-        
-        // 这里的 install_step = 0 会触发 show_install_progress 里的启动线程逻辑
-        // 我们只需确保 decrypting_partitions 为空，这样 UI 就不会卡在解密界面
+        // 没有需要等待解密的分区，立即启动安装线程（解密完成时由进度更新启动）
         self.install_step = 0;
+        self.start_install_worker();
     }
     
     /// 开始异步检测分区中的无人值守配置文件
@@ -1203,8 +1773,6 @@ impl App {
     
     /// 检查分区中的无人值守配置文件（在后台线程执行）
     fn check_unattend_files_in_partition(partition_letter: &str) -> UnattendCheckResult {
-        use std::path::Path;
-        
         // 常见的无人值守配置文件位置
         let unattend_locations = [
             // Windows 安装后的位置
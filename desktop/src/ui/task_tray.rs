@@ -0,0 +1,252 @@
+use egui;
+
+use crate::app::{App, Panel};
+
+/// 后台任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundTaskKind {
+    Install,
+    Backup,
+    Download,
+    ImageVerify,
+    PartitionCopy,
+    BatchFormat,
+    DriverBackup,
+    QuickPartition,
+}
+
+/// 正在运行的后台任务（每帧根据各功能的状态生成）
+#[derive(Debug, Clone)]
+pub struct BackgroundTask {
+    pub kind: BackgroundTaskKind,
+    /// 任务名称
+    pub title: String,
+    /// 当前状态描述
+    pub status: String,
+    /// 进度 (0.0-1.0)，None 表示无法获取进度
+    pub progress: Option<f32>,
+    /// 是否支持取消
+    pub cancellable: bool,
+}
+
+impl App {
+    /// 检查是否有会占用系统的操作正在进行（安装、备份、下载）
+    pub fn is_busy(&self) -> bool {
+        self.is_installing || self.is_backing_up || self.current_download.is_some()
+    }
+
+    /// 收集当前正在运行的后台任务
+    pub fn background_tasks(&self) -> Vec<BackgroundTask> {
+        let mut tasks = Vec::new();
+
+        if self.is_installing {
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::Install,
+                title: "系统安装".to_string(),
+                status: self.install_progress.current_step.clone(),
+                progress: Some(self.install_progress.total_progress as f32 / 100.0),
+                cancellable: false,
+            });
+        }
+
+        if self.is_backing_up {
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::Backup,
+                title: "系统备份".to_string(),
+                status: String::new(),
+                progress: Some(self.backup_progress as f32 / 100.0),
+                cancellable: false,
+            });
+        }
+
+        if self.current_download.is_some() {
+            let progress = self.download_progress.as_ref();
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::Download,
+                title: "下载".to_string(),
+                status: self.current_download_filename.clone().unwrap_or_default(),
+                progress: progress.map(|p| p.percentage as f32 / 100.0),
+                cancellable: true,
+            });
+        }
+
+        if self.image_verify_loading {
+            let progress = self.image_verify_progress.as_ref();
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::ImageVerify,
+                title: "镜像校验".to_string(),
+                status: progress.map(|p| p.status.clone()).unwrap_or_default(),
+                progress: progress.map(|p| p.percentage as f32 / 100.0),
+                cancellable: true,
+            });
+        }
+
+        if self.partition_copy_copying {
+            let progress = self.partition_copy_progress.as_ref();
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::PartitionCopy,
+                title: "分区对拷".to_string(),
+                status: progress.map(|p| p.current_file.clone()).unwrap_or_default(),
+                progress: progress
+                    .filter(|p| p.total_count > 0)
+                    .map(|p| p.copied_count as f32 / p.total_count as f32),
+                cancellable: false,
+            });
+        }
+
+        if self.batch_format_loading {
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::BatchFormat,
+                title: "批量格式化".to_string(),
+                status: String::new(),
+                progress: None,
+                cancellable: false,
+            });
+        }
+
+        if self.driver_backup_loading {
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::DriverBackup,
+                title: "驱动备份还原".to_string(),
+                status: String::new(),
+                progress: None,
+                cancellable: false,
+            });
+        }
+
+        if self.quick_partition_state.executing {
+            tasks.push(BackgroundTask {
+                kind: BackgroundTaskKind::QuickPartition,
+                title: "一键分区".to_string(),
+                status: self.quick_partition_state.message.clone(),
+                progress: None,
+                cancellable: false,
+            });
+        }
+
+        tasks
+    }
+
+    /// 推进安装、备份、下载的进度（在主循环中调用）
+    ///
+    /// 这些进度原本只在进度页面中更新，切换到其他页面后需要继续接收
+    pub fn pump_background_tasks(&mut self) {
+        if self.is_installing {
+            self.update_install_progress();
+        }
+        if self.is_backing_up {
+            self.update_backup_progress();
+        }
+        if self.current_download.is_some() {
+            self.update_download_progress();
+            self.check_hash_verify_result();
+        }
+    }
+
+    /// 显示底部任务栏
+    pub fn render_task_tray(&mut self, ctx: &egui::Context) {
+        let tasks = self.background_tasks();
+        if tasks.is_empty() {
+            return;
+        }
+
+        let mut open = None;
+        let mut cancel = None;
+
+        egui::TopBottomPanel::bottom("task_tray_panel").show(ctx, |ui| {
+            for task in &tasks {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&task.title).strong());
+
+                    match task.progress {
+                        Some(progress) => {
+                            ui.add(
+                                egui::ProgressBar::new(progress.clamp(0.0, 1.0))
+                                    .desired_width(200.0)
+                                    .show_percentage(),
+                            );
+                        }
+                        None => {
+                            ui.spinner();
+                        }
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if task.cancellable && ui.button("取消").clicked() {
+                            cancel = Some(task.kind);
+                        }
+                        if ui.button("查看").clicked() {
+                            open = Some(task.kind);
+                        }
+                        if !task.status.is_empty() {
+                            ui.add(egui::Label::new(&task.status).truncate());
+                        }
+                    });
+                });
+            }
+        });
+
+        if let Some(kind) = open {
+            self.open_background_task(kind);
+        }
+        if let Some(kind) = cancel {
+            self.cancel_background_task(kind);
+        }
+    }
+
+    /// 跳转到任务所在的页面或对话框
+    fn open_background_task(&mut self, kind: BackgroundTaskKind) {
+        match kind {
+            BackgroundTaskKind::Install => self.current_panel = Panel::InstallProgress,
+            BackgroundTaskKind::Backup => self.current_panel = Panel::BackupProgress,
+            BackgroundTaskKind::Download => self.current_panel = Panel::DownloadProgress,
+            BackgroundTaskKind::ImageVerify => {
+                self.current_panel = Panel::Tools;
+                self.show_image_verify_dialog = true;
+            }
+            BackgroundTaskKind::PartitionCopy => {
+                self.current_panel = Panel::Tools;
+                self.show_partition_copy_dialog = true;
+            }
+            BackgroundTaskKind::BatchFormat => {
+                self.current_panel = Panel::Tools;
+                self.show_batch_format_dialog = true;
+            }
+            BackgroundTaskKind::DriverBackup => {
+                self.current_panel = Panel::Tools;
+                self.show_driver_backup_dialog = true;
+            }
+            BackgroundTaskKind::QuickPartition => {
+                self.current_panel = Panel::Tools;
+                self.show_quick_partition_dialog = true;
+            }
+        }
+    }
+
+    /// 取消任务
+    fn cancel_background_task(&mut self, kind: BackgroundTaskKind) {
+        match kind {
+            BackgroundTaskKind::Download => {
+                // 取消下载会跳转回来源页面，不在下载进度页时保持当前页面
+                let panel = self.current_panel.clone();
+                self.cancel_current_download();
+                if panel != Panel::DownloadProgress {
+                    self.current_panel = panel;
+                }
+            }
+            BackgroundTaskKind::ImageVerify => self.cancel_image_verify(),
+            _ => {}
+        }
+    }
+}
+
+/// 有操作进行时在页面顶部显示提示
+pub fn show_busy_notice(ui: &mut egui::Ui, busy: bool) {
+    if busy {
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 165, 0),
+            "⚠ 有操作正在进行，完成后才能在此页面发起新的操作",
+        );
+        ui.add_space(5.0);
+    }
+}
@@ -0,0 +1,194 @@
+//! 技师锁定
+//!
+//! 启用技师 PIN 后，除只读取信息、校验镜像以外的工具、安装系统和程序设置需要先解锁；解锁仅对本次运行有效。
+
+use egui;
+use std::time::Instant;
+
+use crate::app::{App, TechnicianAction};
+use crate::core::tech_lock;
+use crate::download::help::HelpTopic;
+use crate::ui::a11y::dialog_keys;
+use crate::ui::help::help_button;
+use crate::ui::password_input::password_field;
+use crate::tr;
+
+/// 对话框中显示的操作名称
+fn action_label(action: TechnicianAction) -> &'static str {
+    match action {
+        TechnicianAction::OpenTool(tool) => tool.name(),
+        TechnicianAction::LocalInstall => "安装系统",
+        TechnicianAction::Settings => "修改设置",
+    }
+}
+
+impl App {
+    /// 已启用技师锁定且本次运行尚未解锁
+    pub fn is_technician_locked(&self) -> bool {
+        self.app_config.has_technician_pin() && !self.technician_unlocked
+    }
+
+    /// 检查操作是否需要先输入技师 PIN
+    ///
+    /// 处于锁定状态时弹出解锁对话框并返回 true，调用方应中止当前操作，
+    /// 解锁后会重新执行 `action`
+    pub fn require_technician(&mut self, action: TechnicianAction) -> bool {
+        if !self.is_technician_locked() {
+            return false;
+        }
+        log::info!("[TECH LOCK] 操作需要技师 PIN: {}", action_label(action));
+        self.pending_technician_action = Some(action);
+        self.technician_pin_input.clear();
+        self.technician_unlock_message.clear();
+        true
+    }
+
+    /// 显示技师 PIN 解锁对话框
+    pub fn show_technician_unlock_dialog(&mut self, ctx: &egui::Context) {
+        let Some(action) = self.pending_technician_action else {
+            return;
+        };
+
+        let mut submit = false;
+        let mut cancel = false;
+        let throttled = self.technician_throttle.remaining(Instant::now());
+
+        egui::Window::new("技师验证")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                let keys = dialog_keys(ui);
+                ui.label(format!("“{}”已被技师锁定，请输入技师 PIN：", action_label(action)));
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    password_field(ui, "PIN:", &mut self.technician_pin_input, 160.0);
+                });
+
+                if let Some(remaining) = throttled {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("输错次数过多，请 {} 秒后再试", remaining.as_secs() + 1),
+                    );
+                    ctx.request_repaint_after(std::time::Duration::from_secs(1));
+                } else if !self.technician_unlock_message.is_empty() {
+                    ui.colored_label(egui::Color32::RED, &self.technician_unlock_message);
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    let can_submit = throttled.is_none() && !self.technician_pin_input.is_empty();
+                    if ui.add_enabled(can_submit, egui::Button::new("解锁")).clicked() || (can_submit && keys.enter) {
+                        submit = true;
+                    }
+                    if ui.button("取消").clicked() || keys.escape {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if cancel {
+            self.pending_technician_action = None;
+            self.technician_pin_input.clear();
+        } else if submit {
+            let pin = std::mem::take(&mut self.technician_pin_input);
+            if !self.app_config.verify_technician_pin(&pin) {
+                log::warn!("[TECH LOCK] 技师 PIN 错误");
+                self.technician_throttle.record_failure(Instant::now());
+                self.technician_unlock_message = "PIN 错误".to_string();
+                return;
+            }
+
+            log::info!("[TECH LOCK] 已解锁");
+            self.technician_unlocked = true;
+            self.technician_throttle.reset();
+            self.technician_unlock_message.clear();
+            self.pending_technician_action = None;
+            match action {
+                TechnicianAction::OpenTool(tool) => self.open_tool(tool),
+                TechnicianAction::LocalInstall => self.start_installation(),
+                TechnicianAction::Settings => {}
+            }
+        }
+    }
+
+    /// 关于页中的技师锁定设置
+    pub fn show_technician_lock_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.heading(tr!("技师锁定"));
+            help_button(ui, HelpTopic::TechnicianLock);
+        });
+        ui.add_space(10.0);
+
+        if self.is_technician_locked() {
+            ui.horizontal(|ui| {
+                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), tr!("🔒 已锁定，修改设置需要技师 PIN"));
+                if ui.button(tr!("解锁")).clicked() {
+                    self.require_technician(TechnicianAction::Settings);
+                }
+            });
+            return;
+        }
+
+        let has_pin = self.app_config.has_technician_pin();
+        ui.horizontal(|ui| {
+            password_field(
+                ui,
+                &tr!(if has_pin { "新 PIN:" } else { "PIN:" }),
+                &mut self.technician_new_pin,
+                120.0,
+            );
+        });
+        ui.horizontal(|ui| {
+            password_field(ui, &tr!("确认 PIN:"), &mut self.technician_new_pin_confirm, 120.0);
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button(tr!(if has_pin { "修改 PIN" } else { "启用技师锁定" })).clicked() {
+                self.technician_settings_message = if !tech_lock::is_valid_pin(&self.technician_new_pin) {
+                    format!("PIN 须为 {}-{} 位数字", tech_lock::MIN_PIN_LEN, tech_lock::MAX_PIN_LEN)
+                } else if self.technician_new_pin != self.technician_new_pin_confirm {
+                    "两次输入的 PIN 不一致".to_string()
+                } else if let Err(e) = self.app_config.set_technician_pin(&self.technician_new_pin) {
+                    log::warn!("[TECH LOCK] 设置技师 PIN 失败: {}", e);
+                    format!("设置 PIN 失败: {}", e)
+                } else {
+                    self.technician_unlocked = true;
+                    log::info!("[TECH LOCK] 已设置技师 PIN");
+                    if has_pin { "PIN 已修改" } else { "已启用技师锁定，交给顾客前请点击“立即锁定”" }.to_string()
+                };
+                self.technician_new_pin.clear();
+                self.technician_new_pin_confirm.clear();
+            }
+            if has_pin {
+                if ui.button(tr!("立即锁定")).clicked() {
+                    self.technician_unlocked = false;
+                    self.technician_settings_message.clear();
+                    log::info!("[TECH LOCK] 已锁定");
+                }
+                if ui.button(tr!("关闭技师锁定")).clicked() {
+                    self.app_config.clear_technician_pin();
+                    self.technician_settings_message = "已关闭技师锁定".to_string();
+                    log::info!("[TECH LOCK] 已关闭技师锁定");
+                }
+            }
+        });
+
+        if !self.technician_settings_message.is_empty() {
+            ui.label(&self.technician_settings_message);
+        }
+
+        ui.add_space(5.0);
+        ui.indent("tech_lock_desc", |ui| {
+            ui.colored_label(
+                egui::Color32::GRAY,
+                tr!("锁定后，一键分区、格式化、分区对拷、安装系统及程序设置需要输入 PIN，"),
+            );
+            ui.colored_label(
+                egui::Color32::GRAY,
+                tr!("硬件信息、镜像校验等查看类工具不受影响。程序重启后自动恢复锁定。"),
+            );
+        });
+    }
+}
@@ -41,19 +41,43 @@ pub fn launch_ghost() -> Result<(), String> {
     }
 }
 
-/// 启动万能驱动工具
-pub fn launch_wandrv() -> Result<(), String> {
+/// 万能驱动压缩包（QDZC.exe 不存在时解压到 tools 目录）
+const WANDRV_ARCHIVES: [&str; 2] = ["QDZC.7z", "QDZC.zip"];
+
+/// 启动万能驱动工具，返回状态消息
+///
+/// 只有压缩包时在后台解压，解压完成后自动启动
+pub fn launch_wandrv() -> Result<String, String> {
     let tools_dir = get_tools_dir();
     let wandrv_path = tools_dir.join("QDZC.exe");
 
     if wandrv_path.exists() {
-        match Command::new(&wandrv_path).spawn() {
-            Ok(_) => Ok(()),
+        return match Command::new(&wandrv_path).spawn() {
+            Ok(_) => Ok("已启动: QDZC.exe".to_string()),
             Err(e) => Err(format!("启动失败: QDZC.exe - {}", e)),
-        }
-    } else {
-        Err(format!("工具不存在: {:?}", wandrv_path))
+        };
     }
+
+    let Some(archive) = WANDRV_ARCHIVES
+        .iter()
+        .map(|name| tools_dir.join(name))
+        .find(|path| path.exists())
+    else {
+        return Err(format!("工具不存在: {:?}", wandrv_path));
+    };
+
+    std::thread::spawn(move || {
+        match crate::utils::archive::extract(&archive, &tools_dir, |_| {}) {
+            Ok(_) if wandrv_path.exists() => {
+                if let Err(e) = Command::new(&wandrv_path).spawn() {
+                    println!("[WANDRV] 启动失败: {}", e);
+                }
+            }
+            Ok(_) => println!("[WANDRV] 压缩包中没有 QDZC.exe: {}", archive.display()),
+            Err(e) => println!("[WANDRV] 解压失败: {:#}", e),
+        }
+    });
+    Ok("正在解压万能驱动，完成后自动启动...".to_string())
 }
 
 /// 启动 SpaceSniffer 磁盘空间分析工具
@@ -1,9 +1,11 @@
 //! 批量格式化模块
 //!
-//! 提供分区格式化功能，使用系统 format 命令实现
+//! 提供分区格式化功能，优先调用 fmifs.dll 的 FormatEx，不可用时使用系统 format 命令
 
 use std::path::Path;
 
+use crate::core::disk_native;
+
 #[cfg(windows)]
 use windows::{
     core::PCWSTR,
@@ -225,6 +227,24 @@ pub fn format_partition(letter: &str, label: &str, file_system: &str) -> Result<
         vol_label
     );
 
+    // 优先调用 FormatEx，不依赖 format.com 且不需要解析命令输出
+    if disk_native::format_available() {
+        return match disk_native::format_volume(&drive, fs, vol_label, |_| {}) {
+            Ok(()) => {
+                log::info!("分区 {} 格式化成功", drive);
+                Ok(())
+            }
+            Err(e) => {
+                let error_msg = match crate::core::opal::format_failure_hint() {
+                    Some(hint) => format!("{}\n{}", e, hint),
+                    None => e.to_string(),
+                };
+                log::error!("格式化失败: {}", error_msg);
+                Err(error_msg)
+            }
+        };
+    }
+
     // 使用系统 format 命令: format D: /FS:NTFS /V:Label /Q /Y
     let cmd_args = format!("format {} /FS:{} /V:{} /Q /Y", drive, fs, vol_label);
     
@@ -261,6 +281,12 @@ pub fn format_partition(letter: &str, label: &str, file_system: &str) -> Result<
             format!("格式化失败: {}", stdout.trim())
         };
         
+        // 已锁定的自加密硬盘格式化只会报出难以理解的错误，补充说明原因
+        let error_msg = match crate::core::opal::format_failure_hint() {
+            Some(hint) => format!("{}\n{}", error_msg, hint),
+            None => error_msg,
+        };
+
         log::error!("格式化失败: {}", error_msg);
         Err(error_msg)
     }
@@ -309,6 +335,23 @@ where
 
     progress_callback(0, &format!("准备格式化 {} ...", drive));
 
+    if disk_native::format_available() {
+        let target = drive.clone();
+        return disk_native::format_volume(&drive, fs, vol_label, move |percent| {
+            let message = if percent >= 100 {
+                format!("分区 {} 格式化完成", target)
+            } else {
+                format!("正在格式化 {}... {}%", target, percent)
+            };
+            progress_callback(percent, &message);
+        })
+        .map(|_| log::info!("分区 {} 格式化成功", drive))
+        .map_err(|e| {
+            log::error!("{}", e);
+            e.to_string()
+        });
+    }
+
     progress_callback(10, "启动格式化进程...");
 
     // 使用系统 format 命令
@@ -407,10 +450,9 @@ pub fn batch_format_partitions(
     }
 }
 
-/// 检查 format 命令是否可用
+/// 检查格式化接口是否可用（FormatEx 或 format 命令）
 pub fn is_format_api_available() -> bool {
-    // 系统自带的 format.com 应该总是存在
-    std::path::Path::new(r"C:\Windows\System32\format.com").exists()
+    disk_native::format_available() || std::path::Path::new(r"C:\Windows\System32\format.com").exists()
 }
 
 #[cfg(test)]
@@ -427,7 +469,7 @@ mod tests {
     #[test]
     #[cfg(windows)]
     fn test_format_api_available() {
-        // 系统 format.com 应该存在
+        // 系统 fmifs.dll 和 format.com 应该存在
         assert!(is_format_api_available());
     }
 }
\ No newline at end of file
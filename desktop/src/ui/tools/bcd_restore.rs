@@ -0,0 +1,168 @@
+//! 恢复引导配置对话框模块
+//!
+//! 修复引导、创建PE引导项前会自动导出 BCD 备份，这里列出备份并支持还原到选中的备份
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::bcdedit::{self, BootManager};
+use crate::ui::a11y::dialog_keys;
+
+impl App {
+    /// 渲染恢复引导配置对话框
+    pub fn render_bcd_restore_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_bcd_restore_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut start_restore = false;
+        let mut start_backup = false;
+
+        egui::Window::new("恢复引导配置")
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(400.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("修改引导前会自动备份引导配置（BCD），引导出现问题时可以还原到之前的备份。");
+                ui.label(
+                    egui::RichText::new(format!("备份目录: {}", bcdedit::bcd_backup_dir().display()))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                ui.add_space(10.0);
+
+                if self.bcd_backups.is_empty() {
+                    ui.label("暂无引导配置备份");
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("bcd_backups")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (index, backup) in self.bcd_backups.iter().enumerate() {
+                            let store = if backup.store.is_empty() {
+                                "系统存储".to_string()
+                            } else {
+                                backup.store.clone()
+                            };
+                            let text = format!("{}  {}  [{}]", backup.created, backup.reason, store);
+                            if ui
+                                .selectable_label(self.bcd_restore_selected == Some(index), text)
+                                .clicked()
+                            {
+                                self.bcd_restore_selected = Some(index);
+                                self.bcd_restore_confirm = false;
+                            }
+                        }
+                    });
+
+                if self.bcd_restore_selected.is_some() {
+                    ui.add_space(10.0);
+                    ui.checkbox(
+                        &mut self.bcd_restore_confirm,
+                        "我确认用选中的备份覆盖当前引导配置（当前配置会先自动备份）",
+                    );
+                }
+
+                if !self.bcd_restore_message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&self.bcd_restore_message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let can_restore = !self.bcd_restore_loading
+                        && self.bcd_restore_selected.is_some()
+                        && self.bcd_restore_confirm;
+                    if ui.add_enabled(can_restore, egui::Button::new("恢复")).clicked() {
+                        start_restore = true;
+                    }
+                    if ui
+                        .add_enabled(!self.bcd_restore_loading, egui::Button::new("立即备份"))
+                        .clicked()
+                    {
+                        start_backup = true;
+                    }
+                    if ui
+                        .add_enabled(!self.bcd_restore_loading, egui::Button::new("刷新"))
+                        .clicked()
+                    {
+                        self.refresh_bcd_backups();
+                    }
+                    if self.bcd_restore_loading {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if start_restore {
+            self.start_bcd_operation(true);
+        } else if start_backup {
+            self.start_bcd_operation(false);
+        }
+        if should_close {
+            self.show_bcd_restore_dialog = false;
+        }
+    }
+
+    /// 重新读取备份列表
+    pub fn refresh_bcd_backups(&mut self) {
+        self.bcd_backups = bcdedit::list_bcd_backups();
+        self.bcd_restore_selected = None;
+        self.bcd_restore_confirm = false;
+    }
+
+    /// 后台执行恢复（restore 为 true）或手动备份
+    fn start_bcd_operation(&mut self, restore: bool) {
+        let backup = if restore {
+            match self.bcd_restore_selected.and_then(|i| self.bcd_backups.get(i)) {
+                Some(backup) => Some(backup.clone()),
+                None => return,
+            }
+        } else {
+            None
+        };
+
+        self.bcd_restore_loading = true;
+        self.bcd_restore_message = if restore {
+            "正在恢复引导配置...".to_string()
+        } else {
+            "正在备份引导配置...".to_string()
+        };
+
+        let (tx, rx) = mpsc::channel();
+        self.bcd_restore_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let manager = BootManager::new();
+            let result = match backup {
+                Some(backup) => manager
+                    .restore_bcd(&backup)
+                    .map(|_| format!("✅ 已恢复 {} 的引导配置", backup.created)),
+                None => manager
+                    .backup_bcd(None, "手动备份")
+                    .map(|b| format!("✅ 已备份到 {}", b.path.display())),
+            };
+            let _ = tx.send(result.map_err(|e| format!("❌ {:#}", e)));
+        });
+    }
+
+    /// 检查恢复 / 备份结果
+    pub fn check_bcd_restore_result(&mut self) {
+        if let Some(ref rx) = self.bcd_restore_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.bcd_restore_message = result.unwrap_or_else(|e| e);
+                self.bcd_restore_loading = false;
+                self.bcd_restore_rx = None;
+                self.refresh_bcd_backups();
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+//! 引导项编辑对话框模块
+//!
+//! 列出 BCD 存储中的引导项和菜单等待时间，可设置默认项、修改名称和等待时间、删除引导项、
+//! 复制为安全模式引导项；除当前系统存储外，也可编辑其他分区或 ESP 上的 BCD 文件。
+//! 每次修改前自动备份，可在「恢复引导配置」中还原
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::bcdedit::{BootManager, BootMenu, SafeBootMode};
+use crate::ui::a11y::dialog_keys;
+
+/// 引导项编辑操作
+enum BootEditorAction {
+    SetDefault(String),
+    SetTimeout(u32),
+    Rename(String, String),
+    Delete(String),
+    AddSafeMode(String, String, SafeBootMode),
+}
+
+/// 引导项编辑对话框状态
+#[derive(Default)]
+pub struct BootEditorDialogState {
+    /// BCD 文件路径，为空表示当前系统存储
+    pub store: String,
+    /// 各分区上找到的 BCD 文件
+    pub stores: Vec<String>,
+    pub menu: Option<BootMenu>,
+    pub menu_rx: Option<mpsc::Receiver<(Result<BootMenu, String>, Vec<String>)>>,
+    /// 选中引导项的标识符
+    pub selected: Option<String>,
+    pub timeout: u32,
+    /// 选中引导项的名称（编辑中）
+    pub description: String,
+    pub safe_mode: SafeBootMode,
+    /// 等待确认删除
+    pub confirm_delete: bool,
+    pub esp_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub result_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub message: String,
+}
+
+impl BootEditorDialogState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.menu_rx.is_some() || self.esp_rx.is_some() || self.result_rx.is_some()
+    }
+
+    fn store(&self) -> Option<String> {
+        let store = self.store.trim();
+        (!store.is_empty()).then(|| store.to_string())
+    }
+
+    /// 选中引导项，同步名称编辑框
+    fn select(&mut self, identifier: Option<String>) {
+        self.description = identifier
+            .as_deref()
+            .and_then(|id| self.menu.as_ref()?.entries.iter().find(|e| e.identifier == id))
+            .map(|entry| entry.description.clone())
+            .unwrap_or_default();
+        self.selected = identifier;
+        self.confirm_delete = false;
+    }
+}
+
+impl App {
+    /// 打开引导项编辑对话框
+    pub fn init_boot_editor_dialog(&mut self) {
+        self.show_boot_editor_dialog = true;
+        self.boot_editor_state.message.clear();
+        self.boot_editor_state.confirm_delete = false;
+        self.load_boot_menu();
+    }
+
+    /// 后台读取当前存储的引导项
+    fn load_boot_menu(&mut self) {
+        let state = &mut self.boot_editor_state;
+        let store = state.store();
+        let (tx, rx) = mpsc::channel();
+        state.menu_rx = Some(rx);
+        std::thread::spawn(move || {
+            let manager = BootManager::new();
+            let menu = manager
+                .enumerate(store.as_deref())
+                .map_err(|e| format!("❌ {:#}", e));
+            let _ = tx.send((menu, manager.find_stores()));
+        });
+    }
+
+    /// 渲染引导项编辑对话框
+    pub fn render_boot_editor_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_boot_editor_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut reload = false;
+        let mut mount_esp = false;
+        let mut action: Option<BootEditorAction> = None;
+
+        egui::Window::new("引导项编辑")
+            .resizable(true)
+            .default_width(640.0)
+            .default_height(460.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.boot_editor_state;
+                let busy = state.is_loading();
+                ui.label("编辑启动菜单中的引导项。每次修改前会自动备份引导配置，可在「恢复引导配置」中还原。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("BCD 存储:");
+                    let selected_text = if state.store.trim().is_empty() {
+                        "当前系统".to_string()
+                    } else {
+                        state.store.clone()
+                    };
+                    let old_store = state.store.clone();
+                    ui.add_enabled_ui(!busy, |ui| {
+                        egui::ComboBox::from_id_salt("boot_editor_store")
+                            .selected_text(selected_text)
+                            .width(320.0)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut state.store, String::new(), "当前系统");
+                                for store in &state.stores {
+                                    ui.selectable_value(&mut state.store, store.clone(), store);
+                                }
+                            });
+                    });
+                    if ui.add_enabled(!busy, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new().set_file_name("BCD").pick_file() {
+                            state.store = path.to_string_lossy().to_string();
+                        }
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("挂载 ESP")).clicked() {
+                        mount_esp = true;
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("刷新")).clicked() {
+                        reload = true;
+                    }
+                    if state.store != old_store {
+                        reload = true;
+                    }
+                });
+
+                ui.add_space(5.0);
+                if state.menu_rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("正在读取引导配置...");
+                    });
+                }
+
+                let mut clicked: Option<String> = None;
+                if let Some(ref menu) = state.menu {
+                    if menu.entries.is_empty() {
+                        ui.label("没有找到引导项");
+                    }
+                    egui::ScrollArea::vertical()
+                        .id_salt("boot_editor_entries")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for entry in &menu.entries {
+                                let mut text = if entry.description.is_empty() {
+                                    "（无名称）".to_string()
+                                } else {
+                                    entry.description.clone()
+                                };
+                                if menu.is_default(&entry.identifier) {
+                                    text.push_str("  [默认]");
+                                }
+                                if let Some(ref mode) = entry.safeboot {
+                                    text.push_str(&format!("  [安全模式: {}]", mode));
+                                }
+                                if !entry.in_menu {
+                                    text.push_str("  [不在菜单中]");
+                                }
+                                let is_selected = state.selected.as_deref() == Some(entry.identifier.as_str());
+                                let response = ui.selectable_label(is_selected, text);
+                                let device = if entry.osdevice.is_empty() { &entry.device } else { &entry.osdevice };
+                                ui.label(
+                                    egui::RichText::new(format!("    {}  {}", entry.identifier, device))
+                                        .small()
+                                        .color(egui::Color32::GRAY),
+                                );
+                                if response.clicked() {
+                                    clicked = Some(entry.identifier.clone());
+                                }
+                            }
+                        });
+                }
+                if clicked.is_some() {
+                    state.select(clicked);
+                }
+
+                if state.menu.is_some() {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label("菜单等待时间:");
+                        ui.add_enabled(
+                            !busy,
+                            egui::DragValue::new(&mut state.timeout).range(0..=999).suffix(" 秒"),
+                        );
+                        if ui.add_enabled(!busy, egui::Button::new("应用")).clicked() {
+                            action = Some(BootEditorAction::SetTimeout(state.timeout));
+                        }
+                    });
+                }
+
+                if let Some(selected) = state.selected.clone() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        let label = ui.label("名称:");
+                        ui.add_enabled(
+                            !busy,
+                            egui::TextEdit::singleline(&mut state.description).desired_width(300.0),
+                        )
+                        .labelled_by(label.id);
+                        let can_rename = !busy && !state.description.trim().is_empty();
+                        if ui.add_enabled(can_rename, egui::Button::new("重命名")).clicked() {
+                            let description = state.description.trim().to_string();
+                            action = Some(BootEditorAction::Rename(selected.clone(), description));
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let is_default = state.menu.as_ref().is_some_and(|m| m.is_default(&selected));
+                        if ui.add_enabled(!busy && !is_default, egui::Button::new("设为默认")).clicked() {
+                            action = Some(BootEditorAction::SetDefault(selected.clone()));
+                        }
+                        if ui.add_enabled(!busy, egui::Button::new("删除")).clicked() {
+                            state.confirm_delete = true;
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("复制为:");
+                        ui.add_enabled_ui(!busy, |ui| {
+                            egui::ComboBox::from_id_salt("boot_editor_safe_mode")
+                                .selected_text(state.safe_mode.label())
+                                .show_ui(ui, |ui| {
+                                    for mode in SafeBootMode::ALL {
+                                        ui.selectable_value(&mut state.safe_mode, mode, mode.label());
+                                    }
+                                });
+                        });
+                        if ui.add_enabled(!busy, egui::Button::new("添加引导项")).clicked() {
+                            let name = match state.description.trim() {
+                                "" => "Windows",
+                                name => name,
+                            };
+                            let description = format!("{}（{}）", name, state.safe_mode.label());
+                            action =
+                                Some(BootEditorAction::AddSafeMode(selected.clone(), description, state.safe_mode));
+                        }
+                    });
+
+                    if state.confirm_delete {
+                        ui.add_space(5.0);
+                        let is_default = state.menu.as_ref().is_some_and(|m| m.is_default(&selected));
+                        let warning = if is_default {
+                            "⚠ 这是默认引导项，删除后需要另选默认项，否则可能无法启动。确定删除？"
+                        } else {
+                            "⚠ 确定删除选中的引导项？"
+                        };
+                        ui.colored_label(egui::Color32::from_rgb(255, 165, 0), warning);
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!busy, egui::Button::new("确认删除")).clicked() {
+                                action = Some(BootEditorAction::Delete(selected.clone()));
+                            }
+                            if ui.button("取消").clicked() {
+                                state.confirm_delete = false;
+                            }
+                        });
+                    }
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if let Some(action) = action {
+            self.start_boot_editor_action(action);
+        } else if mount_esp {
+            self.start_boot_editor_mount_esp();
+        } else if reload {
+            self.boot_editor_state.select(None);
+            self.load_boot_menu();
+        }
+        if should_close {
+            self.show_boot_editor_dialog = false;
+        }
+    }
+
+    /// 后台执行修改
+    fn start_boot_editor_action(&mut self, action: BootEditorAction) {
+        let state = &mut self.boot_editor_state;
+        let store = state.store();
+        state.confirm_delete = false;
+        state.message = "正在修改引导配置...".to_string();
+        let (tx, rx) = mpsc::channel();
+        state.result_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let manager = BootManager::new();
+            let store = store.as_deref();
+            let result = match action {
+                BootEditorAction::SetDefault(id) => manager
+                    .set_default_boot(store, &id)
+                    .map(|_| "✅ 已设为默认引导项".to_string()),
+                BootEditorAction::SetTimeout(seconds) => manager
+                    .set_timeout(store, seconds)
+                    .map(|_| format!("✅ 菜单等待时间已设为 {} 秒", seconds)),
+                BootEditorAction::Rename(id, description) => manager
+                    .set_description(store, &id, &description)
+                    .map(|_| format!("✅ 已重命名为 {}", description)),
+                BootEditorAction::Delete(id) => manager
+                    .delete_boot_entry(store, &id)
+                    .map(|_| "✅ 已删除引导项".to_string()),
+                BootEditorAction::AddSafeMode(id, description, mode) => manager
+                    .add_safe_mode_entry(store, &id, &description, mode)
+                    .map(|guid| format!("✅ 已添加 {} {}", description, guid)),
+            };
+            let _ = tx.send(result.map_err(|e| format!("❌ {:#}", e)));
+        });
+    }
+
+    /// 后台挂载 ESP 并切换到其中的 BCD
+    fn start_boot_editor_mount_esp(&mut self) {
+        let state = &mut self.boot_editor_state;
+        state.message = "正在挂载 ESP 分区...".to_string();
+        let (tx, rx) = mpsc::channel();
+        state.esp_rx = Some(rx);
+        std::thread::spawn(move || {
+            let result = BootManager::new()
+                .find_and_mount_esp()
+                .map(|esp| format!("{}\\EFI\\Microsoft\\Boot\\BCD", esp.trim_end_matches('\\')))
+                .map_err(|e| format!("❌ 挂载 ESP 失败: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 检查读取和修改结果
+    pub fn check_boot_editor_result(&mut self) {
+        let mut reload = false;
+        let state = &mut self.boot_editor_state;
+
+        if let Some(ref rx) = state.menu_rx {
+            if let Ok((menu, stores)) = rx.try_recv() {
+                match menu {
+                    Ok(menu) => {
+                        state.timeout = menu.timeout.unwrap_or(30);
+                        state.menu = Some(menu);
+                        // 重新读取后保留选中项（已删除时取消选中）
+                        let selected = state.selected.take().filter(|id| {
+                            state.menu.as_ref().is_some_and(|m| m.entries.iter().any(|e| e.identifier == *id))
+                        });
+                        state.select(selected);
+                    }
+                    Err(e) => {
+                        state.menu = None;
+                        state.select(None);
+                        state.message = e;
+                    }
+                }
+                state.stores = stores;
+                state.menu_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.esp_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(store) => {
+                        state.message = format!("已挂载 ESP: {}", store);
+                        state.store = store;
+                        reload = true;
+                    }
+                    Err(e) => state.message = e,
+                }
+                state.esp_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.result_rx {
+            if let Ok(result) = rx.try_recv() {
+                state.message = result.unwrap_or_else(|e| e);
+                state.result_rx = None;
+                reload = true;
+            }
+        }
+
+        if reload {
+            self.load_boot_menu();
+        }
+    }
+}
@@ -0,0 +1,352 @@
+//! 工具目录
+//!
+//! 定义工具箱中的全部工具及其分类、搜索关键字和可用环境
+
+/// 工具分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCategory {
+    Disk,
+    System,
+    Network,
+    Image,
+}
+
+impl ToolCategory {
+    /// 全部分类（按显示顺序）
+    pub const ALL: [ToolCategory; 4] = [
+        ToolCategory::Disk,
+        ToolCategory::System,
+        ToolCategory::Network,
+        ToolCategory::Image,
+    ];
+
+    /// 分类名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ToolCategory::Disk => "磁盘",
+            ToolCategory::System => "系统",
+            ToolCategory::Network => "网络",
+            ToolCategory::Image => "镜像",
+        }
+    }
+}
+
+/// 工具
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolId {
+    NvidiaUninstall,
+    PartitionCopy,
+    BatchFormat,
+    ImportStorageDriver,
+    QuickPartition,
+    RemoveAppx,
+    DriverBackup,
+    RepairBoot,
+    RestoreBcd,
+    HiveRestore,
+    NetworkInfo,
+    SoftwareList,
+    TimeSync,
+    Ghost,
+    WanDrv,
+    GhoPassword,
+    ResetNetwork,
+    SpaceSniffer,
+    ImageVerify,
+    WimBrowser,
+    SedUnlock,
+    DriverPacks,
+    DriveOptimize,
+    ImageConvert,
+    WimManage,
+    RecoveryEnv,
+    Netcast,
+    BootEditor,
+}
+
+impl ToolId {
+    /// 全部工具（按显示顺序）
+    pub const ALL: [ToolId; 28] = [
+        ToolId::QuickPartition,
+        ToolId::PartitionCopy,
+        ToolId::BatchFormat,
+        ToolId::SedUnlock,
+        ToolId::SpaceSniffer,
+        ToolId::DriveOptimize,
+        ToolId::NvidiaUninstall,
+        ToolId::ImportStorageDriver,
+        ToolId::DriverPacks,
+        ToolId::DriverBackup,
+        ToolId::WanDrv,
+        ToolId::RemoveAppx,
+        ToolId::RepairBoot,
+        ToolId::RestoreBcd,
+        ToolId::BootEditor,
+        ToolId::HiveRestore,
+        ToolId::RecoveryEnv,
+        ToolId::SoftwareList,
+        ToolId::TimeSync,
+        ToolId::NetworkInfo,
+        ToolId::ResetNetwork,
+        ToolId::Netcast,
+        ToolId::ImageVerify,
+        ToolId::WimBrowser,
+        ToolId::ImageConvert,
+        ToolId::WimManage,
+        ToolId::GhoPassword,
+        ToolId::Ghost,
+    ];
+
+    /// 持久化使用的标识（写入 config.json，不可随意修改）
+    pub fn key(&self) -> &'static str {
+        match self {
+            ToolId::NvidiaUninstall => "nvidia_uninstall",
+            ToolId::PartitionCopy => "partition_copy",
+            ToolId::BatchFormat => "batch_format",
+            ToolId::ImportStorageDriver => "import_storage_driver",
+            ToolId::QuickPartition => "quick_partition",
+            ToolId::RemoveAppx => "remove_appx",
+            ToolId::DriverBackup => "driver_backup",
+            ToolId::RepairBoot => "repair_boot",
+            ToolId::RestoreBcd => "restore_bcd",
+            ToolId::HiveRestore => "hive_restore",
+            ToolId::NetworkInfo => "network_info",
+            ToolId::SoftwareList => "software_list",
+            ToolId::TimeSync => "time_sync",
+            ToolId::Ghost => "ghost",
+            ToolId::WanDrv => "wandrv",
+            ToolId::GhoPassword => "gho_password",
+            ToolId::ResetNetwork => "reset_network",
+            ToolId::SpaceSniffer => "space_sniffer",
+            ToolId::ImageVerify => "image_verify",
+            ToolId::WimBrowser => "wim_browser",
+            ToolId::SedUnlock => "sed_unlock",
+            ToolId::DriverPacks => "driver_packs",
+            ToolId::DriveOptimize => "drive_optimize",
+            ToolId::ImageConvert => "image_convert",
+            ToolId::WimManage => "wim_manage",
+            ToolId::RecoveryEnv => "recovery_env",
+            ToolId::Netcast => "netcast",
+            ToolId::BootEditor => "boot_editor",
+        }
+    }
+
+    /// 根据持久化标识查找工具
+    pub fn from_key(key: &str) -> Option<ToolId> {
+        Self::ALL.iter().copied().find(|t| t.key() == key)
+    }
+
+    /// 按钮显示名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ToolId::NvidiaUninstall => "英伟达显卡驱动卸载",
+            ToolId::PartitionCopy => "分区对拷",
+            ToolId::BatchFormat => "批量格式化",
+            ToolId::ImportStorageDriver => "导入存储驱动",
+            ToolId::QuickPartition => "一键分区",
+            ToolId::RemoveAppx => "移除APPX应用",
+            ToolId::DriverBackup => "驱动备份还原",
+            ToolId::RepairBoot => "一键修复引导",
+            ToolId::RestoreBcd => "恢复引导配置",
+            ToolId::HiveRestore => "注册表配置单元恢复",
+            ToolId::NetworkInfo => "本机网络信息",
+            ToolId::SoftwareList => "软件列表",
+            ToolId::TimeSync => "系统时间校准",
+            ToolId::Ghost => "手动运行Ghost",
+            ToolId::WanDrv => "万能驱动",
+            ToolId::GhoPassword => "查看GHO密码",
+            ToolId::ResetNetwork => "重置网络设置",
+            ToolId::SpaceSniffer => "SpaceSniffer",
+            ToolId::ImageVerify => "镜像校验",
+            ToolId::WimBrowser => "从备份提取文件",
+            ToolId::SedUnlock => "自加密硬盘解锁",
+            ToolId::DriverPacks => "存储驱动包管理",
+            ToolId::DriveOptimize => "驱动器优化",
+            ToolId::ImageConvert => "镜像格式转换",
+            ToolId::WimManage => "镜像分卷管理",
+            ToolId::RecoveryEnv => "一键恢复环境",
+            ToolId::Netcast => "局域网分发",
+            ToolId::BootEditor => "引导项编辑",
+        }
+    }
+
+    /// 所属分类
+    pub fn category(&self) -> ToolCategory {
+        match self {
+            ToolId::QuickPartition
+            | ToolId::PartitionCopy
+            | ToolId::BatchFormat
+            | ToolId::SedUnlock
+            | ToolId::SpaceSniffer
+            | ToolId::DriveOptimize => ToolCategory::Disk,
+            ToolId::NvidiaUninstall
+            | ToolId::ImportStorageDriver
+            | ToolId::DriverPacks
+            | ToolId::DriverBackup
+            | ToolId::WanDrv
+            | ToolId::RemoveAppx
+            | ToolId::RepairBoot
+            | ToolId::RestoreBcd
+            | ToolId::BootEditor
+            | ToolId::HiveRestore
+            | ToolId::RecoveryEnv
+            | ToolId::SoftwareList
+            | ToolId::TimeSync => ToolCategory::System,
+            ToolId::NetworkInfo | ToolId::ResetNetwork | ToolId::Netcast => ToolCategory::Network,
+            ToolId::ImageVerify
+            | ToolId::WimBrowser
+            | ToolId::ImageConvert
+            | ToolId::WimManage
+            | ToolId::GhoPassword
+            | ToolId::Ghost => ToolCategory::Image,
+        }
+    }
+
+    /// 额外搜索关键字（名称本身总是参与匹配）
+    fn keywords(&self) -> &'static [&'static str] {
+        match self {
+            ToolId::NvidiaUninstall => &["nvidia", "显卡", "gpu", "驱动"],
+            ToolId::PartitionCopy => &["复制", "克隆", "clone"],
+            ToolId::BatchFormat => &["format", "格式化"],
+            ToolId::ImportStorageDriver => &["vmd", "raid", "硬盘控制器", "驱动"],
+            ToolId::QuickPartition => &["分区", "diskpart", "gpt", "mbr"],
+            ToolId::RemoveAppx => &["uwp", "应用", "预装"],
+            ToolId::DriverBackup => &["驱动", "导出", "导入"],
+            ToolId::RepairBoot => &["bcd", "启动", "引导", "boot"],
+            ToolId::RestoreBcd => &["bcd", "引导", "备份", "还原", "boot"],
+            ToolId::HiveRestore => &["注册表", "hive", "software", "system", "修复"],
+            ToolId::NetworkInfo => &["ip", "网卡", "mac"],
+            ToolId::SoftwareList => &["软件", "程序"],
+            ToolId::TimeSync => &["ntp", "时间", "时钟"],
+            ToolId::Ghost => &["gho", "ghost"],
+            ToolId::WanDrv => &["驱动", "qdzc"],
+            ToolId::GhoPassword => &["gho", "密码"],
+            ToolId::ResetNetwork => &["winsock", "网络", "重置"],
+            ToolId::SpaceSniffer => &["空间", "磁盘占用"],
+            ToolId::ImageVerify => &["wim", "esd", "iso", "gho", "校验"],
+            ToolId::WimBrowser => &["wim", "esd", "备份", "文件", "提取", "浏览", "挂载"],
+            ToolId::SedUnlock => &["opal", "edrive", "sed", "psid", "硬件加密", "锁定"],
+            ToolId::DriverPacks => &["vmd", "raid", "virtio", "apple", "驱动包", "下载"],
+            ToolId::DriveOptimize => &["defrag", "碎片整理", "trim", "ssd", "hdd", "优化"],
+            ToolId::ImageConvert => &["wim", "esd", "转换", "压缩", "导出", "export", "lzx", "xpress", "lzms"],
+            ToolId::WimManage => &["wim", "esd", "分卷", "删除", "导出", "重命名", "版本", "flags"],
+            ToolId::RecoveryEnv => &["恢复", "还原", "启动菜单", "f11", "winre", "reagentc", "pe", "隐藏分区"],
+            ToolId::Netcast => &["机房", "批量", "部署", "分发", "组播", "多播", "网络克隆", "netcast"],
+            ToolId::BootEditor => &["bcd", "bcdedit", "引导", "启动菜单", "默认", "超时", "安全模式", "boot"],
+        }
+    }
+
+    /// 检查工具在当前环境下是否可用
+    pub fn is_available(&self, is_pe: bool) -> bool {
+        match self {
+            ToolId::RepairBoot => is_pe,
+            ToolId::SoftwareList | ToolId::ResetNetwork | ToolId::RecoveryEnv => !is_pe,
+            _ => true,
+        }
+    }
+
+    /// 是否会清除或覆盖磁盘数据、引导配置、注册表或已安装的驱动和应用
+    pub fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            ToolId::QuickPartition
+                | ToolId::PartitionCopy
+                | ToolId::BatchFormat
+                | ToolId::NvidiaUninstall
+                | ToolId::ResetNetwork
+                | ToolId::SedUnlock
+                | ToolId::Ghost
+                | ToolId::RecoveryEnv
+                | ToolId::BootEditor
+                | ToolId::RepairBoot
+                | ToolId::RestoreBcd
+                | ToolId::HiveRestore
+                | ToolId::RemoveAppx
+                | ToolId::ImportStorageDriver
+                | ToolId::DriverBackup
+                | ToolId::WimManage
+        )
+    }
+
+    /// 是否只读取信息、不修改本机（演示模式下只有这些工具可用）
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            ToolId::NetworkInfo
+                | ToolId::SoftwareList
+                | ToolId::GhoPassword
+                | ToolId::ImageVerify
+                | ToolId::SpaceSniffer
+        )
+    }
+
+    /// 启用技师锁定时是否需要先输入 PIN（只读取信息、校验镜像的工具保持开放）
+    pub fn requires_technician(&self) -> bool {
+        !self.is_read_only()
+    }
+
+    /// 检查工具是否匹配搜索词（忽略大小写，空搜索词匹配全部）
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        self.name().to_lowercase().contains(&query)
+            || self.category().label().contains(&query)
+            || self.keywords().iter().any(|k| k.contains(&query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_are_unique_and_round_trip() {
+        for tool in ToolId::ALL {
+            assert_eq!(ToolId::from_key(tool.key()), Some(tool));
+        }
+        let mut keys: Vec<_> = ToolId::ALL.iter().map(|t| t.key()).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), ToolId::ALL.len());
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(ToolId::ImageVerify.matches(""));
+        assert!(ToolId::ImageVerify.matches("WIM"));
+        assert!(ToolId::QuickPartition.matches("磁盘"));
+        assert!(ToolId::NetworkInfo.matches("网络"));
+        assert!(!ToolId::TimeSync.matches("gho"));
+    }
+
+    #[test]
+    fn test_read_only_tools_are_not_destructive() {
+        for tool in ToolId::ALL {
+            assert!(!(tool.is_read_only() && tool.is_destructive()), "{:?}", tool);
+        }
+        assert!(ToolId::ImageVerify.is_read_only());
+        assert!(!ToolId::TimeSync.is_read_only());
+        assert!(!ToolId::Netcast.is_read_only());
+    }
+
+    #[test]
+    fn test_requires_technician() {
+        assert!(ToolId::QuickPartition.requires_technician());
+        assert!(ToolId::BatchFormat.requires_technician());
+        assert!(ToolId::BootEditor.requires_technician());
+        assert!(ToolId::RestoreBcd.requires_technician());
+        assert!(ToolId::HiveRestore.requires_technician());
+        assert!(ToolId::WimManage.requires_technician());
+        assert!(ToolId::NvidiaUninstall.requires_technician());
+        assert!(ToolId::ResetNetwork.requires_technician());
+        assert!(ToolId::DriveOptimize.requires_technician());
+        assert!(ToolId::Netcast.requires_technician());
+        for tool in ToolId::ALL {
+            assert!(!tool.is_destructive() || tool.requires_technician(), "{:?}", tool);
+        }
+        assert!(!ToolId::ImageVerify.requires_technician());
+        assert!(!ToolId::NetworkInfo.requires_technician());
+    }
+}
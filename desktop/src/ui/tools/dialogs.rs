@@ -12,6 +12,9 @@ use super::network::get_detailed_network_info;
 use super::appx::{get_appx_packages, remove_appx_packages};
 use super::software::{truncate_string, save_software_list_to_file, get_installed_software};
 use super::network::reset_network;
+use crate::ui::a11y::{dialog_keys, focus_if_idle, labeled_text_edit};
+use crate::ui::password_input::{password_field, recovery_key_field};
+use crate::ui::recent::recent_menu;
 
 impl App {
     /// 检查并处理异步操作结果
@@ -130,6 +133,37 @@ impl App {
         // 检查GHO密码读取结果
         self.check_gho_password_result();
         
+        // 检查自加密硬盘操作结果
+        self.check_sed_unlock_result();
+        
+        // 检查驱动包下载结果
+        self.check_driver_packs_result();
+        
+        // 检查引导配置恢复结果
+        self.check_bcd_restore_result();
+        
+        // 检查引导项编辑结果
+        self.check_boot_editor_result();
+        
+        // 检查注册表配置单元恢复结果
+        self.check_hive_restore_result();
+        
+        // 检查备份镜像浏览结果
+        self.check_wim_browser_result();
+        
+        // 检查镜像格式转换进度
+        self.check_image_convert_result();
+        self.check_wim_manage_result();
+        
+        // 检查一键恢复环境安装进度
+        self.check_recovery_env_result();
+        
+        // 检查局域网分发启动进度
+        self.check_netcast_result();
+        
+        // 检查驱动器优化进度
+        self.check_drive_optimize_progress();
+        
         // 检查英伟达驱动卸载结果
         self.check_nvidia_uninstall_result();
         
@@ -181,12 +215,14 @@ impl App {
             return;
         }
 
+        let mut escape = false;
         egui::Window::new("本机网络信息")
             .open(&mut self.show_network_info_dialog)
             .resizable(true)
             .default_width(500.0)
             .default_height(400.0)
             .show(ui.ctx(), |ui| {
+                escape = dialog_keys(ui).escape;
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     if let Some(ref adapters) = self.network_info_cache {
                         if adapters.is_empty() {
@@ -256,6 +292,10 @@ impl App {
                     }
                 });
             });
+
+        if escape {
+            self.show_network_info_dialog = false;
+        }
     }
 
     /// 渲染导入存储驱动对话框
@@ -272,6 +312,7 @@ impl App {
             .resizable(false)
             .default_width(450.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("将 Intel VMD / Apple SSD / Visior 等硬盘控制器驱动导入到离线系统");
                 ui.add_space(10.0);
 
@@ -340,7 +381,7 @@ impl App {
                         }
                     }
 
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -405,6 +446,7 @@ impl App {
             .default_width(550.0)
             .default_height(450.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 if is_pe {
                     ui.label("移除离线系统中预装的 Microsoft Store 应用");
                 } else {
@@ -552,7 +594,7 @@ impl App {
                         self.start_load_appx_list();
                     }
 
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -626,6 +668,7 @@ impl App {
             .resizable(false)
             .default_width(500.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("导出或导入系统驱动");
                 ui.add_space(10.0);
 
@@ -677,16 +720,15 @@ impl App {
 
                             ui.add_space(5.0);
                             ui.horizontal(|ui| {
-                                ui.label("保存目录:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.driver_backup_path)
-                                        .desired_width(300.0),
-                                );
+                                labeled_text_edit(ui, "保存目录:", &mut self.driver_backup_path, false, 300.0);
                                 if ui.button("浏览...").clicked() {
                                     if let Some(path) = rfd::FileDialog::new().pick_folder() {
                                         self.driver_backup_path = path.to_string_lossy().to_string();
                                     }
                                 }
+                                if let Some(path) = recent_menu(ui, &self.app_config.recent_driver_dirs) {
+                                    self.driver_backup_path = path;
+                                }
                             });
                         }
                         DriverBackupMode::Import => {
@@ -720,16 +762,15 @@ impl App {
 
                             ui.add_space(5.0);
                             ui.horizontal(|ui| {
-                                ui.label("驱动目录:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.driver_backup_path)
-                                        .desired_width(300.0),
-                                );
+                                labeled_text_edit(ui, "驱动目录:", &mut self.driver_backup_path, false, 300.0);
                                 if ui.button("浏览...").clicked() {
                                     if let Some(path) = rfd::FileDialog::new().pick_folder() {
                                         self.driver_backup_path = path.to_string_lossy().to_string();
                                     }
                                 }
+                                if let Some(path) = recent_menu(ui, &self.app_config.recent_driver_dirs) {
+                                    self.driver_backup_path = path;
+                                }
                             });
                         }
                     }
@@ -766,7 +807,7 @@ impl App {
                         }
                     }
 
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -794,6 +835,7 @@ impl App {
 
         let path = self.driver_backup_path.clone();
         let mode = self.driver_backup_mode;
+        self.app_config.add_recent_driver_dir(&path);
         
         self.driver_backup_loading = true;
         self.driver_backup_message = match mode {
@@ -849,6 +891,7 @@ impl App {
             .default_width(500.0)
             .default_height(450.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 if is_loading {
                     ui.horizontal(|ui| {
                         ui.spinner();
@@ -903,7 +946,7 @@ impl App {
                         }
                     }
 
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -933,6 +976,7 @@ impl App {
             .default_width(400.0)
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.vertical_centered(|ui| {
                     ui.add_space(10.0);
                     ui.label(egui::RichText::new("⚠").size(32.0).color(egui::Color32::from_rgb(255, 180, 0)));
@@ -962,7 +1006,7 @@ impl App {
                         do_reset = true;
                         should_close = true;
                     }
-                    if ui.button("取消").clicked() {
+                    if ui.button("取消").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -1021,6 +1065,7 @@ impl App {
             .default_width(400.0)
             .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.vertical_centered(|ui| {
                     ui.add_space(10.0);
                     ui.label(egui::RichText::new("🕐").size(32.0));
@@ -1047,10 +1092,10 @@ impl App {
                         ui.spinner();
                         ui.label("正在同步时间...");
                     } else {
-                        if ui.button("确定").clicked() {
+                        if ui.button("确定").clicked() || keys.enter {
                             do_sync = true;
                         }
-                        if ui.button("取消").clicked() {
+                        if ui.button("取消").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
@@ -1100,6 +1145,7 @@ impl App {
             .default_width(500.0)
             .default_height(400.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("选择要格式化的分区（系统盘已自动隐藏）");
                 ui.add_space(10.0);
 
@@ -1192,7 +1238,7 @@ impl App {
                             self.start_load_formatable_partitions();
                         }
 
-                        if ui.button("关闭").clicked() {
+                        if ui.button("关闭").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
@@ -1351,12 +1397,15 @@ impl App {
 
         let mut should_close = false;
         let mut do_copy = false;
+        let mut do_retry = false;
+        let mut export_path: Option<std::path::PathBuf> = None;
 
         egui::Window::new("分区对拷")
             .resizable(true)
             .default_width(650.0)
             .default_height(550.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("将源分区的所有文件复制到目标分区（支持断点续传）");
                 ui.add_space(10.0);
 
@@ -1550,6 +1599,58 @@ impl App {
                     ui.add_space(10.0);
                 }
 
+                // 复制结束后显示失败文件列表
+                let failed_files = self
+                    .partition_copy_progress
+                    .as_ref()
+                    .filter(|p| p.completed)
+                    .map(|p| p.failed_files.clone())
+                    .unwrap_or_default();
+                if !self.partition_copy_copying && !failed_files.is_empty() {
+                    egui::CollapsingHeader::new(format!("失败文件列表（{} 个）", failed_files.len()))
+                        .id_salt("partition_copy_failed")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical()
+                                .id_salt("partition_copy_failed_scroll")
+                                .max_height(120.0)
+                                .show(ui, |ui| {
+                                    egui::Grid::new("partition_copy_failed_list")
+                                        .num_columns(3)
+                                        .spacing([10.0, 2.0])
+                                        .striped(true)
+                                        .show(ui, |ui| {
+                                            ui.label(egui::RichText::new("错误码").strong());
+                                            ui.label(egui::RichText::new("文件").strong());
+                                            ui.label(egui::RichText::new("错误信息").strong());
+                                            ui.end_row();
+                                            for failed in &failed_files {
+                                                ui.label(failed.code_text());
+                                                ui.label(&failed.relative_path);
+                                                ui.label(&failed.message);
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+
+                            ui.horizontal(|ui| {
+                                if ui.button("重试失败项").clicked() {
+                                    do_retry = true;
+                                }
+                                if ui.button("导出列表").clicked() {
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .set_file_name("partition_copy_failed.txt")
+                                        .add_filter("文本文件", &["txt"])
+                                        .save_file()
+                                    {
+                                        export_path = Some(path);
+                                    }
+                                }
+                            });
+                        });
+                    ui.add_space(10.0);
+                }
+
                 ui.horizontal(|ui| {
                     if self.partition_copy_copying {
                         ui.spinner();
@@ -1594,7 +1695,7 @@ impl App {
                             self.start_load_copyable_partitions();
                         }
 
-                        if ui.button("关闭").clicked() {
+                        if ui.button("关闭").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
@@ -1605,6 +1706,14 @@ impl App {
             self.start_partition_copy();
         }
 
+        if do_retry {
+            self.start_partition_copy_retry();
+        }
+
+        if let Some(path) = export_path {
+            self.export_partition_copy_failed(&path);
+        }
+
         if should_close {
             self.show_partition_copy_dialog = false;
         }
@@ -1662,15 +1771,62 @@ impl App {
         self.partition_copy_message = "正在准备复制...".to_string();
 
         let is_resume = self.partition_copy_is_resume;
+        let io = self.app_config.io_options();
         
         let (tx, rx) = mpsc::channel();
         self.partition_copy_progress_rx = Some(rx);
 
         std::thread::spawn(move || {
-            super::partition_copy::execute_partition_copy(&source, &target, tx, is_resume);
+            super::partition_copy::execute_partition_copy(&source, &target, tx, is_resume, io);
+        });
+    }
+
+    /// 重新复制上次失败的文件
+    fn start_partition_copy_retry(&mut self) {
+        let (Some(source), Some(target)) = (
+            self.partition_copy_source.clone(),
+            self.partition_copy_target.clone(),
+        ) else {
+            return;
+        };
+        let failed_files = match &self.partition_copy_progress {
+            Some(progress) if !progress.failed_files.is_empty() => progress.failed_files.clone(),
+            _ => return,
+        };
+
+        self.partition_copy_copying = true;
+        self.partition_copy_log.clear();
+        self.partition_copy_message = format!("正在重试 {} 个失败文件...", failed_files.len());
+        let io = self.app_config.io_options();
+
+        let (tx, rx) = mpsc::channel();
+        self.partition_copy_progress_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            super::partition_copy::retry_failed_files(&source, &target, failed_files, tx, io);
         });
     }
 
+    /// 导出失败文件列表
+    fn export_partition_copy_failed(&mut self, path: &std::path::Path) {
+        let Some(progress) = &self.partition_copy_progress else {
+            return;
+        };
+        let report = super::partition_copy::format_failed_report(
+            self.partition_copy_source.as_deref().unwrap_or("-"),
+            self.partition_copy_target.as_deref().unwrap_or("-"),
+            &progress.failed_files,
+        );
+        match std::fs::write(path, report) {
+            Ok(_) => {
+                self.partition_copy_message = format!("失败列表已导出至: {}", path.display());
+            }
+            Err(e) => {
+                self.partition_copy_message = format!("错误: 导出失败列表失败: {}", e);
+            }
+        }
+    }
+
     // ==================== 安装时BitLocker解锁对话框 ====================
 
     /// 渲染安装时BitLocker解锁对话框
@@ -1695,6 +1851,7 @@ impl App {
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.set_min_width(500.0);
                 
                 ui.label("检测到以下分区被BitLocker加密锁定，需要解锁后才能继续安装：");
@@ -1772,22 +1929,13 @@ impl App {
                     match self.install_bitlocker_mode {
                         BitLockerUnlockMode::Password => {
                             ui.horizontal(|ui| {
-                                ui.label("密码:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.install_bitlocker_password)
-                                        .password(true)
-                                        .desired_width(300.0),
-                                );
+                                let input = password_field(ui, "密码:", &mut self.install_bitlocker_password, 300.0);
+                                focus_if_idle(ui, &input);
                             });
                         }
                         BitLockerUnlockMode::RecoveryKey => {
                             ui.horizontal(|ui| {
-                                ui.label("恢复密钥:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.install_bitlocker_recovery_key)
-                                        .desired_width(300.0)
-                                        .hint_text("000000-000000-000000-000000-000000-000000-000000-000000"),
-                                );
+                                recovery_key_field(ui, "恢复密钥:", &mut self.install_bitlocker_recovery_key, 300.0);
                             });
                         }
                     }
@@ -1823,7 +1971,9 @@ impl App {
                                 BitLockerUnlockMode::RecoveryKey => !self.install_bitlocker_recovery_key.is_empty(),
                             };
 
-                        if ui.add_enabled(can_unlock, egui::Button::new("解锁")).clicked() {
+                        if ui.add_enabled(can_unlock, egui::Button::new("解锁")).clicked()
+                            || (can_unlock && keys.enter)
+                        {
                             do_unlock = true;
                         }
 
@@ -1835,19 +1985,19 @@ impl App {
                             do_skip_all = true;
                         }
 
-                        if ui.button("取消安装").clicked() {
+                        if ui.button("取消安装").clicked() || keys.escape {
                             should_close = true;
                         }
                     } else {
                         // 所有分区都已解锁
-                        if ui.button("继续安装").clicked() {
+                        if ui.button("继续安装").clicked() || keys.enter {
                             should_close = true;
                             if self.install_bitlocker_continue_after {
                                 self.continue_installation_after_bitlocker();
                             }
                         }
 
-                        if ui.button("取消").clicked() {
+                        if ui.button("取消").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
@@ -1994,6 +2144,7 @@ impl App {
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.set_min_width(500.0);
                 
                 ui.label("检测到以下分区被BitLocker加密锁定，需要解锁后才能继续备份：");
@@ -2071,22 +2222,13 @@ impl App {
                     match self.backup_bitlocker_mode {
                         BitLockerUnlockMode::Password => {
                             ui.horizontal(|ui| {
-                                ui.label("密码:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.backup_bitlocker_password)
-                                        .password(true)
-                                        .desired_width(300.0),
-                                );
+                                let input = password_field(ui, "密码:", &mut self.backup_bitlocker_password, 300.0);
+                                focus_if_idle(ui, &input);
                             });
                         }
                         BitLockerUnlockMode::RecoveryKey => {
                             ui.horizontal(|ui| {
-                                ui.label("恢复密钥:");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut self.backup_bitlocker_recovery_key)
-                                        .desired_width(300.0)
-                                        .hint_text("000000-000000-000000-000000-000000-000000-000000-000000"),
-                                );
+                                recovery_key_field(ui, "恢复密钥:", &mut self.backup_bitlocker_recovery_key, 300.0);
                             });
                         }
                     }
@@ -2122,7 +2264,9 @@ impl App {
                                 BitLockerUnlockMode::RecoveryKey => !self.backup_bitlocker_recovery_key.is_empty(),
                             };
 
-                        if ui.add_enabled(can_unlock, egui::Button::new("解锁")).clicked() {
+                        if ui.add_enabled(can_unlock, egui::Button::new("解锁")).clicked()
+                            || (can_unlock && keys.enter)
+                        {
                             do_unlock = true;
                         }
 
@@ -2134,19 +2278,19 @@ impl App {
                             do_skip_all = true;
                         }
 
-                        if ui.button("取消备份").clicked() {
+                        if ui.button("取消备份").clicked() || keys.escape {
                             should_close = true;
                         }
                     } else {
                         // 所有分区都已解锁
-                        if ui.button("继续备份").clicked() {
+                        if ui.button("继续备份").clicked() || keys.enter {
                             should_close = true;
                             if self.backup_bitlocker_continue_after {
                                 self.continue_backup_after_bitlocker();
                             }
                         }
 
-                        if ui.button("取消").clicked() {
+                        if ui.button("取消").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
@@ -2285,6 +2429,7 @@ impl App {
             .resizable(false)
             .default_width(450.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("修复Windows系统的启动引导");
                 ui.add_space(10.0);
 
@@ -2390,7 +2535,7 @@ impl App {
                         self.refresh_windows_partitions_cache();
                     }
 
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
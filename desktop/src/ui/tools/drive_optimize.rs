@@ -0,0 +1,410 @@
+//! 驱动器优化模块
+//!
+//! 机械硬盘执行碎片整理，固态硬盘执行重新 TRIM（defrag /L），磁盘类型取自硬件信息中的 is_ssd。
+//! 还原系统后运行一次可整理新释放的文件并通知 SSD 回收空闲块。
+//! 缺少 defrag.exe 时 SSD 改为直接下发 TRIM，只支持 NTFS 卷。
+
+use egui;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::mpsc::{self, Sender};
+
+use crate::app::App;
+use crate::core::disk::{DiskManager, Partition};
+use crate::core::hardware_info::DiskInfo;
+use crate::core::volume_trim::{file_system_name, supports_native_trim};
+use crate::ui::a11y::dialog_keys;
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// 优化方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizeMethod {
+    /// 碎片整理（机械硬盘）
+    Defrag,
+    /// 重新 TRIM（固态硬盘）
+    Retrim,
+}
+
+impl OptimizeMethod {
+    /// 根据磁盘类型选择优化方式
+    pub fn for_disk(is_ssd: bool) -> Self {
+        if is_ssd {
+            OptimizeMethod::Retrim
+        } else {
+            OptimizeMethod::Defrag
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            OptimizeMethod::Defrag => "碎片整理",
+            OptimizeMethod::Retrim => "重新 TRIM",
+        }
+    }
+
+    /// defrag.exe 对应的参数
+    fn defrag_arg(&self) -> &'static str {
+        match self {
+            OptimizeMethod::Defrag => "/D",
+            OptimizeMethod::Retrim => "/L",
+        }
+    }
+}
+
+/// 可优化的卷
+#[derive(Debug, Clone)]
+pub struct OptimizableVolume {
+    /// 盘符（如 "D:"）
+    pub letter: String,
+    /// 卷标
+    pub label: String,
+    /// 总大小（MB）
+    pub total_size_mb: u64,
+    /// 所在磁盘型号
+    pub disk_model: String,
+    /// 文件系统（如 "NTFS"）
+    pub file_system: String,
+    /// 优化方式
+    pub method: OptimizeMethod,
+}
+
+impl OptimizableVolume {
+    /// 不能优化的原因：没有 defrag.exe 时无法碎片整理，直接 TRIM 只支持 NTFS
+    pub fn unsupported_reason(&self, has_defrag: bool) -> Option<String> {
+        match self.method {
+            _ if has_defrag => None,
+            OptimizeMethod::Defrag => Some("当前环境缺少 defrag.exe，无法进行碎片整理".to_string()),
+            OptimizeMethod::Retrim if !supports_native_trim(&self.file_system) => Some(format!(
+                "当前环境缺少 defrag.exe，{} 卷不支持直接 TRIM（仅支持 NTFS）",
+                if self.file_system.is_empty() { "未知文件系统" } else { self.file_system.as_str() }
+            )),
+            OptimizeMethod::Retrim => None,
+        }
+    }
+}
+
+/// 优化进度
+#[derive(Debug, Clone)]
+pub enum OptimizeProgress {
+    /// 百分比与当前输出行
+    Running(u8, String),
+    /// 完成（成功消息或错误消息）
+    Finished(Result<String, String>),
+}
+
+/// 列出可优化的卷（只包含能确定磁盘类型的卷）
+pub fn list_optimizable_volumes(partitions: &[Partition], disks: &[DiskInfo]) -> Vec<OptimizableVolume> {
+    partitions
+        .iter()
+        .filter_map(|partition| {
+            let disk_number = partition.disk_number?;
+            let disk = disks.iter().find(|d| d.disk_index == disk_number)?;
+            Some(OptimizableVolume {
+                letter: partition.letter.clone(),
+                label: partition.label.clone(),
+                total_size_mb: partition.total_size_mb,
+                disk_model: disk.model.clone(),
+                file_system: file_system_name(&partition.letter).unwrap_or_default(),
+                method: OptimizeMethod::for_disk(disk.is_ssd),
+            })
+        })
+        .collect()
+}
+
+/// defrag.exe 路径（部分 PE 不包含）
+fn defrag_path() -> Option<PathBuf> {
+    let windir = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    let path = PathBuf::from(windir).join("System32").join("defrag.exe");
+    path.exists().then_some(path)
+}
+
+/// 从 defrag /U 输出行中解析百分比（如 "Retrim:  45% complete..."、"碎片整理: 45% 已完成..."）
+fn parse_percent(line: &str) -> Option<u8> {
+    let end = line.find('%')?;
+    let digits: String = line[..end]
+        .trim_end()
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    digits.parse::<u8>().ok().filter(|p| *p <= 100)
+}
+
+/// 执行优化，进度通过 progress_tx 发送
+pub fn optimize_volume(letter: &str, method: OptimizeMethod, progress_tx: Sender<OptimizeProgress>) {
+    let result = run_optimize(letter, method, &progress_tx);
+    let _ = progress_tx.send(OptimizeProgress::Finished(result));
+}
+
+fn run_optimize(letter: &str, method: OptimizeMethod, progress_tx: &Sender<OptimizeProgress>) -> Result<String, String> {
+    let Some(defrag) = defrag_path() else {
+        // 没有 defrag.exe 时 SSD 直接下发 TRIM，簇号只有在 NTFS 上才能换算为卷内偏移
+        let file_system = file_system_name(letter).unwrap_or_default();
+        return match method {
+            OptimizeMethod::Retrim if !supports_native_trim(&file_system) => Err(format!(
+                "❌ 当前环境缺少 defrag.exe，{} 的文件系统（{}）不支持直接 TRIM，仅支持 NTFS",
+                letter, file_system
+            )),
+            OptimizeMethod::Retrim => DiskManager::trim_free_space(letter)
+                .map(|bytes| format!("✅ {} 已 TRIM {} MB 空闲空间", letter, bytes / 1024 / 1024))
+                .map_err(|e| format!("❌ TRIM 失败: {:#}", e)),
+            OptimizeMethod::Defrag => Err("❌ 当前环境缺少 defrag.exe，无法进行碎片整理".to_string()),
+        };
+    };
+
+    log::info!("[OPTIMIZE] {} {} {} /U /V", defrag.display(), letter, method.defrag_arg());
+    let mut child = create_command(&defrag)
+        .args([letter, method.defrag_arg(), "/U", "/V"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("❌ 无法启动 defrag: {}", e))?;
+
+    // defrag /U 用回车刷新进度行，按 \r 与 \n 分行
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+        while let Ok(read) = stdout.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            for &byte in &buffer[..read] {
+                if byte != b'\r' && byte != b'\n' {
+                    pending.push(byte);
+                    continue;
+                }
+                let line = gbk_to_utf8(&pending).trim().to_string();
+                pending.clear();
+                if line.is_empty() {
+                    continue;
+                }
+                if let Some(percent) = parse_percent(&line) {
+                    let _ = progress_tx.send(OptimizeProgress::Running(percent, line.clone()));
+                } else {
+                    log::info!("[OPTIMIZE] {}", line);
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| format!("❌ 等待 defrag 失败: {}", e))?;
+    if status.success() {
+        Ok(format!("✅ {} {}完成", letter, method.label()))
+    } else {
+        Err(format!(
+            "❌ {}失败（退出码 {:?}）\n{}",
+            method.label(),
+            status.code(),
+            output.trim()
+        ))
+    }
+}
+
+impl App {
+    /// 打开驱动器优化对话框
+    pub fn init_drive_optimize_dialog(&mut self) {
+        self.show_drive_optimize_dialog = true;
+        self.drive_optimize_message.clear();
+        self.drive_optimize_selected = None;
+        self.drive_optimize_progress = None;
+        let disks = self
+            .hardware_info
+            .as_ref()
+            .map(|h| h.disks.as_slice())
+            .unwrap_or_default();
+        self.drive_optimize_volumes = list_optimizable_volumes(&self.partitions, disks);
+    }
+
+    /// 渲染驱动器优化对话框
+    pub fn render_drive_optimize_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_drive_optimize_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut start = false;
+
+        egui::Window::new("驱动器优化")
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(360.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("机械硬盘进行碎片整理，固态硬盘重新 TRIM 空闲空间。适合在还原系统后运行。");
+                ui.add_space(10.0);
+
+                if self.drive_optimize_volumes.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⚠ 未找到可优化的分区");
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("drive_optimize_volumes")
+                    .max_height(180.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("drive_optimize_grid")
+                            .num_columns(4)
+                            .spacing([10.0, 4.0])
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.label(egui::RichText::new("分区").strong());
+                                ui.label(egui::RichText::new("大小").strong());
+                                ui.label(egui::RichText::new("磁盘").strong());
+                                ui.label(egui::RichText::new("优化方式").strong());
+                                ui.end_row();
+
+                                let has_defrag = defrag_path().is_some();
+                                for (index, volume) in self.drive_optimize_volumes.iter().enumerate() {
+                                    let unsupported = volume.unsupported_reason(has_defrag);
+                                    let text = if volume.label.is_empty() {
+                                        volume.letter.clone()
+                                    } else {
+                                        format!("{} [{}]", volume.letter, volume.label)
+                                    };
+                                    if ui
+                                        .add_enabled(
+                                            !self.drive_optimize_loading && unsupported.is_none(),
+                                            egui::SelectableLabel::new(self.drive_optimize_selected == Some(index), text),
+                                        )
+                                        .clicked()
+                                    {
+                                        self.drive_optimize_selected = Some(index);
+                                    }
+                                    ui.label(format!("{:.1} GB", volume.total_size_mb as f64 / 1024.0));
+                                    ui.label(&volume.disk_model);
+                                    match unsupported {
+                                        Some(reason) => {
+                                            ui.label(egui::RichText::new("不支持").color(egui::Color32::GRAY))
+                                                .on_hover_text(reason);
+                                        }
+                                        None => {
+                                            ui.label(volume.method.label());
+                                        }
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                if let Some((percent, ref line)) = self.drive_optimize_progress {
+                    ui.add_space(10.0);
+                    ui.add(egui::ProgressBar::new(percent as f32 / 100.0).show_percentage());
+                    ui.label(egui::RichText::new(line).small().color(egui::Color32::GRAY));
+                }
+
+                if !self.drive_optimize_message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&self.drive_optimize_message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let can_start = !self.drive_optimize_loading && self.drive_optimize_selected.is_some();
+                    if ui.add_enabled(can_start, egui::Button::new("开始优化")).clicked() {
+                        start = true;
+                    }
+                    if self.drive_optimize_loading {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if start {
+            self.start_drive_optimize();
+        }
+        if should_close {
+            self.show_drive_optimize_dialog = false;
+        }
+    }
+
+    /// 后台执行优化
+    fn start_drive_optimize(&mut self) {
+        let Some(volume) = self
+            .drive_optimize_selected
+            .and_then(|i| self.drive_optimize_volumes.get(i))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.drive_optimize_loading = true;
+        self.drive_optimize_progress = Some((0, String::new()));
+        self.drive_optimize_message = format!("正在对 {} 进行{}...", volume.letter, volume.method.label());
+
+        let (tx, rx) = mpsc::channel();
+        self.drive_optimize_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            optimize_volume(&volume.letter, volume.method, tx);
+        });
+    }
+
+    /// 检查优化进度
+    pub fn check_drive_optimize_progress(&mut self) {
+        let Some(ref rx) = self.drive_optimize_rx else {
+            return;
+        };
+        while let Ok(progress) = rx.try_recv() {
+            match progress {
+                OptimizeProgress::Running(percent, line) => {
+                    self.drive_optimize_progress = Some((percent, line));
+                }
+                OptimizeProgress::Finished(result) => {
+                    self.drive_optimize_message = result.unwrap_or_else(|e| e);
+                    self.drive_optimize_loading = false;
+                    self.drive_optimize_progress = None;
+                    self.drive_optimize_rx = None;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(parse_percent("Retrim:  45% complete..."), Some(45));
+        assert_eq!(parse_percent("碎片整理: 100% 已完成..."), Some(100));
+        assert_eq!(parse_percent("Pass 2:   7 % defragmented"), Some(7));
+        assert_eq!(parse_percent("Invoking retrim on (C:)..."), None);
+    }
+
+    #[test]
+    fn test_method_for_disk() {
+        assert_eq!(OptimizeMethod::for_disk(true), OptimizeMethod::Retrim);
+        assert_eq!(OptimizeMethod::for_disk(false).defrag_arg(), "/D");
+    }
+
+    #[test]
+    fn test_unsupported_reason() {
+        let volume = |file_system: &str, method| OptimizableVolume {
+            letter: "D:".to_string(),
+            label: String::new(),
+            total_size_mb: 1024,
+            disk_model: String::new(),
+            file_system: file_system.to_string(),
+            method,
+        };
+        assert!(volume("FAT32", OptimizeMethod::Retrim).unsupported_reason(true).is_none());
+        assert!(volume("NTFS", OptimizeMethod::Retrim).unsupported_reason(false).is_none());
+        assert!(volume("FAT32", OptimizeMethod::Retrim).unsupported_reason(false).is_some());
+        assert!(volume("exFAT", OptimizeMethod::Retrim).unsupported_reason(false).is_some());
+        assert!(volume("NTFS", OptimizeMethod::Defrag).unsupported_reason(false).is_some());
+    }
+}
@@ -0,0 +1,190 @@
+//! 存储驱动包管理对话框模块
+//!
+//! 列出本地存储控制器驱动包及其与当前硬件的匹配情况，支持从服务器下载或更新驱动包
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::driver_pack;
+use crate::download::config::OnlineDriverPack;
+use crate::ui::a11y::dialog_keys;
+
+impl App {
+    /// 渲染存储驱动包管理对话框
+    pub fn render_driver_packs_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_driver_packs_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut pack_to_download: Option<OnlineDriverPack> = None;
+
+        let online_packs: Vec<OnlineDriverPack> = self
+            .config
+            .as_ref()
+            .map(|c| c.driver_packs.clone())
+            .unwrap_or_default();
+
+        egui::Window::new("存储驱动包管理")
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(440.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("安装系统时导入的磁盘控制器驱动，每个驱动包对应一类硬盘控制器。");
+                ui.label(
+                    egui::RichText::new(format!("驱动包目录: {}", driver_pack::packs_dir().display()))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                ui.add_space(10.0);
+
+                // 本地驱动包
+                ui.label(egui::RichText::new("本地驱动包").strong());
+                if self.driver_packs.is_empty() {
+                    ui.label("未找到驱动包");
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("local_driver_packs")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for pack in &self.driver_packs {
+                            ui.horizontal(|ui| {
+                                ui.strong(&pack.name);
+                                if !pack.version.is_empty() {
+                                    ui.label(&pack.version);
+                                }
+                                ui.label(
+                                    egui::RichText::new(format!("{} 个硬件ID", pack.hardware_ids.len()))
+                                        .small()
+                                        .color(egui::Color32::GRAY),
+                                );
+                                if pack.matched {
+                                    ui.colored_label(egui::Color32::GREEN, "✓ 匹配当前硬件");
+                                }
+                            });
+                        }
+                    });
+
+                ui.add_space(10.0);
+                ui.separator();
+
+                // 在线驱动包
+                ui.label(egui::RichText::new("在线驱动包").strong());
+                if online_packs.is_empty() {
+                    if self.remote_config_loading {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("正在加载在线资源...");
+                        });
+                    } else {
+                        ui.label("服务器暂未提供驱动包");
+                    }
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("online_driver_packs")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for pack in &online_packs {
+                            let local = self.driver_packs.iter().find(|p| p.id == pack.id);
+                            let needs_update = driver_pack::needs_update(local, pack);
+                            ui.horizontal(|ui| {
+                                ui.vertical(|ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.strong(&pack.name);
+                                        ui.label(&pack.version);
+                                        if !pack.file_size.is_empty() {
+                                            ui.label(format!("| {}", pack.file_size));
+                                        }
+                                    });
+                                    if !pack.description.is_empty() {
+                                        ui.small(&pack.description);
+                                    }
+                                });
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    let label = match (local, needs_update) {
+                                        (None, _) => "下载",
+                                        (Some(_), true) => "更新",
+                                        (Some(_), false) => "已是最新",
+                                    };
+                                    let enabled = needs_update && !self.driver_packs_loading;
+                                    if ui.add_enabled(enabled, egui::Button::new(label)).clicked() {
+                                        pack_to_download = Some(pack.clone());
+                                    }
+                                });
+                            });
+                        }
+                    });
+
+                if !self.driver_packs_message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.label(&self.driver_packs_message);
+                }
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.driver_packs_loading && !self.remote_config_loading,
+                            egui::Button::new("刷新"),
+                        )
+                        .clicked()
+                    {
+                        self.detect_raid_controllers();
+                        self.start_remote_config_loading();
+                    }
+                    if ui.button("打开目录").clicked() {
+                        let dir = driver_pack::packs_dir();
+                        let _ = std::fs::create_dir_all(&dir);
+                        let _ = std::process::Command::new("explorer.exe").arg(&dir).spawn();
+                    }
+                    if self.driver_packs_loading {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if let Some(pack) = pack_to_download {
+            self.start_download_driver_pack(pack);
+        }
+        if should_close {
+            self.show_driver_packs_dialog = false;
+        }
+    }
+
+    /// 后台下载并安装驱动包
+    fn start_download_driver_pack(&mut self, pack: OnlineDriverPack) {
+        self.driver_packs_loading = true;
+        self.driver_packs_message = format!("正在下载 {} {}...", pack.name, pack.version);
+
+        let (tx, rx) = mpsc::channel();
+        self.driver_packs_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = driver_pack::download_and_install(&pack)
+                .map(|_| format!("✅ {} {} 已安装", pack.name, pack.version))
+                .map_err(|e| format!("❌ {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 检查驱动包下载结果
+    pub fn check_driver_packs_result(&mut self) {
+        if let Some(ref rx) = self.driver_packs_rx {
+            if let Ok(result) = rx.try_recv() {
+                let success = result.is_ok();
+                self.driver_packs_message = result.unwrap_or_else(|e| e);
+                self.driver_packs_loading = false;
+                self.driver_packs_rx = None;
+                if success {
+                    self.detect_raid_controllers();
+                }
+            }
+        }
+    }
+}
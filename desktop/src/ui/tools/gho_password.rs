@@ -6,6 +6,8 @@ use egui;
 use std::sync::mpsc;
 
 use crate::app::App;
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
 use crate::core::gho_password::read_gho_password;
 use super::types::GhoPasswordResult;
 
@@ -23,17 +25,19 @@ impl App {
             .default_width(500.0)
             .default_height(300.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("查看Ghost镜像文件(.gho)的密码信息");
                 ui.add_space(10.0);
 
                 // 文件路径输入
                 ui.horizontal(|ui| {
-                    ui.label("GHO文件路径:");
+                    let label = ui.label("GHO文件路径:");
                     ui.add(
                         egui::TextEdit::singleline(&mut self.gho_password_file_path)
                             .hint_text("输入或选择GHO文件路径")
                             .desired_width(300.0),
-                    );
+                    )
+                    .labelled_by(label.id);
                     
                     if ui.button("浏览...").clicked() {
                         if let Some(path) = rfd::FileDialog::new()
@@ -44,6 +48,20 @@ impl App {
                             self.gho_password_file_path = path.to_string_lossy().to_string();
                         }
                     }
+
+                    let recent_gho: Vec<String> = self
+                        .app_config
+                        .recent_images
+                        .iter()
+                        .filter(|p| {
+                            let lower = p.to_lowercase();
+                            lower.ends_with(".gho") || lower.ends_with(".ghs")
+                        })
+                        .cloned()
+                        .collect();
+                    if let Some(path) = recent_menu(ui, &recent_gho) {
+                        self.gho_password_file_path = path;
+                    }
                 });
 
                 ui.add_space(15.0);
@@ -52,7 +70,9 @@ impl App {
                 ui.horizontal(|ui| {
                     let can_view = !self.gho_password_file_path.is_empty() && !self.gho_password_loading;
                     
-                    if ui.add_enabled(can_view, egui::Button::new("查看密码")).clicked() {
+                    if ui.add_enabled(can_view, egui::Button::new("查看密码")).clicked()
+                        || (can_view && keys.enter)
+                    {
                         self.start_read_gho_password();
                     }
 
@@ -98,14 +118,15 @@ impl App {
                             if let Some(ref pwd) = result.password {
                                 ui.add_space(5.0);
                                 ui.horizontal(|ui| {
-                                    ui.label("🔑 密码:");
+                                    let label = ui.label("🔑 密码:");
                                     // 使用可选择的文本框显示密码，方便复制
                                     let mut pwd_display = pwd.clone();
                                     ui.add(
                                         egui::TextEdit::singleline(&mut pwd_display)
                                             .desired_width(200.0)
                                             .interactive(true)
-                                    );
+                                    )
+                                    .labelled_by(label.id);
                                     
                                     if ui.button("复制").clicked() {
                                         ui.ctx().copy_text(pwd.clone());
@@ -131,7 +152,7 @@ impl App {
 
                 // 关闭按钮
                 ui.horizontal(|ui| {
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -152,6 +173,9 @@ impl App {
         if file_path.is_empty() {
             return;
         }
+        if std::path::Path::new(&file_path).is_file() {
+            self.app_config.add_recent_image(&file_path);
+        }
 
         self.gho_password_loading = true;
         self.gho_password_result = None;
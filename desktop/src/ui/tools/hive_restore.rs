@@ -0,0 +1,274 @@
+//! 注册表配置单元恢复对话框模块
+//!
+//! 从系统备份镜像中取出单个配置单元（SOFTWARE、SYSTEM 等）替换到目标系统，
+//! 用于修复配置单元损坏导致的无法启动，而不必整盘还原
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::dism::Dism;
+use crate::core::hive_restore::{self, Hive};
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+use super::types::WindowsPartitionInfo;
+
+impl App {
+    /// 渲染注册表配置单元恢复对话框
+    pub fn render_hive_restore_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_hive_restore_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut start_restore = false;
+        let mut load_images = false;
+        let windows_partitions = self.get_cached_windows_partitions();
+        let is_loading_partitions = self.windows_partitions_loading;
+        let is_pe = self.is_pe_environment();
+
+        egui::Window::new("注册表配置单元恢复")
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(380.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("从系统备份镜像中取出单个注册表配置单元，替换目标系统中损坏的配置单元。");
+                ui.label(
+                    egui::RichText::new("原配置单元会先改名备份；对当前运行的系统，替换在重启后生效。")
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let label = ui.label("备份镜像:");
+                    let response = ui
+                        .add(
+                            egui::TextEdit::singleline(&mut self.hive_restore_image_path)
+                                .hint_text("选择系统备份的 WIM/ESD 文件")
+                                .desired_width(340.0),
+                        )
+                        .labelled_by(label.id);
+                    if response.lost_focus() && !self.hive_restore_image_path.is_empty() {
+                        load_images = true;
+                    }
+
+                    let can_browse = !self.hive_restore_loading;
+                    if ui.add_enabled(can_browse, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WIM/ESD/SWM", &["wim", "esd", "swm"])
+                            .add_filter("所有文件", &["*"])
+                            .pick_file()
+                        {
+                            self.hive_restore_image_path = path.to_string_lossy().to_string();
+                            load_images = true;
+                        }
+                    }
+                    if can_browse {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            self.hive_restore_image_path = path;
+                            load_images = true;
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("镜像索引:");
+                    if self.hive_restore_images_rx.is_some() {
+                        ui.spinner();
+                        ui.label("正在读取镜像信息...");
+                    } else if self.hive_restore_images.is_empty() {
+                        ui.label("请先选择镜像");
+                    } else {
+                        let selected = self
+                            .hive_restore_images
+                            .iter()
+                            .find(|i| i.index == self.hive_restore_index)
+                            .map(|i| format!("{}. {}", i.index, i.name))
+                            .unwrap_or_else(|| "请选择".to_string());
+                        egui::ComboBox::from_id_salt("hive_restore_index")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for image in &self.hive_restore_images {
+                                    ui.selectable_value(
+                                        &mut self.hive_restore_index,
+                                        image.index,
+                                        format!("{}. {}", image.index, image.name),
+                                    );
+                                }
+                            });
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("配置单元:");
+                    egui::ComboBox::from_id_salt("hive_restore_hive")
+                        .selected_text(self.hive_restore_hive.label())
+                        .show_ui(ui, |ui| {
+                            for hive in Hive::ALL {
+                                ui.selectable_value(&mut self.hive_restore_hive, hive, hive.label());
+                            }
+                        });
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("目标系统:");
+                    if is_loading_partitions {
+                        ui.spinner();
+                        ui.label("正在检测Windows分区...");
+                    } else if windows_partitions.is_empty() {
+                        ui.label("未找到Windows分区");
+                    } else {
+                        let current_text = self
+                            .hive_restore_target
+                            .as_ref()
+                            .map(|letter| format_partition_display(&windows_partitions, letter))
+                            .unwrap_or_else(|| "请选择".to_string());
+                        egui::ComboBox::from_id_salt("hive_restore_target")
+                            .selected_text(current_text)
+                            .show_ui(ui, |ui| {
+                                for partition in &windows_partitions {
+                                    ui.selectable_value(
+                                        &mut self.hive_restore_target,
+                                        Some(partition.letter.clone()),
+                                        format!(
+                                            "{} [{}] [{}]",
+                                            partition.letter, partition.windows_version, partition.architecture
+                                        ),
+                                    );
+                                }
+                            });
+                    }
+                });
+
+                if let Some(ref target) = self.hive_restore_target {
+                    if hive_restore::is_running_system(target, is_pe) {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            "⚠ 目标是当前运行的系统，替换将在重启后生效",
+                        );
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.checkbox(
+                    &mut self.hive_restore_confirm,
+                    format!(
+                        "我确认用镜像中的 {} 替换目标系统的配置单元",
+                        self.hive_restore_hive.file_name()
+                    ),
+                );
+
+                if !self.hive_restore_message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&self.hive_restore_message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let can_restore = !self.hive_restore_loading
+                        && !self.hive_restore_images.is_empty()
+                        && self.hive_restore_target.is_some()
+                        && self.hive_restore_confirm;
+                    if ui.add_enabled(can_restore, egui::Button::new("恢复")).clicked() {
+                        start_restore = true;
+                    }
+                    if self.hive_restore_loading {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if load_images {
+            self.start_load_hive_restore_images();
+        }
+        if start_restore {
+            self.start_hive_restore();
+        }
+        if should_close {
+            self.show_hive_restore_dialog = false;
+        }
+    }
+
+    /// 后台读取备份镜像的索引列表
+    fn start_load_hive_restore_images(&mut self) {
+        self.hive_restore_images.clear();
+        self.hive_restore_confirm = false;
+        let image_path = self.hive_restore_image_path.clone();
+
+        let (tx, rx) = mpsc::channel();
+        self.hive_restore_images_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = Dism::new()
+                .get_image_info(&image_path)
+                .map_err(|e| format!("❌ 读取镜像信息失败: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 后台执行配置单元恢复
+    fn start_hive_restore(&mut self) {
+        let Some(target) = self.hive_restore_target.clone() else {
+            return;
+        };
+        let image_path = self.hive_restore_image_path.clone();
+        let index = self.hive_restore_index;
+        let hive = self.hive_restore_hive;
+        let is_pe = self.is_pe_environment();
+
+        self.hive_restore_loading = true;
+        self.hive_restore_confirm = false;
+        self.hive_restore_message = format!("正在从镜像中恢复 {}...", hive.file_name());
+        self.app_config.add_recent_image(&image_path);
+
+        let (tx, rx) = mpsc::channel();
+        self.hive_restore_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = hive_restore::restore_hive_from_image(&image_path, index, hive, &target, is_pe)
+                .map(|message| format!("✅ {}", message))
+                .map_err(|e| format!("❌ {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 检查镜像信息读取和恢复结果
+    pub fn check_hive_restore_result(&mut self) {
+        if let Some(ref rx) = self.hive_restore_images_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(images) => {
+                        self.hive_restore_index = images.first().map(|i| i.index).unwrap_or(1);
+                        self.hive_restore_images = images;
+                        self.hive_restore_message.clear();
+                    }
+                    Err(e) => self.hive_restore_message = e,
+                }
+                self.hive_restore_images_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = self.hive_restore_rx {
+            if let Ok(result) = rx.try_recv() {
+                self.hive_restore_message = result.unwrap_or_else(|e| e);
+                self.hive_restore_loading = false;
+                self.hive_restore_rx = None;
+            }
+        }
+    }
+}
+
+/// 格式化分区显示文本
+fn format_partition_display(partitions: &[WindowsPartitionInfo], letter: &str) -> String {
+    partitions
+        .iter()
+        .find(|p| p.letter == letter)
+        .map(|p| format!("{} [{}] [{}]", p.letter, p.windows_version, p.architecture))
+        .unwrap_or_else(|| letter.to_string())
+}
@@ -0,0 +1,408 @@
+//! 镜像格式转换对话框模块
+//!
+//! 把 ESD 转换为 WIM，或在 LZX、XPRESS 与 LZMS 固实压缩之间重新压缩，
+//! 可选择要导出的分卷和输出分区，开始前检查剩余空间
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::dism::{Dism, DismProgress, ImageInfo};
+use crate::core::image_convert::{
+    check_free_space, convert_image, default_output_path, estimate_output_bytes, ConvertBackend, ConvertFormat,
+    ConvertRequest,
+};
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+
+/// 镜像格式转换对话框状态
+#[derive(Default)]
+pub struct ImageConvertDialogState {
+    pub image_path: String,
+    pub images: Vec<ImageInfo>,
+    pub images_rx: Option<mpsc::Receiver<Result<Vec<ImageInfo>, String>>>,
+    /// 选中要导出的分卷索引
+    pub selected: Vec<u32>,
+    pub format: ConvertFormat,
+    pub backend: Option<ConvertBackend>,
+    /// 输出分区（如 "D:"）
+    pub target_partition: String,
+    pub output_path: String,
+    pub progress: Option<DismProgress>,
+    pub progress_rx: Option<mpsc::Receiver<DismProgress>>,
+    pub result_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub message: String,
+}
+
+impl ImageConvertDialogState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.images_rx.is_some() || self.result_rx.is_some()
+    }
+
+    fn is_converting(&self) -> bool {
+        self.result_rx.is_some()
+    }
+
+    /// 所选分卷的展开大小之和
+    fn selected_expanded_bytes(&self) -> u64 {
+        self.images
+            .iter()
+            .filter(|i| self.selected.contains(&i.index))
+            .map(|i| i.size_bytes)
+            .sum()
+    }
+
+    /// 按源镜像、输出分区和格式重新生成输出路径
+    fn update_output_path(&mut self) {
+        if !self.target_partition.is_empty() && !self.image_path.is_empty() {
+            self.output_path = default_output_path(&self.image_path, &self.target_partition, self.format);
+        }
+    }
+}
+
+impl App {
+    /// 打开镜像格式转换对话框
+    pub fn init_image_convert_dialog(&mut self) {
+        self.show_image_convert_dialog = true;
+        let state = &mut self.image_convert_state;
+        state.message.clear();
+        state.progress = None;
+        if state.backend.is_none() {
+            state.backend = Some(ConvertBackend::detect());
+        }
+    }
+
+    /// 渲染镜像格式转换对话框
+    pub fn render_image_convert_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_image_convert_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut load_images = false;
+        let mut start = false;
+
+        egui::Window::new("镜像格式转换")
+            .resizable(true)
+            .default_width(620.0)
+            .default_height(480.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.image_convert_state;
+                let busy = state.is_converting();
+                ui.label("把 ESD 转换为 WIM，或重新压缩为其他格式。可只导出需要的版本以减小体积。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let label = ui.label("源镜像:");
+                    let response = ui
+                        .add_enabled(
+                            !busy,
+                            egui::TextEdit::singleline(&mut state.image_path)
+                                .hint_text("选择 WIM/ESD/SWM 文件")
+                                .desired_width(360.0),
+                        )
+                        .labelled_by(label.id);
+                    if response.lost_focus() && !state.image_path.is_empty() {
+                        load_images = true;
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WIM/ESD/SWM", &["wim", "esd", "swm"])
+                            .add_filter("所有文件", &["*"])
+                            .pick_file()
+                        {
+                            state.image_path = path.to_string_lossy().to_string();
+                            load_images = true;
+                        }
+                    }
+                    if !busy {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            state.image_path = path;
+                            load_images = true;
+                        }
+                    }
+                });
+
+                ui.add_space(5.0);
+                if state.images_rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("正在读取镜像信息...");
+                    });
+                } else if !state.images.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label("导出分卷:");
+                        if ui.add_enabled(!busy, egui::Button::new("全选").small()).clicked() {
+                            state.selected = state.images.iter().map(|i| i.index).collect();
+                        }
+                        if ui.add_enabled(!busy, egui::Button::new("清除").small()).clicked() {
+                            state.selected.clear();
+                        }
+                    });
+                    let mut toggled: Option<u32> = None;
+                    egui::ScrollArea::vertical()
+                        .id_salt("image_convert_indexes")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for image in &state.images {
+                                let mut checked = state.selected.contains(&image.index);
+                                let text = format!(
+                                    "{}. {}（{:.1} GB）",
+                                    image.index,
+                                    image.name,
+                                    image.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+                                );
+                                if ui.add_enabled(!busy, egui::Checkbox::new(&mut checked, text)).changed() {
+                                    toggled = Some(image.index);
+                                }
+                            }
+                        });
+                    if let Some(index) = toggled {
+                        if let Some(pos) = state.selected.iter().position(|i| *i == index) {
+                            state.selected.remove(pos);
+                        } else {
+                            state.selected.push(index);
+                            state.selected.sort_unstable();
+                        }
+                    }
+                }
+
+                ui.add_space(5.0);
+                egui::Grid::new("image_convert_options")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("目标格式:");
+                        let old_format = state.format;
+                        ui.add_enabled_ui(!busy, |ui| {
+                            egui::ComboBox::from_id_salt("image_convert_format")
+                                .selected_text(state.format.label())
+                                .show_ui(ui, |ui| {
+                                    for format in ConvertFormat::ALL {
+                                        ui.selectable_value(&mut state.format, format, format.label());
+                                    }
+                                });
+                        });
+                        if old_format != state.format {
+                            state.update_output_path();
+                        }
+                        ui.end_row();
+
+                        ui.label("转换方式:");
+                        ui.add_enabled_ui(!busy, |ui| {
+                            let selected = state.backend.map(|b| b.label()).unwrap_or("请选择");
+                            egui::ComboBox::from_id_salt("image_convert_backend")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for backend in ConvertBackend::ALL {
+                                        ui.selectable_value(&mut state.backend, Some(backend), backend.label());
+                                    }
+                                });
+                        });
+                        ui.end_row();
+
+                        ui.label("输出分区:");
+                        let old_partition = state.target_partition.clone();
+                        ui.add_enabled_ui(!busy, |ui| {
+                            let selected = if state.target_partition.is_empty() {
+                                "请选择".to_string()
+                            } else {
+                                state.target_partition.clone()
+                            };
+                            egui::ComboBox::from_id_salt("image_convert_partition")
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for partition in &self.partitions {
+                                        let text = format!(
+                                            "{} {}（可用 {:.1} GB）",
+                                            partition.letter,
+                                            partition.label,
+                                            partition.free_size_mb as f64 / 1024.0
+                                        );
+                                        ui.selectable_value(&mut state.target_partition, partition.letter.clone(), text);
+                                    }
+                                });
+                        });
+                        if old_partition != state.target_partition {
+                            state.update_output_path();
+                        }
+                        ui.end_row();
+
+                        ui.label("输出文件:");
+                        ui.horizontal(|ui| {
+                            ui.add_enabled(
+                                !busy,
+                                egui::TextEdit::singleline(&mut state.output_path).desired_width(300.0),
+                            );
+                            if ui.add_enabled(!busy, egui::Button::new("另存为...")).clicked() {
+                                let extension = state.format.extension();
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter(extension.to_uppercase(), &[extension])
+                                    .save_file()
+                                {
+                                    state.output_path = path.to_string_lossy().to_string();
+                                }
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                // 按输出文件所在分区的剩余空间检查
+                let output_bytes = estimate_output_bytes(state.selected_expanded_bytes(), state.format);
+                let free_bytes = self
+                    .partitions
+                    .iter()
+                    .find(|p| {
+                        state
+                            .output_path
+                            .get(..2)
+                            .is_some_and(|volume| volume.eq_ignore_ascii_case(&p.letter))
+                    })
+                    .map(|p| p.free_size_mb * 1024 * 1024);
+                let space_check = check_free_space(free_bytes, output_bytes);
+                if !state.selected.is_empty() {
+                    ui.add_space(5.0);
+                    let estimate = format!("预计输出约 {:.1} GB", output_bytes as f64 / 1024.0 / 1024.0 / 1024.0);
+                    match space_check {
+                        Ok(()) => {
+                            ui.label(egui::RichText::new(estimate).small().color(egui::Color32::GRAY));
+                        }
+                        Err(ref e) => {
+                            ui.colored_label(egui::Color32::RED, format!("✗ {}", e));
+                        }
+                    }
+                }
+
+                if let Some(ref progress) = state.progress {
+                    ui.add_space(10.0);
+                    ui.add(
+                        egui::ProgressBar::new(progress.percentage as f32 / 100.0)
+                            .text(format!("{}% - {}", progress.percentage, progress.status)),
+                    );
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let can_start = !state.is_loading()
+                        && !state.selected.is_empty()
+                        && state.backend.is_some()
+                        && !state.output_path.trim().is_empty()
+                        && space_check.is_ok();
+                    if ui.add_enabled(can_start, egui::Button::new("开始转换")).clicked() {
+                        start = true;
+                    }
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if load_images {
+            self.start_load_image_convert_images();
+        }
+        if start {
+            self.start_image_convert();
+        }
+        if should_close {
+            self.show_image_convert_dialog = false;
+        }
+    }
+
+    /// 后台读取源镜像的分卷列表
+    fn start_load_image_convert_images(&mut self) {
+        let state = &mut self.image_convert_state;
+        state.images.clear();
+        state.selected.clear();
+        state.message.clear();
+        state.update_output_path();
+        let image_path = state.image_path.clone();
+
+        let (tx, rx) = mpsc::channel();
+        state.images_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = Dism::new()
+                .get_image_info(&image_path)
+                .map_err(|e| format!("❌ 读取镜像信息失败: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 后台执行转换
+    fn start_image_convert(&mut self) {
+        let state = &mut self.image_convert_state;
+        let Some(backend) = state.backend else {
+            return;
+        };
+        let request = ConvertRequest {
+            source: state.image_path.clone(),
+            indexes: state.selected.clone(),
+            destination: state.output_path.trim().to_string(),
+            format: state.format,
+            backend,
+            expanded_bytes: state.selected_expanded_bytes(),
+        };
+        self.app_config.add_recent_image(&state.image_path);
+
+        state.message = format!("正在转换为 {}...", request.format.label());
+        state.progress = Some(DismProgress {
+            percentage: 0,
+            status: "正在准备...".to_string(),
+        });
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        state.progress_rx = Some(progress_rx);
+        state.result_rx = Some(result_rx);
+
+        std::thread::spawn(move || {
+            let result = convert_image(&request, progress_tx)
+                .map(|_| format!("✅ 已转换为 {}", request.destination))
+                .map_err(|e| format!("❌ 转换失败: {:#}", e));
+            let _ = result_tx.send(result);
+        });
+    }
+
+    /// 检查镜像信息读取和转换进度
+    pub fn check_image_convert_result(&mut self) {
+        let state = &mut self.image_convert_state;
+
+        if let Some(ref rx) = state.images_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(images) => {
+                        state.selected = images.first().map(|i| vec![i.index]).unwrap_or_default();
+                        state.images = images;
+                    }
+                    Err(e) => state.message = e,
+                }
+                state.images_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.progress_rx {
+            while let Ok(progress) = rx.try_recv() {
+                state.progress = Some(progress);
+            }
+        }
+
+        if let Some(ref rx) = state.result_rx {
+            if let Ok(result) = rx.try_recv() {
+                state.message = result.unwrap_or_else(|e| e);
+                state.progress = None;
+                state.progress_rx = None;
+                state.result_rx = None;
+            }
+        }
+    }
+}
@@ -10,6 +10,9 @@ use std::sync::mpsc;
 use std::sync::atomic::Ordering;
 
 use crate::app::App;
+use crate::ui::a11y::dialog_keys;
+use crate::ui::image_library::{library_button, LibraryTarget};
+use crate::ui::recent::recent_menu;
 use crate::core::image_verify::{ImageType, ImageVerifier, VerifyProgress, VerifyStatus};
 use super::types::ImageVerifyResult;
 
@@ -27,17 +30,19 @@ impl App {
             .default_width(600.0)
             .default_height(450.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("校验镜像文件的完整性，支持 WIM、ESD、SWM、GHO、ISO 格式");
                 ui.add_space(10.0);
 
                 // 文件路径输入区域
                 ui.horizontal(|ui| {
-                    ui.label("镜像文件:");
+                    let label = ui.label("镜像文件:");
                     ui.add(
                         egui::TextEdit::singleline(&mut self.image_verify_file_path)
                             .hint_text("输入或选择镜像文件路径")
                             .desired_width(380.0),
-                    );
+                    )
+                    .labelled_by(label.id);
 
                     let can_browse = !self.image_verify_loading;
                     if ui.add_enabled(can_browse, egui::Button::new("浏览...")).clicked() {
@@ -54,6 +59,15 @@ impl App {
                             self.image_verify_result = None;
                         }
                     }
+                    if can_browse {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            self.image_verify_file_path = path;
+                            self.image_verify_result = None;
+                        }
+                        if library_button(ui) {
+                            self.image_library.open(LibraryTarget::Verify);
+                        }
+                    }
                 });
 
                 ui.add_space(15.0);
@@ -62,7 +76,9 @@ impl App {
                 ui.horizontal(|ui| {
                     let can_verify = !self.image_verify_file_path.is_empty() && !self.image_verify_loading;
 
-                    if ui.add_enabled(can_verify, egui::Button::new("开始校验")).clicked() {
+                    if ui.add_enabled(can_verify, egui::Button::new("开始校验")).clicked()
+                        || (can_verify && keys.enter)
+                    {
                         self.start_image_verify();
                     }
 
@@ -112,7 +128,7 @@ impl App {
 
                 // 关闭按钮
                 ui.horizontal(|ui| {
-                    if ui.button("关闭").clicked() {
+                    if ui.button("关闭").clicked() || keys.escape {
                         should_close = true;
                     }
                 });
@@ -234,6 +250,9 @@ impl App {
         if file_path.is_empty() {
             return;
         }
+        if std::path::Path::new(&file_path).is_file() {
+            self.app_config.add_recent_image(&file_path);
+        }
 
         // 检查文件是否存在
         if !std::path::Path::new(&file_path).exists() {
@@ -294,7 +313,7 @@ impl App {
     }
 
     /// 取消镜像校验
-    fn cancel_image_verify(&mut self) {
+    pub(crate) fn cancel_image_verify(&mut self) {
         if let Some(ref cancel_flag) = self.image_verify_cancel_flag {
             cancel_flag.store(true, Ordering::SeqCst);
             println!("[IMAGE VERIFY] 已发送取消请求");
@@ -3,6 +3,7 @@
 //! 提供各种系统维护和修复工具
 
 pub mod types;
+pub mod catalog;
 pub mod version_detect;
 pub mod network;
 pub mod driver;
@@ -18,6 +19,17 @@ pub mod nvidia_uninstall;
 pub mod partition_copy;
 pub mod quick_partition;
 pub mod image_verify;
+pub mod sed_unlock;
+pub mod driver_packs;
+pub mod bcd_restore;
+pub mod boot_editor;
+pub mod hive_restore;
+pub mod wim_browser;
+pub mod image_convert;
+pub mod wim_manage;
+pub mod recovery_env;
+pub mod netcast;
+pub mod drive_optimize;
 
 // 重新导出常用类型
 pub use types::{DriverBackupMode, AppxPackageInfo, InstalledSoftware, WindowsPartitionInfo, ImageVerifyResult};
@@ -25,10 +37,18 @@ pub use batch_format::FormatablePartition;
 pub use bitlocker::BitLockerPartition;
 pub use partition_copy::{CopyablePartition, CopyProgress};
 pub use quick_partition::QuickPartitionDialogState;
+pub use wim_browser::WimBrowserDialogState;
+pub use image_convert::ImageConvertDialogState;
+pub use wim_manage::WimManageDialogState;
+pub use recovery_env::RecoveryEnvDialogState;
+pub use netcast::NetcastDialogState;
+pub use boot_editor::BootEditorDialogState;
 
 use egui;
 
-use crate::app::App;
+use crate::app::{App, TechnicianAction};
+use crate::ui::a11y::icon_button_label;
+use catalog::{ToolCategory, ToolId};
 
 impl App {
     /// 显示工具箱页面
@@ -42,204 +62,67 @@ impl App {
             .map(|s| s.is_pe_environment)
             .unwrap_or(false);
 
-        ui.label("常用工具");
+        // 搜索框
+        ui.horizontal(|ui| {
+            let label = ui.label("🔍 搜索:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.tools_search)
+                    .hint_text("输入工具名称或关键字")
+                    .desired_width(250.0),
+            )
+            .labelled_by(label.id);
+            if !self.tools_search.is_empty() && ui.small_button("清除").clicked() {
+                self.tools_search.clear();
+            }
+        });
         ui.add_space(10.0);
 
-        egui::Grid::new("tools_grid")
-            .num_columns(4)
-            .spacing([15.0, 12.0])
-            .show(ui, |ui| {
-                let button_size = egui::vec2(130.0, 50.0);
-
-                // ========== 第一行 ==========
-                if ui
-                    .add(egui::Button::new("英伟达显卡驱动卸载").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_nvidia_uninstall_dialog = true;
-                    self.nvidia_uninstall_message.clear();
-                    self.nvidia_uninstall_hardware_summary = None;
-                    self.start_load_nvidia_hardware_summary();
-                }
-
-                if ui
-                    .add(egui::Button::new("分区对拷").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_partition_copy_dialog = true;
-                    self.partition_copy_message.clear();
-                    self.partition_copy_log.clear();
-                    self.partition_copy_source = None;
-                    self.partition_copy_target = None;
-                    self.start_load_copyable_partitions();
-                }
-
-                if ui
-                    .add(egui::Button::new("批量格式化").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_batch_format_dialog = true;
-                    self.batch_format_message.clear();
-                    self.batch_format_partitions.clear();
-                    self.batch_format_selected.clear();
-                    self.start_load_formatable_partitions();
-                }
+        let query = self.tools_search.clone();
+        let mut clicked_tool: Option<ToolId> = None;
+        let mut toggled_favorite: Option<ToolId> = None;
 
-                if ui
-                    .add(egui::Button::new("导入存储驱动").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_import_storage_driver_dialog = true;
-                    self.import_storage_driver_message.clear();
-                }
-
-                ui.end_row();
-
-                // ========== 第二行 ==========
-                if ui
-                    .add(egui::Button::new("一键分区").min_size(button_size))
-                    .clicked()
-                {
-                    self.init_quick_partition_dialog();
-                }
-
-                if ui
-                    .add(egui::Button::new("移除APPX应用").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_remove_appx_dialog = true;
-                    self.remove_appx_message.clear();
-                    self.remove_appx_list.clear();
-                    self.remove_appx_selected.clear();
-                }
-
-                if ui
-                    .add(egui::Button::new("驱动备份还原").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_driver_backup_dialog = true;
-                    self.driver_backup_message.clear();
-                }
-
-                if is_pe {
-                    if ui
-                        .add(egui::Button::new("一键修复引导").min_size(button_size))
-                        .clicked()
-                    {
-                        // 打开一键修复引导对话框，让用户选择分区
-                        self.show_repair_boot_dialog = true;
-                        self.repair_boot_message.clear();
-                        self.repair_boot_selected_partition = None;
-                        // 确保Windows分区信息已加载
-                        if self.windows_partitions_cache.is_none() && !self.windows_partitions_loading {
-                            self.start_load_windows_partitions();
-                        }
-                    }
-                } else {
-                    ui.add_enabled(
-                        false,
-                        egui::Button::new("一键修复引导").min_size(button_size),
-                    );
-                }
-
-                ui.end_row();
-
-                // ========== 第三行 ==========
-
-                if ui
-                    .add(egui::Button::new("本机网络信息").min_size(button_size))
-                    .clicked()
-                {
-                    self.init_network_info_dialog();
-                }
-
-                if !is_pe {
-                    if ui
-                        .add(egui::Button::new("软件列表").min_size(button_size))
-                        .clicked()
-                    {
-                        self.init_software_list_dialog();
-                    }
-                } else {
-                    ui.add_enabled(
-                        false,
-                        egui::Button::new("软件列表").min_size(button_size),
-                    );
-                }
-
-                if ui
-                    .add(egui::Button::new("系统时间校准").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_time_sync_dialog = true;
-                    self.time_sync_message.clear();
-                }
-
-                if ui
-                    .add(egui::Button::new("手动运行Ghost").min_size(button_size))
-                    .clicked()
-                {
-                    self.launch_ghost_tool();
-                }
-
-                ui.end_row();
-
-                // ========== 第四行 ==========
-
-                if ui
-                    .add(egui::Button::new("万能驱动").min_size(button_size))
-                    .clicked()
-                {
-                    self.launch_wandrv_tool();
-                }
-
-                if ui
-                    .add(egui::Button::new("查看GHO密码").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_gho_password_dialog = true;
-                    self.gho_password_file_path.clear();
-                    self.gho_password_result = None;
+        egui::ScrollArea::vertical()
+            .id_salt("tools_scroll")
+            .show(ui, |ui| {
+                // 收藏（搜索时不单独显示，避免重复）
+                let favorites: Vec<ToolId> = self
+                    .app_config
+                    .favorite_tools
+                    .iter()
+                    .filter_map(|key| ToolId::from_key(key))
+                    .collect();
+                if query.trim().is_empty() && !favorites.is_empty() {
+                    self.show_tool_group(ui, "★ 收藏", &favorites, is_pe, &mut clicked_tool, &mut toggled_favorite);
                 }
 
-                if !is_pe {
-                    if ui
-                        .add(egui::Button::new("重置网络设置").min_size(button_size))
-                        .clicked()
-                    {
-                        self.show_reset_network_confirm_dialog = true;
+                let mut any_match = false;
+                for category in ToolCategory::ALL {
+                    let tools: Vec<ToolId> = ToolId::ALL
+                        .iter()
+                        .copied()
+                        .filter(|t| t.category() == category && t.matches(&query))
+                        .collect();
+                    if tools.is_empty() {
+                        continue;
                     }
-                } else {
-                    ui.add_enabled(
-                        false,
-                        egui::Button::new("重置网络设置").min_size(button_size),
-                    );
+                    any_match = true;
+                    self.show_tool_group(ui, category.label(), &tools, is_pe, &mut clicked_tool, &mut toggled_favorite);
                 }
 
-                if ui
-                    .add(egui::Button::new("SpaceSniffer").min_size(button_size))
-                    .clicked()
-                {
-                    self.launch_space_sniffer_tool();
+                if !any_match {
+                    ui.label("没有找到匹配的工具");
                 }
-
-                ui.end_row();
-
-                // ========== 第五行 ==========
-
-                if ui
-                    .add(egui::Button::new("镜像校验").min_size(button_size))
-                    .clicked()
-                {
-                    self.show_image_verify_dialog = true;
-                    self.image_verify_file_path.clear();
-                    self.image_verify_result = None;
-                    self.image_verify_progress = None;
-                }
-
-                ui.end_row();
             });
 
+        if let Some(tool) = toggled_favorite {
+            self.app_config.toggle_favorite_tool(tool.key());
+        }
+        if let Some(tool) = clicked_tool {
+            if !(tool.requires_technician() && self.require_technician(TechnicianAction::OpenTool(tool))) {
+                self.open_tool(tool);
+            }
+        }
+
         // ========== 对话框渲染 ==========
         self.render_network_info_dialog(ui);
         self.render_import_storage_driver_dialog(ui);
@@ -254,6 +137,17 @@ impl App {
         self.render_partition_copy_dialog(ui);
         self.render_quick_partition_dialog(ui);
         self.render_image_verify_dialog(ui);
+        self.render_wim_browser_dialog(ui);
+        self.render_image_convert_dialog(ui);
+        self.render_wim_manage_dialog(ui);
+        self.render_sed_unlock_dialog(ui);
+        self.render_driver_packs_dialog(ui);
+        self.render_bcd_restore_dialog(ui);
+        self.render_boot_editor_dialog(ui);
+        self.render_hive_restore_dialog(ui);
+        self.render_recovery_env_dialog(ui);
+        self.render_netcast_dialog(ui);
+        self.render_drive_optimize_dialog(ui);
         self.render_repair_boot_dialog(ui);
 
         // 显示工具状态
@@ -264,6 +158,197 @@ impl App {
         }
     }
 
+    /// 显示一组工具按钮（每个按钮旁带收藏切换）
+    fn show_tool_group(
+        &self,
+        ui: &mut egui::Ui,
+        title: &str,
+        tools: &[ToolId],
+        is_pe: bool,
+        clicked_tool: &mut Option<ToolId>,
+        toggled_favorite: &mut Option<ToolId>,
+    ) {
+        ui.label(egui::RichText::new(title).strong());
+        ui.add_space(5.0);
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(6.0, 12.0);
+            let button_size = egui::vec2(130.0, 50.0);
+
+            let locked = self.is_technician_locked();
+            let demo = crate::core::demo::is_enabled();
+            for tool in tools {
+                // 演示模式下只有只读工具可用
+                let enabled = tool.is_available(is_pe) && !(demo && !tool.is_read_only());
+                let name = if locked && tool.requires_technician() {
+                    format!("🔒 {}", tool.name())
+                } else {
+                    tool.name().to_string()
+                };
+                if ui
+                    .add_enabled(enabled, egui::Button::new(name).min_size(button_size))
+                    .clicked()
+                {
+                    *clicked_tool = Some(*tool);
+                }
+
+                let is_favorite = self.app_config.is_favorite_tool(tool.key());
+                let (icon, label) = if is_favorite {
+                    ("★", "取消收藏")
+                } else {
+                    ("☆", "收藏")
+                };
+                if icon_button_label(ui.small_button(icon), label).clicked() {
+                    *toggled_favorite = Some(*tool);
+                }
+                ui.add_space(9.0);
+            }
+        });
+        ui.add_space(10.0);
+    }
+
+    /// 打开工具（对话框或外部程序）
+    pub fn open_tool(&mut self, tool: ToolId) {
+        if crate::core::demo::is_enabled() && !tool.is_read_only() {
+            self.tool_message = format!("演示模式下不能使用「{}」", tool.name());
+            return;
+        }
+        match tool {
+            ToolId::NvidiaUninstall => {
+                self.show_nvidia_uninstall_dialog = true;
+                self.nvidia_uninstall_message.clear();
+                self.nvidia_uninstall_hardware_summary = None;
+                self.start_load_nvidia_hardware_summary();
+            }
+            ToolId::PartitionCopy => {
+                self.show_partition_copy_dialog = true;
+                self.partition_copy_message.clear();
+                self.partition_copy_log.clear();
+                self.partition_copy_source = None;
+                self.partition_copy_target = None;
+                self.start_load_copyable_partitions();
+            }
+            ToolId::BatchFormat => {
+                self.show_batch_format_dialog = true;
+                self.batch_format_message.clear();
+                self.batch_format_partitions.clear();
+                self.batch_format_selected.clear();
+                self.start_load_formatable_partitions();
+            }
+            ToolId::ImportStorageDriver => {
+                self.show_import_storage_driver_dialog = true;
+                self.import_storage_driver_message.clear();
+            }
+            ToolId::QuickPartition => {
+                self.init_quick_partition_dialog();
+            }
+            ToolId::RemoveAppx => {
+                self.show_remove_appx_dialog = true;
+                self.remove_appx_message.clear();
+                self.remove_appx_list.clear();
+                self.remove_appx_selected.clear();
+            }
+            ToolId::DriverBackup => {
+                self.show_driver_backup_dialog = true;
+                self.driver_backup_message.clear();
+            }
+            ToolId::RepairBoot => {
+                // 打开一键修复引导对话框，让用户选择分区
+                self.show_repair_boot_dialog = true;
+                self.repair_boot_message.clear();
+                self.repair_boot_selected_partition = None;
+                // 确保Windows分区信息已加载
+                if self.windows_partitions_cache.is_none() && !self.windows_partitions_loading {
+                    self.start_load_windows_partitions();
+                }
+            }
+            ToolId::RestoreBcd => {
+                self.show_bcd_restore_dialog = true;
+                self.bcd_restore_message.clear();
+                self.bcd_restore_confirm = false;
+                self.refresh_bcd_backups();
+            }
+            ToolId::HiveRestore => {
+                self.show_hive_restore_dialog = true;
+                self.hive_restore_message.clear();
+                self.hive_restore_confirm = false;
+                self.hive_restore_target = None;
+                if self.windows_partitions_cache.is_none() && !self.windows_partitions_loading {
+                    self.start_load_windows_partitions();
+                }
+            }
+            ToolId::NetworkInfo => {
+                self.init_network_info_dialog();
+            }
+            ToolId::SoftwareList => {
+                self.init_software_list_dialog();
+            }
+            ToolId::TimeSync => {
+                self.show_time_sync_dialog = true;
+                self.time_sync_message.clear();
+            }
+            ToolId::Ghost => {
+                self.launch_ghost_tool();
+            }
+            ToolId::WanDrv => {
+                self.launch_wandrv_tool();
+            }
+            ToolId::GhoPassword => {
+                self.show_gho_password_dialog = true;
+                self.gho_password_file_path.clear();
+                self.gho_password_result = None;
+            }
+            ToolId::ResetNetwork => {
+                self.show_reset_network_confirm_dialog = true;
+            }
+            ToolId::SpaceSniffer => {
+                self.launch_space_sniffer_tool();
+            }
+            ToolId::DriveOptimize => {
+                self.init_drive_optimize_dialog();
+            }
+            ToolId::ImageVerify => {
+                self.show_image_verify_dialog = true;
+                self.image_verify_file_path.clear();
+                self.image_verify_result = None;
+                self.image_verify_progress = None;
+            }
+            ToolId::WimBrowser => {
+                self.show_wim_browser_dialog = true;
+                self.wim_browser_state.message.clear();
+            }
+            ToolId::ImageConvert => {
+                self.init_image_convert_dialog();
+            }
+            ToolId::WimManage => {
+                self.show_wim_manage_dialog = true;
+                self.wim_manage_state.message.clear();
+            }
+            ToolId::RecoveryEnv => {
+                self.init_recovery_env_dialog();
+            }
+            ToolId::Netcast => {
+                self.init_netcast_dialog();
+            }
+            ToolId::BootEditor => {
+                self.init_boot_editor_dialog();
+            }
+            ToolId::DriverPacks => {
+                self.show_driver_packs_dialog = true;
+                self.driver_packs_message.clear();
+                self.detect_raid_controllers();
+            }
+            ToolId::SedUnlock => {
+                self.show_sed_unlock_dialog = true;
+                self.sed_unlock_message.clear();
+                self.sed_unlock_password.clear();
+                self.sed_unlock_psid.clear();
+                self.sed_unlock_psid_confirm = false;
+                self.start_scan_sed_drives();
+            }
+        }
+    }
+
     /// 启动Ghost工具
     fn launch_ghost_tool(&mut self) {
         match actions::launch_ghost() {
@@ -279,8 +364,8 @@ impl App {
     /// 启动万能驱动工具
     fn launch_wandrv_tool(&mut self) {
         match actions::launch_wandrv() {
-            Ok(_) => {
-                self.tool_message = "已启动: QDZC.exe".to_string();
+            Ok(message) => {
+                self.tool_message = message;
             }
             Err(e) => {
                 self.tool_message = e;
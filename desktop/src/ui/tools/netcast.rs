@@ -0,0 +1,299 @@
+//! 局域网分发对话框模块
+//!
+//! 本机作为发送端共享镜像，机房中的其他电脑在系统安装页把镜像路径填为 `netcast://` 地址，
+//! 重启进入 PE 后从本机接收镜像并安装；显示各客户端的接收进度。
+//! 地址中带有本次分发的密钥，防火墙规则只在分发期间存在
+
+use egui;
+use std::path::Path;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::netcast::{self, FirewallRule, Server, DEFAULT_PORT};
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+
+/// 局域网分发对话框状态
+#[derive(Default)]
+pub struct NetcastDialogState {
+    pub image_path: String,
+    pub port: u16,
+    /// 正在分发的发送端
+    pub server: Option<Server>,
+    /// 分发期间的防火墙规则，停止分发时删除
+    pub firewall: Option<FirewallRule>,
+    /// 本机 IPv4 地址
+    pub addresses: Vec<String>,
+    /// 分块哈希计算进度
+    pub hash_progress: Option<u8>,
+    pub progress_rx: Option<mpsc::Receiver<u8>>,
+    pub start_rx: Option<mpsc::Receiver<Result<(Server, Option<FirewallRule>, Vec<String>), String>>>,
+    pub message: String,
+}
+
+impl NetcastDialogState {
+    /// 是否有后台操作进行中（分发期间需要持续刷新客户端进度）
+    pub fn is_loading(&self) -> bool {
+        self.start_rx.is_some() || self.server.is_some()
+    }
+
+    /// 客户端使用的镜像源地址（最后一项不带发送端地址，PE 中自动发现）
+    fn source_urls(&self) -> Vec<String> {
+        let Some(ref server) = self.server else {
+            return Vec::new();
+        };
+        let name = &server.manifest().name;
+        self.addresses
+            .iter()
+            .map(|ip| format!("netcast://{}:{}/{}?key={}", ip, server.port(), name, server.key()))
+            .chain(std::iter::once(format!("netcast:///{}?key={}", name, server.key())))
+            .collect()
+    }
+
+    /// 停止分发并删除防火墙规则
+    fn stop(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.stop();
+            self.message = "已停止分发".to_string();
+        }
+        self.firewall = None;
+    }
+}
+
+/// 本机可供其他电脑连接的 IPv4 地址
+fn local_ipv4_addresses() -> Vec<String> {
+    let mut addresses: Vec<String> = crate::ui::tools::network::get_detailed_network_info()
+        .into_iter()
+        .flat_map(|adapter| adapter.ip_addresses)
+        .filter(|ip| {
+            ip.parse::<std::net::Ipv4Addr>()
+                .is_ok_and(|ip| !ip.is_loopback() && !ip.is_link_local() && !ip.is_unspecified())
+        })
+        .collect();
+    addresses.dedup();
+    addresses
+}
+
+impl App {
+    /// 打开局域网分发对话框
+    pub fn init_netcast_dialog(&mut self) {
+        self.show_netcast_dialog = true;
+        let state = &mut self.netcast_state;
+        if state.port == 0 {
+            state.port = DEFAULT_PORT;
+        }
+        if state.server.is_none() {
+            state.message.clear();
+        }
+    }
+
+    /// 渲染局域网分发对话框
+    pub fn render_netcast_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_netcast_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut start = false;
+        let mut stop = false;
+
+        egui::Window::new("局域网分发")
+            .resizable(true)
+            .default_width(600.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.netcast_state;
+                let starting = state.start_rx.is_some();
+                let running = state.server.is_some();
+                ui.label("机房批量装机时由本机共享镜像，其他电脑重启进入 PE 后从本机接收镜像再安装，不必逐台复制。");
+                ui.label("镜像按块校验，传输出错的块自动重新发送；客户端中断后重新安装会从已接收的位置继续。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let label = ui.label("镜像文件:");
+                    ui.add_enabled(
+                        !starting && !running,
+                        egui::TextEdit::singleline(&mut state.image_path)
+                            .hint_text("选择 WIM/ESD/GHO 文件")
+                            .desired_width(360.0),
+                    )
+                    .labelled_by(label.id);
+                    if ui.add_enabled(!starting && !running, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("系统镜像", &["wim", "esd", "swm", "gho", "ghs"])
+                            .add_filter("所有文件", &["*"])
+                            .pick_file()
+                        {
+                            state.image_path = path.to_string_lossy().to_string();
+                        }
+                    }
+                    if !starting && !running {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            state.image_path = path;
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("端口:");
+                    ui.add_enabled(
+                        !starting && !running,
+                        egui::DragValue::new(&mut state.port).range(1024..=65535),
+                    );
+                    ui.weak(format!("客户端通过 UDP {} 端口自动发现本机", netcast::DISCOVERY_PORT));
+                });
+
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    if running {
+                        if ui.button("停止分发").clicked() {
+                            stop = true;
+                        }
+                    } else {
+                        let can_start = !starting && Path::new(&state.image_path).is_file();
+                        if ui.add_enabled(can_start, egui::Button::new("开始分发")).clicked() {
+                            start = true;
+                        }
+                    }
+                });
+
+                if starting {
+                    ui.add_space(5.0);
+                    let percent = state.hash_progress.unwrap_or(0);
+                    ui.add(
+                        egui::ProgressBar::new(percent as f32 / 100.0)
+                            .text(format!("{}% - 正在计算分块校验值...", percent)),
+                    );
+                }
+
+                if running {
+                    ui.add_space(10.0);
+                    ui.label("在其他电脑的系统安装页把镜像路径填为以下地址，并选择通过 PE 安装：");
+                    let urls = state.source_urls();
+                    for url in &urls {
+                        ui.horizontal(|ui| {
+                            ui.monospace(url);
+                            if ui.small_button("复制").clicked() {
+                                ui.ctx().copy_text(url.clone());
+                            }
+                        });
+                    }
+                    ui.weak("不带发送端地址时 PE 在同一网段中自动查找发送端；地址中的密钥用于认证，只在本次分发中有效");
+
+                    ui.add_space(10.0);
+                    let clients = state.server.as_ref().map(Server::clients).unwrap_or_default();
+                    let done = clients.iter().filter(|client| client.done).count();
+                    ui.label(format!("客户端: {} 台，已完成 {} 台", clients.len(), done));
+                    egui::ScrollArea::vertical()
+                        .id_salt("netcast_clients")
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("netcast_client_grid")
+                                .num_columns(3)
+                                .striped(true)
+                                .spacing([20.0, 4.0])
+                                .show(ui, |ui| {
+                                    for client in &clients {
+                                        ui.label(&client.peer);
+                                        ui.label(format!("已发送 {}/{} 块", client.sent, client.total));
+                                        if client.done {
+                                            ui.colored_label(egui::Color32::from_rgb(0, 180, 0), "✅ 完成");
+                                        } else {
+                                            ui.label("接收中");
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if starting {
+                        ui.spinner();
+                    }
+                    let close_text = if running { "停止并关闭" } else { "关闭" };
+                    if ui.add_enabled(!starting, egui::Button::new(close_text)).clicked()
+                        || (keys.escape && !starting && !running)
+                    {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if start {
+            self.start_netcast();
+        }
+        if stop || should_close {
+            self.netcast_state.stop();
+        }
+        if should_close {
+            self.show_netcast_dialog = false;
+        }
+    }
+
+    /// 后台计算分块校验值并开始监听
+    fn start_netcast(&mut self) {
+        let state = &mut self.netcast_state;
+        let image_path = state.image_path.clone();
+        let port = state.port;
+        self.app_config.add_recent_image(&image_path);
+        state.message.clear();
+        state.hash_progress = None;
+
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        state.progress_rx = Some(progress_rx);
+        state.start_rx = Some(result_rx);
+        std::thread::spawn(move || {
+            let firewall = netcast::allow_firewall()
+                .map_err(|e| println!("[NETCAST] 添加防火墙规则失败: {:#}", e))
+                .ok();
+            // 启动失败时防火墙规则随 firewall 一起删除
+            let result = Server::start(Path::new(&image_path), port, |percent| {
+                let _ = progress_tx.send(percent);
+            })
+            .map(|server| (server, firewall, local_ipv4_addresses()))
+            .map_err(|e| format!("❌ 开始分发失败: {:#}", e));
+            let _ = result_tx.send(result);
+        });
+    }
+
+    /// 检查分发启动进度
+    pub fn check_netcast_result(&mut self) {
+        let state = &mut self.netcast_state;
+
+        if let Some(ref rx) = state.progress_rx {
+            while let Ok(percent) = rx.try_recv() {
+                state.hash_progress = Some(percent);
+            }
+        }
+
+        if let Some(ref rx) = state.start_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok((server, firewall, addresses)) => {
+                        state.message = if addresses.is_empty() {
+                            "✅ 已开始分发，未获取到本机 IP 地址，请在客户端使用不带地址的 netcast:// 自动发现".to_string()
+                        } else {
+                            "✅ 已开始分发".to_string()
+                        };
+                        state.server = Some(server);
+                        state.firewall = firewall;
+                        state.addresses = addresses;
+                    }
+                    Err(e) => state.message = e,
+                }
+                state.hash_progress = None;
+                state.progress_rx = None;
+                state.start_rx = None;
+            }
+        }
+    }
+}
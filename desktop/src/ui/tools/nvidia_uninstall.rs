@@ -6,6 +6,7 @@ use egui;
 use std::sync::mpsc;
 
 use crate::app::App;
+use crate::ui::a11y::dialog_keys;
 use crate::core::nvidia_driver::{
     beautify_gpu_name, get_system_hardware_summary,
     uninstall_nvidia_drivers_offline, uninstall_nvidia_drivers_online,
@@ -30,6 +31,7 @@ impl App {
             .default_width(600.0)
             .default_height(500.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 ui.label("此工具用于卸载系统中的英伟达(NVIDIA)显卡驱动");
                 ui.add_space(10.0);
 
@@ -241,7 +243,7 @@ impl App {
                             self.refresh_windows_partitions_cache();
                         }
 
-                        if ui.button("关闭").clicked() {
+                        if ui.button("关闭").clicked() || keys.escape {
                             should_close = true;
                         }
                     }
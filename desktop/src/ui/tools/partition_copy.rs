@@ -5,10 +5,12 @@
 
 use std::collections::HashSet;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::Sender;
 
+use crate::core::io_priority::{self, IoOptions, IoPriority, Throttle};
+
 #[cfg(windows)]
 use windows::{
     core::PCWSTR,
@@ -28,6 +30,9 @@ const DRIVE_REMOVABLE: u32 = 2;
 const DRIVE_FIXED: u32 = 3;
 const DRIVE_CDROM: u32 = 5;
 
+/// 限速或后台优先级复制时的缓冲区大小
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
 /// 标记文件名
 const COPY_MARKER_FILENAME: &str = ".letrecovery_partition_copy_marker";
 
@@ -79,7 +84,36 @@ pub struct CopyProgress {
     /// 失败的文件数量
     pub failed_count: usize,
     /// 失败的文件列表
-    pub failed_files: Vec<String>,
+    pub failed_files: Vec<FailedFile>,
+}
+
+/// 复制失败的文件
+#[derive(Debug, Clone)]
+pub struct FailedFile {
+    /// 相对于源分区根目录的路径
+    pub relative_path: String,
+    /// Win32 错误码（若可获取）
+    pub error_code: Option<i32>,
+    /// 错误描述
+    pub message: String,
+}
+
+impl FailedFile {
+    fn from_io_error(relative_path: &str, err: &std::io::Error) -> Self {
+        Self {
+            relative_path: relative_path.to_string(),
+            error_code: err.raw_os_error(),
+            message: err.to_string(),
+        }
+    }
+
+    /// 错误码显示文本
+    pub fn code_text(&self) -> String {
+        match self.error_code {
+            Some(code) => code.to_string(),
+            None => "-".to_string(),
+        }
+    }
 }
 
 impl Default for CopyProgress {
@@ -405,9 +439,45 @@ fn get_relative_path(full_path: &str, root_path: &str) -> String {
         .unwrap_or_else(|_| full_path.to_string())
 }
 
+/// 复制文件内容
+///
+/// 正常优先级且不限速时直接使用系统复制；否则按块复制，为句柄设置 I/O 优先级提示并限速。
+fn copy_file_contents(
+    source: &str,
+    target: &str,
+    priority: IoPriority,
+    throttle: &mut Throttle,
+) -> std::io::Result<()> {
+    if priority == IoPriority::Normal && !throttle.is_enabled() {
+        fs::copy(source, target)?;
+        return Ok(());
+    }
+
+    let mut reader = File::open(source)?;
+    let mut writer = File::create(target)?;
+    io_priority::apply_to_file(&reader, priority);
+    io_priority::apply_to_file(&writer, priority);
+
+    let mut buffer = vec![0u8; COPY_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        throttle.consume(read as u64);
+    }
+    Ok(())
+}
+
 /// 复制单个文件（使用 WinAPI 保持文件属性和时间戳）
 #[cfg(windows)]
-fn copy_file_with_attributes(source: &str, target: &str) -> std::io::Result<()> {
+fn copy_file_with_attributes(
+    source: &str,
+    target: &str,
+    priority: IoPriority,
+    throttle: &mut Throttle,
+) -> std::io::Result<()> {
     // 确保目标目录存在
     if let Some(parent) = Path::new(target).parent() {
         fs::create_dir_all(parent)?;
@@ -417,8 +487,8 @@ fn copy_file_with_attributes(source: &str, target: &str) -> std::io::Result<()>
     let wide_source: Vec<u16> = source.encode_utf16().chain(std::iter::once(0)).collect();
     let source_attrs = unsafe { GetFileAttributesW(PCWSTR(wide_source.as_ptr())) };
 
-    // 使用标准库复制文件内容
-    fs::copy(source, target)?;
+    // 复制文件内容
+    copy_file_contents(source, target, priority, throttle)?;
 
     // 复制文件属性
     if source_attrs != INVALID_FILE_ATTRIBUTES {
@@ -438,12 +508,16 @@ fn copy_file_with_attributes(source: &str, target: &str) -> std::io::Result<()>
 }
 
 #[cfg(not(windows))]
-fn copy_file_with_attributes(source: &str, target: &str) -> std::io::Result<()> {
+fn copy_file_with_attributes(
+    source: &str,
+    target: &str,
+    priority: IoPriority,
+    throttle: &mut Throttle,
+) -> std::io::Result<()> {
     if let Some(parent) = Path::new(target).parent() {
         fs::create_dir_all(parent)?;
     }
-    fs::copy(source, target)?;
-    Ok(())
+    copy_file_contents(source, target, priority, throttle)
 }
 
 /// 复制文件时间戳
@@ -534,9 +608,11 @@ pub fn execute_partition_copy(
     target_partition: &str,
     progress_tx: Sender<CopyProgress>,
     is_resume: bool,
+    io: IoOptions,
 ) {
-    let source_root = format!("{}\\", source_partition);
-    let target_root = format!("{}\\", target_partition);
+    // 分区可以是盘符、挂载文件夹或卷 GUID 路径，统一按根目录访问
+    let source_root = crate::core::disk::volume_root(source_partition);
+    let target_root = crate::core::disk::volume_root(target_partition);
 
     // 发送初始进度
     let mut progress = CopyProgress::default();
@@ -573,6 +649,7 @@ pub fn execute_partition_copy(
     }
 
     // 开始复制
+    let mut throttle = Throttle::new(io.throttle_mb_per_sec);
     let mut actual_copied = 0usize;
     for source_file in all_files.iter() {
         let relative_path = get_relative_path(source_file, &source_root);
@@ -591,7 +668,7 @@ pub fn execute_partition_copy(
         let target_file = format!("{}{}", target_root, relative_path);
 
         // 复制文件
-        match copy_file_with_attributes(source_file, &target_file) {
+        match copy_file_with_attributes(source_file, &target_file, io.priority, &mut throttle) {
             Ok(_) => {
                 // 记录到标记文件
                 marker.copied_files.insert(relative_path.clone());
@@ -603,7 +680,7 @@ pub fn execute_partition_copy(
             }
             Err(e) => {
                 progress.failed_count += 1;
-                progress.failed_files.push(format!("{}: {}", relative_path, e));
+                progress.failed_files.push(FailedFile::from_io_error(&relative_path, &e));
                 // 继续复制其他文件，不中断
             }
         }
@@ -620,6 +697,63 @@ pub fn execute_partition_copy(
     let _ = progress_tx.send(progress);
 }
 
+/// 重新复制上次失败的文件
+pub fn retry_failed_files(
+    source_partition: &str,
+    target_partition: &str,
+    failed_files: Vec<FailedFile>,
+    progress_tx: Sender<CopyProgress>,
+    io: IoOptions,
+) {
+    let source_root = crate::core::disk::volume_root(source_partition);
+    let target_root = crate::core::disk::volume_root(target_partition);
+
+    let mut progress = CopyProgress::default();
+    progress.total_count = failed_files.len();
+    let mut throttle = Throttle::new(io.throttle_mb_per_sec);
+
+    for failed in &failed_files {
+        progress.current_file = failed.relative_path.clone();
+        let _ = progress_tx.send(progress.clone());
+
+        let source_file = format!("{}{}", source_root, failed.relative_path);
+        let target_file = format!("{}{}", target_root, failed.relative_path);
+
+        match copy_file_with_attributes(&source_file, &target_file, io.priority, &mut throttle) {
+            Ok(_) => progress.copied_count += 1,
+            Err(e) => {
+                progress.failed_count += 1;
+                progress
+                    .failed_files
+                    .push(FailedFile::from_io_error(&failed.relative_path, &e));
+            }
+        }
+    }
+
+    progress.completed = true;
+    progress.current_file = "重试完成".to_string();
+    let _ = progress_tx.send(progress);
+}
+
+/// 生成失败文件列表报告
+pub fn format_failed_report(source_partition: &str, target_partition: &str, failed_files: &[FailedFile]) -> String {
+    let mut report = format!(
+        "分区对拷失败文件列表\n源分区: {}\n目标分区: {}\n失败数量: {}\n\n",
+        source_partition,
+        target_partition,
+        failed_files.len()
+    );
+    for failed in failed_files {
+        report.push_str(&format!(
+            "[{}] {}\t{}\n",
+            failed.code_text(),
+            failed.relative_path,
+            failed.message
+        ));
+    }
+    report
+}
+
 /// 检查是否有足够的目标空间
 pub fn check_target_space(source_partition: &str, target_partition: &str) -> Result<(), String> {
     let source_info = get_partition_info(source_partition)
@@ -648,4 +782,20 @@ mod tests {
     fn test_marker_file_name() {
         assert_eq!(COPY_MARKER_FILENAME, ".letrecovery_partition_copy_marker");
     }
+
+    #[test]
+    fn test_format_failed_report() {
+        let failed = vec![
+            FailedFile::from_io_error("Users\\a.txt", &std::io::Error::from_raw_os_error(5)),
+            FailedFile {
+                relative_path: "b.dat".to_string(),
+                error_code: None,
+                message: "未知错误".to_string(),
+            },
+        ];
+        let report = format_failed_report("D:", "E:", &failed);
+        assert!(report.contains("失败数量: 2"));
+        assert!(report.contains("[5] Users\\a.txt"));
+        assert!(report.contains("[-] b.dat\t未知错误"));
+    }
 }
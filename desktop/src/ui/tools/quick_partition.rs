@@ -5,13 +5,17 @@
 use std::sync::mpsc;
 
 use crate::app::App;
-use crate::core::disk::PartitionStyle;
+use crate::core::disk::{
+    describe_gpt_partition, DiskManager, GptAttributes, GptPartitionType, PartitionPlan, PartitionStyle,
+};
 use crate::core::quick_partition::{
     execute_quick_partition, get_next_available_drive_letter, get_physical_disks,
     get_recommended_partition_style, get_unallocated_space_after_partition_with_disk,
     get_used_drive_letters, resize_existing_partition, PartitionLayout, PhysicalDisk,
-    ResizePartitionResult,
+    QuickPartitionResult, ResizePartitionResult,
 };
+use crate::core::system_info::BootMode;
+use crate::ui::a11y::{dialog_keys, focus_if_idle};
 
 /// 分区编辑器状态
 #[derive(Debug, Clone)]
@@ -108,6 +112,12 @@ pub struct EditablePartition {
     pub free_gb: f64,
     /// 磁盘编号（仅已有分区）
     pub disk_number: Option<u32>,
+    /// GPT 分区类型
+    pub gpt_type: GptPartitionType,
+    /// GPT 分区属性
+    pub gpt_attributes: GptAttributes,
+    /// 分区类型 GUID 或 MBR 类型 ID（仅已有分区）
+    pub partition_type: String,
 }
 
 impl EditablePartition {
@@ -127,6 +137,9 @@ impl EditablePartition {
             used_gb: 0.0,
             free_gb: size_gb,
             disk_number: None,
+            gpt_type: GptPartitionType::BasicData,
+            gpt_attributes: GptAttributes::default(),
+            partition_type: String::new(),
         }
     }
 
@@ -146,6 +159,9 @@ impl EditablePartition {
             used_gb: 0.0,
             free_gb: size_gb,
             disk_number: None,
+            gpt_type: GptPartitionType::Esp,
+            gpt_attributes: GptAttributes::default(),
+            partition_type: String::new(),
         }
     }
     
@@ -165,6 +181,9 @@ impl EditablePartition {
             used_gb: partition.used_gb(),
             free_gb: partition.free_gb(),
             disk_number: Some(disk_number),
+            gpt_type: GptPartitionType::from_guid(&partition.partition_type).unwrap_or_default(),
+            gpt_attributes: partition.gpt_attributes,
+            partition_type: partition.partition_type.clone(),
         }
     }
 
@@ -176,6 +195,37 @@ impl EditablePartition {
             label: self.label.clone(),
             is_esp: self.is_esp,
             file_system: self.file_system.clone(),
+            gpt_type: self.gpt_type,
+            gpt_attributes: self.gpt_attributes,
+        }
+    }
+
+    /// 设置新规划分区的 GPT 类型，并使用该类型的默认属性
+    fn set_gpt_type(&mut self, gpt_type: GptPartitionType) {
+        self.gpt_type = gpt_type;
+        self.gpt_attributes = gpt_type.default_attributes();
+        self.is_msr = gpt_type == GptPartitionType::Msr;
+        self.is_recovery = gpt_type == GptPartitionType::Recovery;
+        if gpt_type != GptPartitionType::BasicData {
+            self.drive_letter = None;
+        }
+        if gpt_type == GptPartitionType::Recovery && self.label.is_empty() {
+            self.label = "Recovery".to_string();
+        }
+    }
+
+    /// 分区类型和属性的显示文本
+    fn type_text(&self, style: PartitionStyle) -> String {
+        if self.is_existing {
+            if self.partition_type.starts_with("0x") {
+                format!("MBR {}", self.partition_type)
+            } else {
+                describe_gpt_partition(&self.partition_type, self.gpt_attributes)
+            }
+        } else if style == PartitionStyle::GPT {
+            describe_gpt_partition(self.gpt_type.guid(), self.gpt_attributes)
+        } else {
+            "-".to_string()
         }
     }
     
@@ -515,6 +565,81 @@ impl App {
         layouts.remove(index);
     }
 
+    /// 当前选中的磁盘
+    fn selected_partition_disk(&self) -> Option<PhysicalDisk> {
+        let index = self.quick_partition_state.editor.selected_disk_index?;
+        self.quick_partition_state.physical_disks.get(index).cloned()
+    }
+
+    /// 按当前启动模式填入 Windows 标准分区布局（UEFI：ESP + MSR + 系统 + 恢复；Legacy：系统）
+    fn apply_standard_layout(&mut self) {
+        let Some(disk) = self.selected_partition_disk() else {
+            return;
+        };
+        let uefi = self
+            .system_info
+            .as_ref()
+            .map_or(true, |info| matches!(info.boot_mode, BootMode::UEFI));
+        let plan = PartitionPlan::standard(disk.disk_number, uefi, None, uefi);
+
+        let fixed_gb: f64 = plan.partitions.iter().filter_map(|p| p.size_mb).map(|mb| mb as f64 / 1024.0).sum();
+        let state = &mut self.quick_partition_state;
+        state.editor.partition_style = plan.target_style;
+        state.editor.show_esp_button = uefi;
+        state.editor.partition_layouts.clear();
+        for planned in &plan.partitions {
+            state.partition_id_counter += 1;
+            let id = state.partition_id_counter;
+            let size_gb = planned.size_mb.map_or(disk.size_gb() - fixed_gb, |mb| mb as f64 / 1024.0);
+            let partition = if planned.partition_type == GptPartitionType::Esp {
+                EditablePartition::new_esp(id, size_gb)
+            } else {
+                let mut partition = EditablePartition::new(id, size_gb, planned.drive_letter);
+                partition.set_gpt_type(planned.partition_type);
+                partition.label = planned.label.clone();
+                partition
+            };
+            state.editor.partition_layouts.push(partition);
+        }
+        state.message = format!("已填入标准布局，执行后将{}", plan.describe());
+    }
+
+    /// 使用 mbr2gpt 将选中的 MBR 磁盘无损转换为 GPT
+    fn convert_selected_disk_to_gpt(&mut self) {
+        let Some(disk) = self.selected_partition_disk() else {
+            return;
+        };
+        let plan = match PartitionPlan::match_firmware(disk.disk_number, disk.partition_style, true) {
+            Ok(plan) => plan,
+            Err(e) => {
+                self.quick_partition_state.message = format!("✗ {}", e);
+                return;
+            }
+        };
+
+        self.quick_partition_state.executing = true;
+        self.quick_partition_state.message = format!("正在{}...", plan.describe());
+
+        let (tx, rx) = mpsc::channel();
+        self.quick_partition_result_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = match DiskManager::plan_and_apply(&plan) {
+                Ok(created_partitions) => QuickPartitionResult {
+                    success: true,
+                    message: "转换完成".to_string(),
+                    created_partitions,
+                },
+                Err(e) => QuickPartitionResult {
+                    success: false,
+                    message: format!("{:#}", e),
+                    created_partitions: Vec::new(),
+                },
+            };
+            let _ = tx.send(result);
+        });
+    }
+
     /// 执行一键分区
     fn execute_quick_partition(&mut self) {
         let state = &self.quick_partition_state;
@@ -585,6 +710,8 @@ impl App {
         let mut should_close = false;
         let mut should_add_partition = false;
         let mut should_add_esp = false;
+        let mut should_use_standard_layout = false;
+        let mut should_convert_to_gpt = false;
         let mut should_delete_partition: Option<usize> = None;
         let mut should_execute = false;
         let mut should_show_confirm = false;
@@ -593,7 +720,9 @@ impl App {
         let mut should_show_resize_dialog: Option<usize> = None;
         let mut should_show_resize_existing_dialog: Option<usize> = None;
         let mut should_execute_resize_existing = false;
-        
+        let mut should_set_gpt_type: Option<(usize, GptPartitionType)> = None;
+        let mut should_set_gpt_attribute: Option<(usize, u64, bool)> = None;
+
         // 使用局部变量控制窗口开关，避免借用冲突
         let mut window_open = self.show_quick_partition_dialog;
 
@@ -604,6 +733,7 @@ impl App {
             .min_width(600.0)
             .default_height(500.0)
             .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
                 // 加载中
                 if self.quick_partition_state.loading {
                     ui.vertical_centered(|ui| {
@@ -698,6 +828,14 @@ impl App {
 
                                 if disk.is_initialized {
                                     ui.label(format!("(当前: {})", disk.partition_style));
+                                    if disk.partition_style == PartitionStyle::MBR
+                                        && ui
+                                            .button("无损转换为 GPT")
+                                            .on_hover_text("使用 mbr2gpt 保留现有分区和数据转换为 GPT 并创建 ESP，\n转换后需在固件中改为 UEFI 启动")
+                                            .clicked()
+                                    {
+                                        should_convert_to_gpt = true;
+                                    }
                                 } else {
                                     if let Some(info) = &self.system_info {
                                         let recommended = get_recommended_partition_style(&info.boot_mode);
@@ -713,6 +851,15 @@ impl App {
                             // 工具栏
                             ui.horizontal(|ui| {
                                 ui.label(egui::RichText::new(&disk.display_name()).strong());
+                                ui.label(
+                                    egui::RichText::new(disk.sector_size.label())
+                                        .small()
+                                        .color(egui::Color32::GRAY),
+                                )
+                                .on_hover_text(format!(
+                                    "逻辑扇区 {} 字节 / 物理扇区 {} 字节",
+                                    disk.sector_size.logical, disk.sector_size.physical
+                                ));
                                 ui.add_space(20.0);
 
                                 if ui.button("➕ 添加分区").clicked() {
@@ -725,6 +872,14 @@ impl App {
                                         should_add_esp = true;
                                     }
                                 }
+
+                                if ui
+                                    .button("标准布局")
+                                    .on_hover_text("按当前启动模式填入 Windows 标准分区布局")
+                                    .clicked()
+                                {
+                                    should_use_standard_layout = true;
+                                }
                             });
 
                             ui.add_space(15.0);
@@ -815,6 +970,14 @@ impl App {
                                         egui::vec2(width, bar_height),
                                         egui::Sense::click(),
                                     );
+                                    // 自绘分区块没有文字控件，需要单独提供读屏名称
+                                    response.widget_info(|| {
+                                        egui::WidgetInfo::labeled(
+                                            egui::WidgetType::Button,
+                                            true,
+                                            format!("{} {}", name, size_text),
+                                        )
+                                    });
                                     
                                     let is_hovered = response.hovered();
                                     let fill_color = if is_hovered {
@@ -881,6 +1044,36 @@ impl App {
                                                 should_delete_partition = Some(*idx);
                                                 ui.close_menu();
                                             }
+
+                                            // ESP 由专用按钮创建，类型和属性只对 GPT 磁盘的其他分区开放
+                                            let editor = &self.quick_partition_state.editor;
+                                            if let Some(p) = editor.partition_layouts.get(*idx).filter(|p| {
+                                                !p.is_esp && editor.partition_style == PartitionStyle::GPT
+                                            }) {
+                                                ui.separator();
+                                                ui.menu_button("分区类型", |ui| {
+                                                    for gpt_type in [
+                                                        GptPartitionType::BasicData,
+                                                        GptPartitionType::Recovery,
+                                                        GptPartitionType::Msr,
+                                                    ] {
+                                                        if ui.radio(p.gpt_type == gpt_type, gpt_type.label()).clicked() {
+                                                            should_set_gpt_type = Some((*idx, gpt_type));
+                                                            ui.close_menu();
+                                                        }
+                                                    }
+                                                });
+                                                if p.gpt_type != GptPartitionType::Msr {
+                                                    ui.menu_button("分区属性", |ui| {
+                                                        for (flag, name) in GptAttributes::FLAGS {
+                                                            let mut enabled = p.gpt_attributes.contains(flag);
+                                                            if ui.checkbox(&mut enabled, name).changed() {
+                                                                should_set_gpt_attribute = Some((*idx, flag, enabled));
+                                                            }
+                                                        }
+                                                    });
+                                                }
+                                            }
                                         }
                                     });
                                 }
@@ -927,8 +1120,9 @@ impl App {
                             egui::ScrollArea::vertical()
                                 .max_height(150.0)
                                 .show(ui, |ui| {
+                                    let style = self.quick_partition_state.editor.partition_style;
                                     egui::Grid::new("partition_list")
-                                        .num_columns(6)
+                                        .num_columns(7)
                                         .spacing([15.0, 8.0])
                                         .striped(true)
                                         .show(ui, |ui| {
@@ -938,6 +1132,7 @@ impl App {
                                             ui.label(egui::RichText::new("已用/空闲").strong());
                                             ui.label(egui::RichText::new("卷标").strong());
                                             ui.label(egui::RichText::new("文件系统").strong());
+                                            ui.label(egui::RichText::new("类型/属性").strong());
                                             ui.end_row();
 
                                             let layouts_clone = self.quick_partition_state.editor.partition_layouts.clone();
@@ -973,6 +1168,9 @@ impl App {
                                                 // 文件系统
                                                 ui.label(&partition.file_system);
 
+                                                // 类型/属性
+                                                ui.label(partition.type_text(style));
+
                                                 ui.end_row();
                                             }
                                         });
@@ -1012,7 +1210,7 @@ impl App {
                                     should_show_confirm = true;
                                 }
 
-                                if ui.button("关闭").clicked() {
+                                if ui.button("关闭").clicked() || keys.escape {
                                     should_close = true;
                                 }
                             });
@@ -1028,6 +1226,7 @@ impl App {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ui.ctx(), |ui| {
+                    let keys = dialog_keys(ui);
                     ui.vertical_centered(|ui| {
                         ui.add_space(10.0);
                         ui.colored_label(egui::Color32::from_rgb(241, 196, 15), "⚠️");
@@ -1039,7 +1238,7 @@ impl App {
                             if ui.button("确定执行").clicked() {
                                 should_execute = true;
                             }
-                            if ui.button("取消").clicked() {
+                            if ui.button("取消").clicked() || keys.escape {
                                 self.quick_partition_state.show_confirm_dialog = false;
                             }
                         });
@@ -1058,6 +1257,7 @@ impl App {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ui.ctx(), |ui| {
+                    let keys = dialog_keys(ui);
                     ui.vertical(|ui| {
                         ui.add_space(10.0);
                         
@@ -1068,11 +1268,13 @@ impl App {
                                 ui.add_space(10.0);
                                 
                                 ui.horizontal(|ui| {
-                                    ui.label("新大小 (GB):");
-                                    ui.add(
+                                    let label = ui.label("新大小 (GB):");
+                                    let input = ui.add(
                                         egui::TextEdit::singleline(&mut self.quick_partition_state.editor.resize_size_text)
                                             .desired_width(100.0)
-                                    );
+                                    )
+                                    .labelled_by(label.id);
+                                    focus_if_idle(ui, &input);
                                 });
                                 
                                 ui.add_space(10.0);
@@ -1097,10 +1299,10 @@ impl App {
                                 ui.add_space(15.0);
                                 
                                 ui.horizontal(|ui| {
-                                    if ui.button("确定").clicked() {
+                                    if ui.button("确定").clicked() || keys.enter {
                                         apply_resize = true;
                                     }
-                                    if ui.button("取消").clicked() {
+                                    if ui.button("取消").clicked() || keys.escape {
                                         close_resize_dialog = true;
                                     }
                                 });
@@ -1161,6 +1363,7 @@ impl App {
                 .resizable(false)
                 .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
                 .show(ui.ctx(), |ui| {
+                    let keys = dialog_keys(ui);
                     ui.vertical(|ui| {
                         ui.add_space(10.0);
                         
@@ -1222,11 +1425,12 @@ impl App {
                                 ui.add_space(10.0);
                                 
                                 ui.horizontal(|ui| {
-                                    ui.label("新大小 (GB):");
+                                    let label = ui.label("新大小 (GB):");
                                     ui.add(
                                         egui::TextEdit::singleline(&mut self.quick_partition_state.editor.resize_size_text)
                                             .desired_width(100.0)
-                                    );
+                                    )
+                                    .labelled_by(label.id);
                                 });
                                 
                                 // 显示大小滑块
@@ -1260,13 +1464,13 @@ impl App {
                                     if ui.button("执行调整").clicked() {
                                         apply_resize = true;
                                     }
-                                    if ui.button("取消").clicked() {
+                                    if ui.button("取消").clicked() || keys.escape {
                                         close_dialog = true;
                                     }
                                 });
                             } else {
                                 ui.label("分区信息不可用");
-                                if ui.button("关闭").clicked() {
+                                if ui.button("关闭").clicked() || keys.escape {
                                     close_dialog = true;
                                 }
                             }
@@ -1296,6 +1500,14 @@ impl App {
             self.add_esp_partition();
         }
 
+        if should_use_standard_layout {
+            self.apply_standard_layout();
+        }
+
+        if should_convert_to_gpt {
+            self.convert_selected_disk_to_gpt();
+        }
+
         if let Some(idx) = should_delete_partition {
             self.delete_partition(idx);
         }
@@ -1323,6 +1535,21 @@ impl App {
             self.start_load_physical_disks();
         }
         
+        // 处理设置分区类型和属性
+        if let Some((idx, gpt_type)) = should_set_gpt_type {
+            if let Some(partition) = self.quick_partition_state.editor.partition_layouts.get_mut(idx) {
+                partition.set_gpt_type(gpt_type);
+            }
+        }
+        if let Some((idx, flag, enabled)) = should_set_gpt_attribute {
+            if let Some(partition) = self.quick_partition_state.editor.partition_layouts.get_mut(idx) {
+                partition.gpt_attributes.set(flag, enabled);
+                if flag == GptAttributes::NO_DRIVE_LETTER && enabled {
+                    partition.drive_letter = None;
+                }
+            }
+        }
+
         // 处理显示调整大小对话框
         if let Some(idx) = should_show_resize_dialog {
             if let Some(partition) = self.quick_partition_state.editor.partition_layouts.get(idx) {
@@ -0,0 +1,446 @@
+//! 一键恢复环境对话框模块
+//!
+//! 显示一键恢复环境的安装状态，选择 PE 安装或更新到隐藏恢复分区，也可以卸载并回收空间；
+//! 另可把备份镜像注册为「重置此电脑」使用的恢复映像
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::dism::DismProgress;
+use crate::core::pe::PeManager;
+use crate::core::recovery_env::{self, RecoveryInstallOptions, RecoveryStatus, MAX_SIZE_MB, MIN_SIZE_MB};
+use crate::core::winre::{self, WinreStatus};
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+
+/// 一键恢复环境对话框状态
+#[derive(Default)]
+pub struct RecoveryEnvDialogState {
+    /// 已安装的一键恢复环境
+    pub status: Option<RecoveryStatus>,
+    /// Windows RE 状态（查询失败时为 None）
+    pub winre_status: Option<WinreStatus>,
+    pub status_rx: Option<mpsc::Receiver<(Option<RecoveryStatus>, Option<WinreStatus>)>>,
+    pub options: RecoveryInstallOptions,
+    /// 卸载后把空间合并回系统分区
+    pub extend_system: bool,
+    /// 等待确认卸载
+    pub confirm_remove: bool,
+    /// 注册为恢复映像的备份镜像
+    pub reset_image_path: String,
+    pub reset_image_index: u32,
+    pub progress: Option<DismProgress>,
+    pub progress_rx: Option<mpsc::Receiver<DismProgress>>,
+    pub result_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub message: String,
+}
+
+impl RecoveryEnvDialogState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.status_rx.is_some() || self.result_rx.is_some()
+    }
+
+    fn is_busy(&self) -> bool {
+        self.result_rx.is_some()
+    }
+}
+
+/// 操作类型
+enum RecoveryEnvAction {
+    Install,
+    Remove,
+    RegisterResetImage,
+    UnregisterResetImage,
+}
+
+impl App {
+    /// 打开一键恢复环境对话框
+    pub fn init_recovery_env_dialog(&mut self) {
+        self.show_recovery_env_dialog = true;
+        let state = &mut self.recovery_env_state;
+        state.message.clear();
+        state.confirm_remove = false;
+        state.reset_image_index = state.reset_image_index.max(1);
+        self.start_detect_recovery_env();
+    }
+
+    /// 后台检测已安装的一键恢复环境
+    fn start_detect_recovery_env(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        self.recovery_env_state.status_rx = Some(rx);
+        std::thread::spawn(move || {
+            let winre_status = winre::query_status()
+                .map_err(|e| log::warn!("[RECOVERY] 查询 Windows RE 状态失败: {:#}", e))
+                .ok();
+            let _ = tx.send((recovery_env::detect(), winre_status));
+        });
+    }
+
+    /// 渲染一键恢复环境对话框
+    pub fn render_recovery_env_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_recovery_env_dialog {
+            return;
+        }
+
+        // 已下载的 PE（显示名称，完整路径）
+        let downloaded_pe: Vec<(String, String)> = self
+            .config
+            .as_ref()
+            .map(|config| {
+                config
+                    .pe_list
+                    .iter()
+                    .filter_map(|pe| {
+                        let (exists, path) = PeManager::check_pe_exists(&pe.filename);
+                        exists.then(|| (pe.display_name.clone(), path))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut should_close = false;
+        let mut action: Option<RecoveryEnvAction> = None;
+
+        egui::Window::new("一键恢复环境")
+            .resizable(true)
+            .default_width(600.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.recovery_env_state;
+                let busy = state.is_busy();
+                ui.label("把 PE 安装到系统盘末尾的隐藏恢复分区，并在启动菜单中添加入口，系统无法启动时也能进入 PE 还原备份。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    ui.label("当前状态:");
+                    if state.status_rx.is_some() {
+                        ui.spinner();
+                        ui.label("正在检测...");
+                    } else if state.status.is_some() {
+                        ui.colored_label(egui::Color32::GREEN, "已安装");
+                    } else {
+                        ui.colored_label(egui::Color32::GRAY, "未安装");
+                    }
+                });
+                if let Some(ref status) = state.status {
+                    let info = &status.info;
+                    egui::Grid::new("recovery_env_status")
+                        .num_columns(2)
+                        .spacing([12.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("启动菜单名称:");
+                            ui.label(&info.display_name);
+                            ui.end_row();
+                            ui.label("PE 文件:");
+                            ui.label(&info.pe_file);
+                            ui.end_row();
+                            ui.label("安装时间:");
+                            ui.label(info.installed_at_text());
+                            ui.end_row();
+                            ui.label("恢复分区:");
+                            ui.label(format!("{} MB", info.size_mb));
+                            ui.end_row();
+                            ui.label("F11 热键:");
+                            ui.label(if info.hotkey { "已设置" } else { "未设置" });
+                            ui.end_row();
+                            ui.label("Windows RE:");
+                            ui.label(if info.winre { "已注册" } else { "未注册" });
+                            ui.end_row();
+                        });
+                    if !status.entry_present {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            "⚠ 启动菜单项已丢失，点击「更新」重新创建",
+                        );
+                    }
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_enabled_ui(!busy, |ui| {
+                    egui::Grid::new("recovery_env_options")
+                        .num_columns(2)
+                        .spacing([12.0, 8.0])
+                        .show(ui, |ui| {
+                            ui.label("PE 文件:");
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut state.options.pe_path)
+                                        .hint_text("选择 PE 的 WIM 或 ISO 文件")
+                                        .desired_width(320.0),
+                                );
+                                if ui.button("浏览...").clicked() {
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .add_filter("PE 文件", &["wim", "iso"])
+                                        .pick_file()
+                                    {
+                                        state.options.pe_path = path.to_string_lossy().to_string();
+                                    }
+                                }
+                                if !downloaded_pe.is_empty() {
+                                    ui.menu_button("已下载", |ui| {
+                                        for (name, path) in &downloaded_pe {
+                                            if ui.button(name).clicked() {
+                                                state.options.pe_path = path.clone();
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    });
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.label("启动菜单名称:");
+                            ui.add(egui::TextEdit::singleline(&mut state.options.display_name).desired_width(200.0));
+                            ui.end_row();
+
+                            ui.label("恢复分区大小:");
+                            if state.status.is_some() {
+                                ui.label("使用已安装的恢复分区");
+                            } else {
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut state.options.size_mb)
+                                            .range(MIN_SIZE_MB..=MAX_SIZE_MB)
+                                            .speed(64),
+                                    );
+                                    ui.label("MB（从系统分区末尾缩小）");
+                                });
+                            }
+                            ui.end_row();
+                        });
+
+                    ui.checkbox(&mut state.options.hotkey, "开机按 F11 直接进入")
+                        .on_hover_text("部分电脑的固件会占用 F11，此时仍可在启动菜单中选择");
+                    ui.checkbox(&mut state.options.winre, "同时注册为 Windows RE")
+                        .on_hover_text("「设置 → 恢复 → 高级启动」和连续启动失败后的自动修复将进入此 PE，卸载时恢复系统自带的 Windows RE");
+                });
+
+                let validation = state.options.validate();
+                if let Err(ref e) = validation {
+                    if !state.options.pe_path.is_empty() {
+                        ui.colored_label(egui::Color32::RED, format!("✗ {}", e));
+                    }
+                }
+
+                ui.add_space(10.0);
+                let installed = state.status.is_some();
+                let ready = !busy && state.status_rx.is_none();
+                ui.horizontal(|ui| {
+                    let label = if installed { "更新" } else { "安装" };
+                    if ui.add_enabled(ready && validation.is_ok(), egui::Button::new(label)).clicked() {
+                        action = Some(RecoveryEnvAction::Install);
+                    }
+                    if installed && ui.add_enabled(ready, egui::Button::new("卸载")).clicked() {
+                        state.confirm_remove = true;
+                    }
+                });
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.strong("「重置此电脑」恢复映像");
+                ui.label("把备份镜像注册为 Windows 的恢复映像，系统设置中的「重置此电脑」将使用该镜像还原。");
+                match state.winre_status {
+                    Some(ref status) if status.image_registered() => {
+                        ui.label(format!(
+                            "已注册: {}（分卷 {}）",
+                            status.image_location,
+                            status.image_index.map_or("-".to_string(), |i| i.to_string())
+                        ));
+                    }
+                    Some(_) => {
+                        ui.colored_label(egui::Color32::GRAY, "未注册恢复映像");
+                    }
+                    None if state.status_rx.is_none() => {
+                        ui.colored_label(egui::Color32::GRAY, "无法查询 Windows RE 状态");
+                    }
+                    None => {}
+                }
+                let os_image_supported = state.winre_status.as_ref().is_some_and(|s| s.os_image_supported);
+                if state.winre_status.is_some() && !os_image_supported {
+                    ui.colored_label(
+                        egui::Color32::GRAY,
+                        "Windows 10 起「重置此电脑」只能使用系统自身的文件，请改用上方的一键恢复环境还原备份。",
+                    );
+                }
+                ui.add_enabled_ui(ready, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut state.reset_image_path)
+                                .hint_text("选择备份的 WIM 镜像")
+                                .desired_width(300.0),
+                        );
+                        if ui.button("浏览...").clicked() {
+                            if let Some(path) = rfd::FileDialog::new().add_filter("WIM", &["wim"]).pick_file() {
+                                state.reset_image_path = path.to_string_lossy().to_string();
+                            }
+                        }
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            state.reset_image_path = path;
+                        }
+                        ui.label("分卷:");
+                        ui.add(egui::DragValue::new(&mut state.reset_image_index).range(1..=999));
+                    });
+                    ui.horizontal(|ui| {
+                        let can_register =
+                            os_image_supported && state.reset_image_path.to_lowercase().ends_with(".wim");
+                        if ui.add_enabled(can_register, egui::Button::new("注册为恢复映像")).clicked() {
+                            action = Some(RecoveryEnvAction::RegisterResetImage);
+                        }
+                        let registered = state.winre_status.as_ref().is_some_and(|s| s.image_registered());
+                        if ui.add_enabled(registered, egui::Button::new("取消注册")).clicked() {
+                            action = Some(RecoveryEnvAction::UnregisterResetImage);
+                        }
+                    });
+                });
+
+                if state.confirm_remove && ready {
+                    ui.add_space(5.0);
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        "⚠ 将删除启动菜单项和恢复分区，分区中的 PE 文件会被清除。",
+                    );
+                    ui.checkbox(&mut state.extend_system, "把释放的空间合并到系统分区");
+                    ui.horizontal(|ui| {
+                        if ui.button("确认卸载").clicked() {
+                            action = Some(RecoveryEnvAction::Remove);
+                        }
+                        if ui.button("取消").clicked() {
+                            state.confirm_remove = false;
+                        }
+                    });
+                }
+
+                if let Some(ref progress) = state.progress {
+                    ui.add_space(10.0);
+                    ui.add(
+                        egui::ProgressBar::new(progress.percentage as f32 / 100.0)
+                            .text(format!("{}% - {}", progress.percentage, progress.status)),
+                    );
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("关闭")).clicked() || (keys.escape && !busy) {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if let Some(action) = action {
+            self.start_recovery_env_action(action);
+        }
+        if should_close {
+            self.show_recovery_env_dialog = false;
+        }
+    }
+
+    /// 后台安装或卸载
+    fn start_recovery_env_action(&mut self, action: RecoveryEnvAction) {
+        let state = &mut self.recovery_env_state;
+        state.confirm_remove = false;
+        state.progress = None;
+        let (result_tx, result_rx) = mpsc::channel();
+        state.result_rx = Some(result_rx);
+
+        match action {
+            RecoveryEnvAction::Install => {
+                state.message = "正在安装一键恢复环境...".to_string();
+                let options = state.options.clone();
+                let (progress_tx, progress_rx) = mpsc::channel();
+                state.progress_rx = Some(progress_rx);
+                std::thread::spawn(move || {
+                    let result = recovery_env::install(&options, progress_tx)
+                        .map(|message| format!("✅ {}", message))
+                        .map_err(|e| format!("❌ 安装失败: {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            RecoveryEnvAction::Remove => {
+                let Some(status) = state.status.clone() else {
+                    state.result_rx = None;
+                    return;
+                };
+                state.message = "正在卸载一键恢复环境...".to_string();
+                let extend_system = state.extend_system;
+                std::thread::spawn(move || {
+                    let result = recovery_env::remove(&status, extend_system)
+                        .map(|message| format!("✅ {}", message))
+                        .map_err(|e| format!("❌ 卸载失败: {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            RecoveryEnvAction::RegisterResetImage => {
+                state.message = "正在注册恢复映像...".to_string();
+                let image_path = state.reset_image_path.clone();
+                let index = state.reset_image_index;
+                self.app_config.add_recent_image(&image_path);
+                std::thread::spawn(move || {
+                    let result = winre::register_image(&image_path, index)
+                        .map(|message| format!("✅ {}", message))
+                        .map_err(|e| format!("❌ 注册失败: {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            RecoveryEnvAction::UnregisterResetImage => {
+                state.message = "正在取消注册恢复映像...".to_string();
+                std::thread::spawn(move || {
+                    let result = winre::unregister_image()
+                        .map(|_| "✅ 已取消注册恢复映像".to_string())
+                        .map_err(|e| format!("❌ 取消注册失败: {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+        }
+    }
+
+    /// 检查状态检测和操作结果
+    pub fn check_recovery_env_result(&mut self) {
+        let mut reload = false;
+        let state = &mut self.recovery_env_state;
+
+        if let Some(ref rx) = state.status_rx {
+            if let Ok((status, winre_status)) = rx.try_recv() {
+                if let Some(ref status) = status {
+                    state.options.display_name = status.info.display_name.clone();
+                    state.options.hotkey = status.info.hotkey;
+                    state.options.winre = status.info.winre;
+                }
+                state.status = status;
+                state.winre_status = winre_status;
+                state.status_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.progress_rx {
+            while let Ok(progress) = rx.try_recv() {
+                state.progress = Some(progress);
+            }
+        }
+
+        if let Some(ref rx) = state.result_rx {
+            if let Ok(result) = rx.try_recv() {
+                reload = true;
+                state.message = result.unwrap_or_else(|e| e);
+                state.progress = None;
+                state.progress_rx = None;
+                state.result_rx = None;
+            }
+        }
+
+        if reload {
+            self.start_detect_recovery_env();
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! 自加密硬盘解锁对话框模块
+//!
+//! 检测已锁定的 TCG Opal / eDrive 硬盘，支持使用锁定密码解锁或使用 PSID 恢复出厂设置
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::opal;
+use crate::ui::a11y::{dialog_keys, labeled_text_edit};
+use crate::ui::password_input::password_field;
+
+impl App {
+    /// 渲染自加密硬盘解锁对话框
+    pub fn render_sed_unlock_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_sed_unlock_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut start_unlock = false;
+        let mut start_revert = false;
+
+        egui::Window::new("自加密硬盘解锁")
+            .resizable(true)
+            .default_width(560.0)
+            .default_height(420.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                ui.label("硬件加密（TCG Opal / eDrive）的硬盘被锁定后无法读写和格式化，需要先解锁。");
+                ui.add_space(10.0);
+
+                if !opal::is_available() {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        "⚠ 未找到 sedutil-cli，请将 sedutil-cli.exe 放到程序目录 bin\\sedutil 下",
+                    );
+                }
+
+                // 硬盘列表
+                ui.horizontal(|ui| {
+                    ui.label("自加密硬盘:");
+                    if ui
+                        .add_enabled(!self.sed_unlock_loading, egui::Button::new("刷新"))
+                        .clicked()
+                    {
+                        self.start_scan_sed_drives();
+                    }
+                    if self.sed_unlock_loading {
+                        ui.spinner();
+                    }
+                });
+
+                if self.sed_unlock_drives.is_empty() && !self.sed_unlock_loading {
+                    ui.label("未检测到支持 Opal 的硬盘");
+                }
+
+                egui::ScrollArea::vertical()
+                    .id_salt("sed_unlock_drives")
+                    .max_height(140.0)
+                    .show(ui, |ui| {
+                        for (index, drive) in self.sed_unlock_drives.iter().enumerate() {
+                            let state = if drive.locked {
+                                "🔒 已锁定"
+                            } else if drive.locking_enabled {
+                                "🔓 已解锁"
+                            } else {
+                                "未启用锁定"
+                            };
+                            ui.radio_value(
+                                &mut self.sed_unlock_selected,
+                                Some(index),
+                                format!("{}  [Opal {}]  {}", drive.display_name(), drive.ssc, state),
+                            );
+                        }
+                    });
+
+                let selected = self
+                    .sed_unlock_selected
+                    .and_then(|i| self.sed_unlock_drives.get(i))
+                    .cloned();
+                let busy = self.sed_unlock_loading;
+
+                ui.add_space(10.0);
+                ui.separator();
+
+                // 使用密码解锁
+                ui.label(egui::RichText::new("使用密码解锁（保留数据）").strong());
+                ui.horizontal(|ui| {
+                    password_field(ui, "锁定密码:", &mut self.sed_unlock_password, 260.0);
+                    let can_unlock = selected.is_some() && !self.sed_unlock_password.is_empty() && !busy;
+                    if ui.add_enabled(can_unlock, egui::Button::new("解锁")).clicked()
+                        || (can_unlock && keys.enter)
+                    {
+                        start_unlock = true;
+                    }
+                });
+                ui.label(
+                    egui::RichText::new("解锁在断电或重启前有效")
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+
+                ui.add_space(10.0);
+                ui.separator();
+
+                // PSID 恢复出厂设置
+                ui.label(egui::RichText::new("忘记密码：使用 PSID 恢复出厂设置").strong());
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 80, 80),
+                    "⚠ 将永久清除硬盘上的全部数据！PSID 印在硬盘标签上（32 位字母或数字）",
+                );
+                ui.horizontal(|ui| {
+                    labeled_text_edit(ui, "PSID:", &mut self.sed_unlock_psid, false, 320.0);
+                });
+                ui.checkbox(&mut self.sed_unlock_psid_confirm, "我已了解该硬盘上的全部数据将被清除");
+                let can_revert = selected.is_some()
+                    && !self.sed_unlock_psid.trim().is_empty()
+                    && self.sed_unlock_psid_confirm
+                    && !busy;
+                if ui.add_enabled(can_revert, egui::Button::new("恢复出厂设置")).clicked() {
+                    start_revert = true;
+                }
+
+                if !self.sed_unlock_message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.label(&self.sed_unlock_message);
+                }
+
+                ui.add_space(10.0);
+                if ui.button("关闭").clicked() || keys.escape {
+                    should_close = true;
+                }
+            });
+
+        if start_unlock {
+            self.start_sed_operation(false);
+        }
+        if start_revert {
+            self.start_sed_operation(true);
+        }
+        if should_close {
+            self.show_sed_unlock_dialog = false;
+            self.sed_unlock_password.clear();
+            self.sed_unlock_psid.clear();
+        }
+    }
+
+    /// 后台扫描自加密硬盘
+    pub fn start_scan_sed_drives(&mut self) {
+        if self.sed_unlock_loading || !opal::is_available() {
+            return;
+        }
+        self.sed_unlock_loading = true;
+
+        let (tx, rx) = mpsc::channel();
+        self.sed_unlock_drives_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let _ = tx.send(opal::scan_drives().map_err(|e| format!("{:#}", e)));
+        });
+    }
+
+    /// 后台执行解锁（false）或 PSID 恢复出厂设置（true）
+    fn start_sed_operation(&mut self, psid_revert: bool) {
+        let Some(drive) = self
+            .sed_unlock_selected
+            .and_then(|i| self.sed_unlock_drives.get(i))
+            .cloned()
+        else {
+            return;
+        };
+
+        self.sed_unlock_loading = true;
+        self.sed_unlock_message = if psid_revert {
+            format!("正在恢复出厂设置: {}...", drive.display_name())
+        } else {
+            format!("正在解锁: {}...", drive.display_name())
+        };
+
+        let secret = if psid_revert {
+            self.sed_unlock_psid.clone()
+        } else {
+            std::mem::take(&mut self.sed_unlock_password)
+        };
+
+        let (tx, rx) = mpsc::channel();
+        self.sed_unlock_result_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = if psid_revert {
+                opal::psid_revert(&drive.device, &secret)
+                    .map(|_| format!("✅ {} 已恢复出厂设置，锁定已解除", drive.display_name()))
+            } else {
+                opal::unlock(&drive.device, &secret)
+                    .map(|_| format!("✅ {} 已解锁", drive.display_name()))
+            };
+            let _ = tx.send(result.map_err(|e| format!("❌ {:#}", e)));
+        });
+    }
+
+    /// 检查自加密硬盘扫描和操作结果
+    pub fn check_sed_unlock_result(&mut self) {
+        if let Some(ref rx) = self.sed_unlock_drives_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(drives) => {
+                        // 默认选中第一个已锁定的硬盘
+                        self.sed_unlock_selected = drives.iter().position(|d| d.locked);
+                        self.sed_unlock_drives = drives;
+                    }
+                    Err(e) => {
+                        self.sed_unlock_drives.clear();
+                        self.sed_unlock_selected = None;
+                        self.sed_unlock_message = format!("扫描失败: {}", e);
+                    }
+                }
+                self.sed_unlock_loading = false;
+                self.sed_unlock_drives_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = self.sed_unlock_result_rx {
+            if let Ok(result) = rx.try_recv() {
+                let success = result.is_ok();
+                self.sed_unlock_message = result.unwrap_or_else(|e| e);
+                self.sed_unlock_loading = false;
+                self.sed_unlock_result_rx = None;
+                if success {
+                    self.sed_unlock_psid.clear();
+                    self.sed_unlock_psid_confirm = false;
+                    // 刷新锁定状态和分区列表
+                    self.start_scan_sed_drives();
+                    self.refresh_partitions();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! 从备份提取文件对话框模块
+//!
+//! 浏览系统备份镜像（WIM/ESD/SWM）中的目录树，搜索并提取选中的文件或文件夹；
+//! 也可以把镜像只读挂载为虚拟驱动器，在资源管理器中浏览
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::dism::{Dism, ImageInfo};
+use crate::core::wim_browser::{parent_path, BrowseEvent, BrowseRequest, WimBrowser, SEARCH_LIMIT};
+use crate::core::wim_mount::{self, MountedImage};
+use crate::core::wimlib::{WimDirEntry, WimlibProgress};
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+
+/// 从备份提取文件对话框状态
+#[derive(Default)]
+pub struct WimBrowserDialogState {
+    pub image_path: String,
+    pub images: Vec<ImageInfo>,
+    pub images_rx: Option<mpsc::Receiver<Result<Vec<ImageInfo>, String>>>,
+    pub index: u32,
+    /// 已打开的浏览会话
+    pub browser: Option<WimBrowser>,
+    /// 正在等待后台线程响应
+    pub busy: bool,
+    pub current_path: String,
+    pub entries: Vec<WimDirEntry>,
+    pub search_keyword: String,
+    /// 搜索结果（None 表示显示目录）
+    pub search_results: Option<Vec<WimDirEntry>>,
+    /// 选中项的镜像内完整路径
+    pub selected: Vec<String>,
+    pub target_dir: String,
+    pub progress: Option<WimlibProgress>,
+    pub message: String,
+    /// 已挂载为虚拟驱动器的镜像
+    pub mounted: Vec<MountedImage>,
+    pub mount_rx: Option<mpsc::Receiver<Result<MountedImage, String>>>,
+}
+
+impl WimBrowserDialogState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.busy || self.images_rx.is_some() || self.mount_rx.is_some()
+    }
+
+    /// 在后台卸载全部虚拟驱动器（关闭对话框时调用）
+    fn unmount_all_in_background(&mut self) {
+        let mounted: Vec<MountedImage> = self.mounted.drain(..).collect();
+        if mounted.is_empty() {
+            return;
+        }
+        std::thread::spawn(move || {
+            for mounted in mounted {
+                if let Err(e) = wim_mount::unmount_image(&mounted) {
+                    println!("[WIM MOUNT] {:#}", e);
+                }
+            }
+        });
+    }
+
+    /// 卸载全部虚拟驱动器（程序退出时调用）
+    pub fn unmount_all(&mut self) {
+        for mounted in self.mounted.drain(..) {
+            if let Err(e) = wim_mount::unmount_image(&mounted) {
+                println!("[WIM MOUNT] {:#}", e);
+            }
+        }
+    }
+
+    /// 关闭浏览会话并清空目录内容
+    fn close_browser(&mut self) {
+        self.browser = None;
+        self.busy = false;
+        self.current_path.clear();
+        self.entries.clear();
+        self.search_results = None;
+        self.selected.clear();
+        self.progress = None;
+    }
+
+    fn request(&mut self, request: BrowseRequest) {
+        if let Some(ref browser) = self.browser {
+            browser.send(request);
+            self.busy = true;
+        }
+    }
+}
+
+impl App {
+    /// 渲染从备份提取文件对话框
+    pub fn render_wim_browser_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_wim_browser_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut load_images = false;
+        let mut open_browser = false;
+        let mut navigate_to: Option<String> = None;
+        let mut start_search = false;
+        let mut start_extract = false;
+        let mut start_mount = false;
+        let mut unmount_index: Option<usize> = None;
+
+        egui::Window::new("从备份提取文件")
+            .resizable(true)
+            .default_width(680.0)
+            .default_height(520.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.wim_browser_state;
+                ui.label("浏览系统备份镜像中的文件，把需要的文件或文件夹提取出来，无需还原整个系统。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let label = ui.label("备份镜像:");
+                    let response = ui
+                        .add_enabled(
+                            !state.busy,
+                            egui::TextEdit::singleline(&mut state.image_path)
+                                .hint_text("选择 WIM/ESD/SWM 文件")
+                                .desired_width(380.0),
+                        )
+                        .labelled_by(label.id);
+                    if response.lost_focus() && !state.image_path.is_empty() {
+                        load_images = true;
+                    }
+                    if ui.add_enabled(!state.busy, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WIM/ESD/SWM", &["wim", "esd", "swm"])
+                            .add_filter("所有文件", &["*"])
+                            .pick_file()
+                        {
+                            state.image_path = path.to_string_lossy().to_string();
+                            load_images = true;
+                        }
+                    }
+                    if !state.busy {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            state.image_path = path;
+                            load_images = true;
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("镜像索引:");
+                    if state.images_rx.is_some() {
+                        ui.spinner();
+                        ui.label("正在读取镜像信息...");
+                    } else if state.images.is_empty() {
+                        ui.label("请先选择镜像");
+                    } else {
+                        let old_index = state.index;
+                        let selected = state
+                            .images
+                            .iter()
+                            .find(|i| i.index == state.index)
+                            .map(|i| format!("{}. {}", i.index, i.name))
+                            .unwrap_or_else(|| "请选择".to_string());
+                        egui::ComboBox::from_id_salt("wim_browser_index")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for image in &state.images {
+                                    ui.selectable_value(
+                                        &mut state.index,
+                                        image.index,
+                                        format!("{}. {}", image.index, image.name),
+                                    );
+                                }
+                            });
+                        if old_index != state.index {
+                            state.close_browser();
+                        }
+                        if state.browser.is_none() && ui.add_enabled(!state.busy, egui::Button::new("打开")).clicked() {
+                            open_browser = true;
+                        }
+                        if ui
+                            .add_enabled(state.mount_rx.is_none(), egui::Button::new("挂载为驱动器"))
+                            .on_hover_text("只读挂载，可在资源管理器中浏览，关闭此对话框时自动卸载")
+                            .clicked()
+                        {
+                            start_mount = true;
+                        }
+                        if state.mount_rx.is_some() {
+                            ui.spinner();
+                        }
+                    }
+                });
+
+                if state.browser.is_some() {
+                    ui.add_space(5.0);
+                    ui.separator();
+
+                    ui.horizontal(|ui| {
+                        let label = ui.label("搜索:");
+                        let response = ui
+                            .add(
+                                egui::TextEdit::singleline(&mut state.search_keyword)
+                                    .hint_text("输入文件名")
+                                    .desired_width(260.0),
+                            )
+                            .labelled_by(label.id);
+                        let can_search = !state.busy && !state.search_keyword.trim().is_empty();
+                        if ui.add_enabled(can_search, egui::Button::new("搜索")).clicked()
+                            || (can_search && response.lost_focus() && keys.enter)
+                        {
+                            start_search = true;
+                        }
+                        if state.search_results.is_some() && ui.button("返回目录").clicked() {
+                            state.search_results = None;
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        let parent = parent_path(&state.current_path);
+                        if ui
+                            .add_enabled(parent.is_some() && !state.busy && state.search_results.is_none(), egui::Button::new("⬆ 上级"))
+                            .clicked()
+                        {
+                            navigate_to = parent;
+                        }
+                        let location = match state.search_results {
+                            Some(ref results) if results.len() >= SEARCH_LIMIT => {
+                                format!("搜索结果（仅显示前 {} 项）", SEARCH_LIMIT)
+                            }
+                            Some(ref results) => format!("搜索结果（{} 项）", results.len()),
+                            None if state.current_path.is_empty() => "\\".to_string(),
+                            None => state.current_path.clone(),
+                        };
+                        ui.label(location);
+                        if state.busy {
+                            ui.spinner();
+                        }
+                    });
+
+                    let entries = state.search_results.as_ref().unwrap_or(&state.entries);
+                    let show_full_path = state.search_results.is_some();
+                    let mut toggled: Option<String> = None;
+                    egui::ScrollArea::vertical()
+                        .id_salt("wim_browser_entries")
+                        .max_height(260.0)
+                        .show(ui, |ui| {
+                            for entry in entries {
+                                ui.horizontal(|ui| {
+                                    let mut checked = state.selected.contains(&entry.full_path);
+                                    let text = if show_full_path { &entry.full_path } else { &entry.name };
+                                    let icon = if entry.is_dir { "📁" } else { "📄" };
+                                    if ui.checkbox(&mut checked, format!("{} {}", icon, text)).changed() {
+                                        toggled = Some(entry.full_path.clone());
+                                    }
+                                    if entry.is_dir && !state.busy && ui.small_button("进入").clicked() {
+                                        navigate_to = Some(entry.full_path.clone());
+                                    }
+                                });
+                            }
+                        });
+                    if let Some(path) = toggled {
+                        if let Some(pos) = state.selected.iter().position(|p| *p == path) {
+                            state.selected.remove(pos);
+                        } else {
+                            state.selected.push(path);
+                        }
+                    }
+
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        let label = ui.label("提取到:");
+                        ui.add(egui::TextEdit::singleline(&mut state.target_dir).desired_width(340.0))
+                            .labelled_by(label.id);
+                        if ui.add_enabled(!state.busy, egui::Button::new("选择...")).clicked() {
+                            if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                                state.target_dir = path.to_string_lossy().to_string();
+                            }
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        let can_extract = !state.busy && !state.selected.is_empty() && !state.target_dir.is_empty();
+                        if ui
+                            .add_enabled(can_extract, egui::Button::new(format!("提取选中的 {} 项", state.selected.len())))
+                            .clicked()
+                        {
+                            start_extract = true;
+                        }
+                        if !state.selected.is_empty() && ui.button("清除选择").clicked() {
+                            state.selected.clear();
+                        }
+                    });
+
+                    if let Some(ref progress) = state.progress {
+                        ui.add(
+                            egui::ProgressBar::new(progress.percentage as f32 / 100.0)
+                                .text(format!("{}% - {}", progress.percentage, progress.status)),
+                        );
+                    }
+                }
+
+                if !state.mounted.is_empty() {
+                    ui.add_space(5.0);
+                    ui.separator();
+                    ui.label("已挂载的镜像:");
+                    for (i, mounted) in state.mounted.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let file_name = std::path::Path::new(&mounted.image_file)
+                                .file_name()
+                                .map(|n| n.to_string_lossy().to_string())
+                                .unwrap_or_default();
+                            ui.label(format!("{}  {} #{}", mounted.browse_path(), file_name, mounted.index));
+                            if ui.small_button("打开").clicked() {
+                                let _ = std::process::Command::new("explorer.exe").arg(mounted.browse_path()).spawn();
+                            }
+                            if ui.small_button("卸载").clicked() {
+                                unmount_index = Some(i);
+                            }
+                        });
+                    }
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if load_images {
+            self.start_load_wim_browser_images();
+        }
+        let state = &mut self.wim_browser_state;
+        if open_browser {
+            state.close_browser();
+            state.message = "正在打开镜像...".to_string();
+            state.browser = Some(WimBrowser::open(state.image_path.clone(), state.index as i32));
+            state.busy = true;
+            self.app_config.add_recent_image(&state.image_path);
+        }
+        if let Some(path) = navigate_to {
+            state.search_results = None;
+            state.request(BrowseRequest::List(path));
+        }
+        if start_search {
+            let keyword = state.search_keyword.trim().to_string();
+            state.request(BrowseRequest::Search(keyword));
+        }
+        if start_extract {
+            state.progress = None;
+            state.message = "正在提取...".to_string();
+            let paths = state.selected.clone();
+            let target_dir = state.target_dir.clone();
+            state.request(BrowseRequest::Extract { paths, target_dir });
+        }
+        if start_mount {
+            let image_path = state.image_path.clone();
+            let index = state.index;
+            state.message = "正在挂载镜像...".to_string();
+            let (tx, rx) = mpsc::channel();
+            state.mount_rx = Some(rx);
+            std::thread::spawn(move || {
+                let result = wim_mount::mount_image(&image_path, index).map_err(|e| format!("{:#}", e));
+                let _ = tx.send(result);
+            });
+        }
+        if let Some(i) = unmount_index {
+            let mounted = state.mounted.remove(i);
+            std::thread::spawn(move || {
+                if let Err(e) = wim_mount::unmount_image(&mounted) {
+                    println!("[WIM MOUNT] {:#}", e);
+                }
+            });
+        }
+        if should_close {
+            self.show_wim_browser_dialog = false;
+            self.wim_browser_state.close_browser();
+            self.wim_browser_state.unmount_all_in_background();
+        }
+    }
+
+    /// 后台读取备份镜像的索引列表
+    fn start_load_wim_browser_images(&mut self) {
+        let state = &mut self.wim_browser_state;
+        state.close_browser();
+        state.images.clear();
+        state.message.clear();
+        let image_path = state.image_path.clone();
+
+        let (tx, rx) = mpsc::channel();
+        state.images_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = Dism::new()
+                .get_image_info(&image_path)
+                .map_err(|e| format!("❌ 读取镜像信息失败: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 检查镜像信息读取和浏览会话的响应
+    pub fn check_wim_browser_result(&mut self) {
+        let dialog_open = self.show_wim_browser_dialog;
+        let state = &mut self.wim_browser_state;
+
+        if let Some(ref rx) = state.images_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(images) => {
+                        state.index = images.first().map(|i| i.index).unwrap_or(1);
+                        state.images = images;
+                    }
+                    Err(e) => state.message = e,
+                }
+                state.images_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.mount_rx {
+            if let Ok(result) = rx.try_recv() {
+                state.message = match result {
+                    Ok(mounted) => {
+                        let message = format!("✅ 已挂载到 {}", mounted.browse_path());
+                        state.mounted.push(mounted);
+                        message
+                    }
+                    Err(e) => format!("❌ {}", e),
+                };
+                state.mount_rx = None;
+                // 挂载完成前对话框已关闭
+                if !dialog_open {
+                    state.unmount_all_in_background();
+                }
+            }
+        }
+
+        let mut opened = false;
+        let mut failed = false;
+        if let Some(ref browser) = state.browser {
+            while let Some(event) = browser.try_recv() {
+                match event {
+                    BrowseEvent::Opened => opened = true,
+                    BrowseEvent::Listing { path, entries } => {
+                        state.current_path = path;
+                        state.entries = entries;
+                        state.busy = false;
+                        state.message.clear();
+                    }
+                    BrowseEvent::SearchResult { keyword, entries } => {
+                        state.message = if entries.is_empty() {
+                            format!("没有找到包含“{}”的文件", keyword)
+                        } else {
+                            String::new()
+                        };
+                        state.search_results = Some(entries);
+                        state.busy = false;
+                    }
+                    BrowseEvent::Progress(progress) => state.progress = Some(progress),
+                    BrowseEvent::Extracted(result) => {
+                        state.message = match result {
+                            Ok(message) => format!("✅ {}", message),
+                            Err(e) => format!("❌ {}", e),
+                        };
+                        state.busy = false;
+                    }
+                    BrowseEvent::Error(e) => {
+                        state.message = format!("❌ {}", e);
+                        state.busy = false;
+                        // 尚未打开成功时关闭会话，以便重试
+                        failed = state.entries.is_empty() && state.current_path.is_empty();
+                    }
+                }
+            }
+        }
+
+        if opened {
+            state.request(BrowseRequest::List(String::new()));
+        } else if failed {
+            state.browser = None;
+        }
+    }
+}
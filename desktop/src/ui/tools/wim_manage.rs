@@ -0,0 +1,473 @@
+//! 镜像分卷管理对话框模块
+//!
+//! 列出 WIM/ESD 中的全部分卷，可删除分卷、把分卷导出为新 WIM，
+//! 修改分卷名称、描述和标志，以及向分卷添加或删除文件
+
+use egui;
+use std::sync::mpsc;
+
+use crate::app::App;
+use crate::core::dism::DismProgress;
+use crate::core::image_convert::{convert_image, ConvertBackend, ConvertFormat, ConvertRequest};
+use crate::core::wim_manage::{
+    add_command, check_deletable, delete_image, list_images, set_properties, update_files, validate_delete_path,
+    validate_properties, ImageProperties, WimImageEntry,
+};
+use crate::core::wimlib::WimUpdateCommand;
+use crate::ui::a11y::dialog_keys;
+use crate::ui::recent::recent_menu;
+
+/// 镜像分卷管理对话框状态
+#[derive(Default)]
+pub struct WimManageDialogState {
+    pub image_path: String,
+    pub images: Vec<WimImageEntry>,
+    pub images_rx: Option<mpsc::Receiver<Result<Vec<WimImageEntry>, String>>>,
+    /// 选中的分卷索引
+    pub selected: Option<u32>,
+    /// 正在编辑的分卷信息
+    pub edit: ImageProperties,
+    pub export_format: ConvertFormat,
+    /// 等待确认删除
+    pub confirm_delete: bool,
+    /// 添加或删除文件时的镜像内路径
+    pub update_path: String,
+    pub progress: Option<DismProgress>,
+    pub progress_rx: Option<mpsc::Receiver<DismProgress>>,
+    pub result_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    pub message: String,
+}
+
+impl WimManageDialogState {
+    /// 是否有后台操作进行中
+    pub fn is_loading(&self) -> bool {
+        self.images_rx.is_some() || self.result_rx.is_some()
+    }
+
+    fn is_busy(&self) -> bool {
+        self.result_rx.is_some()
+    }
+
+    fn selected_entry(&self) -> Option<&WimImageEntry> {
+        let index = self.selected?;
+        self.images.iter().find(|image| image.index == index)
+    }
+
+    /// 选中分卷并载入其信息用于编辑
+    fn select(&mut self, index: Option<u32>) {
+        self.selected = index;
+        self.confirm_delete = false;
+        self.edit = self.selected_entry().map(ImageProperties::from).unwrap_or_default();
+    }
+}
+
+/// 操作类型
+enum WimManageAction {
+    Save,
+    Delete,
+    Export(String),
+    Update(Vec<WimUpdateCommand>),
+}
+
+impl App {
+    /// 渲染镜像分卷管理对话框
+    pub fn render_wim_manage_dialog(&mut self, ui: &mut egui::Ui) {
+        if !self.show_wim_manage_dialog {
+            return;
+        }
+
+        let mut should_close = false;
+        let mut load_images = false;
+        let mut action: Option<WimManageAction> = None;
+
+        egui::Window::new("镜像分卷管理")
+            .resizable(true)
+            .default_width(640.0)
+            .default_height(520.0)
+            .show(ui.ctx(), |ui| {
+                let keys = dialog_keys(ui);
+                let state = &mut self.wim_manage_state;
+                let busy = state.is_busy();
+                ui.label("管理 WIM/ESD 中的分卷：删除不需要的版本、导出单个版本、修改名称和描述，或添加、删除文件。");
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let label = ui.label("镜像文件:");
+                    let response = ui
+                        .add_enabled(
+                            !busy,
+                            egui::TextEdit::singleline(&mut state.image_path)
+                                .hint_text("选择 WIM/ESD 文件")
+                                .desired_width(360.0),
+                        )
+                        .labelled_by(label.id);
+                    if response.lost_focus() && !state.image_path.is_empty() {
+                        load_images = true;
+                    }
+                    if ui.add_enabled(!busy, egui::Button::new("浏览...")).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("WIM/ESD", &["wim", "esd"])
+                            .add_filter("所有文件", &["*"])
+                            .pick_file()
+                        {
+                            state.image_path = path.to_string_lossy().to_string();
+                            load_images = true;
+                        }
+                    }
+                    if !busy {
+                        if let Some(path) = recent_menu(ui, &self.app_config.recent_images) {
+                            state.image_path = path;
+                            load_images = true;
+                        }
+                    }
+                });
+
+                ui.add_space(5.0);
+                if state.images_rx.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("正在读取镜像信息...");
+                    });
+                } else if !state.images.is_empty() {
+                    let mut clicked: Option<u32> = None;
+                    egui::ScrollArea::vertical()
+                        .id_salt("wim_manage_images")
+                        .max_height(180.0)
+                        .show(ui, |ui| {
+                            egui::Grid::new("wim_manage_images_grid")
+                                .num_columns(4)
+                                .striped(true)
+                                .spacing([12.0, 4.0])
+                                .show(ui, |ui| {
+                                    ui.strong("索引");
+                                    ui.strong("名称");
+                                    ui.strong("标志");
+                                    ui.strong("大小");
+                                    ui.end_row();
+                                    for image in &state.images {
+                                        let selected = state.selected == Some(image.index);
+                                        if ui
+                                            .add_enabled(
+                                                !busy,
+                                                egui::SelectableLabel::new(selected, image.index.to_string()),
+                                            )
+                                            .clicked()
+                                        {
+                                            clicked = Some(image.index);
+                                        }
+                                        let name = ui.add_enabled(
+                                            !busy,
+                                            egui::SelectableLabel::new(selected, &image.name),
+                                        );
+                                        if name.clicked() {
+                                            clicked = Some(image.index);
+                                        }
+                                        if !image.description.is_empty() {
+                                            name.on_hover_text(&image.description);
+                                        }
+                                        ui.label(&image.flags);
+                                        ui.label(format!("{:.1} GB", image.total_bytes as f64 / 1024.0 / 1024.0 / 1024.0));
+                                        ui.end_row();
+                                    }
+                                });
+                        });
+                    if let Some(index) = clicked {
+                        state.select(Some(index));
+                    }
+                }
+
+                if state.selected_entry().is_some() {
+                    ui.add_space(10.0);
+                    ui.separator();
+                    egui::Grid::new("wim_manage_properties")
+                        .num_columns(2)
+                        .spacing([12.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.label("名称:");
+                            ui.add_enabled(!busy, egui::TextEdit::singleline(&mut state.edit.name).desired_width(360.0));
+                            ui.end_row();
+
+                            ui.label("描述:");
+                            ui.add_enabled(
+                                !busy,
+                                egui::TextEdit::singleline(&mut state.edit.description).desired_width(360.0),
+                            );
+                            ui.end_row();
+
+                            ui.label("标志:");
+                            ui.add_enabled(
+                                !busy,
+                                egui::TextEdit::singleline(&mut state.edit.flags)
+                                    .hint_text("如 Professional")
+                                    .desired_width(200.0),
+                            );
+                            ui.end_row();
+                        });
+
+                    let selected = state.selected.unwrap_or_default();
+                    let changed = state.selected_entry().map(ImageProperties::from).as_ref() != Some(&state.edit);
+                    let validation = validate_properties(&state.images, selected, &state.edit);
+                    if let Err(ref e) = validation {
+                        ui.colored_label(egui::Color32::RED, format!("✗ {}", e));
+                    }
+
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(!busy && changed && validation.is_ok(), egui::Button::new("保存修改"))
+                            .clicked()
+                        {
+                            action = Some(WimManageAction::Save);
+                        }
+
+                        ui.separator();
+                        ui.add_enabled_ui(!busy, |ui| {
+                            egui::ComboBox::from_id_salt("wim_manage_export_format")
+                                .selected_text(state.export_format.label())
+                                .show_ui(ui, |ui| {
+                                    for format in ConvertFormat::ALL {
+                                        ui.selectable_value(&mut state.export_format, format, format.label());
+                                    }
+                                });
+                        });
+                        if ui.add_enabled(!busy, egui::Button::new("导出为新镜像...")).clicked() {
+                            let extension = state.export_format.extension();
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter(extension.to_uppercase(), &[extension])
+                                .save_file()
+                            {
+                                action = Some(WimManageAction::Export(path.to_string_lossy().to_string()));
+                            }
+                        }
+
+                        ui.separator();
+                        let deletable = check_deletable(&state.images, selected);
+                        let response = ui.add_enabled(!busy && deletable.is_ok(), egui::Button::new("删除分卷"));
+                        if response.clicked() {
+                            state.confirm_delete = true;
+                        }
+                        if let Err(e) = deletable {
+                            response.on_disabled_hover_text(e.to_string());
+                        }
+                    });
+
+                    if state.confirm_delete && !busy {
+                        ui.add_space(5.0);
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            format!(
+                                "⚠ 将从镜像中删除分卷 {}（{}），并重写整个镜像文件，此操作不可撤销。",
+                                selected, state.edit.name
+                            ),
+                        );
+                        ui.horizontal(|ui| {
+                            if ui.button("确认删除").clicked() {
+                                action = Some(WimManageAction::Delete);
+                            }
+                            if ui.button("取消").clicked() {
+                                state.confirm_delete = false;
+                            }
+                        });
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.label("向分卷添加驱动、脚本、壁纸等文件，或删除分卷中的文件，无需重新捕获：");
+                    ui.horizontal(|ui| {
+                        let label = ui.label("镜像内路径:");
+                        ui.add_enabled(
+                            !busy,
+                            egui::TextEdit::singleline(&mut state.update_path)
+                                .hint_text("如 \\Windows\\Web\\Wallpaper")
+                                .desired_width(300.0),
+                        )
+                        .labelled_by(label.id);
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!busy, egui::Button::new("添加文件...")).clicked() {
+                            if let Some(files) = rfd::FileDialog::new().pick_files() {
+                                let commands = files.iter().map(|file| add_command(file, &state.update_path)).collect();
+                                action = Some(WimManageAction::Update(commands));
+                            }
+                        }
+                        if ui.add_enabled(!busy, egui::Button::new("添加文件夹内容...")).clicked() {
+                            if let Some(folder) = rfd::FileDialog::new().pick_folder() {
+                                action = Some(WimManageAction::Update(vec![add_command(&folder, &state.update_path)]));
+                            }
+                        }
+
+                        let deletable = validate_delete_path(&state.update_path);
+                        let response = ui.add_enabled(!busy && deletable.is_ok(), egui::Button::new("删除此路径"));
+                        if response.clicked() {
+                            action = Some(WimManageAction::Update(vec![WimUpdateCommand::Delete {
+                                path: state.update_path.trim().to_string(),
+                            }]));
+                        }
+                        if let Err(e) = deletable {
+                            response.on_disabled_hover_text(e.to_string());
+                        }
+                    });
+                }
+
+                if let Some(ref progress) = state.progress {
+                    ui.add_space(10.0);
+                    ui.add(
+                        egui::ProgressBar::new(progress.percentage as f32 / 100.0)
+                            .text(format!("{}% - {}", progress.percentage, progress.status)),
+                    );
+                }
+
+                if !state.message.is_empty() {
+                    ui.add_space(10.0);
+                    ui.label(&state.message);
+                }
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if busy {
+                        ui.spinner();
+                    }
+                    if ui.button("关闭").clicked() || keys.escape {
+                        should_close = true;
+                    }
+                });
+            });
+
+        if load_images {
+            self.wim_manage_state.message.clear();
+            self.wim_manage_state.select(None);
+            self.start_load_wim_manage_images();
+        }
+        if let Some(action) = action {
+            self.start_wim_manage_action(action);
+        }
+        if should_close {
+            self.show_wim_manage_dialog = false;
+        }
+    }
+
+    /// 后台读取镜像的分卷列表
+    fn start_load_wim_manage_images(&mut self) {
+        let state = &mut self.wim_manage_state;
+        state.images.clear();
+        let image_path = state.image_path.clone();
+
+        let (tx, rx) = mpsc::channel();
+        state.images_rx = Some(rx);
+
+        std::thread::spawn(move || {
+            let result = list_images(&image_path).map_err(|e| format!("❌ 读取镜像信息失败: {:#}", e));
+            let _ = tx.send(result);
+        });
+    }
+
+    /// 后台执行修改、删除或导出
+    fn start_wim_manage_action(&mut self, action: WimManageAction) {
+        let state = &mut self.wim_manage_state;
+        let Some(entry) = state.selected_entry().cloned() else {
+            return;
+        };
+        let image_path = state.image_path.clone();
+        self.app_config.add_recent_image(&image_path);
+
+        state.confirm_delete = false;
+        state.progress = None;
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        state.progress_rx = Some(progress_rx);
+        state.result_rx = Some(result_rx);
+
+        match action {
+            WimManageAction::Save => {
+                state.message = format!("正在保存分卷 {} 的信息...", entry.index);
+                let new = state.edit.clone();
+                std::thread::spawn(move || {
+                    let result = set_properties(&image_path, entry.index, &ImageProperties::from(&entry), &new)
+                        .map(|_| format!("✅ 已保存分卷 {} 的信息", entry.index))
+                        .map_err(|e| format!("❌ {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            WimManageAction::Delete => {
+                state.message = format!("正在删除分卷 {}...", entry.index);
+                std::thread::spawn(move || {
+                    let result = delete_image(&image_path, entry.index, progress_tx)
+                        .map(|_| format!("✅ 已删除分卷 {}（{}）", entry.index, entry.name))
+                        .map_err(|e| format!("❌ {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            WimManageAction::Export(destination) => {
+                let request = ConvertRequest {
+                    source: image_path,
+                    indexes: vec![entry.index],
+                    destination,
+                    format: state.export_format,
+                    backend: ConvertBackend::detect(),
+                    expanded_bytes: entry.total_bytes,
+                };
+                state.message = format!("正在导出分卷 {}...", entry.index);
+                std::thread::spawn(move || {
+                    let result = convert_image(&request, progress_tx)
+                        .map(|_| format!("✅ 已导出到 {}", request.destination))
+                        .map_err(|e| format!("❌ 导出失败: {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+            WimManageAction::Update(commands) => {
+                state.message = format!("正在更新分卷 {} 中的文件...", entry.index);
+                std::thread::spawn(move || {
+                    let result = update_files(&image_path, entry.index, &commands, progress_tx)
+                        .map(|_| format!("✅ 已更新分卷 {} 中的文件（{} 项）", entry.index, commands.len()))
+                        .map_err(|e| format!("❌ {:#}", e));
+                    let _ = result_tx.send(result);
+                });
+            }
+        }
+    }
+
+    /// 检查分卷列表读取和操作结果
+    pub fn check_wim_manage_result(&mut self) {
+        let mut reload = false;
+        let state = &mut self.wim_manage_state;
+
+        if let Some(ref rx) = state.images_rx {
+            if let Ok(result) = rx.try_recv() {
+                match result {
+                    Ok(images) => {
+                        state.images = images;
+                        // 删除分卷后后续分卷的索引前移，原索引不存在时改选第一个
+                        let selected = state
+                            .selected
+                            .filter(|index| state.images.iter().any(|image| image.index == *index))
+                            .or_else(|| state.images.first().map(|image| image.index));
+                        state.select(selected);
+                    }
+                    Err(e) => state.message = e,
+                }
+                state.images_rx = None;
+            }
+        }
+
+        if let Some(ref rx) = state.progress_rx {
+            while let Ok(progress) = rx.try_recv() {
+                state.progress = Some(progress);
+            }
+        }
+
+        if let Some(ref rx) = state.result_rx {
+            if let Ok(result) = rx.try_recv() {
+                reload = result.is_ok();
+                state.message = result.unwrap_or_else(|e| e);
+                state.progress = None;
+                state.progress_rx = None;
+                state.result_rx = None;
+            }
+        }
+
+        if reload {
+            self.start_load_wim_manage_images();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! 无人值守设置面板
+//!
+//! 编辑 [`UnattendSettings`]，安装时写入 unattend.xml；通过 PE 安装时随 install_config 传给 PE。
+
+use egui;
+
+use crate::core::unattend::{validate_computer_name, validate_product_key, NetworkLocation, UnattendSettings};
+use crate::ui::password_input::password_field;
+
+/// 常用区域（语言标记, 名称）
+const LOCALES: [(&str, &str); 7] = [
+    ("zh-CN", "中文(简体)"),
+    ("zh-TW", "中文(台湾)"),
+    ("zh-HK", "中文(香港)"),
+    ("en-US", "英语(美国)"),
+    ("en-GB", "英语(英国)"),
+    ("ja-JP", "日语"),
+    ("ko-KR", "韩语"),
+];
+
+/// 常用时区（Windows 时区 ID, 名称）
+const TIME_ZONES: [(&str, &str); 8] = [
+    ("China Standard Time", "(UTC+08:00) 北京，重庆，香港，乌鲁木齐"),
+    ("Taipei Standard Time", "(UTC+08:00) 台北"),
+    ("Singapore Standard Time", "(UTC+08:00) 吉隆坡，新加坡"),
+    ("Tokyo Standard Time", "(UTC+09:00) 大阪，札幌，东京"),
+    ("UTC", "(UTC) 协调世界时"),
+    ("GMT Standard Time", "(UTC+00:00) 都柏林，爱丁堡，里斯本，伦敦"),
+    ("Eastern Standard Time", "(UTC-05:00) 东部时间(美国和加拿大)"),
+    ("Pacific Standard Time", "(UTC-08:00) 太平洋时间(美国和加拿大)"),
+];
+
+/// 输入框 + 常用值下拉（也可直接输入其他值）
+fn text_with_presets(ui: &mut egui::Ui, id: &str, value: &mut String, presets: &[(&str, &str)]) {
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(value)
+                .desired_width(180.0)
+                .hint_text("沿用镜像默认"),
+        );
+        egui::ComboBox::from_id_salt(id)
+            .selected_text("常用")
+            .width(60.0)
+            .show_ui(ui, |ui| {
+                for (code, name) in presets {
+                    if ui
+                        .selectable_label(value.as_str() == *code, format!("{} ({})", name, code))
+                        .clicked()
+                    {
+                        *value = code.to_string();
+                    }
+                }
+            });
+    });
+}
+
+/// 显示无人值守设置
+pub fn show_unattend_settings(ui: &mut egui::Ui, settings: &mut UnattendSettings, unattended_enabled: bool) {
+    if !unattended_enabled {
+        ui.colored_label(
+            egui::Color32::from_rgb(255, 165, 0),
+            "⚠ 未勾选“无人值守”，以下设置不会生效",
+        );
+        ui.add_space(5.0);
+    }
+
+    ui.heading("区域和时区");
+    ui.separator();
+    egui::Grid::new("unattend_region_grid")
+        .num_columns(2)
+        .spacing([12.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("区域格式:");
+            text_with_presets(ui, "unattend_locale", &mut settings.locale, &LOCALES);
+            ui.end_row();
+
+            ui.label("界面语言:");
+            text_with_presets(ui, "unattend_ui_language", &mut settings.ui_language, &LOCALES);
+            ui.end_row();
+
+            ui.label("输入法:");
+            text_with_presets(ui, "unattend_input_locale", &mut settings.input_locale, &LOCALES);
+            ui.end_row();
+
+            ui.label("时区:");
+            text_with_presets(ui, "unattend_time_zone", &mut settings.time_zone, &TIME_ZONES);
+            ui.end_row();
+        });
+    ui.label(
+        egui::RichText::new("界面语言需镜像中已包含对应语言包")
+            .small()
+            .color(egui::Color32::GRAY),
+    );
+
+    ui.add_space(15.0);
+    ui.heading("系统和账户");
+    ui.separator();
+    egui::Grid::new("unattend_system_grid")
+        .num_columns(2)
+        .spacing([12.0, 6.0])
+        .show(ui, |ui| {
+            ui.label("计算机名:");
+            ui.add(
+                egui::TextEdit::singleline(&mut settings.computer_name)
+                    .desired_width(180.0)
+                    .hint_text("随机生成"),
+            );
+            ui.end_row();
+
+            ui.label("产品密钥:");
+            ui.add(
+                egui::TextEdit::singleline(&mut settings.product_key)
+                    .desired_width(260.0)
+                    .hint_text("XXXXX-XXXXX-XXXXX-XXXXX-XXXXX"),
+            );
+            ui.end_row();
+        });
+    for error in [
+        validate_computer_name(&settings.computer_name),
+        validate_product_key(&settings.product_key),
+    ]
+    .into_iter()
+    .filter_map(Result::err)
+    {
+        ui.colored_label(egui::Color32::RED, format!("✗ {}", error));
+    }
+
+    ui.horizontal(|ui| {
+        password_field(ui, "账户密码:", &mut settings.password, 180.0);
+    });
+    ui.label(
+        egui::RichText::new("用户名在“高级选项 → 用户设置”中修改；密码留空表示无密码，\n密码编码后写入 unattend.xml，部署完成时从中清除")
+            .small()
+            .color(egui::Color32::GRAY),
+    );
+    ui.checkbox(&mut settings.auto_logon, "首次启动自动登录一次");
+
+    ui.add_space(15.0);
+    ui.heading("网络和隐私");
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("网络位置:");
+        egui::ComboBox::from_id_salt("unattend_network_location")
+            .selected_text(settings.network_location.label())
+            .show_ui(ui, |ui| {
+                for location in NetworkLocation::ALL {
+                    ui.selectable_value(&mut settings.network_location, location, location.label());
+                }
+            });
+        ui.label(egui::RichText::new("仅 Win7/Win8").small().color(egui::Color32::GRAY));
+    });
+    ui.checkbox(&mut settings.recommended_protection, "使用推荐的自动更新保护设置")
+        .on_hover_text("不勾选时关闭 OOBE 中的自动保护设置");
+
+    ui.label(egui::RichText::new("以下选项仅对 Win10/11 生效").small().color(egui::Color32::GRAY));
+    ui.checkbox(&mut settings.skip_privacy_experience, "跳过隐私设置页面");
+    ui.checkbox(&mut settings.disable_telemetry, "关闭诊断数据收集");
+    ui.checkbox(&mut settings.disable_advertising_id, "关闭广告 ID");
+    ui.checkbox(&mut settings.disable_location, "关闭定位服务");
+}
@@ -0,0 +1,200 @@
+//! 压缩包解压模块
+//!
+//! ZIP 使用纯 Rust 的 zip 库解压；7z 及 zip 库不支持的压缩方式交给程序自带的 bin\7z\7z.exe；
+//! CAB 交给 expand.exe。驱动包下载、万能驱动工具包等都通过这里解压，进度以百分比回调。
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+use crate::utils::path::get_bin_dir;
+
+/// 压缩包格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    SevenZip,
+    Cab,
+}
+
+impl ArchiveKind {
+    /// 根据文件头识别格式
+    pub fn from_magic(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(ArchiveKind::Zip)
+        } else if header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+            Some(ArchiveKind::SevenZip)
+        } else if header.starts_with(b"MSCF") {
+            Some(ArchiveKind::Cab)
+        } else {
+            None
+        }
+    }
+
+    /// 根据扩展名识别格式
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "zip" => Some(ArchiveKind::Zip),
+            "7z" => Some(ArchiveKind::SevenZip),
+            "cab" => Some(ArchiveKind::Cab),
+            _ => None,
+        }
+    }
+
+    /// 识别文件格式（优先文件头，读取失败或无法识别时按扩展名）
+    pub fn detect(path: &Path) -> Option<Self> {
+        let mut header = [0u8; 8];
+        let read = File::open(path).and_then(|mut f| f.read(&mut header)).unwrap_or(0);
+        Self::from_magic(&header[..read]).or_else(|| Self::from_extension(path))
+    }
+}
+
+/// 程序自带的 7z.exe 路径
+fn seven_zip_path() -> Option<PathBuf> {
+    let path = get_bin_dir().join("7z").join("7z.exe");
+    path.exists().then_some(path)
+}
+
+/// 解压压缩包到 dest_dir，返回解压出的文件列表
+///
+/// progress 接收 0-100 的百分比
+pub fn extract(archive: &Path, dest_dir: &Path, mut progress: impl FnMut(u8)) -> Result<Vec<PathBuf>> {
+    let kind = ArchiveKind::detect(archive)
+        .ok_or_else(|| anyhow!("无法识别的压缩包格式: {}", archive.display()))?;
+    std::fs::create_dir_all(dest_dir).with_context(|| format!("无法创建目录: {}", dest_dir.display()))?;
+    println!("[ARCHIVE] 解压 {:?}: {} -> {}", kind, archive.display(), dest_dir.display());
+
+    let files = match kind {
+        ArchiveKind::Zip => match extract_zip(archive, dest_dir, &mut progress) {
+            Ok(files) => files,
+            Err(e) if seven_zip_path().is_some() => {
+                println!("[ARCHIVE] zip 解压失败，改用 7z: {:#}", e);
+                extract_with_7z(archive, dest_dir, &mut progress)?
+            }
+            Err(e) => return Err(e),
+        },
+        ArchiveKind::SevenZip => extract_with_7z(archive, dest_dir, &mut progress)?,
+        ArchiveKind::Cab => crate::core::cabinet::extract_cab(archive, dest_dir)?,
+    };
+
+    progress(100);
+    println!("[ARCHIVE] 共解压 {} 个文件", files.len());
+    Ok(files)
+}
+
+/// 使用 zip 库解压
+fn extract_zip(archive: &Path, dest_dir: &Path, progress: &mut impl FnMut(u8)) -> Result<Vec<PathBuf>> {
+    let file = File::open(archive).with_context(|| format!("无法打开压缩包: {}", archive.display()))?;
+    let mut zip = zip::ZipArchive::new(file).context("读取 zip 目录失败")?;
+    let total = zip.len().max(1);
+    let mut files = Vec::new();
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).context("读取 zip 条目失败")?;
+        // 拒绝包含 .. 或绝对路径的条目
+        let Some(relative) = entry.enclosed_name() else {
+            bail!("压缩包中包含不安全的路径: {}", entry.name());
+        };
+        let output = dest_dir.join(relative);
+
+        if entry.is_dir() {
+            std::fs::create_dir_all(&output).with_context(|| format!("无法创建目录: {}", output.display()))?;
+        } else {
+            if let Some(parent) = output.parent() {
+                std::fs::create_dir_all(parent).with_context(|| format!("无法创建目录: {}", parent.display()))?;
+            }
+            let mut out = File::create(&output).with_context(|| format!("无法创建文件: {}", output.display()))?;
+            io::copy(&mut entry, &mut out).with_context(|| format!("解压失败: {}", entry.name()))?;
+            files.push(output);
+        }
+        progress(((index + 1) * 100 / total) as u8);
+    }
+
+    Ok(files)
+}
+
+/// 使用 7z.exe 解压
+fn extract_with_7z(archive: &Path, dest_dir: &Path, progress: &mut impl FnMut(u8)) -> Result<Vec<PathBuf>> {
+    let seven_zip = seven_zip_path().ok_or_else(|| anyhow!("缺少 7z.exe，无法解压 {}", archive.display()))?;
+    let mut child = new_command(&seven_zip)
+        .arg("x")
+        .arg(archive)
+        .arg(format!("-o{}", dest_dir.display()))
+        .args(["-y", "-bsp1", "-bso0", "-bse1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("无法启动 7z.exe")?;
+
+    // -bsp1 用退格和回车刷新进度，按控制字符分行
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+        while let Ok(read) = stdout.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            for &byte in &buffer[..read] {
+                if !matches!(byte, b'\r' | b'\n' | 0x08) {
+                    pending.push(byte);
+                    continue;
+                }
+                let line = gbk_to_utf8(&pending).trim().to_string();
+                pending.clear();
+                if let Some(percent) = parse_7z_percent(&line) {
+                    progress(percent);
+                } else if !line.is_empty() {
+                    output.push_str(&line);
+                    output.push('\n');
+                }
+            }
+        }
+    }
+
+    let status = child.wait().context("等待 7z.exe 失败")?;
+    if !status.success() {
+        bail!("7z.exe 解压失败（退出码 {:?}）: {}", status.code(), output.trim());
+    }
+
+    Ok(walkdir::WalkDir::new(dest_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect())
+}
+
+/// 解析 7z -bsp1 的进度行（如 " 45% 12 - drivers\\vmd.inf"）
+fn parse_7z_percent(line: &str) -> Option<u8> {
+    let (digits, _) = line.split_once('%')?;
+    digits.trim().parse::<u8>().ok().filter(|p| *p <= 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_magic() {
+        assert_eq!(ArchiveKind::from_magic(b"PK\x03\x04\x14\x00"), Some(ArchiveKind::Zip));
+        assert_eq!(ArchiveKind::from_magic(b"7z\xBC\xAF\x27\x1C\x00\x04"), Some(ArchiveKind::SevenZip));
+        assert_eq!(ArchiveKind::from_magic(b"MSCF\x00\x00\x00\x00"), Some(ArchiveKind::Cab));
+        assert_eq!(ArchiveKind::from_magic(b"MZ\x90\x00"), None);
+        assert_eq!(ArchiveKind::from_extension(Path::new("pack.7Z")), Some(ArchiveKind::SevenZip));
+    }
+
+    #[test]
+    fn test_parse_7z_percent() {
+        assert_eq!(parse_7z_percent(" 45% 12 - drivers\\vmd.inf"), Some(45));
+        assert_eq!(parse_7z_percent("100%"), Some(100));
+        assert_eq!(parse_7z_percent("Everything is Ok"), None);
+        assert_eq!(parse_7z_percent("Size: 10%"), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! 进程退出码
+//!
+//! 命令行模式（`--pe-install`、`--pe-backup`、`--verify`、`--scheduled-backup` 以及 `utils::cli` 的子命令）以退出码报告结果，
+//! 供部署脚本判断失败原因。数值一经发布不可修改，新增原因只能追加新值。
+
+/// 退出码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitCode {
+    /// 成功
+    Success = 0,
+    /// 其他未归类的失败
+    Failed = 1,
+    /// 未找到配置文件或配置无法读取
+    ConfigMissing = 2,
+    /// 镜像不存在、无法识别或已损坏
+    ImageInvalid = 3,
+    /// 格式化目标分区失败
+    FormatFailed = 4,
+    /// 释放镜像失败
+    ApplyFailed = 5,
+    /// 修复引导失败
+    BootRepairFailed = 6,
+    /// 操作被取消
+    Cancelled = 7,
+    /// 捕获备份镜像失败
+    BackupFailed = 8,
+    /// 命令行参数错误
+    InvalidArguments = 9,
+}
+
+impl ExitCode {
+    /// 退出码数值
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// 以此退出码结束进程
+    pub fn exit(self) -> ! {
+        log::info!("进程退出，退出码: {} ({:?})", self.code(), self);
+        std::process::exit(self.code())
+    }
+
+    /// 将错误标记为此退出码对应的失败
+    pub fn fail(self, error: anyhow::Error) -> CliFailure {
+        CliFailure { code: self, error }
+    }
+}
+
+/// 带退出码的命令行失败
+#[derive(Debug)]
+pub struct CliFailure {
+    pub code: ExitCode,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for CliFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes_are_stable() {
+        // 退出码是对外契约，修改前请确认所有部署脚本已同步
+        let codes = [
+            (ExitCode::Success, 0),
+            (ExitCode::Failed, 1),
+            (ExitCode::ConfigMissing, 2),
+            (ExitCode::ImageInvalid, 3),
+            (ExitCode::FormatFailed, 4),
+            (ExitCode::ApplyFailed, 5),
+            (ExitCode::BootRepairFailed, 6),
+            (ExitCode::Cancelled, 7),
+            (ExitCode::BackupFailed, 8),
+            (ExitCode::InvalidArguments, 9),
+        ];
+        for (exit_code, value) in codes {
+            assert_eq!(exit_code.code(), value);
+        }
+    }
+}
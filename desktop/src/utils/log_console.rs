@@ -0,0 +1,169 @@
+//! 日志控制台模块
+//!
+//! 将 tracing/log 输出同步缓存到内存环形缓冲区，供界面内的日志面板显示。
+//! 与文件日志相互独立，关闭文件日志时控制台仍然可用。
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+
+use parking_lot::RwLock;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+/// 缓冲区最多保留的日志条数
+const MAX_RECORDS: usize = 2000;
+
+/// 全局日志缓冲区
+static LOG_BUFFER: OnceLock<RwLock<VecDeque<LogRecord>>> = OnceLock::new();
+
+/// 单条日志
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// 时间（本地时间 HH:MM:SS）
+    pub time: String,
+    /// 级别
+    pub level: Level,
+    /// 来源模块
+    pub target: String,
+    /// 日志内容
+    pub message: String,
+}
+
+impl LogRecord {
+    /// 从 tracing 事件提取日志内容
+    pub(crate) fn from_event(event: &Event<'_>) -> Self {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        // 通过 log 桥接的事件，真实模块名记录在 log.target 字段中
+        let target = visitor
+            .log_target
+            .take()
+            .unwrap_or_else(|| metadata.target().to_string());
+
+        Self {
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            level: *metadata.level(),
+            target,
+            message: visitor.message,
+        }
+    }
+
+    /// 格式化为单行文本（用于复制和导出）
+    pub fn to_line(&self) -> String {
+        format!("{} {:<5} [{}] {}", self.time, self.level, self.target, self.message)
+    }
+}
+
+/// 日志控制台层，挂载到 tracing 订阅器上
+pub struct ConsoleLayer;
+
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        push(LogRecord::from_event(event));
+    }
+}
+
+/// 提取日志消息和附加字段
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    log_target: Option<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            "log.target" => self.log_target = Some(value.to_string()),
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.message, " {}={}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name if name.starts_with("log.") => {}
+            name => {
+                let _ = write!(self.message, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+fn buffer() -> &'static RwLock<VecDeque<LogRecord>> {
+    LOG_BUFFER.get_or_init(|| RwLock::new(VecDeque::with_capacity(MAX_RECORDS)))
+}
+
+/// 追加一条日志，超出容量时丢弃最旧的记录
+fn push(record: LogRecord) {
+    let mut records = buffer().write();
+    if records.len() >= MAX_RECORDS {
+        records.pop_front();
+    }
+    records.push_back(record);
+}
+
+/// 获取不低于指定级别的日志副本
+pub fn snapshot(max_verbosity: Level) -> Vec<LogRecord> {
+    buffer()
+        .read()
+        .iter()
+        .filter(|r| r.level <= max_verbosity)
+        .cloned()
+        .collect()
+}
+
+/// 清空日志缓冲区
+pub fn clear() {
+    buffer().write().clear();
+}
+
+/// 将日志格式化为多行文本
+pub fn format_records(records: &[LogRecord]) -> String {
+    records
+        .iter()
+        .map(LogRecord::to_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: Level, message: &str) -> LogRecord {
+        LogRecord {
+            time: "12:00:00".to_string(),
+            level,
+            target: "test".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_buffer_capacity_and_filter() {
+        clear();
+        for i in 0..MAX_RECORDS + 10 {
+            push(record(Level::INFO, &i.to_string()));
+        }
+        push(record(Level::ERROR, "boom"));
+
+        let all = snapshot(Level::TRACE);
+        assert_eq!(all.len(), MAX_RECORDS);
+        assert_eq!(all[0].message, "11");
+
+        let errors = snapshot(Level::WARN);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(format_records(&errors), "12:00:00 ERROR [test] boom");
+    }
+}
@@ -1,28 +1,161 @@
 //! 日志管理模块
 //! 
 //! 提供文件日志记录功能，支持：
-//! - 日志文件存储在 `{软件运行目录}/log` 目录
+//! - 日志文件存储在 `{软件运行目录}/logs` 目录，每次启动写入新文件
+//! - 每行带时间戳、级别、会话 ID 和来源模块，便于合并多次运行的日志后按会话筛选
+//! - 单个文件超过大小上限时轮转，目录中只保留最近的若干个文件
 //! - 日志实时刷新到文件
 //! - 可在运行时动态开关日志
 //! - 日志状态持久化到配置文件
+//! - 同步输出到界面内的日志控制台
 
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use parking_lot::RwLock;
-use tracing_appender::non_blocking::WorkerGuard;
-use tracing_subscriber::layer::SubscriberExt;
+use parking_lot::Mutex;
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter, Layer};
+use tracing_subscriber::{EnvFilter, Layer};
 
+use super::log_console::{ConsoleLayer, LogRecord};
 use super::path::get_exe_dir;
 
+/// 日志文件名前缀
+const LOG_FILE_PREFIX: &str = "LetRecovery";
+
+/// 单个日志文件的大小上限，超过后轮转到新文件
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 日志目录中最多保留的日志文件数
+const MAX_LOG_FILES: usize = 30;
+
 /// 全局日志启用状态
 static LOG_ENABLED: AtomicBool = AtomicBool::new(true);
 
-/// 全局日志守卫（保持文件写入器存活）
-static LOG_GUARD: OnceLock<RwLock<Option<WorkerGuard>>> = OnceLock::new();
+/// 本次运行的会话 ID
+static SESSION_ID: OnceLock<String> = OnceLock::new();
+
+/// 当前写入的日志文件
+static CURRENT_LOG_FILE: OnceLock<Mutex<PathBuf>> = OnceLock::new();
+
+/// 本次运行的会话 ID（8 位十六进制），写入每行日志
+pub fn session_id() -> &'static str {
+    SESSION_ID.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        format!("{:08X}", nanos ^ std::process::id().rotate_left(16))
+    })
+}
+
+/// 日志文件名，轮转后的文件追加序号
+fn log_file_name(base: &str, part: u32) -> String {
+    if part == 0 {
+        format!("{}.log", base)
+    } else {
+        format!("{}.{}.log", base, part)
+    }
+}
+
+/// 按修改时间保留最新的 `keep` 个文件，返回需要删除的文件
+fn files_to_prune(mut files: Vec<(PathBuf, SystemTime)>, keep: usize) -> Vec<PathBuf> {
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    files.into_iter().skip(keep).map(|(path, _)| path).collect()
+}
+
+/// 列出日志目录中的日志文件及修改时间
+fn list_log_files(dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|ext| ext == "log").unwrap_or(false))
+        .filter_map(|p| {
+            let modified = p.metadata().and_then(|m| m.modified()).ok()?;
+            Some((p, modified))
+        })
+        .collect()
+}
+
+/// 按大小轮转的日志文件
+struct RotatingFile {
+    dir: PathBuf,
+    base: String,
+    part: u32,
+    max_size: u64,
+    written: u64,
+    file: Option<File>,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path, base: String, max_size: u64) -> std::io::Result<Self> {
+        let mut rotating = Self {
+            dir: dir.to_path_buf(),
+            base,
+            part: 0,
+            max_size,
+            written: 0,
+            file: None,
+        };
+        rotating.file = Some(File::create(rotating.path())?);
+        Ok(rotating)
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(log_file_name(&self.base, self.part))
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.written > 0 && self.written + line.len() as u64 > self.max_size {
+            self.rotate();
+        }
+        if let Some(ref mut file) = self.file {
+            if file.write_all(line.as_bytes()).is_ok() {
+                self.written += line.len() as u64;
+            }
+        }
+    }
+
+    fn rotate(&mut self) {
+        self.part += 1;
+        self.written = 0;
+        self.file = File::create(self.path()).ok();
+        if let Some(current) = CURRENT_LOG_FILE.get() {
+            *current.lock() = self.path();
+        }
+        for path in files_to_prune(list_log_files(&self.dir), MAX_LOG_FILES) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// 文件日志层：每条日志格式化为一行写入轮转文件
+struct FileLayer {
+    file: Mutex<RotatingFile>,
+}
+
+impl<S: Subscriber> Layer<S> for FileLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let record = LogRecord::from_event(event);
+        let line = format!(
+            "{} {:<5} [{}] [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            record.level,
+            session_id(),
+            record.target,
+            record.message
+        );
+        self.file.lock().write_line(&line);
+    }
+}
 
 /// 日志管理器
 pub struct LogManager;
@@ -30,7 +163,7 @@ pub struct LogManager;
 impl LogManager {
     /// 获取日志目录路径
     pub fn get_log_dir() -> PathBuf {
-        get_exe_dir().join("log")
+        get_exe_dir().join("logs")
     }
 
     /// 初始化日志系统
@@ -43,68 +176,52 @@ impl LogManager {
     pub fn init(enabled: bool) -> anyhow::Result<()> {
         LOG_ENABLED.store(enabled, Ordering::SeqCst);
 
+        if !enabled {
+            // 界面日志控制台不受文件日志开关影响
+            tracing_subscriber::registry()
+                .with(ConsoleLayer.with_filter(EnvFilter::new("info")))
+                .init();
+            return Ok(());
+        }
+
         // 创建日志目录
         let log_dir = Self::get_log_dir();
-        if enabled {
-            std::fs::create_dir_all(&log_dir)?;
-        }
+        std::fs::create_dir_all(&log_dir)?;
+
+        // 每次启动写入新文件：LetRecovery_20260101_120000_<会话ID>.log
+        let base = format!(
+            "{}_{}_{}",
+            LOG_FILE_PREFIX,
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            session_id()
+        );
+        let file = RotatingFile::open(&log_dir, base, MAX_LOG_FILE_SIZE)?;
+        let _ = CURRENT_LOG_FILE.set(Mutex::new(file.path()));
 
         // 配置环境过滤器
         let env_filter = EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new("info"));
+        let file_layer = FileLayer { file: Mutex::new(file) }.with_filter(env_filter);
 
-        if enabled {
-            // 创建文件日志写入器（按日期滚动）
-            let file_appender = tracing_appender::rolling::daily(&log_dir, "LetRecovery.log");
-            let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
-
-            // 文件日志格式层
-            let file_layer = fmt::layer()
-                .with_writer(non_blocking)
-                .with_ansi(false)
-                .with_target(true)
-                .with_thread_ids(false)
-                .with_thread_names(false)
-                .with_file(true)
-                .with_line_number(true)
-                .with_filter(env_filter);
-
-            // 初始化 tracing 订阅器
-            tracing_subscriber::registry()
-                .with(file_layer)
-                .init();
-
-            // 保存守卫以保持日志文件打开
-            let lock = LOG_GUARD.get_or_init(|| RwLock::new(None));
-            *lock.write() = Some(guard);
+        // 初始化 tracing 订阅器（log crate 的宏通过 tracing-log 桥接）
+        tracing_subscriber::registry()
+            .with(file_layer)
+            // 界面日志控制台
+            .with(ConsoleLayer.with_filter(EnvFilter::new("info")))
+            .init();
 
-            // 兼容 log crate 的宏
-            Self::setup_log_compat();
-
-            log::info!("日志系统初始化完成，日志目录: {}", log_dir.display());
-        } else {
-            // 日志禁用时，使用空订阅器
-            let noop_layer = fmt::layer()
-                .with_writer(std::io::sink)
-                .with_filter(EnvFilter::new("off"));
-
-            tracing_subscriber::registry()
-                .with(noop_layer)
-                .init();
-
-            // 仍然设置 log 兼容层（但输出会被过滤）
-            Self::setup_log_compat();
+        for path in files_to_prune(list_log_files(&log_dir), MAX_LOG_FILES) {
+            let _ = std::fs::remove_file(path);
         }
 
+        log::info!(
+            "日志系统初始化完成，会话 ID: {}，日志目录: {}",
+            session_id(),
+            log_dir.display()
+        );
         Ok(())
     }
 
-    /// 设置 log crate 兼容层
-    fn setup_log_compat() {
-        // tracing-log 桥接已经通过 tracing-subscriber 自动处理
-        // 这里不需要额外操作，tracing-subscriber 默认支持 log crate
-    }
-
     /// 检查日志是否启用
     pub fn is_enabled() -> bool {
         LOG_ENABLED.load(Ordering::SeqCst)
@@ -122,24 +239,35 @@ impl LogManager {
         }
     }
 
-    /// 刷新日志缓冲区
-    /// 
-    /// 强制将所有缓冲的日志写入文件
-    pub fn flush() {
-        // non_blocking writer 会在 guard 被 drop 时自动刷新
-        // 这里通过写入一条空日志来触发刷新
-        if Self::is_enabled() {
-            log::trace!("日志刷新");
-        }
+    /// 获取当前写入的日志文件路径
+    pub fn get_current_log_file() -> Option<PathBuf> {
+        CURRENT_LOG_FILE.get().map(|current| current.lock().clone())
     }
 
-    /// 获取当前日志文件路径
-    /// 
-    /// 返回当天的日志文件路径
-    pub fn get_current_log_file() -> PathBuf {
-        let log_dir = Self::get_log_dir();
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-        log_dir.join(format!("LetRecovery.log.{}", today))
+    /// 将日志目录中的全部日志文件打包为 zip，返回打包的文件数
+    pub fn export_logs(dest: &Path) -> anyhow::Result<usize> {
+        use anyhow::Context;
+        use zip::write::SimpleFileOptions;
+
+        let mut files = list_log_files(&Self::get_log_dir());
+        if files.is_empty() {
+            anyhow::bail!("没有可导出的日志文件");
+        }
+        files.sort_by_key(|(_, modified)| *modified);
+
+        let output = File::create(dest).with_context(|| format!("创建文件失败: {}", dest.display()))?;
+        let mut zip = zip::ZipWriter::new(output);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (path, _) in &files {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            zip.start_file(name, options)?;
+            let content = std::fs::read(path).with_context(|| format!("读取日志失败: {}", path.display()))?;
+            zip.write_all(&content)?;
+        }
+        zip.finish().context("写入压缩包失败")?;
+
+        log::info!("已导出 {} 个日志文件到 {}", files.len(), dest.display());
+        Ok(files.len())
     }
 
     /// 清理旧日志文件
@@ -276,6 +404,36 @@ mod tests {
     #[test]
     fn test_log_dir_path() {
         let log_dir = LogManager::get_log_dir();
-        assert!(log_dir.ends_with("log"));
+        assert!(log_dir.ends_with("logs"));
+    }
+
+    #[test]
+    fn test_session_id_is_stable() {
+        assert_eq!(session_id().len(), 8);
+        assert_eq!(session_id(), session_id());
+    }
+
+    #[test]
+    fn test_files_to_prune_keeps_newest() {
+        let t = |secs| UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        let files = vec![
+            (PathBuf::from("a.log"), t(1)),
+            (PathBuf::from("c.log"), t(3)),
+            (PathBuf::from("b.log"), t(2)),
+        ];
+        assert_eq!(files_to_prune(files, 2), vec![PathBuf::from("a.log")]);
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = std::env::temp_dir().join(format!("letrecovery_log_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut file = RotatingFile::open(&dir, "test".to_string(), 20).unwrap();
+        file.write_line("0123456789\n");
+        file.write_line("0123456789\n");
+        file.write_line("01234\n");
+        assert_eq!(std::fs::read_to_string(dir.join("test.log")).unwrap(), "0123456789\n");
+        assert_eq!(std::fs::read_to_string(dir.join("test.1.log")).unwrap(), "0123456789\n01234\n");
+        let _ = std::fs::remove_dir_all(&dir);
     }
 }
@@ -1,7 +1,16 @@
+pub mod archive;
+#[path = "../../../shared/cli.rs"]
+pub mod cli;
 pub mod cmd;
 pub mod command;
+#[path = "../../../shared/dpi.rs"]
+pub mod dpi;
 pub mod encoding;
+pub mod exit_code;
 pub mod i18n;
+#[path = "../../../shared/json_progress.rs"]
+pub mod json_progress;
+pub mod log_console;
 pub mod logger;
 pub mod path;
 pub mod privilege;
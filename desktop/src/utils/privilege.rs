@@ -30,7 +30,7 @@ pub fn is_admin() -> bool {
     }
 }
 
-/// 以管理员权限重新启动程序
+/// 以管理员权限重新启动程序（保留命令行参数，如 --demo）
 pub fn restart_as_admin() -> Result<()> {
     use windows::core::PCWSTR;
     use windows::Win32::UI::Shell::ShellExecuteW;
@@ -43,6 +43,15 @@ pub fn restart_as_admin() -> Result<()> {
         .chain(std::iter::once(0))
         .collect();
 
+    let parameters: Vec<u16> = std::env::args()
+        .skip(1)
+        .map(|arg| format!("\"{}\"", arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
     let operation: Vec<u16> = "runas\0".encode_utf16().collect();
 
     unsafe {
@@ -50,7 +59,7 @@ pub fn restart_as_admin() -> Result<()> {
             None,
             PCWSTR(operation.as_ptr()),
             PCWSTR(exe_path_wide.as_ptr()),
-            PCWSTR::null(),
+            PCWSTR(parameters.as_ptr()),
             PCWSTR::null(),
             SW_SHOWNORMAL,
         );
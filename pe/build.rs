@@ -15,7 +15,7 @@ fn main() {
         res.set("ProductVersion", "2026.2.6");
         res.set("FileVersion", "2026.2.6");
 
-        // 包含 Common Controls 6.0 和管理员权限
+        // 包含 Common Controls 6.0、管理员权限和按显示器 DPI 感知
         res.set_manifest(r#"
 <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
@@ -35,6 +35,12 @@ fn main() {
             <supportedOS Id="{e2011457-1546-43c5-a5fe-008deee3d3f0}"/>
         </application>
     </compatibility>
+    <application xmlns="urn:schemas-microsoft-com:asm.v3">
+        <windowsSettings>
+            <dpiAware xmlns="http://schemas.microsoft.com/SMI/2005/WindowsSettings">true/pm</dpiAware>
+            <dpiAwareness xmlns="http://schemas.microsoft.com/SMI/2016/WindowsSettings">PerMonitorV2, PerMonitor</dpiAwareness>
+        </windowsSettings>
+    </application>
     <dependency>
         <dependentAssembly>
             <assemblyIdentity
@@ -7,7 +7,13 @@ use eframe::egui;
 
 use crate::core::config::{ConfigFileManager, OperationType};
 use crate::core::dism::DismProgress;
+use crate::core::dism_exe::report_driver_results;
+use crate::core::install_engine::{EngineEvent, StepContext};
+use crate::core::power::CompletionAction;
+use crate::core::watchdog::{Watchdog, HANG_TIMEOUT};
+use crate::ui::countdown::{Countdown, CountdownAction, REBOOT_COUNTDOWN, RETRY_COUNTDOWN};
 use crate::ui::progress::{InstallStep, BackupStep, ProgressState, ProgressUI};
+use crate::utils::json_progress::{self, ProgressEvent};
 use crate::utils::reboot_pe;
 
 /// 递归查找目录中的所有 CAB 文件
@@ -35,6 +41,43 @@ fn find_cab_files_recursive(dir: &Path, cab_files: &mut Vec<PathBuf>) {
     }
 }
 
+/// 失败后自动重试的最大次数
+const MAX_AUTO_RETRIES: u32 = 1;
+
+/// 把工作线程消息同步输出为进度事件（`--progress-json` / `--progress-pipe` 模式）
+fn emit_progress_event(msg: &WorkerMessage, status: &str, will_retry: bool) {
+    let event = match msg {
+        WorkerMessage::SetInstallStep(step) => ProgressEvent::Step {
+            step: step.index() as u8 + 1,
+            total: InstallStep::total() as u8,
+            name: step.name().to_string(),
+        },
+        WorkerMessage::SetBackupStep(step) => ProgressEvent::Step {
+            step: step.index() as u8 + 1,
+            total: BackupStep::total() as u8,
+            name: step.name().to_string(),
+        },
+        WorkerMessage::SetProgress(percentage) => ProgressEvent::Progress {
+            percentage: *percentage,
+            status: status.to_string(),
+        },
+        WorkerMessage::SetStatus(message) => ProgressEvent::Message { message: message.clone() },
+        WorkerMessage::Completed(action) => ProgressEvent::Finished {
+            success: true,
+            message: format!("操作完成，完成后: {}", action.label()),
+        },
+        WorkerMessage::Failed(message) => {
+            json_progress::emit(ProgressEvent::Error { message: message.clone() });
+            // 还会自动重试时不输出结束事件
+            if will_retry {
+                return;
+            }
+            ProgressEvent::Finished { success: false, message: message.clone() }
+        }
+    };
+    json_progress::emit(event);
+}
+
 /// 工作线程消息
 #[derive(Debug, Clone)]
 pub enum WorkerMessage {
@@ -46,8 +89,8 @@ pub enum WorkerMessage {
     SetProgress(u8),
     /// 更新状态消息
     SetStatus(String),
-    /// 标记完成
-    Completed,
+    /// 标记完成（附带完成后的操作）
+    Completed(CompletionAction),
     /// 标记失败
     Failed(String),
 }
@@ -61,6 +104,14 @@ pub struct App {
     started: bool,
     /// 操作类型
     operation_type: Option<OperationType>,
+    /// 看门狗（崩溃或卡死时自动恢复）
+    watchdog: Option<Watchdog>,
+    /// 完成后的自动重启 / 失败后的自动重试倒计时
+    countdown: Option<Countdown>,
+    /// 完成后的操作（完成界面上可以更改）
+    completion_action: CompletionAction,
+    /// 已自动重试的次数
+    retry_count: u32,
 }
 
 impl App {
@@ -68,6 +119,12 @@ impl App {
         // 设置中文字体
         Self::setup_fonts(&cc.egui_ctx);
 
+        // 触屏设备放大控件
+        crate::ui::touch::apply_style(&cc.egui_ctx);
+
+        // 与窗口大小使用相同的缩放比例
+        cc.egui_ctx.set_zoom_factor(crate::utils::dpi::ui_zoom());
+
         // 检测操作类型
         let operation_type = ConfigFileManager::detect_operation_type();
 
@@ -77,16 +134,23 @@ impl App {
             None => ProgressState::new_install(),
         }));
 
+        // 只有实际执行安装/备份时才需要看门狗
+        let watchdog = operation_type.map(|_| Watchdog::start(HANG_TIMEOUT));
+
         Self {
             progress_state,
             message_rx: None,
             started: false,
             operation_type,
+            watchdog,
+            countdown: None,
+            completion_action: CompletionAction::default(),
+            retry_count: 0,
         }
     }
 
     /// 设置中文字体（从PE的X盘加载微软雅黑）
-    fn setup_fonts(ctx: &egui::Context) {
+    pub(crate) fn setup_fonts(ctx: &egui::Context) {
         let mut fonts = egui::FontDefinitions::default();
 
         // PE环境下字体路径固定为 X:\Windows\Fonts\msyh.ttc
@@ -129,8 +193,12 @@ impl App {
         self.message_rx = Some(rx);
 
         let operation_type = self.operation_type;
+        let watchdog = self.watchdog.clone();
 
         thread::spawn(move || {
+            if let Some(watchdog) = watchdog {
+                watchdog.watch_current_thread();
+            }
             match operation_type {
                 Some(OperationType::Install) => {
                     execute_install_workflow(tx);
@@ -145,11 +213,65 @@ impl App {
         });
     }
 
+    /// 完成后操作对应的倒计时（立即重启时倒计时为 0，留在 PE 时没有倒计时）
+    fn completion_countdown(action: CompletionAction) -> Option<Countdown> {
+        let duration = match action {
+            CompletionAction::RebootNow => std::time::Duration::ZERO,
+            CompletionAction::StayInPe => return None,
+            _ => REBOOT_COUNTDOWN,
+        };
+        Some(Countdown::new(CountdownAction::Finish(action), duration))
+    }
+
+    /// 执行倒计时结束或用户选择的操作
+    fn run_action(&mut self, action: CountdownAction) {
+        self.countdown = None;
+        match action {
+            CountdownAction::Finish(CompletionAction::StayInPe) => {}
+            CountdownAction::Finish(CompletionAction::RebootNow | CompletionAction::RebootCountdown) => {
+                log::info!("即将重启...");
+                reboot_pe();
+            }
+            CountdownAction::Finish(completion) => {
+                log::info!("即将{}...", completion.verb());
+                if let Err(e) = completion.execute(0, "") {
+                    log::error!("{:#}", e);
+                }
+            }
+            CountdownAction::Retry => {
+                self.retry_count += 1;
+                log::info!("重新执行流程（第 {} 次重试）", self.retry_count);
+                if let Ok(mut state) = self.progress_state.lock() {
+                    *state = match self.operation_type {
+                        Some(OperationType::Backup) => ProgressState::new_backup(),
+                        _ => ProgressState::new_install(),
+                    };
+                }
+                if let Some(ref watchdog) = self.watchdog {
+                    watchdog.restart();
+                }
+                self.message_rx = None;
+                self.started = false;
+            }
+            CountdownAction::Close => {}
+        }
+    }
+
     /// 处理工作线程消息
     fn process_messages(&mut self) {
         if let Some(ref rx) = self.message_rx {
             while let Ok(msg) = rx.try_recv() {
+                if let Some(ref watchdog) = self.watchdog {
+                    match &msg {
+                        WorkerMessage::SetInstallStep(step) => watchdog.set_stage(step.name()),
+                        WorkerMessage::SetBackupStep(step) => watchdog.set_stage(step.name()),
+                        // 流程已结束，失败信息由界面展示，不再自动重启
+                        WorkerMessage::Completed(_) | WorkerMessage::Failed(_) => watchdog.finish(),
+                        _ => watchdog.heartbeat(),
+                    }
+                }
                 if let Ok(mut state) = self.progress_state.lock() {
+                    emit_progress_event(&msg, &state.status_message, self.retry_count < MAX_AUTO_RETRIES);
                     match msg {
                         WorkerMessage::SetInstallStep(step) => {
                             state.set_install_step(step);
@@ -163,11 +285,17 @@ impl App {
                         WorkerMessage::SetStatus(s) => {
                             state.status_message = s;
                         }
-                        WorkerMessage::Completed => {
+                        WorkerMessage::Completed(action) => {
                             state.mark_completed();
+                            self.completion_action = action;
+                            self.countdown = Self::completion_countdown(action);
                         }
                         WorkerMessage::Failed(e) => {
                             state.mark_failed(&e);
+                            // 自动重试次数用完后由用户决定重试或重启
+                            if self.retry_count < MAX_AUTO_RETRIES {
+                                self.countdown = Some(Countdown::new(CountdownAction::Retry, RETRY_COUNTDOWN));
+                            }
                         }
                     }
                 }
@@ -186,6 +314,89 @@ impl eframe::App for App {
         // 处理消息
         self.process_messages();
 
+        // 倒计时与操作按钮
+        let (is_completed, is_failed) = self
+            .progress_state
+            .lock()
+            .map(|s| (s.is_completed, s.is_failed))
+            .unwrap_or((false, false));
+        if self.countdown.is_some() || is_completed || is_failed {
+            let mut action = None;
+            egui::TopBottomPanel::bottom("countdown_panel").show(ctx, |ui| {
+                ui.add_space(8.0);
+                if is_completed {
+                    let mut selected = self.completion_action;
+                    ui.horizontal(|ui| {
+                        ui.label("完成后:");
+                        egui::ComboBox::from_id_salt("completion_action")
+                            .selected_text(selected.label())
+                            .show_ui(ui, |ui| {
+                                for option in CompletionAction::ALL {
+                                    ui.selectable_value(&mut selected, option, option.label());
+                                }
+                            });
+                    });
+                    // 更改操作后重新开始倒计时
+                    if selected != self.completion_action {
+                        self.completion_action = selected;
+                        self.countdown = Self::completion_countdown(selected);
+                    }
+                    ui.add_space(4.0);
+                }
+                match self.countdown {
+                    Some(ref mut countdown) => {
+                        if countdown.show(ui) {
+                            action = Some(countdown.action);
+                        }
+                    }
+                    None if is_failed => {
+                        ui.horizontal(|ui| {
+                            let button_size = crate::ui::touch::button_size(100.0, 32.0);
+                            ui.add_space((ui.available_width() - button_size[0] * 2.0 - 10.0).max(0.0) / 2.0);
+                            if ui.add_sized(button_size, egui::Button::new("重试")).clicked() {
+                                action = Some(CountdownAction::Retry);
+                            }
+                            if ui.add_sized(button_size, egui::Button::new("重启")).clicked() {
+                                action = Some(CountdownAction::Finish(CompletionAction::RebootNow));
+                            }
+                        });
+                    }
+                    None => {}
+                }
+                ui.add_space(8.0);
+            });
+            if let Some(action) = action {
+                self.run_action(action);
+            }
+        }
+
+        // 超时没有进度时由用户决定继续等待还是重启回原系统
+        if let Some(watchdog) = self.watchdog.as_ref().filter(|watchdog| watchdog.is_hung()) {
+            egui::TopBottomPanel::bottom("watchdog_panel").show(ctx, |ui| {
+                ui.add_space(8.0);
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    format!(
+                        "已超过 {} 分钟没有进度，故障报告已保存到数据分区。慢速磁盘上可能需要更久，可以继续等待，或删除 PE 引导项后重启回原系统。",
+                        HANG_TIMEOUT.as_secs() / 60
+                    ),
+                );
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    let button_size = crate::ui::touch::button_size(120.0, 32.0);
+                    ui.add_space((ui.available_width() - button_size[0] * 2.0 - 10.0).max(0.0) / 2.0);
+                    if ui.add_sized(button_size, egui::Button::new("继续等待")).clicked() {
+                        watchdog.heartbeat();
+                    }
+                    if ui.add_sized(button_size, egui::Button::new("重启回原系统")).clicked() {
+                        let watchdog = watchdog.clone();
+                        thread::spawn(move || watchdog.reboot_to_original());
+                    }
+                });
+                ui.add_space(8.0);
+            });
+        }
+
         // 绘制界面
         egui::CentralPanel::default().show(ctx, |ui| {
             if let Ok(state) = self.progress_state.lock() {
@@ -198,13 +409,73 @@ impl eframe::App for App {
     }
 }
 
+/// 安装引擎步骤对应的界面步骤
+fn install_step_for(id: &str) -> Option<InstallStep> {
+    match id {
+        "convert" | "format" => Some(InstallStep::FormatPartition),
+        "apply" | "verify" => Some(InstallStep::ApplyImage),
+        "drivers" | "hwid" | "reinject" => Some(InstallStep::ImportDrivers),
+        "cab" => Some(InstallStep::InstallCabPackages),
+        "boot" => Some(InstallStep::RepairBoot),
+        "options" | "hooks" | "health" => Some(InstallStep::ApplyAdvancedOptions),
+        "unattend" => Some(InstallStep::GenerateUnattend),
+        "cleanup" => Some(InstallStep::Cleanup),
+        _ => None,
+    }
+}
+
+/// 将安装引擎事件转换为工作线程消息
+fn report_engine_event(tx: &Sender<WorkerMessage>, event: EngineEvent) {
+    let message = match event {
+        EngineEvent::StepStarted { id, .. } => match install_step_for(id) {
+            Some(step) => WorkerMessage::SetInstallStep(step),
+            None => return,
+        },
+        EngineEvent::Progress(p) => WorkerMessage::SetProgress(p),
+        EngineEvent::Status(s) => WorkerMessage::SetStatus(s),
+        EngineEvent::Retrying { name, attempt, max_attempts, error } => WorkerMessage::SetStatus(format!(
+            "{}失败（第 {}/{} 次），正在重试: {}",
+            name, attempt, max_attempts, error
+        )),
+        EngineEvent::StepSkipped { name, reason } => WorkerMessage::SetStatus(format!("跳过{}（{}）", name, reason)),
+        EngineEvent::Warning { message, .. } => WorkerMessage::SetStatus(message),
+        EngineEvent::RollingBack { name } => WorkerMessage::SetStatus(format!("正在回滚: {}", name)),
+        EngineEvent::RollbackFailed { name, error } => WorkerMessage::SetStatus(format!("回滚{}失败: {}", name, error)),
+    };
+    let _ = tx.send(message);
+}
+
+/// 创建 DISM 进度通道，进度转发为引擎事件（指定前缀时同时转发状态）
+///
+/// 发送端释放后转发线程结束
+fn forward_dism_progress(
+    ctx: &StepContext,
+    status_prefix: Option<&'static str>,
+) -> (Sender<DismProgress>, thread::JoinHandle<()>) {
+    let (progress_tx, progress_rx) = channel::<DismProgress>();
+    let sink = ctx.sink();
+    let handle = thread::spawn(move || {
+        while let Ok(progress) = progress_rx.recv() {
+            sink(EngineEvent::Progress(progress.percentage));
+            if let Some(prefix) = status_prefix {
+                sink(EngineEvent::Status(format!("{}: {}", prefix, progress.status)));
+            }
+        }
+    });
+    (progress_tx, handle)
+}
+
 /// 执行安装工作流
 fn execute_install_workflow(tx: Sender<WorkerMessage>) {
     use crate::core::bcdedit::BootManager;
     use crate::core::dism::Dism;
-    use crate::core::disk::DiskManager;
+    use crate::core::disk::{DiskConversion, DiskManager};
     use crate::core::ghost::Ghost;
+    use crate::core::install_engine::{InstallEngine, RetryPolicy, Step};
+    use crate::core::zst_pack;
     use crate::ui::advanced_options::apply_advanced_options;
+    use std::cell::RefCell;
+    use std::time::Duration;
 
     log::info!("========== 开始PE安装流程 ==========");
 
@@ -221,10 +492,10 @@ fn execute_install_workflow(tx: Sender<WorkerMessage>) {
     let _ = tx.send(WorkerMessage::SetStatus(format!("数据分区: {}", data_partition)));
 
     // 读取安装配置
-    let config = match ConfigFileManager::read_install_config(&data_partition) {
+    let mut config = match ConfigFileManager::read_install_config(&data_partition) {
         Ok(c) => c,
         Err(e) => {
-            let _ = tx.send(WorkerMessage::Failed(format!("读取配置失败: {}", e)));
+            let _ = tx.send(WorkerMessage::Failed(format!("读取配置失败: {:#}", e)));
             return;
         }
     };
@@ -238,273 +509,391 @@ fn execute_install_workflow(tx: Sender<WorkerMessage>) {
 
     // 构建完整镜像路径
     let data_dir = ConfigFileManager::get_data_dir(&data_partition);
-    let image_path = format!("{}\\{}", data_dir, config.image_path);
-
-    if !std::path::Path::new(&image_path).exists() {
-        let _ = tx.send(WorkerMessage::Failed(format!("镜像文件不存在: {}", image_path)));
-        return;
-    }
-
-    log::info!("完整镜像路径: {}", image_path);
-
-    // Step 1: 格式化分区
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::FormatPartition));
-    let _ = tx.send(WorkerMessage::SetStatus("正在格式化目标分区...".to_string()));
-
-    // 使用卷标参数（如果有配置的话）
-    let volume_label = if config.volume_label.is_empty() {
-        None
-    } else {
-        Some(config.volume_label.as_str())
-    };
-    
-    match DiskManager::format_partition_with_label(&target_partition, volume_label) {
-        Ok(_) => {
-            log::info!("分区格式化成功");
-            let _ = tx.send(WorkerMessage::SetProgress(100));
-        }
-        Err(e) => {
-            let _ = tx.send(WorkerMessage::Failed(format!("格式化分区失败: {}", e)));
-            return;
+    // 主程序暂存的 BitLocker 一次性密钥，安装流程结束时（包括失败）删除
+    let auto_unlock_keys = crate::core::auto_unlock::pending_keys(&data_dir);
+    // 主程序单独保存的共享密码等凭据，读取后立即删除
+    let secrets = crate::core::install_secrets::InstallSecrets::take(&data_dir);
+    config.unattend.password = secrets.unattend_password.clone();
+
+    // 导入桌面端携带的 Wi-Fi 配置，文件含明文密码，导入后立即删除
+    let wifi_dir = std::path::Path::new(&data_dir).join(crate::core::wifi::WIFI_PROFILE_DIR);
+    if let Some(wifi_dir) = crate::core::secure_dir::SensitiveDir::existing(wifi_dir) {
+        if config.wifi_profiles {
+            let _ = tx.send(WorkerMessage::SetStatus("正在连接 Wi-Fi...".to_string()));
+            match crate::core::wifi::import_profiles(wifi_dir.path()) {
+                Ok(count) => log::info!("已导入 {} 个 Wi-Fi 配置", count),
+                Err(e) => log::warn!("导入 Wi-Fi 配置失败: {:#}", e),
+            }
         }
     }
 
-    // Step 2: 释放镜像
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::ApplyImage));
-    let _ = tx.send(WorkerMessage::SetStatus("正在释放系统镜像...".to_string()));
-
-    let apply_dir = format!("{}\\", target_partition);
-
-    // 创建进度通道
-    let (progress_tx, progress_rx) = channel::<DismProgress>();
-    let tx_clone = tx.clone();
-
-    // 启动进度监控线程
-    let progress_handle = thread::spawn(move || {
-        while let Ok(progress) = progress_rx.recv() {
-            let _ = tx_clone.send(WorkerMessage::SetProgress(progress.percentage));
-        }
-    });
-
-    let apply_result = if config.is_gho {
-        // GHO镜像使用Ghost
-        let ghost = Ghost::new();
-        if !ghost.is_available() {
-            let _ = tx.send(WorkerMessage::Failed("Ghost工具不可用".to_string()));
-            return;
+    // 网络镜像源：连接 SMB 共享或下载到数据目录
+    let image_path = match config.network_source() {
+        Some(source) => {
+            log::info!("网络镜像源: {}", config.image_source);
+            let _ = tx.send(WorkerMessage::SetStatus(format!("正在准备网络镜像: {}", source.file_name())));
+            let on_progress = |percentage: u8| {
+                let _ = tx.send(WorkerMessage::SetProgress(percentage));
+            };
+            match crate::core::network_source::prepare(&source, &config.share_user, &secrets.share_password, &data_dir, on_progress) {
+                Ok(path) => path,
+                Err(e) => {
+                    let _ = tx.send(WorkerMessage::Failed(format!("准备网络镜像失败: {:#}", e)));
+                    return;
+                }
+            }
         }
-
-        let partitions = DiskManager::get_partitions().unwrap_or_default();
-        ghost.restore_image_to_letter(&image_path, &target_partition, &partitions, Some(progress_tx))
-    } else {
-        // WIM/ESD使用DISM
-        let dism = Dism::new();
-        dism.apply_image(&image_path, &apply_dir, config.volume_index, Some(progress_tx))
+        None => format!("{}\\{}", data_dir, config.image_path),
     };
 
-    // 等待进度监控线程结束
-    let _ = progress_handle.join();
-
-    if let Err(e) = apply_result {
-        let _ = tx.send(WorkerMessage::Failed(format!("释放镜像失败: {}", e)));
+    if !std::path::Path::new(&image_path).exists() {
+        let _ = tx.send(WorkerMessage::Failed(format!("镜像文件不存在: {}", image_path)));
         return;
     }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
 
-    // Step 3: 导入驱动
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::ImportDrivers));
+    log::info!("完整镜像路径: {}", image_path);
 
-    // 根据 driver_action_mode 决定是否导入驱动
-    // 0 = 无, 1 = 仅保存（不导入）, 2 = 自动导入
-    let driver_path = format!("{}\\drivers", data_dir);
-    let driver_path_exists = std::path::Path::new(&driver_path).exists();
-    
-    if config.should_import_drivers() && driver_path_exists {
-        let _ = tx.send(WorkerMessage::SetStatus("正在导入驱动...".to_string()));
-        
-        // 创建进度通道
-        let (driver_progress_tx, driver_progress_rx) = channel::<DismProgress>();
-        let tx_driver = tx.clone();
-        
-        // 启动进度监控线程
-        let driver_progress_handle = thread::spawn(move || {
-            while let Ok(progress) = driver_progress_rx.recv() {
-                let _ = tx_driver.send(WorkerMessage::SetProgress(progress.percentage));
-                let _ = tx_driver.send(WorkerMessage::SetStatus(format!("导入驱动: {}", progress.status)));
-            }
-        });
-        
-        let dism = Dism::new();
-        match dism.add_drivers_offline_with_progress(&apply_dir, &driver_path, Some(driver_progress_tx)) {
-            Ok(_) => {
-                log::info!("驱动导入成功");
-            }
-            Err(e) => {
-                log::warn!("导入驱动失败: {}", e);
-                // 不中断安装流程，继续执行
-            }
-        }
-        
-        // 等待进度监控线程结束
-        let _ = driver_progress_handle.join();
-        
-        // 同时检查驱动目录中是否有 CAB 文件并安装
-        let cab_files_in_driver_dir = find_cab_files_in_directory(&driver_path);
-        if !cab_files_in_driver_dir.is_empty() {
-            log::info!("在驱动目录中发现 {} 个 CAB 文件，将一并安装", cab_files_in_driver_dir.len());
-            let _ = tx.send(WorkerMessage::SetStatus(format!(
-                "正在安装驱动目录中的 {} 个 CAB 更新包...", 
-                cab_files_in_driver_dir.len()
-            )));
-            
-            // 创建进度通道
-            let (cab_progress_tx, cab_progress_rx) = channel::<DismProgress>();
-            let tx_cab = tx.clone();
-            
-            // 启动进度监控线程
-            let cab_progress_handle = thread::spawn(move || {
-                while let Ok(progress) = cab_progress_rx.recv() {
-                    let _ = tx_cab.send(WorkerMessage::SetProgress(progress.percentage));
-                    let _ = tx_cab.send(WorkerMessage::SetStatus(format!("安装CAB: {}", progress.status)));
+    let apply_dir = format!("{}\\", target_partition);
+    // 从目标分区现有系统导出的驱动，释放镜像后重新注入
+    let harvested_dir = format!("{}\\harvested_drivers", data_dir);
+    // zstd 驱动/更新包的解压暂存目录，清理步骤中删除
+    let pack_staging = std::path::PathBuf::from(format!("{}\\LetRecovery_Packs", target_partition));
+    let boot_manager = BootManager::new();
+    // 修复引导前导出的 BCD（回滚时恢复，保留 PE 引导项以便重试）
+    let bcd_backup = RefCell::new(None);
+
+    let engine = InstallEngine::new()
+        .step(
+            Step::new("harvest", "导出现有系统驱动", |ctx| {
+                if !config.harvest_drivers {
+                    return Ok(());
                 }
-            });
-            
-            let dism = Dism::new();
-            match dism.add_packages_offline_from_dir(&apply_dir, &driver_path, Some(cab_progress_tx)) {
-                Ok((success, fail)) => {
-                    log::info!("驱动目录中的CAB安装完成: {} 成功, {} 失败", success, fail);
+                ctx.status("正在导出目标分区现有系统的驱动...");
+                let count = Dism::new().harvest_drivers(&target_partition, &harvested_dir)?;
+                log::info!("已从现有系统导出 {} 个驱动包", count);
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            // 固件为 UEFI 而目标磁盘为 MBR 时，格式化前用 mbr2gpt 无损转换（需原系统可引导）
+            Step::new("convert", "转换分区表", |ctx| {
+                let plan = DiskManager::plan_for_firmware(&target_partition, DiskManager::detect_uefi_mode())?;
+                if plan.conversion == DiskConversion::None {
+                    return Ok(());
                 }
-                Err(e) => {
-                    log::warn!("驱动目录中的CAB安装失败: {}", e);
+                ctx.status(plan.describe());
+                DiskManager::plan_and_apply(&plan)?;
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            Step::new("format", "格式化分区", |ctx| {
+                ctx.status("正在格式化目标分区...");
+                // 使用卷标参数（如果有配置的话）
+                let volume_label = if config.volume_label.is_empty() {
+                    None
+                } else {
+                    Some(config.volume_label.as_str())
+                };
+                DiskManager::format_partition_with_label(&target_partition, volume_label)?;
+                log::info!("分区格式化成功");
+                Ok(())
+            })
+            // 卷可能被短暂占用，稍后重试
+            .retry(RetryPolicy::times(3, Duration::from_secs(3))),
+        )
+        .step(
+            Step::new("apply", "释放系统镜像", |ctx| {
+                ctx.status("正在释放系统镜像...");
+                if config.is_gho {
+                    // GHO镜像使用Ghost
+                    let ghost = Ghost::new();
+                    if !ghost.is_available() {
+                        anyhow::bail!("Ghost工具不可用");
+                    }
+                    let partitions = DiskManager::get_partitions().unwrap_or_default();
+                    let (progress_tx, progress_handle) = forward_dism_progress(ctx, None);
+                    let result = ghost.restore_image_to_letter(&image_path, config.gho_partition, &target_partition, &partitions, Some(progress_tx));
+                    let _ = progress_handle.join();
+                    result
+                } else {
+                    // WIM/ESD使用DISM
+                    let (progress_tx, progress_handle) = forward_dism_progress(ctx, None);
+                    let result = Dism::with_backend(config.image_backend).apply_image(
+                        &image_path,
+                        &apply_dir,
+                        config.volume_index,
+                        Some(progress_tx),
+                    );
+                    let _ = progress_handle.join();
+                    result
                 }
-            }
-            
-            let _ = cab_progress_handle.join();
-        }
-    } else if config.should_import_drivers() && !driver_path_exists {
-        log::info!("驱动目录不存在，跳过驱动导入: {}", driver_path);
-        let _ = tx.send(WorkerMessage::SetStatus("跳过驱动导入（目录不存在）".to_string()));
-    } else if config.has_driver_data() {
-        // SaveOnly 模式：驱动已保存但不导入
-        let _ = tx.send(WorkerMessage::SetStatus("跳过驱动导入（仅保存模式）".to_string()));
-        log::info!("驱动操作模式为仅保存，跳过驱动导入");
-    } else {
-        let _ = tx.send(WorkerMessage::SetStatus("跳过驱动导入".to_string()));
-        log::info!("驱动操作模式为无，跳过驱动导入");
-    }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // Step 4: 安装CAB更新包
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::InstallCabPackages));
-
-    if config.install_cab_packages {
-        let cab_path = format!("{}\\updates", data_dir);
-        if std::path::Path::new(&cab_path).exists() {
-            let _ = tx.send(WorkerMessage::SetStatus("正在安装更新包...".to_string()));
-            
-            // 创建进度通道
-            let (cab_progress_tx, cab_progress_rx) = channel::<DismProgress>();
-            let tx_cab = tx.clone();
-            
-            // 启动进度监控线程
-            let cab_progress_handle = thread::spawn(move || {
-                while let Ok(progress) = cab_progress_rx.recv() {
-                    let _ = tx_cab.send(WorkerMessage::SetProgress(progress.percentage));
-                    let _ = tx_cab.send(WorkerMessage::SetStatus(format!("安装更新: {}", progress.status)));
+            })
+            .after(&["format"]),
+        )
+        .step(
+            Step::new("verify", "校验已释放的文件", |ctx| {
+                if config.is_gho || config.apply_verify == crate::core::apply_verify::ApplyVerifyMode::Off {
+                    return Ok(());
                 }
-            });
-            
-            let dism = Dism::new();
-            match dism.add_packages_offline_from_dir(&apply_dir, &cab_path, Some(cab_progress_tx)) {
-                Ok((success, fail)) => {
-                    log::info!("CAB更新包安装完成: {} 成功, {} 失败", success, fail);
-                    let _ = tx.send(WorkerMessage::SetStatus(
-                        format!("更新包安装完成: {} 成功, {} 失败", success, fail)
-                    ));
+                ctx.status(config.apply_verify.label());
+                let on_progress = |done: usize, total: usize| ctx.progress((done * 100 / total.max(1)) as u8);
+                match crate::core::apply_verify::verify_applied_image(
+                    &image_path,
+                    config.volume_index,
+                    &apply_dir,
+                    config.apply_verify,
+                    &on_progress,
+                ) {
+                    Ok(report) if report.is_ok() => {
+                        ctx.status(report.summary());
+                        Ok(())
+                    }
+                    Ok(report) => anyhow::bail!("{}", report.summary()),
+                    Err(e) => {
+                        ctx.warn(format!("无法进行释放后校验: {:#}", e));
+                        Ok(())
+                    }
                 }
-                Err(e) => {
-                    log::warn!("CAB更新包安装失败: {}", e);
-                    // 不中断安装流程，继续执行
+            })
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("drivers", "导入驱动", |ctx| {
+                // 根据 driver_action_mode 决定是否导入驱动
+                // 0 = 无, 1 = 仅保存（不导入）, 2 = 自动导入
+                let driver_path = format!("{}\\drivers", data_dir);
+                let driver_path_exists = std::path::Path::new(&driver_path).exists();
+
+                if config.should_import_drivers() && driver_path_exists {
+                    ctx.status("正在导入驱动...");
+
+                    // 解压数据目录中的 zstd 驱动包，与原目录依次导入
+                    let driver_dirs = zst_pack::expand_packs(&driver_path, &pack_staging.join("drivers"));
+                    for driver_dir in &driver_dirs {
+                        let (progress_tx, progress_handle) = forward_dism_progress(ctx, Some("导入驱动"));
+                        match Dism::new().add_drivers_offline_with_progress(&apply_dir, driver_dir, Some(progress_tx)) {
+                            Ok(results) => report_driver_results(ctx, &results),
+                            // 不中断安装流程，继续执行
+                            Err(e) => ctx.warn(format!("导入驱动失败: {}", e)),
+                        }
+                        let _ = progress_handle.join();
+
+                        // 同时检查驱动目录中是否有 CAB 文件并安装
+                        let cab_files_in_driver_dir = find_cab_files_in_directory(driver_dir);
+                        if !cab_files_in_driver_dir.is_empty() {
+                            log::info!("在驱动目录中发现 {} 个 CAB 文件，将一并安装", cab_files_in_driver_dir.len());
+                            ctx.status(format!(
+                                "正在安装驱动目录中的 {} 个 CAB 更新包...",
+                                cab_files_in_driver_dir.len()
+                            ));
+
+                            let (progress_tx, progress_handle) = forward_dism_progress(ctx, Some("安装CAB"));
+                            match Dism::new().add_packages_offline_from_dir(&apply_dir, driver_dir, Some(progress_tx)) {
+                                Ok((success, fail)) => {
+                                    log::info!("驱动目录中的CAB安装完成: {} 成功, {} 失败", success, fail);
+                                }
+                                Err(e) => ctx.warn(format!("驱动目录中的CAB安装失败: {}", e)),
+                            }
+                            let _ = progress_handle.join();
+                        }
+                    }
+                } else if config.should_import_drivers() {
+                    log::info!("驱动目录不存在，跳过驱动导入: {}", driver_path);
+                    ctx.status("跳过驱动导入（目录不存在）");
+                } else if config.has_driver_data() {
+                    // SaveOnly 模式：驱动已保存但不导入
+                    log::info!("驱动操作模式为仅保存，跳过驱动导入");
+                    ctx.status("跳过驱动导入（仅保存模式）");
+                } else {
+                    log::info!("驱动操作模式为无，跳过驱动导入");
+                    ctx.status("跳过驱动导入");
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("hwid", "注入网卡/存储驱动", |ctx| {
+                // 自动导入模式下已随 drivers 目录一并导入
+                let hwid_dir = format!("{}\\drivers\\hwid", data_dir);
+                if !config.hwid_drivers || config.should_import_drivers() || !std::path::Path::new(&hwid_dir).exists() {
+                    return Ok(());
+                }
+                ctx.status("正在注入按硬件 ID 下载的驱动...");
+                let (progress_tx, progress_handle) = forward_dism_progress(ctx, Some("注入驱动"));
+                let result = Dism::new().add_drivers_offline_with_progress(&apply_dir, &hwid_dir, Some(progress_tx));
+                let _ = progress_handle.join();
+                report_driver_results(ctx, &result?);
+                Ok(())
+            })
+            .optional()
+            .after(&["drivers"]),
+        )
+        .step(
+            Step::new("reinject", "重新注入现有系统驱动", |ctx| {
+                if !config.harvest_drivers || !std::path::Path::new(&harvested_dir).exists() {
+                    return Ok(());
+                }
+                ctx.status("正在重新注入现有系统的驱动...");
+                let (progress_tx, progress_handle) = forward_dism_progress(ctx, Some("注入驱动"));
+                let result = Dism::new().add_drivers_offline_with_progress(&apply_dir, &harvested_dir, Some(progress_tx));
+                let _ = progress_handle.join();
+                report_driver_results(ctx, &result?);
+                Ok(())
+            })
+            .optional()
+            .after(&["harvest", "apply"]),
+        )
+        .step(
+            Step::new("cab", "安装更新包", |ctx| {
+                if !config.install_cab_packages {
+                    log::info!("未启用CAB更新包安装");
+                    ctx.status("跳过更新包安装");
+                    return Ok(());
+                }
+                let cab_path = format!("{}\\updates", data_dir);
+                if !std::path::Path::new(&cab_path).exists() {
+                    log::info!("更新包目录不存在，跳过CAB安装: {}", cab_path);
+                    ctx.status("跳过更新包安装（目录不存在）");
+                    return Ok(());
                 }
-            }
-            
-            // 等待进度监控线程结束
-            let _ = cab_progress_handle.join();
-        } else {
-            log::info!("更新包目录不存在，跳过CAB安装: {}", cab_path);
-            let _ = tx.send(WorkerMessage::SetStatus("跳过更新包安装（目录不存在）".to_string()));
-        }
-    } else {
-        let _ = tx.send(WorkerMessage::SetStatus("跳过更新包安装".to_string()));
-        log::info!("未启用CAB更新包安装");
-    }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // Step 5: 修复引导
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::RepairBoot));
-    let _ = tx.send(WorkerMessage::SetStatus("正在修复引导...".to_string()));
-
-    let boot_manager = BootManager::new();
-    let use_uefi = DiskManager::detect_uefi_mode();
-
-    if let Err(e) = boot_manager.repair_boot_advanced(&target_partition, use_uefi) {
-        let _ = tx.send(WorkerMessage::Failed(format!("修复引导失败: {}", e)));
-        return;
-    }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // Step 6: 应用高级选项
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::ApplyAdvancedOptions));
-    let _ = tx.send(WorkerMessage::SetStatus("正在应用高级选项...".to_string()));
-
-    if let Err(e) = apply_advanced_options(&target_partition, &config) {
-        log::warn!("应用高级选项失败: {}", e);
-    }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // Step 7: 生成无人值守配置
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::GenerateUnattend));
-
-    if config.unattended {
-        let _ = tx.send(WorkerMessage::SetStatus("正在生成无人值守配置...".to_string()));
-        if let Err(e) = generate_unattend_xml(&target_partition, &config) {
-            log::warn!("生成无人值守配置失败: {}", e);
-        }
-    } else {
-        let _ = tx.send(WorkerMessage::SetStatus("跳过无人值守配置".to_string()));
-    }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // Step 8: 清理临时文件
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::Cleanup));
-    let _ = tx.send(WorkerMessage::SetStatus("正在清理临时文件...".to_string()));
 
-    ConfigFileManager::cleanup_all(&data_partition, &target_partition);
-    let _ = tx.send(WorkerMessage::SetProgress(50));
+                ctx.status("正在安装更新包...");
+                let update_dirs = zst_pack::expand_packs(&cab_path, &pack_staging.join("updates"));
+                for update_dir in &update_dirs {
+                    let (progress_tx, progress_handle) = forward_dism_progress(ctx, Some("安装更新"));
+                    match Dism::new().add_packages_offline_from_dir(&apply_dir, update_dir, Some(progress_tx)) {
+                        Ok((success, fail)) => {
+                            log::info!("CAB更新包安装完成: {} 成功, {} 失败", success, fail);
+                            ctx.status(format!("更新包安装完成: {} 成功, {} 失败", success, fail));
+                        }
+                        // 不中断安装流程，继续执行
+                        Err(e) => ctx.warn(format!("CAB更新包安装失败: {}", e)),
+                    }
+                    let _ = progress_handle.join();
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("boot", "修复引导", |ctx| {
+                ctx.status("正在修复引导...");
+                if bcd_backup.borrow().is_none() {
+                    match boot_manager.export_bcd() {
+                        Ok(path) => *bcd_backup.borrow_mut() = Some(path),
+                        Err(e) => log::warn!("修复引导前备份 BCD 失败: {:#}", e),
+                    }
+                }
+                boot_manager.repair_boot_advanced(&target_partition, DiskManager::detect_uefi_mode())
+            })
+            .retry(RetryPolicy::times(2, Duration::from_secs(2)))
+            .after(&["apply"])
+            .rollback(|| match bcd_backup.borrow().as_deref() {
+                Some(path) => boot_manager.import_bcd(path),
+                None => Ok(()),
+            }),
+        )
+        .step(
+            Step::new("options", "应用高级选项", |ctx| {
+                ctx.status("正在应用高级选项...");
+                if let Err(e) = apply_advanced_options(&target_partition, &config) {
+                    ctx.warn(format!("应用高级选项失败: {}", e));
+                }
 
-    // 清理自动创建的数据分区并扩展目标分区
-    let _ = tx.send(WorkerMessage::SetStatus("正在清理自动创建的分区...".to_string()));
-    match DiskManager::cleanup_auto_created_partition_and_extend(&target_partition) {
-        Ok(_) => {
-            log::info!("自动创建分区清理完成");
+                // 其他 BitLocker 数据分区在新系统首次启动时解锁
+                match crate::core::auto_unlock::install_pending(&data_dir, &target_partition) {
+                    Ok(true) => ctx.status("已安装 BitLocker 数据分区解锁脚本"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装 BitLocker 数据分区解锁脚本失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["boot"]),
+        )
+        .step(
+            Step::new("hooks", "安装首次启动脚本", |ctx| {
+                let scripts_dir = std::path::Path::new(&data_dir).join(crate::core::script_hooks::SCRIPTS_DIR);
+                // 未配置时按文件名顺序执行 scripts 文件夹中的全部脚本
+                let hooks = if config.script_hooks.is_empty() {
+                    crate::core::script_hooks::discover(&scripts_dir, &[])
+                } else {
+                    config.script_hooks.clone()
+                };
+                match crate::core::script_hooks::install_hooks(&target_partition, &scripts_dir, &hooks) {
+                    Ok(0) => {}
+                    Ok(count) => ctx.status(format!("已安装 {} 个首次启动脚本", count)),
+                    Err(e) => ctx.warn(format!("安装首次启动脚本失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("health", "安装首次启动健康检查", |ctx| {
+                match crate::core::health_check::install_agent(&target_partition, Some(&data_partition), &config.health_check) {
+                    Ok(true) => ctx.status("已安装首次启动健康检查"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装首次启动健康检查失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("unattend", "生成无人值守配置", |ctx| {
+                if !config.unattended {
+                    ctx.status("跳过无人值守配置");
+                    return Ok(());
+                }
+                ctx.status("正在生成无人值守配置...");
+                generate_unattend_xml(&target_partition, &config)
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("cleanup", "清理临时文件", |ctx| {
+                ctx.status("正在清理临时文件...");
+                let _ = std::fs::remove_dir_all(&pack_staging);
+                ConfigFileManager::cleanup_all(&data_partition, &target_partition);
+                ctx.progress(50);
+
+                // 清理自动创建的数据分区并扩展目标分区
+                ctx.status("正在清理自动创建的分区...");
+                DiskManager::cleanup_auto_created_partition_and_extend(&target_partition, &config.data_partition_id)?;
+                log::info!("自动创建分区清理完成");
+                Ok(())
+            })
+            .optional(),
+        );
+
+    let event_tx = tx.clone();
+    let result = engine.run(move |event| report_engine_event(&event_tx, event));
+    drop(auto_unlock_keys);
+    match result {
+        Ok(()) => {
+            let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::Complete));
+            let _ = tx.send(WorkerMessage::Completed(config.completion_action()));
+            log::info!("========== PE安装流程完成 ==========");
         }
-        Err(e) => {
-            // 不中断安装流程，只记录警告
-            log::warn!("清理自动创建分区失败: {}", e);
+        Err(failure) => {
+            let message = if failure.rolled_back {
+                failure.to_string()
+            } else {
+                format!("{}（回滚未全部完成）", failure)
+            };
+            let _ = tx.send(WorkerMessage::Failed(message));
         }
     }
-    let _ = tx.send(WorkerMessage::SetProgress(100));
-
-    // 完成
-    let _ = tx.send(WorkerMessage::SetInstallStep(InstallStep::Complete));
-    let _ = tx.send(WorkerMessage::Completed);
-
-    log::info!("========== PE安装流程完成 ==========");
-
-    // PE环境下安装完成后强制重启
-    log::info!("即将重启...");
-    std::thread::sleep(std::time::Duration::from_secs(3));
-    reboot_pe();
 }
 
 /// 执行备份工作流
@@ -534,15 +923,15 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
     let config = match ConfigFileManager::read_backup_config(&data_partition) {
         Ok(c) => c,
         Err(e) => {
-            let _ = tx.send(WorkerMessage::Failed(format!("读取配置失败: {}", e)));
+            let _ = tx.send(WorkerMessage::Failed(format!("读取配置失败: {:#}", e)));
             return;
         }
     };
 
     log::info!("源分区: {}", config.source_partition);
     log::info!("保存路径: {}", config.save_path);
-    log::info!("备份格式: {:?}", config.format);
-    if config.format == BackupFormat::Swm {
+    log::info!("备份格式: {:?}", config.backup_format());
+    if config.backup_format() == BackupFormat::Swm {
         log::info!("SWM分卷大小: {} MB", config.swm_split_size);
     }
     let _ = tx.send(WorkerMessage::SetProgress(100));
@@ -554,7 +943,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
     // Step 2: 执行备份
     let _ = tx.send(WorkerMessage::SetBackupStep(BackupStep::CaptureImage));
     
-    let capture_dir = format!("{}\\", source_partition);
+    let capture_dir = crate::core::disk::volume_root(&source_partition);
 
     // 创建进度通道
     let (progress_tx, progress_rx) = channel::<DismProgress>();
@@ -566,7 +955,8 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
         }
     });
 
-    let backup_result = match config.format {
+    let size_before = crate::core::backup_catalog::image_size(&config.save_path);
+    let backup_result = match config.backup_format() {
         BackupFormat::Gho => {
             // GHO格式使用Ghost
             let _ = tx.send(WorkerMessage::SetStatus("正在使用Ghost备份系统...".to_string()));
@@ -590,6 +980,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
                     &capture_dir,
                     &config.name,
                     &config.description,
+                    &config.capture,
                     Some(progress_tx),
                 )
             } else {
@@ -598,6 +989,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
                     &capture_dir,
                     &config.name,
                     &config.description,
+                    &config.capture,
                     Some(progress_tx),
                 )
             }
@@ -612,19 +1004,21 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
                 &config.name,
                 &config.description,
                 config.swm_split_size,
+                &config.capture,
                 Some(progress_tx),
             )
         }
         BackupFormat::Wim => {
             // 标准WIM格式
             let _ = tx.send(WorkerMessage::SetStatus("正在执行系统备份...".to_string()));
-            let dism = Dism::new();
+            let dism = Dism::with_backend(config.image_backend);
             if config.incremental && std::path::Path::new(&config.save_path).exists() {
                 dism.append_image(
                     &config.save_path,
                     &capture_dir,
                     &config.name,
                     &config.description,
+                    &config.capture,
                     Some(progress_tx),
                 )
             } else {
@@ -633,6 +1027,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
                     &capture_dir,
                     &config.name,
                     &config.description,
+                    &config.capture,
                     Some(progress_tx),
                 )
             }
@@ -653,7 +1048,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
     let _ = tx.send(WorkerMessage::SetStatus("正在验证备份文件...".to_string()));
 
     // 对于SWM格式，检查第一个分卷文件
-    let verify_path = if config.format == BackupFormat::Swm {
+    let verify_path = if config.backup_format() == BackupFormat::Swm {
         // SWM的第一个文件可能是 xxx.swm 或 xxx.swm
         config.save_path.clone()
     } else {
@@ -664,6 +1059,17 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
         let _ = tx.send(WorkerMessage::Failed("备份文件验证失败".to_string()));
         return;
     }
+
+    // WIM/ESD 记录还原点，供桌面端选择还原
+    if let Err(e) = crate::core::backup_catalog::record_backup(
+        &config.save_path,
+        &source_partition,
+        &config.name,
+        &config.description,
+        size_before,
+    ) {
+        log::warn!("[BACKUP] 记录还原点失败: {:#}", e);
+    }
     let _ = tx.send(WorkerMessage::SetProgress(100));
 
     // Step 4: 恢复引导
@@ -686,14 +1092,9 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
 
     // 完成
     let _ = tx.send(WorkerMessage::SetBackupStep(BackupStep::Complete));
-    let _ = tx.send(WorkerMessage::Completed);
+    let _ = tx.send(WorkerMessage::Completed(CompletionAction::RebootCountdown));
 
     log::info!("========== PE备份流程完成 ==========");
-
-    // 自动重启
-    log::info!("即将重启...");
-    std::thread::sleep(std::time::Duration::from_secs(3));
-    reboot_pe();
 }
 
 /// 生成无人值守XML
@@ -702,7 +1103,7 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
 /// - Windows 10/11: 完整的 OOBE 跳过设置
 /// - Windows 7/8/8.1: 兼容的简化配置
 /// 
-/// 同时自动检测目标系统架构（x86/amd64/arm64）
+/// 同时自动检测目标系统架构（x86/amd64/arm64）和语言
 /// 
 /// 配置内容包括：
 /// - windowsPE pass: 基本设置
@@ -710,15 +1111,11 @@ fn execute_backup_workflow(tx: Sender<WorkerMessage>) {
 /// - oobeSystem pass: OOBE设置、用户账户、首次登录命令
 fn generate_unattend_xml(target_partition: &str, config: &crate::core::config::InstallConfig) -> anyhow::Result<()> {
     use crate::ui::advanced_options::get_scripts_dir_name;
-    use crate::core::system_utils::{get_file_version, get_offline_system_architecture};
+    use crate::core::image_language::TargetLanguage;
+    use crate::core::system_utils::get_offline_system_architecture;
+    use crate::core::unattend::{write_unattend, UnattendBuilder, UnattendTarget};
     use std::path::Path;
     
-    let username = if config.custom_username.is_empty() { 
-        "User".to_string() 
-    } else { 
-        config.custom_username.clone() 
-    };
-
     let scripts_dir = get_scripts_dir_name();
 
     // 检测目标系统架构
@@ -727,305 +1124,36 @@ fn generate_unattend_xml(target_partition: &str, config: &crate::core::config::I
     log::info!("[UNATTEND] 检测到目标系统架构: {}", arch_str);
 
     // 通过 ntdll.dll 文件版本检测目标系统版本
-    // Windows 7: 6.1.x, Windows 8: 6.2.x, Windows 8.1: 6.3.x, Windows 10/11: 10.0.x
-    let ntdll_path = Path::new(target_partition).join("Windows").join("System32").join("ntdll.dll");
-    let (is_win7, is_win8) = match get_file_version(&ntdll_path) {
-        Some((major, minor, build, _)) => {
-            log::info!("[UNATTEND] 检测到目标系统版本 (ntdll.dll): {}.{}.{}", major, minor, build);
-            
-            let is_win7 = major == 6 && minor == 1;
-            let is_win8 = major == 6 && (minor == 2 || minor == 3);
-            (is_win7, is_win8)
-        }
-        None => {
-            log::warn!("[UNATTEND] 无法读取 ntdll.dll 版本: {:?}, 默认使用 Win10/11 配置", ntdll_path);
-            (false, false)
-        }
-    };
-
-    // 构建 FirstLogonCommands
-    let mut first_logon_commands = String::new();
-    let mut order = 1;
-
-    // 首次登录脚本（如果存在）
-    first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>cmd /c if exist %SystemDrive%\{}\firstlogon.bat call %SystemDrive%\{}\firstlogon.bat</CommandLine>
-                    <Description>Run first login script</Description>
-                </SynchronousCommand>"#, order, scripts_dir, scripts_dir));
-    order += 1;
+    let target = UnattendTarget::detect(target_partition);
+
+    let mut builder = UnattendBuilder::new(arch_str, target)
+        .username(&config.custom_username)
+        .settings(&config.unattend)
+        .win11_bypass(config.win11_bypass())
+        .language(TargetLanguage::detect(target_partition))
+        .deploy_command(
+            format!(r"cmd /c if exist %SystemDrive%\{0}\deploy.bat call %SystemDrive%\{0}\deploy.bat", scripts_dir),
+            "Run custom deploy script",
+        )
+        .first_logon_command(
+            format!(r"cmd /c if exist %SystemDrive%\{0}\firstlogon.bat call %SystemDrive%\{0}\firstlogon.bat", scripts_dir),
+            "Run first login script",
+        );
 
     // 如果需要删除UWP应用（仅 Win10/11 支持）
-    if config.remove_uwp_apps && !is_win7 && !is_win8 {
-        first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>powershell -ExecutionPolicy Bypass -File %SystemDrive%\{}\remove_uwp.ps1</CommandLine>
-                    <Description>Remove preinstalled UWP apps</Description>
-                </SynchronousCommand>"#, order, scripts_dir));
-        order += 1;
+    if config.remove_uwp_apps && target == UnattendTarget::Win10 {
+        builder = builder.first_logon_command(
+            format!(r"powershell -ExecutionPolicy Bypass -File %SystemDrive%\{}\remove_uwp.ps1", scripts_dir),
+            "Remove preinstalled UWP apps",
+        );
     }
 
     // 清理脚本目录（最后执行）
-    first_logon_commands.push_str(&format!(r#"
-                <SynchronousCommand wcm:action="add">
-                    <Order>{}</Order>
-                    <CommandLine>cmd /c rd /s /q %SystemDrive%\{}</CommandLine>
-                    <Description>Cleanup scripts directory</Description>
-                </SynchronousCommand>"#, order, scripts_dir));
-
-    // 根据系统版本生成不同的 XML 内容
-    let xml_content = if is_win7 {
-        // Windows 7 专用无人值守配置
-        // Win7 不支持: HideOnlineAccountScreens, HideWirelessSetupInOOBE, SkipMachineOOBE, SkipUserOOBE, HideLocalAccountScreen, HideOEMRegistrationScreen(家庭版)
-        generate_win7_unattend_xml(&username, &scripts_dir, &first_logon_commands, arch_str)
-    } else if is_win8 {
-        // Windows 8/8.1 无人值守配置
-        // Win8 支持部分 Win10 的选项，但不支持所有
-        generate_win8_unattend_xml(&username, &scripts_dir, &first_logon_commands, arch_str)
-    } else {
-        // Windows 10/11 无人值守配置（默认）
-        generate_win10_unattend_xml(&username, &scripts_dir, &first_logon_commands, arch_str)
-    };
-
-    let panther_dir = format!("{}\\Windows\\Panther", target_partition);
-    std::fs::create_dir_all(&panther_dir)?;
+    builder = builder.first_logon_command(
+        format!(r"cmd /c rd /s /q %SystemDrive%\{}", scripts_dir),
+        "Cleanup scripts directory",
+    );
 
-    let unattend_path = format!("{}\\unattend.xml", panther_dir);
-    std::fs::write(&unattend_path, &xml_content)?;
-    log::info!("[UNATTEND] 已写入: {} ({})", unattend_path, 
-        if is_win7 { "Win7配置" } else if is_win8 { "Win8配置" } else { "Win10/11配置" });
-
-    // 同时写入到 Sysprep 目录
-    let sysprep_dir = format!("{}\\Windows\\System32\\Sysprep", target_partition);
-    if std::path::Path::new(&sysprep_dir).exists() {
-        let sysprep_unattend = format!("{}\\unattend.xml", sysprep_dir);
-        let _ = std::fs::write(&sysprep_unattend, &xml_content);
-        log::info!("[UNATTEND] 已写入: {}", sysprep_unattend);
-    }
-
-    Ok(())
-}
-
-/// 生成 Windows 7 专用的无人值守配置
-/// 
-/// Win7 的 OOBE 配置与 Win10/11 有显著差异：
-/// - 不支持 HideOnlineAccountScreens
-/// - 不支持 HideWirelessSetupInOOBE  
-/// - 不支持 SkipMachineOOBE / SkipUserOOBE
-/// - 不支持 HideLocalAccountScreen
-/// - 不支持 HideOEMRegistrationScreen（家庭版不支持）
-/// - 需要设置 NetworkLocation 来跳过网络位置选择
-fn generate_win7_unattend_xml(username: &str, scripts_dir: &str, first_logon_commands: &str, arch: &str) -> String {
-    // Win7 使用最小化的OOBE配置以确保兼容所有版本（包括家庭版）
-    format!(r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="specialize">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <ComputerName>*</ComputerName>
-        </component>
-        <component name="Microsoft-Windows-Deployment" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <RunSynchronous>
-                <RunSynchronousCommand wcm:action="add">
-                    <Order>1</Order>
-                    <Path>cmd /c if exist %SystemDrive%\{scripts_dir}\deploy.bat call %SystemDrive%\{scripts_dir}\deploy.bat</Path>
-                    <Description>Run custom deploy script</Description>
-                </RunSynchronousCommand>
-            </RunSynchronous>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{username}</DisplayName>
-                        <Group>Administrators</Group>
-                        <Name>{username}</Name>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <LogonCount>1</LogonCount>
-                <Username>{username}</Username>
-            </AutoLogon>
-            <FirstLogonCommands>{first_logon_commands}
-            </FirstLogonCommands>
-        </component>
-    </settings>
-</unattend>"#, arch = arch, scripts_dir = scripts_dir, username = username, first_logon_commands = first_logon_commands)
-}
-
-/// 生成 Windows 8/8.1 专用的无人值守配置
-/// 
-/// Win8/8.1 支持部分 Win10 的选项：
-/// - 支持 HideLocalAccountScreen
-/// - 不支持 HideOnlineAccountScreens
-/// - 不支持 HideWirelessSetupInOOBE
-/// - 不支持 SkipMachineOOBE / SkipUserOOBE
-fn generate_win8_unattend_xml(username: &str, scripts_dir: &str, first_logon_commands: &str, arch: &str) -> String {
-    format!(r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="specialize">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <ComputerName>*</ComputerName>
-        </component>
-        <component name="Microsoft-Windows-Deployment" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <RunSynchronous>
-                <RunSynchronousCommand wcm:action="add">
-                    <Order>1</Order>
-                    <Path>cmd /c if exist %SystemDrive%\{scripts_dir}\deploy.bat call %SystemDrive%\{scripts_dir}\deploy.bat</Path>
-                    <Description>Run custom deploy script</Description>
-                </RunSynchronousCommand>
-            </RunSynchronous>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <ProtectYourPC>3</ProtectYourPC>
-                <NetworkLocation>Home</NetworkLocation>
-            </OOBE>
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{username}</DisplayName>
-                        <Group>Administrators</Group>
-                        <Name>{username}</Name>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <LogonCount>1</LogonCount>
-                <Username>{username}</Username>
-            </AutoLogon>
-            <FirstLogonCommands>{first_logon_commands}
-            </FirstLogonCommands>
-        </component>
-    </settings>
-</unattend>"#, arch = arch, scripts_dir = scripts_dir, username = username, first_logon_commands = first_logon_commands)
-}
-
-/// 生成 Windows 10/11 无人值守配置
-/// 
-/// 完整支持所有 OOBE 跳过选项：
-/// - HideLocalAccountScreen
-/// - HideOnlineAccountScreens
-/// - HideWirelessSetupInOOBE
-/// - SkipMachineOOBE
-/// - SkipUserOOBE
-fn generate_win10_unattend_xml(username: &str, scripts_dir: &str, first_logon_commands: &str, arch: &str) -> String {
-    format!(r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="specialize">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <ComputerName>*</ComputerName>
-        </component>
-        <component name="Microsoft-Windows-Deployment" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <RunSynchronous>
-                <RunSynchronousCommand wcm:action="add">
-                    <Order>1</Order>
-                    <Path>cmd /c if exist %SystemDrive%\{scripts_dir}\deploy.bat call %SystemDrive%\{scripts_dir}\deploy.bat</Path>
-                    <Description>Run custom deploy script</Description>
-                </RunSynchronousCommand>
-            </RunSynchronous>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="{arch}" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <HideOnlineAccountScreens>true</HideOnlineAccountScreens>
-                <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>
-                <ProtectYourPC>3</ProtectYourPC>
-                <SkipMachineOOBE>true</SkipMachineOOBE>
-                <SkipUserOOBE>true</SkipUserOOBE>
-            </OOBE>
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{username}</DisplayName>
-                        <Group>Administrators</Group>
-                        <Name>{username}</Name>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <LogonCount>1</LogonCount>
-                <Username>{username}</Username>
-            </AutoLogon>
-            <FirstLogonCommands>{first_logon_commands}
-            </FirstLogonCommands>
-        </component>
-    </settings>
-</unattend>"#, arch = arch, scripts_dir = scripts_dir, username = username, first_logon_commands = first_logon_commands)
+    log::info!("[UNATTEND] 目标系统: {:?}", target);
+    write_unattend(target_partition, &builder.build())
 }
@@ -1,7 +1,11 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 use std::{fs, path::PathBuf};
 
+use crate::core::disk::{DiskManager, PartitionStyle, TempDriveLetter};
+use crate::core::driver_signing::{self, SigningOverride};
+use crate::core::nt5_boot::{self, NT5_LOADER_FILES};
+use crate::core::system_utils::get_file_version;
 use crate::utils::command::new_command;
 use crate::utils::encoding::gbk_to_utf8;
 use crate::utils::path::get_bin_dir;
@@ -48,6 +52,14 @@ impl BootManager {
     pub fn find_esp_on_same_disk(&self, windows_partition: &str) -> Result<String> {
         log::info!("查找 {} 所在磁盘的 ESP 分区...", windows_partition);
 
+        // 优先按 GPT 分区类型查找，ESP 没有盘符时直接使用卷 GUID 路径
+        if let Some(esp) = DiskManager::disk_number_of(windows_partition)
+            .and_then(|disk| DiskManager::find_esp_volume(Some(disk)))
+        {
+            log::info!("找到 ESP: {}", esp);
+            return Ok(esp);
+        }
+
         let drive_letter = windows_partition
             .trim_end_matches(':')
             .trim_end_matches('\\');
@@ -175,7 +187,13 @@ assign letter=S
             return Ok("S:".to_string());
         }
 
-        // 方法2: 使用 mountvol /s 挂载 ESP 到 S:
+        // 方法2: 按 GPT 分区类型查找，无需分配盘符
+        if let Some(esp) = DiskManager::find_esp_volume(None) {
+            log::info!("找到 ESP: {}", esp);
+            return Ok(esp);
+        }
+
+        // 方法3: 使用 mountvol /s 挂载 ESP 到 S:
         log::info!("尝试使用 mountvol /s 挂载 ESP");
         let output = new_command("mountvol").args(["S:", "/s"]).output();
         if output.is_ok() {
@@ -186,7 +204,7 @@ assign letter=S
             }
         }
 
-        // 方法3: 使用 diskpart 查找所有磁盘的 ESP
+        // 方法4: 使用 diskpart 查找所有磁盘的 ESP
         self.find_esp_with_diskpart()
     }
 
@@ -269,6 +287,174 @@ assign letter=S
         Ok(())
     }
 
+    /// 导出系统 BCD 存储到临时目录（安装失败时用于恢复）
+    pub fn export_bcd(&self) -> Result<PathBuf> {
+        let path = Self::reliable_temp_dir().join("LetRecovery_BCD_backup");
+        let _ = fs::remove_file(&path);
+        let path_str = path.to_string_lossy().to_string();
+
+        let output = new_command(&self.bcdedit_path)
+            .args(["/export", &path_str])
+            .output()?;
+        if !output.status.success() || !path.exists() {
+            anyhow::bail!("导出BCD失败: {}", gbk_to_utf8(&output.stdout).trim());
+        }
+
+        log::info!("BCD 已备份到 {}", path_str);
+        Ok(path)
+    }
+
+    /// 从 [`export_bcd`](Self::export_bcd) 导出的文件恢复系统 BCD 存储
+    pub fn import_bcd(&self, path: &Path) -> Result<()> {
+        log::info!("从 {} 恢复 BCD", path.display());
+
+        let output = new_command(&self.bcdedit_path)
+            .args(["/import", &path.to_string_lossy()])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("导入BCD失败: {}", gbk_to_utf8(&output.stdout).trim());
+        }
+        Ok(())
+    }
+
+    /// 获取盘符所在分区的分区号
+    fn partition_number_of(&self, windows_partition: &str) -> Option<u32> {
+        let drive_letter = windows_partition.trim_end_matches(':').trim_end_matches('\\');
+        let script = format!("select volume {}\ndetail partition\n", drive_letter);
+        let script_path = Self::reliable_temp_dir().join("detail_partition.txt");
+        fs::write(&script_path, &script).ok()?;
+
+        let output = new_command("diskpart")
+            .args(["/s", &script_path.to_string_lossy()])
+            .output()
+            .ok()?;
+        nt5_boot::parse_partition_number(&gbk_to_utf8(&output.stdout))
+    }
+
+    /// 目标分区上是否为 XP / 2003（按 ntoskrnl.exe 的版本号判断）
+    fn is_nt5_target(windows_partition: &str) -> bool {
+        let kernel = format!("{}\\Windows\\System32\\ntoskrnl.exe", windows_partition);
+        let major = get_file_version(Path::new(&kernel)).map(|(major, _, _, _)| major);
+        nt5_boot::is_nt5_system(windows_partition, major)
+    }
+
+    /// 修复 XP / 2003 的引导：写入 NT5 引导扇区、激活分区、补齐 NTLDR 和 boot.ini
+    pub fn repair_nt5_boot(&self, windows_partition: &str) -> Result<()> {
+        let disk_number = DiskManager::disk_number_of(windows_partition);
+        let style = DiskManager::get_partitions()
+            .ok()
+            .and_then(|partitions| {
+                let letter = windows_partition.trim_end_matches('\\').to_uppercase();
+                partitions.into_iter().find(|p| p.letter.to_uppercase() == letter)
+            })
+            .map(|p| p.partition_style);
+        if style == Some(PartitionStyle::GPT) {
+            anyhow::bail!("XP / 2003 只能从 MBR 磁盘以 Legacy 方式启动，{} 位于 GPT 磁盘上", windows_partition);
+        }
+
+        log::info!("NT5 系统：使用 NTLDR 引导");
+        let root = format!("{}\\", windows_partition);
+
+        // 补齐引导文件：程序目录 bin\nt5 或系统自带的 SP 源文件
+        for name in NT5_LOADER_FILES {
+            let target = Path::new(&root).join(name);
+            if target.exists() {
+                continue;
+            }
+            let candidates = [
+                get_bin_dir().join("nt5").join(name),
+                Path::new(&root).join("WINDOWS\\ServicePackFiles\\i386").join(name),
+                Path::new(&root).join("i386").join(name),
+            ];
+            let source = candidates
+                .iter()
+                .find(|p| p.exists())
+                .ok_or_else(|| anyhow::anyhow!("缺少引导文件 {}，请将其放到程序目录 bin\\nt5 下", name))?;
+            fs::copy(source, &target).with_context(|| format!("复制 {} 失败", name))?;
+            log::info!("已复制 {}", source.display());
+        }
+
+        let boot_ini = Path::new(&root).join("boot.ini");
+        if !boot_ini.exists() {
+            let partition_number = self.partition_number_of(windows_partition).unwrap_or(1);
+            let content = nt5_boot::boot_ini_content(disk_number.unwrap_or(0), partition_number);
+            fs::write(&boot_ini, content).context("写入 boot.ini 失败")?;
+            log::info!("已创建 boot.ini（分区 {}）", partition_number);
+        }
+
+        // NTLDR 只能从活动分区启动
+        let drive_letter = windows_partition.trim_end_matches(':').trim_end_matches('\\');
+        let script_path = Self::reliable_temp_dir().join("set_active.txt");
+        fs::write(&script_path, format!("select volume {}\nactive\n", drive_letter))?;
+        let output = new_command("diskpart")
+            .args(["/s", &script_path.to_string_lossy()])
+            .output()?;
+        log::debug!("设置活动分区: {}", gbk_to_utf8(&output.stdout));
+
+        let bootsect_path = get_bin_dir().join("bootsect.exe");
+        if !bootsect_path.exists() {
+            anyhow::bail!("缺少 bootsect.exe，无法写入 NT5 引导扇区");
+        }
+        let output = new_command(&bootsect_path)
+            .args(["/nt52", windows_partition, "/mbr", "/force"])
+            .output()?;
+        log::debug!("bootsect stdout: {}", gbk_to_utf8(&output.stdout));
+        if !output.status.success() {
+            anyhow::bail!("写入 NT5 引导扇区失败: {}", gbk_to_utf8(&output.stderr));
+        }
+
+        for name in NT5_LOADER_FILES.iter().chain(["boot.ini"].iter()) {
+            let _ = new_command("attrib")
+                .args(["+h", "+s", "+r", &format!("{}{}", root, name)])
+                .output();
+        }
+
+        log::info!("NT5 引导修复成功");
+        Ok(())
+    }
+
+    /// 查找默认项指向目标系统分区的 BCD 存储文件（ESP 或活动分区上的 \Boot\BCD）
+    fn find_store_for(&self, windows_partition: &str) -> Option<String> {
+        let mut stores: Vec<String> = Vec::new();
+        if let Ok(esp) = self.find_esp_on_same_disk(windows_partition) {
+            stores.push(format!("{}\\EFI\\Microsoft\\Boot\\BCD", esp));
+        }
+        stores.extend(('C'..='Z').map(|letter| format!("{}:\\Boot\\BCD", letter)));
+
+        let device = format!("partition={}", windows_partition.trim_end_matches('\\')).to_uppercase();
+        stores.into_iter().filter(|store| Path::new(store).exists()).find(|store| {
+            new_command(&self.bcdedit_path)
+                .args(["/store", store, "/enum", "{default}"])
+                .output()
+                .map(|output| gbk_to_utf8(&output.stdout).to_uppercase().contains(&device))
+                .unwrap_or(false)
+        })
+    }
+
+    /// 仅在新系统首次启动时放宽驱动签名强制，首次启动完成后由 SetupComplete.cmd 恢复
+    ///
+    /// Windows 7 及以上使用测试签名模式，开启安全启动时无效
+    pub fn disable_integrity_checks_once(&self, windows_partition: &str) -> Result<()> {
+        let store = self
+            .find_store_for(windows_partition)
+            .ok_or_else(|| anyhow::anyhow!("未找到 {} 的引导配置", windows_partition))?;
+
+        let kernel = format!("{}\\Windows\\System32\\ntoskrnl.exe", windows_partition);
+        let build = get_file_version(Path::new(&kernel)).map(|(_, _, build, _)| build);
+        let mode = SigningOverride::for_build(build);
+        let output = new_command(&self.bcdedit_path)
+            .args(["/store", &store, "/set", "{default}", mode.element(), "on"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("设置 {} 失败: {}", mode.element(), gbk_to_utf8(&output.stdout).trim());
+        }
+
+        driver_signing::stage_restore(windows_partition, mode)?;
+
+        log::info!("已在 {} 中设置首次启动 {} on", store, mode.element());
+        Ok(())
+    }
+
     /// 修复指定分区的引导（高级版本，支持指定引导模式）
     pub fn repair_boot_advanced(&self, windows_partition: &str, use_uefi: bool) -> Result<()> {
         let windows_path = format!("{}\\Windows", windows_partition);
@@ -288,6 +474,14 @@ assign letter=S
         // 先删除当前PE引导项
         let _ = self.delete_current_boot_entry();
 
+        // XP / 2003 没有 bcdboot 所需的引导文件，且只能以 Legacy 方式启动
+        if Self::is_nt5_target(windows_partition) {
+            if use_uefi {
+                log::warn!("XP / 2003 不支持 UEFI 启动，改用 Legacy 引导（需在固件中开启 CSM）");
+            }
+            return self.repair_nt5_boot(windows_partition);
+        }
+
         if use_uefi {
             log::info!("UEFI 模式：查找 ESP 分区");
 
@@ -296,15 +490,19 @@ assign letter=S
                 .or_else(|_| self.find_and_mount_esp());
 
             match esp_result {
-                Ok(esp_letter) => {
-                    log::info!("ESP 分区: {}", esp_letter);
+                Ok(esp) => {
+                    log::info!("ESP 分区: {}", esp);
 
-                    let efi_ms_dir = format!("{}\\EFI\\Microsoft", esp_letter);
-                    let efi_boot_dir = format!("{}\\EFI\\Boot", esp_letter);
+                    let efi_ms_dir = format!("{}\\EFI\\Microsoft", esp);
+                    let efi_boot_dir = format!("{}\\EFI\\Boot", esp);
 
                     let _ = std::fs::create_dir_all(&efi_ms_dir);
                     let _ = std::fs::create_dir_all(&efi_boot_dir);
 
+                    // bcdboot 的 /s 参数只接受盘符，ESP 没有盘符时临时分配
+                    let esp_drive = TempDriveLetter::acquire(&esp)?;
+                    let esp_letter = esp_drive.drive();
+
                     log::info!(
                         "执行: bcdboot {} /s {} /f UEFI /l zh-cn",
                         windows_path,
@@ -1,162 +1,8 @@
 use anyhow::{Context, Result};
 use std::path::Path;
 
-/// 驱动操作模式
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub enum DriverActionMode {
-    /// 无操作
-    #[default]
-    None = 0,
-    /// 仅保存驱动（到数据目录）
-    SaveOnly = 1,
-    /// 自动导入（保存并导入到新系统）
-    AutoImport = 2,
-}
-
-impl DriverActionMode {
-    /// 从数值转换
-    pub fn from_u8(value: u8) -> Self {
-        match value {
-            1 => Self::SaveOnly,
-            2 => Self::AutoImport,
-            _ => Self::None,
-        }
-    }
-    
-    /// 是否需要导入驱动
-    pub fn should_import(&self) -> bool {
-        *self == Self::AutoImport
-    }
-    
-    /// 是否有驱动目录（SaveOnly 或 AutoImport 时都有）
-    pub fn has_drivers(&self) -> bool {
-        *self != Self::None
-    }
-}
-
-/// 系统安装配置（用于PE环境内安装）
-#[derive(Debug, Clone, Default)]
-pub struct InstallConfig {
-    /// 无人值守安装
-    pub unattended: bool,
-    /// 驱动还原（兼容旧版本）
-    pub restore_drivers: bool,
-    /// 驱动操作模式: 0=无, 1=仅保存, 2=自动导入
-    pub driver_action_mode: DriverActionMode,
-    /// 立即重启
-    pub auto_reboot: bool,
-    /// 原系统引导GUID（用于删除旧引导项）
-    pub original_guid: String,
-    /// 安装分卷索引
-    pub volume_index: u32,
-    /// 目标分区盘符
-    pub target_partition: String,
-    /// 镜像文件路径（相对于数据分区）
-    pub image_path: String,
-    /// 是否为GHO格式
-    pub is_gho: bool,
-    /// CAB更新包安装: true=安装, false=不安装
-    pub install_cab_packages: bool,
-
-    // 高级选项
-    /// 移除快捷方式小箭头
-    pub remove_shortcut_arrow: bool,
-    /// Win11恢复经典右键
-    pub restore_classic_context_menu: bool,
-    /// OOBE绕过强制联网
-    pub bypass_nro: bool,
-    /// 禁用Windows更新
-    pub disable_windows_update: bool,
-    /// 禁用Windows安全中心
-    pub disable_windows_defender: bool,
-    /// 禁用系统保留空间
-    pub disable_reserved_storage: bool,
-    /// 禁用用户账户控制
-    pub disable_uac: bool,
-    /// 禁用自动设备加密
-    pub disable_device_encryption: bool,
-    /// 删除预装UWP应用
-    pub remove_uwp_apps: bool,
-    /// 导入磁盘控制器驱动
-    pub import_storage_controller_drivers: bool,
-    /// 自定义用户名
-    pub custom_username: String,
-    /// 自定义系统盘卷标
-    pub volume_label: String,
-    
-    // Win7 专用选项
-    /// Win7 UEFI 补丁（使用 UefiSeven）
-    pub win7_uefi_patch: bool,
-    /// Win7 注入USB3驱动
-    pub win7_inject_usb3_driver: bool,
-    /// Win7 注入NVMe驱动
-    pub win7_inject_nvme_driver: bool,
-    /// Win7 修复ACPI蓝屏
-    pub win7_fix_acpi_bsod: bool,
-    /// Win7 修复存储控制器蓝屏
-    pub win7_fix_storage_bsod: bool,
-}
-
-impl InstallConfig {
-    /// 判断是否需要导入驱动
-    /// 优先使用新的driver_action_mode，兼容旧的restore_drivers
-    pub fn should_import_drivers(&self) -> bool {
-        // 优先使用新的driver_action_mode
-        if self.driver_action_mode != DriverActionMode::None {
-            self.driver_action_mode.should_import()
-        } else {
-            // 兼容旧版本
-            self.restore_drivers
-        }
-    }
-    
-    /// 判断是否有驱动目录需要处理
-    pub fn has_driver_data(&self) -> bool {
-        self.driver_action_mode.has_drivers() || self.restore_drivers
-    }
-}
-
-/// 备份格式
-#[derive(Debug, Clone, Copy, PartialEq, Default)]
-pub enum BackupFormat {
-    #[default]
-    Wim = 0,
-    Esd = 1,
-    Swm = 2,
-    Gho = 3,
-}
-
-impl BackupFormat {
-    /// 从数值转换
-    pub fn from_u8(value: u8) -> Self {
-        match value {
-            0 => Self::Wim,
-            1 => Self::Esd,
-            2 => Self::Swm,
-            3 => Self::Gho,
-            _ => Self::Wim,
-        }
-    }
-}
-
-/// 系统备份配置（用于PE环境内备份）
-#[derive(Debug, Clone, Default)]
-pub struct BackupConfig {
-    /// 备份保存路径
-    pub save_path: String,
-    /// 备份名称
-    pub name: String,
-    /// 备份描述
-    pub description: String,
-    /// 源分区盘符
-    pub source_partition: String,
-    /// 是否增量备份
-    pub incremental: bool,
-    /// 备份格式
-    pub format: BackupFormat,
-    /// SWM分卷大小（MB）
-    pub swm_split_size: u32,
-}
+use crate::core::config_schema::CONFIG_SCHEMA_VERSION;
+pub use crate::core::config_schema::{BackupConfig, BackupFormat, InstallConfig};
 
 /// 配置文件管理器
 pub struct ConfigFileManager;
@@ -167,8 +13,12 @@ impl ConfigFileManager {
     const BACKUP_MARKER: &'static str = "LetRecovery_Backup.marker";
 
     /// 配置文件名
-    const INSTALL_CONFIG: &'static str = "LetRecovery_Install.ini";
-    const BACKUP_CONFIG: &'static str = "LetRecovery_Backup.ini";
+    const INSTALL_CONFIG: &'static str = "LetRecovery_Install.json";
+    const BACKUP_CONFIG: &'static str = "LetRecovery_Backup.json";
+
+    /// 旧版INI配置文件名（兼容旧版桌面端写入的配置）
+    const LEGACY_INSTALL_CONFIG: &'static str = "LetRecovery_Install.ini";
+    const LEGACY_BACKUP_CONFIG: &'static str = "LetRecovery_Backup.ini";
 
     /// PE文件目录名
     const PE_DIR: &'static str = "LetRecovery_PE";
@@ -203,19 +53,39 @@ impl ConfigFileManager {
     /// 查找包含配置文件的数据分区
     pub fn find_data_partition() -> Option<String> {
         for letter in ['C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K'] {
-            let config_path = format!("{}:\\{}\\{}", letter, Self::DATA_DIR, Self::INSTALL_CONFIG);
-            if Path::new(&config_path).exists() {
-                log::info!("找到安装配置分区: {}:", letter);
-                return Some(format!("{}:", letter));
+            let partition = format!("{}:", letter);
+            if Self::has_install_config(&partition) {
+                log::info!("找到安装配置分区: {}", partition);
+                return Some(partition);
             }
-            let backup_config_path =
-                format!("{}:\\{}\\{}", letter, Self::DATA_DIR, Self::BACKUP_CONFIG);
-            if Path::new(&backup_config_path).exists() {
-                log::info!("找到备份配置分区: {}:", letter);
-                return Some(format!("{}:", letter));
+            if Self::has_backup_config(&partition) {
+                log::info!("找到备份配置分区: {}", partition);
+                return Some(partition);
             }
         }
-        None
+
+        // 磁盘较多时数据分区可能没有分到盘符，只把包含配置的卷挂载到文件夹
+        let partition = crate::core::disk::DiskManager::find_unlettered_volume(|partition| {
+            Self::has_install_config(partition) || Self::has_backup_config(partition)
+        })?;
+        log::info!("找到挂载到文件夹的配置分区: {}", partition);
+        Some(partition)
+    }
+
+    /// 分区的数据目录中是否存在安装配置（新版JSON或旧版INI）
+    fn has_install_config(partition: &str) -> bool {
+        let data_dir = Self::get_data_dir(partition);
+        [Self::INSTALL_CONFIG, Self::LEGACY_INSTALL_CONFIG]
+            .iter()
+            .any(|name| Path::new(&format!("{}\\{}", data_dir, name)).exists())
+    }
+
+    /// 分区的数据目录中是否存在备份配置（新版JSON或旧版INI）
+    fn has_backup_config(partition: &str) -> bool {
+        let data_dir = Self::get_data_dir(partition);
+        [Self::BACKUP_CONFIG, Self::LEGACY_BACKUP_CONFIG]
+            .iter()
+            .any(|name| Path::new(&format!("{}\\{}", data_dir, name)).exists())
     }
 
     /// 检测操作类型 (安装或备份)
@@ -223,13 +93,7 @@ impl ConfigFileManager {
         // 先检查安装标记
         if Self::find_install_marker_partition().is_some() {
             if let Some(data_part) = Self::find_data_partition() {
-                let install_config_path = format!(
-                    "{}\\{}\\{}",
-                    data_part,
-                    Self::DATA_DIR,
-                    Self::INSTALL_CONFIG
-                );
-                if Path::new(&install_config_path).exists() {
+                if Self::has_install_config(&data_part) {
                     return Some(OperationType::Install);
                 }
             }
@@ -238,9 +102,7 @@ impl ConfigFileManager {
         // 再检查备份标记
         if Self::find_backup_marker_partition().is_some() {
             if let Some(data_part) = Self::find_data_partition() {
-                let backup_config_path =
-                    format!("{}\\{}\\{}", data_part, Self::DATA_DIR, Self::BACKUP_CONFIG);
-                if Path::new(&backup_config_path).exists() {
+                if Self::has_backup_config(&data_part) {
                     return Some(OperationType::Backup);
                 }
             }
@@ -250,31 +112,79 @@ impl ConfigFileManager {
     }
 
     /// 读取安装配置
+    ///
+    /// 优先读取JSON配置，不存在时回退到旧版INI配置
     pub fn read_install_config(data_partition: &str) -> Result<InstallConfig> {
-        let config_path = format!(
-            "{}\\{}\\{}",
-            data_partition,
-            Self::DATA_DIR,
-            Self::INSTALL_CONFIG
-        );
-        log::info!("读取安装配置: {}", config_path);
-        let content =
-            std::fs::read_to_string(&config_path).context("读取安装配置文件失败")?;
-        Self::deserialize_install_config(&content)
+        let data_dir = Self::get_data_dir(data_partition);
+        let config_path = format!("{}\\{}", data_dir, Self::INSTALL_CONFIG);
+
+        let config = if Path::new(&config_path).exists() {
+            log::info!("读取安装配置: {}", config_path);
+            let content =
+                std::fs::read_to_string(&config_path).context("读取安装配置文件失败")?;
+            Self::parse_install_config(&content)?
+        } else {
+            let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_INSTALL_CONFIG);
+            log::info!("读取旧版安装配置: {}", legacy_path);
+            let content =
+                std::fs::read_to_string(&legacy_path).context("读取安装配置文件失败")?;
+            Self::deserialize_install_config(&content)?
+        };
+
+        config.validate().context("安装配置校验失败")?;
+        Ok(config)
     }
 
     /// 读取备份配置
+    ///
+    /// 优先读取JSON配置，不存在时回退到旧版INI配置
     pub fn read_backup_config(data_partition: &str) -> Result<BackupConfig> {
-        let config_path = format!(
-            "{}\\{}\\{}",
-            data_partition,
-            Self::DATA_DIR,
-            Self::BACKUP_CONFIG
-        );
-        log::info!("读取备份配置: {}", config_path);
-        let content =
-            std::fs::read_to_string(&config_path).context("读取备份配置文件失败")?;
-        Self::deserialize_backup_config(&content)
+        let data_dir = Self::get_data_dir(data_partition);
+        let config_path = format!("{}\\{}", data_dir, Self::BACKUP_CONFIG);
+
+        let config = if Path::new(&config_path).exists() {
+            log::info!("读取备份配置: {}", config_path);
+            let content =
+                std::fs::read_to_string(&config_path).context("读取备份配置文件失败")?;
+            Self::parse_backup_config(&content)?
+        } else {
+            let legacy_path = format!("{}\\{}", data_dir, Self::LEGACY_BACKUP_CONFIG);
+            log::info!("读取旧版备份配置: {}", legacy_path);
+            let content =
+                std::fs::read_to_string(&legacy_path).context("读取备份配置文件失败")?;
+            Self::deserialize_backup_config(&content)?
+        };
+
+        config.validate().context("备份配置校验失败")?;
+        Ok(config)
+    }
+
+    /// 解析JSON格式的安装配置
+    pub fn parse_install_config(content: &str) -> Result<InstallConfig> {
+        let config: InstallConfig =
+            serde_json::from_str(content).context("解析安装配置文件失败")?;
+        Self::warn_newer_schema(config.schema_version, &config.extra);
+        Ok(config)
+    }
+
+    /// 解析JSON格式的备份配置
+    pub fn parse_backup_config(content: &str) -> Result<BackupConfig> {
+        let config: BackupConfig =
+            serde_json::from_str(content).context("解析备份配置文件失败")?;
+        Self::warn_newer_schema(config.schema_version, &config.extra);
+        Ok(config)
+    }
+
+    /// 配置由更新版本的桌面端写入时给出提示（未识别字段会被保留但不会生效）
+    fn warn_newer_schema(version: u32, extra: &serde_json::Map<String, serde_json::Value>) {
+        if version > CONFIG_SCHEMA_VERSION {
+            log::warn!(
+                "配置文件版本 {} 高于当前PE支持的版本 {}，以下字段将被忽略: {:?}",
+                version,
+                CONFIG_SCHEMA_VERSION,
+                extra.keys().collect::<Vec<_>>()
+            );
+        }
     }
 
     /// 获取数据目录路径
@@ -332,7 +242,7 @@ impl ConfigFileManager {
         Self::cleanup_pe_dir(data_partition);
     }
 
-    /// 反序列化安装配置
+    /// 反序列化旧版INI安装配置
     fn deserialize_install_config(content: &str) -> Result<InstallConfig> {
         let mut config = InstallConfig::default();
         config.volume_index = 1; // 默认值
@@ -350,16 +260,14 @@ impl ConfigFileManager {
                 match key {
                     "Unattended" => config.unattended = value.parse().unwrap_or(false),
                     "RestoreDrivers" => config.restore_drivers = value.parse().unwrap_or(false),
-                    "DriverActionMode" => {
-                        let mode_value: u8 = value.parse().unwrap_or(0);
-                        config.driver_action_mode = DriverActionMode::from_u8(mode_value);
-                    }
+                    "DriverActionMode" => config.driver_action_mode = value.parse().unwrap_or(0),
                     "AutoReboot" => config.auto_reboot = value.parse().unwrap_or(false),
                     "OriginalGUID" => config.original_guid = value.to_string(),
                     "VolumeIndex" => config.volume_index = value.parse().unwrap_or(1),
                     "TargetPartition" => config.target_partition = value.to_string(),
                     "ImagePath" => config.image_path = value.to_string(),
                     "IsGho" => config.is_gho = value.parse().unwrap_or(false),
+                    "GhoPartition" => config.gho_partition = value.parse().unwrap_or(0),
                     "InstallCabPackages" => config.install_cab_packages = value.parse().unwrap_or(false),
                     "RemoveShortcutArrow" => {
                         config.remove_shortcut_arrow = value.parse().unwrap_or(false)
@@ -392,7 +300,9 @@ impl ConfigFileManager {
                     "Win7InjectNvmeDriver" => config.win7_inject_nvme_driver = value.parse().unwrap_or(false),
                     "Win7FixAcpiBsod" => config.win7_fix_acpi_bsod = value.parse().unwrap_or(false),
                     "Win7FixStorageBsod" => config.win7_fix_storage_bsod = value.parse().unwrap_or(false),
-                    _ => {}
+                    _ => {
+                        config.extra.insert(key.to_string(), value.into());
+                    }
                 }
             }
         }
@@ -400,7 +310,7 @@ impl ConfigFileManager {
         Ok(config)
     }
 
-    /// 反序列化备份配置
+    /// 反序列化旧版INI备份配置
     fn deserialize_backup_config(content: &str) -> Result<BackupConfig> {
         let mut config = BackupConfig::default();
         config.swm_split_size = 4096; // 默认4GB
@@ -421,12 +331,11 @@ impl ConfigFileManager {
                     "Description" => config.description = value.to_string(),
                     "SourcePartition" => config.source_partition = value.to_string(),
                     "Incremental" => config.incremental = value.parse().unwrap_or(false),
-                    "Format" => {
-                        let format_value: u8 = value.parse().unwrap_or(0);
-                        config.format = BackupFormat::from_u8(format_value);
-                    }
+                    "Format" => config.format = value.parse().unwrap_or(0),
                     "SwmSplitSize" => config.swm_split_size = value.parse().unwrap_or(4096),
-                    _ => {}
+                    _ => {
+                        config.extra.insert(key.to_string(), value.into());
+                    }
                 }
             }
         }
@@ -1,20 +1,43 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 use windows::core::PCWSTR;
-use windows::Win32::Storage::FileSystem::{GetDiskFreeSpaceExW, GetDriveTypeW, GetVolumeInformationW};
+use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::Storage::FileSystem::{
+    CreateFileW, DeleteVolumeMountPointW, FindFirstVolumeW, FindNextVolumeW, FindVolumeClose,
+    GetDiskFreeSpaceExW, GetDriveTypeW, GetVolumeInformationW, GetVolumeNameForVolumeMountPointW,
+    GetVolumePathNamesForVolumeNameW, SetVolumeMountPointW, FILE_SHARE_READ, FILE_SHARE_WRITE,
+    OPEN_EXISTING,
+};
+use windows::Win32::System::Ioctl::{
+    IOCTL_DISK_GET_PARTITION_INFO_EX, IOCTL_STORAGE_GET_DEVICE_NUMBER, IOCTL_STORAGE_QUERY_PROPERTY,
+    PARTITION_STYLE_GPT,
+};
+use windows::Win32::System::IO::DeviceIoControl;
 
 use crate::utils::command::new_command;
 use crate::utils::encoding::gbk_to_utf8;
 use crate::utils::path::get_bin_dir;
 
+use crate::core::partition_plan;
+pub use crate::core::partition_plan::{DiskConversion, PartitionPlan};
+
+const DRIVE_REMOVABLE: u32 = 2;
 const DRIVE_FIXED: u32 = 3;
 
-/// 自动创建分区的标志文件名
+/// EFI 系统分区类型 GUID（c12a7328-f81f-11d2-ba4b-00a0c93ec93b）的内存布局
+const ESP_PARTITION_TYPE: [u8; 16] = [
+    0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b,
+];
+
+/// 自动创建分区的标志文件名（旧版本桌面端仅通过该文件标识数据分区）
 pub const AUTO_CREATED_PARTITION_MARKER: &str = "LetRecovery_AutoCreated.marker";
 
+/// 自动创建的数据分区卷标（与桌面端保持一致）
+pub const DATA_PARTITION_LABEL: &str = "LETRECOVERY_DATA";
+
 /// 获取 diskpart 可执行文件路径
 /// 优先使用内置的 diskpart，如果不存在则使用系统的
 fn get_diskpart_path() -> String {
@@ -116,16 +139,24 @@ impl DiskManager {
             }
         }
 
+        // 盘符用尽时挂载到文件夹的卷
+        for folder in Self::mounted_folders() {
+            if let Ok(info) = Self::get_partition_info(&folder) {
+                partitions.push(info);
+            }
+        }
+
         Ok(partitions)
     }
 
     fn get_partition_info(drive: &str) -> Result<Partition> {
-        let path = format!("{}\\", drive);
+        let path = volume_root(drive);
         let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
 
-        // 获取驱动器类型
+        // 获取驱动器类型，平板的 eMMC / SD 存储可能报告为可移动磁盘
         let drive_type = unsafe { GetDriveTypeW(PCWSTR(wide_path.as_ptr())) };
-        if drive_type != DRIVE_FIXED {
+        let is_flash_card = drive_type == DRIVE_REMOVABLE && Self::is_flash_card_volume(drive);
+        if drive_type != DRIVE_FIXED && !is_flash_card {
             anyhow::bail!("Not a fixed drive");
         }
 
@@ -194,8 +225,7 @@ impl DiskManager {
 
     /// 使用 diskpart 获取分区信息（备用方法）
     fn get_partition_style_diskpart(drive: &str) -> PartitionDetail {
-        let letter = drive.chars().next().unwrap_or('C');
-        let script = format!("select volume {}\ndetail volume", letter);
+        let script = format!("select volume {}\ndetail volume", diskpart_volume(drive));
 
         let temp_dir = Self::reliable_temp_dir();
         let script_path = temp_dir.join("dp_style.txt");
@@ -298,6 +328,28 @@ impl DiskManager {
         }
     }
 
+    /// 让目标分区所在磁盘的分区表匹配固件启动模式的规划（保留数据）
+    pub fn plan_for_firmware(partition: &str, uefi: bool) -> Result<PartitionPlan> {
+        let disk_number = Self::disk_number_of(partition)
+            .with_context(|| format!("无法确定分区 {} 所在的磁盘", partition))?;
+        PartitionPlan::match_firmware(disk_number, Self::get_disk_partition_style(disk_number), uefi)
+    }
+
+    /// 执行分区规划，返回创建的分区名称
+    ///
+    /// 重新分区会清空整个磁盘，调用方须事先确认；mbr2gpt 转换保留现有分区和数据。
+    pub fn plan_and_apply(plan: &PartitionPlan) -> Result<Vec<String>> {
+        log::info!("[DISK] 分区规划: {}", plan.describe());
+        match plan.conversion {
+            DiskConversion::None => Ok(Vec::new()),
+            DiskConversion::Mbr2Gpt => {
+                partition_plan::convert_mbr_to_gpt(plan.disk_number, false)?;
+                Ok(vec!["ESP".to_string()])
+            }
+            DiskConversion::Repartition => plan.apply_with_diskpart(&get_diskpart_path()),
+        }
+    }
+
     /// 格式化指定分区
     pub fn format_partition(partition: &str) -> Result<String> {
         Self::format_partition_with_label(partition, None)
@@ -310,14 +362,9 @@ impl DiskManager {
     pub fn format_partition_with_label(partition: &str, volume_label: Option<&str>) -> Result<String> {
         log::info!("格式化分区: {} 卷标: {:?}", partition, volume_label);
 
-        // 提取盘符
-        let drive_letter = partition
-            .chars()
-            .next()
-            .unwrap_or('C')
-            .to_ascii_uppercase();
-
-        let drive = format!("{}:", drive_letter);
+        // format 只接受盘符，没有盘符的分区临时分配
+        let temp_letter = TempDriveLetter::acquire(partition)?;
+        let drive = temp_letter.drive();
 
         // 卷标处理
         let vol_label = match volume_label {
@@ -351,6 +398,11 @@ impl DiskManager {
         
         if output.status.success() || has_success_indicator {
             log::info!("分区 {} 格式化成功", drive);
+
+            // SSD 在释放镜像前 TRIM 全部空闲空间，失败不影响安装
+            if let Err(e) = Self::trim_free_space(partition) {
+                log::warn!("TRIM 失败: {}", e);
+            }
             Ok(stdout)
         } else {
             let error_msg = if !stderr.is_empty() {
@@ -392,23 +444,303 @@ impl DiskManager {
         false
     }
 
-    /// 查找自动创建的分区（通过标志文件）
-    /// 返回 (盘符, 磁盘号Option) 如果找到的话
-    pub fn find_auto_created_partition() -> Option<(char, Option<u32>)> {
-        for letter in b'A'..=b'Z' {
-            let c = letter as char;
-            // 跳过 X 盘（PE系统盘）
-            if c == 'X' {
+    /// 挂载文件夹的根目录（盘符用尽时将卷挂载到这里的子文件夹）
+    fn mount_root() -> PathBuf {
+        Self::reliable_temp_dir().join("LetRecovery_Mounts")
+    }
+
+    /// 获取卷 GUID 路径（如 `\\?\Volume{...}\`）
+    fn volume_guid_path(partition: &str) -> Option<String> {
+        let root: Vec<u16> = volume_root(partition)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut name = [0u16; 64];
+        unsafe { GetVolumeNameForVolumeMountPointW(PCWSTR(root.as_ptr()), &mut name).ok()? };
+        Some(String::from_utf16_lossy(&name).trim_end_matches('\0').to_string())
+    }
+
+    /// 枚举全部卷，返回卷 GUID 路径（不带结尾反斜杠）
+    fn list_volumes() -> Vec<String> {
+        let mut volumes = Vec::new();
+        let mut name = [0u16; 64];
+
+        let handle = match unsafe { FindFirstVolumeW(&mut name) } {
+            Ok(h) => h,
+            Err(e) => {
+                log::warn!("枚举卷失败: {}", e);
+                return volumes;
+            }
+        };
+
+        loop {
+            let volume = String::from_utf16_lossy(&name).trim_end_matches('\0').to_string();
+            volumes.push(volume.trim_end_matches('\\').to_string());
+
+            name = [0u16; 64];
+            if unsafe { FindNextVolumeW(handle, &mut name) }.is_err() {
+                break;
+            }
+        }
+        let _ = unsafe { FindVolumeClose(handle) };
+
+        volumes
+    }
+
+    /// 枚举没有盘符也没有挂载路径的卷，返回卷 GUID 路径（带结尾反斜杠）
+    fn list_unmounted_volumes() -> Vec<String> {
+        Self::list_volumes()
+            .into_iter()
+            .map(|volume| volume_root(&volume))
+            .filter(|volume| {
+                let wide_volume: Vec<u16> = volume.encode_utf16().chain(std::iter::once(0)).collect();
+                let mut paths = [0u16; 261];
+                let mut length: u32 = 0;
+                let has_path = unsafe {
+                    GetVolumePathNamesForVolumeNameW(PCWSTR(wide_volume.as_ptr()), Some(&mut paths), &mut length)
+                }
+                .is_ok()
+                    && paths[0] != 0;
+                !has_path
+            })
+            .collect()
+    }
+
+    /// 将卷挂载到 NTFS 空文件夹（SetVolumeMountPointW），返回挂载文件夹路径
+    fn mount_volume_folder(volume_name: &str, folder: &Path) -> Result<String> {
+        fs::create_dir_all(folder)?;
+
+        let mount_point = volume_root(&folder.to_string_lossy());
+        Self::set_mount_point(volume_name, &mount_point)?;
+        Ok(mount_point.trim_end_matches('\\').to_string())
+    }
+
+    /// 为卷设置挂载点（盘符根目录或 NTFS 空文件夹，均需带结尾反斜杠）
+    fn set_mount_point(volume_name: &str, mount_point: &str) -> Result<()> {
+        let wide_mount: Vec<u16> = mount_point.encode_utf16().chain(std::iter::once(0)).collect();
+        let wide_volume: Vec<u16> = volume_name.encode_utf16().chain(std::iter::once(0)).collect();
+        unsafe {
+            SetVolumeMountPointW(PCWSTR(wide_mount.as_ptr()), PCWSTR(wide_volume.as_ptr()))
+                .map_err(|e| anyhow::anyhow!("挂载 {} 到 {} 失败: {}", volume_name, mount_point, e))?;
+        }
+
+        log::info!("已将 {} 挂载到 {}", volume_name, mount_point);
+        Ok(())
+    }
+
+    /// 打开卷设备执行查询类 IOCTL，返回写入的字节数
+    fn volume_ioctl(partition: &str, code: u32, input: &[u8], buffer: &mut [u8]) -> Option<usize> {
+        // 卷 GUID 路径去掉结尾反斜杠即为卷设备路径
+        let device_path = Self::volume_guid_path(partition)?;
+        let wide_path: Vec<u16> = device_path
+            .trim_end_matches('\\')
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        unsafe {
+            let handle = CreateFileW(
+                PCWSTR(wide_path.as_ptr()),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                Default::default(),
+                None,
+            )
+            .ok()?;
+
+            let mut bytes_returned: u32 = 0;
+            let result = DeviceIoControl(
+                handle,
+                code,
+                (!input.is_empty()).then(|| input.as_ptr() as *const _),
+                input.len() as u32,
+                Some(buffer.as_mut_ptr() as *mut _),
+                buffer.len() as u32,
+                Some(&mut bytes_returned),
+                None,
+            );
+            let _ = CloseHandle(handle);
+
+            result.ok()?;
+            Some(bytes_returned as usize)
+        }
+    }
+
+    /// 分区所在的磁盘号（支持盘符、挂载文件夹和卷 GUID 路径）
+    pub fn disk_number_of(partition: &str) -> Option<u32> {
+        // STORAGE_DEVICE_NUMBER: DeviceType, DeviceNumber, PartitionNumber
+        let mut buffer = [0u8; 12];
+        if Self::volume_ioctl(partition, IOCTL_STORAGE_GET_DEVICE_NUMBER, &[], &mut buffer)? < buffer.len() {
+            return None;
+        }
+        Some(u32::from_le_bytes(buffer[4..8].try_into().ok()?))
+    }
+
+    /// 分区是否位于 eMMC / SD 存储上（STORAGE_DEVICE_DESCRIPTOR.BusType 为 SD 或 MMC）
+    fn is_flash_card_volume(partition: &str) -> bool {
+        // STORAGE_PROPERTY_QUERY { StorageDeviceProperty, PropertyStandardQuery } 全为 0
+        let query = [0u8; 12];
+        let mut buffer = [0u8; 512];
+        match Self::volume_ioctl(partition, IOCTL_STORAGE_QUERY_PROPERTY, &query, &mut buffer) {
+            // BusType 位于 offset 28：BusTypeSd = 12，BusTypeMmc = 13
+            Some(size) if size >= 32 => {
+                matches!(i32::from_le_bytes([buffer[28], buffer[29], buffer[30], buffer[31]]), 12 | 13)
+            }
+            _ => false,
+        }
+    }
+
+    /// 对 SSD 分区的全部空闲空间执行 TRIM，返回 TRIM 的字节数（设备不支持时返回 0）
+    pub fn trim_free_space(partition: &str) -> Result<u64> {
+        let device_path = Self::volume_guid_path(partition).context("无法获取卷 GUID 路径")?;
+        crate::core::volume_trim::trim_free_space(partition, device_path.trim_end_matches('\\'))
+    }
+
+    /// 是否为 EFI 系统分区（按 GPT 分区类型判断）
+    fn is_esp_volume(partition: &str) -> bool {
+        // PARTITION_INFORMATION_EX 结构大小固定为 144 字节
+        // offset 0: PartitionStyle，offset 32: PartitionType GUID
+        let mut buffer = [0u8; 144];
+        match Self::volume_ioctl(partition, IOCTL_DISK_GET_PARTITION_INFO_EX, &[], &mut buffer) {
+            Some(size) if size >= buffer.len() => {
+                buffer[0..4] == (PARTITION_STYLE_GPT.0 as u32).to_le_bytes()
+                    && buffer[32..48] == ESP_PARTITION_TYPE
+            }
+            _ => false,
+        }
+    }
+
+    /// 按 GPT 分区类型查找 EFI 系统分区，返回卷 GUID 路径（不带结尾反斜杠）
+    ///
+    /// ESP 通常没有盘符，直接通过卷 GUID 路径访问其中的文件；指定磁盘号时只查找该磁盘
+    pub fn find_esp_volume(disk_number: Option<u32>) -> Option<String> {
+        Self::list_volumes().into_iter().find(|volume| {
+            Self::is_esp_volume(volume)
+                && disk_number.map_or(true, |disk| Self::disk_number_of(volume) == Some(disk))
+        })
+    }
+
+    /// 在没有盘符的卷中查找满足条件的卷（PE 中磁盘较多、盘符用尽时使用）
+    ///
+    /// 先查找已挂载到文件夹的卷；其余卷直接通过卷 GUID 路径检查，
+    /// 只把满足条件的卷挂载到文件夹并返回挂载文件夹，不挂载其他卷
+    pub fn find_unlettered_volume(matches: impl Fn(&str) -> bool) -> Option<String> {
+        let mounted = Self::mounted_folders();
+        if let Some(folder) = mounted.iter().find(|folder| matches(folder)) {
+            return Some(folder.clone());
+        }
+
+        for volume in Self::list_unmounted_volumes() {
+            // 数据分区都是 NTFS，跳过 ESP、MSR、未格式化分区等
+            let wide_volume: Vec<u16> = volume.encode_utf16().chain(std::iter::once(0)).collect();
+            let mut fs_name = [0u16; 32];
+            let readable = unsafe {
+                GetVolumeInformationW(PCWSTR(wide_volume.as_ptr()), None, None, None, None, Some(&mut fs_name))
+            }
+            .is_ok();
+            if !readable
+                || String::from_utf16_lossy(&fs_name).trim_end_matches('\0') != "NTFS"
+                || !matches(volume.trim_end_matches('\\'))
+            {
                 continue;
             }
-            
-            let marker_path = format!("{}:\\{}", c, AUTO_CREATED_PARTITION_MARKER);
-            if Path::new(&marker_path).exists() {
-                log::info!("找到自动创建的分区: {}:", c);
-                
+
+            let folder = (mounted.len() + 1..)
+                .map(|index| Self::mount_root().join(format!("Volume{}", index)))
+                .find(|folder| !folder.exists())?;
+            match Self::mount_volume_folder(&volume, &folder) {
+                Ok(mount_point) => return Some(mount_point),
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+        None
+    }
+
+    /// 已挂载到文件夹的卷
+    fn mounted_folders() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(Self::mount_root()) else {
+            return Vec::new();
+        };
+        let mut folders: Vec<String> = entries
+            .flatten()
+            .map(|e| e.path().to_string_lossy().to_string())
+            .filter(|path| Self::volume_guid_path(path).is_some())
+            .collect();
+        folders.sort();
+        folders
+    }
+
+    /// 获取卷标
+    fn volume_label(partition: &str) -> String {
+        let root: Vec<u16> = volume_root(partition)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut volume_name = [0u16; 261];
+        unsafe {
+            let _ = GetVolumeInformationW(
+                PCWSTR(root.as_ptr()),
+                Some(&mut volume_name),
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+        String::from_utf16_lossy(&volume_name)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    /// 获取分区标识（磁盘ID:分区偏移），与桌面端创建数据分区时记录的标识比较
+    fn partition_identity(partition: &str) -> Option<String> {
+        let script_path = Self::reliable_temp_dir().join("lr_partition_id.txt");
+        std::fs::write(
+            &script_path,
+            format!("select volume {}\nuniqueid disk\ndetail partition", diskpart_volume(partition)),
+        )
+        .ok()?;
+
+        let output = new_command(&get_diskpart_path())
+            .args(["/s", script_path.to_str().unwrap()])
+            .output()
+            .ok()?;
+        let _ = std::fs::remove_file(&script_path);
+
+        parse_partition_identity(&gbk_to_utf8(&output.stdout))
+    }
+
+    /// 查找自动创建的分区
+    ///
+    /// 按卷标查找，配置中记录了分区标识时还要求标识一致（PE 中盘符可能与桌面端不同）；
+    /// 没有记录标识时才接受旧版本的标志文件。
+    /// 返回 (盘符或挂载文件夹, 磁盘号Option) 如果找到的话
+    pub fn find_auto_created_partition(data_partition_id: &str) -> Option<(String, Option<u32>)> {
+        // 跳过 X 盘（PE系统盘）
+        let candidates = (b'A'..=b'Z')
+            .map(|letter| letter as char)
+            .filter(|c| *c != 'X' && Path::new(&format!("{}:\\", c)).exists())
+            .map(|c| format!("{}:", c))
+            .chain(Self::mounted_folders());
+
+        for partition in candidates {
+            let labelled = Self::volume_label(&partition).eq_ignore_ascii_case(DATA_PARTITION_LABEL);
+            let found = if !data_partition_id.is_empty() {
+                labelled
+                    && Self::partition_identity(&partition)
+                        .is_some_and(|id| id.eq_ignore_ascii_case(data_partition_id))
+            } else {
+                labelled || Path::new(&format!("{}\\{}", partition, AUTO_CREATED_PARTITION_MARKER)).exists()
+            };
+
+            if found {
+                log::info!("找到自动创建的分区: {}", partition);
+
                 // 获取该分区所在的磁盘号
-                let detail = Self::get_partition_style(&format!("{}:", c));
-                return Some((c, detail.disk_number));
+                let detail = Self::get_partition_style(&partition);
+                return Some((partition, detail.disk_number));
             }
         }
         None
@@ -418,6 +750,7 @@ impl DiskManager {
     /// 
     /// # Arguments
     /// * `target_partition` - 目标安装分区（如 "D:"），删除数据分区后要扩展的分区
+    /// * `data_partition_id` - 桌面端记录的数据分区标识，为空时按卷标或标志文件查找
     /// 
     /// 流程：
     /// 1. 找到自动创建的分区
@@ -428,7 +761,10 @@ impl DiskManager {
     /// 6. 刷新磁盘信息
     /// 7. 扩展目标分区以使用释放的空间
     /// 8. 验证分区大小是否增加
-    pub fn cleanup_auto_created_partition_and_extend(target_partition: &str) -> Result<()> {
+    pub fn cleanup_auto_created_partition_and_extend(
+        target_partition: &str,
+        data_partition_id: &str,
+    ) -> Result<()> {
         let target_letter = target_partition.chars().next().unwrap_or('C').to_ascii_uppercase();
         
         log::info!("[CLEANUP] ========================================");
@@ -437,7 +773,7 @@ impl DiskManager {
         log::info!("[CLEANUP] ========================================");
 
         // 查找自动创建的分区
-        let (auto_letter, auto_disk_num_opt) = match Self::find_auto_created_partition() {
+        let (auto_partition, auto_disk_num_opt) = match Self::find_auto_created_partition(data_partition_id) {
             Some(info) => info,
             None => {
                 log::info!("[CLEANUP] 未找到自动创建的分区，无需清理");
@@ -446,19 +782,19 @@ impl DiskManager {
         };
 
         // 获取自动创建分区的详细信息
-        let auto_detail = Self::get_partition_style(&format!("{}:", auto_letter));
+        let auto_detail = Self::get_partition_style(&auto_partition);
         let auto_disk_num = match auto_disk_num_opt.or(auto_detail.disk_number) {
             Some(num) => num,
             None => {
-                log::warn!("[CLEANUP] 无法获取自动创建分区 {} 的磁盘号，只删除不扩展", auto_letter);
-                return Self::delete_partition_by_letter(auto_letter);
+                log::warn!("[CLEANUP] 无法获取自动创建分区 {} 的磁盘号，只删除不扩展", auto_partition);
+                return Self::delete_partition_volume(&auto_partition);
             }
         };
         let auto_part_num = auto_detail.partition_number;
 
         log::info!(
-            "[CLEANUP] 找到自动创建的分区: {}:, 磁盘 {}, 分区号 {:?}",
-            auto_letter, auto_disk_num, auto_part_num
+            "[CLEANUP] 找到自动创建的分区: {}, 磁盘 {}, 分区号 {:?}",
+            auto_partition, auto_disk_num, auto_part_num
         );
 
         // 获取目标分区所在的磁盘号和分区号
@@ -467,7 +803,7 @@ impl DiskManager {
             Some(num) => num,
             None => {
                 log::warn!("[CLEANUP] 无法获取目标分区 {} 的磁盘号，只删除分区不扩展", target_letter);
-                return Self::delete_partition_by_letter(auto_letter);
+                return Self::delete_partition_volume(&auto_partition);
             }
         };
         let target_part_num = target_detail.partition_number;
@@ -483,7 +819,7 @@ impl DiskManager {
                 "[CLEANUP] 自动创建的分区 (磁盘{}) 和目标分区 (磁盘{}) 不在同一磁盘，只删除分区不扩展",
                 auto_disk_num, target_disk_num
             );
-            return Self::delete_partition_by_letter(auto_letter);
+            return Self::delete_partition_volume(&auto_partition);
         }
 
         // 检查分区相邻性：临时分区应该在目标分区之后
@@ -496,7 +832,7 @@ impl DiskManager {
                 );
                 log::warn!("[CLEANUP] extend 命令只能向后扩展，删除后的空间可能无法自动合并");
                 log::warn!("[CLEANUP] 将只删除分区，用户可在安装完成后使用磁盘管理工具手动合并");
-                return Self::delete_partition_by_letter(auto_letter);
+                return Self::delete_partition_volume(&auto_partition);
             }
             
             // 检查是否相邻（分区号相差1）
@@ -512,17 +848,17 @@ impl DiskManager {
         }
 
         // 删除自动创建分区并扩展目标分区
-        log::info!("[CLEANUP] 开始删除分区 {} 并扩展目标分区 {}...", auto_letter, target_letter);
-        Self::delete_partition_and_extend(auto_letter, target_letter, auto_disk_num)
+        log::info!("[CLEANUP] 开始删除分区 {} 并扩展目标分区 {}...", auto_partition, target_letter);
+        Self::delete_partition_and_extend(&auto_partition, target_letter, auto_disk_num)
     }
 
-    /// 删除指定盘符的分区
-    fn delete_partition_by_letter(letter: char) -> Result<()> {
-        log::info!("[CLEANUP] 删除分区 {}:", letter);
+    /// 删除指定盘符或挂载文件夹对应的分区
+    fn delete_partition_volume(partition: &str) -> Result<()> {
+        log::info!("[CLEANUP] 删除分区 {}", partition);
 
         let script_content = format!(
             "select volume {}\ndelete partition override",
-            letter
+            diskpart_volume(partition)
         );
 
         let temp_dir = Self::reliable_temp_dir();
@@ -547,13 +883,21 @@ impl DiskManager {
             anyhow::bail!("删除分区失败: {}", output_text);
         }
 
-        log::info!("[CLEANUP] 分区 {} 删除成功", letter);
+        Self::remove_mount_folder(partition);
+        log::info!("[CLEANUP] 分区 {} 删除成功", partition);
         Ok(())
     }
 
+    /// 分区删除后清理残留的挂载文件夹（盘符无需处理）
+    fn remove_mount_folder(partition: &str) {
+        if drive_letter_of(partition).is_none() {
+            let _ = fs::remove_dir(partition);
+        }
+    }
+
     /// 获取分区大小（MB）
     fn get_partition_size_mb(letter: char) -> Option<u64> {
-        let path = format!("{}:\\", letter);
+        let path = volume_root(&format!("{}:", letter));
         let wide_path: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
         
         let mut total_bytes: u64 = 0;
@@ -575,17 +919,17 @@ impl DiskManager {
     }
 
     /// 删除分区并扩展目标分区
-    fn delete_partition_and_extend(auto_letter: char, target_letter: char, disk_num: u32) -> Result<()> {
+    fn delete_partition_and_extend(auto_partition: &str, target_letter: char, disk_num: u32) -> Result<()> {
         // 记录扩展前的分区大小
         let size_before = Self::get_partition_size_mb(target_letter);
         log::info!("[CLEANUP] 扩展前目标分区大小: {:?} MB", size_before);
 
         // Step 1: 删除分区
-        log::info!("[CLEANUP] Step 1: 删除分区 {}:", auto_letter);
+        log::info!("[CLEANUP] Step 1: 删除分区 {}", auto_partition);
         
         let delete_script = format!(
             "select volume {}\ndelete partition override",
-            auto_letter
+            diskpart_volume(auto_partition)
         );
 
         let temp_dir = Self::reliable_temp_dir();
@@ -611,7 +955,8 @@ impl DiskManager {
             anyhow::bail!("删除分区失败: {}", output_text);
         }
 
-        log::info!("[CLEANUP] 分区 {} 删除成功", auto_letter);
+        Self::remove_mount_folder(auto_partition);
+        log::info!("[CLEANUP] 分区 {} 删除成功", auto_partition);
 
         // Step 2: 运行 rescan 命令刷新磁盘信息
         log::info!("[CLEANUP] Step 2: 刷新磁盘信息 (rescan)");
@@ -796,3 +1141,97 @@ impl DiskManager {
         anyhow::bail!("extend 状态不确定: {}", output_text)
     }
 }
+
+/// 从 diskpart `uniqueid disk` 和 `detail partition` 的输出中解析分区标识（磁盘ID:偏移）
+fn parse_partition_identity(output: &str) -> Option<String> {
+    let mut disk_id = None;
+    let mut offset = None;
+
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        if disk_id.is_none() && (key.eq_ignore_ascii_case("Disk ID") || key == "磁盘 ID") {
+            disk_id = Some(value.trim_matches(|c| c == '{' || c == '}').to_uppercase());
+        } else if offset.is_none() && (key.contains("Offset") || key.contains("偏移")) {
+            let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+            if !digits.is_empty() {
+                offset = Some(digits);
+            }
+        }
+    }
+
+    Some(format!("{}:{}", disk_id?, offset?))
+}
+
+/// 临时盘符
+///
+/// 分区内部统一通过卷 GUID 路径访问，只有 bcdboot、format 等必须使用盘符的外部工具才需要临时分配，
+/// 离开作用域时自动移除临时分配的盘符
+pub struct TempDriveLetter {
+    letter: char,
+    assigned: bool,
+}
+
+impl TempDriveLetter {
+    /// 获取分区的盘符：已有盘符直接使用，否则临时分配一个空闲盘符
+    pub fn acquire(partition: &str) -> Result<Self> {
+        if let Some(letter) = drive_letter_of(partition) {
+            return Ok(Self { letter, assigned: false });
+        }
+
+        let volume_name = DiskManager::volume_guid_path(partition)
+            .ok_or_else(|| anyhow::anyhow!("无法获取卷 {} 的 GUID 路径", partition))?;
+        let letter = ('D'..='Z')
+            .rev()
+            .find(|letter| !Path::new(&format!("{}:\\", letter)).exists())
+            .ok_or_else(|| anyhow::anyhow!("没有可用的盘符，无法为 {} 分配临时盘符", partition))?;
+        DiskManager::set_mount_point(&volume_name, &format!("{}:\\", letter))?;
+        Ok(Self { letter, assigned: true })
+    }
+
+    /// 盘符（如 "S:"）
+    pub fn drive(&self) -> String {
+        format!("{}:", self.letter)
+    }
+}
+
+impl Drop for TempDriveLetter {
+    fn drop(&mut self) {
+        if !self.assigned {
+            return;
+        }
+        let wide_mount: Vec<u16> = format!("{}:\\", self.letter)
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        if let Err(e) = unsafe { DeleteVolumeMountPointW(PCWSTR(wide_mount.as_ptr())) } {
+            log::warn!("移除临时盘符 {}: 失败: {}", self.letter, e);
+        }
+    }
+}
+
+/// 分区路径对应的盘符（仅 "C:"、"C:\\" 形式）
+fn drive_letter_of(partition: &str) -> Option<char> {
+    let trimmed = partition.trim_end_matches('\\');
+    let mut chars = trimmed.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(letter), Some(':'), None) if letter.is_ascii_alphabetic() => Some(letter.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// 分区根目录（带结尾反斜杠），分区可以是盘符、卷 GUID 路径或挂载文件夹
+pub fn volume_root(partition: &str) -> String {
+    format!("{}\\", partition.trim_end_matches('\\'))
+}
+
+/// diskpart `select volume` 的参数：盘符或挂载文件夹路径
+fn diskpart_volume(partition: &str) -> String {
+    match drive_letter_of(partition) {
+        Some(letter) => letter.to_string(),
+        None => volume_root(partition),
+    }
+}
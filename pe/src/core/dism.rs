@@ -1,8 +1,9 @@
 //! 镜像操作模块
 //!
 //! 该模块封装了 Windows 系统镜像操作功能：
-//! - 镜像释放/应用：使用 wimgapi.dll
-//! - 镜像备份/捕获：使用 wimgapi.dll
+//! - 镜像释放/应用：使用 wimgapi.dll，存在 wimlib.dll 时按桌面端的后端设置改用 wimlib；
+//!   SWM 分卷镜像在 wimlib 不可用时使用 dism.exe `/SWMFile`
+//! - 镜像备份/捕获：使用 wimgapi.dll，存在 wimlib.dll 时按桌面端的后端设置改用 wimlib
 //! - 驱动导入：使用 dism.exe 命令行（PE 环境兼容性最佳）
 //! - CAB 包安装：使用 dism.exe 命令行
 
@@ -10,8 +11,10 @@ use anyhow::Result;
 use std::path::Path;
 use std::sync::mpsc::Sender;
 
-use crate::core::dism_exe::{DismExe, DismExeProgress};
-use crate::core::wimgapi::{WimManager, WimProgress, WIM_COMPRESS_LZX, WIM_COMPRESS_LZMS};
+use crate::core::capture_options::CaptureOptions;
+use crate::core::dism_exe::{DismExe, DismExeProgress, DriverInjectResult};
+use crate::core::wimgapi::{WimManager, WimProgress, WIM_COMPRESS_LZMS};
+use crate::core::wimlib::{find_swm_parts, ImageBackend, Wimlib, WimlibProgress};
 
 /// 操作进度
 #[derive(Debug, Clone)]
@@ -31,19 +34,56 @@ pub struct ImageInfo {
     pub installation_type: String,
 }
 
-pub struct Dism;
+pub struct Dism {
+    backend: ImageBackend,
+}
 
 impl Dism {
     pub fn new() -> Self {
-        Self
+        Self::with_backend(ImageBackend::default())
+    }
+
+    /// 指定镜像释放/捕获后端
+    pub fn with_backend(backend: ImageBackend) -> Self {
+        Self { backend }
     }
 
     // ========================================================================
-    // 镜像操作 - 使用 wimgapi.dll
+    // 镜像操作 - 使用 wimgapi.dll / wimlib.dll
     // ========================================================================
 
-    /// 应用系统镜像 (WIM/ESD)
-    /// 使用 wimgapi.dll 实现
+    /// 是否为 SWM 分卷镜像
+    fn is_swm(image_file: &str) -> bool {
+        image_file.to_lowercase().ends_with(".swm")
+    }
+
+    /// 将 wimlib 进度转发为 DismProgress
+    fn forward_wimlib_progress(
+        progress_tx: Option<Sender<DismProgress>>,
+    ) -> (Sender<WimlibProgress>, std::thread::JoinHandle<()>) {
+        let (wimlib_tx, wimlib_rx) = std::sync::mpsc::channel::<WimlibProgress>();
+        let handle = std::thread::spawn(move || {
+            while let Ok(progress) = wimlib_rx.recv() {
+                if let Some(ref tx) = progress_tx {
+                    let _ = tx.send(DismProgress {
+                        percentage: progress.percentage,
+                        status: progress.status,
+                    });
+                }
+            }
+        });
+        (wimlib_tx, handle)
+    }
+
+    /// 按后端设置加载支持释放的 wimlib（SWM 分卷还需要支持引用分卷）
+    fn wimlib_for_apply(&self, image_file: &str) -> Option<Wimlib> {
+        self.backend
+            .load_wimlib()
+            .filter(|w| w.supports_apply() && (!Self::is_swm(image_file) || w.supports_split_wim()))
+    }
+
+    /// 应用系统镜像 (WIM/ESD/SWM)
+    /// 优先使用 wimlib（按后端设置），否则使用 wimgapi.dll
     pub fn apply_image(
         &self,
         image_file: &str,
@@ -51,6 +91,25 @@ impl Dism {
         index: u32,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
+        if let Some(wimlib) = self.wimlib_for_apply(image_file) {
+            log::info!("[Dism] 使用 wimlib 应用镜像: {} -> {}", image_file, apply_dir);
+            let (wimlib_tx, forward_thread) = Self::forward_wimlib_progress(progress_tx);
+            let result = wimlib.apply_image(image_file, index, apply_dir, Some(wimlib_tx));
+            let _ = forward_thread.join();
+            return match result {
+                Ok(_) => {
+                    log::info!("[Dism] 镜像应用成功");
+                    Ok(())
+                }
+                Err(e) => anyhow::bail!("镜像应用失败: {}", e.detailed()),
+            };
+        }
+
+        // 没有支持分卷的 wimlib 时，SWM 交给 dism.exe 用 /SWMFile 引用其余分卷
+        if Self::is_swm(image_file) {
+            return self.apply_swm_with_dism_exe(image_file, apply_dir, index, progress_tx);
+        }
+
         log::info!("[Dism] 使用 wimgapi 应用镜像: {} -> {}", image_file, apply_dir);
 
         let wim_manager = WimManager::new()
@@ -89,16 +148,93 @@ impl Dism {
         }
     }
 
+    /// 使用 dism.exe 释放 SWM 分卷镜像（传入任一分卷均可）
+    fn apply_swm_with_dism_exe(
+        &self,
+        image_file: &str,
+        apply_dir: &str,
+        index: u32,
+        progress_tx: Option<Sender<DismProgress>>,
+    ) -> Result<()> {
+        let first_part = find_swm_parts(image_file).remove(0);
+        let first_part = first_part.to_string_lossy();
+        log::info!("[Dism] 使用 dism.exe 释放分卷镜像: {} -> {}", first_part, apply_dir);
+
+        let dism_exe = DismExe::new()
+            .map_err(|e| anyhow::anyhow!("dism.exe 初始化失败: {}", e))?;
+
+        let (exe_tx, exe_rx) = std::sync::mpsc::channel::<DismExeProgress>();
+        let forward_thread = std::thread::spawn(move || {
+            while let Ok(progress) = exe_rx.recv() {
+                if let Some(ref tx) = progress_tx {
+                    let _ = tx.send(DismProgress {
+                        percentage: progress.percentage,
+                        status: progress.status,
+                    });
+                }
+            }
+        });
+
+        let result = dism_exe.apply_swm(&first_part, apply_dir, index, Some(exe_tx));
+        let _ = forward_thread.join();
+
+        match result {
+            Ok(()) => {
+                log::info!("[Dism] 镜像应用成功");
+                Ok(())
+            }
+            Err(e) => anyhow::bail!("镜像应用失败: {}", e),
+        }
+    }
+
     /// 捕获系统镜像 (备份)
-    /// 使用 wimgapi.dll 实现
+    /// 优先使用 wimlib（按后端设置），否则使用 wimgapi.dll；按 `options` 设置压缩方式和排除规则
     pub fn capture_image(
         &self,
         image_file: &str,
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
+        log::info!(
+            "[Dism] 捕获选项: {}，排除 {} 条规则",
+            options.compression.label(),
+            options.exclusions.len()
+        );
+
+        // 分卷备份始终交给 wimgapi
+        let wimlib = self
+            .backend
+            .load_wimlib()
+            .filter(|w| w.supports_capture() && !Self::is_swm(image_file));
+        if let Some(wimlib) = wimlib {
+            log::info!("[Dism] 使用 wimlib 捕获镜像: {} -> {}", capture_dir, image_file);
+            // wimlib 通过 WimScript.ini 格式的配置文件读取排除规则
+            let config_file = std::env::temp_dir().join("LetRecovery_WimScript.ini");
+            options.write_wimscript(&config_file)?;
+            let (wimlib_tx, forward_thread) = Self::forward_wimlib_progress(progress_tx);
+            let result = wimlib.capture_image(
+                capture_dir,
+                image_file,
+                name,
+                description,
+                options.compression.wimlib_type(),
+                Some(&config_file),
+                Some(wimlib_tx),
+            );
+            let _ = forward_thread.join();
+            let _ = std::fs::remove_file(&config_file);
+            return match result {
+                Ok(_) => {
+                    log::info!("[Dism] 镜像捕获成功");
+                    Ok(())
+                }
+                Err(e) => anyhow::bail!("镜像捕获失败: {}", e.detailed()),
+            };
+        }
+
         log::info!("[Dism] 使用 wimgapi 捕获镜像: {} -> {}", capture_dir, image_file);
 
         let wim_manager = WimManager::new()
@@ -123,7 +259,8 @@ impl Dism {
             image_file,
             name,
             description,
-            WIM_COMPRESS_LZX,
+            options.compression.wimgapi_type(),
+            &options.exclusions,
             Some(wim_tx),
         );
 
@@ -148,12 +285,13 @@ impl Dism {
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
         log::info!("[Dism] 使用 wimgapi 追加镜像: {} -> {}", capture_dir, image_file);
 
         // 对于追加操作，WimManager 的 capture_image 在文件存在时会自动追加
-        self.capture_image(image_file, capture_dir, name, description, progress_tx)
+        self.capture_image(image_file, capture_dir, name, description, options, progress_tx)
     }
 
     /// 捕获系统镜像为ESD格式（高压缩）
@@ -164,6 +302,7 @@ impl Dism {
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
         log::info!("[Dism] 使用 wimgapi 捕获ESD镜像: {} -> {}", capture_dir, image_file);
@@ -191,6 +330,7 @@ impl Dism {
             name,
             description,
             WIM_COMPRESS_LZMS,
+            &options.exclusions,
             Some(wim_tx),
         );
 
@@ -214,14 +354,16 @@ impl Dism {
         capture_dir: &str,
         name: &str,
         description: &str,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
         log::info!("[Dism] 使用 wimgapi 追加ESD镜像: {} -> {}", capture_dir, image_file);
-        self.capture_image_esd(image_file, capture_dir, name, description, progress_tx)
+        self.capture_image_esd(image_file, capture_dir, name, description, options, progress_tx)
     }
 
     /// 捕获系统镜像为SWM分卷格式
     /// 先创建WIM，然后分割
+    #[allow(clippy::too_many_arguments)]
     pub fn capture_image_swm(
         &self,
         image_file: &str,
@@ -229,6 +371,7 @@ impl Dism {
         name: &str,
         description: &str,
         split_size_mb: u32,
+        options: &CaptureOptions,
         progress_tx: Option<Sender<DismProgress>>,
     ) -> Result<()> {
         log::info!("[Dism] 捕获SWM分卷镜像: {} -> {} (分卷大小: {}MB)", capture_dir, image_file, split_size_mb);
@@ -267,7 +410,8 @@ impl Dism {
             &temp_wim,
             name,
             description,
-            WIM_COMPRESS_LZX,
+            options.compression.wimgapi_type(),
+            &options.exclusions,
             Some(wim_tx),
         );
 
@@ -313,33 +457,24 @@ impl Dism {
     // ========================================================================
 
     /// 导入驱动到离线系统 (PE环境下使用)
-    /// 使用 dism.exe 命令行实现，在 PE 环境下兼容性最佳
+    /// 使用 dism.exe 命令行逐个 INF 导入，单个驱动失败只记录日志
     pub fn add_drivers_offline(&self, image_path: &str, driver_path: &str) -> Result<()> {
-        log::info!(
-            "[Dism] 使用 dism.exe 离线导入驱动: {} -> {}",
-            driver_path,
-            image_path
-        );
-
-        // 使用 dism.exe 命令行方式导入驱动
-        let dism_exe = DismExe::new()
-            .map_err(|e| anyhow::anyhow!("dism.exe 初始化失败: {}", e))?;
-
-        dism_exe.add_driver_offline(image_path, driver_path, true, false, None)?;
-
-        log::info!("[Dism] 离线驱动导入完成");
+        self.add_drivers_offline_with_progress(image_path, driver_path, None)?;
         Ok(())
     }
 
     /// 导入驱动到离线系统（带进度回调）
+    ///
+    /// 枚举驱动目录中的 INF 并依次调用 dism.exe 导入，返回每个 INF 的结果。
+    /// 只有全部驱动都失败时才返回错误，部分失败由调用方逐个报告。
     pub fn add_drivers_offline_with_progress(
         &self,
         image_path: &str,
         driver_path: &str,
         progress_tx: Option<Sender<DismProgress>>,
-    ) -> Result<()> {
+    ) -> Result<Vec<DriverInjectResult>> {
         log::info!(
-            "[Dism] 使用 dism.exe 离线导入驱动（带进度）: {} -> {}",
+            "[Dism] 使用 dism.exe 离线导入驱动: {} -> {}",
             driver_path,
             image_path
         );
@@ -363,22 +498,61 @@ impl Dism {
             }
         });
 
-        let result = dism_exe.add_driver_offline(image_path, driver_path, true, false, Some(exe_tx));
+        let result = dism_exe.add_drivers_individually(
+            image_path,
+            Path::new(driver_path),
+            false,
+            Some(exe_tx),
+        );
 
         // 等待转发线程结束
         let _ = forward_thread.join();
 
         match result {
-            Ok(_) => {
-                log::info!("[Dism] 离线驱动导入成功");
-                Ok(())
-            }
+            Ok(results) => Self::check_driver_results(results),
             Err(e) => {
                 anyhow::bail!("离线驱动导入失败: {}", e)
             }
         }
     }
 
+    /// 全部驱动导入失败时返回错误（通常是镜像路径或 dism.exe 本身的问题）
+    fn check_driver_results(results: Vec<DriverInjectResult>) -> Result<Vec<DriverInjectResult>> {
+        if !results.is_empty() && results.iter().all(|r| !r.is_success()) {
+            let first = results[0].failure_message().unwrap_or_default();
+            anyhow::bail!("离线驱动导入失败: {} 个驱动全部失败，首个错误: {}", results.len(), first);
+        }
+        log::info!("[Dism] 离线驱动导入完成");
+        Ok(results)
+    }
+
+    /// 从目标分区的现有系统导出第三方驱动（格式化前调用，供释放镜像后重新注入）
+    ///
+    /// 目标分区上没有 Windows 时返回 0，否则返回导出的驱动包数量
+    pub fn harvest_drivers(&self, target_partition: &str, destination: &str) -> Result<usize> {
+        let system_root = format!("{}\\", target_partition.trim_end_matches('\\'));
+        if !Path::new(&system_root).join("Windows\\System32\\config\\SYSTEM").exists() {
+            log::info!("[Dism] {} 上没有可导出驱动的 Windows 系统", target_partition);
+            return Ok(0);
+        }
+
+        // 清除上次安装残留的驱动，避免注入旧驱动
+        if Path::new(destination).exists() {
+            let _ = std::fs::remove_dir_all(destination);
+        }
+
+        let dism_exe = DismExe::new()
+            .map_err(|e| anyhow::anyhow!("dism.exe 初始化失败: {}", e))?;
+        dism_exe.export_driver_offline(&system_root, destination, None)?;
+
+        // dism.exe 为每个驱动包创建一个子目录
+        let count = std::fs::read_dir(destination)
+            .map(|entries| entries.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()).count())
+            .unwrap_or(0);
+        log::info!("[Dism] 已从 {} 导出 {} 个第三方驱动包", target_partition, count);
+        Ok(count)
+    }
+
     /// 添加 CAB 更新包到离线系统
     /// 使用 dism.exe 命令行实现
     pub fn add_package_offline(&self, image_path: &str, cab_path: &str) -> Result<()> {
@@ -1,8 +1,9 @@
 //! DISM.exe 命令行封装模块
 //!
 //! 该模块使用 PE 环境自带的 dism.exe 命令行工具实现：
-//! - 离线驱动导入
+//! - 离线驱动导入（逐个 INF 导入，保留每个驱动的错误码）
 //! - 离线 Windows Update CAB 包安装
+//! - SWM 分卷镜像释放（`/SWMFile` 引用其余分卷）
 //!
 //! 相比 DISM API 或 WinAPI，直接调用 dism.exe 在 PE 环境下更加可靠稳定。
 //! dism.exe 位于 PE 环境的 X:\Windows\System32\dism.exe
@@ -14,6 +15,8 @@ use std::sync::mpsc::Sender;
 
 use anyhow::{bail, Context, Result};
 
+use crate::core::install_engine::StepContext;
+use crate::core::wimlib::swm_file_pattern;
 use crate::utils::encoding::gbk_to_utf8;
 
 /// Windows CREATE_NO_WINDOW 标志，用于隐藏控制台窗口
@@ -27,6 +30,62 @@ pub struct DismExeProgress {
     pub status: String,
 }
 
+/// 单个 INF 驱动的导入结果
+#[derive(Debug, Clone)]
+pub struct DriverInjectResult {
+    /// INF 文件路径
+    pub inf: PathBuf,
+    /// dism.exe 退出码（无法启动时为 None）
+    pub exit_code: Option<i32>,
+    /// 失败时的错误信息
+    pub error: Option<String>,
+}
+
+impl DriverInjectResult {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// 失败描述（文件名、错误码和错误信息），成功时返回 None
+    pub fn failure_message(&self) -> Option<String> {
+        let error = self.error.as_ref()?;
+        let name = self
+            .inf
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.inf.display().to_string());
+        Some(format!(
+            "{} (错误码 {}): {}",
+            name,
+            format_exit_code(self.exit_code),
+            error.lines().next().unwrap_or_default().trim()
+        ))
+    }
+}
+
+/// 逐个报告导入失败的驱动（含错误码），并汇总成功/失败数量
+pub fn report_driver_results(ctx: &StepContext, results: &[DriverInjectResult]) {
+    let failures: Vec<String> = results.iter().filter_map(|r| r.failure_message()).collect();
+    for failure in &failures {
+        ctx.warn(format!("导入驱动失败: {}", failure));
+    }
+    log::info!("驱动导入完成: {} 成功, {} 失败", results.len() - failures.len(), failures.len());
+    ctx.status(format!(
+        "驱动导入完成: {} 成功, {} 失败",
+        results.len() - failures.len(),
+        failures.len()
+    ));
+}
+
+/// 格式化 dism.exe 退出码（HRESULT 按十六进制显示）
+pub fn format_exit_code(code: Option<i32>) -> String {
+    match code {
+        Some(code) if code < 0 => format!("0x{:08X}", code as u32),
+        Some(code) => code.to_string(),
+        None => "无".to_string(),
+    }
+}
+
 /// DISM.exe 执行器
 ///
 /// 封装了使用 dism.exe 命令行工具进行离线镜像服务的所有操作。
@@ -273,15 +332,7 @@ impl DismExe {
         }
 
         if !status.success() {
-            let error_msg = if !stderr_text.trim().is_empty() {
-                stderr_text.trim().to_string()
-            } else if !stdout_text.trim().is_empty() {
-                // DISM 有时会将错误信息输出到 stdout
-                Self::extract_error_from_output(&stdout_text)
-            } else {
-                format!("dism.exe 退出码: {:?}", status.code())
-            };
-
+            let error_msg = Self::failure_message(&stdout_text, &stderr_text, status.code());
             bail!("DISM 操作失败: {}", error_msg);
         }
 
@@ -289,6 +340,43 @@ impl DismExe {
         Ok(stdout_text)
     }
 
+    /// 执行 DISM 命令并保留退出码（不解析进度）
+    ///
+    /// # 返回
+    /// - Ok((退出码, 失败时的错误信息))，无法启动 dism.exe 时返回 Err
+    fn execute_with_exit_code(&self, args: &[&str]) -> Result<(Option<i32>, Option<String>)> {
+        log::info!("[DISM.EXE] 执行: {} {}", self.dism_path.display(), args.join(" "));
+
+        let output = self
+            .create_command()
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .context("启动 dism.exe 失败")?;
+
+        if output.status.success() {
+            return Ok((output.status.code(), None));
+        }
+
+        let stdout_text = gbk_to_utf8(&output.stdout);
+        let stderr_text = gbk_to_utf8(&output.stderr);
+        let error_msg = Self::failure_message(&stdout_text, &stderr_text, output.status.code());
+        Ok((output.status.code(), Some(error_msg)))
+    }
+
+    /// 根据输出生成失败信息
+    fn failure_message(stdout_text: &str, stderr_text: &str, code: Option<i32>) -> String {
+        if !stderr_text.trim().is_empty() {
+            stderr_text.trim().to_string()
+        } else if !stdout_text.trim().is_empty() {
+            // DISM 有时会将错误信息输出到 stdout
+            Self::extract_error_from_output(stdout_text)
+        } else {
+            format!("dism.exe 退出码: {}", format_exit_code(code))
+        }
+    }
+
     /// 解析 DISM 输出中的进度信息
     ///
     /// DISM 输出格式通常为:
@@ -358,80 +446,202 @@ impl DismExe {
     // 公共 API - 驱动操作
     // =========================================================================
 
-    /// 添加驱动到离线系统镜像
-    ///
-    /// 使用 dism.exe /Add-Driver 命令将驱动添加到离线 Windows 镜像。
+    /// 导入单个 INF 驱动到离线系统，保留 dism.exe 退出码
     ///
     /// # 参数
     /// - `image_path`: 离线系统根目录（如 "D:\\"）
-    /// - `driver_path`: 驱动目录或 INF 文件路径
-    /// - `recurse`: 是否递归搜索子目录
+    /// - `inf`: INF 文件路径
+    /// - `scratch_dir`: 本次调用使用的临时目录
     /// - `force_unsigned`: 是否强制安装未签名驱动
-    /// - `progress_tx`: 进度通道（可选）
+    pub fn add_single_driver_offline(
+        &self,
+        image_path: &str,
+        inf: &Path,
+        scratch_dir: &str,
+        force_unsigned: bool,
+    ) -> DriverInjectResult {
+        let normalized_image = format!("{}\\", image_path.trim_end_matches('\\'));
+        let mut args = vec![
+            format!("/Image:{}", normalized_image),
+            "/Add-Driver".to_string(),
+            format!("/Driver:{}", inf.display()),
+            format!("/scratchdir:{}", scratch_dir),
+        ];
+        if force_unsigned {
+            args.push("/ForceUnsigned".to_string());
+        }
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+        let (exit_code, error) = match self.execute_with_exit_code(&args_ref) {
+            Ok(result) => result,
+            Err(e) => (None, Some(format!("{:#}", e))),
+        };
+        DriverInjectResult {
+            inf: inf.to_path_buf(),
+            exit_code,
+            error,
+        }
+    }
+
+    /// 逐个 INF 导入目录中的驱动
     ///
-    /// # 示例
-    /// ```ignore
-    /// let dism = DismExe::new()?;
-    /// dism.add_driver_offline("D:\\", "C:\\Drivers", true, false, None)?;
-    /// ```
-    pub fn add_driver_offline(
+    /// 递归枚举 `driver_dir` 下的 INF 文件（也可直接传入单个 INF），
+    /// 依次调用 dism.exe 导入，单个驱动失败不影响其他驱动。
+    /// 同一离线镜像同时只运行一个 dism.exe，避免争用驱动库和镜像挂载状态。
+    ///
+    /// # 返回
+    /// - 每个 INF 的导入结果（按路径排序），目录中没有 INF 时为空
+    pub fn add_drivers_individually(
         &self,
         image_path: &str,
-        driver_path: &str,
-        recurse: bool,
+        driver_dir: &Path,
         force_unsigned: bool,
         progress_tx: Option<Sender<DismExeProgress>>,
-    ) -> Result<()> {
+    ) -> Result<Vec<DriverInjectResult>> {
+        if !driver_dir.exists() {
+            bail!("驱动路径不存在: {}", driver_dir.display());
+        }
+
+        let infs = if driver_dir.is_file() {
+            vec![driver_dir.to_path_buf()]
+        } else {
+            Self::find_inf_files(driver_dir)
+        };
+        let total = infs.len();
+        if total == 0 {
+            log::info!("[DISM.EXE] 目录中没有找到 INF 文件: {}", driver_dir.display());
+            return Ok(Vec::new());
+        }
+        log::info!("[DISM.EXE] 在 {} 中找到 {} 个 INF", driver_dir.display(), total);
+
+        let scratch_dir = format!("{}\\LetRecovery_drv", Self::ensure_scratch_directory());
+        let _ = std::fs::create_dir_all(&scratch_dir);
+
+        let mut results = Vec::with_capacity(total);
+        for (index, inf) in infs.iter().enumerate() {
+            let result = self.add_single_driver_offline(image_path, inf, &scratch_dir, force_unsigned);
+            match result.failure_message() {
+                None => log::info!("[DISM.EXE] 驱动导入成功: {}", inf.display()),
+                Some(msg) => log::warn!("[DISM.EXE] 驱动导入失败: {}", msg),
+            }
+            results.push(result);
+
+            if let Some(ref tx) = progress_tx {
+                let done = index + 1;
+                let _ = tx.send(DismExeProgress {
+                    percentage: ((done * 100) / total) as u8,
+                    status: format!("导入驱动 {}/{}", done, total),
+                });
+            }
+        }
+        let _ = std::fs::remove_dir_all(&scratch_dir);
+
+        results.sort_by(|a, b| a.inf.cmp(&b.inf));
+        let failed = results.iter().filter(|r| !r.is_success()).count();
         log::info!(
-            "[DISM.EXE] 添加驱动到离线系统: {} -> {}",
-            driver_path,
-            image_path
+            "[DISM.EXE] 驱动导入完成: 成功 {}, 失败 {}",
+            total - failed,
+            failed
         );
+        Ok(results)
+    }
+
+    /// 递归查找目录中的所有 INF 文件（按路径排序）
+    pub fn find_inf_files(dir: &Path) -> Vec<PathBuf> {
+        let mut inf_files = Vec::new();
 
-        // 验证路径
-        let driver_path_obj = Path::new(driver_path);
-        if !driver_path_obj.exists() {
-            bail!("驱动路径不存在: {}", driver_path);
+        if let Ok(entries) = std::fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file() {
+                    if let Some(ext) = path.extension() {
+                        if ext.to_string_lossy().to_lowercase() == "inf" {
+                            inf_files.push(path);
+                        }
+                    }
+                } else if path.is_dir() {
+                    inf_files.extend(Self::find_inf_files(&path));
+                }
+            }
         }
 
-        // 规范化镜像路径（确保以反斜杠结尾）
+        inf_files.sort();
+        inf_files
+    }
+
+    /// 从离线系统导出第三方驱动
+    ///
+    /// 等效于: `dism /Image:<image_path> /Export-Driver /Destination:<destination>`
+    ///
+    /// # 参数
+    /// - `image_path`: 离线系统根目录（如 "D:\\"）
+    /// - `destination`: 导出目标目录
+    /// - `progress_tx`: 进度通道（可选）
+    pub fn export_driver_offline(
+        &self,
+        image_path: &str,
+        destination: &str,
+        progress_tx: Option<Sender<DismExeProgress>>,
+    ) -> Result<()> {
+        log::info!("[DISM.EXE] 从离线系统导出驱动: {} -> {}", image_path, destination);
+
         let normalized_image = if image_path.ends_with('\\') {
             image_path.to_string()
         } else {
             format!("{}\\", image_path)
         };
+        if !Path::new(&normalized_image).exists() {
+            bail!("离线系统路径不存在: {}", normalized_image);
+        }
+        std::fs::create_dir_all(destination).with_context(|| format!("创建导出目录失败: {}", destination))?;
 
-        // 确保 scratchdir 存在
         let scratch_dir = Self::ensure_scratch_directory();
-
-        // 构建命令参数
-        let mut args = vec![
-            "/Image:".to_string() + &normalized_image,
-            "/Add-Driver".to_string(),
-            "/Driver:".to_string() + driver_path,
+        let args = [
+            format!("/Image:{}", normalized_image),
+            "/Export-Driver".to_string(),
+            format!("/Destination:{}", destination),
+            format!("/scratchdir:{}", scratch_dir),
         ];
+        let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
-        if recurse {
-            args.push("/Recurse".to_string());
-        }
+        self.execute_with_progress(&args_ref, progress_tx)?;
+        Ok(())
+    }
 
-        args.push(format!("/scratchdir:{}", scratch_dir));
+    // =========================================================================
+    // 公共 API - 更新包操作
+    // =========================================================================
 
-        if force_unsigned {
-            args.push("/ForceUnsigned".to_string());
+    /// 释放 SWM 分卷镜像到目标目录（index 从 1 开始）
+    ///
+    /// `swm_file` 为主分卷，其余分卷须与主分卷位于同一目录并按 xxx2.swm、xxx3.swm 命名
+    pub fn apply_swm(
+        &self,
+        swm_file: &str,
+        apply_dir: &str,
+        index: u32,
+        progress_tx: Option<Sender<DismExeProgress>>,
+    ) -> Result<()> {
+        log::info!("[DISM.EXE] 释放 SWM 分卷镜像: {} -> {}", swm_file, apply_dir);
+        if !Path::new(swm_file).exists() {
+            bail!("SWM 主分卷不存在: {}", swm_file);
         }
 
-        // 转换为 &str 切片
+        let scratch_dir = Self::ensure_scratch_directory();
+        let args = [
+            "/Apply-Image".to_string(),
+            format!("/ImageFile:{}", swm_file),
+            format!("/SWMFile:{}", swm_file_pattern(Path::new(swm_file))),
+            format!("/Index:{}", index),
+            format!("/ApplyDir:{}", apply_dir),
+            format!("/scratchdir:{}", scratch_dir),
+        ];
         let args_ref: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
         self.execute_with_progress(&args_ref, progress_tx)?;
         Ok(())
     }
 
-    // =========================================================================
-    // 公共 API - 更新包操作
-    // =========================================================================
-
     /// 添加 Windows Update CAB 包到离线系统镜像
     ///
     /// 使用 dism.exe /Add-Package 命令安装 Windows Update 包。
@@ -632,6 +842,30 @@ mod tests {
         assert!(DismExe::parse_progress_line("Random text").is_none());
     }
 
+    #[test]
+    fn test_format_exit_code() {
+        assert_eq!(format_exit_code(Some(-2146498530)), "0x800F081E");
+        assert_eq!(format_exit_code(Some(2)), "2");
+        assert_eq!(format_exit_code(None), "无");
+    }
+
+    #[test]
+    fn test_driver_failure_message() {
+        let ok = DriverInjectResult {
+            inf: Path::new("Drivers").join("net").join("e1d.inf"),
+            exit_code: Some(0),
+            error: None,
+        };
+        assert!(ok.failure_message().is_none());
+
+        let failed = DriverInjectResult {
+            error: Some("Error: 2\n系统找不到指定的文件。".to_string()),
+            exit_code: Some(2),
+            ..ok
+        };
+        assert_eq!(failed.failure_message().unwrap(), "e1d.inf (错误码 2): Error: 2");
+    }
+
     #[test]
     fn test_extract_error() {
         let output = "Line 1\nError: Something went wrong\nDetails here\nMore info\nLast line";
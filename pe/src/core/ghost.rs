@@ -123,9 +123,12 @@ impl Ghost {
     }
 
     /// 恢复 GHO 镜像到指定分区
+    ///
+    /// `source_partition` 为磁盘镜像中要还原的分区序号（从 1 开始），0 表示不指定（分区镜像）。
     pub fn restore_image(
         &self,
         gho_file: &str,
+        source_partition: u32,
         disk_number: u32,
         partition_number: u32,
         progress_tx: Option<Sender<DismProgress>>,
@@ -151,6 +154,9 @@ impl Ghost {
         log::info!("========================================");
         log::info!("开始恢复 GHO 镜像");
         log::info!("镜像文件: {}", gho_file);
+        if source_partition > 0 {
+            log::info!("镜像中的分区: {}", source_partition);
+        }
         log::info!(
             "目标分区: {} (磁盘 {} 分区 {})",
             target_partition,
@@ -171,7 +177,12 @@ impl Ghost {
             });
         }
 
-        let clone_param = format!("-clone,mode=pload,src={},dst={}", gho_file, target_partition);
+        let source = if source_partition > 0 {
+            format!("{}:{}", gho_file, source_partition)
+        } else {
+            gho_file.to_string()
+        };
+        let clone_param = format!("-clone,mode=pload,src={},dst={}", source, target_partition);
 
         log::info!(
             "执行命令: {} {} -sure -fx -batch",
@@ -198,6 +209,7 @@ impl Ghost {
     pub fn restore_image_to_letter(
         &self,
         gho_file: &str,
+        source_partition: u32,
         target_letter: &str,
         partitions: &[Partition],
         progress_tx: Option<Sender<DismProgress>>,
@@ -251,7 +263,7 @@ impl Ghost {
         );
         log::info!("  Ghost:   {}:{}", ghost_disk, ghost_partition);
 
-        self.restore_image(gho_file, ghost_disk, ghost_partition, progress_tx)
+        self.restore_image(gho_file, source_partition, ghost_disk, ghost_partition, progress_tx)
     }
 
     /// 从盘符创建GHO镜像（备份）
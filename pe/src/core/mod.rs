@@ -1,12 +1,74 @@
+// shared 目录中的模块与桌面端共用，部分函数只有桌面端使用
+#[path = "../../../shared/apply_verify.rs"]
+#[allow(dead_code)]
+pub mod apply_verify;
+#[path = "../../../shared/auto_unlock.rs"]
+#[allow(dead_code)]
+pub mod auto_unlock;
+#[path = "../../../shared/backup_catalog.rs"]
+#[allow(dead_code)]
+pub mod backup_catalog;
 pub mod bcdedit;
 pub mod cabinet;
+#[path = "../../../shared/capture_options.rs"]
+#[allow(dead_code)]
+pub mod capture_options;
 pub mod config;
+#[path = "../../../shared/config_schema.rs"]
+pub mod config_schema;
 pub mod dism;
 pub mod dism_exe;
 pub mod dismapi;
 pub mod disk;
+#[path = "../../../shared/disk_policy.rs"]
+#[allow(dead_code)]
+pub mod disk_policy;
 pub mod driver;
+#[path = "../../../shared/driver_signing.rs"]
+pub mod driver_signing;
 pub mod ghost;
+#[path = "../../../shared/health_check.rs"]
+pub mod health_check;
+#[path = "../../../shared/image_language.rs"]
+pub mod image_language;
+#[path = "../../../shared/install_engine.rs"]
+pub mod install_engine;
+#[path = "../../../shared/install_secrets.rs"]
+#[allow(dead_code)]
+pub mod install_secrets;
+#[path = "../../../shared/netcast.rs"]
+#[allow(dead_code)]
+pub mod netcast;
+#[path = "../../../shared/network_source.rs"]
+#[allow(dead_code)]
+pub mod network_source;
+#[path = "../../../shared/nt5_boot.rs"]
+pub mod nt5_boot;
+#[path = "../../../shared/offline_registry.rs"]
+#[allow(dead_code)]
+pub mod offline_registry;
+#[path = "../../../shared/partition_plan.rs"]
+#[allow(dead_code)]
+pub mod partition_plan;
+pub mod power;
 pub mod registry;
+pub mod script_hooks;
+#[path = "../../../shared/secure_dir.rs"]
+#[allow(dead_code)]
+pub mod secure_dir;
+pub mod storage_controller;
 pub mod system_utils;
+#[path = "../../../shared/unattend.rs"]
+#[allow(dead_code)]
+pub mod unattend;
+#[path = "../../../shared/volume_trim.rs"]
+pub mod volume_trim;
+pub mod watchdog;
+pub mod wifi;
 pub mod wimgapi;
+#[path = "../../../shared/wimlib.rs"]
+#[allow(dead_code)]
+pub mod wimlib;
+#[path = "../../../shared/zst_pack.rs"]
+#[allow(dead_code)]
+pub mod zst_pack;
@@ -0,0 +1,95 @@
+//! 完成后的电源操作
+//!
+//! 安装、备份结束后的重启、关机、进入固件设置统一在这里通过 shutdown.exe 执行，
+//! 具体操作由安装配置中的 [`CompletionAction`] 决定，PE 完成界面上也可以临时更改。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::command::new_command;
+
+/// 完成后的操作
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompletionAction {
+    /// 立即重启
+    RebootNow,
+    /// 倒计时结束后重启（倒计时期间可取消）
+    #[default]
+    RebootCountdown,
+    /// 关机
+    Shutdown,
+    /// 不重启，留在 PE（或当前系统）中
+    StayInPe,
+    /// 重启并进入 UEFI 固件设置
+    FirmwareSetup,
+}
+
+impl CompletionAction {
+    /// 全部操作（按显示顺序）
+    pub const ALL: [CompletionAction; 5] = [
+        CompletionAction::RebootNow,
+        CompletionAction::RebootCountdown,
+        CompletionAction::Shutdown,
+        CompletionAction::StayInPe,
+        CompletionAction::FirmwareSetup,
+    ];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            CompletionAction::RebootNow => "立即重启",
+            CompletionAction::RebootCountdown => "倒计时后重启",
+            CompletionAction::Shutdown => "关机",
+            CompletionAction::StayInPe => "不重启（留在 PE）",
+            CompletionAction::FirmwareSetup => "重启进入固件设置",
+        }
+    }
+
+    /// 执行前是否先倒计时（立即重启直接执行，留在 PE 不执行任何操作）
+    pub fn uses_countdown(&self) -> bool {
+        matches!(
+            self,
+            CompletionAction::RebootCountdown | CompletionAction::Shutdown | CompletionAction::FirmwareSetup
+        )
+    }
+
+    /// 操作动词，用于倒计时提示（如 "15 秒后自动关机"）
+    pub fn verb(&self) -> &'static str {
+        match self {
+            CompletionAction::RebootNow | CompletionAction::RebootCountdown => "重启",
+            CompletionAction::Shutdown => "关机",
+            CompletionAction::StayInPe => "留在 PE",
+            CompletionAction::FirmwareSetup => "进入固件设置",
+        }
+    }
+
+    /// shutdown.exe 参数，留在 PE 时返回 None
+    fn shutdown_args(&self, delay_secs: u64, comment: &str) -> Option<Vec<String>> {
+        let mut args: Vec<String> = match self {
+            CompletionAction::RebootNow | CompletionAction::RebootCountdown => vec!["/r".into()],
+            CompletionAction::Shutdown => vec!["/s".into()],
+            CompletionAction::FirmwareSetup => vec!["/r".into(), "/fw".into()],
+            CompletionAction::StayInPe => return None,
+        };
+        args.extend(["/t".to_string(), delay_secs.to_string()]);
+        if !comment.is_empty() {
+            args.extend(["/c".to_string(), comment.to_string()]);
+        }
+        Some(args)
+    }
+
+    /// 执行操作，系统在 `delay_secs` 秒后重启/关机（期间可用 `shutdown /a` 取消）
+    pub fn execute(&self, delay_secs: u64, comment: &str) -> Result<()> {
+        let Some(args) = self.shutdown_args(delay_secs, comment) else {
+            log::info!("[POWER] 完成后留在当前环境，不重启");
+            return Ok(());
+        };
+        log::info!("[POWER] {}: shutdown {}", self.label(), args.join(" "));
+        new_command("shutdown")
+            .args(&args)
+            .spawn()
+            .with_context(|| format!("执行“{}”失败", self.label()))?;
+        Ok(())
+    }
+}
+
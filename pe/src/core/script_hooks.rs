@@ -0,0 +1,263 @@
+//! 首次启动脚本钩子
+//!
+//! 把用户放在 scripts 文件夹中的 .cmd/.bat/.ps1 脚本复制到目标系统的
+//! `Windows\Setup\Scripts\LetRecovery_Hooks`，按配置的顺序接入 SetupComplete.cmd
+//! （部署完成后以 SYSTEM 身份执行），或通过 RunOnce 在首个用户登录时执行。
+//! 脚本位于数据目录的 scripts 文件夹，由主程序在准备 PE 时从程序目录复制过去。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::core::registry::OfflineRegistry;
+
+/// 用户脚本所在的文件夹名（程序目录或数据目录下）
+pub const SCRIPTS_DIR: &str = "scripts";
+/// 目标系统中存放钩子脚本的文件夹（位于 Setup\Scripts 下）
+pub const HOOKS_DIR: &str = "LetRecovery_Hooks";
+/// 按顺序调用 RunOnce 脚本的包装脚本
+const RUNONCE_SCRIPT: &str = "runonce.cmd";
+/// RunOnce 注册表值名
+const RUNONCE_VALUE: &str = "LetRecoveryHooks";
+const RUNONCE_KEY: &str = "Microsoft\\Windows\\CurrentVersion\\RunOnce";
+/// 支持的脚本扩展名
+const SCRIPT_EXTENSIONS: [&str; 3] = ["cmd", "bat", "ps1"];
+/// 自动编号时相邻脚本的顺序间隔，便于之后插入
+const ORDER_STEP: u32 = 10;
+
+/// 脚本执行时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HookStage {
+    /// 部署完成后、进入桌面前以 SYSTEM 身份执行（SetupComplete.cmd）
+    #[default]
+    SetupComplete,
+    /// 首个用户登录时执行一次（RunOnce）
+    RunOnce,
+}
+
+/// 单个脚本钩子
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ScriptHook {
+    /// 脚本文件名（相对于 scripts 文件夹）
+    pub file: String,
+    /// 执行时机
+    pub stage: HookStage,
+    /// 执行顺序，小的先执行
+    pub order: u32,
+    /// 是否启用
+    pub enabled: bool,
+}
+
+impl Default for ScriptHook {
+    fn default() -> Self {
+        Self {
+            file: String::new(),
+            stage: HookStage::default(),
+            order: 0,
+            enabled: true,
+        }
+    }
+}
+
+/// 是否为支持的脚本文件
+pub fn is_script_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SCRIPT_EXTENSIONS.iter().any(|s| e.eq_ignore_ascii_case(s)))
+        .unwrap_or(false)
+}
+
+/// 校验钩子脚本文件名：只能是 scripts 文件夹中的文件名，且为支持的脚本类型
+pub fn validate_file_name(file: &str) -> std::result::Result<(), String> {
+    if file.trim().is_empty() {
+        return Err("脚本文件名不能为空".to_string());
+    }
+    if file.contains(['\\', '/', ':']) || file == "." || file == ".." {
+        return Err(format!("脚本应为 scripts 文件夹中的文件名，实际为 {}", file));
+    }
+    if !is_script_file(Path::new(file)) {
+        return Err(format!("不支持的脚本类型: {}（仅支持 .cmd/.bat/.ps1）", file));
+    }
+    Ok(())
+}
+
+/// 列出目录中的脚本并与已有配置合并
+pub fn discover(dir: &Path, existing: &[ScriptHook]) -> Vec<ScriptHook> {
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && is_script_file(p))
+                .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|f| f.to_lowercase());
+    merge_hooks(&files, existing)
+}
+
+/// 合并目录中的脚本和已有配置
+///
+/// 已配置的脚本沿用原有设置，已不存在的脚本被移除，新脚本按文件名顺序排在最后。
+pub fn merge_hooks(files: &[String], existing: &[ScriptHook]) -> Vec<ScriptHook> {
+    let mut hooks: Vec<ScriptHook> = existing
+        .iter()
+        .filter(|h| files.iter().any(|f| f.eq_ignore_ascii_case(&h.file)))
+        .cloned()
+        .collect();
+
+    let mut next_order = hooks.iter().map(|h| h.order).max().map_or(0, |o| o + ORDER_STEP);
+    for file in files {
+        if !hooks.iter().any(|h| h.file.eq_ignore_ascii_case(file)) {
+            hooks.push(ScriptHook {
+                file: file.clone(),
+                order: next_order,
+                ..Default::default()
+            });
+            next_order += ORDER_STEP;
+        }
+    }
+
+    sort_hooks(&mut hooks);
+    hooks
+}
+
+/// 按执行顺序排序（顺序相同时按文件名）
+pub fn sort_hooks(hooks: &mut [ScriptHook]) {
+    hooks.sort_by(|a, b| {
+        a.order
+            .cmp(&b.order)
+            .then_with(|| a.file.to_lowercase().cmp(&b.file.to_lowercase()))
+    });
+}
+
+/// 调用脚本的命令行
+fn call_line(file: &str, path: &str) -> String {
+    let is_ps1 = Path::new(file)
+        .extension()
+        .map(|e| e.eq_ignore_ascii_case("ps1"))
+        .unwrap_or(false);
+    if is_ps1 {
+        format!("powershell -NoProfile -ExecutionPolicy Bypass -File \"{}\"", path)
+    } else {
+        format!("call \"{}\"", path)
+    }
+}
+
+/// 在 SetupComplete.cmd 内容末尾追加尚未存在的调用，无需修改时返回 None
+fn append_calls(content: &str, calls: &[String]) -> Option<String> {
+    let mut content = content.to_string();
+    let mut changed = false;
+    for call in calls {
+        if content.contains(call.as_str()) {
+            continue;
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push_str("\r\n");
+        }
+        content.push_str(call);
+        content.push_str("\r\n");
+        changed = true;
+    }
+    changed.then_some(content)
+}
+
+/// 在目标系统的 SetupComplete.cmd 末尾追加命令（已存在的不重复追加）
+///
+/// 保留镜像自带的 SetupComplete.cmd 内容，文件不存在时新建。
+pub fn append_setup_complete(target_partition: &str, calls: &[String]) -> Result<()> {
+    if calls.is_empty() {
+        return Ok(());
+    }
+    let scripts_dir = format!("{}\\Windows\\Setup\\Scripts", target_partition);
+    std::fs::create_dir_all(&scripts_dir).context("创建 Setup\\Scripts 目录失败")?;
+    let setup_complete = format!("{}\\SetupComplete.cmd", scripts_dir);
+    let content = std::fs::read_to_string(&setup_complete).unwrap_or_default();
+    if let Some(content) = append_calls(&content, calls) {
+        std::fs::write(&setup_complete, content).context("写入 SetupComplete.cmd 失败")?;
+    }
+    Ok(())
+}
+
+/// 生成按顺序调用 RunOnce 脚本的包装脚本
+fn runonce_script(hooks: &[&ScriptHook]) -> String {
+    let mut script = String::from("@echo off\r\n");
+    for hook in hooks {
+        script.push_str(&call_line(&hook.file, &format!("%~dp0{}", hook.file)));
+        script.push_str("\r\n");
+    }
+    script
+}
+
+/// 把启用的脚本安装到目标系统，返回已安装的脚本数
+///
+/// `source_dir` 为用户脚本所在的 scripts 文件夹；找不到的脚本会被跳过。
+pub fn install_hooks(target_partition: &str, source_dir: &Path, hooks: &[ScriptHook]) -> Result<usize> {
+    let mut enabled: Vec<ScriptHook> = hooks.iter().filter(|h| h.enabled).cloned().collect();
+    if enabled.is_empty() {
+        return Ok(0);
+    }
+    sort_hooks(&mut enabled);
+
+    let scripts_dir = format!("{}\\Windows\\Setup\\Scripts", target_partition);
+    let hooks_dir = format!("{}\\{}", scripts_dir, HOOKS_DIR);
+    std::fs::create_dir_all(&hooks_dir).context("创建脚本钩子目录失败")?;
+
+    let mut installed = Vec::new();
+    for hook in &enabled {
+        if let Err(reason) = validate_file_name(&hook.file) {
+            log::warn!("[HOOKS] 跳过无效脚本: {}", reason);
+            continue;
+        }
+        let source = source_dir.join(&hook.file);
+        if !source.is_file() {
+            log::warn!("[HOOKS] 脚本不存在，跳过: {}", source.display());
+            continue;
+        }
+        std::fs::copy(&source, format!("{}\\{}", hooks_dir, hook.file))
+            .with_context(|| format!("复制脚本 {} 失败", hook.file))?;
+        installed.push(hook);
+    }
+
+    // SetupComplete 阶段：保留镜像自带的 SetupComplete.cmd，仅追加调用
+    let calls: Vec<String> = installed
+        .iter()
+        .filter(|h| h.stage == HookStage::SetupComplete)
+        .map(|h| call_line(&h.file, &format!("%~dp0{}\\{}", HOOKS_DIR, h.file)))
+        .collect();
+    append_setup_complete(target_partition, &calls)?;
+
+    // RunOnce 阶段：一个包装脚本按顺序调用，只注册一个 RunOnce 值
+    let runonce: Vec<&ScriptHook> = installed
+        .iter()
+        .copied()
+        .filter(|h| h.stage == HookStage::RunOnce)
+        .collect();
+    if !runonce.is_empty() {
+        std::fs::write(format!("{}\\{}", hooks_dir, RUNONCE_SCRIPT), runonce_script(&runonce))
+            .context("写入 RunOnce 包装脚本失败")?;
+        register_runonce(target_partition)?;
+    }
+
+    log::info!("[HOOKS] 已安装 {} 个首次启动脚本", installed.len());
+    Ok(installed.len())
+}
+
+/// 在目标系统中注册 RunOnce 项
+fn register_runonce(target_partition: &str) -> Result<()> {
+    let software_hive = format!("{}\\Windows\\System32\\config\\SOFTWARE", target_partition);
+    OfflineRegistry::load_hive("pc-soft", &software_hive).context("加载目标系统 SOFTWARE 配置单元失败")?;
+
+    let result = OfflineRegistry::set_expand_string(
+        &format!("HKLM\\pc-soft\\{}", RUNONCE_KEY),
+        RUNONCE_VALUE,
+        &format!(
+            "cmd /c \"%SystemRoot%\\Setup\\Scripts\\{}\\{}\"",
+            HOOKS_DIR, RUNONCE_SCRIPT
+        ),
+    );
+    let _ = OfflineRegistry::unload_hive("pc-soft");
+    result.context("注册 RunOnce 项失败")
+}
@@ -0,0 +1,176 @@
+//! Intel VMD / RST RAID 控制器检测模块
+//!
+//! 硬盘挂在 VMD / RAID 控制器下时，目标系统缺少驱动会无法启动（INACCESSIBLE_BOOT_DEVICE），
+//! 因此检测到这类控制器时自动导入磁盘控制器驱动（优先导入匹配的驱动包），并在驱动缺失时记录警告。
+
+use std::path::{Path, PathBuf};
+
+use crate::utils::cmd::create_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// PCI 设备注册表路径
+const PCI_ENUM_KEY: &str = r"HKLM\SYSTEM\CurrentControlSet\Enum\PCI";
+
+/// Intel 厂商 ID
+const INTEL_VENDOR: &str = "VEN_8086";
+
+/// RAID 控制器的 PCI 类代码（大容量存储 / RAID）
+const RAID_CLASS_CODE: &str = "CC_0104";
+
+/// Intel VMD 控制器设备 ID
+const VMD_DEVICE_IDS: [&str; 9] = [
+    "9A0B", "4C3D", "467F", "A77F", "7D0B", "AD0B", "09AB", "201D", "28C0",
+];
+
+/// Intel RST RAID 模式 SATA 控制器设备 ID
+const RST_RAID_DEVICE_IDS: [&str; 3] = ["2822", "2826", "282A"];
+
+/// 检测到的存储控制器
+#[derive(Debug, Clone)]
+pub struct RaidController {
+    /// 是否为 VMD 控制器（否则为 RST RAID）
+    pub is_vmd: bool,
+    /// 设备 ID（如 "9A0B"）
+    pub device_id: String,
+    /// PE 是否已加载驱动
+    pub driver_loaded: bool,
+}
+
+impl RaidController {
+    /// 显示名称
+    pub fn display_name(&self) -> String {
+        let kind = if self.is_vmd { "Intel VMD" } else { "Intel RST RAID" };
+        format!("{} (DEV_{})", kind, self.device_id)
+    }
+}
+
+/// 根据 PCI 硬件 ID 判断是否为 Intel VMD / RST RAID 控制器，返回（是否 VMD, 设备 ID）
+fn classify_hardware_id(hardware_id: &str) -> Option<(bool, String)> {
+    let upper = hardware_id.to_uppercase();
+    if !upper.contains(INTEL_VENDOR) {
+        return None;
+    }
+    let index = upper.find("DEV_")? + "DEV_".len();
+    let device_id = upper.get(index..index + 4)?.to_string();
+    if VMD_DEVICE_IDS.contains(&device_id.as_str()) {
+        Some((true, device_id))
+    } else if upper.contains(RAID_CLASS_CODE) || RST_RAID_DEVICE_IDS.contains(&device_id.as_str()) {
+        Some((false, device_id))
+    } else {
+        None
+    }
+}
+
+/// 执行 reg query，返回输出文本
+fn reg_query(args: &[&str]) -> Option<String> {
+    let output = create_command("reg.exe").arg("query").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(gbk_to_utf8(&output.stdout))
+}
+
+/// 枚举 PCI 设备，检测 Intel VMD / RST RAID 控制器
+pub fn detect_controllers() -> Vec<RaidController> {
+    let mut controllers: Vec<RaidController> = Vec::new();
+    let Some(output) = reg_query(&[PCI_ENUM_KEY, "/s", "/v", "HardwareID"]) else {
+        return controllers;
+    };
+
+    // 输出格式：实例键路径一行，随后缩进的 HardwareID 值一行
+    let mut current_key = String::new();
+    for line in output.lines() {
+        if line.starts_with("HKEY_") {
+            current_key = line.trim().to_string();
+            continue;
+        }
+        if !line.trim_start().starts_with("HardwareID") {
+            continue;
+        }
+        let Some((is_vmd, device_id)) = classify_hardware_id(line) else {
+            continue;
+        };
+        if controllers.iter().any(|c| c.device_id == device_id) {
+            continue;
+        }
+        let driver_loaded = reg_query(&[&current_key, "/v", "Service"]).is_some();
+        controllers.push(RaidController {
+            is_vmd,
+            device_id,
+            driver_loaded,
+        });
+    }
+
+    for controller in &controllers {
+        if controller.driver_loaded {
+            log::info!("[STORAGE] 检测到 {}", controller.display_name());
+        } else {
+            log::warn!(
+                "[STORAGE] 检测到 {}，但当前PE未加载其驱动，该控制器下的硬盘不可见",
+                controller.display_name()
+            );
+        }
+    }
+    controllers
+}
+
+/// 读取 INF 文件文本（兼容 UTF-16 LE 编码）
+fn read_inf_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    } else {
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// 检查驱动目录（递归）中是否有支持该控制器的驱动
+pub fn driver_dir_supports(dir: &Path, controller: &RaidController) -> bool {
+    let pattern = format!("{}&DEV_{}", INTEL_VENDOR, controller.device_id);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return false;
+    };
+    entries.filter_map(|e| e.ok()).any(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            driver_dir_supports(&path, controller)
+        } else {
+            path.extension()
+                .map(|ext| ext.eq_ignore_ascii_case("inf"))
+                .unwrap_or(false)
+                && read_inf_text(&path)
+                    .map(|text| text.to_uppercase().contains(&pattern))
+                    .unwrap_or(false)
+        }
+    })
+}
+
+/// 安装时要导入的驱动目录
+///
+/// 未选择驱动包时导入整个根目录；否则先导入包含已检测控制器驱动的驱动包，再导入选中的驱动包
+pub fn pack_dirs_for_install(root: &Path, selected: &[String], controllers: &[RaidController]) -> Vec<PathBuf> {
+    if selected.is_empty() {
+        return vec![root.to_path_buf()];
+    }
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(root) {
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.is_dir() && controllers.iter().any(|c| driver_dir_supports(&path, c)) {
+                dirs.push(path);
+            }
+        }
+    }
+    for id in selected {
+        let path = root.join(id);
+        let valid_id = id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if valid_id && path.is_dir() && !dirs.contains(&path) {
+            dirs.push(path);
+        }
+    }
+    dirs
+}
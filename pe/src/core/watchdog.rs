@@ -0,0 +1,277 @@
+//! 看门狗模块
+//!
+//! 安装/备份工作线程崩溃（panic）时，写入失败标记、收集日志到数据分区，
+//! 并删除PE引导项后重启回原系统，避免机器停留在无响应的PE界面。
+//!
+//! 长时间无进度只保存故障报告并提示，慢速磁盘上的正常安装也可能很久没有进度，
+//! 是否重启回原系统由用户在界面上确认，命令行模式下不自动重启。
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::core::bcdedit::BootManager;
+use crate::core::config::ConfigFileManager;
+use crate::utils::json_progress::{self, ProgressEvent};
+use crate::utils::logger;
+use crate::utils::reboot_pe;
+
+/// 无进度超时时间（超过即视为卡死）
+pub const HANG_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 故障报告目录名（位于数据分区根目录，不随数据目录清理）
+const CRASH_REPORT_DIR: &str = "LetRecovery_CrashReport";
+
+/// 失败标记文件名
+const FAILURE_MARKER: &str = "failure.json";
+
+/// 需要收集的系统日志（源路径, 保存文件名），本程序的日志另行收集
+const COLLECTED_LOGS: [(&str, &str); 2] = [
+    (r"X:\Windows\Logs\DISM\dism.log", "dism.log"),
+    (r"X:\Windows\Logs\CBS\CBS.log", "CBS.log"),
+];
+
+/// 当前的看门狗（命令行模式的进度输出据此报告进度）
+static CURRENT: Mutex<Option<Watchdog>> = Mutex::new(None);
+
+/// 向当前的看门狗报告进度（未启动看门狗时忽略）
+pub fn heartbeat() {
+    if let Some(watchdog) = current() {
+        watchdog.heartbeat();
+    }
+}
+
+/// 向当前的看门狗报告进入新步骤（未启动看门狗时忽略）
+pub fn set_stage(stage: &str) {
+    if let Some(watchdog) = current() {
+        watchdog.set_stage(stage);
+    }
+}
+
+fn current() -> Option<Watchdog> {
+    CURRENT.lock().ok().and_then(|current| current.clone())
+}
+
+/// 失败标记内容
+#[derive(Debug, Serialize)]
+struct FailureMarker {
+    /// 故障原因
+    reason: String,
+    /// 故障时所处的步骤
+    stage: String,
+    /// Unix 时间戳（秒）
+    timestamp: u64,
+}
+
+/// 看门狗内部状态
+struct WatchdogState {
+    /// 最近一次收到进度的时间
+    last_heartbeat: Mutex<Instant>,
+    /// 当前步骤
+    stage: Mutex<String>,
+    /// 执行流程的工作线程，只有该线程崩溃时才恢复
+    worker: Mutex<Option<ThreadId>>,
+    /// 流程已结束（成功或已上报失败），不再监控
+    finished: AtomicBool,
+    /// 已超时且尚未恢复进度，等待用户决定
+    hung: AtomicBool,
+    /// 已触发恢复，避免重复执行
+    recovering: AtomicBool,
+}
+
+/// 看门狗
+#[derive(Clone)]
+pub struct Watchdog {
+    state: Arc<WatchdogState>,
+}
+
+impl Watchdog {
+    /// 启动看门狗监控线程并接管 panic 处理
+    ///
+    /// 执行流程的线程需调用 `watch_current_thread`，其他线程（界面、进度转发等）崩溃时不恢复。
+    pub fn start(timeout: Duration) -> Self {
+        let watchdog = Self {
+            state: Arc::new(WatchdogState {
+                last_heartbeat: Mutex::new(Instant::now()),
+                stage: Mutex::new(String::new()),
+                worker: Mutex::new(None),
+                finished: AtomicBool::new(false),
+                hung: AtomicBool::new(false),
+                recovering: AtomicBool::new(false),
+            }),
+        };
+
+        // 工作线程 panic 时先输出默认信息，再执行恢复
+        let panic_watchdog = watchdog.clone();
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            default_hook(info);
+            if panic_watchdog.is_worker(thread::current().id()) {
+                panic_watchdog.recover(&format!("程序崩溃: {}", info));
+            }
+        }));
+
+        let monitor = watchdog.clone();
+        thread::spawn(move || loop {
+            thread::sleep(CHECK_INTERVAL);
+            if monitor.state.recovering.load(Ordering::SeqCst) {
+                break;
+            }
+            if monitor.state.finished.load(Ordering::SeqCst) || monitor.is_hung() {
+                continue;
+            }
+            let elapsed = monitor
+                .state
+                .last_heartbeat
+                .lock()
+                .map(|t| t.elapsed())
+                .unwrap_or_default();
+            if elapsed > timeout {
+                monitor.report_hang(&format!("超过 {} 分钟没有进度", timeout.as_secs() / 60));
+            }
+        });
+
+        if let Ok(mut current) = CURRENT.lock() {
+            *current = Some(watchdog.clone());
+        }
+        log::info!("看门狗已启动，超时时间: {} 分钟", timeout.as_secs() / 60);
+        watchdog
+    }
+
+    /// 把当前线程登记为执行流程的工作线程
+    pub fn watch_current_thread(&self) {
+        if let Ok(mut worker) = self.state.worker.lock() {
+            *worker = Some(thread::current().id());
+        }
+    }
+
+    fn is_worker(&self, id: ThreadId) -> bool {
+        self.state.worker.lock().map(|worker| *worker == Some(id)).unwrap_or(false)
+    }
+
+    /// 报告流程仍在运行（超时后恢复进度时撤销超时状态）
+    pub fn heartbeat(&self) {
+        if let Ok(mut t) = self.state.last_heartbeat.lock() {
+            *t = Instant::now();
+        }
+        self.state.hung.store(false, Ordering::SeqCst);
+    }
+
+    /// 是否已超时没有进度（界面据此询问用户是否重启回原系统）
+    pub fn is_hung(&self) -> bool {
+        self.state.hung.load(Ordering::SeqCst)
+    }
+
+    /// 记录当前步骤（写入失败标记）
+    pub fn set_stage(&self, stage: &str) {
+        if let Ok(mut s) = self.state.stage.lock() {
+            *s = stage.to_string();
+        }
+        self.heartbeat();
+    }
+
+    /// 流程已结束，暂停监控
+    pub fn finish(&self) {
+        self.state.finished.store(true, Ordering::SeqCst);
+    }
+
+    /// 重新执行流程时恢复监控
+    pub fn restart(&self) {
+        self.heartbeat();
+        self.state.finished.store(false, Ordering::SeqCst);
+    }
+
+    /// 超时没有进度：保存故障报告并等待用户决定，不自动重启
+    fn report_hang(&self, reason: &str) {
+        self.state.hung.store(true, Ordering::SeqCst);
+        let stage = self.save_report(reason);
+        json_progress::emit(ProgressEvent::Warning {
+            message: format!("{}（步骤: {}），故障报告已保存到数据分区", reason, stage),
+        });
+    }
+
+    /// 写入失败标记并收集日志，返回当前步骤
+    fn save_report(&self, reason: &str) -> String {
+        let stage = self.state.stage.lock().map(|s| s.clone()).unwrap_or_default();
+        log::error!("[WATCHDOG] 检测到故障: {} (步骤: {})", reason, stage);
+
+        match write_crash_report(reason, &stage) {
+            Ok(dir) => log::info!("[WATCHDOG] 故障报告已保存到: {}", dir),
+            Err(e) => log::error!("[WATCHDOG] 保存故障报告失败: {}", e),
+        }
+        stage
+    }
+
+    /// 工作线程崩溃：写入失败标记、收集日志并重启回原系统
+    fn recover(&self, reason: &str) {
+        if self.state.recovering.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        self.finish();
+        self.save_report(reason);
+        self.reboot_to_original();
+    }
+
+    /// 删除PE引导项后重启回原系统（超时后由用户确认调用）
+    pub fn reboot_to_original(&self) {
+        self.state.recovering.store(true, Ordering::SeqCst);
+        self.finish();
+
+        // 删除PE引导项，重启后回到原系统
+        if let Err(e) = BootManager::new().delete_current_boot_entry() {
+            log::warn!("[WATCHDOG] 删除PE引导项失败: {}", e);
+        }
+
+        log::info!("[WATCHDOG] 即将重启...");
+        thread::sleep(Duration::from_secs(3));
+        reboot_pe();
+    }
+}
+
+/// 写入失败标记并复制日志到数据分区，返回报告目录
+fn write_crash_report(reason: &str, stage: &str) -> anyhow::Result<String> {
+    let partition = ConfigFileManager::find_data_partition()
+        .or_else(ConfigFileManager::find_install_marker_partition)
+        .or_else(ConfigFileManager::find_backup_marker_partition)
+        .ok_or_else(|| anyhow::anyhow!("未找到可写入故障报告的分区"))?;
+
+    let report_dir = format!("{}\\{}", partition, CRASH_REPORT_DIR);
+    std::fs::create_dir_all(&report_dir)?;
+
+    let marker = FailureMarker {
+        reason: reason.to_string(),
+        stage: stage.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+    std::fs::write(
+        format!("{}\\{}", report_dir, FAILURE_MARKER),
+        serde_json::to_string_pretty(&marker)?,
+    )?;
+
+    if let Some(path) = logger::current_log_file() {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if let Err(e) = std::fs::copy(&path, format!("{}\\{}", report_dir, name)) {
+            log::warn!("[WATCHDOG] 复制日志失败 {}: {}", path.display(), e);
+        }
+    }
+
+    for (source, name) in COLLECTED_LOGS {
+        if Path::new(source).exists() {
+            if let Err(e) = std::fs::copy(source, format!("{}\\{}", report_dir, name)) {
+                log::warn!("[WATCHDOG] 复制日志失败 {}: {}", source, e);
+            }
+        }
+    }
+
+    Ok(report_dir)
+}
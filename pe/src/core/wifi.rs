@@ -0,0 +1,67 @@
+//! 导入桌面端携带的 Wi-Fi 配置
+//!
+//! 桌面端重启进入 PE 前把已连接网络的配置（含明文密码）导出到数据目录的 wifi 子目录，
+//! 这里启动 WLAN 服务后逐个导入并改为自动连接。PE 没有无线网卡驱动或 WLAN 组件时导入失败，不影响安装。
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// 数据目录下存放 Wi-Fi 配置的子目录
+pub const WIFI_PROFILE_DIR: &str = "wifi";
+
+/// 启动 WLAN 服务并导入目录中的 Wi-Fi 配置，返回导入成功的数量
+pub fn import_profiles(dir: &Path) -> Result<usize> {
+    let profiles: Vec<_> = std::fs::read_dir(dir)
+        .context("读取 Wi-Fi 配置目录失败")?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+        })
+        .collect();
+    if profiles.is_empty() {
+        return Ok(0);
+    }
+
+    // 服务已在运行时 net start 返回错误，忽略结果
+    match new_command("net").args(["start", "wlansvc"]).output() {
+        Ok(output) => log::info!("[WIFI] 启动 WLAN 服务: {}", gbk_to_utf8(&output.stdout).trim()),
+        Err(e) => log::warn!("[WIFI] 启动 WLAN 服务失败: {}", e),
+    }
+    // 等待服务枚举无线网卡
+    std::thread::sleep(std::time::Duration::from_secs(2));
+
+    let mut imported = 0;
+    for path in &profiles {
+        // 手动连接的配置导入后不会自动连接
+        if let Ok(xml) = std::fs::read_to_string(path) {
+            let _ = std::fs::write(
+                path,
+                xml.replace("<connectionMode>manual</connectionMode>", "<connectionMode>auto</connectionMode>"),
+            );
+        }
+        let output = new_command("netsh")
+            .args(["wlan", "add", "profile"])
+            .arg(format!("filename={}", path.display()))
+            .arg("user=all")
+            .output()
+            .context("执行 netsh 失败")?;
+        let message = gbk_to_utf8(&output.stdout);
+        if output.status.success() {
+            log::info!("[WIFI] 已导入 {}: {}", path.display(), message.trim());
+            imported += 1;
+        } else {
+            log::warn!("[WIFI] 导入 {} 失败: {}", path.display(), message.trim());
+        }
+    }
+
+    if imported == 0 {
+        bail!("导入 Wi-Fi 配置失败（PE 可能缺少无线网卡驱动或 WLAN 组件）");
+    }
+    Ok(imported)
+}
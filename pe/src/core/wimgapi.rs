@@ -17,6 +17,8 @@ use std::sync::Arc;
 
 use libloading::Library;
 
+use crate::core::wimlib::find_swm_parts;
+
 #[cfg(windows)]
 use windows::Win32::Foundation::GetLastError;
 
@@ -86,6 +88,9 @@ pub const WIM_COMPRESS_LZX: u32 = 2;
 #[allow(dead_code)]
 pub const WIM_COMPRESS_LZMS: u32 = 3;
 
+// 引用文件标志
+pub const WIM_REFERENCE_APPEND: u32 = 0x0001_0000;
+
 // 消息类型
 // WIM_MSG = WM_APP + 0x1476 = 0x8000 + 0x1476 = 0x9476
 // WIM_MSG_TEXT = WIM_MSG + 1 = 0x9477
@@ -135,6 +140,8 @@ type FnWimCloseHandle = unsafe extern "system" fn(hObject: Handle) -> i32;
 
 type FnWimSetTemporaryPath = unsafe extern "system" fn(hWim: Handle, pszPath: Pcwstr) -> i32;
 
+type FnWimSetReferenceFile = unsafe extern "system" fn(hWim: Handle, pszPath: Pcwstr, dwFlags: u32) -> i32;
+
 type FnWimLoadImage = unsafe extern "system" fn(hWim: Handle, dwImageIndex: u32) -> Handle;
 
 type FnWimGetImageCount = unsafe extern "system" fn(hWim: Handle) -> u32;
@@ -304,6 +311,11 @@ pub struct WimProgress {
 
 static GLOBAL_PROGRESS: AtomicU8 = AtomicU8::new(0);
 
+/// 判断 WIM_MSG_PROCESS 报告的文件（完整路径）是否被排除
+fn is_capture_excluded(path: *const u16) -> bool {
+    crate::core::capture_options::is_excluded_from_active_capture(&wide_cstr_to_string(path))
+}
+
 /// 进度回调函数
 /// 
 /// 根据 Microsoft 文档，WIM_MSG_PROGRESS 消息中：
@@ -314,7 +326,7 @@ static GLOBAL_PROGRESS: AtomicU8 = AtomicU8::new(0);
 extern "system" fn progress_callback(
     msg_id: u32,
     wparam: usize,
-    _lparam: isize,
+    lparam: isize,
     _user_data: *mut c_void,
 ) -> u32 {
     match msg_id {
@@ -339,6 +351,12 @@ extern "system" fn progress_callback(
             log::error!("[WIMGAPI] WIM操作发生错误 (msg_id={:#x})", msg_id);
             return WIM_MSG_ABORT_IMAGE;
         }
+        WIM_MSG_PROCESS => {
+            // wParam 为文件完整路径，lParam 指向 BOOL，置为 FALSE 时跳过该文件
+            if lparam != 0 && is_capture_excluded(wparam as *const u16) {
+                unsafe { *(lparam as *mut i32) = 0 };
+            }
+        }
         _ => {
             // 记录未知消息类型，便于调试
             if msg_id >= 0x9476 && msg_id <= 0x94A0 {
@@ -359,6 +377,7 @@ pub struct Wimgapi {
     wim_create_file: FnWimCreateFile,
     wim_close_handle: FnWimCloseHandle,
     wim_set_temporary_path: FnWimSetTemporaryPath,
+    wim_set_reference_file: FnWimSetReferenceFile,
     wim_load_image: FnWimLoadImage,
     wim_get_image_count: FnWimGetImageCount,
     wim_apply_image: FnWimApplyImage,
@@ -396,6 +415,20 @@ fn utf16_ptr_to_string(ptr: *const u16, max_len: usize) -> String {
     }
 }
 
+/// 将以 NUL 结尾的 UTF-16 指针转换为 Rust 字符串
+fn wide_cstr_to_string(ptr: *const u16) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe {
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len))
+    }
+}
+
 /// 获取最后的 Win32 错误码
 #[cfg(windows)]
 fn get_last_error() -> u32 {
@@ -471,6 +504,7 @@ impl Wimgapi {
                 wim_create_file: *lib.get(b"WIMCreateFile")?,
                 wim_close_handle: *lib.get(b"WIMCloseHandle")?,
                 wim_set_temporary_path: *lib.get(b"WIMSetTemporaryPath")?,
+                wim_set_reference_file: *lib.get(b"WIMSetReferenceFile")?,
                 wim_load_image: *lib.get(b"WIMLoadImage")?,
                 wim_get_image_count: *lib.get(b"WIMGetImageCount")?,
                 wim_apply_image: *lib.get(b"WIMApplyImage")?,
@@ -548,6 +582,16 @@ impl Wimgapi {
         Ok(())
     }
 
+    /// 设置引用文件（用于 SWM 分卷镜像）
+    pub fn set_reference_file(&self, handle: Handle, ref_path: &Path, flags: u32) -> Result<(), WimApiError> {
+        let wide_path = path_to_wide(ref_path);
+        let result = unsafe { (self.wim_set_reference_file)(handle, wide_path.as_ptr(), flags) };
+        if result == 0 {
+            return Err(WimApiError::Win32Error(get_last_error()));
+        }
+        Ok(())
+    }
+
     /// 加载镜像
     pub fn load_image(&self, handle: Handle, index: u32) -> Result<Handle, WimApiError> {
         log::info!("[WIMGAPI] load_image: 即将调用 WIMLoadImage(index={})...", index);
@@ -1096,6 +1140,18 @@ impl WimManager {
         })
     }
 
+    /// 镜像为 SWM 分卷时引用其余分卷
+    fn reference_swm_parts(&self, wim_handle: Handle, image_file: &str) -> Result<(), WimApiError> {
+        if !image_file.to_lowercase().ends_with(".swm") {
+            return Ok(());
+        }
+        for part in find_swm_parts(image_file).iter().skip(1) {
+            log::info!("[WIMGAPI] 引用分卷: {}", part.display());
+            self.wimgapi.set_reference_file(wim_handle, part, WIM_REFERENCE_APPEND)?;
+        }
+        Ok(())
+    }
+
     /// 释放/应用 WIM/ESD 镜像到目标目录
     pub fn apply_image(
         &self,
@@ -1137,6 +1193,12 @@ impl WimManager {
         self.wimgapi.set_temp_path(wim_handle, &temp_dir)?;
         log::info!("[WIMGAPI] 临时路径设置成功");
 
+        // SWM 分卷镜像需要引用其余分卷（相当于 DISM 的 /SWMFile）
+        if let Err(e) = self.reference_swm_parts(wim_handle, image_file) {
+            self.wimgapi.close(wim_handle)?;
+            return Err(e);
+        }
+
         // 注册进度回调
         log::info!("[WIMGAPI] 注册进度回调...");
         self.wimgapi.register_callback(wim_handle);
@@ -1210,7 +1272,8 @@ impl WimManager {
         apply_result
     }
 
-    /// 捕获/备份目录到 WIM 文件
+    /// 捕获/备份目录到 WIM 文件，`exclusions` 为相对于源目录的排除规则
+    #[allow(clippy::too_many_arguments)]
     pub fn capture_image(
         &self,
         source_dir: &str,
@@ -1218,6 +1281,7 @@ impl WimManager {
         name: &str,
         description: &str,
         compression: u32,
+        exclusions: &[String],
         progress_tx: Option<std::sync::mpsc::Sender<WimProgress>>,
     ) -> Result<(), WimApiError> {
         let source_path = Path::new(source_dir);
@@ -1283,7 +1347,9 @@ impl WimManager {
 
         // 捕获镜像
         log::info!("[WIMGAPI] 开始捕获...");
+        crate::core::capture_options::set_active_capture(Some((source_dir, exclusions)));
         let capture_result = self.wimgapi.capture_image(wim_handle, source_path, 0);
+        crate::core::capture_options::set_active_capture(None);
 
         let image_handle = match capture_result {
             Ok(h) => {
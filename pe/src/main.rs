@@ -7,26 +7,48 @@ mod utils;
 
 use eframe::egui;
 
+use core::power::CompletionAction;
+use ui::countdown::{run_countdown_dialog, CountdownAction, DIALOG_COUNTDOWN, REBOOT_COUNTDOWN};
+use utils::exit_code::ExitCode;
+use utils::json_progress::{self, ProgressEvent};
+
 fn main() -> eframe::Result<()> {
     // 初始化日志
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp(Some(env_logger::TimestampPrecision::Millis))
-        .init();
+    utils::logger::init();
 
     log::info!("LetRecovery PE 启动中...");
 
     // 检查命令行参数
     let args: Vec<String> = std::env::args().collect();
 
+    // 机器可读进度输出（供部署脚本集成）
+    if let Err(e) = json_progress::init_from_args(&args) {
+        cli_eprintln!("{}", e);
+        ExitCode::InvalidArguments.exit();
+    }
+
+    // 镜像进度同时作为看门狗的心跳
+    json_progress::on_progress(core::watchdog::heartbeat);
+
+    // 平板/二合一设备使用触屏布局
+    ui::touch::detect();
+
+    // 命令行子命令（install / backup / verify / list-partitions / list-images）
+    if let Some(invocation) = utils::cli::parse(&args[1..]) {
+        utils::cli::attach_console();
+        log::info!("命令行子命令: {:?}", invocation);
+        run_cli(invocation).exit();
+    }
+
     // 命令行模式（无GUI）
     if args.contains(&"/PEINSTALL".to_string()) || args.contains(&"--pe-install".to_string()) {
         log::info!("检测到PE安装模式（命令行），执行自动安装...");
-        return run_cli_mode(true);
+        run_cli_mode(true).exit();
     }
 
     if args.contains(&"/PEBACKUP".to_string()) || args.contains(&"--pe-backup".to_string()) {
         log::info!("检测到PE备份模式（命令行），执行自动备份...");
-        return run_cli_mode(false);
+        run_cli_mode(false).exit();
     }
 
     // 自动检测模式
@@ -45,7 +67,7 @@ fn main() -> eframe::Result<()> {
             None => {
                 log::warn!("未检测到配置文件，启动默认界面...");
                 show_error_message("未检测到安装或备份配置文件。\n\n请确保已正确准备配置文件后重试。");
-                return Ok(());
+                ExitCode::ConfigMissing.exit();
             }
         }
     }
@@ -55,12 +77,15 @@ fn main() -> eframe::Result<()> {
     // 加载图标
     let icon = load_icon();
 
+    // 高分辨率屏幕上按缩放比例放大窗口
+    let window_size = utils::dpi::scaled_size([600.0, 500.0]);
+
     // 设置窗口选项 - 窗口不可关闭，不可调整大小
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([600.0, 500.0])
-            .with_min_inner_size([600.0, 500.0])
-            .with_max_inner_size([600.0, 500.0])
+            .with_inner_size(window_size)
+            .with_min_inner_size(window_size)
+            .with_max_inner_size(window_size)
             .with_resizable(false)
             .with_maximize_button(false)
             .with_minimize_button(false)
@@ -98,64 +123,43 @@ fn load_icon() -> egui::IconData {
 }
 
 /// 命令行模式执行
-fn run_cli_mode(is_install: bool) -> eframe::Result<()> {
+fn run_cli_mode(is_install: bool) -> ExitCode {
     use core::bcdedit::BootManager;
     use core::config::ConfigFileManager;
-    use core::dism::Dism;
-    use core::disk::DiskManager;
-    use core::ghost::Ghost;
-    use ui::advanced_options::apply_advanced_options;
+    use core::watchdog::{Watchdog, HANG_TIMEOUT};
 
-    /// 递归查找目录中的所有 CAB 文件
-    fn find_cab_files_in_dir(dir: &str) -> Vec<std::path::PathBuf> {
-        fn find_recursive(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
-            if let Ok(entries) = std::fs::read_dir(dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.is_file() {
-                        if let Some(ext) = path.extension() {
-                            if ext.to_string_lossy().to_lowercase() == "cab" {
-                                files.push(path);
-                            }
-                        }
-                    } else if path.is_dir() {
-                        find_recursive(&path, files);
-                    }
-                }
-            }
-        }
-        let mut files = Vec::new();
-        find_recursive(std::path::Path::new(dir), &mut files);
-        files
-    }
+    // 本线程执行流程，崩溃时由看门狗恢复；超时只保存故障报告，不自动重启
+    let watchdog = Watchdog::start(HANG_TIMEOUT);
+    watchdog.watch_current_thread();
 
     if is_install {
-        println!("[PE INSTALL] ========== PE自动安装模式 ==========");
+        cli_println!("[PE INSTALL] ========== PE自动安装模式 ==========");
+        json_progress::emit(ProgressEvent::Start { operation: "install" });
 
         // 查找配置文件所在分区
         let data_partition = match ConfigFileManager::find_data_partition() {
             Some(p) => p,
             None => {
-                eprintln!("[PE INSTALL] 错误: 未找到安装配置文件");
+                cli_eprintln!("[PE INSTALL] 错误: 未找到安装配置文件");
                 show_error_message("未找到安装配置文件，无法继续安装。");
-                return Ok(());
+                return ExitCode::ConfigMissing;
             }
         };
 
-        println!("[PE INSTALL] 数据分区: {}", data_partition);
+        cli_println!("[PE INSTALL] 数据分区: {}", data_partition);
 
         // 读取安装配置
-        let config = match ConfigFileManager::read_install_config(&data_partition) {
+        let mut config = match ConfigFileManager::read_install_config(&data_partition) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("[PE INSTALL] 错误: 读取配置失败: {}", e);
-                show_error_message(&format!("读取安装配置失败: {}", e));
-                return Ok(());
+                cli_eprintln!("[PE INSTALL] 错误: 读取配置失败: {:#}", e);
+                show_error_message(&format!("读取安装配置失败: {:#}", e));
+                return ExitCode::ConfigMissing;
             }
         };
 
-        println!("[PE INSTALL] 目标分区: {}", config.target_partition);
-        println!("[PE INSTALL] 镜像文件: {}", config.image_path);
+        cli_println!("[PE INSTALL] 目标分区: {}", config.target_partition);
+        cli_println!("[PE INSTALL] 镜像文件: {}", config.image_path);
 
         // 查找安装标记分区
         let target_partition = ConfigFileManager::find_install_marker_partition()
@@ -163,223 +167,112 @@ fn run_cli_mode(is_install: bool) -> eframe::Result<()> {
 
         // 构建完整镜像路径
         let data_dir = ConfigFileManager::get_data_dir(&data_partition);
-        let image_path = format!("{}\\{}", data_dir, config.image_path);
-
-        if !std::path::Path::new(&image_path).exists() {
-            eprintln!("[PE INSTALL] 错误: 镜像文件不存在: {}", image_path);
-            show_error_message(&format!("镜像文件不存在: {}", image_path));
-            return Ok(());
-        }
-
-        println!("[PE INSTALL] 完整镜像路径: {}", image_path);
-
-        // Step 1: 格式化分区
-        println!("[PE INSTALL] Step 1: 格式化分区");
-        if let Err(e) = DiskManager::format_partition(&target_partition) {
-            eprintln!("[PE INSTALL] 格式化失败: {}", e);
-            show_error_message(&format!("格式化分区失败: {}", e));
-            return Ok(());
-        }
-
-        // Step 2: 释放镜像
-        println!("[PE INSTALL] Step 2: 释放镜像");
-        let apply_dir = format!("{}\\", target_partition);
-
-        let apply_result = if config.is_gho {
-            let ghost = Ghost::new();
-            if !ghost.is_available() {
-                show_error_message("Ghost工具不可用");
-                return Ok(());
-            }
-            let partitions = DiskManager::get_partitions().unwrap_or_default();
-            ghost.restore_image_to_letter(&image_path, &target_partition, &partitions, None)
-        } else {
-            let dism = Dism::new();
-            dism.apply_image(&image_path, &apply_dir, config.volume_index, None)
-        };
-
-        if let Err(e) = apply_result {
-            eprintln!("[PE INSTALL] 释放镜像失败: {}", e);
-            show_error_message(&format!("释放镜像失败: {}", e));
-            return Ok(());
-        }
-
-        // Step 3: 导入驱动
-        println!("[PE INSTALL] Step 3: 导入驱动");
-        let driver_path = format!("{}\\drivers", data_dir);
-        let driver_path_exists = std::path::Path::new(&driver_path).exists();
-        
-        if config.should_import_drivers() && driver_path_exists {
-            let dism = Dism::new();
-            match dism.add_drivers_offline_with_progress(&apply_dir, &driver_path, None) {
-                Ok(_) => println!("[PE INSTALL] 驱动导入成功"),
-                Err(e) => {
-                    eprintln!("[PE INSTALL] 警告: 驱动导入失败: {} (继续安装)", e);
-                    log::warn!("驱动导入失败: {}", e);
-                }
-            }
-            
-            // 同时检查驱动目录中是否有 CAB 文件并安装
-            let cab_files = find_cab_files_in_dir(&driver_path);
-            if !cab_files.is_empty() {
-                println!("[PE INSTALL] 在驱动目录中发现 {} 个 CAB 文件，一并安装", cab_files.len());
-                match dism.add_packages_offline_from_dir(&apply_dir, &driver_path, None) {
-                    Ok((success, fail)) => {
-                        println!("[PE INSTALL] 驱动目录中的CAB安装完成: {} 成功, {} 失败", success, fail);
-                    }
-                    Err(e) => {
-                        eprintln!("[PE INSTALL] 警告: 驱动目录中的CAB安装失败: {} (继续安装)", e);
-                        log::warn!("驱动目录中的CAB安装失败: {}", e);
-                    }
+        // 主程序暂存的 BitLocker 一次性密钥，安装流程结束时（包括失败）删除
+        let auto_unlock_keys = core::auto_unlock::pending_keys(&data_dir);
+        // 主程序单独保存的共享密码等凭据，读取后立即删除
+        let secrets = core::install_secrets::InstallSecrets::take(&data_dir);
+        config.unattend.password = secrets.unattend_password.clone();
+
+        // 导入桌面端携带的 Wi-Fi 配置，文件含明文密码，导入后立即删除
+        let wifi_dir = std::path::Path::new(&data_dir).join(core::wifi::WIFI_PROFILE_DIR);
+        if let Some(wifi_dir) = core::secure_dir::SensitiveDir::existing(wifi_dir) {
+            if config.wifi_profiles {
+                match core::wifi::import_profiles(wifi_dir.path()) {
+                    Ok(count) => cli_println!("[PE INSTALL] 已导入 {} 个 Wi-Fi 配置", count),
+                    Err(e) => cli_println!("[PE INSTALL] 导入 Wi-Fi 配置失败: {:#}", e),
                 }
             }
-        } else if config.should_import_drivers() && !driver_path_exists {
-            println!("[PE INSTALL] 驱动目录不存在，跳过驱动导入");
-        } else {
-            println!("[PE INSTALL] 跳过驱动导入");
         }
 
-        // Step 4: 安装CAB更新包
-        println!("[PE INSTALL] Step 4: 安装CAB更新包");
-        if config.install_cab_packages {
-            let cab_path = format!("{}\\updates", data_dir);
-            if std::path::Path::new(&cab_path).exists() {
-                let dism = Dism::new();
-                match dism.add_packages_offline_from_dir(&apply_dir, &cab_path, None) {
-                    Ok((success, fail)) => {
-                        println!("[PE INSTALL] CAB更新包安装完成: {} 成功, {} 失败", success, fail);
-                    }
+        // 网络镜像源：连接 SMB 共享或下载到数据目录
+        let image_path = match config.network_source() {
+            Some(source) => {
+                cli_println!("[PE INSTALL] 网络镜像源: {}", config.image_source);
+                let on_progress = |percentage: u8| {
+                    json_progress::emit(ProgressEvent::Progress { percentage, status: "下载网络镜像".to_string() });
+                };
+                match core::network_source::prepare(&source, &config.share_user, &secrets.share_password, &data_dir, on_progress) {
+                    Ok(path) => path,
                     Err(e) => {
-                        eprintln!("[PE INSTALL] 警告: CAB更新包安装失败: {} (继续安装)", e);
-                        log::warn!("CAB更新包安装失败: {}", e);
+                        cli_eprintln!("[PE INSTALL] 错误: 准备网络镜像失败: {:#}", e);
+                        show_error_message(&format!("准备网络镜像失败: {:#}", e));
+                        return ExitCode::ImageInvalid;
                     }
                 }
-            } else {
-                println!("[PE INSTALL] 更新包目录不存在，跳过CAB安装");
             }
-        } else {
-            println!("[PE INSTALL] 跳过CAB更新包安装");
-        }
-
-        // Step 5: 修复引导
-        println!("[PE INSTALL] Step 5: 修复引导");
-        let boot_manager = BootManager::new();
-        let use_uefi = DiskManager::detect_uefi_mode();
-
-        if let Err(e) = boot_manager.repair_boot_advanced(&target_partition, use_uefi) {
-            eprintln!("[PE INSTALL] 修复引导失败: {}", e);
-            show_error_message(&format!("修复引导失败: {}", e));
-            return Ok(());
-        }
-
-        // Step 5.5: 如果启用了 Win7 UEFI 补丁，应用 UefiSeven
-        if use_uefi && config.win7_uefi_patch {
-            println!("[PE INSTALL] Step 5.5: 应用 Win7 UEFI 补丁 (UefiSeven)");
-            match ui::advanced_options::apply_uefiseven_patch(&data_partition, &target_partition) {
-                Ok(_) => println!("[PE INSTALL] UefiSeven 补丁应用成功"),
-                Err(e) => {
-                    // UefiSeven 补丁失败不中断安装，只记录警告
-                    eprintln!("[PE INSTALL] 警告: UefiSeven 补丁应用失败: {} (继续安装)", e);
-                    log::warn!("UefiSeven 补丁应用失败: {}", e);
-                }
-            }
-        }
-
-        // Step 6: 应用高级选项
-        println!("[PE INSTALL] Step 6: 应用高级选项");
-        let _ = apply_advanced_options(&target_partition, &config);
+            None => format!("{}\\{}", data_dir, config.image_path),
+        };
 
-        // Step 7: 生成无人值守配置
-        if config.unattended {
-            println!("[PE INSTALL] Step 7: 生成无人值守配置");
-            let _ = generate_unattend_xml(&target_partition, &config.custom_username);
+        if !std::path::Path::new(&image_path).exists() {
+            cli_eprintln!("[PE INSTALL] 错误: 镜像文件不存在: {}", image_path);
+            show_error_message(&format!("镜像文件不存在: {}", image_path));
+            return ExitCode::ImageInvalid;
         }
 
-        // Step 8: 清理
-        println!("[PE INSTALL] Step 8: 清理临时文件");
-        ConfigFileManager::cleanup_all(&data_partition, &target_partition);
+        cli_println!("[PE INSTALL] 完整镜像路径: {}", image_path);
 
-        // Step 9: 清理自动创建的数据分区并扩展目标分区
-        println!("[PE INSTALL] Step 9: 清理自动创建的分区");
-        match DiskManager::cleanup_auto_created_partition_and_extend(&target_partition) {
-            Ok(_) => println!("[PE INSTALL] 自动创建分区清理完成"),
-            Err(e) => {
-                // 不中断安装流程，只记录警告
-                eprintln!("[PE INSTALL] 警告: 清理自动创建分区失败: {}", e);
-                log::warn!("清理自动创建分区失败: {}", e);
+        let result = execute_install(&target_partition, &image_path, &config, &data_dir, Some(&data_partition));
+        watchdog.finish();
+        drop(auto_unlock_keys);
+        if let Err(failure) = result {
+            cli_eprintln!("[PE INSTALL] {}", failure);
+            if !failure.rolled_back {
+                cli_eprintln!("[PE INSTALL] 警告: 回滚未全部完成");
             }
+            show_error_message(&failure.to_string());
+            return install_exit_code(&failure);
         }
 
-        println!("[PE INSTALL] 安装完成!");
+        cli_println!("[PE INSTALL] 安装完成!");
 
-        if config.auto_reboot {
-            println!("[PE INSTALL] 即将重启...");
-            let _ = utils::command::new_command("shutdown")
-                .args(["/r", "/t", "10", "/c", "LetRecovery 系统安装完成，即将重启..."])
-                .spawn();
-        } else {
+        let action = config.completion_action();
+        if action == CompletionAction::StayInPe {
             show_success_message("系统安装完成！请手动重启计算机。");
+        } else {
+            cli_println!("[PE INSTALL] 即将{}...", action.verb());
+            finish_after_countdown(action, &format!("LetRecovery 系统安装完成，即将{}...", action.verb()));
         }
     } else {
         // 备份模式
-        println!("[PE BACKUP] ========== PE自动备份模式 ==========");
+        cli_println!("[PE BACKUP] ========== PE自动备份模式 ==========");
+        json_progress::emit(ProgressEvent::Start { operation: "backup" });
 
         // 查找配置文件所在分区
         let data_partition = match ConfigFileManager::find_data_partition() {
             Some(p) => p,
             None => {
-                eprintln!("[PE BACKUP] 错误: 未找到备份配置文件");
+                cli_eprintln!("[PE BACKUP] 错误: 未找到备份配置文件");
                 show_error_message("未找到备份配置文件，无法继续备份。");
-                return Ok(());
+                return ExitCode::ConfigMissing;
             }
         };
 
-        println!("[PE BACKUP] 数据分区: {}", data_partition);
+        cli_println!("[PE BACKUP] 数据分区: {}", data_partition);
 
         // 读取备份配置
         let config = match ConfigFileManager::read_backup_config(&data_partition) {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("[PE BACKUP] 错误: 读取配置失败: {}", e);
-                show_error_message(&format!("读取备份配置失败: {}", e));
-                return Ok(());
+                cli_eprintln!("[PE BACKUP] 错误: 读取配置失败: {:#}", e);
+                show_error_message(&format!("读取备份配置失败: {:#}", e));
+                return ExitCode::ConfigMissing;
             }
         };
 
-        println!("[PE BACKUP] 源分区: {}", config.source_partition);
-        println!("[PE BACKUP] 保存路径: {}", config.save_path);
+        cli_println!("[PE BACKUP] 源分区: {}", config.source_partition);
+        cli_println!("[PE BACKUP] 保存路径: {}", config.save_path);
 
         // 查找备份标记分区
         let source_partition = ConfigFileManager::find_backup_marker_partition()
             .unwrap_or_else(|| config.source_partition.clone());
 
         // 执行备份
-        let dism = Dism::new();
-        let capture_dir = format!("{}\\", source_partition);
-
-        let backup_result =
-            if config.incremental && std::path::Path::new(&config.save_path).exists() {
-                dism.append_image(
-                    &config.save_path,
-                    &capture_dir,
-                    &config.name,
-                    &config.description,
-                    None,
-                )
-            } else {
-                dism.capture_image(
-                    &config.save_path,
-                    &capture_dir,
-                    &config.name,
-                    &config.description,
-                    None,
-                )
-            };
-
-        if let Err(e) = backup_result {
-            eprintln!("[PE BACKUP] 备份失败: {}", e);
-            show_error_message(&format!("系统备份失败: {}", e));
-            return Ok(());
+        let result = execute_backup(&source_partition, &config);
+        watchdog.finish();
+        if let Err(e) = result {
+            cli_eprintln!("[PE BACKUP] 备份失败: {:#}", e);
+            show_error_message(&format!("系统备份失败: {:#}", e));
+            return ExitCode::BackupFailed;
         }
 
         // 删除PE引导项
@@ -391,173 +284,796 @@ fn run_cli_mode(is_install: bool) -> eframe::Result<()> {
         ConfigFileManager::cleanup_data_dir(&data_partition);
         ConfigFileManager::cleanup_pe_dir(&data_partition);
 
-        println!("[PE BACKUP] 备份完成!");
-        show_success_message(&format!(
-            "系统备份完成！\n保存位置: {}",
+        cli_println!("[PE BACKUP] 备份完成!");
+        finish_after_countdown(CompletionAction::RebootCountdown, &format!(
+            "系统备份完成！保存位置: {}",
             config.save_path
         ));
+    }
 
-        // 自动重启
-        let _ = utils::command::new_command("shutdown")
-            .args([
-                "/r",
-                "/t",
-                "10",
-                "/c",
-                "LetRecovery 系统备份完成，即将重启...",
-            ])
-            .spawn();
+    ExitCode::Success
+}
+
+/// 按安装配置执行安装流程
+///
+/// `data_partition` 为主程序准备的数据分区，命令行子命令安装时为 None，数据目录为临时目录，结束时删除
+fn execute_install(
+    target_partition: &str,
+    image_path: &str,
+    config: &core::config::InstallConfig,
+    data_dir: &str,
+    data_partition: Option<&str>,
+) -> Result<(), core::install_engine::EngineFailure> {
+    use core::bcdedit::BootManager;
+    use core::config::ConfigFileManager;
+    use core::dism::Dism;
+    use core::disk::{DiskConversion, DiskManager};
+    use core::ghost::Ghost;
+    use core::install_engine::{InstallEngine, RetryPolicy, Step};
+    use std::cell::RefCell;
+    use std::time::Duration;
+    use core::zst_pack;
+    use ui::advanced_options::apply_advanced_options;
+
+    /// 递归查找目录中的所有 CAB 文件
+    fn find_cab_files_in_dir(dir: &str) -> Vec<std::path::PathBuf> {
+        fn find_recursive(dir: &std::path::Path, files: &mut Vec<std::path::PathBuf>) {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path.is_file() {
+                        if let Some(ext) = path.extension() {
+                            if ext.to_string_lossy().to_lowercase() == "cab" {
+                                files.push(path);
+                            }
+                        }
+                    } else if path.is_dir() {
+                        find_recursive(&path, files);
+                    }
+                }
+            }
+        }
+        let mut files = Vec::new();
+        find_recursive(std::path::Path::new(dir), &mut files);
+        files
     }
 
-    Ok(())
+    let apply_dir = format!("{}\\", target_partition);
+    let harvested_dir = format!("{}\\harvested_drivers", data_dir);
+    // zstd 驱动/更新包的解压目录（位于目标分区，清理步骤中删除）
+    let pack_staging = std::path::PathBuf::from(format!("{}\\LetRecovery_Packs", target_partition));
+    let boot_manager = BootManager::new();
+    let use_uefi = DiskManager::detect_uefi_mode();
+    // 修复引导前导出的 BCD（回滚时恢复，保留 PE 引导项以便重试）
+    let bcd_backup = RefCell::new(None);
+
+    let engine = InstallEngine::new()
+        .step(
+            // 格式化前从目标分区的现有系统导出第三方驱动
+            Step::new("harvest", "导出现有系统驱动", |ctx| {
+                if !config.harvest_drivers {
+                    ctx.status("跳过导出现有系统驱动");
+                    return Ok(());
+                }
+                let count = Dism::new().harvest_drivers(&target_partition, &harvested_dir)?;
+                ctx.status(format!("已从现有系统导出 {} 个驱动包", count));
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            // 固件为 UEFI 而目标磁盘为 MBR 时，格式化前用 mbr2gpt 无损转换（需原系统可引导）
+            Step::new("convert", "转换分区表", |ctx| {
+                let plan = DiskManager::plan_for_firmware(&target_partition, use_uefi)?;
+                if plan.conversion == DiskConversion::None {
+                    return Ok(());
+                }
+                ctx.status(plan.describe());
+                DiskManager::plan_and_apply(&plan)?;
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            Step::new("format", "格式化分区", |_| DiskManager::format_partition(&target_partition).map(|_| ()))
+                // 卷可能被短暂占用，稍后重试
+                .retry(RetryPolicy::times(3, Duration::from_secs(3))),
+        )
+        .step(
+            Step::new("apply", "释放镜像", |_| {
+                if config.is_gho {
+                    let ghost = Ghost::new();
+                    if !ghost.is_available() {
+                        anyhow::bail!("Ghost工具不可用");
+                    }
+                    let partitions = DiskManager::get_partitions().unwrap_or_default();
+                    ghost.restore_image_to_letter(&image_path, config.gho_partition, &target_partition, &partitions, json_progress::dism_progress())
+                } else {
+                    Dism::with_backend(config.image_backend).apply_image(
+                        &image_path,
+                        &apply_dir,
+                        config.volume_index,
+                        json_progress::dism_progress(),
+                    )
+                }
+            })
+            .after(&["format"]),
+        )
+        .step(
+            Step::new("verify", "校验已释放的文件", |ctx| {
+                if config.is_gho || config.apply_verify == core::apply_verify::ApplyVerifyMode::Off {
+                    return Ok(());
+                }
+                ctx.status(config.apply_verify.label());
+                let on_progress = |done: usize, total: usize| ctx.progress((done * 100 / total.max(1)) as u8);
+                match core::apply_verify::verify_applied_image(
+                    &image_path,
+                    config.volume_index,
+                    &apply_dir,
+                    config.apply_verify,
+                    &on_progress,
+                ) {
+                    Ok(report) if report.is_ok() => {
+                        ctx.status(report.summary());
+                        Ok(())
+                    }
+                    Ok(report) => anyhow::bail!("{}", report.summary()),
+                    Err(e) => {
+                        ctx.warn(format!("无法进行释放后校验: {:#}", e));
+                        Ok(())
+                    }
+                }
+            })
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("drivers", "导入驱动", |ctx| {
+                let driver_path = format!("{}\\drivers", data_dir);
+                if !config.should_import_drivers() {
+                    ctx.status("跳过驱动导入");
+                    return Ok(());
+                }
+                if !std::path::Path::new(&driver_path).exists() {
+                    ctx.status("驱动目录不存在，跳过驱动导入");
+                    return Ok(());
+                }
+
+                let dism = Dism::new();
+                // 解压数据目录中的 zstd 驱动包，与原目录依次导入
+                for driver_dir in zst_pack::expand_packs(&driver_path, &pack_staging.join("drivers")) {
+                    match dism.add_drivers_offline_with_progress(&apply_dir, &driver_dir, None) {
+                        Ok(results) => core::dism_exe::report_driver_results(ctx, &results),
+                        Err(e) => ctx.warn(format!("驱动导入失败: {} (继续安装)", e)),
+                    }
+
+                    // 同时检查驱动目录中是否有 CAB 文件并安装
+                    let cab_files = find_cab_files_in_dir(&driver_dir);
+                    if !cab_files.is_empty() {
+                        ctx.status(format!("在驱动目录中发现 {} 个 CAB 文件，一并安装", cab_files.len()));
+                        match dism.add_packages_offline_from_dir(&apply_dir, &driver_dir, None) {
+                            Ok((success, fail)) => {
+                                ctx.status(format!("驱动目录中的CAB安装完成: {} 成功, {} 失败", success, fail));
+                            }
+                            Err(e) => ctx.warn(format!("驱动目录中的CAB安装失败: {} (继续安装)", e)),
+                        }
+                    }
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("hwid", "注入网卡/存储驱动", |ctx| {
+                // 自动导入模式下已随 drivers 目录一并导入
+                let hwid_dir = format!("{}\\drivers\\hwid", data_dir);
+                if !config.hwid_drivers || config.should_import_drivers() || !std::path::Path::new(&hwid_dir).exists() {
+                    ctx.status("跳过注入网卡/存储驱动");
+                    return Ok(());
+                }
+                let results = Dism::new().add_drivers_offline_with_progress(&apply_dir, &hwid_dir, json_progress::dism_progress())?;
+                core::dism_exe::report_driver_results(ctx, &results);
+                Ok(())
+            })
+            .optional()
+            .after(&["drivers"]),
+        )
+        .step(
+            Step::new("reinject", "重新注入现有系统驱动", |ctx| {
+                if !config.harvest_drivers || !std::path::Path::new(&harvested_dir).exists() {
+                    ctx.status("跳过重新注入现有系统驱动");
+                    return Ok(());
+                }
+                let results = Dism::new().add_drivers_offline_with_progress(&apply_dir, &harvested_dir, json_progress::dism_progress())?;
+                core::dism_exe::report_driver_results(ctx, &results);
+                Ok(())
+            })
+            .optional()
+            .after(&["harvest", "apply"]),
+        )
+        .step(
+            Step::new("cab", "安装CAB更新包", |ctx| {
+                if !config.install_cab_packages {
+                    ctx.status("跳过CAB更新包安装");
+                    return Ok(());
+                }
+                let cab_path = format!("{}\\updates", data_dir);
+                if !std::path::Path::new(&cab_path).exists() {
+                    ctx.status("更新包目录不存在，跳过CAB安装");
+                    return Ok(());
+                }
+                for update_dir in zst_pack::expand_packs(&cab_path, &pack_staging.join("updates")) {
+                    let (success, fail) = Dism::new().add_packages_offline_from_dir(&apply_dir, &update_dir, None)?;
+                    ctx.status(format!("CAB更新包安装完成: {} 成功, {} 失败", success, fail));
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("boot", "修复引导", |_| {
+                if bcd_backup.borrow().is_none() {
+                    match boot_manager.export_bcd() {
+                        Ok(path) => *bcd_backup.borrow_mut() = Some(path),
+                        Err(e) => log::warn!("修复引导前备份 BCD 失败: {:#}", e),
+                    }
+                }
+                boot_manager.repair_boot_advanced(&target_partition, use_uefi)
+            })
+            .retry(RetryPolicy::times(2, Duration::from_secs(2)))
+            .after(&["apply"])
+            .rollback(|| match bcd_backup.borrow().as_deref() {
+                Some(path) => boot_manager.import_bcd(path),
+                None => Ok(()),
+            }),
+        )
+        .step(
+            // 如果启用了 Win7 UEFI 补丁，应用 UefiSeven
+            Step::new("uefiseven", "应用 Win7 UEFI 补丁", |ctx| {
+                if !(use_uefi && config.win7_uefi_patch) {
+                    ctx.status("跳过 Win7 UEFI 补丁");
+                    return Ok(());
+                }
+                // UefiSeven 文件由主程序放在数据分区中
+                let Some(data_partition) = data_partition else {
+                    ctx.warn("没有数据分区，跳过 Win7 UEFI 补丁");
+                    return Ok(());
+                };
+                ui::advanced_options::apply_uefiseven_patch(data_partition, target_partition)?;
+                ctx.status("UefiSeven 补丁应用成功");
+                Ok(())
+            })
+            .optional()
+            .after(&["boot"]),
+        )
+        .step(
+            Step::new("options", "应用高级选项", |ctx| {
+                if let Err(e) = apply_advanced_options(&target_partition, &config) {
+                    ctx.warn(format!("应用高级选项失败: {}", e));
+                }
+                match core::auto_unlock::install_pending(&data_dir, &target_partition) {
+                    Ok(true) => ctx.status("已安装 BitLocker 数据分区解锁脚本"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装 BitLocker 数据分区解锁脚本失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["boot"]),
+        )
+        .step(
+            Step::new("hooks", "安装首次启动脚本", |ctx| {
+                let scripts_dir = std::path::Path::new(&data_dir).join(core::script_hooks::SCRIPTS_DIR);
+                // 未配置时按文件名顺序执行 scripts 文件夹中的全部脚本
+                let hooks = if config.script_hooks.is_empty() {
+                    core::script_hooks::discover(&scripts_dir, &[])
+                } else {
+                    config.script_hooks.clone()
+                };
+                match core::script_hooks::install_hooks(&target_partition, &scripts_dir, &hooks) {
+                    Ok(0) => {}
+                    Ok(count) => ctx.status(format!("已安装 {} 个首次启动脚本", count)),
+                    Err(e) => ctx.warn(format!("安装首次启动脚本失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("health", "安装首次启动健康检查", |ctx| {
+                match core::health_check::install_agent(target_partition, data_partition, &config.health_check) {
+                    Ok(true) => ctx.status("已安装首次启动健康检查"),
+                    Ok(false) => {}
+                    Err(e) => ctx.warn(format!("安装首次启动健康检查失败: {:#}", e)),
+                }
+                Ok(())
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("unattend", "生成无人值守配置", |ctx| {
+                if !config.unattended {
+                    ctx.status("跳过无人值守配置");
+                    return Ok(());
+                }
+                generate_unattend_xml(&target_partition, &config)
+            })
+            .optional()
+            .after(&["apply"]),
+        )
+        .step(
+            Step::new("cleanup", "清理临时文件", |_| {
+                let _ = std::fs::remove_dir_all(&pack_staging);
+                match data_partition {
+                    Some(data_partition) => ConfigFileManager::cleanup_all(data_partition, target_partition),
+                    None => {
+                        let _ = std::fs::remove_dir_all(data_dir);
+                    }
+                }
+                Ok(())
+            })
+            .optional(),
+        )
+        .step(
+            // 清理自动创建的数据分区并扩展目标分区
+            Step::new("partition", "清理自动创建的分区", |ctx| {
+                DiskManager::cleanup_auto_created_partition_and_extend(&target_partition, &config.data_partition_id)?;
+                ctx.status("自动创建分区清理完成");
+                Ok(())
+            })
+            .optional(),
+        );
+
+    engine.run(report_install_event)
 }
 
-/// 生成无人值守XML
-fn generate_unattend_xml(target_partition: &str, username: &str) -> anyhow::Result<()> {
-    let username = if username.is_empty() { "User" } else { username };
-
-    let xml_content = format!(
-        r#"<?xml version="1.0" encoding="utf-8"?>
-<unattend xmlns="urn:schemas-microsoft-com:unattend" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State">
-    <settings pass="windowsPE">
-        <component name="Microsoft-Windows-Setup" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS">
-            <UserData>
-                <ProductKey>
-                    <WillShowUI>OnError</WillShowUI>
-                </ProductKey>
-                <AcceptEula>true</AcceptEula>
-            </UserData>
-        </component>
-    </settings>
-    <settings pass="oobeSystem">
-        <component name="Microsoft-Windows-Shell-Setup" processorArchitecture="amd64" publicKeyToken="31bf3856ad364e35" language="neutral" versionScope="nonSxS" xmlns:wcm="http://schemas.microsoft.com/WMIConfig/2002/State" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
-            <OOBE>
-                <HideEULAPage>true</HideEULAPage>
-                <HideLocalAccountScreen>true</HideLocalAccountScreen>
-                <HideOEMRegistrationScreen>true</HideOEMRegistrationScreen>
-                <HideOnlineAccountScreens>true</HideOnlineAccountScreens>
-                <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>
-                <ProtectYourPC>3</ProtectYourPC>
-                <SkipMachineOOBE>true</SkipMachineOOBE>
-                <SkipUserOOBE>true</SkipUserOOBE>
-            </OOBE>
-            <UserAccounts>
-                <LocalAccounts>
-                    <LocalAccount wcm:action="add">
-                        <Password>
-                            <Value></Value>
-                            <PlainText>true</PlainText>
-                        </Password>
-                        <Description>Local User</Description>
-                        <DisplayName>{}</DisplayName>
-                        <Group>Administrators</Group>
-                        <Name>{}</Name>
-                    </LocalAccount>
-                </LocalAccounts>
-            </UserAccounts>
-            <AutoLogon>
-                <Password>
-                    <Value></Value>
-                    <PlainText>true</PlainText>
-                </Password>
-                <Enabled>true</Enabled>
-                <Username>{}</Username>
-            </AutoLogon>
-        </component>
-    </settings>
-</unattend>"#,
-        username, username, username
-    );
-
-    let panther_dir = format!("{}\\Windows\\Panther", target_partition);
-    std::fs::create_dir_all(&panther_dir)?;
-
-    let unattend_path = format!("{}\\unattend.xml", panther_dir);
-    std::fs::write(&unattend_path, &xml_content)?;
+/// 命令行子命令
+fn run_cli(invocation: Result<utils::cli::Invocation, String>) -> ExitCode {
+    use utils::cli::{self, Command};
 
-    Ok(())
+    let invocation = match invocation {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::usage("LetRecoveryPE.exe"));
+            return ExitCode::InvalidArguments;
+        }
+    };
+    if invocation.json {
+        json_progress::enable();
+    }
+
+    match invocation.command {
+        Command::Help => {
+            println!("{}", cli::usage("LetRecoveryPE.exe"));
+            ExitCode::Success
+        }
+        Command::ListPartitions => run_list_partitions(invocation.json),
+        Command::ListImages { image } => run_list_images(&image, invocation.json),
+        Command::Verify { image } => run_verify(&image),
+        Command::Install(args) => run_cli_install(&args),
+        Command::Backup(args) => run_cli_backup(&args),
+    }
 }
 
-/// 显示错误消息框
-fn show_error_message(message: &str) {
-    #[cfg(windows)]
-    {
-        use std::ffi::OsStr;
-        use std::os::windows::ffi::OsStrExt;
-        use std::ptr::null_mut;
-
-        let wide_message: Vec<u16> = OsStr::new(message)
-            .encode_wide()
-            .chain(std::iter::once(0))
-            .collect();
-        let wide_title: Vec<u16> = OsStr::new("LetRecovery PE 错误")
-            .encode_wide()
-            .chain(std::iter::once(0))
-            .collect();
+/// 命令行子命令失败：输出错误并返回退出码
+fn cli_fail(tag: &str, code: ExitCode, message: String) -> ExitCode {
+    cli_eprintln!("{} {}", tag, message);
+    json_progress::emit(ProgressEvent::Finished { success: false, message });
+    code
+}
 
-        unsafe {
-            #[link(name = "user32")]
-            extern "system" {
-                fn MessageBoxW(
-                    hwnd: *mut std::ffi::c_void,
-                    text: *const u16,
-                    caption: *const u16,
-                    utype: u32,
-                ) -> i32;
+/// 安装失败步骤对应的退出码
+fn install_exit_code(failure: &core::install_engine::EngineFailure) -> ExitCode {
+    match failure.step {
+        "format" => ExitCode::FormatFailed,
+        "apply" => ExitCode::ApplyFailed,
+        "boot" => ExitCode::BootRepairFailed,
+        _ => ExitCode::Failed,
+    }
+}
+
+/// 命令行安装系统
+fn run_cli_install(args: &utils::cli::InstallArgs) -> ExitCode {
+    use core::watchdog::{Watchdog, HANG_TIMEOUT};
+    const TAG: &str = "[CLI INSTALL]";
+
+    cli_println!("{} {} (分卷 {}) -> {}", TAG, args.image, args.index, args.target);
+    json_progress::emit(ProgressEvent::Start { operation: "install" });
+
+    let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "X:".to_string());
+    if args.target.eq_ignore_ascii_case(&system_drive) {
+        return cli_fail(TAG, ExitCode::Failed, format!("{} 是 PE 系统所在的分区", args.target));
+    }
+    if !std::path::Path::new(&args.image).exists() {
+        return cli_fail(TAG, ExitCode::ImageInvalid, format!("镜像文件不存在: {}", args.image));
+    }
+
+    let lower = args.image.to_lowercase();
+    let is_gho = lower.ends_with(".gho") || lower.ends_with(".ghs");
+    let action = if args.no_reboot {
+        CompletionAction::StayInPe
+    } else {
+        CompletionAction::RebootCountdown
+    };
+    let config = core::config::InstallConfig {
+        target_partition: args.target.clone(),
+        image_path: args.image.clone(),
+        volume_index: args.index,
+        is_gho,
+        gho_partition: args.index,
+        completion_action: Some(action),
+        ..Default::default()
+    };
+
+    // 安装结束时会删除数据目录，使用单独的临时目录
+    let data_dir = std::env::temp_dir().join(format!("LetRecovery_CLI_{}", std::process::id()));
+    if let Err(e) = std::fs::create_dir_all(&data_dir) {
+        return cli_fail(TAG, ExitCode::Failed, format!("创建临时目录失败: {}", e));
+    }
+    let data_dir = data_dir.to_string_lossy().to_string();
+
+    let watchdog = Watchdog::start(HANG_TIMEOUT);
+    watchdog.watch_current_thread();
+    let result = execute_install(&args.target, &args.image, &config, &data_dir, None);
+    watchdog.finish();
+
+    match result {
+        Ok(()) => {
+            let message = if action == CompletionAction::StayInPe {
+                "系统安装完成，请手动重启计算机".to_string()
+            } else {
+                format!("系统安装完成，即将{}", action.verb())
+            };
+            cli_println!("{} {}", TAG, message);
+            json_progress::emit(ProgressEvent::Finished { success: true, message });
+            if let Err(e) = action.execute(REBOOT_COUNTDOWN.as_secs(), "LetRecovery 系统安装完成") {
+                cli_eprintln!("{} {:#}", TAG, e);
             }
-            MessageBoxW(
-                null_mut(),
-                wide_message.as_ptr(),
-                wide_title.as_ptr(),
-                0x10,
-            ); // MB_ICONERROR
+            ExitCode::Success
         }
+        Err(failure) => cli_fail(TAG, install_exit_code(&failure), format!("系统安装失败: {}", failure)),
     }
+}
 
-    #[cfg(not(windows))]
-    {
-        eprintln!("错误: {}", message);
+/// 命令行校验镜像完整性
+///
+/// PE 中使用 wimlib 逐个校验 WIM/ESD/SWM 镜像中的分卷
+fn run_verify(image_path: &str) -> ExitCode {
+    use core::wimlib::{Wimlib, WimlibProgress};
+    const TAG: &str = "[CLI VERIFY]";
+
+    cli_println!("{} 校验镜像: {}", TAG, image_path);
+    json_progress::emit(ProgressEvent::Start { operation: "verify" });
+
+    if !std::path::Path::new(image_path).is_file() {
+        return cli_fail(TAG, ExitCode::ImageInvalid, format!("镜像文件不存在: {}", image_path));
     }
+    let lower = image_path.to_lowercase();
+    if ![".wim", ".esd", ".swm"].iter().any(|ext| lower.ends_with(ext)) {
+        return cli_fail(TAG, ExitCode::ImageInvalid, "PE 版只能校验 WIM/ESD/SWM 镜像".to_string());
+    }
+
+    let wimlib = match Wimlib::new() {
+        Ok(wimlib) => wimlib,
+        Err(e) => return cli_fail(TAG, ExitCode::Failed, format!("无法加载 wimlib: {}", e)),
+    };
+    let wim = match wimlib.open_image_file(image_path) {
+        Ok(wim) => wim,
+        Err(e) => return cli_fail(TAG, ExitCode::ImageInvalid, format!("无法打开镜像: {}", e.detailed())),
+    };
+
+    if wimlib.supports_verify_image() {
+        let count = wim.get_image_count();
+        for index in 1..=count {
+            let stage = format!("校验分卷 {}/{}", index, count);
+            cli_println!("{} {}", TAG, stage);
+            let progress_tx = json_progress::forward_progress(move |p: WimlibProgress| {
+                (p.percentage, format!("{} {}", stage, p.status))
+            });
+            if let Err(e) = wim.verify_image(index, progress_tx) {
+                return cli_fail(TAG, ExitCode::ImageInvalid, format!("分卷 {} 已损坏: {}", index, e.detailed()));
+            }
+        }
+    } else if let Err(e) = wim.verify() {
+        // 旧版 wimlib 不能单独校验分卷，整体校验
+        return cli_fail(TAG, ExitCode::ImageInvalid, format!("镜像已损坏: {}", e.detailed()));
+    }
+
+    let message = "镜像完整".to_string();
+    cli_println!("{} {}", TAG, message);
+    json_progress::emit(ProgressEvent::Finished { success: true, message });
+    ExitCode::Success
 }
 
-/// 显示成功消息框
-fn show_success_message(message: &str) {
-    #[cfg(windows)]
-    {
-        use std::ffi::OsStr;
-        use std::os::windows::ffi::OsStrExt;
-        use std::ptr::null_mut;
-
-        let wide_message: Vec<u16> = OsStr::new(message)
-            .encode_wide()
-            .chain(std::iter::once(0))
+/// 命令行备份分区
+fn run_cli_backup(args: &utils::cli::BackupArgs) -> ExitCode {
+    cli_println!("[CLI BACKUP] {} -> {}", args.source, args.output);
+    json_progress::emit(ProgressEvent::Start { operation: "backup" });
+
+    if let Some(parent) = std::path::Path::new(&args.output).parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            let message = format!("创建备份目录失败: {}", e);
+            cli_eprintln!("[CLI BACKUP] {}", message);
+            json_progress::emit(ProgressEvent::Finished { success: false, message });
+            return ExitCode::BackupFailed;
+        }
+    }
+
+    let name = if args.name.trim().is_empty() {
+        core::backup_catalog::default_name(&args.source)
+    } else {
+        args.name.clone()
+    };
+    let config = core::config::BackupConfig {
+        save_path: args.output.clone(),
+        name,
+        description: args.description.clone(),
+        source_partition: args.source.clone(),
+        incremental: args.append,
+        ..Default::default()
+    };
+
+    let (success, message) = match execute_backup(&args.source, &config) {
+        Ok(_) => (true, format!("系统备份完成，保存位置: {}", args.output)),
+        Err(e) => (false, format!("系统备份失败: {:#}", e)),
+    };
+    if success {
+        cli_println!("[CLI BACKUP] {}", message);
+    } else {
+        cli_eprintln!("[CLI BACKUP] {}", message);
+    }
+    json_progress::emit(ProgressEvent::Finished { success, message });
+    if success {
+        ExitCode::Success
+    } else {
+        ExitCode::BackupFailed
+    }
+}
+
+/// 列出分区
+fn run_list_partitions(json: bool) -> ExitCode {
+    use utils::cli::PartitionRow;
+
+    let rows: Vec<PartitionRow> = core::disk::DiskManager::get_partitions()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| PartitionRow {
+            letter: p.letter,
+            label: p.label,
+            total_mb: p.total_size_mb,
+            free_mb: p.free_size_mb,
+            partition_style: p.partition_style.to_string(),
+            disk_number: p.disk_number,
+            partition_number: p.partition_number,
+            is_system: p.is_system_partition,
+            has_windows: p.has_windows,
+        })
+        .collect();
+
+    if json {
+        utils::cli::print_json(&rows);
+    } else {
+        let table: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                vec![
+                    row.letter.clone(),
+                    row.label.clone(),
+                    format!("{:.1} GB", row.total_mb as f64 / 1024.0),
+                    format!("{:.1} GB", row.free_mb as f64 / 1024.0),
+                    row.partition_style.clone(),
+                    if row.has_windows { "Windows".to_string() } else { String::new() },
+                ]
+            })
             .collect();
-        let wide_title: Vec<u16> = OsStr::new("LetRecovery PE")
-            .encode_wide()
-            .chain(std::iter::once(0))
+        println!(
+            "{}",
+            utils::cli::format_table(&["盘符", "卷标", "总容量", "可用", "分区表", "备注"], &table)
+        );
+    }
+    ExitCode::Success
+}
+
+/// 列出镜像中的分卷
+fn run_list_images(image_path: &str, json: bool) -> ExitCode {
+    use utils::cli::ImageRow;
+
+    let images = match core::wimgapi::WimManager::new().and_then(|wim| wim.get_image_info(image_path)) {
+        Ok(images) => images,
+        Err(e) => {
+            cli_eprintln!("[CLI] 读取镜像失败: {}", e);
+            return ExitCode::ImageInvalid;
+        }
+    };
+    let rows: Vec<ImageRow> = images
+        .into_iter()
+        .map(|image| ImageRow {
+            index: image.index,
+            name: image.name,
+            description: image.description,
+            size_bytes: image.size_bytes,
+        })
+        .collect();
+
+    if json {
+        utils::cli::print_json(&rows);
+    } else {
+        let table: Vec<Vec<String>> = rows
+            .iter()
+            .map(|row| {
+                vec![
+                    row.index.to_string(),
+                    row.name.clone(),
+                    format!("{:.2} GB", row.size_bytes as f64 / 1024.0 / 1024.0 / 1024.0),
+                    row.description.clone(),
+                ]
+            })
             .collect();
+        println!("{}", utils::cli::format_table(&["分卷", "名称", "大小", "描述"], &table));
+    }
+    ExitCode::Success
+}
 
-        unsafe {
-            #[link(name = "user32")]
-            extern "system" {
-                fn MessageBoxW(
-                    hwnd: *mut std::ffi::c_void,
-                    text: *const u16,
-                    caption: *const u16,
-                    utype: u32,
-                ) -> i32;
-            }
-            MessageBoxW(
-                null_mut(),
-                wide_message.as_ptr(),
-                wide_title.as_ptr(),
-                0x40,
-            ); // MB_ICONINFORMATION
+/// PE备份步骤总数
+const BACKUP_STEP_COUNT: u8 = 2;
+
+/// 执行备份：捕获或追加镜像，并记录还原点
+fn execute_backup(source_partition: &str, config: &core::config::BackupConfig) -> anyhow::Result<()> {
+    cli_step("[PE BACKUP]", 1, BACKUP_STEP_COUNT, "捕获镜像");
+    let dism = core::dism::Dism::with_backend(config.image_backend);
+    let capture_dir = core::disk::volume_root(source_partition);
+
+    let size_before = core::backup_catalog::image_size(&config.save_path);
+    if config.incremental && std::path::Path::new(&config.save_path).exists() {
+        dism.append_image(
+            &config.save_path,
+            &capture_dir,
+            &config.name,
+            &config.description,
+            &config.capture,
+            json_progress::dism_progress(),
+        )?;
+    } else {
+        dism.capture_image(
+            &config.save_path,
+            &capture_dir,
+            &config.name,
+            &config.description,
+            &config.capture,
+            json_progress::dism_progress(),
+        )?;
+    }
+
+    // 验证备份文件
+    cli_step("[PE BACKUP]", 2, BACKUP_STEP_COUNT, "验证备份文件");
+    let backup_size = std::fs::metadata(&config.save_path)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    if backup_size == 0 {
+        anyhow::bail!("备份文件不存在或为空: {}", config.save_path);
+    }
+    if let Err(e) = core::backup_catalog::record_backup(
+        &config.save_path,
+        source_partition,
+        &config.name,
+        &config.description,
+        size_before,
+    ) {
+        log::warn!("[PE BACKUP] 记录还原点失败: {:#}", e);
+    }
+    Ok(())
+}
+
+/// 输出命令行模式的步骤信息
+fn cli_step(tag: &str, step: u8, total: u8, name: &str) {
+    core::watchdog::set_stage(name);
+    cli_println!("{} Step {}: {}", tag, step, name);
+    json_progress::emit(ProgressEvent::Step { step, total, name: name.to_string() });
+}
+
+/// 输出不中断流程的警告
+fn cli_warn(tag: &str, message: String) {
+    eprintln!("{} 警告: {}", tag, message);
+    json_progress::emit(ProgressEvent::Warning { message });
+}
+
+/// 将安装引擎事件输出到命令行和 JSON 进度
+fn report_install_event(event: core::install_engine::EngineEvent) {
+    use core::install_engine::EngineEvent;
+
+    core::watchdog::heartbeat();
+    let warning = match event {
+        EngineEvent::StepStarted { index, total, name, .. } => {
+            cli_step("[PE INSTALL]", index as u8 + 1, total as u8, name);
+            return;
+        }
+        EngineEvent::Status(status) => {
+            cli_println!("[PE INSTALL] {}", status);
+            json_progress::emit(ProgressEvent::Message { message: status });
+            return;
+        }
+        // 进度由 DISM/Ghost 进度通道直接输出
+        EngineEvent::Progress(_) => return,
+        EngineEvent::Retrying { name, attempt, max_attempts, error } => {
+            format!("{}失败（第 {}/{} 次），稍后重试: {}", name, attempt, max_attempts, error)
+        }
+        EngineEvent::StepSkipped { name, reason } => format!("跳过{}: {}", name, reason),
+        EngineEvent::Warning { message, .. } => message,
+        EngineEvent::RollingBack { name } => format!("正在回滚: {}", name),
+        EngineEvent::RollbackFailed { name, error } => format!("回滚{}失败: {}", name, error),
+    };
+    cli_warn("[PE INSTALL]", warning);
+}
+
+/// 生成无人值守XML
+fn generate_unattend_xml(target_partition: &str, config: &core::config::InstallConfig) -> anyhow::Result<()> {
+    use crate::core::image_language::TargetLanguage;
+    use crate::core::system_utils::get_offline_system_architecture;
+    use crate::core::unattend::{write_unattend, UnattendBuilder, UnattendTarget};
+
+    let arch = get_offline_system_architecture(std::path::Path::new(target_partition));
+    let xml = UnattendBuilder::new(arch.as_unattend_str(), UnattendTarget::detect(target_partition))
+        .username(&config.custom_username)
+        .settings(&config.unattend)
+        .win11_bypass(config.win11_bypass())
+        .language(TargetLanguage::detect(target_partition))
+        .build();
+    write_unattend(target_partition, &xml)
+}
+
+/// 显示错误提示（带倒计时自动关闭，不阻塞无人值守流程）
+///
+/// JSON 进度模式下不弹出窗口，改为输出失败事件
+fn show_error_message(message: &str) {
+    log::error!("{}", message);
+    if json_progress::is_enabled() {
+        eprintln!("错误: {}", message);
+        json_progress::emit(ProgressEvent::Finished { success: false, message: message.to_string() });
+        return;
+    }
+
+    eprintln!("错误: {}", message);
+    run_countdown_dialog("LetRecovery PE 错误", message, true, CountdownAction::Close, DIALOG_COUNTDOWN);
+}
+
+/// 显示成功提示（带倒计时自动关闭）
+///
+/// JSON 进度模式下不弹出窗口，改为输出成功事件
+fn show_success_message(message: &str) {
+    log::info!("{}", message);
+    if json_progress::is_enabled() {
+        eprintln!("成功: {}", message);
+        json_progress::emit(ProgressEvent::Finished { success: true, message: message.to_string() });
+        return;
+    }
+
+    println!("成功: {}", message);
+    run_countdown_dialog("LetRecovery PE", message, false, CountdownAction::Close, DIALOG_COUNTDOWN);
+}
+
+/// 操作完成后执行电源操作：立即重启直接执行，其余操作先倒计时，用户取消则不执行
+///
+/// JSON 进度模式下不弹出窗口，由系统按相同的等待时间执行
+fn finish_after_countdown(action: CompletionAction, message: &str) {
+    if json_progress::is_enabled() {
+        json_progress::emit(ProgressEvent::Finished { success: true, message: message.to_string() });
+        let delay = if action.uses_countdown() { REBOOT_COUNTDOWN.as_secs() } else { 0 };
+        if let Err(e) = action.execute(delay, message) {
+            log::error!("{:#}", e);
         }
+        return;
     }
 
-    #[cfg(not(windows))]
-    {
-        println!("成功: {}", message);
+    let confirmed = !action.uses_countdown()
+        || run_countdown_dialog("LetRecovery PE", message, false, CountdownAction::Finish(action), REBOOT_COUNTDOWN);
+    if !confirmed {
+        log::info!("用户取消了自动{}", action.verb());
+        return;
+    }
+    if let Err(e) = action.execute(0, "") {
+        log::error!("{:#}", e);
     }
 }
@@ -1,6 +1,9 @@
 use crate::core::config::InstallConfig;
+use crate::core::disk_policy::{self, WriteCachePolicy};
 use crate::core::dism::Dism;
-use crate::core::registry::OfflineRegistry;
+use crate::core::image_language::TargetLanguage;
+use crate::core::offline_registry::{OfflineHive, OfflineRegistrySession};
+use crate::core::storage_controller;
 use crate::utils::path;
 use std::path::{Path, PathBuf};
 
@@ -12,24 +15,17 @@ const SCRIPTS_DIR: &str = "LetRecovery_Scripts";
 /// 此函数在PE环境中执行，负责将用户选择的高级选项应用到目标系统。
 /// 通过离线修改注册表和生成必要的脚本来实现各项功能。
 pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) -> anyhow::Result<()> {
-    let windows_path = format!("{}\\Windows", target_partition);
-    let software_hive = format!("{}\\System32\\config\\SOFTWARE", windows_path);
-    let system_hive = format!("{}\\System32\\config\\SYSTEM", windows_path);
-    let default_hive = format!("{}\\System32\\config\\DEFAULT", windows_path);
-
     log::info!("[ADVANCED] 开始应用高级选项到: {}", target_partition);
 
-    // 加载离线注册表
+    // 加载离线注册表（离开本函数时自动卸载）
     log::info!("[ADVANCED] 加载离线注册表...");
-    OfflineRegistry::load_hive("pc-soft", &software_hive)?;
-    OfflineRegistry::load_hive("pc-sys", &system_hive)?;
-    
-    // DEFAULT hive 用于设置默认用户配置（如经典右键菜单）
-    let default_loaded = OfflineRegistry::load_hive("pc-default", &default_hive).is_ok();
-    if default_loaded {
-        log::info!("[ADVANCED] DEFAULT hive 加载成功");
-    } else {
-        log::warn!("[ADVANCED] DEFAULT hive 加载失败，部分用户级设置可能无法应用");
+    let mut registry = OfflineRegistrySession::open(
+        target_partition,
+        &[OfflineHive::Software, OfflineHive::System],
+    )?;
+    // 默认用户的 NTUSER.DAT 用于设置新用户配置（如经典右键菜单）
+    if let Err(e) = registry.load(OfflineHive::DefaultUser) {
+        log::warn!("[ADVANCED] {:#}，部分用户级设置可能无法应用", e);
     }
 
     // 创建脚本目录（用于存放自定义脚本）
@@ -42,8 +38,9 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     // 1. 移除快捷方式小箭头
     if config.remove_shortcut_arrow {
         log::info!("[ADVANCED] 移除快捷方式小箭头");
-        let _ = OfflineRegistry::set_string(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Shell Icons",
+        let _ = registry.set_string(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\Explorer\\Shell Icons",
             "29",
             "%systemroot%\\system32\\imageres.dll,197",
         );
@@ -52,25 +49,29 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     // 2. Win11恢复经典右键菜单
     if config.restore_classic_context_menu {
         log::info!("[ADVANCED] 恢复经典右键菜单");
-        // 在 DEFAULT hive 中设置（影响所有新用户）
-        if default_loaded {
+        // 在默认用户配置中设置（影响所有新用户）
+        if registry.is_loaded(OfflineHive::DefaultUser) {
             // 创建空的 InprocServer32 键，这会禁用新式右键菜单
-            let _ = OfflineRegistry::create_key(
-                "HKLM\\pc-default\\Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
+            let _ = registry.create_key(
+                OfflineHive::DefaultUser,
+                "Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
             );
             // 设置默认值为空字符串
-            let _ = OfflineRegistry::set_string(
-                "HKLM\\pc-default\\Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
+            let _ = registry.set_string(
+                OfflineHive::DefaultUser,
+                "Software\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
                 "",
                 "",
             );
         }
         // 同时在 SOFTWARE 中设置（系统级）
-        let _ = OfflineRegistry::create_key(
-            "HKLM\\pc-soft\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
+        let _ = registry.create_key(
+            OfflineHive::Software,
+            "Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32"
         );
-        let _ = OfflineRegistry::set_string(
-            "HKLM\\pc-soft\\Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
+        let _ = registry.set_string(
+            OfflineHive::Software,
+            "Classes\\CLSID\\{86ca1aa0-34aa-4e8b-a509-50c905bae2a2}\\InprocServer32",
             "",
             "",
         );
@@ -79,31 +80,41 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     // 3. OOBE绕过强制联网
     if config.bypass_nro {
         log::info!("[ADVANCED] 设置OOBE绕过联网");
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\OOBE",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\OOBE",
             "BypassNRO",
             1,
         );
     }
 
+    // 3.1 Win11 硬件要求检查绕过
+    for (key, name) in config.win11_bypass().registry_values() {
+        log::info!("[ADVANCED] 设置 {}\\{}", key, name);
+        let _ = registry.set_dword(OfflineHive::System, key, name, 1);
+    }
+
     // 4. 禁用Windows更新
     if config.disable_windows_update {
         log::info!("[ADVANCED] 禁用Windows更新服务");
         // 禁用 Windows Update 服务 (Start=4 表示禁用)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\wuauserv",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\wuauserv",
             "Start",
             4,
         );
         // 禁用 Update Orchestrator Service
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\UsoSvc",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\UsoSvc",
             "Start",
             4,
         );
         // 设置策略禁用自动更新
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Policies\\Microsoft\\Windows\\WindowsUpdate\\AU",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Policies\\Microsoft\\Windows\\WindowsUpdate\\AU",
             "NoAutoUpdate",
             1,
         );
@@ -113,32 +124,37 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     if config.disable_windows_defender {
         log::info!("[ADVANCED] 禁用Windows Defender");
         // 禁用反间谍软件（Defender主开关）
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Policies\\Microsoft\\Windows Defender",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Policies\\Microsoft\\Windows Defender",
             "DisableAntiSpyware",
             1,
         );
         // 禁用实时保护
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Policies\\Microsoft\\Windows Defender\\Real-Time Protection",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Policies\\Microsoft\\Windows Defender\\Real-Time Protection",
             "DisableRealtimeMonitoring",
             1,
         );
         // 禁用 Windows Defender 服务 (Start=4 表示禁用)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\WinDefend",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\WinDefend",
             "Start",
             4,
         );
         // 禁用 Defender 网络检查服务
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\WdNisSvc",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\WdNisSvc",
             "Start",
             4,
         );
         // 禁用安全健康服务
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\SecurityHealthService",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\SecurityHealthService",
             "Start",
             4,
         );
@@ -147,13 +163,15 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     // 6. 禁用系统保留空间
     if config.disable_reserved_storage {
         log::info!("[ADVANCED] 禁用系统保留空间");
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\ReserveManager",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\ReserveManager",
             "ShippedWithReserves",
             0,
         );
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\ReserveManager",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\ReserveManager",
             "PassedPolicy",
             0,
         );
@@ -162,13 +180,15 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     // 7. 禁用UAC
     if config.disable_uac {
         log::info!("[ADVANCED] 禁用UAC");
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Policies\\System",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\Policies\\System",
             "EnableLUA",
             0,
         );
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Microsoft\\Windows\\CurrentVersion\\Policies\\System",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Microsoft\\Windows\\CurrentVersion\\Policies\\System",
             "ConsentPromptBehaviorAdmin",
             0,
         );
@@ -178,20 +198,23 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     if config.disable_device_encryption {
         log::info!("[ADVANCED] 禁用自动设备加密");
         // 禁用 BitLocker 自动加密
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Control\\BitLocker",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Control\\BitLocker",
             "PreventDeviceEncryption",
             1,
         );
         // 禁用 MBAM (Microsoft BitLocker Administration and Monitoring)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-soft\\Policies\\Microsoft\\FVE",
+        let _ = registry.set_dword(
+            OfflineHive::Software,
+            "Policies\\Microsoft\\FVE",
             "OSRecovery",
             0,
         );
         // 禁用 BitLocker 服务
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\BDESVC",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\BDESVC",
             "Start",
             4,
         );
@@ -201,44 +224,56 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
     if config.remove_uwp_apps {
         log::info!("[ADVANCED] 配置删除预装UWP应用");
         // 创建首次登录脚本来删除UWP应用
-        let remove_uwp_script = generate_remove_uwp_script();
+        let language = TargetLanguage::detect_in(&registry, target_partition).unwrap_or_else(|| TargetLanguage::new("zh-CN"));
+        let remove_uwp_script = generate_remove_uwp_script(&language);
         let uwp_script_path = format!("{}\\remove_uwp.ps1", scripts_dir);
-        std::fs::write(&uwp_script_path, &remove_uwp_script)?;
+        // 带 BOM，避免 Windows PowerShell 按 ANSI 读取非英文提示
+        std::fs::write(&uwp_script_path, format!("\u{feff}{}", remove_uwp_script))?;
         log::info!("[ADVANCED] UWP删除脚本已写入: {}", uwp_script_path);
     }
 
-    // 10. 导入磁盘控制器驱动（Win10/Win11 x64）
-    if config.import_storage_controller_drivers {
+    // 10. 导入磁盘控制器驱动（Win10/Win11 x64，由主程序按镜像版本和检测到的控制器决定是否勾选）
+    let raid_controllers = storage_controller::detect_controllers();
+    if !config.import_storage_controller_drivers {
+        for controller in &raid_controllers {
+            log::warn!(
+                "[ADVANCED] 检测到 {}，但未勾选导入磁盘控制器驱动，新系统可能无法启动",
+                controller.display_name()
+            );
+        }
+    } else {
         let storage_drivers_dir = path::get_exe_dir()
             .join("drivers")
             .join("storage_controller");
-        if storage_drivers_dir.is_dir() {
-            log::info!(
-                "[ADVANCED] 导入磁盘控制器驱动: {}",
-                storage_drivers_dir.display()
-            );
-
-            // 先卸载注册表，因为驱动注入可能需要独占访问
-            let _ = OfflineRegistry::unload_hive("pc-soft");
-            let _ = OfflineRegistry::unload_hive("pc-sys");
-            if default_loaded {
-                let _ = OfflineRegistry::unload_hive("pc-default");
+        let pack_dirs = storage_controller::pack_dirs_for_install(
+            &storage_drivers_dir,
+            &config.storage_driver_packs,
+            &raid_controllers,
+        );
+        let pack_dirs: Vec<PathBuf> = pack_dirs.into_iter().filter(|dir| dir.is_dir()).collect();
+        if !pack_dirs.is_empty() {
+            for controller in &raid_controllers {
+                if !pack_dirs.iter().any(|dir| storage_controller::driver_dir_supports(dir, controller)) {
+                    log::warn!(
+                        "[ADVANCED] 驱动目录中缺少 {} 的驱动，新系统可能无法启动",
+                        controller.display_name()
+                    );
+                }
             }
 
+            // 导入期间卸载注册表，因为 DISM 可能需要独占访问
             let dism = Dism::new();
             let image_path = format!("{}\\", target_partition);
-            let storage_drivers_path = storage_drivers_dir.to_string_lossy().to_string();
-            match dism.add_drivers_offline(&image_path, &storage_drivers_path) {
-                Ok(_) => log::info!("[ADVANCED] 磁盘控制器驱动导入成功"),
-                Err(e) => log::warn!("[ADVANCED] 磁盘控制器驱动导入失败: {}", e),
-            }
-
-            // 重新加载注册表
-            let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-            let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
-            if default_loaded {
-                let _ = OfflineRegistry::load_hive("pc-default", &default_hive);
-            }
+            registry.while_unloaded(|| {
+                for dir in &pack_dirs {
+                    log::info!("[ADVANCED] 导入磁盘控制器驱动: {}", dir.display());
+                    let storage_drivers_path = dir.to_string_lossy().to_string();
+                    match dism.add_drivers_offline(&image_path, &storage_drivers_path) {
+                        Ok(_) => log::info!("[ADVANCED] 磁盘控制器驱动导入成功"),
+                        Err(e) => log::warn!("[ADVANCED] 磁盘控制器驱动导入失败: {}", e),
+                    }
+                }
+            });
         } else {
             log::warn!(
                 "[ADVANCED] 未找到磁盘控制器驱动目录: {}",
@@ -247,6 +282,15 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         }
     }
 
+    // 10.5 首次启动禁用驱动签名强制（需在修复引导后执行）
+    if config.disable_driver_signature_once {
+        log::info!("[ADVANCED] 设置首次启动禁用驱动签名强制");
+        match crate::core::bcdedit::BootManager::new().disable_integrity_checks_once(target_partition) {
+            Ok(_) => log::info!("[ADVANCED] 驱动签名强制将在首次启动后恢复"),
+            Err(e) => log::warn!("[ADVANCED] 设置驱动签名强制失败: {}", e),
+        }
+    }
+
     // 11. 自定义用户名 - 写入标记文件供无人值守使用
     if !config.custom_username.is_empty() {
         log::info!("[ADVANCED] 设置自定义用户名: {}", config.custom_username);
@@ -254,6 +298,19 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         std::fs::write(&username_file, &config.custom_username)?;
     }
 
+    // 11.5 磁盘写入缓存与 TRIM 策略
+    if config.ensure_trim {
+        log::info!("[ADVANCED] 启用 TRIM");
+        let _ = disk_policy::enable_trim_offline(&registry);
+    }
+    if config.ensure_trim || config.write_cache_policy != WriteCachePolicy::Unchanged {
+        log::info!("[ADVANCED] 写入缓存策略: {}", config.write_cache_policy.label());
+        let script = disk_policy::generate_script(config.write_cache_policy, config.ensure_trim);
+        if let Err(e) = disk_policy::install_script(target_partition, &script) {
+            log::warn!("[ADVANCED] 安装磁盘策略脚本失败: {}", e);
+        }
+    }
+
     // ============ Win7 专用选项 ============
 
     // 12. Win7 注入 USB3 驱动
@@ -262,19 +319,13 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         let usb3_dir = path::get_exe_dir().join("drivers").join("usb3");
         
         if usb3_dir.is_dir() {
-            // 先卸载注册表
-            let _ = OfflineRegistry::unload_hive("pc-soft");
-            let _ = OfflineRegistry::unload_hive("pc-sys");
-            if default_loaded {
-                let _ = OfflineRegistry::unload_hive("pc-default");
-            }
-            
             // 处理驱动（包括解压.cab文件）
             match prepare_win7_drivers(&usb3_dir) {
                 Ok(processed_path) => {
+                    // 注入期间卸载注册表，因为 DISM 可能需要独占访问
                     let dism = Dism::new();
                     let image_path = format!("{}\\", target_partition);
-                    match dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy()) {
+                    match registry.while_unloaded(|| dism.add_drivers_offline(&image_path, &processed_path.to_string_lossy())) {
                         Ok(_) => log::info!("[ADVANCED] Win7 USB3驱动注入成功"),
                         Err(e) => log::warn!("[ADVANCED] Win7 USB3驱动注入失败: {} (继续执行)", e),
                     }
@@ -286,13 +337,6 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
                 }
                 Err(e) => log::warn!("[ADVANCED] Win7 USB3驱动准备失败: {}", e),
             }
-            
-            // 重新加载注册表
-            let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-            let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
-            if default_loaded {
-                let _ = OfflineRegistry::load_hive("pc-default", &default_hive);
-            }
         } else {
             log::warn!("[ADVANCED] Win7 USB3驱动目录不存在: {}", usb3_dir.display());
         }
@@ -304,25 +348,11 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         let nvme_dir = path::get_exe_dir().join("drivers").join("nvme");
         
         if nvme_dir.is_dir() {
-            // 先卸载注册表
-            let _ = OfflineRegistry::unload_hive("pc-soft");
-            let _ = OfflineRegistry::unload_hive("pc-sys");
-            if default_loaded {
-                let _ = OfflineRegistry::unload_hive("pc-default");
-            }
-            
-            // 使用新的处理函数
-            match install_win7_nvme_drivers(&nvme_dir, target_partition) {
+            // 注入期间卸载注册表，因为 DISM 可能需要独占访问
+            match registry.while_unloaded(|| install_win7_nvme_drivers(&nvme_dir, target_partition)) {
                 Ok(_) => log::info!("[ADVANCED] Win7 NVMe驱动注入成功"),
                 Err(e) => log::warn!("[ADVANCED] Win7 NVMe驱动注入失败: {} (继续执行)", e),
             }
-            
-            // 重新加载注册表
-            let _ = OfflineRegistry::load_hive("pc-soft", &software_hive);
-            let _ = OfflineRegistry::load_hive("pc-sys", &system_hive);
-            if default_loaded {
-                let _ = OfflineRegistry::load_hive("pc-default", &default_hive);
-            }
         } else {
             log::warn!("[ADVANCED] Win7 NVMe驱动目录不存在: {}", nvme_dir.display());
         }
@@ -333,39 +363,45 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         log::info!("[ADVANCED] Win7: 修复ACPI蓝屏问题");
         
         // 禁用 intelppm 服务 (Intel 电源管理)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\intelppm",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\intelppm",
             "Start",
             4, // 4 = Disabled
         );
         
         // 禁用 amdppm 服务 (AMD 电源管理)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\amdppm",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\amdppm",
             "Start",
             4,
         );
         
         // 禁用 Processor 服务
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\Processor",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\Processor",
             "Start",
             4,
         );
         
         // 同时设置 ControlSet002 (如果存在)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\intelppm",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\intelppm",
             "Start",
             4,
         );
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\amdppm",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\amdppm",
             "Start",
             4,
         );
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\Processor",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\Processor",
             "Start",
             4,
         );
@@ -379,188 +415,216 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         
         // ========== AHCI 相关驱动 ==========
         // msahci - Microsoft AHCI 驱动 (Win7原版自带但默认禁用)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\msahci",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\msahci",
             "Start",
             0, // 0 = Boot
         );
         
         // iaStorV - Intel 存储驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\iaStorV",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\iaStorV",
             "Start",
             0,
         );
         
         // iaStorAV - Intel AHCI 驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\iaStorAV",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\iaStorAV",
             "Start",
             0,
         );
         
         // iaStor - Intel SATA 驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\iaStor",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\iaStor",
             "Start",
             0,
         );
         
         // iaStorA - Intel AHCI Controller
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\iaStorA",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\iaStorA",
             "Start",
             0,
         );
         
         // ========== AMD/ATI 存储驱动 ==========
         // amd_sata - AMD SATA 驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\amd_sata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\amd_sata",
             "Start",
             0,
         );
         
         // amd_xata - AMD XATA 驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\amd_xata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\amd_xata",
             "Start",
             0,
         );
         
         // amdsata - AMD SATA 驱动 (另一个版本)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\amdsata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\amdsata",
             "Start",
             0,
         );
         
         // amdxata - AMD XATA 驱动 (另一个版本)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\amdxata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\amdxata",
             "Start",
             0,
         );
         
         // ========== NVMe 驱动 ==========
         // stornvme - Microsoft NVMe 驱动 (Win8+)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\stornvme",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\stornvme",
             "Start",
             0,
         );
         
         // ========== 标准 Windows 存储驱动 ==========
         // storahci - 标准 AHCI 驱动 (Win8+)
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\storahci",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\storahci",
             "Start",
             0,
         );
         
         // pciide - PCI IDE 控制器
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\pciide",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\pciide",
             "Start",
             0,
         );
         
         // intelide - Intel IDE 控制器
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\intelide",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\intelide",
             "Start",
             0,
         );
         
         // atapi - ATAPI 驱动
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet001\\Services\\atapi",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet001\\Services\\atapi",
             "Start",
             0,
         );
         
         // ========== 同时设置 ControlSet002 ==========
         // msahci
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\msahci",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\msahci",
             "Start",
             0,
         );
         // iaStorV
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\iaStorV",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\iaStorV",
             "Start",
             0,
         );
         // iaStorAV
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\iaStorAV",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\iaStorAV",
             "Start",
             0,
         );
         // iaStor
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\iaStor",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\iaStor",
             "Start",
             0,
         );
         // iaStorA
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\iaStorA",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\iaStorA",
             "Start",
             0,
         );
         // amd_sata
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\amd_sata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\amd_sata",
             "Start",
             0,
         );
         // amd_xata
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\amd_xata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\amd_xata",
             "Start",
             0,
         );
         // amdsata
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\amdsata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\amdsata",
             "Start",
             0,
         );
         // amdxata
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\amdxata",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\amdxata",
             "Start",
             0,
         );
         // stornvme
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\stornvme",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\stornvme",
             "Start",
             0,
         );
         // storahci
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\storahci",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\storahci",
             "Start",
             0,
         );
         // pciide
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\pciide",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\pciide",
             "Start",
             0,
         );
         // intelide
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\intelide",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\intelide",
             "Start",
             0,
         );
         // atapi
-        let _ = OfflineRegistry::set_dword(
-            "HKLM\\pc-sys\\ControlSet002\\Services\\atapi",
+        let _ = registry.set_dword(
+            OfflineHive::System,
+            "ControlSet002\\Services\\atapi",
             "Start",
             0,
         );
@@ -568,14 +632,8 @@ pub fn apply_advanced_options(target_partition: &str, config: &InstallConfig) ->
         log::info!("[ADVANCED] Win7 存储控制器蓝屏修复设置完成");
     }
 
-    // 卸载注册表（确保正确卸载）
     log::info!("[ADVANCED] 卸载离线注册表...");
-    std::thread::sleep(std::time::Duration::from_millis(500));
-    let _ = OfflineRegistry::unload_hive("pc-soft");
-    let _ = OfflineRegistry::unload_hive("pc-sys");
-    if default_loaded {
-        let _ = OfflineRegistry::unload_hive("pc-default");
-    }
+    drop(registry);
 
     log::info!("[ADVANCED] 高级选项应用完成");
     Ok(())
@@ -968,19 +1026,13 @@ fn copy_driver_files_recursive(
 
 /// 注册NVMe驱动服务到离线注册表
 fn register_nvme_driver_services(target_partition: &str) -> anyhow::Result<()> {
-    let system_hive = format!("{}\\Windows\\System32\\config\\SYSTEM", target_partition);
-    
-    if !std::path::Path::new(&system_hive).exists() {
-        log::warn!("[NVME] SYSTEM hive不存在，跳过服务注册");
-        return Ok(());
-    }
-    
-    let hive_key = format!("nvme_drv_{}", std::process::id());
-    
-    if OfflineRegistry::load_hive(&hive_key, &system_hive).is_err() {
-        log::warn!("[NVME] 无法加载SYSTEM hive，跳过服务注册");
-        return Ok(());
-    }
+    let registry = match OfflineRegistrySession::open(target_partition, &[OfflineHive::System]) {
+        Ok(registry) => registry,
+        Err(e) => {
+            log::warn!("[NVME] {:#}，跳过服务注册", e);
+            return Ok(());
+        }
+    };
     
     // 注册stornvme服务（NVMe标准驱动）
     let services = [
@@ -990,33 +1042,23 @@ fn register_nvme_driver_services(target_partition: &str) -> anyhow::Result<()> {
     ];
     
     for (service_name, binary, service_type, start_type) in &services {
-        let key_path = format!("HKLM\\{}\\ControlSet001\\Services\\{}", hive_key, service_name);
-        
-        let _ = OfflineRegistry::create_key(&key_path);
-        let _ = OfflineRegistry::set_dword(&key_path, "Type", *service_type);
-        let _ = OfflineRegistry::set_dword(&key_path, "Start", *start_type);
-        let _ = OfflineRegistry::set_dword(&key_path, "ErrorControl", 1);
-        let _ = OfflineRegistry::set_expand_string(
-            &key_path, 
-            "ImagePath", 
-            &format!("System32\\drivers\\{}", binary)
-        );
-        
         // 同时设置ControlSet002
-        let key_path2 = format!("HKLM\\{}\\ControlSet002\\Services\\{}", hive_key, service_name);
-        let _ = OfflineRegistry::create_key(&key_path2);
-        let _ = OfflineRegistry::set_dword(&key_path2, "Type", *service_type);
-        let _ = OfflineRegistry::set_dword(&key_path2, "Start", *start_type);
-        let _ = OfflineRegistry::set_dword(&key_path2, "ErrorControl", 1);
-        let _ = OfflineRegistry::set_expand_string(
-            &key_path2, 
-            "ImagePath", 
-            &format!("System32\\drivers\\{}", binary)
-        );
+        for control_set in ["ControlSet001", "ControlSet002"] {
+            let key_path = format!("{}\\Services\\{}", control_set, service_name);
+            let _ = registry.create_key(OfflineHive::System, &key_path);
+            let _ = registry.set_dword(OfflineHive::System, &key_path, "Type", *service_type);
+            let _ = registry.set_dword(OfflineHive::System, &key_path, "Start", *start_type);
+            let _ = registry.set_dword(OfflineHive::System, &key_path, "ErrorControl", 1);
+            let _ = registry.set_expand_string(
+                OfflineHive::System,
+                &key_path,
+                "ImagePath",
+                &format!("System32\\drivers\\{}", binary),
+            );
+        }
     }
     
-    let _ = OfflineRegistry::unload_hive(&hive_key);
-    
+    drop(registry);
     log::info!("[NVME] NVMe服务注册完成");
     Ok(())
 }
@@ -1159,8 +1201,9 @@ fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// 生成删除预装UWP应用的PowerShell脚本
-fn generate_remove_uwp_script() -> String {
+/// 生成删除预装UWP应用的PowerShell脚本（提示文字使用目标系统语言）
+fn generate_remove_uwp_script(language: &TargetLanguage) -> String {
+    let (removing, done) = language.uwp_script_messages();
     r#"# LetRecovery - 删除预装UWP应用脚本
 # 此脚本会删除大部分预装的UWP应用，保留必要的系统组件
 
@@ -1210,13 +1253,15 @@ $AppsToRemove = @(
 )
 
 foreach ($App in $AppsToRemove) {
-    Write-Host "正在删除: $App"
+    Write-Host "{{REMOVING}}: $App"
     Get-AppxPackage -Name $App -AllUsers | Remove-AppxPackage -AllUsers -ErrorAction SilentlyContinue
     Get-AppxProvisionedPackage -Online | Where-Object {$_.PackageName -like "*$App*"} | Remove-AppxProvisionedPackage -Online -ErrorAction SilentlyContinue
 }
 
-Write-Host "UWP应用清理完成"
-"#.to_string()
+Write-Host "{{DONE}}"
+"#
+    .replace("{{REMOVING}}", removing)
+    .replace("{{DONE}}", done)
 }
 
 /// 获取脚本目录名称
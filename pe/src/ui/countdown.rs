@@ -0,0 +1,221 @@
+//! 倒计时自动操作
+//!
+//! 完成后的重启/关机、失败重试、结果提示等操作在倒计时结束后自动执行，
+//! 用户可以按 Esc / 点击「取消」中止，或按 Enter / 点击按钮立即执行。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use egui::{Color32, RichText};
+
+use crate::core::power::CompletionAction;
+
+/// 安装/备份完成后自动重启（关机）的等待时间
+pub const REBOOT_COUNTDOWN: Duration = Duration::from_secs(15);
+
+/// 失败后自动重试的等待时间
+pub const RETRY_COUNTDOWN: Duration = Duration::from_secs(30);
+
+/// 提示框自动关闭的等待时间
+pub const DIALOG_COUNTDOWN: Duration = Duration::from_secs(30);
+
+/// 倒计时结束后执行的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountdownAction {
+    /// 执行完成后的电源操作
+    Finish(CompletionAction),
+    /// 重新执行失败的流程
+    Retry,
+    /// 关闭提示
+    Close,
+}
+
+impl CountdownAction {
+    /// 按钮文字
+    pub fn label(&self) -> &'static str {
+        match self {
+            CountdownAction::Finish(CompletionAction::Shutdown) => "立即关机",
+            CountdownAction::Finish(CompletionAction::FirmwareSetup) => "立即进入固件设置",
+            CountdownAction::Finish(_) => "立即重启",
+            CountdownAction::Retry => "立即重试",
+            CountdownAction::Close => "确定",
+        }
+    }
+
+    /// 倒计时提示文字
+    fn pending_text(&self, seconds: u64) -> String {
+        match self {
+            CountdownAction::Finish(action) => format!("{} 秒后自动{}", seconds, action.verb()),
+            CountdownAction::Retry => format!("{} 秒后自动重试", seconds),
+            CountdownAction::Close => format!("{} 秒后自动关闭", seconds),
+        }
+    }
+}
+
+/// 倒计时状态
+#[derive(Debug, Clone)]
+pub struct Countdown {
+    pub action: CountdownAction,
+    started: Instant,
+    duration: Duration,
+    /// 用户已取消自动执行
+    cancelled: bool,
+}
+
+impl Countdown {
+    pub fn new(action: CountdownAction, duration: Duration) -> Self {
+        Self {
+            action,
+            started: Instant::now(),
+            duration,
+            cancelled: false,
+        }
+    }
+
+    /// 剩余秒数（向上取整）
+    pub fn remaining_secs(&self) -> u64 {
+        let remaining = self.duration.saturating_sub(self.started.elapsed());
+        remaining.as_millis().div_ceil(1000) as u64
+    }
+
+    /// 是否已到时间且未被取消
+    pub fn is_expired(&self) -> bool {
+        !self.cancelled && self.started.elapsed() >= self.duration
+    }
+
+    /// 取消自动执行
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    /// 绘制倒计时，返回 true 表示应立即执行操作
+    pub fn show(&mut self, ui: &mut egui::Ui) -> bool {
+        let (enter, escape) = ui.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.cancel();
+        }
+
+        let mut execute = enter || self.is_expired();
+
+        ui.vertical_centered(|ui| {
+            if !self.cancelled {
+                let remaining = self.remaining_secs();
+                ui.label(
+                    RichText::new(self.action.pending_text(remaining))
+                        .size(15.0)
+                        .color(Color32::from_rgb(255, 180, 50)),
+                );
+                let fraction = remaining as f32 / self.duration.as_secs().max(1) as f32;
+                ui.add(egui::ProgressBar::new(fraction).desired_width(300.0));
+                ui.label(
+                    RichText::new("按 Esc 取消，按 Enter 立即执行")
+                        .size(12.0)
+                        .color(Color32::GRAY),
+                );
+            }
+
+            ui.horizontal(|ui| {
+                let button_size = super::touch::button_size(100.0, 32.0);
+                let buttons_width = if self.cancelled { button_size[0] } else { button_size[0] * 2.0 + 10.0 };
+                ui.add_space(((ui.available_width() - buttons_width) / 2.0).max(0.0));
+                if ui
+                    .add_sized(button_size, egui::Button::new(self.action.label()))
+                    .clicked()
+                {
+                    execute = true;
+                }
+                if !self.cancelled
+                    && ui.add_sized(button_size, egui::Button::new("取消")).clicked()
+                {
+                    self.cancel();
+                }
+            });
+        });
+
+        // 倒计时期间持续刷新
+        ui.ctx().request_repaint_after(Duration::from_millis(200));
+        execute
+    }
+}
+
+/// 独立的倒计时提示窗口（命令行模式使用，替代阻塞的消息框）
+struct CountdownDialog {
+    message: String,
+    is_error: bool,
+    countdown: Countdown,
+    /// 用户确认执行（或倒计时结束）
+    confirmed: Arc<AtomicBool>,
+}
+
+impl eframe::App for CountdownDialog {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.add_space(20.0);
+            ui.vertical_centered(|ui| {
+                let color = if self.is_error {
+                    Color32::from_rgb(255, 100, 100)
+                } else {
+                    Color32::from_rgb(100, 255, 100)
+                };
+                ui.label(RichText::new(&self.message).size(15.0).color(color));
+            });
+            ui.add_space(20.0);
+
+            if self.countdown.show(ui) {
+                self.confirmed.store(true, Ordering::SeqCst);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+        });
+    }
+}
+
+/// 显示带倒计时的提示窗口，返回是否执行操作（倒计时结束或用户确认）
+///
+/// 用户取消后窗口保留，关闭窗口视为不执行
+pub fn run_countdown_dialog(
+    title: &str,
+    message: &str,
+    is_error: bool,
+    action: CountdownAction,
+    duration: Duration,
+) -> bool {
+    let confirmed = Arc::new(AtomicBool::new(false));
+    let dialog = CountdownDialog {
+        message: message.to_string(),
+        is_error,
+        countdown: Countdown::new(action, duration),
+        confirmed: confirmed.clone(),
+    };
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size(crate::utils::dpi::scaled_size([460.0, 260.0]))
+            .with_resizable(false)
+            .with_maximize_button(false)
+            .with_minimize_button(false)
+            .with_always_on_top(),
+        ..Default::default()
+    };
+
+    let result = eframe::run_native(
+        title,
+        options,
+        Box::new(|cc| {
+            crate::app::App::setup_fonts(&cc.egui_ctx);
+            super::touch::apply_style(&cc.egui_ctx);
+            cc.egui_ctx.set_zoom_factor(crate::utils::dpi::ui_zoom());
+            Ok(Box::new(dialog))
+        }),
+    );
+    if let Err(e) = result {
+        log::error!("显示倒计时窗口失败: {}", e);
+    }
+
+    confirmed.load(Ordering::SeqCst)
+}
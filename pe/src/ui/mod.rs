@@ -1,2 +1,6 @@
+pub mod countdown;
 pub mod progress;
+#[path = "../../../shared/touch.rs"]
+#[allow(dead_code)]
+pub mod touch;
 pub mod advanced_options;
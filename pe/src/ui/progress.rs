@@ -121,6 +121,28 @@ pub enum StepStatus {
     Failed,
 }
 
+impl StepStatus {
+    /// 状态图标
+    fn icon(&self) -> &'static str {
+        match self {
+            StepStatus::Pending => "○",
+            StepStatus::InProgress => "▶",
+            StepStatus::Completed => "✔",
+            StepStatus::Failed => "✖",
+        }
+    }
+
+    /// 状态颜色
+    fn color(&self) -> Color32 {
+        match self {
+            StepStatus::Pending => Color32::from_rgb(128, 128, 128),
+            StepStatus::InProgress => Color32::from_rgb(255, 180, 50),
+            StepStatus::Completed => Color32::from_rgb(100, 255, 100),
+            StepStatus::Failed => Color32::from_rgb(255, 100, 100),
+        }
+    }
+}
+
 /// 进度状态
 #[derive(Debug, Clone)]
 pub struct ProgressState {
@@ -238,7 +260,7 @@ impl ProgressUI {
     /// 绘制进度界面
     pub fn show(ui: &mut egui::Ui, state: &ProgressState) {
         ui.vertical_centered(|ui| {
-            ui.add_space(20.0);
+            ui.add_space(15.0);
 
             // 标题
             let title = if state.is_install_mode {
@@ -248,63 +270,37 @@ impl ProgressUI {
             };
             ui.heading(RichText::new(title).size(24.0).strong());
 
-            ui.add_space(30.0);
+            ui.add_space(15.0);
 
-            // 当前步骤
-            let current_step_name = if state.is_install_mode {
-                state.current_install_step.name()
-            } else {
-                state.current_backup_step.name()
-            };
-            ui.label(
-                RichText::new(format!("当前步骤: [{}]", current_step_name))
-                    .size(16.0)
-                    .color(Color32::from_rgb(100, 180, 255)),
+            // 总体进度条
+            let progress = state.overall_progress as f32 / 100.0;
+            ui.add(
+                egui::ProgressBar::new(progress)
+                    .desired_width(460.0)
+                    .show_percentage(),
             );
 
-            ui.add_space(20.0);
-
-            // 步骤进度条
-            ui.horizontal(|ui| {
-                ui.label("步骤进度:");
-                let progress = state.step_progress as f32 / 100.0;
-                ui.add(
-                    egui::ProgressBar::new(progress)
-                        .desired_width(400.0)
-                        .show_percentage(),
-                );
-            });
-
             ui.add_space(10.0);
+            ui.separator();
+        });
 
-            // 总体进度条
-            ui.horizontal(|ui| {
-                ui.label("总体进度:");
-                let progress = state.overall_progress as f32 / 100.0;
-                ui.add(
-                    egui::ProgressBar::new(progress)
-                        .desired_width(400.0)
-                        .show_percentage(),
-                );
+        // 步骤清单
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, true])
+            .max_height(ui.available_height() - 90.0)
+            .show(ui, |ui| {
+                if state.is_install_mode {
+                    Self::show_install_steps(ui, state);
+                } else {
+                    Self::show_backup_steps(ui, state);
+                }
             });
 
-            ui.add_space(30.0);
-
-            // 分隔线
-            ui.separator();
-
-            ui.add_space(20.0);
-
-            // 步骤列表
-            if state.is_install_mode {
-                Self::show_install_steps(ui, state);
-            } else {
-                Self::show_backup_steps(ui, state);
-            }
+        ui.separator();
 
+        ui.vertical_centered(|ui| {
             // 状态消息
-            if !state.status_message.is_empty() {
-                ui.add_space(20.0);
+            if !state.status_message.is_empty() && !state.is_failed {
                 ui.label(
                     RichText::new(&state.status_message)
                         .size(14.0)
@@ -312,51 +308,49 @@ impl ProgressUI {
                 );
             }
 
-            // 错误信息
+            // 错误信息（摘要 + 可展开的详情）
             if let Some(ref error) = state.error_message {
-                ui.add_space(20.0);
-                ui.label(
-                    RichText::new(format!("错误: {}", error))
-                        .size(14.0)
-                        .color(Color32::from_rgb(255, 100, 100)),
-                );
+                Self::show_error(ui, error);
             }
 
             // 完成提示
             if state.is_completed {
-                ui.add_space(30.0);
+                ui.add_space(10.0);
                 let message = if state.is_install_mode {
-                    "系统安装完成！即将重启..."
+                    "系统安装完成！"
                 } else {
-                    "系统备份完成！即将重启..."
+                    "系统备份完成！"
                 };
                 ui.label(
                     RichText::new(message)
                         .size(18.0)
-                        .color(Color32::from_rgb(100, 255, 100))
+                        .color(StepStatus::Completed.color())
                         .strong(),
                 );
             }
         });
     }
 
+    /// 计算步骤状态
+    fn step_status(state: &ProgressState, idx: usize, current_idx: usize) -> StepStatus {
+        if state.is_failed && idx == current_idx {
+            StepStatus::Failed
+        } else if idx < current_idx || (idx == current_idx && (state.step_progress == 100 || state.is_completed)) {
+            StepStatus::Completed
+        } else if idx == current_idx && !state.is_failed {
+            StepStatus::InProgress
+        } else {
+            StepStatus::Pending
+        }
+    }
+
     /// 显示安装步骤列表
     fn show_install_steps(ui: &mut egui::Ui, state: &ProgressState) {
         let current_idx = state.current_install_step.index();
 
         for step in InstallStep::all() {
-            let idx = step.index();
-            let status = if state.is_failed && idx == current_idx {
-                StepStatus::Failed
-            } else if idx < current_idx || (idx == current_idx && state.step_progress == 100) {
-                StepStatus::Completed
-            } else if idx == current_idx {
-                StepStatus::InProgress
-            } else {
-                StepStatus::Pending
-            };
-
-            Self::show_step_item(ui, step.name(), status);
+            let status = Self::step_status(state, step.index(), current_idx);
+            Self::show_step_item(ui, step.name(), status, state.step_progress);
         }
     }
 
@@ -365,37 +359,63 @@ impl ProgressUI {
         let current_idx = state.current_backup_step.index();
 
         for step in BackupStep::all() {
-            let idx = step.index();
-            let status = if state.is_failed && idx == current_idx {
-                StepStatus::Failed
-            } else if idx < current_idx || (idx == current_idx && state.step_progress == 100) {
-                StepStatus::Completed
-            } else if idx == current_idx {
-                StepStatus::InProgress
-            } else {
-                StepStatus::Pending
-            };
-
-            Self::show_step_item(ui, step.name(), status);
+            let status = Self::step_status(state, step.index(), current_idx);
+            Self::show_step_item(ui, step.name(), status, state.step_progress);
         }
     }
 
     /// 显示单个步骤项
-    fn show_step_item(ui: &mut egui::Ui, name: &str, status: StepStatus) {
+    fn show_step_item(ui: &mut egui::Ui, name: &str, status: StepStatus, step_progress: u8) {
         ui.horizontal(|ui| {
-            ui.add_space(50.0);
+            ui.add_space(60.0);
 
-            let (icon, color) = match status {
-                StepStatus::Completed => ("OK", Color32::from_rgb(100, 255, 100)),
-                StepStatus::InProgress => (">>", Color32::from_rgb(255, 180, 50)),
-                StepStatus::Pending => ("  ", Color32::from_rgb(128, 128, 128)),
-                StepStatus::Failed => ("!!", Color32::from_rgb(255, 100, 100)),
-            };
+            let color = status.color();
+            if status == StepStatus::InProgress {
+                ui.add(egui::Spinner::new().size(16.0).color(color));
+            } else {
+                ui.label(RichText::new(status.icon()).size(16.0).color(color));
+            }
+            ui.add_space(8.0);
 
-            ui.label(RichText::new(icon).size(14.0).color(color).monospace());
-            ui.add_space(10.0);
-            ui.label(RichText::new(name).size(14.0).color(color));
+            let text = RichText::new(name).size(15.0).color(color);
+            ui.label(if status == StepStatus::InProgress { text.strong() } else { text });
+
+            // 正在执行的步骤显示自身进度
+            if status == StepStatus::InProgress && step_progress > 0 {
+                ui.add_space(10.0);
+                ui.add(
+                    egui::ProgressBar::new(step_progress as f32 / 100.0)
+                        .desired_width(160.0)
+                        .show_percentage(),
+                );
+            }
         });
-        ui.add_space(5.0);
+        ui.add_space(4.0);
+    }
+
+    /// 显示错误摘要和可展开的完整错误信息
+    fn show_error(ui: &mut egui::Ui, error: &str) {
+        let summary = error.lines().next().unwrap_or(error);
+        ui.label(
+            RichText::new(format!("错误: {}", summary))
+                .size(14.0)
+                .color(StepStatus::Failed.color()),
+        );
+
+        egui::CollapsingHeader::new("错误详情")
+            .id_salt("pe_error_detail")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(120.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::Label::new(RichText::new(error).monospace().size(12.0))
+                                .wrap(),
+                        );
+                    });
+                if ui.button("复制错误信息").clicked() {
+                    ui.ctx().copy_text(error.to_string());
+                }
+            });
     }
 }
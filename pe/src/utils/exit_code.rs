@@ -0,0 +1,44 @@
+//! 进程退出码
+//!
+//! 命令行模式（`--pe-install`、`--pe-backup`、`--auto` 以及 `utils::cli` 的子命令）以退出码报告结果，
+//! 供部署脚本判断失败原因。数值一经发布不可修改，新增原因只能追加新值。
+
+/// 退出码（与桌面端保持一致，部分取值仅桌面端使用）
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExitCode {
+    /// 成功
+    Success = 0,
+    /// 其他未归类的失败
+    Failed = 1,
+    /// 未找到配置文件或配置无法读取
+    ConfigMissing = 2,
+    /// 镜像不存在、无法识别或已损坏
+    ImageInvalid = 3,
+    /// 格式化目标分区失败
+    FormatFailed = 4,
+    /// 释放镜像失败
+    ApplyFailed = 5,
+    /// 修复引导失败
+    BootRepairFailed = 6,
+    /// 操作被取消
+    Cancelled = 7,
+    /// 捕获备份镜像失败
+    BackupFailed = 8,
+    /// 命令行参数错误
+    InvalidArguments = 9,
+}
+
+impl ExitCode {
+    /// 退出码数值
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+
+    /// 以此退出码结束进程
+    pub fn exit(self) -> ! {
+        log::info!("进程退出，退出码: {} ({:?})", self.code(), self);
+        std::process::exit(self.code())
+    }
+}
@@ -0,0 +1,174 @@
+//! PE日志模块
+//!
+//! 日志写入 `{程序目录}/logs` 下的日志文件（程序目录不可写时改用临时目录），
+//! 每行带时间戳、级别、会话 ID 和来源模块；单个文件超过大小上限时轮转，
+//! 供看门狗在故障时收集。命令行输出由 `cli_println!` 负责，
+//! 因此仅 debug 构建同时输出到标准错误。
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::path::get_exe_dir;
+
+/// 日志文件名前缀
+const LOG_FILE_PREFIX: &str = "LetRecoveryPE";
+
+/// 单个日志文件的大小上限，超过后轮转到新文件
+const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// 日志目录中最多保留的日志文件数
+const MAX_LOG_FILES: usize = 10;
+
+/// 本次运行的会话 ID
+static SESSION_ID: OnceLock<String> = OnceLock::new();
+
+/// 当前写入的日志文件
+static CURRENT_LOG_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// 本次运行的会话 ID（8 位十六进制），写入每行日志
+pub fn session_id() -> &'static str {
+    SESSION_ID.get_or_init(|| {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        format!("{:08X}", nanos ^ std::process::id().rotate_left(16))
+    })
+}
+
+/// 当前写入的日志文件路径
+pub fn current_log_file() -> Option<PathBuf> {
+    CURRENT_LOG_FILE.lock().ok().and_then(|current| current.clone())
+}
+
+/// 删除最旧的日志文件，只保留最近的若干个
+fn prune_log_files(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut files: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map(|ext| ext == "log").unwrap_or(false))
+        .filter_map(|p| {
+            let modified = p.metadata().and_then(|m| m.modified()).ok()?;
+            Some((p, modified))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    for (path, _) in files.into_iter().skip(MAX_LOG_FILES) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// 按大小轮转的日志文件
+struct RotatingWriter {
+    dir: PathBuf,
+    base: String,
+    part: u32,
+    written: u64,
+    file: Option<File>,
+}
+
+impl RotatingWriter {
+    fn path(&self) -> PathBuf {
+        if self.part == 0 {
+            self.dir.join(format!("{}.log", self.base))
+        } else {
+            self.dir.join(format!("{}.{}.log", self.base, self.part))
+        }
+    }
+
+    /// 打开当前序号的日志文件并记录为当前日志
+    fn open_file(&mut self) {
+        let path = self.path();
+        self.file = File::create(&path).ok();
+        if self.file.is_some() {
+            if let Ok(mut current) = CURRENT_LOG_FILE.lock() {
+                *current = Some(path);
+            }
+        }
+    }
+
+    fn rotate(&mut self) {
+        self.part += 1;
+        self.written = 0;
+        self.open_file();
+        prune_log_files(&self.dir);
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(debug_assertions)]
+        let _ = io::stderr().write_all(buf);
+        // env_logger 每条记录整体写入一次，轮转不会截断记录
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_FILE_SIZE {
+            self.rotate();
+        }
+        if let Some(ref mut file) = self.file {
+            if file.write_all(buf).is_ok() {
+                self.written += buf.len() as u64;
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        #[cfg(debug_assertions)]
+        let _ = io::stderr().flush();
+        if let Some(ref mut file) = self.file {
+            let _ = file.flush();
+        }
+        Ok(())
+    }
+}
+
+/// 日志目录：优先程序目录（可能位于只读介质），不可写时改用临时目录
+fn log_dir() -> PathBuf {
+    let preferred = get_exe_dir().join("logs");
+    if std::fs::create_dir_all(&preferred).is_ok() {
+        return preferred;
+    }
+    let fallback = std::env::temp_dir().join("LetRecovery").join("logs");
+    let _ = std::fs::create_dir_all(&fallback);
+    fallback
+}
+
+/// 初始化日志
+pub fn init() {
+    let dir = log_dir();
+    let base = format!("{}_{}_{}", LOG_FILE_PREFIX, std::process::id(), session_id());
+    let mut writer = RotatingWriter {
+        dir,
+        base,
+        part: 0,
+        written: 0,
+        file: None,
+    };
+    writer.open_file();
+    prune_log_files(&writer.dir);
+
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|buf, record| {
+            writeln!(
+                buf,
+                "{} {:<5} [{}] [{}] {}",
+                buf.timestamp_millis(),
+                record.level(),
+                session_id(),
+                record.target(),
+                record.args()
+            )
+        })
+        .target(env_logger::Target::Pipe(Box::new(writer)))
+        .init();
+
+    match current_log_file() {
+        Some(path) => log::info!("日志会话 ID: {}，日志文件: {}", session_id(), path.display()),
+        None => log::warn!("无法创建日志文件"),
+    }
+}
@@ -1,6 +1,17 @@
+#[path = "../../../shared/cli.rs"]
+#[allow(dead_code)]
+pub mod cli;
 pub mod cmd;
 pub mod command;
+#[path = "../../../shared/dpi.rs"]
+#[allow(dead_code)]
+pub mod dpi;
 pub mod encoding;
+pub mod exit_code;
+#[path = "../../../shared/json_progress.rs"]
+#[allow(dead_code)]
+pub mod json_progress;
+pub mod logger;
 pub mod path;
 pub mod reboot;
 
@@ -0,0 +1,407 @@
+//! 释放后校验
+//!
+//! 镜像释放完成后按 WIM 中记录的 SHA-1 重新读取目标分区上的文件并比对，
+//! 在重启进入新系统之前发现故障硬盘上的静默写入损坏。
+//! - 抽样：必查启动相关的关键文件，其余文件按固定步长抽取约 3000 个
+//! - 完整：校验镜像中的全部文件
+//!
+//! 读取时绕过系统缓存（Windows 上使用 FILE_FLAG_NO_BUFFERING），读到的是磁盘上的实际数据，
+//! 而不是刚刚写入、仍留在内存中的缓存页。文件哈希通过 wimlib 读取，wimgapi 不提供。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::core::wimlib::{WimFileHash, Wimlib};
+
+/// 抽样模式下除关键文件外抽取的文件数
+const SAMPLE_SIZE: usize = 3000;
+/// 校验线程数
+const VERIFY_WORKERS: usize = 4;
+/// 每次读取的大小（须为扇区大小的整数倍）
+const READ_SIZE: usize = 1024 * 1024;
+/// 无缓冲读取的最小缓冲区对齐（覆盖 512 字节和 4K 扇区），扇区更大的磁盘按扇区大小对齐
+const READ_ALIGN: usize = 4096;
+/// 报告中列出的损坏文件数上限
+const SUMMARY_LIMIT: usize = 5;
+
+/// 关键目录（小写，镜像内路径前缀）
+const CRITICAL_DIRS: [&str; 3] = [
+    "\\windows\\system32\\drivers\\",
+    "\\windows\\system32\\config\\",
+    "\\windows\\boot\\",
+];
+
+/// 关键文件（小写，镜像内完整路径）
+const CRITICAL_FILES: [&str; 5] = [
+    "\\windows\\system32\\ntoskrnl.exe",
+    "\\windows\\system32\\hal.dll",
+    "\\windows\\system32\\winload.exe",
+    "\\windows\\system32\\winload.efi",
+    "\\windows\\system32\\ci.dll",
+];
+
+/// 释放后校验模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApplyVerifyMode {
+    /// 不校验
+    #[default]
+    Off,
+    /// 抽样校验
+    Sampled,
+    /// 完整校验
+    Full,
+}
+
+impl ApplyVerifyMode {
+    pub const ALL: [ApplyVerifyMode; 3] = [ApplyVerifyMode::Off, ApplyVerifyMode::Sampled, ApplyVerifyMode::Full];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApplyVerifyMode::Off => "不校验",
+            ApplyVerifyMode::Sampled => "抽样校验",
+            ApplyVerifyMode::Full => "完整校验",
+        }
+    }
+}
+
+/// 与镜像不一致的文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyVerifyMismatch {
+    /// 镜像内路径
+    pub path: String,
+    /// 不一致的原因
+    pub reason: String,
+}
+
+/// 校验结果
+#[derive(Debug, Clone, Default)]
+pub struct ApplyVerifyReport {
+    /// 已校验的文件数
+    pub checked: usize,
+    /// 与镜像不一致的文件
+    pub mismatches: Vec<ApplyVerifyMismatch>,
+}
+
+impl ApplyVerifyReport {
+    /// 是否全部一致
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// 结果摘要
+    pub fn summary(&self) -> String {
+        if self.is_ok() {
+            return format!("已校验 {} 个文件，全部与镜像一致", self.checked);
+        }
+        let mut listed: Vec<String> = self
+            .mismatches
+            .iter()
+            .take(SUMMARY_LIMIT)
+            .map(|m| format!("{}（{}）", m.path, m.reason))
+            .collect();
+        if self.mismatches.len() > SUMMARY_LIMIT {
+            listed.push(format!("等 {} 个", self.mismatches.len()));
+        }
+        format!(
+            "已校验 {} 个文件，{} 个与镜像不一致，目标磁盘可能存在故障: {}",
+            self.checked,
+            self.mismatches.len(),
+            listed.join("、")
+        )
+    }
+}
+
+/// 是否为启动相关的关键文件
+fn is_critical(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    CRITICAL_FILES.contains(&lower.as_str()) || CRITICAL_DIRS.iter().any(|dir| lower.starts_with(dir))
+}
+
+/// 按校验模式选出要校验的文件
+///
+/// 抽样时按固定步长选取，同一镜像每次选出的文件相同，便于复现。
+fn select_files(files: Vec<WimFileHash>, mode: ApplyVerifyMode) -> Vec<WimFileHash> {
+    match mode {
+        ApplyVerifyMode::Off => Vec::new(),
+        ApplyVerifyMode::Full => files,
+        ApplyVerifyMode::Sampled => {
+            let (mut selected, others): (Vec<_>, Vec<_>) = files.into_iter().partition(|f| is_critical(&f.path));
+            let stride = others.len().div_ceil(SAMPLE_SIZE).max(1);
+            selected.extend(others.into_iter().step_by(stride));
+            selected
+        }
+    }
+}
+
+/// 以绕过系统缓存的方式打开文件
+#[cfg(windows)]
+fn open_uncached(path: &Path) -> std::io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+    const FILE_FLAG_SEQUENTIAL_SCAN: u32 = 0x0800_0000;
+    std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(FILE_FLAG_NO_BUFFERING | FILE_FLAG_SEQUENTIAL_SCAN)
+        .open(path)
+}
+
+#[cfg(not(windows))]
+fn open_uncached(path: &Path) -> std::io::Result<File> {
+    File::open(path)
+}
+
+/// 目标卷的逻辑扇区大小，读取失败时返回 None
+#[cfg(windows)]
+fn volume_sector_size(target_dir: &Path) -> Option<usize> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceW;
+
+    let root = format!("{}\\", target_dir.to_string_lossy().trim_end_matches('\\'));
+    let wide_root: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut bytes_per_sector: u32 = 0;
+    unsafe { GetDiskFreeSpaceW(PCWSTR(wide_root.as_ptr()), None, Some(&mut bytes_per_sector), None, None) }.ok()?;
+    (bytes_per_sector > 0).then_some(bytes_per_sector as usize)
+}
+
+#[cfg(not(windows))]
+fn volume_sector_size(_target_dir: &Path) -> Option<usize> {
+    None
+}
+
+/// 无缓冲读取的缓冲区对齐：不小于卷的扇区大小
+fn read_alignment(sector_size: Option<usize>) -> usize {
+    sector_size.map_or(READ_ALIGN, |size| size.next_power_of_two().clamp(READ_ALIGN, READ_SIZE))
+}
+
+/// 校验线程的读取缓冲区（按扇区大小对齐）
+struct AlignedBuffer {
+    storage: Vec<u8>,
+    offset: usize,
+}
+
+impl AlignedBuffer {
+    fn new(align: usize) -> Self {
+        let storage = vec![0u8; READ_SIZE + align];
+        let offset = storage.as_ptr().align_offset(align);
+        Self { storage, offset }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.storage[self.offset..self.offset + READ_SIZE]
+    }
+}
+
+/// 计算磁盘上文件的 SHA-1
+fn file_sha1(path: &Path, buffer: &mut AlignedBuffer) -> std::io::Result<[u8; 20]> {
+    let mut file = open_uncached(path)?;
+    let buf = buffer.as_mut_slice();
+    let mut hasher = sha1::Sha1::new();
+    loop {
+        let read = file.read(buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    let mut digest = [0u8; 20];
+    digest.copy_from_slice(&hasher.finalize());
+    Ok(digest)
+}
+
+/// 校验单个文件，不一致时返回原因
+fn check_file(target_dir: &Path, entry: &WimFileHash, buffer: &mut AlignedBuffer) -> Option<String> {
+    let path = target_dir.join(entry.path.trim_start_matches(['\\', '/']));
+    let size = match std::fs::metadata(&path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Some("文件不存在".to_string()),
+    };
+    if size != entry.size {
+        return Some(format!("大小 {} 字节，镜像中为 {} 字节", size, entry.size));
+    }
+    // 空文件在 WIM 中没有数据流，哈希为全 0
+    if entry.sha1 == [0u8; 20] {
+        return None;
+    }
+    match file_sha1(&path, buffer) {
+        Ok(sha1) if sha1 == entry.sha1 => None,
+        Ok(_) => Some("内容与镜像不一致".to_string()),
+        Err(e) => Some(format!("读取失败: {}", e)),
+    }
+}
+
+/// 多线程校验文件列表
+///
+/// `on_progress(已校验, 总数)` 在校验线程中调用
+fn verify_files(target_dir: &Path, files: &[WimFileHash], on_progress: &(dyn Fn(usize, usize) + Sync)) -> ApplyVerifyReport {
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let mismatches = Mutex::new(Vec::new());
+    let workers = VERIFY_WORKERS.min(files.len()).max(1);
+    let align = read_alignment(volume_sector_size(target_dir));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut buffer = AlignedBuffer::new(align);
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(entry) = files.get(index) else {
+                        break;
+                    };
+                    if let Some(reason) = check_file(target_dir, entry, &mut buffer) {
+                        log::warn!("[APPLY VERIFY] {} - {}", entry.path, reason);
+                        mismatches.lock().unwrap().push(ApplyVerifyMismatch {
+                            path: entry.path.clone(),
+                            reason,
+                        });
+                    }
+                    on_progress(done.fetch_add(1, Ordering::Relaxed) + 1, files.len());
+                }
+            });
+        }
+    });
+
+    let mut mismatches = mismatches.into_inner().unwrap();
+    mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+    ApplyVerifyReport {
+        checked: files.len(),
+        mismatches,
+    }
+}
+
+/// 按镜像中记录的哈希校验已释放到 `target_dir` 的文件（index 从 1 开始）
+///
+/// 无法读取镜像哈希（wimlib 不可用、GHO 镜像等）时返回错误，调用方可按警告处理；
+/// 文件不一致时返回的报告中 `is_ok()` 为 false。
+pub fn verify_applied_image(
+    image_file: &str,
+    index: u32,
+    target_dir: &str,
+    mode: ApplyVerifyMode,
+    on_progress: &(dyn Fn(usize, usize) + Sync),
+) -> Result<ApplyVerifyReport> {
+    if mode == ApplyVerifyMode::Off {
+        return Ok(ApplyVerifyReport::default());
+    }
+
+    let wimlib = Wimlib::new().map_err(|e| anyhow!("无法加载 wimlib: {}", e))?;
+    if !wimlib.supports_browse() {
+        anyhow::bail!("当前 wimlib 不支持读取镜像文件哈希");
+    }
+    let files = wimlib
+        .open_image_file(image_file)
+        .and_then(|wim| wim.file_hashes(index as i32))
+        .map_err(|e| anyhow!("读取镜像文件哈希失败: {}", e.detailed()))?;
+
+    let selected = select_files(files, mode);
+    log::info!("[APPLY VERIFY] {}：校验 {} 个文件", mode.label(), selected.len());
+    let report = verify_files(Path::new(target_dir), &selected, on_progress);
+    log::info!("[APPLY VERIFY] {}", report.summary());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> WimFileHash {
+        WimFileHash {
+            path: path.to_string(),
+            size: 0,
+            sha1: [0u8; 20],
+        }
+    }
+
+    #[test]
+    fn test_is_critical() {
+        assert!(is_critical("\\Windows\\System32\\ntoskrnl.exe"));
+        assert!(is_critical("\\Windows\\System32\\drivers\\stornvme.sys"));
+        assert!(is_critical("\\Windows\\System32\\config\\SYSTEM"));
+        assert!(is_critical("\\Windows\\Boot\\EFI\\bootmgfw.efi"));
+        assert!(!is_critical("\\Windows\\System32\\notepad.exe"));
+        assert!(!is_critical("\\Windows\\System32\\driversx\\a.sys"));
+    }
+
+    #[test]
+    fn test_read_alignment() {
+        assert_eq!(read_alignment(None), READ_ALIGN);
+        assert_eq!(read_alignment(Some(512)), READ_ALIGN);
+        assert_eq!(read_alignment(Some(4096)), 4096);
+        assert_eq!(read_alignment(Some(8192)), 8192);
+        assert_eq!(read_alignment(Some(4 * 1024 * 1024)), READ_SIZE);
+
+        let mut buffer = AlignedBuffer::new(8192);
+        assert_eq!(buffer.as_mut_slice().as_ptr() as usize % 8192, 0);
+        assert_eq!(buffer.as_mut_slice().len(), READ_SIZE);
+    }
+
+    #[test]
+    fn test_select_files() {
+        let mut files: Vec<WimFileHash> = (0..10_000).map(|i| entry(&format!("\\Program Files\\f{}.dat", i))).collect();
+        files.push(entry("\\Windows\\System32\\hal.dll"));
+        files.push(entry("\\Windows\\System32\\drivers\\disk.sys"));
+
+        assert!(select_files(files.clone(), ApplyVerifyMode::Off).is_empty());
+        assert_eq!(select_files(files.clone(), ApplyVerifyMode::Full).len(), files.len());
+
+        let sampled = select_files(files.clone(), ApplyVerifyMode::Sampled);
+        assert!(sampled.len() <= SAMPLE_SIZE + 2);
+        assert!(sampled.len() > SAMPLE_SIZE / 2);
+        assert!(sampled.iter().any(|f| f.path.ends_with("hal.dll")));
+        assert!(sampled.iter().any(|f| f.path.ends_with("disk.sys")));
+        assert_eq!(sampled, select_files(files, ApplyVerifyMode::Sampled));
+    }
+
+    #[test]
+    fn test_select_small_image_checks_all() {
+        let files: Vec<WimFileHash> = (0..100).map(|i| entry(&format!("\\f{}", i))).collect();
+        assert_eq!(select_files(files, ApplyVerifyMode::Sampled).len(), 100);
+    }
+
+    #[test]
+    fn test_verify_files() {
+        let dir = std::env::temp_dir().join(format!("letrecovery_apply_verify_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Windows")).unwrap();
+        std::fs::write(dir.join("Windows").join("good.txt"), b"abc").unwrap();
+        std::fs::write(dir.join("Windows").join("bad.txt"), b"abd").unwrap();
+        std::fs::write(dir.join("Windows").join("empty.txt"), b"").unwrap();
+
+        // SHA-1("abc")
+        let abc: [u8; 20] = [
+            0xA9, 0x99, 0x3E, 0x36, 0x47, 0x06, 0x81, 0x6A, 0xBA, 0x3E, 0x25, 0x71, 0x78, 0x50, 0xC2, 0x6C, 0x9C, 0xD0,
+            0xD8, 0x9D,
+        ];
+        let sep = std::path::MAIN_SEPARATOR;
+        let file = |name: &str, size: u64, sha1: [u8; 20]| WimFileHash {
+            path: format!("{}Windows{}{}", sep, sep, name),
+            size,
+            sha1,
+        };
+        let files = vec![
+            file("good.txt", 3, abc),
+            file("bad.txt", 3, abc),
+            file("empty.txt", 0, [0u8; 20]),
+            file("short.txt", 3, abc),
+            file("missing.txt", 1, abc),
+        ];
+        std::fs::write(dir.join("Windows").join("short.txt"), b"ab").unwrap();
+
+        let report = verify_files(&dir, &files, &|_, _| {});
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert_eq!(report.checked, 5);
+        let failed: Vec<&str> = report.mismatches.iter().map(|m| m.path.rsplit(sep).next().unwrap()).collect();
+        assert_eq!(failed, vec!["bad.txt", "missing.txt", "short.txt"]);
+        assert!(!report.is_ok());
+        assert!(report.summary().contains("3 个与镜像不一致"));
+    }
+}
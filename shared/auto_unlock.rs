@@ -0,0 +1,161 @@
+//! 重装后解锁 BitLocker 数据分区
+//!
+//! 原系统中的自动解锁密钥保存在旧系统的注册表中，重装后其他 BitLocker 数据分区会保持锁定。
+//! 主程序在安装准备阶段（数据分区仍处于解锁状态）为每个分区添加一个一次性的外部密钥保护器，
+//! 密钥文件（.BEK）和解锁脚本放在受限访问的暂存目录中，不保存恢复密码。
+//!
+//! 新系统部署完成时（SetupComplete.cmd）脚本用密钥文件解锁分区并删除一次性保护器。
+//! Windows 只允许在系统分区已启用 BitLocker 时启用自动解锁，刚安装的系统通常没有启用，
+//! 这时分区仅在本次启动时解锁，之后需要用户输入密码，结果写入日志。
+//! 密钥目录执行后删除；安装失败时暂存目录随守卫删除，留在分区上的一次性保护器失去密钥文件后无法再使用。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::core::script_hooks::append_setup_complete;
+use crate::core::secure_dir::SensitiveDir;
+
+/// 密钥和解锁脚本所在的目录名（PE 安装时位于数据目录，安装后位于 Setup\Scripts）
+pub const AUTO_UNLOCK_DIR: &str = "bitlocker_autounlock";
+
+/// 解锁脚本文件名
+pub const UNLOCK_SCRIPT: &str = "unlock.cmd";
+
+/// 为数据分区添加的一次性外部密钥保护器
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoUnlockKey {
+    /// 当前盘符（如 "D:"），新系统中盘符可能变化，脚本逐个盘符尝试
+    pub letter: String,
+    /// 保护器 ID（带花括号），密钥文件名为不带花括号的 ID 加 `.BEK`
+    pub protector_id: String,
+}
+
+impl AutoUnlockKey {
+    /// 由 manage-bde 生成的密钥文件名得到保护器
+    pub fn from_key_file(letter: &str, file_name: &str) -> Option<Self> {
+        let (stem, ext) = file_name.rsplit_once('.')?;
+        if !ext.eq_ignore_ascii_case("bek") || stem.len() != 36 {
+            return None;
+        }
+        Some(Self {
+            letter: letter.to_string(),
+            protector_id: format!("{{{}}}", stem.to_uppercase()),
+        })
+    }
+
+    pub fn key_file(&self) -> String {
+        format!("{}.BEK", self.protector_id.trim_matches(|c| c == '{' || c == '}'))
+    }
+}
+
+/// 生成新系统部署完成时执行的解锁脚本（脚本与密钥文件位于同一目录，不包含任何密钥）
+pub fn generate_script(keys: &[AutoUnlockKey]) -> String {
+    let mut script = String::from(
+        "@echo off\r\nrem LetRecovery: unlock BitLocker data volumes with one-time keys\r\n\
+         set LOG=%SystemRoot%\\Temp\\LetRecovery_AutoUnlock.log\r\n\
+         set OSPROTECTED=0\r\n\
+         manage-bde -status %SystemDrive% -ProtectionAsErrorLevel >nul 2>&1 && set OSPROTECTED=1\r\n",
+    );
+    for key in keys {
+        script.push_str(&format!("rem {}\r\n", key.letter));
+        script.push_str("for %%d in (C D E F G H I J K L M N O P Q R S T U V W X Y Z) do (\r\n");
+        script.push_str(&format!(
+            "manage-bde -unlock %%d: -RecoveryKey \"%~dp0{}\" >nul 2>&1 && (\r\n",
+            key.key_file()
+        ));
+        script.push_str(
+            "if \"%OSPROTECTED%\"==\"1\" (manage-bde -autounlock -enable %%d: >>\"%LOG%\" 2>&1) \
+             else (echo %%d: auto-unlock requires BitLocker on %SystemDrive%, unlocked for this boot only>>\"%LOG%\")\r\n",
+        );
+        script.push_str(&format!(
+            "manage-bde -protectors -delete %%d: -id {} >>\"%LOG%\" 2>&1\r\n",
+            key.protector_id
+        ));
+        script.push_str(")\r\n)\r\n");
+    }
+    script
+}
+
+/// SetupComplete.cmd 中追加的命令：执行解锁脚本后无论成败都删除密钥目录
+fn setup_complete_calls() -> Vec<String> {
+    vec![
+        format!("call \"%~dp0{}\\{}\"", AUTO_UNLOCK_DIR, UNLOCK_SCRIPT),
+        format!("rd /s /q \"%~dp0{}\"", AUTO_UNLOCK_DIR),
+    ]
+}
+
+/// 把暂存目录中的密钥和脚本安装到目标系统，返回目标系统中密钥目录的守卫
+///
+/// 之后的安装步骤失败时由守卫删除目标系统中的密钥，安装完成后调用 `keep`。
+pub fn install(target_partition: &str, staged: &Path) -> Result<SensitiveDir> {
+    let dest = Path::new(target_partition)
+        .join("Windows\\Setup\\Scripts")
+        .join(AUTO_UNLOCK_DIR);
+    let guard = SensitiveDir::create(&dest)?;
+    for entry in std::fs::read_dir(staged).context("读取密钥暂存目录失败")?.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            std::fs::copy(&path, dest.join(entry.file_name()))
+                .with_context(|| format!("复制失败: {}", path.display()))?;
+        }
+    }
+    append_setup_complete(target_partition, &setup_complete_calls())?;
+    log::info!("[BITLOCKER] 已安装数据分区解锁脚本: {}", dest.display());
+    Ok(guard)
+}
+
+/// PE 安装时数据目录中暂存的密钥，守卫在安装流程的任何退出路径上删除暂存目录
+pub fn pending_keys(data_dir: &str) -> Option<SensitiveDir> {
+    SensitiveDir::existing(Path::new(data_dir).join(AUTO_UNLOCK_DIR))
+}
+
+/// 安装主程序在数据目录中暂存的密钥（没有则跳过），返回是否已安装
+///
+/// PE 安装中此步骤位于修复引导之后，之后的步骤都不会使安装失败，目标系统中的密钥直接保留。
+pub fn install_pending(data_dir: &str, target_partition: &str) -> Result<bool> {
+    let staged = Path::new(data_dir).join(AUTO_UNLOCK_DIR);
+    if !staged.join(UNLOCK_SCRIPT).exists() {
+        return Ok(false);
+    }
+    install(target_partition, &staged)?.keep();
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_from_file_name() {
+        let key = AutoUnlockKey::from_key_file("D:", "a1b2c3d4-0000-1111-2222-333344445555.BEK").unwrap();
+        assert_eq!(key.protector_id, "{A1B2C3D4-0000-1111-2222-333344445555}");
+        assert_eq!(key.key_file(), "A1B2C3D4-0000-1111-2222-333344445555.BEK");
+        assert!(AutoUnlockKey::from_key_file("D:", "unlock.cmd").is_none());
+        assert!(AutoUnlockKey::from_key_file("D:", "short.BEK").is_none());
+    }
+
+    #[test]
+    fn test_unlock_script() {
+        let keys = vec![AutoUnlockKey {
+            letter: "D:".to_string(),
+            protector_id: "{A1B2C3D4-0000-1111-2222-333344445555}".to_string(),
+        }];
+        let script = generate_script(&keys);
+        assert!(script.starts_with("@echo off\r\n"));
+        assert!(script.contains("-ProtectionAsErrorLevel"));
+        assert!(
+            script.contains("manage-bde -unlock %%d: -RecoveryKey \"%~dp0A1B2C3D4-0000-1111-2222-333344445555.BEK\"")
+        );
+        assert!(script.contains("manage-bde -autounlock -enable %%d:"));
+        assert!(script.contains("manage-bde -protectors -delete %%d: -id {A1B2C3D4-0000-1111-2222-333344445555}"));
+        assert!(!script.contains("-RecoveryPassword"));
+        assert!(!script.contains("\n\n"));
+
+        let calls = setup_complete_calls();
+        assert_eq!(calls[0], "call \"%~dp0bitlocker_autounlock\\unlock.cmd\"");
+        assert_eq!(calls[1], "rd /s /q \"%~dp0bitlocker_autounlock\"");
+    }
+}
@@ -0,0 +1,243 @@
+//! 备份目录（还原点）
+//!
+//! 每次备份（首次捕获或增量追加）成功后，在镜像旁的 `<镜像文件名>.catalog.json` 中记录
+//! 分卷索引、备份时间、源分区、本次新增的文件大小和源系统的 Windows 版本。
+//! 增量备份的每个分卷都是完整的系统快照（相同文件只存储一次），任一还原点都可单独还原或删除。
+//! 只有 WIM/ESD 镜像记录还原点，GHO 和分卷（SWM）镜像不能按分卷还原或删除。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 目录文件后缀
+const CATALOG_SUFFIX: &str = ".catalog.json";
+
+/// 目录结构版本
+const CATALOG_SCHEMA_VERSION: u32 = 1;
+
+/// 记录还原点的镜像格式（扩展名）
+const CATALOG_EXTENSIONS: [&str; 2] = ["wim", "esd"];
+
+/// 一次备份的记录
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct CatalogEntry {
+    /// 镜像中的分卷索引（从 1 开始）
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    /// 备份时间（Unix 时间戳，秒）
+    pub created_at: u64,
+    /// 源分区盘符
+    pub source_partition: String,
+    /// 本次备份使镜像文件增加的大小（字节）
+    pub added_bytes: u64,
+    /// 源系统的 Windows 版本（如 10.0.22631.4317），无法读取时为空
+    pub windows_build: String,
+}
+
+/// 备份目录
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BackupCatalog {
+    pub schema_version: u32,
+    /// 按分卷索引排序的备份记录
+    pub entries: Vec<CatalogEntry>,
+}
+
+impl BackupCatalog {
+    /// 镜像对应的目录文件路径
+    pub fn path_for(image_file: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}", image_file, CATALOG_SUFFIX))
+    }
+
+    /// 读取镜像的备份目录，不存在时返回 None
+    pub fn load(image_file: &str) -> Result<Option<Self>> {
+        let path = Self::path_for(image_file);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("读取备份目录失败: {}", path.display()))?;
+        let catalog = serde_json::from_str(&content)
+            .with_context(|| format!("解析备份目录失败: {}", path.display()))?;
+        Ok(Some(catalog))
+    }
+
+    /// 写入镜像的备份目录
+    pub fn save(&self, image_file: &str) -> Result<()> {
+        let path = Self::path_for(image_file);
+        let content = serde_json::to_string_pretty(self).context("序列化备份目录失败")?;
+        std::fs::write(&path, content).with_context(|| format!("写入备份目录失败: {}", path.display()))
+    }
+
+    /// 添加一条记录（替换同一索引的旧记录）
+    pub fn add(&mut self, entry: CatalogEntry) {
+        self.schema_version = CATALOG_SCHEMA_VERSION;
+        self.entries.retain(|e| e.index != entry.index);
+        self.entries.push(entry);
+        self.entries.sort_by_key(|e| e.index);
+    }
+
+    /// 删除分卷后移除对应记录，后面的分卷索引依次减一（与镜像中的编号保持一致）
+    pub fn remove(&mut self, index: u32) {
+        self.entries.retain(|e| e.index != index);
+        for entry in &mut self.entries {
+            if entry.index > index {
+                entry.index -= 1;
+            }
+        }
+    }
+}
+
+/// 镜像格式是否记录还原点（WIM/ESD）
+pub fn supports_catalog(image_file: &str) -> bool {
+    Path::new(image_file)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| CATALOG_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// 未填写名称时的分卷名称（源分区加备份时间）
+///
+/// 还原点按名称匹配备份记录，名称带上时间可避免增量备份的分卷同名。
+pub fn default_name(source_partition: &str) -> String {
+    match local_time_text() {
+        Some(time) => format!("{} {}", source_partition, time),
+        None => format!("{} 备份", source_partition),
+    }
+}
+
+/// 当前本地时间（YYYY-MM-DD HH:MM）
+#[cfg(windows)]
+fn local_time_text() -> Option<String> {
+    let now = unsafe { windows::Win32::System::SystemInformation::GetLocalTime() };
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        now.wYear, now.wMonth, now.wDay, now.wHour, now.wMinute
+    ))
+}
+
+#[cfg(not(windows))]
+fn local_time_text() -> Option<String> {
+    None
+}
+
+/// 备份前的镜像文件大小（不存在时为 0），用于计算本次新增的大小
+pub fn image_size(image_file: &str) -> u64 {
+    std::fs::metadata(image_file).map(|m| m.len()).unwrap_or(0)
+}
+
+/// 分区上 Windows 的版本号（读取 ntdll.dll 的文件版本）
+pub fn windows_build(partition: &str) -> String {
+    let ntdll = Path::new(&crate::core::disk::volume_root(partition))
+        .join("Windows")
+        .join("System32")
+        .join("ntdll.dll");
+    crate::core::system_utils::get_file_version(&ntdll)
+        .map(|(major, minor, build, revision)| format!("{}.{}.{}.{}", major, minor, build, revision))
+        .unwrap_or_default()
+}
+
+/// 备份成功后记录还原点（新分卷为镜像中的最后一个分卷），不记录还原点的格式直接跳过
+pub fn record_backup(
+    image_file: &str,
+    source_partition: &str,
+    name: &str,
+    description: &str,
+    size_before: u64,
+) -> Result<()> {
+    if !supports_catalog(image_file) {
+        return Ok(());
+    }
+    let index = crate::core::wimgapi::WimManager::new()
+        .map_err(|e| anyhow::anyhow!("wimgapi 初始化失败: {}", e))?
+        .get_image_info(image_file)
+        .map_err(|e| anyhow::anyhow!("读取镜像信息失败: {}", e))?
+        .iter()
+        .map(|image| image.index)
+        .max()
+        .context("镜像中没有分卷")?;
+
+    let mut catalog = BackupCatalog::load(image_file)?.unwrap_or_default();
+    catalog.add(CatalogEntry {
+        index,
+        name: name.to_string(),
+        description: description.to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        source_partition: source_partition.to_string(),
+        added_bytes: image_size(image_file).saturating_sub(size_before),
+        windows_build: windows_build(source_partition),
+    });
+    catalog.save(image_file)?;
+    log::info!("[CATALOG] 已记录还原点 {}: {}", index, name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(index: u32, name: &str) -> CatalogEntry {
+        CatalogEntry {
+            index,
+            name: name.to_string(),
+            created_at: 1_700_000_000 + index as u64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut catalog = BackupCatalog::default();
+        catalog.add(entry(2, "b"));
+        catalog.add(entry(1, "a"));
+        catalog.add(entry(3, "c"));
+        catalog.add(entry(2, "b2"));
+        assert_eq!(catalog.schema_version, CATALOG_SCHEMA_VERSION);
+        assert_eq!(
+            catalog.entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(),
+            ["a", "b2", "c"]
+        );
+
+        catalog.remove(2);
+        assert_eq!(
+            catalog.entries.iter().map(|e| (e.index, e.name.as_str())).collect::<Vec<_>>(),
+            [(1, "a"), (2, "c")]
+        );
+    }
+
+    #[test]
+    fn test_catalog_json() {
+        let mut catalog = BackupCatalog::default();
+        catalog.add(entry(1, "base"));
+        let json = serde_json::to_string(&catalog).unwrap();
+        assert!(json.contains("\"Entries\":[{\"Index\":1"));
+        assert_eq!(serde_json::from_str::<BackupCatalog>(&json).unwrap(), catalog);
+        assert_eq!(
+            BackupCatalog::path_for("D:\\backup.wim"),
+            PathBuf::from("D:\\backup.wim.catalog.json")
+        );
+    }
+
+    #[test]
+    fn test_supports_catalog() {
+        assert!(supports_catalog("D:\\backup.wim"));
+        assert!(supports_catalog("D:\\backup.ESD"));
+        assert!(!supports_catalog("D:\\backup.gho"));
+        assert!(!supports_catalog("D:\\backup.swm"));
+        assert!(!supports_catalog("D:\\backup"));
+    }
+
+    #[test]
+    fn test_default_name() {
+        assert!(default_name("C:").starts_with("C: "));
+        assert!(default_name("D:\\Mounts\\Volume1").starts_with("D:\\Mounts\\Volume1 "));
+    }
+}
@@ -0,0 +1,313 @@
+//! 备份捕获选项
+//!
+//! 压缩方式（最大/快速/不压缩）和 WimScript.ini 风格的排除列表。
+//! 排除规则以 `\` 开头（或包含 `\`）时从捕获目录根开始匹配完整路径，否则匹配任意层级的文件/目录名；
+//! 支持 `*` 和 `?` 通配符（不跨越路径分隔符），不区分大小写，目录被排除时其内容一并排除。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::core::wimgapi::{WIM_COMPRESS_LZX, WIM_COMPRESS_NONE, WIM_COMPRESS_XPRESS};
+use crate::core::wimlib::ExportCompression;
+
+/// 默认排除列表（与 DISM 默认的 WimScript.ini 一致，另外排除回收站和临时目录）
+pub const DEFAULT_EXCLUSIONS: &[&str] = &[
+    "\\$ntfs.log",
+    "\\hiberfil.sys",
+    "\\pagefile.sys",
+    "\\swapfile.sys",
+    "\\System Volume Information",
+    "\\RECYCLER",
+    "\\$Recycle.Bin",
+    "\\Windows\\CSC",
+    "\\Windows\\Temp\\*",
+    "\\Users\\*\\AppData\\Local\\Temp\\*",
+];
+
+/// 正在进行的捕获：(捕获目录, 排除列表)，wimgapi 在文件处理回调中据此跳过文件
+static ACTIVE_CAPTURE: Mutex<Option<(String, Vec<String>)>> = Mutex::new(None);
+
+/// 捕获压缩方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptureCompression {
+    /// LZX（文件最小，速度较慢）
+    #[default]
+    Max,
+    /// XPRESS（速度快，文件较大）
+    Fast,
+    /// 不压缩
+    None,
+}
+
+impl CaptureCompression {
+    pub const ALL: [CaptureCompression; 3] =
+        [CaptureCompression::Max, CaptureCompression::Fast, CaptureCompression::None];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CaptureCompression::Max => "最大压缩（LZX）",
+            CaptureCompression::Fast => "快速压缩（XPRESS）",
+            CaptureCompression::None => "不压缩",
+        }
+    }
+
+    /// wimgapi 压缩类型
+    pub fn wimgapi_type(&self) -> u32 {
+        match self {
+            CaptureCompression::Max => WIM_COMPRESS_LZX,
+            CaptureCompression::Fast => WIM_COMPRESS_XPRESS,
+            CaptureCompression::None => WIM_COMPRESS_NONE,
+        }
+    }
+
+    /// wimlib 压缩类型
+    pub fn wimlib_type(&self) -> ExportCompression {
+        match self {
+            CaptureCompression::Max => ExportCompression::Lzx,
+            CaptureCompression::Fast => ExportCompression::Xpress,
+            CaptureCompression::None => ExportCompression::None,
+        }
+    }
+}
+
+/// 捕获选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct CaptureOptions {
+    /// 压缩方式（追加到已有镜像时沿用镜像原有的压缩方式）
+    pub compression: CaptureCompression,
+    /// 排除规则（WimScript.ini 的 [ExclusionList]）
+    pub exclusions: Vec<String>,
+}
+
+impl Default for CaptureOptions {
+    fn default() -> Self {
+        Self {
+            compression: CaptureCompression::default(),
+            exclusions: DEFAULT_EXCLUSIONS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+impl CaptureOptions {
+    /// 判断捕获目录内的路径（如 `\Windows\Temp\a.log`）是否被排除
+    pub fn is_excluded(&self, path: &str) -> bool {
+        is_excluded(&self.exclusions, path)
+    }
+
+    /// 校验排除规则：不能为空，也不能排除整个捕获目录
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        for pattern in &self.exclusions {
+            let trimmed = pattern.trim().trim_matches(['\\', '/']);
+            if trimmed.is_empty() {
+                return Err("排除规则不能为空".to_string());
+            }
+            if trimmed.chars().all(|c| c == '*') {
+                return Err(format!("排除规则 {} 会排除全部文件", pattern));
+            }
+        }
+        Ok(())
+    }
+
+    /// 生成 WimScript.ini 内容
+    pub fn to_wimscript(&self) -> String {
+        let mut content = String::from("[ExclusionList]\r\n");
+        for pattern in &self.exclusions {
+            content.push_str(pattern);
+            content.push_str("\r\n");
+        }
+        content
+    }
+
+    /// 写入 WimScript.ini（UTF-8）
+    pub fn write_wimscript(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, self.to_wimscript())
+            .with_context(|| format!("写入配置文件失败: {}", path.display()))
+    }
+}
+
+/// 解析 WimScript.ini 的 [ExclusionList]（忽略其他节和 `;` 注释）
+pub fn parse_wimscript(content: &str) -> Vec<String> {
+    let mut in_exclusions = false;
+    let mut exclusions = Vec::new();
+    for line in content.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_exclusions = line.eq_ignore_ascii_case("[ExclusionList]");
+            continue;
+        }
+        if in_exclusions {
+            exclusions.push(line.trim_matches('"').to_string());
+        }
+    }
+    exclusions
+}
+
+/// 读取 WimScript.ini 的排除列表（DISM 自带的配置文件通常为 UTF-16 编码）
+pub fn load_wimscript(path: &Path) -> Result<Vec<String>> {
+    let bytes = std::fs::read(path).with_context(|| format!("读取配置文件失败: {}", path.display()))?;
+    let content = match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    Ok(parse_wimscript(&content))
+}
+
+/// 把完整路径转换为相对捕获目录根的路径（以 `\` 开头），不在目录内时返回 None
+pub fn relative_path(root: &str, full_path: &str) -> Option<String> {
+    let root = root.trim_end_matches(['\\', '/']);
+    let prefix = full_path.get(..root.len())?;
+    if !prefix.eq_ignore_ascii_case(root) {
+        return None;
+    }
+    let rest = &full_path[root.len()..];
+    if !rest.is_empty() && !rest.starts_with(['\\', '/']) {
+        return None;
+    }
+    Some(format!("\\{}", rest.trim_start_matches(['\\', '/'])))
+}
+
+/// 开始捕获时登记捕获目录和排除规则，捕获结束后传入 None 清除
+pub fn set_active_capture(capture: Option<(&str, &[String])>) {
+    *ACTIVE_CAPTURE.lock().unwrap_or_else(|e| e.into_inner()) =
+        capture.map(|(root, exclusions)| (root.to_string(), exclusions.to_vec()));
+}
+
+/// 文件（完整路径）是否被正在进行的捕获排除，没有登记捕获时返回 false
+pub fn is_excluded_from_active_capture(full_path: &str) -> bool {
+    let active = ACTIVE_CAPTURE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((root, exclusions)) = active.as_ref() else {
+        return false;
+    };
+    match relative_path(root, full_path) {
+        Some(relative) if is_excluded(exclusions, &relative) => {
+            log::debug!("[CAPTURE] 排除: {}", full_path);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// 判断路径是否匹配任一排除规则
+pub fn is_excluded(exclusions: &[String], path: &str) -> bool {
+    let components: Vec<&str> = path.split(['\\', '/']).filter(|c| !c.is_empty()).collect();
+    exclusions.iter().any(|pattern| matches_pattern(pattern, &components))
+}
+
+fn matches_pattern(pattern: &str, components: &[&str]) -> bool {
+    let pattern = pattern.trim().replace('/', "\\");
+    // 去掉盘符（如 C:\pagefile.sys）
+    let pattern = match pattern.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => &pattern[2..],
+        _ => pattern.as_str(),
+    };
+    let parts: Vec<&str> = pattern.split('\\').filter(|c| !c.is_empty()).collect();
+    if parts.is_empty() {
+        return false;
+    }
+
+    if pattern.contains('\\') {
+        // 从根开始匹配，路径本身或任一上级目录匹配即排除
+        components.len() >= parts.len()
+            && parts.iter().zip(components).all(|(part, component)| wildcard_match(part, component))
+    } else {
+        components.iter().any(|component| wildcard_match(parts[0], component))
+    }
+}
+
+/// 不区分大小写的通配符匹配（`*` 匹配任意字符，`?` 匹配单个字符）
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_exclusions() {
+        let options = CaptureOptions::default();
+        assert!(options.is_excluded("\\pagefile.sys"));
+        assert!(options.is_excluded("\\PageFile.SYS"));
+        assert!(options.is_excluded("\\System Volume Information\\tracking.log"));
+        assert!(options.is_excluded("\\Windows\\Temp\\setup\\a.log"));
+        assert!(options.is_excluded("\\Users\\Admin\\AppData\\Local\\Temp\\x.tmp"));
+        assert!(!options.is_excluded("\\Windows\\Temp"));
+        assert!(!options.is_excluded("\\Users\\Admin\\pagefile.sys"));
+        assert!(!options.is_excluded("\\Windows\\System32\\kernel32.dll"));
+    }
+
+    #[test]
+    fn test_name_patterns() {
+        let exclusions = vec!["*.tmp".to_string(), "Thumbs.db".to_string(), "C:\\Data\\cache?".to_string()];
+        assert!(is_excluded(&exclusions, "\\Users\\a\\b.TMP"));
+        assert!(is_excluded(&exclusions, "\\Pictures\\thumbs.db"));
+        assert!(is_excluded(&exclusions, "\\Data\\cache1\\file"));
+        assert!(!is_excluded(&exclusions, "\\Data\\cache12"));
+        assert!(!is_excluded(&exclusions, "\\a.tmp.bak"));
+    }
+
+    #[test]
+    fn test_wimscript_round_trip() {
+        let content = "\u{feff}[ExclusionList]\r\n\\pagefile.sys\r\n; 注释\r\n\"\\Windows\\Temp\\*\"\r\n\r\n\
+                       [CompressionExclusionList]\r\n*.mp3\r\n";
+        let exclusions = parse_wimscript(content);
+        assert_eq!(exclusions, ["\\pagefile.sys", "\\Windows\\Temp\\*"]);
+
+        let options = CaptureOptions {
+            compression: CaptureCompression::Fast,
+            exclusions,
+        };
+        assert_eq!(parse_wimscript(&options.to_wimscript()), options.exclusions);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("C:\\", "C:\\pagefile.sys").as_deref(), Some("\\pagefile.sys"));
+        assert_eq!(relative_path("\\\\?\\C:", "\\\\?\\c:\\Windows").as_deref(), Some("\\Windows"));
+        assert_eq!(relative_path("C:\\", "C:\\").as_deref(), Some("\\"));
+        assert_eq!(relative_path("D:\\", "C:\\Windows"), None);
+    }
+
+    #[test]
+    fn test_active_capture() {
+        assert!(!is_excluded_from_active_capture("C:\\pagefile.sys"));
+        let exclusions = vec!["\\pagefile.sys".to_string()];
+        set_active_capture(Some(("C:\\", &exclusions)));
+        assert!(is_excluded_from_active_capture("C:\\pagefile.sys"));
+        assert!(!is_excluded_from_active_capture("C:\\Windows\\pagefile.sys"));
+        assert!(!is_excluded_from_active_capture("D:\\pagefile.sys"));
+        set_active_capture(None);
+        assert!(!is_excluded_from_active_capture("C:\\pagefile.sys"));
+    }
+}
@@ -0,0 +1,435 @@
+//! 命令行子命令
+//!
+//! 供部署脚本在不打开界面的情况下调用：
+//!
+//! ```text
+//! LetRecovery.exe install --image D:\win11.wim --target E: [--index 1] [--no-reboot] [--json]
+//! LetRecovery.exe backup --source E: --output D:\backup.wim [--name 名称] [--description 描述] [--append] [--json]
+//! LetRecovery.exe verify --image D:\win11.wim [--json]
+//! LetRecovery.exe list-partitions [--json]
+//! LetRecovery.exe list-images --image D:\win11.wim [--json]
+//! ```
+//!
+//! `--json` 时 install/backup/verify 以 JSON 事件逐行输出进度（同 `--json-progress`），
+//! list-* 在标准输出打印一行 JSON。结果通过 `exit_code` 中的退出码返回。
+//! PE 版（LetRecoveryPE.exe）支持同样的子命令，原有的 /PEINSTALL、/PEBACKUP、--verify 参数不受影响。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::utils::json_progress::{JSON_PROGRESS_ARG, PROGRESS_JSON_ARG, PROGRESS_PIPE_ARG};
+
+/// 输出 JSON 的参数
+pub const JSON_ARG: &str = "--json";
+
+/// 启用演示模式的参数（只有桌面端支持演示模式）
+pub const DEMO_ARG: &str = "--demo";
+
+/// 由其他模块处理的全局参数，解析子命令时忽略
+const GLOBAL_FLAGS: [&str; 3] = [JSON_PROGRESS_ARG, PROGRESS_JSON_ARG, DEMO_ARG];
+
+/// 由其他模块处理、后跟取值的全局参数
+const GLOBAL_OPTIONS: [&str; 1] = [PROGRESS_PIPE_ARG];
+
+/// 用法说明，program 为程序文件名（如 `LetRecovery.exe`）
+pub fn usage(program: &str) -> String {
+    [
+        "install --image <镜像> --target <盘符> [--index <分卷>] [--no-reboot] [--json]",
+        "backup --source <盘符> --output <WIM 路径> [--name <名称>] [--description <描述>] [--append] [--json]",
+        "verify --image <镜像> [--json]",
+        "list-partitions [--json]",
+        "list-images --image <镜像> [--json]",
+        "help",
+    ]
+    .iter()
+    .fold("用法:".to_string(), |usage, command| format!("{}\n  {} {}", usage, program, command))
+}
+
+/// 安装参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallArgs {
+    pub image: String,
+    /// 目标分区盘符（如 `E:`）
+    pub target: String,
+    /// 分卷索引（从 1 开始），GHO 镜像为分区序号
+    pub index: u32,
+    /// 完成后不重启
+    pub no_reboot: bool,
+}
+
+/// 备份参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupArgs {
+    /// 源分区盘符（如 `C:`）
+    pub source: String,
+    pub output: String,
+    pub name: String,
+    pub description: String,
+    /// 追加到已有镜像
+    pub append: bool,
+}
+
+/// 子命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Install(InstallArgs),
+    Backup(BackupArgs),
+    Verify { image: String },
+    ListPartitions,
+    ListImages { image: String },
+    Help,
+}
+
+/// 一次命令行调用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub command: Command,
+    /// 以 JSON 输出
+    pub json: bool,
+}
+
+/// 子命令接受的参数
+struct Spec {
+    /// 需要取值的参数
+    values: &'static [&'static str],
+    /// 开关参数
+    switches: &'static [&'static str],
+}
+
+impl Spec {
+    fn parse(&self, args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if GLOBAL_FLAGS.contains(&arg.as_str()) {
+                continue;
+            }
+            if GLOBAL_OPTIONS.contains(&arg.as_str()) {
+                iter.next();
+                continue;
+            }
+            if arg == JSON_ARG {
+                options.json = true;
+                continue;
+            }
+            let (key, inline_value) = match arg.split_once('=') {
+                Some((key, value)) if key.starts_with("--") => (key, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+            if let Some(&name) = self.values.iter().find(|&&name| name == key) {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .filter(|value| !value.starts_with("--"))
+                        .cloned()
+                        .ok_or_else(|| format!("参数 {} 缺少取值", key))?,
+                };
+                options.values.insert(name, value);
+            } else if let Some(&name) = self.switches.iter().find(|&&name| name == key) {
+                if inline_value.is_some() {
+                    return Err(format!("参数 {} 不需要取值", key));
+                }
+                options.switches.push(name);
+            } else if key.starts_with('-') {
+                return Err(format!("未知参数: {}", arg));
+            } else {
+                return Err(format!("多余的参数: {}", arg));
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// 解析后的参数
+#[derive(Default)]
+struct Options {
+    values: HashMap<&'static str, String>,
+    switches: Vec<&'static str>,
+    json: bool,
+}
+
+impl Options {
+    fn required(&mut self, name: &str) -> Result<String, String> {
+        self.values
+            .remove(name)
+            .filter(|value| !value.trim().is_empty())
+            .ok_or_else(|| format!("缺少参数 {}", name))
+    }
+
+    fn optional(&mut self, name: &str) -> String {
+        self.values.remove(name).unwrap_or_default()
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.contains(&name)
+    }
+}
+
+/// 解析命令行（不含程序路径）
+///
+/// 第一个非全局参数不是子命令时返回 `None`，按原有方式启动
+pub fn parse(args: &[String]) -> Option<Result<Invocation, String>> {
+    let mut position = 0;
+    let mut json = false;
+    while let Some(arg) = args.get(position) {
+        if arg == JSON_ARG {
+            json = true;
+        } else if GLOBAL_OPTIONS.contains(&arg.as_str()) {
+            position += 1;
+        } else if !GLOBAL_FLAGS.contains(&arg.as_str()) {
+            break;
+        }
+        position += 1;
+    }
+    let rest = args.get(position + 1..)?;
+
+    let result = match args[position].to_ascii_lowercase().as_str() {
+        "install" => parse_install(rest),
+        "backup" => parse_backup(rest),
+        "verify" => parse_verify(rest),
+        "list-partitions" => parse_list_partitions(rest),
+        "list-images" => parse_list_images(rest),
+        "help" | "--help" | "-h" | "/?" => Ok(Invocation { command: Command::Help, json: false }),
+        _ => return None,
+    };
+    Some(result.map(|invocation| Invocation { json: invocation.json || json, ..invocation }))
+}
+
+fn parse_install(args: &[String]) -> Result<Invocation, String> {
+    let spec = Spec { values: &["--image", "--target", "--index"], switches: &["--no-reboot"] };
+    let mut options = spec.parse(args)?;
+    let index = match options.values.remove("--index") {
+        Some(value) => parse_index(&value)?,
+        None => 1,
+    };
+    let command = Command::Install(InstallArgs {
+        image: options.required("--image")?,
+        target: normalize_drive(&options.required("--target")?)?,
+        index,
+        no_reboot: options.switch("--no-reboot"),
+    });
+    Ok(Invocation { command, json: options.json })
+}
+
+fn parse_backup(args: &[String]) -> Result<Invocation, String> {
+    let spec = Spec { values: &["--source", "--output", "--name", "--description"], switches: &["--append"] };
+    let mut options = spec.parse(args)?;
+    let command = Command::Backup(BackupArgs {
+        source: normalize_drive(&options.required("--source")?)?,
+        output: options.required("--output")?,
+        name: options.optional("--name"),
+        description: options.optional("--description"),
+        append: options.switch("--append"),
+    });
+    Ok(Invocation { command, json: options.json })
+}
+
+fn parse_verify(args: &[String]) -> Result<Invocation, String> {
+    let spec = Spec { values: &["--image"], switches: &[] };
+    let mut options = spec.parse(args)?;
+    let command = Command::Verify { image: options.required("--image")? };
+    Ok(Invocation { command, json: options.json })
+}
+
+fn parse_list_partitions(args: &[String]) -> Result<Invocation, String> {
+    let spec = Spec { values: &[], switches: &[] };
+    let options = spec.parse(args)?;
+    Ok(Invocation { command: Command::ListPartitions, json: options.json })
+}
+
+fn parse_list_images(args: &[String]) -> Result<Invocation, String> {
+    let spec = Spec { values: &["--image"], switches: &[] };
+    let mut options = spec.parse(args)?;
+    let command = Command::ListImages { image: options.required("--image")? };
+    Ok(Invocation { command, json: options.json })
+}
+
+fn parse_index(value: &str) -> Result<u32, String> {
+    value
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|&index| index > 0)
+        .ok_or_else(|| format!("无效的分卷索引: {}", value))
+}
+
+/// 规范化盘符：`e`、`E:`、`E:\` 均转换为 `E:`
+pub fn normalize_drive(value: &str) -> Result<String, String> {
+    let trimmed = value.trim().trim_end_matches(['\\', '/']);
+    let letter = trimmed.strip_suffix(':').unwrap_or(trimmed);
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(format!("{}:", c.to_ascii_uppercase())),
+        _ => Err(format!("无效的盘符: {}（应为 D: 这样的形式）", value)),
+    }
+}
+
+/// list-partitions 输出的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct PartitionRow {
+    pub letter: String,
+    pub label: String,
+    pub total_mb: u64,
+    pub free_mb: u64,
+    pub partition_style: String,
+    pub disk_number: Option<u32>,
+    pub partition_number: Option<u32>,
+    pub is_system: bool,
+    pub has_windows: bool,
+}
+
+/// list-images 输出的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageRow {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub size_bytes: u64,
+}
+
+/// 在标准输出打印一行 JSON
+pub fn print_json<T: Serialize + ?Sized>(value: &T) {
+    match serde_json::to_string(value) {
+        Ok(line) => println!("{}", line),
+        Err(e) => eprintln!("序列化输出失败: {}", e),
+    }
+}
+
+/// 按列对齐输出表格
+pub fn format_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let width = |text: &str| text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum::<usize>();
+    let mut widths: Vec<usize> = headers.iter().map(|header| width(header)).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate().take(widths.len()) {
+            widths[i] = widths[i].max(width(cell));
+        }
+    }
+
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| {
+        let line: String = cells
+            .zip(&widths)
+            .map(|(cell, &column)| format!("{}{}", cell, " ".repeat(column - width(cell) + 2)))
+            .collect();
+        line.trim_end().to_string()
+    };
+    let mut lines = vec![format_row(&mut headers.iter().copied())];
+    for row in rows {
+        lines.push(format_row(&mut row.iter().map(String::as_str)));
+    }
+    lines.join("\n")
+}
+
+/// 附加到父进程的控制台
+///
+/// 发布版是窗口程序，不附加时从命令提示符运行看不到任何输出
+pub fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn AttachConsole(process_id: u32) -> i32;
+        }
+        // ATTACH_PARENT_PROCESS
+        AttachConsole(u32::MAX);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    fn command(text: &str) -> Result<Invocation, String> {
+        parse(&args(text)).expect("应识别为子命令")
+    }
+
+    #[test]
+    fn test_not_a_subcommand() {
+        assert!(parse(&args("")).is_none());
+        assert!(parse(&args("/PEINSTALL")).is_none());
+        assert!(parse(&args("--json-progress --pe-backup")).is_none());
+        assert!(parse(&args("--verify D:\\a.wim")).is_none());
+        assert!(parse(&args("--progress-pipe install")).is_none());
+    }
+
+    #[test]
+    fn test_parse_install() {
+        let invocation = command("install --image D:\\win11.wim --target=e --index 3 --no-reboot --json").unwrap();
+        assert!(invocation.json);
+        assert_eq!(
+            invocation.command,
+            Command::Install(InstallArgs {
+                image: "D:\\win11.wim".to_string(),
+                target: "E:".to_string(),
+                index: 3,
+                no_reboot: true,
+            })
+        );
+
+        let invocation = command("--json-progress install --target E:\\ --image D:\\a.esd --progress-pipe mdt").unwrap();
+        assert!(!invocation.json);
+        match invocation.command {
+            Command::Install(install) => {
+                assert_eq!(install.index, 1);
+                assert_eq!(install.target, "E:");
+                assert!(!install.no_reboot);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(command("install --image D:\\a.wim").is_err());
+        assert!(command("install --image D:\\a.wim --target E: --index 0").is_err());
+        assert!(command("install --image --target E:").is_err());
+        assert!(command("install --image D:\\a.wim --target EF:").is_err());
+        assert!(command("install --image D:\\a.wim --target E: --force").is_err());
+        assert!(command("list-partitions extra").is_err());
+        assert!(command("list-images").is_err());
+        assert!(command("backup --source C: --output D:\\b.wim --append=1").is_err());
+    }
+
+    #[test]
+    fn test_parse_other_commands() {
+        let invocation = command("--json backup --source c --output D:\\b.wim --name Daily --append").unwrap();
+        assert!(invocation.json);
+        assert_eq!(
+            invocation.command,
+            Command::Backup(BackupArgs {
+                source: "C:".to_string(),
+                output: "D:\\b.wim".to_string(),
+                name: "Daily".to_string(),
+                description: String::new(),
+                append: true,
+            })
+        );
+        assert_eq!(command("verify --image D:\\a.wim").unwrap().command, Command::Verify { image: "D:\\a.wim".to_string() });
+        assert_eq!(command("LIST-PARTITIONS --json").unwrap().command, Command::ListPartitions);
+        assert_eq!(command("help").unwrap().command, Command::Help);
+    }
+
+    #[test]
+    fn test_usage() {
+        let usage = usage("LetRecoveryPE.exe");
+        assert!(usage.starts_with("用法:\n  LetRecoveryPE.exe install --image"));
+        assert!(usage.ends_with("\n  LetRecoveryPE.exe help"));
+        assert_eq!(usage.lines().count(), 7);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let rows = vec![
+            vec!["C:".to_string(), "系统".to_string()],
+            vec!["D:".to_string(), "Data".to_string()],
+        ];
+        assert_eq!(format_table(&["盘符", "卷标"], &rows), "盘符  卷标\nC:    系统\nD:    Data");
+    }
+}
@@ -0,0 +1,591 @@
+//! 安装和备份配置的结构与校验
+//!
+//! 桌面端写入、PE 端读取同一份 `LetRecovery_Install.json` / `LetRecovery_Backup.json`，
+//! 两端必须使用相同的字段和校验规则；配置文件的读写见各端的 `ConfigFileManager`。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 配置文件结构版本
+///
+/// 新增字段时只需保证有默认值即可，无需递增；
+/// 只有已有字段的含义发生变化时才需要递增此版本号。
+pub const CONFIG_SCHEMA_VERSION: u32 = 1;
+
+/// 配置校验错误
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("配置项 {0} 不能为空")]
+    Missing(&'static str),
+
+    #[error("配置项 {key} 的值无效: {reason}")]
+    InvalidValue { key: &'static str, reason: String },
+}
+
+/// 驱动操作模式（配置中保存为数值）
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DriverActionMode {
+    /// 无操作
+    #[default]
+    None = 0,
+    /// 仅保存驱动（到数据目录）
+    SaveOnly = 1,
+    /// 自动导入（保存并导入到新系统）
+    AutoImport = 2,
+}
+
+impl DriverActionMode {
+    /// 从数值转换
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::SaveOnly,
+            2 => Self::AutoImport,
+            _ => Self::None,
+        }
+    }
+
+    /// 是否需要导入驱动
+    pub fn should_import(&self) -> bool {
+        *self == Self::AutoImport
+    }
+
+    /// 是否有驱动目录（SaveOnly 或 AutoImport 时都有）
+    pub fn has_drivers(&self) -> bool {
+        *self != Self::None
+    }
+}
+
+/// 备份格式（配置中保存为数值）
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BackupFormat {
+    #[default]
+    Wim = 0,
+    Esd = 1,
+    Swm = 2,
+    Gho = 3,
+}
+
+impl BackupFormat {
+    /// 从数值转换
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Esd,
+            2 => Self::Swm,
+            3 => Self::Gho,
+            _ => Self::Wim,
+        }
+    }
+}
+
+/// 系统安装配置（用于PE环境内安装）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct InstallConfig {
+    /// 配置结构版本（0 表示从旧版 INI 配置解析而来）
+    pub schema_version: u32,
+    /// 无人值守安装
+    pub unattended: bool,
+    /// 驱动还原（兼容旧版本）
+    pub restore_drivers: bool,
+    /// 驱动操作模式: 0=无, 1=仅保存, 2=自动导入
+    pub driver_action_mode: u8,
+    /// 格式化前从目标分区的现有系统导出第三方驱动，释放镜像后重新注入
+    pub harvest_drivers: bool,
+    /// 数据分区 drivers\hwid 下有按硬件 ID 下载的网卡/存储驱动，不导入驱动时单独注入
+    pub hwid_drivers: bool,
+    /// 数据目录 wifi 下有桌面端导出的 Wi-Fi 配置，PE 启动安装时导入后删除
+    pub wifi_profiles: bool,
+    /// 释放镜像后按镜像中的哈希校验已写入的文件（抽样或完整）
+    pub apply_verify: crate::core::apply_verify::ApplyVerifyMode,
+    /// 立即重启（旧版配置；新配置以 completion_action 为准）
+    pub auto_reboot: bool,
+    /// 完成后的操作，为空时按 auto_reboot 决定
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completion_action: Option<crate::core::power::CompletionAction>,
+    /// 原系统引导GUID（用于删除旧引导项）
+    #[serde(rename = "OriginalGUID")]
+    pub original_guid: String,
+    /// 安装分卷索引
+    pub volume_index: u32,
+    /// 目标分区盘符
+    pub target_partition: String,
+    /// 镜像文件路径（相对于数据分区）
+    pub image_path: String,
+    /// 网络镜像源（SMB 共享路径或 HTTP(S) 地址），设置后从网络读取镜像，不使用 image_path
+    pub image_source: String,
+    /// 连接 SMB 共享的用户名，为空时使用当前身份（密码不写入配置，见 `core::install_secrets`）
+    pub share_user: String,
+    /// 是否为GHO格式
+    pub is_gho: bool,
+    /// 释放镜像使用的后端（桌面端设置），PE 中找不到 wimlib.dll 时回退到 wimgapi
+    pub image_backend: crate::core::wimlib::ImageBackend,
+    /// GHO 磁盘镜像中要还原的分区序号（从 1 开始），0 表示不指定
+    pub gho_partition: u32,
+    /// 自动创建的数据分区标识（磁盘ID:偏移），为空表示使用已有分区
+    pub data_partition_id: String,
+    /// CAB更新包安装: true=安装, false=不安装
+    pub install_cab_packages: bool,
+
+    // 高级选项
+    /// 移除快捷方式小箭头
+    pub remove_shortcut_arrow: bool,
+    /// Win11恢复经典右键
+    pub restore_classic_context_menu: bool,
+    /// OOBE绕过强制联网
+    #[serde(rename = "BypassNRO")]
+    pub bypass_nro: bool,
+    /// Win11 绕过 TPM 检查
+    #[serde(rename = "BypassTPMCheck")]
+    pub bypass_tpm_check: bool,
+    /// Win11 绕过安全启动检查
+    pub bypass_secure_boot_check: bool,
+    /// Win11 绕过内存检查
+    #[serde(rename = "BypassRAMCheck")]
+    pub bypass_ram_check: bool,
+    /// 禁用Windows更新
+    pub disable_windows_update: bool,
+    /// 禁用Windows安全中心
+    pub disable_windows_defender: bool,
+    /// 禁用系统保留空间
+    pub disable_reserved_storage: bool,
+    /// 禁用用户账户控制
+    #[serde(rename = "DisableUAC")]
+    pub disable_uac: bool,
+    /// 禁用自动设备加密
+    pub disable_device_encryption: bool,
+    /// 删除预装UWP应用
+    #[serde(rename = "RemoveUWPApps")]
+    pub remove_uwp_apps: bool,
+    /// 导入磁盘控制器驱动
+    pub import_storage_controller_drivers: bool,
+    /// 要导入的存储控制器驱动包（为空表示全部导入）
+    pub storage_driver_packs: Vec<String>,
+    /// 首次启动禁用驱动签名强制
+    pub disable_driver_signature_once: bool,
+    /// 确保启用TRIM
+    #[serde(rename = "EnsureTRIM")]
+    pub ensure_trim: bool,
+    /// 磁盘写入缓存策略
+    pub write_cache_policy: crate::core::disk_policy::WriteCachePolicy,
+    /// 自定义用户名
+    pub custom_username: String,
+    /// 自定义系统盘卷标
+    pub volume_label: String,
+    /// 首次启动脚本钩子（为空表示按文件名顺序执行 scripts 文件夹中的全部脚本）
+    pub script_hooks: Vec<crate::core::script_hooks::ScriptHook>,
+    /// 无人值守设置（区域、时区、产品密钥、计算机名、密码、隐私选项等）
+    pub unattend: crate::core::unattend::UnattendSettings,
+    /// 首次启动健康检查（网络、激活、问题设备、磁盘健康报告）
+    pub health_check: crate::core::health_check::HealthCheckSettings,
+    
+    // Win7 专用选项
+    /// Win7 UEFI 补丁（使用 UefiSeven）
+    pub win7_uefi_patch: bool,
+    /// Win7 注入USB3驱动
+    pub win7_inject_usb3_driver: bool,
+    /// Win7 注入NVMe驱动
+    pub win7_inject_nvme_driver: bool,
+    /// Win7 修复ACPI蓝屏
+    pub win7_fix_acpi_bsod: bool,
+    /// Win7 修复存储控制器蓝屏
+    pub win7_fix_storage_bsod: bool,
+
+    /// 当前版本不认识的字段（由更新的桌面端/PE写入），读写时原样保留
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl InstallConfig {
+    /// 完成后的操作（兼容只有 AutoReboot 的旧配置：重启或留在 PE）
+    pub fn completion_action(&self) -> crate::core::power::CompletionAction {
+        use crate::core::power::CompletionAction;
+        self.completion_action.unwrap_or(if self.auto_reboot {
+            CompletionAction::RebootCountdown
+        } else {
+            CompletionAction::StayInPe
+        })
+    }
+
+    /// Win11 硬件要求检查绕过
+    pub fn win11_bypass(&self) -> crate::core::unattend::Win11Bypass {
+        crate::core::unattend::Win11Bypass {
+            tpm: self.bypass_tpm_check,
+            secure_boot: self.bypass_secure_boot_check,
+            ram: self.bypass_ram_check,
+        }
+    }
+
+    /// 驱动操作模式
+    pub fn driver_action(&self) -> DriverActionMode {
+        DriverActionMode::from_u8(self.driver_action_mode)
+    }
+
+    /// 判断是否需要导入驱动
+    /// 优先使用新的driver_action_mode，兼容旧的restore_drivers
+    pub fn should_import_drivers(&self) -> bool {
+        match self.driver_action() {
+            DriverActionMode::None => self.restore_drivers,
+            mode => mode.should_import(),
+        }
+    }
+
+    /// 判断是否有驱动目录需要处理
+    pub fn has_driver_data(&self) -> bool {
+        self.driver_action().has_drivers() || self.restore_drivers
+    }
+
+    /// 网络镜像源，未设置时返回 None
+    pub fn network_source(&self) -> Option<crate::core::network_source::NetworkSource> {
+        crate::core::network_source::NetworkSource::parse(&self.image_source)
+    }
+
+    /// 校验配置内容，返回第一个发现的问题
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        validate_partition("TargetPartition", &self.target_partition)?;
+
+        if !self.image_source.trim().is_empty() {
+            match self.network_source() {
+                None => {
+                    return Err(ConfigError::InvalidValue {
+                        key: "ImageSource",
+                        reason: format!("应为 SMB 共享路径（\\\\服务器\\共享\\文件）、HTTP(S) 或 netcast:// 地址，实际为 {}", self.image_source),
+                    });
+                }
+                Some(source) => {
+                    source.check().map_err(|reason| ConfigError::InvalidValue { key: "ImageSource", reason })?;
+                }
+            }
+        } else if self.image_path.trim().is_empty() {
+            return Err(ConfigError::Missing("ImagePath"));
+        }
+        if self.image_source.trim().is_empty() && Path::new(&self.image_path).is_absolute() {
+            return Err(ConfigError::InvalidValue {
+                key: "ImagePath",
+                reason: format!("应为相对于数据目录的路径，实际为 {}", self.image_path),
+            });
+        }
+
+        if !self.is_gho && self.volume_index == 0 {
+            return Err(ConfigError::InvalidValue {
+                key: "VolumeIndex",
+                reason: "分卷索引从 1 开始".to_string(),
+            });
+        }
+
+        if self.driver_action_mode > 2 {
+            return Err(ConfigError::InvalidValue {
+                key: "DriverActionMode",
+                reason: format!("只能为 0/1/2，实际为 {}", self.driver_action_mode),
+            });
+        }
+
+        if !self.custom_username.is_empty() {
+            validate_username(&self.custom_username)?;
+        }
+
+        // NTFS 卷标最长 32 个字符
+        if self.volume_label.chars().count() > 32 {
+            return Err(ConfigError::InvalidValue {
+                key: "VolumeLabel",
+                reason: "卷标不能超过 32 个字符".to_string(),
+            });
+        }
+
+        self.unattend
+            .validate()
+            .map_err(|reason| ConfigError::InvalidValue { key: "Unattend", reason })?;
+
+        for hook in &self.script_hooks {
+            crate::core::script_hooks::validate_file_name(&hook.file)
+                .map_err(|reason| ConfigError::InvalidValue { key: "ScriptHooks", reason })?;
+        }
+
+        self.health_check
+            .validate()
+            .map_err(|reason| ConfigError::InvalidValue { key: "HealthCheck", reason })?;
+
+        Ok(())
+    }
+}
+
+/// 系统备份配置（用于PE环境内备份）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct BackupConfig {
+    /// 配置结构版本（0 表示从旧版 INI 配置解析而来）
+    pub schema_version: u32,
+    /// 备份保存路径（相对路径）
+    pub save_path: String,
+    /// 备份名称
+    pub name: String,
+    /// 备份描述
+    pub description: String,
+    /// 源分区盘符
+    pub source_partition: String,
+    /// 是否增量备份
+    pub incremental: bool,
+    /// 备份格式: 0=WIM, 1=ESD, 2=SWM, 3=GHO
+    pub format: u8,
+    /// SWM分卷大小（MB）
+    pub swm_split_size: u32,
+    /// 捕获选项（压缩方式、排除列表），旧版配置没有此项时使用默认值
+    pub capture: crate::core::capture_options::CaptureOptions,
+    /// 捕获镜像使用的后端（桌面端设置），PE 中找不到 wimlib.dll 时回退到 wimgapi
+    pub image_backend: crate::core::wimlib::ImageBackend,
+
+    /// 当前版本不认识的字段（由更新的桌面端/PE写入），读写时原样保留
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl BackupConfig {
+    /// 备份格式
+    pub fn backup_format(&self) -> BackupFormat {
+        BackupFormat::from_u8(self.format)
+    }
+
+    /// 校验配置内容，返回第一个发现的问题
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        validate_partition("SourcePartition", &self.source_partition)?;
+
+        if self.save_path.trim().is_empty() {
+            return Err(ConfigError::Missing("SavePath"));
+        }
+        if self.name.trim().is_empty() {
+            return Err(ConfigError::Missing("Name"));
+        }
+
+        if self.format > 3 {
+            return Err(ConfigError::InvalidValue {
+                key: "Format",
+                reason: format!("只能为 0(WIM)/1(ESD)/2(SWM)/3(GHO)，实际为 {}", self.format),
+            });
+        }
+
+        // SWM 分卷过小会产生成百上千个分卷文件
+        if self.backup_format() == BackupFormat::Swm && self.swm_split_size < 100 {
+            return Err(ConfigError::InvalidValue {
+                key: "SwmSplitSize",
+                reason: format!("分卷大小至少为 100MB，实际为 {}MB", self.swm_split_size),
+            });
+        }
+
+        self.capture
+            .validate()
+            .map_err(|reason| ConfigError::InvalidValue { key: "Capture", reason })?;
+
+        Ok(())
+    }
+}
+
+/// 校验盘符格式（如 "C:"）
+fn validate_partition(key: &'static str, value: &str) -> std::result::Result<(), ConfigError> {
+    if value.is_empty() {
+        return Err(ConfigError::Missing(key));
+    }
+
+    let mut chars = value.chars();
+    let valid = matches!(
+        (chars.next(), chars.next(), chars.next()),
+        (Some(letter), Some(':'), None) if letter.is_ascii_alphabetic()
+    );
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidValue {
+            key,
+            reason: format!("应为 \"X:\" 形式的盘符，实际为 {}", value),
+        })
+    }
+}
+
+/// 校验本地账户用户名（规则与 Windows 本地账户一致）
+fn validate_username(username: &str) -> std::result::Result<(), ConfigError> {
+    const INVALID_CHARS: &[char] = &[
+        '"', '/', '\\', '[', ']', ':', ';', '|', '=', ',', '+', '*', '?', '<', '>', '@',
+    ];
+
+    if username.chars().count() > 20 {
+        return Err(ConfigError::InvalidValue {
+            key: "CustomUsername",
+            reason: "用户名不能超过 20 个字符".to_string(),
+        });
+    }
+
+    if let Some(c) = username.chars().find(|c| INVALID_CHARS.contains(c) || c.is_control()) {
+        return Err(ConfigError::InvalidValue {
+            key: "CustomUsername",
+            reason: format!("用户名包含非法字符 '{}'", c),
+        });
+    }
+
+    if username.trim_end_matches('.').trim().is_empty() {
+        return Err(ConfigError::InvalidValue {
+            key: "CustomUsername",
+            reason: "用户名不能只包含空格或句点".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_install_config() -> InstallConfig {
+        InstallConfig {
+            schema_version: CONFIG_SCHEMA_VERSION,
+            driver_action_mode: 2,
+            volume_index: 3,
+            target_partition: "C:".to_string(),
+            image_path: "install.wim".to_string(),
+            custom_username: "Admin".to_string(),
+            script_hooks: vec![crate::core::script_hooks::ScriptHook {
+                file: "tweaks.ps1".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_values() {
+        let mut config = sample_install_config();
+        config.target_partition = "C:\\Windows".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "TargetPartition", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.volume_index = 0;
+        assert!(config.validate().is_err());
+
+        // GHO 镜像不使用分卷索引
+        config.is_gho = true;
+        assert!(config.validate().is_ok());
+
+        // 使用网络镜像源时不要求 ImagePath
+        let mut config = sample_install_config();
+        config.image_path.clear();
+        assert!(matches!(config.validate(), Err(ConfigError::Missing("ImagePath"))));
+        config.image_source = "\\\\deploy\\images\\install.wim".to_string();
+        assert!(config.validate().is_ok());
+        config.image_source = "netcast://?key=0123abcd".to_string();
+        assert!(config.validate().is_ok());
+        config.image_source =
+            "http://deploy.lab/install.wim#sha256=9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_string();
+        assert!(config.validate().is_ok());
+        config.image_source = "http://deploy.lab/install.wim".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "ImageSource", .. })
+        ));
+        config.image_source = "netcast://".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "ImageSource", .. })
+        ));
+        config.image_source = "D:\\install.wim".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "ImageSource", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.image_path = "D:\\install.wim".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "ImagePath", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.driver_action_mode = 3;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "DriverActionMode", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.custom_username = "a/b".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "CustomUsername", .. })
+        ));
+        config.custom_username = " .".to_string();
+        assert!(config.validate().is_err());
+
+        let mut config = sample_install_config();
+        config.script_hooks[0].file = "..\\setup.cmd".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "ScriptHooks", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.unattend.computer_name = "PC_01".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "Unattend", .. })
+        ));
+
+        let mut config = sample_install_config();
+        config.health_check.report_url = "report.example.com".to_string();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidValue { key: "HealthCheck", .. })
+        ));
+
+        let backup = BackupConfig {
+            source_partition: "C:".to_string(),
+            save_path: "D:\\backup.swm".to_string(),
+            name: "backup".to_string(),
+            format: 2,
+            swm_split_size: 0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            backup.validate(),
+            Err(ConfigError::InvalidValue { key: "SwmSplitSize", .. })
+        ));
+    }
+
+    #[test]
+    fn test_driver_action() {
+        let mut config = sample_install_config();
+        assert!(config.should_import_drivers() && config.has_driver_data());
+        config.driver_action_mode = 1;
+        assert!(!config.should_import_drivers() && config.has_driver_data());
+
+        // 旧版配置只有 RestoreDrivers
+        config.driver_action_mode = 0;
+        assert!(!config.should_import_drivers() && !config.has_driver_data());
+        config.restore_drivers = true;
+        assert!(config.should_import_drivers() && config.has_driver_data());
+    }
+
+    #[test]
+    fn test_backup_format() {
+        let mut backup = BackupConfig {
+            source_partition: "C:".to_string(),
+            save_path: "D:\\backup.wim".to_string(),
+            name: "backup".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(backup.backup_format(), BackupFormat::Wim);
+        assert!(backup.validate().is_ok());
+
+        backup.format = 3;
+        assert_eq!(backup.backup_format(), BackupFormat::Gho);
+        backup.format = 4;
+        assert!(matches!(backup.validate(), Err(ConfigError::InvalidValue { key: "Format", .. })));
+
+        backup.format = 0;
+        backup.name.clear();
+        assert!(matches!(backup.validate(), Err(ConfigError::Missing("Name"))));
+    }
+}
@@ -0,0 +1,154 @@
+//! 磁盘写入缓存与 TRIM 策略
+//!
+//! TRIM 通过离线注册表直接启用；写入缓存是按磁盘设备保存的设置，新系统的设备实例在首次启动时才生成，
+//! 因此生成 PowerShell 脚本由 SetupComplete.cmd 在首次启动时执行，对每块非 USB 磁盘应用策略，
+//! 同时把 `fsutil behavior query DisableDeleteNotify` 的结果写入日志以确认 TRIM 已启用。
+//! 脚本只使用 PowerShell 2.0 的命令（WMI 枚举磁盘），Windows 7 上也能执行。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::core::offline_registry::{OfflineHive, OfflineRegistrySession};
+use crate::core::script_hooks;
+
+/// 首次启动时执行的磁盘策略脚本
+pub const DISK_POLICY_SCRIPT: &str = "disk_policy.ps1";
+
+/// 磁盘写入缓存策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WriteCachePolicy {
+    /// 保持系统默认
+    #[default]
+    Unchanged,
+    /// 启用写入缓存
+    Enabled,
+    /// 启用写入缓存并关闭缓冲区刷新（仅适合有掉电保护的设备）
+    EnabledNoFlush,
+    /// 禁用写入缓存
+    Disabled,
+}
+
+impl WriteCachePolicy {
+    /// 全部策略（按显示顺序）
+    pub const ALL: [WriteCachePolicy; 4] = [
+        WriteCachePolicy::Unchanged,
+        WriteCachePolicy::Enabled,
+        WriteCachePolicy::EnabledNoFlush,
+        WriteCachePolicy::Disabled,
+    ];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            WriteCachePolicy::Unchanged => "保持默认",
+            WriteCachePolicy::Enabled => "启用写入缓存",
+            WriteCachePolicy::EnabledNoFlush => "启用写入缓存并关闭缓冲区刷新",
+            WriteCachePolicy::Disabled => "禁用写入缓存",
+        }
+    }
+
+    /// 写入 Device Parameters\Disk 的值（UserWriteCacheSetting, CacheIsPowerProtected）
+    fn registry_values(&self) -> Option<(u32, u32)> {
+        match self {
+            WriteCachePolicy::Unchanged => None,
+            WriteCachePolicy::Enabled => Some((1, 0)),
+            WriteCachePolicy::EnabledNoFlush => Some((1, 1)),
+            WriteCachePolicy::Disabled => Some((0, 0)),
+        }
+    }
+}
+
+/// 生成首次启动时执行的磁盘策略脚本，执行后删除自身
+pub fn generate_script(write_cache: WriteCachePolicy, ensure_trim: bool) -> String {
+    let mut lines: Vec<String> = vec![
+        "# LetRecovery: apply disk write caching and TRIM policy".to_string(),
+        "$log = \"$env:SystemRoot\\Temp\\LetRecovery_DiskPolicy.log\"".to_string(),
+    ];
+    if ensure_trim {
+        lines.push("fsutil behavior set DisableDeleteNotify 0 | Out-File -Append $log".to_string());
+        lines.push("fsutil behavior query DisableDeleteNotify | Out-File -Append $log".to_string());
+    }
+    if let Some((user_setting, power_protected)) = write_cache.registry_values() {
+        lines.push(
+            "Get-WmiObject Win32_DiskDrive | Where-Object { $_.InterfaceType -ne 'USB' -and $_.PNPDeviceID -notlike 'USBSTOR*' } | ForEach-Object {"
+                .to_string(),
+        );
+        lines.push(
+            "    $key = \"HKLM:\\SYSTEM\\CurrentControlSet\\Enum\\$($_.PNPDeviceID)\\Device Parameters\\Disk\"".to_string(),
+        );
+        lines.push("    if (-not (Test-Path $key)) { New-Item -Path $key -Force | Out-Null }".to_string());
+        lines.push(format!(
+            "    Set-ItemProperty -Path $key -Name UserWriteCacheSetting -Type DWord -Value {}",
+            user_setting
+        ));
+        lines.push(format!(
+            "    Set-ItemProperty -Path $key -Name CacheIsPowerProtected -Type DWord -Value {}",
+            power_protected
+        ));
+        lines.push(format!(
+            "    \"$($_.Caption): {}\" | Out-File -Append $log",
+            write_cache.label()
+        ));
+        lines.push("}".to_string());
+    }
+    lines.push("Remove-Item -Force $MyInvocation.MyCommand.Path".to_string());
+    lines.push(String::new());
+    lines.join("\r\n")
+}
+
+/// 离线启用 TRIM（需已加载 SYSTEM 配置单元）
+pub fn enable_trim_offline(registry: &OfflineRegistrySession) -> anyhow::Result<()> {
+    registry.set_dword(
+        OfflineHive::System,
+        "ControlSet001\\Control\\FileSystem",
+        "DisableDeleteNotification",
+        0,
+    )
+}
+
+/// 将磁盘策略脚本安装到目标系统，由 SetupComplete.cmd 在系统部署完成后执行
+pub fn install_script(target_partition: &str, script: &str) -> anyhow::Result<()> {
+    let scripts_dir = format!("{}\\Windows\\Setup\\Scripts", target_partition);
+    std::fs::create_dir_all(&scripts_dir).context("创建 Setup\\Scripts 目录失败")?;
+
+    let script_path = format!("{}\\{}", scripts_dir, DISK_POLICY_SCRIPT);
+    std::fs::write(&script_path, script).context("写入磁盘策略脚本失败")?;
+
+    script_hooks::append_setup_complete(
+        target_partition,
+        &[format!(
+            "powershell -NoProfile -ExecutionPolicy Bypass -File \"%~dp0{}\"",
+            DISK_POLICY_SCRIPT
+        )],
+    )?;
+
+    log::info!("[DISK POLICY] 已安装磁盘策略脚本: {}", script_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_script_trim_only() {
+        let script = generate_script(WriteCachePolicy::Unchanged, true);
+        assert!(script.contains("fsutil behavior query DisableDeleteNotify"));
+        assert!(!script.contains("UserWriteCacheSetting"));
+        assert!(script.ends_with("Remove-Item -Force $MyInvocation.MyCommand.Path\r\n"));
+    }
+
+    #[test]
+    fn test_generate_script_write_cache() {
+        let script = generate_script(WriteCachePolicy::EnabledNoFlush, false);
+        assert!(!script.contains("fsutil"));
+        assert!(script.contains("-Name UserWriteCacheSetting -Type DWord -Value 1"));
+        assert!(script.contains("-Name CacheIsPowerProtected -Type DWord -Value 1"));
+        assert!(script.contains("USBSTOR*"));
+        // Windows 7 自带的 PowerShell 2.0 没有 Get-PnpDevice
+        assert!(script.contains("Get-WmiObject Win32_DiskDrive"));
+        assert!(!script.contains("Get-PnpDevice"));
+    }
+}
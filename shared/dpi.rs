@@ -0,0 +1,99 @@
+//! DPI 缩放
+//!
+//! 程序清单已声明按显示器 DPI 感知，正常系统中由 eframe 按系统缩放比例绘制。
+//! PE 环境没有显示缩放设置，始终按 96 DPI（100%）运行，窗口在 4K 屏上小得无法使用，
+//! 此时按屏幕物理分辨率估算缩放比例，同时放大窗口和界面。正常系统中尊重用户选择的缩放比例。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::sync::OnceLock;
+
+/// 估算缩放比例时的基准屏幕高度（1080p 对应 100%）
+const BASE_SCREEN_HEIGHT: f32 = 1080.0;
+
+/// 最大缩放比例
+const MAX_SCALE: f32 = 3.0;
+
+/// 界面额外缩放比例（进程内缓存）
+static ZOOM: OnceLock<f32> = OnceLock::new();
+
+/// 系统显示缩放比例（1.0 = 100%）
+#[cfg(windows)]
+pub fn system_scale() -> f32 {
+    use windows::Win32::UI::HiDpi::GetDpiForSystem;
+
+    let dpi = unsafe { GetDpiForSystem() };
+    if dpi == 0 {
+        1.0
+    } else {
+        dpi as f32 / 96.0
+    }
+}
+
+#[cfg(not(windows))]
+pub fn system_scale() -> f32 {
+    1.0
+}
+
+/// 主显示器物理高度（像素）
+#[cfg(windows)]
+pub fn primary_screen_height() -> u32 {
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CYSCREEN};
+
+    unsafe { GetSystemMetrics(SM_CYSCREEN) }.max(0) as u32
+}
+
+#[cfg(not(windows))]
+pub fn primary_screen_height() -> u32 {
+    0
+}
+
+/// 按屏幕高度估算缩放比例，取 25% 的整数倍
+pub fn scale_for_screen_height(height: u32) -> f32 {
+    let scale = (height as f32 / BASE_SCREEN_HEIGHT * 4.0).round() / 4.0;
+    scale.clamp(1.0, MAX_SCALE)
+}
+
+/// 计算界面额外缩放比例，需在创建窗口前调用
+///
+/// `estimate` 为 false（非PE环境）或系统已设置缩放时为 1.0（由 eframe 处理），否则按屏幕分辨率估算；
+/// 结果在进程内缓存，之后的调用不再改变结果，保证窗口大小和界面缩放使用同一个值
+pub fn init(estimate: bool) -> f32 {
+    *ZOOM.get_or_init(|| {
+        let system = system_scale();
+        let zoom = if !estimate || system > 1.0 {
+            1.0
+        } else {
+            scale_for_screen_height(primary_screen_height())
+        };
+        log::info!("系统缩放: {:.0}%，界面缩放: {:.0}%", system * 100.0, zoom * 100.0);
+        zoom
+    })
+}
+
+/// 界面额外缩放比例（未调用 [`init`] 时按屏幕分辨率估算）
+pub fn ui_zoom() -> f32 {
+    init(true)
+}
+
+/// 按界面缩放比例换算窗口大小
+pub fn scaled_size(size: [f32; 2]) -> [f32; 2] {
+    let zoom = ui_zoom();
+    [size[0] * zoom, size[1] * zoom]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scale_for_screen_height() {
+        assert_eq!(scale_for_screen_height(0), 1.0);
+        assert_eq!(scale_for_screen_height(768), 1.0);
+        assert_eq!(scale_for_screen_height(1080), 1.0);
+        assert_eq!(scale_for_screen_height(1440), 1.25);
+        assert_eq!(scale_for_screen_height(1600), 1.5);
+        assert_eq!(scale_for_screen_height(2160), 2.0);
+        assert_eq!(scale_for_screen_height(4320), 3.0);
+    }
+}
@@ -0,0 +1,89 @@
+//! 首次启动放宽驱动签名强制
+//!
+//! `nointegritychecks` 只在 Vista 上生效，Windows 7 起引导程序忽略该设置，
+//! 因此 Windows 7 及以上改为开启测试签名模式（可加载自签名、测试签名的驱动，开启安全启动时无效）。
+//! 首次启动完成后由 SetupComplete.cmd 调用恢复脚本删除对应的 BCD 设置。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+
+use crate::core::script_hooks;
+
+/// 首次启动后恢复驱动签名强制的脚本（由 SetupComplete.cmd 调用）
+pub const INTEGRITY_RESTORE_SCRIPT: &str = "restore_integrity_checks.cmd";
+
+/// Windows 7 的版本号，从该版本起 `nointegritychecks` 不再生效
+const WIN7_BUILD: u32 = 7600;
+
+/// 放宽驱动签名强制使用的 BCD 设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SigningOverride {
+    /// 关闭完整性检查（Vista）
+    NoIntegrityChecks,
+    /// 测试签名模式（Windows 7 及以上）
+    TestSigning,
+}
+
+impl SigningOverride {
+    /// 按目标系统版本号选择，无法读取版本时按 Windows 7 及以上处理
+    pub fn for_build(build: Option<u32>) -> Self {
+        match build {
+            Some(build) if build < WIN7_BUILD => Self::NoIntegrityChecks,
+            _ => Self::TestSigning,
+        }
+    }
+
+    /// BCD 元素名
+    pub fn element(&self) -> &'static str {
+        match self {
+            Self::NoIntegrityChecks => "nointegritychecks",
+            Self::TestSigning => "testsigning",
+        }
+    }
+}
+
+/// 生成恢复驱动签名强制的脚本，执行后删除自身
+fn restore_script(mode: SigningOverride) -> String {
+    [
+        "@echo off".to_string(),
+        "rem LetRecovery: restore driver signature enforcement after first boot".to_string(),
+        format!("bcdedit /deletevalue {{current}} {} >nul 2>&1", mode.element()),
+        "del /f /q \"%~f0\"".to_string(),
+        String::new(),
+    ]
+    .join("\r\n")
+}
+
+/// 写入恢复脚本并接入目标系统的 SetupComplete.cmd
+pub fn stage_restore(windows_partition: &str, mode: SigningOverride) -> Result<()> {
+    let scripts_dir = format!("{}\\Windows\\Setup\\Scripts", windows_partition);
+    std::fs::create_dir_all(&scripts_dir).context("创建 Setup\\Scripts 目录失败")?;
+    std::fs::write(format!("{}\\{}", scripts_dir, INTEGRITY_RESTORE_SCRIPT), restore_script(mode))
+        .context("写入驱动签名恢复脚本失败")?;
+    script_hooks::append_setup_complete(
+        windows_partition,
+        &[format!("call \"%~dp0{}\"", INTEGRITY_RESTORE_SCRIPT)],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_build() {
+        assert_eq!(SigningOverride::for_build(Some(6002)), SigningOverride::NoIntegrityChecks);
+        assert_eq!(SigningOverride::for_build(Some(7601)), SigningOverride::TestSigning);
+        assert_eq!(SigningOverride::for_build(Some(22631)), SigningOverride::TestSigning);
+        assert_eq!(SigningOverride::for_build(None), SigningOverride::TestSigning);
+    }
+
+    #[test]
+    fn test_restore_script() {
+        let script = restore_script(SigningOverride::TestSigning);
+        assert!(script.contains("bcdedit /deletevalue {current} testsigning >nul 2>&1"));
+        assert!(script.ends_with("del /f /q \"%~f0\"\r\n"));
+        assert!(restore_script(SigningOverride::NoIntegrityChecks).contains("{current} nointegritychecks"));
+    }
+}
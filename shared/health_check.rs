@@ -0,0 +1,233 @@
+//! 首次启动健康检查
+//!
+//! 在目标系统中放置一次性的 PowerShell 检查脚本，由 SetupComplete.cmd 注册为计划任务，
+//! 首个用户登录后以 SYSTEM 身份运行：检查网络、激活状态、设备管理器中的问题设备和磁盘健康，
+//! 把 JSON 报告写入数据分区的 LetRecovery_Reports 文件夹（找不到时写入系统盘），
+//! 配置了上传地址时同时 POST 到该地址，完成后删除计划任务和脚本本身。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::script_hooks;
+
+/// 报告文件夹名（位于分区根目录）
+pub const REPORTS_DIR: &str = "LetRecovery_Reports";
+/// 目标系统中存放检查脚本的文件夹（位于 Setup\Scripts 下）
+const AGENT_DIR: &str = "LetRecovery_Health";
+const AGENT_SCRIPT: &str = "healthcheck.ps1";
+/// 计划任务名
+const TASK_NAME: &str = "LetRecoveryHealthCheck";
+/// 登录后延迟运行，等待驱动和网络就绪（schtasks 的 mmmm:ss 格式）
+const TASK_DELAY: &str = "0001:00";
+/// 脚本中待替换的上传地址
+const URL_PLACEHOLDER: &str = "{{REPORT_URL}}";
+
+/// 检查脚本模板（兼容 Windows 7 自带的 PowerShell 2.0）
+const AGENT_TEMPLATE: &str = r#"# LetRecovery 首次启动健康检查，运行一次后自动删除
+$ErrorActionPreference = 'SilentlyContinue'
+$TaskName = 'LetRecoveryHealthCheck'
+$ReportDirName = 'LetRecovery_Reports'
+$ReportUrl = '{{REPORT_URL}}'
+
+function Esc([string]$s) {
+    $s = $s -replace '\\', '\\' -replace '"', '\"' -replace "`r", '\r' -replace "`n", '\n' -replace "`t", '\t'
+    return '"' + $s + '"'
+}
+
+function Bool($b) {
+    if ($b) { return 'true' } else { return 'false' }
+}
+
+# 网络：最多等待 3 分钟
+$networkAvailable = $false
+$internet = $false
+for ($i = 0; $i -lt 18 -and -not $internet; $i++) {
+    $networkAvailable = [System.Net.NetworkInformation.NetworkInterface]::GetIsNetworkAvailable()
+    if ($networkAvailable) {
+        try { [void][System.Net.Dns]::GetHostAddresses('www.msftconnecttest.com'); $internet = $true } catch { }
+    }
+    if (-not $internet) { Start-Sleep -Seconds 10 }
+}
+
+# 激活状态
+$statusNames = @('Unlicensed', 'Licensed', 'OOBGrace', 'OOTGrace', 'NonGenuineGrace', 'Notification', 'ExtendedGrace')
+$activation = 'Unknown'
+$license = Get-WmiObject SoftwareLicensingProduct -Filter "ApplicationID='55c92734-d682-4d71-983e-d6ec3f16059f' AND PartialProductKey IS NOT NULL" | Select-Object -First 1
+if ($license) { $activation = $statusNames[[int]$license.LicenseStatus] }
+
+# 设备管理器中有问题的设备
+$devices = @(Get-WmiObject Win32_PnPEntity | Where-Object { $_.ConfigManagerErrorCode -ne 0 })
+$deviceJson = @($devices | ForEach-Object {
+    '{"name":' + (Esc $_.Name) + ',"id":' + (Esc $_.DeviceID) + ',"code":' + [int]$_.ConfigManagerErrorCode + '}'
+}) -join ','
+
+# 磁盘健康
+$smartFailures = @(Get-WmiObject -Namespace root\wmi -Class MSStorageDriver_FailurePredictStatus | Where-Object { $_.PredictFailure })
+$diskJson = @(Get-WmiObject Win32_DiskDrive | ForEach-Object {
+    '{"model":' + (Esc $_.Model) + ',"status":' + (Esc $_.Status) + ',"sizeGB":' + [math]::Round($_.Size / 1GB) + '}'
+}) -join ','
+
+$os = Get-WmiObject Win32_OperatingSystem
+$healthy = $internet -and $activation -eq 'Licensed' -and $devices.Count -eq 0 -and $smartFailures.Count -eq 0
+$json = '{"computer":' + (Esc $env:COMPUTERNAME) +
+    ',"time":' + (Esc (Get-Date -Format 'yyyy-MM-dd HH:mm:ss')) +
+    ',"os":' + (Esc $os.Caption) +
+    ',"version":' + (Esc $os.Version) +
+    ',"healthy":' + (Bool $healthy) +
+    ',"networkAvailable":' + (Bool $networkAvailable) +
+    ',"internet":' + (Bool $internet) +
+    ',"activation":' + (Esc $activation) +
+    ',"problemDevices":[' + $deviceJson + ']' +
+    ',"disks":[' + $diskJson + ']' +
+    ',"smartFailures":' + $smartFailures.Count + '}'
+
+# 写入报告：优先数据分区上安装时创建的报告文件夹
+$reportDir = $null
+foreach ($disk in Get-WmiObject Win32_LogicalDisk -Filter 'DriveType=3') {
+    $candidate = $disk.DeviceID + '\' + $ReportDirName
+    if (Test-Path $candidate) { $reportDir = $candidate; break }
+}
+if (-not $reportDir) {
+    $reportDir = $env:SystemDrive + '\' + $ReportDirName
+    New-Item -ItemType Directory -Path $reportDir -Force | Out-Null
+}
+$reportFile = Join-Path $reportDir ($env:COMPUTERNAME + '_' + (Get-Date -Format 'yyyyMMdd_HHmmss') + '.json')
+[System.IO.File]::WriteAllText($reportFile, $json, (New-Object System.Text.UTF8Encoding $false))
+
+if ($ReportUrl) {
+    try {
+        $client = New-Object System.Net.WebClient
+        $client.Encoding = [System.Text.Encoding]::UTF8
+        $client.Headers.Add('Content-Type', 'application/json; charset=utf-8')
+        [void]$client.UploadString($ReportUrl, 'POST', $json)
+    } catch { }
+}
+
+# 清理：删除计划任务和脚本目录
+schtasks /Delete /TN $TaskName /F | Out-Null
+Remove-Item -Path (Split-Path -Parent $MyInvocation.MyCommand.Path) -Recurse -Force
+"#;
+
+/// 首次启动健康检查设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct HealthCheckSettings {
+    /// 是否安装检查脚本
+    pub enabled: bool,
+    /// 报告上传地址（为空表示只写入报告文件）
+    pub report_url: String,
+}
+
+impl HealthCheckSettings {
+    /// 校验上传地址
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let url = self.report_url.trim();
+        if url.is_empty() {
+            return Ok(());
+        }
+        let lower = url.to_ascii_lowercase();
+        if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            return Err(format!("上传地址必须以 http:// 或 https:// 开头: {}", url));
+        }
+        if url.chars().any(|c| c.is_control()) {
+            return Err("上传地址不能包含控制字符".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 生成检查脚本（上传地址写入 PowerShell 单引号字符串）
+pub fn agent_script(report_url: &str) -> String {
+    let quoted = report_url.trim().replace('\'', "''");
+    AGENT_TEMPLATE
+        .replace(URL_PLACEHOLDER, &quoted)
+        .replace('\n', "\r\n")
+}
+
+/// SetupComplete.cmd 中注册计划任务的命令
+fn register_task_line() -> String {
+    format!(
+        "schtasks /Create /TN \"{}\" /SC ONLOGON /DELAY {} /RU SYSTEM /RL HIGHEST /F \
+         /TR \"powershell -NoProfile -ExecutionPolicy Bypass -File %SystemRoot%\\Setup\\Scripts\\{}\\{}\"",
+        TASK_NAME, TASK_DELAY, AGENT_DIR, AGENT_SCRIPT
+    )
+}
+
+/// 把健康检查脚本安装到目标系统，未启用时返回 false
+///
+/// `report_partition` 为保存报告的分区（通常是数据分区），会在其根目录预先创建报告文件夹，
+/// 检查脚本据此找到该分区；为 None 时报告写入新系统的系统盘。
+pub fn install_agent(
+    target_partition: &str,
+    report_partition: Option<&str>,
+    settings: &HealthCheckSettings,
+) -> Result<bool> {
+    if !settings.enabled {
+        return Ok(false);
+    }
+    settings.validate().map_err(anyhow::Error::msg)?;
+
+    let agent_dir = format!("{}\\Windows\\Setup\\Scripts\\{}", target_partition, AGENT_DIR);
+    std::fs::create_dir_all(&agent_dir).context("创建健康检查脚本目录失败")?;
+    // 带 BOM，避免 Windows PowerShell 按 ANSI 读取中文注释
+    let script = format!("\u{feff}{}", agent_script(&settings.report_url));
+    std::fs::write(format!("{}\\{}", agent_dir, AGENT_SCRIPT), script).context("写入健康检查脚本失败")?;
+
+    script_hooks::append_setup_complete(target_partition, &[register_task_line()])?;
+
+    if let Some(partition) = report_partition {
+        let reports_dir = format!("{}\\{}", partition.trim_end_matches('\\'), REPORTS_DIR);
+        if let Err(e) = std::fs::create_dir_all(&reports_dir) {
+            log::warn!("[HEALTH] 创建报告文件夹失败: {} - {}", reports_dir, e);
+        }
+    }
+
+    log::info!("[HEALTH] 已安装首次启动健康检查");
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_report_url() {
+        let mut settings = HealthCheckSettings {
+            enabled: true,
+            report_url: String::new(),
+        };
+        assert!(settings.validate().is_ok());
+        settings.report_url = "https://example.com/report".to_string();
+        assert!(settings.validate().is_ok());
+        settings.report_url = "ftp://example.com".to_string();
+        assert!(settings.validate().is_err());
+        settings.report_url = "http://example.com/\nx".to_string();
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_agent_script_quotes_url() {
+        let script = agent_script(" http://host/it's ");
+        assert!(script.contains("$ReportUrl = 'http://host/it''s'\r\n"));
+        assert!(!script.contains(URL_PLACEHOLDER));
+        assert!(!script.replace("\r\n", "").contains('\n'));
+
+        assert!(agent_script("").contains("$ReportUrl = ''\r\n"));
+    }
+
+    #[test]
+    fn test_agent_script_matches_constants() {
+        let script = agent_script("");
+        assert!(script.contains(&format!("$TaskName = '{}'", TASK_NAME)));
+        assert!(script.contains(&format!("$ReportDirName = '{}'", REPORTS_DIR)));
+    }
+
+    #[test]
+    fn test_register_task_line() {
+        let line = register_task_line();
+        assert!(line.starts_with("schtasks /Create /TN \"LetRecoveryHealthCheck\" /SC ONLOGON"));
+        assert!(line.ends_with("%SystemRoot%\\Setup\\Scripts\\LetRecovery_Health\\healthcheck.ps1\""));
+    }
+}
@@ -0,0 +1,287 @@
+//! 目标系统语言检测
+//!
+//! 从已释放系统的 SYSTEM 配置单元读取安装语言（`Control\Nls\Language` 的 InstallLanguage，
+//! 与 WIM XML 中 LANGUAGES/DEFAULT 一致），读不到时才按 `Windows\servicing\Packages`
+//! 中唯一的语言包清单判断。生成 unattend.xml、首次登录脚本和默认账户名时按该语言本地化，
+//! GHO 等没有 WIM 元数据的镜像同样适用。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::PathBuf;
+
+use crate::core::offline_registry::{OfflineHive, OfflineRegistrySession};
+
+/// 安装语言所在的键（相对 SYSTEM 配置单元）
+const NLS_LANGUAGE_KEY: &str = "ControlSet001\\Control\\Nls\\Language";
+
+/// 常见语言的 LCID 与语言标记
+const LCID_TAGS: [(u16, &str); 38] = [
+    (0x0401, "ar-SA"),
+    (0x0402, "bg-BG"),
+    (0x0404, "zh-TW"),
+    (0x0405, "cs-CZ"),
+    (0x0406, "da-DK"),
+    (0x0407, "de-DE"),
+    (0x0408, "el-GR"),
+    (0x0409, "en-US"),
+    (0x040B, "fi-FI"),
+    (0x040C, "fr-FR"),
+    (0x040D, "he-IL"),
+    (0x040E, "hu-HU"),
+    (0x0410, "it-IT"),
+    (0x0411, "ja-JP"),
+    (0x0412, "ko-KR"),
+    (0x0413, "nl-NL"),
+    (0x0414, "nb-NO"),
+    (0x0415, "pl-PL"),
+    (0x0416, "pt-BR"),
+    (0x0418, "ro-RO"),
+    (0x0419, "ru-RU"),
+    (0x041A, "hr-HR"),
+    (0x041B, "sk-SK"),
+    (0x041D, "sv-SE"),
+    (0x041E, "th-TH"),
+    (0x041F, "tr-TR"),
+    (0x0422, "uk-UA"),
+    (0x0424, "sl-SI"),
+    (0x0425, "et-EE"),
+    (0x0426, "lv-LV"),
+    (0x0427, "lt-LT"),
+    (0x042A, "vi-VN"),
+    (0x0804, "zh-CN"),
+    (0x0809, "en-GB"),
+    (0x080A, "es-MX"),
+    (0x0816, "pt-PT"),
+    (0x0C04, "zh-HK"),
+    (0x0C0A, "es-ES"),
+];
+
+/// 未识别语言时使用的默认账户名
+const FALLBACK_USERNAME: &str = "User";
+
+/// 目标系统语言
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetLanguage {
+    /// 语言标记（如 zh-CN、en-US）
+    pub tag: String,
+}
+
+impl TargetLanguage {
+    pub fn new(tag: &str) -> Self {
+        Self { tag: tag.to_string() }
+    }
+
+    /// 检测已释放到目标分区的系统语言，无法确定时返回 None
+    ///
+    /// 需要临时加载目标系统的 SYSTEM 配置单元，已有离线注册表会话时改用 `detect_in`。
+    pub fn detect(target_partition: &str) -> Option<Self> {
+        match OfflineRegistrySession::open(target_partition, &[OfflineHive::System]) {
+            Ok(registry) => Self::detect_in(&registry, target_partition),
+            Err(e) => {
+                log::warn!("[LANGUAGE] 无法加载目标系统注册表: {:#}", e);
+                Self::from_packages(target_partition)
+            }
+        }
+    }
+
+    /// 使用已加载 SYSTEM 配置单元的会话检测系统语言
+    pub fn detect_in(registry: &OfflineRegistrySession, target_partition: &str) -> Option<Self> {
+        Self::from_registry(registry).or_else(|| Self::from_packages(target_partition))
+    }
+
+    /// 读取安装语言（InstallLanguage，缺失时取 Default）
+    fn from_registry(registry: &OfflineRegistrySession) -> Option<Self> {
+        let tag = ["InstallLanguage", "Default"].iter().find_map(|name| {
+            let value = registry.get_string(OfflineHive::System, NLS_LANGUAGE_KEY, name).ok()??;
+            tag_from_lcid(&value)
+        })?;
+        log::info!("[LANGUAGE] 检测到目标系统安装语言: {}", tag);
+        Some(Self::new(tag))
+    }
+
+    /// 按语言包清单判断，只有一个语言包时才能确定默认语言
+    fn from_packages(target_partition: &str) -> Option<Self> {
+        // "C:" 需要补上根目录，否则会被当成 C 盘的当前目录
+        let partition = target_partition.trim_end_matches('\\');
+        let root = if partition.ends_with(':') {
+            PathBuf::from(format!("{}\\", partition))
+        } else {
+            PathBuf::from(partition)
+        };
+        let packages_dir = root.join("Windows").join("servicing").join("Packages");
+        let entries = match std::fs::read_dir(&packages_dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("[LANGUAGE] 无法读取语言包清单: {} - {}", packages_dir.display(), e);
+                return None;
+            }
+        };
+        let mut languages: Vec<String> = entries
+            .flatten()
+            .filter_map(|e| language_from_package(&e.file_name().to_string_lossy()))
+            .collect();
+        languages.sort();
+        languages.dedup();
+
+        match languages.as_slice() {
+            [tag] => {
+                log::info!("[LANGUAGE] 按语言包检测到目标系统语言: {}", tag);
+                Some(Self::new(tag))
+            }
+            [] => None,
+            _ => {
+                log::warn!("[LANGUAGE] 镜像包含多个语言包 {:?}，无法确定默认语言", languages);
+                None
+            }
+        }
+    }
+
+    /// 主语言（如 zh-CN 的 zh）
+    fn primary(&self) -> String {
+        self.tag.split('-').next().unwrap_or_default().to_ascii_lowercase()
+    }
+
+    /// 是否使用繁体中文
+    fn is_traditional_chinese(&self) -> bool {
+        let tag = self.tag.to_ascii_lowercase();
+        tag.starts_with("zh-tw") || tag.starts_with("zh-hk") || tag.starts_with("zh-mo") || tag.contains("hant")
+    }
+
+    /// 本地账户的显示名称
+    pub fn account_display_name(&self) -> &'static str {
+        match self.primary().as_str() {
+            "zh" if self.is_traditional_chinese() => "使用者",
+            "zh" => "用户",
+            "ja" => "ユーザー",
+            "ko" => "사용자",
+            "ru" => "Пользователь",
+            "de" => "Benutzer",
+            "fr" => "Utilisateur",
+            "es" => "Usuario",
+            "it" => "Utente",
+            "nl" => "Gebruiker",
+            "pt" => "Usuário",
+            _ => FALLBACK_USERNAME,
+        }
+    }
+
+    /// 未指定用户名时的默认账户名，同时决定用户文件夹名
+    ///
+    /// 非 ASCII 的显示名称不用作账户名：不少程序仍无法处理含非 ASCII 字符的用户文件夹路径。
+    pub fn default_username(&self) -> &'static str {
+        let name = self.account_display_name();
+        if name.is_ascii() {
+            name
+        } else {
+            FALLBACK_USERNAME
+        }
+    }
+
+    /// 删除 UWP 应用脚本中的提示文字（正在删除, 清理完成）
+    pub fn uwp_script_messages(&self) -> (&'static str, &'static str) {
+        match self.primary().as_str() {
+            "zh" if self.is_traditional_chinese() => ("正在刪除", "UWP 應用程式清理完成"),
+            "zh" => ("正在删除", "UWP应用清理完成"),
+            _ => ("Removing", "UWP app cleanup completed"),
+        }
+    }
+}
+
+/// 将 InstallLanguage 中的十六进制 LCID（如 0804）转换为语言标记
+fn tag_from_lcid(value: &str) -> Option<&'static str> {
+    let lcid = u16::from_str_radix(value.trim(), 16).ok()?;
+    LCID_TAGS.iter().find(|(id, _)| *id == lcid).map(|(_, tag)| *tag)
+}
+
+/// 从语言包清单文件名中提取语言标记
+///
+/// 如 `Microsoft-Windows-Client-LanguagePack-Package~31bf3856ad364e35~amd64~zh-CN~10.0.19041.1.mum`
+fn language_from_package(file_name: &str) -> Option<String> {
+    let lower = file_name.to_ascii_lowercase();
+    if !lower.ends_with(".mum") || !lower.contains("-languagepack-package~") {
+        return None;
+    }
+    let language = file_name.split('~').nth(3)?;
+    if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some(language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_from_package() {
+        assert_eq!(
+            language_from_package("Microsoft-Windows-Client-LanguagePack-Package~31bf3856ad364e35~amd64~zh-CN~10.0.19041.1.mum"),
+            Some("zh-CN".to_string())
+        );
+        assert_eq!(
+            language_from_package("Microsoft-Windows-Server-LanguagePack-Package~31bf3856ad364e35~amd64~en-US~10.0.20348.1.mum"),
+            Some("en-US".to_string())
+        );
+        assert_eq!(
+            language_from_package("Microsoft-Windows-Client-LanguagePack-Package~31bf3856ad364e35~amd64~zh-CN~10.0.19041.1.cat"),
+            None
+        );
+        assert_eq!(
+            language_from_package("Microsoft-Windows-Foundation-Package~31bf3856ad364e35~amd64~~10.0.19041.1.mum"),
+            None
+        );
+        assert_eq!(language_from_package("Microsoft-Windows-Client-LanguagePack-Package~x~amd64~~1.mum"), None);
+    }
+
+    #[test]
+    fn test_tag_from_lcid() {
+        assert_eq!(tag_from_lcid("0804"), Some("zh-CN"));
+        assert_eq!(tag_from_lcid("0409"), Some("en-US"));
+        assert_eq!(tag_from_lcid("0c04"), Some("zh-HK"));
+        assert_eq!(tag_from_lcid(" 0411 "), Some("ja-JP"));
+        assert_eq!(tag_from_lcid("7fff"), None);
+        assert_eq!(tag_from_lcid(""), None);
+    }
+
+    #[test]
+    fn test_from_packages() {
+        let root = std::env::temp_dir().join(format!("lr_lang_{}", std::process::id()));
+        let packages = root.join("Windows").join("servicing").join("Packages");
+        std::fs::create_dir_all(&packages).unwrap();
+        let partition = root.to_string_lossy().to_string();
+        let package = |tag: &str| {
+            format!("Microsoft-Windows-Client-LanguagePack-Package~31bf3856ad364e35~amd64~{}~10.0.19041.1.mum", tag)
+        };
+        assert_eq!(TargetLanguage::from_packages(&partition), None);
+
+        std::fs::write(packages.join(package("zh-CN")), b"").unwrap();
+        assert_eq!(TargetLanguage::from_packages(&partition), Some(TargetLanguage::new("zh-CN")));
+
+        // 多个语言包时无法确定默认语言，不按字母顺序猜测
+        std::fs::write(packages.join(package("en-US")), b"").unwrap();
+        assert_eq!(TargetLanguage::from_packages(&partition), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_localized_names() {
+        let zh = TargetLanguage::new("zh-CN");
+        assert_eq!(zh.account_display_name(), "用户");
+        assert_eq!(zh.default_username(), "User");
+        assert_eq!(TargetLanguage::new("zh-TW").account_display_name(), "使用者");
+
+        let de = TargetLanguage::new("de-DE");
+        assert_eq!(de.default_username(), "Benutzer");
+        // 含非 ASCII 字符的名称只用于显示
+        assert_eq!(TargetLanguage::new("pt-BR").default_username(), "User");
+        assert_eq!(TargetLanguage::new("sv-SE").default_username(), "User");
+    }
+
+    #[test]
+    fn test_uwp_script_messages() {
+        assert_eq!(TargetLanguage::new("zh-CN").uwp_script_messages().0, "正在删除");
+        assert_eq!(TargetLanguage::new("zh-HK").uwp_script_messages().0, "正在刪除");
+        assert_eq!(TargetLanguage::new("en-US").uwp_script_messages().0, "Removing");
+        assert_eq!(TargetLanguage::new("ja-JP").uwp_script_messages().0, "Removing");
+    }
+}
@@ -0,0 +1,423 @@
+//! 安装任务引擎
+//!
+//! 把系统安装拆成明确的步骤（格式化、释放镜像、导入驱动、修复引导、应用选项等），
+//! 每个步骤可以声明依赖的前置步骤、重试策略和回滚操作：
+//! - 步骤失败时按重试策略重新执行
+//! - 可选步骤失败只上报警告，依赖它的后续步骤自动跳过
+//! - 必需步骤最终失败时，按倒序执行已执行步骤（含失败步骤本身）的回滚操作，
+//!   例如恢复修复引导前的 BCD，避免留下无法启动的机器
+//!
+//! 执行过程以 [`EngineEvent`] 上报，由调用方转换为命令行输出、JSON 进度或界面消息。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+
+/// 步骤重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最多执行次数（含第一次）
+    pub max_attempts: u32,
+    /// 两次执行之间的等待时间
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        delay: Duration::ZERO,
+    };
+
+    /// 最多执行 `max_attempts` 次，每次失败后等待 `delay`
+    pub const fn times(max_attempts: u32, delay: Duration) -> Self {
+        Self { max_attempts, delay }
+    }
+}
+
+/// 引擎上报的事件
+#[derive(Debug, Clone)]
+pub enum EngineEvent {
+    /// 开始执行步骤（index 从 0 开始）
+    StepStarted {
+        index: usize,
+        total: usize,
+        id: &'static str,
+        name: &'static str,
+    },
+    /// 当前步骤进度 (0-100)
+    Progress(u8),
+    /// 当前步骤状态
+    Status(String),
+    /// 步骤失败，即将重试
+    Retrying {
+        name: &'static str,
+        attempt: u32,
+        max_attempts: u32,
+        error: String,
+    },
+    /// 依赖的步骤未成功完成，跳过此步骤
+    StepSkipped { name: &'static str, reason: String },
+    /// 可忽略的问题（可选步骤失败等），流程继续
+    Warning { name: &'static str, message: String },
+    /// 开始回滚步骤
+    RollingBack { name: &'static str },
+    /// 回滚失败
+    RollbackFailed { name: &'static str, error: String },
+}
+
+/// 事件回调
+pub type EventSink = Arc<dyn Fn(EngineEvent) + Send + Sync>;
+
+/// 步骤执行上下文，用于上报进度和状态
+pub struct StepContext {
+    name: &'static str,
+    sink: EventSink,
+}
+
+impl StepContext {
+    /// 上报当前步骤进度
+    pub fn progress(&self, percentage: u8) {
+        (self.sink)(EngineEvent::Progress(percentage.min(100)));
+    }
+
+    /// 上报当前步骤状态
+    pub fn status(&self, message: impl Into<String>) {
+        (self.sink)(EngineEvent::Status(message.into()));
+    }
+
+    /// 上报可忽略的问题
+    pub fn warn(&self, message: impl Into<String>) {
+        let message = message.into();
+        log::warn!("[ENGINE] {}: {}", self.name, message);
+        (self.sink)(EngineEvent::Warning { name: self.name, message });
+    }
+
+    /// 事件回调（供进度转发线程使用）
+    pub fn sink(&self) -> EventSink {
+        self.sink.clone()
+    }
+}
+
+type RunFn<'a> = Box<dyn FnMut(&StepContext) -> Result<()> + 'a>;
+type RollbackFn<'a> = Box<dyn FnMut() -> Result<()> + 'a>;
+
+/// 安装步骤
+pub struct Step<'a> {
+    id: &'static str,
+    name: &'static str,
+    required: bool,
+    retry: RetryPolicy,
+    depends_on: Vec<&'static str>,
+    run: RunFn<'a>,
+    rollback: Option<RollbackFn<'a>>,
+}
+
+impl<'a> Step<'a> {
+    /// 创建必需步骤（失败时终止安装并回滚）
+    pub fn new(id: &'static str, name: &'static str, run: impl FnMut(&StepContext) -> Result<()> + 'a) -> Self {
+        Self {
+            id,
+            name,
+            required: true,
+            retry: RetryPolicy::NONE,
+            depends_on: Vec::new(),
+            run: Box::new(run),
+            rollback: None,
+        }
+    }
+
+    /// 标记为可选步骤（失败只上报警告）
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    /// 设置重试策略
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// 声明依赖的前置步骤，前置步骤未成功完成时跳过此步骤
+    pub fn after(mut self, ids: &[&'static str]) -> Self {
+        self.depends_on.extend_from_slice(ids);
+        self
+    }
+
+    /// 设置回滚操作（安装失败时调用，步骤执行失败时也会调用以撤销部分修改）
+    pub fn rollback(mut self, rollback: impl FnMut() -> Result<()> + 'a) -> Self {
+        self.rollback = Some(Box::new(rollback));
+        self
+    }
+}
+
+/// 步骤执行结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    Completed,
+    Skipped,
+    Failed,
+}
+
+/// 安装失败信息
+#[derive(Debug)]
+pub struct EngineFailure {
+    /// 失败步骤标识
+    pub step: &'static str,
+    /// 失败步骤名称
+    pub name: &'static str,
+    pub error: anyhow::Error,
+    /// 回滚是否全部成功
+    pub rolled_back: bool,
+}
+
+impl std::fmt::Display for EngineFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}失败: {:#}", self.name, self.error)
+    }
+}
+
+/// 安装任务引擎
+#[derive(Default)]
+pub struct InstallEngine<'a> {
+    steps: Vec<Step<'a>>,
+}
+
+impl<'a> InstallEngine<'a> {
+    pub fn new() -> Self {
+        Self { steps: Vec::new() }
+    }
+
+    /// 追加步骤
+    ///
+    /// 依赖只能指向已添加的步骤，因此添加顺序即为执行顺序
+    pub fn step(mut self, step: Step<'a>) -> Self {
+        assert!(
+            self.steps.iter().all(|s| s.id != step.id),
+            "重复的安装步骤: {}",
+            step.id
+        );
+        for dep in &step.depends_on {
+            assert!(
+                self.steps.iter().any(|s| s.id == *dep),
+                "安装步骤 {} 依赖未添加的步骤 {}",
+                step.id,
+                dep
+            );
+        }
+        self.steps.push(step);
+        self
+    }
+
+    /// 步骤数量
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// 依次执行全部步骤
+    pub fn run(mut self, on_event: impl Fn(EngineEvent) + Send + Sync + 'static) -> Result<(), EngineFailure> {
+        let sink: EventSink = Arc::new(on_event);
+        let total = self.steps.len();
+        let mut outcomes: HashMap<&'static str, StepOutcome> = HashMap::new();
+        // 已执行（含失败）的步骤，回滚时倒序处理
+        let mut executed: Vec<usize> = Vec::new();
+
+        for index in 0..total {
+            let step = &mut self.steps[index];
+            sink(EngineEvent::StepStarted {
+                index,
+                total,
+                id: step.id,
+                name: step.name,
+            });
+
+            if let Some(dep) = step
+                .depends_on
+                .iter()
+                .find(|dep| outcomes.get(*dep) != Some(&StepOutcome::Completed))
+            {
+                let reason = format!("前置步骤 {} 未完成", dep);
+                log::info!("[ENGINE] 跳过 {}: {}", step.name, reason);
+                sink(EngineEvent::StepSkipped { name: step.name, reason });
+                outcomes.insert(step.id, StepOutcome::Skipped);
+                continue;
+            }
+
+            log::info!("[ENGINE] 开始 {}", step.name);
+            let ctx = StepContext {
+                name: step.name,
+                sink: sink.clone(),
+            };
+            let max_attempts = step.retry.max_attempts.max(1);
+            let mut attempt = 1;
+            let result = loop {
+                match (step.run)(&ctx) {
+                    Ok(()) => break Ok(()),
+                    Err(e) if attempt < max_attempts => {
+                        log::warn!("[ENGINE] {} 第 {} 次执行失败: {:#}", step.name, attempt, e);
+                        sink(EngineEvent::Retrying {
+                            name: step.name,
+                            attempt,
+                            max_attempts,
+                            error: format!("{:#}", e),
+                        });
+                        std::thread::sleep(step.retry.delay);
+                        attempt += 1;
+                    }
+                    Err(e) => break Err(e),
+                }
+            };
+            executed.push(index);
+
+            match result {
+                Ok(()) => {
+                    ctx.progress(100);
+                    outcomes.insert(step.id, StepOutcome::Completed);
+                }
+                Err(e) if !step.required => {
+                    ctx.warn(format!("{:#}", e));
+                    outcomes.insert(step.id, StepOutcome::Failed);
+                }
+                Err(error) => {
+                    log::error!("[ENGINE] {} 失败: {:#}", step.name, error);
+                    let (step_id, name) = (step.id, step.name);
+                    let rolled_back = self.roll_back(&executed, &sink);
+                    return Err(EngineFailure {
+                        step: step_id,
+                        name,
+                        error,
+                        rolled_back,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 倒序执行回滚操作，返回是否全部成功
+    fn roll_back(&mut self, executed: &[usize], sink: &EventSink) -> bool {
+        let mut success = true;
+        for &index in executed.iter().rev() {
+            let step = &mut self.steps[index];
+            let Some(ref mut rollback) = step.rollback else {
+                continue;
+            };
+            log::info!("[ENGINE] 回滚 {}", step.name);
+            sink(EngineEvent::RollingBack { name: step.name });
+            if let Err(e) = rollback() {
+                log::error!("[ENGINE] 回滚 {} 失败: {:#}", step.name, e);
+                sink(EngineEvent::RollbackFailed {
+                    name: step.name,
+                    error: format!("{:#}", e),
+                });
+                success = false;
+            }
+        }
+        success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::Mutex;
+
+    fn collect() -> (Arc<Mutex<Vec<String>>>, impl Fn(EngineEvent) + Send + Sync + 'static) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        (events, move |event: EngineEvent| {
+            let line = match event {
+                EngineEvent::StepStarted { name, .. } => format!("start {}", name),
+                EngineEvent::Retrying { name, attempt, .. } => format!("retry {} {}", name, attempt),
+                EngineEvent::StepSkipped { name, .. } => format!("skip {}", name),
+                EngineEvent::Warning { name, .. } => format!("warn {}", name),
+                EngineEvent::RollingBack { name } => format!("rollback {}", name),
+                EngineEvent::RollbackFailed { name, .. } => format!("rollback failed {}", name),
+                EngineEvent::Progress(_) | EngineEvent::Status(_) => return,
+            };
+            sink.lock().unwrap().push(line);
+        })
+    }
+
+    #[test]
+    fn test_retry_until_success() {
+        let attempts = RefCell::new(0);
+        let (events, sink) = collect();
+        let result = InstallEngine::new()
+            .step(
+                Step::new("format", "格式化", |_| {
+                    *attempts.borrow_mut() += 1;
+                    if *attempts.borrow() < 3 {
+                        anyhow::bail!("卷正在使用");
+                    }
+                    Ok(())
+                })
+                .retry(RetryPolicy::times(3, Duration::ZERO)),
+            )
+            .run(sink);
+        assert!(result.is_ok());
+        assert_eq!(*attempts.borrow(), 3);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["start 格式化", "retry 格式化 1", "retry 格式化 2"]
+        );
+    }
+
+    #[test]
+    fn test_optional_failure_skips_dependents() {
+        let (events, sink) = collect();
+        let result = InstallEngine::new()
+            .step(Step::new("drivers", "驱动", |_| anyhow::bail!("失败")).optional())
+            .step(Step::new("cab", "更新包", |_| Ok(())).after(&["drivers"]))
+            .step(Step::new("boot", "引导", |_| Ok(())))
+            .run(sink);
+        assert!(result.is_ok());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["start 驱动", "warn 驱动", "start 更新包", "skip 更新包", "start 引导"]
+        );
+    }
+
+    #[test]
+    fn test_required_failure_rolls_back_in_reverse() {
+        let rolled = RefCell::new(Vec::new());
+        let (events, sink) = collect();
+        let failure = InstallEngine::new()
+            .step(Step::new("a", "A", |_| Ok(())).rollback(|| {
+                rolled.borrow_mut().push("A");
+                Ok(())
+            }))
+            .step(Step::new("b", "B", |_| Ok(())))
+            .step(Step::new("c", "C", |_| anyhow::bail!("失败")).rollback(|| {
+                rolled.borrow_mut().push("C");
+                anyhow::bail!("无法恢复")
+            }))
+            .step(Step::new("d", "D", |_| Ok(())))
+            .run(sink)
+            .unwrap_err();
+        assert_eq!(failure.step, "c");
+        assert!(!failure.rolled_back);
+        assert_eq!(*rolled.borrow(), vec!["C", "A"]);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["start A", "start B", "start C", "rollback C", "rollback failed C", "rollback A"]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_dependency_panics() {
+        let _ = InstallEngine::new().step(Step::new("boot", "引导", |_| Ok(())).after(&["apply"]));
+    }
+}
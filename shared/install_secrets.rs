@@ -0,0 +1,89 @@
+//! 安装凭据
+//!
+//! 安装配置（config.json）位于数据分区，普通用户也能读取；连接共享的密码、新系统账户密码等凭据不写入配置，
+//! 由主程序单独保存在数据目录中只允许 SYSTEM 和管理员访问的目录里。
+//! PE 端开始安装时读取并立即删除该目录；主程序写入配置失败时目录随守卫删除。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::core::secure_dir::SensitiveDir;
+
+/// 凭据目录名（位于数据目录）
+pub const SECRETS_DIR: &str = "secrets";
+
+/// 凭据文件名
+const SECRETS_FILE: &str = "secrets.json";
+
+/// 交给 PE 端的安装凭据
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct InstallSecrets {
+    /// 连接 SMB 共享的密码
+    pub share_password: String,
+    /// 无人值守安装创建的本地账户密码
+    pub unattend_password: String,
+}
+
+impl InstallSecrets {
+    pub fn is_empty(&self) -> bool {
+        self.share_password.is_empty() && self.unattend_password.is_empty()
+    }
+
+    /// 保存到数据目录，没有凭据时不创建目录
+    ///
+    /// 返回凭据目录的守卫，安装配置写入成功后调用 `keep`。
+    pub fn stage(&self, data_dir: &str) -> Result<Option<SensitiveDir>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let dir = SensitiveDir::create(Path::new(data_dir).join(SECRETS_DIR))?;
+        let content = serde_json::to_string(self).context("序列化安装凭据失败")?;
+        std::fs::write(dir.path().join(SECRETS_FILE), content).context("写入安装凭据失败")?;
+        Ok(Some(dir))
+    }
+
+    /// 读取主程序保存的凭据并删除凭据目录，没有或无法读取时返回空凭据
+    pub fn take(data_dir: &str) -> Self {
+        let Some(dir) = SensitiveDir::existing(Path::new(data_dir).join(SECRETS_DIR)) else {
+            return Self::default();
+        };
+        let result = std::fs::read_to_string(dir.path().join(SECRETS_FILE))
+            .context("读取安装凭据失败")
+            .and_then(|content| serde_json::from_str(&content).context("解析安装凭据失败"));
+        result.unwrap_or_else(|e| {
+            log::warn!("{:#}", e);
+            Self::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_removes_dir() {
+        let data_dir = std::env::temp_dir().join(format!("lr_install_secrets_{}", std::process::id()));
+        let secrets_dir = data_dir.join(SECRETS_DIR);
+        std::fs::create_dir_all(&secrets_dir).unwrap();
+        std::fs::write(secrets_dir.join(SECRETS_FILE), r#"{"SharePassword":"p@ss","UnattendPassword":"pw"}"#).unwrap();
+
+        let data_dir_str = data_dir.to_string_lossy().to_string();
+        let secrets = InstallSecrets::take(&data_dir_str);
+        assert_eq!(secrets.share_password, "p@ss");
+        assert_eq!(secrets.unattend_password, "pw");
+        assert!(!secrets_dir.exists());
+        assert!(InstallSecrets::take(&data_dir_str).is_empty());
+
+        std::fs::create_dir_all(&secrets_dir).unwrap();
+        std::fs::write(secrets_dir.join(SECRETS_FILE), "not json").unwrap();
+        assert!(InstallSecrets::take(&data_dir_str).is_empty());
+        assert!(!secrets_dir.exists());
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! 机器可读进度输出
+//!
+//! 使用 `--json-progress`（或 `--progress-json`）启动时，在标准输出逐行输出一个 JSON 事件，
+//! 便于部署脚本把 LetRecovery（桌面端或 PE）作为安装引擎嵌入。此模式下人类可读的输出改写到标准错误，
+//! 并且不再弹出消息框。使用 `--progress-pipe <名称>` 时事件改为写入调用方创建的命名管道，
+//! 标准输出保持不变。命令行模式和界面上报进度的通道都会同步输出事件。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// 命令行参数
+pub const JSON_PROGRESS_ARG: &str = "--json-progress";
+/// `--json-progress` 的别名
+pub const PROGRESS_JSON_ARG: &str = "--progress-json";
+/// 输出到命名管道的参数（后跟管道名称）
+pub const PROGRESS_PIPE_ARG: &str = "--progress-pipe";
+
+/// 命名管道路径前缀
+const PIPE_PREFIX: &str = r"\\.\pipe\";
+
+/// 命令行模式下镜像进度在控制台输出的间隔（百分比）
+const CONSOLE_PROGRESS_STEP: u8 = 5;
+
+/// 是否启用 JSON 进度输出
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// 是否输出到命名管道（管道断开后丢弃事件，不回退到标准输出）
+static PIPE_MODE: AtomicBool = AtomicBool::new(false);
+/// 已连接的命名管道
+static PIPE: Mutex<Option<File>> = Mutex::new(None);
+/// 每收到一次进度时调用（PE 端据此向看门狗报告进度）
+static PROGRESS_HOOK: OnceLock<fn()> = OnceLock::new();
+
+/// 进度事件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    /// 操作开始（install / backup / verify）
+    Start { operation: &'static str },
+    /// 进入新步骤
+    Step { step: u8, total: u8, name: String },
+    /// 当前步骤进度
+    Progress { percentage: u8, status: String },
+    /// 可忽略的警告（操作继续）
+    Warning { message: String },
+    /// 人类可读的信息
+    Message { message: String },
+    /// 错误信息（随后通常是失败的 `finished` 事件，界面模式下可能自动重试）
+    Error { message: String },
+    /// 操作结束
+    Finished { success: bool, message: String },
+    /// 准备工作完成，重启后在 PE 中继续安装（本进程不再输出 `finished` 事件）
+    Handoff { message: String },
+}
+
+/// 输出的一行（附带时间戳）
+#[derive(Serialize)]
+struct EventLine<'a> {
+    /// Unix 时间戳（毫秒）
+    time: u64,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+/// 启用 JSON 进度输出
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// 检查是否启用了 JSON 进度输出
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// 事件是否输出到标准输出（此时人类可读的输出改写到标准错误）
+pub fn writes_stdout() -> bool {
+    is_enabled() && !PIPE_MODE.load(Ordering::SeqCst)
+}
+
+/// 命名管道的完整路径（只给名称时补全 `\\.\pipe\` 前缀）
+pub fn pipe_path(name: &str) -> String {
+    if name.starts_with(r"\\") {
+        name.to_string()
+    } else {
+        format!("{}{}", PIPE_PREFIX, name)
+    }
+}
+
+/// 连接调用方创建的命名管道，之后的事件写入管道
+pub fn enable_pipe(name: &str) -> std::io::Result<()> {
+    let pipe = std::fs::OpenOptions::new().write(true).open(pipe_path(name))?;
+    *PIPE.lock().unwrap_or_else(|e| e.into_inner()) = Some(pipe);
+    PIPE_MODE.store(true, Ordering::SeqCst);
+    enable();
+    Ok(())
+}
+
+/// 按命令行参数启用 JSON 进度输出
+pub fn init_from_args(args: &[String]) -> Result<(), String> {
+    if args.iter().any(|a| a == JSON_PROGRESS_ARG || a == PROGRESS_JSON_ARG) {
+        enable();
+    }
+    if let Some(pos) = args.iter().position(|a| a == PROGRESS_PIPE_ARG) {
+        let name = args
+            .get(pos + 1)
+            .filter(|name| !name.starts_with("--"))
+            .ok_or_else(|| format!("{} 需要指定管道名称", PROGRESS_PIPE_ARG))?;
+        enable_pipe(name).map_err(|e| format!("连接进度管道 {} 失败: {}", pipe_path(name), e))?;
+    }
+    Ok(())
+}
+
+/// 设置收到进度时的回调（只能设置一次）
+pub fn on_progress(hook: fn()) {
+    let _ = PROGRESS_HOOK.set(hook);
+}
+
+/// 调用收到进度时的回调
+fn progress_received() {
+    if let Some(hook) = PROGRESS_HOOK.get() {
+        hook();
+    }
+}
+
+/// 将事件序列化为单行 JSON
+pub fn to_line(event: &ProgressEvent) -> String {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    serde_json::to_string(&EventLine { time, event }).unwrap_or_default()
+}
+
+/// 输出事件（未启用时忽略）
+pub fn emit(event: ProgressEvent) {
+    if !is_enabled() {
+        return;
+    }
+    let line = to_line(&event);
+    if PIPE_MODE.load(Ordering::SeqCst) {
+        let mut pipe = PIPE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ref mut file) = *pipe {
+            if writeln!(file, "{}", line).and_then(|_| file.flush()).is_err() {
+                log::warn!("进度管道已断开，不再输出进度事件");
+                *pipe = None;
+            }
+        }
+        return;
+    }
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", line);
+    let _ = stdout.flush();
+}
+
+/// 创建进度转发通道，收到的进度以 `progress` 事件输出
+///
+/// 未启用时返回 None，调用方按原样传给不带进度的接口；
+/// 发送端被丢弃后转发线程自动退出
+pub fn forward_progress<T, F>(map: F) -> Option<Sender<T>>
+where
+    T: Send + 'static,
+    F: Fn(T) -> (u8, String) + Send + 'static,
+{
+    if !is_enabled() {
+        return None;
+    }
+    let (tx, rx) = mpsc::channel::<T>();
+    std::thread::spawn(move || {
+        let mut last = None;
+        for item in rx {
+            progress_received();
+            let (percentage, status) = map(item);
+            // 相同进度只输出一次，避免刷屏
+            if last.as_ref() == Some(&(percentage, status.clone())) {
+                continue;
+            }
+            last = Some((percentage, status.clone()));
+            emit(ProgressEvent::Progress { percentage, status });
+        }
+    });
+    Some(tx)
+}
+
+/// 创建 DISM/Ghost 进度转发通道
+///
+/// JSON 模式下以 `progress` 事件输出；否则按间隔输出到控制台，
+/// 没有界面的命令行模式下同样能看到 wimgapi/wimlib 回调上报的释放、捕获进度
+pub fn dism_progress() -> Option<Sender<crate::core::dism::DismProgress>> {
+    if is_enabled() {
+        return forward_progress(|p: crate::core::dism::DismProgress| (p.percentage, p.status));
+    }
+    let (tx, rx) = mpsc::channel::<crate::core::dism::DismProgress>();
+    std::thread::spawn(move || {
+        let mut last = None;
+        for progress in rx {
+            progress_received();
+            if console_progress_due(last, progress.percentage) {
+                last = Some(progress.percentage);
+                println!("[{:>3}%] {}", progress.percentage, progress.status);
+            }
+        }
+    });
+    Some(tx)
+}
+
+/// 是否需要在控制台输出：首次上报、跨过输出间隔或到达 100%
+fn console_progress_due(last: Option<u8>, percentage: u8) -> bool {
+    match last {
+        None => true,
+        Some(last) if last == percentage => false,
+        Some(last) => percentage == 100 || percentage / CONSOLE_PROGRESS_STEP != last / CONSOLE_PROGRESS_STEP,
+    }
+}
+
+/// 输出人类可读的信息并写入日志（JSON 输出到标准输出时改写到标准错误，保持标准输出只有 JSON）
+///
+/// 只输出日志，不产生 JSON 事件；需要上报的信息由调用方单独 `emit`
+#[macro_export]
+macro_rules! cli_println {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        log::info!("{}", line);
+        if $crate::utils::json_progress::writes_stdout() {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }};
+}
+
+/// 输出错误信息并写入日志（始终写到标准错误）
+#[macro_export]
+macro_rules! cli_eprintln {
+    ($($arg:tt)*) => {{
+        let line = format!($($arg)*);
+        log::error!("{}", line);
+        eprintln!("{}", line);
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_line_format() {
+        let line = to_line(&ProgressEvent::Step { step: 2, total: 6, name: "释放镜像".to_string() });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "step");
+        assert_eq!(value["step"], 2);
+        assert_eq!(value["total"], 6);
+        assert_eq!(value["name"], "释放镜像");
+        assert!(value["time"].as_u64().unwrap() > 0);
+        assert!(!line.contains('\n'));
+
+        let line = to_line(&ProgressEvent::Finished { success: false, message: "x".to_string() });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "finished");
+        assert_eq!(value["success"], false);
+
+        let line = to_line(&ProgressEvent::Error { message: "释放镜像失败".to_string() });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "error");
+        assert_eq!(value["message"], "释放镜像失败");
+
+        let line = to_line(&ProgressEvent::Handoff { message: "PE 环境准备完成".to_string() });
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "handoff");
+        assert!(value.get("success").is_none());
+    }
+
+    #[test]
+    fn test_pipe_path() {
+        assert_eq!(pipe_path("mdt_progress"), r"\\.\pipe\mdt_progress");
+        assert_eq!(pipe_path(r"\\.\pipe\mdt_progress"), r"\\.\pipe\mdt_progress");
+    }
+
+    #[test]
+    fn test_console_progress_due() {
+        assert!(console_progress_due(None, 0));
+        assert!(!console_progress_due(Some(0), 0));
+        assert!(!console_progress_due(Some(1), 4));
+        assert!(console_progress_due(Some(4), 5));
+        assert!(console_progress_due(Some(12), 20));
+        assert!(console_progress_due(Some(96), 100));
+        assert!(!console_progress_due(Some(100), 100));
+    }
+}
@@ -0,0 +1,865 @@
+//! 局域网镜像分发
+//!
+//! 机房批量装机时由一台电脑作为发送端共享镜像，各台电脑重启进入 PE 后从发送端接收镜像再安装。
+//! 采用 TCP 扇出：每个客户端单独连接发送端，按块请求数据并逐块校验 SHA-256，
+//! 校验失败或连接中断时重新请求该块；中断后重新接收会先校验已写入的块，只请求缺失或损坏的部分。
+//! 客户端未指定发送端地址时通过 UDP 广播自动发现，逐个尝试应答的发送端，只使用通过认证的一个。
+//!
+//! 每次分发生成随机的会话密钥，写在客户端填写的 `netcast://` 地址中。连接后双方用 HMAC-SHA256
+//! 互相证明持有密钥（密钥不在网络上传输），清单附带 HMAC，各块再按清单中的 SHA-256 校验：
+//! 局域网中的其他电脑不能下载镜像，也不能冒充发送端提供被篡改的镜像。
+//!
+//! 协议（每条消息一行）：
+//! - 连接后发送端发送 `HELLO <随机数>`，客户端回复 `AUTH <HMAC("client", 随机数)> <客户端随机数>`，
+//!   认证通过时发送端回复 `OK <HMAC("server", 客户端随机数)>`，否则回复 `DENIED` 并断开
+//! - `MANIFEST`：返回一行 JSON 清单（文件名、大小、块大小、各块 SHA-256）和一行清单的 HMAC
+//! - `GET <序号>`：返回 8 字节大端长度和块数据，序号无效时长度为 0
+//! - `DONE`：客户端接收完成
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// 默认 TCP 端口
+pub const DEFAULT_PORT: u16 = 9870;
+/// UDP 发现端口
+pub const DISCOVERY_PORT: u16 = 9871;
+/// 分块大小
+pub const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// 发现请求
+const DISCOVERY_REQUEST: &str = "LETRECOVERY_NETCAST?";
+/// 发现应答前缀，后跟 TCP 端口和镜像文件名
+const DISCOVERY_REPLY: &str = "LETRECOVERY_NETCAST";
+/// 发送端防火墙规则名称
+const FIREWALL_RULE: &str = "LetRecovery 局域网分发";
+/// 单个块的最大重试次数
+const MAX_RETRIES: u32 = 5;
+/// 接收数据超时
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// 客户端完成认证的时限
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+/// 同时等待认证的连接数上限，超出时直接断开新连接
+const MAX_PENDING_HANDSHAKES: usize = 16;
+/// 收到第一个发现应答后继续等待其他发送端应答的时间
+const DISCOVERY_GRACE: Duration = Duration::from_secs(2);
+
+/// 镜像清单
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub name: String,
+    pub size: u64,
+    pub chunk_size: u64,
+    /// 各块的 SHA-256（小写十六进制）
+    pub hashes: Vec<String>,
+}
+
+impl Manifest {
+    /// 读取文件并计算各块哈希；进度为 0-100
+    pub fn build(path: &Path, chunk_size: u64, mut on_progress: impl FnMut(u8)) -> Result<Self> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| anyhow!("无效的镜像路径: {}", path.display()))?;
+        let mut file = std::fs::File::open(path).with_context(|| format!("打开镜像失败: {}", path.display()))?;
+        let size = file.metadata()?.len();
+        let count = size.div_ceil(chunk_size) as usize;
+
+        let mut hashes = Vec::with_capacity(count);
+        let mut buffer = vec![0u8; chunk_size as usize];
+        for index in 0..count {
+            let len = chunk_len(size, chunk_size, index) as usize;
+            file.read_exact(&mut buffer[..len]).context("读取镜像失败")?;
+            hashes.push(hash_chunk(&buffer[..len]));
+            on_progress(((index + 1) * 100 / count) as u8);
+        }
+        Ok(Self { name, size, chunk_size, hashes })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// 第 `index` 块的长度
+    pub fn chunk_len(&self, index: usize) -> u64 {
+        chunk_len(self.size, self.chunk_size, index)
+    }
+
+    /// 清单是否自洽，防止异常数据导致超大内存分配
+    fn validate(&self) -> Result<()> {
+        if self.chunk_size == 0 || self.chunk_size > 64 * 1024 * 1024 {
+            bail!("无效的块大小: {}", self.chunk_size);
+        }
+        if self.size.div_ceil(self.chunk_size) != self.hashes.len() as u64 {
+            bail!("清单中的块数与文件大小不符");
+        }
+        Ok(())
+    }
+}
+
+fn chunk_len(size: u64, chunk_size: u64, index: usize) -> u64 {
+    let offset = index as u64 * chunk_size;
+    size.saturating_sub(offset).min(chunk_size)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 计算块的 SHA-256
+pub fn hash_chunk(data: &[u8]) -> String {
+    to_hex(&sha2::Sha256::digest(data))
+}
+
+/// HMAC-SHA256（RFC 2104），返回小写十六进制
+pub fn hmac(key: &str, parts: &[&str]) -> String {
+    const BLOCK: usize = 64;
+    let mut block_key = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block_key[..32].copy_from_slice(&sha2::Sha256::digest(key.as_bytes()));
+    } else {
+        block_key[..key.len()].copy_from_slice(key.as_bytes());
+    }
+
+    let mut inner = sha2::Sha256::new();
+    inner.update(block_key.map(|b| b ^ 0x36));
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            inner.update(b"\n");
+        }
+        inner.update(part.as_bytes());
+    }
+    let mut outer = sha2::Sha256::new();
+    outer.update(block_key.map(|b| b ^ 0x5c));
+    outer.update(inner.finalize());
+    to_hex(&outer.finalize())
+}
+
+/// 系统随机数生成的十六进制字符串，用作会话密钥和认证随机数
+pub fn random_hex(bytes: usize) -> Result<String> {
+    let mut out = vec![0u8; bytes];
+    getrandom::getrandom(&mut out).map_err(|e| anyhow!("获取系统随机数失败: {}", e))?;
+    Ok(to_hex(&out))
+}
+
+/// 生成会话密钥（128 位）
+pub fn generate_key() -> Result<String> {
+    random_hex(16)
+}
+
+/// 比较两个 HMAC，耗时与内容无关
+fn hmac_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// 是否为局域网地址（发送端只为这些地址提供镜像）
+pub fn is_lan_peer(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_lan_peer(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            // 唯一本地地址 fc00::/7 和链路本地地址 fe80::/10
+            ip.is_loopback() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+/// 解析发现应答，返回 TCP 端口和镜像文件名
+pub fn parse_discovery_reply(reply: &str) -> Option<(u16, String)> {
+    let rest = reply.trim().strip_prefix(DISCOVERY_REPLY)?.strip_prefix(' ')?;
+    let (port, name) = rest.split_once(' ').unwrap_or((rest, ""));
+    Some((port.parse().ok()?, name.to_string()))
+}
+
+/// 把 `host` 或 `host:port` 解析为地址，未写端口时使用默认端口
+pub fn resolve_addr(addr: &str) -> Result<SocketAddr> {
+    let addr = addr.trim();
+    let with_port = if addr.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        addr.to_string()
+    } else {
+        format!("{}:{}", addr, DEFAULT_PORT)
+    };
+    with_port
+        .to_socket_addrs()
+        .with_context(|| format!("无法解析发送端地址: {}", addr))?
+        .next()
+        .ok_or_else(|| anyhow!("无法解析发送端地址: {}", addr))
+}
+
+/// 客户端接收状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientStatus {
+    /// 客户端 IP
+    pub peer: String,
+    /// 已发送的块数（续传的客户端不会请求已有的块）
+    pub sent: usize,
+    pub total: usize,
+    pub done: bool,
+}
+
+/// 发送端
+pub struct Server {
+    manifest: Arc<Manifest>,
+    key: String,
+    port: u16,
+    clients: Arc<Mutex<Vec<ClientStatus>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Server {
+    /// 计算镜像分块哈希后开始监听 `port`（0 表示随机端口），生成新的会话密钥；进度为哈希计算进度
+    pub fn start(image: &Path, port: u16, on_progress: impl FnMut(u8)) -> Result<Self> {
+        Self::start_with_chunk_size(image, port, CHUNK_SIZE, on_progress)
+    }
+
+    fn start_with_chunk_size(image: &Path, port: u16, chunk_size: u64, on_progress: impl FnMut(u8)) -> Result<Self> {
+        let manifest = Arc::new(Manifest::build(image, chunk_size, on_progress)?);
+        let listener = TcpListener::bind(("0.0.0.0", port)).with_context(|| format!("监听端口 {} 失败", port))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+
+        let server = Self {
+            manifest,
+            key: generate_key()?,
+            port,
+            clients: Arc::new(Mutex::new(Vec::new())),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+
+        let session = Arc::new(Session {
+            image: image.to_path_buf(),
+            manifest: server.manifest.clone(),
+            key: server.key.clone(),
+            clients: server.clients.clone(),
+            stop: server.stop.clone(),
+            handshakes: Arc::new(AtomicUsize::new(0)),
+        });
+        std::thread::spawn(move || accept_loop(listener, session));
+
+        // 发现端口被占用时仍可手动填写地址
+        match UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)) {
+            Ok(socket) => {
+                let reply = format!("{} {} {}", DISCOVERY_REPLY, port, server.manifest.name);
+                let stop = server.stop.clone();
+                std::thread::spawn(move || discovery_loop(socket, reply, stop));
+            }
+            Err(e) => log::warn!("[NETCAST] 绑定发现端口 {} 失败: {}", DISCOVERY_PORT, e),
+        }
+
+        log::info!(
+            "[NETCAST] 开始分发 {}（{} 字节，{} 块），端口 {}",
+            server.manifest.name,
+            server.manifest.size,
+            server.manifest.chunk_count(),
+            port
+        );
+        Ok(server)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    /// 本次分发的会话密钥，客户端地址中需要包含此密钥
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// 各客户端的接收状态
+    pub fn clients(&self) -> Vec<ClientStatus> {
+        self.clients.lock().map(|clients| clients.clone()).unwrap_or_default()
+    }
+
+    /// 停止接受新连接，已连接的客户端在当前请求结束后断开
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 各客户端连接共用的分发状态
+struct Session {
+    image: PathBuf,
+    manifest: Arc<Manifest>,
+    key: String,
+    clients: Arc<Mutex<Vec<ClientStatus>>>,
+    stop: Arc<AtomicBool>,
+    /// 正在等待认证的连接数
+    handshakes: Arc<AtomicUsize>,
+}
+
+/// 占用一个认证名额，认证结束（无论成败）后释放
+struct HandshakeSlot(Arc<AtomicUsize>);
+
+impl HandshakeSlot {
+    fn acquire(counter: &Arc<AtomicUsize>) -> Option<Self> {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < MAX_PENDING_HANDSHAKES).then_some(n + 1))
+            .ok()
+            .map(|_| Self(counter.clone()))
+    }
+}
+
+impl Drop for HandshakeSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn accept_loop(listener: TcpListener, session: Arc<Session>) {
+    while !session.stop.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                if !is_lan_peer(peer.ip()) {
+                    log::warn!("[NETCAST] 拒绝非局域网地址的连接: {}", peer);
+                    continue;
+                }
+                let Some(slot) = HandshakeSlot::acquire(&session.handshakes) else {
+                    log::warn!("[NETCAST] 等待认证的连接过多，断开: {}", peer);
+                    continue;
+                };
+                log::info!("[NETCAST] 客户端已连接: {}", peer);
+                let session = session.clone();
+                std::thread::spawn(move || {
+                    if let Err(e) = serve_client(stream, peer, &session, slot) {
+                        log::warn!("[NETCAST] 客户端 {} 断开: {:#}", peer, e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => {
+                log::warn!("[NETCAST] 接受连接失败: {}", e);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+    log::info!("[NETCAST] 已停止分发");
+}
+
+fn discovery_loop(socket: UdpSocket, reply: String, stop: Arc<AtomicBool>) {
+    let _ = socket.set_read_timeout(Some(Duration::from_millis(500)));
+    let mut buffer = [0u8; 256];
+    while !stop.load(Ordering::SeqCst) {
+        let Ok((len, peer)) = socket.recv_from(&mut buffer) else {
+            continue;
+        };
+        if is_lan_peer(peer.ip()) && String::from_utf8_lossy(&buffer[..len]).trim() == DISCOVERY_REQUEST {
+            let _ = socket.send_to(reply.as_bytes(), peer);
+        }
+    }
+}
+
+/// 更新客户端状态，同一 IP 重新连接时沿用原记录
+fn update_client(clients: &Mutex<Vec<ClientStatus>>, peer: &str, total: usize, update: impl FnOnce(&mut ClientStatus)) {
+    let Ok(mut clients) = clients.lock() else {
+        return;
+    };
+    let index = match clients.iter().position(|client| client.peer == peer) {
+        Some(index) => index,
+        None => {
+            clients.push(ClientStatus { peer: peer.to_string(), sent: 0, total, done: false });
+            clients.len() - 1
+        }
+    };
+    update(&mut clients[index]);
+}
+
+/// 发送端一侧的认证：发送随机数，校验客户端的 HMAC 并回复发送端的 HMAC，返回客户端随机数
+fn authenticate_client(reader: &mut BufReader<TcpStream>, writer: &mut TcpStream, key: &str) -> Result<String> {
+    let nonce = random_hex(16)?;
+    writer.write_all(format!("HELLO {}\n", nonce).as_bytes())?;
+    reader.get_ref().set_read_timeout(Some(AUTH_TIMEOUT))?;
+    let mut line = String::new();
+    reader.read_line(&mut line).context("等待客户端认证超时")?;
+
+    let mut parts = line.split_whitespace();
+    let (Some("AUTH"), Some(proof), Some(client_nonce)) = (parts.next(), parts.next(), parts.next()) else {
+        writer.write_all(b"DENIED\n")?;
+        bail!("客户端未认证");
+    };
+    if !hmac_eq(proof, &hmac(key, &["client", &nonce])) {
+        writer.write_all(b"DENIED\n")?;
+        bail!("客户端密钥错误");
+    }
+    writer.write_all(format!("OK {}\n", hmac(key, &["server", client_nonce])).as_bytes())?;
+    Ok(client_nonce.to_string())
+}
+
+fn serve_client(stream: TcpStream, peer: SocketAddr, session: &Session, slot: HandshakeSlot) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    let manifest = &session.manifest;
+    let clients = &session.clients;
+    let ip = peer.ip().to_string();
+    let total = manifest.chunk_count();
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let client_nonce = authenticate_client(&mut reader, &mut writer, &session.key)?;
+    drop(slot);
+    reader.get_ref().set_read_timeout(Some(Duration::from_secs(1)))?;
+
+    let mut file = std::fs::File::open(&session.image).context("打开镜像失败")?;
+    let mut buffer = vec![0u8; manifest.chunk_size as usize];
+    let mut line = String::new();
+    while !session.stop.load(Ordering::SeqCst) {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            // 空闲超时，检查是否已停止分发
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => continue,
+            Err(e) => return Err(e.into()),
+        }
+
+        let command = line.trim();
+        if command == "MANIFEST" {
+            update_client(clients, &ip, total, |client| client.done = false);
+            let json = serde_json::to_string(manifest.as_ref())?;
+            let tag = hmac(&session.key, &["manifest", &client_nonce, &json]);
+            writer.write_all(format!("{}\n{}\n", json, tag).as_bytes())?;
+        } else if let Some(index) = command.strip_prefix("GET ") {
+            let index = index.trim().parse::<usize>().ok().filter(|index| *index < total);
+            let Some(index) = index else {
+                log::warn!("[NETCAST] 客户端 {} 请求了无效的块: {}", peer, command);
+                writer.write_all(&0u64.to_be_bytes())?;
+                continue;
+            };
+            let len = manifest.chunk_len(index) as usize;
+            file.seek(SeekFrom::Start(index as u64 * manifest.chunk_size))?;
+            file.read_exact(&mut buffer[..len]).context("读取镜像失败")?;
+            writer.write_all(&(len as u64).to_be_bytes())?;
+            writer.write_all(&buffer[..len])?;
+            update_client(clients, &ip, total, |client| client.sent = (client.sent + 1).min(total));
+        } else if command == "DONE" {
+            log::info!("[NETCAST] 客户端 {} 接收完成", peer);
+            update_client(clients, &ip, total, |client| client.done = true);
+            return Ok(());
+        } else {
+            bail!("未知命令: {}", command);
+        }
+    }
+    Ok(())
+}
+
+/// 分发期间的防火墙入站规则，离开作用域时删除
+pub struct FirewallRule;
+
+impl Drop for FirewallRule {
+    fn drop(&mut self) {
+        match delete_firewall_rule() {
+            Ok(()) => log::info!("[NETCAST] 已删除防火墙规则"),
+            Err(e) => log::warn!("[NETCAST] 删除防火墙规则失败: {:#}", e),
+        }
+    }
+}
+
+fn delete_firewall_rule() -> Result<()> {
+    let output = new_command("netsh")
+        .args(["advfirewall", "firewall", "delete", "rule"])
+        .arg(format!("name={}", FIREWALL_RULE))
+        .output()
+        .context("执行 netsh 失败")?;
+    if !output.status.success() {
+        bail!("{}", gbk_to_utf8(&output.stdout).trim());
+    }
+    Ok(())
+}
+
+/// 允许本程序接受本地子网的入站连接（发现和数据端口），已有同名规则（上次异常退出留下）时先删除
+pub fn allow_firewall() -> Result<FirewallRule> {
+    let exe = std::env::current_exe().context("获取程序路径失败")?;
+    let _ = delete_firewall_rule();
+    let output = new_command("netsh")
+        .args(["advfirewall", "firewall", "add", "rule"])
+        .arg(format!("name={}", FIREWALL_RULE))
+        .args(["dir=in", "action=allow", "enable=yes", "remoteip=localsubnet"])
+        .arg(format!("program={}", exe.display()))
+        .output()
+        .context("执行 netsh 失败")?;
+    if !output.status.success() {
+        bail!("添加防火墙规则失败: {}", gbk_to_utf8(&output.stdout).trim());
+    }
+    Ok(FirewallRule)
+}
+
+/// 广播查找局域网中的发送端，返回在时限内应答的全部发送端（按应答顺序）
+pub fn discover(timeout: Duration) -> Result<Vec<(SocketAddr, String)>> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).context("创建 UDP 套接字失败")?;
+    socket.set_broadcast(true)?;
+    socket.set_read_timeout(Some(Duration::from_millis(500)))?;
+
+    let mut deadline = std::time::Instant::now() + timeout;
+    let mut found: Vec<(SocketAddr, String)> = Vec::new();
+    let mut buffer = [0u8; 512];
+    while std::time::Instant::now() < deadline {
+        socket
+            .send_to(DISCOVERY_REQUEST.as_bytes(), ("255.255.255.255", DISCOVERY_PORT))
+            .context("发送发现请求失败")?;
+        let Ok((len, from)) = socket.recv_from(&mut buffer) else {
+            continue;
+        };
+        let Some((port, name)) = parse_discovery_reply(&String::from_utf8_lossy(&buffer[..len])) else {
+            continue;
+        };
+        let addr = SocketAddr::new(from.ip(), port);
+        if found.iter().any(|(known, _)| *known == addr) {
+            continue;
+        }
+        log::info!("[NETCAST] 发现发送端 {}，镜像 {}", addr, name);
+        if found.is_empty() {
+            // 同一网段中可能有多个发送端，稍等其他应答
+            deadline = deadline.min(std::time::Instant::now() + DISCOVERY_GRACE);
+        }
+        found.push((addr, name));
+    }
+    if found.is_empty() {
+        bail!("局域网中没有找到发送端，请确认发送端已开始分发且在同一网段");
+    }
+    Ok(found)
+}
+
+/// 到发送端的已认证连接
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// 本连接中客户端的随机数，用于校验清单的 HMAC
+    nonce: String,
+}
+
+impl Connection {
+    fn open(addr: SocketAddr, key: &str) -> Result<Self> {
+        let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(10))
+            .with_context(|| format!("连接发送端 {} 失败", addr))?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let _ = stream.set_nodelay(true);
+        let mut connection =
+            Self { writer: stream.try_clone()?, reader: BufReader::new(stream), nonce: random_hex(16)? };
+        connection.authenticate(key).with_context(|| format!("发送端 {} 认证失败", addr))?;
+        Ok(connection)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            bail!("连接已断开");
+        }
+        Ok(line.trim().to_string())
+    }
+
+    /// 客户端一侧的认证：证明持有密钥，并校验发送端也持有同一密钥
+    fn authenticate(&mut self, key: &str) -> Result<()> {
+        let hello = self.read_line()?;
+        let server_nonce = hello.strip_prefix("HELLO ").context("不是局域网分发的发送端")?;
+        let proof = hmac(key, &["client", server_nonce]);
+        let nonce = self.nonce.clone();
+        self.send(&format!("AUTH {} {}", proof, nonce))?;
+        let reply = self.read_line()?;
+        let server_proof = reply.strip_prefix("OK ").context("地址中的密钥与发送端不一致")?;
+        if !hmac_eq(server_proof, &hmac(key, &["server", &nonce])) {
+            bail!("发送端未能证明持有密钥");
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, command: &str) -> std::io::Result<()> {
+        self.writer.write_all(command.as_bytes())?;
+        self.writer.write_all(b"\n")
+    }
+
+    fn manifest(&mut self, key: &str) -> Result<Manifest> {
+        self.send("MANIFEST")?;
+        let json = self.read_line().context("读取镜像清单失败")?;
+        let tag = self.read_line().context("读取镜像清单失败")?;
+        if !hmac_eq(&tag, &hmac(key, &["manifest", &self.nonce, &json])) {
+            bail!("镜像清单校验失败");
+        }
+        let manifest: Manifest = serde_json::from_str(&json).context("解析镜像清单失败")?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn chunk(&mut self, index: usize, max_len: u64, buffer: &mut Vec<u8>) -> std::io::Result<()> {
+        self.send(&format!("GET {}", index))?;
+        let mut len = [0u8; 8];
+        self.reader.read_exact(&mut len)?;
+        let len = u64::from_be_bytes(len);
+        if len > max_len {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "块长度超出清单"));
+        }
+        buffer.resize(len as usize, 0);
+        self.reader.read_exact(buffer)
+    }
+}
+
+/// 校验已有文件中的各块，返回缺失或损坏的块序号
+fn missing_chunks(file: &mut std::fs::File, manifest: &Manifest) -> Result<Vec<usize>> {
+    if file.metadata()?.len() != manifest.size {
+        file.set_len(manifest.size).context("预分配文件失败")?;
+        return Ok((0..manifest.chunk_count()).collect());
+    }
+    let mut missing = Vec::new();
+    let mut buffer = vec![0u8; manifest.chunk_size as usize];
+    file.seek(SeekFrom::Start(0))?;
+    for (index, hash) in manifest.hashes.iter().enumerate() {
+        let len = manifest.chunk_len(index) as usize;
+        file.read_exact(&mut buffer[..len]).context("读取已接收的数据失败")?;
+        if hash_chunk(&buffer[..len]) != *hash {
+            missing.push(index);
+        }
+    }
+    Ok(missing)
+}
+
+/// 连接并认证发送端；未指定地址时逐个尝试自动发现的发送端，只使用认证通过的一个
+fn connect(addr: Option<&str>, key: &str) -> Result<(SocketAddr, Connection, Manifest)> {
+    if let Some(addr) = addr.map(str::trim).filter(|addr| !addr.is_empty()) {
+        let addr = resolve_addr(addr)?;
+        let mut connection = Connection::open(addr, key)?;
+        let manifest = connection.manifest(key)?;
+        return Ok((addr, connection, manifest));
+    }
+
+    let mut last_error = None;
+    for (addr, _) in discover(Duration::from_secs(30))? {
+        let result = Connection::open(addr, key).and_then(|mut connection| {
+            let manifest = connection.manifest(key)?;
+            Ok((connection, manifest))
+        });
+        match result {
+            Ok((connection, manifest)) => return Ok((addr, connection, manifest)),
+            Err(e) => {
+                log::warn!("[NETCAST] 跳过发送端 {}: {:#}", addr, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| anyhow!("局域网中没有找到发送端"))
+        .context("局域网中没有与地址中的密钥匹配的发送端"))
+}
+
+/// 从发送端接收镜像到 `dest_dir`，`addr` 为空时自动发现发送端，`key` 为发送端的会话密钥；
+/// 返回镜像路径，进度为 0-100
+pub fn receive(addr: Option<&str>, key: &str, dest_dir: &Path, mut on_progress: impl FnMut(u8)) -> Result<PathBuf> {
+    if key.is_empty() {
+        bail!("netcast 地址中缺少发送端的密钥（key=），请从发送端复制完整地址");
+    }
+    let (addr, connection, manifest) = connect(addr, key)?;
+    let name: String = manifest
+        .name
+        .chars()
+        .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') && !c.is_control())
+        .collect();
+    if name.trim().is_empty() {
+        bail!("发送端的镜像文件名无效");
+    }
+    log::info!("[NETCAST] 从 {} 接收 {}（{} 块）", addr, name, manifest.chunk_count());
+
+    std::fs::create_dir_all(dest_dir).context("创建接收目录失败")?;
+    let dest = dest_dir.join(&name);
+    let partial = dest_dir.join(format!("{}.part", name));
+    // 上次已接收完成的文件也重新校验，与发送端不一致时续传修复
+    if dest.exists() && !partial.exists() {
+        std::fs::rename(&dest, &partial).context("重命名已有文件失败")?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&partial)
+        .context("创建接收文件失败")?;
+
+    let missing = missing_chunks(&mut file, &manifest)?;
+    let total = manifest.chunk_count().max(1);
+    let mut received = total - missing.len();
+    on_progress((received * 100 / total) as u8);
+    if missing.len() < manifest.chunk_count() {
+        log::info!("[NETCAST] 已有 {} 块校验通过，续传 {} 块", received, missing.len());
+    }
+
+    let mut connection = Some(connection);
+    let mut buffer = Vec::with_capacity(manifest.chunk_size as usize);
+    for index in missing {
+        let mut retries = 0;
+        loop {
+            let conn = match connection.as_mut() {
+                Some(conn) => conn,
+                None => match Connection::open(addr, key) {
+                    Ok(conn) => connection.insert(conn),
+                    Err(e) => {
+                        retries += 1;
+                        if retries > MAX_RETRIES {
+                            return Err(e.context("重新连接发送端失败"));
+                        }
+                        std::thread::sleep(Duration::from_secs(retries as u64));
+                        continue;
+                    }
+                },
+            };
+
+            let expected = manifest.chunk_len(index);
+            match conn.chunk(index, expected, &mut buffer) {
+                Ok(()) if buffer.len() as u64 == expected && hash_chunk(&buffer) == manifest.hashes[index] => break,
+                Ok(()) => log::warn!("[NETCAST] 第 {} 块校验失败，重新请求", index),
+                Err(e) => {
+                    log::warn!("[NETCAST] 接收第 {} 块失败: {}，重新连接", index, e);
+                    connection = None;
+                }
+            }
+            retries += 1;
+            if retries > MAX_RETRIES {
+                bail!("第 {} 块重试 {} 次后仍然失败", index, MAX_RETRIES);
+            }
+        }
+
+        file.seek(SeekFrom::Start(index as u64 * manifest.chunk_size))?;
+        file.write_all(&buffer).context("写入接收文件失败")?;
+        received += 1;
+        on_progress((received * 100 / total) as u8);
+    }
+
+    file.flush()?;
+    drop(file);
+    if let Some(mut conn) = connection {
+        let _ = conn.send("DONE");
+    }
+    std::fs::rename(&partial, &dest).context("保存接收文件失败")?;
+    log::info!("[NETCAST] 接收完成: {}", dest.display());
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lr_netcast_test_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sample_data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_manifest_chunks() {
+        let dir = temp_dir("manifest");
+        let image = dir.join("install.wim");
+        let data = sample_data(2500);
+        std::fs::write(&image, &data).unwrap();
+
+        let manifest = Manifest::build(&image, 1024, |_| {}).unwrap();
+        assert_eq!(manifest.name, "install.wim");
+        assert_eq!(manifest.chunk_count(), 3);
+        assert_eq!(manifest.chunk_len(2), 452);
+        assert_eq!(manifest.hashes[1], hash_chunk(&data[1024..2048]));
+        assert!(manifest.validate().is_ok());
+
+        let mut broken = manifest.clone();
+        broken.hashes.pop();
+        assert!(broken.validate().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_discovery_reply() {
+        assert_eq!(
+            parse_discovery_reply("LETRECOVERY_NETCAST 9870 Win 11.wim\n"),
+            Some((9870, "Win 11.wim".to_string()))
+        );
+        assert_eq!(parse_discovery_reply("LETRECOVERY_NETCAST 9870"), Some((9870, String::new())));
+        assert!(parse_discovery_reply("LETRECOVERY_NETCAST? ").is_none());
+        assert!(parse_discovery_reply("LETRECOVERY_NETCAST abc x.wim").is_none());
+        assert_eq!(resolve_addr("127.0.0.1").unwrap().port(), DEFAULT_PORT);
+        assert_eq!(resolve_addr("127.0.0.1:10000").unwrap().port(), 10000);
+    }
+
+    #[test]
+    fn test_hmac_and_key() {
+        // RFC 4231 测试用例 2
+        assert_eq!(
+            hmac("Jefe", &["what do ya want for nothing?"]),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(hmac("k", &["a", "b"]), hmac("k", &["a\nb"]));
+        let key = generate_key().unwrap();
+        assert_eq!(key.len(), 32);
+        assert_ne!(key, generate_key().unwrap());
+        assert!(hmac_eq("abc", "abc"));
+        assert!(!hmac_eq("abc", "abd"));
+        assert!(is_lan_peer("192.168.1.10".parse().unwrap()));
+        assert!(is_lan_peer("fe80::1".parse().unwrap()));
+        assert!(!is_lan_peer("8.8.8.8".parse().unwrap()));
+        assert!(!is_lan_peer("2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_handshake_slot() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<_> = (0..MAX_PENDING_HANDSHAKES).map(|_| HandshakeSlot::acquire(&counter).unwrap()).collect();
+        assert!(HandshakeSlot::acquire(&counter).is_none());
+        drop(slots);
+        assert_eq!(counter.load(Ordering::SeqCst), 0);
+        assert!(HandshakeSlot::acquire(&counter).is_some());
+    }
+
+    #[test]
+    fn test_receive_and_resume() {
+        let dir = temp_dir("transfer");
+        let image = dir.join("source.wim");
+        let data = sample_data(5000);
+        std::fs::write(&image, &data).unwrap();
+        let server = Server::start_with_chunk_size(&image, 0, 1024, |_| {}).unwrap();
+        let addr = format!("127.0.0.1:{}", server.port());
+
+        let dest_dir = dir.join("received");
+        let received = receive(Some(&addr), server.key(), &dest_dir, |_| {}).unwrap();
+        assert_eq!(received, dest_dir.join("source.wim"));
+        assert_eq!(std::fs::read(&received).unwrap(), data);
+
+        // 损坏一块后重新接收，只修复损坏的块
+        let mut corrupted = data.clone();
+        corrupted[1500] ^= 0xFF;
+        std::fs::write(&received, &corrupted).unwrap();
+        let mut progress = Vec::new();
+        receive(Some(&addr), server.key(), &dest_dir, |p| progress.push(p)).unwrap();
+        assert_eq!(std::fs::read(&received).unwrap(), data);
+        assert_eq!(progress, vec![80, 100]);
+
+        // 密钥错误时拒绝连接
+        let error = receive(Some(&addr), &generate_key().unwrap(), &dir.join("denied"), |_| {}).unwrap_err();
+        assert!(format!("{:#}", error).contains("认证失败"));
+        assert!(!dir.join("denied").exists());
+
+        server.stop();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,363 @@
+//! 网络镜像源
+//!
+//! 安装配置中的 `ImageSource` 可以是 SMB 共享中的镜像（`\\服务器\共享\install.wim`）或 HTTP(S) 地址，
+//! 机房部署时直接从服务器安装，不必先把镜像复制到本地磁盘。
+//! SMB 共享用 `WNetAddConnection2W` 连接（可带用户名和密码，密码不出现在命令行中），
+//! 再由 DISM / Ghost 直接读取共享中的文件。
+//! HTTP 镜像地址必须以 `#sha256=校验值` 结尾，流式下载到数据目录并校验后才安装。
+//! `netcast://发送端[:端口][/文件名]?key=密钥` 从局域网分发的发送端接收镜像（见 `core::netcast`），
+//! 省略发送端时广播自动发现；文件名只用于显示和判断是否为 GHO，密钥用于认证发送端。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::core::netcast;
+
+/// 下载文件名无法从地址中得到时使用的名称
+const DEFAULT_FILE_NAME: &str = "network_image.wim";
+
+/// 网络镜像源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkSource {
+    /// SMB 共享中的文件
+    Share {
+        /// 共享根路径，如 \\server\images
+        share: String,
+        /// 文件完整路径
+        path: String,
+    },
+    /// HTTP(S) 地址
+    Http {
+        /// 下载地址（不含 `#` 之后的部分）
+        url: String,
+        /// 镜像文件的 SHA-256（小写十六进制），为空表示地址中没有提供
+        sha256: String,
+    },
+    /// 局域网分发的发送端
+    Netcast {
+        /// 发送端地址，为空时自动发现
+        addr: Option<String>,
+        /// 镜像文件名，可为空
+        name: String,
+        /// 发送端的会话密钥
+        key: String,
+    },
+}
+
+impl NetworkSource {
+    /// 解析镜像源，不是 UNC 路径、HTTP(S) 或 netcast 地址时返回 None
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        let lower = source.to_lowercase();
+        if lower.starts_with("netcast://") {
+            let rest = &source["netcast://".len()..];
+            let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+            let (host, name) = rest.split_once('/').unwrap_or((rest, ""));
+            let key = query.split('&').find_map(|pair| pair.strip_prefix("key=")).unwrap_or_default();
+            return Some(NetworkSource::Netcast {
+                addr: (!host.is_empty()).then(|| host.to_string()),
+                name: name.rsplit('/').next().unwrap_or_default().to_string(),
+                key: key.to_string(),
+            });
+        }
+        if lower.starts_with("http://") || lower.starts_with("https://") {
+            let (url, fragment) = source.split_once('#').unwrap_or((source, ""));
+            let host = url.split_once("://")?.1.split(['/', '?']).next()?;
+            let sha256 = fragment.split('&').find_map(|pair| pair.strip_prefix("sha256=")).unwrap_or_default();
+            return (!host.is_empty())
+                .then(|| NetworkSource::Http { url: url.to_string(), sha256: sha256.trim().to_lowercase() });
+        }
+
+        let path = source.replace('/', "\\");
+        let rest = path.strip_prefix("\\\\")?;
+        let parts: Vec<&str> = rest.split('\\').collect();
+        // 至少需要服务器、共享名和文件名
+        if parts.len() < 3 || parts.iter().any(|part| part.is_empty()) {
+            return None;
+        }
+        Some(NetworkSource::Share { share: format!("\\\\{}\\{}", parts[0], parts[1]), path })
+    }
+
+    /// 检查地址是否带有安装所需的认证信息，返回第一个发现的问题
+    pub fn check(&self) -> std::result::Result<(), String> {
+        match self {
+            NetworkSource::Netcast { key, .. } if key.is_empty() => {
+                Err("netcast:// 地址缺少发送端密钥（key=）".to_string())
+            }
+            NetworkSource::Http { sha256, .. }
+                if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) =>
+            {
+                Err("HTTP(S) 地址缺少镜像的 SHA-256 校验值（#sha256=64 位十六进制）".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 镜像文件名
+    pub fn file_name(&self) -> String {
+        let name = match self {
+            NetworkSource::Share { path, .. } => path.rsplit('\\').next().unwrap_or_default(),
+            NetworkSource::Http { url, .. } => {
+                let path = url.split('?').next().unwrap_or_default();
+                path.split_once("://")
+                    .and_then(|(_, rest)| rest.split_once('/'))
+                    .and_then(|(_, path)| path.rsplit('/').next())
+                    .unwrap_or_default()
+            }
+            NetworkSource::Netcast { name, .. } => name.as_str(),
+        };
+        let name: String = name
+            .chars()
+            .filter(|c| !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*') && !c.is_control())
+            .collect();
+        if name.trim().is_empty() {
+            DEFAULT_FILE_NAME.to_string()
+        } else {
+            name
+        }
+    }
+
+    /// 是否为 GHO 镜像
+    pub fn is_gho(&self) -> bool {
+        let name = self.file_name().to_lowercase();
+        name.ends_with(".gho") || name.ends_with(".ghs")
+    }
+}
+
+/// 连接 SMB 共享，用户名为空时使用当前身份
+pub fn connect_share(share: &str, user: &str, password: &str) -> Result<()> {
+    use windows::core::{PCWSTR, PWSTR};
+    use windows::Win32::Foundation::NO_ERROR;
+    use windows::Win32::NetworkManagement::WNet::{
+        WNetAddConnection2W, CONNECT_TEMPORARY, NETRESOURCEW, RESOURCETYPE_DISK,
+    };
+
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(std::iter::once(0)).collect() };
+    let mut remote = wide(share);
+    let resource =
+        NETRESOURCEW { dwType: RESOURCETYPE_DISK, lpRemoteName: PWSTR(remote.as_mut_ptr()), ..Default::default() };
+    let credentials = (!user.is_empty()).then(|| (wide(user), wide(password)));
+    let (user_ptr, password_ptr) = match credentials {
+        Some((ref user, ref password)) => (PCWSTR(user.as_ptr()), PCWSTR(password.as_ptr())),
+        None => (PCWSTR::null(), PCWSTR::null()),
+    };
+
+    let result = unsafe { WNetAddConnection2W(&resource, password_ptr, user_ptr, CONNECT_TEMPORARY) };
+    if result != NO_ERROR {
+        let error = std::io::Error::from_raw_os_error(result.0 as i32);
+        // 已用其他凭据连接过同一服务器时返回 ERROR_SESSION_CREDENTIAL_CONFLICT，共享仍可访问
+        if Path::new(share).exists() {
+            log::warn!("[NETWORK SOURCE] 连接 {} 返回错误，共享仍可访问: {}", share, error);
+            return Ok(());
+        }
+        bail!("连接共享 {} 失败: {}", share, error);
+    }
+    log::info!("[NETWORK SOURCE] 已连接共享 {}", share);
+    Ok(())
+}
+
+/// 计算文件的 SHA-256（小写十六进制）
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).context("读取文件失败")?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 下载 HTTP 镜像到 `dest` 并校验 SHA-256，已存在校验一致的文件时直接使用；进度为 0-100
+///
+/// 下载中断、不完整或校验不一致时删除下载的文件并返回错误。
+pub fn download(url: &str, sha256: &str, dest: &Path, mut on_progress: impl FnMut(u8)) -> Result<()> {
+    if dest.is_file() {
+        if file_sha256(dest)? == sha256 {
+            log::info!("[NETWORK SOURCE] 已存在校验一致的下载文件: {}", dest.display());
+            on_progress(100);
+            return Ok(());
+        }
+        log::warn!("[NETWORK SOURCE] 已有的下载文件校验不一致，重新下载: {}", dest.display());
+        std::fs::remove_file(dest).context("删除校验不一致的文件失败")?;
+    }
+
+    let response = ureq::AgentBuilder::new()
+        .timeout_connect(std::time::Duration::from_secs(30))
+        .build()
+        .get(url)
+        .call()
+        .map_err(|e| anyhow::anyhow!("下载镜像失败: {}", e))?;
+    let total: u64 = response.header("Content-Length").and_then(|len| len.parse().ok()).unwrap_or(0);
+
+    let partial = dest.with_extension("part");
+    let actual = match write_partial(response.into_reader(), &partial, total, &mut on_progress) {
+        Ok(actual) => actual,
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+    if actual != sha256 {
+        let _ = std::fs::remove_file(&partial);
+        bail!("下载的镜像校验不一致，已删除: 期望 {}，实际 {}", sha256, actual);
+    }
+    std::fs::rename(&partial, dest).context("保存下载文件失败")?;
+    Ok(())
+}
+
+/// 把响应写入 `partial`，返回内容的 SHA-256（十六进制）；`total` 为 0 表示长度未知
+fn write_partial(mut reader: impl Read, partial: &Path, total: u64, on_progress: &mut impl FnMut(u8)) -> Result<String> {
+    let mut file = std::fs::File::create(partial).context("创建下载文件失败")?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut downloaded: u64 = 0;
+    let mut last_percent = 0;
+    loop {
+        let read = reader.read(&mut buffer).context("下载镜像中断")?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read]).context("写入下载文件失败")?;
+        hasher.update(&buffer[..read]);
+        downloaded += read as u64;
+        if let Some(percent) = (downloaded * 100).checked_div(total).map(|percent| percent as u8) {
+            if percent != last_percent {
+                last_percent = percent;
+                on_progress(percent);
+            }
+        }
+    }
+    file.flush()?;
+
+    if total > 0 && downloaded != total {
+        bail!("下载不完整: {}/{} 字节", downloaded, total);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 准备网络镜像，返回可供 DISM / Ghost 读取的本地或 UNC 路径
+pub fn prepare(
+    source: &NetworkSource,
+    user: &str,
+    password: &str,
+    download_dir: &str,
+    on_progress: impl FnMut(u8),
+) -> Result<String> {
+    if let Err(reason) = source.check() {
+        bail!("{}", reason);
+    }
+    match source {
+        NetworkSource::Share { share, path } => {
+            connect_share(share, user, password)?;
+            if !Path::new(path).exists() {
+                bail!("共享中的镜像不存在: {}", path);
+            }
+            Ok(path.clone())
+        }
+        NetworkSource::Http { url, sha256 } => {
+            let dest = Path::new(download_dir).join(source.file_name());
+            log::info!("[NETWORK SOURCE] 下载 {} -> {}", url, dest.display());
+            download(url, sha256, &dest, on_progress)?;
+            Ok(dest.to_string_lossy().to_string())
+        }
+        NetworkSource::Netcast { addr, key, .. } => {
+            log::info!("[NETWORK SOURCE] 从局域网发送端接收镜像: {}", addr.as_deref().unwrap_or("自动发现"));
+            let dest = netcast::receive(addr.as_deref(), key, Path::new(download_dir), on_progress)?;
+            Ok(dest.to_string_lossy().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_parse_share() {
+        let source = NetworkSource::parse("\\\\deploy\\images\\win11\\install.wim").unwrap();
+        assert_eq!(
+            source,
+            NetworkSource::Share {
+                share: "\\\\deploy\\images".to_string(),
+                path: "\\\\deploy\\images\\win11\\install.wim".to_string(),
+            }
+        );
+        assert_eq!(source.file_name(), "install.wim");
+        assert_eq!(source.check(), Ok(()));
+        assert_eq!(NetworkSource::parse("//10.0.0.2/images/a.gho").unwrap().file_name(), "a.gho");
+        assert!(NetworkSource::parse("\\\\deploy\\images").is_none());
+        assert!(NetworkSource::parse("\\\\deploy\\\\install.wim").is_none());
+        assert!(NetworkSource::parse("D:\\images\\install.wim").is_none());
+        assert!(NetworkSource::parse("").is_none());
+    }
+
+    #[test]
+    fn test_parse_http() {
+        let source = NetworkSource::parse(&format!(
+            "https://deploy.lab/images/win11.esd?token=1#sha256={}",
+            HASH.to_uppercase()
+        ))
+        .unwrap();
+        assert_eq!(
+            source,
+            NetworkSource::Http {
+                url: "https://deploy.lab/images/win11.esd?token=1".to_string(),
+                sha256: HASH.to_string(),
+            }
+        );
+        assert_eq!(source.file_name(), "win11.esd");
+        assert!(!source.is_gho());
+        assert_eq!(source.check(), Ok(()));
+        assert_eq!(NetworkSource::parse("HTTP://deploy.lab/").unwrap().file_name(), DEFAULT_FILE_NAME);
+        assert!(NetworkSource::parse("http:///install.wim").is_none());
+        assert!(NetworkSource::parse("ftp://deploy.lab/install.wim").is_none());
+        assert!(NetworkSource::parse("http://deploy.lab/xp.GHO").unwrap().is_gho());
+
+        // 没有校验值或校验值格式不对时不能安装
+        assert!(NetworkSource::parse("http://deploy.lab/install.wim").unwrap().check().is_err());
+        assert!(NetworkSource::parse("http://deploy.lab/install.wim#sha256=abc").unwrap().check().is_err());
+    }
+
+    #[test]
+    fn test_parse_netcast() {
+        assert_eq!(
+            NetworkSource::parse("netcast://192.168.1.10:9870/win11.wim?key=0123abcd").unwrap(),
+            NetworkSource::Netcast {
+                addr: Some("192.168.1.10:9870".to_string()),
+                name: "win11.wim".to_string(),
+                key: "0123abcd".to_string(),
+            }
+        );
+        let discover = NetworkSource::parse("NETCAST://").unwrap();
+        assert_eq!(discover, NetworkSource::Netcast { addr: None, name: String::new(), key: String::new() });
+        assert!(discover.check().is_err());
+        assert_eq!(
+            NetworkSource::parse("netcast://?key=0123abcd").unwrap(),
+            NetworkSource::Netcast { addr: None, name: String::new(), key: "0123abcd".to_string() }
+        );
+        assert_eq!(discover.file_name(), DEFAULT_FILE_NAME);
+        assert!(NetworkSource::parse("netcast:///xp.gho").unwrap().is_gho());
+    }
+
+    #[test]
+    fn test_file_sha256() {
+        let path = std::env::temp_dir().join(format!("lr_network_source_{}", std::process::id()));
+        std::fs::write(&path, "test").unwrap();
+        assert_eq!(file_sha256(&path).unwrap(), HASH);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_partial() {
+        let path = std::env::temp_dir().join(format!("lr_network_source_{}.part", std::process::id()));
+        let mut progress = Vec::new();
+        assert_eq!(write_partial(&b"test"[..], &path, 4, &mut |p| progress.push(p)).unwrap(), HASH);
+        assert_eq!(progress, [100]);
+        assert!(write_partial(&b"test"[..], &path, 8, &mut |_| {}).unwrap_err().to_string().contains("下载不完整"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
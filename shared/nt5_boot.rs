@@ -0,0 +1,124 @@
+//! XP / 2003（NT5）引导
+//!
+//! NT5 系统没有 bcdboot 所需的引导文件，只能由 MBR 磁盘上活动分区中的 NTLDR 以 Legacy 方式启动。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::{Path, PathBuf};
+
+/// NT5（XP / 2003）引导所需的文件
+pub const NT5_LOADER_FILES: [&str; 2] = ["ntldr", "NTDETECT.COM"];
+
+/// 只有 Vista 及以上才有的文件（相对 Windows 目录），任意一个存在即不是 NT5
+const VISTA_MARKERS: [&str; 4] = [
+    "System32\\winload.exe",
+    "System32\\ci.dll",
+    "System32\\config\\COMPONENTS",
+    "Boot\\PCAT",
+];
+
+/// 是否为 XP / 2003 等 NT5 系统
+///
+/// `kernel_major` 为 ntoskrnl.exe 的主版本号；读不到版本时才按文件判断，
+/// 缺少 winload.exe 的损坏的 Vista 及以上系统仍有其他特征文件，不会被当成 NT5。
+pub fn is_nt5_system(windows_partition: &str, kernel_major: Option<u32>) -> bool {
+    let windows = windows_dir(windows_partition);
+    let exists = |relative: &str| relative.split('\\').fold(windows.clone(), |path, part| path.join(part)).exists();
+    if !exists("System32\\ntoskrnl.exe") {
+        return false;
+    }
+    match kernel_major {
+        Some(major) => major == 5,
+        None => !VISTA_MARKERS.iter().any(|marker| exists(marker)),
+    }
+}
+
+/// 分区上的 Windows 目录（"C:" 需要补上根目录，否则会被当成 C 盘的当前目录）
+fn windows_dir(windows_partition: &str) -> PathBuf {
+    let partition = windows_partition.trim_end_matches('\\');
+    if partition.ends_with(':') {
+        PathBuf::from(format!("{}\\Windows", partition))
+    } else {
+        Path::new(partition).join("Windows")
+    }
+}
+
+/// 从 diskpart "detail partition" 输出中解析分区号
+pub fn parse_partition_number(output: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let index = parts
+            .iter()
+            .position(|p| p.eq_ignore_ascii_case("partition") || *p == "分区")?;
+        parts.get(index + 1)?.parse().ok()
+    })
+}
+
+/// 生成 boot.ini 内容
+///
+/// NTLDR 和系统在同一块磁盘上，从该磁盘启动时它是 BIOS 的第一块硬盘，默认项使用 rdisk(0)；
+/// 系统所在磁盘不是 0 号磁盘时追加一项按磁盘序号的 ARC 路径，供不调整磁盘顺序的 BIOS 选择。
+pub fn boot_ini_content(disk_number: u32, partition_number: u32) -> String {
+    let arc_path = |rdisk: u32| format!("multi(0)disk(0)rdisk({})partition({})\\WINDOWS", rdisk, partition_number);
+    let mut lines = vec![
+        "[boot loader]".to_string(),
+        "timeout=3".to_string(),
+        format!("default={}", arc_path(0)),
+        "[operating systems]".to_string(),
+        format!("{}=\"Microsoft Windows\" /noexecute=optin /fastdetect", arc_path(0)),
+    ];
+    if disk_number != 0 {
+        lines.push(format!(
+            "{}=\"Microsoft Windows (disk {})\" /noexecute=optin /fastdetect",
+            arc_path(disk_number),
+            disk_number
+        ));
+    }
+    lines.push(String::new());
+    lines.join("\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partition_number() {
+        assert_eq!(parse_partition_number("\nPartition 2\nType  : 07\n"), Some(2));
+        assert_eq!(parse_partition_number("\n分区 3\n类型  : 07\n"), Some(3));
+        assert_eq!(parse_partition_number("没有选择分区。"), None);
+    }
+
+    #[test]
+    fn test_boot_ini_content() {
+        let content = boot_ini_content(0, 2);
+        assert!(content.starts_with("[boot loader]\r\n"));
+        assert!(content.contains("default=multi(0)disk(0)rdisk(0)partition(2)\\WINDOWS\r\n"));
+        assert!(content.ends_with("/fastdetect\r\n"));
+        assert!(!content.contains("rdisk(1)"));
+
+        let content = boot_ini_content(1, 3);
+        assert!(content.contains("default=multi(0)disk(0)rdisk(0)partition(3)\\WINDOWS\r\n"));
+        assert!(content.contains("multi(0)disk(0)rdisk(1)partition(3)\\WINDOWS=\"Microsoft Windows (disk 1)\""));
+    }
+
+    #[test]
+    fn test_is_nt5_system() {
+        let root = std::env::temp_dir().join(format!("lr_nt5_{}", std::process::id()));
+        let system32 = root.join("Windows").join("System32");
+        std::fs::create_dir_all(&system32).unwrap();
+        let partition = root.to_string_lossy().to_string();
+        assert!(!is_nt5_system(&partition, Some(5)));
+
+        std::fs::write(system32.join("ntoskrnl.exe"), b"").unwrap();
+        assert!(is_nt5_system(&partition, Some(5)));
+        assert!(!is_nt5_system(&partition, Some(6)));
+        assert!(is_nt5_system(&partition, None));
+
+        // 缺少 winload.exe 的损坏系统仍有 Vista 及以上的特征文件
+        std::fs::create_dir_all(system32.join("config")).unwrap();
+        std::fs::write(system32.join("config").join("COMPONENTS"), b"").unwrap();
+        assert!(!is_nt5_system(&partition, None));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,449 @@
+//! 离线注册表编辑模块
+//!
+//! 通过 RegLoadKeyW 把目标分区系统的配置单元加载到 HKLM 下，提供按类型读写、删除的操作：
+//! - SOFTWARE、SYSTEM：系统级设置和服务
+//! - 默认用户的 NTUSER.DAT：新建用户的 HKCU 模板（如经典右键菜单）
+//!
+//! 会话结束（drop）时自动卸载已加载的配置单元，出错提前返回也不会残留挂载。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+/// 卸载配置单元的重试次数（注册表句柄可能尚未全部关闭）
+const UNLOAD_RETRIES: u32 = 4;
+
+/// 启用加载、替换配置单元所需的还原和备份权限
+pub fn enable_hive_privileges() -> Result<()> {
+    imp::enable_privilege("SeRestorePrivilege")?;
+    imp::enable_privilege("SeBackupPrivilege")
+}
+
+/// 离线配置单元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineHive {
+    /// Windows\System32\config\SOFTWARE
+    Software,
+    /// Windows\System32\config\SYSTEM
+    System,
+    /// Users\Default\NTUSER.DAT
+    DefaultUser,
+}
+
+impl OfflineHive {
+    pub const ALL: [OfflineHive; 3] = [OfflineHive::Software, OfflineHive::System, OfflineHive::DefaultUser];
+
+    /// 加载到 HKLM 下的键名（与导入 .reg 文件时的路径转换一致）
+    pub fn mount_name(&self) -> &'static str {
+        match self {
+            OfflineHive::Software => "pc-soft",
+            OfflineHive::System => "pc-sys",
+            OfflineHive::DefaultUser => "pc-default",
+        }
+    }
+
+    /// 目标分区上的配置单元文件（target_partition 形如 "D:"）
+    pub fn file_path(&self, target_partition: &str) -> PathBuf {
+        let relative = match self {
+            OfflineHive::Software => "Windows\\System32\\config\\SOFTWARE",
+            OfflineHive::System => "Windows\\System32\\config\\SYSTEM",
+            OfflineHive::DefaultUser => "Users\\Default\\NTUSER.DAT",
+        };
+        PathBuf::from(format!("{}\\{}", target_partition.trim_end_matches('\\'), relative))
+    }
+
+    /// 配置单元内的键相对 HKLM 的路径
+    pub fn subkey_path(&self, key: &str) -> String {
+        let key = key.trim_matches('\\');
+        if key.is_empty() {
+            self.mount_name().to_string()
+        } else {
+            format!("{}\\{}", self.mount_name(), key)
+        }
+    }
+}
+
+/// 已加载的离线注册表
+///
+/// drop 时卸载全部已加载的配置单元。
+pub struct OfflineRegistrySession {
+    target_partition: String,
+    loaded: Vec<OfflineHive>,
+}
+
+impl OfflineRegistrySession {
+    /// 加载目标分区的配置单元，任一加载失败时卸载已加载的并返回错误
+    pub fn open(target_partition: &str, hives: &[OfflineHive]) -> Result<Self> {
+        enable_hive_privileges()?;
+        let mut session = Self {
+            target_partition: target_partition.to_string(),
+            loaded: Vec::new(),
+        };
+        for &hive in hives {
+            session.load(hive)?;
+        }
+        Ok(session)
+    }
+
+    /// 加载配置单元（已加载时直接返回）
+    pub fn load(&mut self, hive: OfflineHive) -> Result<()> {
+        if self.is_loaded(hive) {
+            return Ok(());
+        }
+        let file = hive.file_path(&self.target_partition);
+        if !file.exists() {
+            bail!("配置单元不存在: {}", file.display());
+        }
+        // 上次异常退出可能残留同名挂载
+        let _ = imp::unload(hive.mount_name());
+        imp::load(hive.mount_name(), &file)
+            .with_context(|| format!("加载配置单元失败: {}", file.display()))?;
+        log::info!("[REGISTRY] 已加载 {} -> HKLM\\{}", file.display(), hive.mount_name());
+        self.loaded.push(hive);
+        Ok(())
+    }
+
+    /// 卸载配置单元（未加载时直接返回）
+    pub fn unload(&mut self, hive: OfflineHive) -> Result<()> {
+        if !self.is_loaded(hive) {
+            return Ok(());
+        }
+        let mut attempt = 1;
+        loop {
+            match imp::unload(hive.mount_name()) {
+                Ok(()) => break,
+                Err(_) if attempt < UNLOAD_RETRIES => {
+                    attempt += 1;
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+                Err(e) => return Err(e.context(format!("卸载 HKLM\\{} 失败", hive.mount_name()))),
+            }
+        }
+        self.loaded.retain(|&h| h != hive);
+        Ok(())
+    }
+
+    /// 配置单元是否已加载
+    pub fn is_loaded(&self, hive: OfflineHive) -> bool {
+        self.loaded.contains(&hive)
+    }
+
+    /// 临时卸载全部配置单元执行 f（DISM 离线操作需要独占访问），结束后重新加载
+    pub fn while_unloaded<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let hives = self.loaded.clone();
+        for &hive in &hives {
+            if let Err(e) = self.unload(hive) {
+                log::warn!("[REGISTRY] {:#}", e);
+            }
+        }
+        let result = f();
+        for hive in hives {
+            if let Err(e) = self.load(hive) {
+                log::warn!("[REGISTRY] {:#}", e);
+            }
+        }
+        result
+    }
+
+    /// 写入 REG_DWORD 值（键不存在时创建）
+    pub fn set_dword(&self, hive: OfflineHive, key: &str, name: &str, value: u32) -> Result<()> {
+        imp::set_dword(&self.path(hive, key)?, name, value)
+            .with_context(|| format!("写入 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 写入 REG_SZ 值（键不存在时创建，name 为空表示默认值）
+    pub fn set_string(&self, hive: OfflineHive, key: &str, name: &str, value: &str) -> Result<()> {
+        imp::set_string(&self.path(hive, key)?, name, value, false)
+            .with_context(|| format!("写入 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 写入 REG_EXPAND_SZ 值（用于包含环境变量引用的路径）
+    pub fn set_expand_string(&self, hive: OfflineHive, key: &str, name: &str, value: &str) -> Result<()> {
+        imp::set_string(&self.path(hive, key)?, name, value, true)
+            .with_context(|| format!("写入 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 读取 REG_DWORD 值，键或值不存在时返回 None
+    pub fn get_dword(&self, hive: OfflineHive, key: &str, name: &str) -> Result<Option<u32>> {
+        imp::get_dword(&self.path(hive, key)?, name)
+            .with_context(|| format!("读取 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 读取字符串值，键或值不存在时返回 None
+    pub fn get_string(&self, hive: OfflineHive, key: &str, name: &str) -> Result<Option<String>> {
+        imp::get_string(&self.path(hive, key)?, name)
+            .with_context(|| format!("读取 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 创建键（已存在时不做处理）
+    pub fn create_key(&self, hive: OfflineHive, key: &str) -> Result<()> {
+        imp::create_key(&self.path(hive, key)?)
+            .with_context(|| format!("创建 {} 失败", hive.subkey_path(key)))
+    }
+
+    /// 删除值（不存在时忽略）
+    pub fn delete_value(&self, hive: OfflineHive, key: &str, name: &str) -> Result<()> {
+        imp::delete_value(&self.path(hive, key)?, name)
+            .with_context(|| format!("删除 {}\\{} 失败", hive.subkey_path(key), name))
+    }
+
+    /// 删除键及其全部子键（不存在时忽略）
+    pub fn delete_key(&self, hive: OfflineHive, key: &str) -> Result<()> {
+        if key.trim_matches('\\').is_empty() {
+            bail!("不能删除配置单元根键");
+        }
+        imp::delete_key(&self.path(hive, key)?)
+            .with_context(|| format!("删除 {} 失败", hive.subkey_path(key)))
+    }
+
+    fn path(&self, hive: OfflineHive, key: &str) -> Result<String> {
+        if !self.is_loaded(hive) {
+            bail!("配置单元未加载: HKLM\\{}", hive.mount_name());
+        }
+        Ok(hive.subkey_path(key))
+    }
+}
+
+impl Drop for OfflineRegistrySession {
+    fn drop(&mut self) {
+        for hive in self.loaded.clone() {
+            if let Err(e) = self.unload(hive) {
+                log::warn!("[REGISTRY] {:#}", e);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// 平台实现
+// ============================================================================
+
+#[cfg(windows)]
+mod imp {
+    use std::ffi::OsStr;
+    use std::io::ErrorKind;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    use anyhow::{bail, Result};
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, GetLastError, HANDLE, LUID};
+    use windows::Win32::Security::{
+        AdjustTokenPrivileges, LookupPrivilegeValueW, LUID_AND_ATTRIBUTES, SE_PRIVILEGE_ENABLED,
+        TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY,
+    };
+    use windows::Win32::System::Registry::{RegLoadKeyW, RegUnLoadKeyW, HKEY_LOCAL_MACHINE};
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+    use winreg::enums::{KEY_SET_VALUE, REG_EXPAND_SZ, REG_SZ};
+    use winreg::{RegKey, RegValue};
+
+    fn to_wide(s: &OsStr) -> Vec<u16> {
+        s.encode_wide().chain(Some(0)).collect()
+    }
+
+    fn hklm() -> RegKey {
+        RegKey::predef(winreg::enums::HKEY_LOCAL_MACHINE)
+    }
+
+    /// 键或值不存在视为 None
+    fn optional<T>(result: std::io::Result<T>) -> Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn enable_privilege(privilege_name: &str) -> Result<()> {
+        unsafe {
+            let mut token_handle = HANDLE::default();
+            if let Err(e) = OpenProcessToken(GetCurrentProcess(), TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY, &mut token_handle) {
+                bail!("OpenProcessToken 失败: {}", e);
+            }
+
+            let wide_name = to_wide(OsStr::new(privilege_name));
+            let mut luid = LUID::default();
+            if let Err(e) = LookupPrivilegeValueW(PCWSTR::null(), PCWSTR(wide_name.as_ptr()), &mut luid) {
+                let _ = CloseHandle(token_handle);
+                bail!("LookupPrivilegeValueW 失败: {}", e);
+            }
+
+            let tp = TOKEN_PRIVILEGES {
+                PrivilegeCount: 1,
+                Privileges: [LUID_AND_ATTRIBUTES {
+                    Luid: luid,
+                    Attributes: SE_PRIVILEGE_ENABLED,
+                }],
+            };
+            if let Err(e) = AdjustTokenPrivileges(token_handle, false, Some(&tp), 0, None, None) {
+                let _ = CloseHandle(token_handle);
+                bail!("AdjustTokenPrivileges 失败: {}", e);
+            }
+
+            // AdjustTokenPrivileges 成功时也可能设置错误码，1300 = ERROR_NOT_ALL_ASSIGNED
+            let last_error = GetLastError();
+            let _ = CloseHandle(token_handle);
+            if last_error.0 != 0 && last_error.0 != 1300 {
+                log::warn!("[REGISTRY] AdjustTokenPrivileges 警告: 错误码 {}", last_error.0);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(mount_name: &str, file: &Path) -> Result<()> {
+        let wide_key = to_wide(OsStr::new(mount_name));
+        let wide_file = to_wide(file.as_os_str());
+        let result = unsafe {
+            RegLoadKeyW(HKEY_LOCAL_MACHINE, PCWSTR(wide_key.as_ptr()), PCWSTR(wide_file.as_ptr()))
+        };
+        if result.0 != 0 {
+            bail!("RegLoadKeyW 失败: 错误码 {}", result.0);
+        }
+        Ok(())
+    }
+
+    pub fn unload(mount_name: &str) -> Result<()> {
+        let wide_key = to_wide(OsStr::new(mount_name));
+        let result = unsafe { RegUnLoadKeyW(HKEY_LOCAL_MACHINE, PCWSTR(wide_key.as_ptr())) };
+        if result.0 != 0 {
+            bail!("RegUnLoadKeyW 失败: 错误码 {}", result.0);
+        }
+        Ok(())
+    }
+
+    pub fn set_dword(path: &str, name: &str, value: u32) -> Result<()> {
+        let (key, _) = hklm().create_subkey(path)?;
+        key.set_value(name, &value)?;
+        Ok(())
+    }
+
+    pub fn set_string(path: &str, name: &str, value: &str, expand: bool) -> Result<()> {
+        let (key, _) = hklm().create_subkey(path)?;
+        let bytes = value
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        let vtype = if expand { REG_EXPAND_SZ } else { REG_SZ };
+        key.set_raw_value(name, &RegValue { bytes, vtype })?;
+        Ok(())
+    }
+
+    pub fn get_dword(path: &str, name: &str) -> Result<Option<u32>> {
+        let Some(key) = optional(hklm().open_subkey(path))? else {
+            return Ok(None);
+        };
+        optional(key.get_value(name))
+    }
+
+    pub fn get_string(path: &str, name: &str) -> Result<Option<String>> {
+        let Some(key) = optional(hklm().open_subkey(path))? else {
+            return Ok(None);
+        };
+        optional(key.get_value(name))
+    }
+
+    pub fn create_key(path: &str) -> Result<()> {
+        hklm().create_subkey(path)?;
+        Ok(())
+    }
+
+    pub fn delete_value(path: &str, name: &str) -> Result<()> {
+        let Some(key) = optional(hklm().open_subkey_with_flags(path, KEY_SET_VALUE))? else {
+            return Ok(());
+        };
+        optional(key.delete_value(name))?;
+        Ok(())
+    }
+
+    pub fn delete_key(path: &str) -> Result<()> {
+        optional(hklm().delete_subkey_all(path))?;
+        Ok(())
+    }
+}
+
+#[cfg(not(windows))]
+mod imp {
+    use std::path::Path;
+
+    use anyhow::{bail, Result};
+
+    pub fn enable_privilege(_privilege_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn load(_mount_name: &str, _file: &Path) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn unload(_mount_name: &str) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn set_dword(_path: &str, _name: &str, _value: u32) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn set_string(_path: &str, _name: &str, _value: &str, _expand: bool) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn get_dword(_path: &str, _name: &str) -> Result<Option<u32>> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn get_string(_path: &str, _name: &str) -> Result<Option<String>> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn create_key(_path: &str) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn delete_value(_path: &str, _name: &str) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+
+    pub fn delete_key(_path: &str) -> Result<()> {
+        bail!("仅支持 Windows 平台")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hive_file_path() {
+        assert_eq!(
+            OfflineHive::Software.file_path("D:"),
+            PathBuf::from("D:\\Windows\\System32\\config\\SOFTWARE")
+        );
+        assert_eq!(
+            OfflineHive::DefaultUser.file_path("D:\\"),
+            PathBuf::from("D:\\Users\\Default\\NTUSER.DAT")
+        );
+    }
+
+    #[test]
+    fn test_subkey_path() {
+        assert_eq!(OfflineHive::System.subkey_path(""), "pc-sys");
+        assert_eq!(
+            OfflineHive::System.subkey_path("\\ControlSet001\\Services\\wuauserv"),
+            "pc-sys\\ControlSet001\\Services\\wuauserv"
+        );
+    }
+
+    #[test]
+    fn test_operations_require_loaded_hive() {
+        let session = OfflineRegistrySession {
+            target_partition: "D:".to_string(),
+            loaded: Vec::new(),
+        };
+        assert!(session.set_dword(OfflineHive::Software, "Test", "Value", 1).is_err());
+        assert!(session.delete_key(OfflineHive::Software, "").is_err());
+    }
+}
@@ -0,0 +1,550 @@
+//! 分区类型与分区规划
+//!
+//! [`PartitionPlan`] 描述对一块磁盘的操作：无需操作、使用 mbr2gpt 无损转换为 GPT，
+//! 或清空磁盘后按规划重新创建分区（ESP、MSR、系统分区、恢复分区）。
+//! 执行方式由各端的 `DiskManager::plan_and_apply` 决定。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+
+use crate::core::disk::PartitionStyle;
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+use crate::utils::path::get_bin_dir;
+
+/// GPT 分区类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GptPartitionType {
+    /// 基本数据分区
+    #[default]
+    BasicData,
+    /// EFI 系统分区
+    Esp,
+    /// 微软保留分区
+    Msr,
+    /// Windows 恢复分区
+    Recovery,
+}
+
+impl GptPartitionType {
+    /// 全部类型（按显示顺序）
+    pub const ALL: [GptPartitionType; 4] = [
+        GptPartitionType::BasicData,
+        GptPartitionType::Esp,
+        GptPartitionType::Msr,
+        GptPartitionType::Recovery,
+    ];
+
+    /// 分区类型 GUID（diskpart `set id` 使用的格式）
+    pub fn guid(&self) -> &'static str {
+        match self {
+            GptPartitionType::BasicData => "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+            GptPartitionType::Esp => "c12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+            GptPartitionType::Msr => "e3c9e316-0b5c-4db8-817d-f92df00215ae",
+            GptPartitionType::Recovery => "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
+        }
+    }
+
+    /// 根据类型 GUID 识别分区类型（忽略大小写和花括号）
+    pub fn from_guid(guid: &str) -> Option<Self> {
+        let guid = guid.trim().trim_matches(|c| c == '{' || c == '}');
+        Self::ALL
+            .into_iter()
+            .find(|t| t.guid().eq_ignore_ascii_case(guid))
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            GptPartitionType::BasicData => "基本数据",
+            GptPartitionType::Esp => "EFI 系统分区",
+            GptPartitionType::Msr => "微软保留分区",
+            GptPartitionType::Recovery => "恢复分区",
+        }
+    }
+
+    /// 创建该类型分区时默认设置的属性
+    pub fn default_attributes(&self) -> GptAttributes {
+        match self {
+            GptPartitionType::Recovery => GptAttributes(GptAttributes::REQUIRED | GptAttributes::NO_DRIVE_LETTER),
+            _ => GptAttributes::default(),
+        }
+    }
+}
+
+/// GPT 分区属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GptAttributes(pub u64);
+
+impl GptAttributes {
+    /// 平台必需（磁盘管理不允许删除）
+    pub const REQUIRED: u64 = 0x0000_0000_0000_0001;
+    /// 隐藏（不自动挂载）
+    pub const HIDDEN: u64 = 0x4000_0000_0000_0000;
+    /// 不分配盘符
+    pub const NO_DRIVE_LETTER: u64 = 0x8000_0000_0000_0000;
+
+    /// 可在界面中设置的属性及其名称
+    pub const FLAGS: [(u64, &'static str); 3] = [
+        (Self::HIDDEN, "隐藏"),
+        (Self::NO_DRIVE_LETTER, "无盘符"),
+        (Self::REQUIRED, "必需"),
+    ];
+
+    pub fn contains(&self, flag: u64) -> bool {
+        self.0 & flag != 0
+    }
+
+    pub fn set(&mut self, flag: u64, enabled: bool) {
+        if enabled {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+
+    /// 已设置属性的名称，如 "隐藏、无盘符"
+    pub fn describe(&self) -> String {
+        Self::FLAGS
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join("、")
+    }
+}
+
+/// 分区类型和属性的显示文本，如 "恢复分区 (无盘符、必需)"
+pub fn describe_gpt_partition(type_guid: &str, attributes: GptAttributes) -> String {
+    let type_name = GptPartitionType::from_guid(type_guid)
+        .map(|t| t.label())
+        .unwrap_or("其他");
+    let attrs = attributes.describe();
+    if attrs.is_empty() {
+        type_name.to_string()
+    } else {
+        format!("{} ({})", type_name, attrs)
+    }
+}
+
+/// 设置分区类型的 diskpart 命令（需已选中分区）
+pub fn gpt_type_command(partition_type: GptPartitionType) -> String {
+    format!("set id={} override\n", partition_type.guid())
+}
+
+/// 设置 GPT 分区属性的 diskpart 命令（需已选中分区）
+pub fn gpt_attributes_command(attributes: GptAttributes) -> String {
+    format!("gpt attributes=0x{:016x}\n", attributes.0)
+}
+
+/// EFI 系统分区大小（MB）
+pub const ESP_SIZE_MB: u64 = 300;
+/// 微软保留分区大小（MB）
+pub const MSR_SIZE_MB: u64 = 16;
+/// 恢复分区大小（MB）
+pub const RECOVERY_SIZE_MB: u64 = 1024;
+
+/// 规划中的分区
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedPartition {
+    /// 分区类型（MBR 磁盘上 ESP/恢复分区使用对应的 MBR 类型 ID）
+    pub partition_type: GptPartitionType,
+    /// 大小（MB），None 表示使用剩余空间
+    pub size_mb: Option<u64>,
+    /// 卷标（为空时使用“新加卷”）
+    pub label: String,
+    /// 文件系统（为空时使用 NTFS，MSR 分区不格式化）
+    pub file_system: String,
+    /// 盘符（为空时自动分配）
+    pub drive_letter: Option<char>,
+    /// GPT 分区属性（仅 GPT 磁盘生效）
+    pub gpt_attributes: GptAttributes,
+    /// 设为活动分区（仅 MBR 磁盘生效，Legacy 启动的系统分区）
+    pub active: bool,
+}
+
+impl PlannedPartition {
+    /// 按分区类型填入默认的卷标、文件系统和属性
+    pub fn new(partition_type: GptPartitionType, size_mb: Option<u64>) -> Self {
+        let (label, file_system) = match partition_type {
+            GptPartitionType::Esp => ("EFI", "FAT32"),
+            GptPartitionType::Msr => ("", ""),
+            GptPartitionType::Recovery => ("Recovery", "NTFS"),
+            GptPartitionType::BasicData => ("", "NTFS"),
+        };
+        Self {
+            partition_type,
+            size_mb,
+            label: label.to_string(),
+            file_system: file_system.to_string(),
+            drive_letter: None,
+            gpt_attributes: partition_type.default_attributes(),
+            active: false,
+        }
+    }
+}
+
+/// 分区表转换方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskConversion {
+    /// 分区表已符合要求，无需操作
+    None,
+    /// 使用 mbr2gpt 无损转换为 GPT（保留现有分区和数据，自动创建 ESP）
+    Mbr2Gpt,
+    /// 清空磁盘、转换分区表后按规划重新创建分区（数据全部丢失）
+    Repartition,
+}
+
+/// 磁盘分区规划，由 [`DiskManager::plan_and_apply`] 执行
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionPlan {
+    pub disk_number: u32,
+    /// 目标分区表类型
+    pub target_style: PartitionStyle,
+    pub conversion: DiskConversion,
+    /// 重新分区时按顺序创建的分区
+    pub partitions: Vec<PlannedPartition>,
+}
+
+impl PartitionPlan {
+    /// 保留数据，仅让分区表匹配固件启动模式（UEFI 需要 GPT）
+    ///
+    /// MBR 磁盘在 UEFI 下规划为 mbr2gpt 无损转换；GPT 无法无损转换为 MBR，Legacy 下返回错误。
+    pub fn match_firmware(disk_number: u32, current_style: PartitionStyle, uefi: bool) -> Result<Self> {
+        let target_style = if uefi { PartitionStyle::GPT } else { PartitionStyle::MBR };
+        let conversion = match (current_style, uefi) {
+            (PartitionStyle::MBR, true) => DiskConversion::Mbr2Gpt,
+            (PartitionStyle::GPT, false) => {
+                anyhow::bail!("磁盘 {} 为 GPT 分区表，无法无损转换为 MBR，请改用 UEFI 启动或使用一键分区重新分区", disk_number)
+            }
+            (PartitionStyle::Unknown, _) => anyhow::bail!("无法识别磁盘 {} 的分区表类型", disk_number),
+            _ => DiskConversion::None,
+        };
+        Ok(Self {
+            disk_number,
+            target_style,
+            conversion,
+            partitions: Vec::new(),
+        })
+    }
+
+    /// 清空磁盘后按给定分区重新分区
+    pub fn repartition(disk_number: u32, target_style: PartitionStyle, partitions: Vec<PlannedPartition>) -> Self {
+        Self {
+            disk_number,
+            target_style,
+            conversion: DiskConversion::Repartition,
+            partitions,
+        }
+    }
+
+    /// 清空磁盘后创建 Windows 标准布局
+    ///
+    /// UEFI：ESP + MSR + 系统分区 (+ 恢复分区)，GPT 分区表；
+    /// Legacy：系统分区（活动）(+ 恢复分区)，MBR 分区表。系统分区使用剩余空间。
+    pub fn standard(disk_number: u32, uefi: bool, os_letter: Option<char>, with_recovery: bool) -> Self {
+        let mut partitions = Vec::new();
+        if uefi {
+            partitions.push(PlannedPartition::new(GptPartitionType::Esp, Some(ESP_SIZE_MB)));
+            partitions.push(PlannedPartition::new(GptPartitionType::Msr, Some(MSR_SIZE_MB)));
+        }
+        let mut os = PlannedPartition::new(GptPartitionType::BasicData, None);
+        os.label = "OS".to_string();
+        os.drive_letter = os_letter;
+        os.active = !uefi;
+        partitions.push(os);
+        if with_recovery {
+            partitions.push(PlannedPartition::new(GptPartitionType::Recovery, Some(RECOVERY_SIZE_MB)));
+        }
+        let target_style = if uefi { PartitionStyle::GPT } else { PartitionStyle::MBR };
+        Self::repartition(disk_number, target_style, partitions)
+    }
+
+    /// 是否会清除磁盘上的数据
+    pub fn is_destructive(&self) -> bool {
+        self.conversion == DiskConversion::Repartition
+    }
+
+    /// 规划说明（用于确认提示和日志）
+    pub fn describe(&self) -> String {
+        match self.conversion {
+            DiskConversion::None => format!("磁盘 {} 已是 {} 分区表，无需转换", self.disk_number, self.target_style),
+            DiskConversion::Mbr2Gpt => format!("使用 mbr2gpt 将磁盘 {} 无损转换为 GPT 分区表", self.disk_number),
+            DiskConversion::Repartition => {
+                let parts: Vec<String> = self
+                    .partitions
+                    .iter()
+                    .map(|p| {
+                        let size = p.size_mb.map_or("剩余空间".to_string(), |mb| format!("{} MB", mb));
+                        format!("{} {}", p.partition_type.label(), size)
+                    })
+                    .collect();
+                format!(
+                    "清空磁盘 {} 并转换为 {}：{}",
+                    self.disk_number,
+                    self.target_style,
+                    parts.join(" + ")
+                )
+            }
+        }
+    }
+
+    /// 生成重新分区的 diskpart 脚本，同时返回创建的分区名称
+    pub fn diskpart_script(&self) -> Result<(String, Vec<String>)> {
+        let is_gpt = match self.target_style {
+            PartitionStyle::GPT => true,
+            PartitionStyle::MBR => false,
+            PartitionStyle::Unknown => anyhow::bail!("无效的分区表类型"),
+        };
+        if self.partitions.is_empty() {
+            anyhow::bail!("没有要创建的分区");
+        }
+
+        let mut script = format!(
+            "select disk {}\nclean\nconvert {}\n",
+            self.disk_number,
+            if is_gpt { "gpt" } else { "mbr" }
+        );
+        let mut created = Vec::new();
+
+        for (i, partition) in self.partitions.iter().enumerate() {
+            let is_last = i == self.partitions.len() - 1;
+            match partition.partition_type {
+                GptPartitionType::Esp if is_gpt => {
+                    let size_mb = partition.size_mb.unwrap_or(ESP_SIZE_MB);
+                    script.push_str(&format!("create partition efi size={}\n", size_mb));
+                    script.push_str(&format!("format fs=fat32 quick label=\"{}\"\n", partition.label));
+                    created.push("ESP".to_string());
+                    continue;
+                }
+                GptPartitionType::Msr if is_gpt => {
+                    // MSR 分区不能格式化，也不分配盘符
+                    let size_mb = partition.size_mb.unwrap_or(MSR_SIZE_MB);
+                    script.push_str(&format!("create partition msr size={}\n", size_mb));
+                    created.push("MSR".to_string());
+                    continue;
+                }
+                GptPartitionType::Msr => anyhow::bail!("MBR 磁盘不支持微软保留分区"),
+                _ => {}
+            }
+
+            // 最后一个分区使用剩余空间；中间的分区使用剩余空间时，为其后的分区缩小出空间
+            let reserve_mb = match partition.size_mb {
+                Some(size_mb) if !is_last => {
+                    script.push_str(&format!("create partition primary size={}\n", size_mb));
+                    0
+                }
+                _ => {
+                    script.push_str("create partition primary\n");
+                    self.partitions[i + 1..]
+                        .iter()
+                        .map(|p| p.size_mb.ok_or_else(|| anyhow::anyhow!("只有一个分区可以使用剩余空间")))
+                        .sum::<Result<u64>>()?
+                }
+            };
+
+            let label = if partition.label.is_empty() { "新加卷" } else { &partition.label };
+            let fs = if partition.file_system.is_empty() { "NTFS" } else { &partition.file_system };
+            script.push_str(&format!("format fs={} quick label=\"{}\"\n", fs, label));
+            if reserve_mb > 0 {
+                script.push_str(&format!("shrink desired={} minimum={}\n", reserve_mb, reserve_mb));
+            }
+
+            let hide_letter = if is_gpt {
+                if partition.partition_type != GptPartitionType::BasicData {
+                    script.push_str(&gpt_type_command(partition.partition_type));
+                }
+                if partition.gpt_attributes != GptAttributes::default() {
+                    script.push_str(&gpt_attributes_command(partition.gpt_attributes));
+                }
+                partition.gpt_attributes.contains(GptAttributes::NO_DRIVE_LETTER)
+            } else {
+                match partition.partition_type {
+                    GptPartitionType::Esp => script.push_str("set id=ef override\n"),
+                    GptPartitionType::Recovery => script.push_str("set id=27 override\n"),
+                    _ => {}
+                }
+                if partition.active {
+                    script.push_str("active\n");
+                }
+                partition.partition_type == GptPartitionType::Recovery
+            };
+
+            if hide_letter {
+                created.push(partition.partition_type.label().to_string());
+            } else if let Some(letter) = partition.drive_letter {
+                script.push_str(&format!("assign letter={}\n", letter));
+                created.push(format!("{}:", letter));
+            } else {
+                script.push_str("assign\n");
+                created.push(format!("分区 {}", i + 1));
+            }
+        }
+
+        Ok((script, created))
+    }
+
+    /// 使用 diskpart 执行重新分区，返回创建的分区名称（会清空整个磁盘，调用方须事先确认）
+    pub fn apply_with_diskpart(&self, diskpart: &str) -> Result<Vec<String>> {
+        let (script, created) = self.diskpart_script()?;
+        log::debug!("[DISK] Diskpart 脚本内容:\n{}", script);
+
+        let script_path = std::env::temp_dir().join("lr_partition_plan.txt");
+        std::fs::write(&script_path, &script)?;
+        let output = new_command(diskpart)
+            .args(["/s", script_path.to_str().unwrap()])
+            .output();
+        let _ = std::fs::remove_file(&script_path);
+        let output = output.context("执行 diskpart 失败")?;
+
+        let output_text = gbk_to_utf8(&output.stdout);
+        log::info!("[DISK] Diskpart 输出: {}", output_text);
+        let output_lower = output_text.to_lowercase();
+        if ["错误", "error", "失败", "failed"].iter().any(|k| output_lower.contains(k)) {
+            anyhow::bail!("分区操作失败: {}", output_text);
+        }
+        Ok(created)
+    }
+}
+
+/// mbr2gpt 退出码说明
+fn mbr2gpt_error(code: i32) -> &'static str {
+    match code {
+        1 => "用户取消",
+        2 => "内部错误",
+        3 => "初始化失败",
+        4 => "命令行参数无效",
+        5 => "读取磁盘布局失败",
+        6 => "磁盘不满足转换条件（最多 3 个主分区、不能有扩展分区、需有活动的系统分区和可引导的 Windows）",
+        7 => "创建 EFI 系统分区失败",
+        8 => "安装 UEFI 引导文件失败",
+        9 => "写入 GPT 分区表失败",
+        _ => "未知错误",
+    }
+}
+
+/// 使用 mbr2gpt 将 MBR 磁盘无损转换为 GPT（优先使用 bin 目录中的 mbr2gpt.exe）
+///
+/// 在正常系统中运行时需要 `allow_full_os`（mbr2gpt 默认只允许在 PE 中转换）
+pub fn convert_mbr_to_gpt(disk_number: u32, allow_full_os: bool) -> Result<()> {
+    let builtin = get_bin_dir().join("mbr2gpt.exe");
+    let mbr2gpt = if builtin.exists() {
+        builtin.to_string_lossy().to_string()
+    } else {
+        "mbr2gpt.exe".to_string()
+    };
+
+    let mut args = vec!["/convert".to_string(), format!("/disk:{}", disk_number)];
+    if allow_full_os {
+        args.push("/allowFullOS".to_string());
+    }
+    let output = new_command(&mbr2gpt)
+        .args(&args)
+        .output()
+        .context("无法运行 mbr2gpt（需要 Windows 10 1703 及以上版本的系统或 PE）")?;
+    log::info!("[DISK] mbr2gpt 输出: {}", gbk_to_utf8(&output.stdout));
+
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // 已转换为 GPT，但部分 BCD 项未能恢复，之后修复引导会重新写入
+        Some(100) => {
+            log::warn!("[DISK] mbr2gpt 转换完成，但部分引导配置未能恢复");
+            Ok(())
+        }
+        Some(code) => anyhow::bail!("mbr2gpt 转换磁盘 {} 失败 (错误码 {}): {}", disk_number, code, mbr2gpt_error(code)),
+        None => anyhow::bail!("mbr2gpt 被异常终止"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpt_partition_type_from_guid() {
+        assert_eq!(
+            GptPartitionType::from_guid("{DE94BBA4-06D1-4D40-A16A-BFD50179D6AC}"),
+            Some(GptPartitionType::Recovery)
+        );
+        assert_eq!(
+            GptPartitionType::from_guid("ebd0a0a2-b9e5-4433-87c0-68b6b72699c7"),
+            Some(GptPartitionType::BasicData)
+        );
+        assert_eq!(GptPartitionType::from_guid("0FC63DAF-8483-4772-8E79-3D69D8477DE4"), None);
+    }
+
+    #[test]
+    fn test_match_firmware_plan() {
+        let plan = PartitionPlan::match_firmware(1, PartitionStyle::MBR, true).unwrap();
+        assert_eq!(plan.conversion, DiskConversion::Mbr2Gpt);
+        assert_eq!(plan.target_style, PartitionStyle::GPT);
+        assert!(!plan.is_destructive());
+
+        let plan = PartitionPlan::match_firmware(1, PartitionStyle::GPT, true).unwrap();
+        assert_eq!(plan.conversion, DiskConversion::None);
+        assert!(PartitionPlan::match_firmware(1, PartitionStyle::MBR, false).is_ok());
+        assert!(PartitionPlan::match_firmware(1, PartitionStyle::GPT, false).is_err());
+        assert!(PartitionPlan::match_firmware(1, PartitionStyle::Unknown, true).is_err());
+    }
+
+    #[test]
+    fn test_standard_uefi_script() {
+        let plan = PartitionPlan::standard(2, true, Some('C'), true);
+        assert!(plan.is_destructive());
+        let (script, created) = plan.diskpart_script().unwrap();
+        assert_eq!(
+            script,
+            "select disk 2\nclean\nconvert gpt\n\
+             create partition efi size=300\nformat fs=fat32 quick label=\"EFI\"\n\
+             create partition msr size=16\n\
+             create partition primary\nformat fs=NTFS quick label=\"OS\"\n\
+             shrink desired=1024 minimum=1024\nassign letter=C\n\
+             create partition primary\nformat fs=NTFS quick label=\"Recovery\"\n\
+             set id=de94bba4-06d1-4d40-a16a-bfd50179d6ac override\ngpt attributes=0x8000000000000001\n"
+        );
+        assert_eq!(created, vec!["ESP", "MSR", "C:", "恢复分区"]);
+    }
+
+    #[test]
+    fn test_standard_legacy_script() {
+        let plan = PartitionPlan::standard(0, false, None, false);
+        let (script, created) = plan.diskpart_script().unwrap();
+        assert_eq!(
+            script,
+            "select disk 0\nclean\nconvert mbr\n\
+             create partition primary\nformat fs=NTFS quick label=\"OS\"\nactive\nassign\n"
+        );
+        assert_eq!(created, vec!["分区 1"]);
+    }
+
+    #[test]
+    fn test_repartition_rejects_invalid_plans() {
+        let rest = PlannedPartition::new(GptPartitionType::BasicData, None);
+        let plan = PartitionPlan::repartition(0, PartitionStyle::GPT, vec![rest.clone(), rest.clone()]);
+        assert!(plan.diskpart_script().is_err());
+
+        let msr = PlannedPartition::new(GptPartitionType::Msr, Some(MSR_SIZE_MB));
+        let plan = PartitionPlan::repartition(0, PartitionStyle::MBR, vec![msr, rest]);
+        assert!(plan.diskpart_script().is_err());
+
+        let plan = PartitionPlan::repartition(0, PartitionStyle::GPT, Vec::new());
+        assert!(plan.diskpart_script().is_err());
+    }
+
+    #[test]
+    fn test_gpt_attributes() {
+        let attrs = GptPartitionType::Recovery.default_attributes();
+        assert_eq!(gpt_attributes_command(attrs), "gpt attributes=0x8000000000000001\n");
+        assert_eq!(
+            describe_gpt_partition(GptPartitionType::Recovery.guid(), attrs),
+            "恢复分区 (无盘符、必需)"
+        );
+
+        let mut attrs = GptAttributes::default();
+        attrs.set(GptAttributes::HIDDEN, true);
+        assert_eq!(describe_gpt_partition("", attrs), "其他 (隐藏)");
+        attrs.set(GptAttributes::HIDDEN, false);
+        assert_eq!(attrs.0, 0);
+    }
+}
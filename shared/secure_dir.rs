@@ -0,0 +1,103 @@
+//! 敏感文件暂存目录
+//!
+//! Wi-Fi 明文密码、BitLocker 临时密钥等需要跨越重启交给 PE 或新系统的文件放在单独的目录中：
+//! 创建时去掉继承的权限，只允许 SYSTEM 和管理员访问；守卫离开作用域时删除目录，
+//! 安装失败或提前返回时不会把这些文件留在磁盘上。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::utils::command::new_command;
+use crate::utils::encoding::gbk_to_utf8;
+
+/// 只允许 SYSTEM 和 Administrators 访问（去掉从上级目录继承的 Users 等权限）
+pub fn restrict_to_admins(path: &Path) -> Result<()> {
+    let grant = if path.is_dir() { "(OI)(CI)F" } else { "F" };
+    let output = new_command("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r"])
+        .arg(format!("*S-1-5-18:{}", grant))
+        .arg(format!("*S-1-5-32-544:{}", grant))
+        .output()
+        .context("执行 icacls 失败")?;
+    if !output.status.success() {
+        bail!("设置访问权限失败: {}", gbk_to_utf8(&output.stdout).trim());
+    }
+    Ok(())
+}
+
+/// 敏感文件目录，离开作用域时删除，调用 [`SensitiveDir::keep`] 后保留
+pub struct SensitiveDir {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl SensitiveDir {
+    /// 新建目录（已存在时先清空）并限制访问权限
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).with_context(|| format!("创建目录失败: {}", path.display()))?;
+        // 限制权限失败时守卫随错误返回一起删除目录
+        let dir = Self { path, keep: false };
+        restrict_to_admins(&dir.path)?;
+        Ok(dir)
+    }
+
+    /// 接管上一阶段留下的目录，不存在时返回 None
+    pub fn existing(path: impl Into<PathBuf>) -> Option<Self> {
+        let path = path.into();
+        path.is_dir().then_some(Self { path, keep: false })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 目录已交给下一阶段（PE 或新系统）处理，离开作用域时不再删除
+    pub fn keep(&mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for SensitiveDir {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => log::info!("已删除敏感文件目录: {}", self.path.display()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => log::warn!("删除敏感文件目录失败: {} - {}", self.path.display(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_removed_unless_kept() {
+        let base = std::env::temp_dir().join(format!("lr_secure_dir_{}", std::process::id()));
+        let removed = base.join("removed");
+        let kept = base.join("kept");
+        std::fs::create_dir_all(&removed).unwrap();
+        std::fs::create_dir_all(&kept).unwrap();
+        std::fs::write(removed.join("secret.txt"), "x").unwrap();
+
+        drop(SensitiveDir::existing(&removed).unwrap());
+        assert!(!removed.exists());
+
+        let mut guard = SensitiveDir::existing(&kept).unwrap();
+        guard.keep();
+        drop(guard);
+        assert!(kept.exists());
+
+        assert!(SensitiveDir::existing(base.join("missing")).is_none());
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! 触屏布局
+//!
+//! 在PE中维修平板/二合一设备时往往没有实体键盘和鼠标。
+//! 检测到触摸屏时放大按钮、间距和字体，并在输入框获得焦点时打开屏幕键盘（如 BitLocker 解锁密码）。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 是否启用触屏布局
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// 触屏布局下按钮的放大倍数
+const BUTTON_SCALE: f32 = 1.4;
+
+/// 触屏布局下字体的放大倍数
+const FONT_SCALE: f32 = 1.2;
+
+/// 检测是否存在可用的触摸屏
+#[cfg(windows)]
+pub fn has_touch_screen() -> bool {
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_DIGITIZER};
+
+    const NID_INTEGRATED_TOUCH: i32 = 0x01;
+    const NID_EXTERNAL_TOUCH: i32 = 0x02;
+    const NID_READY: i32 = 0x80;
+
+    let digitizer = unsafe { GetSystemMetrics(SM_DIGITIZER) };
+    digitizer & NID_READY != 0 && digitizer & (NID_INTEGRATED_TOUCH | NID_EXTERNAL_TOUCH) != 0
+}
+
+#[cfg(not(windows))]
+pub fn has_touch_screen() -> bool {
+    false
+}
+
+/// 检测触摸屏并决定是否启用触屏布局
+pub fn detect() -> bool {
+    let enabled = has_touch_screen();
+    ENABLED.store(enabled, Ordering::SeqCst);
+    if enabled {
+        log::info!("检测到触摸屏，启用触屏布局");
+    }
+    enabled
+}
+
+/// 是否启用了触屏布局
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// 放大控件尺寸、间距和字体（未启用时不做修改）
+pub fn apply_style(ctx: &egui::Context) {
+    if !is_enabled() {
+        return;
+    }
+    ctx.all_styles_mut(|style| {
+        for font in style.text_styles.values_mut() {
+            font.size *= FONT_SCALE;
+        }
+        style.spacing.interact_size.y = 40.0;
+        style.spacing.button_padding = egui::vec2(16.0, 10.0);
+        style.spacing.item_spacing = egui::vec2(12.0, 12.0);
+        style.spacing.icon_width = 24.0;
+        style.spacing.scroll.bar_width = 16.0;
+        style.spacing.scroll.floating = false;
+    });
+}
+
+/// 按钮尺寸（触屏布局下放大）
+pub fn button_size(width: f32, height: f32) -> [f32; 2] {
+    if is_enabled() {
+        [width * BUTTON_SCALE, height * BUTTON_SCALE]
+    } else {
+        [width, height]
+    }
+}
+
+/// 打开屏幕键盘
+pub fn show_on_screen_keyboard() {
+    match crate::utils::command::new_command("osk.exe").spawn() {
+        Ok(_) => log::info!("已打开屏幕键盘"),
+        Err(e) => log::warn!("打开屏幕键盘失败: {}", e),
+    }
+}
+
+/// 触屏布局下输入框获得焦点时打开屏幕键盘，并在输入框后显示键盘按钮（键盘被关闭后可再次打开）
+///
+/// 需在输入框之后、同一个 `ui.horizontal` 中调用
+pub fn keyboard_for(ui: &mut egui::Ui, response: &egui::Response) {
+    if !is_enabled() {
+        return;
+    }
+    if response.gained_focus() {
+        show_on_screen_keyboard();
+    }
+    if ui.button("⌨").on_hover_text("屏幕键盘").clicked() {
+        response.request_focus();
+        show_on_screen_keyboard();
+    }
+}
@@ -0,0 +1,803 @@
+//! 无人值守配置（unattend.xml）生成
+//!
+//! [`UnattendSettings`] 保存界面中可编辑的设置，随 install_config 传给 PE（账户密码不写入配置，
+//! 由 install_secrets 单独传递）；[`UnattendBuilder`] 根据目标系统版本、架构和语言组装完整的 unattend.xml。
+//! 账户密码按 unattend 规则编码（PlainText=false），并在 SetupComplete 阶段从磁盘上的
+//! unattend.xml 中清除，避免凭据留在安装好的系统里。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::core::image_language::TargetLanguage;
+use crate::core::script_hooks;
+
+/// 未指定用户名且未检测到目标系统语言时创建的本地账户
+const DEFAULT_USERNAME: &str = "User";
+/// 清除 unattend.xml 中密码的脚本（位于 Setup\Scripts，执行后删除自身）
+const SANITIZE_SCRIPT: &str = "LetRecovery_SanitizeUnattend.ps1";
+
+/// 把 unattend.xml 中的密码替换为系统自身使用的占位文本，并删除 Sysprep 目录中的副本
+///
+/// 不直接删除 Panther\unattend.xml：首次登录命令等设置在 SetupComplete 之后仍可能读取它。
+const SANITIZE_TEMPLATE: &str = r#"$ErrorActionPreference = 'SilentlyContinue'
+$files = @("$env:SystemRoot\Panther\unattend.xml", "$env:SystemRoot\Panther\Unattend\unattend.xml")
+foreach ($file in $files) {
+    if (Test-Path $file) {
+        $xml = [System.IO.File]::ReadAllText($file)
+        $xml = [regex]::Replace($xml, '(<(?:Administrator)?Password>\s*<Value>)[^<]*(</Value>)', '${1}*SENSITIVE*DATA*DELETED*${2}')
+        [System.IO.File]::WriteAllText($file, $xml)
+    }
+}
+Remove-Item "$env:SystemRoot\System32\Sysprep\unattend.xml" -Force
+Remove-Item $MyInvocation.MyCommand.Path -Force
+"#;
+
+/// 首次联网时的网络位置（仅 Win7/Win8 的 OOBE 使用）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NetworkLocation {
+    #[default]
+    Home,
+    Work,
+    Other,
+}
+
+impl NetworkLocation {
+    pub const ALL: [NetworkLocation; 3] = [NetworkLocation::Home, NetworkLocation::Work, NetworkLocation::Other];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkLocation::Home => "Home",
+            NetworkLocation::Work => "Work",
+            NetworkLocation::Other => "Other",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NetworkLocation::Home => "家庭网络",
+            NetworkLocation::Work => "工作网络",
+            NetworkLocation::Other => "公用网络",
+        }
+    }
+}
+
+/// 可编辑的无人值守设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct UnattendSettings {
+    /// 系统区域和用户区域（如 zh-CN），为空表示沿用镜像默认
+    pub locale: String,
+    /// 界面语言（须已安装对应语言包），为空表示沿用镜像默认
+    #[serde(rename = "UILanguage")]
+    pub ui_language: String,
+    /// 输入法区域（如 zh-CN 或 0804:00000804），为空表示沿用镜像默认
+    pub input_locale: String,
+    /// 时区（Windows 时区 ID，如 China Standard Time），为空表示沿用镜像默认
+    pub time_zone: String,
+    /// 产品密钥，为空表示不写入
+    pub product_key: String,
+    /// 计算机名，为空表示随机生成
+    pub computer_name: String,
+    /// 本地账户密码，为空表示无密码（不随配置序列化，通过 PE 安装时由 install_secrets 传递）
+    #[serde(skip)]
+    pub password: String,
+    /// 首次启动自动登录一次
+    pub auto_logon: bool,
+    /// 网络位置（Win7/Win8）
+    pub network_location: NetworkLocation,
+    /// 使用推荐的自动更新保护设置（否则关闭）
+    pub recommended_protection: bool,
+    /// 跳过 OOBE 隐私设置页（Win10/11）
+    pub skip_privacy_experience: bool,
+    /// 关闭诊断数据收集（Win10/11）
+    pub disable_telemetry: bool,
+    /// 关闭广告 ID（Win10/11）
+    pub disable_advertising_id: bool,
+    /// 关闭定位服务（Win10/11）
+    pub disable_location: bool,
+}
+
+impl Default for UnattendSettings {
+    fn default() -> Self {
+        Self {
+            locale: String::new(),
+            ui_language: String::new(),
+            input_locale: String::new(),
+            time_zone: String::new(),
+            product_key: String::new(),
+            computer_name: String::new(),
+            password: String::new(),
+            auto_logon: true,
+            network_location: NetworkLocation::default(),
+            recommended_protection: false,
+            skip_privacy_experience: false,
+            disable_telemetry: false,
+            disable_advertising_id: false,
+            disable_location: false,
+        }
+    }
+}
+
+impl UnattendSettings {
+    /// 校验计算机名和产品密钥
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        validate_computer_name(&self.computer_name)?;
+        validate_product_key(&self.product_key)?;
+        Ok(())
+    }
+
+    /// 隐私选项对应的注册表命令（在 specialize 阶段执行）
+    fn privacy_commands(&self) -> Vec<(String, &'static str)> {
+        let policies = [
+            (self.skip_privacy_experience, "OOBE", "DisablePrivacyExperience", 1, "Skip privacy experience"),
+            (self.disable_telemetry, "DataCollection", "AllowTelemetry", 0, "Disable telemetry"),
+            (self.disable_advertising_id, "AdvertisingInfo", "DisabledByGroupPolicy", 1, "Disable advertising ID"),
+            (self.disable_location, "LocationAndSensors", "DisableLocation", 1, "Disable location"),
+        ];
+        policies
+            .into_iter()
+            .filter(|(enabled, ..)| *enabled)
+            .map(|(_, key, name, value, description)| {
+                (
+                    format!(
+                        "reg add \"HKLM\\SOFTWARE\\Policies\\Microsoft\\Windows\\{}\" /v {} /t REG_DWORD /d {} /f",
+                        key, name, value
+                    ),
+                    description,
+                )
+            })
+            .collect()
+    }
+}
+
+/// 校验计算机名：最多 15 个字符，只能包含字母、数字和连字符，且不能全是数字
+pub fn validate_computer_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name == "*" {
+        return Ok(());
+    }
+    if name.len() > 15 {
+        return Err("计算机名不能超过 15 个字符".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("计算机名只能包含字母、数字和连字符: {}", name));
+    }
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        return Err("计算机名不能全是数字".to_string());
+    }
+    Ok(())
+}
+
+/// 校验产品密钥格式（XXXXX-XXXXX-XXXXX-XXXXX-XXXXX）
+pub fn validate_product_key(key: &str) -> std::result::Result<(), String> {
+    if key.is_empty() {
+        return Ok(());
+    }
+    let groups: Vec<&str> = key.split('-').collect();
+    let valid = groups.len() == 5
+        && groups
+            .iter()
+            .all(|g| g.len() == 5 && g.chars().all(|c| c.is_ascii_alphanumeric()));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("产品密钥格式应为 XXXXX-XXXXX-XXXXX-XXXXX-XXXXX: {}", key))
+    }
+}
+
+/// 目标系统版本（决定 OOBE 中可用的选项）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnattendTarget {
+    Win7,
+    /// Windows 8 / 8.1
+    Win8,
+    /// Windows 10 / 11
+    Win10,
+}
+
+impl UnattendTarget {
+    /// 通过目标系统 ntdll.dll 的文件版本检测，无法读取时按 Win10/11 处理
+    pub fn detect(target_partition: &str) -> Self {
+        let ntdll_path = Path::new(target_partition).join("Windows").join("System32").join("ntdll.dll");
+        match crate::core::system_utils::get_file_version(&ntdll_path) {
+            Some((major, minor, build, _)) => {
+                log::info!("[UNATTEND] 检测到目标系统版本 (ntdll.dll): {}.{}.{}", major, minor, build);
+                Self::from_version(major, minor)
+            }
+            None => {
+                log::warn!("[UNATTEND] 无法读取 ntdll.dll 版本: {:?}, 默认使用 Win10/11 配置", ntdll_path);
+                UnattendTarget::Win10
+            }
+        }
+    }
+
+    /// Windows 7: 6.1，Windows 8: 6.2，Windows 8.1: 6.3，其他按 Win10/11 处理
+    fn from_version(major: impl Into<u32>, minor: impl Into<u32>) -> Self {
+        match (major.into(), minor.into()) {
+            (6, 1) => UnattendTarget::Win7,
+            (6, 2) | (6, 3) => UnattendTarget::Win8,
+            _ => UnattendTarget::Win10,
+        }
+    }
+}
+
+/// Windows 11 硬件要求检查绕过，避免在不满足要求的电脑上安装后卡在 OOBE
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Win11Bypass {
+    pub tpm: bool,
+    pub secure_boot: bool,
+    pub ram: bool,
+}
+
+impl Win11Bypass {
+    /// 需要写为 1 的注册表值（相对 SYSTEM 配置单元的路径, 值名）
+    pub fn registry_values(&self) -> Vec<(&'static str, &'static str)> {
+        [
+            (self.tpm, "Setup\\LabConfig", "BypassTPMCheck"),
+            (self.secure_boot, "Setup\\LabConfig", "BypassSecureBootCheck"),
+            (self.ram, "Setup\\LabConfig", "BypassRAMCheck"),
+            // 之后通过安装程序就地升级时同样跳过 TPM/CPU 检查
+            (self.tpm, "Setup\\MoSetup", "AllowUpgradesWithUnsupportedTPMOrCPU"),
+        ]
+        .into_iter()
+        .filter(|(enabled, ..)| *enabled)
+        .map(|(_, key, name)| (key, name))
+        .collect()
+    }
+
+    /// 写入注册表值的命令
+    fn commands(&self) -> Vec<(String, String)> {
+        self.registry_values()
+            .into_iter()
+            .map(|(key, name)| {
+                (
+                    format!("reg add \"HKLM\\SYSTEM\\{}\" /v {} /t REG_DWORD /d 1 /f", key, name),
+                    format!("Set {}", name),
+                )
+            })
+            .collect()
+    }
+}
+
+/// unattend.xml 构建器
+pub struct UnattendBuilder {
+    arch: String,
+    target: UnattendTarget,
+    /// 自定义用户名，为空时按目标系统语言决定
+    username: Option<String>,
+    language: Option<TargetLanguage>,
+    settings: UnattendSettings,
+    win11_bypass: Win11Bypass,
+    deploy_commands: Vec<(String, String)>,
+    first_logon_commands: Vec<(String, String)>,
+}
+
+impl UnattendBuilder {
+    /// `arch` 为 processorArchitecture（amd64/x86/arm64）
+    pub fn new(arch: &str, target: UnattendTarget) -> Self {
+        Self {
+            arch: arch.to_string(),
+            target,
+            username: None,
+            language: None,
+            settings: UnattendSettings::default(),
+            win11_bypass: Win11Bypass::default(),
+            deploy_commands: Vec::new(),
+            first_logon_commands: Vec::new(),
+        }
+    }
+
+    /// 本地管理员账户名，为空时使用默认账户名
+    pub fn username(mut self, username: &str) -> Self {
+        if !username.is_empty() {
+            self.username = Some(username.to_string());
+        }
+        self
+    }
+
+    /// 目标系统语言：区域设置留空时沿用该语言，默认账户名和显示名称按该语言本地化
+    pub fn language(mut self, language: Option<TargetLanguage>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// 账户名和显示名称
+    fn account_names(&self) -> (&str, &str) {
+        if let Some(username) = self.username.as_deref() {
+            return (username, username);
+        }
+        match self.language {
+            Some(ref language) => (language.default_username(), language.account_display_name()),
+            None => (DEFAULT_USERNAME, DEFAULT_USERNAME),
+        }
+    }
+
+    pub fn settings(mut self, settings: &UnattendSettings) -> Self {
+        self.settings = settings.clone();
+        self
+    }
+
+    /// Windows 11 硬件要求检查绕过（windowsPE 和 specialize 阶段写入 LabConfig）
+    pub fn win11_bypass(mut self, bypass: Win11Bypass) -> Self {
+        self.win11_bypass = bypass;
+        self
+    }
+
+    /// 部署阶段（specialize）以 SYSTEM 身份执行的命令
+    pub fn deploy_command(mut self, command: impl Into<String>, description: &str) -> Self {
+        self.deploy_commands.push((command.into(), description.to_string()));
+        self
+    }
+
+    /// 首次登录时执行的命令
+    pub fn first_logon_command(mut self, command: impl Into<String>, description: &str) -> Self {
+        self.first_logon_commands.push((command.into(), description.to_string()));
+        self
+    }
+
+    /// 生成 unattend.xml 内容
+    pub fn build(&self) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+             <unattend xmlns=\"urn:schemas-microsoft-com:unattend\" xmlns:wcm=\"http://schemas.microsoft.com/WMIConfig/2002/State\">\n",
+        );
+        self.push_windows_pe(&mut xml);
+        self.push_specialize(&mut xml);
+        self.push_oobe_system(&mut xml);
+        xml.push_str("</unattend>\n");
+        xml
+    }
+
+    fn component(&self, name: &str) -> String {
+        format!(
+            "        <component name=\"{}\" processorArchitecture=\"{}\" publicKeyToken=\"31bf3856ad364e35\" language=\"neutral\" versionScope=\"nonSxS\" xmlns:wcm=\"http://schemas.microsoft.com/WMIConfig/2002/State\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\n",
+            name, self.arch
+        )
+    }
+
+    fn push_windows_pe(&self, xml: &mut String) {
+        xml.push_str("    <settings pass=\"windowsPE\">\n");
+        xml.push_str(&self.component("Microsoft-Windows-Setup"));
+        // 使用该应答文件运行安装程序时，在硬件检查前写入 LabConfig
+        let bypass_commands = self.win11_bypass.commands();
+        if !bypass_commands.is_empty() {
+            xml.push_str("            <RunSynchronous>\n");
+            for (order, (command, description)) in bypass_commands.iter().enumerate() {
+                xml.push_str("                <RunSynchronousCommand wcm:action=\"add\">\n");
+                push_element(xml, 20, "Order", &(order + 1).to_string());
+                push_element(xml, 20, "Path", command);
+                push_element(xml, 20, "Description", description);
+                xml.push_str("                </RunSynchronousCommand>\n");
+            }
+            xml.push_str("            </RunSynchronous>\n");
+        }
+        xml.push_str("            <UserData>\n");
+        xml.push_str("                <ProductKey>\n");
+        xml.push_str("                    <WillShowUI>OnError</WillShowUI>\n");
+        xml.push_str("                </ProductKey>\n");
+        xml.push_str("                <AcceptEula>true</AcceptEula>\n");
+        xml.push_str("            </UserData>\n");
+        xml.push_str("        </component>\n");
+        xml.push_str("    </settings>\n");
+    }
+
+    fn push_specialize(&self, xml: &mut String) {
+        let computer_name = if self.settings.computer_name.is_empty() {
+            "*"
+        } else {
+            self.settings.computer_name.as_str()
+        };
+
+        xml.push_str("    <settings pass=\"specialize\">\n");
+        xml.push_str(&self.component("Microsoft-Windows-Shell-Setup"));
+        push_element(xml, 12, "ComputerName", computer_name);
+        push_element(xml, 12, "ProductKey", &self.settings.product_key);
+        push_element(xml, 12, "TimeZone", &self.settings.time_zone);
+        xml.push_str("        </component>\n");
+
+        let mut commands: Vec<(String, String)> = self.deploy_commands.clone();
+        if self.target == UnattendTarget::Win10 {
+            commands.extend(self.win11_bypass.commands());
+            commands.extend(
+                self.settings
+                    .privacy_commands()
+                    .into_iter()
+                    .map(|(command, description)| (command, description.to_string())),
+            );
+        }
+        if !commands.is_empty() {
+            xml.push_str(&self.component("Microsoft-Windows-Deployment"));
+            xml.push_str("            <RunSynchronous>\n");
+            for (order, (command, description)) in commands.iter().enumerate() {
+                xml.push_str("                <RunSynchronousCommand wcm:action=\"add\">\n");
+                push_element(xml, 20, "Order", &(order + 1).to_string());
+                push_element(xml, 20, "Path", command);
+                push_element(xml, 20, "Description", description);
+                xml.push_str("                </RunSynchronousCommand>\n");
+            }
+            xml.push_str("            </RunSynchronous>\n");
+            xml.push_str("        </component>\n");
+        }
+        xml.push_str("    </settings>\n");
+    }
+
+    fn push_oobe_system(&self, xml: &mut String) {
+        let settings = &self.settings;
+        let (username, display_name) = self.account_names();
+        xml.push_str("    <settings pass=\"oobeSystem\">\n");
+
+        // 留空的区域设置沿用目标系统语言
+        let language = self.language.as_ref().map(|l| l.tag.as_str()).unwrap_or_default();
+        let input_locale = non_empty_or(&settings.input_locale, language);
+        let locale = non_empty_or(&settings.locale, language);
+        let ui_language = non_empty_or(&settings.ui_language, language);
+        if !locale.is_empty() || !ui_language.is_empty() || !input_locale.is_empty() {
+            xml.push_str(&self.component("Microsoft-Windows-International-Core"));
+            push_element(xml, 12, "InputLocale", input_locale);
+            push_element(xml, 12, "SystemLocale", locale);
+            push_element(xml, 12, "UILanguage", ui_language);
+            push_element(xml, 12, "UserLocale", locale);
+            xml.push_str("        </component>\n");
+        }
+
+        xml.push_str(&self.component("Microsoft-Windows-Shell-Setup"));
+        self.push_oobe(xml);
+
+        xml.push_str("            <UserAccounts>\n");
+        xml.push_str("                <LocalAccounts>\n");
+        xml.push_str("                    <LocalAccount wcm:action=\"add\">\n");
+        push_password(xml, 24, &settings.password);
+        xml.push_str("                        <Description>Local User</Description>\n");
+        push_element(xml, 24, "DisplayName", display_name);
+        xml.push_str("                        <Group>Administrators</Group>\n");
+        push_element(xml, 24, "Name", username);
+        xml.push_str("                    </LocalAccount>\n");
+        xml.push_str("                </LocalAccounts>\n");
+        xml.push_str("            </UserAccounts>\n");
+
+        if settings.auto_logon {
+            xml.push_str("            <AutoLogon>\n");
+            push_password(xml, 16, &settings.password);
+            xml.push_str("                <Enabled>true</Enabled>\n");
+            xml.push_str("                <LogonCount>1</LogonCount>\n");
+            push_element(xml, 16, "Username", username);
+            xml.push_str("            </AutoLogon>\n");
+        }
+
+        if !self.first_logon_commands.is_empty() {
+            xml.push_str("            <FirstLogonCommands>\n");
+            for (order, (command, description)) in self.first_logon_commands.iter().enumerate() {
+                xml.push_str("                <SynchronousCommand wcm:action=\"add\">\n");
+                push_element(xml, 20, "Order", &(order + 1).to_string());
+                push_element(xml, 20, "CommandLine", command);
+                push_element(xml, 20, "Description", description);
+                xml.push_str("                </SynchronousCommand>\n");
+            }
+            xml.push_str("            </FirstLogonCommands>\n");
+        }
+
+        xml.push_str("        </component>\n");
+        xml.push_str("    </settings>\n");
+    }
+
+    /// OOBE 选项：Win7 家庭版不支持 HideLocalAccountScreen 等新选项，Win8 不支持跳过联网和 OOBE 页面
+    fn push_oobe(&self, xml: &mut String) {
+        let protect_your_pc = if self.settings.recommended_protection { "1" } else { "3" };
+
+        xml.push_str("            <OOBE>\n");
+        xml.push_str("                <HideEULAPage>true</HideEULAPage>\n");
+        if self.target != UnattendTarget::Win7 {
+            xml.push_str("                <HideLocalAccountScreen>true</HideLocalAccountScreen>\n");
+        }
+        if self.target == UnattendTarget::Win10 {
+            xml.push_str("                <HideOnlineAccountScreens>true</HideOnlineAccountScreens>\n");
+            xml.push_str("                <HideWirelessSetupInOOBE>true</HideWirelessSetupInOOBE>\n");
+        }
+        push_element(xml, 16, "ProtectYourPC", protect_your_pc);
+        if self.target == UnattendTarget::Win10 {
+            xml.push_str("                <SkipMachineOOBE>true</SkipMachineOOBE>\n");
+            xml.push_str("                <SkipUserOOBE>true</SkipUserOOBE>\n");
+        } else {
+            push_element(xml, 16, "NetworkLocation", self.settings.network_location.as_str());
+        }
+        xml.push_str("            </OOBE>\n");
+    }
+}
+
+/// 写入 Panther\unattend.xml（Sysprep 目录存在时同时写入）
+pub fn write_unattend(target_partition: &str, xml: &str) -> Result<()> {
+    let panther_dir = format!("{}\\Windows\\Panther", target_partition);
+    std::fs::create_dir_all(&panther_dir).context("创建 Panther 目录失败")?;
+
+    let unattend_path = format!("{}\\unattend.xml", panther_dir);
+    std::fs::write(&unattend_path, xml).context("写入 unattend.xml 失败")?;
+    log::info!("[UNATTEND] 已写入: {}", unattend_path);
+
+    let sysprep_dir = format!("{}\\Windows\\System32\\Sysprep", target_partition);
+    if Path::new(&sysprep_dir).exists() {
+        let sysprep_unattend = format!("{}\\unattend.xml", sysprep_dir);
+        let _ = std::fs::write(&sysprep_unattend, xml);
+        log::info!("[UNATTEND] 已写入: {}", sysprep_unattend);
+    }
+
+    if contains_credentials(xml) {
+        install_credential_cleanup(target_partition)?;
+    }
+    Ok(())
+}
+
+/// unattend.xml 中是否包含非空密码
+fn contains_credentials(xml: &str) -> bool {
+    xml.contains("<PlainText>false</PlainText>")
+}
+
+/// 在 SetupComplete.cmd 中接入清除密码的脚本
+///
+/// 此时账户已创建、自动登录凭据已由系统保存，unattend.xml 中的密码不再需要。
+fn install_credential_cleanup(target_partition: &str) -> Result<()> {
+    let scripts_dir = format!("{}\\Windows\\Setup\\Scripts", target_partition);
+    std::fs::create_dir_all(&scripts_dir).context("创建 Setup\\Scripts 目录失败")?;
+    std::fs::write(
+        format!("{}\\{}", scripts_dir, SANITIZE_SCRIPT),
+        SANITIZE_TEMPLATE.replace('\n', "\r\n"),
+    )
+    .context("写入密码清除脚本失败")?;
+    script_hooks::append_setup_complete(
+        target_partition,
+        &[format!(
+            "powershell -NoProfile -ExecutionPolicy Bypass -File \"%~dp0{}\"",
+            SANITIZE_SCRIPT
+        )],
+    )
+    .context("接入密码清除脚本失败")?;
+    log::info!("[UNATTEND] 已配置部署完成后清除 unattend.xml 中的密码");
+    Ok(())
+}
+
+/// 值为空时使用默认值
+fn non_empty_or<'a>(value: &'a str, default: &'a str) -> &'a str {
+    if value.is_empty() {
+        default
+    } else {
+        value
+    }
+}
+
+/// 写入元素（值为空时跳过）
+fn push_element(xml: &mut String, indent: usize, name: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    xml.push_str(&format!("{:indent$}<{name}>{}</{name}>\n", "", escape_xml(value), indent = indent, name = name));
+}
+
+/// 写入 Password 元素（非空密码按 unattend 规则编码，不以明文保存）
+fn push_password(xml: &mut String, indent: usize, password: &str) {
+    let (value, plain_text) = if password.is_empty() {
+        (String::new(), "true")
+    } else {
+        (encode_password(password), "false")
+    };
+    let pad = " ".repeat(indent);
+    xml.push_str(&format!("{pad}<Password>\n"));
+    xml.push_str(&format!("{pad}    <Value>{}</Value>\n", value));
+    xml.push_str(&format!("{pad}    <PlainText>{}</PlainText>\n", plain_text));
+    xml.push_str(&format!("{pad}</Password>\n"));
+}
+
+/// unattend 密码编码：Base64(UTF-16LE(密码 + "Password"))
+fn encode_password(password: &str) -> String {
+    let bytes: Vec<u8> = format!("{}Password", password)
+        .encode_utf16()
+        .flat_map(|c| c.to_le_bytes())
+        .collect();
+    base64_encode(&bytes)
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_password() {
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(encode_password(""), "UABhAHMAcwB3AG8AcgBkAA==");
+    }
+
+    #[test]
+    fn test_target_from_version() {
+        assert_eq!(UnattendTarget::from_version(6u32, 1u32), UnattendTarget::Win7);
+        assert_eq!(UnattendTarget::from_version(6u16, 3u16), UnattendTarget::Win8);
+        assert_eq!(UnattendTarget::from_version(10u32, 0u32), UnattendTarget::Win10);
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        let mut settings = UnattendSettings::default();
+        assert!(settings.validate().is_ok());
+
+        settings.computer_name = "DESKTOP-01".to_string();
+        settings.product_key = "VK7JG-NPHTM-C97JM-9MPGT-3V66T".to_string();
+        assert!(settings.validate().is_ok());
+
+        settings.computer_name = "12345".to_string();
+        assert!(settings.validate().is_err());
+        settings.computer_name = "a_very_long_computer_name".to_string();
+        assert!(settings.validate().is_err());
+
+        settings.computer_name.clear();
+        settings.product_key = "VK7JG-NPHTM".to_string();
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_build_defaults_match_previous_template() {
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win10).build();
+        assert!(xml.contains("<ComputerName>*</ComputerName>"));
+        assert!(xml.contains("<Name>User</Name>"));
+        assert!(xml.contains("<ProtectYourPC>3</ProtectYourPC>"));
+        assert!(xml.contains("<SkipUserOOBE>true</SkipUserOOBE>"));
+        assert!(xml.contains("<LogonCount>1</LogonCount>"));
+        assert!(!xml.contains("NetworkLocation"));
+        assert!(!xml.contains("International-Core"));
+        assert!(!xml.contains("RunSynchronous"));
+        assert!(!xml.contains("FirstLogonCommands"));
+        assert!(!xml.contains("<Key>"));
+    }
+
+    #[test]
+    fn test_build_follows_target_language() {
+        let xml = UnattendBuilder::new("arm64", UnattendTarget::Win10)
+            .language(Some(TargetLanguage::new("de-DE")))
+            .build();
+        assert!(xml.contains("<InputLocale>de-DE</InputLocale>"));
+        assert!(xml.contains("<UILanguage>de-DE</UILanguage>"));
+        assert!(xml.contains("<Name>Benutzer</Name>"));
+        assert!(xml.contains("<Username>Benutzer</Username>"));
+
+        // 界面中的设置和自定义用户名优先
+        let settings = UnattendSettings {
+            locale: "en-GB".to_string(),
+            ..UnattendSettings::default()
+        };
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win10)
+            .username("Admin")
+            .settings(&settings)
+            .language(Some(TargetLanguage::new("zh-CN")))
+            .build();
+        assert!(xml.contains("<SystemLocale>en-GB</SystemLocale>"));
+        assert!(xml.contains("<UILanguage>zh-CN</UILanguage>"));
+        assert!(xml.contains("<DisplayName>Admin</DisplayName>"));
+
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win10)
+            .language(Some(TargetLanguage::new("zh-CN")))
+            .build();
+        assert!(xml.contains("<DisplayName>用户</DisplayName>"));
+        assert!(xml.contains("<Name>User</Name>"));
+    }
+
+    #[test]
+    fn test_build_with_settings() {
+        let settings = UnattendSettings {
+            locale: "zh-CN".to_string(),
+            time_zone: "China Standard Time".to_string(),
+            computer_name: "OFFICE-PC".to_string(),
+            password: "p&ss".to_string(),
+            network_location: NetworkLocation::Work,
+            disable_telemetry: true,
+            ..Default::default()
+        };
+        let xml = UnattendBuilder::new("x86", UnattendTarget::Win7)
+            .username("A<B")
+            .settings(&settings)
+            .deploy_command("cmd /c deploy.bat", "Deploy")
+            .first_logon_command("cmd /c first.bat", "First logon")
+            .build();
+
+        assert!(xml.contains("processorArchitecture=\"x86\""));
+        assert!(xml.contains("<UserLocale>zh-CN</UserLocale>"));
+        assert!(!xml.contains("<UILanguage>"));
+        assert!(xml.contains("<ComputerName>OFFICE-PC</ComputerName>"));
+        assert!(xml.contains("<TimeZone>China Standard Time</TimeZone>"));
+        assert!(xml.contains("<Name>A&lt;B</Name>"));
+        assert!(xml.contains(&format!("<Value>{}</Value>", encode_password("p&ss"))));
+        assert!(xml.contains("<PlainText>false</PlainText>"));
+        assert!(xml.contains("<NetworkLocation>Work</NetworkLocation>"));
+        assert!(!xml.contains("HideLocalAccountScreen"));
+        // 隐私策略只用于 Win10/11
+        assert!(!xml.contains("AllowTelemetry"));
+        assert!(xml.contains("<Path>cmd /c deploy.bat</Path>"));
+        assert!(xml.contains("<CommandLine>cmd /c first.bat</CommandLine>"));
+    }
+
+    #[test]
+    fn test_contains_credentials() {
+        let builder = UnattendBuilder::new("amd64", UnattendTarget::Win10);
+        assert!(!contains_credentials(&builder.build()));
+
+        let settings = UnattendSettings {
+            password: "secret".to_string(),
+            ..Default::default()
+        };
+        let xml = builder.settings(&settings).build();
+        assert!(contains_credentials(&xml));
+        assert!(!xml.contains("secret"));
+    }
+
+    #[test]
+    fn test_privacy_commands_follow_deploy_commands() {
+        let settings = UnattendSettings {
+            disable_telemetry: true,
+            disable_location: true,
+            ..Default::default()
+        };
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win10)
+            .settings(&settings)
+            .deploy_command("cmd /c deploy.bat", "Deploy")
+            .build();
+        let deploy = xml.find("deploy.bat").unwrap();
+        let telemetry = xml.find("AllowTelemetry /t REG_DWORD /d 0").unwrap();
+        let location = xml.find("DisableLocation").unwrap();
+        assert!(deploy < telemetry && telemetry < location);
+        assert!(xml.contains("<Order>3</Order>"));
+    }
+
+    #[test]
+    fn test_win11_bypass_commands() {
+        let bypass = Win11Bypass { tpm: true, secure_boot: false, ram: true };
+        assert_eq!(
+            bypass.registry_values(),
+            vec![
+                ("Setup\\LabConfig", "BypassTPMCheck"),
+                ("Setup\\LabConfig", "BypassRAMCheck"),
+                ("Setup\\MoSetup", "AllowUpgradesWithUnsupportedTPMOrCPU"),
+            ]
+        );
+
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win10).win11_bypass(bypass).build();
+        let line = r#"reg add "HKLM\SYSTEM\Setup\LabConfig" /v BypassTPMCheck /t REG_DWORD /d 1 /f"#;
+        // windowsPE 和 specialize 阶段各写入一次
+        assert_eq!(xml.matches(line).count(), 2);
+        assert!(!xml.contains("BypassSecureBootCheck"));
+
+        // Win7/Win8 没有这些检查，只在 windowsPE 阶段保留
+        let xml = UnattendBuilder::new("amd64", UnattendTarget::Win7).win11_bypass(bypass).build();
+        assert_eq!(xml.matches(line).count(), 1);
+    }
+
+    #[test]
+    fn test_settings_serde_defaults() {
+        let settings: UnattendSettings = serde_json::from_str(r#"{"TimeZone":"UTC","UILanguage":"en-US"}"#).unwrap();
+        assert_eq!(settings.time_zone, "UTC");
+        assert_eq!(settings.ui_language, "en-US");
+        assert!(settings.auto_logon);
+    }
+}
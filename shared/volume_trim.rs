@@ -0,0 +1,304 @@
+//! 空闲空间 TRIM
+//!
+//! 格式化之后、释放镜像之前对 SSD 分区的全部空闲空间下发 TRIM：锁定卷后读取簇位图
+//! （FSCTL_GET_VOLUME_BITMAP），对所有空闲簇范围下发 DSM Trim（IOCTL_STORAGE_MANAGE_DATA_SET_ATTRIBUTES）。
+//! 只支持 NTFS：FAT32/exFAT 的簇号从数据区开始计算，不能直接换算为卷内偏移。
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::Result;
+
+/// 每次读取的簇位图字节数（每字节 8 个簇）
+const TRIM_BITMAP_CHUNK_BYTES: usize = 4 * 1024 * 1024;
+/// 每个 DSM Trim 请求包含的最大范围数
+const TRIM_RANGES_PER_REQUEST: usize = 256;
+/// DeviceDsmAction_Trim
+const DEVICE_DSM_ACTION_TRIM: u32 = 1;
+
+/// 卷所在设备是否支持 TRIM（IOCTL_STORAGE_QUERY_PROPERTY，StorageDeviceTrimProperty）
+///
+/// `device_path` 为可直接打开的卷设备路径（如 `\\.\C:` 或不带结尾反斜杠的卷 GUID 路径）
+#[cfg(windows)]
+pub fn is_trim_supported(device_path: &str) -> bool {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::Storage::FileSystem::{CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING};
+    use windows::Win32::System::Ioctl::{
+        PropertyStandardQuery, StorageDeviceTrimProperty, DEVICE_TRIM_DESCRIPTOR, IOCTL_STORAGE_QUERY_PROPERTY,
+        STORAGE_PROPERTY_QUERY,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    let wide_path: Vec<u16> = device_path.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        let Ok(handle) = CreateFileW(
+            PCWSTR(wide_path.as_ptr()),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            Default::default(),
+            None,
+        ) else {
+            return false;
+        };
+
+        let query = STORAGE_PROPERTY_QUERY {
+            PropertyId: StorageDeviceTrimProperty,
+            QueryType: PropertyStandardQuery,
+            AdditionalParameters: [0],
+        };
+        let mut descriptor = DEVICE_TRIM_DESCRIPTOR::default();
+        let mut bytes_returned: u32 = 0;
+        let result = DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            Some(&query as *const _ as *const _),
+            std::mem::size_of::<STORAGE_PROPERTY_QUERY>() as u32,
+            Some(&mut descriptor as *mut _ as *mut _),
+            std::mem::size_of::<DEVICE_TRIM_DESCRIPTOR>() as u32,
+            Some(&mut bytes_returned),
+            None,
+        );
+        let _ = CloseHandle(handle);
+
+        result.is_ok() && descriptor.TrimEnabled.0 != 0
+    }
+}
+
+#[cfg(not(windows))]
+pub fn is_trim_supported(_device_path: &str) -> bool {
+    false
+}
+
+/// 卷的文件系统名称（如 "NTFS"），读取失败时返回 None
+#[cfg(windows)]
+pub fn file_system_name(partition: &str) -> Option<String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::GetVolumeInformationW;
+
+    let root: Vec<u16> = crate::core::disk::volume_root(partition)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut name = [0u16; 64];
+    unsafe { GetVolumeInformationW(PCWSTR(root.as_ptr()), None, None, None, None, Some(&mut name)).ok()? };
+    Some(String::from_utf16_lossy(&name).trim_end_matches('\0').to_string())
+}
+
+#[cfg(not(windows))]
+pub fn file_system_name(_partition: &str) -> Option<String> {
+    None
+}
+
+/// 簇号是否等于卷内偏移（簇大小为单位），只有 NTFS 满足，其他文件系统不能 TRIM
+pub fn supports_native_trim(file_system: &str) -> bool {
+    file_system.eq_ignore_ascii_case("NTFS")
+}
+
+/// 对分区的全部空闲空间执行 TRIM，返回 TRIM 的字节数（设备不支持时返回 0）
+///
+/// `device_path` 为分区的卷设备路径，见 [`is_trim_supported`]
+#[cfg(windows)]
+pub fn trim_free_space(partition: &str, device_path: &str) -> Result<u64> {
+    use anyhow::Context;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, GENERIC_READ, GENERIC_WRITE};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, GetDiskFreeSpaceW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+
+    let file_system = file_system_name(partition).unwrap_or_default();
+    if !supports_native_trim(&file_system) {
+        anyhow::bail!("{} 的文件系统（{}）不支持 TRIM，仅支持 NTFS", partition, file_system);
+    }
+    if !is_trim_supported(device_path) {
+        log::info!("[TRIM] {} 所在设备不支持 TRIM，跳过", partition);
+        return Ok(0);
+    }
+
+    // 簇大小
+    let root: Vec<u16> = crate::core::disk::volume_root(partition)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut sectors_per_cluster: u32 = 0;
+    let mut bytes_per_sector: u32 = 0;
+    unsafe {
+        GetDiskFreeSpaceW(
+            PCWSTR(root.as_ptr()),
+            Some(&mut sectors_per_cluster),
+            Some(&mut bytes_per_sector),
+            None,
+            None,
+        )
+        .context("获取簇大小失败")?;
+    }
+    let cluster_size = sectors_per_cluster as u64 * bytes_per_sector as u64;
+
+    let wide_path: Vec<u16> = device_path.encode_utf16().chain(std::iter::once(0)).collect();
+    unsafe {
+        let handle = CreateFileW(
+            PCWSTR(wide_path.as_ptr()),
+            GENERIC_READ.0 | GENERIC_WRITE.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            Default::default(),
+            None,
+        )
+        .context("打开卷失败")?;
+
+        let result = trim_locked_volume(handle, cluster_size);
+        // 关闭句柄同时解除卷锁定
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+#[cfg(not(windows))]
+pub fn trim_free_space(_partition: &str, _device_path: &str) -> Result<u64> {
+    Ok(0)
+}
+
+/// 锁定卷并对空闲簇执行 TRIM，锁定可防止 TRIM 期间有簇被重新分配
+#[cfg(windows)]
+unsafe fn trim_locked_volume(handle: windows::Win32::Foundation::HANDLE, cluster_size: u64) -> Result<u64> {
+    use anyhow::Context;
+    use windows::Win32::Foundation::ERROR_MORE_DATA;
+    use windows::Win32::System::Ioctl::{
+        FSCTL_GET_VOLUME_BITMAP, FSCTL_LOCK_VOLUME, IOCTL_STORAGE_MANAGE_DATA_SET_ATTRIBUTES,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    let mut bytes_returned: u32 = 0;
+    DeviceIoControl(handle, FSCTL_LOCK_VOLUME, None, 0, None, 0, Some(&mut bytes_returned), None)
+        .context("锁定卷失败（可能有程序正在访问该分区）")?;
+
+    // 分段读取簇位图：输出为 VOLUME_BITMAP_BUFFER { StartingLcn, BitmapSize, Buffer[] }
+    let mut runs = Vec::new();
+    let mut bitmap = vec![0u8; 16 + TRIM_BITMAP_CHUNK_BYTES];
+    let mut next_lcn: i64 = 0;
+    loop {
+        let result = DeviceIoControl(
+            handle,
+            FSCTL_GET_VOLUME_BITMAP,
+            Some(&next_lcn as *const _ as *const _),
+            std::mem::size_of::<i64>() as u32,
+            Some(bitmap.as_mut_ptr() as *mut _),
+            bitmap.len() as u32,
+            Some(&mut bytes_returned),
+            None,
+        );
+        let more_data = match result {
+            Ok(()) => false,
+            Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => true,
+            Err(e) => anyhow::bail!("读取卷位图失败: {}", e),
+        };
+
+        let returned = bytes_returned as usize;
+        if returned <= 16 {
+            break;
+        }
+        let starting_lcn = u64::from_le_bytes(bitmap[0..8].try_into()?);
+        let bitmap_size = u64::from_le_bytes(bitmap[8..16].try_into()?);
+        let bit_count = bitmap_size.min(((returned - 16) * 8) as u64);
+        runs.extend(free_cluster_runs(&bitmap[16..returned], starting_lcn, bit_count));
+
+        if !more_data {
+            break;
+        }
+        next_lcn = (starting_lcn + bit_count) as i64;
+    }
+
+    let mut trimmed = 0u64;
+    for batch in runs.chunks(TRIM_RANGES_PER_REQUEST) {
+        let input = dsm_trim_input(batch, cluster_size);
+        DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_MANAGE_DATA_SET_ATTRIBUTES,
+            Some(input.as_ptr() as *const _),
+            input.len() as u32,
+            None,
+            0,
+            Some(&mut bytes_returned),
+            None,
+        )
+        .context("下发 TRIM 失败")?;
+        trimmed += batch.iter().map(|(_, count)| count * cluster_size).sum::<u64>();
+    }
+
+    log::info!("[TRIM] 已 TRIM {} 个空闲范围，共 {} MB", runs.len(), trimmed / 1024 / 1024);
+    Ok(trimmed)
+}
+
+/// 从簇位图中提取空闲簇范围（起始 LCN，簇数），位为 0 表示空闲
+fn free_cluster_runs(bitmap: &[u8], starting_lcn: u64, bit_count: u64) -> Vec<(u64, u64)> {
+    let mut runs = Vec::new();
+    let mut run_start: Option<u64> = None;
+    for bit in 0..bit_count {
+        let used = bitmap[(bit / 8) as usize] & (1 << (bit % 8)) != 0;
+        match (used, run_start) {
+            (false, None) => run_start = Some(bit),
+            (true, Some(start)) => {
+                runs.push((starting_lcn + start, bit - start));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start {
+        runs.push((starting_lcn + start, bit_count - start));
+    }
+    runs
+}
+
+/// 构造 DSM Trim 请求：DEVICE_MANAGE_DATA_SET_ATTRIBUTES 头（28 字节，按 8 字节对齐）后接 DEVICE_DATA_SET_RANGE 数组
+fn dsm_trim_input(runs: &[(u64, u64)], cluster_size: u64) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 28;
+    const RANGES_OFFSET: u32 = 32;
+    let ranges_length = (runs.len() * 16) as u32;
+
+    let mut input = Vec::with_capacity((RANGES_OFFSET + ranges_length) as usize);
+    for value in [HEADER_SIZE, DEVICE_DSM_ACTION_TRIM, 0, 0, 0, RANGES_OFFSET, ranges_length] {
+        input.extend_from_slice(&value.to_le_bytes());
+    }
+    input.resize(RANGES_OFFSET as usize, 0);
+    for &(lcn, count) in runs {
+        input.extend_from_slice(&(lcn * cluster_size).to_le_bytes());
+        input.extend_from_slice(&(count * cluster_size).to_le_bytes());
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_cluster_runs() {
+        // 簇 0-1 已用，2-4 空闲，5 已用，6-9 空闲
+        let bitmap = [0b0010_0011, 0b0000_0000];
+        assert_eq!(free_cluster_runs(&bitmap, 100, 10), vec![(102, 3), (106, 4)]);
+        assert!(free_cluster_runs(&[0xFF], 0, 8).is_empty());
+    }
+
+    #[test]
+    fn test_supports_native_trim() {
+        assert!(supports_native_trim("NTFS"));
+        assert!(supports_native_trim("ntfs"));
+        assert!(!supports_native_trim("FAT32"));
+        assert!(!supports_native_trim("exFAT"));
+        assert!(!supports_native_trim(""));
+    }
+
+    #[test]
+    fn test_dsm_trim_input() {
+        let input = dsm_trim_input(&[(2, 3)], 4096);
+        assert_eq!(input.len(), 48);
+        assert_eq!(u32::from_le_bytes(input[4..8].try_into().unwrap()), DEVICE_DSM_ACTION_TRIM);
+        assert_eq!(u32::from_le_bytes(input[20..24].try_into().unwrap()), 32);
+        assert_eq!(u64::from_le_bytes(input[32..40].try_into().unwrap()), 8192);
+        assert_eq!(u64::from_le_bytes(input[40..48].try_into().unwrap()), 12288);
+    }
+}
@@ -0,0 +1,2438 @@
+//! wimlib.dll 动态库封装
+//!
+//! 该模块封装了 wimlib.dll 的主要功能，用于 WIM/ESD 镜像的完整性校验、释放、捕获与就地更新。
+//! wimlib 是一个开源的 WIM 处理库，提供了比微软官方 API 更快、更可靠的校验功能，
+//! 在精简 PE 中也可替代 wimgapi 完成镜像释放和备份。
+//!
+//! # 特性
+//! - 自动检测并加载 DLL（支持多种命名约定）
+//! - 跨平台符号解析（标准/stdcall/下划线前缀）
+//! - 线程安全的进度回调
+//! - RAII 风格的资源管理
+//!
+//! # 参考
+//! - https://wimlib.net/
+//! - https://wimlib.net/apidoc/
+//!
+//! 桌面端和 PE 端共用此文件。
+
+#![allow(non_snake_case)]
+#![allow(non_camel_case_types)]
+
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::ptr::{null, null_mut};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::mpsc::Sender;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use libloading::{Library, Symbol};
+use serde::{Deserialize, Serialize};
+
+// ============================================================================
+// 日志宏定义
+// ============================================================================
+
+/// 写入统一日志（带 [WIMLIB] 前缀），debug 级别仅在 debug 构建中输出
+macro_rules! wimlib_log {
+    (debug, $($arg:tt)*) => {
+        #[cfg(debug_assertions)]
+        log::debug!("[WIMLIB] {}", format!($($arg)*));
+    };
+    (info, $($arg:tt)*) => {
+        log::info!("[WIMLIB] {}", format!($($arg)*));
+    };
+    (warn, $($arg:tt)*) => {
+        log::warn!("[WIMLIB] {}", format!($($arg)*));
+    };
+    (error, $($arg:tt)*) => {
+        log::error!("[WIMLIB] {}", format!($($arg)*));
+    };
+}
+
+// ============================================================================
+// 常量定义
+// ============================================================================
+
+/// wimlib 进度消息类型
+mod progress_msg {
+    pub const EXTRACT_STREAMS: i32 = 4;
+    pub const EXTRACT_METADATA: i32 = 6;
+    pub const VERIFY_INTEGRITY: i32 = 16;
+    pub const CALC_INTEGRITY: i32 = 17;
+    pub const SCAN_BEGIN: i32 = 9;
+    pub const WRITE_STREAMS: i32 = 12;
+    pub const WRITE_METADATA_BEGIN: i32 = 13;
+    pub const UPDATE_BEGIN_COMMAND: i32 = 21;
+    pub const UPDATE_END_COMMAND: i32 = 22;
+    pub const BEGIN_VERIFY_IMAGE: i32 = 27;
+    pub const VERIFY_STREAMS: i32 = 29;
+}
+
+/// 打开 WIM 时请求写权限（追加镜像）
+const OPEN_FLAG_WRITE_ACCESS: i32 = 0x0000_0004;
+/// 捕获时使用 Windows 默认排除列表（pagefile.sys、System Volume Information 等）
+const ADD_FLAG_WINCONFIG: i32 = 0x0000_0800;
+/// 添加文件时不捕获 ACL（释放后继承目标目录的权限，避免带入本机账户的权限）
+const ADD_FLAG_NO_ACLS: i32 = 0x0000_0020;
+/// 递归删除目录
+const DELETE_FLAG_RECURSIVE: i32 = 0x0000_0002;
+/// 更新镜像时发送每条命令的进度
+const UPDATE_FLAG_SEND_PROGRESS: i32 = 0x0000_0001;
+/// 重写整个 WIM（回收被删除或替换的数据占用的空间）
+const WRITE_FLAG_REBUILD: i32 = 0x0000_0040;
+/// wimlib_update_op
+const UPDATE_OP_ADD: i32 = 0;
+const UPDATE_OP_DELETE: i32 = 1;
+/// wimlib_compression_type
+const COMPRESSION_TYPE_NONE: i32 = 0;
+const COMPRESSION_TYPE_XPRESS: i32 = 1;
+const COMPRESSION_TYPE_LZX: i32 = 2;
+const COMPRESSION_TYPE_LZMS: i32 = 3;
+/// 写入固实资源（ESD 格式）
+const WRITE_FLAG_SOLID: i32 = 0x0000_1000;
+/// 写入全部镜像
+const ALL_IMAGES: i32 = -1;
+/// 递归遍历目录树
+const ITERATE_DIR_TREE_FLAG_RECURSIVE: i32 = 0x0000_0001;
+/// 只遍历目录的直接子项
+const ITERATE_DIR_TREE_FLAG_CHILDREN: i32 = 0x0000_0002;
+/// 提取时不还原 ACL（提取到其他系统时原 ACL 可能导致文件无法访问）
+const EXTRACT_FLAG_NO_ACLS: i32 = 0x0000_0040;
+/// 提取到目标目录下，不保留镜像内的上级目录
+const EXTRACT_FLAG_NO_PRESERVE_DIR_STRUCTURE: i32 = 0x0040_0000;
+/// 目录属性
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0000_0010;
+/// 重解析点（符号链接、目录联接等）
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x0000_0400;
+/// EFS 加密文件
+const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x0000_4000;
+/// wimlib_resource_entry 位域中的 is_missing（数据不在已打开的 WIM 中）
+const RESOURCE_FLAG_MISSING: u32 = 1 << 4;
+/// 遍历回调返回此值提前结束遍历
+const ITERATE_STOP: i32 = -1;
+
+/// wimlib 错误码（与 wimlib.h 中的 WIMLIB_ERR_* 一致，已废弃的编号不再使用）
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WimlibError {
+    Success = 0,
+    AlreadyLocked = 1,
+    Decompression = 2,
+    Fuse = 6,
+    GlobHadNoMatches = 8,
+    ImageCount = 10,
+    ImageNameCollision = 11,
+    InsufficientPrivileges = 12,
+    Integrity = 13,
+    InvalidCaptureConfig = 14,
+    InvalidChunkSize = 15,
+    InvalidCompressionType = 16,
+    InvalidHeader = 17,
+    InvalidImage = 18,
+    InvalidIntegrityTable = 19,
+    InvalidLookupTableEntry = 20,
+    InvalidMetadataResource = 21,
+    InvalidOverlay = 23,
+    InvalidParam = 24,
+    InvalidPartNumber = 25,
+    InvalidPipableWim = 26,
+    InvalidReparseData = 27,
+    InvalidResourceHash = 28,
+    InvalidUtf16String = 30,
+    InvalidUtf8String = 31,
+    IsDirectory = 32,
+    IsSplitWim = 33,
+    Link = 35,
+    MetadataNotFound = 36,
+    Mkdir = 37,
+    Mqueue = 38,
+    Nomem = 39,
+    Notdir = 40,
+    Notempty = 41,
+    NotARegularFile = 42,
+    NotAWimFile = 43,
+    NotPipable = 44,
+    NoFilename = 45,
+    Ntfs3g = 46,
+    Open = 47,
+    Opendir = 48,
+    PathDoesNotExist = 49,
+    Read = 50,
+    Readlink = 51,
+    Rename = 52,
+    ReparsePointFixupFailed = 54,
+    ResourceNotFound = 55,
+    ResourceOrder = 56,
+    SetAttributes = 57,
+    SetReparseData = 58,
+    SetSecurity = 59,
+    SetShortName = 60,
+    SetTimestamps = 61,
+    SplitInvalid = 62,
+    Stat = 63,
+    UnexpectedEndOfFile = 65,
+    UnicodeStringNotRepresentable = 66,
+    UnknownVersion = 67,
+    Unsupported = 68,
+    UnsupportedFile = 69,
+    WimIsReadonly = 71,
+    Write = 72,
+    Xml = 73,
+    WimIsEncrypted = 74,
+    Wimboot = 75,
+    AbortedByProgress = 76,
+    UnknownProgressStatus = 77,
+    Mknod = 78,
+    MountedImageIsBusy = 79,
+    NotAMountpoint = 80,
+    NotPermittedToUnmount = 81,
+    FveLockedVolume = 82,
+    UnableToReadCaptureConfig = 83,
+    WimIsIncomplete = 84,
+    CompactionNotPossible = 85,
+    ImageHasMultipleReferences = 86,
+    DuplicateExportedImage = 87,
+    ConcurrentModificationDetected = 88,
+    SnapshotFailure = 89,
+    InvalidXattr = 90,
+    SetXattr = 91,
+}
+
+impl WimlibError {
+    /// 从错误码创建枚举值（未知或已废弃的错误码返回 None）
+    pub fn from_code(code: i32) -> Option<Self> {
+        let error = match code {
+            0 => Self::Success,
+            1 => Self::AlreadyLocked,
+            2 => Self::Decompression,
+            6 => Self::Fuse,
+            8 => Self::GlobHadNoMatches,
+            10 => Self::ImageCount,
+            11 => Self::ImageNameCollision,
+            12 => Self::InsufficientPrivileges,
+            13 => Self::Integrity,
+            14 => Self::InvalidCaptureConfig,
+            15 => Self::InvalidChunkSize,
+            16 => Self::InvalidCompressionType,
+            17 => Self::InvalidHeader,
+            18 => Self::InvalidImage,
+            19 => Self::InvalidIntegrityTable,
+            20 => Self::InvalidLookupTableEntry,
+            21 => Self::InvalidMetadataResource,
+            23 => Self::InvalidOverlay,
+            24 => Self::InvalidParam,
+            25 => Self::InvalidPartNumber,
+            26 => Self::InvalidPipableWim,
+            27 => Self::InvalidReparseData,
+            28 => Self::InvalidResourceHash,
+            30 => Self::InvalidUtf16String,
+            31 => Self::InvalidUtf8String,
+            32 => Self::IsDirectory,
+            33 => Self::IsSplitWim,
+            35 => Self::Link,
+            36 => Self::MetadataNotFound,
+            37 => Self::Mkdir,
+            38 => Self::Mqueue,
+            39 => Self::Nomem,
+            40 => Self::Notdir,
+            41 => Self::Notempty,
+            42 => Self::NotARegularFile,
+            43 => Self::NotAWimFile,
+            44 => Self::NotPipable,
+            45 => Self::NoFilename,
+            46 => Self::Ntfs3g,
+            47 => Self::Open,
+            48 => Self::Opendir,
+            49 => Self::PathDoesNotExist,
+            50 => Self::Read,
+            51 => Self::Readlink,
+            52 => Self::Rename,
+            54 => Self::ReparsePointFixupFailed,
+            55 => Self::ResourceNotFound,
+            56 => Self::ResourceOrder,
+            57 => Self::SetAttributes,
+            58 => Self::SetReparseData,
+            59 => Self::SetSecurity,
+            60 => Self::SetShortName,
+            61 => Self::SetTimestamps,
+            62 => Self::SplitInvalid,
+            63 => Self::Stat,
+            65 => Self::UnexpectedEndOfFile,
+            66 => Self::UnicodeStringNotRepresentable,
+            67 => Self::UnknownVersion,
+            68 => Self::Unsupported,
+            69 => Self::UnsupportedFile,
+            71 => Self::WimIsReadonly,
+            72 => Self::Write,
+            73 => Self::Xml,
+            74 => Self::WimIsEncrypted,
+            75 => Self::Wimboot,
+            76 => Self::AbortedByProgress,
+            77 => Self::UnknownProgressStatus,
+            78 => Self::Mknod,
+            79 => Self::MountedImageIsBusy,
+            80 => Self::NotAMountpoint,
+            81 => Self::NotPermittedToUnmount,
+            82 => Self::FveLockedVolume,
+            83 => Self::UnableToReadCaptureConfig,
+            84 => Self::WimIsIncomplete,
+            85 => Self::CompactionNotPossible,
+            86 => Self::ImageHasMultipleReferences,
+            87 => Self::DuplicateExportedImage,
+            88 => Self::ConcurrentModificationDetected,
+            89 => Self::SnapshotFailure,
+            90 => Self::InvalidXattr,
+            91 => Self::SetXattr,
+            _ => return None,
+        };
+        Some(error)
+    }
+
+    /// 错误码
+    pub fn code(&self) -> i32 {
+        *self as i32
+    }
+
+    /// 获取错误描述（中文）
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Success => "操作成功",
+            Self::AlreadyLocked => "WIM 文件已被其他程序锁定",
+            Self::Decompression => "解压缩失败",
+            Self::Fuse => "挂载文件系统失败",
+            Self::GlobHadNoMatches => "通配符没有匹配的路径",
+            Self::ImageCount => "镜像数量与文件头记录不一致",
+            Self::ImageNameCollision => "镜像名称已存在",
+            Self::InsufficientPrivileges => "权限不足",
+            Self::Integrity => "完整性校验失败",
+            Self::InvalidCaptureConfig => "无效的捕获配置",
+            Self::InvalidChunkSize => "无效的压缩块大小",
+            Self::InvalidCompressionType => "无效的压缩类型",
+            Self::InvalidHeader => "无效的文件头",
+            Self::InvalidImage => "无效的镜像",
+            Self::InvalidIntegrityTable => "无效的完整性表",
+            Self::InvalidLookupTableEntry => "无效的资源表项",
+            Self::InvalidMetadataResource => "无效的元数据资源",
+            Self::InvalidOverlay => "无效的 WIMBoot 覆盖信息",
+            Self::InvalidParam => "无效的参数",
+            Self::InvalidPartNumber => "无效的分卷编号",
+            Self::InvalidPipableWim => "无效的可管道传输 WIM",
+            Self::InvalidReparseData => "无效的重解析点数据",
+            Self::InvalidResourceHash => "资源哈希校验失败",
+            Self::InvalidUtf16String => "无效的 UTF-16 字符串",
+            Self::InvalidUtf8String => "无效的 UTF-8 字符串",
+            Self::IsDirectory => "目标是目录",
+            Self::IsSplitWim => "这是分卷 WIM 文件",
+            Self::Link => "创建链接失败",
+            Self::MetadataNotFound => "找不到镜像元数据",
+            Self::Mkdir => "创建目录失败",
+            Self::Mqueue => "消息队列操作失败",
+            Self::Nomem => "内存不足",
+            Self::Notdir => "路径不是目录",
+            Self::Notempty => "目录不为空",
+            Self::NotARegularFile => "不是普通文件",
+            Self::NotAWimFile => "不是有效的 WIM 文件",
+            Self::NotPipable => "WIM 文件不支持管道传输",
+            Self::NoFilename => "缺少文件名",
+            Self::Ntfs3g => "NTFS-3G 操作失败",
+            Self::Open => "无法打开文件",
+            Self::Opendir => "无法打开目录",
+            Self::PathDoesNotExist => "路径不存在",
+            Self::Read => "读取文件失败",
+            Self::Readlink => "读取链接失败",
+            Self::Rename => "重命名文件失败",
+            Self::ReparsePointFixupFailed => "修正重解析点失败",
+            Self::ResourceNotFound => "找不到镜像数据资源",
+            Self::ResourceOrder => "资源顺序异常",
+            Self::SetAttributes => "设置文件属性失败",
+            Self::SetReparseData => "设置重解析点失败",
+            Self::SetSecurity => "设置安全描述符失败",
+            Self::SetShortName => "设置短文件名失败",
+            Self::SetTimestamps => "设置文件时间失败",
+            Self::SplitInvalid => "分卷 WIM 不完整或不匹配",
+            Self::Stat => "获取文件信息失败",
+            Self::UnexpectedEndOfFile => "文件意外结束（可能被截断）",
+            Self::UnicodeStringNotRepresentable => "文件名无法转换为目标编码",
+            Self::UnknownVersion => "不支持的 WIM 版本",
+            Self::Unsupported => "不支持的操作",
+            Self::UnsupportedFile => "不支持的文件类型",
+            Self::WimIsReadonly => "WIM 文件为只读",
+            Self::Write => "写入文件失败",
+            Self::Xml => "XML 数据无效",
+            Self::WimIsEncrypted => "WIM 文件已加密",
+            Self::Wimboot => "WIMBoot 操作失败",
+            Self::AbortedByProgress => "操作已被取消",
+            Self::UnknownProgressStatus => "进度回调返回了未知状态",
+            Self::Mknod => "创建特殊文件失败",
+            Self::MountedImageIsBusy => "挂载的镜像正在使用",
+            Self::NotAMountpoint => "不是挂载点",
+            Self::NotPermittedToUnmount => "没有权限卸载镜像",
+            Self::FveLockedVolume => "卷已被 BitLocker 锁定",
+            Self::UnableToReadCaptureConfig => "无法读取捕获配置",
+            Self::WimIsIncomplete => "WIM 文件不完整",
+            Self::CompactionNotPossible => "无法原地压缩 WIM 文件",
+            Self::ImageHasMultipleReferences => "镜像被多次引用",
+            Self::DuplicateExportedImage => "导出的镜像已存在",
+            Self::ConcurrentModificationDetected => "WIM 文件在操作期间被修改",
+            Self::SnapshotFailure => "创建卷快照失败",
+            Self::InvalidXattr => "无效的扩展属性",
+            Self::SetXattr => "设置扩展属性失败",
+        }
+    }
+
+    /// 修复建议（没有通用修复方法的错误返回 None）
+    pub fn hint(&self) -> Option<&'static str> {
+        let hint = match self {
+            Self::AlreadyLocked => "关闭正在使用该镜像的程序（如 DISM、其他 LetRecovery 实例）后重试",
+            Self::Decompression
+            | Self::Integrity
+            | Self::InvalidResourceHash
+            | Self::InvalidLookupTableEntry
+            | Self::InvalidMetadataResource
+            | Self::ResourceNotFound
+            | Self::UnexpectedEndOfFile => "镜像文件已损坏或下载不完整，请重新下载并校验哈希值",
+            Self::InvalidHeader | Self::NotAWimFile | Self::UnknownVersion => {
+                "文件不是受支持的 WIM/ESD 镜像，请确认选择了正确的文件"
+            }
+            Self::InvalidImage | Self::MetadataNotFound => "镜像索引不存在，请重新选择要安装的版本",
+            Self::IsSplitWim | Self::SplitInvalid | Self::InvalidPartNumber => {
+                "请将全部 .swm 分卷放在同一目录，并选择第一个分卷"
+            }
+            Self::Nomem => "关闭其他程序释放内存后重试",
+            Self::Open | Self::Opendir | Self::PathDoesNotExist => "确认文件存在且路径中没有无法访问的驱动器",
+            Self::Read => "检查源磁盘或 U 盘是否连接稳定，必要时将镜像复制到本地硬盘",
+            Self::InsufficientPrivileges => "以管理员身份运行，或在 PE 中执行此操作",
+            Self::FveLockedVolume => "先解锁 BitLocker 分区后重试",
+            Self::Write | Self::Mkdir => "检查目标分区是否有足够空间、是否为只读或被 BitLocker 锁定",
+            Self::SetSecurity | Self::SetAttributes | Self::SetReparseData => "以管理员身份运行，或在 PE 中执行此操作",
+            Self::WimIsReadonly => "去掉镜像文件的只读属性，或将其复制到可写位置",
+            Self::WimIsEncrypted => "该镜像已加密，请使用未加密的镜像",
+            Self::Unsupported | Self::UnsupportedFile => "请更新 wimlib.dll 到最新版本",
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
+/// wimlib 调用失败信息
+///
+/// 保留错误码和 wimlib 的原始错误信息，由调用方决定如何展示（描述、修复建议）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimlibFailure {
+    /// wimlib 返回的错误码（非 wimlib 返回的错误为 None，如功能不支持）
+    pub code: Option<i32>,
+    /// wimlib 的原始错误信息，或非 wimlib 错误的描述
+    pub message: String,
+}
+
+impl WimlibFailure {
+    /// 对应的错误码枚举
+    pub fn kind(&self) -> Option<WimlibError> {
+        self.code.and_then(WimlibError::from_code)
+    }
+
+    /// 修复建议
+    pub fn hint(&self) -> Option<&'static str> {
+        self.kind().and_then(|kind| kind.hint())
+    }
+
+    /// 完整错误信息，附带修复建议（供界面直接显示）
+    pub fn detailed(&self) -> String {
+        match self.hint() {
+            Some(hint) => format!("{}。修复建议: {}", self, hint),
+            None => self.to_string(),
+        }
+    }
+
+    /// 在原始错误信息前加上出错时的操作
+    fn context(mut self, context: &str) -> Self {
+        self.message = if self.message.is_empty() {
+            context.to_string()
+        } else {
+            format!("{}: {}", context, self.message)
+        };
+        self
+    }
+}
+
+impl std::fmt::Display for WimlibFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(code) = self.code else {
+            return f.write_str(&self.message);
+        };
+        let description = self.kind().map(|kind| kind.description()).unwrap_or("未知错误");
+        if self.message.is_empty() {
+            write!(f, "{} (错误码: {})", description, code)
+        } else {
+            write!(f, "{} (错误码 {}: {})", description, code, self.message)
+        }
+    }
+}
+
+impl std::error::Error for WimlibFailure {}
+
+impl From<String> for WimlibFailure {
+    fn from(message: String) -> Self {
+        Self { code: None, message }
+    }
+}
+
+impl From<&str> for WimlibFailure {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+/// 镜像释放/捕获后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ImageBackend {
+    /// 存在 wimlib.dll 时使用 wimlib，否则使用 wimgapi
+    #[default]
+    Auto,
+    /// 始终使用 wimgapi
+    Wimgapi,
+    /// 优先使用 wimlib（不可用时回退到 wimgapi）
+    Wimlib,
+}
+
+impl ImageBackend {
+    /// 全部后端（按显示顺序）
+    pub const ALL: [ImageBackend; 3] = [ImageBackend::Auto, ImageBackend::Wimgapi, ImageBackend::Wimlib];
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageBackend::Auto => "自动（优先 wimlib）",
+            ImageBackend::Wimgapi => "wimgapi",
+            ImageBackend::Wimlib => "wimlib",
+        }
+    }
+
+    /// 按后端设置加载 wimlib（使用 wimgapi 或 wimlib 不可用时返回 None）
+    pub fn load_wimlib(&self) -> Option<Wimlib> {
+        if *self == ImageBackend::Wimgapi {
+            return None;
+        }
+        match Wimlib::new() {
+            Ok(wimlib) => Some(wimlib),
+            Err(e) => {
+                if *self == ImageBackend::Wimlib {
+                    log::warn!("[WIMLIB] wimlib 不可用，回退到 wimgapi: {}", e);
+                }
+                None
+            }
+        }
+    }
+}
+
+// ============================================================================
+// 性能调优
+// ============================================================================
+
+/// 捕获镜像时的压缩参数
+///
+/// wimlib 只在写入（捕获、追加）时支持多线程压缩，释放镜像没有线程数参数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WimlibTuning {
+    /// 压缩线程数（0 表示由 wimlib 自行决定）
+    pub threads: u32,
+    /// LZX 压缩级别（None 表示 wimlib 默认的 50，越高越慢、压缩率越高）
+    pub compression_level: Option<u32>,
+    /// 输出块大小（字节，须为 2 的幂；None 表示默认的 32 KiB，
+    /// 其他大小的 LZX 镜像在 Windows 7 及更早的 DISM 中无法释放）
+    pub chunk_size: Option<u32>,
+}
+
+impl WimlibTuning {
+    /// wimlib 默认参数
+    pub const DEFAULT: Self = Self {
+        threads: 0,
+        compression_level: None,
+        chunk_size: None,
+    };
+
+    /// 最多使用的压缩线程数（单个处理器组的上限）
+    const MAX_THREADS: u32 = 64;
+
+    /// 按逻辑处理器数自动调优
+    ///
+    /// 线程数跑满全部逻辑处理器；双核及以下的机器改用较低的压缩级别，
+    /// 避免捕获被压缩拖慢。块大小保持默认以兼容所有版本的 DISM。
+    pub fn for_cpu(logical_processors: u32) -> Self {
+        if logical_processors == 0 {
+            return Self::DEFAULT;
+        }
+        Self {
+            threads: logical_processors.min(Self::MAX_THREADS),
+            compression_level: (logical_processors <= 2).then_some(20),
+            chunk_size: None,
+        }
+    }
+}
+
+impl Default for WimlibTuning {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// 导出/捕获镜像时的压缩方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportCompression {
+    /// 不压缩
+    None,
+    /// XPRESS（压缩快，文件较大）
+    Xpress,
+    /// LZX（标准 WIM）
+    Lzx,
+    /// LZMS 固实压缩（ESD）
+    LzmsSolid,
+}
+
+impl ExportCompression {
+    fn compression_type(&self) -> i32 {
+        match self {
+            ExportCompression::None => COMPRESSION_TYPE_NONE,
+            ExportCompression::Xpress => COMPRESSION_TYPE_XPRESS,
+            ExportCompression::Lzx => COMPRESSION_TYPE_LZX,
+            ExportCompression::LzmsSolid => COMPRESSION_TYPE_LZMS,
+        }
+    }
+
+    fn write_flags(&self) -> i32 {
+        match self {
+            ExportCompression::LzmsSolid => WRITE_FLAG_SOLID,
+            _ => 0,
+        }
+    }
+}
+
+// ============================================================================
+// FFI 类型定义
+// ============================================================================
+
+type WIMStruct = *mut c_void;
+type ProgressFunc = unsafe extern "C" fn(msg: i32, info: *const c_void, ctx: *mut c_void) -> i32;
+
+/// 完整性校验进度信息
+#[repr(C)]
+struct ProgressInfoVerifyIntegrity {
+    total_bytes: u64,
+    completed_bytes: u64,
+    total_chunks: u32,
+    completed_chunks: u32,
+    chunk_size: u32,
+    filename: *const u16,
+}
+
+/// 数据校验进度信息（wimlib_progress_info.verify_streams）
+#[repr(C)]
+struct ProgressInfoVerifyStreams {
+    wimfile: *const u16,
+    total_streams: u64,
+    total_bytes: u64,
+    completed_streams: u64,
+    completed_bytes: u64,
+}
+
+/// 镜像元数据校验进度信息（wimlib_progress_info.verify_image）
+#[repr(C)]
+struct ProgressInfoVerifyImage {
+    wimfile: *const u16,
+    total_images: u32,
+    current_image: u32,
+}
+
+/// WIM 文件信息结构体
+/// 
+/// 该结构体严格按照 wimlib 的 C 头文件定义布局
+/// 参考: https://wimlib.net/apidoc/structwimlib__wim__info.html
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WimInfo {
+    /// WIM 文件的 GUID
+    pub guid: [u8; 16],
+    /// 镜像数量
+    pub image_count: u32,
+    /// 启动镜像索引
+    pub boot_index: u32,
+    /// WIM 版本
+    pub wim_version: u32,
+    /// 块大小
+    pub chunk_size: u32,
+    /// 分卷编号
+    pub part_number: u16,
+    /// 总分卷数
+    pub total_parts: u16,
+    /// 压缩类型
+    pub compression_type: i32,
+    /// 总大小（字节）
+    pub total_bytes: u64,
+    /// 是否有完整性表
+    pub has_integrity_table: u32,
+    /// 是否已打开为可写
+    pub opened_for_write: u32,
+    /// 是否只读
+    pub is_readonly: u32,
+    /// 是否有引用的资源
+    pub has_rpfix: u32,
+    /// 是否为管道格式
+    pub is_pipable: u32,
+    /// 是否为固实 WIM
+    pub is_solid: u32,
+    /// 保留字段，确保结构体大小正确
+    _reserved: [u8; 48],
+}
+
+impl Default for WimInfo {
+    fn default() -> Self {
+        unsafe { std::mem::zeroed() }
+    }
+}
+
+/// 释放进度信息（wimlib_progress_info.extract）
+#[repr(C)]
+struct ProgressInfoExtract {
+    image: u32,
+    extract_flags: u32,
+    wimfile_name: *const u16,
+    image_name: *const u16,
+    target: *const u16,
+    reserved: *const u16,
+    total_bytes: u64,
+    completed_bytes: u64,
+    total_streams: u64,
+    completed_streams: u64,
+    part_number: u32,
+    total_parts: u32,
+    guid: [u8; 16],
+    current_file_count: u64,
+    end_file_count: u64,
+}
+
+/// 写入进度信息（wimlib_progress_info.write_streams）
+#[repr(C)]
+struct ProgressInfoWriteStreams {
+    total_bytes: u64,
+    total_streams: u64,
+    completed_bytes: u64,
+    completed_streams: u64,
+    num_threads: u32,
+    compression_type: i32,
+    total_parts: u32,
+    completed_parts: u32,
+    completed_compressed_bytes: u64,
+}
+
+/// 添加命令（wimlib_add_command）
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct AddCommand {
+    fs_source_path: *const u16,
+    wim_target_path: *const u16,
+    config_file: *const u16,
+    add_flags: i32,
+}
+
+/// 删除命令（wimlib_delete_command）
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DeleteCommand {
+    wim_path: *const u16,
+    delete_flags: i32,
+}
+
+/// 命令参数（重命名命令不使用，其大小不超过添加命令，不影响布局）
+#[repr(C)]
+union UpdateCommandParams {
+    add: AddCommand,
+    delete: DeleteCommand,
+}
+
+/// 更新命令（wimlib_update_command）
+#[repr(C)]
+struct UpdateCommand {
+    op: i32,
+    params: UpdateCommandParams,
+}
+
+/// 更新进度信息（wimlib_progress_info.update）
+#[repr(C)]
+struct ProgressInfoUpdate {
+    command: *const UpdateCommand,
+    completed_commands: usize,
+    total_commands: usize,
+}
+
+/// wimlib_dir_entry 的前部字段（之后的字段不使用，也不依赖其布局）
+#[repr(C)]
+struct DirEntryHeader {
+    filename: *const u16,
+    dos_name: *const u16,
+    full_path: *const u16,
+    depth: usize,
+    security_descriptor: *const c_void,
+    security_descriptor_size: usize,
+    attributes: u32,
+}
+
+/// wimlib_resource_entry
+#[repr(C)]
+struct ResourceEntry {
+    uncompressed_size: u64,
+    compressed_size: u64,
+    offset: u64,
+    sha1_hash: [u8; 20],
+    part_number: u32,
+    reference_count: u32,
+    /// 位域：is_compressed、is_metadata、is_free、is_spanned、is_missing、packed
+    flags: u32,
+    raw_resource_offset_in_wim: u64,
+    raw_resource_compressed_size: u64,
+    raw_resource_uncompressed_size: u64,
+    reserved: [u64; 1],
+}
+
+/// wimlib_stream_entry
+#[repr(C)]
+struct StreamEntry {
+    stream_name: *const u16,
+    resource: ResourceEntry,
+    reserved: [u64; 4],
+}
+
+/// wimlib_timespec（32 位 Windows 上 tv_sec 为 32 位）
+#[repr(C)]
+struct Timespec {
+    tv_sec: isize,
+    tv_nsec: i32,
+}
+
+/// wimlib_dir_entry 完整布局（1.13 起），streams 为紧随其后的柔性数组
+///
+/// streams[0] 为未命名数据流，之后是 num_named_streams 个命名流。
+#[repr(C)]
+struct DirEntry {
+    filename: *const u16,
+    dos_name: *const u16,
+    full_path: *const u16,
+    depth: usize,
+    security_descriptor: *const c_void,
+    security_descriptor_size: usize,
+    attributes: u32,
+    reparse_tag: u32,
+    num_links: u32,
+    num_named_streams: u32,
+    hard_link_group_id: u64,
+    creation_time: Timespec,
+    last_write_time: Timespec,
+    last_access_time: Timespec,
+    unix_uid: u32,
+    unix_gid: u32,
+    unix_mode: u32,
+    unix_rdev: u32,
+    object_id: [u8; 64],
+    creation_time_high: i32,
+    last_write_time_high: i32,
+    last_access_time_high: i32,
+    reserved2: i32,
+    reserved: [u64; 4],
+    streams: [StreamEntry; 0],
+}
+
+/// 镜像内普通文件的数据哈希（WIM 按 SHA-1 存储每个数据流）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimFileHash {
+    /// 镜像内完整路径（如 \Windows\System32\ntdll.dll）
+    pub path: String,
+    /// 文件大小
+    pub size: u64,
+    /// 未命名数据流的 SHA-1（空文件为全 0）
+    pub sha1: [u8; 20],
+}
+
+/// 镜像内的文件或目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WimDirEntry {
+    /// 文件名
+    pub name: String,
+    /// 镜像内完整路径（如 \Windows\System32）
+    pub full_path: String,
+    pub is_dir: bool,
+}
+
+/// 镜像就地更新命令（镜像内路径如 \Windows\Setup\Scripts）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WimUpdateCommand {
+    /// 把本地文件或目录添加到镜像内的路径（同名文件被替换，目录合并）
+    Add { source: PathBuf, target: String },
+    /// 删除镜像内的文件或目录（目录递归删除）
+    Delete { path: String },
+}
+
+/// 释放/捕获进度
+#[derive(Debug, Clone)]
+pub struct WimlibProgress {
+    /// 进度百分比 (0-100)
+    pub percentage: u8,
+    /// 状态描述
+    pub status: String,
+}
+
+// ============================================================================
+// 函数指针类型
+// ============================================================================
+
+type FnGlobalInit = unsafe extern "C" fn(flags: i32) -> i32;
+type FnGlobalCleanup = unsafe extern "C" fn();
+type FnOpenWim = unsafe extern "C" fn(path: *const u16, flags: i32, wim: *mut WIMStruct, progress: Option<ProgressFunc>) -> i32;
+type FnFree = unsafe extern "C" fn(wim: WIMStruct);
+type FnVerifyWim = unsafe extern "C" fn(wim: WIMStruct, flags: i32) -> i32;
+type FnRegisterProgressFunction = unsafe extern "C" fn(wim: WIMStruct, func: ProgressFunc, ctx: *mut c_void);
+type FnGetErrorString = unsafe extern "C" fn(code: i32) -> *const u16;
+type FnGetWimInfo = unsafe extern "C" fn(wim: WIMStruct, info: *mut WimInfo) -> i32;
+type FnGetImageName = unsafe extern "C" fn(wim: WIMStruct, index: i32) -> *const u16;
+type FnGetImageDescription = unsafe extern "C" fn(wim: WIMStruct, index: i32) -> *const u16;
+type FnExtractImage = unsafe extern "C" fn(wim: WIMStruct, image: i32, target: *const u16, flags: i32) -> i32;
+type FnCreateNewWim = unsafe extern "C" fn(compression_type: i32, wim: *mut WIMStruct) -> i32;
+type FnAddImage = unsafe extern "C" fn(wim: WIMStruct, source: *const u16, name: *const u16, config_file: *const u16, flags: i32) -> i32;
+type FnWrite = unsafe extern "C" fn(wim: WIMStruct, path: *const u16, image: i32, flags: i32, num_threads: u32) -> i32;
+type FnOverwrite = unsafe extern "C" fn(wim: WIMStruct, flags: i32, num_threads: u32) -> i32;
+type FnSetImageProperty = unsafe extern "C" fn(wim: WIMStruct, image: i32, name: *const u16, value: *const u16) -> i32;
+type FnReferenceResourceFiles = unsafe extern "C" fn(wim: WIMStruct, paths: *const *const u16, count: u32, ref_flags: i32, open_flags: i32) -> i32;
+type FnIterateDirTreeCallback = unsafe extern "C" fn(dentry: *const DirEntryHeader, ctx: *mut c_void) -> i32;
+type FnIterateDirTree = unsafe extern "C" fn(wim: WIMStruct, image: i32, path: *const u16, flags: i32, cb: FnIterateDirTreeCallback, ctx: *mut c_void) -> i32;
+type FnExtractPaths = unsafe extern "C" fn(wim: WIMStruct, image: i32, target: *const u16, paths: *const *const u16, num_paths: usize, flags: i32) -> i32;
+type FnExportImage = unsafe extern "C" fn(src_wim: WIMStruct, src_image: i32, dest_wim: WIMStruct, dest_name: *const u16, dest_description: *const u16, flags: i32) -> i32;
+type FnSetDefaultCompressionLevel = unsafe extern "C" fn(ctype: i32, level: u32) -> i32;
+type FnSetOutputChunkSize = unsafe extern "C" fn(wim: WIMStruct, chunk_size: u32) -> i32;
+type FnUpdateImage = unsafe extern "C" fn(wim: WIMStruct, image: i32, cmds: *const UpdateCommand, num_cmds: usize, flags: i32) -> i32;
+type FnDeleteImage = unsafe extern "C" fn(wim: WIMStruct, image: i32) -> i32;
+type FnGetImageProperty = unsafe extern "C" fn(wim: WIMStruct, image: i32, name: *const u16) -> *const u16;
+
+// ============================================================================
+// 全局状态
+// ============================================================================
+
+/// wimlib_get_error_string 结果缓存
+static ERROR_STRINGS: OnceLock<Mutex<HashMap<i32, String>>> = OnceLock::new();
+
+/// 捕获镜像时使用的压缩参数
+static TUNING: Mutex<WimlibTuning> = Mutex::new(WimlibTuning::DEFAULT);
+
+/// 设置捕获镜像时使用的压缩参数（对之后的捕获生效）
+pub fn set_tuning(tuning: WimlibTuning) {
+    *TUNING.lock().unwrap_or_else(|e| e.into_inner()) = tuning;
+    wimlib_log!(info, "压缩参数: {:?}", tuning);
+}
+
+/// 当前的压缩参数
+pub fn tuning() -> WimlibTuning {
+    *TUNING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// ============================================================================
+// 操作状态
+// ============================================================================
+
+/// WIM 句柄上的操作进度与取消标志
+///
+/// 每个句柄各自持有，经回调的 ctx 指针传入，多个句柄并发校验时互不干扰。
+/// 可通过 [`WimHandle::state`] 在其他线程读取进度或请求取消。
+#[derive(Debug, Default)]
+pub struct WimOperationState {
+    /// 当前进度（0-100）
+    progress: AtomicU8,
+    /// 取消标志（对句柄后续的所有操作生效）
+    cancelled: AtomicBool,
+}
+
+impl WimOperationState {
+    /// 当前进度（0-100）
+    pub fn progress(&self) -> u8 {
+        self.progress.load(Ordering::SeqCst)
+    }
+
+    /// 请求取消，正在进行的操作在下一次回调时中止
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// 是否已请求取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 新操作开始时清零进度
+    fn reset_progress(&self) {
+        self.progress.store(0, Ordering::SeqCst);
+    }
+
+    fn set_progress(&self, percentage: u8) {
+        self.progress.store(percentage, Ordering::SeqCst);
+    }
+
+    /// 只更新更大的进度值（避免回退）
+    fn advance_progress(&self, percentage: u8) {
+        self.progress.fetch_max(percentage, Ordering::SeqCst);
+    }
+}
+
+/// 校验进度回调，ctx 指向句柄的 WimOperationState
+extern "C" fn progress_callback(msg: i32, info: *const c_void, ctx: *mut c_void) -> i32 {
+    if ctx.is_null() {
+        return 0;
+    }
+    let state = unsafe { &*(ctx as *const WimOperationState) };
+    if state.is_cancelled() {
+        return 1; // WIMLIB_PROGRESS_STATUS_ABORT
+    }
+
+    let bytes = match msg {
+        progress_msg::VERIFY_INTEGRITY if !info.is_null() => {
+            let verify_info = unsafe { &*(info as *const ProgressInfoVerifyIntegrity) };
+            Some((verify_info.completed_bytes, verify_info.total_bytes))
+        }
+        progress_msg::VERIFY_STREAMS if !info.is_null() => {
+            let verify_info = unsafe { &*(info as *const ProgressInfoVerifyStreams) };
+            Some((verify_info.completed_bytes, verify_info.total_bytes))
+        }
+        _ => None,
+    };
+
+    if let Some((completed_bytes, total_bytes)) = bytes {
+        if total_bytes > 0 {
+            state.advance_progress(percent_of(completed_bytes, total_bytes));
+        }
+    }
+
+    0 // WIMLIB_PROGRESS_STATUS_CONTINUE
+}
+
+/// 释放/捕获操作的进度上下文
+struct OperationContext {
+    state: Arc<WimOperationState>,
+    progress_tx: Option<Sender<WimlibProgress>>,
+    last_percentage: u8,
+    last_status: &'static str,
+}
+
+impl OperationContext {
+    fn new(state: Arc<WimOperationState>, progress_tx: Option<Sender<WimlibProgress>>) -> Self {
+        state.reset_progress();
+        Self {
+            state,
+            progress_tx,
+            last_percentage: 0,
+            last_status: "",
+        }
+    }
+
+    /// 进度或状态变化时才发送
+    fn report(&mut self, percentage: u8, status: &'static str) {
+        if percentage == self.last_percentage && status == self.last_status {
+            return;
+        }
+        self.last_percentage = percentage;
+        self.last_status = status;
+        self.state.set_progress(percentage);
+        if let Some(ref tx) = self.progress_tx {
+            let _ = tx.send(WimlibProgress {
+                percentage,
+                status: status.to_string(),
+            });
+        }
+    }
+}
+
+fn percent_of(completed: u64, total: u64) -> u8 {
+    if total == 0 {
+        0
+    } else {
+        (completed.min(total) * 100 / total) as u8
+    }
+}
+
+/// 释放/捕获进度回调，ctx 指向 OperationContext
+extern "C" fn operation_progress_callback(msg: i32, info: *const c_void, ctx: *mut c_void) -> i32 {
+    if ctx.is_null() {
+        return 0;
+    }
+
+    let context = unsafe { &mut *(ctx as *mut OperationContext) };
+    if context.state.is_cancelled() {
+        return 1; // WIMLIB_PROGRESS_STATUS_ABORT
+    }
+    match msg {
+        progress_msg::EXTRACT_STREAMS if !info.is_null() => {
+            let extract = unsafe { &*(info as *const ProgressInfoExtract) };
+            context.report(percent_of(extract.completed_bytes, extract.total_bytes), "正在释放文件");
+        }
+        progress_msg::EXTRACT_METADATA => {
+            context.report(context.last_percentage, "正在应用文件属性");
+        }
+        progress_msg::SCAN_BEGIN => {
+            context.report(0, "正在扫描文件");
+        }
+        progress_msg::WRITE_STREAMS if !info.is_null() => {
+            let write = unsafe { &*(info as *const ProgressInfoWriteStreams) };
+            context.report(percent_of(write.completed_bytes, write.total_bytes), "正在压缩写入");
+        }
+        progress_msg::WRITE_METADATA_BEGIN => {
+            context.report(context.last_percentage, "正在写入元数据");
+        }
+        progress_msg::UPDATE_BEGIN_COMMAND | progress_msg::UPDATE_END_COMMAND if !info.is_null() => {
+            let update = unsafe { &*(info as *const ProgressInfoUpdate) };
+            let percentage = percent_of(update.completed_commands as u64, update.total_commands as u64);
+            context.report(percentage, "正在更新镜像");
+        }
+        progress_msg::BEGIN_VERIFY_IMAGE if !info.is_null() => {
+            let image = unsafe { &*(info as *const ProgressInfoVerifyImage) };
+            let status = if image.total_images > 1 { "正在校验各镜像元数据" } else { "正在校验元数据" };
+            context.report(0, status);
+        }
+        progress_msg::VERIFY_STREAMS if !info.is_null() => {
+            let verify = unsafe { &*(info as *const ProgressInfoVerifyStreams) };
+            context.report(percent_of(verify.completed_bytes, verify.total_bytes), "正在校验数据");
+        }
+        _ => {}
+    }
+
+    0
+}
+
+/// 目录遍历上下文
+struct IterateContext {
+    state: Arc<WimOperationState>,
+    /// 文件名过滤（小写），None 表示收集全部
+    keyword: Option<String>,
+    limit: usize,
+    entries: Vec<WimDirEntry>,
+}
+
+impl IterateContext {
+    fn new(state: Arc<WimOperationState>, keyword: Option<String>, limit: usize) -> Self {
+        Self {
+            state,
+            keyword,
+            limit,
+            entries: Vec::new(),
+        }
+    }
+
+    /// 收集一项，达到上限时返回 false
+    fn push(&mut self, entry: WimDirEntry) -> bool {
+        if entry.name.is_empty() {
+            // 根目录本身
+            return true;
+        }
+        if let Some(ref keyword) = self.keyword {
+            if !entry.name.to_lowercase().contains(keyword.as_str()) {
+                return true;
+            }
+        }
+        self.entries.push(entry);
+        self.entries.len() < self.limit
+    }
+}
+
+/// 目录遍历回调，ctx 指向 IterateContext
+extern "C" fn iterate_dir_tree_callback(dentry: *const DirEntryHeader, ctx: *mut c_void) -> i32 {
+    if dentry.is_null() || ctx.is_null() {
+        return 0;
+    }
+
+    let context = unsafe { &mut *(ctx as *mut IterateContext) };
+    if context.state.is_cancelled() {
+        return ITERATE_STOP;
+    }
+    let dentry = unsafe { &*dentry };
+    let entry = unsafe {
+        WimDirEntry {
+            name: Wimlib::utf16_ptr_to_string(dentry.filename).unwrap_or_default(),
+            full_path: Wimlib::utf16_ptr_to_string(dentry.full_path).unwrap_or_default(),
+            is_dir: dentry.attributes & FILE_ATTRIBUTE_DIRECTORY != 0,
+        }
+    };
+    if context.push(entry) {
+        0
+    } else {
+        ITERATE_STOP
+    }
+}
+
+/// 文件哈希收集上下文
+struct HashContext {
+    state: Arc<WimOperationState>,
+    files: Vec<WimFileHash>,
+    /// 目录项布局与当前 wimlib 不符（未命名流出现了流名）
+    layout_mismatch: bool,
+}
+
+/// 文件哈希收集回调，ctx 指向 HashContext
+extern "C" fn collect_hashes_callback(dentry: *const DirEntryHeader, ctx: *mut c_void) -> i32 {
+    if dentry.is_null() || ctx.is_null() {
+        return 0;
+    }
+
+    let context = unsafe { &mut *(ctx as *mut HashContext) };
+    if context.state.is_cancelled() {
+        return ITERATE_STOP;
+    }
+    let dentry = unsafe { &*(dentry as *const DirEntry) };
+    if dentry.attributes & (FILE_ATTRIBUTE_DIRECTORY | FILE_ATTRIBUTE_REPARSE_POINT | FILE_ATTRIBUTE_ENCRYPTED) != 0 {
+        return 0;
+    }
+
+    let stream = unsafe { &*dentry.streams.as_ptr() };
+    if !stream.stream_name.is_null() {
+        context.layout_mismatch = true;
+        return ITERATE_STOP;
+    }
+    if stream.resource.flags & RESOURCE_FLAG_MISSING != 0 {
+        return 0;
+    }
+    if let Some(path) = unsafe { Wimlib::utf16_ptr_to_string(dentry.full_path) } {
+        context.files.push(WimFileHash {
+            path,
+            size: stream.resource.uncompressed_size,
+            sha1: stream.resource.sha1_hash,
+        });
+    }
+    0
+}
+
+/// 规范化镜像内路径：统一为反斜杠并以 \ 开头，空路径表示根目录
+fn normalize_wim_path(path: &str) -> String {
+    let trimmed = path.trim().replace('/', "\\");
+    let trimmed = trimmed.trim_matches('\\');
+    format!("\\{}", trimmed)
+}
+
+/// 转换为以 0 结尾的 UTF-16 字符串
+fn to_utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+// ============================================================================
+// 符号加载器
+// ============================================================================
+
+/// 符号变体类型
+#[derive(Debug, Clone, Copy)]
+enum SymbolVariant {
+    /// 标准名称: wimlib_xxx
+    Standard,
+    /// 下划线前缀: _wimlib_xxx
+    Underscore,
+    /// stdcall 格式: _wimlib_xxx@N
+    Stdcall(usize),
+    /// stdcall 无前缀: wimlib_xxx@N
+    StdcallNoPrefix(usize),
+}
+
+/// 符号加载器
+struct SymbolLoader<'a> {
+    lib: &'a Library,
+}
+
+impl<'a> SymbolLoader<'a> {
+    fn new(lib: &'a Library) -> Self {
+        Self { lib }
+    }
+
+    /// 尝试加载符号，支持多种变体
+    unsafe fn load<T>(&self, name: &str, stdcall_size: usize) -> Result<Symbol<'a, T>, String> {
+        let variants = [
+            SymbolVariant::Standard,
+            SymbolVariant::Underscore,
+            SymbolVariant::Stdcall(stdcall_size),
+            SymbolVariant::StdcallNoPrefix(stdcall_size),
+        ];
+
+        for variant in &variants {
+            let symbol_name = self.format_symbol_name(name, *variant);
+            if let Ok(symbol) = self.lib.get::<T>(symbol_name.as_bytes()) {
+                wimlib_log!(debug, "符号 '{}' -> '{}'", name, symbol_name);
+                return Ok(symbol);
+            }
+        }
+
+        Err(format!("无法找到符号 '{}'", name))
+    }
+
+    /// 尝试加载可选符号
+    unsafe fn load_optional<T>(&self, name: &str, stdcall_size: usize) -> Option<Symbol<'a, T>> {
+        self.load(name, stdcall_size).ok()
+    }
+
+    fn format_symbol_name(&self, name: &str, variant: SymbolVariant) -> String {
+        match variant {
+            SymbolVariant::Standard => name.to_string(),
+            SymbolVariant::Underscore => format!("_{}", name),
+            SymbolVariant::Stdcall(size) => format!("_{}@{}", name, size),
+            SymbolVariant::StdcallNoPrefix(size) => format!("{}@{}", name, size),
+        }
+    }
+}
+
+// ============================================================================
+// Wimlib 主结构体
+// ============================================================================
+
+/// Wimlib DLL 封装
+pub struct Wimlib {
+    _lib: Arc<Library>,
+    global_init: FnGlobalInit,
+    global_cleanup: FnGlobalCleanup,
+    open_wim: FnOpenWim,
+    free_wim: FnFree,
+    verify_wim: FnVerifyWim,
+    register_progress_function: FnRegisterProgressFunction,
+    get_error_string: FnGetErrorString,
+    get_wim_info: Option<FnGetWimInfo>,
+    get_image_name: Option<FnGetImageName>,
+    get_image_description: Option<FnGetImageDescription>,
+    extract_image: Option<FnExtractImage>,
+    create_new_wim: Option<FnCreateNewWim>,
+    add_image: Option<FnAddImage>,
+    write: Option<FnWrite>,
+    overwrite: Option<FnOverwrite>,
+    set_image_property: Option<FnSetImageProperty>,
+    export_image: Option<FnExportImage>,
+    reference_resource_files: Option<FnReferenceResourceFiles>,
+    iterate_dir_tree: Option<FnIterateDirTree>,
+    extract_paths: Option<FnExtractPaths>,
+    set_default_compression_level: Option<FnSetDefaultCompressionLevel>,
+    set_output_chunk_size: Option<FnSetOutputChunkSize>,
+    update_image: Option<FnUpdateImage>,
+    delete_image: Option<FnDeleteImage>,
+    get_image_property: Option<FnGetImageProperty>,
+}
+
+impl Wimlib {
+    /// 加载并初始化 wimlib
+    ///
+    /// 按以下顺序查找 DLL：
+    /// 1. 程序所在目录
+    /// 2. 系统 PATH
+    ///
+    /// 支持的 DLL 名称：libwim-15.dll, wimlib-15.dll, wimlib.dll
+    pub fn new() -> Result<Self, String> {
+        let dll_names = ["libwim-15.dll", "wimlib-15.dll", "wimlib.dll"];
+        
+        // 查找并加载 DLL
+        let lib = Self::find_and_load_dll(&dll_names)?;
+        let lib_arc = Arc::new(lib);
+
+        unsafe {
+            let loader = SymbolLoader::new(&lib_arc);
+
+            // 加载必需符号
+            let global_init = *loader.load::<FnGlobalInit>("wimlib_global_init", 4)
+                .map_err(|e| format!("加载 wimlib_global_init 失败: {}", e))?;
+            let global_cleanup = *loader.load::<FnGlobalCleanup>("wimlib_global_cleanup", 0)
+                .map_err(|e| format!("加载 wimlib_global_cleanup 失败: {}", e))?;
+            let open_wim = *loader.load::<FnOpenWim>("wimlib_open_wim", 16)
+                .map_err(|e| format!("加载 wimlib_open_wim 失败: {}", e))?;
+            let free_wim = *loader.load::<FnFree>("wimlib_free", 4)
+                .map_err(|e| format!("加载 wimlib_free 失败: {}", e))?;
+            let verify_wim = *loader.load::<FnVerifyWim>("wimlib_verify_wim", 8)
+                .map_err(|e| format!("加载 wimlib_verify_wim 失败: {}", e))?;
+            let register_progress_function = *loader.load::<FnRegisterProgressFunction>("wimlib_register_progress_function", 12)
+                .map_err(|e| format!("加载 wimlib_register_progress_function 失败: {}", e))?;
+            let get_error_string = *loader.load::<FnGetErrorString>("wimlib_get_error_string", 4)
+                .map_err(|e| format!("加载 wimlib_get_error_string 失败: {}", e))?;
+
+            // 加载可选符号
+            let get_wim_info = loader.load_optional::<FnGetWimInfo>("wimlib_get_wim_info", 8).map(|s| *s);
+            let get_image_name = loader.load_optional::<FnGetImageName>("wimlib_get_image_name", 8).map(|s| *s);
+            let get_image_description = loader.load_optional::<FnGetImageDescription>("wimlib_get_image_description", 8).map(|s| *s);
+            let extract_image = loader.load_optional::<FnExtractImage>("wimlib_extract_image", 16).map(|s| *s);
+            let create_new_wim = loader.load_optional::<FnCreateNewWim>("wimlib_create_new_wim", 8).map(|s| *s);
+            let add_image = loader.load_optional::<FnAddImage>("wimlib_add_image", 20).map(|s| *s);
+            let write = loader.load_optional::<FnWrite>("wimlib_write", 20).map(|s| *s);
+            let overwrite = loader.load_optional::<FnOverwrite>("wimlib_overwrite", 12).map(|s| *s);
+            let set_image_property = loader.load_optional::<FnSetImageProperty>("wimlib_set_image_property", 16).map(|s| *s);
+            let export_image = loader.load_optional::<FnExportImage>("wimlib_export_image", 24).map(|s| *s);
+            let reference_resource_files = loader.load_optional::<FnReferenceResourceFiles>("wimlib_reference_resource_files", 20).map(|s| *s);
+            let iterate_dir_tree = loader.load_optional::<FnIterateDirTree>("wimlib_iterate_dir_tree", 24).map(|s| *s);
+            let extract_paths = loader.load_optional::<FnExtractPaths>("wimlib_extract_paths", 24).map(|s| *s);
+            let set_default_compression_level = loader.load_optional::<FnSetDefaultCompressionLevel>("wimlib_set_default_compression_level", 8).map(|s| *s);
+            let set_output_chunk_size = loader.load_optional::<FnSetOutputChunkSize>("wimlib_set_output_chunk_size", 8).map(|s| *s);
+            let update_image = loader.load_optional::<FnUpdateImage>("wimlib_update_image", 20).map(|s| *s);
+            let delete_image = loader.load_optional::<FnDeleteImage>("wimlib_delete_image", 8).map(|s| *s);
+            let get_image_property = loader.load_optional::<FnGetImageProperty>("wimlib_get_image_property", 12).map(|s| *s);
+
+            // 初始化库
+            let init_result = global_init(0);
+            if init_result != 0 {
+                return Err(format!("wimlib 初始化失败，错误码: {}", init_result));
+            }
+
+            wimlib_log!(info, "初始化完成");
+
+            Ok(Self {
+                _lib: lib_arc,
+                global_init,
+                global_cleanup,
+                open_wim,
+                free_wim,
+                verify_wim,
+                register_progress_function,
+                get_error_string,
+                get_wim_info,
+                get_image_name,
+                get_image_description,
+                extract_image,
+                create_new_wim,
+                add_image,
+                write,
+                overwrite,
+                set_image_property,
+                export_image,
+                reference_resource_files,
+                iterate_dir_tree,
+                extract_paths,
+                set_default_compression_level,
+                set_output_chunk_size,
+                update_image,
+                delete_image,
+                get_image_property,
+            })
+        }
+    }
+
+    /// 查找并加载 DLL
+    fn find_and_load_dll(names: &[&str]) -> Result<Library, String> {
+        let mut last_error = String::new();
+
+        // 1. 尝试程序目录
+        if let Ok(exe_path) = std::env::current_exe() {
+            if let Some(exe_dir) = exe_path.parent() {
+                for name in names {
+                    let dll_path = exe_dir.join(name);
+                    if dll_path.exists() {
+                        match unsafe { Library::new(&dll_path) } {
+                            Ok(lib) => {
+                                wimlib_log!(info, "已加载: {:?}", dll_path);
+                                return Ok(lib);
+                            }
+                            Err(e) => {
+                                last_error = format!("{:?}: {}", dll_path, e);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // 2. 尝试系统 PATH
+        for name in names {
+            match unsafe { Library::new(*name) } {
+                Ok(lib) => {
+                    wimlib_log!(info, "已从系统路径加载: {}", name);
+                    return Ok(lib);
+                }
+                Err(e) => {
+                    last_error = format!("{}: {}", name, e);
+                }
+            }
+        }
+
+        Err(format!("无法加载 wimlib DLL: {}", last_error))
+    }
+
+    /// 打开 WIM 文件
+    pub fn open_wim(&self, path: &str) -> Result<WimHandle<'_>, WimlibFailure> {
+        self.open_wim_with_flags(path, 0)
+    }
+
+    fn open_wim_with_flags(&self, path: &str, flags: i32) -> Result<WimHandle<'_>, WimlibFailure> {
+        let path_utf16 = to_utf16(path);
+        let mut wim: WIMStruct = null_mut();
+
+        let ret = unsafe { (self.open_wim)(path_utf16.as_ptr(), flags, &mut wim, None) };
+
+        if ret != 0 {
+            return Err(self.failure(ret));
+        }
+
+        if wim.is_null() {
+            return Err("打开 WIM 失败：返回空句柄".into());
+        }
+
+        Ok(WimHandle::new(wim, self))
+    }
+
+    /// 打开 SWM 分卷镜像并引用其余分卷（传入任一分卷均可）
+    pub fn open_split_wim(&self, swm_file: &str) -> Result<WimHandle<'_>, WimlibFailure> {
+        let parts = find_swm_parts(swm_file);
+        let wim = self.open_wim(&parts[0].to_string_lossy())?;
+        wim.reference_parts(&parts[1..])?;
+        wimlib_log!(info, "已引用 {} 个分卷: {}", parts.len(), swm_file);
+        Ok(wim)
+    }
+
+    /// 打开镜像文件，SWM 分卷镜像自动引用其余分卷
+    pub fn open_image_file(&self, image_file: &str) -> Result<WimHandle<'_>, WimlibFailure> {
+        if image_file.to_lowercase().ends_with(".swm") {
+            self.open_split_wim(image_file)
+        } else {
+            self.open_wim(image_file)
+        }
+    }
+
+    /// 是否支持 SWM 分卷镜像
+    pub fn supports_split_wim(&self) -> bool {
+        self.reference_resource_files.is_some()
+    }
+
+    /// 是否支持释放镜像
+    pub fn supports_apply(&self) -> bool {
+        self.extract_image.is_some()
+    }
+
+    /// 是否支持捕获镜像
+    pub fn supports_capture(&self) -> bool {
+        self.create_new_wim.is_some() && self.add_image.is_some() && self.write.is_some() && self.overwrite.is_some()
+    }
+
+    /// 是否支持单独校验镜像
+    pub fn supports_verify_image(&self) -> bool {
+        self.create_new_wim.is_some() && self.export_image.is_some()
+    }
+
+    /// 是否支持就地更新镜像（添加/删除文件）
+    pub fn supports_update(&self) -> bool {
+        self.update_image.is_some() && self.overwrite.is_some()
+    }
+
+    /// 是否支持导出镜像（格式转换）
+    pub fn supports_export(&self) -> bool {
+        self.create_new_wim.is_some() && self.export_image.is_some() && self.write.is_some()
+    }
+
+    /// 是否支持管理分卷（删除分卷、修改名称/描述/标志）
+    pub fn supports_manage(&self) -> bool {
+        self.delete_image.is_some() && self.set_image_property.is_some() && self.overwrite.is_some()
+    }
+
+    /// 是否支持浏览镜像内容和提取文件
+    pub fn supports_browse(&self) -> bool {
+        self.iterate_dir_tree.is_some() && self.extract_paths.is_some()
+    }
+
+    /// 释放镜像到目标目录（index 从 1 开始）
+    pub fn apply_image(
+        &self,
+        image_file: &str,
+        index: u32,
+        target_dir: &str,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let extract_image = self.extract_image.ok_or("当前 wimlib 不支持释放镜像")?;
+
+        let wim = self.open_image_file(image_file)?;
+        let mut context = OperationContext::new(wim.state(), progress_tx);
+        wim.register_operation_progress(&mut context);
+
+        let target = to_utf16(target_dir);
+        let ret = unsafe { extract_image(wim.wim, index as i32, target.as_ptr(), 0) };
+        if ret != 0 {
+            return Err(self.failure(ret));
+        }
+
+        context.report(100, "释放完成");
+        Ok(())
+    }
+
+    /// 捕获目录为镜像，文件已存在时追加为新分卷（沿用原有压缩方式）
+    ///
+    /// `config_file` 为 WimScript.ini 格式的排除配置，为 None 时使用 wimlib 默认的 Windows 排除规则。
+    #[allow(clippy::too_many_arguments)]
+    pub fn capture_image(
+        &self,
+        source_dir: &str,
+        image_file: &str,
+        name: &str,
+        description: &str,
+        compression: ExportCompression,
+        config_file: Option<&Path>,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let (Some(create_new_wim), Some(add_image), Some(write), Some(overwrite)) =
+            (self.create_new_wim, self.add_image, self.write, self.overwrite)
+        else {
+            return Err("当前 wimlib 不支持捕获镜像".into());
+        };
+
+        let append = Path::new(image_file).exists();
+        let wim = if append {
+            self.open_wim_with_flags(image_file, OPEN_FLAG_WRITE_ACCESS)?
+        } else {
+            let mut wim: WIMStruct = null_mut();
+            let ret = unsafe { create_new_wim(compression.compression_type(), &mut wim) };
+            if ret != 0 || wim.is_null() {
+                return Err(self.failure(ret));
+            }
+            WimHandle::new(wim, self)
+        };
+        let mut context = OperationContext::new(wim.state(), progress_tx);
+        wim.register_operation_progress(&mut context);
+
+        let source = to_utf16(source_dir);
+        let name_utf16 = to_utf16(name);
+        let config = config_file.map(|path| to_utf16(&path.to_string_lossy()));
+        let config_ptr = config.as_ref().map_or(null(), |config| config.as_ptr());
+        let ret = unsafe { add_image(wim.wim, source.as_ptr(), name_utf16.as_ptr(), config_ptr, ADD_FLAG_WINCONFIG) };
+        if ret != 0 {
+            return Err(self.failure(ret));
+        }
+
+        if !description.is_empty() {
+            if let Some(set_image_property) = self.set_image_property {
+                let property = to_utf16("DESCRIPTION");
+                let value = to_utf16(description);
+                let ret = unsafe { set_image_property(wim.wim, wim.get_image_count(), property.as_ptr(), value.as_ptr()) };
+                if ret != 0 {
+                    wimlib_log!(warn, "设置镜像描述失败: {}", self.failure(ret));
+                }
+            }
+        }
+
+        let tuning = tuning();
+        self.apply_tuning(&wim, &tuning, append);
+        let ret = if append {
+            unsafe { overwrite(wim.wim, 0, tuning.threads) }
+        } else {
+            let path = to_utf16(image_file);
+            unsafe { write(wim.wim, path.as_ptr(), ALL_IMAGES, 0, tuning.threads) }
+        };
+        if ret != 0 {
+            return Err(self.failure(ret));
+        }
+
+        context.report(100, "捕获完成");
+        Ok(())
+    }
+
+    /// 把指定镜像（index 从 1 开始）导出为新文件，按 `compression` 重新压缩
+    pub fn export_images(
+        &self,
+        image_file: &str,
+        indexes: &[u32],
+        dest_file: &str,
+        compression: ExportCompression,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let (Some(create_new_wim), Some(export_image), Some(write)) =
+            (self.create_new_wim, self.export_image, self.write)
+        else {
+            return Err("当前 wimlib 不支持导出镜像".into());
+        };
+
+        let source = self.open_image_file(image_file)?;
+        let mut dest_wim: WIMStruct = null_mut();
+        let ret = unsafe { create_new_wim(compression.compression_type(), &mut dest_wim) };
+        if ret != 0 || dest_wim.is_null() {
+            return Err(self.failure(ret));
+        }
+        // 目标 WIM 引用源句柄的数据，须先于源句柄释放
+        let dest = WimHandle::new(dest_wim, self);
+        let mut context = OperationContext::new(dest.state(), progress_tx);
+        dest.register_operation_progress(&mut context);
+
+        for &index in indexes {
+            let ret = unsafe { export_image(source.wim, index as i32, dest.wim, null(), null(), 0) };
+            if ret != 0 {
+                return Err(self.failure(ret).context(&format!("导出分卷 {} 失败", index)));
+            }
+        }
+
+        let tuning = tuning();
+        // 压缩参数按 LZX 设置，其他压缩方式使用 wimlib 默认值
+        if compression == ExportCompression::Lzx {
+            self.apply_tuning(&dest, &tuning, false);
+        }
+        let path = to_utf16(dest_file);
+        let ret = unsafe { write(dest.wim, path.as_ptr(), ALL_IMAGES, compression.write_flags(), tuning.threads) };
+        if ret != 0 {
+            return Err(self.failure(ret));
+        }
+
+        wimlib_log!(info, "已导出 {} 分卷 {:?} -> {}（{:?}）", image_file, indexes, dest_file, compression);
+        context.report(100, "导出完成");
+        Ok(())
+    }
+
+    /// 就地更新 WIM 中的镜像（index 从 1 开始），添加或删除文件而无需重新捕获
+    ///
+    /// 默认只把新增数据追加到文件末尾；`rebuild` 为 true 时重写整个文件，
+    /// 回收被删除或替换的数据占用的空间（耗时与完整导出相当）。
+    pub fn update_image(
+        &self,
+        image_file: &str,
+        index: u32,
+        commands: &[WimUpdateCommand],
+        rebuild: bool,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let (Some(update_image), Some(overwrite)) = (self.update_image, self.overwrite) else {
+            return Err("当前 wimlib 不支持更新镜像".into());
+        };
+        if commands.is_empty() {
+            return Ok(());
+        }
+        if image_file.to_lowercase().ends_with(".swm") {
+            return Err("分卷镜像不支持就地更新".into());
+        }
+
+        let wim = self.open_wim_with_flags(image_file, OPEN_FLAG_WRITE_ACCESS)?;
+        let mut context = OperationContext::new(wim.state(), progress_tx);
+        wim.register_operation_progress(&mut context);
+
+        // 命令中的字符串指针须在 update_image 返回前保持有效
+        let strings: Vec<(Vec<u16>, Vec<u16>)> = commands
+            .iter()
+            .map(|command| match command {
+                WimUpdateCommand::Add { source, target } => {
+                    (to_utf16(&source.to_string_lossy()), to_utf16(&normalize_wim_path(target)))
+                }
+                WimUpdateCommand::Delete { path } => (to_utf16(&normalize_wim_path(path)), Vec::new()),
+            })
+            .collect();
+        let raw_commands: Vec<UpdateCommand> = commands
+            .iter()
+            .zip(&strings)
+            .map(|(command, (first, second))| match command {
+                WimUpdateCommand::Add { .. } => UpdateCommand {
+                    op: UPDATE_OP_ADD,
+                    params: UpdateCommandParams {
+                        add: AddCommand {
+                            fs_source_path: first.as_ptr(),
+                            wim_target_path: second.as_ptr(),
+                            config_file: null(),
+                            add_flags: ADD_FLAG_NO_ACLS,
+                        },
+                    },
+                },
+                WimUpdateCommand::Delete { .. } => UpdateCommand {
+                    op: UPDATE_OP_DELETE,
+                    params: UpdateCommandParams {
+                        delete: DeleteCommand {
+                            wim_path: first.as_ptr(),
+                            delete_flags: DELETE_FLAG_RECURSIVE,
+                        },
+                    },
+                },
+            })
+            .collect();
+
+        let ret = unsafe {
+            update_image(
+                wim.wim,
+                index as i32,
+                raw_commands.as_ptr(),
+                raw_commands.len(),
+                UPDATE_FLAG_SEND_PROGRESS,
+            )
+        };
+        if ret != 0 {
+            return Err(self.failure(ret).context("更新镜像失败"));
+        }
+
+        let tuning = tuning();
+        self.apply_tuning(&wim, &tuning, true);
+        let flags = if rebuild { WRITE_FLAG_REBUILD } else { 0 };
+        let ret = unsafe { overwrite(wim.wim, flags, tuning.threads) };
+        if ret != 0 {
+            return Err(self.failure(ret).context("写回镜像失败"));
+        }
+
+        wimlib_log!(info, "已更新镜像 {} 分卷 {}（{} 条命令）", image_file, index, commands.len());
+        context.report(100, "更新完成");
+        Ok(())
+    }
+
+    /// 从 WIM 中删除分卷（index 从 1 开始），重写整个文件以回收该分卷独占的数据
+    pub fn delete_image(
+        &self,
+        image_file: &str,
+        index: u32,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        self.delete_images(image_file, &[index], progress_tx)
+    }
+
+    /// 一次删除多个分卷，全部删除后只重写一次文件
+    pub fn delete_images(
+        &self,
+        image_file: &str,
+        indices: &[u32],
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let (Some(delete_image), Some(overwrite)) = (self.delete_image, self.overwrite) else {
+            return Err("当前 wimlib 不支持删除分卷".into());
+        };
+        if image_file.to_lowercase().ends_with(".swm") {
+            return Err("分卷镜像不支持删除分卷".into());
+        }
+
+        let wim = self.open_wim_with_flags(image_file, OPEN_FLAG_WRITE_ACCESS)?;
+        let mut context = OperationContext::new(wim.state(), progress_tx);
+        wim.register_operation_progress(&mut context);
+
+        // 从大到小删除，前面分卷的索引不会变化
+        let mut indices = indices.to_vec();
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        indices.dedup();
+        for &index in &indices {
+            let ret = unsafe { delete_image(wim.wim, index as i32) };
+            if ret != 0 {
+                return Err(self.failure(ret).context(&format!("删除分卷 {} 失败", index)));
+            }
+        }
+        let ret = unsafe { overwrite(wim.wim, WRITE_FLAG_REBUILD, tuning().threads) };
+        if ret != 0 {
+            return Err(self.failure(ret).context("写回镜像失败"));
+        }
+
+        wimlib_log!(info, "已删除 {} 的分卷 {:?}", image_file, indices);
+        context.report(100, "删除完成");
+        Ok(())
+    }
+
+    /// 修改分卷的 XML 属性（如 NAME、DESCRIPTION、FLAGS），值为空时删除该属性
+    ///
+    /// 只追加新的 XML 数据，不重写镜像内容。
+    pub fn set_image_properties(
+        &self,
+        image_file: &str,
+        index: u32,
+        properties: &[(&str, &str)],
+    ) -> Result<(), WimlibFailure> {
+        let (Some(set_image_property), Some(overwrite)) = (self.set_image_property, self.overwrite) else {
+            return Err("当前 wimlib 不支持修改分卷信息".into());
+        };
+        if image_file.to_lowercase().ends_with(".swm") {
+            return Err("分卷镜像不支持修改分卷信息".into());
+        }
+
+        let wim = self.open_wim_with_flags(image_file, OPEN_FLAG_WRITE_ACCESS)?;
+        for (name, value) in properties {
+            let name_utf16 = to_utf16(name);
+            let value_utf16 = to_utf16(value);
+            let ret = unsafe { set_image_property(wim.wim, index as i32, name_utf16.as_ptr(), value_utf16.as_ptr()) };
+            if ret != 0 {
+                return Err(self.failure(ret).context(&format!("设置 {} 失败", name)));
+            }
+        }
+        let ret = unsafe { overwrite(wim.wim, 0, tuning().threads) };
+        if ret != 0 {
+            return Err(self.failure(ret).context("写回镜像失败"));
+        }
+
+        wimlib_log!(info, "已修改 {} 分卷 {} 的信息", image_file, index);
+        Ok(())
+    }
+
+    /// 在写入前应用压缩级别和块大小（失败时保留默认值继续捕获）
+    fn apply_tuning(&self, wim: &WimHandle, tuning: &WimlibTuning, append: bool) {
+        if let (Some(level), Some(set_level)) = (tuning.compression_level, self.set_default_compression_level) {
+            let ret = unsafe { set_level(COMPRESSION_TYPE_LZX, level) };
+            if ret != 0 {
+                wimlib_log!(warn, "设置压缩级别失败: {}", self.failure(ret));
+            }
+        }
+        // 追加镜像时须沿用已有 WIM 的块大小
+        if append {
+            return;
+        }
+        if let (Some(chunk_size), Some(set_chunk_size)) = (tuning.chunk_size, self.set_output_chunk_size) {
+            let ret = unsafe { set_chunk_size(wim.wim, chunk_size) };
+            if ret != 0 {
+                wimlib_log!(warn, "设置块大小失败: {}", self.failure(ret));
+            }
+        }
+    }
+
+    /// wimlib 的原始错误信息（按错误码缓存，错误字符串是 wimlib 内部的常量）
+    fn error_string(&self, code: i32) -> String {
+        let cache = ERROR_STRINGS.get_or_init(Default::default);
+        if let Some(message) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&code) {
+            return message.clone();
+        }
+        let message = unsafe { Self::utf16_ptr_to_string((self.get_error_string)(code)) }.unwrap_or_default();
+        cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(code, message.clone());
+        message
+    }
+
+    /// 根据错误码生成失败信息
+    fn failure(&self, code: i32) -> WimlibFailure {
+        WimlibFailure {
+            code: Some(code),
+            message: self.error_string(code),
+        }
+    }
+
+    /// 将 UTF-16 指针转换为 String
+    unsafe fn utf16_ptr_to_string(ptr: *const u16) -> Option<String> {
+        if ptr.is_null() {
+            return None;
+        }
+
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+            if len > 4096 {
+                return None; // 安全限制
+            }
+        }
+
+        if len == 0 {
+            return None;
+        }
+
+        let slice = std::slice::from_raw_parts(ptr, len);
+        Some(String::from_utf16_lossy(slice))
+    }
+
+}
+
+impl Drop for Wimlib {
+    fn drop(&mut self) {
+        unsafe {
+            (self.global_cleanup)();
+        }
+        wimlib_log!(debug, "已清理");
+    }
+}
+
+// ============================================================================
+// WIM 句柄
+// ============================================================================
+
+/// WIM 文件句柄（RAII）
+pub struct WimHandle<'a> {
+    wim: WIMStruct,
+    lib: &'a Wimlib,
+    state: Arc<WimOperationState>,
+}
+
+impl<'a> WimHandle<'a> {
+    fn new(wim: WIMStruct, lib: &'a Wimlib) -> Self {
+        Self {
+            wim,
+            lib,
+            state: Arc::default(),
+        }
+    }
+
+    /// 本句柄的进度与取消状态（可在其他线程读取进度或请求取消）
+    pub fn state(&self) -> Arc<WimOperationState> {
+        Arc::clone(&self.state)
+    }
+
+    /// 验证 WIM 完整性
+    pub fn verify(&self) -> Result<(), WimlibFailure> {
+        self.state.reset_progress();
+
+        // 注册进度回调，ctx 指向本句柄的状态（句柄存活期间有效）
+        unsafe {
+            (self.lib.register_progress_function)(
+                self.wim,
+                progress_callback,
+                Arc::as_ptr(&self.state) as *mut c_void,
+            );
+        }
+
+        // 执行校验
+        let ret = unsafe { (self.lib.verify_wim)(self.wim, 0) };
+
+        if ret != 0 {
+            return Err(self.lib.failure(ret));
+        }
+
+        Ok(())
+    }
+
+    /// 引用 SWM 的其余分卷
+    pub fn reference_parts(&self, parts: &[PathBuf]) -> Result<(), WimlibFailure> {
+        if parts.is_empty() {
+            return Ok(());
+        }
+        let reference_resource_files = self
+            .lib
+            .reference_resource_files
+            .ok_or("当前 wimlib 不支持分卷镜像")?;
+
+        let wide_parts: Vec<Vec<u16>> = parts.iter().map(|p| to_utf16(&p.to_string_lossy())).collect();
+        let part_ptrs: Vec<*const u16> = wide_parts.iter().map(|p| p.as_ptr()).collect();
+        let ret = unsafe { reference_resource_files(self.wim, part_ptrs.as_ptr(), part_ptrs.len() as u32, 0, 0) };
+        if ret != 0 {
+            return Err(self.lib.failure(ret).context("引用分卷失败"));
+        }
+        Ok(())
+    }
+
+    /// 单独校验指定镜像（index 从 1 开始）
+    ///
+    /// 先把镜像导出到内存中的临时 WIM（导出时解析元数据），再对临时 WIM 执行校验，
+    /// 只读取该镜像引用的数据，可定位多镜像 ESD 中具体损坏的镜像。
+    pub fn verify_image(&self, index: i32, progress_tx: Option<Sender<WimlibProgress>>) -> Result<(), WimlibFailure> {
+        let (Some(create_new_wim), Some(export_image)) = (self.lib.create_new_wim, self.lib.export_image) else {
+            return Err("当前 wimlib 不支持单独校验镜像".into());
+        };
+
+        let mut temp_wim: WIMStruct = null_mut();
+        let ret = unsafe { create_new_wim(COMPRESSION_TYPE_LZX, &mut temp_wim) };
+        if ret != 0 || temp_wim.is_null() {
+            return Err(self.lib.failure(ret));
+        }
+        // 临时 WIM 引用本句柄的数据，须先于本句柄释放；共享本句柄的状态以便取消
+        let temp = WimHandle {
+            wim: temp_wim,
+            lib: self.lib,
+            state: self.state(),
+        };
+
+        let ret = unsafe { export_image(self.wim, index, temp.wim, null(), null(), 0) };
+        if ret != 0 {
+            return Err(self.lib.failure(ret).context("镜像元数据损坏"));
+        }
+
+        let mut context = OperationContext::new(temp.state(), progress_tx);
+        temp.register_operation_progress(&mut context);
+        let ret = unsafe { (self.lib.verify_wim)(temp.wim, 0) };
+        if ret != 0 {
+            return Err(self.lib.failure(ret));
+        }
+
+        context.report(100, "校验完成");
+        Ok(())
+    }
+
+    /// 列出镜像内目录的直接子项（index 从 1 开始，path 为空表示根目录）
+    pub fn list_dir(&self, index: i32, path: &str) -> Result<Vec<WimDirEntry>, WimlibFailure> {
+        let mut context = IterateContext::new(self.state(), None, usize::MAX);
+        self.iterate_dir_tree(index, path, ITERATE_DIR_TREE_FLAG_CHILDREN, &mut context)?;
+        context.entries.sort_by(|a, b| {
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        Ok(context.entries)
+    }
+
+    /// 在整个镜像中按文件名搜索（忽略大小写），最多返回 limit 项
+    pub fn search(&self, index: i32, keyword: &str, limit: usize) -> Result<Vec<WimDirEntry>, WimlibFailure> {
+        let mut context = IterateContext::new(self.state(), Some(keyword.to_lowercase()), limit);
+        self.iterate_dir_tree(index, "", ITERATE_DIR_TREE_FLAG_RECURSIVE, &mut context)?;
+        Ok(context.entries)
+    }
+
+    /// 列出镜像内全部普通文件及其数据哈希（跳过目录、重解析点和加密文件）
+    pub fn file_hashes(&self, index: i32) -> Result<Vec<WimFileHash>, WimlibFailure> {
+        let mut context = HashContext {
+            state: self.state(),
+            files: Vec::new(),
+            layout_mismatch: false,
+        };
+        self.iterate_raw(
+            index,
+            "",
+            ITERATE_DIR_TREE_FLAG_RECURSIVE,
+            collect_hashes_callback,
+            &mut context as *mut HashContext as *mut c_void,
+        )?;
+        if context.layout_mismatch {
+            return Err("当前 wimlib 版本的目录项格式不受支持，无法读取文件哈希".into());
+        }
+        if self.state.is_cancelled() {
+            return Err("操作已取消".into());
+        }
+        Ok(context.files)
+    }
+
+    fn iterate_dir_tree(&self, index: i32, path: &str, flags: i32, context: &mut IterateContext) -> Result<(), WimlibFailure> {
+        self.iterate_raw(
+            index,
+            path,
+            flags,
+            iterate_dir_tree_callback,
+            context as *mut IterateContext as *mut c_void,
+        )
+    }
+
+    fn iterate_raw(
+        &self,
+        index: i32,
+        path: &str,
+        flags: i32,
+        callback: FnIterateDirTreeCallback,
+        context: *mut c_void,
+    ) -> Result<(), WimlibFailure> {
+        let iterate_dir_tree = self.lib.iterate_dir_tree.ok_or("当前 wimlib 不支持浏览镜像内容")?;
+        let path_utf16 = to_utf16(path);
+        let ret = unsafe { iterate_dir_tree(self.wim, index, path_utf16.as_ptr(), flags, callback, context) };
+        if ret != 0 && ret != ITERATE_STOP {
+            return Err(self.lib.failure(ret));
+        }
+        Ok(())
+    }
+
+    /// 提取镜像内的文件或目录到 target_dir（不保留上级目录，不还原 ACL）
+    pub fn extract_paths(
+        &self,
+        index: i32,
+        paths: &[String],
+        target_dir: &str,
+        progress_tx: Option<Sender<WimlibProgress>>,
+    ) -> Result<(), WimlibFailure> {
+        let extract_paths = self.lib.extract_paths.ok_or("当前 wimlib 不支持提取文件")?;
+
+        let mut context = OperationContext::new(self.state(), progress_tx);
+        self.register_operation_progress(&mut context);
+
+        let wide_paths: Vec<Vec<u16>> = paths.iter().map(|p| to_utf16(p)).collect();
+        let path_ptrs: Vec<*const u16> = wide_paths.iter().map(|p| p.as_ptr()).collect();
+        let target = to_utf16(target_dir);
+        let flags = EXTRACT_FLAG_NO_ACLS | EXTRACT_FLAG_NO_PRESERVE_DIR_STRUCTURE;
+        let ret = unsafe { extract_paths(self.wim, index, target.as_ptr(), path_ptrs.as_ptr(), path_ptrs.len(), flags) };
+        if ret != 0 {
+            return Err(self.lib.failure(ret));
+        }
+
+        context.report(100, "提取完成");
+        Ok(())
+    }
+
+    /// 获取 WIM 信息
+    pub fn get_info(&self) -> Option<WimInfo> {
+        let func = self.lib.get_wim_info?;
+        let mut info = WimInfo::default();
+
+        let ret = unsafe { func(self.wim, &mut info) };
+        if ret == 0 {
+            Some(info)
+        } else {
+            None
+        }
+    }
+
+    /// 获取镜像数量
+    pub fn get_image_count(&self) -> i32 {
+        if let Some(info) = self.get_info() {
+            info.image_count as i32
+        } else {
+            -1
+        }
+    }
+
+    /// 获取镜像名称
+    pub fn get_image_name(&self, index: i32) -> Option<String> {
+        let func = self.lib.get_image_name?;
+        unsafe {
+            let ptr = func(self.wim, index);
+            Wimlib::utf16_ptr_to_string(ptr)
+        }
+    }
+
+    /// 获取镜像描述
+    pub fn get_image_description(&self, index: i32) -> Option<String> {
+        let func = self.lib.get_image_description?;
+        unsafe {
+            let ptr = func(self.wim, index);
+            Wimlib::utf16_ptr_to_string(ptr)
+        }
+    }
+
+    /// 获取镜像的 XML 属性（如 FLAGS、TOTALBYTES、WINDOWS/EDITIONID）
+    pub fn get_image_property(&self, index: i32, name: &str) -> Option<String> {
+        let func = self.lib.get_image_property?;
+        let name = to_utf16(name);
+        unsafe {
+            let ptr = func(self.wim, index, name.as_ptr());
+            Wimlib::utf16_ptr_to_string(ptr)
+        }
+    }
+
+    /// 获取镜像信息（名称和描述）
+    pub fn get_image_info(&self, index: i32) -> (String, String) {
+        let name = self.get_image_name(index).unwrap_or_default();
+        let desc = self.get_image_description(index).unwrap_or_default();
+        (name, desc)
+    }
+
+    /// 获取当前校验进度
+    pub fn get_verify_progress(&self) -> u8 {
+        self.state.progress()
+    }
+
+    /// 注册释放/捕获进度回调（context 须在操作结束前保持有效）
+    fn register_operation_progress(&self, context: &mut OperationContext) {
+        unsafe {
+            (self.lib.register_progress_function)(
+                self.wim,
+                operation_progress_callback,
+                context as *mut OperationContext as *mut c_void,
+            );
+        }
+    }
+}
+
+impl<'a> Drop for WimHandle<'a> {
+    fn drop(&mut self) {
+        if !self.wim.is_null() {
+            unsafe {
+                (self.lib.free_wim)(self.wim);
+            }
+        }
+    }
+}
+
+// ============================================================================
+// 单元测试
+// ============================================================================
+
+/// 查找 SWM 分卷文件（第一个为主分卷 xxx.swm，其后依次为 xxx2.swm、xxx3.swm...）
+///
+/// 传入任一分卷均可
+pub fn find_swm_parts(swm_file: &str) -> Vec<PathBuf> {
+    let path = Path::new(swm_file);
+    let parent = path.parent().unwrap_or(Path::new("."));
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "swm".to_string());
+    let part_path = |base: &str, number: u32| parent.join(format!("{}{}.{}", base, number, ext));
+
+    // 文件名本身可能以数字结尾（如 win10.swm），存在 win102.swm 时才视为主分卷
+    let trimmed = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let base_name = if part_path(&stem, 2).exists() {
+        stem.as_str()
+    } else if trimmed != stem && parent.join(format!("{}.{}", trimmed, ext)).exists() {
+        trimmed
+    } else {
+        stem.as_str()
+    };
+
+    let mut parts = vec![parent.join(format!("{}.{}", base_name, ext))];
+    for number in 2.. {
+        let part = part_path(base_name, number);
+        if !part.exists() {
+            break;
+        }
+        parts.push(part);
+    }
+    parts
+}
+
+/// DISM `/SWMFile` 参数使用的分卷通配符（如 `D:\\install*.swm`）
+pub fn swm_file_pattern(first_part: &Path) -> String {
+    let parent = first_part.parent().unwrap_or(Path::new("."));
+    let stem = first_part.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = first_part.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_else(|| "swm".to_string());
+    parent.join(format!("{}*.{}", stem, ext)).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(WimlibError::from_code(0), Some(WimlibError::Success));
+        assert_eq!(WimlibError::from_code(13), Some(WimlibError::Integrity));
+        assert_eq!(WimlibError::from_code(43), Some(WimlibError::NotAWimFile));
+        assert_eq!(WimlibError::from_code(47), Some(WimlibError::Open));
+        assert_eq!(WimlibError::from_code(74), Some(WimlibError::WimIsEncrypted));
+        assert_eq!(WimlibError::from_code(91), Some(WimlibError::SetXattr));
+        // 已废弃的编号
+        assert_eq!(WimlibError::from_code(3), None);
+        assert_eq!(WimlibError::from_code(22), None);
+        assert_eq!(WimlibError::from_code(-1), None);
+        assert_eq!(WimlibError::from_code(100), None);
+    }
+
+    #[test]
+    fn test_error_descriptions() {
+        assert_eq!(WimlibError::Success.description(), "操作成功");
+        assert_eq!(WimlibError::Integrity.description(), "完整性校验失败");
+        assert_eq!(WimlibError::NotAWimFile.description(), "不是有效的 WIM 文件");
+        for code in 0..100 {
+            if let Some(error) = WimlibError::from_code(code) {
+                assert_eq!(error.code(), code);
+                assert!(!error.description().is_empty());
+            }
+        }
+        assert!(WimlibError::Integrity.hint().is_some());
+        assert!(WimlibError::Success.hint().is_none());
+    }
+
+    #[test]
+    fn test_failure_display() {
+        let failure = WimlibFailure {
+            code: Some(13),
+            message: "The WIM file is corrupt".to_string(),
+        };
+        assert_eq!(failure.kind(), Some(WimlibError::Integrity));
+        assert_eq!(failure.to_string(), "完整性校验失败 (错误码 13: The WIM file is corrupt)");
+        assert!(failure.detailed().contains("修复建议"));
+
+        let failure = failure.context("镜像元数据损坏");
+        assert_eq!(failure.message, "镜像元数据损坏: The WIM file is corrupt");
+
+        let unsupported = WimlibFailure::from("当前 wimlib 不支持提取文件");
+        assert_eq!(unsupported.kind(), None);
+        assert_eq!(unsupported.detailed(), "当前 wimlib 不支持提取文件");
+    }
+
+    #[test]
+    fn test_compression_types() {
+        // wimlib.h: WIMLIB_COMPRESSION_TYPE_XPRESS = 1, LZX = 2, LZMS = 3
+        assert_eq!(ExportCompression::None.compression_type(), 0);
+        assert_eq!(ExportCompression::Xpress.compression_type(), 1);
+        assert_eq!(ExportCompression::Lzx.compression_type(), 2);
+        assert_eq!(ExportCompression::LzmsSolid.compression_type(), 3);
+        assert_eq!(ExportCompression::LzmsSolid.write_flags(), WRITE_FLAG_SOLID);
+    }
+
+    #[test]
+    fn test_wim_info_default() {
+        let info = WimInfo::default();
+        assert_eq!(info.image_count, 0);
+        assert_eq!(info.total_bytes, 0);
+    }
+
+    #[test]
+    fn test_operation_progress() {
+        let state = WimOperationState::default();
+        assert_eq!(state.progress(), 0);
+
+        state.advance_progress(50);
+        state.advance_progress(30);
+        assert_eq!(state.progress(), 50);
+
+        state.reset_progress();
+        assert_eq!(state.progress(), 0);
+    }
+
+    #[test]
+    fn test_operation_states_are_independent() {
+        let first = Arc::new(WimOperationState::default());
+        let second = Arc::new(WimOperationState::default());
+        first.advance_progress(80);
+        first.cancel();
+        assert_eq!(second.progress(), 0);
+        assert!(!second.is_cancelled());
+
+        let mut context = OperationContext::new(Arc::clone(&second), None);
+        context.report(40, "正在校验数据");
+        assert_eq!(second.progress(), 40);
+        assert_eq!(first.progress(), 80);
+    }
+
+    #[test]
+    fn test_percent_of() {
+        assert_eq!(percent_of(0, 0), 0);
+        assert_eq!(percent_of(50, 200), 25);
+        assert_eq!(percent_of(300, 200), 100);
+    }
+
+    #[test]
+    fn test_iterate_context_filter_and_limit() {
+        let entry = |name: &str| WimDirEntry {
+            name: name.to_string(),
+            full_path: format!("\\{}", name),
+            is_dir: false,
+        };
+        let mut context = IterateContext::new(Arc::default(), Some("hosts".to_string()), 2);
+        assert!(context.push(entry("")));
+        assert!(context.push(entry("HOSTS")));
+        assert!(context.push(entry("services")));
+        assert!(!context.push(entry("hosts.bak")));
+        assert_eq!(context.entries.len(), 2);
+    }
+
+    #[test]
+    fn test_tuning_for_cpu() {
+        assert_eq!(WimlibTuning::for_cpu(0), WimlibTuning::DEFAULT);
+        let dual = WimlibTuning::for_cpu(2);
+        assert_eq!(dual.threads, 2);
+        assert_eq!(dual.compression_level, Some(20));
+        let big = WimlibTuning::for_cpu(32);
+        assert_eq!(big.threads, 32);
+        assert_eq!(big.compression_level, None);
+        assert_eq!(big.chunk_size, None);
+        assert_eq!(WimlibTuning::for_cpu(256).threads, 64);
+    }
+
+    #[test]
+    fn test_normalize_wim_path() {
+        assert_eq!(normalize_wim_path(""), "\\");
+        assert_eq!(normalize_wim_path("/"), "\\");
+        assert_eq!(normalize_wim_path("Windows/Setup/Scripts/"), "\\Windows\\Setup\\Scripts");
+        assert_eq!(normalize_wim_path("\\Windows\\INF"), "\\Windows\\INF");
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_dir_entry_layout() {
+        assert_eq!(std::mem::size_of::<ResourceEntry>(), 88);
+        assert_eq!(std::mem::size_of::<StreamEntry>(), 128);
+        assert_eq!(std::mem::size_of::<DirEntry>(), 248);
+        assert_eq!(std::mem::offset_of!(DirEntry, object_id), 136);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_update_command_layout() {
+        // op + 对齐填充 + 三个指针 + add_flags（含尾部填充）
+        assert_eq!(std::mem::size_of::<UpdateCommand>(), 40);
+        assert_eq!(std::mem::size_of::<ProgressInfoUpdate>(), 24);
+    }
+
+    #[test]
+    fn test_cancel_flag() {
+        let state = WimOperationState::default();
+        assert!(!state.is_cancelled());
+
+        state.cancel();
+        assert!(state.is_cancelled());
+
+        // 新操作开始不清除取消标志
+        state.reset_progress();
+        assert!(state.is_cancelled());
+    }
+
+    #[test]
+    fn test_find_swm_parts() {
+        let dir = std::env::temp_dir().join("letrecovery_swm_parts_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["install.swm", "install2.swm", "install3.swm", "win10.swm"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let from_second = find_swm_parts(&dir.join("install2.swm").to_string_lossy());
+        assert_eq!(from_second.len(), 3);
+        assert_eq!(from_second[0], dir.join("install.swm"));
+        assert_eq!(from_second[2], dir.join("install3.swm"));
+
+        let single = find_swm_parts(&dir.join("win10.swm").to_string_lossy());
+        assert_eq!(single, vec![dir.join("win10.swm")]);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_swm_file_pattern() {
+        let first = Path::new("data").join("install.swm");
+        assert_eq!(swm_file_pattern(&first), Path::new("data").join("install*.swm").to_string_lossy());
+    }
+}
@@ -0,0 +1,188 @@
+//! Zstandard 压缩的驱动/更新包
+//!
+//! 数据目录中的 .zst / .tar.zst（.tzst）包体积更小，写入 U 盘数据分区更快。
+//! 安装时解压到目标分区的暂存目录，再与散装驱动文件夹和 CAB 一起导入。
+//! - `xxx.tar.zst` / `xxx.tzst`：解包到 `暂存目录\xxx\`
+//! - `xxx.cab.zst` 等单文件：解压为 `暂存目录\xxx.cab`
+//!
+//! 桌面端和 PE 端共用此文件。
+
+use anyhow::{anyhow, Context, Result};
+use std::fs::{self, File};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use ruzstd::StreamingDecoder;
+
+/// 判断是否为 Zstandard 压缩包
+pub fn is_zst_pack(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .is_some_and(|n| n.ends_with(".zst") || n.ends_with(".tzst"))
+}
+
+/// 是否为 tar 归档
+fn is_tar_pack(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with(".tar.zst") || name.ends_with(".tzst")
+}
+
+/// 去掉压缩扩展名后的名称（`a.tar.zst` -> `a`，`b.cab.zst` -> `b.cab`）
+fn pack_stem(name: &str) -> &str {
+    let lower = name.to_lowercase();
+    let suffix_len = [".tar.zst", ".tzst", ".zst"]
+        .iter()
+        .find(|s| lower.ends_with(*s))
+        .map(|s| s.len())
+        .unwrap_or(0);
+    &name[..name.len() - suffix_len]
+}
+
+/// 递归查找目录中的 Zstandard 压缩包
+pub fn find_zst_packs(dir: &Path) -> Vec<PathBuf> {
+    let mut packs = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                packs.extend(find_zst_packs(&path));
+            } else if is_zst_pack(&path) {
+                packs.push(path);
+            }
+        }
+    }
+    packs
+}
+
+/// 解压单个压缩包到 dest_dir，返回解压出的文件或目录
+pub fn extract_pack(pack: &Path, dest_dir: &Path) -> Result<PathBuf> {
+    let name = pack
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| anyhow!("无效的压缩包路径: {}", pack.display()))?;
+    let file = File::open(pack).with_context(|| format!("无法打开压缩包: {}", pack.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut decoder = StreamingDecoder::new(&mut reader).map_err(|e| anyhow!("无法解析 zstd 数据 {}: {}", name, e))?;
+
+    let output = dest_dir.join(pack_stem(&name));
+    if is_tar_pack(&name) {
+        fs::create_dir_all(&output).with_context(|| format!("无法创建目录: {}", output.display()))?;
+        tar::Archive::new(decoder)
+            .unpack(&output)
+            .with_context(|| format!("解包失败: {}", name))?;
+    } else {
+        fs::create_dir_all(dest_dir).with_context(|| format!("无法创建目录: {}", dest_dir.display()))?;
+        let mut out = File::create(&output).with_context(|| format!("无法创建文件: {}", output.display()))?;
+        io::copy(&mut decoder, &mut out).with_context(|| format!("解压失败: {}", name))?;
+    }
+    Ok(output)
+}
+
+/// 解压 source_dir 中的全部压缩包到 staging_dir
+///
+/// 返回需要处理的目录列表：原目录，以及有包解压成功时的暂存目录
+pub fn expand_packs(source_dir: &str, staging_dir: &Path) -> Vec<String> {
+    let mut dirs = vec![source_dir.to_string()];
+    let packs = find_zst_packs(Path::new(source_dir));
+    if packs.is_empty() {
+        return dirs;
+    }
+
+    log::info!("发现 {} 个 zstd 压缩包，解压到: {}", packs.len(), staging_dir.display());
+    let mut extracted = 0;
+    for pack in &packs {
+        match extract_pack(pack, staging_dir) {
+            Ok(output) => {
+                log::info!("已解压: {} -> {}", pack.display(), output.display());
+                extracted += 1;
+            }
+            Err(e) => log::warn!("解压 {} 失败: {:#}", pack.display(), e),
+        }
+    }
+
+    if extracted > 0 {
+        dirs.push(staging_dir.to_string_lossy().to_string());
+    }
+    dirs
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_names() {
+        assert!(is_zst_pack(Path::new("drivers\\net.tar.zst")));
+        assert!(is_zst_pack(Path::new("KB5034441.CAB.ZST")));
+        assert!(is_zst_pack(Path::new("storage.tzst")));
+        assert!(!is_zst_pack(Path::new("update.cab")));
+
+        assert!(is_tar_pack("Net.TAR.ZST"));
+        assert!(!is_tar_pack("update.cab.zst"));
+
+        assert_eq!(pack_stem("net.tar.zst"), "net");
+        assert_eq!(pack_stem("storage.TZST"), "storage");
+        assert_eq!(pack_stem("KB5034441.cab.zst"), "KB5034441.cab");
+        assert_eq!(pack_stem("plain.cab"), "plain.cab");
+    }
+
+    /// 只含一个未压缩块的 zstd 帧（窗口 128KB，不写内容大小）
+    fn raw_frame(data: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0x28, 0xB5, 0x2F, 0xFD, 0x00, 0x38];
+        let header = 1 | ((data.len() as u32) << 3);
+        frame.extend_from_slice(&header.to_le_bytes()[..3]);
+        frame.extend_from_slice(data);
+        frame
+    }
+
+    #[test]
+    fn test_extract_pack() {
+        let root = std::env::temp_dir().join(format!("lr_zst_extract_{}", std::process::id()));
+        let source = root.join("updates");
+        let staging = root.join("staging");
+        fs::create_dir_all(&source).unwrap();
+
+        fs::write(source.join("KB1.cab.zst"), raw_frame(b"cab data")).unwrap();
+        let output = extract_pack(&source.join("KB1.cab.zst"), &staging).unwrap();
+        assert_eq!(output, staging.join("KB1.cab"));
+        assert_eq!(fs::read(&output).unwrap(), b"cab data");
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "net/net.inf", &b"inf"[..]).unwrap();
+        let archive = builder.into_inner().unwrap();
+        fs::write(source.join("net.tar.zst"), raw_frame(&archive)).unwrap();
+
+        let source_str = source.to_string_lossy().to_string();
+        let dirs = expand_packs(&source_str, &staging);
+        assert_eq!(dirs, vec![source_str, staging.to_string_lossy().to_string()]);
+        assert_eq!(fs::read(staging.join("net").join("net").join("net.inf")).unwrap(), b"inf");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_expand_packs() {
+        let root = std::env::temp_dir().join(format!("lr_zst_pack_{}", std::process::id()));
+        let source = root.join("drivers");
+        let staging = root.join("staging");
+        fs::create_dir_all(source.join("sub")).unwrap();
+        fs::write(source.join("readme.inf"), "x").unwrap();
+        let source_str = source.to_string_lossy().to_string();
+
+        // 没有压缩包时只返回原目录
+        assert_eq!(expand_packs(&source_str, &staging), vec![source_str.clone()]);
+
+        // 损坏的压缩包解压失败，不加入暂存目录
+        fs::write(source.join("sub").join("bad.cab.zst"), "not zstd").unwrap();
+        assert_eq!(find_zst_packs(&source).len(), 1);
+        assert!(extract_pack(&source.join("sub").join("bad.cab.zst"), &staging).is_err());
+        assert_eq!(expand_packs(&source_str, &staging), vec![source_str.clone()]);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}